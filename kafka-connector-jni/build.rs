@@ -0,0 +1,18 @@
+use std::env;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=JAVA_HOME");
+    println!("cargo:rerun-if-env-changed=CI");
+    println!("cargo:rustc-check-cfg=cfg(jvm_tests)");
+
+    // Exports the natives of the library linked into the unit test executable, so the JVM of
+    // the tests binds them, see `tests/jvm/mod.rs`.
+    if env::var("CARGO_CFG_TARGET_FAMILY").as_deref() == Ok("unix") {
+        println!("cargo:rustc-link-arg=-rdynamic");
+    }
+    // Tests running on the JVM run by default where a JVM is expected, ignored elsewhere.
+    if env::var_os("JAVA_HOME").is_some() || env::var_os("CI").is_some() {
+        println!("cargo:rustc-cfg=jvm_tests");
+    }
+}
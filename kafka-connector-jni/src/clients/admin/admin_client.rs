@@ -0,0 +1,223 @@
+use std::{collections::HashSet, time::Duration};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::consumer::offset_and_metadata::OffsetAndMetadata,
+    common::{
+        acl::{acl_binding::AclBinding, acl_binding_filter::AclBindingFilter},
+        config::config_resource::ConfigResource,
+        topic_partition::TopicPartition,
+    },
+};
+
+use super::{
+    alter_config_op::AlterConfigOp,
+    new_partitions::NewPartitions,
+    new_topic::NewTopic,
+    offset_spec::OffsetSpec,
+    options::{
+        AlterConfigsOptions, AlterConsumerGroupOffsetsOptions, CreateAclsOptions,
+        CreatePartitionsOptions, CreateTopicsOptions, DeleteAclsOptions,
+        DeleteConsumerGroupOffsetsOptions, DeleteConsumerGroupsOptions, DeleteTopicsOptions,
+        DescribeAclsOptions, DescribeClusterOptions, DescribeConfigsOptions,
+        DescribeConsumerGroupsOptions, DescribeTopicsOptions, ListConsumerGroupOffsetsOptions,
+        ListConsumerGroupsOptions, ListOffsetsOptions, ListTopicsOptions,
+    },
+    results::{
+        AlterConfigsResult, AlterConsumerGroupOffsetsResult, CreateAclsResult,
+        CreatePartitionsResult, CreateTopicsResult, DeleteAclsResult,
+        DeleteConsumerGroupOffsetsResult, DeleteConsumerGroupsResult, DeleteTopicsResult,
+        DescribeAclsResult, DescribeClusterResult, DescribeConfigsResult,
+        DescribeConsumerGroupsResult, DescribeTopicsResult, ListConsumerGroupOffsetsResult,
+        ListConsumerGroupsResult, ListOffsetsResult, ListTopicsResult,
+    },
+};
+
+/// The administrative client for Kafka, which supports managing and inspecting topics,
+/// brokers, configurations and ACLs.
+///
+/// The operations are asynchronous: they return immediately and their results hold a
+/// `KafkaFuture` per resource, completed once the cluster answered. `None` options use the
+/// defaults.
+pub trait Admin: Send + Sync {
+    /// Close the Admin client and release all associated resources.
+    ///
+    /// The close operation has a grace period during which current operations will be allowed
+    /// to complete, specified by the given duration (`None` waits for all of them). New
+    /// operations will not be accepted during the grace period. Once the grace period is over,
+    /// all operations that have not yet been completed will be aborted with a `Timeout` error.
+    fn close(&self, timeout: Option<Duration>);
+
+    /// Create a batch of new topics.
+    ///
+    /// This operation is not transactional so it may succeed for some topics while fail for
+    /// others. It may take several seconds after the result of `create_topics` returns success
+    /// for all the brokers to become aware that the topics have been created. During this time,
+    /// `list_topics` and `describe_topics` may not return information about the new topics.
+    fn create_topics(
+        &self,
+        new_topics: Vec<NewTopic>,
+        options: Option<CreateTopicsOptions>,
+    ) -> CreateTopicsResult;
+
+    /// Delete a batch of topics.
+    ///
+    /// This operation is not transactional so it may succeed for some topics while fail for
+    /// others. It may take several seconds after the result of `delete_topics` returns success
+    /// for all the brokers to become aware that the topics are gone. During this time,
+    /// `list_topics` and `describe_topics` may continue to return information about the deleted
+    /// topics. If delete.topic.enable is false on the brokers, `delete_topics` will mark the
+    /// topics for deletion, but not actually delete them. The futures will return successfully
+    /// in this case.
+    fn delete_topics(
+        &self,
+        topic_names: Vec<String>,
+        options: Option<DeleteTopicsOptions>,
+    ) -> DeleteTopicsResult;
+
+    /// List the topics available in the cluster.
+    fn list_topics(&self, options: Option<ListTopicsOptions>) -> ListTopicsResult;
+
+    /// Describe some topics in the cluster.
+    fn describe_topics(
+        &self,
+        topic_names: Vec<String>,
+        options: Option<DescribeTopicsOptions>,
+    ) -> DescribeTopicsResult;
+
+    /// Get information about the nodes in the cluster.
+    fn describe_cluster(&self, options: Option<DescribeClusterOptions>) -> DescribeClusterResult;
+
+    /// Lists access control lists (ACLs) according to the supplied filter.
+    ///
+    /// Note: it may take some time for changes made by `create_acls` or `delete_acls` to be
+    /// reflected in the output of `describe_acls`.
+    fn describe_acls(
+        &self,
+        filter: AclBindingFilter,
+        options: Option<DescribeAclsOptions>,
+    ) -> DescribeAclsResult;
+
+    /// Creates access control lists (ACLs) which are bound to specific resources.
+    ///
+    /// This operation is not transactional so it may succeed for some ACLs while fail for
+    /// others. If you attempt to add an ACL that duplicates an existing ACL, no error will be
+    /// raised, but no changes will be made.
+    fn create_acls(
+        &self,
+        acls: Vec<AclBinding>,
+        options: Option<CreateAclsOptions>,
+    ) -> CreateAclsResult;
+
+    /// Deletes access control lists (ACLs) according to the supplied filters.
+    ///
+    /// This operation is not transactional so it may succeed for some ACLs while fail for
+    /// others.
+    fn delete_acls(
+        &self,
+        filters: Vec<AclBindingFilter>,
+        options: Option<DeleteAclsOptions>,
+    ) -> DeleteAclsResult;
+
+    /// Get the configuration for the specified resources.
+    ///
+    /// The returned configuration includes default values and the `is_default` method can be
+    /// used to distinguish them from user supplied values.
+    ///
+    /// The value of config entries where `is_sensitive` is true is always `None` so that
+    /// sensitive information is not disclosed.
+    ///
+    /// Config entries where `is_read_only` is true cannot be updated.
+    fn describe_configs(
+        &self,
+        resources: Vec<ConfigResource>,
+        options: Option<DescribeConfigsOptions>,
+    ) -> DescribeConfigsResult;
+
+    /// Incrementally update the configuration for the specified resources.
+    ///
+    /// Updates are not transactional so they may succeed for some resources while fail for
+    /// others. The configs for a particular resource are updated atomically.
+    ///
+    /// The following errors may be returned by the futures:
+    /// - `ClusterAuthorization` if the authenticated user didn't have alter access to the
+    ///   cluster.
+    /// - `TopicAuthorization` if the authenticated user didn't have alter access to the Topic.
+    /// - `UnknownTopicOrPartition` if the Topic doesn't exist.
+    /// - `InvalidRequest` if the request details are invalid. e.g., a configuration key was
+    ///   specified more than once for a resource
+    fn incremental_alter_configs(
+        &self,
+        configs: IndexMap<ConfigResource, Vec<AlterConfigOp>>,
+        options: Option<AlterConfigsOptions>,
+    ) -> AlterConfigsResult;
+
+    /// Increase the number of partitions of the topics given as the keys of `new_partitions`
+    /// according to the corresponding values. If partitions are increased for a topic that has
+    /// a key, the partition logic or ordering of the messages will be affected.
+    ///
+    /// This operation is not transactional so it may succeed for some topics while fail for
+    /// others.
+    fn create_partitions(
+        &self,
+        new_partitions: IndexMap<String, NewPartitions>,
+        options: Option<CreatePartitionsOptions>,
+    ) -> CreatePartitionsResult;
+
+    /// List the consumer groups available in the cluster.
+    fn list_consumer_groups(
+        &self,
+        options: Option<ListConsumerGroupsOptions>,
+    ) -> ListConsumerGroupsResult;
+
+    /// Describe some group IDs in the cluster.
+    fn describe_consumer_groups(
+        &self,
+        group_ids: Vec<String>,
+        options: Option<DescribeConsumerGroupsOptions>,
+    ) -> DescribeConsumerGroupsResult;
+
+    /// Delete consumer groups from the cluster.
+    fn delete_consumer_groups(
+        &self,
+        group_ids: Vec<String>,
+        options: Option<DeleteConsumerGroupsOptions>,
+    ) -> DeleteConsumerGroupsResult;
+
+    /// List the consumer group offsets available in the cluster.
+    fn list_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        options: Option<ListConsumerGroupOffsetsOptions>,
+    ) -> ListConsumerGroupOffsetsResult;
+
+    /// Delete committed offsets for a set of partitions in a consumer group. This will succeed
+    /// at the partition level only if the group is not actively subscribed to the corresponding
+    /// topic.
+    fn delete_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        partitions: HashSet<TopicPartition>,
+        options: Option<DeleteConsumerGroupOffsetsOptions>,
+    ) -> DeleteConsumerGroupOffsetsResult;
+
+    /// Alters offsets for the specified group. In order to succeed, the group must be empty.
+    ///
+    /// This operation is not transactional so it may succeed for some partitions while fail
+    /// for others.
+    fn alter_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        options: Option<AlterConsumerGroupOffsetsOptions>,
+    ) -> AlterConsumerGroupOffsetsResult;
+
+    /// List offset for the specified partitions. This operation enables to find the beginning
+    /// offset, end offset as well as the offset matching a timestamp in partitions.
+    fn list_offsets(
+        &self,
+        topic_partition_offsets: IndexMap<TopicPartition, OffsetSpec>,
+        options: Option<ListOffsetsOptions>,
+    ) -> ListOffsetsResult;
+}
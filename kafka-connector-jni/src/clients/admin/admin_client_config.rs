@@ -0,0 +1,214 @@
+use std::{collections::HashSet, ops::Deref};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::common_client_configs::{self, *},
+    common::{
+        config::{
+            abstract_config::AbstractConfig,
+            config_def::{
+                CaseInsensitiveValidString, ConfigDef, DefaultValue, Importance, Range, Type,
+                ValidString,
+            },
+        },
+        errors::Result,
+    },
+};
+
+/// The configuration keys used by the admin client.
+pub struct AdminClientConfig {
+    config: AbstractConfig,
+}
+
+impl AdminClientConfig {
+    /// Parses and validates `props`, filling in defaults.
+    pub fn new(props: IndexMap<String, String>) -> Result<AdminClientConfig> {
+        AdminClientConfig::with_logging(props, true)
+    }
+
+    pub fn with_logging(
+        props: IndexMap<String, String>,
+        do_log: bool,
+    ) -> Result<AdminClientConfig> {
+        let config = AbstractConfig::new(
+            &AdminClientConfig::config_def()?,
+            props,
+            "AdminClientConfig",
+            do_log,
+            common_client_configs::post_process_reconnect_backoff_configs,
+        )?;
+        Ok(AdminClientConfig { config })
+    }
+
+    pub fn config_def() -> Result<ConfigDef> {
+        ConfigDef::new()
+            .define(
+                BOOTSTRAP_SERVERS_CONFIG,
+                Type::List,
+                DefaultValue::NoDefault,
+                Importance::High,
+                BOOTSTRAP_SERVERS_DOC,
+            )?
+            .define(
+                CLIENT_ID_CONFIG,
+                Type::String,
+                "",
+                Importance::Medium,
+                CLIENT_ID_DOC,
+            )?
+            .define(
+                METADATA_MAX_AGE_CONFIG,
+                Type::Long,
+                5 * 60 * 1000i64,
+                Importance::Low,
+                METADATA_MAX_AGE_DOC,
+            )?
+            .define_with_validator(
+                SEND_BUFFER_CONFIG,
+                Type::Int,
+                128 * 1024,
+                Range::at_least(SEND_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                SEND_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                RECEIVE_BUFFER_CONFIG,
+                Type::Int,
+                64 * 1024,
+                Range::at_least(RECEIVE_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                RECEIVE_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MS_CONFIG,
+                Type::Long,
+                50i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MAX_MS_CONFIG,
+                Type::Long,
+                1000i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MAX_MS_DOC,
+            )?
+            .define_with_validator(
+                RETRY_BACKOFF_MS_CONFIG,
+                Type::Long,
+                100i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RETRY_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                REQUEST_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                30000,
+                Range::at_least(0),
+                Importance::Medium,
+                REQUEST_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_DOC,
+            )?
+            .define(
+                CONNECTIONS_MAX_IDLE_MS_CONFIG,
+                Type::Long,
+                5 * 60 * 1000i64,
+                Importance::Medium,
+                CONNECTIONS_MAX_IDLE_MS_DOC,
+            )?
+            .define_with_validator(
+                RETRIES_CONFIG,
+                Type::Int,
+                i32::MAX,
+                Range::between(0, i32::MAX),
+                Importance::Low,
+                RETRIES_DOC,
+            )?
+            .define_with_validator(
+                DEFAULT_API_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                60000,
+                Range::at_least(0),
+                Importance::Medium,
+                DEFAULT_API_TIMEOUT_MS_DOC,
+            )?
+            .define_with_validator(
+                METRICS_SAMPLE_WINDOW_MS_CONFIG,
+                Type::Long,
+                30000i64,
+                Range::at_least(0),
+                Importance::Low,
+                METRICS_SAMPLE_WINDOW_MS_DOC,
+            )?
+            .define_with_validator(
+                METRICS_NUM_SAMPLES_CONFIG,
+                Type::Int,
+                2,
+                Range::at_least(1),
+                Importance::Low,
+                METRICS_NUM_SAMPLES_DOC,
+            )?
+            .define(
+                METRIC_REPORTER_CLASSES_CONFIG,
+                Type::List,
+                "",
+                Importance::Low,
+                METRIC_REPORTER_CLASSES_DOC,
+            )?
+            .define_with_validator(
+                METRICS_RECORDING_LEVEL_CONFIG,
+                Type::String,
+                "INFO",
+                ValidString::one_of(&["INFO", "DEBUG", "TRACE"]),
+                Importance::Low,
+                METRICS_RECORDING_LEVEL_DOC,
+            )?
+            .define_with_validator(
+                CLIENT_DNS_LOOKUP_CONFIG,
+                Type::String,
+                USE_ALL_DNS_IPS,
+                ValidString::one_of(&[USE_ALL_DNS_IPS, RESOLVE_CANONICAL_BOOTSTRAP_SERVERS_ONLY]),
+                Importance::Medium,
+                CLIENT_DNS_LOOKUP_DOC,
+            )?
+            .define_with_validator(
+                SECURITY_PROTOCOL_CONFIG,
+                Type::String,
+                DEFAULT_SECURITY_PROTOCOL,
+                CaseInsensitiveValidString::one_of(&SECURITY_PROTOCOLS),
+                Importance::Medium,
+                SECURITY_PROTOCOL_DOC,
+            )?
+            .with_client_ssl_support()?
+            .with_client_sasl_support()
+    }
+
+    pub fn config_names() -> Result<HashSet<String>> {
+        Ok(AdminClientConfig::config_def()?.names())
+    }
+}
+
+impl Deref for AdminClientConfig {
+    type Target = AbstractConfig;
+
+    fn deref(&self) -> &Self::Target {
+        &self.config
+    }
+}
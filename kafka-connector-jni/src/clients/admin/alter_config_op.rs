@@ -0,0 +1,70 @@
+use std::fmt::{self, Display};
+
+use super::config_entry::ConfigEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpType {
+    /// Set the value of the configuration entry.
+    Set,
+    /// Revert the configuration entry to the default value (possibly null).
+    Delete,
+    /// (For list-type configuration entries only.) Add the specified values to the current
+    /// value of the configuration entry. If the configuration value has not been set, adds to
+    /// the default value.
+    Append,
+    /// (For list-type configuration entries only.) Removes the specified values from the
+    /// current value of the configuration entry. It is legal to remove values that are not
+    /// currently in the configuration entry. Removing all entries from the current
+    /// configuration value leaves an empty list and does NOT revert to the default value of the
+    /// entry.
+    Subtract,
+}
+
+impl OpType {
+    pub fn id(&self) -> i8 {
+        match self {
+            OpType::Set => 0,
+            OpType::Delete => 1,
+            OpType::Append => 2,
+            OpType::Subtract => 3,
+        }
+    }
+}
+
+impl Display for OpType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OpType::Set => "SET",
+            OpType::Delete => "DELETE",
+            OpType::Append => "APPEND",
+            OpType::Subtract => "SUBTRACT",
+        })
+    }
+}
+
+/// A class representing a alter configuration entry containing name, value and operation
+/// type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlterConfigOp {
+    pub config_entry: ConfigEntry,
+    pub op_type: OpType,
+}
+
+impl AlterConfigOp {
+    pub fn new(config_entry: ConfigEntry, op_type: OpType) -> AlterConfigOp {
+        AlterConfigOp {
+            config_entry,
+            op_type,
+        }
+    }
+}
+
+impl Display for AlterConfigOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AlterConfigOp{{opType={}, configEntry={}}}",
+            self.op_type, self.config_entry
+        )
+    }
+}
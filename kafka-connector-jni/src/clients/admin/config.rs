@@ -0,0 +1,46 @@
+use std::fmt::{self, Display};
+
+use indexmap::IndexMap;
+
+use super::config_entry::ConfigEntry;
+
+/// A configuration object containing the configuration entries for a resource.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    entries: IndexMap<String, ConfigEntry>,
+}
+
+impl Config {
+    /// Create a configuration instance with the provided entries.
+    pub fn new(entries: Vec<ConfigEntry>) -> Config {
+        Config {
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.name.clone(), entry))
+                .collect(),
+        }
+    }
+
+    /// Configuration entries for a resource.
+    pub fn entries(&self) -> impl Iterator<Item = &ConfigEntry> {
+        self.entries.values()
+    }
+
+    /// Get the configuration entry with the provided name or `None` if there isn't one.
+    pub fn get(&self, name: &str) -> Option<&ConfigEntry> {
+        self.entries.get(name)
+    }
+}
+
+impl Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Config(entries=[")?;
+        for (i, entry) in self.entries.values().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            entry.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
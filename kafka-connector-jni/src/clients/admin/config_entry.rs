@@ -0,0 +1,190 @@
+use std::fmt::{self, Display};
+
+/// Source of configuration entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigSource {
+    /// dynamic topic config that is configured for a specific topic
+    DynamicTopicConfig,
+    /// dynamic broker logger config that is configured for a specific broker
+    DynamicBrokerLoggerConfig,
+    /// dynamic broker config that is configured for a specific broker
+    DynamicBrokerConfig,
+    /// dynamic broker config that is configured as default for all brokers in the cluster
+    DynamicDefaultBrokerConfig,
+    /// static broker config provided as broker properties at start up (e.g. server.properties file)
+    StaticBrokerConfig,
+    /// built-in default configuration for configs that have a default value
+    DefaultConfig,
+    /// source unknown e.g. in the ConfigEntry used for alter requests where source is not set
+    Unknown,
+}
+
+impl ConfigSource {
+    /// Maps the source id of a DescribeConfigs response, unknown ids are mapped to `Unknown`.
+    pub fn for_id(id: i8) -> ConfigSource {
+        match id {
+            1 => ConfigSource::DynamicTopicConfig,
+            2 => ConfigSource::DynamicBrokerConfig,
+            3 => ConfigSource::DynamicDefaultBrokerConfig,
+            4 => ConfigSource::StaticBrokerConfig,
+            5 => ConfigSource::DefaultConfig,
+            6 => ConfigSource::DynamicBrokerLoggerConfig,
+            _ => ConfigSource::Unknown,
+        }
+    }
+}
+
+impl Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigSource::DynamicTopicConfig => "DYNAMIC_TOPIC_CONFIG",
+            ConfigSource::DynamicBrokerLoggerConfig => "DYNAMIC_BROKER_LOGGER_CONFIG",
+            ConfigSource::DynamicBrokerConfig => "DYNAMIC_BROKER_CONFIG",
+            ConfigSource::DynamicDefaultBrokerConfig => "DYNAMIC_DEFAULT_BROKER_CONFIG",
+            ConfigSource::StaticBrokerConfig => "STATIC_BROKER_CONFIG",
+            ConfigSource::DefaultConfig => "DEFAULT_CONFIG",
+            ConfigSource::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+/// Data type of configuration entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigType {
+    Unknown,
+    Boolean,
+    String,
+    Int,
+    Short,
+    Long,
+    Double,
+    List,
+    Class,
+    Password,
+}
+
+impl ConfigType {
+    /// Maps the type id of a DescribeConfigs response, unknown ids are mapped to `Unknown`.
+    pub fn for_id(id: i8) -> ConfigType {
+        match id {
+            1 => ConfigType::Boolean,
+            2 => ConfigType::String,
+            3 => ConfigType::Int,
+            4 => ConfigType::Short,
+            5 => ConfigType::Long,
+            6 => ConfigType::Double,
+            7 => ConfigType::List,
+            8 => ConfigType::Class,
+            9 => ConfigType::Password,
+            _ => ConfigType::Unknown,
+        }
+    }
+}
+
+impl Display for ConfigType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigType::Unknown => "UNKNOWN",
+            ConfigType::Boolean => "BOOLEAN",
+            ConfigType::String => "STRING",
+            ConfigType::Int => "INT",
+            ConfigType::Short => "SHORT",
+            ConfigType::Long => "LONG",
+            ConfigType::Double => "DOUBLE",
+            ConfigType::List => "LIST",
+            ConfigType::Class => "CLASS",
+            ConfigType::Password => "PASSWORD",
+        })
+    }
+}
+
+/// Class representing a configuration synonym of a `ConfigEntry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigSynonym {
+    pub name: String,
+    pub value: Option<String>,
+    pub source: ConfigSource,
+}
+
+impl Display for ConfigSynonym {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ConfigSynonym(name={}, value={}, source={})",
+            self.name,
+            self.value.as_deref().unwrap_or("null"),
+            self.source
+        )
+    }
+}
+
+/// A class representing a configuration entry containing name, value and additional metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigEntry {
+    pub name: String,
+    /// The value or `None`. `None` is returned if the config is unset or if `is_sensitive` is
+    /// true.
+    pub value: Option<String>,
+    pub source: ConfigSource,
+    /// Whether the config value is sensitive. The value is always set to `None` by the broker if
+    /// the config value is sensitive.
+    pub is_sensitive: bool,
+    /// Whether the config is read-only and cannot be updated.
+    pub is_read_only: bool,
+    /// All config values that may be used as the value of this config along with their source,
+    /// in the order of precedence. The list starts with the value returned in this ConfigEntry.
+    /// The list is empty if synonyms were not requested using
+    /// `DescribeConfigsOptions::include_synonyms`.
+    pub synonyms: Vec<ConfigSynonym>,
+    pub type_: ConfigType,
+    /// The config documentation, `None` unless requested using
+    /// `DescribeConfigsOptions::include_documentation`.
+    pub documentation: Option<String>,
+}
+
+impl ConfigEntry {
+    /// Create a configuration entry with the provided values, used for alter requests.
+    pub fn new(name: impl Into<String>, value: Option<String>) -> ConfigEntry {
+        ConfigEntry {
+            name: name.into(),
+            value,
+            source: ConfigSource::Unknown,
+            is_sensitive: false,
+            is_read_only: false,
+            synonyms: vec![],
+            type_: ConfigType::Unknown,
+            documentation: None,
+        }
+    }
+
+    /// Return whether the config value is the default or if it's been explicitly set.
+    pub fn is_default(&self) -> bool {
+        self.source == ConfigSource::DefaultConfig
+    }
+}
+
+impl Display for ConfigEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ConfigEntry(name={}, value={}, source={}, isSensitive={}, isReadOnly={}, synonyms=[",
+            self.name,
+            self.value.as_deref().unwrap_or("null"),
+            self.source,
+            self.is_sensitive,
+            self.is_read_only
+        )?;
+        for (i, synonym) in self.synonyms.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            synonym.fmt(f)?;
+        }
+        write!(
+            f,
+            "], type={}, documentation={})",
+            self.type_,
+            self.documentation.as_deref().unwrap_or("null")
+        )
+    }
+}
@@ -0,0 +1,60 @@
+use std::fmt::{self, Display};
+
+use crate::common::{consumer_group_state::ConsumerGroupState, node::Node};
+
+use super::member_description::MemberDescription;
+
+/// A detailed description of a single consumer group in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerGroupDescription {
+    pub group_id: String,
+    /// If consumer group is simple or not.
+    pub is_simple_consumer_group: bool,
+    pub members: Vec<MemberDescription>,
+    /// The consumer group partition assignor.
+    pub partition_assignor: String,
+    pub state: ConsumerGroupState,
+    /// The consumer group coordinator.
+    pub coordinator: Node,
+}
+
+impl ConsumerGroupDescription {
+    pub fn new(
+        group_id: impl Into<String>,
+        is_simple_consumer_group: bool,
+        members: Vec<MemberDescription>,
+        partition_assignor: impl Into<String>,
+        state: ConsumerGroupState,
+        coordinator: Node,
+    ) -> ConsumerGroupDescription {
+        ConsumerGroupDescription {
+            group_id: group_id.into(),
+            is_simple_consumer_group,
+            members,
+            partition_assignor: partition_assignor.into(),
+            state,
+            coordinator,
+        }
+    }
+}
+
+impl Display for ConsumerGroupDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(groupId={}, isSimpleConsumerGroup={}, members=",
+            self.group_id, self.is_simple_consumer_group
+        )?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            member.fmt(f)?;
+        }
+        write!(
+            f,
+            ", partitionAssignor={}, state={}, coordinator={})",
+            self.partition_assignor, self.state, self.coordinator
+        )
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::{self, Display};
+
+use crate::common::consumer_group_state::ConsumerGroupState;
+
+/// A listing of a consumer group in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerGroupListing {
+    pub group_id: String,
+    /// If consumer group is simple or not.
+    pub is_simple_consumer_group: bool,
+    /// The consumer group state, `None` if the broker does not report it.
+    pub state: Option<ConsumerGroupState>,
+}
+
+impl ConsumerGroupListing {
+    pub fn new(
+        group_id: impl Into<String>,
+        is_simple_consumer_group: bool,
+        state: Option<ConsumerGroupState>,
+    ) -> ConsumerGroupListing {
+        ConsumerGroupListing {
+            group_id: group_id.into(),
+            is_simple_consumer_group,
+            state,
+        }
+    }
+}
+
+impl Display for ConsumerGroupListing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(groupId='{}', isSimpleConsumerGroup={}, state=",
+            self.group_id, self.is_simple_consumer_group
+        )?;
+        match self.state {
+            Some(state) => write!(f, "Optional[{}])", state),
+            None => f.write_str("Optional.empty)"),
+        }
+    }
+}
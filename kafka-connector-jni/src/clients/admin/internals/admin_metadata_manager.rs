@@ -0,0 +1,235 @@
+use std::sync::Arc;
+
+use log::{debug, info, trace};
+
+use crate::{
+    clients::metadata_updater::ManualMetadataUpdater,
+    common::{cluster::Cluster, errors::KafkaError, node::Node},
+};
+
+/// The current metadata state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Quiescent,
+    UpdateRequested,
+    UpdatePending,
+}
+
+/// Manages the metadata for KafkaAdminClient.
+///
+/// This class is not thread-safe. It is only accessed from the AdminClient service thread
+/// (which also uses the NetworkClient).
+pub struct AdminMetadataManager {
+    /// The minimum amount of time that we should wait between subsequent retries, when fetching
+    /// metadata.
+    refresh_backoff_ms: u128,
+    /// The minimum amount of time that we should wait before triggering an automatic metadata
+    /// refresh.
+    metadata_expire_ms: u128,
+    state: State,
+    /// The time in wall-clock milliseconds when we last updated the metadata.
+    last_metadata_update_ms: u128,
+    /// The time in wall-clock milliseconds when we last attempted to fetch new metadata.
+    last_metadata_fetch_attempt_ms: u128,
+    /// The current cluster information.
+    cluster: Cluster,
+    /// The metadata updater of the network client, which is given the nodes of the cluster.
+    updater: Arc<ManualMetadataUpdater>,
+}
+
+impl AdminMetadataManager {
+    pub fn new(refresh_backoff_ms: u128, metadata_expire_ms: u128) -> AdminMetadataManager {
+        AdminMetadataManager {
+            refresh_backoff_ms,
+            metadata_expire_ms,
+            state: State::Quiescent,
+            last_metadata_update_ms: 0,
+            last_metadata_fetch_attempt_ms: 0,
+            cluster: Cluster::empty(),
+            updater: Arc::new(ManualMetadataUpdater::default()),
+        }
+    }
+
+    /// The metadata updater to create the network client with. The metadata itself is fetched
+    /// by the AdminClient thread, so the updater only provides the nodes of the current cluster.
+    pub fn updater(&self) -> Arc<ManualMetadataUpdater> {
+        self.updater.clone()
+    }
+
+    /// Determine if the AdminClient should fetch new metadata.
+    pub fn is_ready(&self) -> bool {
+        if self.cluster.nodes.is_empty() {
+            trace!("Metadata is not ready: bootstrap nodes have not been initialized yet.");
+            return false;
+        }
+        if self.cluster.is_bootstrap_configured {
+            trace!(
+                "Metadata is not ready: we have not fetched metadata from the bootstrap nodes yet."
+            );
+            return false;
+        }
+        trace!("Metadata is ready to use.");
+        true
+    }
+
+    /// Provide the metadata controller node, or `None` if it is not known.
+    pub fn controller(&self) -> Option<&Node> {
+        self.cluster.controller.as_ref()
+    }
+
+    /// Find the node with the given id, or `None` if it is not known.
+    pub fn node_by_id(&self, node_id: i32) -> Option<&Node> {
+        self.cluster.node_by_id(node_id)
+    }
+
+    pub fn cluster(&self) -> &Cluster {
+        &self.cluster
+    }
+
+    pub fn request_update(&mut self) {
+        if self.state == State::Quiescent {
+            self.state = State::UpdateRequested;
+            debug!("Requesting metadata update.");
+        }
+    }
+
+    pub fn clear_controller(&mut self) {
+        if let Some(controller) = &self.cluster.controller {
+            trace!("Clearing cached controller node {}.", controller);
+            let cluster = Cluster::new(
+                self.cluster.cluster_id.clone(),
+                self.cluster.nodes.clone(),
+                vec![],
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                None,
+            );
+            self.cluster = cluster;
+        }
+    }
+
+    /// Determine how long we need to wait before fetching new metadata. Returns 0 if we should
+    /// fetch metadata now.
+    pub fn metadata_fetch_delay_ms(&self, now: u128) -> u128 {
+        match self.state {
+            State::Quiescent => {
+                // Calculate the time remaining until the next periodic update.
+                // We want to avoid making many metadata requests in a short amount of time,
+                // so there is a metadata refresh backoff period.
+                self.delay_before_next_expire_ms(now)
+                    .max(self.delay_before_next_attempt_ms(now))
+            }
+            State::UpdateRequested => {
+                // Respect the backoff, even if an update has been requested
+                self.delay_before_next_attempt_ms(now)
+            }
+            // An update is already pending, so we don't need to initiate another one.
+            State::UpdatePending => u128::MAX,
+        }
+    }
+
+    fn delay_before_next_expire_ms(&self, now: u128) -> u128 {
+        let time_since_update = now.saturating_sub(self.last_metadata_update_ms);
+        self.metadata_expire_ms.saturating_sub(time_since_update)
+    }
+
+    fn delay_before_next_attempt_ms(&self, now: u128) -> u128 {
+        let time_since_attempt = now.saturating_sub(self.last_metadata_fetch_attempt_ms);
+        self.refresh_backoff_ms.saturating_sub(time_since_attempt)
+    }
+
+    /// Transition into the UPDATE_PENDING state. Updates lastMetadataFetchAttemptMs.
+    pub fn transition_to_update_pending(&mut self, now: u128) {
+        self.state = State::UpdatePending;
+        self.last_metadata_fetch_attempt_ms = now;
+    }
+
+    pub fn update_failed(&mut self, error: &KafkaError) {
+        // We depend on pending calls to request another metadata update
+        self.state = State::Quiescent;
+        info!("Metadata update failed: {}", error);
+    }
+
+    /// Receive new metadata, and transition into the QUIESCENT state. Updates
+    /// lastMetadataUpdateMs, cluster, and authException.
+    pub fn update(&mut self, cluster: Cluster, now: u128) {
+        if cluster.is_bootstrap_configured {
+            debug!("Setting bootstrap cluster metadata {:?}.", cluster.nodes);
+        } else {
+            debug!("Updating cluster metadata to {:?}", cluster.nodes);
+            self.last_metadata_update_ms = now;
+        }
+
+        self.state = State::Quiescent;
+
+        if !cluster.nodes.is_empty() {
+            self.updater.set_nodes(cluster.nodes.clone());
+            self.cluster = cluster;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        clients::metadata_updater::MetadataUpdater,
+        common::{cluster::Cluster, errors::KafkaError, node::Node},
+    };
+
+    use super::AdminMetadataManager;
+
+    const REFRESH_BACKOFF_MS: u128 = 100;
+    const METADATA_EXPIRE_MS: u128 = 60_000;
+
+    fn cluster(nodes: Vec<Node>) -> Cluster {
+        let controller = nodes.first().cloned();
+        Cluster::new(
+            None,
+            nodes,
+            vec![],
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            controller,
+        )
+    }
+
+    #[test]
+    fn updater_provides_the_nodes_of_the_current_cluster() {
+        let mut manager = AdminMetadataManager::new(REFRESH_BACKOFF_MS, METADATA_EXPIRE_MS);
+        let updater = manager.updater();
+        assert!(updater.fetch_nodes().is_empty());
+
+        let bootstrap = Cluster::bootstrap(&[("localhost".to_owned(), 9092)]);
+        manager.update(bootstrap.clone(), 1000);
+        assert_eq!(updater.fetch_nodes(), bootstrap.nodes);
+        assert!(!manager.is_ready());
+
+        let nodes = vec![Node::new(0, "broker0", 9092), Node::new(1, "broker1", 9092)];
+        manager.update(cluster(nodes.clone()), 2000);
+        assert_eq!(updater.fetch_nodes(), nodes);
+        assert!(manager.is_ready());
+
+        // empty clusters are ignored
+        manager.update(cluster(vec![]), 3000);
+        assert_eq!(updater.fetch_nodes(), nodes);
+    }
+
+    #[test]
+    fn metadata_fetch_delay_respects_expiry_and_backoff() {
+        let mut manager = AdminMetadataManager::new(REFRESH_BACKOFF_MS, METADATA_EXPIRE_MS);
+        manager.update(cluster(vec![Node::new(0, "broker0", 9092)]), 1000);
+        assert_eq!(manager.metadata_fetch_delay_ms(1000), METADATA_EXPIRE_MS);
+
+        manager.transition_to_update_pending(1000);
+        assert_eq!(manager.metadata_fetch_delay_ms(1000), u128::MAX);
+
+        manager.update_failed(&KafkaError::Timeout("timed out".to_owned()));
+        manager.request_update();
+        assert_eq!(manager.metadata_fetch_delay_ms(1050), 50);
+        assert_eq!(manager.metadata_fetch_delay_ms(1100), 0);
+    }
+}
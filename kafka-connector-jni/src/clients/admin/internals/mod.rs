@@ -0,0 +1 @@
+pub mod admin_metadata_manager;
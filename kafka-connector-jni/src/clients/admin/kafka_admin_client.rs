@@ -0,0 +1,3396 @@
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    clients::{
+        api_versions::ApiVersions,
+        client_response::ClientResponse,
+        client_utils,
+        common_client_configs::{
+            BOOTSTRAP_SERVERS_CONFIG, CLIENT_ID_CONFIG, DEFAULT_API_TIMEOUT_MS_CONFIG,
+            METADATA_MAX_AGE_CONFIG, REQUEST_TIMEOUT_MS_CONFIG, RETRIES_CONFIG,
+            RETRY_BACKOFF_MS_CONFIG,
+        },
+        consumer::{
+            internals::consumer_protocol::{self, PROTOCOL_TYPE},
+            offset_and_metadata::OffsetAndMetadata,
+        },
+        kafka_client::KafkaClient,
+    },
+    common::{
+        acl::{acl_binding::AclBinding, acl_binding_filter::AclBindingFilter},
+        cluster::Cluster,
+        config::config_resource::{ConfigResource, ConfigResourceType},
+        consumer_group_state::ConsumerGroupState,
+        errors::{KafkaError, Result},
+        isolation_level::IsolationLevel,
+        kafka_future::KafkaFuture,
+        node::Node,
+        protocol::errors::Errors,
+        requests::{
+            abstract_request::AbstractRequest,
+            abstract_response::AbstractResponse,
+            create_acls_request::CreateAclsRequest,
+            create_partitions_request::{CreatePartitionsRequest, CreatePartitionsTopic},
+            create_topics_request::{CreatableTopic, CreateTopicsRequest},
+            delete_acls_request::DeleteAclsRequest,
+            delete_groups_request::DeleteGroupsRequest,
+            delete_topics_request::DeleteTopicsRequest,
+            describe_acls_request::DescribeAclsRequest,
+            describe_configs_request::{DescribeConfigsRequest, DescribeConfigsResource},
+            describe_configs_response::DescribeConfigsResult as DescribeConfigsResourceResults,
+            describe_groups_request::DescribeGroupsRequest,
+            find_coordinator_request::{CoordinatorType, FindCoordinatorRequest},
+            incremental_alter_configs_request::{AlterableConfig, IncrementalAlterConfigsRequest},
+            join_group_request::{UNKNOWN_GENERATION_ID, UNKNOWN_MEMBER_ID},
+            list_groups_request::ListGroupsRequest,
+            list_offsets_request::{ListOffsetsPartition, ListOffsetsRequest},
+            metadata_request::MetadataRequest,
+            offset_commit_request::OffsetCommitRequest,
+            offset_delete_request::OffsetDeleteRequest,
+            offset_fetch_request::OffsetFetchRequest,
+            txn_offset_commit_request::CommittedOffset,
+        },
+        topic_partition::TopicPartition,
+        topic_partition_info::TopicPartitionInfo,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::{SystemTime, Time},
+        },
+    },
+};
+use indexmap::IndexMap;
+
+use super::{
+    admin_client::Admin,
+    admin_client_config::AdminClientConfig,
+    alter_config_op::AlterConfigOp,
+    config::Config,
+    config_entry::{ConfigEntry, ConfigSource, ConfigSynonym, ConfigType},
+    consumer_group_description::ConsumerGroupDescription,
+    consumer_group_listing::ConsumerGroupListing,
+    internals::admin_metadata_manager::AdminMetadataManager,
+    member_assignment::MemberAssignment,
+    member_description::MemberDescription,
+    new_partitions::NewPartitions,
+    new_topic::NewTopic,
+    offset_spec::OffsetSpec,
+    options::{
+        AlterConfigsOptions, AlterConsumerGroupOffsetsOptions, CreateAclsOptions,
+        CreatePartitionsOptions, CreateTopicsOptions, DeleteAclsOptions,
+        DeleteConsumerGroupOffsetsOptions, DeleteConsumerGroupsOptions, DeleteTopicsOptions,
+        DescribeAclsOptions, DescribeClusterOptions, DescribeConfigsOptions,
+        DescribeConsumerGroupsOptions, DescribeTopicsOptions, ListConsumerGroupOffsetsOptions,
+        ListConsumerGroupsOptions, ListOffsetsOptions, ListTopicsOptions,
+    },
+    results::{
+        AlterConfigsResult, AlterConsumerGroupOffsetsResult, CreateAclsResult,
+        CreatePartitionsResult, CreateTopicsResult, DeleteAclsResult,
+        DeleteConsumerGroupOffsetsResult, DeleteConsumerGroupsResult, DeleteTopicsResult,
+        DescribeAclsResult, DescribeClusterResult, DescribeConfigsResult,
+        DescribeConsumerGroupsResult, DescribeTopicsResult, FilterResult, FilterResults,
+        ListConsumerGroupOffsetsResult, ListConsumerGroupsResult, ListOffsetsResult,
+        ListOffsetsResultInfo, ListTopicsResult, TopicMetadataAndConfig,
+    },
+    topic_description::TopicDescription,
+    topic_listing::TopicListing,
+};
+
+/// The next integer to use to name a KafkaAdminClient which the user hasn't specified an
+/// explicit name for.
+static ADMIN_CLIENT_ID_SEQUENCE: AtomicUsize = AtomicUsize::new(1);
+
+/// The prefix to use for the name of the network thread.
+const NETWORK_THREAD_PREFIX: &str = "kafka-admin-client-thread";
+
+/// The maximum time the admin client thread waits in a single poll.
+const MAX_POLL_TIMEOUT_MS: u128 = 1_200_000;
+
+type CreateRequest = Box<dyn FnMut(i32) -> Result<AbstractRequest> + Send>;
+type HandleResponse = Box<dyn FnMut(AbstractResponse, &mut CallContext<'_>) -> Result<()> + Send>;
+type HandleFailure = Box<dyn FnOnce(KafkaError) + Send>;
+
+/// Get the current time remaining before a deadline as an integer.
+fn calc_timeout_ms_remaining_as_int(now: u128, deadline_ms: u128) -> i32 {
+    let delta_ms = deadline_ms as i128 - now as i128;
+    delta_ms.clamp(i32::MIN as i128, i32::MAX as i128) as i32
+}
+
+fn topic_name_is_unrepresentable(topic_name: &str) -> bool {
+    topic_name.is_empty()
+}
+
+fn unexpected_response(call_name: &str, response: &AbstractResponse) -> KafkaError {
+    KafkaError::IllegalState(format!(
+        "Unexpected {} response to {} call",
+        response.api_key(),
+        call_name
+    ))
+}
+
+/// Unwraps the expected variant of a response, failing the call on any other.
+macro_rules! expect_response {
+    ($response:expr, $variant:ident, $call_name:expr) => {
+        match $response {
+            AbstractResponse::$variant(response) => response,
+            other => return Err(unexpected_response($call_name, &other)),
+        }
+    };
+}
+
+/// Fail futures in the given stream which are not done. Used when a response handler expected
+/// a result for some entity but no result was present.
+fn complete_unrealized_futures<K, V>(
+    futures: &IndexMap<K, KafkaFuture<V>>,
+    message_formatter: impl Fn(&K) -> String,
+) where
+    V: Clone + Send + 'static,
+{
+    for (key, future) in futures {
+        if !future.is_done() {
+            future.complete_exceptionally(KafkaError::Kafka(message_formatter(key)));
+        }
+    }
+}
+
+/// Fail futures in the given collection with the given error.
+fn complete_all_exceptionally<'a, V>(
+    futures: impl IntoIterator<Item = &'a KafkaFuture<V>>,
+    error: KafkaError,
+) where
+    V: Clone + Send + 'static,
+{
+    for future in futures {
+        future.complete_exceptionally(error.clone());
+    }
+}
+
+/// Provides the node a call should be sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeProvider {
+    /// Chooses the least loaded broker, once the cluster metadata is known.
+    LeastLoaded,
+    /// Chooses the controller broker.
+    Controller,
+    /// Chooses a specific broker.
+    ConstantNodeId(i32),
+    /// Chooses the least loaded broker even before the cluster metadata is known, used by the
+    /// metadata fetches themselves.
+    MetadataUpdate,
+}
+
+/// A request to the cluster together with the handling of its outcome.
+struct Call {
+    internal: bool,
+    call_name: &'static str,
+    deadline_ms: u128,
+    node_provider: NodeProvider,
+    tries: i32,
+    aborted: bool,
+    next_allowed_try_ms: u128,
+    cur_node: Option<Node>,
+    create_request: CreateRequest,
+    handle_response: HandleResponse,
+    handle_failure: HandleFailure,
+}
+
+impl Call {
+    /// * `create_request` - Create a request which will be sent to the assigned node, given the
+    ///   request timeout.
+    /// * `handle_response` - Process the call response. Returning an error fails the call, which
+    ///   is then retried if the error is retriable.
+    /// * `handle_failure` - Handle a failure. This will only be called if the failure could not
+    ///   be retried.
+    fn new<C, H, F>(
+        call_name: &'static str,
+        deadline_ms: u128,
+        node_provider: NodeProvider,
+        create_request: C,
+        handle_response: H,
+        handle_failure: F,
+    ) -> Call
+    where
+        C: FnMut(i32) -> Result<AbstractRequest> + Send + 'static,
+        H: FnMut(AbstractResponse, &mut CallContext<'_>) -> Result<()> + Send + 'static,
+        F: FnOnce(KafkaError) + Send + 'static,
+    {
+        Call {
+            internal: false,
+            call_name,
+            deadline_ms,
+            node_provider,
+            tries: 0,
+            aborted: false,
+            next_allowed_try_ms: 0,
+            cur_node: None,
+            create_request: Box::new(create_request),
+            handle_response: Box::new(handle_response),
+            handle_failure: Box::new(handle_failure),
+        }
+    }
+
+    /// Marks the call as internal, those do not keep the client thread alive during a graceful
+    /// shutdown.
+    fn internal(mut self) -> Call {
+        self.internal = true;
+        self
+    }
+}
+
+impl Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Call(callName={}, deadlineMs={}, tries={}, nextAllowedTryMs={})",
+            self.call_name, self.deadline_ms, self.tries, self.next_allowed_try_ms
+        )
+    }
+}
+
+/// What a response handler can act on besides the futures it completes.
+struct CallContext<'a> {
+    log: &'a Logger,
+    now: u128,
+    metadata_manager: &'a mut AdminMetadataManager,
+    new_calls: Vec<Call>,
+    unassign_unsent_calls: bool,
+}
+
+impl CallContext<'_> {
+    /// Schedules a follow-up call.
+    fn call(&mut self, call: Call) {
+        self.new_calls.push(call);
+    }
+}
+
+/// Tracks which calls have timed out and when the next one will.
+struct TimeoutProcessor {
+    /// The current time in milliseconds.
+    now: u128,
+    /// The minimum time until a call is going to be timed out.
+    next_timeout_ms: u128,
+}
+
+impl TimeoutProcessor {
+    fn new(now: u128) -> TimeoutProcessor {
+        TimeoutProcessor {
+            now,
+            next_timeout_ms: u128::MAX,
+        }
+    }
+
+    /// Check whether a call should be timed out. The remaining milliseconds until the next
+    /// timeout will be updated.
+    fn call_has_expired(&mut self, call: &Call) -> bool {
+        let remaining_ms = calc_timeout_ms_remaining_as_int(self.now, call.deadline_ms);
+        if remaining_ms < 0 {
+            return true;
+        }
+        self.next_timeout_ms = min(self.next_timeout_ms, remaining_ms as u128);
+        false
+    }
+}
+
+/// The state shared between the client and its thread.
+struct AdminClientState {
+    /// Calls which have been submitted but not yet picked up by the client thread, `None` once
+    /// the thread exited.
+    new_calls: Option<Vec<Call>>,
+    /// The time at which the client thread gives up on the remaining calls, set once the client
+    /// is closing.
+    hard_shutdown_time_ms: Option<u128>,
+}
+
+fn lock_state(state: &Mutex<AdminClientState>) -> MutexGuard<'_, AdminClientState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The admin client thread, which sends the calls and dispatches their responses.
+struct AdminClientRunnable {
+    log: Logger,
+    client: Arc<dyn KafkaClient>,
+    time: Arc<dyn Time>,
+    metadata_manager: AdminMetadataManager,
+    state: Arc<Mutex<AdminClientState>>,
+    max_retries: i32,
+    retry_backoff_ms: u128,
+    request_timeout_ms: i32,
+    /// Calls which have not yet been assigned to a node.
+    pending_calls: Vec<Call>,
+    /// Maps nodes to calls that we want to send.
+    calls_to_send: IndexMap<Node, VecDeque<Call>>,
+    /// Maps node ID strings to the correlation id of the call in flight to them.
+    calls_in_flight: HashMap<String, i32>,
+    /// Maps correlation IDs to calls that have been sent.
+    correlation_id_to_calls: HashMap<i32, Call>,
+}
+
+impl AdminClientRunnable {
+    fn run(mut self) {
+        self.log.debug(format_args!("Thread starting"));
+        self.process_requests();
+
+        let message = "The AdminClient thread has exited.";
+        let new_calls = lock_state(&self.state).new_calls.take();
+        let pending_calls = std::mem::take(&mut self.pending_calls);
+        let calls_to_send = std::mem::take(&mut self.calls_to_send);
+        let calls_in_flight = std::mem::take(&mut self.correlation_id_to_calls);
+        self.calls_in_flight.clear();
+        let mut num_timed_out = 0;
+        for call in new_calls
+            .into_iter()
+            .flatten()
+            .chain(pending_calls)
+            .chain(calls_to_send.into_iter().flat_map(|(_, calls)| calls))
+            .chain(calls_in_flight.into_values())
+        {
+            self.log
+                .info(format_args!("Timing out {}. {}", call, message));
+            let error = KafkaError::Timeout(format!("{} Call: {}", message, call.call_name));
+            self.handle_failure(call, error);
+            num_timed_out += 1;
+        }
+        if num_timed_out > 0 {
+            self.log.info(format_args!(
+                "Timed out {} remaining operation(s) during close.",
+                num_timed_out
+            ));
+        }
+        self.client.close();
+        self.log
+            .debug(format_args!("Exiting AdminClientRunnable thread."));
+    }
+
+    fn process_requests(&mut self) {
+        let mut now = self.time.milliseconds();
+        loop {
+            // Copy newCalls into pendingCalls.
+            self.drain_new_calls();
+
+            // Check if the AdminClient thread should shut down.
+            let hard_shutdown_time_ms = lock_state(&self.state).hard_shutdown_time_ms;
+            if let Some(hard_shutdown_time_ms) = hard_shutdown_time_ms {
+                if self.thread_should_exit(now, hard_shutdown_time_ms) {
+                    break;
+                }
+            }
+
+            // Handle timeouts.
+            let mut timeout_processor = TimeoutProcessor::new(now);
+            self.timeout_pending_calls(&mut timeout_processor);
+            self.timeout_calls_to_send(&mut timeout_processor);
+            self.timeout_calls_in_flight(&mut timeout_processor);
+
+            // Compute the timeout for the subsequent poll operation.
+            let mut poll_timeout = min(MAX_POLL_TIMEOUT_MS, timeout_processor.next_timeout_ms);
+            if let Some(hard_shutdown_time_ms) = hard_shutdown_time_ms {
+                poll_timeout = min(poll_timeout, hard_shutdown_time_ms.saturating_sub(now));
+            }
+
+            // Choose nodes for our pending calls.
+            poll_timeout = min(poll_timeout, self.maybe_drain_pending_calls(now));
+            let metadata_fetch_delay_ms = self.metadata_manager.metadata_fetch_delay_ms(now);
+            if metadata_fetch_delay_ms == 0 {
+                self.metadata_manager.transition_to_update_pending(now);
+                let metadata_call = self.make_metadata_call(now);
+                // Create a new metadata fetch call and add it to the end of pendingCalls.
+                // Assign a node for just the new call (we handled the other pending nodes above).
+                if let Some(metadata_call) = self.maybe_drain_pending_call(metadata_call, now) {
+                    self.pending_calls.push(metadata_call);
+                }
+            }
+            poll_timeout = min(poll_timeout, self.send_eligible_calls(now));
+
+            if metadata_fetch_delay_ms > 0 {
+                poll_timeout = min(poll_timeout, metadata_fetch_delay_ms);
+            }
+
+            // Ensure that we use a small poll timeout if there are pending calls which need to be
+            // sent
+            if !self.pending_calls.is_empty() {
+                poll_timeout = min(poll_timeout, self.retry_backoff_ms);
+            }
+
+            // Wait for network responses.
+            self.log.trace(format_args!(
+                "Entering KafkaClient#poll(timeout={})",
+                poll_timeout
+            ));
+            let responses = self.client.poll(poll_timeout, now);
+            self.log.trace(format_args!(
+                "KafkaClient#poll retrieved {} response(s)",
+                responses.len()
+            ));
+
+            // unassign calls to disconnected nodes
+            let client = self.client.clone();
+            self.unassign_unsent_calls(|node| client.connection_failed(node));
+
+            // Update the current time and handle the latest responses.
+            now = self.time.milliseconds();
+            self.handle_responses(now, responses);
+        }
+    }
+
+    fn drain_new_calls(&mut self) {
+        let new_calls = match lock_state(&self.state).new_calls.as_mut() {
+            Some(new_calls) => std::mem::take(new_calls),
+            None => return,
+        };
+        if !new_calls.is_empty() {
+            self.log
+                .trace(format_args!("Draining {} newCalls", new_calls.len()));
+        }
+        self.pending_calls.extend(new_calls);
+    }
+
+    /// Return true if there are currently active external calls.
+    fn has_active_external_calls(&self) -> bool {
+        self.pending_calls.iter().any(|call| !call.internal)
+            || self
+                .calls_to_send
+                .values()
+                .flatten()
+                .any(|call| !call.internal)
+            || self
+                .correlation_id_to_calls
+                .values()
+                .any(|call| !call.internal)
+    }
+
+    /// Check whether the client thread should exit.
+    fn thread_should_exit(&self, now: u128, hard_shutdown_time_ms: u128) -> bool {
+        if !self.has_active_external_calls() {
+            self.log.trace(format_args!(
+                "All work has been completed, and the I/O thread is now exiting."
+            ));
+            return true;
+        }
+        if now >= hard_shutdown_time_ms {
+            self.log.info(format_args!(
+                "Forcing a hard I/O thread shutdown. Requests in progress will be aborted."
+            ));
+            return true;
+        }
+        self.log.debug(format_args!(
+            "Hard shutdown in {} ms.",
+            hard_shutdown_time_ms - now
+        ));
+        false
+    }
+
+    /// Fails the calls which have timed out, returning the remaining ones.
+    fn handle_timeouts(
+        &mut self,
+        calls: Vec<Call>,
+        processor: &mut TimeoutProcessor,
+        message: &str,
+    ) -> Vec<Call> {
+        let mut remaining = Vec::with_capacity(calls.len());
+        for call in calls {
+            if processor.call_has_expired(&call) {
+                self.log
+                    .info(format_args!("Timing out {}. {}", call, message));
+                let error = KafkaError::Timeout(format!("{} Call: {}", message, call.call_name));
+                self.handle_failure(call, error);
+            } else {
+                remaining.push(call);
+            }
+        }
+        remaining
+    }
+
+    /// Time out the elements in the pendingCalls list which are expired.
+    fn timeout_pending_calls(&mut self, processor: &mut TimeoutProcessor) {
+        let pending_calls = std::mem::take(&mut self.pending_calls);
+        self.pending_calls = self.handle_timeouts(
+            pending_calls,
+            processor,
+            "Timed out waiting for a node assignment.",
+        );
+    }
+
+    /// Time out calls which have been assigned to nodes.
+    fn timeout_calls_to_send(&mut self, processor: &mut TimeoutProcessor) {
+        for (node, calls) in std::mem::take(&mut self.calls_to_send) {
+            let calls = self.handle_timeouts(
+                calls.into_iter().collect(),
+                processor,
+                "Timed out waiting to send the call.",
+            );
+            self.calls_to_send.insert(node, calls.into_iter().collect());
+        }
+    }
+
+    /// Time out the calls in flight by disconnecting from their nodes, the calls are then failed
+    /// by the next poll.
+    fn timeout_calls_in_flight(&mut self, processor: &mut TimeoutProcessor) {
+        let mut num_timed_out = 0;
+        for (node_id, correlation_id) in &self.calls_in_flight {
+            let call = match self.correlation_id_to_calls.get_mut(correlation_id) {
+                Some(call) => call,
+                None => continue,
+            };
+            if processor.call_has_expired(call) {
+                if call.aborted {
+                    self.log.warn(format_args!(
+                        "Aborted call {} is still in callsInFlight.",
+                        call
+                    ));
+                } else {
+                    self.log.debug(format_args!(
+                        "Closing connection to {} due to timeout while awaiting {}",
+                        node_id, call
+                    ));
+                    call.aborted = true;
+                    self.client.disconnect(node_id);
+                    num_timed_out += 1;
+                    // We don't remove anything from the callsInFlight data structure. Because
+                    // the connection has been closed, the calls should be returned by the next
+                    // client poll, and handled at that point.
+                }
+            }
+        }
+        if num_timed_out > 0 {
+            self.log.debug(format_args!(
+                "Timed out {} call(s) in flight.",
+                num_timed_out
+            ));
+        }
+    }
+
+    /// Choose nodes for the calls in the pendingCalls list.
+    ///
+    /// Returns the minimum time until a call is ready to be retried if any of the pending calls
+    /// are backing off after a failure.
+    fn maybe_drain_pending_calls(&mut self, now: u128) -> u128 {
+        let mut poll_timeout = u128::MAX;
+        self.log.trace(format_args!(
+            "Trying to choose nodes for {} pending calls at {}",
+            self.pending_calls.len(),
+            now
+        ));
+        for call in std::mem::take(&mut self.pending_calls) {
+            // If the call is being retried, await the proper backoff before finding the node
+            if now < call.next_allowed_try_ms {
+                poll_timeout = min(poll_timeout, call.next_allowed_try_ms - now);
+                self.pending_calls.push(call);
+            } else if let Some(call) = self.maybe_drain_pending_call(call, now) {
+                self.pending_calls.push(call);
+            }
+        }
+        poll_timeout
+    }
+
+    /// Check whether a pending call can be assigned a node. Returns the call back if it could
+    /// not.
+    fn maybe_drain_pending_call(&mut self, mut call: Call, now: u128) -> Option<Call> {
+        match self.provide_node(call.node_provider, now) {
+            Some(node) => {
+                self.log
+                    .trace(format_args!("Assigned {} to node {}", call, node));
+                call.cur_node = Some(node.clone());
+                self.calls_to_send.entry(node).or_default().push_back(call);
+                None
+            }
+            None => {
+                self.log
+                    .trace(format_args!("Unable to assign {} to a node.", call));
+                Some(call)
+            }
+        }
+    }
+
+    fn provide_node(&mut self, node_provider: NodeProvider, now: u128) -> Option<Node> {
+        let node = match node_provider {
+            // This may return None if all nodes are busy. In that case, we will postpone node
+            // assignment.
+            NodeProvider::MetadataUpdate => return self.client.least_loaded_node(now),
+            _ if !self.metadata_manager.is_ready() => None,
+            NodeProvider::LeastLoaded => return self.client.least_loaded_node(now),
+            NodeProvider::Controller => self.metadata_manager.controller().cloned(),
+            NodeProvider::ConstantNodeId(node_id) => {
+                self.metadata_manager.node_by_id(node_id).cloned()
+            }
+        };
+        if node.is_none() {
+            self.metadata_manager.request_update();
+        }
+        node
+    }
+
+    /// Send the calls which are ready.
+    ///
+    /// Returns the minimum time until we need to poll again to send more calls.
+    fn send_eligible_calls(&mut self, now: u128) -> u128 {
+        let mut poll_timeout = u128::MAX;
+        let nodes: Vec<Node> = self.calls_to_send.keys().cloned().collect();
+        for node in nodes {
+            let calls = &self.calls_to_send[&node];
+            if calls.is_empty() {
+                self.calls_to_send.shift_remove(&node);
+                continue;
+            }
+            let node_id = node.id_string();
+            if self.calls_in_flight.contains_key(&node_id) {
+                self.log.trace(format_args!(
+                    "Still waiting for other calls to finish on node {}.",
+                    node
+                ));
+                continue;
+            }
+            if !self.client.ready(&node, now) {
+                let node_timeout = self.client.poll_delay_ms(&node, now);
+                poll_timeout = min(poll_timeout, node_timeout);
+                self.log.trace(format_args!(
+                    "Client is not ready to send to {}. Must delay {} ms",
+                    node, node_timeout
+                ));
+                continue;
+            }
+            let mut call = match self
+                .calls_to_send
+                .get_mut(&node)
+                .and_then(VecDeque::pop_front)
+            {
+                Some(call) => call,
+                None => continue,
+            };
+            let request_timeout_ms = min(
+                self.request_timeout_ms,
+                calc_timeout_ms_remaining_as_int(now, call.deadline_ms),
+            );
+            let request = match (call.create_request)(request_timeout_ms) {
+                Ok(request) => request,
+                Err(e) => {
+                    let error = KafkaError::Kafka(format!(
+                        "Internal error sending {} to {}: {}",
+                        call.call_name, node, e
+                    ));
+                    self.fail_call(call, now, error);
+                    continue;
+                }
+            };
+            let client_request = self.client.new_client_request(
+                &node_id,
+                request,
+                now,
+                true,
+                request_timeout_ms.max(0) as u128,
+                None,
+            );
+            let correlation_id = client_request.correlation_id;
+            self.log.debug(format_args!(
+                "Sending {} to {}. correlationId={}, timeoutMs={}",
+                client_request.request, node, correlation_id, request_timeout_ms
+            ));
+            self.client.send(client_request, now);
+            self.calls_in_flight.insert(node_id, correlation_id);
+            self.correlation_id_to_calls.insert(correlation_id, call);
+        }
+        poll_timeout
+    }
+
+    /// Unassign calls that have not yet been sent based on some predicate. For example, this is
+    /// used to reassign the calls that have been assigned to a disconnected node.
+    fn unassign_unsent_calls(&mut self, should_unassign: impl Fn(&Node) -> bool) {
+        for (node, calls) in std::mem::take(&mut self.calls_to_send) {
+            if calls.is_empty() {
+                continue;
+            }
+            if should_unassign(&node) {
+                for mut call in calls {
+                    call.cur_node = None;
+                    self.pending_calls.push(call);
+                }
+            } else {
+                self.calls_to_send.insert(node, calls);
+            }
+        }
+    }
+
+    /// Handle responses from the server.
+    fn handle_responses(&mut self, now: u128, responses: Vec<ClientResponse>) {
+        for response in responses {
+            let correlation_id = response.request_header.correlation_id;
+            let call = match self.correlation_id_to_calls.remove(&correlation_id) {
+                Some(call) => call,
+                None => {
+                    // If the server returns information about a correlation ID we didn't use
+                    // yet, an internal server error has occurred. Close the connection and log
+                    // an error message.
+                    self.log.error(format_args!(
+                        "Internal server error on {}: server returned information about unknown correlation ID {}, requestHeader = {:?}",
+                        response.destination, correlation_id, response.request_header
+                    ));
+                    self.client.disconnect(&response.destination);
+                    continue;
+                }
+            };
+
+            // Stop tracking this call.
+            if self.calls_in_flight.get(&response.destination) == Some(&correlation_id) {
+                self.calls_in_flight.remove(&response.destination);
+            } else {
+                self.log.error(format_args!(
+                    "Internal server error on {}: ignoring call {} in correlationIdToCall that did not exist in callsInFlight",
+                    response.destination, call
+                ));
+                continue;
+            }
+
+            // Handle the result of the call. This may involve retrying the call, if we got a
+            // retriable exception.
+            if let Some(version_mismatch) = response.version_mismatch {
+                self.fail_call(call, now, version_mismatch);
+            } else if response.disconnected {
+                self.metadata_manager.request_update();
+                let error = KafkaError::Network(format!(
+                    "Cancelled {} request with correlation id {} due to node {} being disconnected",
+                    call.call_name, correlation_id, response.destination
+                ));
+                self.fail_call(call, now, error);
+            } else if let Some(response_body) = response.response_body {
+                self.handle_response(call, now, response_body);
+            } else {
+                let error = KafkaError::IllegalState(format!(
+                    "Response for {} with correlation id {} has no body",
+                    call.call_name, correlation_id
+                ));
+                self.fail_call(call, now, error);
+            }
+        }
+    }
+
+    fn handle_response(&mut self, mut call: Call, now: u128, response: AbstractResponse) {
+        let mut context = CallContext {
+            log: &self.log,
+            now,
+            metadata_manager: &mut self.metadata_manager,
+            new_calls: Vec::new(),
+            unassign_unsent_calls: false,
+        };
+        let result = (call.handle_response)(response, &mut context);
+        let CallContext {
+            new_calls,
+            unassign_unsent_calls,
+            ..
+        } = context;
+        match result {
+            Ok(()) => self.log.trace(format_args!("{} got response", call)),
+            Err(e) => {
+                self.log
+                    .trace(format_args!("{} handleResponse failed with {}", call, e));
+                self.fail_call(call, now, e);
+            }
+        }
+        if !new_calls.is_empty() {
+            let accepting = lock_state(&self.state).hard_shutdown_time_ms.is_none();
+            for new_call in new_calls {
+                if accepting {
+                    self.pending_calls.push(new_call);
+                } else {
+                    self.log.debug(format_args!(
+                        "The AdminClient is not accepting new calls. Timing out {}.",
+                        new_call
+                    ));
+                    let error = KafkaError::Timeout(
+                        "The AdminClient thread is not accepting new calls.".to_owned(),
+                    );
+                    self.handle_failure(new_call, error);
+                }
+            }
+        }
+        if unassign_unsent_calls {
+            // Unassign all unsent requests after a metadata refresh to allow for a new
+            // destination to be selected from the new metadata
+            self.unassign_unsent_calls(|_| true);
+        }
+    }
+
+    /// Handle a failure.
+    ///
+    /// Depending on what the exception is and how many times we have already tried, we may
+    /// choose to fail the Call, or retry it. It is important to print the stack traces here in
+    /// some cases, since they are not necessarily preserved in ApiVersionException objects.
+    fn fail_call(&mut self, mut call: Call, now: u128, error: KafkaError) {
+        if call.aborted {
+            // If the call was aborted while in flight due to a timeout, deliver a Timeout. In
+            // this case, we do not get any more retries - the call has failed. We increment
+            // tries anyway in order to display an accurate log message.
+            call.tries += 1;
+            self.fail_with_timeout(call, now, error);
+            return;
+        }
+        call.tries += 1;
+        call.next_allowed_try_ms = now + self.retry_backoff_ms;
+
+        // If the call has timed out, fail.
+        if calc_timeout_ms_remaining_as_int(now, call.deadline_ms) < 0 {
+            self.fail_with_timeout(call, now, error);
+            return;
+        }
+        // If the exception is not retriable, fail.
+        if !error.is_retriable() {
+            self.log.debug(format_args!(
+                "{} failed with non-retriable exception after {} attempt(s): {}",
+                call, call.tries, error
+            ));
+            self.handle_failure(call, error);
+            return;
+        }
+        // If we are out of retries, fail.
+        if call.tries > self.max_retries {
+            self.fail_with_timeout(call, now, error);
+            return;
+        }
+        self.log.debug(format_args!(
+            "{} failed: {}. Beginning retry #{}",
+            call, error, call.tries
+        ));
+        call.cur_node = None;
+        self.pending_calls.push(call);
+    }
+
+    fn fail_with_timeout(&mut self, call: Call, now: u128, cause: KafkaError) {
+        self.log.debug(format_args!(
+            "{} timed out at {} after {} attempt(s): {}",
+            call, now, call.tries, cause
+        ));
+        let error = KafkaError::Timeout(format!(
+            "{} timed out at {} after {} attempt(s)",
+            call, now, call.tries
+        ));
+        self.handle_failure(call, error);
+    }
+
+    fn handle_failure(&mut self, call: Call, error: KafkaError) {
+        if call.node_provider == NodeProvider::MetadataUpdate {
+            // The metadata manager is owned by this thread, so the metadata call reports its
+            // failures here.
+            self.metadata_manager.update_failed(&error);
+        }
+        (call.handle_failure)(error);
+    }
+
+    /// Create a new metadata call.
+    fn make_metadata_call(&self, now: u128) -> Call {
+        let deadline_ms = now + self.request_timeout_ms.max(0) as u128;
+        Call::new(
+            "fetchMetadata",
+            deadline_ms,
+            NodeProvider::MetadataUpdate,
+            |_| {
+                // Since this only requests node information, it's safe to pass true for
+                // allowAutoTopicCreation (and it simplifies communication with older brokers)
+                Ok(AbstractRequest::Metadata(MetadataRequest::new(
+                    vec![],
+                    true,
+                )))
+            },
+            |response, context| {
+                let response = expect_response!(response, Metadata, "fetchMetadata");
+                let now = context.now;
+                context
+                    .metadata_manager
+                    .update(response.build_cluster(), now);
+                // Unassign all unsent requests after a metadata refresh to allow for a new
+                // destination to be selected from the new metadata
+                context.unassign_unsent_calls = true;
+                Ok(())
+            },
+            |_| {},
+        )
+        .internal()
+    }
+}
+
+/// The context for the operations on a single consumer group: they locate the group
+/// coordinator first and then send their request to it.
+#[derive(Clone)]
+struct ConsumerGroupOperationContext<T, D> {
+    group_id: String,
+    /// What the operation needs to build its request.
+    data: D,
+    deadline_ms: u128,
+    future: KafkaFuture<T>,
+    node: Option<Node>,
+}
+
+type NextGroupCall<T, D> = fn(ConsumerGroupOperationContext<T, D>) -> Call;
+
+/// Handles the group level error of a response. Coordinator loading errors are returned so
+/// that the call is retried, others fail the future.
+///
+/// Returns true if the future was failed.
+fn handle_group_request_error<T>(error: Errors, future: &KafkaFuture<T>) -> Result<bool>
+where
+    T: Clone + Send + 'static,
+{
+    match error.exception(None) {
+        None => Ok(false),
+        Some(e @ KafkaError::CoordinatorLoadInProgress(_))
+        | Some(e @ KafkaError::CoordinatorNotAvailable(_)) => Err(e),
+        Some(e) => {
+            future.complete_exceptionally(e);
+            Ok(true)
+        }
+    }
+}
+
+/// Returns a call locating the coordinator of the context group, which then schedules
+/// `next_call` against it.
+fn find_coordinator_call<T, D>(
+    context: ConsumerGroupOperationContext<T, D>,
+    next_call: NextGroupCall<T, D>,
+) -> Call
+where
+    T: Clone + Send + 'static,
+    D: Clone + Send + 'static,
+{
+    let group_id = context.group_id.clone();
+    let failure_future = context.future.clone();
+    Call::new(
+        "findCoordinator",
+        context.deadline_ms,
+        NodeProvider::LeastLoaded,
+        move |_| {
+            Ok(AbstractRequest::FindCoordinator(
+                FindCoordinatorRequest::new(group_id.clone(), CoordinatorType::Group),
+            ))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, FindCoordinator, "findCoordinator");
+            if handle_group_request_error(response.error, &context.future)? {
+                return Ok(());
+            }
+            let mut context = context.clone();
+            context.node = Some(response.node);
+            call_context.call(next_call(context));
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+fn reschedule_find_coordinator_task<T, D>(
+    mut context: ConsumerGroupOperationContext<T, D>,
+    next_call: NextGroupCall<T, D>,
+    call_context: &mut CallContext<'_>,
+) where
+    T: Clone + Send + 'static,
+    D: Clone + Send + 'static,
+{
+    call_context.log.info(format_args!(
+        "Node {} is no longer the Coordinator. Retrying with new coordinator.",
+        context
+            .node
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_else(|| "null".to_owned())
+    ));
+    // Requeue the task so that we can try with new coordinator
+    context.node = None;
+    call_context.call(find_coordinator_call(context, next_call));
+}
+
+/// The id of the coordinator the context has located.
+fn coordinator_id<T, D>(context: &ConsumerGroupOperationContext<T, D>) -> i32 {
+    context.node.as_ref().map_or(-1, |node| node.id)
+}
+
+fn describe_consumer_groups_call(
+    context: ConsumerGroupOperationContext<ConsumerGroupDescription, ()>,
+) -> Call {
+    let group_id = context.group_id.clone();
+    let failure_future = context.future.clone();
+    Call::new(
+        "describeConsumerGroups",
+        context.deadline_ms,
+        NodeProvider::ConstantNodeId(coordinator_id(&context)),
+        move |_| {
+            Ok(AbstractRequest::DescribeGroups(DescribeGroupsRequest::new(
+                vec![group_id.clone()],
+                false,
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, DescribeGroups, "describeConsumerGroups");
+            let described_groups = response.groups;
+            if described_groups.is_empty() {
+                context
+                    .future
+                    .complete_exceptionally(KafkaError::InvalidGroupId(format!(
+                        "No consumer group found for GroupId: {}",
+                        context.group_id
+                    )));
+                return Ok(());
+            }
+            if described_groups.len() > 1 || described_groups[0].group_id != context.group_id {
+                let ids: Vec<&str> = described_groups
+                    .iter()
+                    .map(|group| group.group_id.as_str())
+                    .collect();
+                context
+                    .future
+                    .complete_exceptionally(KafkaError::InvalidGroupId(format!(
+                        "DescribeConsumerGroup request for GroupId: {} returned {:?}",
+                        context.group_id, ids
+                    )));
+                return Ok(());
+            }
+            let described_group = &described_groups[0];
+
+            // If coordinator changed since we fetched it, retry
+            if described_group.error == Errors::NotCoordinator {
+                reschedule_find_coordinator_task(
+                    context.clone(),
+                    describe_consumer_groups_call,
+                    call_context,
+                );
+                return Ok(());
+            }
+            if handle_group_request_error(described_group.error, &context.future)? {
+                return Ok(());
+            }
+
+            let protocol_type = &described_group.protocol_type;
+            if protocol_type == PROTOCOL_TYPE || protocol_type.is_empty() {
+                let mut member_descriptions = Vec::with_capacity(described_group.members.len());
+                for group_member in &described_group.members {
+                    let mut partitions = HashSet::new();
+                    if !group_member.member_assignment.is_empty() {
+                        match consumer_protocol::deserialize_assignment(
+                            group_member.member_assignment.clone(),
+                        ) {
+                            Ok(assignment) => partitions.extend(assignment.partitions),
+                            Err(e) => {
+                                context.future.complete_exceptionally(e);
+                                return Ok(());
+                            }
+                        }
+                    }
+                    member_descriptions.push(MemberDescription::new(
+                        group_member.member_id.clone(),
+                        group_member.group_instance_id.clone(),
+                        group_member.client_id.clone(),
+                        group_member.client_host.clone(),
+                        MemberAssignment::new(partitions),
+                    ));
+                }
+                let coordinator = context.node.clone().unwrap_or_else(Node::no_node);
+                context.future.complete(ConsumerGroupDescription::new(
+                    context.group_id.clone(),
+                    protocol_type.is_empty(),
+                    member_descriptions,
+                    described_group.protocol_data.clone(),
+                    ConsumerGroupState::parse(&described_group.group_state),
+                    coordinator,
+                ));
+            } else {
+                context
+                    .future
+                    .complete_exceptionally(KafkaError::IllegalArgument(format!(
+                        "GroupId {} is not a consumer group ({}).",
+                        context.group_id, protocol_type
+                    )));
+            }
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+fn delete_consumer_groups_call(context: ConsumerGroupOperationContext<(), ()>) -> Call {
+    let group_id = context.group_id.clone();
+    let failure_future = context.future.clone();
+    Call::new(
+        "deleteConsumerGroups",
+        context.deadline_ms,
+        NodeProvider::ConstantNodeId(coordinator_id(&context)),
+        move |_| {
+            Ok(AbstractRequest::DeleteGroups(DeleteGroupsRequest::new(
+                vec![group_id.clone()],
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, DeleteGroups, "deleteConsumerGroups");
+
+            // If coordinator changed since we fetched it, retry
+            if response
+                .results
+                .values()
+                .any(|e| *e == Errors::NotCoordinator)
+            {
+                reschedule_find_coordinator_task(
+                    context.clone(),
+                    delete_consumer_groups_call,
+                    call_context,
+                );
+                return Ok(());
+            }
+
+            let group_error = match response.results.get(&context.group_id) {
+                Some(group_error) => *group_error,
+                None => {
+                    return Err(KafkaError::IllegalArgument(format!(
+                        "could not find group {} in the delete group response",
+                        context.group_id
+                    )))
+                }
+            };
+            if handle_group_request_error(group_error, &context.future)? {
+                return Ok(());
+            }
+            context.future.complete(());
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+type ListGroupOffsetsContext = ConsumerGroupOperationContext<
+    IndexMap<TopicPartition, Option<OffsetAndMetadata>>,
+    Option<Vec<TopicPartition>>,
+>;
+
+fn list_consumer_group_offsets_call(context: ListGroupOffsetsContext) -> Call {
+    let group_id = context.group_id.clone();
+    let topic_partitions = context.data.clone();
+    let failure_future = context.future.clone();
+    Call::new(
+        "listConsumerGroupOffsets",
+        context.deadline_ms,
+        NodeProvider::ConstantNodeId(coordinator_id(&context)),
+        move |_| {
+            // Set the flag to false as for admin client request, we don't need to wait for any
+            // pending offset state to clear.
+            Ok(AbstractRequest::OffsetFetch(OffsetFetchRequest::new(
+                group_id.clone(),
+                false,
+                topic_partitions.clone(),
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, OffsetFetch, "listConsumerGroupOffsets");
+
+            // If coordinator changed since we fetched it, retry
+            if response.error == Errors::NotCoordinator
+                || response
+                    .response_data
+                    .values()
+                    .any(|data| data.error == Errors::NotCoordinator)
+            {
+                reschedule_find_coordinator_task(
+                    context.clone(),
+                    list_consumer_group_offsets_call,
+                    call_context,
+                );
+                return Ok(());
+            }
+            if handle_group_request_error(response.error, &context.future)? {
+                return Ok(());
+            }
+
+            let mut group_offsets_listing = IndexMap::new();
+            for (topic_partition, partition_data) in response.response_data {
+                if partition_data.error == Errors::None {
+                    // Negative offset indicates that the group has no committed offset for this
+                    // partition
+                    let offset_and_metadata = if partition_data.offset < 0 {
+                        None
+                    } else {
+                        Some(OffsetAndMetadata::new(
+                            partition_data.offset,
+                            partition_data.leader_epoch,
+                            partition_data.metadata.unwrap_or_default(),
+                        ))
+                    };
+                    group_offsets_listing.insert(topic_partition, offset_and_metadata);
+                } else {
+                    call_context.log.warn(format_args!(
+                        "Skipping return offset for {} due to error {:?}.",
+                        topic_partition, partition_data.error
+                    ));
+                }
+            }
+            context.future.complete(group_offsets_listing);
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+type DeleteGroupOffsetsContext =
+    ConsumerGroupOperationContext<IndexMap<TopicPartition, Errors>, Vec<TopicPartition>>;
+
+fn delete_consumer_group_offsets_call(context: DeleteGroupOffsetsContext) -> Call {
+    let group_id = context.group_id.clone();
+    let partitions = context.data.clone();
+    let failure_future = context.future.clone();
+    Call::new(
+        "deleteConsumerGroupOffsets",
+        context.deadline_ms,
+        NodeProvider::ConstantNodeId(coordinator_id(&context)),
+        move |_| {
+            Ok(AbstractRequest::OffsetDelete(OffsetDeleteRequest::new(
+                group_id.clone(),
+                partitions.clone(),
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, OffsetDelete, "deleteConsumerGroupOffsets");
+
+            // If coordinator changed since we fetched it, retry
+            if response.error == Errors::NotCoordinator
+                || response
+                    .partitions
+                    .values()
+                    .any(|e| *e == Errors::NotCoordinator)
+            {
+                reschedule_find_coordinator_task(
+                    context.clone(),
+                    delete_consumer_group_offsets_call,
+                    call_context,
+                );
+                return Ok(());
+            }
+
+            // If the error is an error at the group level, the future is failed with it
+            if handle_group_request_error(response.error, &context.future)? {
+                return Ok(());
+            }
+            context.future.complete(response.partitions);
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+type AlterGroupOffsetsContext = ConsumerGroupOperationContext<
+    IndexMap<TopicPartition, Errors>,
+    IndexMap<TopicPartition, OffsetAndMetadata>,
+>;
+
+fn alter_consumer_group_offsets_call(context: AlterGroupOffsetsContext) -> Call {
+    let group_id = context.group_id.clone();
+    let offsets: IndexMap<TopicPartition, CommittedOffset> = context
+        .data
+        .iter()
+        .map(|(tp, offset)| {
+            (
+                tp.clone(),
+                CommittedOffset::new(offset.offset, offset.metadata.clone(), offset.leader_epoch),
+            )
+        })
+        .collect();
+    let failure_future = context.future.clone();
+    Call::new(
+        "commitOffsets",
+        context.deadline_ms,
+        NodeProvider::ConstantNodeId(coordinator_id(&context)),
+        move |_| {
+            Ok(AbstractRequest::OffsetCommit(OffsetCommitRequest::new(
+                group_id.clone(),
+                UNKNOWN_GENERATION_ID,
+                UNKNOWN_MEMBER_ID,
+                None,
+                offsets.clone(),
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, OffsetCommit, "commitOffsets");
+
+            // If coordinator changed since we fetched it, retry
+            if response.errors.values().any(|error| {
+                *error == Errors::NotCoordinator || *error == Errors::CoordinatorNotAvailable
+            }) {
+                reschedule_find_coordinator_task(
+                    context.clone(),
+                    alter_consumer_group_offsets_call,
+                    call_context,
+                );
+                return Ok(());
+            }
+            context.future.complete(response.errors);
+            Ok(())
+        },
+        move |error| {
+            failure_future.complete_exceptionally(error);
+        },
+    )
+}
+
+/// Returns the node the config resource has to be sent to, `None` for the resources which can
+/// be handled by any broker.
+fn node_for(resource: &ConfigResource) -> Result<Option<i32>> {
+    let broker_specific = (resource.type_ == ConfigResourceType::Broker && !resource.is_default())
+        || resource.type_ == ConfigResourceType::BrokerLogger;
+    if !broker_specific {
+        return Ok(None);
+    }
+    resource.name.parse().map(Some).map_err(|_| {
+        KafkaError::InvalidRequest(format!(
+            "Invalid broker id {} of config resource {}",
+            resource.name, resource
+        ))
+    })
+}
+
+/// Groups the futures of the config resources by the node their request is sent to.
+fn group_by_node<V>(
+    futures: &IndexMap<ConfigResource, KafkaFuture<V>>,
+) -> IndexMap<Option<i32>, IndexMap<ConfigResource, KafkaFuture<V>>>
+where
+    V: Clone + Send + 'static,
+{
+    let mut grouped: IndexMap<Option<i32>, IndexMap<ConfigResource, KafkaFuture<V>>> =
+        IndexMap::new();
+    for (resource, future) in futures {
+        match node_for(resource) {
+            Ok(node) => {
+                grouped
+                    .entry(node)
+                    .or_default()
+                    .insert(resource.clone(), future.clone());
+            }
+            Err(e) => {
+                future.complete_exceptionally(e);
+            }
+        }
+    }
+    grouped
+}
+
+fn node_provider_for(node: Option<i32>) -> NodeProvider {
+    match node {
+        Some(node_id) => NodeProvider::ConstantNodeId(node_id),
+        None => NodeProvider::LeastLoaded,
+    }
+}
+
+fn describe_config_result(result: DescribeConfigsResourceResults) -> Config {
+    Config::new(
+        result
+            .configs
+            .into_iter()
+            .map(|config| ConfigEntry {
+                name: config.name,
+                value: config.value,
+                source: ConfigSource::for_id(config.config_source),
+                is_sensitive: config.is_sensitive,
+                is_read_only: config.read_only,
+                synonyms: config
+                    .synonyms
+                    .into_iter()
+                    .map(|synonym| ConfigSynonym {
+                        name: synonym.name,
+                        value: synonym.value,
+                        source: ConfigSource::for_id(synonym.source),
+                    })
+                    .collect(),
+                type_: ConfigType::for_id(config.config_type),
+                documentation: config.documentation,
+            })
+            .collect(),
+    )
+}
+
+/// Results of the ListGroups requests sent to all brokers, completed once every broker
+/// answered.
+struct ListConsumerGroupsResults {
+    errors: Vec<KafkaError>,
+    listings: IndexMap<String, ConsumerGroupListing>,
+    remaining: HashSet<Node>,
+    future: KafkaFuture<Vec<Result<ConsumerGroupListing>>>,
+}
+
+impl ListConsumerGroupsResults {
+    fn add_error(&mut self, error: KafkaError) {
+        self.errors.push(error);
+    }
+
+    fn add_listing(&mut self, listing: ConsumerGroupListing) {
+        self.listings.insert(listing.group_id.clone(), listing);
+    }
+
+    fn try_complete(&mut self, leader: &Node) {
+        self.remaining.remove(leader);
+        if self.remaining.is_empty() {
+            let results = self
+                .listings
+                .values()
+                .cloned()
+                .map(Ok)
+                .chain(self.errors.iter().cloned().map(Err))
+                .collect();
+            self.future.complete(results);
+        }
+    }
+}
+
+fn list_consumer_groups_call(
+    node: Node,
+    states: Vec<String>,
+    deadline_ms: u128,
+    results: Arc<Mutex<ListConsumerGroupsResults>>,
+) -> Call {
+    let failure_node = node.clone();
+    let failure_results = results.clone();
+    Call::new(
+        "listConsumerGroups",
+        deadline_ms,
+        NodeProvider::ConstantNodeId(node.id),
+        move |_| {
+            Ok(AbstractRequest::ListGroups(ListGroupsRequest::new(
+                states.clone(),
+            )))
+        },
+        move |response, _| {
+            let response = expect_response!(response, ListGroups, "listConsumerGroups");
+            let mut results = results.lock().unwrap_or_else(|e| e.into_inner());
+            match response.error.exception(None) {
+                Some(e @ KafkaError::CoordinatorLoadInProgress(_))
+                | Some(e @ KafkaError::CoordinatorNotAvailable(_)) => return Err(e),
+                Some(_) => {
+                    let message = format!("Error listing groups on {}", node);
+                    if let Some(error) = response.error.exception(Some(&message)) {
+                        results.add_error(error);
+                    }
+                }
+                None => {
+                    for group in response.groups {
+                        let protocol_type = group.protocol_type;
+                        if protocol_type == PROTOCOL_TYPE || protocol_type.is_empty() {
+                            let state = group
+                                .group_state
+                                .filter(|state| !state.is_empty())
+                                .map(|state| ConsumerGroupState::parse(&state));
+                            results.add_listing(ConsumerGroupListing::new(
+                                group.group_id,
+                                protocol_type.is_empty(),
+                                state,
+                            ));
+                        }
+                    }
+                }
+            }
+            results.try_complete(&node);
+            Ok(())
+        },
+        move |error| {
+            let mut results = failure_results.lock().unwrap_or_else(|e| e.into_inner());
+            results.add_error(error);
+            results.try_complete(&failure_node);
+        },
+    )
+}
+
+/// Returns a call fetching the metadata of the topics to query, which then schedules the
+/// ListOffsets requests to their leaders.
+fn list_offsets_metadata_call(
+    topic_partition_offsets: IndexMap<TopicPartition, OffsetSpec>,
+    isolation_level: IsolationLevel,
+    deadline_ms: u128,
+    futures: IndexMap<TopicPartition, KafkaFuture<ListOffsetsResultInfo>>,
+) -> Call {
+    let mut topics: Vec<String> = Vec::new();
+    for tp in topic_partition_offsets.keys() {
+        if !topics.contains(&tp.topic) {
+            topics.push(tp.topic.clone());
+        }
+    }
+    let failure_futures: Vec<_> = topic_partition_offsets
+        .keys()
+        .filter_map(|tp| futures.get(tp).cloned())
+        .collect();
+    Call::new(
+        "metadata",
+        deadline_ms,
+        NodeProvider::LeastLoaded,
+        move |_| {
+            Ok(AbstractRequest::Metadata(MetadataRequest::new(
+                topics.clone(),
+                false,
+            )))
+        },
+        move |response, call_context| {
+            let response = expect_response!(response, Metadata, "metadata");
+            for topic in &response.topics {
+                for partition in &topic.partitions {
+                    if let Some(error) = partition.error.exception(None) {
+                        if error.is_invalid_metadata() {
+                            return Err(error);
+                        }
+                    }
+                }
+            }
+            let cluster = response.build_cluster();
+            let errors = response.errors();
+            for call in list_offsets_calls(
+                &cluster,
+                &errors,
+                &topic_partition_offsets,
+                isolation_level,
+                deadline_ms,
+                &futures,
+            ) {
+                call_context.call(call);
+            }
+            Ok(())
+        },
+        move |error| complete_all_exceptionally(&failure_futures, error),
+    )
+}
+
+fn list_offsets_calls(
+    cluster: &Cluster,
+    errors: &IndexMap<String, Errors>,
+    topic_partition_offsets: &IndexMap<TopicPartition, OffsetSpec>,
+    isolation_level: IsolationLevel,
+    deadline_ms: u128,
+    futures: &IndexMap<TopicPartition, KafkaFuture<ListOffsetsResultInfo>>,
+) -> Vec<Call> {
+    // grouping topic partitions per leader
+    let mut leaders: IndexMap<Node, IndexMap<TopicPartition, OffsetSpec>> = IndexMap::new();
+    for (tp, offset_spec) in topic_partition_offsets {
+        let future = match futures.get(tp) {
+            Some(future) => future,
+            None => continue,
+        };
+        // avoid sending listOffsets request for topics with errors
+        match errors
+            .get(&tp.topic)
+            .and_then(|error| error.exception(None))
+        {
+            Some(error) => {
+                future.complete_exceptionally(error);
+            }
+            None => match cluster.leader_for(tp) {
+                Some(node) => {
+                    leaders
+                        .entry(node.clone())
+                        .or_default()
+                        .insert(tp.clone(), *offset_spec);
+                }
+                None => {
+                    if let Some(error) = Errors::LeaderNotAvailable.exception(None) {
+                        future.complete_exceptionally(error);
+                    }
+                }
+            },
+        }
+    }
+
+    let mut calls = Vec::with_capacity(leaders.len());
+    for (node, partitions_to_query) in leaders {
+        let broker_id = node.id;
+        let request_partitions: IndexMap<TopicPartition, ListOffsetsPartition> =
+            partitions_to_query
+                .iter()
+                .map(|(tp, offset_spec)| {
+                    (
+                        tp.clone(),
+                        ListOffsetsPartition::new(offset_spec.timestamp(), None),
+                    )
+                })
+                .collect();
+        let futures = futures.clone();
+        let failure_futures: Vec<_> = partitions_to_query
+            .keys()
+            .filter_map(|tp| futures.get(tp).cloned())
+            .collect();
+        calls.push(Call::new(
+            "listOffsets",
+            deadline_ms,
+            NodeProvider::ConstantNodeId(broker_id),
+            move |_| {
+                Ok(AbstractRequest::ListOffsets(ListOffsetsRequest::for_consumer(
+                    isolation_level,
+                    request_partitions.clone(),
+                )))
+            },
+            move |response, call_context| {
+                let response = expect_response!(response, ListOffsets, "listOffsets");
+                let mut retry_topic_partition_offsets = IndexMap::new();
+                for (tp, partition) in response.partitions {
+                    let offset_request_spec = match partitions_to_query.get(&tp) {
+                        Some(offset_request_spec) => *offset_request_spec,
+                        None => {
+                            call_context.log.warn(format_args!("Server response mentioned unknown topic partition {}", tp));
+                            continue;
+                        }
+                    };
+                    let future = match futures.get(&tp) {
+                        Some(future) => future,
+                        None => continue,
+                    };
+                    match partition.error.exception(None) {
+                        Some(error) if error.is_invalid_metadata() => {
+                            retry_topic_partition_offsets.insert(tp, offset_request_spec);
+                        }
+                        Some(error) => {
+                            future.complete_exceptionally(error);
+                        }
+                        None => {
+                            future.complete(ListOffsetsResultInfo {
+                                offset: partition.offset,
+                                timestamp: partition.timestamp,
+                                leader_epoch: partition.leader_epoch,
+                            });
+                        }
+                    }
+                }
+
+                if retry_topic_partition_offsets.is_empty() {
+                    // The server should send back a response for every topic partition. But do a
+                    // sanity check anyway.
+                    for tp in partitions_to_query.keys() {
+                        if let Some(future) = futures.get(tp) {
+                            if !future.is_done() {
+                                future.complete_exceptionally(KafkaError::Kafka(format!(
+                                    "The response from broker {} did not contain a result for topic partition {}",
+                                    broker_id, tp
+                                )));
+                            }
+                        }
+                    }
+                } else {
+                    call_context.log.info(format_args!("Retrying to fetch metadata."));
+                    // Requeue the task so that we can re-attempt fetching metadata
+                    call_context.call(list_offsets_metadata_call(
+                        retry_topic_partition_offsets,
+                        isolation_level,
+                        deadline_ms,
+                        futures.clone(),
+                    ));
+                }
+                Ok(())
+            },
+            move |error| complete_all_exceptionally(&failure_futures, error),
+        ));
+    }
+    calls
+}
+
+/// The default implementation of `Admin`. An instance of this class is created by invoking
+/// `KafkaAdminClient::create`.
+///
+/// This class is thread-safe.
+pub struct KafkaAdminClient {
+    log: Logger,
+    client_id: String,
+    time: Arc<dyn Time>,
+    /// The network client to use.
+    client: Arc<dyn KafkaClient>,
+    /// The default timeout to use for an operation.
+    default_api_timeout_ms: i32,
+    max_retries: i32,
+    state: Arc<Mutex<AdminClientState>>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl KafkaAdminClient {
+    /// Creates the admin client from its configuration, with a network client connecting to the
+    /// bootstrap servers.
+    pub fn create(config: &AdminClientConfig) -> Result<KafkaAdminClient> {
+        let client_id = KafkaAdminClient::generate_client_id(config)?;
+        let log_context = KafkaAdminClient::create_log_context(&client_id);
+        let time: Arc<dyn Time> = Arc::new(SystemTime);
+        let mut metadata_manager = AdminMetadataManager::new(
+            config
+                .get_long(RETRY_BACKOFF_MS_CONFIG)?
+                .unwrap_or_default()
+                .max(0) as u128,
+            config
+                .get_long(METADATA_MAX_AGE_CONFIG)?
+                .unwrap_or_default()
+                .max(0) as u128,
+        );
+        let addresses = client_utils::parse_and_validate_addresses(
+            &config
+                .get_list(BOOTSTRAP_SERVERS_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        metadata_manager.update(Cluster::bootstrap(&addresses), time.milliseconds());
+        let client = Arc::new(client_utils::create_network_client(
+            config,
+            &log_context,
+            Arc::new(ApiVersions::new()),
+            time.clone(),
+            1,
+            metadata_manager.updater(),
+        )?);
+        KafkaAdminClient::create_internal(config, client_id, metadata_manager, client, time)
+    }
+
+    /// Creates the admin client from its configuration, sending its requests through `client`
+    /// and starting from the cluster known to `metadata_manager`.
+    pub fn create_internal(
+        config: &AdminClientConfig,
+        client_id: impl Into<String>,
+        metadata_manager: AdminMetadataManager,
+        client: Arc<dyn KafkaClient>,
+        time: Arc<dyn Time>,
+    ) -> Result<KafkaAdminClient> {
+        let client_id = client_id.into();
+        let log = KafkaAdminClient::create_log_context(&client_id).logger(module_path!());
+        let retry_backoff_ms = config
+            .get_long(RETRY_BACKOFF_MS_CONFIG)?
+            .unwrap_or_default()
+            .max(0) as u128;
+        let request_timeout_ms = config
+            .get_int(REQUEST_TIMEOUT_MS_CONFIG)?
+            .unwrap_or_default();
+        let default_api_timeout_ms =
+            KafkaAdminClient::configure_default_api_timeout_ms(config, &log)?;
+        let max_retries = config.get_int(RETRIES_CONFIG)?.unwrap_or_default();
+        let admin_client = KafkaAdminClient::new(
+            client_id,
+            client,
+            metadata_manager,
+            time,
+            default_api_timeout_ms,
+            request_timeout_ms,
+            retry_backoff_ms,
+            max_retries,
+        )?;
+        config.log_unused();
+        Ok(admin_client)
+    }
+
+    /// Creates the admin client and starts its thread.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client_id: impl Into<String>,
+        client: Arc<dyn KafkaClient>,
+        metadata_manager: AdminMetadataManager,
+        time: Arc<dyn Time>,
+        default_api_timeout_ms: i32,
+        request_timeout_ms: i32,
+        retry_backoff_ms: u128,
+        max_retries: i32,
+    ) -> Result<KafkaAdminClient> {
+        let client_id = client_id.into();
+        let log = KafkaAdminClient::create_log_context(&client_id).logger(module_path!());
+        let state = Arc::new(Mutex::new(AdminClientState {
+            new_calls: Some(Vec::new()),
+            hard_shutdown_time_ms: None,
+        }));
+        let runnable = AdminClientRunnable {
+            log: log.clone(),
+            client: client.clone(),
+            time: time.clone(),
+            metadata_manager,
+            state: state.clone(),
+            max_retries,
+            retry_backoff_ms,
+            request_timeout_ms,
+            pending_calls: Vec::new(),
+            calls_to_send: IndexMap::new(),
+            calls_in_flight: HashMap::new(),
+            correlation_id_to_calls: HashMap::new(),
+        };
+        let thread_name = format!("{} | {}", NETWORK_THREAD_PREFIX, client_id);
+        let thread = thread::Builder::new()
+            .name(thread_name)
+            .spawn(move || runnable.run())
+            .map_err(|e| {
+                KafkaError::Kafka(format!("Failed to start the admin client thread: {}", e))
+            })?;
+        log.debug(format_args!("Kafka admin client initialized"));
+        Ok(KafkaAdminClient {
+            log,
+            client_id,
+            time,
+            client,
+            default_api_timeout_ms,
+            max_retries,
+            state,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    fn generate_client_id(config: &AdminClientConfig) -> Result<String> {
+        let client_id = config.get_string(CLIENT_ID_CONFIG)?.unwrap_or_default();
+        if !client_id.is_empty() {
+            return Ok(client_id);
+        }
+        Ok(format!(
+            "adminclient-{}",
+            ADMIN_CLIENT_ID_SEQUENCE.fetch_add(1, Ordering::SeqCst)
+        ))
+    }
+
+    fn create_log_context(client_id: &str) -> LogContext {
+        LogContext::new(format!("[AdminClient clientId={}] ", client_id))
+    }
+
+    fn configure_default_api_timeout_ms(config: &AdminClientConfig, log: &Logger) -> Result<i32> {
+        let request_timeout_ms = config
+            .get_int(REQUEST_TIMEOUT_MS_CONFIG)?
+            .unwrap_or_default();
+        let default_api_timeout_ms = config
+            .get_int(DEFAULT_API_TIMEOUT_MS_CONFIG)?
+            .unwrap_or_default();
+        if default_api_timeout_ms < request_timeout_ms {
+            if config
+                .originals()
+                .contains_key(DEFAULT_API_TIMEOUT_MS_CONFIG)
+            {
+                return Err(KafkaError::Config(format!(
+                    "The specified value of {} must be no smaller than the value of {}.",
+                    DEFAULT_API_TIMEOUT_MS_CONFIG, REQUEST_TIMEOUT_MS_CONFIG
+                )));
+            }
+            log.warn(format_args!(
+                "Overriding the default value for {} ({}) with the explicitly configured request timeout {}",
+                DEFAULT_API_TIMEOUT_MS_CONFIG, default_api_timeout_ms, request_timeout_ms
+            ));
+            return Ok(request_timeout_ms);
+        }
+        Ok(default_api_timeout_ms)
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, AdminClientState> {
+        lock_state(&self.state)
+    }
+
+    /// Get the deadline for a particular call.
+    fn calc_deadline_ms(&self, now: u128, option_timeout_ms: Option<i32>) -> u128 {
+        let timeout_ms = option_timeout_ms
+            .map(|timeout_ms| timeout_ms.max(0))
+            .unwrap_or(self.default_api_timeout_ms);
+        now + timeout_ms.max(0) as u128
+    }
+
+    /// Initiate a new call.
+    ///
+    /// This will fail if the AdminClient is scheduled to shut down.
+    fn run_call(&self, call: Call, now: u128) {
+        if call.tries > self.max_retries {
+            self.log.debug(format_args!(
+                "Max retries {} for {} reached",
+                self.max_retries, call
+            ));
+            let error =
+                KafkaError::Timeout(format!("Exceeded maxRetries after {} tries.", call.tries));
+            (call.handle_failure)(error);
+            return;
+        }
+        self.log.debug(format_args!(
+            "Queueing {} with a timeout {} ms from now.",
+            call,
+            call.deadline_ms.saturating_sub(now)
+        ));
+        let mut state = self.lock_state();
+        if state.hard_shutdown_time_ms.is_some() {
+            drop(state);
+            self.log.debug(format_args!(
+                "The AdminClient is not accepting new calls. Timing out {}.",
+                call
+            ));
+            (call.handle_failure)(KafkaError::Timeout(
+                "The AdminClient thread is not accepting new calls.".to_owned(),
+            ));
+            return;
+        }
+        match state.new_calls.as_mut() {
+            Some(new_calls) => {
+                new_calls.push(call);
+                drop(state);
+                // wake the thread if it is in poll()
+                self.client.wakeup();
+            }
+            None => {
+                drop(state);
+                self.log.debug(format_args!(
+                    "The AdminClient thread has exited. Timing out {}.",
+                    call
+                ));
+                (call.handle_failure)(KafkaError::Timeout(
+                    "The AdminClient thread has exited.".to_owned(),
+                ));
+            }
+        }
+    }
+
+    fn create_topics_call(
+        options: &CreateTopicsOptions,
+        futures: IndexMap<String, KafkaFuture<TopicMetadataAndConfig>>,
+        topics: Vec<CreatableTopic>,
+        deadline_ms: u128,
+    ) -> Call {
+        let validate_only = options.validate_only;
+        let failure_futures = futures.clone();
+        Call::new(
+            "createTopics",
+            deadline_ms,
+            NodeProvider::Controller,
+            move |timeout_ms| {
+                Ok(AbstractRequest::CreateTopics(CreateTopicsRequest::new(
+                    topics.clone(),
+                    timeout_ms,
+                    validate_only,
+                )))
+            },
+            move |response, context| {
+                let response = expect_response!(response, CreateTopics, "createTopics");
+                // Check for controller change
+                handle_not_controller_error(response.has_error(Errors::NotController), context)?;
+                for result in response.topics {
+                    let future = match futures.get(&result.name) {
+                        Some(future) => future,
+                        None => {
+                            context.log.warn(format_args!(
+                                "Server response mentioned unknown topic {}",
+                                result.name
+                            ));
+                            continue;
+                        }
+                    };
+                    match result.error.exception(result.error_message.as_deref()) {
+                        Some(error) => {
+                            future.complete_exceptionally(error);
+                        }
+                        None => {
+                            let config = Config::new(
+                                result
+                                    .configs
+                                    .into_iter()
+                                    .map(|config| ConfigEntry {
+                                        name: config.name,
+                                        value: config.value,
+                                        source: ConfigSource::for_id(config.config_source),
+                                        is_sensitive: config.is_sensitive,
+                                        is_read_only: config.read_only,
+                                        synonyms: Vec::new(),
+                                        type_: ConfigType::Unknown,
+                                        documentation: None,
+                                    })
+                                    .collect(),
+                            );
+                            future.complete(TopicMetadataAndConfig {
+                                num_partitions: result.num_partitions,
+                                replication_factor: result.replication_factor,
+                                config: Some(config),
+                            });
+                        }
+                    }
+                }
+                complete_unrealized_futures(&futures, |topic| {
+                    format!(
+                        "The controller response did not contain a result for topic {}",
+                        topic
+                    )
+                });
+                Ok(())
+            },
+            move |error| complete_all_exceptionally(failure_futures.values(), error),
+        )
+    }
+}
+
+/// Handles a NotController error by refreshing the metadata and returning the error, so that
+/// the call is retried against the new controller.
+fn handle_not_controller_error(not_controller: bool, context: &mut CallContext<'_>) -> Result<()> {
+    if not_controller {
+        context.metadata_manager.clear_controller();
+        context.metadata_manager.request_update();
+        return Err(KafkaError::NotController(
+            Errors::NotController.message().to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+impl Admin for KafkaAdminClient {
+    fn close(&self, timeout: Option<Duration>) {
+        let wait_time_ms = timeout.map_or(u128::MAX, |timeout| timeout.as_millis());
+        let now = self.time.milliseconds();
+        let mut new_hard_shutdown_time_ms = now.saturating_add(wait_time_ms);
+        {
+            let mut state = self.lock_state();
+            match state.hard_shutdown_time_ms {
+                Some(prev) if prev < new_hard_shutdown_time_ms => {
+                    self.log.debug(format_args!(
+                        "Hard shutdown time is already earlier than requested."
+                    ));
+                    new_hard_shutdown_time_ms = prev;
+                }
+                prev => {
+                    if prev.is_none() {
+                        self.log.debug(format_args!("Initiating close operation."));
+                    } else {
+                        self.log
+                            .debug(format_args!("Moving hard shutdown time forward."));
+                    }
+                    state.hard_shutdown_time_ms = Some(new_hard_shutdown_time_ms);
+                }
+            }
+        }
+        // Wake the thread, if it is blocked inside poll().
+        self.client.wakeup();
+        self.log.debug(format_args!(
+            "Waiting for the I/O thread to exit. Hard shutdown in {} ms.",
+            new_hard_shutdown_time_ms.saturating_sub(self.time.milliseconds())
+        ));
+        let thread = self.thread.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(thread) = thread {
+            // close can be called by the AdminClient thread when it invokes a callback. That
+            // would cause a deadlock, so check for that condition.
+            if thread::current().id() != thread.thread().id() && thread.join().is_err() {
+                self.log.warn(format_args!(
+                    "Admin client thread panicked before it was closed"
+                ));
+            }
+        }
+        self.log.debug(format_args!("Kafka admin client closed."));
+    }
+
+    fn create_topics(
+        &self,
+        new_topics: Vec<NewTopic>,
+        options: Option<CreateTopicsOptions>,
+    ) -> CreateTopicsResult {
+        let options = options.unwrap_or_default();
+        let mut topic_futures = IndexMap::new();
+        let mut topics = Vec::new();
+        for new_topic in new_topics {
+            if topic_name_is_unrepresentable(&new_topic.name) {
+                let future = KafkaFuture::failed_future(KafkaError::InvalidTopic(format!(
+                    "The given topic name '{}' cannot be represented in a request.",
+                    new_topic.name
+                )));
+                topic_futures.insert(new_topic.name, future);
+            } else if !topic_futures.contains_key(&new_topic.name) {
+                topic_futures.insert(new_topic.name.clone(), KafkaFuture::new());
+                topics.push(new_topic.convert_to_creatable_topic());
+            }
+        }
+        if !topics.is_empty() {
+            let now = self.time.milliseconds();
+            let deadline_ms = self.calc_deadline_ms(now, options.timeout_ms);
+            let futures = topic_futures
+                .iter()
+                .filter(|(_, future)| !future.is_done())
+                .map(|(name, future)| (name.clone(), future.clone()))
+                .collect();
+            let call = KafkaAdminClient::create_topics_call(&options, futures, topics, deadline_ms);
+            self.run_call(call, now);
+        }
+        CreateTopicsResult::new(topic_futures)
+    }
+
+    fn delete_topics(
+        &self,
+        topic_names: Vec<String>,
+        options: Option<DeleteTopicsOptions>,
+    ) -> DeleteTopicsResult {
+        let options = options.unwrap_or_default();
+        let mut topic_futures = IndexMap::new();
+        let mut valid_topic_names = Vec::new();
+        for topic_name in topic_names {
+            if topic_name_is_unrepresentable(&topic_name) {
+                let future = KafkaFuture::failed_future(KafkaError::InvalidTopic(format!(
+                    "The given topic name '{}' cannot be represented in a request.",
+                    topic_name
+                )));
+                topic_futures.insert(topic_name, future);
+            } else if !topic_futures.contains_key(&topic_name) {
+                topic_futures.insert(topic_name.clone(), KafkaFuture::new());
+                valid_topic_names.push(topic_name);
+            }
+        }
+        if !valid_topic_names.is_empty() {
+            let now = self.time.milliseconds();
+            let deadline_ms = self.calc_deadline_ms(now, options.timeout_ms);
+            let futures: IndexMap<String, KafkaFuture<()>> = valid_topic_names
+                .iter()
+                .filter_map(|name| Some((name.clone(), topic_futures.get(name)?.clone())))
+                .collect();
+            let failure_futures = futures.clone();
+            let call = Call::new(
+                "deleteTopics",
+                deadline_ms,
+                NodeProvider::Controller,
+                move |timeout_ms| {
+                    Ok(AbstractRequest::DeleteTopics(DeleteTopicsRequest::new(
+                        valid_topic_names.clone(),
+                        timeout_ms,
+                    )))
+                },
+                move |response, context| {
+                    let response = expect_response!(response, DeleteTopics, "deleteTopics");
+                    // Check for controller change
+                    handle_not_controller_error(
+                        response.has_error(Errors::NotController),
+                        context,
+                    )?;
+                    for result in response.responses {
+                        match futures.get(&result.name) {
+                            Some(future) => {
+                                match result.error.exception(result.error_message.as_deref()) {
+                                    Some(error) => future.complete_exceptionally(error),
+                                    None => future.complete(()),
+                                };
+                            }
+                            None => context.log.warn(format_args!(
+                                "Server response mentioned unknown topic {}",
+                                result.name
+                            )),
+                        }
+                    }
+                    complete_unrealized_futures(&futures, |topic| {
+                        format!(
+                            "The controller response did not contain a result for topic {}",
+                            topic
+                        )
+                    });
+                    Ok(())
+                },
+                move |error| complete_all_exceptionally(failure_futures.values(), error),
+            );
+            self.run_call(call, now);
+        }
+        DeleteTopicsResult::new(topic_futures)
+    }
+
+    fn list_topics(&self, options: Option<ListTopicsOptions>) -> ListTopicsResult {
+        let options = options.unwrap_or_default();
+        let topic_listing_future = KafkaFuture::new();
+        let now = self.time.milliseconds();
+        let list_internal = options.list_internal;
+        let future = topic_listing_future.clone();
+        let failure_future = topic_listing_future.clone();
+        self.run_call(
+            Call::new(
+                "listTopics",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                |_| Ok(AbstractRequest::Metadata(MetadataRequest::all_topics())),
+                move |response, _| {
+                    let response = expect_response!(response, Metadata, "listTopics");
+                    let mut topic_listing = IndexMap::new();
+                    for topic in response.topics {
+                        if !topic.is_internal || list_internal {
+                            topic_listing.insert(
+                                topic.name.clone(),
+                                TopicListing::new(topic.name, topic.is_internal),
+                            );
+                        }
+                    }
+                    future.complete(topic_listing);
+                    Ok(())
+                },
+                move |error| {
+                    failure_future.complete_exceptionally(error);
+                },
+            ),
+            now,
+        );
+        ListTopicsResult::new(topic_listing_future)
+    }
+
+    fn describe_topics(
+        &self,
+        topic_names: Vec<String>,
+        options: Option<DescribeTopicsOptions>,
+    ) -> DescribeTopicsResult {
+        let options = options.unwrap_or_default();
+        let mut topic_futures = IndexMap::new();
+        let mut topic_names_list = Vec::new();
+        for topic_name in topic_names {
+            if topic_name_is_unrepresentable(&topic_name) {
+                let future = KafkaFuture::failed_future(KafkaError::InvalidTopic(format!(
+                    "The given topic name '{}' cannot be represented in a request.",
+                    topic_name
+                )));
+                topic_futures.insert(topic_name, future);
+            } else if !topic_futures.contains_key(&topic_name) {
+                topic_futures.insert(topic_name.clone(), KafkaFuture::new());
+                topic_names_list.push(topic_name);
+            }
+        }
+        let now = self.time.milliseconds();
+        let futures = topic_futures.clone();
+        let failure_futures = topic_futures.clone();
+        self.run_call(
+            Call::new(
+                "describeTopics",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                move |_| {
+                    Ok(AbstractRequest::Metadata(MetadataRequest::new(
+                        topic_names_list.clone(),
+                        false,
+                    )))
+                },
+                move |response, _| {
+                    let response = expect_response!(response, Metadata, "describeTopics");
+                    // Handle server responses for particular topics.
+                    let cluster = response.build_cluster();
+                    let errors = response.errors();
+                    for (topic_name, future) in &futures {
+                        if let Some(topic_error) = errors.get(topic_name) {
+                            if let Some(error) = topic_error.exception(None) {
+                                future.complete_exceptionally(error);
+                            }
+                            continue;
+                        }
+                        if !cluster.topics().any(|topic| topic == topic_name) {
+                            future.complete_exceptionally(KafkaError::UnknownTopicOrPartition(
+                                format!("Topic {} not found.", topic_name),
+                            ));
+                            continue;
+                        }
+                        let is_internal = cluster.internal_topics.contains(topic_name);
+                        let mut partitions: Vec<TopicPartitionInfo> = cluster
+                            .partitions_for_topic(topic_name)
+                            .iter()
+                            .map(|partition_info| {
+                                TopicPartitionInfo::new(
+                                    partition_info.partition,
+                                    partition_info
+                                        .leader
+                                        .clone()
+                                        .filter(|leader| !leader.is_empty()),
+                                    partition_info.replicas.clone(),
+                                    partition_info.in_sync_replicas.clone(),
+                                )
+                            })
+                            .collect();
+                        partitions.sort_by_key(|partition| partition.partition);
+                        future.complete(TopicDescription::new(
+                            topic_name.clone(),
+                            is_internal,
+                            partitions,
+                        ));
+                    }
+                    Ok(())
+                },
+                move |error| complete_all_exceptionally(failure_futures.values(), error),
+            ),
+            now,
+        );
+        DescribeTopicsResult::new(topic_futures)
+    }
+
+    fn describe_cluster(&self, options: Option<DescribeClusterOptions>) -> DescribeClusterResult {
+        let options = options.unwrap_or_default();
+        let result = DescribeClusterResult {
+            nodes: KafkaFuture::new(),
+            controller: KafkaFuture::new(),
+            cluster_id: KafkaFuture::new(),
+        };
+        let now = self.time.milliseconds();
+        let (nodes, controller, cluster_id) = (
+            result.nodes.clone(),
+            result.controller.clone(),
+            result.cluster_id.clone(),
+        );
+        let (failure_nodes, failure_controller, failure_cluster_id) = (
+            result.nodes.clone(),
+            result.controller.clone(),
+            result.cluster_id.clone(),
+        );
+        self.run_call(
+            Call::new(
+                "listNodes",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                |_| {
+                    // Since this only requests node information, it's safe to pass true for
+                    // allowAutoTopicCreation (and it simplifies communication with older
+                    // brokers)
+                    Ok(AbstractRequest::Metadata(MetadataRequest::new(
+                        vec![],
+                        true,
+                    )))
+                },
+                move |response, _| {
+                    let response = expect_response!(response, Metadata, "listNodes");
+                    controller.complete(response.controller());
+                    nodes.complete(response.brokers);
+                    cluster_id.complete(response.cluster_id);
+                    Ok(())
+                },
+                move |error| {
+                    failure_nodes.complete_exceptionally(error.clone());
+                    failure_controller.complete_exceptionally(error.clone());
+                    failure_cluster_id.complete_exceptionally(error);
+                },
+            ),
+            now,
+        );
+        result
+    }
+
+    fn describe_acls(
+        &self,
+        filter: AclBindingFilter,
+        options: Option<DescribeAclsOptions>,
+    ) -> DescribeAclsResult {
+        let options = options.unwrap_or_default();
+        if filter.is_unknown() {
+            return DescribeAclsResult::new(KafkaFuture::failed_future(
+                KafkaError::InvalidRequest(
+                    "The AclBindingFilter must not contain UNKNOWN elements.".to_owned(),
+                ),
+            ));
+        }
+        let now = self.time.milliseconds();
+        let future = KafkaFuture::new();
+        let response_future = future.clone();
+        let failure_future = future.clone();
+        self.run_call(
+            Call::new(
+                "describeAcls",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                move |_| {
+                    Ok(AbstractRequest::DescribeAcls(DescribeAclsRequest::new(
+                        filter.clone(),
+                    )))
+                },
+                move |response, _| {
+                    let response = expect_response!(response, DescribeAcls, "describeAcls");
+                    match response.error.exception(response.error_message.as_deref()) {
+                        Some(error) => response_future.complete_exceptionally(error),
+                        None => response_future.complete(response.acls),
+                    };
+                    Ok(())
+                },
+                move |error| {
+                    failure_future.complete_exceptionally(error);
+                },
+            ),
+            now,
+        );
+        DescribeAclsResult::new(future)
+    }
+
+    fn create_acls(
+        &self,
+        acls: Vec<AclBinding>,
+        options: Option<CreateAclsOptions>,
+    ) -> CreateAclsResult {
+        let options = options.unwrap_or_default();
+        let now = self.time.milliseconds();
+        let mut futures: IndexMap<AclBinding, KafkaFuture<()>> = IndexMap::new();
+        let mut acl_bindings_sent = Vec::new();
+        for acl in acls {
+            if futures.contains_key(&acl) {
+                continue;
+            }
+            let future = KafkaFuture::new();
+            match acl.to_filter().find_indefinite_field() {
+                None => acl_bindings_sent.push(acl.clone()),
+                Some(indefinite) => {
+                    future.complete_exceptionally(KafkaError::InvalidRequest(format!(
+                        "Invalid ACL creation: {}",
+                        indefinite
+                    )));
+                }
+            }
+            futures.insert(acl, future);
+        }
+        let response_futures = futures.clone();
+        let failure_futures = futures.clone();
+        let sent = acl_bindings_sent.clone();
+        self.run_call(
+            Call::new(
+                "createAcls",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                move |_| {
+                    Ok(AbstractRequest::CreateAcls(CreateAclsRequest::new(
+                        sent.clone(),
+                    )))
+                },
+                move |response, _| {
+                    let response = expect_response!(response, CreateAcls, "createAcls");
+                    let mut results = response.results.into_iter();
+                    for acl_binding in &acl_bindings_sent {
+                        let future = match response_futures.get(acl_binding) {
+                            Some(future) => future,
+                            None => continue,
+                        };
+                        match results.next() {
+                            None => {
+                                future.complete_exceptionally(KafkaError::UnknownServer(format!(
+                                    "The broker reported no creation result for the given ACL: {}",
+                                    acl_binding
+                                )));
+                            }
+                            Some(creation) => {
+                                match creation.error.exception(creation.error_message.as_deref()) {
+                                    Some(error) => future.complete_exceptionally(error),
+                                    None => future.complete(()),
+                                };
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+                move |error| complete_all_exceptionally(failure_futures.values(), error),
+            ),
+            now,
+        );
+        CreateAclsResult::new(futures)
+    }
+
+    fn delete_acls(
+        &self,
+        filters: Vec<AclBindingFilter>,
+        options: Option<DeleteAclsOptions>,
+    ) -> DeleteAclsResult {
+        let options = options.unwrap_or_default();
+        let now = self.time.milliseconds();
+        let mut futures: IndexMap<AclBindingFilter, KafkaFuture<FilterResults>> = IndexMap::new();
+        for filter in filters {
+            futures.entry(filter).or_default();
+        }
+        let filter_list: Vec<AclBindingFilter> = futures.keys().cloned().collect();
+        let response_futures = futures.clone();
+        let failure_futures = futures.clone();
+        self.run_call(
+            Call::new(
+                "deleteAcls",
+                self.calc_deadline_ms(now, options.timeout_ms),
+                NodeProvider::LeastLoaded,
+                move |_| {
+                    Ok(AbstractRequest::DeleteAcls(DeleteAclsRequest::new(
+                        filter_list.clone(),
+                    )))
+                },
+                move |response, _| {
+                    let response = expect_response!(response, DeleteAcls, "deleteAcls");
+                    let mut results = response.filter_results.into_iter();
+                    for future in response_futures.values() {
+                        let filter_result = match results.next() {
+                            Some(filter_result) => filter_result,
+                            None => {
+                                future.complete_exceptionally(KafkaError::UnknownServer(
+                                    "The broker reported no deletion result for the given filter."
+                                        .to_owned(),
+                                ));
+                                continue;
+                            }
+                        };
+                        match filter_result
+                            .error
+                            .exception(filter_result.error_message.as_deref())
+                        {
+                            Some(error) => {
+                                future.complete_exceptionally(error);
+                            }
+                            None => {
+                                let values = filter_result
+                                    .matching_acls
+                                    .into_iter()
+                                    .map(|matching_acl| FilterResult {
+                                        binding: Some(matching_acl.acl),
+                                        error: matching_acl
+                                            .error
+                                            .exception(matching_acl.error_message.as_deref()),
+                                    })
+                                    .collect();
+                                future.complete(FilterResults { values });
+                            }
+                        }
+                    }
+                    Ok(())
+                },
+                move |error| complete_all_exceptionally(failure_futures.values(), error),
+            ),
+            now,
+        );
+        DeleteAclsResult::new(futures)
+    }
+
+    fn describe_configs(
+        &self,
+        resources: Vec<ConfigResource>,
+        options: Option<DescribeConfigsOptions>,
+    ) -> DescribeConfigsResult {
+        let options = options.unwrap_or_default();
+        let mut all_futures: IndexMap<ConfigResource, KafkaFuture<Config>> = IndexMap::new();
+        for resource in resources {
+            all_futures.entry(resource).or_default();
+        }
+        // Partition the requested config resources based on which broker they must be sent to
+        // with the None broker being used for config resources which can be obtained from any
+        // broker
+        let now = self.time.milliseconds();
+        for (broker, unified) in group_by_node(&all_futures) {
+            let request_resources: Vec<DescribeConfigsResource> = unified
+                .keys()
+                .cloned()
+                .map(DescribeConfigsResource::new)
+                .collect();
+            let include_synonyms = options.include_synonyms;
+            let include_documentation = options.include_documentation;
+            let failure_futures = unified.clone();
+            self.run_call(
+                Call::new(
+                    "describeConfigs",
+                    self.calc_deadline_ms(now, options.timeout_ms),
+                    node_provider_for(broker),
+                    move |_| {
+                        Ok(AbstractRequest::DescribeConfigs(DescribeConfigsRequest::new(
+                            request_resources.clone(),
+                            include_synonyms,
+                            include_documentation,
+                        )))
+                    },
+                    move |response, context| {
+                        let response =
+                            expect_response!(response, DescribeConfigs, "describeConfigs");
+                        for (config_resource, describe_configs_result) in response.results {
+                            let future = match unified.get(&config_resource) {
+                                Some(future) => future,
+                                None => {
+                                    match broker {
+                                        Some(broker) => context.log.warn(format_args!(
+                                            "The config {} in the response from broker {} is not in the request",
+                                            config_resource, broker
+                                        )),
+                                        None => context.log.warn(format_args!(
+                                            "The config {} in the response from the least loaded broker is not in the request",
+                                            config_resource
+                                        )),
+                                    }
+                                    continue;
+                                }
+                            };
+                            match describe_configs_result
+                                .error
+                                .exception(describe_configs_result.error_message.as_deref())
+                            {
+                                Some(error) => future.complete_exceptionally(error),
+                                None => {
+                                    future.complete(describe_config_result(describe_configs_result))
+                                }
+                            };
+                        }
+                        complete_unrealized_futures(&unified, |config_resource| {
+                            format!(
+                                "The broker response did not contain a result for config resource {}",
+                                config_resource
+                            )
+                        });
+                        Ok(())
+                    },
+                    move |error| complete_all_exceptionally(failure_futures.values(), error),
+                ),
+                now,
+            );
+        }
+        DescribeConfigsResult::new(all_futures)
+    }
+
+    fn incremental_alter_configs(
+        &self,
+        configs: IndexMap<ConfigResource, Vec<AlterConfigOp>>,
+        options: Option<AlterConfigsOptions>,
+    ) -> AlterConfigsResult {
+        let options = options.unwrap_or_default();
+        let all_futures: IndexMap<ConfigResource, KafkaFuture<()>> = configs
+            .keys()
+            .map(|resource| (resource.clone(), KafkaFuture::new()))
+            .collect();
+        // We must make a separate AlterConfigs request for every BROKER resource we want to
+        // alter and send the request to that specific broker. Other resources are grouped
+        // together into a single request that may be sent to any broker.
+        let mut requests = Vec::new();
+        for (node, futures) in group_by_node(&all_futures) {
+            match node {
+                Some(_) => {
+                    requests.extend(futures.into_iter().map(|(resource, future)| {
+                        (node, IndexMap::from_iter([(resource, future)]))
+                    }))
+                }
+                None => requests.push((node, futures)),
+            }
+        }
+        let now = self.time.milliseconds();
+        for (node, futures) in requests {
+            let resources: IndexMap<ConfigResource, Vec<AlterableConfig>> = futures
+                .keys()
+                .map(|resource| {
+                    let ops = configs[resource]
+                        .iter()
+                        .map(|op| AlterableConfig {
+                            name: op.config_entry.name.clone(),
+                            config_operation: op.op_type.id(),
+                            value: op.config_entry.value.clone(),
+                        })
+                        .collect();
+                    (resource.clone(), ops)
+                })
+                .collect();
+            let validate_only = options.validate_only;
+            let failure_futures = futures.clone();
+            self.run_call(
+                Call::new(
+                    "incrementalAlterConfigs",
+                    self.calc_deadline_ms(now, options.timeout_ms),
+                    node_provider_for(node),
+                    move |_| {
+                        Ok(AbstractRequest::IncrementalAlterConfigs(
+                            IncrementalAlterConfigsRequest::new(resources.clone(), validate_only),
+                        ))
+                    },
+                    move |response, _| {
+                        let response = expect_response!(
+                            response,
+                            IncrementalAlterConfigs,
+                            "incrementalAlterConfigs"
+                        );
+                        for (resource, future) in &futures {
+                            match response.responses.get(resource) {
+                                Some(result) => {
+                                    match result.error.exception(result.error_message.as_deref()) {
+                                        Some(error) => future.complete_exceptionally(error),
+                                        None => future.complete(()),
+                                    };
+                                }
+                                None => {
+                                    future.complete_exceptionally(KafkaError::Kafka(format!(
+                                        "The broker response did not contain a result for config resource {}",
+                                        resource
+                                    )));
+                                }
+                            }
+                        }
+                        Ok(())
+                    },
+                    move |error| complete_all_exceptionally(failure_futures.values(), error),
+                ),
+                now,
+            );
+        }
+        AlterConfigsResult::new(all_futures)
+    }
+
+    fn create_partitions(
+        &self,
+        new_partitions: IndexMap<String, NewPartitions>,
+        options: Option<CreatePartitionsOptions>,
+    ) -> CreatePartitionsResult {
+        let options = options.unwrap_or_default();
+        let mut futures = IndexMap::new();
+        let mut topics = Vec::with_capacity(new_partitions.len());
+        for (topic, new_partition) in new_partitions {
+            topics.push(CreatePartitionsTopic {
+                name: topic.clone(),
+                count: new_partition.total_count,
+                assignments: new_partition.new_assignments,
+            });
+            futures.insert(topic, KafkaFuture::new());
+        }
+        if !topics.is_empty() {
+            let now = self.time.milliseconds();
+            let validate_only = options.validate_only;
+            let response_futures = futures.clone();
+            let failure_futures = futures.clone();
+            self.run_call(
+                Call::new(
+                    "createPartitions",
+                    self.calc_deadline_ms(now, options.timeout_ms),
+                    NodeProvider::Controller,
+                    move |timeout_ms| {
+                        Ok(AbstractRequest::CreatePartitions(
+                            CreatePartitionsRequest::new(topics.clone(), timeout_ms, validate_only),
+                        ))
+                    },
+                    move |response, context| {
+                        let response =
+                            expect_response!(response, CreatePartitions, "createPartitions");
+                        // Check for controller change
+                        handle_not_controller_error(
+                            response.has_error(Errors::NotController),
+                            context,
+                        )?;
+                        for result in response.results {
+                            match response_futures.get(&result.name) {
+                                Some(future) => {
+                                    match result.error.exception(result.error_message.as_deref()) {
+                                        Some(error) => future.complete_exceptionally(error),
+                                        None => future.complete(()),
+                                    };
+                                }
+                                None => context.log.warn(format_args!(
+                                    "Server response mentioned unknown topic {}",
+                                    result.name
+                                )),
+                            }
+                        }
+                        complete_unrealized_futures(&response_futures, |topic| {
+                            format!(
+                                "The controller response did not contain a result for topic {}",
+                                topic
+                            )
+                        });
+                        Ok(())
+                    },
+                    move |error| complete_all_exceptionally(failure_futures.values(), error),
+                ),
+                now,
+            );
+        }
+        CreatePartitionsResult::new(futures)
+    }
+
+    fn list_consumer_groups(
+        &self,
+        options: Option<ListConsumerGroupsOptions>,
+    ) -> ListConsumerGroupsResult {
+        let options = options.unwrap_or_default();
+        let all = KafkaFuture::new();
+        let now_metadata = self.time.milliseconds();
+        let deadline_ms = self.calc_deadline_ms(now_metadata, options.timeout_ms);
+        let states: Vec<String> = options
+            .states
+            .iter()
+            .map(|state| state.name().to_owned())
+            .collect();
+        let response_all = all.clone();
+        let failure_all = all.clone();
+        self.run_call(
+            Call::new(
+                "findAllBrokers",
+                deadline_ms,
+                NodeProvider::LeastLoaded,
+                |_| {
+                    Ok(AbstractRequest::Metadata(MetadataRequest::new(
+                        vec![],
+                        true,
+                    )))
+                },
+                move |response, context| {
+                    let response = expect_response!(response, Metadata, "findAllBrokers");
+                    if response.brokers.is_empty() {
+                        return Err(KafkaError::Network(
+                            "Metadata fetch failed due to missing broker list".to_owned(),
+                        ));
+                    }
+                    let all_nodes: HashSet<Node> = response.brokers.into_iter().collect();
+                    let results = Arc::new(Mutex::new(ListConsumerGroupsResults {
+                        errors: Vec::new(),
+                        listings: IndexMap::new(),
+                        remaining: all_nodes.clone(),
+                        future: response_all.clone(),
+                    }));
+                    for node in all_nodes {
+                        context.call(list_consumer_groups_call(
+                            node,
+                            states.clone(),
+                            deadline_ms,
+                            results.clone(),
+                        ));
+                    }
+                    Ok(())
+                },
+                move |error| {
+                    failure_all.complete(vec![Err(KafkaError::Kafka(format!(
+                        "Failed to find brokers to send ListGroups: {}",
+                        error
+                    )))]);
+                },
+            ),
+            now_metadata,
+        );
+        ListConsumerGroupsResult::new(all)
+    }
+
+    fn describe_consumer_groups(
+        &self,
+        group_ids: Vec<String>,
+        options: Option<DescribeConsumerGroupsOptions>,
+    ) -> DescribeConsumerGroupsResult {
+        let options = options.unwrap_or_default();
+        let mut futures: IndexMap<String, KafkaFuture<ConsumerGroupDescription>> = IndexMap::new();
+        for group_id in group_ids {
+            futures.entry(group_id).or_default();
+        }
+        for (group_id, future) in &futures {
+            let start_find_coordinator_ms = self.time.milliseconds();
+            let context = ConsumerGroupOperationContext {
+                group_id: group_id.clone(),
+                data: (),
+                deadline_ms: self.calc_deadline_ms(start_find_coordinator_ms, options.timeout_ms),
+                future: future.clone(),
+                node: None,
+            };
+            self.run_call(
+                find_coordinator_call(context, describe_consumer_groups_call),
+                start_find_coordinator_ms,
+            );
+        }
+        DescribeConsumerGroupsResult::new(futures)
+    }
+
+    fn delete_consumer_groups(
+        &self,
+        group_ids: Vec<String>,
+        options: Option<DeleteConsumerGroupsOptions>,
+    ) -> DeleteConsumerGroupsResult {
+        let options = options.unwrap_or_default();
+        let mut futures: IndexMap<String, KafkaFuture<()>> = IndexMap::new();
+        for group_id in group_ids {
+            futures.entry(group_id).or_default();
+        }
+        for (group_id, future) in &futures {
+            let start_find_coordinator_ms = self.time.milliseconds();
+            let context = ConsumerGroupOperationContext {
+                group_id: group_id.clone(),
+                data: (),
+                deadline_ms: self.calc_deadline_ms(start_find_coordinator_ms, options.timeout_ms),
+                future: future.clone(),
+                node: None,
+            };
+            self.run_call(
+                find_coordinator_call(context, delete_consumer_groups_call),
+                start_find_coordinator_ms,
+            );
+        }
+        DeleteConsumerGroupsResult::new(futures)
+    }
+
+    fn list_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        options: Option<ListConsumerGroupOffsetsOptions>,
+    ) -> ListConsumerGroupOffsetsResult {
+        let options = options.unwrap_or_default();
+        let group_offset_listing_future = KafkaFuture::new();
+        let start_find_coordinator_ms = self.time.milliseconds();
+        let context = ConsumerGroupOperationContext {
+            group_id: group_id.to_owned(),
+            data: options.topic_partitions,
+            deadline_ms: self.calc_deadline_ms(start_find_coordinator_ms, options.timeout_ms),
+            future: group_offset_listing_future.clone(),
+            node: None,
+        };
+        self.run_call(
+            find_coordinator_call(context, list_consumer_group_offsets_call),
+            start_find_coordinator_ms,
+        );
+        ListConsumerGroupOffsetsResult::new(group_offset_listing_future)
+    }
+
+    fn delete_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        partitions: HashSet<TopicPartition>,
+        options: Option<DeleteConsumerGroupOffsetsOptions>,
+    ) -> DeleteConsumerGroupOffsetsResult {
+        let options = options.unwrap_or_default();
+        let future = KafkaFuture::new();
+        let start_find_coordinator_ms = self.time.milliseconds();
+        let context = ConsumerGroupOperationContext {
+            group_id: group_id.to_owned(),
+            data: partitions.iter().cloned().collect(),
+            deadline_ms: self.calc_deadline_ms(start_find_coordinator_ms, options.timeout_ms),
+            future: future.clone(),
+            node: None,
+        };
+        self.run_call(
+            find_coordinator_call(context, delete_consumer_group_offsets_call),
+            start_find_coordinator_ms,
+        );
+        DeleteConsumerGroupOffsetsResult::new(future, partitions)
+    }
+
+    fn alter_consumer_group_offsets(
+        &self,
+        group_id: &str,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        options: Option<AlterConsumerGroupOffsetsOptions>,
+    ) -> AlterConsumerGroupOffsetsResult {
+        let options = options.unwrap_or_default();
+        let future = KafkaFuture::new();
+        let start_find_coordinator_ms = self.time.milliseconds();
+        let context = ConsumerGroupOperationContext {
+            group_id: group_id.to_owned(),
+            data: offsets,
+            deadline_ms: self.calc_deadline_ms(start_find_coordinator_ms, options.timeout_ms),
+            future: future.clone(),
+            node: None,
+        };
+        self.run_call(
+            find_coordinator_call(context, alter_consumer_group_offsets_call),
+            start_find_coordinator_ms,
+        );
+        AlterConsumerGroupOffsetsResult::new(future)
+    }
+
+    fn list_offsets(
+        &self,
+        topic_partition_offsets: IndexMap<TopicPartition, OffsetSpec>,
+        options: Option<ListOffsetsOptions>,
+    ) -> ListOffsetsResult {
+        let options = options.unwrap_or_default();
+        // preparing topics list for asking metadata about them
+        let futures: IndexMap<TopicPartition, KafkaFuture<ListOffsetsResultInfo>> =
+            topic_partition_offsets
+                .keys()
+                .map(|tp| (tp.clone(), KafkaFuture::new()))
+                .collect();
+        let now_metadata = self.time.milliseconds();
+        let deadline_ms = self.calc_deadline_ms(now_metadata, options.timeout_ms);
+        self.run_call(
+            list_offsets_metadata_call(
+                topic_partition_offsets,
+                options.isolation_level,
+                deadline_ms,
+                futures.clone(),
+            ),
+            now_metadata,
+        );
+        ListOffsetsResult::new(futures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::{
+            admin::{
+                admin_client::Admin,
+                admin_client_config::AdminClientConfig,
+                alter_config_op::{AlterConfigOp, OpType},
+                config_entry::{ConfigEntry, ConfigSource},
+                internals::admin_metadata_manager::AdminMetadataManager,
+                new_topic::NewTopic,
+                options::CreateTopicsOptions,
+            },
+            api_versions::ApiVersions,
+            common_client_configs::{
+                BOOTSTRAP_SERVERS_CONFIG, CLIENT_ID_CONFIG, RETRIES_CONFIG, RETRY_BACKOFF_MS_CONFIG,
+            },
+            mock_client::MockClient,
+        },
+        common::{
+            acl::{
+                access_control_entry::AccessControlEntry, acl_binding::AclBinding,
+                acl_binding_filter::AclBindingFilter, acl_operation::AclOperation,
+                acl_permission_type::AclPermissionType,
+            },
+            cluster::Cluster,
+            config::config_resource::{ConfigResource, ConfigResourceType},
+            errors::KafkaError,
+            node::Node,
+            protocol::errors::Errors,
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                create_acls_response::{AclCreationResult, CreateAclsResponse},
+                create_topics_response::{
+                    CreatableTopicConfigs, CreatableTopicResult, CreateTopicsResponse,
+                },
+                delete_acls_response::{
+                    DeleteAclsFilterResult, DeleteAclsMatchingAcl, DeleteAclsResponse,
+                },
+                describe_acls_response::DescribeAclsResponse,
+                describe_configs_response::{
+                    DescribeConfigsResourceResult, DescribeConfigsResponse, DescribeConfigsResult,
+                },
+                incremental_alter_configs_response::{
+                    AlterConfigsResourceResponse, IncrementalAlterConfigsResponse,
+                },
+                metadata_response::MetadataResponse,
+            },
+            resource::{
+                pattern_type::PatternType, resource_pattern::ResourcePattern,
+                resource_type::ResourceType,
+            },
+            utils::{mock_time::MockTime, time::Time},
+        },
+    };
+
+    use super::KafkaAdminClient;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn config(extra: &[(&str, &str)]) -> AdminClientConfig {
+        let mut props: IndexMap<String, String> = [
+            (BOOTSTRAP_SERVERS_CONFIG, "localhost:9092"),
+            (RETRY_BACKOFF_MS_CONFIG, "0"),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        for (key, value) in extra {
+            props.insert(key.to_string(), value.to_string());
+        }
+        AdminClientConfig::new(props).unwrap()
+    }
+
+    /// An admin client connected to a mock cluster of two brokers, the first of which is the
+    /// controller.
+    struct Env {
+        time: Arc<MockTime>,
+        nodes: Vec<Node>,
+        client: Arc<MockClient>,
+        admin: KafkaAdminClient,
+    }
+
+    impl Env {
+        fn new(extra: &[(&str, &str)]) -> Env {
+            let time = Arc::new(MockTime::default());
+            let nodes = vec![
+                Node::new(0, "localhost", 8121),
+                Node::new(1, "localhost", 8122),
+            ];
+            let client = Arc::new(MockClient::new(
+                time.clone(),
+                nodes.clone(),
+                Arc::new(ApiVersions::new()),
+            ));
+            let mut metadata_manager = AdminMetadataManager::new(0, 300_000);
+            metadata_manager.update(Env::cluster(&nodes, &nodes[0]), time.milliseconds());
+            let admin = KafkaAdminClient::create_internal(
+                &config(extra),
+                "admin",
+                metadata_manager,
+                client.clone(),
+                time.clone(),
+            )
+            .unwrap();
+            Env {
+                time,
+                nodes,
+                client,
+                admin,
+            }
+        }
+
+        fn cluster(nodes: &[Node], controller: &Node) -> Cluster {
+            Cluster::new(
+                Some("cluster".to_owned()),
+                nodes.to_vec(),
+                vec![],
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+                Some(controller.clone()),
+            )
+        }
+
+        /// Wait until the admin client thread sent a request which was not answered.
+        fn await_request(&self) {
+            let deadline = Instant::now() + TIMEOUT;
+            while self.client.requests().is_empty() {
+                assert!(Instant::now() < deadline, "no request was sent");
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+    }
+
+    impl Drop for Env {
+        fn drop(&mut self) {
+            self.admin.close(Some(Duration::ZERO));
+        }
+    }
+
+    fn create_topics_response(topic: &str, error: Errors) -> AbstractResponse {
+        AbstractResponse::CreateTopics(CreateTopicsResponse::new(vec![CreatableTopicResult::new(
+            topic, error,
+        )]))
+    }
+
+    fn acl(principal: &str) -> AclBinding {
+        AclBinding::new(
+            ResourcePattern::new(ResourceType::Topic, "topic", PatternType::Literal).unwrap(),
+            AccessControlEntry::new(principal, "*", AclOperation::Read, AclPermissionType::Allow)
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn create_builds_a_network_client_for_the_bootstrap_servers() {
+        let admin = KafkaAdminClient::create(&config(&[(CLIENT_ID_CONFIG, "admin-1")])).unwrap();
+        assert_eq!(admin.client_id(), "admin-1");
+        assert!(admin.client.active());
+        admin.close(Some(Duration::ZERO));
+        assert!(!admin.client.active());
+    }
+
+    #[test]
+    fn create_topics_returns_the_topic_configs() {
+        let env = Env::new(&[]);
+        let mut result = CreatableTopicResult::new("topic", Errors::None);
+        result.num_partitions = 3;
+        result.replication_factor = 2;
+        result.configs = vec![CreatableTopicConfigs {
+            name: "cleanup.policy".to_owned(),
+            value: Some("compact".to_owned()),
+            read_only: false,
+            config_source: 1,
+            is_sensitive: false,
+        }];
+        env.client.prepare_response_from(
+            &env.nodes[0],
+            AbstractResponse::CreateTopics(CreateTopicsResponse::new(vec![result])),
+        );
+
+        let result = env
+            .admin
+            .create_topics(vec![NewTopic::new("topic", Some(3), Some(2))], None);
+        assert_eq!(
+            result
+                .num_partitions("topic")
+                .unwrap()
+                .get_timeout(TIMEOUT)
+                .unwrap(),
+            3
+        );
+        let config = result
+            .config("topic")
+            .unwrap()
+            .get_timeout(TIMEOUT)
+            .unwrap();
+        let entry = config.get("cleanup.policy").unwrap();
+        assert_eq!(entry.value.as_deref(), Some("compact"));
+        assert_eq!(entry.source, ConfigSource::DynamicTopicConfig);
+    }
+
+    #[test]
+    fn create_topics_reports_topic_errors() {
+        let env = Env::new(&[]);
+        env.client
+            .prepare_response(create_topics_response("topic", Errors::TopicAlreadyExists));
+        let result = env
+            .admin
+            .create_topics(vec![NewTopic::new("topic", None, None)], None);
+        assert!(matches!(
+            result.all().get_timeout(TIMEOUT),
+            Err(KafkaError::TopicExists(_))
+        ));
+    }
+
+    #[test]
+    fn create_topics_is_retried_against_the_new_controller() {
+        let env = Env::new(&[]);
+        env.client.prepare_response_matching(
+            |request| matches!(request, AbstractRequest::CreateTopics(_)),
+            create_topics_response("topic", Errors::NotController),
+        );
+        env.client.prepare_response_matching(
+            |request| matches!(request, AbstractRequest::Metadata(_)),
+            AbstractResponse::Metadata(MetadataResponse::new(
+                env.nodes.clone(),
+                Some("cluster".to_owned()),
+                1,
+                vec![],
+            )),
+        );
+        env.client
+            .prepare_response_from(&env.nodes[1], create_topics_response("topic", Errors::None));
+
+        let result = env
+            .admin
+            .create_topics(vec![NewTopic::new("topic", None, None)], None);
+        result.all().get_timeout(TIMEOUT).unwrap();
+        assert!(!env.client.has_pending_responses());
+    }
+
+    #[test]
+    fn calls_fail_once_out_of_retries() {
+        let env = Env::new(&[(RETRIES_CONFIG, "0")]);
+        env.client
+            .prepare_response(create_topics_response("topic", Errors::NotController));
+        let result = env
+            .admin
+            .create_topics(vec![NewTopic::new("topic", None, None)], None);
+        match result.all().get_timeout(TIMEOUT) {
+            Err(KafkaError::Timeout(message)) => {
+                assert!(message.ends_with("after 1 attempt(s)"), "{}", message)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn calls_in_flight_time_out_at_their_deadline() {
+        let env = Env::new(&[]);
+        let result = env.admin.create_topics(
+            vec![NewTopic::new("topic", None, None)],
+            Some(CreateTopicsOptions {
+                timeout_ms: Some(1000),
+                ..CreateTopicsOptions::default()
+            }),
+        );
+        env.await_request();
+        env.time.sleep(1001);
+        match result.all().get_timeout(TIMEOUT) {
+            Err(KafkaError::Timeout(message)) => {
+                assert!(
+                    message.starts_with("Call(callName=createTopics"),
+                    "{}",
+                    message
+                )
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn describe_configs_returns_the_entries_of_each_resource() {
+        let env = Env::new(&[]);
+        let resource = ConfigResource::new(ConfigResourceType::Topic, "topic");
+        let mut results = IndexMap::new();
+        results.insert(
+            resource.clone(),
+            DescribeConfigsResult::new(
+                Errors::None,
+                vec![DescribeConfigsResourceResult {
+                    name: "retention.ms".to_owned(),
+                    value: Some("1000".to_owned()),
+                    read_only: false,
+                    config_source: 5,
+                    is_sensitive: false,
+                    synonyms: vec![],
+                    config_type: 5,
+                    documentation: None,
+                }],
+            ),
+        );
+        env.client
+            .prepare_response(AbstractResponse::DescribeConfigs(
+                DescribeConfigsResponse::new(results),
+            ));
+
+        let result = env.admin.describe_configs(vec![resource.clone()], None);
+        let configs = result.all().get_timeout(TIMEOUT).unwrap();
+        let entry = configs[&resource].get("retention.ms").unwrap();
+        assert_eq!(entry.value.as_deref(), Some("1000"));
+        assert_eq!(entry.source, ConfigSource::DefaultConfig);
+    }
+
+    #[test]
+    fn incremental_alter_configs_reports_errors_per_resource() {
+        let env = Env::new(&[]);
+        let valid = ConfigResource::new(ConfigResourceType::Topic, "valid");
+        let invalid = ConfigResource::new(ConfigResourceType::Topic, "invalid");
+        let mut responses = IndexMap::new();
+        responses.insert(
+            valid.clone(),
+            AlterConfigsResourceResponse::new(Errors::None),
+        );
+        responses.insert(
+            invalid.clone(),
+            AlterConfigsResourceResponse::new(Errors::InvalidRequest),
+        );
+        env.client
+            .prepare_response(AbstractResponse::IncrementalAlterConfigs(
+                IncrementalAlterConfigsResponse::new(responses),
+            ));
+
+        let op = AlterConfigOp::new(
+            ConfigEntry::new("cleanup.policy", Some("compact".to_owned())),
+            OpType::Set,
+        );
+        let mut configs = IndexMap::new();
+        configs.insert(valid.clone(), vec![op.clone()]);
+        configs.insert(invalid.clone(), vec![op]);
+        let result = env.admin.incremental_alter_configs(configs, None);
+        result.values()[&valid].get_timeout(TIMEOUT).unwrap();
+        assert!(matches!(
+            result.values()[&invalid].get_timeout(TIMEOUT),
+            Err(KafkaError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn create_acls_reports_errors_per_binding() {
+        let env = Env::new(&[]);
+        env.client
+            .prepare_response(AbstractResponse::CreateAcls(CreateAclsResponse::new(vec![
+                AclCreationResult::new(Errors::None),
+                AclCreationResult::new(Errors::SecurityDisabled),
+            ])));
+
+        let result = env
+            .admin
+            .create_acls(vec![acl("User:alice"), acl("User:bob")], None);
+        result.values()[&acl("User:alice")]
+            .get_timeout(TIMEOUT)
+            .unwrap();
+        assert!(matches!(
+            result.values()[&acl("User:bob")].get_timeout(TIMEOUT),
+            Err(KafkaError::SecurityDisabled(_))
+        ));
+    }
+
+    #[test]
+    fn describe_and_delete_acls_return_the_matching_bindings() {
+        let env = Env::new(&[]);
+        env.client
+            .prepare_response(AbstractResponse::DescribeAcls(DescribeAclsResponse::new(
+                Errors::None,
+                vec![acl("User:alice"), acl("User:bob")],
+            )));
+        let described = env
+            .admin
+            .describe_acls(AclBindingFilter::ANY, None)
+            .values()
+            .get_timeout(TIMEOUT)
+            .unwrap();
+        assert_eq!(described, vec![acl("User:alice"), acl("User:bob")]);
+
+        env.client
+            .prepare_response(AbstractResponse::DeleteAcls(DeleteAclsResponse::new(vec![
+                DeleteAclsFilterResult::new(
+                    Errors::None,
+                    vec![DeleteAclsMatchingAcl {
+                        error: Errors::None,
+                        error_message: None,
+                        acl: acl("User:alice"),
+                    }],
+                ),
+            ])));
+        let deleted = env
+            .admin
+            .delete_acls(vec![acl("User:alice").to_filter()], None)
+            .all()
+            .get_timeout(TIMEOUT)
+            .unwrap();
+        assert_eq!(deleted, vec![acl("User:alice")]);
+    }
+}
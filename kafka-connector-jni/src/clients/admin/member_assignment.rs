@@ -0,0 +1,31 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+use crate::common::topic_partition::TopicPartition;
+
+/// A description of the assignments of a specific group member.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemberAssignment {
+    pub topic_partitions: HashSet<TopicPartition>,
+}
+
+impl MemberAssignment {
+    pub fn new(topic_partitions: HashSet<TopicPartition>) -> MemberAssignment {
+        MemberAssignment { topic_partitions }
+    }
+}
+
+impl Display for MemberAssignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(topicPartitions=")?;
+        for (i, tp) in self.topic_partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            tp.fmt(f)?;
+        }
+        f.write_str(")")
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt::{self, Display};
+
+use super::member_assignment::MemberAssignment;
+
+/// A detailed description of a single group instance in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberDescription {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub client_id: String,
+    pub host: String,
+    pub assignment: MemberAssignment,
+}
+
+impl MemberDescription {
+    pub fn new(
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        client_id: impl Into<String>,
+        host: impl Into<String>,
+        assignment: MemberAssignment,
+    ) -> MemberDescription {
+        MemberDescription {
+            member_id: member_id.into(),
+            group_instance_id,
+            client_id: client_id.into(),
+            host: host.into(),
+            assignment,
+        }
+    }
+}
+
+impl Display for MemberDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(memberId={}, groupInstanceId={}, clientId={}, host={}, assignment={})",
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null"),
+            self.client_id,
+            self.host,
+            self.assignment
+        )
+    }
+}
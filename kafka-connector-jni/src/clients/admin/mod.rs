@@ -0,0 +1,18 @@
+pub mod admin_client;
+pub mod admin_client_config;
+pub mod alter_config_op;
+pub mod config;
+pub mod config_entry;
+pub mod consumer_group_description;
+pub mod consumer_group_listing;
+pub mod internals;
+pub mod kafka_admin_client;
+pub mod member_assignment;
+pub mod member_description;
+pub mod new_partitions;
+pub mod new_topic;
+pub mod offset_spec;
+pub mod options;
+pub mod results;
+pub mod topic_description;
+pub mod topic_listing;
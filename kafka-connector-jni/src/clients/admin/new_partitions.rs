@@ -0,0 +1,59 @@
+use std::fmt::{self, Display};
+
+/// Describes new partitions for a particular topic in a call to `Admin::create_partitions`.
+///
+/// The API of this class is evolving, see `Admin` for details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewPartitions {
+    /// The total number of partitions after the operation succeeds.
+    pub total_count: i32,
+    /// The replica assignments for the new partitions, or `None` if the assignment will be done
+    /// by the controller.
+    pub new_assignments: Option<Vec<Vec<i32>>>,
+}
+
+impl NewPartitions {
+    /// Increase the partition count for a topic to the given `total_count`. The assignment of
+    /// new replicas to brokers will be decided by the broker.
+    pub fn increase_to(total_count: i32) -> NewPartitions {
+        NewPartitions {
+            total_count,
+            new_assignments: None,
+        }
+    }
+
+    /// Increase the partition count for a topic to the given `total_count` assigning the new
+    /// partitions according to the given `new_assignments`. The length of the given
+    /// `new_assignments` should equal `total_count - old_count`, since the assignment of existing
+    /// partitions are not changed. Each inner list of `new_assignments` should have a length
+    /// equal to the topic's replication factor. The first broker id in each inner list is the
+    /// "preferred replica".
+    ///
+    /// For example, suppose a topic currently has a replication factor of 2, and has 3
+    /// partitions. The number of partitions can be increased to 6 using a `NewPartition`
+    /// constructed like this:
+    ///
+    /// `NewPartitions::increase_to_with_assignments(6, vec![vec![1, 2], vec![2, 3], vec![3, 1]])`
+    ///
+    /// In this example partition 3's preferred leader will be broker 1, partition 4's preferred
+    /// leader will be broker 2 and partition 5's preferred leader will be broker 3.
+    pub fn increase_to_with_assignments(
+        total_count: i32,
+        new_assignments: Vec<Vec<i32>>,
+    ) -> NewPartitions {
+        NewPartitions {
+            total_count,
+            new_assignments: Some(new_assignments),
+        }
+    }
+}
+
+impl Display for NewPartitions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(totalCount={}, newAssignments=", self.total_count)?;
+        match &self.new_assignments {
+            Some(new_assignments) => write!(f, "{:?})", new_assignments),
+            None => f.write_str("null)"),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt::{self, Display};
+
+use indexmap::IndexMap;
+
+use crate::common::requests::create_topics_request::{
+    CreatableTopic, NO_NUM_PARTITIONS, NO_REPLICATION_FACTOR,
+};
+
+/// A new topic to be created via `Admin::create_topics`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewTopic {
+    pub name: String,
+    /// The number of partitions, `None` to use the broker default.
+    pub num_partitions: Option<i32>,
+    /// The replication factor, `None` to use the broker default.
+    pub replication_factor: Option<i16>,
+    /// A map from partition id to replica ids (i.e. broker ids), `None` if the partitions and
+    /// replication factor are given instead.
+    pub replicas_assignments: Option<IndexMap<i32, Vec<i32>>>,
+    /// The configuration for the new topic.
+    pub configs: IndexMap<String, String>,
+}
+
+impl NewTopic {
+    /// A new topic with the specified replication factor and number of partitions, `None`
+    /// values use the broker defaults.
+    pub fn new(
+        name: impl Into<String>,
+        num_partitions: Option<i32>,
+        replication_factor: Option<i16>,
+    ) -> NewTopic {
+        NewTopic {
+            name: name.into(),
+            num_partitions,
+            replication_factor,
+            replicas_assignments: None,
+            configs: IndexMap::new(),
+        }
+    }
+
+    /// A new topic with the specified replica assignment configuration.
+    ///
+    /// * `replicas_assignments` - a map from partition id to replica ids (i.e. broker ids).
+    ///   Although not enforced, it is generally a good idea for all partitions to have the same
+    ///   number of replicas.
+    pub fn with_replicas_assignments(
+        name: impl Into<String>,
+        replicas_assignments: IndexMap<i32, Vec<i32>>,
+    ) -> NewTopic {
+        NewTopic {
+            replicas_assignments: Some(replicas_assignments),
+            ..NewTopic::new(name, None, None)
+        }
+    }
+
+    /// Set the configuration to use on the new topic.
+    pub fn configs(mut self, configs: IndexMap<String, String>) -> NewTopic {
+        self.configs = configs;
+        self
+    }
+
+    pub(crate) fn convert_to_creatable_topic(&self) -> CreatableTopic {
+        CreatableTopic {
+            name: self.name.clone(),
+            num_partitions: self.num_partitions.unwrap_or(NO_NUM_PARTITIONS),
+            replication_factor: self.replication_factor.unwrap_or(NO_REPLICATION_FACTOR),
+            assignments: self.replicas_assignments.clone().unwrap_or_default(),
+            configs: self
+                .configs
+                .iter()
+                .map(|(name, value)| (name.clone(), Some(value.clone())))
+                .collect(),
+        }
+    }
+}
+
+impl Display for NewTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(name={}, numPartitions=", self.name)?;
+        match self.num_partitions {
+            Some(num_partitions) => write!(f, "{}", num_partitions)?,
+            None => f.write_str("default")?,
+        }
+        f.write_str(", replicationFactor=")?;
+        match self.replication_factor {
+            Some(replication_factor) => write!(f, "{}", replication_factor)?,
+            None => f.write_str("default")?,
+        }
+        f.write_str(", replicasAssignments=")?;
+        match &self.replicas_assignments {
+            Some(replicas_assignments) => write!(f, "{:?}", replicas_assignments)?,
+            None => f.write_str("null")?,
+        }
+        write!(f, ", configs={:?})", self.configs)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::common::requests::list_offsets_request::{EARLIEST_TIMESTAMP, LATEST_TIMESTAMP};
+
+/// This class allows to specify the desired offsets when using `Admin::list_offsets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffsetSpec {
+    /// Used to retrieve the earliest offset of a partition
+    Earliest,
+    /// Used to retrieve the latest offset of a partition
+    Latest,
+    /// Used to retrieve the earliest offset whose timestamp is greater than or equal to the
+    /// given timestamp in the corresponding partition
+    ForTimestamp(i64),
+}
+
+impl OffsetSpec {
+    /// The timestamp to send in the ListOffsets request.
+    pub(crate) fn timestamp(&self) -> i64 {
+        match self {
+            OffsetSpec::Earliest => EARLIEST_TIMESTAMP,
+            OffsetSpec::Latest => LATEST_TIMESTAMP,
+            OffsetSpec::ForTimestamp(timestamp) => *timestamp,
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Options for the `Admin` operations.
+//!
+//! Every options struct has a `timeout_ms` which, when set, overrides the request timeout
+//! (`default.api.timeout.ms`) of the call.
+
+use std::collections::HashSet;
+
+use crate::common::{
+    consumer_group_state::ConsumerGroupState, isolation_level::IsolationLevel,
+    topic_partition::TopicPartition,
+};
+
+/// Options for `Admin::create_topics`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateTopicsOptions {
+    pub timeout_ms: Option<i32>,
+    /// Validate the request only and don't actually create the topics.
+    pub validate_only: bool,
+}
+
+/// Options for `Admin::delete_topics`.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteTopicsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::list_topics`.
+#[derive(Debug, Clone, Default)]
+pub struct ListTopicsOptions {
+    pub timeout_ms: Option<i32>,
+    /// Whether we should list internal topics.
+    pub list_internal: bool,
+}
+
+/// Options for `Admin::describe_topics`.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeTopicsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::describe_cluster`.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeClusterOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::create_partitions`.
+#[derive(Debug, Clone, Default)]
+pub struct CreatePartitionsOptions {
+    pub timeout_ms: Option<i32>,
+    /// Validate the request only and don't actually create the new partitions.
+    pub validate_only: bool,
+}
+
+/// Options for `Admin::describe_configs`.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeConfigsOptions {
+    pub timeout_ms: Option<i32>,
+    /// Return synonym configs for each config entry.
+    pub include_synonyms: bool,
+    /// Return documentation for each config entry.
+    pub include_documentation: bool,
+}
+
+/// Options for `Admin::incremental_alter_configs`.
+#[derive(Debug, Clone, Default)]
+pub struct AlterConfigsOptions {
+    pub timeout_ms: Option<i32>,
+    /// Validate the request only and don't actually alter the configs.
+    pub validate_only: bool,
+}
+
+/// Options for `Admin::create_acls`.
+#[derive(Debug, Clone, Default)]
+pub struct CreateAclsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::describe_acls`.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeAclsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::delete_acls`.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteAclsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::list_consumer_groups`.
+#[derive(Debug, Clone, Default)]
+pub struct ListConsumerGroupsOptions {
+    pub timeout_ms: Option<i32>,
+    /// If states is set, only groups in these states will be returned by
+    /// `list_consumer_groups`. Otherwise, all groups are returned. This operation is supported
+    /// by brokers with version 2.6.0 or later.
+    pub states: HashSet<ConsumerGroupState>,
+}
+
+/// Options for `Admin::describe_consumer_groups`.
+#[derive(Debug, Clone, Default)]
+pub struct DescribeConsumerGroupsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::delete_consumer_groups`.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteConsumerGroupsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::list_consumer_group_offsets`.
+#[derive(Debug, Clone, Default)]
+pub struct ListConsumerGroupOffsetsOptions {
+    pub timeout_ms: Option<i32>,
+    /// The topic partitions to list, `None` to list all topic partitions of the group.
+    pub topic_partitions: Option<Vec<TopicPartition>>,
+}
+
+/// Options for `Admin::delete_consumer_group_offsets`.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteConsumerGroupOffsetsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::alter_consumer_group_offsets`.
+#[derive(Debug, Clone, Default)]
+pub struct AlterConsumerGroupOffsetsOptions {
+    pub timeout_ms: Option<i32>,
+}
+
+/// Options for `Admin::list_offsets`.
+#[derive(Debug, Clone)]
+pub struct ListOffsetsOptions {
+    pub timeout_ms: Option<i32>,
+    pub isolation_level: IsolationLevel,
+}
+
+impl Default for ListOffsetsOptions {
+    fn default() -> Self {
+        ListOffsetsOptions {
+            timeout_ms: None,
+            isolation_level: IsolationLevel::ReadUncommitted,
+        }
+    }
+}
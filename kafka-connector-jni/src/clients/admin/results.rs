@@ -0,0 +1,643 @@
+//! Results of the `Admin` operations. They hold one `KafkaFuture` per resource of the request,
+//! completed by the admin client thread.
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::consumer::offset_and_metadata::OffsetAndMetadata,
+    common::{
+        acl::{acl_binding::AclBinding, acl_binding_filter::AclBindingFilter},
+        config::config_resource::ConfigResource,
+        errors::{KafkaError, Result},
+        kafka_future::KafkaFuture,
+        node::Node,
+        protocol::errors::Errors,
+        topic_partition::TopicPartition,
+    },
+};
+
+use super::{
+    config::Config, consumer_group_description::ConsumerGroupDescription,
+    consumer_group_listing::ConsumerGroupListing, topic_description::TopicDescription,
+    topic_listing::TopicListing,
+};
+
+/// Returns a future completed with the values of all `futures` once they all complete.
+fn all_values<K, V>(futures: &IndexMap<K, KafkaFuture<V>>) -> KafkaFuture<IndexMap<K, V>>
+where
+    K: Clone + Eq + Hash + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    let futures = futures.clone();
+    KafkaFuture::all_of(futures.values().cloned().collect()).then_apply(move |_| {
+        futures
+            .iter()
+            .map(|(key, future)| Ok((key.clone(), future.get()?)))
+            .collect()
+    })
+}
+
+/// Returns the error of `key` in `errors`, `IllegalArgument` with `key_not_found_message` if it
+/// is missing.
+fn sub_level_error<K: Eq + Hash>(
+    errors: &IndexMap<K, Errors>,
+    key: &K,
+    key_not_found_message: impl FnOnce() -> String,
+) -> Option<KafkaError> {
+    match errors.get(key) {
+        Some(error) => error.exception(None),
+        None => Some(KafkaError::IllegalArgument(key_not_found_message())),
+    }
+}
+
+/// The number of partitions, replication factor and configs of a created topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicMetadataAndConfig {
+    pub num_partitions: i32,
+    pub replication_factor: i16,
+    /// The topic configs, `None` for brokers which do not return them.
+    pub config: Option<Config>,
+}
+
+/// The result of `Admin::create_topics`.
+pub struct CreateTopicsResult {
+    futures: IndexMap<String, KafkaFuture<TopicMetadataAndConfig>>,
+}
+
+impl CreateTopicsResult {
+    pub(crate) fn new(
+        futures: IndexMap<String, KafkaFuture<TopicMetadataAndConfig>>,
+    ) -> CreateTopicsResult {
+        CreateTopicsResult { futures }
+    }
+
+    /// Return a map from topic names to futures, which can be used to check the status of
+    /// individual topic creations.
+    pub fn values(&self) -> &IndexMap<String, KafkaFuture<TopicMetadataAndConfig>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds if all the topic creations succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+
+    /// Returns a future that provides topic configs for the topic when the request completes.
+    ///
+    /// If broker version doesn't support replication factor in the response, the future fails
+    /// with `UnsupportedVersion`.
+    pub fn config(&self, topic: &str) -> Option<KafkaFuture<Config>> {
+        self.futures.get(topic).map(|future| {
+            future.then_apply(|metadata| {
+                metadata.config.ok_or_else(|| {
+                    KafkaError::UnsupportedVersion(
+                        "Topic metadata and configs in CreateTopics response not supported"
+                            .to_owned(),
+                    )
+                })
+            })
+        })
+    }
+
+    /// Returns a future that provides number of partitions in the topic when the request
+    /// completes.
+    pub fn num_partitions(&self, topic: &str) -> Option<KafkaFuture<i32>> {
+        self.futures
+            .get(topic)
+            .map(|future| future.then_apply(|metadata| Ok(metadata.num_partitions)))
+    }
+
+    /// Returns a future that provides replication factor for the topic when the request
+    /// completes.
+    pub fn replication_factor(&self, topic: &str) -> Option<KafkaFuture<i16>> {
+        self.futures
+            .get(topic)
+            .map(|future| future.then_apply(|metadata| Ok(metadata.replication_factor)))
+    }
+}
+
+/// The result of `Admin::delete_topics`.
+pub struct DeleteTopicsResult {
+    futures: IndexMap<String, KafkaFuture<()>>,
+}
+
+impl DeleteTopicsResult {
+    pub(crate) fn new(futures: IndexMap<String, KafkaFuture<()>>) -> DeleteTopicsResult {
+        DeleteTopicsResult { futures }
+    }
+
+    /// Return a map from topic names to futures which can be used to check the status of
+    /// individual deletions.
+    pub fn values(&self) -> &IndexMap<String, KafkaFuture<()>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the topic deletions succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+}
+
+/// The result of `Admin::list_topics`.
+pub struct ListTopicsResult {
+    future: KafkaFuture<IndexMap<String, TopicListing>>,
+}
+
+impl ListTopicsResult {
+    pub(crate) fn new(future: KafkaFuture<IndexMap<String, TopicListing>>) -> ListTopicsResult {
+        ListTopicsResult { future }
+    }
+
+    /// Return a future which yields a map of topic names to TopicListing objects.
+    pub fn names_to_listings(&self) -> KafkaFuture<IndexMap<String, TopicListing>> {
+        self.future.clone()
+    }
+
+    /// Return a future which yields a collection of TopicListing objects.
+    pub fn listings(&self) -> KafkaFuture<Vec<TopicListing>> {
+        self.future
+            .then_apply(|listings| Ok(listings.into_iter().map(|(_, listing)| listing).collect()))
+    }
+
+    /// Return a future which yields a collection of topic names.
+    pub fn names(&self) -> KafkaFuture<HashSet<String>> {
+        self.future
+            .then_apply(|listings| Ok(listings.into_iter().map(|(name, _)| name).collect()))
+    }
+}
+
+/// The result of `Admin::describe_topics`.
+pub struct DescribeTopicsResult {
+    futures: IndexMap<String, KafkaFuture<TopicDescription>>,
+}
+
+impl DescribeTopicsResult {
+    pub(crate) fn new(
+        futures: IndexMap<String, KafkaFuture<TopicDescription>>,
+    ) -> DescribeTopicsResult {
+        DescribeTopicsResult { futures }
+    }
+
+    /// Return a map from topic names to futures which can be used to check the status of
+    /// individual topics.
+    pub fn values(&self) -> &IndexMap<String, KafkaFuture<TopicDescription>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the topic descriptions succeed.
+    pub fn all(&self) -> KafkaFuture<IndexMap<String, TopicDescription>> {
+        all_values(&self.futures)
+    }
+}
+
+/// The result of `Admin::describe_cluster`.
+pub struct DescribeClusterResult {
+    pub(crate) nodes: KafkaFuture<Vec<Node>>,
+    pub(crate) controller: KafkaFuture<Option<Node>>,
+    pub(crate) cluster_id: KafkaFuture<Option<String>>,
+}
+
+impl DescribeClusterResult {
+    /// Returns a future which yields a collection of nodes.
+    pub fn nodes(&self) -> KafkaFuture<Vec<Node>> {
+        self.nodes.clone()
+    }
+
+    /// Returns a future which yields the current controller id. Note that this may yield
+    /// `None`, if the controller ID is not yet known.
+    pub fn controller(&self) -> KafkaFuture<Option<Node>> {
+        self.controller.clone()
+    }
+
+    /// Returns a future which yields the current cluster id. The future value will be `None` if
+    /// the cluster id is not available.
+    pub fn cluster_id(&self) -> KafkaFuture<Option<String>> {
+        self.cluster_id.clone()
+    }
+}
+
+/// The result of `Admin::create_partitions`.
+pub struct CreatePartitionsResult {
+    futures: IndexMap<String, KafkaFuture<()>>,
+}
+
+impl CreatePartitionsResult {
+    pub(crate) fn new(futures: IndexMap<String, KafkaFuture<()>>) -> CreatePartitionsResult {
+        CreatePartitionsResult { futures }
+    }
+
+    /// Return a map from topic names to futures, which can be used to check the status of
+    /// individual partition creations.
+    pub fn values(&self) -> &IndexMap<String, KafkaFuture<()>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds if all the partition creations succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+}
+
+/// The result of `Admin::describe_configs`.
+pub struct DescribeConfigsResult {
+    futures: IndexMap<ConfigResource, KafkaFuture<Config>>,
+}
+
+impl DescribeConfigsResult {
+    pub(crate) fn new(
+        futures: IndexMap<ConfigResource, KafkaFuture<Config>>,
+    ) -> DescribeConfigsResult {
+        DescribeConfigsResult { futures }
+    }
+
+    /// Return a map from resources to futures which can be used to check the status of the
+    /// configuration for each resource.
+    pub fn values(&self) -> &IndexMap<ConfigResource, KafkaFuture<Config>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the config descriptions succeed.
+    pub fn all(&self) -> KafkaFuture<IndexMap<ConfigResource, Config>> {
+        all_values(&self.futures)
+    }
+}
+
+/// The result of `Admin::incremental_alter_configs`.
+pub struct AlterConfigsResult {
+    futures: IndexMap<ConfigResource, KafkaFuture<()>>,
+}
+
+impl AlterConfigsResult {
+    pub(crate) fn new(futures: IndexMap<ConfigResource, KafkaFuture<()>>) -> AlterConfigsResult {
+        AlterConfigsResult { futures }
+    }
+
+    /// Return a map from resources to futures which can be used to check the status of the
+    /// operation on each resource.
+    pub fn values(&self) -> &IndexMap<ConfigResource, KafkaFuture<()>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the alter configs operations succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+}
+
+/// The result of `Admin::create_acls`.
+pub struct CreateAclsResult {
+    futures: IndexMap<AclBinding, KafkaFuture<()>>,
+}
+
+impl CreateAclsResult {
+    pub(crate) fn new(futures: IndexMap<AclBinding, KafkaFuture<()>>) -> CreateAclsResult {
+        CreateAclsResult { futures }
+    }
+
+    /// Return a map from ACL bindings to futures which can be used to check the status of the
+    /// creation of each ACL binding.
+    pub fn values(&self) -> &IndexMap<AclBinding, KafkaFuture<()>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the ACL creations succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+}
+
+/// The result of `Admin::describe_acls`.
+pub struct DescribeAclsResult {
+    future: KafkaFuture<Vec<AclBinding>>,
+}
+
+impl DescribeAclsResult {
+    pub(crate) fn new(future: KafkaFuture<Vec<AclBinding>>) -> DescribeAclsResult {
+        DescribeAclsResult { future }
+    }
+
+    /// Return a future containing the ACLs requested.
+    pub fn values(&self) -> KafkaFuture<Vec<AclBinding>> {
+        self.future.clone()
+    }
+}
+
+/// A class containing either the deleted ACL binding or an error if the delete failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterResult {
+    /// The deleted ACL binding, `None` if there was an error.
+    pub binding: Option<AclBinding>,
+    /// The error if the deletion failed.
+    pub error: Option<KafkaError>,
+}
+
+/// A class containing the results of the delete ACLs operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterResults {
+    pub values: Vec<FilterResult>,
+}
+
+/// The result of `Admin::delete_acls`.
+pub struct DeleteAclsResult {
+    futures: IndexMap<AclBindingFilter, KafkaFuture<FilterResults>>,
+}
+
+impl DeleteAclsResult {
+    pub(crate) fn new(
+        futures: IndexMap<AclBindingFilter, KafkaFuture<FilterResults>>,
+    ) -> DeleteAclsResult {
+        DeleteAclsResult { futures }
+    }
+
+    /// Return a map from acl filters to futures which can be used to check the status of the
+    /// deletions by each filter.
+    pub fn values(&self) -> &IndexMap<AclBindingFilter, KafkaFuture<FilterResults>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the ACLs deletions succeed, and which contains
+    /// all the deleted ACLs. Note that it if the filters don't match any ACLs, this is not
+    /// considered an error.
+    pub fn all(&self) -> KafkaFuture<Vec<AclBinding>> {
+        all_values(&self.futures).then_apply(|results| {
+            let mut acls = vec![];
+            for (_, results) in results {
+                for result in results.values {
+                    if let Some(error) = result.error {
+                        return Err(error);
+                    }
+                    acls.extend(result.binding);
+                }
+            }
+            Ok(acls)
+        })
+    }
+}
+
+/// The result of `Admin::list_consumer_groups`.
+pub struct ListConsumerGroupsResult {
+    future: KafkaFuture<Vec<Result<ConsumerGroupListing>>>,
+}
+
+impl ListConsumerGroupsResult {
+    pub(crate) fn new(
+        future: KafkaFuture<Vec<Result<ConsumerGroupListing>>>,
+    ) -> ListConsumerGroupsResult {
+        ListConsumerGroupsResult { future }
+    }
+
+    /// Returns a future that yields either an error, or the full set of consumer group
+    /// listings.
+    ///
+    /// In the event of a failure, the future yields nothing but the first error which occurred.
+    pub fn all(&self) -> KafkaFuture<Vec<ConsumerGroupListing>> {
+        self.future
+            .then_apply(|results| results.into_iter().collect::<Result<Vec<_>>>())
+    }
+
+    /// Returns a future which yields just the valid listings.
+    ///
+    /// This future never fails with an error, no matter what happens. Errors are completely
+    /// ignored. If nothing can be fetched, an empty collection is yielded. If there is an
+    /// error, but some results can be returned, this future will yield those partial results.
+    /// When using this future, it is a good idea to also check the errors future so that errors
+    /// can be displayed and handled.
+    pub fn valid(&self) -> KafkaFuture<Vec<ConsumerGroupListing>> {
+        self.future
+            .then_apply(|results| Ok(results.into_iter().filter_map(|r| r.ok()).collect()))
+    }
+
+    /// Returns a future which yields just the errors which occurred.
+    ///
+    /// If this future yields a non-empty collection, it is very likely that elements are
+    /// missing from the valid() set.
+    ///
+    /// This future itself never fails with an error. In the event of an error, this future will
+    /// successfully yield a collection containing at least one error.
+    pub fn errors(&self) -> KafkaFuture<Vec<KafkaError>> {
+        self.future
+            .then_apply(|results| Ok(results.into_iter().filter_map(|r| r.err()).collect()))
+    }
+}
+
+/// The result of `Admin::describe_consumer_groups`.
+pub struct DescribeConsumerGroupsResult {
+    futures: IndexMap<String, KafkaFuture<ConsumerGroupDescription>>,
+}
+
+impl DescribeConsumerGroupsResult {
+    pub(crate) fn new(
+        futures: IndexMap<String, KafkaFuture<ConsumerGroupDescription>>,
+    ) -> DescribeConsumerGroupsResult {
+        DescribeConsumerGroupsResult { futures }
+    }
+
+    /// Return a map from group id to futures which yield group descriptions.
+    pub fn described_groups(&self) -> &IndexMap<String, KafkaFuture<ConsumerGroupDescription>> {
+        &self.futures
+    }
+
+    /// Return a future which yields all ConsumerGroupDescription objects, if all the describes
+    /// succeed.
+    pub fn all(&self) -> KafkaFuture<IndexMap<String, ConsumerGroupDescription>> {
+        all_values(&self.futures)
+    }
+}
+
+/// The result of `Admin::delete_consumer_groups`.
+pub struct DeleteConsumerGroupsResult {
+    futures: IndexMap<String, KafkaFuture<()>>,
+}
+
+impl DeleteConsumerGroupsResult {
+    pub(crate) fn new(futures: IndexMap<String, KafkaFuture<()>>) -> DeleteConsumerGroupsResult {
+        DeleteConsumerGroupsResult { futures }
+    }
+
+    /// Return a map from group id to futures which can be used to check the status of
+    /// individual deletions.
+    pub fn deleted_groups(&self) -> &IndexMap<String, KafkaFuture<()>> {
+        &self.futures
+    }
+
+    /// Return a future which succeeds only if all the consumer group deletions succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        KafkaFuture::all_of(self.futures.values().cloned().collect())
+    }
+}
+
+/// The result of `Admin::list_consumer_group_offsets`.
+pub struct ListConsumerGroupOffsetsResult {
+    future: KafkaFuture<IndexMap<TopicPartition, Option<OffsetAndMetadata>>>,
+}
+
+impl ListConsumerGroupOffsetsResult {
+    pub(crate) fn new(
+        future: KafkaFuture<IndexMap<TopicPartition, Option<OffsetAndMetadata>>>,
+    ) -> ListConsumerGroupOffsetsResult {
+        ListConsumerGroupOffsetsResult { future }
+    }
+
+    /// Return a future which yields a map of topic partitions to OffsetAndMetadata objects. If
+    /// the group does not have a committed offset for this partition, the corresponding value
+    /// in the returned map will be `None`.
+    pub fn partitions_to_offset_and_metadata(
+        &self,
+    ) -> KafkaFuture<IndexMap<TopicPartition, Option<OffsetAndMetadata>>> {
+        self.future.clone()
+    }
+}
+
+/// The result of `Admin::delete_consumer_group_offsets`.
+pub struct DeleteConsumerGroupOffsetsResult {
+    future: KafkaFuture<IndexMap<TopicPartition, Errors>>,
+    partitions: HashSet<TopicPartition>,
+}
+
+impl DeleteConsumerGroupOffsetsResult {
+    pub(crate) fn new(
+        future: KafkaFuture<IndexMap<TopicPartition, Errors>>,
+        partitions: HashSet<TopicPartition>,
+    ) -> DeleteConsumerGroupOffsetsResult {
+        DeleteConsumerGroupOffsetsResult { future, partitions }
+    }
+
+    /// Return a future which can be used to check the result for a given partition.
+    pub fn partition_result(&self, partition: &TopicPartition) -> Result<KafkaFuture<()>> {
+        if !self.partitions.contains(partition) {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Partition {} was not included in the original request",
+                partition
+            )));
+        }
+        let partition = partition.clone();
+        Ok(self.future.then_apply(move |errors| {
+            match sub_level_error(&errors, &partition, || {
+                format!(
+                    "Offset deletion result for partition \"{}\" was not included in the response",
+                    partition
+                )
+            }) {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }))
+    }
+
+    /// Return a future which succeeds only if all the deletions succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        let partitions = self.partitions.clone();
+        self.future.then_apply(move |errors| {
+            for partition in &partitions {
+                if let Some(error) = sub_level_error(&errors, partition, || {
+                    format!(
+                        "Offset deletion result for partition \"{}\" was not included in the response",
+                        partition
+                    )
+                }) {
+                    return Err(error);
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// The result of `Admin::alter_consumer_group_offsets`.
+pub struct AlterConsumerGroupOffsetsResult {
+    future: KafkaFuture<IndexMap<TopicPartition, Errors>>,
+}
+
+impl AlterConsumerGroupOffsetsResult {
+    pub(crate) fn new(
+        future: KafkaFuture<IndexMap<TopicPartition, Errors>>,
+    ) -> AlterConsumerGroupOffsetsResult {
+        AlterConsumerGroupOffsetsResult { future }
+    }
+
+    /// Return a future which can be used to check the result for a given partition.
+    pub fn partition_result(&self, partition: &TopicPartition) -> KafkaFuture<()> {
+        let partition = partition.clone();
+        self.future.then_apply(move |errors| {
+            match sub_level_error(&errors, &partition, || {
+                format!(
+                    "Alter offset for partition \"{}\" was not attempted",
+                    partition
+                )
+            }) {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Return a future which succeeds if all the alter offsets succeed.
+    pub fn all(&self) -> KafkaFuture<()> {
+        self.future.then_apply(|errors| {
+            match errors.values().find_map(|error| error.exception(None)) {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        })
+    }
+}
+
+/// The offset, timestamp and leader epoch returned for a partition by `Admin::list_offsets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListOffsetsResultInfo {
+    pub offset: i64,
+    pub timestamp: i64,
+    pub leader_epoch: Option<i32>,
+}
+
+impl Display for ListOffsetsResultInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ListOffsetsResultInfo(offset={}, timestamp={}, leaderEpoch=",
+            self.offset, self.timestamp
+        )?;
+        match self.leader_epoch {
+            Some(leader_epoch) => write!(f, "{})", leader_epoch),
+            None => f.write_str("null)"),
+        }
+    }
+}
+
+/// The result of `Admin::list_offsets`.
+pub struct ListOffsetsResult {
+    futures: IndexMap<TopicPartition, KafkaFuture<ListOffsetsResultInfo>>,
+}
+
+impl ListOffsetsResult {
+    pub(crate) fn new(
+        futures: IndexMap<TopicPartition, KafkaFuture<ListOffsetsResultInfo>>,
+    ) -> ListOffsetsResult {
+        ListOffsetsResult { futures }
+    }
+
+    /// Return a future which can be used to check the result for a given partition.
+    pub fn partition_result(
+        &self,
+        partition: &TopicPartition,
+    ) -> Result<KafkaFuture<ListOffsetsResultInfo>> {
+        self.futures.get(partition).cloned().ok_or_else(|| {
+            KafkaError::IllegalArgument(format!(
+                "List Offsets for partition \"{}\" was not attempted",
+                partition
+            ))
+        })
+    }
+
+    /// Return a future which succeeds only if offsets for all specified partitions have been
+    /// successfully retrieved.
+    pub fn all(&self) -> KafkaFuture<IndexMap<TopicPartition, ListOffsetsResultInfo>> {
+        all_values(&self.futures)
+    }
+}
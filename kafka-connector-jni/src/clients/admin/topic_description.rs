@@ -0,0 +1,46 @@
+use std::fmt::{self, Display};
+
+use crate::common::topic_partition_info::TopicPartitionInfo;
+
+/// A detailed description of a single topic in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicDescription {
+    pub name: String,
+    /// Whether the topic is internal to Kafka. An example of an internal topic is the offsets
+    /// and group management topic: `__consumer_offsets`.
+    pub internal: bool,
+    /// A list of partitions where the index represents the partition id and the element
+    /// contains leadership and replica information for that partition.
+    pub partitions: Vec<TopicPartitionInfo>,
+}
+
+impl TopicDescription {
+    pub fn new(
+        name: impl Into<String>,
+        internal: bool,
+        partitions: Vec<TopicPartitionInfo>,
+    ) -> TopicDescription {
+        TopicDescription {
+            name: name.into(),
+            internal,
+            partitions,
+        }
+    }
+}
+
+impl Display for TopicDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(name={}, internal={}, partitions=",
+            self.name, self.internal
+        )?;
+        for (i, partition) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            partition.fmt(f)?;
+        }
+        f.write_str(")")
+    }
+}
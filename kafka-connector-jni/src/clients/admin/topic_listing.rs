@@ -0,0 +1,25 @@
+use std::fmt::{self, Display};
+
+/// A listing of a topic in the cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicListing {
+    pub name: String,
+    /// Whether the topic is internal to Kafka. An example of an internal topic is the offsets
+    /// and group management topic: `__consumer_offsets`.
+    pub internal: bool,
+}
+
+impl TopicListing {
+    pub fn new(name: impl Into<String>, internal: bool) -> TopicListing {
+        TopicListing {
+            name: name.into(),
+            internal,
+        }
+    }
+}
+
+impl Display for TopicListing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(name={}, internal={})", self.name, self.internal)
+    }
+}
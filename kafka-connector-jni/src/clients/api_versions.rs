@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::api_keys::ApiKeys,
+};
+
+/// Range of versions of a single api supported by a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ApiVersion {
+    pub api_key: ApiKeys,
+    pub min_version: i16,
+    pub max_version: i16,
+}
+
+impl ApiVersion {
+    pub fn new(api_key: ApiKeys, min_version: i16, max_version: i16) -> ApiVersion {
+        ApiVersion {
+            api_key,
+            min_version,
+            max_version,
+        }
+    }
+}
+
+/// An internal class which represents the API versions supported by a particular node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeApiVersions {
+    supported_versions: HashMap<ApiKeys, ApiVersion>,
+}
+
+impl NodeApiVersions {
+    pub fn new(node_api_versions: impl IntoIterator<Item = ApiVersion>) -> NodeApiVersions {
+        NodeApiVersions {
+            supported_versions: node_api_versions
+                .into_iter()
+                .map(|version| (version.api_key, version))
+                .collect(),
+        }
+    }
+
+    /// Get the version information for a given API.
+    pub fn api_version(&self, api_key: ApiKeys) -> Option<&ApiVersion> {
+        self.supported_versions.get(&api_key)
+    }
+
+    /// Get the latest version supported by both the node and this client.
+    pub fn latest_usable_version(&self, api_key: ApiKeys) -> Result<i16> {
+        let (oldest_allowed_version, latest_allowed_version) =
+            api_key.supported_versions().ok_or_else(|| {
+                KafkaError::UnsupportedVersion(format!("The client does not support {}", api_key))
+            })?;
+        let version_range = self.api_version(api_key).ok_or_else(|| {
+            KafkaError::UnsupportedVersion(format!("The broker does not support {}", api_key))
+        })?;
+        let min_allowed_version = oldest_allowed_version.max(version_range.min_version);
+        let max_allowed_version = latest_allowed_version.min(version_range.max_version);
+        if min_allowed_version > max_allowed_version {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker does not support {} with version in range [{},{}]. The supported range is [{},{}].",
+                api_key,
+                oldest_allowed_version,
+                latest_allowed_version,
+                version_range.min_version,
+                version_range.max_version
+            )));
+        }
+        Ok(max_allowed_version)
+    }
+}
+
+/// Maintains node api versions for access outside of the network client (which is where the
+/// information is derived). The pattern is akin to the use of `Metadata` for topic metadata.
+#[derive(Default)]
+pub struct ApiVersions {
+    node_api_versions: Mutex<HashMap<String, NodeApiVersions>>,
+}
+
+impl ApiVersions {
+    pub fn new() -> ApiVersions {
+        ApiVersions::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, NodeApiVersions>> {
+        self.node_api_versions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn update(&self, node_id: impl Into<String>, node_api_versions: NodeApiVersions) {
+        self.lock().insert(node_id.into(), node_api_versions);
+    }
+
+    pub fn remove(&self, node_id: &str) {
+        self.lock().remove(node_id);
+    }
+
+    pub fn get(&self, node_id: &str) -> Option<NodeApiVersions> {
+        self.lock().get(node_id).cloned()
+    }
+}
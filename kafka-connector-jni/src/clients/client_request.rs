@@ -0,0 +1,81 @@
+use std::fmt::{self, Display};
+
+use crate::common::requests::{abstract_request::AbstractRequest, request_header::RequestHeader};
+
+use super::client_response::ClientResponse;
+
+/// A callback interface for attaching an action to be executed when a request is complete and the
+/// corresponding response has been received. This handler will also be invoked if there is a
+/// disconnection while handling the request.
+pub type RequestCompletionHandler = Box<dyn FnOnce(ClientResponse) + Send>;
+
+/// A request being sent to the server. This holds both the network send as well as the client-level
+/// metadata.
+pub struct ClientRequest {
+    pub destination: String,
+    pub request: AbstractRequest,
+    pub correlation_id: i32,
+    pub client_id: String,
+    pub created_time_ms: u128,
+    pub expect_response: bool,
+    pub request_timeout_ms: u128,
+    pub callback: Option<RequestCompletionHandler>,
+}
+
+impl ClientRequest {
+    /// * `destination` - The brokerId to send the request to
+    /// * `request` - The request
+    /// * `correlation_id` - The correlation id for this client request
+    /// * `client_id` - The client ID to use for the header
+    /// * `created_time_ms` - The unix timestamp in milliseconds for the time at which this request was created.
+    /// * `expect_response` - Should we expect a response message or is this request complete once it is sent?
+    /// * `request_timeout_ms` - The timeout for the request
+    /// * `callback` - A callback to execute when the response has been received (or `None` if no callback is necessary)
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        destination: impl Into<String>,
+        request: AbstractRequest,
+        correlation_id: i32,
+        client_id: impl Into<String>,
+        created_time_ms: u128,
+        expect_response: bool,
+        request_timeout_ms: u128,
+        callback: Option<RequestCompletionHandler>,
+    ) -> ClientRequest {
+        ClientRequest {
+            destination: destination.into(),
+            request,
+            correlation_id,
+            client_id: client_id.into(),
+            created_time_ms,
+            expect_response,
+            request_timeout_ms,
+            callback,
+        }
+    }
+
+    pub fn make_header(&self, version: i16) -> RequestHeader {
+        RequestHeader::new(
+            self.request.api_key(),
+            version,
+            self.client_id.clone(),
+            self.correlation_id,
+        )
+    }
+}
+
+impl Display for ClientRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ClientRequest(expectResponse={}, callback={}, destination={}, correlationId={}, clientId={}, createdTimeMs={}, requestBuilder={})",
+            self.expect_response,
+            self.callback.is_some(),
+            self.destination,
+            self.correlation_id,
+            self.client_id,
+            self.created_time_ms,
+            self.request
+        )
+    }
+}
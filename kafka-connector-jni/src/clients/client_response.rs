@@ -0,0 +1,67 @@
+use std::fmt::{self, Display};
+
+use crate::common::{
+    errors::KafkaError,
+    requests::{abstract_response::AbstractResponse, request_header::RequestHeader},
+};
+
+/// A response from the server. Contains both the body of the response as well as the correlated
+/// request metadata that was originally sent.
+#[derive(Debug, Clone)]
+pub struct ClientResponse {
+    pub request_header: RequestHeader,
+    pub destination: String,
+    pub received_time_ms: u128,
+    pub latency_ms: u128,
+    pub disconnected: bool,
+    pub version_mismatch: Option<KafkaError>,
+    pub response_body: Option<AbstractResponse>,
+}
+
+impl ClientResponse {
+    /// * `request_header` - The header of the corresponding request
+    /// * `destination` - The node the corresponding request was sent to
+    /// * `created_time_ms` - The unix timestamp when the corresponding request was created
+    /// * `received_time_ms` - The unix timestamp when this response was received
+    /// * `disconnected` - Whether the client disconnected before fully reading a response
+    /// * `version_mismatch` - Whether there was a version mismatch that prevented sending the request.
+    /// * `response_body` - The response contents (or `None`) if we disconnected, no response was expected,
+    ///   or if there was a version mismatch.
+    pub fn new(
+        request_header: RequestHeader,
+        destination: impl Into<String>,
+        created_time_ms: u128,
+        received_time_ms: u128,
+        disconnected: bool,
+        version_mismatch: Option<KafkaError>,
+        response_body: Option<AbstractResponse>,
+    ) -> ClientResponse {
+        ClientResponse {
+            request_header,
+            destination: destination.into(),
+            received_time_ms,
+            latency_ms: received_time_ms.saturating_sub(created_time_ms),
+            disconnected,
+            version_mismatch,
+            response_body,
+        }
+    }
+
+    pub fn has_response(&self) -> bool {
+        self.response_body.is_some()
+    }
+}
+
+impl Display for ClientResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ClientResponse(receivedTimeMs={}, latencyMs={}, disconnected={}, requestHeader={}, responseBody={:?})",
+            self.received_time_ms,
+            self.latency_ms,
+            self.disconnected,
+            self.request_header,
+            self.response_body
+        )
+    }
+}
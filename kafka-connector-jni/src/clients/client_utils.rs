@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use regex::Regex;
+
+use crate::common::{
+    config::abstract_config::AbstractConfig,
+    errors::{KafkaError, Result},
+    network::{network_receive::NetworkReceive, selector::Selector},
+    utils::{log_context::LogContext, time::Time},
+};
+
+use super::{
+    api_versions::ApiVersions,
+    common_client_configs::{
+        BOOTSTRAP_SERVERS_CONFIG, CLIENT_ID_CONFIG, CONNECTIONS_MAX_IDLE_MS_CONFIG,
+        RECONNECT_BACKOFF_MAX_MS_CONFIG, RECONNECT_BACKOFF_MS_CONFIG, REQUEST_TIMEOUT_MS_CONFIG,
+        SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG, SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG,
+    },
+    metadata_updater::MetadataUpdater,
+    network_client::NetworkClient,
+};
+
+/// Parses the `host:port` pairs of `urls`, as given in `bootstrap.servers`. IPv6 hosts may be
+/// enclosed in square brackets.
+///
+/// Host names are not resolved here, that is left to the network client connecting to them.
+pub fn parse_and_validate_addresses(urls: &[String]) -> Result<Vec<(String, i32)>> {
+    let host_port = Regex::new(r"^.*?\[?([0-9a-zA-Z\-%._:]*)\]?:([0-9]+)$").unwrap();
+    let mut addresses = vec![];
+    for url in urls {
+        let url = url.trim();
+        if url.is_empty() {
+            continue;
+        }
+        let captures = host_port.captures(url);
+        let address = captures.and_then(|captures| {
+            let host = captures.get(1)?.as_str();
+            let port = captures.get(2)?.as_str().parse::<i32>().ok()?;
+            Some((host.to_owned(), port)).filter(|(host, _)| !host.is_empty())
+        });
+        match address {
+            Some(address) => addresses.push(address),
+            None => {
+                return Err(KafkaError::Config(format!(
+                    "Invalid url in {}: {}",
+                    BOOTSTRAP_SERVERS_CONFIG, url
+                )))
+            }
+        }
+    }
+    if addresses.is_empty() {
+        return Err(KafkaError::Config(format!(
+            "No resolvable bootstrap urls given in {}",
+            BOOTSTRAP_SERVERS_CONFIG
+        )));
+    }
+    Ok(addresses)
+}
+
+/// Creates the network client of a producer, consumer or admin client from the common client
+/// configs in `config`.
+pub fn create_network_client(
+    config: &AbstractConfig,
+    log_context: &LogContext,
+    api_versions: Arc<ApiVersions>,
+    time: Arc<dyn Time>,
+    max_in_flight_requests_per_connection: usize,
+    metadata_updater: Arc<dyn MetadataUpdater>,
+) -> Result<NetworkClient> {
+    let get_ms = |key: &str| -> Result<u128> {
+        Ok(config.get_long(key)?.unwrap_or_default().max(0) as u128)
+    };
+    let selector = Selector::new(
+        Some(get_ms(CONNECTIONS_MAX_IDLE_MS_CONFIG)?),
+        NetworkReceive::UNLIMITED,
+        time.clone(),
+        log_context,
+    )
+    .map_err(|e| KafkaError::Kafka(format!("Failed to create the selector: {}", e)))?;
+    let request_timeout_ms = config
+        .get_int(REQUEST_TIMEOUT_MS_CONFIG)?
+        .unwrap_or_default()
+        .max(0) as u128;
+    Ok(NetworkClient::new(
+        selector,
+        metadata_updater,
+        config.get_string(CLIENT_ID_CONFIG)?.unwrap_or_default(),
+        max_in_flight_requests_per_connection,
+        get_ms(RECONNECT_BACKOFF_MS_CONFIG)?,
+        get_ms(RECONNECT_BACKOFF_MAX_MS_CONFIG)?,
+        request_timeout_ms,
+        get_ms(SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG)?,
+        get_ms(SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG)?,
+        time,
+        true,
+        api_versions,
+        log_context,
+    ))
+}
@@ -0,0 +1,476 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    io::{self, ErrorKind},
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use crate::common::utils::exponential_backoff::ExponentialBackoff;
+
+/// The states of a node connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Disconnected,
+    Connecting,
+    CheckingApiVersions,
+    Ready,
+}
+
+impl ConnectionState {
+    pub fn is_disconnected(self) -> bool {
+        self == ConnectionState::Disconnected
+    }
+
+    pub fn is_connected(self) -> bool {
+        self == ConnectionState::CheckingApiVersions || self == ConnectionState::Ready
+    }
+}
+
+impl Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConnectionState::Disconnected => "DISCONNECTED",
+            ConnectionState::Connecting => "CONNECTING",
+            ConnectionState::CheckingApiVersions => "CHECKING_API_VERSIONS",
+            ConnectionState::Ready => "READY",
+        })
+    }
+}
+
+/// The state of our connection to a node.
+#[derive(Debug)]
+struct NodeConnectionState {
+    state: ConnectionState,
+    last_connect_attempt_ms: u128,
+    failed_attempts: u32,
+    failed_connect_attempts: u32,
+    reconnect_backoff_ms: u128,
+    connection_setup_timeout_ms: u128,
+    // Connection is being throttled if current time < throttle_until_time_ms.
+    throttle_until_time_ms: u128,
+    host: String,
+    port: i32,
+    addresses: Vec<SocketAddr>,
+    address_index: usize,
+}
+
+impl NodeConnectionState {
+    /// The address to use for the next connection attempt. Each attempt moves to the next
+    /// resolved address of the host, the host is resolved again once all of them were tried.
+    fn current_address(&mut self) -> io::Result<SocketAddr> {
+        if self.address_index >= self.addresses.len() {
+            self.addresses = (self.host.as_str(), self.port as u16)
+                .to_socket_addrs()?
+                .collect();
+            self.address_index = 0;
+        }
+        let address = self
+            .addresses
+            .get(self.address_index)
+            .copied()
+            .ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("No resolvable address for host {}", self.host),
+                )
+            })?;
+        self.address_index += 1;
+        Ok(address)
+    }
+}
+
+/// The state of our connection to each node in the cluster.
+pub struct ClusterConnectionStates {
+    reconnect_backoff: ExponentialBackoff,
+    connection_setup_timeout: ExponentialBackoff,
+    node_state: HashMap<String, NodeConnectionState>,
+    connecting_nodes: HashSet<String>,
+}
+
+impl ClusterConnectionStates {
+    const RECONNECT_BACKOFF_EXP_BASE: u32 = 2;
+    const RECONNECT_BACKOFF_JITTER: f64 = 0.2;
+    const CONNECTION_SETUP_TIMEOUT_EXP_BASE: u32 = 2;
+    const CONNECTION_SETUP_TIMEOUT_JITTER: f64 = 0.2;
+
+    pub fn new(
+        reconnect_backoff_ms: u128,
+        reconnect_backoff_max_ms: u128,
+        connection_setup_timeout_ms: u128,
+        connection_setup_timeout_max_ms: u128,
+    ) -> ClusterConnectionStates {
+        ClusterConnectionStates {
+            reconnect_backoff: ExponentialBackoff::new(
+                reconnect_backoff_ms,
+                Self::RECONNECT_BACKOFF_EXP_BASE,
+                reconnect_backoff_max_ms,
+                Self::RECONNECT_BACKOFF_JITTER,
+            ),
+            connection_setup_timeout: ExponentialBackoff::new(
+                connection_setup_timeout_ms,
+                Self::CONNECTION_SETUP_TIMEOUT_EXP_BASE,
+                connection_setup_timeout_max_ms,
+                Self::CONNECTION_SETUP_TIMEOUT_JITTER,
+            ),
+            node_state: HashMap::new(),
+            connecting_nodes: HashSet::new(),
+        }
+    }
+
+    /// Return true iff we can currently initiate a new connection. This will be the case if we are not
+    /// connected and haven't been connected for at least the minimum reconnection backoff period.
+    pub fn can_connect(&self, id: &str, now: u128) -> bool {
+        match self.node_state.get(id) {
+            None => true,
+            Some(state) => {
+                state.state.is_disconnected()
+                    && now.saturating_sub(state.last_connect_attempt_ms)
+                        >= state.reconnect_backoff_ms
+            }
+        }
+    }
+
+    /// Return true if we are disconnected from the given node and can't re-establish a connection yet.
+    pub fn is_blacked_out(&self, id: &str, now: u128) -> bool {
+        match self.node_state.get(id) {
+            None => false,
+            Some(state) => {
+                state.state.is_disconnected()
+                    && now.saturating_sub(state.last_connect_attempt_ms)
+                        < state.reconnect_backoff_ms
+            }
+        }
+    }
+
+    /// Returns the number of milliseconds to wait, based on the connection state, before attempting to send data. When
+    /// disconnected, this respects the reconnect backoff time. When connecting, return a delay based on the connection
+    /// timeout. When connected, wait indefinitely (i.e. until a wakeup).
+    pub fn connection_delay(&self, id: &str, now: u128) -> u128 {
+        match self.node_state.get(id) {
+            None => 0,
+            Some(state) if state.state == ConnectionState::Connecting => {
+                state.connection_setup_timeout_ms
+            }
+            Some(state) if state.state.is_disconnected() => {
+                let time_waited = now.saturating_sub(state.last_connect_attempt_ms);
+                state.reconnect_backoff_ms.saturating_sub(time_waited)
+            }
+            // When connected, we should be able to delay indefinitely since other events (connection or
+            // data acked) will cause a wakeup once data can be sent.
+            Some(_) => u128::MAX,
+        }
+    }
+
+    /// Return true if a specific connection establishment is currently underway
+    pub fn is_connecting(&self, id: &str) -> bool {
+        self.state(id) == Some(ConnectionState::Connecting)
+    }
+
+    /// Check whether a connection is either being established or awaiting API version information.
+    pub fn is_preparing_connection(&self, id: &str) -> bool {
+        matches!(
+            self.state(id),
+            Some(ConnectionState::Connecting | ConnectionState::CheckingApiVersions)
+        )
+    }
+
+    /// Enter the connecting state for the given connection, moving to a new resolved address if
+    /// necessary.
+    pub fn connecting(&mut self, id: &str, now: u128, host: &str, port: i32) {
+        self.connecting_nodes.insert(id.to_owned());
+        if let Some(state) = self.node_state.get_mut(id) {
+            if state.host == host && state.port == port {
+                state.state = ConnectionState::Connecting;
+                state.last_connect_attempt_ms = now;
+                return;
+            }
+        }
+        // Create a new NodeConnectionState if node_state does not already contain one
+        // for the specified id or if the hostname associated with the node id changed.
+        self.node_state.insert(
+            id.to_owned(),
+            NodeConnectionState {
+                state: ConnectionState::Connecting,
+                last_connect_attempt_ms: now,
+                failed_attempts: 0,
+                failed_connect_attempts: 0,
+                reconnect_backoff_ms: self.reconnect_backoff.backoff(0),
+                connection_setup_timeout_ms: self.connection_setup_timeout.backoff(0),
+                throttle_until_time_ms: 0,
+                host: host.to_owned(),
+                port,
+                addresses: vec![],
+                address_index: 0,
+            },
+        );
+    }
+
+    /// Returns the address to use for the connection to the given node, resolving the host of the
+    /// node if needed.
+    pub fn current_address(&mut self, id: &str) -> io::Result<SocketAddr> {
+        match self.node_state.get_mut(id) {
+            Some(state) => state.current_address(),
+            None => Err(io::Error::new(
+                ErrorKind::NotFound,
+                format!("No entry found for connection {}", id),
+            )),
+        }
+    }
+
+    /// Enter the disconnected state for the given node.
+    pub fn disconnected(&mut self, id: &str, now: u128) {
+        let reconnect_backoff = self.reconnect_backoff;
+        let connection_setup_timeout = self.connection_setup_timeout;
+        if let Some(state) = self.node_state.get_mut(id) {
+            state.last_connect_attempt_ms = now;
+            state.reconnect_backoff_ms = reconnect_backoff.backoff(state.failed_attempts);
+            state.failed_attempts += 1;
+            if state.state == ConnectionState::Connecting {
+                state.failed_connect_attempts += 1;
+                state.connection_setup_timeout_ms =
+                    connection_setup_timeout.backoff(state.failed_connect_attempts);
+                self.connecting_nodes.remove(id);
+            } else {
+                state.failed_connect_attempts = 0;
+                state.connection_setup_timeout_ms = connection_setup_timeout.backoff(0);
+            }
+            state.state = ConnectionState::Disconnected;
+        }
+    }
+
+    /// Indicate that the connection is throttled until the specified deadline.
+    pub fn throttle(&mut self, id: &str, throttle_until_time_ms: u128) {
+        if let Some(state) = self.node_state.get_mut(id) {
+            // The new throttle_until_time_ms takes precedence over the existing one.
+            state.throttle_until_time_ms = state.throttle_until_time_ms.max(throttle_until_time_ms);
+        }
+    }
+
+    /// Return the remaining throttling delay in milliseconds if throttling is in progress. Return 0, otherwise.
+    pub fn throttle_delay_ms(&self, id: &str, now: u128) -> u128 {
+        match self.node_state.get(id) {
+            Some(state) => state.throttle_until_time_ms.saturating_sub(now),
+            None => 0,
+        }
+    }
+
+    /// Return the number of milliseconds to wait, based on the connection state and the throttle time, before
+    /// attempting to send data. If the connection has been established but being throttled, return throttle delay.
+    /// Otherwise, return connection delay.
+    pub fn poll_delay_ms(&self, id: &str, now: u128) -> u128 {
+        let throttle_delay_ms = self.throttle_delay_ms(id, now);
+        if self.is_connected(id) && throttle_delay_ms > 0 {
+            throttle_delay_ms
+        } else {
+            self.connection_delay(id, now)
+        }
+    }
+
+    /// Enter the checking_api_versions state for the given node.
+    pub fn checking_api_versions(&mut self, id: &str) {
+        if let Some(state) = self.node_state.get_mut(id) {
+            state.state = ConnectionState::CheckingApiVersions;
+        }
+        self.connecting_nodes.remove(id);
+    }
+
+    /// Enter the ready state for the given node.
+    pub fn ready(&mut self, id: &str) {
+        let reconnect_backoff = self.reconnect_backoff;
+        let connection_setup_timeout = self.connection_setup_timeout;
+        if let Some(state) = self.node_state.get_mut(id) {
+            state.state = ConnectionState::Ready;
+            state.failed_attempts = 0;
+            state.reconnect_backoff_ms = reconnect_backoff.backoff(0);
+            state.failed_connect_attempts = 0;
+            state.connection_setup_timeout_ms = connection_setup_timeout.backoff(0);
+            // Move to the first address of the host so the next connection attempt starts over.
+            state.addresses.clear();
+        }
+        self.connecting_nodes.remove(id);
+    }
+
+    /// Return true if the connection is ready and not throttled.
+    pub fn is_ready(&self, id: &str, now: u128) -> bool {
+        matches!(
+            self.node_state.get(id),
+            Some(state) if state.state == ConnectionState::Ready && state.throttle_until_time_ms <= now
+        )
+    }
+
+    /// Return true if there is at least one node with connection in the READY state and not throttled. Returns false
+    /// otherwise.
+    pub fn has_ready_nodes(&self, now: u128) -> bool {
+        self.node_state.keys().any(|id| self.is_ready(id, now))
+    }
+
+    /// Return true if the connection has been established
+    pub fn is_connected(&self, id: &str) -> bool {
+        self.state(id).is_some_and(ConnectionState::is_connected)
+    }
+
+    /// Return true if the connection has been disconnected
+    pub fn is_disconnected(&self, id: &str) -> bool {
+        self.state(id).is_some_and(ConnectionState::is_disconnected)
+    }
+
+    /// Remove the given node from the tracked connection states. The main difference between this and `disconnected`
+    /// is the impact on `connection_delay`: it will be 0 after this call whereas `reconnect_backoff_ms` will be taken
+    /// into account after `disconnected` is called.
+    pub fn remove(&mut self, id: &str) {
+        self.node_state.remove(id);
+        self.connecting_nodes.remove(id);
+    }
+
+    /// Get the state of a given connection.
+    pub fn state(&self, id: &str) -> Option<ConnectionState> {
+        self.node_state.get(id).map(|state| state.state)
+    }
+
+    pub fn last_connect_attempt_ms(&self, id: &str) -> u128 {
+        self.node_state
+            .get(id)
+            .map_or(0, |state| state.last_connect_attempt_ms)
+    }
+
+    /// Get the current socket connection setup timeout of the given node.
+    pub fn connection_setup_timeout_ms(&self, id: &str) -> u128 {
+        self.node_state
+            .get(id)
+            .map_or(0, |state| state.connection_setup_timeout_ms)
+    }
+
+    /// Return true if there's at least one connection establishment is currently underway
+    pub fn is_any_node_connecting(&self) -> bool {
+        !self.connecting_nodes.is_empty()
+    }
+
+    /// Test if the connection to the given node has reached its timeout
+    pub fn is_connection_setup_timeout(&self, id: &str, now: u128) -> bool {
+        match self.node_state.get(id) {
+            Some(state) if state.state == ConnectionState::Connecting => {
+                now.saturating_sub(state.last_connect_attempt_ms)
+                    > state.connection_setup_timeout_ms
+            }
+            _ => false,
+        }
+    }
+
+    /// Return the list of nodes whose connection setup has timed out.
+    pub fn nodes_with_connection_setup_timeout(&self, now: u128) -> Vec<String> {
+        self.connecting_nodes
+            .iter()
+            .filter(|id| self.is_connection_setup_timeout(id, now))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClusterConnectionStates, ConnectionState};
+
+    const NODE: &str = "1";
+
+    #[test]
+    fn cluster_connection_state_changes() {
+        let mut states = ClusterConnectionStates::new(100, 100, 10_000, 10_000);
+        assert!(states.can_connect(NODE, 0));
+        assert_eq!(states.connection_delay(NODE, 0), 0);
+
+        states.connecting(NODE, 0, "localhost", 9092);
+        assert_eq!(states.state(NODE), Some(ConnectionState::Connecting));
+        assert!(states.is_connecting(NODE));
+        assert!(states.is_any_node_connecting());
+        assert!(!states.can_connect(NODE, 0));
+        assert_eq!(states.connection_delay(NODE, 0), 10_000);
+
+        states.checking_api_versions(NODE);
+        assert!(states.is_preparing_connection(NODE));
+        assert!(!states.is_any_node_connecting());
+        assert!(states.is_connected(NODE));
+
+        states.ready(NODE);
+        assert!(states.is_ready(NODE, 0));
+        assert!(states.has_ready_nodes(0));
+        assert_eq!(states.connection_delay(NODE, 0), u128::MAX);
+
+        states.disconnected(NODE, 50);
+        assert!(states.is_disconnected(NODE));
+        assert!(states.is_blacked_out(NODE, 100));
+        assert!(!states.can_connect(NODE, 100));
+        assert_eq!(states.connection_delay(NODE, 100), 50);
+        assert!(states.can_connect(NODE, 150));
+
+        states.remove(NODE);
+        assert_eq!(states.state(NODE), None);
+        assert!(states.can_connect(NODE, 150));
+    }
+
+    #[test]
+    fn exponential_reconnect_backoff() {
+        let mut states = ClusterConnectionStates::new(100, 1600, 10_000, 10_000);
+        let mut now = 0;
+        for attempt in 0..6 {
+            states.connecting(NODE, now, "localhost", 9092);
+            states.disconnected(NODE, now);
+            let expected = 100.0 * 2f64.powi(attempt.min(4));
+            let delay = states.connection_delay(NODE, now) as f64;
+            assert!(
+                delay >= expected * 0.8 - 1.0 && delay <= expected * 1.2,
+                "delay {} of attempt {}",
+                delay,
+                attempt
+            );
+            now += delay as u128;
+        }
+
+        states.connecting(NODE, now, "localhost", 9092);
+        states.ready(NODE);
+        states.disconnected(NODE, now);
+        let delay = states.connection_delay(NODE, now);
+        assert!((80..=120).contains(&delay), "delay {}", delay);
+    }
+
+    #[test]
+    fn throttled_connections_are_not_ready() {
+        let mut states = ClusterConnectionStates::new(100, 100, 10_000, 10_000);
+        states.connecting(NODE, 0, "localhost", 9092);
+        states.ready(NODE);
+        states.throttle(NODE, 200);
+        states.throttle(NODE, 100);
+        assert!(!states.is_ready(NODE, 100));
+        assert_eq!(states.poll_delay_ms(NODE, 100), 100);
+        assert!(states.is_ready(NODE, 200));
+        assert_eq!(states.poll_delay_ms(NODE, 200), u128::MAX);
+    }
+
+    #[test]
+    fn connection_setup_timeout() {
+        let mut states = ClusterConnectionStates::new(100, 100, 1000, 4000);
+        states.connecting(NODE, 0, "localhost", 9092);
+        let timeout = states.connection_setup_timeout_ms(NODE);
+        assert!((800..=1200).contains(&timeout), "timeout {}", timeout);
+        assert!(!states.is_connection_setup_timeout(NODE, timeout));
+        assert!(states.is_connection_setup_timeout(NODE, timeout + 1));
+        assert_eq!(
+            states.nodes_with_connection_setup_timeout(timeout + 1),
+            vec![NODE]
+        );
+
+        // the timeout of the next attempt grows
+        states.disconnected(NODE, timeout + 1);
+        let timeout = states.connection_setup_timeout_ms(NODE);
+        assert!((1600..=2400).contains(&timeout), "timeout {}", timeout);
+        assert!(states.nodes_with_connection_setup_timeout(5000).is_empty());
+    }
+
+    #[test]
+    fn addresses_are_tried_in_turn() {
+        let mut states = ClusterConnectionStates::new(100, 100, 1000, 1000);
+        states.connecting(NODE, 0, "127.0.0.1", 9092);
+        let address = states.current_address(NODE).unwrap();
+        assert_eq!(address.to_string(), "127.0.0.1:9092");
+        assert!(states.current_address("2").is_err());
+    }
+}
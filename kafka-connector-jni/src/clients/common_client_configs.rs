@@ -0,0 +1,157 @@
+//! Configurations shared by Kafka client applications: producer, consumer, connect, etc.
+
+use indexmap::IndexMap;
+use log::debug;
+
+use crate::common::{
+    config::{abstract_config::AbstractConfig, config_def::ConfigValue},
+    errors::Result,
+};
+
+// NOTE: DO NOT CHANGE EITHER CONFIG NAMES AS THESE ARE PART OF THE PUBLIC API AND CHANGE WILL BREAK USER CODE.
+
+pub const BOOTSTRAP_SERVERS_CONFIG: &str = "bootstrap.servers";
+pub const BOOTSTRAP_SERVERS_DOC: &str = "A list of host/port pairs to use for establishing the initial connection to the Kafka cluster. The client will make use of all servers irrespective of which servers are specified here for bootstrapping&mdash;this list only impacts the initial hosts used to discover the full set of servers. This list should be in the form <code>host1:port1,host2:port2,...</code>. Since these servers are just used for the initial connection to discover the full cluster membership (which may change dynamically), this list need not contain the full set of servers (you may want more than one, though, in case a server is down).";
+
+pub const CLIENT_DNS_LOOKUP_CONFIG: &str = "client.dns.lookup";
+pub const CLIENT_DNS_LOOKUP_DOC: &str = "Controls how the client uses DNS lookups. If set to <code>use_all_dns_ips</code>, connect to each returned IP address in sequence until a successful connection is established. After a disconnection, the next IP is used. Once all IPs have been used once, the client resolves the IP(s) from the hostname again (both the JVM and the OS cache DNS name lookups, however). If set to <code>resolve_canonical_bootstrap_servers_only</code>, resolve each bootstrap address into a list of canonical names. After the bootstrap phase, this behaves the same as <code>use_all_dns_ips</code>.";
+pub const USE_ALL_DNS_IPS: &str = "use_all_dns_ips";
+pub const RESOLVE_CANONICAL_BOOTSTRAP_SERVERS_ONLY: &str =
+    "resolve_canonical_bootstrap_servers_only";
+
+pub const METADATA_MAX_AGE_CONFIG: &str = "metadata.max.age.ms";
+pub const METADATA_MAX_AGE_DOC: &str = "The period of time in milliseconds after which we force a refresh of metadata even if we haven't seen any partition leadership changes to proactively discover any new brokers or partitions.";
+
+pub const SEND_BUFFER_CONFIG: &str = "send.buffer.bytes";
+pub const SEND_BUFFER_DOC: &str = "The size of the TCP send buffer (SO_SNDBUF) to use when sending data. If the value is -1, the OS default will be used.";
+pub const SEND_BUFFER_LOWER_BOUND: i32 = -1;
+
+pub const RECEIVE_BUFFER_CONFIG: &str = "receive.buffer.bytes";
+pub const RECEIVE_BUFFER_DOC: &str = "The size of the TCP receive buffer (SO_RCVBUF) to use when reading data. If the value is -1, the OS default will be used.";
+pub const RECEIVE_BUFFER_LOWER_BOUND: i32 = -1;
+
+pub const CLIENT_ID_CONFIG: &str = "client.id";
+pub const CLIENT_ID_DOC: &str = "An id string to pass to the server when making requests. The purpose of this is to be able to track the source of requests beyond just ip/port by allowing a logical application name to be included in server-side request logging.";
+
+pub const CLIENT_RACK_CONFIG: &str = "client.rack";
+pub const CLIENT_RACK_DOC: &str = "A rack identifier for this client. This can be any string value which indicates where this client is physically located. It corresponds with the broker config 'broker.rack'";
+
+pub const RECONNECT_BACKOFF_MS_CONFIG: &str = "reconnect.backoff.ms";
+pub const RECONNECT_BACKOFF_MS_DOC: &str = "The base amount of time to wait before attempting to reconnect to a given host. This avoids repeatedly connecting to a host in a tight loop. This backoff applies to all connection attempts by the client to a broker.";
+
+pub const RECONNECT_BACKOFF_MAX_MS_CONFIG: &str = "reconnect.backoff.max.ms";
+pub const RECONNECT_BACKOFF_MAX_MS_DOC: &str = "The maximum amount of time in milliseconds to wait when reconnecting to a broker that has repeatedly failed to connect. If provided, the backoff per host will increase exponentially for each consecutive connection failure, up to this maximum. After calculating the backoff increase, 20% random jitter is added to avoid connection storms.";
+
+pub const RETRIES_CONFIG: &str = "retries";
+pub const RETRIES_DOC: &str = "Setting a value greater than zero will cause the client to resend any request that fails with a potentially transient error. \
+    It is recommended to set the value to either zero or `MAX_VALUE` and use corresponding timeout parameters to control how long a client should retry a request.";
+
+pub const RETRY_BACKOFF_MS_CONFIG: &str = "retry.backoff.ms";
+pub const RETRY_BACKOFF_MS_DOC: &str = "The amount of time to wait before attempting to retry a failed request to a given topic partition. This avoids repeatedly sending requests in a tight loop under some failure scenarios.";
+
+pub const METRICS_SAMPLE_WINDOW_MS_CONFIG: &str = "metrics.sample.window.ms";
+pub const METRICS_SAMPLE_WINDOW_MS_DOC: &str =
+    "The window of time a metrics sample is computed over.";
+
+pub const METRICS_NUM_SAMPLES_CONFIG: &str = "metrics.num.samples";
+pub const METRICS_NUM_SAMPLES_DOC: &str = "The number of samples maintained to compute metrics.";
+
+pub const METRICS_RECORDING_LEVEL_CONFIG: &str = "metrics.recording.level";
+pub const METRICS_RECORDING_LEVEL_DOC: &str = "The highest recording level for metrics.";
+
+pub const METRIC_REPORTER_CLASSES_CONFIG: &str = "metric.reporters";
+pub const METRIC_REPORTER_CLASSES_DOC: &str = "A list of classes to use as metrics reporters. Implementing the <code>org.apache.kafka.common.metrics.MetricsReporter</code> interface allows plugging in classes that will be notified of new metric creation. The JmxReporter is always included to register JMX statistics.";
+
+pub const SECURITY_PROTOCOL_CONFIG: &str = "security.protocol";
+pub const SECURITY_PROTOCOL_DOC: &str = "Protocol used to communicate with brokers. Valid values are: PLAINTEXT, SSL, SASL_PLAINTEXT, SASL_SSL.";
+pub const DEFAULT_SECURITY_PROTOCOL: &str = "PLAINTEXT";
+pub const SECURITY_PROTOCOLS: [&str; 4] = ["PLAINTEXT", "SSL", "SASL_PLAINTEXT", "SASL_SSL"];
+
+pub const SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG: &str = "socket.connection.setup.timeout.ms";
+pub const SOCKET_CONNECTION_SETUP_TIMEOUT_MS_DOC: &str = "The amount of time the client will wait for the socket connection to be established. If the connection is not built before the timeout elapses, clients will close the socket channel.";
+pub const DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MS: i64 = 10 * 1000;
+
+pub const SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG: &str =
+    "socket.connection.setup.timeout.max.ms";
+pub const SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_DOC: &str = "The maximum amount of time the client will wait for the socket connection to be established. The connection setup timeout will increase exponentially for each consecutive connection failure up to this maximum. To avoid connection storms, a randomization factor of 0.2 will be applied to the timeout resulting in a random range between 20% below and 20% above the computed value.";
+pub const DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS: i64 = 30 * 1000;
+
+pub const CONNECTIONS_MAX_IDLE_MS_CONFIG: &str = "connections.max.idle.ms";
+pub const CONNECTIONS_MAX_IDLE_MS_DOC: &str =
+    "Close idle connections after the number of milliseconds specified by this config.";
+
+pub const REQUEST_TIMEOUT_MS_CONFIG: &str = "request.timeout.ms";
+pub const REQUEST_TIMEOUT_MS_DOC: &str =
+    "The configuration controls the maximum amount of time the client will wait \
+    for the response of a request. If the response is not received before the timeout \
+    elapses the client will resend the request if necessary or fail the request if \
+    retries are exhausted.";
+
+pub const GROUP_ID_CONFIG: &str = "group.id";
+pub const GROUP_ID_DOC: &str = "A unique string that identifies the consumer group this consumer belongs to. This property is required if the consumer uses either the group management functionality by using <code>subscribe(topic)</code> or the Kafka-based offset management strategy.";
+
+pub const GROUP_INSTANCE_ID_CONFIG: &str = "group.instance.id";
+pub const GROUP_INSTANCE_ID_DOC: &str = "A unique identifier of the consumer instance provided by the end user. \
+    Only non-empty strings are permitted. If set, the consumer is treated as a static member, \
+    which means that only one instance with this ID is allowed in the consumer group at any time. \
+    This can be used in combination with a larger session timeout to avoid group rebalances caused by transient unavailability \
+    (e.g. process restarts). If not set, the consumer will join the group as a dynamic member, which is the traditional behavior.";
+
+pub const MAX_POLL_INTERVAL_MS_CONFIG: &str = "max.poll.interval.ms";
+pub const MAX_POLL_INTERVAL_MS_DOC: &str = "The maximum delay between invocations of poll() when using \
+    consumer group management. This places an upper bound on the amount of time that the consumer can be idle \
+    before fetching more records. If poll() is not called before expiration of this timeout, then the consumer \
+    is considered failed and the group will rebalance in order to reassign the partitions to another member. \
+    For consumers using a non-null <code>group.instance.id</code> which reach this timeout, partitions will not be immediately reassigned. \
+    Instead, the consumer will stop sending heartbeats and partitions will be reassigned \
+    after expiration of <code>session.timeout.ms</code>. This mirrors the behavior of a static consumer which has shutdown.";
+
+pub const REBALANCE_TIMEOUT_MS_CONFIG: &str = "rebalance.timeout.ms";
+pub const REBALANCE_TIMEOUT_MS_DOC: &str = "The maximum allowed time for each worker to join the group \
+    once a rebalance has begun. This is basically a limit on the amount of time needed for all tasks to \
+    flush any pending data and commit offsets. If the timeout is exceeded, then the worker will be removed \
+    from the group, which will cause offset commit failures.";
+
+pub const SESSION_TIMEOUT_MS_CONFIG: &str = "session.timeout.ms";
+pub const SESSION_TIMEOUT_MS_DOC: &str = "The timeout used to detect client failures when using \
+    Kafka's group management facility. The client sends periodic heartbeats to indicate its liveness \
+    to the broker. If no heartbeats are received by the broker before the expiration of this session timeout, \
+    then the broker will remove this client from the group and initiate a rebalance. Note that the value \
+    must be in the allowable range as configured in the broker configuration by <code>group.min.session.timeout.ms</code> \
+    and <code>group.max.session.timeout.ms</code>.";
+
+pub const HEARTBEAT_INTERVAL_MS_CONFIG: &str = "heartbeat.interval.ms";
+pub const HEARTBEAT_INTERVAL_MS_DOC: &str = "The expected time between heartbeats to the consumer \
+    coordinator when using Kafka's group management facilities. Heartbeats are used to ensure that the \
+    consumer's session stays active and to facilitate rebalancing when new consumers join or leave the group. \
+    The value must be set lower than <code>session.timeout.ms</code>, but typically should be set no higher \
+    than 1/3 of that value. It can be adjusted even lower to control the expected time for normal rebalances.";
+
+pub const DEFAULT_API_TIMEOUT_MS_CONFIG: &str = "default.api.timeout.ms";
+pub const DEFAULT_API_TIMEOUT_MS_DOC: &str = "Specifies the timeout (in milliseconds) for client APIs. \
+    This configuration is used as the default timeout for all client operations that do not specify a <code>timeout</code> parameter.";
+
+/// Postprocess the configuration so that exponential backoff is disabled when reconnect backoff
+/// is explicitly configured but the maximum reconnect backoff is not explicitly configured.
+///
+/// Returns the values to override in the config.
+pub fn post_process_reconnect_backoff_configs(
+    config: &AbstractConfig,
+) -> Result<IndexMap<String, Option<ConfigValue>>> {
+    let mut rval = IndexMap::new();
+    let originals = config.originals();
+    if !originals.contains_key(RECONNECT_BACKOFF_MAX_MS_CONFIG)
+        && originals.contains_key(RECONNECT_BACKOFF_MS_CONFIG)
+    {
+        let reconnect_backoff = config.get_long(RECONNECT_BACKOFF_MS_CONFIG)?;
+        debug!(
+            "Disabling exponential reconnect backoff because {} is set, but {} is not.",
+            RECONNECT_BACKOFF_MS_CONFIG, RECONNECT_BACKOFF_MAX_MS_CONFIG
+        );
+        rval.insert(
+            RECONNECT_BACKOFF_MAX_MS_CONFIG.to_owned(),
+            reconnect_backoff.map(ConfigValue::Long),
+        );
+    }
+    Ok(rval)
+}
@@ -0,0 +1,532 @@
+use std::{
+    collections::HashSet,
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::common_client_configs::{self, *},
+    common::{
+        config::{
+            abstract_config::AbstractConfig,
+            config_def::{
+                CaseInsensitiveValidString, ConfigDef, ConfigValue, DefaultValue, Importance,
+                NonNullValidator, Range, Type, ValidString,
+            },
+        },
+        errors::{KafkaError, Result},
+        requests::join_group_request::validate_group_instance_id,
+    },
+};
+
+pub const GROUP_ID_CONFIG: &str = common_client_configs::GROUP_ID_CONFIG;
+pub const GROUP_INSTANCE_ID_CONFIG: &str = common_client_configs::GROUP_INSTANCE_ID_CONFIG;
+pub const MAX_POLL_INTERVAL_MS_CONFIG: &str = common_client_configs::MAX_POLL_INTERVAL_MS_CONFIG;
+pub const SESSION_TIMEOUT_MS_CONFIG: &str = common_client_configs::SESSION_TIMEOUT_MS_CONFIG;
+pub const HEARTBEAT_INTERVAL_MS_CONFIG: &str = common_client_configs::HEARTBEAT_INTERVAL_MS_CONFIG;
+pub const DEFAULT_API_TIMEOUT_MS_CONFIG: &str =
+    common_client_configs::DEFAULT_API_TIMEOUT_MS_CONFIG;
+
+pub const MAX_POLL_RECORDS_CONFIG: &str = "max.poll.records";
+const MAX_POLL_RECORDS_DOC: &str =
+    "The maximum number of records returned in a single call to poll().";
+
+pub const PARTITION_ASSIGNMENT_STRATEGY_CONFIG: &str = "partition.assignment.strategy";
+const PARTITION_ASSIGNMENT_STRATEGY_DOC: &str = "A list of class names or class types, \
+    ordered by preference, of supported partition assignment strategies that the client will use to distribute \
+    partition ownership amongst consumer instances when group management is used.";
+
+pub const ENABLE_AUTO_COMMIT_CONFIG: &str = "enable.auto.commit";
+const ENABLE_AUTO_COMMIT_DOC: &str =
+    "If true the consumer's offset will be periodically committed in the background.";
+
+pub const AUTO_COMMIT_INTERVAL_MS_CONFIG: &str = "auto.commit.interval.ms";
+const AUTO_COMMIT_INTERVAL_MS_DOC: &str = "The frequency in milliseconds that the consumer offsets are auto-committed to Kafka if <code>enable.auto.commit</code> is set to <code>true</code>.";
+
+pub const AUTO_OFFSET_RESET_CONFIG: &str = "auto.offset.reset";
+pub const AUTO_OFFSET_RESET_DOC: &str = "What to do when there is no initial offset in Kafka or if the current offset does not exist any more on the server (e.g. because that data has been deleted): \
+    <ul><li>earliest: automatically reset the offset to the earliest offset<li>latest: automatically reset the offset to the latest offset</li>\
+    <li>none: throw exception to the consumer if no previous offset is found for the consumer's group</li><li>anything else: throw exception to the consumer.</li></ul>";
+
+pub const FETCH_MIN_BYTES_CONFIG: &str = "fetch.min.bytes";
+const FETCH_MIN_BYTES_DOC: &str = "The minimum amount of data the server should return for a fetch request. If insufficient data is available the request will wait for that much data to accumulate before answering the request. \
+    The default setting of 1 byte means that fetch requests are answered as soon as a single byte of data is available or the fetch request times out waiting for data to arrive. \
+    Setting this to something greater than 1 will cause the server to wait for larger amounts of data to accumulate which can improve server throughput a bit at the cost of some additional latency.";
+
+pub const FETCH_MAX_BYTES_CONFIG: &str = "fetch.max.bytes";
+const FETCH_MAX_BYTES_DOC: &str = "The maximum amount of data the server should return for a fetch request. \
+    Records are fetched in batches by the consumer, and if the first record batch in the first non-empty partition of the fetch is larger than \
+    this value, the record batch will still be returned to ensure that the consumer can make progress. As such, this is not a absolute maximum. \
+    The maximum record batch size accepted by the broker is defined via <code>message.max.bytes</code> (broker config) or \
+    <code>max.message.bytes</code> (topic config). Note that the consumer performs multiple fetches in parallel.";
+pub const DEFAULT_FETCH_MAX_BYTES: i32 = 50 * 1024 * 1024;
+
+pub const FETCH_MAX_WAIT_MS_CONFIG: &str = "fetch.max.wait.ms";
+const FETCH_MAX_WAIT_MS_DOC: &str = "The maximum amount of time the server will block before answering the fetch request if there isn't sufficient data to immediately satisfy the requirement given by fetch.min.bytes.";
+
+pub const MAX_PARTITION_FETCH_BYTES_CONFIG: &str = "max.partition.fetch.bytes";
+const MAX_PARTITION_FETCH_BYTES_DOC: &str = "The maximum amount of data per-partition the server \
+    will return. Records are fetched in batches by the consumer. If the first record batch in the first non-empty \
+    partition of the fetch is larger than this limit, the \
+    batch will still be returned to ensure that the consumer can make progress. The maximum record batch size \
+    accepted by the broker is defined via <code>message.max.bytes</code> (broker config) or \
+    <code>max.message.bytes</code> (topic config). See fetch.max.bytes for limiting the consumer request size.";
+pub const DEFAULT_MAX_PARTITION_FETCH_BYTES: i32 = 1024 * 1024;
+
+pub const CHECK_CRCS_CONFIG: &str = "check.crcs";
+const CHECK_CRCS_DOC: &str = "Automatically check the CRC32 of the records consumed. This ensures no on-the-wire or on-disk corruption to the messages occurred. This check adds some overhead, so it may be disabled in cases seeking extreme performance.";
+
+pub const KEY_DESERIALIZER_CLASS_CONFIG: &str = "key.deserializer";
+pub const KEY_DESERIALIZER_CLASS_DOC: &str = "Deserializer class for key that implements the <code>org.apache.kafka.common.serialization.Deserializer</code> interface.";
+
+pub const VALUE_DESERIALIZER_CLASS_CONFIG: &str = "value.deserializer";
+pub const VALUE_DESERIALIZER_CLASS_DOC: &str = "Deserializer class for value that implements the <code>org.apache.kafka.common.serialization.Deserializer</code> interface.";
+
+pub const INTERCEPTOR_CLASSES_CONFIG: &str = "interceptor.classes";
+pub const INTERCEPTOR_CLASSES_DOC: &str = "A list of classes to use as interceptors. \
+    Implementing the <code>org.apache.kafka.clients.consumer.ConsumerInterceptor</code> interface allows you to intercept (and possibly mutate) records \
+    received by the consumer. By default, there are no interceptors.";
+
+pub const EXCLUDE_INTERNAL_TOPICS_CONFIG: &str = "exclude.internal.topics";
+const EXCLUDE_INTERNAL_TOPICS_DOC: &str = "Whether internal topics matching a subscribed pattern should \
+    be excluded from the subscription. It is always possible to explicitly subscribe to an internal topic.";
+pub const DEFAULT_EXCLUDE_INTERNAL_TOPICS: bool = true;
+
+/// Internal config to control whether the consumer sends a leave group request on close.
+pub const LEAVE_GROUP_ON_CLOSE_CONFIG: &str = "internal.leave.group.on.close";
+
+pub const ISOLATION_LEVEL_CONFIG: &str = "isolation.level";
+pub const ISOLATION_LEVEL_DOC: &str = "Controls how to read messages written transactionally. If set to <code>read_committed</code>, consumer.poll() will only return \
+    transactional messages which have been committed. If set to <code>read_uncommitted</code> (the default), consumer.poll() will return all messages, even transactional messages \
+    which have been aborted. Non-transactional messages will be returned unconditionally in either mode.";
+pub const DEFAULT_ISOLATION_LEVEL: &str = "read_uncommitted";
+
+pub const ALLOW_AUTO_CREATE_TOPICS_CONFIG: &str = "allow.auto.create.topics";
+const ALLOW_AUTO_CREATE_TOPICS_DOC: &str = "Allow automatic topic creation on the broker when \
+    subscribing to or assigning a topic. A topic being subscribed to will be automatically created only if the \
+    broker allows for it using `auto.create.topics.enable` broker configuration. This configuration must \
+    be set to `false` when using brokers older than 0.11.0";
+pub const DEFAULT_ALLOW_AUTO_CREATE_TOPICS: bool = true;
+
+const DEFAULT_PARTITION_ASSIGNMENT_STRATEGY: &str =
+    "org.apache.kafka.clients.consumer.RangeAssignor";
+
+static CONSUMER_CLIENT_ID_SEQUENCE: AtomicUsize = AtomicUsize::new(1);
+
+/// The consumer configuration keys
+pub struct ConsumerConfig {
+    config: AbstractConfig,
+}
+
+impl ConsumerConfig {
+    /// Parses and validates `props`, filling in defaults and the generated `client.id`.
+    pub fn new(props: IndexMap<String, String>) -> Result<ConsumerConfig> {
+        let config = AbstractConfig::new(
+            &ConsumerConfig::config_def()?,
+            props,
+            "ConsumerConfig",
+            true,
+            post_process_parsed_config,
+        )?;
+        Ok(ConsumerConfig { config })
+    }
+
+    pub fn config_def() -> Result<ConfigDef> {
+        ConfigDef::new()
+            .define_with_validator(
+                BOOTSTRAP_SERVERS_CONFIG,
+                Type::List,
+                Vec::<String>::new(),
+                NonNullValidator,
+                Importance::High,
+                BOOTSTRAP_SERVERS_DOC,
+            )?
+            .define_with_validator(
+                CLIENT_DNS_LOOKUP_CONFIG,
+                Type::String,
+                USE_ALL_DNS_IPS,
+                ValidString::one_of(&[USE_ALL_DNS_IPS, RESOLVE_CANONICAL_BOOTSTRAP_SERVERS_ONLY]),
+                Importance::Medium,
+                CLIENT_DNS_LOOKUP_DOC,
+            )?
+            .define(
+                GROUP_ID_CONFIG,
+                Type::String,
+                DefaultValue::Null,
+                Importance::High,
+                GROUP_ID_DOC,
+            )?
+            .define(
+                GROUP_INSTANCE_ID_CONFIG,
+                Type::String,
+                DefaultValue::Null,
+                Importance::Medium,
+                GROUP_INSTANCE_ID_DOC,
+            )?
+            .define(
+                SESSION_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                10000,
+                Importance::High,
+                SESSION_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                HEARTBEAT_INTERVAL_MS_CONFIG,
+                Type::Int,
+                3000,
+                Importance::High,
+                HEARTBEAT_INTERVAL_MS_DOC,
+            )?
+            .define_with_validator(
+                PARTITION_ASSIGNMENT_STRATEGY_CONFIG,
+                Type::List,
+                DEFAULT_PARTITION_ASSIGNMENT_STRATEGY,
+                NonNullValidator,
+                Importance::Medium,
+                PARTITION_ASSIGNMENT_STRATEGY_DOC,
+            )?
+            .define_with_validator(
+                METADATA_MAX_AGE_CONFIG,
+                Type::Long,
+                5 * 60 * 1000i64,
+                Range::at_least(0),
+                Importance::Low,
+                METADATA_MAX_AGE_DOC,
+            )?
+            .define(
+                ENABLE_AUTO_COMMIT_CONFIG,
+                Type::Boolean,
+                true,
+                Importance::Medium,
+                ENABLE_AUTO_COMMIT_DOC,
+            )?
+            .define_with_validator(
+                AUTO_COMMIT_INTERVAL_MS_CONFIG,
+                Type::Int,
+                5000,
+                Range::at_least(0),
+                Importance::Low,
+                AUTO_COMMIT_INTERVAL_MS_DOC,
+            )?
+            .define(
+                CLIENT_ID_CONFIG,
+                Type::String,
+                "",
+                Importance::Low,
+                CLIENT_ID_DOC,
+            )?
+            .define(
+                CLIENT_RACK_CONFIG,
+                Type::String,
+                "",
+                Importance::Low,
+                CLIENT_RACK_DOC,
+            )?
+            .define_with_validator(
+                MAX_PARTITION_FETCH_BYTES_CONFIG,
+                Type::Int,
+                DEFAULT_MAX_PARTITION_FETCH_BYTES,
+                Range::at_least(0),
+                Importance::High,
+                MAX_PARTITION_FETCH_BYTES_DOC,
+            )?
+            .define_with_validator(
+                SEND_BUFFER_CONFIG,
+                Type::Int,
+                128 * 1024,
+                Range::at_least(SEND_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                SEND_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                RECEIVE_BUFFER_CONFIG,
+                Type::Int,
+                64 * 1024,
+                Range::at_least(RECEIVE_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                RECEIVE_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                FETCH_MIN_BYTES_CONFIG,
+                Type::Int,
+                1,
+                Range::at_least(0),
+                Importance::High,
+                FETCH_MIN_BYTES_DOC,
+            )?
+            .define_with_validator(
+                FETCH_MAX_BYTES_CONFIG,
+                Type::Int,
+                DEFAULT_FETCH_MAX_BYTES,
+                Range::at_least(0),
+                Importance::Medium,
+                FETCH_MAX_BYTES_DOC,
+            )?
+            .define_with_validator(
+                FETCH_MAX_WAIT_MS_CONFIG,
+                Type::Int,
+                500,
+                Range::at_least(0),
+                Importance::Low,
+                FETCH_MAX_WAIT_MS_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MS_CONFIG,
+                Type::Long,
+                50i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MAX_MS_CONFIG,
+                Type::Long,
+                1000i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MAX_MS_DOC,
+            )?
+            .define_with_validator(
+                RETRY_BACKOFF_MS_CONFIG,
+                Type::Long,
+                100i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RETRY_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                AUTO_OFFSET_RESET_CONFIG,
+                Type::String,
+                "latest",
+                ValidString::one_of(&["latest", "earliest", "none"]),
+                Importance::Medium,
+                AUTO_OFFSET_RESET_DOC,
+            )?
+            .define(
+                CHECK_CRCS_CONFIG,
+                Type::Boolean,
+                true,
+                Importance::Low,
+                CHECK_CRCS_DOC,
+            )?
+            .define_with_validator(
+                METRICS_SAMPLE_WINDOW_MS_CONFIG,
+                Type::Long,
+                30000i64,
+                Range::at_least(0),
+                Importance::Low,
+                METRICS_SAMPLE_WINDOW_MS_DOC,
+            )?
+            .define_with_validator(
+                METRICS_NUM_SAMPLES_CONFIG,
+                Type::Int,
+                2,
+                Range::at_least(1),
+                Importance::Low,
+                METRICS_NUM_SAMPLES_DOC,
+            )?
+            .define_with_validator(
+                METRICS_RECORDING_LEVEL_CONFIG,
+                Type::String,
+                "INFO",
+                ValidString::one_of(&["INFO", "DEBUG", "TRACE"]),
+                Importance::Low,
+                METRICS_RECORDING_LEVEL_DOC,
+            )?
+            .define(
+                METRIC_REPORTER_CLASSES_CONFIG,
+                Type::List,
+                "",
+                Importance::Low,
+                METRIC_REPORTER_CLASSES_DOC,
+            )?
+            .define(
+                KEY_DESERIALIZER_CLASS_CONFIG,
+                Type::Class,
+                DefaultValue::NoDefault,
+                Importance::High,
+                KEY_DESERIALIZER_CLASS_DOC,
+            )?
+            .define(
+                VALUE_DESERIALIZER_CLASS_CONFIG,
+                Type::Class,
+                DefaultValue::NoDefault,
+                Importance::High,
+                VALUE_DESERIALIZER_CLASS_DOC,
+            )?
+            .define_with_validator(
+                REQUEST_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                30000,
+                Range::at_least(0),
+                Importance::Medium,
+                REQUEST_TIMEOUT_MS_DOC,
+            )?
+            .define_with_validator(
+                DEFAULT_API_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                60 * 1000,
+                Range::at_least(0),
+                Importance::Medium,
+                DEFAULT_API_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_DOC,
+            )?
+            // default is set to be a bit lower than the server default (10 min), to avoid both client and server closing connection at same time
+            .define(
+                CONNECTIONS_MAX_IDLE_MS_CONFIG,
+                Type::Long,
+                9 * 60 * 1000i64,
+                Importance::Medium,
+                CONNECTIONS_MAX_IDLE_MS_DOC,
+            )?
+            .define(
+                INTERCEPTOR_CLASSES_CONFIG,
+                Type::List,
+                "",
+                Importance::Low,
+                INTERCEPTOR_CLASSES_DOC,
+            )?
+            .define_with_validator(
+                MAX_POLL_RECORDS_CONFIG,
+                Type::Int,
+                500,
+                Range::at_least(1),
+                Importance::Medium,
+                MAX_POLL_RECORDS_DOC,
+            )?
+            .define_with_validator(
+                MAX_POLL_INTERVAL_MS_CONFIG,
+                Type::Int,
+                300000,
+                Range::at_least(1),
+                Importance::Medium,
+                MAX_POLL_INTERVAL_MS_DOC,
+            )?
+            .define(
+                EXCLUDE_INTERNAL_TOPICS_CONFIG,
+                Type::Boolean,
+                DEFAULT_EXCLUDE_INTERNAL_TOPICS,
+                Importance::Medium,
+                EXCLUDE_INTERNAL_TOPICS_DOC,
+            )?
+            .define_internal(
+                LEAVE_GROUP_ON_CLOSE_CONFIG,
+                Type::Boolean,
+                true,
+                Importance::Low,
+            )?
+            .define_with_validator(
+                ISOLATION_LEVEL_CONFIG,
+                Type::String,
+                DEFAULT_ISOLATION_LEVEL,
+                ValidString::one_of(&["read_committed", "read_uncommitted"]),
+                Importance::Medium,
+                ISOLATION_LEVEL_DOC,
+            )?
+            .define(
+                ALLOW_AUTO_CREATE_TOPICS_CONFIG,
+                Type::Boolean,
+                DEFAULT_ALLOW_AUTO_CREATE_TOPICS,
+                Importance::Medium,
+                ALLOW_AUTO_CREATE_TOPICS_DOC,
+            )?
+            .define_with_validator(
+                SECURITY_PROTOCOL_CONFIG,
+                Type::String,
+                DEFAULT_SECURITY_PROTOCOL,
+                CaseInsensitiveValidString::one_of(&SECURITY_PROTOCOLS),
+                Importance::Medium,
+                SECURITY_PROTOCOL_DOC,
+            )?
+            .with_client_ssl_support()?
+            .with_client_sasl_support()
+    }
+
+    pub fn config_names() -> Result<HashSet<String>> {
+        Ok(ConsumerConfig::config_def()?.names())
+    }
+
+    /// Returns whether offsets should be committed automatically, which is disabled by default
+    /// when no `group.id` is configured.
+    ///
+    /// Fails with `InvalidConfiguration` if auto commit is explicitly enabled without a group.
+    pub fn maybe_override_enable_auto_commit(&self) -> Result<bool> {
+        let group_id = self.get_string(GROUP_ID_CONFIG)?;
+        let mut enable_auto_commit = self.get_boolean(ENABLE_AUTO_COMMIT_CONFIG)? == Some(true);
+        if group_id.is_none() {
+            // overwrite in case of default group id where the config is not explicitly provided
+            if !self.originals().contains_key(ENABLE_AUTO_COMMIT_CONFIG) {
+                enable_auto_commit = false;
+            } else if enable_auto_commit {
+                return Err(KafkaError::InvalidConfiguration(format!(
+                    "{} cannot be set to true when default group id (null) is used.",
+                    ENABLE_AUTO_COMMIT_CONFIG
+                )));
+            }
+        }
+        Ok(enable_auto_commit)
+    }
+}
+
+impl Deref for ConsumerConfig {
+    type Target = AbstractConfig;
+
+    fn deref(&self) -> &Self::Target {
+        &self.config
+    }
+}
+
+fn post_process_parsed_config(
+    config: &AbstractConfig,
+) -> Result<IndexMap<String, Option<ConfigValue>>> {
+    let mut refined_configs =
+        common_client_configs::post_process_reconnect_backoff_configs(config)?;
+    maybe_override_client_id(config, &mut refined_configs)?;
+    Ok(refined_configs)
+}
+
+fn maybe_override_client_id(
+    config: &AbstractConfig,
+    configs: &mut IndexMap<String, Option<ConfigValue>>,
+) -> Result<()> {
+    let client_id = config.get_string(CLIENT_ID_CONFIG)?;
+    if matches!(&client_id, Some(client_id) if !client_id.is_empty()) {
+        return Ok(());
+    }
+    let group_id = config.get_string(GROUP_ID_CONFIG)?;
+    let group_instance_id = config.originals().get(GROUP_INSTANCE_ID_CONFIG);
+    if let Some(group_instance_id) = group_instance_id {
+        validate_group_instance_id(group_instance_id)?;
+    }
+    let group_instance_id_part = match group_instance_id {
+        Some(group_instance_id) => group_instance_id.clone(),
+        None => CONSUMER_CLIENT_ID_SEQUENCE
+            .fetch_add(1, Ordering::SeqCst)
+            .to_string(),
+    };
+    let generated_client_id = format!(
+        "consumer-{}-{}",
+        group_id.as_deref().unwrap_or("null"),
+        group_instance_id_part
+    );
+    configs.insert(
+        CLIENT_ID_CONFIG.to_owned(),
+        Some(ConfigValue::String(generated_client_id)),
+    );
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use std::fmt::{self, Display};
+
+pub const UNKNOWN_MEMBER_ID: &str = "";
+pub const UNKNOWN_GENERATION_ID: i32 = -1;
+
+/// A metadata struct containing the consumer group information.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsumerGroupMetadata {
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+impl ConsumerGroupMetadata {
+    pub fn new(
+        group_id: impl Into<String>,
+        generation_id: i32,
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+    ) -> ConsumerGroupMetadata {
+        ConsumerGroupMetadata {
+            group_id: group_id.into(),
+            generation_id,
+            member_id: member_id.into(),
+            group_instance_id,
+        }
+    }
+
+    pub fn from_group_id(group_id: impl Into<String>) -> ConsumerGroupMetadata {
+        ConsumerGroupMetadata::new(group_id, UNKNOWN_GENERATION_ID, UNKNOWN_MEMBER_ID, None)
+    }
+}
+
+impl Display for ConsumerGroupMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GroupMetadata(groupId = {}, generationId = {}, memberId = {}, groupInstanceId = {})",
+            self.group_id,
+            self.generation_id,
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("")
+        )
+    }
+}
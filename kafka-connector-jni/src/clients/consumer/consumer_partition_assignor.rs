@@ -0,0 +1,222 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    cluster::Cluster,
+    errors::{KafkaError, Result},
+    topic_partition::TopicPartition,
+};
+
+use super::consumer_group_metadata::ConsumerGroupMetadata;
+
+/// This interface is used to define custom partition assignment for use in
+/// `KafkaConsumer`. Members of the consumer group subscribe
+/// to the topics they are interested in and forward their subscriptions to a Kafka broker
+/// serving as the group coordinator. The coordinator selects one member to perform the group
+/// assignment and propagates the subscriptions of all members to it. Then `assign` is called
+/// to perform the assignment and the results are forwarded back to each respective members
+///
+/// In some cases, it is useful to forward additional metadata to the assignor in order to make
+/// assignment decisions. For this, you can override `subscription_user_data` and provide custom
+/// userData in the returned Subscription. For example, to have a rack-aware assignor, an implementation
+/// can use this user data to forward the rackId belonging to each member.
+pub trait ConsumerPartitionAssignor: Send + Sync {
+    /// Return serialized data that will be included in the `Subscription` sent to the leader
+    /// and can be leveraged in `assign` (e.g. local host/rack information)
+    fn subscription_user_data(&self, _topics: &HashSet<String>) -> Option<Bytes> {
+        None
+    }
+
+    /// Perform the group assignment given the member subscriptions and current cluster metadata.
+    ///
+    /// * `metadata` - Current topic/broker metadata known by consumer
+    /// * `group_subscription` - Subscriptions from all members including metadata provided through
+    ///   `subscription_user_data`
+    fn assign(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment>;
+
+    /// Callback which is invoked when a group member receives its assignment from the leader.
+    ///
+    /// * `assignment` - The local member's assignment as provided by the leader in `assign`
+    /// * `metadata` - Additional metadata on the consumer (optional)
+    fn on_assignment(&self, _assignment: &Assignment, _metadata: Option<&ConsumerGroupMetadata>) {}
+
+    /// Indicate which rebalance protocol this assignor works with;
+    /// By default it should always work with `RebalanceProtocol::Eager`.
+    fn supported_protocols(&self) -> Vec<RebalanceProtocol> {
+        vec![RebalanceProtocol::Eager]
+    }
+
+    /// Return the version of the assignor which indicates how the user metadata encodings
+    /// and the assignment algorithm gets evolved.
+    fn version(&self) -> i16 {
+        0
+    }
+
+    /// Unique name for this assignor (e.g. "range" or "roundrobin" or "sticky"). Note, this is not required
+    /// to be the same as the class name specified in `partition.assignment.strategy`
+    fn name(&self) -> &str;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub topics: Vec<String>,
+    pub user_data: Option<Bytes>,
+    pub owned_partitions: Vec<TopicPartition>,
+    pub group_instance_id: Option<String>,
+}
+
+impl Subscription {
+    pub fn new(
+        topics: Vec<String>,
+        user_data: Option<Bytes>,
+        owned_partitions: Vec<TopicPartition>,
+    ) -> Subscription {
+        Subscription {
+            topics,
+            user_data,
+            owned_partitions,
+            group_instance_id: None,
+        }
+    }
+
+    pub fn from_topics(topics: Vec<String>) -> Subscription {
+        Subscription::new(topics, None, vec![])
+    }
+}
+
+impl Display for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Subscription(topics={:?}", self.topics)?;
+        if let Some(user_data) = &self.user_data {
+            write!(f, ", userDataSize={}", user_data.len())?;
+        }
+        write!(
+            f,
+            ", ownedPartitions={:?}, groupInstanceId={})",
+            self.owned_partitions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            self.group_instance_id.as_deref().unwrap_or("null")
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assignment {
+    pub partitions: Vec<TopicPartition>,
+    pub user_data: Option<Bytes>,
+}
+
+impl Assignment {
+    pub fn new(partitions: Vec<TopicPartition>, user_data: Option<Bytes>) -> Assignment {
+        Assignment {
+            partitions,
+            user_data,
+        }
+    }
+
+    pub fn from_partitions(partitions: Vec<TopicPartition>) -> Assignment {
+        Assignment::new(partitions, None)
+    }
+}
+
+impl Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Assignment(partitions={:?}",
+            self.partitions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        )?;
+        if let Some(user_data) = &self.user_data {
+            write!(f, ", userDataSize={}", user_data.len())?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Subscriptions of all members of the group, keyed by member id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupSubscription {
+    pub subscriptions: IndexMap<String, Subscription>,
+}
+
+impl GroupSubscription {
+    pub fn new(subscriptions: IndexMap<String, Subscription>) -> GroupSubscription {
+        GroupSubscription { subscriptions }
+    }
+}
+
+/// Assignments of all members of the group, keyed by member id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupAssignment {
+    pub assignments: IndexMap<String, Assignment>,
+}
+
+impl GroupAssignment {
+    pub fn new(assignments: IndexMap<String, Assignment>) -> GroupAssignment {
+        GroupAssignment { assignments }
+    }
+}
+
+/// The rebalance protocol defines partition assignment and revocation semantics. The purpose is to establish a
+/// consistent set of rules that all consumers in a group follow in order to transfer ownership of a partition.
+/// `ConsumerPartitionAssignor` implementors can claim supporting one or more rebalance protocols via the
+/// `supported_protocols`, and it is their responsibility to respect the rules of those protocols in their
+/// `assign` implementations. Failures to follow the rules of the supported protocols would lead to runtime
+/// error or undefined behavior.
+///
+/// The `Eager` rebalance protocol requires a consumer to always revoke all its owned
+/// partitions before participating in a rebalance event. It therefore allows a complete reshuffling of the assignment.
+///
+/// `Cooperative` rebalance protocol allows a consumer to retain its currently owned
+/// partitions before participating in a rebalance event. The assignor should not reassign any owned partitions
+/// immediately, but instead may indicate consumers the need for partition revocation so that the revoked
+/// partitions can be reassigned to other consumers in the next rebalance event. This is designed for sticky assignment
+/// logic which attempts to minimize partition reassignment with cooperative adjustments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RebalanceProtocol {
+    Cooperative,
+    Eager,
+}
+
+impl RebalanceProtocol {
+    pub fn id(&self) -> i8 {
+        match self {
+            RebalanceProtocol::Cooperative => 1,
+            RebalanceProtocol::Eager => 0,
+        }
+    }
+
+    pub fn for_id(id: i8) -> Result<RebalanceProtocol> {
+        match id {
+            0 => Ok(RebalanceProtocol::Eager),
+            1 => Ok(RebalanceProtocol::Cooperative),
+            _ => Err(KafkaError::IllegalArgument(format!(
+                "Unknown rebalance protocol id: {}",
+                id
+            ))),
+        }
+    }
+}
+
+impl Display for RebalanceProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebalanceProtocol::Cooperative => write!(f, "COOPERATIVE"),
+            RebalanceProtocol::Eager => write!(f, "EAGER"),
+        }
+    }
+}
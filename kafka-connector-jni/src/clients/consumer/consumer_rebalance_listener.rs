@@ -0,0 +1,122 @@
+use jni::objects::GlobalRef;
+
+use crate::{
+    clone_to_java::CloneToJava,
+    common::{errors::Result, topic_partition::TopicPartition},
+    java_vm::call_java,
+    jni_cache,
+};
+
+/// A callback interface that the user can implement to trigger custom actions when the set of partitions assigned to the
+/// consumer changes.
+///
+/// This is applicable when the consumer is having Kafka auto-manage group membership. If the consumer directly assigns partitions,
+/// those partitions will never be reassigned and this callback is not applicable.
+///
+/// When Kafka is managing the group membership, a partition re-assignment will be triggered any time the members of the group change or the subscription
+/// of the members changes. This can occur when processes die, new process instances are added or old instances come back to life after failure.
+/// Partition re-assignments can also be triggered by changes affecting the subscribed topics (e.g. when the number of partitions is
+/// administratively adjusted).
+///
+/// There are many uses for this functionality. One common use is saving offsets in a custom store. By saving offsets in
+/// the `on_partitions_revoked` call we can ensure that any time partition assignment changes
+/// the offset gets saved.
+///
+/// All callbacks are invoked on the thread calling `KafkaConsumer::poll`, errors returned from them are
+/// propagated to that call after the rebalance completed.
+pub trait ConsumerRebalanceListener: Send + Sync {
+    /// A callback method the user can implement to provide handling of offset commits to a customized store.
+    /// This method will be called during a rebalance operation when the consumer has to give up some partitions.
+    /// It can also be called when consumer is being closed or is unsubscribing.
+    /// It is recommended that offsets should be committed in this callback to either Kafka or a
+    /// custom offset store to prevent duplicate data.
+    ///
+    /// In eager rebalancing, it will always be called at the start of a rebalance and after the consumer stops fetching data.
+    /// In cooperative rebalancing, it will be called at the end of a rebalance on the set of partitions being revoked iff the set is non-empty.
+    fn on_partitions_revoked(&self, partitions: &[TopicPartition]) -> Result<()>;
+
+    /// A callback method the user can implement to provide handling of customized offsets on completion of a successful
+    /// partition re-assignment. This method will be called after the partition re-assignment completes and before the
+    /// consumer starts fetching data, and only as the result of a `KafkaConsumer::poll` call.
+    ///
+    /// It is guaranteed that under normal conditions all the processes in a consumer group will execute their
+    /// `on_partitions_revoked` callback before any instance executes its
+    /// `on_partitions_assigned` callback. During exceptional scenarios, partitions may be migrated
+    /// without the old owner being notified (i.e. their `on_partitions_revoked` callback not triggered),
+    /// and later when the old owner consumer realized this event, the `on_partitions_lost` callback
+    /// will be triggered by the consumer then.
+    fn on_partitions_assigned(&self, partitions: &[TopicPartition]) -> Result<()>;
+
+    /// A callback method you can implement to provide handling of cleaning up resources for partitions that have already
+    /// been reassigned to other consumers. This method will not be called during normal execution as the owned partitions would
+    /// first be revoked by calling the `on_partitions_revoked`, before being reassigned
+    /// to other consumers during a rebalance event. However, during exceptional scenarios when the consumer realized that it
+    /// does not own this partition any longer, i.e. not revoked via a normal rebalance event, then this method would be invoked.
+    ///
+    /// For example, this function is called if a consumer's session timeout has expired, or if a fatal error has been
+    /// received indicating the consumer is no longer part of the group.
+    ///
+    /// By default it will just trigger `on_partitions_revoked`; for users who want to distinguish
+    /// the handling logic of revoked partitions v.s. lost partitions, they can override the default implementation.
+    fn on_partitions_lost(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.on_partitions_revoked(partitions)
+    }
+}
+
+/// Listener used when none was provided with the subscription.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoOpConsumerRebalanceListener;
+
+impl ConsumerRebalanceListener for NoOpConsumerRebalanceListener {
+    fn on_partitions_revoked(&self, _partitions: &[TopicPartition]) -> Result<()> {
+        Ok(())
+    }
+
+    fn on_partitions_assigned(&self, _partitions: &[TopicPartition]) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Java `org.apache.kafka.clients.consumer.ConsumerRebalanceListener`, called on the polling
+/// thread which is attached to the JVM if needed. Exceptions thrown by the listener are
+/// returned as `KafkaError::Kafka`.
+#[derive(Clone)]
+pub struct JavaConsumerRebalanceListener {
+    listener: GlobalRef,
+}
+
+impl JavaConsumerRebalanceListener {
+    pub fn new(listener: GlobalRef) -> JavaConsumerRebalanceListener {
+        JavaConsumerRebalanceListener { listener }
+    }
+
+    fn call(&self, method: &'static str, partitions: &[TopicPartition]) -> Result<()> {
+        call_java(|env| {
+            let partitions = partitions.to_vec().clone_to_java(env)?;
+            jni_cache::call_method(
+                env,
+                self.listener.as_obj(),
+                "org/apache/kafka/clients/consumer/ConsumerRebalanceListener",
+                method,
+                "(Ljava/util/Collection;)V",
+                &[partitions],
+            )?;
+            Ok(())
+        })
+        .map_err(Into::into)
+    }
+}
+
+impl ConsumerRebalanceListener for JavaConsumerRebalanceListener {
+    fn on_partitions_revoked(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.call("onPartitionsRevoked", partitions)
+    }
+
+    fn on_partitions_assigned(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.call("onPartitionsAssigned", partitions)
+    }
+
+    fn on_partitions_lost(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.call("onPartitionsLost", partitions)
+    }
+}
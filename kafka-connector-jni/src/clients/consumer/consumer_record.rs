@@ -1,5 +1,5 @@
 use jni::{
-    objects::{GlobalRef, JObject, JValue},
+    objects::{GlobalRef, JObject},
     sys::{jint, jlong, jobject, jstring},
     JNIEnv,
 };
@@ -7,11 +7,18 @@ use kafka_connector_macros::rust_property_getter;
 
 use crate::{
     clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
     common::{
-        header::internals::record_headers::RecordHeaders, record::timestamp_type::TimestampType,
+        errors::JniResult, header::internals::record_headers::RecordHeaders,
+        record::timestamp_type::TimestampType,
     },
+    java_optional::JavaOptional,
+    java_stored_object::JavaStoredObject,
+    jni_guard::jni_guard,
 };
 
+const CONSUMER_RECORD: &str = "org/apache/kafka/clients/consumer/ConsumerRecord";
+
 pub struct ConsumerRecord<K, V> {
     pub topic: String,
     pub partition: i32,
@@ -48,37 +55,36 @@ pub extern "system" fn Java_org_apache_kafka_clients_consumer_ConsumerRecord_rus
     headers: jobject,
     leader_epoch: jobject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let topic = CloneFromJava::clone_from_java(env, topic.into())?;
         let timestamp_type = CloneFromJava::clone_from_java(env, timestamp_type.into())?;
         let headers = CloneFromJava::clone_from_java(env, headers.into())?;
+        // Deserialized by java, kept as the java objects instead of bytes.
         let key: GlobalRef = CloneFromJava::clone_from_java(env, key.into())?;
         let value: GlobalRef = CloneFromJava::clone_from_java(env, value.into())?;
-        let leader_epoch = CloneFromJava::clone_from_java(env, leader_epoch.into())?;
-
-        let record_headers = Box::new(ConsumerRecord {
-            topic,
-            partition,
-            offset,
-            timestamp,
-            timestamp_type,
-            serialized_key_size,
-            serialized_value_size,
-            headers,
-            key,
-            value,
-            leader_epoch,
-        });
-        let ptr = Box::into_raw(record_headers);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        let JavaOptional(leader_epoch) =
+            JavaOptional::<i32>::clone_from_java(env, leader_epoch.into())?;
+
+        JavaStoredObject::store(
+            env,
+            obj,
+            ConsumerRecord {
+                topic,
+                partition,
+                offset,
+                timestamp,
+                timestamp_type,
+                serialized_key_size,
+                serialized_value_size,
+                headers,
+                key,
+                value,
+                leader_epoch,
+            },
+        )?;
 
         Ok(())
-    }();
-
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -92,16 +98,11 @@ pub extern "system" fn Java_org_apache_kafka_clients_consumer_ConsumerRecord_rus
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut ConsumerRecord<GlobalRef, GlobalRef>) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<ConsumerRecord<GlobalRef, GlobalRef>>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    };
+    })
 }
 
 rust_property_getter!(
@@ -174,9 +175,24 @@ rust_property_getter!(
  * Signature: ()I
 );
 
-rust_property_getter!(
- * Struct:    ConsumerRecord<GlobalRef,GlobalRef>
+/*
  * Class:     org_apache_kafka_clients_consumer_ConsumerRecord
  * Method:    leaderEpoch
  * Signature: ()Ljava/util/Optional;
-);
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_consumer_ConsumerRecord_leaderEpoch(
+    env: JNIEnv,
+    obj: JObject,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let record = JavaStoredObject::<ConsumerRecord<GlobalRef, GlobalRef>>::new(
+            env,
+            obj,
+            CONSUMER_RECORD,
+        )?;
+        let leader_epoch = JavaOptional(record.lock().leader_epoch);
+        Ok(leader_epoch.clone_to_java(env)?.l()?.into_inner())
+    })
+}
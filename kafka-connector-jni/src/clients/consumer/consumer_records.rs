@@ -0,0 +1,73 @@
+use indexmap::IndexMap;
+
+use crate::common::topic_partition::TopicPartition;
+
+use super::consumer_record::ConsumerRecord;
+
+/// A container that holds the list `ConsumerRecord` per partition for a
+/// particular topic. There is one `ConsumerRecord` list for every topic
+/// partition returned by a `KafkaConsumer::poll` operation.
+pub struct ConsumerRecords<K, V> {
+    records: IndexMap<TopicPartition, Vec<ConsumerRecord<K, V>>>,
+}
+
+impl<K, V> ConsumerRecords<K, V> {
+    pub fn new(records: IndexMap<TopicPartition, Vec<ConsumerRecord<K, V>>>) -> Self {
+        ConsumerRecords { records }
+    }
+
+    pub fn empty() -> Self {
+        ConsumerRecords::new(IndexMap::new())
+    }
+
+    /// Get just the records for the given partition
+    pub fn records(&self, partition: &TopicPartition) -> &[ConsumerRecord<K, V>] {
+        self.records
+            .get(partition)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Get just the records for the given topic
+    pub fn records_for_topic<'a>(
+        &'a self,
+        topic: &'a str,
+    ) -> impl Iterator<Item = &'a ConsumerRecord<K, V>> + 'a {
+        self.records
+            .iter()
+            .filter(move |(tp, _)| tp.topic == topic)
+            .flat_map(|(_, records)| records.iter())
+    }
+
+    /// Get the partitions which have records contained in this record set.
+    pub fn partitions(&self) -> impl Iterator<Item = &TopicPartition> {
+        self.records.keys()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConsumerRecord<K, V>> {
+        self.records.values().flat_map(|records| records.iter())
+    }
+
+    /// The number of records for all topics
+    pub fn count(&self) -> usize {
+        self.records.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+impl<K, V> IntoIterator for ConsumerRecords<K, V> {
+    type Item = ConsumerRecord<K, V>;
+    #[allow(clippy::type_complexity)]
+    type IntoIter = std::iter::FlatMap<
+        indexmap::map::IntoIter<TopicPartition, Vec<ConsumerRecord<K, V>>>,
+        Vec<ConsumerRecord<K, V>>,
+        fn((TopicPartition, Vec<ConsumerRecord<K, V>>)) -> Vec<ConsumerRecord<K, V>>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter().flat_map(|(_, records)| records)
+    }
+}
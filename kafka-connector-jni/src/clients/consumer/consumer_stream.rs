@@ -0,0 +1,296 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use futures_core::Stream;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    utils::exponential_backoff::ExponentialBackoff,
+};
+
+use super::{consumer_record::ConsumerRecord, kafka_consumer::KafkaConsumer};
+
+/// The maximum time the stream backs off after consecutive errors of the consumer.
+const MAX_ERROR_BACKOFF_MS: u128 = 1000;
+
+/// Adapts a `KafkaConsumer` to an asynchronous `Stream` of records.
+///
+/// Every poll of the stream drains the records buffered by the consumer without blocking. When no
+/// records are available the task is woken up again after `poll_interval`, so the executor is never
+/// blocked on network I/O. Errors returned by the consumer are yielded as stream items, after which
+/// the stream backs off exponentially before polling the consumer again; the stream ends once the
+/// consumer is closed.
+///
+/// The consumer is not thread-safe, so the stream must be polled from the thread that owns the consumer
+/// (e.g. with a current thread executor).
+pub struct ConsumerStream<'a, K, V> {
+    consumer: &'a KafkaConsumer<K, V>,
+    poll_interval: Duration,
+    buffer: VecDeque<ConsumerRecord<K, V>>,
+    error_backoff: ExponentialBackoff,
+    failed_attempts: u32,
+    backoff_deadline: Option<Instant>,
+    timer: WakeupTimer,
+}
+
+impl<'a, K, V> ConsumerStream<'a, K, V> {
+    pub fn new(consumer: &'a KafkaConsumer<K, V>, poll_interval: Duration) -> Self {
+        ConsumerStream {
+            consumer,
+            poll_interval,
+            buffer: VecDeque::new(),
+            error_backoff: ExponentialBackoff::new(
+                poll_interval.as_millis(),
+                2,
+                MAX_ERROR_BACKOFF_MS,
+                0.2,
+            ),
+            failed_attempts: 0,
+            backoff_deadline: None,
+            timer: WakeupTimer::new(),
+        }
+    }
+}
+
+impl<K: Unpin, V: Unpin> Stream for ConsumerStream<'_, K, V> {
+    type Item = Result<ConsumerRecord<K, V>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(record) = self.buffer.pop_front() {
+            return Poll::Ready(Some(Ok(record)));
+        }
+        if self.consumer.is_closed() {
+            return Poll::Ready(None);
+        }
+        if let Some(deadline) = self.backoff_deadline {
+            if Instant::now() < deadline {
+                return match self.timer.schedule(deadline, cx.waker()) {
+                    Ok(()) => Poll::Pending,
+                    Err(error) => Poll::Ready(Some(Err(error))),
+                };
+            }
+            self.backoff_deadline = None;
+        }
+
+        match self.consumer.poll(Duration::ZERO) {
+            Ok(records) => {
+                self.failed_attempts = 0;
+                self.buffer.extend(records);
+                match self.buffer.pop_front() {
+                    Some(record) => Poll::Ready(Some(Ok(record))),
+                    None => {
+                        let deadline = Instant::now() + self.poll_interval;
+                        match self.timer.schedule(deadline, cx.waker()) {
+                            Ok(()) => Poll::Pending,
+                            Err(error) => Poll::Ready(Some(Err(error))),
+                        }
+                    }
+                }
+            }
+            Err(error) => {
+                let backoff_ms = self.error_backoff.backoff(self.failed_attempts);
+                self.failed_attempts = self.failed_attempts.saturating_add(1);
+                self.backoff_deadline =
+                    Some(Instant::now() + Duration::from_millis(backoff_ms as u64));
+                Poll::Ready(Some(Err(error)))
+            }
+        }
+    }
+}
+
+/// Wakes up the task polling a stream once a deadline has passed. A single thread, started on first
+/// use and stopped when the timer is dropped, serves all the wakeups of the stream.
+struct WakeupTimer {
+    shared: Arc<(Mutex<WakeupTimerState>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct WakeupTimerState {
+    wakeup: Option<(Instant, Waker)>,
+    closed: bool,
+}
+
+impl WakeupTimer {
+    fn new() -> WakeupTimer {
+        WakeupTimer {
+            shared: Arc::new((Mutex::new(WakeupTimerState::default()), Condvar::new())),
+            thread: None,
+        }
+    }
+
+    fn lock(shared: &(Mutex<WakeupTimerState>, Condvar)) -> MutexGuard<'_, WakeupTimerState> {
+        shared.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Wake up `waker` at `deadline`, replacing the wakeup scheduled before.
+    fn schedule(&mut self, deadline: Instant, waker: &Waker) -> Result<()> {
+        if self.thread.is_none() {
+            let shared = self.shared.clone();
+            let thread = thread::Builder::new()
+                .name("kafka-consumer-stream-timer".to_owned())
+                .spawn(move || WakeupTimer::run(&shared))
+                .map_err(|e| {
+                    KafkaError::Kafka(format!("Failed to start the stream timer thread: {}", e))
+                })?;
+            self.thread = Some(thread);
+        }
+        WakeupTimer::lock(&self.shared).wakeup = Some((deadline, waker.clone()));
+        self.shared.1.notify_one();
+        Ok(())
+    }
+
+    fn run(shared: &(Mutex<WakeupTimerState>, Condvar)) {
+        let mut state = WakeupTimer::lock(shared);
+        while !state.closed {
+            let deadline = match &state.wakeup {
+                Some((deadline, _)) => *deadline,
+                None => {
+                    state = shared.1.wait(state).unwrap_or_else(|e| e.into_inner());
+                    continue;
+                }
+            };
+            let now = Instant::now();
+            if now < deadline {
+                state = shared
+                    .1
+                    .wait_timeout(state, deadline - now)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            } else if let Some((_, waker)) = state.wakeup.take() {
+                drop(state);
+                waker.wake();
+                state = WakeupTimer::lock(shared);
+            }
+        }
+    }
+}
+
+impl Drop for WakeupTimer {
+    fn drop(&mut self) {
+        WakeupTimer::lock(&self.shared).closed = true;
+        self.shared.1.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        sync::{
+            mpsc::{self, Receiver, Sender},
+            Arc, Mutex,
+        },
+        task::{Context, Poll, Wake, Waker},
+        time::Duration,
+    };
+
+    use futures_core::Stream;
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::consumer::{
+            consumer_config::{
+                ConsumerConfig, KEY_DESERIALIZER_CLASS_CONFIG, VALUE_DESERIALIZER_CLASS_CONFIG,
+            },
+            kafka_consumer::KafkaConsumer,
+        },
+        common::{
+            errors::KafkaError, serialization::string_deserializer::StringDeserializer,
+            topic_partition::TopicPartition,
+        },
+    };
+
+    use super::ConsumerStream;
+
+    const STRING_DESERIALIZER: &str = "org.apache.kafka.common.serialization.StringDeserializer";
+
+    struct ChannelWaker(Mutex<Sender<()>>);
+
+    impl Wake for ChannelWaker {
+        fn wake(self: Arc<Self>) {
+            let _ = self.0.lock().unwrap().send(());
+        }
+    }
+
+    fn waker() -> (Waker, Receiver<()>) {
+        let (sender, receiver) = mpsc::channel();
+        (
+            Waker::from(Arc::new(ChannelWaker(Mutex::new(sender)))),
+            receiver,
+        )
+    }
+
+    fn consumer() -> KafkaConsumer<Option<String>, Option<String>> {
+        let props: IndexMap<String, String> = [
+            ("bootstrap.servers", "localhost:9092"),
+            (KEY_DESERIALIZER_CLASS_CONFIG, STRING_DESERIALIZER),
+            (VALUE_DESERIALIZER_CLASS_CONFIG, STRING_DESERIALIZER),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        KafkaConsumer::from_config(
+            ConsumerConfig::new(props).unwrap(),
+            Box::new(StringDeserializer),
+            Box::new(StringDeserializer),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn errors_are_yielded_and_followed_by_a_backoff() {
+        let consumer = consumer();
+        let mut stream = ConsumerStream::new(&consumer, Duration::from_millis(20));
+        let (waker, woken) = waker();
+        let mut cx = Context::from_waker(&waker);
+
+        // polling without subscription fails
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(Some(Err(KafkaError::IllegalState(_))))
+        ));
+        assert!(Pin::new(&mut stream).poll_next(&mut cx).is_pending());
+        woken.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(Some(Err(KafkaError::IllegalState(_))))
+        ));
+        assert_eq!(stream.failed_attempts, 2);
+    }
+
+    #[test]
+    fn empty_polls_reuse_the_timer_thread() {
+        let consumer = consumer();
+        consumer.assign(&[TopicPartition::new("topic", 0)]).unwrap();
+        let mut stream = ConsumerStream::new(&consumer, Duration::from_millis(10));
+        let (waker, woken) = waker();
+        let mut cx = Context::from_waker(&waker);
+
+        assert!(Pin::new(&mut stream).poll_next(&mut cx).is_pending());
+        let thread_id = stream.timer.thread.as_ref().unwrap().thread().id();
+        for _ in 0..3 {
+            woken.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert!(Pin::new(&mut stream).poll_next(&mut cx).is_pending());
+            assert_eq!(
+                stream.timer.thread.as_ref().unwrap().thread().id(),
+                thread_id
+            );
+        }
+
+        consumer.close(Duration::ZERO).unwrap();
+        assert!(matches!(
+            Pin::new(&mut stream).poll_next(&mut cx),
+            Poll::Ready(None)
+        ));
+    }
+}
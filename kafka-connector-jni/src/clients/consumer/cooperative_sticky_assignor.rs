@@ -0,0 +1,348 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicI32, Ordering},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{cluster::Cluster, errors::Result, topic_partition::TopicPartition};
+
+use super::{
+    consumer_group_metadata::ConsumerGroupMetadata,
+    consumer_partition_assignor::{
+        Assignment, ConsumerPartitionAssignor, GroupAssignment, GroupSubscription,
+        RebalanceProtocol, Subscription,
+    },
+    internals::{
+        abstract_partition_assignor::AbstractPartitionAssignor,
+        abstract_sticky_assignor::{AbstractStickyAssignor, MemberData, DEFAULT_GENERATION},
+    },
+};
+
+pub const COOPERATIVE_STICKY_ASSIGNOR_NAME: &str = "cooperative-sticky";
+
+/// A cooperative version of the `AbstractStickyAssignor`. This follows the same (sticky)
+/// assignment logic as `StickyAssignor` but allows for cooperative rebalancing while the
+/// `StickyAssignor` follows the eager rebalancing protocol. See `RebalanceProtocol` for an explanation
+/// of the rebalancing protocols.
+///
+/// To turn on cooperative rebalancing you must set all your consumers to use this assignor, or implement your own
+/// assignor that supports the `Cooperative` protocol.
+///
+/// IMPORTANT: if upgrading from 2.3 or earlier, you must follow a specific upgrade path in order to safely turn on
+/// cooperative rebalancing. See the upgrade guide for details.
+#[derive(Debug)]
+pub struct CooperativeStickyAssignor {
+    /// consumer group generation
+    generation: AtomicI32,
+}
+
+impl Default for CooperativeStickyAssignor {
+    fn default() -> Self {
+        CooperativeStickyAssignor {
+            generation: AtomicI32::new(DEFAULT_GENERATION),
+        }
+    }
+}
+
+impl CooperativeStickyAssignor {
+    pub fn new() -> CooperativeStickyAssignor {
+        CooperativeStickyAssignor::default()
+    }
+
+    /// Following the cooperative rebalancing protocol requires removing partitions that must first be revoked from the assignment
+    fn adjust_assignment(
+        assignments: &mut IndexMap<String, Vec<TopicPartition>>,
+        partitions_transferring_ownership: &HashMap<TopicPartition, String>,
+    ) {
+        for (partition, consumer) in partitions_transferring_ownership {
+            if let Some(assignment) = assignments.get_mut(consumer) {
+                assignment.retain(|tp| tp != partition);
+            }
+        }
+    }
+
+    fn compute_partitions_transferring_ownership(
+        subscriptions: &IndexMap<String, Subscription>,
+        assignments: &IndexMap<String, Vec<TopicPartition>>,
+    ) -> HashMap<TopicPartition, String> {
+        let mut all_added_partitions = HashMap::new();
+        let mut all_revoked_partitions = HashSet::new();
+
+        for (consumer, assigned_partitions) in assignments {
+            let owned_partitions: &[TopicPartition] = subscriptions
+                .get(consumer)
+                .map_or(&[], |subscription| &subscription.owned_partitions);
+
+            let owned_partitions_set: HashSet<&TopicPartition> = owned_partitions.iter().collect();
+            for tp in assigned_partitions {
+                if !owned_partitions_set.contains(tp) {
+                    all_added_partitions.insert(tp.clone(), consumer.clone());
+                }
+            }
+
+            let assigned_partitions_set: HashSet<&TopicPartition> =
+                assigned_partitions.iter().collect();
+            for tp in owned_partitions {
+                if !assigned_partitions_set.contains(tp) {
+                    all_revoked_partitions.insert(tp.clone());
+                }
+            }
+        }
+
+        all_added_partitions.retain(|tp, _| all_revoked_partitions.contains(tp));
+        all_added_partitions
+    }
+}
+
+impl AbstractStickyAssignor for CooperativeStickyAssignor {
+    fn member_data(&self, subscription: &Subscription) -> MemberData {
+        let encoded_generation = subscription.user_data.as_ref().map(|user_data| {
+            let mut buffer = user_data.clone();
+            if buffer.remaining() >= 4 {
+                buffer.get_i32()
+            } else {
+                DEFAULT_GENERATION
+            }
+        });
+        MemberData::new(subscription.owned_partitions.clone(), encoded_generation)
+    }
+}
+
+impl AbstractPartitionAssignor for CooperativeStickyAssignor {
+    fn assign_partitions(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<IndexMap<String, Vec<TopicPartition>>> {
+        let sticky_assignment = self.sticky_assign(partitions_per_topic, subscriptions)?;
+        let mut assignments = sticky_assignment.assignment;
+        let partitions_transferring_ownership = match sticky_assignment
+            .partitions_transferring_ownership
+        {
+            Some(partitions_transferring_ownership) => partitions_transferring_ownership,
+            None => Self::compute_partitions_transferring_ownership(subscriptions, &assignments),
+        };
+        Self::adjust_assignment(&mut assignments, &partitions_transferring_ownership);
+        Ok(assignments)
+    }
+}
+
+impl ConsumerPartitionAssignor for CooperativeStickyAssignor {
+    fn subscription_user_data(&self, _topics: &HashSet<String>) -> Option<Bytes> {
+        let mut buffer = BytesMut::with_capacity(4);
+        buffer.put_i32(self.generation.load(Ordering::SeqCst));
+        Some(buffer.freeze())
+    }
+
+    fn assign(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment> {
+        self.assign_group(metadata, group_subscription)
+    }
+
+    fn on_assignment(&self, _assignment: &Assignment, metadata: Option<&ConsumerGroupMetadata>) {
+        if let Some(metadata) = metadata {
+            self.generation
+                .store(metadata.generation_id, Ordering::SeqCst);
+        }
+    }
+
+    fn supported_protocols(&self) -> Vec<RebalanceProtocol> {
+        vec![RebalanceProtocol::Cooperative, RebalanceProtocol::Eager]
+    }
+
+    fn name(&self) -> &str {
+        COOPERATIVE_STICKY_ASSIGNOR_NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bytes::{BufMut, BytesMut};
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::consumer::{
+            consumer_partition_assignor::Subscription,
+            internals::abstract_sticky_assignor::tests::{
+                assert_balanced, assert_valid, assign, random_group, topics, Rng, LAYOUTS,
+            },
+        },
+        common::topic_partition::TopicPartition,
+    };
+
+    use super::CooperativeStickyAssignor;
+
+    /// Subscriptions of `members` to all topics, owning the partitions assigned to them in `owned`
+    /// during `generation`.
+    fn subscriptions(
+        topics: &[String],
+        members: &[String],
+        owned: &IndexMap<String, Vec<TopicPartition>>,
+        generation: i32,
+    ) -> IndexMap<String, Subscription> {
+        members
+            .iter()
+            .map(|member| {
+                let mut user_data = BytesMut::with_capacity(4);
+                user_data.put_i32(generation);
+                let owned_partitions = owned.get(member).cloned().unwrap_or_default();
+                let subscription =
+                    Subscription::new(topics.to_vec(), Some(user_data.freeze()), owned_partitions);
+                (member.clone(), subscription)
+            })
+            .collect()
+    }
+
+    /// A partition revoked from its owner can't be handed to another member before the owner
+    /// has given it up, so it stays unassigned for this round.
+    fn assert_no_partition_changes_owner(
+        assignment: &IndexMap<String, Vec<TopicPartition>>,
+        subscriptions: &IndexMap<String, Subscription>,
+        seed: u64,
+    ) {
+        let owners: HashMap<&TopicPartition, &String> = subscriptions
+            .iter()
+            .flat_map(|(member, subscription)| {
+                subscription
+                    .owned_partitions
+                    .iter()
+                    .map(move |tp| (tp, member))
+            })
+            .collect();
+        for (member, partitions) in assignment {
+            for tp in partitions {
+                if let Some(owner) = owners.get(tp) {
+                    assert_eq!(
+                        *owner, member,
+                        "seed {}: {} is reassigned from {} in the round it's revoked",
+                        seed, tp, owner
+                    );
+                }
+            }
+        }
+    }
+
+    /// Two rounds of the cooperative protocol after the group changed from the owners of `owned`
+    /// to `members`: the first one only revokes, the second one assigns the revoked partitions.
+    fn rebalance(
+        partitions_per_topic: &IndexMap<String, usize>,
+        members: &[String],
+        owned: &IndexMap<String, Vec<TopicPartition>>,
+        seed: u64,
+    ) -> IndexMap<String, Vec<TopicPartition>> {
+        let assignor = CooperativeStickyAssignor::new();
+        let topics = topics(partitions_per_topic);
+
+        let revoking = subscriptions(&topics, members, owned, 1);
+        let first = assign(&assignor, partitions_per_topic, &revoking);
+        assert_valid(&first, &revoking);
+        assert_no_partition_changes_owner(&first, &revoking, seed);
+
+        let follow_up = subscriptions(&topics, members, &first, 2);
+        let second = assign(&assignor, partitions_per_topic, &follow_up);
+        assert_valid(&second, &follow_up);
+        assert_no_partition_changes_owner(&second, &follow_up, seed);
+        assert_balanced(&second, partitions_per_topic);
+        // the follow-up rebalance doesn't revoke anything
+        for (member, partitions) in &first {
+            assert!(
+                partitions.iter().all(|tp| second[member].contains(tp)),
+                "seed {}: {} lost partitions in the follow-up rebalance",
+                seed,
+                member
+            );
+        }
+        second
+    }
+
+    #[test]
+    fn assignment_is_balanced() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, members) = random_group(&mut Rng::new(seed));
+            let subscriptions = subscriptions(
+                &topics(&partitions_per_topic),
+                &members,
+                &IndexMap::new(),
+                1,
+            );
+            let assignment = assign(
+                &CooperativeStickyAssignor::new(),
+                &partitions_per_topic,
+                &subscriptions,
+            );
+            assert_valid(&assignment, &subscriptions);
+            assert_balanced(&assignment, &partitions_per_topic);
+        }
+    }
+
+    #[test]
+    fn adding_a_member_revokes_before_reassigning() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, mut members) = random_group(&mut Rng::new(seed));
+            let initial = rebalance(&partitions_per_topic, &members, &IndexMap::new(), seed);
+
+            members.push("new-consumer".to_owned());
+            let assignment = rebalance(&partitions_per_topic, &members, &initial, seed);
+            // the existing members only give up partitions
+            for (member, partitions) in &initial {
+                assert!(
+                    assignment[member].iter().all(|tp| partitions.contains(tp)),
+                    "seed {}: {} got partitions it didn't own before",
+                    seed,
+                    member
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn removing_a_member_keeps_the_other_assignments() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, mut members) = random_group(&mut Rng::new(seed));
+            if members.len() < 2 {
+                continue;
+            }
+            let initial = rebalance(&partitions_per_topic, &members, &IndexMap::new(), seed);
+
+            members.remove(0);
+            let assignment = rebalance(&partitions_per_topic, &members, &initial, seed);
+            for member in &members {
+                assert!(
+                    initial[member]
+                        .iter()
+                        .all(|tp| assignment[member].contains(tp)),
+                    "seed {}: {} lost partitions",
+                    seed,
+                    member
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn changed_membership_never_reassigns_in_the_round_it_revokes() {
+        for seed in 0..LAYOUTS {
+            let mut rng = Rng::new(seed);
+            let (partitions_per_topic, members) = random_group(&mut rng);
+            let initial = rebalance(&partitions_per_topic, &members, &IndexMap::new(), seed);
+
+            // some members leave and others join at the same time
+            let mut members: Vec<String> = members
+                .into_iter()
+                .filter(|_| rng.between(0, 2) > 0)
+                .collect();
+            members.extend((0..rng.between(0, 3)).map(|member| format!("new-consumer{}", member)));
+            if members.is_empty() {
+                continue;
+            }
+            rebalance(&partitions_per_topic, &members, &initial, seed);
+        }
+    }
+}
@@ -0,0 +1,2079 @@
+use std::{
+    fmt::{self, Display},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, MutexGuard, Weak,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::{
+    clients::{client_response::ClientResponse, group_rebalance_config::GroupRebalanceConfig},
+    common::{
+        errors::{KafkaError, Result},
+        node::Node,
+        protocol::{api_keys::ApiKeys, errors::Errors},
+        requests::{
+            abstract_request::AbstractRequest,
+            abstract_response::AbstractResponse,
+            find_coordinator_request::{CoordinatorType, FindCoordinatorRequest},
+            heartbeat_request::HeartbeatRequest,
+            join_group_request::{
+                JoinGroupRequest, JoinGroupRequestProtocol, UNKNOWN_GENERATION_ID,
+                UNKNOWN_MEMBER_ID,
+            },
+            join_group_response::{JoinGroupResponse, JoinGroupResponseMember},
+            leave_group_request::{LeaveGroupRequest, MemberIdentity},
+            sync_group_request::{SyncGroupRequest, SyncGroupRequestAssignment},
+        },
+        utils::{
+            log_context::{LogContext, Logger},
+            time::Time,
+            timer::Timer,
+        },
+    },
+};
+
+use super::{
+    consumer_network_client::ConsumerNetworkClient, heartbeat::Heartbeat,
+    request_future::RequestFuture,
+};
+
+pub const HEARTBEAT_THREAD_PREFIX: &str = "kafka-coordinator-heartbeat-thread";
+pub const JOIN_GROUP_TIMEOUT_LAPSE: u128 = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemberState {
+    /// the client has received join group response, but have not received assignment
+    CompletingRebalance,
+    /// the client has sent the join group request, but have not received response
+    PreparingRebalance,
+    /// the client has joined and is sending heartbeats
+    Stable,
+    /// the client is not part of a group
+    Unjoined,
+}
+
+impl MemberState {
+    pub fn has_not_joined_group(&self) -> bool {
+        matches!(
+            self,
+            MemberState::Unjoined | MemberState::PreparingRebalance
+        )
+    }
+}
+
+impl Display for MemberState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemberState::CompletingRebalance => write!(f, "COMPLETING_REBALANCE"),
+            MemberState::PreparingRebalance => write!(f, "PREPARING_REBALANCE"),
+            MemberState::Stable => write!(f, "STABLE"),
+            MemberState::Unjoined => write!(f, "UNJOINED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Generation {
+    pub generation_id: i32,
+    pub member_id: String,
+    pub protocol_name: Option<String>,
+}
+
+impl Generation {
+    pub fn new(
+        generation_id: i32,
+        member_id: impl Into<String>,
+        protocol_name: Option<String>,
+    ) -> Generation {
+        Generation {
+            generation_id,
+            member_id: member_id.into(),
+            protocol_name,
+        }
+    }
+
+    pub fn no_generation() -> Generation {
+        Generation::new(UNKNOWN_GENERATION_ID, UNKNOWN_MEMBER_ID, None)
+    }
+
+    /// Returns true if this generation has a valid member id, false otherwise. A member might have an id before
+    /// it becomes part of a group generation.
+    pub fn has_member_id(&self) -> bool {
+        !self.member_id.is_empty()
+    }
+}
+
+impl Display for Generation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Generation{{generationId={}, memberId='{}', protocol='{}'}}",
+            self.generation_id,
+            self.member_id,
+            self.protocol_name.as_deref().unwrap_or("null")
+        )
+    }
+}
+
+/// The group protocol specific part of a coordinator: the format of the metadata provided by each
+/// member for group registration and of the state assignment provided by the leader, and the
+/// callbacks invoked around a rebalance.
+///
+/// All methods are invoked on the thread calling `AbstractCoordinator::ensure_active_group` (or
+/// `close`), never from the heartbeat thread.
+pub trait GroupProtocolHandler {
+    /// Get the current list of protocols and their associated metadata supported
+    /// by the local member. The order of the protocols in the list indicates the preference
+    /// of the protocol (the first entry is the most preferred). The coordinator takes this
+    /// preference into account when selecting the generation protocol (generally more preferred
+    /// protocols will be selected as long as all members support them and there is no disagreement
+    /// on the preference).
+    fn metadata(&self) -> Result<Vec<JoinGroupRequestProtocol>>;
+
+    /// Invoked prior to each group join or rejoin. This is typically used to perform any
+    /// cleanup from the previous generation (such as committing offsets for the consumer)
+    fn on_join_prepare(&self, generation: i32, member_id: &str) -> Result<()>;
+
+    /// Perform assignment for the group. This is used by the leader to push state to all the members
+    /// of the group (e.g. to push partition assignments in the case of the new consumer)
+    ///
+    /// Returns a map from each member to their state assignment
+    fn perform_assignment(
+        &self,
+        leader_id: &str,
+        protocol: &str,
+        all_member_metadata: &[JoinGroupResponseMember],
+    ) -> Result<IndexMap<String, Bytes>>;
+
+    /// Invoked when a group member has successfully joined a group. If this call fails with an
+    /// error, then it will be retried using the same assignment state on the next call to
+    /// `ensure_active_group`.
+    fn on_join_complete(
+        &self,
+        generation: i32,
+        member_id: &str,
+        protocol: Option<&str>,
+        member_assignment: Bytes,
+    ) -> Result<()>;
+
+    /// Invoked prior to each leave group event. This is typically used to cleanup assigned partitions;
+    /// note it is triggered by the consumer's API caller thread (i.e. background heartbeat thread would
+    /// not trigger it even if it tries to force leaving group upon heartbeat session expiration)
+    fn on_leave_prepare(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Reasons to (re-)join the group known only to the protocol, e.g. a changed subscription.
+    fn rejoin_needed(&self) -> bool {
+        false
+    }
+}
+
+/// AbstractCoordinator implements group management for a single group member by interacting with
+/// a designated Kafka broker (the coordinator). Group semantics are provided by extending this class.
+/// See `ConsumerCoordinator` for example usage.
+///
+/// From a high level, Kafka's group management protocol consists of the following sequence of actions:
+///
+/// 1. Group Registration: Group members register with the coordinator providing their own metadata
+///    (such as the set of topics they are interested in).
+/// 2. Group/Leader Selection: The coordinator select the members of the group and chooses one member
+///    as the leader.
+/// 3. State Assignment: The leader collects the metadata from all the members of the group and
+///    assigns state.
+/// 4. Group Stabilization: Each member receives the state assigned by the leader and begins
+///    processing.
+///
+/// To leverage this protocol, an implementation must define the format of metadata provided by each
+/// member for group registration in `GroupProtocolHandler::metadata` and the format of the state assignment
+/// provided by the leader in `GroupProtocolHandler::perform_assignment` and becomes available to members in
+/// `GroupProtocolHandler::on_join_complete`.
+///
+/// Note on locking: this class shares state between the caller and a background thread which is
+/// used for sending heartbeats after the client has joined the group. All mutable state as well as
+/// state transitions are protected with the coordinator's lock, which is never held while polling
+/// the network client.
+pub struct AbstractCoordinator {
+    log: Logger,
+    rebalance_config: GroupRebalanceConfig,
+    protocol_type: String,
+    time: Arc<dyn Time>,
+    client: Arc<ConsumerNetworkClient>,
+    state: Mutex<CoordinatorState>,
+    // Signalled to wake up the heartbeat thread
+    heartbeat_notifier: Condvar,
+    // Mirrors `CoordinatorState::pending_leader_join_response`, so the join poll condition can be
+    // checked while the network client is locked without taking the coordinator lock
+    leader_assignment_pending: AtomicBool,
+}
+
+struct CoordinatorState {
+    heartbeat: Heartbeat,
+    coordinator: Option<Node>,
+    rejoin_needed: bool,
+    needs_join_prepare: bool,
+    join_future: Option<Arc<RequestFuture<Bytes>>>,
+    // JoinGroup response received as the leader, waiting for the assignment to be performed
+    // by the thread polling the join future
+    pending_leader_join_response: Option<JoinGroupResponse>,
+    find_coordinator_future: Option<Arc<RequestFuture<()>>>,
+    fatal_find_coordinator_exception: Option<KafkaError>,
+    generation: Generation,
+    last_rebalance_start_ms: Option<u128>,
+    last_rebalance_end_ms: Option<u128>,
+    // starting logging a warning only after unable to connect for a while
+    last_time_of_connection_ms: Option<u128>,
+    state: MemberState,
+    heartbeat_thread: Option<JoinHandle<()>>,
+    // incremented for every started heartbeat thread, a thread exits once it's been superseded
+    heartbeat_thread_epoch: u64,
+    heartbeat_thread_enabled: bool,
+    heartbeat_thread_closed: bool,
+    heartbeat_thread_failed: Option<KafkaError>,
+}
+
+impl AbstractCoordinator {
+    /// Fails with `IllegalArgument` if the heartbeat interval isn't lower than the session
+    /// timeout.
+    pub fn new(
+        rebalance_config: GroupRebalanceConfig,
+        protocol_type: impl Into<String>,
+        time: Arc<dyn Time>,
+        client: Arc<ConsumerNetworkClient>,
+        log_context: &LogContext,
+    ) -> Result<AbstractCoordinator> {
+        let heartbeat = Heartbeat::new(rebalance_config.clone(), time.clone())?;
+        Ok(AbstractCoordinator {
+            log: log_context.logger(module_path!()),
+            rebalance_config,
+            protocol_type: protocol_type.into(),
+            time,
+            client,
+            state: Mutex::new(CoordinatorState {
+                heartbeat,
+                coordinator: None,
+                rejoin_needed: true,
+                needs_join_prepare: true,
+                join_future: None,
+                pending_leader_join_response: None,
+                find_coordinator_future: None,
+                fatal_find_coordinator_exception: None,
+                generation: Generation::no_generation(),
+                last_rebalance_start_ms: None,
+                last_rebalance_end_ms: None,
+                last_time_of_connection_ms: None,
+                state: MemberState::Unjoined,
+                heartbeat_thread: None,
+                heartbeat_thread_epoch: 0,
+                heartbeat_thread_enabled: false,
+                heartbeat_thread_closed: false,
+                heartbeat_thread_failed: None,
+            }),
+            heartbeat_notifier: Condvar::new(),
+            leader_assignment_pending: AtomicBool::new(false),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CoordinatorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn rebalance_config(&self) -> &GroupRebalanceConfig {
+        &self.rebalance_config
+    }
+
+    pub fn protocol_type(&self) -> &str {
+        &self.protocol_type
+    }
+
+    pub fn client(&self) -> &Arc<ConsumerNetworkClient> {
+        &self.client
+    }
+
+    /// Ensure that the coordinator is ready to receive requests.
+    ///
+    /// Returns true if coordinator discovery and initial connection succeeded, false otherwise
+    pub fn ensure_coordinator_ready(self: &Arc<Self>, timer: &mut Timer) -> Result<bool> {
+        if !self.coordinator_unknown() {
+            return Ok(true);
+        }
+
+        loop {
+            if let Some(fatal_exception) = self.lock().fatal_find_coordinator_exception.take() {
+                return Err(fatal_exception);
+            }
+            let future = self.lookup_coordinator();
+            self.client.poll_future(&future, timer)?;
+
+            if !future.is_done() {
+                // ran out of time
+                break;
+            }
+
+            let mut fatal_exception = None;
+            if let Some(exception) = future.exception() {
+                if exception.is_retriable() {
+                    self.log.debug(format_args!(
+                        "Coordinator discovery failed, refreshing metadata: {}",
+                        exception
+                    ));
+                    self.client.await_metadata_update(timer)?;
+                } else {
+                    self.log.info(format_args!(
+                        "FindCoordinator request hit fatal exception: {}",
+                        exception
+                    ));
+                    fatal_exception = Some(exception);
+                }
+            } else if let Some(coordinator) = self.coordinator() {
+                if self.client.is_unavailable(&coordinator) {
+                    // we found the coordinator, but the connection has failed, so mark
+                    // it dead and backoff before retrying discovery
+                    self.mark_coordinator_unknown(false, "coordinator unavailable");
+                    timer.sleep(self.rebalance_config.retry_backoff_ms);
+                }
+            }
+
+            self.clear_find_coordinator_future();
+            if let Some(fatal_exception) = fatal_exception {
+                return Err(fatal_exception);
+            }
+            if !self.coordinator_unknown() || timer.is_expired() {
+                break;
+            }
+        }
+
+        Ok(!self.coordinator_unknown())
+    }
+
+    pub fn lookup_coordinator(self: &Arc<Self>) -> Arc<RequestFuture<()>> {
+        let mut state = self.lock();
+        if let Some(future) = &state.find_coordinator_future {
+            return future.clone();
+        }
+        // find a node to ask about the coordinator
+        match self.client.least_loaded_node() {
+            Some(node) => {
+                let future = self.send_find_coordinator_request(&node);
+                state.find_coordinator_future = Some(future.clone());
+                future
+            }
+            None => {
+                drop(state);
+                self.log.debug(format_args!(
+                    "No broker available to send FindCoordinator request"
+                ));
+                RequestFuture::failure(KafkaError::Network(
+                    "No broker available to send FindCoordinator request".into(),
+                ))
+            }
+        }
+    }
+
+    fn clear_find_coordinator_future(&self) {
+        self.lock().find_coordinator_future = None;
+    }
+
+    /// Check whether the group should be rejoined (e.g. if metadata changes) or whether a
+    /// rejoin request is already in flight and needs to be completed.
+    pub fn rejoin_needed_or_pending(&self) -> bool {
+        let state = self.lock();
+        // if there's a pending joinFuture, we should try to complete handling it.
+        state.rejoin_needed || state.join_future.is_some()
+    }
+
+    /// Check the status of the heartbeat thread (if it is active) and indicate the liveness
+    /// of the client. This must be called periodically after joining with `ensure_active_group`
+    /// to ensure that the member stays in the group. If an interval of time longer than the
+    /// provided rebalance timeout expires without calling this method, then the client will proactively
+    /// leave the group.
+    ///
+    /// Fails with the unexpected error raised from the heartbeat thread.
+    pub fn poll_heartbeat(&self, now: u128) -> Result<()> {
+        let mut state = self.lock();
+        if state.heartbeat_thread.is_some() {
+            if let Some(cause) = state.heartbeat_thread_failed.take() {
+                // drop the heartbeat thread and raise an exception. If the user catches it,
+                // the next call to ensure_active_group() will spawn a new heartbeat thread.
+                state.heartbeat_thread = None;
+                return Err(cause);
+            }
+            // Awake the heartbeat thread if needed
+            if state.heartbeat.should_heartbeat(now) {
+                self.heartbeat_notifier.notify_all();
+            }
+            state.heartbeat.poll(now);
+        }
+        Ok(())
+    }
+
+    pub fn time_to_next_heartbeat(&self, now: u128) -> u128 {
+        let mut state = self.lock();
+        // if we have not joined the group or we are preparing rebalance,
+        // we don't need to send heartbeats
+        if state.state.has_not_joined_group() {
+            return u128::MAX;
+        }
+        state.heartbeat.time_to_next_heartbeat(now)
+    }
+
+    /// Ensure the group is active (i.e., joined and synced)
+    ///
+    /// Returns true iff the group is active
+    pub fn ensure_active_group(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+        timer: &mut Timer,
+    ) -> Result<bool> {
+        // always ensure that the coordinator is ready because we may have been disconnected
+        // when sending heartbeats and does not necessarily require us to rejoin the group.
+        if !self.ensure_coordinator_ready(timer)? {
+            return Ok(false);
+        }
+
+        self.start_heartbeat_thread_if_needed()?;
+        self.join_group_if_needed(handler, timer)
+    }
+
+    fn start_heartbeat_thread_if_needed(self: &Arc<Self>) -> Result<()> {
+        let mut state = self.lock();
+        if state.heartbeat_thread.is_none() {
+            state.heartbeat_thread_epoch += 1;
+            state.heartbeat_thread_enabled = false;
+            state.heartbeat_thread_closed = false;
+            state.heartbeat_thread_failed = None;
+            let epoch = state.heartbeat_thread_epoch;
+            let coordinator = Arc::downgrade(self);
+            let log = self.log.clone();
+            let name = if self.rebalance_config.group_id.is_empty() {
+                HEARTBEAT_THREAD_PREFIX.to_owned()
+            } else {
+                format!(
+                    "{} | {}",
+                    HEARTBEAT_THREAD_PREFIX, self.rebalance_config.group_id
+                )
+            };
+            let handle = thread::Builder::new()
+                .name(name)
+                .spawn(move || run_heartbeat_thread(coordinator, epoch, log))
+                .map_err(|e| {
+                    KafkaError::Kafka(format!("Failed to start the heartbeat thread: {}", e))
+                })?;
+            state.heartbeat_thread = Some(handle);
+        }
+        Ok(())
+    }
+
+    fn close_heartbeat_thread(&self) {
+        let handle = {
+            let mut state = self.lock();
+            state.heartbeat_thread_closed = true;
+            self.heartbeat_notifier.notify_all();
+            state.heartbeat_thread.take()
+        };
+        if let Some(handle) = handle {
+            if handle.thread().id() != thread::current().id() && handle.join().is_err() {
+                self.log.warn(format_args!(
+                    "Heartbeat thread panicked before it was closed"
+                ));
+            }
+        }
+    }
+
+    fn enable_heartbeat_thread(&self, state: &mut CoordinatorState) {
+        if state.heartbeat_thread.is_some() {
+            self.log.debug(format_args!("Enabling heartbeat thread"));
+            state.heartbeat_thread_enabled = true;
+            state.heartbeat.reset_timeouts();
+            self.heartbeat_notifier.notify_all();
+        }
+    }
+
+    /// Joins the group without starting the heartbeat thread.
+    ///
+    /// If this function returns true, the state must always be in STABLE and heartbeat enabled.
+    /// If this function returns false, the state can be in one of the following:
+    ///  * UNJOINED: got error response but times out before being able to re-join, heartbeat disabled
+    ///  * PREPARING_REBALANCE: not yet received join-group response before timeout, heartbeat disabled
+    ///  * COMPLETING_REBALANCE: not yet received sync-group response before timeout, heartbeat enabled
+    ///
+    /// Returns true iff the operation succeeded
+    pub fn join_group_if_needed(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+        timer: &mut Timer,
+    ) -> Result<bool> {
+        while self.rejoin_needed_or_pending() || handler.rejoin_needed() {
+            if !self.ensure_coordinator_ready(timer)? {
+                return Ok(false);
+            }
+
+            // call on_join_prepare if needed. We set a flag to make sure that we do not call it a second
+            // time if the client is woken up before a pending rebalance completes. This must be called
+            // on each iteration of the loop because an event requiring a rebalance (such as a metadata
+            // refresh which changes the matched subscription set) can occur while another rebalance is
+            // still in progress.
+            let prepare_generation = {
+                let mut state = self.lock();
+                // need to reset the flag before calling on_join_prepare since the user callback may
+                // fail, in which case upon retry we should not retry on_join_prepare either.
+                if state.needs_join_prepare {
+                    state.needs_join_prepare = false;
+                    Some(state.generation.clone())
+                } else {
+                    None
+                }
+            };
+            if let Some(generation) = prepare_generation {
+                handler.on_join_prepare(generation.generation_id, &generation.member_id)?;
+            }
+
+            let future = self.initiate_join_group(handler);
+            self.poll_join_future(handler, &future, timer)?;
+            if !future.is_done() {
+                // we ran out of time
+                return Ok(false);
+            }
+
+            match future.result() {
+                Some(Ok(member_assignment)) => {
+                    // Generation data maybe concurrently cleared by Heartbeat thread.
+                    // Can't hold the lock during on_join_complete, because it can be long enough
+                    // and shouldn't block heartbeat thread.
+                    let (generation_snapshot, state_snapshot) = {
+                        let state = self.lock();
+                        (state.generation.clone(), state.state)
+                    };
+
+                    if generation_snapshot != Generation::no_generation()
+                        && state_snapshot == MemberState::Stable
+                    {
+                        handler.on_join_complete(
+                            generation_snapshot.generation_id,
+                            &generation_snapshot.member_id,
+                            generation_snapshot.protocol_name.as_deref(),
+                            member_assignment,
+                        )?;
+
+                        // Generally speaking we should always reset the join group future once the future is done, but here
+                        // we can only reset the join group future after the completion callback returns. This ensures
+                        // that if the callback is woken up, we will retry it on the next join_group_if_needed.
+                        // And because of that we should explicitly trigger reset_join_group_future in other conditions below.
+                        let mut state = self.lock();
+                        self.reset_join_group_future(&mut state);
+                        state.needs_join_prepare = true;
+                    } else {
+                        let reason = format!(
+                            "rebalance failed since the generation/state was modified by heartbeat thread to {}/{} before the rebalance callback triggered",
+                            generation_snapshot, state_snapshot
+                        );
+                        let mut state = self.lock();
+                        self.reset_state_and_rejoin(&mut state, &reason);
+                        self.reset_join_group_future(&mut state);
+                    }
+                }
+                Some(Err(exception)) => {
+                    {
+                        let mut state = self.lock();
+                        self.reset_join_group_future(&mut state);
+                        state.rejoin_needed = true;
+                    }
+
+                    match exception {
+                        KafkaError::UnknownMemberId(_)
+                        | KafkaError::IllegalGeneration(_)
+                        | KafkaError::RebalanceInProgress(_)
+                        | KafkaError::MemberIdRequired(_) => continue,
+                        exception if !exception.is_retriable() => return Err(exception),
+                        _ => {}
+                    }
+
+                    timer.sleep(self.rebalance_config.retry_backoff_ms);
+                }
+                None => {}
+            }
+        }
+        Ok(true)
+    }
+
+    /// Poll until the join future completes, performing the leader assignment on this thread if
+    /// we've been elected as the leader.
+    fn poll_join_future(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+        future: &Arc<RequestFuture<Bytes>>,
+        timer: &mut Timer,
+    ) -> Result<()> {
+        loop {
+            let should_block =
+                || !future.is_done() && !self.leader_assignment_pending.load(Ordering::SeqCst);
+            self.client
+                .poll_with_condition(timer, Some(&should_block), false)?;
+
+            let pending_leader_join_response = {
+                let mut state = self.lock();
+                self.leader_assignment_pending
+                    .store(false, Ordering::SeqCst);
+                state.pending_leader_join_response.take()
+            };
+            if let Some(join_response) = pending_leader_join_response {
+                self.on_join_leader(handler, join_response)
+                    .chain(future.clone());
+            }
+            if future.is_done() || timer.is_expired() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn reset_join_group_future(&self, state: &mut CoordinatorState) {
+        state.join_future = None;
+        state.pending_leader_join_response = None;
+        self.leader_assignment_pending
+            .store(false, Ordering::SeqCst);
+    }
+
+    fn initiate_join_group(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+    ) -> Arc<RequestFuture<Bytes>> {
+        // we store the join future in case we are woken up by the user after beginning the
+        // rebalance in the call to poll below. This ensures that we do not mistakenly attempt
+        // to rejoin before the pending rebalance has completed.
+        {
+            let mut state = self.lock();
+            if let Some(join_future) = &state.join_future {
+                return join_future.clone();
+            }
+            state.state = MemberState::PreparingRebalance;
+            // a rebalance can be triggered consecutively if the previous one failed,
+            // in this case we would not update the start time.
+            if state.last_rebalance_start_ms.is_none() {
+                state.last_rebalance_start_ms = Some(self.time.milliseconds());
+            }
+        }
+        let join_future = self.send_join_group_request(handler);
+        self.lock().join_future = Some(join_future.clone());
+        join_future
+    }
+
+    /// Join the group and return the assignment for the next generation. This function handles both
+    /// JoinGroup and SyncGroup, delegating to `GroupProtocolHandler::perform_assignment` if
+    /// elected leader by the coordinator.
+    ///
+    /// Returns a request future which wraps the assignment returned from the group leader
+    fn send_join_group_request(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+    ) -> Arc<RequestFuture<Bytes>> {
+        let coordinator = match self.check_and_get_coordinator() {
+            Some(coordinator) => coordinator,
+            None => return RequestFuture::coordinator_not_available(),
+        };
+        let protocols = match handler.metadata() {
+            Ok(protocols) => protocols,
+            Err(error) => return RequestFuture::failure(error),
+        };
+
+        // send a join group request to the coordinator
+        self.log.info(format_args!("(Re-)joining group"));
+        let request = JoinGroupRequest::new(
+            self.rebalance_config.group_id.clone(),
+            self.rebalance_config.session_timeout_ms as i32,
+            self.rebalance_config.rebalance_timeout_ms as i32,
+            self.member_id(),
+            self.rebalance_config.group_instance_id.clone(),
+            self.protocol_type.clone(),
+            protocols,
+        );
+
+        self.log.debug(format_args!(
+            "Sending JoinGroup ({}) to coordinator {}",
+            request, coordinator
+        ));
+
+        // Note that we override the request timeout using the rebalance timeout since that is the
+        // maximum time that it may block on the coordinator. We add an extra 5 seconds for small delays.
+        let join_group_timeout_ms = self
+            .client
+            .default_request_timeout_ms()
+            .max(self.rebalance_config.rebalance_timeout_ms + JOIN_GROUP_TIMEOUT_LAPSE);
+        self.send_coordinator_request(
+            &coordinator,
+            AbstractRequest::JoinGroup(request),
+            join_group_timeout_ms,
+            |coordinator, sent_generation, response, future| match response.response_body {
+                Some(AbstractResponse::JoinGroup(join_response)) => {
+                    coordinator.handle_join_group_response(&sent_generation, join_response, future)
+                }
+                _ => future.raise(unexpected_response("JoinGroup", &response)),
+            },
+        )
+    }
+
+    fn handle_join_group_response(
+        self: &Arc<Self>,
+        sent_generation: &Generation,
+        join_response: JoinGroupResponse,
+        future: &Arc<RequestFuture<Bytes>>,
+    ) {
+        let error = join_response.error;
+        match error {
+            Errors::None => {
+                if self.is_protocol_type_inconsistent(join_response.protocol_type.as_deref()) {
+                    self.log.error(format_args!(
+                        "JoinGroup failed: Inconsistent Protocol Type, received {} but expected {}",
+                        join_response.protocol_type.as_deref().unwrap_or("null"),
+                        self.protocol_type
+                    ));
+                    future.raise(exception(Errors::InconsistentGroupProtocol));
+                    return;
+                }
+                self.log.debug(format_args!(
+                    "Received successful JoinGroup response: {:?}",
+                    join_response
+                ));
+
+                let mut state = self.lock();
+                if state.state != MemberState::PreparingRebalance {
+                    drop(state);
+                    // if the consumer was woken up before a rebalance completes, we may have already left
+                    // the group. In this case, we do not want to continue with the sync group.
+                    future.raise(KafkaError::UnjoinedGroup(
+                        "Not currently part of an active group".into(),
+                    ));
+                    return;
+                }
+                state.state = MemberState::CompletingRebalance;
+
+                // we only need to enable heartbeat thread whenever we transit to
+                // COMPLETING_REBALANCE state since we always transit from this state to STABLE
+                self.enable_heartbeat_thread(&mut state);
+
+                state.generation = Generation::new(
+                    join_response.generation_id,
+                    join_response.member_id.clone(),
+                    join_response.protocol_name.clone(),
+                );
+
+                self.log.info(format_args!(
+                    "Successfully joined group with generation {}",
+                    state.generation
+                ));
+
+                if join_response.is_leader() {
+                    // the assignment is performed by the thread waiting for the join future
+                    state.pending_leader_join_response = Some(join_response);
+                    self.leader_assignment_pending.store(true, Ordering::SeqCst);
+                    drop(state);
+                    self.client.client().wakeup();
+                } else {
+                    drop(state);
+                    self.on_join_follower().chain(future.clone());
+                }
+            }
+            Errors::CoordinatorLoadInProgress => {
+                self.log.info(format_args!(
+                    "JoinGroup failed: Coordinator {} is loading the group.",
+                    display_coordinator(&self.coordinator())
+                ));
+                // backoff and retry
+                future.raise(exception(error));
+            }
+            Errors::UnknownMemberId => {
+                self.log.info(format_args!(
+                    "JoinGroup failed: {} Need to re-join the group. Sent generation was {}",
+                    error.message(),
+                    sent_generation
+                ));
+                // only need to reset the member id if generation has not been changed,
+                // then retry immediately
+                if self.generation_unchanged(sent_generation) {
+                    self.reset_generation_on_response_error(ApiKeys::JoinGroup, error);
+                }
+                future.raise(exception(error));
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                // re-discover the coordinator and retry with backoff
+                self.mark_coordinator_unknown_for_error(error);
+                self.log.info(format_args!(
+                    "JoinGroup failed: {} Marking coordinator unknown. Sent generation was {}",
+                    error.message(),
+                    sent_generation
+                ));
+                future.raise(exception(error));
+            }
+            Errors::FencedInstanceId => {
+                // for join-group request, even if the generation has changed we would not expect the instance id
+                // gets fenced, and hence we always treat this as a fatal error
+                self.log.error(format_args!(
+                    "JoinGroup failed: The group instance id {} has been fenced by another instance. Sent generation was {}",
+                    self.rebalance_config.group_instance_id.as_deref().unwrap_or(""),
+                    sent_generation
+                ));
+                future.raise(exception(error));
+            }
+            Errors::InconsistentGroupProtocol
+            | Errors::InvalidSessionTimeout
+            | Errors::InvalidGroupId
+            | Errors::GroupAuthorizationFailed
+            | Errors::GroupMaxSizeReached => {
+                // log the error and re-throw the exception
+                self.log.error(format_args!(
+                    "JoinGroup failed due to fatal error: {}",
+                    error.message()
+                ));
+                if error == Errors::GroupMaxSizeReached {
+                    future.raise(KafkaError::GroupMaxSizeReached(format!(
+                        "Consumer group {} already has the configured maximum number of members.",
+                        self.rebalance_config.group_id
+                    )));
+                } else if error == Errors::GroupAuthorizationFailed {
+                    future.raise(group_authorization_exception(
+                        &self.rebalance_config.group_id,
+                    ));
+                } else {
+                    future.raise(exception(error));
+                }
+            }
+            Errors::UnsupportedVersion => {
+                self.log.error(format_args!("JoinGroup failed due to unsupported version error. Please unset field group.instance.id and retry to see if the problem resolves"));
+                future.raise(exception(error));
+            }
+            Errors::MemberIdRequired => {
+                // Broker requires a concrete member id to be allowed to join the group. Update member id
+                // and send another join group request in next cycle.
+                let member_id = join_response.member_id;
+                self.log.debug(format_args!(
+                    "JoinGroup failed due to non-fatal error: {} Will set the member id as {} and then rejoin. Sent generation was {}",
+                    error, member_id, sent_generation
+                ));
+                self.lock().generation = Generation::new(UNKNOWN_GENERATION_ID, member_id, None);
+                self.request_rejoin("need to re-join with the given member-id");
+
+                future.raise(exception(error));
+            }
+            Errors::RebalanceInProgress => {
+                self.log.info(format_args!("JoinGroup failed due to non-fatal error: REBALANCE_IN_PROGRESS, which could indicate a replication timeout on the broker. Will retry."));
+                future.raise(exception(error));
+            }
+            _ => {
+                // unexpected error, throw the exception
+                self.log.error(format_args!(
+                    "JoinGroup failed due to unexpected error: {}",
+                    error.message()
+                ));
+                future.raise(KafkaError::Kafka(format!(
+                    "Unexpected error in join group response: {}",
+                    error.message()
+                )));
+            }
+        }
+    }
+
+    fn on_join_follower(self: &Arc<Self>) -> Arc<RequestFuture<Bytes>> {
+        // send follower's sync group with an empty assignment
+        let request = self.sync_group_request(vec![]);
+        self.log.debug(format_args!(
+            "Sending follower SyncGroup to coordinator {} at generation {}: {}",
+            display_coordinator(&self.coordinator()),
+            self.generation(),
+            request
+        ));
+        self.send_sync_group_request(request)
+    }
+
+    fn on_join_leader(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+        join_response: JoinGroupResponse,
+    ) -> Arc<RequestFuture<Bytes>> {
+        // perform the leader synchronization and send back the assignment for the group
+        let group_assignment = match handler.perform_assignment(
+            &join_response.leader,
+            join_response.protocol_name.as_deref().unwrap_or_default(),
+            &join_response.members,
+        ) {
+            Ok(group_assignment) => group_assignment,
+            Err(error) => return RequestFuture::failure(error),
+        };
+
+        let assignments = group_assignment
+            .into_iter()
+            .map(|(member_id, assignment)| SyncGroupRequestAssignment::new(member_id, assignment))
+            .collect();
+        let request = self.sync_group_request(assignments);
+        self.log.debug(format_args!(
+            "Sending leader SyncGroup to coordinator {} at generation {}: {}",
+            display_coordinator(&self.coordinator()),
+            self.generation(),
+            request
+        ));
+        self.send_sync_group_request(request)
+    }
+
+    fn sync_group_request(&self, assignments: Vec<SyncGroupRequestAssignment>) -> SyncGroupRequest {
+        let generation = self.generation();
+        SyncGroupRequest::new(
+            self.rebalance_config.group_id.clone(),
+            generation.generation_id,
+            generation.member_id,
+            self.rebalance_config.group_instance_id.clone(),
+            Some(self.protocol_type.clone()),
+            generation.protocol_name,
+            assignments,
+        )
+    }
+
+    fn send_sync_group_request(
+        self: &Arc<Self>,
+        request: SyncGroupRequest,
+    ) -> Arc<RequestFuture<Bytes>> {
+        let coordinator = match self.check_and_get_coordinator() {
+            Some(coordinator) => coordinator,
+            None => return RequestFuture::coordinator_not_available(),
+        };
+        self.send_coordinator_request(
+            &coordinator,
+            AbstractRequest::SyncGroup(request),
+            self.client.default_request_timeout_ms(),
+            |coordinator, sent_generation, response, future| match response.response_body {
+                Some(AbstractResponse::SyncGroup(sync_response)) => {
+                    coordinator.handle_sync_group_response(&sent_generation, sync_response, future)
+                }
+                _ => future.raise(unexpected_response("SyncGroup", &response)),
+            },
+        )
+    }
+
+    fn handle_sync_group_response(
+        &self,
+        sent_generation: &Generation,
+        sync_response: crate::common::requests::sync_group_response::SyncGroupResponse,
+        future: &Arc<RequestFuture<Bytes>>,
+    ) {
+        let error = sync_response.error;
+        match error {
+            Errors::None => {
+                if self.is_protocol_type_inconsistent(sync_response.protocol_type.as_deref()) {
+                    self.log.error(format_args!(
+                        "SyncGroup failed due to inconsistent Protocol Type, received {} but expected {}",
+                        sync_response.protocol_type.as_deref().unwrap_or("null"),
+                        self.protocol_type
+                    ));
+                    future.raise(exception(Errors::InconsistentGroupProtocol));
+                    return;
+                }
+                self.log.debug(format_args!(
+                    "Received successful SyncGroup response: {:?}",
+                    sync_response
+                ));
+
+                let result = {
+                    let mut state = self.lock();
+                    if state.generation != Generation::no_generation()
+                        && state.state == MemberState::CompletingRebalance
+                    {
+                        // check protocol name only if the generation is not reset
+                        let protocol_name_inconsistent = sync_response.protocol_name.is_some()
+                            && sync_response.protocol_name != state.generation.protocol_name;
+
+                        if protocol_name_inconsistent {
+                            self.log.error(format_args!(
+                                "SyncGroup failed due to inconsistent Protocol Name, received {} but expected {}",
+                                sync_response.protocol_name.as_deref().unwrap_or("null"),
+                                state.generation.protocol_name.as_deref().unwrap_or("null")
+                            ));
+                            Err(exception(Errors::InconsistentGroupProtocol))
+                        } else {
+                            self.log.info(format_args!(
+                                "Successfully synced group in generation {}",
+                                state.generation
+                            ));
+                            state.state = MemberState::Stable;
+                            state.rejoin_needed = false;
+                            // record rebalance latency
+                            state.last_rebalance_end_ms = Some(self.time.milliseconds());
+                            state.last_rebalance_start_ms = None;
+                            Ok(sync_response.assignment)
+                        }
+                    } else {
+                        self.log.info(format_args!(
+                            "Generation data was cleared by heartbeat thread to {} and state is now {} before receiving SyncGroup response, marking this rebalance as failed and retry",
+                            state.generation, state.state
+                        ));
+                        // use ILLEGAL_GENERATION error code to let it retry immediately
+                        Err(exception(Errors::IllegalGeneration))
+                    }
+                };
+                match result {
+                    Ok(assignment) => future.complete(assignment),
+                    Err(error) => future.raise(error),
+                }
+            }
+            Errors::GroupAuthorizationFailed => {
+                future.raise(group_authorization_exception(
+                    &self.rebalance_config.group_id,
+                ));
+            }
+            Errors::RebalanceInProgress => {
+                self.log.info(format_args!(
+                    "SyncGroup failed: The group began another rebalance. Need to re-join the group. Sent generation was {}",
+                    sent_generation
+                ));
+                future.raise(exception(error));
+            }
+            Errors::FencedInstanceId => {
+                // for sync-group request, even if the generation has changed we would not expect the instance id
+                // gets fenced, and hence we always treat this as a fatal error
+                self.log.error(format_args!(
+                    "SyncGroup failed: The group instance id {} has been fenced by another instance. Sent generation was {}",
+                    self.rebalance_config.group_instance_id.as_deref().unwrap_or(""),
+                    sent_generation
+                ));
+                future.raise(exception(error));
+            }
+            Errors::UnknownMemberId | Errors::IllegalGeneration => {
+                self.log.info(format_args!(
+                    "SyncGroup failed: {} Need to re-join the group. Sent generation was {}",
+                    error.message(),
+                    sent_generation
+                ));
+                if self.generation_unchanged(sent_generation) {
+                    self.reset_generation_on_response_error(ApiKeys::SyncGroup, error);
+                }
+                future.raise(exception(error));
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                self.log.info(format_args!(
+                    "SyncGroup failed: {} Marking coordinator unknown. Sent generation was {}",
+                    error.message(),
+                    sent_generation
+                ));
+                self.mark_coordinator_unknown_for_error(error);
+                future.raise(exception(error));
+            }
+            _ => {
+                future.raise(KafkaError::Kafka(format!(
+                    "Unexpected error from SyncGroup: {}",
+                    error.message()
+                )));
+            }
+        }
+    }
+
+    /// Discover the current coordinator for the group. Sends a FindCoordinator request to
+    /// one of the brokers. The returned future should be polled to get the result of the request.
+    fn send_find_coordinator_request(self: &Arc<Self>, node: &Node) -> Arc<RequestFuture<()>> {
+        // initiate the group metadata request
+        self.log.debug(format_args!(
+            "Sending FindCoordinator request to broker {}",
+            node
+        ));
+        let request = FindCoordinatorRequest::new(
+            self.rebalance_config.group_id.clone(),
+            CoordinatorType::Group,
+        );
+        let future = RequestFuture::new();
+        let result = future.clone();
+        let coordinator = self.clone();
+        self.client
+            .send(node, AbstractRequest::FindCoordinator(request))
+            .add_listener(move |response| match response {
+                Ok(response) => coordinator.handle_find_coordinator_response(response, &result),
+                Err(error) => {
+                    coordinator.log.debug(format_args!(
+                        "FindCoordinator request failed due to {}",
+                        error
+                    ));
+
+                    if !error.is_retriable() {
+                        // Remember the exception if fatal so we can ensure it gets thrown by the main thread
+                        coordinator.lock().fatal_find_coordinator_exception = Some(error.clone());
+                    }
+                    result.raise(error.clone());
+                }
+            });
+        future
+    }
+
+    fn handle_find_coordinator_response(
+        &self,
+        response: &ClientResponse,
+        future: &Arc<RequestFuture<()>>,
+    ) {
+        self.log.debug(format_args!(
+            "Received FindCoordinator response {:?}",
+            response
+        ));
+
+        let find_coordinator_response = match &response.response_body {
+            Some(AbstractResponse::FindCoordinator(find_coordinator_response)) => {
+                find_coordinator_response
+            }
+            _ => {
+                future.raise(unexpected_response("FindCoordinator", response));
+                return;
+            }
+        };
+        let error = find_coordinator_response.error;
+        if error == Errors::None {
+            let node = &find_coordinator_response.node;
+            // use MAX_VALUE - node.id as the coordinator id to allow separate connections
+            // for the coordinator in the underlying network client layer
+            let coordinator = Node::new(i32::MAX - node.id, node.host.clone(), node.port);
+            {
+                let mut state = self.lock();
+                self.log
+                    .info(format_args!("Discovered group coordinator {}", coordinator));
+                state.coordinator = Some(coordinator.clone());
+                state.heartbeat.reset_session_timeout();
+            }
+            self.client.try_connect(&coordinator);
+            future.complete(());
+        } else if error == Errors::GroupAuthorizationFailed {
+            future.raise(group_authorization_exception(
+                &self.rebalance_config.group_id,
+            ));
+        } else {
+            self.log.debug(format_args!(
+                "Group coordinator lookup failed: {}",
+                find_coordinator_response
+                    .error_message
+                    .as_deref()
+                    .unwrap_or_else(|| error.message())
+            ));
+            future.raise(exception(error));
+        }
+    }
+
+    /// Check if we know who the coordinator is and we have an active connection
+    pub fn coordinator_unknown(&self) -> bool {
+        self.check_and_get_coordinator().is_none()
+    }
+
+    /// Get the coordinator if its connection is still active. Otherwise mark it unknown and
+    /// return `None`.
+    pub fn check_and_get_coordinator(&self) -> Option<Node> {
+        let coordinator = self.coordinator();
+        if let Some(node) = &coordinator {
+            if self.client.is_unavailable(node) {
+                self.mark_coordinator_unknown(true, "coordinator unavailable");
+                return None;
+            }
+        }
+        coordinator
+    }
+
+    fn coordinator(&self) -> Option<Node> {
+        self.lock().coordinator.clone()
+    }
+
+    pub fn mark_coordinator_unknown_for_error(&self, error: Errors) {
+        self.mark_coordinator_unknown(false, &format!("error response {}", error));
+    }
+
+    pub fn mark_coordinator_unknown(&self, is_disconnected: bool, cause: &str) {
+        let mut state = self.lock();
+        let now = self.time.milliseconds();
+        if let Some(old_coordinator) = state.coordinator.take() {
+            self.log.info(format_args!(
+                "Group coordinator {} is unavailable or invalid due to cause: {}. isDisconnected: {}. Rediscovery will be attempted.",
+                old_coordinator, cause, is_disconnected
+            ));
+
+            // Disconnect from the coordinator to ensure that there are no in-flight requests remaining.
+            // Pending callbacks will be invoked with a disconnection error on the next call to poll.
+            if !is_disconnected {
+                self.client.disconnect_async(&old_coordinator);
+            }
+
+            state.last_time_of_connection_ms = Some(now);
+        } else if let Some(last_time_of_connection_ms) = state.last_time_of_connection_ms {
+            let duration_of_ongoing_disconnect = now.saturating_sub(last_time_of_connection_ms);
+            if duration_of_ongoing_disconnect > self.rebalance_config.rebalance_timeout_ms {
+                self.log.warn(format_args!(
+                    "Consumer has been disconnected from the group coordinator for {}ms",
+                    duration_of_ongoing_disconnect
+                ));
+            }
+        }
+    }
+
+    /// Get the current generation state, regardless of whether it is currently stable.
+    /// Note that the generation information can be updated while we are still in the middle
+    /// of a rebalance, after the join-group response is received.
+    pub fn generation(&self) -> Generation {
+        self.lock().generation.clone()
+    }
+
+    /// Get the current generation state if the group is stable, otherwise return `None`
+    pub fn generation_if_stable(&self) -> Option<Generation> {
+        let state = self.lock();
+        if state.state != MemberState::Stable {
+            return None;
+        }
+        Some(state.generation.clone())
+    }
+
+    pub fn rebalance_in_progress(&self) -> bool {
+        matches!(
+            self.lock().state,
+            MemberState::PreparingRebalance | MemberState::CompletingRebalance
+        )
+    }
+
+    pub fn member_id(&self) -> String {
+        self.lock().generation.member_id.clone()
+    }
+
+    pub fn state(&self) -> MemberState {
+        self.lock().state
+    }
+
+    /// Time of the last completed rebalance, `None` if the member hasn't completed a rebalance yet.
+    pub fn last_rebalance_end_ms(&self) -> Option<u128> {
+        self.lock().last_rebalance_end_ms
+    }
+
+    fn generation_unchanged(&self, sent_generation: &Generation) -> bool {
+        self.lock().generation == *sent_generation
+    }
+
+    fn reset_state_and_generation(&self, state: &mut CoordinatorState, reason: &str) {
+        self.log
+            .info(format_args!("Resetting generation due to: {}", reason));
+
+        state.state = MemberState::Unjoined;
+        state.generation = Generation::no_generation();
+    }
+
+    fn reset_state_and_rejoin(&self, state: &mut CoordinatorState, reason: &str) {
+        self.reset_state_and_generation(state, reason);
+        self.log
+            .info(format_args!("Request joining group due to: {}", reason));
+        state.rejoin_needed = true;
+        state.needs_join_prepare = true;
+    }
+
+    pub fn reset_generation_on_response_error(&self, api: ApiKeys, error: Errors) {
+        let reason = format!("encountered {} from {} response", error, api);
+        self.reset_state_and_rejoin(&mut self.lock(), &reason);
+    }
+
+    pub fn reset_generation_on_leave_group(&self) {
+        self.reset_state_and_rejoin(&mut self.lock(), "consumer pro-actively leaving the group");
+    }
+
+    pub fn request_rejoin(&self, reason: &str) {
+        self.log
+            .info(format_args!("Request joining group due to: {}", reason));
+        self.lock().rejoin_needed = true;
+    }
+
+    fn is_protocol_type_inconsistent(&self, protocol_type: Option<&str>) -> bool {
+        matches!(protocol_type, Some(protocol_type) if protocol_type != self.protocol_type)
+    }
+
+    /// Close the coordinator, waiting up to `timer` for the LeaveGroup request and other requests
+    /// pending to the coordinator.
+    pub fn close(
+        self: &Arc<Self>,
+        handler: &dyn GroupProtocolHandler,
+        timer: &mut Timer,
+    ) -> Result<()> {
+        self.close_heartbeat_thread();
+
+        if self.rebalance_config.leave_group_on_close {
+            handler.on_leave_prepare()?;
+            self.maybe_leave_group("the consumer is being closed")?;
+        }
+
+        // At this point, there may be pending commits (async commits or sync commits that were
+        // interrupted using wakeup) and the leave group request which have been queued, but not
+        // yet sent to the broker. Wait up to close timeout for these pending requests to be processed.
+        // If coordinator is not known, requests are aborted.
+        if let Some(coordinator) = self.check_and_get_coordinator() {
+            if !self.client.await_pending_requests(&coordinator, timer)? {
+                self.log.warn(format_args!(
+                    "Close timed out with {} pending requests to coordinator, terminating client connections",
+                    self.client.pending_request_count_for(&coordinator)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends LeaveGroupRequest and logs the `leave_reason`, unless this member is using static membership or is already
+    /// not part of the group (ie does not have a valid member id, is in the UNJOINED state, or the coordinator is unknown).
+    pub fn maybe_leave_group(
+        self: &Arc<Self>,
+        leave_reason: &str,
+    ) -> Result<Option<Arc<RequestFuture<()>>>> {
+        let mut future = None;
+
+        // Starting from 2.3, only dynamic members will send LeaveGroupRequest to the broker,
+        // consumer with valid group.instance.id is viewed as static member that never sends LeaveGroup,
+        // and the membership expiration is only controlled by session timeout.
+        let (generation, state) = {
+            let state = self.lock();
+            (state.generation.clone(), state.state)
+        };
+        if self.is_dynamic_member() && state != MemberState::Unjoined && generation.has_member_id()
+        {
+            if let Some(coordinator) = self.check_and_get_coordinator() {
+                // this is a minimal effort attempt to leave the group. we do not
+                // attempt any resending if the request fails or times out.
+                self.log.info(format_args!(
+                    "Member {} sending LeaveGroup request to coordinator {} due to {}",
+                    generation.member_id, coordinator, leave_reason
+                ));
+                let request = LeaveGroupRequest::new(
+                    self.rebalance_config.group_id.clone(),
+                    vec![MemberIdentity::new(generation.member_id, None)],
+                );
+
+                future = Some(self.send_coordinator_request(
+                    &coordinator,
+                    AbstractRequest::LeaveGroup(request),
+                    self.client.default_request_timeout_ms(),
+                    |coordinator, sent_generation, response, future| match response.response_body {
+                        Some(AbstractResponse::LeaveGroup(leave_response)) => {
+                            if leave_response.members.len() > 1 {
+                                future.raise(KafkaError::IllegalState(format!(
+                                    "The expected leave group response should only contain no more than one member info, however get {:?}",
+                                    leave_response.members
+                                )));
+                                return;
+                            }
+
+                            let error = leave_response.error;
+                            if error == Errors::None {
+                                coordinator.log.debug(format_args!(
+                                    "LeaveGroup response with {} returned successfully: {:?}",
+                                    sent_generation, leave_response
+                                ));
+                                future.complete(());
+                            } else {
+                                coordinator.log.error(format_args!(
+                                    "LeaveGroup request with {} failed with error: {}",
+                                    sent_generation,
+                                    error.message()
+                                ));
+                                future.raise(exception(error));
+                            }
+                        }
+                        _ => future.raise(unexpected_response("LeaveGroup", &response)),
+                    },
+                ));
+                self.client.poll_no_wakeup()?;
+            }
+        }
+
+        self.reset_generation_on_leave_group();
+
+        Ok(future)
+    }
+
+    pub fn is_dynamic_member(&self) -> bool {
+        self.rebalance_config.group_instance_id.is_none()
+    }
+
+    pub fn send_heartbeat_request(self: &Arc<Self>) -> Arc<RequestFuture<()>> {
+        let (generation, coordinator) = {
+            let state = self.lock();
+            (state.generation.clone(), state.coordinator.clone())
+        };
+        let coordinator = match coordinator {
+            Some(coordinator) => coordinator,
+            None => return RequestFuture::coordinator_not_available(),
+        };
+        self.log.debug(format_args!(
+            "Sending Heartbeat request with generation {} and member id {} to coordinator {}",
+            generation.generation_id, generation.member_id, coordinator
+        ));
+        let request = HeartbeatRequest::new(
+            self.rebalance_config.group_id.clone(),
+            generation.generation_id,
+            generation.member_id,
+            self.rebalance_config.group_instance_id.clone(),
+        );
+        self.send_coordinator_request(
+            &coordinator,
+            AbstractRequest::Heartbeat(request),
+            self.client.default_request_timeout_ms(),
+            |coordinator, sent_generation, response, future| match response.response_body {
+                Some(AbstractResponse::Heartbeat(heartbeat_response)) => coordinator
+                    .handle_heartbeat_response(&sent_generation, heartbeat_response.error, future),
+                _ => future.raise(unexpected_response("Heartbeat", &response)),
+            },
+        )
+    }
+
+    fn handle_heartbeat_response(
+        &self,
+        sent_generation: &Generation,
+        error: Errors,
+        future: &Arc<RequestFuture<()>>,
+    ) {
+        match error {
+            Errors::None => {
+                self.log
+                    .debug(format_args!("Received successful Heartbeat response"));
+                future.complete(());
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                self.log.info(format_args!(
+                    "Attempt to heartbeat failed since coordinator {} is either not started or not valid",
+                    display_coordinator(&self.coordinator())
+                ));
+                self.mark_coordinator_unknown_for_error(error);
+                future.raise(exception(error));
+            }
+            Errors::RebalanceInProgress => {
+                // since we may be sending the request during rebalance, we should check
+                // this case and ignore the REBALANCE_IN_PROGRESS error
+                let state = self.state();
+                if state == MemberState::Stable {
+                    self.request_rejoin("group is already rebalancing");
+                    future.raise(exception(error));
+                } else {
+                    self.log.debug(format_args!(
+                        "Ignoring heartbeat response with error {} during {} state",
+                        error, state
+                    ));
+                    future.complete(());
+                }
+            }
+            Errors::IllegalGeneration | Errors::UnknownMemberId | Errors::FencedInstanceId => {
+                if self.generation_unchanged(sent_generation) {
+                    self.log.info(format_args!(
+                        "Attempt to heartbeat with {} and group instance id {} failed due to {}, resetting generation",
+                        sent_generation,
+                        self.rebalance_config.group_instance_id.as_deref().unwrap_or(""),
+                        error
+                    ));
+                    self.reset_generation_on_response_error(ApiKeys::Heartbeat, error);
+                    future.raise(exception(error));
+                } else {
+                    // if the generation has changed, then ignore this error
+                    self.log.info(format_args!(
+                        "Attempt to heartbeat with stale {} and group instance id {} failed due to {}, ignoring the error",
+                        sent_generation,
+                        self.rebalance_config.group_instance_id.as_deref().unwrap_or(""),
+                        error
+                    ));
+                    future.complete(());
+                }
+            }
+            Errors::GroupAuthorizationFailed => {
+                future.raise(group_authorization_exception(
+                    &self.rebalance_config.group_id,
+                ));
+            }
+            _ => {
+                future.raise(KafkaError::Kafka(format!(
+                    "Unexpected error in heartbeat response: {}",
+                    error.message()
+                )));
+            }
+        }
+    }
+
+    /// Send a request to the coordinator, marking the coordinator unknown if it disconnects.
+    /// Successful responses are passed to `handler` along with the generation at the time of sending
+    /// and the future to complete.
+    pub fn send_coordinator_request<T, F>(
+        self: &Arc<Self>,
+        coordinator: &Node,
+        request: AbstractRequest,
+        request_timeout_ms: u128,
+        handler: F,
+    ) -> Arc<RequestFuture<T>>
+    where
+        T: Clone + Send + 'static,
+        F: FnOnce(&Arc<Self>, Generation, ClientResponse, &Arc<RequestFuture<T>>) + Send + 'static,
+    {
+        let sent_generation = self.generation();
+        let future = RequestFuture::new();
+        let result = future.clone();
+        let coordinator_handle = self.clone();
+        self.client
+            .send_with_timeout(coordinator, request, request_timeout_ms)
+            .add_listener(move |response| match response {
+                Ok(response) => handler(
+                    &coordinator_handle,
+                    sent_generation,
+                    response.clone(),
+                    &result,
+                ),
+                Err(error) => {
+                    // mark the coordinator as dead
+                    if let KafkaError::Network(message) = error {
+                        coordinator_handle.mark_coordinator_unknown(true, message);
+                    }
+                    result.raise(error.clone());
+                }
+            });
+        future
+    }
+
+    /// A single iteration of the heartbeat thread loop. Returns false once the thread should exit.
+    fn run_heartbeat_once(self: &Arc<Self>, epoch: u64) -> Result<bool> {
+        {
+            let mut state = self.lock();
+            if state.heartbeat_thread_closed || state.heartbeat_thread_epoch != epoch {
+                return Ok(false);
+            }
+
+            if !state.heartbeat_thread_enabled {
+                self.wait(state, self.rebalance_config.retry_backoff_ms);
+                return Ok(true);
+            }
+
+            // we do not need to heartbeat we are not part of a group yet;
+            // also if we already have fatal error, the client will be
+            // crashed soon, hence we do not need to continue heartbeating either
+            if state.state.has_not_joined_group() || state.heartbeat_thread_failed.is_some() {
+                self.log.debug(format_args!("Disabling heartbeat thread"));
+                state.heartbeat_thread_enabled = false;
+                return Ok(true);
+            }
+        }
+
+        self.client.poll_no_wakeup()?;
+        let now = self.time.milliseconds();
+
+        if self.coordinator_unknown() {
+            let mut state = self.lock();
+            if state.find_coordinator_future.take().is_some() {
+                // clear the future so that after the backoff, if the hb still sees coordinator unknown in
+                // the next iteration it will try to re-discover the coordinator in case the main thread cannot
+
+                // backoff properly
+                self.wait(state, self.rebalance_config.retry_backoff_ms);
+            } else {
+                drop(state);
+                self.lookup_coordinator();
+            }
+            return Ok(true);
+        }
+
+        let mut state = self.lock();
+        if state.heartbeat.session_timeout_expired(now) {
+            drop(state);
+            // the session timeout has expired without seeing a successful heartbeat, so we should
+            // probably make sure the coordinator is still healthy.
+            self.mark_coordinator_unknown(
+                false,
+                "session timed out without receiving a heartbeat response",
+            );
+        } else if state.heartbeat.poll_timeout_expired(now) {
+            drop(state);
+            // the poll timeout has expired, which means that the foreground thread has stalled
+            // in between calls to poll().
+            self.log.warn(format_args!("consumer poll timeout has expired. This means the time between subsequent calls to poll() was longer than the configured max.poll.interval.ms, which typically implies that the poll loop is spending too much time processing messages. You can address this either by increasing max.poll.interval.ms or by reducing the maximum size of batches returned in poll() with max.poll.records."));
+
+            self.maybe_leave_group("consumer poll timeout has expired.")?;
+        } else if !state.heartbeat.should_heartbeat(now) {
+            // poll again after waiting for the retry backoff in case the heartbeat failed or the
+            // coordinator disconnected
+            self.wait(state, self.rebalance_config.retry_backoff_ms);
+        } else {
+            state.heartbeat.sent_heartbeat(now);
+            drop(state);
+            let coordinator = self.clone();
+            self.send_heartbeat_request().add_listener(move |result| {
+                let mut state = coordinator.lock();
+                match result {
+                    Ok(()) => state.heartbeat.receive_heartbeat(),
+                    // it is valid to continue heartbeating while the group is rebalancing. This
+                    // ensures that the coordinator keeps the member in the group for as long
+                    // as the duration of the rebalance timeout. If we stop sending heartbeats,
+                    // however, then the session timeout may expire before we can rejoin.
+                    Err(KafkaError::RebalanceInProgress(_)) => state.heartbeat.receive_heartbeat(),
+                    Err(error @ KafkaError::FencedInstanceId(_)) => {
+                        coordinator.log.error(format_args!(
+                            "Caught fenced group.instance.id {} error in heartbeat thread",
+                            coordinator
+                                .rebalance_config
+                                .group_instance_id
+                                .as_deref()
+                                .unwrap_or("")
+                        ));
+                        state.heartbeat_thread_failed = Some(error.clone());
+                    }
+                    Err(_) => {
+                        state.heartbeat.fail_heartbeat();
+                        // wake up the thread if it's sleeping to reschedule the heartbeat
+                        coordinator.heartbeat_notifier.notify_all();
+                    }
+                }
+            });
+        }
+        Ok(true)
+    }
+
+    fn wait(&self, state: MutexGuard<'_, CoordinatorState>, timeout_ms: u128) {
+        let _ = self
+            .heartbeat_notifier
+            .wait_timeout(state, Duration::from_millis(timeout_ms as u64))
+            .unwrap_or_else(|e| e.into_inner());
+    }
+}
+
+fn run_heartbeat_thread(coordinator: Weak<AbstractCoordinator>, epoch: u64, log: Logger) {
+    log.debug(format_args!("Heartbeat thread started"));
+    // The thread only holds a strong reference for the duration of a single iteration, so it
+    // exits on its own once the coordinator is dropped.
+    while let Some(coordinator) = coordinator.upgrade() {
+        match coordinator.run_heartbeat_once(epoch) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(failure) => {
+                match &failure {
+                    KafkaError::GroupAuthorization(_) => log.error(format_args!(
+                        "A group authorization error occurred in the heartbeat thread: {}",
+                        failure
+                    )),
+                    _ => log.error(format_args!(
+                        "Heartbeat thread failed due to unexpected error: {}",
+                        failure
+                    )),
+                }
+                coordinator.lock().heartbeat_thread_failed = Some(failure);
+                break;
+            }
+        }
+    }
+    log.debug(format_args!("Heartbeat thread has closed"));
+}
+
+pub(super) fn exception(error: Errors) -> KafkaError {
+    error
+        .exception(None)
+        .unwrap_or_else(|| KafkaError::UnknownServer(error.message().to_owned()))
+}
+
+pub(super) fn group_authorization_exception(group_id: &str) -> KafkaError {
+    KafkaError::GroupAuthorization(format!("Not authorized to access group: {}", group_id))
+}
+
+pub(super) fn unexpected_response(api: &str, response: &ClientResponse) -> KafkaError {
+    KafkaError::IllegalState(format!(
+        "Unexpected response to {} request from node {}: {:?}",
+        api, response.destination, response.response_body
+    ))
+}
+
+fn display_coordinator(coordinator: &Option<Node>) -> String {
+    coordinator
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_else(|| "null".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::{
+            group_rebalance_config::GroupRebalanceConfig, metadata::Metadata,
+            mock_client::MockClient,
+        },
+        common::{
+            errors::{KafkaError, Result},
+            node::Node,
+            protocol::errors::Errors,
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                find_coordinator_response::FindCoordinatorResponse,
+                heartbeat_response::HeartbeatResponse,
+                join_group_request::JoinGroupRequestProtocol,
+                join_group_response::{JoinGroupResponse, JoinGroupResponseMember},
+                sync_group_response::SyncGroupResponse,
+            },
+            utils::{log_context::LogContext, mock_time::MockTime, time::Time, timer::Timer},
+        },
+        test_utils::MockBroker,
+    };
+
+    use super::{
+        super::consumer_network_client::ConsumerNetworkClient, AbstractCoordinator, Generation,
+        GroupProtocolHandler, MemberState,
+    };
+
+    const GROUP_ID: &str = "dummy-group";
+    const PROTOCOL_TYPE: &str = "dummy";
+    const PROTOCOL_NAME: &str = "dummy-subprotocol";
+    const MEMBER_ID: &str = "member-id";
+    const LEADER_ID: &str = "leader-id";
+    const SESSION_TIMEOUT_MS: u128 = 10_000;
+    const REBALANCE_TIMEOUT_MS: u128 = 60_000;
+    const HEARTBEAT_INTERVAL_MS: u128 = 3_000;
+    const RETRY_BACKOFF_MS: u128 = 100;
+    const REQUEST_TIMEOUT_MS: u128 = 30_000;
+
+    /// Records the callbacks of a rebalance and hands every member the same assignment.
+    #[derive(Default)]
+    struct DummyHandler {
+        join_prepared: Mutex<Vec<(i32, String)>>,
+        assigned_members: Mutex<Vec<String>>,
+        join_completed: Mutex<Vec<(i32, Bytes)>>,
+    }
+
+    impl GroupProtocolHandler for DummyHandler {
+        fn metadata(&self) -> Result<Vec<JoinGroupRequestProtocol>> {
+            Ok(vec![JoinGroupRequestProtocol::new(
+                PROTOCOL_NAME,
+                Bytes::from_static(b"metadata"),
+            )])
+        }
+
+        fn on_join_prepare(&self, generation: i32, member_id: &str) -> Result<()> {
+            self.join_prepared
+                .lock()
+                .unwrap()
+                .push((generation, member_id.to_owned()));
+            Ok(())
+        }
+
+        fn perform_assignment(
+            &self,
+            _leader_id: &str,
+            _protocol: &str,
+            all_member_metadata: &[JoinGroupResponseMember],
+        ) -> Result<IndexMap<String, Bytes>> {
+            let members: Vec<String> = all_member_metadata
+                .iter()
+                .map(|member| member.member_id.clone())
+                .collect();
+            self.assigned_members
+                .lock()
+                .unwrap()
+                .extend(members.iter().cloned());
+            Ok(members
+                .into_iter()
+                .map(|member_id| (member_id, Bytes::from_static(b"assignment")))
+                .collect())
+        }
+
+        fn on_join_complete(
+            &self,
+            generation: i32,
+            _member_id: &str,
+            _protocol: Option<&str>,
+            member_assignment: Bytes,
+        ) -> Result<()> {
+            self.join_completed
+                .lock()
+                .unwrap()
+                .push((generation, member_assignment));
+            Ok(())
+        }
+    }
+
+    struct Context {
+        time: Arc<MockTime>,
+        broker: Node,
+        client: Arc<MockClient>,
+        coordinator: Arc<AbstractCoordinator>,
+        handler: DummyHandler,
+    }
+
+    impl Context {
+        fn new() -> Context {
+            let MockBroker {
+                time,
+                node: broker,
+                client,
+                ..
+            } = MockBroker::new();
+            let network_client = Arc::new(ConsumerNetworkClient::new(
+                client.clone(),
+                Arc::new(Metadata::new(RETRY_BACKOFF_MS, 300_000)),
+                time.clone(),
+                RETRY_BACKOFF_MS,
+                REQUEST_TIMEOUT_MS,
+                HEARTBEAT_INTERVAL_MS,
+            ));
+            let rebalance_config = GroupRebalanceConfig::new(
+                SESSION_TIMEOUT_MS,
+                REBALANCE_TIMEOUT_MS,
+                HEARTBEAT_INTERVAL_MS,
+                GROUP_ID,
+                None,
+                RETRY_BACKOFF_MS,
+                true,
+            )
+            .unwrap();
+            let coordinator = Arc::new(
+                AbstractCoordinator::new(
+                    rebalance_config,
+                    PROTOCOL_TYPE,
+                    time.clone(),
+                    network_client,
+                    &LogContext::default(),
+                )
+                .unwrap(),
+            );
+            Context {
+                time,
+                broker,
+                client,
+                coordinator,
+                handler: DummyHandler::default(),
+            }
+        }
+
+        fn timer(&self) -> Timer {
+            Timer::new(self.time.clone(), REQUEST_TIMEOUT_MS)
+        }
+
+        fn prepare_find_coordinator(&self) {
+            self.client
+                .prepare_response(AbstractResponse::FindCoordinator(
+                    FindCoordinatorResponse::new(Errors::None, self.broker.clone()),
+                ));
+        }
+
+        fn prepare_join_group(&self, generation_id: i32, leader: &str) {
+            let members = if leader == MEMBER_ID {
+                vec![
+                    JoinGroupResponseMember::new(MEMBER_ID, None, Bytes::new()),
+                    JoinGroupResponseMember::new("other-member", None, Bytes::new()),
+                ]
+            } else {
+                vec![]
+            };
+            self.client.prepare_response_matching(
+                |request| matches!(request, AbstractRequest::JoinGroup(_)),
+                AbstractResponse::JoinGroup(JoinGroupResponse::new(
+                    Errors::None,
+                    generation_id,
+                    Some(PROTOCOL_TYPE.to_owned()),
+                    Some(PROTOCOL_NAME.to_owned()),
+                    leader,
+                    MEMBER_ID,
+                    members,
+                )),
+            );
+        }
+
+        fn prepare_sync_group(&self, generation_id: i32, expected_assignments: usize) {
+            self.client.prepare_response_matching(
+                move |request| {
+                    matches!(request, AbstractRequest::SyncGroup(request)
+                        if request.generation_id == generation_id
+                            && request.member_id == MEMBER_ID
+                            && request.assignments.len() == expected_assignments)
+                },
+                AbstractResponse::SyncGroup(SyncGroupResponse::new(
+                    Errors::None,
+                    Some(PROTOCOL_TYPE.to_owned()),
+                    Some(PROTOCOL_NAME.to_owned()),
+                    Bytes::from_static(b"assignment"),
+                )),
+            );
+        }
+
+        fn prepare_heartbeat(&self, error: Errors) {
+            self.client.prepare_response_matching(
+                |request| matches!(request, AbstractRequest::Heartbeat(_)),
+                AbstractResponse::Heartbeat(HeartbeatResponse::new(error)),
+            );
+        }
+
+        fn join_group(&self, generation_id: i32) {
+            self.prepare_join_group(generation_id, LEADER_ID);
+            self.prepare_sync_group(generation_id, 0);
+            assert!(self
+                .coordinator
+                .join_group_if_needed(&self.handler, &mut self.timer())
+                .unwrap());
+            assert_eq!(self.coordinator.state(), MemberState::Stable);
+        }
+
+        fn ensure_coordinator_ready(&self) {
+            self.prepare_find_coordinator();
+            assert!(self
+                .coordinator
+                .ensure_coordinator_ready(&mut self.timer())
+                .unwrap());
+        }
+
+        /// Let `run_heartbeat_once` proceed as if the heartbeat thread had been started when
+        /// the group was joined.
+        fn enable_heartbeat_thread(&self) {
+            let mut state = self.coordinator.lock();
+            state.heartbeat_thread_enabled = true;
+            state.heartbeat.reset_timeouts();
+        }
+
+        fn heartbeat(&self, error: Errors) -> Option<KafkaError> {
+            self.prepare_heartbeat(error);
+            let future = self.coordinator.send_heartbeat_request();
+            assert!(self
+                .coordinator
+                .client()
+                .poll_future(&future, &mut self.timer())
+                .unwrap());
+            future.exception()
+        }
+    }
+
+    #[test]
+    fn coordinator_discovery() {
+        let ctx = Context::new();
+        assert!(ctx.coordinator.coordinator_unknown());
+        ctx.ensure_coordinator_ready();
+        assert_eq!(
+            ctx.coordinator.check_and_get_coordinator(),
+            Some(Node::new(i32::MAX - ctx.broker.id, "localhost", 9092))
+        );
+    }
+
+    #[test]
+    fn join_and_sync_as_follower() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+
+        assert_eq!(
+            ctx.coordinator.generation(),
+            Generation::new(1, MEMBER_ID, Some(PROTOCOL_NAME.to_owned()))
+        );
+        assert_eq!(
+            *ctx.handler.join_prepared.lock().unwrap(),
+            vec![(-1, String::new())]
+        );
+        assert!(ctx.handler.assigned_members.lock().unwrap().is_empty());
+        assert_eq!(
+            *ctx.handler.join_completed.lock().unwrap(),
+            vec![(1, Bytes::from_static(b"assignment"))]
+        );
+        assert!(!ctx.coordinator.rejoin_needed_or_pending());
+        assert!(ctx.coordinator.last_rebalance_end_ms().is_some());
+    }
+
+    #[test]
+    fn join_and_sync_as_leader() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.prepare_join_group(1, MEMBER_ID);
+        // the leader sends the assignment of every member
+        ctx.prepare_sync_group(1, 2);
+        assert!(ctx
+            .coordinator
+            .join_group_if_needed(&ctx.handler, &mut ctx.timer())
+            .unwrap());
+
+        assert_eq!(ctx.coordinator.state(), MemberState::Stable);
+        assert_eq!(
+            *ctx.handler.assigned_members.lock().unwrap(),
+            vec![MEMBER_ID.to_owned(), "other-member".to_owned()]
+        );
+        assert_eq!(ctx.handler.join_completed.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn successful_heartbeat_keeps_the_member_in_the_group() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+
+        assert!(ctx.heartbeat(Errors::None).is_none());
+        assert_eq!(ctx.coordinator.state(), MemberState::Stable);
+        assert!(!ctx.coordinator.rejoin_needed_or_pending());
+    }
+
+    #[test]
+    fn heartbeat_thread_sends_heartbeats_every_interval() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+        ctx.enable_heartbeat_thread();
+
+        ctx.time.sleep(HEARTBEAT_INTERVAL_MS);
+        ctx.prepare_heartbeat(Errors::None);
+        assert!(ctx.coordinator.run_heartbeat_once(0).unwrap());
+        assert!(ctx.coordinator.lock().heartbeat.has_inflight());
+
+        // the request goes out and its response is handled on the next poll, which starts
+        // the session timer over
+        ctx.coordinator.client().poll_no_wakeup().unwrap();
+        assert_eq!(ctx.client.future_response_count(), 0);
+        assert!(!ctx.coordinator.lock().heartbeat.has_inflight());
+        ctx.time.sleep(SESSION_TIMEOUT_MS - 1);
+        assert!(!ctx
+            .coordinator
+            .lock()
+            .heartbeat
+            .session_timeout_expired(ctx.time.milliseconds()));
+    }
+
+    #[test]
+    fn rebalance_in_progress_heartbeat_triggers_rejoin() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+
+        assert!(matches!(
+            ctx.heartbeat(Errors::RebalanceInProgress),
+            Some(KafkaError::RebalanceInProgress(_))
+        ));
+        // the member keeps its generation until it has rejoined
+        assert!(ctx.coordinator.rejoin_needed_or_pending());
+        assert_eq!(ctx.coordinator.generation().generation_id, 1);
+
+        ctx.join_group(2);
+        assert_eq!(ctx.coordinator.generation().generation_id, 2);
+        assert_eq!(
+            *ctx.handler.join_prepared.lock().unwrap(),
+            vec![(-1, String::new()), (1, MEMBER_ID.to_owned())]
+        );
+        assert_eq!(ctx.handler.join_completed.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn illegal_generation_heartbeat_resets_the_generation() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+
+        assert!(ctx.heartbeat(Errors::IllegalGeneration).is_some());
+        assert_eq!(ctx.coordinator.state(), MemberState::Unjoined);
+        assert_eq!(ctx.coordinator.generation(), Generation::no_generation());
+        assert!(ctx.coordinator.rejoin_needed_or_pending());
+    }
+
+    #[test]
+    fn session_timeout_marks_the_coordinator_unknown() {
+        let ctx = Context::new();
+        ctx.ensure_coordinator_ready();
+        ctx.join_group(1);
+        ctx.enable_heartbeat_thread();
+
+        // heartbeats are sent but never answered
+        ctx.time.sleep(HEARTBEAT_INTERVAL_MS);
+        assert!(ctx.coordinator.run_heartbeat_once(0).unwrap());
+        ctx.coordinator.client().poll_no_wakeup().unwrap();
+        assert_eq!(ctx.client.requests().len(), 1);
+        assert!(!ctx.coordinator.coordinator_unknown());
+
+        ctx.time.sleep(SESSION_TIMEOUT_MS - HEARTBEAT_INTERVAL_MS);
+        assert!(ctx.coordinator.run_heartbeat_once(0).unwrap());
+        assert!(ctx.coordinator.coordinator_unknown());
+    }
+}
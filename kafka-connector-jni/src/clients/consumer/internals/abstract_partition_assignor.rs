@@ -0,0 +1,109 @@
+use std::{cmp::Ordering, collections::HashSet};
+
+use indexmap::IndexMap;
+use log::debug;
+
+use crate::{
+    clients::consumer::consumer_partition_assignor::{
+        Assignment, GroupAssignment, GroupSubscription, Subscription,
+    },
+    common::{cluster::Cluster, errors::Result, topic_partition::TopicPartition},
+};
+
+/// Abstract assignor implementation which does some common grunt work (in particular collecting
+/// partition counts which are always needed in assignors).
+pub trait AbstractPartitionAssignor {
+    /// Perform the group assignment given the partition counts and member subscriptions
+    ///
+    /// * `partitions_per_topic` - The number of partitions for each subscribed topic. Topics not in metadata will be excluded
+    ///   from this map.
+    /// * `subscriptions` - Map from the member id to their respective topic subscription
+    ///
+    /// Returns map from each member to the list of partitions assigned to them.
+    fn assign_partitions(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<IndexMap<String, Vec<TopicPartition>>>;
+
+    /// Collects partition counts of all subscribed topics from `metadata` and delegates to
+    /// `assign_partitions`. Intended to back `ConsumerPartitionAssignor::assign`.
+    fn assign_group(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment> {
+        let subscriptions = &group_subscription.subscriptions;
+        let mut all_subscribed_topics = HashSet::new();
+        for subscription in subscriptions.values() {
+            all_subscribed_topics.extend(subscription.topics.iter().cloned());
+        }
+        let mut all_subscribed_topics: Vec<String> = all_subscribed_topics.into_iter().collect();
+        all_subscribed_topics.sort();
+
+        let mut partitions_per_topic = IndexMap::new();
+        for topic in all_subscribed_topics {
+            match metadata.partition_count_for_topic(&topic) {
+                Some(num_partitions) if num_partitions > 0 => {
+                    partitions_per_topic.insert(topic, num_partitions);
+                }
+                _ => debug!(
+                    "Skipping assignment for topic {} since no metadata is available",
+                    topic
+                ),
+            }
+        }
+
+        let raw_assignments = self.assign_partitions(&partitions_per_topic, subscriptions)?;
+
+        // this class maintains no user data, so just wrap the results
+        Ok(GroupAssignment::new(
+            raw_assignments
+                .into_iter()
+                .map(|(member_id, partitions)| (member_id, Assignment::from_partitions(partitions)))
+                .collect(),
+        ))
+    }
+}
+
+pub fn partitions(topic: &str, num_partitions: usize) -> Vec<TopicPartition> {
+    (0..num_partitions)
+        .map(|i| TopicPartition::new(topic, i as i32))
+        .collect()
+}
+
+/// Member identity used to order the members of a group. Static members (with a group instance id)
+/// come first, ordered by their instance id, followed by dynamic members ordered by member id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberInfo {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+impl MemberInfo {
+    pub fn new(member_id: impl Into<String>, group_instance_id: Option<String>) -> MemberInfo {
+        MemberInfo {
+            member_id: member_id.into(),
+            group_instance_id,
+        }
+    }
+}
+
+impl Ord for MemberInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.group_instance_id, &other.group_instance_id) {
+            (Some(instance_id), Some(other_instance_id)) => instance_id
+                .cmp(other_instance_id)
+                .then_with(|| self.member_id.cmp(&other.member_id)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => self.member_id.cmp(&other.member_id),
+        }
+    }
+}
+
+impl PartialOrd for MemberInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -0,0 +1,1217 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use indexmap::{IndexMap, IndexSet};
+use log::{debug, error, warn};
+
+use crate::{
+    clients::consumer::consumer_partition_assignor::Subscription,
+    common::{
+        errors::{KafkaError, Result},
+        topic_partition::TopicPartition,
+    },
+};
+
+pub const DEFAULT_GENERATION: i32 = -1;
+
+/// Partitions previously owned by a member together with the generation in which it owned them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberData {
+    pub partitions: Vec<TopicPartition>,
+    pub generation: Option<i32>,
+}
+
+impl MemberData {
+    pub fn new(partitions: Vec<TopicPartition>, generation: Option<i32>) -> MemberData {
+        MemberData {
+            partitions,
+            generation,
+        }
+    }
+}
+
+/// Result of a single sticky assignment.
+#[derive(Debug, Clone, Default)]
+pub struct StickyAssignment {
+    pub assignment: IndexMap<String, Vec<TopicPartition>>,
+    /// Partitions being migrated from one consumer to another during assignment, so the cooperative
+    /// assignor can adjust the assignment. `None` when the general assignment algorithm was used,
+    /// in which case it has to be computed from the owned partitions of each member.
+    pub partitions_transferring_ownership: Option<HashMap<TopicPartition, String>>,
+}
+
+/// Sticky assignment shared by the sticky and the cooperative sticky assignors. The assignment is
+/// kept as balanced as possible while preserving as many of the existing partition assignments as
+/// possible. Implementors only decide where the previously owned partitions of a member come from.
+pub trait AbstractStickyAssignor {
+    /// Previously owned partitions and generation of the member which sent `subscription`.
+    fn member_data(&self, subscription: &Subscription) -> MemberData;
+
+    fn sticky_assign(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<StickyAssignment> {
+        let mut consumer_to_owned_partitions = BTreeMap::new();
+        let mut partitions_with_multiple_previous_owners = HashSet::new();
+        let (mut assignment, partitions_transferring_ownership) = if all_subscriptions_equal(
+            self,
+            partitions_per_topic,
+            subscriptions,
+            &mut consumer_to_owned_partitions,
+            &mut partitions_with_multiple_previous_owners,
+        ) {
+            debug!("Detected that all consumers were subscribed to same set of topics, invoking the optimized assignment algorithm");
+            let mut partitions_transferring_ownership = HashMap::new();
+            let assignment = constrained_assign(
+                partitions_per_topic,
+                consumer_to_owned_partitions,
+                &partitions_with_multiple_previous_owners,
+                &mut partitions_transferring_ownership,
+            )?;
+            (assignment, Some(partitions_transferring_ownership))
+        } else {
+            debug!("Detected that not all consumers were subscribed to same set of topics, falling back to the general case assignment algorithm");
+            (
+                general_assign(self, partitions_per_topic, subscriptions),
+                None,
+            )
+        };
+
+        Ok(StickyAssignment {
+            assignment: subscriptions
+                .keys()
+                .map(|member_id| {
+                    let partitions = assignment.remove(member_id).unwrap_or_default();
+                    (member_id.clone(), partitions)
+                })
+                .collect(),
+            partitions_transferring_ownership,
+        })
+    }
+}
+
+/// Returns true iff all consumers have an identical subscription. Also fills out the passed in
+/// `consumer_to_owned_partitions` with each consumer's previously owned and still-subscribed partitions,
+/// and the `partitions_with_multiple_previous_owners` with any partitions claimed by multiple previous owners
+fn all_subscriptions_equal<A: AbstractStickyAssignor + ?Sized>(
+    assignor: &A,
+    all_topics: &IndexMap<String, usize>,
+    subscriptions: &IndexMap<String, Subscription>,
+    consumer_to_owned_partitions: &mut BTreeMap<String, Vec<TopicPartition>>,
+    partitions_with_multiple_previous_owners: &mut HashSet<TopicPartition>,
+) -> bool {
+    let mut max_generation = DEFAULT_GENERATION;
+    let mut members_of_current_highest_generation = HashSet::new();
+    let mut is_all_subscriptions_equal = true;
+
+    // the topics of the first subscription; an empty subscription still counts as the first one
+    let mut subscribed_topics: Option<HashSet<&String>> = None;
+
+    // keep track of all previously owned partitions so we can invalidate them if invalid input is
+    // detected, eg two consumers somehow claiming the same partition in the same/current generation
+    let mut all_previous_partitions_to_owner: HashMap<TopicPartition, String> = HashMap::new();
+
+    for (consumer, subscription) in subscriptions {
+        let topics: HashSet<&String> = subscription.topics.iter().collect();
+        match &subscribed_topics {
+            // initialize the subscribed topics set if this is the first subscription
+            None => subscribed_topics = Some(topics),
+            Some(subscribed_topics) => {
+                if *subscribed_topics != topics {
+                    is_all_subscriptions_equal = false;
+                }
+            }
+        }
+
+        let member_data = assignor.member_data(subscription);
+        consumer_to_owned_partitions.insert(consumer.clone(), vec![]);
+
+        // Only consider this consumer's owned partitions as valid if it is a member of the current highest
+        // generation, or it's generation is not present but we have not seen any known generation so far
+        let is_current_member = match member_data.generation {
+            Some(generation) => generation >= max_generation,
+            None => max_generation == DEFAULT_GENERATION,
+        };
+        if !is_current_member {
+            continue;
+        }
+
+        // If the current member's generation is higher, all the previously owned partitions are invalid
+        if let Some(generation) = member_data.generation {
+            if generation > max_generation {
+                all_previous_partitions_to_owner.clear();
+                partitions_with_multiple_previous_owners.clear();
+                for dropped_out_consumer in members_of_current_highest_generation.drain() {
+                    if let Some(owned) = consumer_to_owned_partitions.get_mut(&dropped_out_consumer)
+                    {
+                        owned.clear();
+                    }
+                }
+                max_generation = generation;
+            }
+        }
+
+        members_of_current_highest_generation.insert(consumer.clone());
+        for tp in member_data.partitions {
+            // filter out any topics that no longer exist or aren't part of the current subscription
+            if !matches!(all_topics.get(&tp.topic), Some(count) if (tp.partition as usize) < *count)
+            {
+                continue;
+            }
+            match all_previous_partitions_to_owner.insert(tp.clone(), consumer.clone()) {
+                None => {
+                    // this partition is not owned by other consumer in the same generation
+                    if let Some(owned) = consumer_to_owned_partitions.get_mut(consumer) {
+                        owned.push(tp);
+                    }
+                }
+                Some(other_consumer) => {
+                    error!(
+                        "Found multiple consumers {} and {} claiming the same TopicPartition {} in the \
+                         same generation {}, this will be invalidated and removed from their previous assignment.",
+                        consumer, other_consumer, tp, max_generation
+                    );
+                    if let Some(owned) = consumer_to_owned_partitions.get_mut(&other_consumer) {
+                        owned.retain(|partition| partition != &tp);
+                    }
+                    partitions_with_multiple_previous_owners.insert(tp);
+                }
+            }
+        }
+    }
+
+    is_all_subscriptions_equal
+}
+
+/// This constrained_assign optimizes the assignment algorithm when all consumers were subscribed to same set of topics.
+/// The method includes the following steps:
+///
+/// 1. Reassign previously owned partitions:
+///    1. if owned less than minQuota partitions, just assign all owned partitions, and put the member into unfilled member list
+///    2. if owned maxQuota or more, and we're still under the number of expected max capacity members, assign maxQuota partitions
+///    3. if owned at least "minQuota" of partitions, assign minQuota partitions, and put the member into unfilled member list if
+///       we're still under the number of expected max capacity members
+/// 2. Fill remaining members up to the expected numbers of maxQuota partitions, otherwise, to minQuota partitions
+fn constrained_assign(
+    partitions_per_topic: &IndexMap<String, usize>,
+    consumer_to_owned_partitions: BTreeMap<String, Vec<TopicPartition>>,
+    partitions_with_multiple_previous_owners: &HashSet<TopicPartition>,
+    partitions_transferring_ownership: &mut HashMap<TopicPartition, String>,
+) -> Result<BTreeMap<String, Vec<TopicPartition>>> {
+    let number_of_consumers = consumer_to_owned_partitions.len();
+    if number_of_consumers == 0 {
+        return Ok(BTreeMap::new());
+    }
+
+    let mut all_revoked_partitions = HashSet::new();
+
+    // the consumers which may still be assigned one or more partitions to reach expected capacity
+    let mut unfilled_members_with_under_min_quota_partitions = vec![];
+    let mut unfilled_members_with_exactly_min_quota_partitions = vec![];
+
+    let total_partitions_count: usize = partitions_per_topic.values().sum();
+
+    let min_quota = total_partitions_count / number_of_consumers;
+    // the expected number of members receiving more than minQuota partitions (zero when minQuota == maxQuota)
+    let expected_num_members_with_over_min_quota_partitions =
+        total_partitions_count % number_of_consumers;
+    let max_quota = if expected_num_members_with_over_min_quota_partitions > 0 {
+        min_quota + 1
+    } else {
+        min_quota
+    };
+    // the current number of members receiving more than minQuota partitions (zero when minQuota == maxQuota)
+    let mut current_num_members_with_over_min_quota_partitions = 0;
+
+    let mut assignment = BTreeMap::new();
+    let mut assigned_partitions = HashSet::new();
+    // Reassign previously owned partitions, up to the expected number of partitions per consumer
+    for (consumer, mut owned_partitions) in consumer_to_owned_partitions {
+        owned_partitions.retain(|partition| {
+            if partitions_with_multiple_previous_owners.contains(partition) {
+                error!(
+                    "Found partition {} still claimed as owned by consumer {}, despite being claimed by multiple \
+                     consumers already in the same generation. Removing it from the ownedPartitions",
+                    partition, consumer
+                );
+                false
+            } else {
+                true
+            }
+        });
+
+        let mut consumer_assignment = Vec::with_capacity(max_quota);
+        if owned_partitions.len() < min_quota {
+            // the expected assignment size is more than this consumer has now, so keep all the owned partitions
+            // and put this member into the unfilled member list
+            assigned_partitions.extend(owned_partitions.iter().cloned());
+            consumer_assignment.extend(owned_partitions);
+            unfilled_members_with_under_min_quota_partitions.push(consumer.clone());
+        } else if owned_partitions.len() >= max_quota
+            && current_num_members_with_over_min_quota_partitions
+                < expected_num_members_with_over_min_quota_partitions
+        {
+            // consumer owned the "maxQuota" of partitions or more, and we're still under the number of expected members
+            // with more than the minQuota partitions, so keep "maxQuota" of the owned partitions, and revoke the rest of the partitions
+            current_num_members_with_over_min_quota_partitions += 1;
+            if current_num_members_with_over_min_quota_partitions
+                == expected_num_members_with_over_min_quota_partitions
+            {
+                unfilled_members_with_exactly_min_quota_partitions.clear();
+            }
+            all_revoked_partitions.extend(owned_partitions.drain(max_quota..));
+            assigned_partitions.extend(owned_partitions.iter().cloned());
+            consumer_assignment.extend(owned_partitions);
+        } else {
+            // consumer owned at least "minQuota" of partitions
+            // so keep "minQuota" of the owned partitions, and revoke the rest of the partitions
+            all_revoked_partitions.extend(owned_partitions.drain(min_quota..));
+            assigned_partitions.extend(owned_partitions.iter().cloned());
+            consumer_assignment.extend(owned_partitions);
+            // this consumer is potential maxQuota candidate since we're still under the number of expected members
+            // with more than the minQuota partitions. Note, if the number of expected members with more than
+            // the minQuota partitions is 0, it means minQuota == maxQuota, and there are no potentially unfilled
+            if current_num_members_with_over_min_quota_partitions
+                < expected_num_members_with_over_min_quota_partitions
+            {
+                unfilled_members_with_exactly_min_quota_partitions.push(consumer.clone());
+            }
+        }
+        assignment.insert(consumer, consumer_assignment);
+    }
+
+    let unassigned_partitions = unassigned_partitions(partitions_per_topic, &assigned_partitions);
+
+    unfilled_members_with_under_min_quota_partitions.sort();
+    unfilled_members_with_exactly_min_quota_partitions.sort();
+    let mut unfilled_members_with_exactly_min_quota_partitions: VecDeque<String> =
+        unfilled_members_with_exactly_min_quota_partitions.into();
+
+    // Round-Robin filling remaining members up to the expected numbers of maxQuota, otherwise, to minQuota
+    let mut next_unfilled_consumer = 0;
+    let unassigned_partitions_count = unassigned_partitions.len();
+    for (index, unassigned_partition) in unassigned_partitions.into_iter().enumerate() {
+        let under_min_quota = !unfilled_members_with_under_min_quota_partitions.is_empty();
+        let unfilled_consumer = if !under_min_quota {
+            match unfilled_members_with_exactly_min_quota_partitions.pop_front() {
+                Some(consumer) => consumer,
+                None => {
+                    // Should not enter here since we have calculated the exact number to assign to each consumer.
+                    // This indicates issues in the assignment algorithm
+                    error!(
+                        "No more unfilled consumers to be assigned. {} partitions remain unassigned",
+                        unassigned_partitions_count - index
+                    );
+                    return Err(KafkaError::IllegalState(
+                        "No more unfilled consumers to be assigned.".to_owned(),
+                    ));
+                }
+            }
+        } else {
+            if next_unfilled_consumer >= unfilled_members_with_under_min_quota_partitions.len() {
+                next_unfilled_consumer = 0;
+            }
+            next_unfilled_consumer += 1;
+            unfilled_members_with_under_min_quota_partitions[next_unfilled_consumer - 1].clone()
+        };
+
+        let consumer_assignment = assignment.entry(unfilled_consumer.clone()).or_default();
+        consumer_assignment.push(unassigned_partition.clone());
+        let current_assigned_count = consumer_assignment.len();
+
+        // We already assigned all possible ownedPartitions, so we know this must be newly assigned to this consumer
+        // or else the partition was actually claimed by multiple previous owners and had to be invalidated from all
+        // members claimed ownedPartitions
+        if all_revoked_partitions.contains(&unassigned_partition)
+            || partitions_with_multiple_previous_owners.contains(&unassigned_partition)
+        {
+            partitions_transferring_ownership
+                .insert(unassigned_partition, unfilled_consumer.clone());
+        }
+
+        if under_min_quota && current_assigned_count == min_quota {
+            next_unfilled_consumer -= 1;
+            unfilled_members_with_under_min_quota_partitions.remove(next_unfilled_consumer);
+            unfilled_members_with_exactly_min_quota_partitions.push_back(unfilled_consumer);
+        } else if current_assigned_count == max_quota {
+            current_num_members_with_over_min_quota_partitions += 1;
+            // We only start to iterate over the "potentially unfilled" members at minQuota after we've filled
+            // all members up to at least minQuota, so once the last minQuota member reaches maxQuota, we
+            // should be done. But in case of some algorithmic error, just log a warning and continue to
+            // assign any remaining partitions within the assignment constraints
+            if current_num_members_with_over_min_quota_partitions
+                == expected_num_members_with_over_min_quota_partitions
+                && index != unassigned_partitions_count - 1
+            {
+                error!("Filled the last member up to maxQuota but still had partitions remaining to assign, \
+                        will continue but this indicates a bug in the assignment.");
+            }
+        }
+    }
+
+    if !unfilled_members_with_under_min_quota_partitions.is_empty() {
+        // we expected all the remaining unfilled members have minQuota partitions and we're already at the expected number
+        // of members with more than the minQuota partitions. Otherwise, there must be error here.
+        if current_num_members_with_over_min_quota_partitions
+            != expected_num_members_with_over_min_quota_partitions
+        {
+            error!(
+                "Current number of members with more than the minQuota partitions: {}, is less than the expected number \
+                 of members with more than the minQuota partitions: {}, and no more partitions to be assigned to the remaining unfilled consumers: {:?}",
+                current_num_members_with_over_min_quota_partitions,
+                expected_num_members_with_over_min_quota_partitions,
+                unfilled_members_with_under_min_quota_partitions
+            );
+            return Err(KafkaError::IllegalState(
+                "We haven't reached the expected number of members with more than the minQuota partitions, \
+                 but no more partitions to be assigned"
+                    .to_owned(),
+            ));
+        }
+        for unfilled_member in &unfilled_members_with_under_min_quota_partitions {
+            let assigned_partitions_count = assignment.get(unfilled_member).map_or(0, Vec::len);
+            if assigned_partitions_count != min_quota {
+                error!(
+                    "Consumer: [{}] should have {} partitions, but got {} partitions, and no more partitions \
+                     to be assigned. The remaining unfilled consumers are: {:?}",
+                    unfilled_member,
+                    min_quota,
+                    assigned_partitions_count,
+                    unfilled_members_with_under_min_quota_partitions
+                );
+                return Err(KafkaError::IllegalState(format!(
+                    "Consumer: [{}] should have {} partitions, but got {} partitions, and no more partitions to be assigned",
+                    unfilled_member, min_quota, assigned_partitions_count
+                )));
+            }
+        }
+    }
+
+    Ok(assignment)
+}
+
+/// All partitions of the subscribed topics, ordered by topic and partition, that are not yet assigned.
+fn unassigned_partitions(
+    partitions_per_topic: &IndexMap<String, usize>,
+    assigned_partitions: &HashSet<TopicPartition>,
+) -> Vec<TopicPartition> {
+    let mut sorted_all_topics: Vec<&String> = partitions_per_topic.keys().collect();
+    sorted_all_topics.sort();
+    let mut unassigned_partitions = vec![];
+    for topic in sorted_all_topics {
+        for i in 0..partitions_per_topic[topic] {
+            let tp = TopicPartition::new(topic.as_str(), i as i32);
+            if !assigned_partitions.contains(&tp) {
+                unassigned_partitions.push(tp);
+            }
+        }
+    }
+    unassigned_partitions
+}
+
+/// This general_assign algorithm guarantees the assignment that is as balanced as possible.
+/// This method includes the following steps:
+///
+/// 1. Preserving all the existing partition assignments
+/// 2. Removing all the partition assignments that have become invalid due to the change that triggers the reassignment
+/// 3. Assigning the unassigned partitions in a way that balances out the overall assignments of partitions to consumers
+/// 4. Further balancing out the resulting assignment by finding the partitions that can be reassigned
+///    to another consumer towards an overall more balanced assignment.
+fn general_assign<A: AbstractStickyAssignor + ?Sized>(
+    assignor: &A,
+    partitions_per_topic: &IndexMap<String, usize>,
+    subscriptions: &IndexMap<String, Subscription>,
+) -> BTreeMap<String, Vec<TopicPartition>> {
+    let mut current_assignment = BTreeMap::new();
+    let mut prev_assignment = HashMap::new();
+    prepopulate_current_assignments(
+        assignor,
+        subscriptions,
+        &mut current_assignment,
+        &mut prev_assignment,
+    );
+
+    // a mapping of all topic partitions to all consumers that can be assigned to them
+    let mut partition2_all_potential_consumers: BTreeMap<TopicPartition, Vec<String>> =
+        BTreeMap::new();
+    // a mapping of all consumers to all potential topic partitions that can be assigned to them
+    let mut consumer2_all_potential_partitions: BTreeMap<String, IndexSet<TopicPartition>> =
+        BTreeMap::new();
+
+    // initialize partition2_all_potential_consumers and consumer2_all_potential_partitions in the following two for loops
+    for (topic, num_partitions) in partitions_per_topic {
+        for i in 0..*num_partitions {
+            partition2_all_potential_consumers
+                .insert(TopicPartition::new(topic.as_str(), i as i32), vec![]);
+        }
+    }
+
+    for (consumer_id, subscription) in subscriptions {
+        let mut potential_partitions = IndexSet::new();
+        for topic in &subscription.topics {
+            if let Some(num_partitions) = partitions_per_topic.get(topic) {
+                for i in 0..*num_partitions {
+                    let topic_partition = TopicPartition::new(topic.as_str(), i as i32);
+                    if let Some(consumers) =
+                        partition2_all_potential_consumers.get_mut(&topic_partition)
+                    {
+                        consumers.push(consumer_id.clone());
+                    }
+                    potential_partitions.insert(topic_partition);
+                }
+            }
+        }
+        consumer2_all_potential_partitions.insert(consumer_id.clone(), potential_partitions);
+
+        // add this consumer to current_assignment (with an empty topic partition assignment) if it does not already exist
+        current_assignment
+            .entry(consumer_id.clone())
+            .or_insert_with(Vec::new);
+    }
+
+    // a mapping of partition to current consumer
+    let mut current_partition_consumer = HashMap::new();
+    for (consumer, partitions) in &current_assignment {
+        for topic_partition in partitions {
+            current_partition_consumer.insert(topic_partition.clone(), consumer.clone());
+        }
+    }
+
+    // an ascending list of topic partitions based on how many consumers can potentially use them
+    let mut sorted_partitions: Vec<TopicPartition> =
+        partition2_all_potential_consumers.keys().cloned().collect();
+    sorted_partitions.sort_by_key(|partition| partition2_all_potential_consumers[partition].len());
+
+    // all partitions that need to be assigned (initially set to all partitions but adjusted in the following loop)
+    let mut still_assigned_partitions = HashSet::new();
+    let mut revocation_required = false;
+    current_assignment.retain(|consumer, partitions| {
+        let subscription = match subscriptions.get(consumer) {
+            Some(subscription) => subscription,
+            None => {
+                // if a consumer that existed before (and had some partition assignments) is now removed, remove it from current_assignment
+                for topic_partition in partitions.iter() {
+                    current_partition_consumer.remove(topic_partition);
+                }
+                return false;
+            }
+        };
+        // otherwise (the consumer still exists)
+        partitions.retain(|partition| {
+            if !partition2_all_potential_consumers.contains_key(partition) {
+                // if this topic partition of this consumer no longer exists remove it from current_assignment of the consumer
+                current_partition_consumer.remove(partition);
+                false
+            } else if !subscription.topics.contains(&partition.topic) {
+                // if this partition cannot remain assigned to its current consumer because the consumer
+                // is no longer subscribed to its topic remove it from current_assignment of the consumer
+                current_partition_consumer.remove(partition);
+                revocation_required = true;
+                false
+            } else {
+                // otherwise, remove the topic partition from those that need to be assigned only if
+                // its current consumer is still subscribed to its topic (because it is already assigned
+                // and we would want to preserve that assignment as much as possible)
+                still_assigned_partitions.insert(partition.clone());
+                true
+            }
+        });
+        true
+    });
+    let unassigned_partitions: Vec<TopicPartition> = sorted_partitions
+        .iter()
+        .filter(|partition| !still_assigned_partitions.contains(partition))
+        .cloned()
+        .collect();
+
+    // at this point we have preserved all valid topic partition to consumer assignments and removed
+    // all invalid topic partitions and invalid consumers. Now we need to assign unassigned_partitions
+    // to consumers so that the topic partition assignments are as balanced as possible.
+
+    let mut balancer = StickyBalancer {
+        sorted_current_subscriptions: current_assignment
+            .iter()
+            .map(|(consumer, partitions)| (partitions.len(), consumer.clone()))
+            .collect(),
+        current_assignment,
+        prev_assignment,
+        consumer2_all_potential_partitions,
+        partition2_all_potential_consumers,
+        current_partition_consumer,
+        partition_movements: PartitionMovements::default(),
+    };
+    balancer.balance(
+        sorted_partitions,
+        unassigned_partitions,
+        revocation_required,
+    );
+    balancer.current_assignment
+}
+
+fn prepopulate_current_assignments<A: AbstractStickyAssignor + ?Sized>(
+    assignor: &A,
+    subscriptions: &IndexMap<String, Subscription>,
+    current_assignment: &mut BTreeMap<String, Vec<TopicPartition>>,
+    prev_assignment: &mut HashMap<TopicPartition, String>,
+) {
+    // we need to process subscriptions' user data with each consumer's reported generation in mind
+    // higher generations overwrite lower generations in case of a conflict
+    // note that a conflict could exists only if user data is for different generations
+
+    // for each partition we create a sorted map of its consumers by generation
+    let mut sorted_partition_consumers_by_generation: BTreeMap<
+        TopicPartition,
+        BTreeMap<i32, String>,
+    > = BTreeMap::new();
+    for (consumer, subscription) in subscriptions {
+        let member_data = assignor.member_data(subscription);
+        for partition in member_data.partitions {
+            let consumers = sorted_partition_consumers_by_generation
+                .entry(partition.clone())
+                .or_default();
+            match member_data.generation {
+                Some(generation) if consumers.contains_key(&generation) => {
+                    // same partition is assigned to two consumers during the same rebalance.
+                    // log a warning and skip this record
+                    warn!(
+                        "Partition '{}' is assigned to multiple consumers following sticky assignment generation {}.",
+                        partition, generation
+                    );
+                }
+                generation => {
+                    consumers.insert(generation.unwrap_or(DEFAULT_GENERATION), consumer.clone());
+                }
+            }
+        }
+    }
+
+    // prev_assignment holds the prior consumer (before current) of each partition
+    // current and previous consumers are the last two consumers of each partition in the above sorted map
+    for (partition, consumers) in sorted_partition_consumers_by_generation {
+        let mut it = consumers.into_iter().rev();
+
+        // let's process the current (most recent) consumer first
+        if let Some((_, consumer)) = it.next() {
+            current_assignment
+                .entry(consumer)
+                .or_default()
+                .push(partition.clone());
+        }
+
+        // now update previous assignment if any
+        if let Some((_, consumer)) = it.next() {
+            prev_assignment.insert(partition, consumer);
+        }
+    }
+}
+
+/// State of the general assignment algorithm while the unassigned partitions are placed and the
+/// assignment is balanced.
+struct StickyBalancer {
+    current_assignment: BTreeMap<String, Vec<TopicPartition>>,
+    prev_assignment: HashMap<TopicPartition, String>,
+    /// An ascending sorted set of consumers based on how many topic partitions are already assigned to them
+    sorted_current_subscriptions: BTreeSet<(usize, String)>,
+    consumer2_all_potential_partitions: BTreeMap<String, IndexSet<TopicPartition>>,
+    partition2_all_potential_consumers: BTreeMap<TopicPartition, Vec<String>>,
+    current_partition_consumer: HashMap<TopicPartition, String>,
+    partition_movements: PartitionMovements,
+}
+
+impl StickyBalancer {
+    /// Balance the current assignment using the data structures created in the general_assign.
+    fn balance(
+        &mut self,
+        mut sorted_partitions: Vec<TopicPartition>,
+        mut unassigned_partitions: Vec<TopicPartition>,
+        revocation_required: bool,
+    ) {
+        let initializing = !matches!(
+            self.sorted_current_subscriptions.iter().next_back(),
+            Some((size, _)) if *size > 0
+        );
+
+        // assign all unassigned partitions
+        for partition in &unassigned_partitions {
+            // skip if there is no potential consumer for the partition
+            if self.partition2_all_potential_consumers[partition].is_empty() {
+                continue;
+            }
+            self.assign_partition(partition);
+        }
+
+        // narrow down the reassignment scope to only those partitions that can actually be reassigned
+        let fixed_partitions: HashSet<TopicPartition> = self
+            .partition2_all_potential_consumers
+            .keys()
+            .filter(|partition| !self.can_partition_participate_in_reassignment(partition))
+            .cloned()
+            .collect();
+        sorted_partitions.retain(|partition| !fixed_partitions.contains(partition));
+        unassigned_partitions.retain(|partition| !fixed_partitions.contains(partition));
+
+        // narrow down the reassignment scope to only those consumers that are subject to reassignment
+        let mut fixed_assignments = BTreeMap::new();
+        let consumers: Vec<String> = self
+            .consumer2_all_potential_partitions
+            .keys()
+            .cloned()
+            .collect();
+        for consumer in consumers {
+            if !self.can_consumer_participate_in_reassignment(&consumer) {
+                if let Some(partitions) = self.current_assignment.remove(&consumer) {
+                    self.sorted_current_subscriptions
+                        .remove(&(partitions.len(), consumer.clone()));
+                    fixed_assignments.insert(consumer, partitions);
+                }
+            }
+        }
+
+        // create a deep copy of the current assignment so we can revert to it if we do not get a more balanced assignment later
+        let pre_balance_assignment = self.current_assignment.clone();
+        let pre_balance_partition_consumers = self.current_partition_consumer.clone();
+
+        // if we don't already need to revoke something due to subscription changes, first try to balance by only moving newly added partitions
+        if !revocation_required {
+            self.perform_reassignments(&unassigned_partitions);
+        }
+
+        let reassignment_performed = self.perform_reassignments(&sorted_partitions);
+
+        // if we are not preserving existing assignments and we have made changes to the current assignment
+        // make sure we are getting a more balanced assignment; otherwise, revert to previous assignment
+        if !initializing
+            && reassignment_performed
+            && balance_score(&self.current_assignment) >= balance_score(&pre_balance_assignment)
+        {
+            self.current_assignment = pre_balance_assignment;
+            self.current_partition_consumer = pre_balance_partition_consumers;
+            self.sorted_current_subscriptions = self
+                .current_assignment
+                .iter()
+                .map(|(consumer, partitions)| (partitions.len(), consumer.clone()))
+                .collect();
+        }
+
+        // add the fixed assignments (those that could not change) back
+        for (consumer, partitions) in fixed_assignments {
+            self.sorted_current_subscriptions
+                .insert((partitions.len(), consumer.clone()));
+            self.current_assignment.insert(consumer, partitions);
+        }
+    }
+
+    /// The assignment should improve the overall balance of the partition assignments to consumers.
+    fn assign_partition(&mut self, partition: &TopicPartition) {
+        let consumer = self
+            .sorted_current_subscriptions
+            .iter()
+            .map(|(_, consumer)| consumer)
+            .find(|consumer| self.consumer2_all_potential_partitions[*consumer].contains(partition))
+            .cloned();
+        if let Some(consumer) = consumer {
+            self.update_assignment(&consumer, |partitions| partitions.push(partition.clone()));
+            self.current_partition_consumer
+                .insert(partition.clone(), consumer);
+        }
+    }
+
+    /// Applies `f` to the partitions of `consumer` while keeping the sorted set of consumers in order.
+    fn update_assignment<F>(&mut self, consumer: &str, f: F)
+    where
+        F: FnOnce(&mut Vec<TopicPartition>),
+    {
+        if let Some(partitions) = self.current_assignment.get_mut(consumer) {
+            self.sorted_current_subscriptions
+                .remove(&(partitions.len(), consumer.to_owned()));
+            f(partitions);
+            self.sorted_current_subscriptions
+                .insert((partitions.len(), consumer.to_owned()));
+        }
+    }
+
+    fn can_partition_participate_in_reassignment(&self, partition: &TopicPartition) -> bool {
+        // if a partition has two or more potential consumers it is subject to reassignment.
+        self.partition2_all_potential_consumers[partition].len() >= 2
+    }
+
+    fn can_consumer_participate_in_reassignment(&self, consumer: &str) -> bool {
+        let current_partitions = match self.current_assignment.get(consumer) {
+            Some(partitions) => partitions,
+            None => return false,
+        };
+        let current_assignment_size = current_partitions.len();
+        let max_assignment_size = self.consumer2_all_potential_partitions[consumer].len();
+        if current_assignment_size > max_assignment_size {
+            error!(
+                "The consumer {} is assigned more partitions than the maximum possible.",
+                consumer
+            );
+        }
+
+        if current_assignment_size < max_assignment_size {
+            // if a consumer is not assigned all its potential partitions it is subject to reassignment
+            return true;
+        }
+
+        // if any of the partitions assigned to a consumer is subject to reassignment the consumer itself
+        // is subject to reassignment
+        current_partitions
+            .iter()
+            .any(|partition| self.can_partition_participate_in_reassignment(partition))
+    }
+
+    fn assignment_size(&self, consumer: &str) -> Option<usize> {
+        self.current_assignment.get(consumer).map(Vec::len)
+    }
+
+    fn perform_reassignments(&mut self, reassignable_partitions: &[TopicPartition]) -> bool {
+        let mut reassignment_performed = false;
+
+        // repeat reassignment until no partition can be moved to improve the balance
+        loop {
+            let mut modified = false;
+            // reassign all reassignable partitions (starting from the partition with least potential consumers and if needed)
+            // until the full list is processed or a balance is achieved
+            for partition in reassignable_partitions {
+                if self.is_balanced() {
+                    break;
+                }
+
+                // the partition must have at least two consumers
+                if self.partition2_all_potential_consumers[partition].len() <= 1 {
+                    error!(
+                        "Expected more than one potential consumer for partition '{}'",
+                        partition
+                    );
+                }
+
+                // the partition must have a current consumer
+                let consumer = match self.current_partition_consumer.get(partition) {
+                    Some(consumer) => consumer.clone(),
+                    None => {
+                        error!(
+                            "Expected partition '{}' to be assigned to a consumer",
+                            partition
+                        );
+                        continue;
+                    }
+                };
+                let consumer_size = match self.assignment_size(&consumer) {
+                    Some(size) => size,
+                    None => continue,
+                };
+
+                // the previous owner may since have unsubscribed from the topic of the partition
+                if let Some(prev_consumer) = self.prev_assignment.get(partition).cloned() {
+                    if matches!(self.assignment_size(&prev_consumer), Some(size) if consumer_size > size + 1)
+                        && self.consumer2_all_potential_partitions[&prev_consumer]
+                            .contains(partition)
+                    {
+                        self.reassign_partition(partition, &prev_consumer);
+                        reassignment_performed = true;
+                        modified = true;
+                        continue;
+                    }
+                }
+
+                // check if a better-suited consumer exist for the partition; if so, reassign it
+                let has_better_suited_consumer = self.partition2_all_potential_consumers
+                    [partition]
+                    .iter()
+                    .any(|other_consumer| {
+                        matches!(self.assignment_size(other_consumer), Some(size) if consumer_size > size + 1)
+                    });
+                if has_better_suited_consumer {
+                    self.reassign_partition_to_least_loaded(partition);
+                    reassignment_performed = true;
+                    modified = true;
+                }
+            }
+            if !modified {
+                return reassignment_performed;
+            }
+        }
+    }
+
+    fn reassign_partition_to_least_loaded(&mut self, partition: &TopicPartition) {
+        // find the new consumer
+        let new_consumer = self
+            .sorted_current_subscriptions
+            .iter()
+            .map(|(_, consumer)| consumer)
+            .find(|consumer| self.consumer2_all_potential_partitions[*consumer].contains(partition))
+            .cloned();
+        if let Some(new_consumer) = new_consumer {
+            self.reassign_partition(partition, &new_consumer);
+        }
+    }
+
+    fn reassign_partition(&mut self, partition: &TopicPartition, new_consumer: &str) {
+        let consumer = match self.current_partition_consumer.get(partition) {
+            Some(consumer) => consumer.clone(),
+            None => return,
+        };
+        // find the correct partition movement considering the stickiness requirement
+        let partition_to_be_moved = self.partition_movements.actual_partition_to_be_moved(
+            partition,
+            &consumer,
+            new_consumer,
+        );
+        self.process_partition_movement(&partition_to_be_moved, new_consumer);
+    }
+
+    fn process_partition_movement(&mut self, partition: &TopicPartition, new_consumer: &str) {
+        let old_consumer = match self.current_partition_consumer.get(partition) {
+            Some(consumer) => consumer.clone(),
+            None => return,
+        };
+
+        self.partition_movements
+            .move_partition(partition, &old_consumer, new_consumer);
+
+        self.update_assignment(&old_consumer, |partitions| {
+            partitions.retain(|tp| tp != partition)
+        });
+        self.update_assignment(new_consumer, |partitions| {
+            partitions.push(partition.clone())
+        });
+        self.current_partition_consumer
+            .insert(partition.clone(), new_consumer.to_owned());
+    }
+
+    /// Determines if the current assignment is a balanced one
+    fn is_balanced(&self) -> bool {
+        let (min, max) = match (
+            self.sorted_current_subscriptions.iter().next(),
+            self.sorted_current_subscriptions.iter().next_back(),
+        ) {
+            (Some((min, _)), Some((max, _))) => (*min, *max),
+            _ => return true,
+        };
+        if max <= min + 1 {
+            // if minimum and maximum numbers of partitions assigned to consumers differ by at most one return true
+            return true;
+        }
+
+        // create a mapping from partitions to the consumer assigned to them
+        let mut all_partitions = HashMap::new();
+        for (consumer, topic_partitions) in &self.current_assignment {
+            for topic_partition in topic_partitions {
+                if all_partitions.insert(topic_partition, consumer).is_some() {
+                    error!("{} is assigned to more than one consumer.", topic_partition);
+                }
+            }
+        }
+
+        // for each consumer that does not have all the topic partitions it can get make sure none of the topic partitions it
+        // could but did not get cannot be moved to it (because that would break the balance)
+        for (consumer_partition_count, consumer) in &self.sorted_current_subscriptions {
+            let consumer_partitions = &self.current_assignment[consumer];
+            let potential_topic_partitions = &self.consumer2_all_potential_partitions[consumer];
+
+            // skip if this consumer already has all the topic partitions it can get
+            if *consumer_partition_count == potential_topic_partitions.len() {
+                continue;
+            }
+
+            // otherwise make sure it cannot get any more
+            for topic_partition in potential_topic_partitions {
+                if consumer_partitions.contains(topic_partition) {
+                    continue;
+                }
+                if let Some(other_consumer) = all_partitions.get(topic_partition) {
+                    let other_consumer_partition_count =
+                        self.current_assignment[*other_consumer].len();
+                    if *consumer_partition_count < other_consumer_partition_count {
+                        debug!(
+                            "{} can be moved from consumer {} to consumer {} for a more balanced assignment.",
+                            topic_partition, other_consumer, consumer
+                        );
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// The balance score of the given assignment, as the sum of assigned partitions size difference of all consumer pairs.
+/// A perfectly balanced assignment (with all consumers getting the same number of partitions) has a balance score of 0.
+/// Lower balance score indicates a more balanced assignment.
+fn balance_score(assignment: &BTreeMap<String, Vec<TopicPartition>>) -> usize {
+    let sizes: Vec<usize> = assignment.values().map(Vec::len).collect();
+    let mut score = 0;
+    for (i, size) in sizes.iter().enumerate() {
+        for other_size in &sizes[i + 1..] {
+            score += size.max(other_size) - size.min(other_size);
+        }
+    }
+    score
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ConsumerPair {
+    src_member_id: String,
+    dst_member_id: String,
+}
+
+impl ConsumerPair {
+    fn new(src_member_id: &str, dst_member_id: &str) -> ConsumerPair {
+        ConsumerPair {
+            src_member_id: src_member_id.to_owned(),
+            dst_member_id: dst_member_id.to_owned(),
+        }
+    }
+}
+
+/// This class maintains some data structures to simplify lookup of partition movements among consumers. At each point of
+/// time during a partition rebalance it keeps track of partition movements corresponding to each topic, and also possible
+/// movement (in form a `ConsumerPair` object) for each partition.
+#[derive(Debug, Default)]
+struct PartitionMovements {
+    partition_movements_by_topic: HashMap<String, HashMap<ConsumerPair, BTreeSet<TopicPartition>>>,
+    partition_movements: HashMap<TopicPartition, ConsumerPair>,
+}
+
+impl PartitionMovements {
+    fn remove_movement_record_of_partition(
+        &mut self,
+        partition: &TopicPartition,
+    ) -> Option<ConsumerPair> {
+        let pair = self.partition_movements.remove(partition)?;
+
+        if let Some(partition_movements_for_this_topic) =
+            self.partition_movements_by_topic.get_mut(&partition.topic)
+        {
+            if let Some(partitions) = partition_movements_for_this_topic.get_mut(&pair) {
+                partitions.remove(partition);
+                if partitions.is_empty() {
+                    partition_movements_for_this_topic.remove(&pair);
+                }
+            }
+            if partition_movements_for_this_topic.is_empty() {
+                self.partition_movements_by_topic.remove(&partition.topic);
+            }
+        }
+
+        Some(pair)
+    }
+
+    fn add_partition_movement_record(&mut self, partition: &TopicPartition, pair: ConsumerPair) {
+        self.partition_movements
+            .insert(partition.clone(), pair.clone());
+
+        self.partition_movements_by_topic
+            .entry(partition.topic.clone())
+            .or_default()
+            .entry(pair)
+            .or_default()
+            .insert(partition.clone());
+    }
+
+    fn move_partition(
+        &mut self,
+        partition: &TopicPartition,
+        old_consumer: &str,
+        new_consumer: &str,
+    ) {
+        match self.remove_movement_record_of_partition(partition) {
+            // this partition has previously moved
+            Some(existing_pair) => {
+                debug_assert_eq!(existing_pair.dst_member_id, old_consumer);
+                if existing_pair.src_member_id != new_consumer {
+                    // the partition is not moving back to its previous consumer
+                    self.add_partition_movement_record(
+                        partition,
+                        ConsumerPair::new(&existing_pair.src_member_id, new_consumer),
+                    );
+                }
+            }
+            None => self.add_partition_movement_record(
+                partition,
+                ConsumerPair::new(old_consumer, new_consumer),
+            ),
+        }
+    }
+
+    fn actual_partition_to_be_moved(
+        &self,
+        partition: &TopicPartition,
+        old_consumer: &str,
+        new_consumer: &str,
+    ) -> TopicPartition {
+        let partition_movements_for_this_topic =
+            match self.partition_movements_by_topic.get(&partition.topic) {
+                Some(movements) => movements,
+                None => return partition.clone(),
+            };
+        // if this partition has previously moved, consider its original consumer
+        let old_consumer = match self.partition_movements.get(partition) {
+            Some(pair) => {
+                debug_assert_eq!(pair.dst_member_id, old_consumer);
+                pair.src_member_id.as_str()
+            }
+            None => old_consumer,
+        };
+
+        let reverse_pair = ConsumerPair::new(new_consumer, old_consumer);
+        match partition_movements_for_this_topic
+            .get(&reverse_pair)
+            .and_then(|partitions| partitions.iter().next())
+        {
+            Some(reverse_partition) => reverse_partition.clone(),
+            None => partition.clone(),
+        }
+    }
+}
+
+/// Group layouts and property checks shared by the tests of the sticky assignors.
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::consumer::{
+            consumer_partition_assignor::Subscription,
+            internals::abstract_partition_assignor::{partitions, AbstractPartitionAssignor},
+            sticky_assignor::StickyAssignor,
+        },
+        common::topic_partition::TopicPartition,
+    };
+
+    /// Number of generated group layouts each property is checked against.
+    pub(crate) const LAYOUTS: u64 = 100;
+
+    /// A linear congruential generator, so a failing layout can be reproduced from its seed.
+    pub(crate) struct Rng(u64);
+
+    impl Rng {
+        pub(crate) fn new(seed: u64) -> Rng {
+            Rng(seed.wrapping_mul(6364136223846793005).wrapping_add(1))
+        }
+
+        /// A number in `min..=max`.
+        pub(crate) fn between(&mut self, min: usize, max: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            min + (self.0 >> 33) as usize % (max - min + 1)
+        }
+    }
+
+    /// Between 1 and 4 topics of up to 12 partitions each and between 1 and 10 members.
+    pub(crate) fn random_group(rng: &mut Rng) -> (IndexMap<String, usize>, Vec<String>) {
+        let partitions_per_topic = (0..rng.between(1, 4))
+            .map(|topic| (format!("topic{}", topic), rng.between(1, 12)))
+            .collect();
+        let members = (0..rng.between(1, 10))
+            .map(|member| format!("consumer{}", member))
+            .collect();
+        (partitions_per_topic, members)
+    }
+
+    pub(crate) fn topics(partitions_per_topic: &IndexMap<String, usize>) -> Vec<String> {
+        partitions_per_topic.keys().cloned().collect()
+    }
+
+    pub(crate) fn assign(
+        assignor: &dyn AbstractPartitionAssignor,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> IndexMap<String, Vec<TopicPartition>> {
+        assignor
+            .assign_partitions(partitions_per_topic, subscriptions)
+            .unwrap()
+    }
+
+    /// Every partition is assigned to at most one member, and only to a member subscribed to its topic.
+    pub(crate) fn assert_valid(
+        assignment: &IndexMap<String, Vec<TopicPartition>>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) {
+        let mut owners = HashMap::new();
+        for (member, partitions) in assignment {
+            for tp in partitions {
+                assert!(
+                    subscriptions[member].topics.contains(&tp.topic),
+                    "{} is assigned to {} which isn't subscribed to its topic",
+                    tp,
+                    member
+                );
+                if let Some(owner) = owners.insert(tp.clone(), member.clone()) {
+                    panic!("{} is assigned to both {} and {}", tp, owner, member);
+                }
+            }
+        }
+    }
+
+    /// All partitions are assigned and the member counts differ by at most one.
+    pub(crate) fn assert_balanced(
+        assignment: &IndexMap<String, Vec<TopicPartition>>,
+        partitions_per_topic: &IndexMap<String, usize>,
+    ) {
+        let assigned: usize = assignment.values().map(Vec::len).sum();
+        let all: usize = partitions_per_topic.values().sum();
+        assert_eq!(
+            assigned, all,
+            "not all partitions are assigned: {:?}",
+            assignment
+        );
+        let min = assignment.values().map(Vec::len).min().unwrap_or_default();
+        let max = assignment.values().map(Vec::len).max().unwrap_or_default();
+        assert!(max - min <= 1, "unbalanced assignment: {:?}", assignment);
+    }
+
+    /// Members subscribed to different topics get each partition of the subscribed topics exactly once.
+    #[test]
+    fn different_subscriptions_assign_each_partition_once() {
+        for seed in 0..LAYOUTS {
+            let mut rng = Rng::new(seed);
+            let (all_partitions_per_topic, members) = random_group(&mut rng);
+            let subscriptions: IndexMap<String, Subscription> = members
+                .into_iter()
+                .map(|member| {
+                    let topics = all_partitions_per_topic
+                        .keys()
+                        .filter(|_| rng.between(0, 1) == 1)
+                        .cloned()
+                        .collect();
+                    (member, Subscription::from_topics(topics))
+                })
+                .collect();
+            // like `assign_group`, only pass the topics somebody is subscribed to
+            let partitions_per_topic: IndexMap<String, usize> = all_partitions_per_topic
+                .into_iter()
+                .filter(|(topic, _)| {
+                    subscriptions
+                        .values()
+                        .any(|subscription| subscription.topics.contains(topic))
+                })
+                .collect();
+
+            let assignment = assign(
+                &StickyAssignor::new(),
+                &partitions_per_topic,
+                &subscriptions,
+            );
+            assert_valid(&assignment, &subscriptions);
+            for (topic, num_partitions) in &partitions_per_topic {
+                for tp in partitions(topic, *num_partitions) {
+                    assert!(
+                        assignment
+                            .values()
+                            .any(|partitions| partitions.contains(&tp)),
+                        "seed {}: {} isn't assigned",
+                        seed,
+                        tp
+                    );
+                }
+            }
+        }
+    }
+}
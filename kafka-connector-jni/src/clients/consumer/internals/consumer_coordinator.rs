@@ -0,0 +1,2144 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::{
+    clients::{
+        consumer::{
+            consumer_group_metadata::ConsumerGroupMetadata,
+            consumer_partition_assignor::{
+                Assignment, ConsumerPartitionAssignor, GroupSubscription, RebalanceProtocol,
+                Subscription,
+            },
+            consumer_rebalance_listener::{
+                ConsumerRebalanceListener, NoOpConsumerRebalanceListener,
+            },
+            cooperative_sticky_assignor::COOPERATIVE_STICKY_ASSIGNOR_NAME,
+            offset_and_metadata::OffsetAndMetadata,
+            offset_commit_callback::OffsetCommitCallback,
+        },
+        group_rebalance_config::GroupRebalanceConfig,
+        metadata::Metadata,
+    },
+    common::{
+        cluster::Cluster,
+        errors::{KafkaError, Result},
+        protocol::{api_keys::ApiKeys, errors::Errors},
+        requests::{
+            abstract_request::AbstractRequest, abstract_response::AbstractResponse,
+            join_group_request::JoinGroupRequestProtocol,
+            join_group_response::JoinGroupResponseMember,
+            offset_commit_request::OffsetCommitRequest,
+            offset_commit_response::OffsetCommitResponse, offset_fetch_request::OffsetFetchRequest,
+            offset_fetch_response::OffsetFetchResponse, txn_offset_commit_request::CommittedOffset,
+        },
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::Time,
+            timer::Timer,
+        },
+    },
+};
+
+use super::{
+    abstract_coordinator::{
+        exception, group_authorization_exception, unexpected_response, AbstractCoordinator,
+        Generation, GroupProtocolHandler,
+    },
+    consumer_network_client::ConsumerNetworkClient,
+    consumer_protocol::{self, PROTOCOL_TYPE},
+    request_future::RequestFuture,
+    subscription_state::{FetchPosition, SubscriptionState},
+};
+
+type CommittedOffsets = IndexMap<TopicPartition, Option<OffsetAndMetadata>>;
+
+/// This class manages the coordination process with the consumer coordinator.
+pub struct ConsumerCoordinator {
+    log: Logger,
+    coordinator: Arc<AbstractCoordinator>,
+    assignors: Vec<Arc<dyn ConsumerPartitionAssignor>>,
+    metadata: Arc<Metadata>,
+    subscriptions: Arc<SubscriptionState>,
+    client: Arc<ConsumerNetworkClient>,
+    time: Arc<dyn Time>,
+    auto_commit_enabled: bool,
+    auto_commit_interval_ms: u128,
+    // None if no assignors are configured, in which case the consumer can't join a group
+    protocol: Option<RebalanceProtocol>,
+    next_auto_commit_timer: Arc<Mutex<Timer>>,
+    // completed async commits, their callbacks are invoked on the thread calling poll
+    completed_offset_commits: Arc<Mutex<VecDeque<OffsetCommitCompletion>>>,
+    // async commits waiting for the coordinator to be discovered
+    pending_async_commits: Arc<AtomicUsize>,
+    // set from the response handler of async commits, which may run on the heartbeat thread
+    async_commit_fenced: Arc<AtomicBool>,
+    state: Mutex<ConsumerCoordinatorState>,
+}
+
+struct ConsumerCoordinatorState {
+    rebalance_listener: Arc<dyn ConsumerRebalanceListener>,
+    metadata_snapshot: MetadataSnapshot,
+    assignment_snapshot: Option<MetadataSnapshot>,
+    is_leader: bool,
+    joined_subscription: HashSet<String>,
+    group_metadata: ConsumerGroupMetadata,
+    pending_committed_offset_request: Option<PendingCommittedOffsetRequest>,
+}
+
+struct OffsetCommitCompletion {
+    callback: OffsetCommitCallback,
+    offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+    exception: Option<KafkaError>,
+}
+
+impl OffsetCommitCompletion {
+    fn invoke(self) {
+        (self.callback)(&self.offsets, self.exception.as_ref());
+    }
+}
+
+struct PendingCommittedOffsetRequest {
+    requested_partitions: HashSet<TopicPartition>,
+    requested_generation: Option<Generation>,
+    response: Arc<RequestFuture<CommittedOffsets>>,
+}
+
+impl PendingCommittedOffsetRequest {
+    fn same_request(
+        &self,
+        current_request: &HashSet<TopicPartition>,
+        current_generation: &Option<Generation>,
+    ) -> bool {
+        self.requested_generation == *current_generation
+            && self.requested_partitions == *current_request
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MetadataSnapshot {
+    version: i32,
+    partitions_per_topic: HashMap<String, usize>,
+}
+
+impl MetadataSnapshot {
+    fn new(subscriptions: &SubscriptionState, cluster: &Cluster, version: i32) -> MetadataSnapshot {
+        let partitions_per_topic = subscriptions
+            .metadata_topics()
+            .into_iter()
+            .filter_map(|topic| {
+                cluster
+                    .partition_count_for_topic(&topic)
+                    .map(|num_partitions| (topic, num_partitions))
+            })
+            .collect();
+        MetadataSnapshot {
+            version,
+            partitions_per_topic,
+        }
+    }
+
+    fn matches(&self, other: &MetadataSnapshot) -> bool {
+        self.version == other.version || self.partitions_per_topic == other.partitions_per_topic
+    }
+}
+
+impl ConsumerCoordinator {
+    /// Fails with `IllegalArgument` if the assignors don't have a commonly supported rebalance protocol
+    /// or the heartbeat interval isn't lower than the session timeout.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rebalance_config: GroupRebalanceConfig,
+        client: Arc<ConsumerNetworkClient>,
+        assignors: Vec<Arc<dyn ConsumerPartitionAssignor>>,
+        metadata: Arc<Metadata>,
+        subscriptions: Arc<SubscriptionState>,
+        time: Arc<dyn Time>,
+        auto_commit_enabled: bool,
+        auto_commit_interval_ms: u128,
+        log_context: &LogContext,
+    ) -> Result<ConsumerCoordinator> {
+        // select the rebalance protocol such that:
+        //   1. only consider protocols that are supported by all the assignors. If there is no common protocols supported
+        //      across all the assignors, throw an exception.
+        //   2. if there are multiple protocols that are commonly supported, select the one with the highest id (i.e. the
+        //      id number indicates how advanced the protocol is).
+        let protocol = match assignors.first() {
+            Some(first) => {
+                let supported_protocols: Vec<RebalanceProtocol> = first
+                    .supported_protocols()
+                    .into_iter()
+                    .filter(|protocol| {
+                        assignors
+                            .iter()
+                            .all(|assignor| assignor.supported_protocols().contains(protocol))
+                    })
+                    .collect();
+                match supported_protocols
+                    .into_iter()
+                    .max_by_key(|protocol| protocol.id())
+                {
+                    Some(protocol) => Some(protocol),
+                    None => {
+                        let names: Vec<&str> =
+                            assignors.iter().map(|assignor| assignor.name()).collect();
+                        return Err(KafkaError::IllegalArgument(format!(
+                            "Specified assignors {:?} do not have commonly supported rebalance protocol",
+                            names
+                        )));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let group_metadata = ConsumerGroupMetadata::new(
+            rebalance_config.group_id.clone(),
+            Generation::no_generation().generation_id,
+            Generation::no_generation().member_id,
+            rebalance_config.group_instance_id.clone(),
+        );
+        let metadata_snapshot =
+            MetadataSnapshot::new(&subscriptions, &metadata.fetch(), metadata.update_version());
+        let coordinator = Arc::new(AbstractCoordinator::new(
+            rebalance_config,
+            PROTOCOL_TYPE,
+            time.clone(),
+            client.clone(),
+            log_context,
+        )?);
+        Ok(ConsumerCoordinator {
+            log: log_context.logger(module_path!()),
+            coordinator,
+            assignors,
+            metadata,
+            subscriptions,
+            next_auto_commit_timer: Arc::new(Mutex::new(Timer::new(
+                time.clone(),
+                auto_commit_interval_ms,
+            ))),
+            client,
+            time,
+            auto_commit_enabled,
+            auto_commit_interval_ms,
+            protocol,
+            completed_offset_commits: Arc::new(Mutex::new(VecDeque::new())),
+            pending_async_commits: Arc::new(AtomicUsize::new(0)),
+            async_commit_fenced: Arc::new(AtomicBool::new(false)),
+            state: Mutex::new(ConsumerCoordinatorState {
+                rebalance_listener: Arc::new(NoOpConsumerRebalanceListener),
+                metadata_snapshot,
+                assignment_snapshot: None,
+                is_leader: false,
+                joined_subscription: HashSet::new(),
+                group_metadata,
+                pending_committed_offset_request: None,
+            }),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ConsumerCoordinatorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The group membership part of the coordinator, shared with the heartbeat thread.
+    pub fn coordinator(&self) -> &Arc<AbstractCoordinator> {
+        &self.coordinator
+    }
+
+    pub fn protocol(&self) -> Option<RebalanceProtocol> {
+        self.protocol
+    }
+
+    /// Set the listener invoked on partition assignment changes of the subscription.
+    pub fn set_rebalance_listener(&self, listener: Arc<dyn ConsumerRebalanceListener>) {
+        self.lock().rebalance_listener = listener;
+    }
+
+    pub fn group_metadata(&self) -> ConsumerGroupMetadata {
+        self.lock().group_metadata.clone()
+    }
+
+    pub fn coordinator_unknown(&self) -> bool {
+        self.coordinator.coordinator_unknown()
+    }
+
+    pub fn ensure_coordinator_ready(&self, timer: &mut Timer) -> Result<bool> {
+        self.coordinator.ensure_coordinator_ready(timer)
+    }
+
+    pub fn request_rejoin(&self, reason: &str) {
+        self.coordinator.request_rejoin(reason)
+    }
+
+    pub fn update_pattern_subscription(&self, cluster: &Cluster) -> Result<()> {
+        let topics_to_subscribe = cluster
+            .topics()
+            .filter(|topic| self.subscriptions.matches_subscribed_pattern(topic))
+            .cloned()
+            .collect();
+        if self
+            .subscriptions
+            .subscribe_from_pattern(topics_to_subscribe)?
+        {
+            self.metadata.request_update_for_new_topics();
+        }
+        Ok(())
+    }
+
+    fn lookup_assignor(&self, name: Option<&str>) -> Option<&Arc<dyn ConsumerPartitionAssignor>> {
+        let name = name?;
+        self.assignors
+            .iter()
+            .find(|assignor| assignor.name() == name)
+    }
+
+    fn maybe_update_joined_subscription(
+        &self,
+        assigned_partitions: &HashSet<TopicPartition>,
+    ) -> Result<()> {
+        if self.subscriptions.has_pattern_subscription() {
+            // Check if the assignment contains some topics that were not in the original
+            // subscription, if yes we will obey what leader has decided and add these topics
+            // into the subscriptions as long as they still match the subscribed pattern
+            let joined_subscription = self.lock().joined_subscription.clone();
+            let added_topics: HashSet<String> = assigned_partitions
+                .iter()
+                .filter(|tp| !joined_subscription.contains(&tp.topic))
+                .map(|tp| tp.topic.clone())
+                .collect();
+
+            if !added_topics.is_empty() {
+                let mut new_subscription = self.subscriptions.subscription();
+                new_subscription.extend(added_topics.iter().cloned());
+
+                if self
+                    .subscriptions
+                    .subscribe_from_pattern(new_subscription)?
+                {
+                    self.metadata.request_update_for_new_topics();
+                }
+                self.lock().joined_subscription.extend(added_topics);
+            }
+        }
+        Ok(())
+    }
+
+    fn invoke_on_assignment(
+        &self,
+        assignor: &Arc<dyn ConsumerPartitionAssignor>,
+        assignment: &Assignment,
+        group_metadata: &ConsumerGroupMetadata,
+    ) {
+        self.log.info(format_args!(
+            "Notifying assignor about the new {}",
+            assignment
+        ));
+        assignor.on_assignment(assignment, Some(group_metadata));
+    }
+
+    fn invoke_partitions_assigned(
+        &self,
+        assigned_partitions: &HashSet<TopicPartition>,
+    ) -> Result<Option<KafkaError>> {
+        // Note that we should only invoke the callback with the set of assigned partitions being
+        // the newly added ones, rather than the full set.
+        let partitions = sorted(assigned_partitions);
+        self.log.info(format_args!(
+            "Adding newly assigned partitions: {}",
+            join(&partitions)
+        ));
+
+        let listener = self.lock().rebalance_listener.clone();
+        callback_error(
+            &self.log,
+            listener.on_partitions_assigned(&partitions),
+            "onPartitionsAssigned",
+            &partitions,
+        )
+    }
+
+    fn invoke_partitions_revoked(
+        &self,
+        revoked_partitions: &HashSet<TopicPartition>,
+    ) -> Result<Option<KafkaError>> {
+        let partitions = sorted(revoked_partitions);
+        self.log.info(format_args!(
+            "Revoke previously assigned partitions {}",
+            join(&partitions)
+        ));
+        let revoke_paused_partitions: Vec<_> = self
+            .subscriptions
+            .paused_partitions()
+            .into_iter()
+            .filter(|tp| revoked_partitions.contains(tp))
+            .collect();
+        if !revoke_paused_partitions.is_empty() {
+            self.log.info(format_args!(
+                "The pause flag in partitions [{}] will be removed due to revocation.",
+                join(&revoke_paused_partitions)
+            ));
+        }
+
+        let listener = self.lock().rebalance_listener.clone();
+        callback_error(
+            &self.log,
+            listener.on_partitions_revoked(&partitions),
+            "onPartitionsRevoked",
+            &partitions,
+        )
+    }
+
+    fn invoke_partitions_lost(
+        &self,
+        lost_partitions: &HashSet<TopicPartition>,
+    ) -> Result<Option<KafkaError>> {
+        let partitions = sorted(lost_partitions);
+        self.log.info(format_args!(
+            "Lost previously assigned partitions {}",
+            join(&partitions)
+        ));
+        let lost_paused_partitions: Vec<_> = self
+            .subscriptions
+            .paused_partitions()
+            .into_iter()
+            .filter(|tp| lost_partitions.contains(tp))
+            .collect();
+        if !lost_paused_partitions.is_empty() {
+            self.log.info(format_args!(
+                "The pause flag in partitions [{}] will be removed due to partition lost.",
+                join(&lost_paused_partitions)
+            ));
+        }
+
+        let listener = self.lock().rebalance_listener.clone();
+        callback_error(
+            &self.log,
+            listener.on_partitions_lost(&partitions),
+            "onPartitionsLost",
+            &partitions,
+        )
+    }
+
+    fn maybe_update_group_subscription(
+        &self,
+        assignor_name: &str,
+        assignments: &IndexMap<String, Assignment>,
+        all_subscribed_topics: &mut HashSet<String>,
+    ) -> Result<()> {
+        // the leader may have assigned partitions which match our subscription pattern, but which
+        // were not explicitly requested, so we update the joined subscription here.
+        let assigned_topics: HashSet<String> = assignments
+            .values()
+            .flat_map(|assignment| assignment.partitions.iter())
+            .map(|tp| tp.topic.clone())
+            .collect();
+
+        if !assigned_topics.is_subset(all_subscribed_topics) {
+            let not_assigned_topics: Vec<_> = assigned_topics
+                .difference(all_subscribed_topics)
+                .cloned()
+                .collect();
+            self.log.warn(format_args!(
+                "The following not-subscribed topics are assigned, and their metadata will be fetched from the brokers: {:?}",
+                not_assigned_topics
+            ));
+            all_subscribed_topics.extend(assigned_topics);
+            self.update_group_subscription(all_subscribed_topics)?;
+        }
+
+        if !self
+            .subscriptions
+            .subscription()
+            .is_subset(all_subscribed_topics)
+        {
+            self.log.debug(format_args!(
+                "Assignor {} assigned topics which are not part of the group subscription",
+                assignor_name
+            ));
+        }
+        Ok(())
+    }
+
+    fn update_group_subscription(&self, topics: &HashSet<String>) -> Result<()> {
+        // the leader will begin watching for changes to any of the topics the group is interested in,
+        // which ensures that all metadata changes will eventually be seen
+        if self.subscriptions.group_subscribe(topics.iter().cloned())? {
+            self.metadata.request_update_for_new_topics();
+        }
+
+        // update metadata (if needed) and keep track of the metadata used for assignment so that
+        // we can check after rebalance completion whether anything has changed
+        self.client
+            .ensure_fresh_metadata(&mut Timer::new(self.time.clone(), u128::MAX))?;
+
+        self.maybe_update_subscription_metadata()
+    }
+
+    /// Used by COOPERATIVE rebalance protocol only.
+    ///
+    /// Validate the assignments returned by the assignor such that no owned partitions are going to
+    /// be reassigned to a different consumer directly: if the assignor wants to reassign an owned partition,
+    /// it must first remove it from the new assignment of the current owner so that it is not assigned to any
+    /// member, and then in the next rebalance it can finally reassign those partitions not owned by anyone to consumers.
+    fn validate_cooperative_assignment(
+        &self,
+        owned_partitions: &IndexMap<String, Vec<TopicPartition>>,
+        assignments: &IndexMap<String, Assignment>,
+    ) -> Result<()> {
+        let mut total_revoked_partitions = HashSet::new();
+        let mut total_added_partitions = HashSet::new();
+        for (member_id, assignment) in assignments {
+            let owned = owned_partitions
+                .get(member_id)
+                .map(|partitions| partitions.iter().collect::<HashSet<_>>())
+                .unwrap_or_default();
+            let assigned: HashSet<_> = assignment.partitions.iter().collect();
+
+            total_added_partitions.extend(assigned.difference(&owned).cloned());
+            total_revoked_partitions.extend(owned.difference(&assigned).cloned());
+        }
+
+        // if there are overlap between revoked partitions and added partitions, it means some partitions
+        // immediately gets re-assigned to another member while it is still claimed by some member
+        let overlapped: Vec<_> = total_revoked_partitions
+            .intersection(&total_added_partitions)
+            .map(|tp| tp.to_string())
+            .collect();
+        if !overlapped.is_empty() {
+            self.log.error(format_args!(
+                "With the COOPERATIVE protocol, owned partitions cannot be reassigned to other members; however the assignor has reassigned partitions {:?} which are still owned by some members",
+                overlapped
+            ));
+            return Err(KafkaError::IllegalState(
+                "Assignor supporting the COOPERATIVE protocol violates its requirements".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Poll for coordinator events. This ensures that the coordinator is known and that the consumer
+    /// has joined the group (if it is using group management). This also handles periodic offset commits
+    /// if they are enabled.
+    ///
+    /// Returns true iff the operation succeeded
+    pub fn poll(&self, timer: &mut Timer, wait_for_join_group: bool) -> Result<bool> {
+        self.maybe_update_subscription_metadata()?;
+
+        self.invoke_completed_offset_commit_callbacks()?;
+
+        if self.subscriptions.has_auto_assigned_partitions() {
+            if self.protocol.is_none() {
+                return Err(KafkaError::IllegalState(
+                    "User configured partition.assignment.strategy to empty while trying to subscribe for group protocol to auto assign partitions".into(),
+                ));
+            }
+            // Always update the heartbeat last poll time so that the heartbeat thread does not leave the
+            // group proactively due to application inactivity even if (say) the coordinator cannot be found.
+            self.coordinator.poll_heartbeat(timer.current_time_ms())?;
+            if self.coordinator_unknown() && !self.ensure_coordinator_ready(timer)? {
+                return Ok(false);
+            }
+
+            if self.rejoin_needed_or_pending() {
+                // due to a race condition between the initial metadata fetch and the initial rebalance,
+                // we need to ensure that the metadata is fresh before joining initially. This ensures
+                // that we have matched the pattern against the cluster's topics at least once before joining.
+                if self.subscriptions.has_pattern_subscription() {
+                    // For consumer group that uses pattern-based subscription, after a topic is created,
+                    // any consumer that discovers the topic after metadata refresh can trigger rebalance
+                    // across the entire consumer group. Multiple rebalances can be triggered after one topic
+                    // creation if consumers refresh metadata at vastly different times. We can significantly
+                    // reduce the number of rebalances caused by single topic creation by asking consumer to
+                    // refresh metadata before re-joining the group as long as the refresh backoff time has
+                    // passed.
+                    if self.metadata.time_to_allow_update(timer.current_time_ms()) == 0 {
+                        self.metadata.request_update();
+                    }
+
+                    if !self.client.ensure_fresh_metadata(timer)? {
+                        return Ok(false);
+                    }
+
+                    self.maybe_update_subscription_metadata()?;
+                }
+
+                // if not wait for join group, we would just use a timer of 0
+                let joined = if wait_for_join_group {
+                    self.coordinator.ensure_active_group(self, timer)?
+                } else {
+                    self.coordinator
+                        .ensure_active_group(self, &mut Timer::new(self.time.clone(), 0))?
+                };
+                if !joined {
+                    // since we may use a different timer in the callee, we'd still need
+                    // to update the original timer's current time after the call
+                    timer.update();
+
+                    return Ok(false);
+                }
+            }
+        } else {
+            // For manually assigned partitions, if there are no ready nodes, await metadata.
+            // If connections to all nodes fail, wakeups triggered while attempting to send fetch
+            // requests result in polls returning immediately, causing a tight loop of polls. Without
+            // the wakeup, poll() with no channels would block for the timeout, delaying re-connection.
+            // await_metadata_update() initiates new connections with configured backoff and avoids the busy loop.
+            // When group management is used, metadata wait is already performed for this scenario as
+            // coordinator is unknown, hence this check is not required.
+            if self.metadata.update_requested()
+                && !self.client.has_ready_nodes(timer.current_time_ms())
+            {
+                self.client.await_metadata_update(timer)?;
+            }
+        }
+
+        self.maybe_auto_commit_offsets_async(timer.current_time_ms())?;
+        Ok(true)
+    }
+
+    /// Return the time to the next needed invocation of `poll`.
+    pub fn time_to_next_poll(&self, now: u128) -> u128 {
+        if !self.auto_commit_enabled {
+            return self.coordinator.time_to_next_heartbeat(now);
+        }
+        let mut next_auto_commit_timer = lock_timer(&self.next_auto_commit_timer);
+        next_auto_commit_timer.update_to(now);
+        next_auto_commit_timer
+            .remaining_ms()
+            .min(self.coordinator.time_to_next_heartbeat(now))
+    }
+
+    fn maybe_update_subscription_metadata(&self) -> Result<()> {
+        let version = self.metadata.update_version();
+        if version > self.lock().metadata_snapshot.version {
+            let cluster = self.metadata.fetch();
+
+            if self.subscriptions.has_pattern_subscription() {
+                self.update_pattern_subscription(&cluster)?;
+            }
+
+            // Update the current snapshot, which will be used to check for subscription
+            // changes that would require a rebalance (e.g. new partitions).
+            self.lock().metadata_snapshot =
+                MetadataSnapshot::new(&self.subscriptions, &cluster, version);
+        }
+        Ok(())
+    }
+
+    /// Check whether the group should be rejoined, either because the group coordinator asked for
+    /// it or because our subscription or the metadata of the assigned topics changed.
+    pub fn rejoin_needed_or_pending(&self) -> bool {
+        if !self.subscriptions.has_auto_assigned_partitions() {
+            return false;
+        }
+        self.rejoin_needed() || self.coordinator.rejoin_needed_or_pending()
+    }
+
+    /// Refresh the committed offsets for provided partitions.
+    ///
+    /// Returns true iff the operation completed within the timeout
+    pub fn refresh_committed_offsets_if_needed(&self, timer: &mut Timer) -> Result<bool> {
+        let initializing_partitions = self.subscriptions.initializing_partitions();
+
+        let offsets = match self.fetch_committed_offsets(&initializing_partitions, timer)? {
+            Some(offsets) => offsets,
+            None => return Ok(false),
+        };
+
+        for (tp, offset_and_metadata) in offsets {
+            if let Some(offset_and_metadata) = offset_and_metadata {
+                // first update the epoch if necessary
+                if let Some(epoch) = offset_and_metadata.leader_epoch {
+                    self.metadata.update_last_seen_epoch_if_newer(&tp, epoch);
+                }
+
+                // it's possible that the partition is no longer assigned when the response is received,
+                // so we need to ignore seeking if that's the case
+                if self.subscriptions.is_assigned(&tp) {
+                    let leader_and_epoch = self.metadata.current_leader(&tp);
+                    let position = FetchPosition::new(
+                        offset_and_metadata.offset,
+                        offset_and_metadata.leader_epoch,
+                        leader_and_epoch,
+                    );
+
+                    self.log.info(format_args!(
+                        "Setting offset for partition {} to the committed offset {}",
+                        tp, position
+                    ));
+                    self.subscriptions.seek_unvalidated(&tp, position)?;
+                } else {
+                    self.log.info(format_args!(
+                        "Ignoring the returned {} since its partition {} is no longer assigned",
+                        offset_and_metadata, tp
+                    ));
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Fetch the current committed offsets from the coordinator for a set of partitions.
+    ///
+    /// Returns a map from partition to the committed offset (`None` for partitions without a
+    /// committed offset), or `None` if we were not able to determine the offsets within the timeout.
+    pub fn fetch_committed_offsets(
+        &self,
+        partitions: &HashSet<TopicPartition>,
+        timer: &mut Timer,
+    ) -> Result<Option<CommittedOffsets>> {
+        if partitions.is_empty() {
+            return Ok(Some(IndexMap::new()));
+        }
+
+        let generation_for_offset_request = self.coordinator.generation_if_stable();
+        {
+            let mut state = self.lock();
+            let same_request = match &state.pending_committed_offset_request {
+                Some(pending) => pending.same_request(partitions, &generation_for_offset_request),
+                None => true,
+            };
+            if !same_request {
+                // if we were waiting for a different request, then just clear it.
+                state.pending_committed_offset_request = None;
+            }
+        }
+
+        loop {
+            if !self.ensure_coordinator_ready(timer)? {
+                return Ok(None);
+            }
+
+            // contact coordinator to fetch committed offsets
+            let pending = self
+                .lock()
+                .pending_committed_offset_request
+                .as_ref()
+                .map(|pending| pending.response.clone());
+            let future = match pending {
+                Some(future) => future,
+                None => {
+                    let future = self.send_offset_fetch_request(partitions);
+                    self.lock().pending_committed_offset_request =
+                        Some(PendingCommittedOffsetRequest {
+                            requested_partitions: partitions.clone(),
+                            requested_generation: generation_for_offset_request.clone(),
+                            response: future.clone(),
+                        });
+                    future
+                }
+            };
+            self.client.poll_future(&future, timer)?;
+
+            match future.result() {
+                Some(result) => {
+                    self.lock().pending_committed_offset_request = None;
+                    match result {
+                        Ok(offsets) => return Ok(Some(offsets)),
+                        Err(error) if !error.is_retriable() => return Err(error),
+                        Err(_) => timer.sleep(self.coordinator.rebalance_config().retry_backoff_ms),
+                    }
+                }
+                None => return Ok(None),
+            }
+
+            if timer.is_expired() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Close the coordinator, waiting if needed to send LeaveGroup.
+    pub fn close(&self, timer: &mut Timer) -> Result<()> {
+        // we do not need to re-enable wakeups since we are closing already
+        self.client.disable_wakeups();
+        let result = (|| {
+            self.maybe_auto_commit_offsets_sync(timer)?;
+            while self.pending_async_commits.load(Ordering::SeqCst) > 0 && timer.not_expired() {
+                self.ensure_coordinator_ready(timer)?;
+                self.client.poll(timer)?;
+                self.invoke_completed_offset_commit_callbacks()?;
+            }
+            Ok(())
+        })();
+        let close_result = self.coordinator.close(self, timer);
+        result.and(close_result)
+    }
+
+    // visible for testing
+    pub fn invoke_completed_offset_commit_callbacks(&self) -> Result<()> {
+        if self.async_commit_fenced.load(Ordering::SeqCst) {
+            return Err(KafkaError::FencedInstanceId(format!(
+                "Get fenced exception for group.instance.id {}, current member.id is {}",
+                self.coordinator
+                    .rebalance_config()
+                    .group_instance_id
+                    .as_deref()
+                    .unwrap_or("unset_instance_id"),
+                self.coordinator.member_id()
+            )));
+        }
+        loop {
+            let completion = lock_completions(&self.completed_offset_commits).pop_front();
+            match completion {
+                Some(completion) => completion.invoke(),
+                None => return Ok(()),
+            }
+        }
+    }
+
+    pub fn commit_offsets_async(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        callback: Option<OffsetCommitCallback>,
+    ) -> Result<()> {
+        self.invoke_completed_offset_commit_callbacks()?;
+
+        let callback = callback.unwrap_or_else(|| default_offset_commit_callback(self.log.clone()));
+        if !self.coordinator_unknown() {
+            self.do_commit_offsets_async(offsets, callback);
+        } else {
+            // we don't know the current coordinator, so try to find it and then send the commit
+            // or fail (we don't want recursive retries which can cause offset commits to arrive
+            // out of order). Note that there may be multiple offset commits chained to the same
+            // coordinator lookup request. This is fine because the listeners will be invoked in
+            // the same order that they were added. Note also that AbstractCoordinator prevents
+            // multiple concurrent coordinator lookup requests.
+            self.pending_async_commits.fetch_add(1, Ordering::SeqCst);
+            let pending_async_commits = self.pending_async_commits.clone();
+            let completed_offset_commits = self.completed_offset_commits.clone();
+            let async_commit_fenced = self.async_commit_fenced.clone();
+            let coordinator = self.coordinator.clone();
+            let subscriptions = self.subscriptions.clone();
+            let log = self.log.clone();
+            self.coordinator
+                .lookup_coordinator()
+                .add_listener(move |result| {
+                    pending_async_commits.fetch_sub(1, Ordering::SeqCst);
+                    match result {
+                        Ok(()) => {
+                            let future = send_offset_commit_request(
+                                &log,
+                                &coordinator,
+                                &subscriptions,
+                                offsets.clone(),
+                            );
+                            add_commit_listener(
+                                &future,
+                                offsets,
+                                callback,
+                                completed_offset_commits,
+                                async_commit_fenced,
+                            );
+                        }
+                        Err(error) => {
+                            lock_completions(&completed_offset_commits).push_back(
+                                OffsetCommitCompletion {
+                                    callback,
+                                    offsets,
+                                    exception: Some(KafkaError::RetriableCommitFailed(
+                                        error.to_string(),
+                                    )),
+                                },
+                            );
+                        }
+                    }
+                });
+        }
+
+        // ensure the commit has a chance to be transmitted (without blocking on its completion).
+        // Note that commits are treated as heartbeats by the coordinator, so there is no need to
+        // explicitly allow heartbeats through delayed task execution.
+        self.client.poll_no_wakeup()
+    }
+
+    fn do_commit_offsets_async(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        callback: OffsetCommitCallback,
+    ) {
+        let future = send_offset_commit_request(
+            &self.log,
+            &self.coordinator,
+            &self.subscriptions,
+            offsets.clone(),
+        );
+        add_commit_listener(
+            &future,
+            offsets,
+            callback,
+            self.completed_offset_commits.clone(),
+            self.async_commit_fenced.clone(),
+        );
+    }
+
+    /// Commit offsets synchronously. This method will retry until the commit completes successfully
+    /// or an unrecoverable error is encountered.
+    ///
+    /// Returns true if the offset commit was successfully sent and a successful response was received from
+    /// the coordinator
+    pub fn commit_offsets_sync(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        timer: &mut Timer,
+    ) -> Result<bool> {
+        self.invoke_completed_offset_commit_callbacks()?;
+
+        if offsets.is_empty() {
+            return Ok(true);
+        }
+
+        loop {
+            if self.coordinator_unknown() && !self.ensure_coordinator_ready(timer)? {
+                return Ok(false);
+            }
+
+            let future = send_offset_commit_request(
+                &self.log,
+                &self.coordinator,
+                &self.subscriptions,
+                offsets.clone(),
+            );
+            self.client.poll_future(&future, timer)?;
+
+            // We may have had in-flight offset commits when the synchronous commit began. If so, ensure that
+            // the corresponding callbacks are invoked prior to returning in order to preserve the order that
+            // the offset commits were applied.
+            self.invoke_completed_offset_commit_callbacks()?;
+
+            if future.succeeded() {
+                return Ok(true);
+            }
+
+            if let Some(error) = future.exception() {
+                if !error.is_retriable() {
+                    return Err(error);
+                }
+            }
+
+            timer.sleep(self.coordinator.rebalance_config().retry_backoff_ms);
+            if timer.is_expired() {
+                return Ok(false);
+            }
+        }
+    }
+
+    pub fn maybe_auto_commit_offsets_async(&self, now: u128) -> Result<()> {
+        if self.auto_commit_enabled {
+            let expired = {
+                let mut next_auto_commit_timer = lock_timer(&self.next_auto_commit_timer);
+                next_auto_commit_timer.update_to(now);
+                if next_auto_commit_timer.is_expired() {
+                    next_auto_commit_timer.reset(self.auto_commit_interval_ms);
+                    true
+                } else {
+                    false
+                }
+            };
+            if expired {
+                self.do_auto_commit_offsets_async()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn do_auto_commit_offsets_async(&self) -> Result<()> {
+        let all_consumed_offsets = self.subscriptions.all_consumed();
+        self.log.debug(format_args!(
+            "Sending asynchronous auto-commit of offsets {}",
+            display_offsets(&all_consumed_offsets)
+        ));
+
+        let next_auto_commit_timer = self.next_auto_commit_timer.clone();
+        let retry_backoff_ms = self.coordinator.rebalance_config().retry_backoff_ms;
+        let log = self.log.clone();
+        self.commit_offsets_async(
+            all_consumed_offsets,
+            Some(Box::new(move |offsets, exception| match exception {
+                Some(exception @ KafkaError::RetriableCommitFailed(_)) => {
+                    log.debug(format_args!(
+                        "Asynchronous auto-commit of offsets {} failed due to retriable error: {}",
+                        display_offsets(offsets),
+                        exception
+                    ));
+                    lock_timer(&next_auto_commit_timer).update_and_reset(retry_backoff_ms);
+                }
+                Some(exception) => log.warn(format_args!(
+                    "Asynchronous auto-commit of offsets {} failed: {}",
+                    display_offsets(offsets),
+                    exception
+                )),
+                None => log.debug(format_args!(
+                    "Completed asynchronous auto-commit of offsets {}",
+                    display_offsets(offsets)
+                )),
+            })),
+        )
+    }
+
+    fn maybe_auto_commit_offsets_sync(&self, timer: &mut Timer) -> Result<()> {
+        if self.auto_commit_enabled {
+            let all_consumed_offsets = self.subscriptions.all_consumed();
+            self.log.debug(format_args!(
+                "Sending synchronous auto-commit of offsets {}",
+                display_offsets(&all_consumed_offsets)
+            ));
+            match self.commit_offsets_sync(all_consumed_offsets.clone(), timer) {
+                Ok(true) => {}
+                Ok(false) => self.log.debug(format_args!(
+                    "Auto-commit of offsets {} timed out before completion",
+                    display_offsets(&all_consumed_offsets)
+                )),
+                Err(error @ KafkaError::Wakeup(_)) | Err(error @ KafkaError::Interrupt(_)) => {
+                    self.log.debug(format_args!(
+                        "Auto-commit of offsets {} was interrupted before completion",
+                        display_offsets(&all_consumed_offsets)
+                    ));
+                    // rethrow wakeups since they are triggered by the user
+                    return Err(error);
+                }
+                Err(error) => {
+                    // consistent with async auto-commit failures, we do not propagate the exception
+                    self.log.warn(format_args!(
+                        "Synchronous auto-commit of offsets {} failed: {}",
+                        display_offsets(&all_consumed_offsets),
+                        error
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Send an offset fetch request to the coordinator for the given partitions.
+    fn send_offset_fetch_request(
+        &self,
+        partitions: &HashSet<TopicPartition>,
+    ) -> Arc<RequestFuture<CommittedOffsets>> {
+        let coordinator = match self.coordinator.check_and_get_coordinator() {
+            Some(coordinator) => coordinator,
+            None => return RequestFuture::coordinator_not_available(),
+        };
+
+        let partitions = sorted(partitions);
+        self.log.debug(format_args!(
+            "Fetching committed offsets for partitions: {}",
+            join(&partitions)
+        ));
+        // construct the request
+        let request = OffsetFetchRequest::new(
+            self.coordinator.rebalance_config().group_id.clone(),
+            true,
+            Some(partitions),
+        );
+
+        // send the request with a callback
+        let log = self.log.clone();
+        self.coordinator.send_coordinator_request(
+            &coordinator,
+            AbstractRequest::OffsetFetch(request),
+            self.client.default_request_timeout_ms(),
+            move |coordinator, _, response, future| match response.response_body {
+                Some(AbstractResponse::OffsetFetch(offset_fetch_response)) => {
+                    handle_offset_fetch_response(&log, coordinator, offset_fetch_response, future)
+                }
+                _ => future.raise(unexpected_response("OffsetFetch", &response)),
+            },
+        )
+    }
+}
+
+impl GroupProtocolHandler for ConsumerCoordinator {
+    fn metadata(&self) -> Result<Vec<JoinGroupRequestProtocol>> {
+        let joined_subscription = self.subscriptions.subscription();
+        self.log.debug(format_args!(
+            "Joining group with current subscription: {:?}",
+            joined_subscription
+        ));
+        self.lock().joined_subscription = joined_subscription.clone();
+
+        let topics: Vec<String> = joined_subscription.iter().cloned().collect();
+        let owned_partitions = self.subscriptions.assigned_partitions_list();
+        self.assignors
+            .iter()
+            .map(|assignor| {
+                let subscription = Subscription::new(
+                    topics.clone(),
+                    assignor.subscription_user_data(&joined_subscription),
+                    owned_partitions.clone(),
+                );
+                let metadata = consumer_protocol::serialize_subscription(&subscription)?;
+                Ok(JoinGroupRequestProtocol::new(assignor.name(), metadata))
+            })
+            .collect()
+    }
+
+    fn on_join_prepare(&self, generation: i32, member_id: &str) -> Result<()> {
+        self.log.debug(format_args!(
+            "Executing onJoinPrepare with generation {} and memberId {}",
+            generation, member_id
+        ));
+        // commit offsets prior to rebalance if auto-commit enabled
+        self.maybe_auto_commit_offsets_sync(&mut Timer::new(
+            self.time.clone(),
+            self.coordinator.rebalance_config().rebalance_timeout_ms,
+        ))?;
+
+        // the generation / member-id can possibly be reset by the heartbeat thread
+        // upon getting errors or heartbeat timeouts; in this case whatever is previously
+        // owned partitions would be lost, we should trigger the callback and cleanup the assignment;
+        // otherwise we can proceed normally and revoke the partitions depending on the protocol,
+        // and in that case we should only change the assignment AFTER the revoke callback is triggered
+        // so that users can still access the previously owned partitions to commit offsets etc.
+        let mut exception = None;
+        let no_generation = Generation::no_generation();
+        if generation == no_generation.generation_id && member_id == no_generation.member_id {
+            let revoked_partitions = self.subscriptions.assigned_partitions();
+
+            if !revoked_partitions.is_empty() {
+                self.log.info(format_args!("Giving away all assigned partitions as lost since generation has been reset, indicating that consumer is no longer part of the group"));
+                exception = self.invoke_partitions_lost(&revoked_partitions)?;
+
+                self.subscriptions.assign_from_subscribed(vec![])?;
+            }
+        } else {
+            match self.protocol {
+                Some(RebalanceProtocol::Eager) | None => {
+                    // revoke all partitions
+                    let revoked_partitions = self.subscriptions.assigned_partitions();
+                    exception = self.invoke_partitions_revoked(&revoked_partitions)?;
+
+                    self.subscriptions.assign_from_subscribed(vec![])?;
+                }
+                Some(RebalanceProtocol::Cooperative) => {
+                    // only revoke those partitions that are not in the subscription any more.
+                    let subscription = self.subscriptions.subscription();
+                    let (revoked_partitions, owned_partitions): (HashSet<_>, HashSet<_>) = self
+                        .subscriptions
+                        .assigned_partitions()
+                        .into_iter()
+                        .partition(|tp| !subscription.contains(&tp.topic));
+
+                    if !revoked_partitions.is_empty() {
+                        exception = self.invoke_partitions_revoked(&revoked_partitions)?;
+
+                        self.subscriptions
+                            .assign_from_subscribed(sorted(&owned_partitions))?;
+                    }
+                }
+            }
+        }
+
+        self.lock().is_leader = false;
+        self.subscriptions.reset_group_subscription();
+
+        match exception {
+            Some(exception) => Err(KafkaError::Kafka(format!(
+                "User rebalance callback throws an error: {}",
+                exception
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn perform_assignment(
+        &self,
+        leader_id: &str,
+        assignment_strategy: &str,
+        all_subscriptions: &[JoinGroupResponseMember],
+    ) -> Result<IndexMap<String, Bytes>> {
+        let assignor = self
+            .lookup_assignor(Some(assignment_strategy))
+            .ok_or_else(|| {
+                KafkaError::IllegalState(format!(
+                    "Coordinator selected invalid assignment protocol: {}",
+                    assignment_strategy
+                ))
+            })?;
+        let assignor_name = assignor.name();
+
+        let mut all_subscribed_topics = HashSet::new();
+        let mut subscriptions = IndexMap::new();
+
+        // collect all the owned partitions
+        let mut owned_partitions = IndexMap::new();
+
+        for member_subscription in all_subscriptions {
+            let mut subscription =
+                consumer_protocol::deserialize_subscription(member_subscription.metadata.clone())?;
+            subscription.group_instance_id = member_subscription.group_instance_id.clone();
+            all_subscribed_topics.extend(subscription.topics.iter().cloned());
+            owned_partitions.insert(
+                member_subscription.member_id.clone(),
+                subscription.owned_partitions.clone(),
+            );
+            subscriptions.insert(member_subscription.member_id.clone(), subscription);
+        }
+
+        // the leader will begin watching for changes to any of the topics the group is interested in,
+        // which ensures that all metadata changes will eventually be seen
+        self.update_group_subscription(&all_subscribed_topics)?;
+
+        self.lock().is_leader = true;
+
+        self.log.debug(format_args!(
+            "Performing assignment using strategy {} with subscriptions {:?}",
+            assignor_name, subscriptions
+        ));
+
+        let assignments = assignor
+            .assign(
+                &self.metadata.fetch(),
+                &GroupSubscription::new(subscriptions),
+            )?
+            .assignments;
+
+        // skip the validation for built-in cooperative sticky assignor since we've considered
+        // the "generation" of owned partition inside the assignor
+        if self.protocol == Some(RebalanceProtocol::Cooperative)
+            && assignor_name != COOPERATIVE_STICKY_ASSIGNOR_NAME
+        {
+            self.validate_cooperative_assignment(&owned_partitions, &assignments)?;
+        }
+
+        self.maybe_update_group_subscription(
+            assignor_name,
+            &assignments,
+            &mut all_subscribed_topics,
+        )?;
+
+        {
+            let mut state = self.lock();
+            state.assignment_snapshot = Some(state.metadata_snapshot.clone());
+        }
+
+        self.log.info(format_args!(
+            "Finished assignment for group at generation {} with leader {}: {:?}",
+            self.coordinator.generation().generation_id,
+            leader_id,
+            assignments
+        ));
+
+        assignments
+            .iter()
+            .map(|(member_id, assignment)| {
+                Ok((
+                    member_id.clone(),
+                    consumer_protocol::serialize_assignment(assignment)?,
+                ))
+            })
+            .collect()
+    }
+
+    fn on_join_complete(
+        &self,
+        generation: i32,
+        member_id: &str,
+        assignment_strategy: Option<&str>,
+        assignment_buffer: Bytes,
+    ) -> Result<()> {
+        self.log.debug(format_args!(
+            "Executing onJoinComplete with generation {} and memberId {}",
+            generation, member_id
+        ));
+
+        // Only the leader is responsible for monitoring for metadata changes (i.e. partition changes)
+        {
+            let mut state = self.lock();
+            if !state.is_leader {
+                state.assignment_snapshot = None;
+            }
+        }
+
+        let assignor = self.lookup_assignor(assignment_strategy).ok_or_else(|| {
+            KafkaError::IllegalState(format!(
+                "Coordinator selected invalid assignment protocol: {}",
+                assignment_strategy.unwrap_or("null")
+            ))
+        })?;
+
+        // Give the assignor a chance to update internal state based on the received assignment
+        let group_metadata = ConsumerGroupMetadata::new(
+            self.coordinator.rebalance_config().group_id.clone(),
+            generation,
+            member_id,
+            self.coordinator
+                .rebalance_config()
+                .group_instance_id
+                .clone(),
+        );
+        self.lock().group_metadata = group_metadata.clone();
+
+        let owned_partitions = self.subscriptions.assigned_partitions();
+
+        // should at least encode the short version
+        if assignment_buffer.len() < 2 {
+            return Err(KafkaError::IllegalState(format!(
+                "There are insufficient bytes available to read assignment from the sync-group response (actual byte size {}) , this is not expected; it is possible that the leader's assign function is buggy and did not return any assignment for this member, or because static member is configured and the protocol is buggy hence did not get the assignment for this member",
+                assignment_buffer.len()
+            )));
+        }
+
+        let assignment = consumer_protocol::deserialize_assignment(assignment_buffer)?;
+
+        let assigned_partitions: HashSet<TopicPartition> =
+            assignment.partitions.iter().cloned().collect();
+
+        if !self
+            .subscriptions
+            .check_assignment_matched_subscription(&assigned_partitions)
+        {
+            let reason = format!(
+                "received assignment {} does not match the current subscription {}; it is likely that the subscription has changed since we joined the group, will re-join with current subscription",
+                assignment,
+                self.subscriptions.pretty_string()
+            );
+            self.request_rejoin(&reason);
+
+            return Ok(());
+        }
+
+        let mut first_exception = None;
+        let added_partitions: HashSet<TopicPartition> = assigned_partitions
+            .difference(&owned_partitions)
+            .cloned()
+            .collect();
+
+        if self.protocol == Some(RebalanceProtocol::Cooperative) {
+            let revoked_partitions: HashSet<TopicPartition> = owned_partitions
+                .difference(&assigned_partitions)
+                .cloned()
+                .collect();
+
+            self.log.info(format_args!(
+                "Updating assignment with\n\tAssigned partitions:                       {}\n\tCurrent owned partitions:                  {}\n\tAdded partitions (assigned - owned):       {}\n\tRevoked partitions (owned - assigned):     {}\n",
+                join(&sorted(&assigned_partitions)),
+                join(&sorted(&owned_partitions)),
+                join(&sorted(&added_partitions)),
+                join(&sorted(&revoked_partitions))
+            ));
+
+            if !revoked_partitions.is_empty() {
+                // Revoke partitions that were previously owned but no longer assigned;
+                // note that we should only change the assignment (or update the assignor's state)
+                // AFTER we've triggered the revoke callback
+                first_exception = self.invoke_partitions_revoked(&revoked_partitions)?;
+
+                // If revoked any partitions, need to re-join the group afterwards
+                let reason = format!(
+                    "need to revoke partitions [{}] as indicated by the current assignment and re-join",
+                    join(&sorted(&revoked_partitions))
+                );
+                self.request_rejoin(&reason);
+            }
+        }
+
+        // The leader may have assigned partitions which match our subscription pattern, but which
+        // were not explicitly requested, so we update the joined subscription here.
+        self.maybe_update_joined_subscription(&assigned_partitions)?;
+
+        self.invoke_on_assignment(assignor, &assignment, &group_metadata);
+
+        // Reschedule the auto commit starting from now
+        if self.auto_commit_enabled {
+            lock_timer(&self.next_auto_commit_timer).update_and_reset(self.auto_commit_interval_ms);
+        }
+
+        self.subscriptions
+            .assign_from_subscribed(assignment.partitions.iter().cloned())?;
+
+        // Add partitions that were not previously owned but are now assigned
+        let assigned_exception = self.invoke_partitions_assigned(&added_partitions)?;
+        match first_exception.or(assigned_exception) {
+            Some(exception @ KafkaError::Kafka(_)) => Err(exception),
+            Some(exception) => Err(KafkaError::Kafka(format!(
+                "User rebalance callback throws an error: {}",
+                exception
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn on_leave_prepare(&self) -> Result<()> {
+        // Save the current Generation and use that to get the member id, as the hb thread can change it at any time
+        let current_generation = self.coordinator.generation();
+        self.log.debug(format_args!(
+            "Executing onLeavePrepare with generation {}",
+            current_generation
+        ));
+
+        // we should reset assignment and trigger the callback before leaving group
+        let dropped_partitions = self.subscriptions.assigned_partitions();
+
+        if self.subscriptions.has_auto_assigned_partitions() && !dropped_partitions.is_empty() {
+            let exception = if current_generation == Generation::no_generation()
+                || self.coordinator.rebalance_in_progress()
+            {
+                self.invoke_partitions_lost(&dropped_partitions)?
+            } else {
+                self.invoke_partitions_revoked(&dropped_partitions)?
+            };
+
+            self.subscriptions.assign_from_subscribed(vec![])?;
+
+            if let Some(exception) = exception {
+                return Err(KafkaError::Kafka(format!(
+                    "User rebalance callback throws an error: {}",
+                    exception
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn rejoin_needed(&self) -> bool {
+        if !self.subscriptions.has_auto_assigned_partitions() {
+            return false;
+        }
+
+        let reason = {
+            let state = self.lock();
+            // we need to rejoin if we performed the assignment and metadata has changed;
+            // also for those owned-but-no-longer-existed partitions we should drop them as lost
+            match &state.assignment_snapshot {
+                Some(assignment_snapshot)
+                    if !assignment_snapshot.matches(&state.metadata_snapshot) =>
+                {
+                    Some(format!(
+                        "cached metadata has changed from {:?} at the beginning of the rebalance to {:?}",
+                        assignment_snapshot.partitions_per_topic,
+                        state.metadata_snapshot.partitions_per_topic
+                    ))
+                }
+                _ => {
+                    // we need to join if our subscription has changed since the last join
+                    let subscription = self.subscriptions.subscription();
+                    if !state.joined_subscription.is_empty()
+                        && state.joined_subscription != subscription
+                    {
+                        Some(format!(
+                            "subscription has changed from topics {:?} to topics {:?}",
+                            state.joined_subscription, subscription
+                        ))
+                    } else {
+                        None
+                    }
+                }
+            }
+        };
+
+        match reason {
+            Some(reason) => {
+                self.request_rejoin(&reason);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn send_offset_commit_request(
+    log: &Logger,
+    coordinator: &Arc<AbstractCoordinator>,
+    subscriptions: &SubscriptionState,
+    offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+) -> Arc<RequestFuture<()>> {
+    if offsets.is_empty() {
+        return RequestFuture::completed(());
+    }
+
+    let node = match coordinator.check_and_get_coordinator() {
+        Some(node) => node,
+        None => return RequestFuture::coordinator_not_available(),
+    };
+
+    // create the offset commit request
+    let mut offset_data = IndexMap::with_capacity(offsets.len());
+    for (tp, offset_and_metadata) in &offsets {
+        if offset_and_metadata.offset < 0 {
+            return RequestFuture::failure(KafkaError::IllegalArgument(format!(
+                "Invalid offset: {}",
+                offset_and_metadata.offset
+            )));
+        }
+        offset_data.insert(
+            tp.clone(),
+            CommittedOffset::new(
+                offset_and_metadata.offset,
+                offset_and_metadata.metadata.clone(),
+                offset_and_metadata.leader_epoch,
+            ),
+        );
+    }
+
+    let generation = if subscriptions.has_auto_assigned_partitions() {
+        match coordinator.generation_if_stable() {
+            Some(generation) => generation,
+            None => {
+                // if the generation is None, we are not part of an active group (and we expect to be).
+                // the only thing we can do is fail the commit and let the user rejoin the group in poll().
+                log.info(format_args!("Failing OffsetCommit request since the consumer is not part of an active group"));
+
+                return if coordinator.rebalance_in_progress() {
+                    // if the client knows it is already rebalancing, we can use RebalanceInProgress instead of
+                    // CommitFailed to indicate this is not a fatal error
+                    RequestFuture::failure(KafkaError::RebalanceInProgress(
+                        "Offset commit cannot be completed since the consumer is undergoing a rebalance for auto partition assignment. You can try completing the rebalance by calling poll() and then retry the operation.".into(),
+                    ))
+                } else {
+                    RequestFuture::failure(KafkaError::CommitFailed(
+                        "Offset commit cannot be completed since the consumer is not part of an active group for auto partition assignment; it is likely that the consumer was kicked out of the group.".into(),
+                    ))
+                };
+            }
+        }
+    } else {
+        Generation::no_generation()
+    };
+
+    let request = OffsetCommitRequest::new(
+        coordinator.rebalance_config().group_id.clone(),
+        generation.generation_id,
+        generation.member_id,
+        coordinator.rebalance_config().group_instance_id.clone(),
+        offset_data,
+    );
+
+    log.trace(format_args!(
+        "Sending OffsetCommit request with {} to coordinator {}",
+        display_offsets(&offsets),
+        node
+    ));
+
+    let log = log.clone();
+    coordinator.send_coordinator_request(
+        &node,
+        AbstractRequest::OffsetCommit(request),
+        coordinator.client().default_request_timeout_ms(),
+        move |coordinator, _, response, future| match response.response_body {
+            Some(AbstractResponse::OffsetCommit(commit_response)) => {
+                handle_offset_commit_response(&log, coordinator, &offsets, commit_response, future)
+            }
+            _ => future.raise(unexpected_response("OffsetCommit", &response)),
+        },
+    )
+}
+
+fn handle_offset_commit_response(
+    log: &Logger,
+    coordinator: &Arc<AbstractCoordinator>,
+    offsets: &IndexMap<TopicPartition, OffsetAndMetadata>,
+    commit_response: OffsetCommitResponse,
+    future: &Arc<RequestFuture<()>>,
+) {
+    let mut unauthorized_topics = HashSet::new();
+
+    for (tp, error) in commit_response.errors {
+        let offset = offsets
+            .get(&tp)
+            .map(|offset_and_metadata| offset_and_metadata.offset)
+            .unwrap_or(-1);
+
+        if error == Errors::None {
+            log.debug(format_args!(
+                "Committed offset {} for partition {}",
+                offset, tp
+            ));
+            continue;
+        }
+
+        if matches!(error.exception(None), Some(e) if e.is_retriable()) {
+            log.warn(format_args!(
+                "Offset commit failed on partition {} at offset {}: {}",
+                tp,
+                offset,
+                error.message()
+            ));
+        } else {
+            log.error(format_args!(
+                "Offset commit failed on partition {} at offset {}: {}",
+                tp,
+                offset,
+                error.message()
+            ));
+        }
+
+        match error {
+            Errors::GroupAuthorizationFailed => {
+                future.raise(group_authorization_exception(
+                    &coordinator.rebalance_config().group_id,
+                ));
+                return;
+            }
+            Errors::TopicAuthorizationFailed => {
+                unauthorized_topics.insert(tp.topic.clone());
+            }
+            Errors::OffsetMetadataTooLarge | Errors::InvalidCommitOffsetSize => {
+                // raise the error to the user
+                future.raise(exception(error));
+                return;
+            }
+            Errors::CoordinatorLoadInProgress | Errors::UnknownTopicOrPartition => {
+                // just retry
+                future.raise(exception(error));
+                return;
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator | Errors::RequestTimedOut => {
+                coordinator.mark_coordinator_unknown_for_error(error);
+                future.raise(exception(error));
+                return;
+            }
+            Errors::FencedInstanceId => {
+                log.info(format_args!(
+                    "OffsetCommit failed with {} due to group instance id {} fenced",
+                    coordinator.generation(),
+                    coordinator
+                        .rebalance_config()
+                        .group_instance_id
+                        .as_deref()
+                        .unwrap_or("")
+                ));
+
+                future.raise(exception(error));
+                return;
+            }
+            Errors::RebalanceInProgress => {
+                // Consumer should not try to commit offset in between join-group and sync-group,
+                // and hence on broker-side it is not expected to see a commit offset request
+                // during CompletingRebalance phase; if it ever happens then broker would return
+                // this error to indicate that we are still in the middle of a rebalance.
+                // In this case we would throw a RebalanceInProgress error,
+                // request re-join but do not reset generations. If the callers decide to retry,
+                // they can go ahead and call poll to finish up the rebalance first, and then
+                // try commit again.
+                coordinator
+                    .request_rejoin("offset commit failed since group is already rebalancing");
+                future.raise(KafkaError::RebalanceInProgress(
+                    "Offset commit cannot be completed since the consumer group is executing a rebalance at the moment. You can try completing the rebalance by calling poll() and then retry commit again".into(),
+                ));
+                return;
+            }
+            Errors::UnknownMemberId | Errors::IllegalGeneration => {
+                log.info(format_args!(
+                    "OffsetCommit failed with {}: {}",
+                    coordinator.generation(),
+                    error.message()
+                ));
+
+                // need to reset generation and re-join group
+                coordinator.reset_generation_on_response_error(ApiKeys::OffsetCommit, error);
+                future.raise(KafkaError::CommitFailed(
+                    "Commit cannot be completed since the group has already rebalanced and assigned the partitions to another member. This means that the time between subsequent calls to poll() was longer than the configured max.poll.interval.ms, which typically implies that the poll loop is spending too much time message processing. You can address this either by increasing max.poll.interval.ms or by reducing the maximum size of batches returned in poll() with max.poll.records.".into(),
+                ));
+                return;
+            }
+            _ => {
+                future.raise(KafkaError::Kafka(format!(
+                    "Unexpected error in commit: {}",
+                    error.message()
+                )));
+                return;
+            }
+        }
+    }
+
+    if !unauthorized_topics.is_empty() {
+        let mut topics: Vec<_> = unauthorized_topics.into_iter().collect();
+        topics.sort();
+        log.error(format_args!(
+            "Not authorized to commit to topics {:?} for group {}",
+            topics,
+            coordinator.rebalance_config().group_id
+        ));
+        future.raise(KafkaError::TopicAuthorization(format!(
+            "Not authorized to access topics: {:?}",
+            topics
+        )));
+    } else {
+        future.complete(());
+    }
+}
+
+fn handle_offset_fetch_response(
+    log: &Logger,
+    coordinator: &Arc<AbstractCoordinator>,
+    response: OffsetFetchResponse,
+    future: &Arc<RequestFuture<CommittedOffsets>>,
+) {
+    if response.has_error() {
+        let error = response.error;
+        log.debug(format_args!("Offset fetch failed: {}", error.message()));
+
+        match error {
+            // just retry
+            Errors::CoordinatorLoadInProgress => future.raise(exception(error)),
+            Errors::NotCoordinator => {
+                // re-discover the coordinator and retry
+                coordinator.mark_coordinator_unknown_for_error(error);
+                future.raise(exception(error));
+            }
+            Errors::GroupAuthorizationFailed => future.raise(group_authorization_exception(
+                &coordinator.rebalance_config().group_id,
+            )),
+            _ => future.raise(KafkaError::Kafka(format!(
+                "Unexpected error in fetch offset response: {}",
+                error.message()
+            ))),
+        }
+        return;
+    }
+
+    let mut unauthorized_topics = HashSet::new();
+    let mut offsets = IndexMap::with_capacity(response.response_data.len());
+    let mut unstable_txn_offset_topic_partitions = vec![];
+    for (tp, partition_data) in response.response_data {
+        if partition_data.has_error() {
+            let error = partition_data.error;
+            log.debug(format_args!(
+                "Failed to fetch offset for partition {}: {}",
+                tp,
+                error.message()
+            ));
+
+            match error {
+                Errors::UnknownTopicOrPartition => {
+                    future.raise(KafkaError::Kafka(format!(
+                        "Topic or Partition {} does not exist",
+                        tp
+                    )));
+                    return;
+                }
+                Errors::TopicAuthorizationFailed => {
+                    unauthorized_topics.insert(tp.topic.clone());
+                }
+                Errors::UnstableOffsetCommit => {
+                    unstable_txn_offset_topic_partitions.push(tp);
+                }
+                _ => {
+                    future.raise(KafkaError::Kafka(format!(
+                        "Unexpected error in fetch offset response for partition {}: {}",
+                        tp,
+                        error.message()
+                    )));
+                    return;
+                }
+            }
+        } else if partition_data.offset >= 0 {
+            // record the position with the offset (-1 indicates no committed offset to fetch);
+            // if there's no committed offset, record as None
+            offsets.insert(
+                tp,
+                Some(OffsetAndMetadata::new(
+                    partition_data.offset,
+                    partition_data.leader_epoch,
+                    partition_data.metadata.unwrap_or_default(),
+                )),
+            );
+        } else {
+            log.info(format_args!(
+                "Found no committed offset for partition {}",
+                tp
+            ));
+            offsets.insert(tp, None);
+        }
+    }
+
+    if !unauthorized_topics.is_empty() {
+        let mut topics: Vec<_> = unauthorized_topics.into_iter().collect();
+        topics.sort();
+        future.raise(KafkaError::TopicAuthorization(format!(
+            "Not authorized to access topics: {:?}",
+            topics
+        )));
+    } else if !unstable_txn_offset_topic_partitions.is_empty() {
+        // just retry
+        log.info(format_args!(
+            "The following partitions still have unstable offsets which are not cleared on the broker side: [{}], this could be either transactional offsets waiting for completion, or normal offsets waiting for replication after appending to local log",
+            join(&unstable_txn_offset_topic_partitions)
+        ));
+        future.raise(KafkaError::UnstableOffsetCommit(
+            "There are unstable offsets for the requested topic partitions".into(),
+        ));
+    } else {
+        future.complete(offsets);
+    }
+}
+
+fn add_commit_listener(
+    future: &Arc<RequestFuture<()>>,
+    offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+    callback: OffsetCommitCallback,
+    completed_offset_commits: Arc<Mutex<VecDeque<OffsetCommitCompletion>>>,
+    async_commit_fenced: Arc<AtomicBool>,
+) {
+    future.add_listener(move |result| {
+        let exception = match result {
+            Ok(()) => None,
+            Err(error) if error.is_retriable() => {
+                Some(KafkaError::RetriableCommitFailed(error.to_string()))
+            }
+            Err(error) => {
+                if let KafkaError::FencedInstanceId(_) = error {
+                    async_commit_fenced.store(true, Ordering::SeqCst);
+                }
+                Some(error.clone())
+            }
+        };
+        lock_completions(&completed_offset_commits).push_back(OffsetCommitCompletion {
+            callback,
+            offsets,
+            exception,
+        });
+    });
+}
+
+fn default_offset_commit_callback(log: Logger) -> OffsetCommitCallback {
+    Box::new(move |offsets, exception| {
+        if let Some(exception) = exception {
+            log.error(format_args!(
+                "Offset commit with offsets {} failed: {}",
+                display_offsets(offsets),
+                exception
+            ));
+        }
+    })
+}
+
+/// Split errors returned from user callbacks: wakeups and interrupts are triggered by the user
+/// and are propagated immediately, other errors are reported once the rebalance step completed.
+fn callback_error(
+    log: &Logger,
+    result: Result<()>,
+    callback: &str,
+    partitions: &[TopicPartition],
+) -> Result<Option<KafkaError>> {
+    match result {
+        Ok(()) => Ok(None),
+        Err(error @ KafkaError::Wakeup(_)) | Err(error @ KafkaError::Interrupt(_)) => Err(error),
+        Err(error) => {
+            log.error(format_args!(
+                "User provided listener failed on invocation of {} for partitions [{}]: {}",
+                callback,
+                join(partitions),
+                error
+            ));
+            Ok(Some(error))
+        }
+    }
+}
+
+fn lock_timer(timer: &Mutex<Timer>) -> MutexGuard<'_, Timer> {
+    timer.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock_completions(
+    completions: &Mutex<VecDeque<OffsetCommitCompletion>>,
+) -> MutexGuard<'_, VecDeque<OffsetCommitCompletion>> {
+    completions.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn sorted(partitions: &HashSet<TopicPartition>) -> Vec<TopicPartition> {
+    let mut partitions: Vec<_> = partitions.iter().cloned().collect();
+    partitions.sort();
+    partitions
+}
+
+fn join(partitions: &[TopicPartition]) -> String {
+    partitions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn display_offsets(offsets: &IndexMap<TopicPartition, OffsetAndMetadata>) -> String {
+    let offsets: Vec<_> = offsets
+        .iter()
+        .map(|(tp, offset_and_metadata)| format!("{}={}", tp, offset_and_metadata))
+        .collect();
+    format!("{{{}}}", offsets.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bytes::Bytes;
+
+    use crate::{
+        clients::{
+            consumer::{
+                consumer_partition_assignor::{Assignment, Subscription},
+                internals::{
+                    abstract_coordinator::MemberState,
+                    consumer_network_client::ConsumerNetworkClient, consumer_protocol,
+                    subscription_state::SubscriptionState,
+                },
+                offset_reset_strategy::OffsetResetStrategy,
+                range_assignor::RangeAssignor,
+            },
+            group_rebalance_config::GroupRebalanceConfig,
+            metadata::Metadata,
+            mock_client::MockClient,
+        },
+        common::{
+            protocol::errors::Errors,
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                find_coordinator_response::FindCoordinatorResponse,
+                heartbeat_response::HeartbeatResponse,
+                join_group_response::{JoinGroupResponse, JoinGroupResponseMember},
+                sync_group_response::SyncGroupResponse,
+            },
+            topic_partition::TopicPartition,
+            utils::{log_context::LogContext, mock_time::MockTime, time::Time, timer::Timer},
+        },
+        test_utils::MockBroker,
+    };
+
+    use super::ConsumerCoordinator;
+
+    const TOPIC: &str = "foo";
+    const GROUP_ID: &str = "test-group";
+    const CONSUMER_ID: &str = "consumer";
+    const OTHER_CONSUMER_ID: &str = "other-consumer";
+    const RETRY_BACKOFF_MS: u128 = 100;
+    const REQUEST_TIMEOUT_MS: u128 = 30_000;
+
+    struct Context {
+        time: Arc<MockTime>,
+        client: Arc<MockClient>,
+        subscriptions: Arc<SubscriptionState>,
+        coordinator: ConsumerCoordinator,
+    }
+
+    impl Context {
+        fn new() -> Context {
+            let mock_broker = MockBroker::new();
+            let cluster = mock_broker.cluster(TOPIC, 2);
+            let MockBroker {
+                time,
+                node: broker,
+                client,
+                ..
+            } = mock_broker;
+
+            let metadata = Arc::new(Metadata::new(RETRY_BACKOFF_MS, 300_000));
+            metadata
+                .update(
+                    metadata.request_version(),
+                    cluster,
+                    false,
+                    time.milliseconds(),
+                )
+                .unwrap();
+
+            let subscriptions = Arc::new(SubscriptionState::new(OffsetResetStrategy::Earliest));
+            subscriptions
+                .subscribe(HashSet::from_iter(vec![TOPIC.to_owned()]))
+                .unwrap();
+
+            let network_client = Arc::new(ConsumerNetworkClient::new(
+                client.clone(),
+                metadata.clone(),
+                time.clone(),
+                RETRY_BACKOFF_MS,
+                REQUEST_TIMEOUT_MS,
+                3_000,
+            ));
+            let rebalance_config = GroupRebalanceConfig::new(
+                10_000,
+                60_000,
+                3_000,
+                GROUP_ID,
+                None,
+                RETRY_BACKOFF_MS,
+                true,
+            )
+            .unwrap();
+            let coordinator = ConsumerCoordinator::new(
+                rebalance_config,
+                network_client,
+                vec![Arc::new(RangeAssignor)],
+                metadata,
+                subscriptions.clone(),
+                time.clone(),
+                false,
+                5_000,
+                &LogContext::default(),
+            )
+            .unwrap();
+
+            client.prepare_response(AbstractResponse::FindCoordinator(
+                FindCoordinatorResponse::new(Errors::None, broker),
+            ));
+            assert!(coordinator
+                .ensure_coordinator_ready(&mut Timer::new(time.clone(), REQUEST_TIMEOUT_MS))
+                .unwrap());
+            Context {
+                time,
+                client,
+                subscriptions,
+                coordinator,
+            }
+        }
+
+        fn timer(&self) -> Timer {
+            Timer::new(self.time.clone(), REQUEST_TIMEOUT_MS)
+        }
+
+        fn prepare_join_group(&self, generation_id: i32, leader: &str) {
+            let members = if leader == CONSUMER_ID {
+                let metadata =
+                    consumer_protocol::serialize_subscription(&Subscription::from_topics(vec![
+                        TOPIC.to_owned(),
+                    ]))
+                    .unwrap();
+                vec![
+                    JoinGroupResponseMember::new(CONSUMER_ID, None, metadata.clone()),
+                    JoinGroupResponseMember::new(OTHER_CONSUMER_ID, None, metadata),
+                ]
+            } else {
+                vec![]
+            };
+            self.client.prepare_response_matching(
+                |request| {
+                    matches!(request, AbstractRequest::JoinGroup(request)
+                    if request.protocols.iter().any(|protocol| protocol.name == "range"))
+                },
+                AbstractResponse::JoinGroup(JoinGroupResponse::new(
+                    Errors::None,
+                    generation_id,
+                    Some(consumer_protocol::PROTOCOL_TYPE.to_owned()),
+                    Some("range".to_owned()),
+                    leader,
+                    CONSUMER_ID,
+                    members,
+                )),
+            );
+        }
+
+        fn prepare_sync_group(
+            &self,
+            matcher: impl Fn(&AbstractRequest) -> bool + Send + 'static,
+            partitions: Vec<TopicPartition>,
+        ) {
+            let assignment =
+                consumer_protocol::serialize_assignment(&Assignment::from_partitions(partitions))
+                    .unwrap();
+            self.client.prepare_response_matching(
+                matcher,
+                AbstractResponse::SyncGroup(SyncGroupResponse::new(
+                    Errors::None,
+                    Some(consumer_protocol::PROTOCOL_TYPE.to_owned()),
+                    Some("range".to_owned()),
+                    assignment,
+                )),
+            );
+        }
+
+        fn join_as_follower(&self, generation_id: i32, partitions: Vec<TopicPartition>) {
+            self.prepare_join_group(generation_id, "leader");
+            self.prepare_sync_group(
+                move |request| {
+                    matches!(request, AbstractRequest::SyncGroup(request)
+                    if request.generation_id == generation_id && request.assignments.is_empty())
+                },
+                partitions,
+            );
+            assert!(self
+                .coordinator
+                .coordinator()
+                .join_group_if_needed(&self.coordinator, &mut self.timer())
+                .unwrap());
+        }
+    }
+
+    fn tp(partition: i32) -> TopicPartition {
+        TopicPartition::new(TOPIC, partition)
+    }
+
+    fn assigned_partitions(assignment: &Bytes) -> Vec<TopicPartition> {
+        consumer_protocol::deserialize_assignment(assignment.clone())
+            .unwrap()
+            .partitions
+    }
+
+    #[test]
+    fn leader_assigns_partitions_to_every_member() {
+        let ctx = Context::new();
+        ctx.prepare_join_group(1, CONSUMER_ID);
+        ctx.prepare_sync_group(
+            |request| match request {
+                AbstractRequest::SyncGroup(request) => {
+                    let assignments: Vec<(&str, Vec<TopicPartition>)> = request
+                        .assignments
+                        .iter()
+                        .map(|assignment| {
+                            (
+                                assignment.member_id.as_str(),
+                                assigned_partitions(&assignment.assignment),
+                            )
+                        })
+                        .collect();
+                    assignments
+                        == vec![(CONSUMER_ID, vec![tp(0)]), (OTHER_CONSUMER_ID, vec![tp(1)])]
+                }
+                _ => false,
+            },
+            vec![tp(0)],
+        );
+        assert!(ctx
+            .coordinator
+            .coordinator()
+            .join_group_if_needed(&ctx.coordinator, &mut ctx.timer())
+            .unwrap());
+
+        assert_eq!(ctx.coordinator.coordinator().state(), MemberState::Stable);
+        assert_eq!(
+            ctx.subscriptions.assigned_partitions(),
+            HashSet::from_iter(vec![tp(0)])
+        );
+        assert!(!ctx.coordinator.rejoin_needed_or_pending());
+    }
+
+    #[test]
+    fn follower_takes_the_assignment_of_the_new_generation_after_a_rebalance() {
+        let ctx = Context::new();
+        ctx.join_as_follower(1, vec![tp(0), tp(1)]);
+        assert_eq!(
+            ctx.subscriptions.assigned_partitions(),
+            HashSet::from_iter(vec![tp(0), tp(1)])
+        );
+
+        ctx.client
+            .prepare_response(AbstractResponse::Heartbeat(HeartbeatResponse::new(
+                Errors::RebalanceInProgress,
+            )));
+        let heartbeat = ctx.coordinator.coordinator().send_heartbeat_request();
+        ctx.coordinator
+            .coordinator()
+            .client()
+            .poll_future(&heartbeat, &mut ctx.timer())
+            .unwrap();
+        assert!(heartbeat.failed());
+        assert!(ctx.coordinator.rejoin_needed_or_pending());
+
+        ctx.join_as_follower(2, vec![tp(1)]);
+        assert_eq!(ctx.coordinator.coordinator().generation().generation_id, 2);
+        assert_eq!(
+            ctx.subscriptions.assigned_partitions(),
+            HashSet::from_iter(vec![tp(1)])
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use crate::{
+    clients::{
+        metadata::Metadata,
+        metadata_updater::{MetadataRequestAndVersion, MetadataUpdater},
+    },
+    common::{
+        errors::KafkaError,
+        node::Node,
+        requests::{metadata_request::MetadataRequest, metadata_response::MetadataResponse},
+        utils::log_context::{LogContext, Logger},
+    },
+};
+
+use super::subscription_state::SubscriptionState;
+
+/// Updates the metadata of the consumer with the topics it is subscribed to, or with all topics
+/// when subscribed to a pattern.
+pub struct ConsumerMetadata {
+    log: Logger,
+    metadata: Arc<Metadata>,
+    include_internal_topics: bool,
+    allow_auto_topic_creation: bool,
+    subscription: Arc<SubscriptionState>,
+}
+
+impl ConsumerMetadata {
+    pub fn new(
+        metadata: Arc<Metadata>,
+        include_internal_topics: bool,
+        allow_auto_topic_creation: bool,
+        subscription: Arc<SubscriptionState>,
+        log_context: &LogContext,
+    ) -> ConsumerMetadata {
+        ConsumerMetadata {
+            log: log_context.logger(module_path!()),
+            metadata,
+            include_internal_topics,
+            allow_auto_topic_creation,
+            subscription,
+        }
+    }
+
+    fn retain_topic(&self, topic: &str, is_internal: bool) -> bool {
+        if self.subscription.needs_metadata(topic) {
+            return true;
+        }
+        if is_internal && !self.include_internal_topics {
+            return false;
+        }
+        self.subscription.matches_subscribed_pattern(topic)
+    }
+}
+
+impl MetadataUpdater for ConsumerMetadata {
+    fn fetch_nodes(&self) -> Vec<Node> {
+        self.metadata.fetch().nodes.clone()
+    }
+
+    fn time_to_next_update(&self, now: u128) -> u128 {
+        self.metadata.time_to_next_update(now)
+    }
+
+    fn new_metadata_request(&self, _now: u128) -> Option<MetadataRequestAndVersion> {
+        let request = if self.subscription.has_pattern_subscription() {
+            MetadataRequest::all_topics()
+        } else {
+            MetadataRequest::new(
+                self.subscription.metadata_topics().into_iter().collect(),
+                self.allow_auto_topic_creation,
+            )
+        };
+        Some(MetadataRequestAndVersion {
+            request,
+            request_version: self.metadata.request_version(),
+            is_partial_update: false,
+        })
+    }
+
+    fn request_update(&self) {
+        self.metadata.request_update();
+    }
+
+    fn handle_failed_request(&self, now: u128, error: Option<KafkaError>) {
+        if let Some(error @ KafkaError::UnsupportedVersion(_)) = error {
+            self.metadata.fatal_error(error);
+        }
+        self.metadata.failed_update(now);
+    }
+
+    fn handle_successful_response(
+        &self,
+        request: &MetadataRequestAndVersion,
+        response: &MetadataResponse,
+        now: u128,
+    ) {
+        let errors = response.errors();
+        if !errors.is_empty() {
+            self.log
+                .warn(format_args!("Error while fetching metadata: {:?}", errors));
+        }
+        if response.brokers.is_empty() {
+            self.log
+                .trace(format_args!("Ignoring empty metadata response."));
+            self.metadata.failed_update(now);
+            return;
+        }
+        let mut response = response.clone();
+        response
+            .topics
+            .retain(|topic| self.retain_topic(&topic.name, topic.is_internal));
+        if let Err(e) = self.metadata.update(
+            request.request_version,
+            response.build_cluster(),
+            request.is_partial_update,
+            now,
+        ) {
+            self.log
+                .warn(format_args!("Failed to update the metadata: {}", e));
+        }
+    }
+
+    fn close(&self) {
+        self.metadata.close();
+    }
+}
@@ -0,0 +1,563 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use indexmap::IndexMap;
+use log::debug;
+
+use crate::{
+    clients::{
+        client_request::ClientRequest, client_response::ClientResponse, kafka_client::KafkaClient,
+        metadata::Metadata,
+    },
+    common::{
+        errors::{KafkaError, Result},
+        node::Node,
+        requests::abstract_request::AbstractRequest,
+        utils::{time::Time, timer::Timer},
+    },
+};
+
+use super::request_future::RequestFuture;
+
+const MAX_POLL_TIMEOUT_MS: u128 = 5000;
+
+type PendingCompletion = (Arc<RequestFuture<ClientResponse>>, Result<ClientResponse>);
+
+/// Higher level consumer access to the network layer with basic support for request futures. This class
+/// is thread-safe, but provides no synchronization for response callbacks. This guarantees that no locks
+/// are held when they are invoked.
+pub struct ConsumerNetworkClient {
+    client: Arc<dyn KafkaClient>,
+    metadata: Arc<Metadata>,
+    time: Arc<dyn Time>,
+    retry_backoff_ms: u128,
+    max_poll_timeout_ms: u128,
+    request_timeout_ms: u128,
+    wakeup_disabled: AtomicBool,
+    // the mutex is held for the whole network poll, which makes it the lock preventing the
+    // application and the heartbeat thread from polling concurrently
+    unsent: Mutex<UnsentRequests>,
+    // when requests complete, they are transferred to this queue prior to invocation. The purpose
+    // is to avoid invoking them while holding this object's monitor which can open the door for deadlocks.
+    pending_completion: Arc<Mutex<VecDeque<PendingCompletion>>>,
+    pending_disconnects: Mutex<Vec<Node>>,
+    // this flag allows the client to be safely woken up without waiting on the lock above. It is
+    // atomic to avoid the need to acquire the lock above in order to enable it concurrently.
+    wakeup: AtomicBool,
+}
+
+struct UnsentRequest {
+    request: ClientRequest,
+    future: Arc<RequestFuture<ClientResponse>>,
+}
+
+/// Requests waiting for a connection to their destination, grouped by node.
+#[derive(Default)]
+struct UnsentRequests {
+    unsent: IndexMap<i32, (Node, VecDeque<UnsentRequest>)>,
+}
+
+impl UnsentRequests {
+    fn put(&mut self, node: &Node, request: UnsentRequest) {
+        self.unsent
+            .entry(node.id)
+            .or_insert_with(|| (node.clone(), VecDeque::new()))
+            .1
+            .push_back(request);
+    }
+
+    fn request_count(&self, node: &Node) -> usize {
+        self.unsent
+            .get(&node.id)
+            .map(|(_, requests)| requests.len())
+            .unwrap_or(0)
+    }
+
+    fn total_request_count(&self) -> usize {
+        self.unsent
+            .values()
+            .map(|(_, requests)| requests.len())
+            .sum()
+    }
+
+    fn remove_expired_requests(&mut self, now: u128) -> Vec<UnsentRequest> {
+        let mut expired = vec![];
+        for (_, requests) in self.unsent.values_mut() {
+            let mut remaining = VecDeque::with_capacity(requests.len());
+            for request in requests.drain(..) {
+                let elapsed = now.saturating_sub(request.request.created_time_ms);
+                if elapsed > request.request.request_timeout_ms {
+                    expired.push(request);
+                } else {
+                    remaining.push_back(request);
+                }
+            }
+            *requests = remaining;
+        }
+        expired
+    }
+
+    /// Clear entries with no requests to keep the map from growing indefinitely.
+    fn clean(&mut self) {
+        self.unsent.retain(|_, (_, requests)| !requests.is_empty());
+    }
+
+    fn remove(&mut self, node: &Node) -> VecDeque<UnsentRequest> {
+        self.unsent
+            .remove(&node.id)
+            .map(|(_, requests)| requests)
+            .unwrap_or_default()
+    }
+
+    fn nodes(&self) -> Vec<Node> {
+        self.unsent.values().map(|(node, _)| node.clone()).collect()
+    }
+}
+
+impl ConsumerNetworkClient {
+    pub fn new(
+        client: Arc<dyn KafkaClient>,
+        metadata: Arc<Metadata>,
+        time: Arc<dyn Time>,
+        retry_backoff_ms: u128,
+        request_timeout_ms: u128,
+        max_poll_timeout_ms: u128,
+    ) -> ConsumerNetworkClient {
+        ConsumerNetworkClient {
+            client,
+            metadata,
+            time,
+            retry_backoff_ms,
+            max_poll_timeout_ms: max_poll_timeout_ms.min(MAX_POLL_TIMEOUT_MS),
+            request_timeout_ms,
+            wakeup_disabled: AtomicBool::new(false),
+            unsent: Mutex::new(UnsentRequests::default()),
+            pending_completion: Arc::new(Mutex::new(VecDeque::new())),
+            pending_disconnects: Mutex::new(vec![]),
+            wakeup: AtomicBool::new(false),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, UnsentRequests> {
+        self.unsent.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock_pending_completion(&self) -> MutexGuard<'_, VecDeque<PendingCompletion>> {
+        self.pending_completion
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn default_request_timeout_ms(&self) -> u128 {
+        self.request_timeout_ms
+    }
+
+    /// The underlying network client.
+    pub fn client(&self) -> &Arc<dyn KafkaClient> {
+        &self.client
+    }
+
+    /// Send a request with the default timeout. See `send_with_timeout`.
+    pub fn send(
+        &self,
+        node: &Node,
+        request: AbstractRequest,
+    ) -> Arc<RequestFuture<ClientResponse>> {
+        self.send_with_timeout(node, request, self.request_timeout_ms)
+    }
+
+    /// Send a new request. Note that the request is not actually transmitted on the
+    /// network until one of the `poll` variants is invoked. At this
+    /// point the request will either be transmitted successfully or will fail.
+    /// Use the returned future to obtain the result of the send. Note that there is no
+    /// need to check for disconnects explicitly on the `ClientResponse` object;
+    /// instead, the future will be failed with a `Network` error.
+    pub fn send_with_timeout(
+        &self,
+        node: &Node,
+        request: AbstractRequest,
+        request_timeout_ms: u128,
+    ) -> Arc<RequestFuture<ClientResponse>> {
+        let now = self.time.milliseconds();
+        let future = RequestFuture::new();
+        let pending_completion = self.pending_completion.clone();
+        let completed = future.clone();
+        let client_request = self.client.new_client_request(
+            &node.id_string(),
+            request,
+            now,
+            true,
+            request_timeout_ms,
+            Some(Box::new(move |response: ClientResponse| {
+                let result = if let Some(version_mismatch) = response.version_mismatch.clone() {
+                    Err(version_mismatch)
+                } else if response.disconnected {
+                    debug!(
+                        "Cancelled request with header {:?} due to node {} being disconnected",
+                        response.request_header, response.destination
+                    );
+                    Err(disconnected(&response.destination))
+                } else {
+                    Ok(response)
+                };
+                pending_completion
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push_back((completed, result));
+            })),
+        );
+        self.lock().put(
+            node,
+            UnsentRequest {
+                request: client_request,
+                future: future.clone(),
+            },
+        );
+
+        // wakeup the client in case it is blocking in poll so that we can send the queued request
+        self.client.wakeup();
+        future
+    }
+
+    pub fn least_loaded_node(&self) -> Option<Node> {
+        let _guard = self.lock();
+        self.client.least_loaded_node(self.time.milliseconds())
+    }
+
+    pub fn has_ready_nodes(&self, now: u128) -> bool {
+        let _guard = self.lock();
+        self.client.has_ready_nodes(now)
+    }
+
+    /// Block waiting on the metadata refresh with a timeout.
+    ///
+    /// Returns true if update succeeded, false otherwise.
+    pub fn await_metadata_update(&self, timer: &mut Timer) -> Result<bool> {
+        let version = self.metadata.request_update();
+        loop {
+            self.poll(timer)?;
+            if self.metadata.update_version() != version || timer.is_expired() {
+                break;
+            }
+        }
+        Ok(self.metadata.update_version() > version)
+    }
+
+    /// Ensure our metadata is fresh (if an update is expected, this will block
+    /// until it has completed).
+    pub fn ensure_fresh_metadata(&self, timer: &mut Timer) -> Result<bool> {
+        if self.metadata.update_requested()
+            || self.metadata.time_to_next_update(timer.current_time_ms()) == 0
+        {
+            self.await_metadata_update(timer)
+        } else {
+            // the metadata is already fresh
+            Ok(true)
+        }
+    }
+
+    /// Wakeup an active poll. This will cause the polling thread to fail with a `Wakeup` error
+    /// either on the current poll if one is active, or the next poll.
+    pub fn wakeup(&self) {
+        // wakeup should be safe without holding the client lock since it simply delegates to
+        // Selector's wakeup, which is thread-safe
+        debug!("Received user wakeup");
+        self.wakeup.store(true, Ordering::SeqCst);
+        self.client.wakeup();
+    }
+
+    /// Block until the provided request future request has finished or the timeout has expired.
+    ///
+    /// Returns true if the future is done, false otherwise. Fails with `Wakeup` if `wakeup` is
+    /// called from another thread.
+    pub fn poll_future<T: Clone + Send + 'static>(
+        &self,
+        future: &RequestFuture<T>,
+        timer: &mut Timer,
+    ) -> Result<bool> {
+        loop {
+            self.poll_with_condition(timer, Some(&|| !future.is_done()), false)?;
+            if future.is_done() || timer.is_expired() {
+                break;
+            }
+        }
+        Ok(future.is_done())
+    }
+
+    /// Poll for any network IO.
+    pub fn poll(&self, timer: &mut Timer) -> Result<()> {
+        self.poll_with_condition(timer, None, false)
+    }
+
+    /// Poll for any network IO.
+    ///
+    /// * `timer` - Timer bounding how long this method can block
+    /// * `poll_condition` - Optional blocking condition, the poll doesn't block when it returns false
+    /// * `disable_wakeup` - If true disable triggering wake-ups
+    pub fn poll_with_condition(
+        &self,
+        timer: &mut Timer,
+        poll_condition: Option<&dyn Fn() -> bool>,
+        disable_wakeup: bool,
+    ) -> Result<()> {
+        // there may be handlers which need to be invoked if we woke up the previous call to poll
+        self.fire_pending_completed_requests();
+
+        let result = {
+            let mut unsent = self.lock();
+            // Handle async disconnects prior to attempting any sends
+            self.handle_pending_disconnects(&mut unsent);
+
+            // send all the requests we can send now
+            let poll_delay_ms = self.try_send(&mut unsent, timer.current_time_ms());
+
+            // check whether the poll is still needed by the caller. Note that if the expected completion
+            // condition becomes satisfied after the call to should_block() (because of a fired completion
+            // handler), the client will be woken up.
+            let should_block = poll_condition.map(|condition| condition()).unwrap_or(true);
+            if self.lock_pending_completion().is_empty() && should_block {
+                // if there are no requests in flight, do not block longer than the retry backoff
+                let mut poll_timeout = timer.remaining_ms().min(poll_delay_ms);
+                if self.client.in_flight_request_count() == 0 {
+                    poll_timeout = poll_timeout.min(self.retry_backoff_ms);
+                }
+                self.client.poll(poll_timeout, timer.current_time_ms());
+            } else {
+                self.client.poll(0, timer.current_time_ms());
+            }
+            timer.update();
+
+            // handle any disconnects by failing the active requests. note that disconnects must
+            // be checked immediately following poll since any subsequent call to client.ready()
+            // will reset the disconnect status
+            self.check_disconnects(&mut unsent);
+            let result = if disable_wakeup {
+                Ok(())
+            } else {
+                // trigger wakeups after checking for disconnects so that the callbacks will be ready
+                // to be fired on the next call to poll()
+                self.maybe_trigger_wakeup()
+            };
+
+            if result.is_ok() {
+                // try again to send requests since buffer space may have been
+                // cleared or a connect finished in the poll
+                self.try_send(&mut unsent, timer.current_time_ms());
+
+                // fail requests that couldn't be sent if they have expired
+                self.fail_expired_requests(&mut unsent, timer.current_time_ms());
+
+                // clean unsent requests collection to keep the map from growing indefinitely
+                unsent.clean();
+            }
+            result
+        };
+        result?;
+
+        // called without the lock to avoid deadlock potential if handlers need to acquire locks
+        self.fire_pending_completed_requests();
+
+        self.metadata.maybe_throw_fatal_exception()
+    }
+
+    /// Poll for network IO and return immediately. This will not trigger wakeups.
+    pub fn poll_no_wakeup(&self) -> Result<()> {
+        let mut timer = Timer::new(self.time.clone(), 0);
+        self.poll_with_condition(&mut timer, None, true)
+    }
+
+    /// Poll for network IO in best-effort only trying to transmit the ready-to-send request.
+    /// Do not check any pending requests or metadata errors so that no error should ever
+    /// be returned, also no wakeups be triggered.
+    pub fn transmit_sends(&self) {
+        let now = self.time.milliseconds();
+        // do not try to handle any disconnects, prev request failures, metadata exception etc;
+        // just try once and return immediately
+        let mut unsent = self.lock();
+        // send all the requests we can send now
+        self.try_send(&mut unsent, now);
+        self.client.poll(0, now);
+    }
+
+    /// Block until all pending requests from the given node have finished.
+    ///
+    /// Returns true if all requests have completed, false otherwise.
+    pub fn await_pending_requests(&self, node: &Node, timer: &mut Timer) -> Result<bool> {
+        while self.has_pending_requests_for(node) && timer.not_expired() {
+            self.poll(timer)?;
+        }
+        Ok(!self.has_pending_requests_for(node))
+    }
+
+    /// Get the count of pending requests to the given node. This includes both request that
+    /// have been transmitted (i.e. in-flight requests) and those which are awaiting transmission.
+    pub fn pending_request_count_for(&self, node: &Node) -> usize {
+        let unsent = self.lock();
+        unsent.request_count(node) + self.client.in_flight_request_count_for(&node.id_string())
+    }
+
+    /// Check whether there is pending request to the given node. This includes both request that
+    /// have been transmitted (i.e. in-flight requests) and those which are awaiting transmission.
+    pub fn has_pending_requests_for(&self, node: &Node) -> bool {
+        self.pending_request_count_for(node) > 0
+    }
+
+    /// Get the total count of pending requests from all nodes. This includes both requests that
+    /// have been transmitted (i.e. in-flight requests) and those which are awaiting transmission.
+    pub fn pending_request_count(&self) -> usize {
+        let unsent = self.lock();
+        unsent.total_request_count() + self.client.in_flight_request_count()
+    }
+
+    /// Check whether there is pending request. This includes both requests that
+    /// have been transmitted (i.e. in-flight requests) and those which are awaiting transmission.
+    pub fn has_pending_requests(&self) -> bool {
+        self.pending_request_count() > 0
+    }
+
+    fn fire_pending_completed_requests(&self) {
+        let mut completed_request_batch = false;
+        loop {
+            let completion = self.lock_pending_completion().pop_front();
+            let (future, result) = match completion {
+                Some(completion) => completion,
+                None => break,
+            };
+            match result {
+                Ok(response) => future.complete(response),
+                Err(error) => future.raise(error),
+            }
+            completed_request_batch = true;
+        }
+
+        // wakeup the client in case it is blocking in poll for this future's completion
+        if completed_request_batch {
+            self.client.wakeup();
+        }
+    }
+
+    fn check_disconnects(&self, unsent: &mut UnsentRequests) {
+        // any disconnects affecting requests that have already been transmitted will be handled
+        // by NetworkClient, so we just need to check whether connections for any of the unsent
+        // requests have been disconnected; if they have, then we complete the corresponding future
+        // and set the disconnect flag in the ClientResponse
+        for node in unsent.nodes() {
+            if self.client.connection_failed(&node) {
+                // Remove entry before invoking request callback to avoid callbacks handling
+                // coordinator failures traversing the unsent list again.
+                self.fail_unsent_requests(unsent, &node, disconnected(&node.id_string()));
+            }
+        }
+    }
+
+    fn handle_pending_disconnects(&self, unsent: &mut UnsentRequests) {
+        let pending_disconnects: Vec<Node> = self
+            .pending_disconnects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+            .collect();
+        for node in pending_disconnects {
+            self.fail_unsent_requests(unsent, &node, disconnected(&node.id_string()));
+            self.client.disconnect(&node.id_string());
+        }
+    }
+
+    /// Disconnect from the node and fail all requests pending to it. The disconnection happens on
+    /// the next poll, so it's safe to call this method from response handlers.
+    pub fn disconnect_async(&self, node: &Node) {
+        self.pending_disconnects
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(node.clone());
+        self.client.wakeup();
+    }
+
+    fn fail_expired_requests(&self, unsent: &mut UnsentRequests, now: u128) {
+        // clear all expired unsent requests and fail their corresponding futures
+        for request in unsent.remove_expired_requests(now) {
+            debug!(
+                "Failed to send request after {} ms",
+                request.request.request_timeout_ms
+            );
+            self.lock_pending_completion().push_back((
+                request.future,
+                Err(KafkaError::Timeout(format!(
+                    "Failed to send request after {} ms.",
+                    request.request.request_timeout_ms
+                ))),
+            ));
+        }
+    }
+
+    fn fail_unsent_requests(&self, unsent: &mut UnsentRequests, node: &Node, error: KafkaError) {
+        // clear unsent requests to node and fail their corresponding futures
+        for request in unsent.remove(node) {
+            self.lock_pending_completion()
+                .push_back((request.future, Err(error.clone())));
+        }
+    }
+
+    fn try_send(&self, unsent: &mut UnsentRequests, now: u128) -> u128 {
+        let mut poll_delay_ms = self.max_poll_timeout_ms;
+
+        // send any requests that can be sent now
+        for (node, requests) in unsent.unsent.values_mut() {
+            if !requests.is_empty() {
+                poll_delay_ms = poll_delay_ms.min(self.client.poll_delay_ms(node, now));
+            }
+            while !requests.is_empty() && self.client.ready(node, now) {
+                if let Some(request) = requests.pop_front() {
+                    self.client.send(request.request, now);
+                }
+            }
+        }
+        poll_delay_ms
+    }
+
+    /// Fail with `Wakeup` if `wakeup` has been called since the last check.
+    pub fn maybe_trigger_wakeup(&self) -> Result<()> {
+        if !self.wakeup_disabled.load(Ordering::SeqCst) && self.wakeup.swap(false, Ordering::SeqCst)
+        {
+            debug!("Raising WakeupException in response to user wakeup");
+            return Err(KafkaError::Wakeup("Wakeup".into()));
+        }
+        Ok(())
+    }
+
+    pub fn disable_wakeups(&self) {
+        self.wakeup_disabled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn close(&self) {
+        let _guard = self.lock();
+        self.client.close();
+    }
+
+    /// Check if the code is disconnected and unavailable for immediate reconnection (i.e. if it is in
+    /// reconnect backoff window following the disconnect).
+    pub fn is_unavailable(&self, node: &Node) -> bool {
+        let _guard = self.lock();
+        self.client.connection_failed(node)
+            && self.client.connection_delay(node, self.time.milliseconds()) > 0
+    }
+
+    /// Initiate a connection if currently possible. This is only really useful for resetting
+    /// the failed status of a socket.
+    pub fn try_connect(&self, node: &Node) {
+        let _guard = self.lock();
+        self.client.ready(node, self.time.milliseconds());
+    }
+}
+
+fn disconnected(node_id: &str) -> KafkaError {
+    KafkaError::Network(format!(
+        "Disconnected from node {} before the response could be read",
+        node_id
+    ))
+}
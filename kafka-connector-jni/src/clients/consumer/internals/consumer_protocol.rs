@@ -0,0 +1,366 @@
+//! ConsumerProtocol contains the schemas for consumer subscriptions and assignments for use with
+//! Kafka's generalized group management protocol.
+//!
+//! The current implementation assumes that future versions will not break compatibility. When
+//! it encounters a newer version, it parses it using the current format. This basically means
+//! that new versions cannot remove or reorder any of the existing fields.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use indexmap::IndexMap;
+
+use crate::{
+    clients::consumer::consumer_partition_assignor::{Assignment, Subscription},
+    common::{
+        errors::{KafkaError, Result},
+        topic_partition::TopicPartition,
+    },
+};
+
+pub const PROTOCOL_TYPE: &str = "consumer";
+
+pub const CONSUMER_PROTOCOL_V0: i16 = 0;
+pub const CONSUMER_PROTOCOL_V1: i16 = 1;
+pub const LOWEST_SUPPORTED_VERSION: i16 = CONSUMER_PROTOCOL_V0;
+pub const HIGHEST_SUPPORTED_VERSION: i16 = CONSUMER_PROTOCOL_V1;
+
+pub fn deserialize_version(buffer: &mut Bytes) -> Result<i16> {
+    if buffer.remaining() < 2 {
+        return Err(schema_error("header"));
+    }
+    Ok(buffer.get_i16())
+}
+
+pub fn serialize_subscription(subscription: &Subscription) -> Result<Bytes> {
+    serialize_subscription_with_version(subscription, HIGHEST_SUPPORTED_VERSION)
+}
+
+pub fn serialize_subscription_with_version(
+    subscription: &Subscription,
+    version: i16,
+) -> Result<Bytes> {
+    let version = check_version("subscription", version)?;
+    let mut buffer = BytesMut::new();
+    buffer.put_i16(version);
+
+    let mut topics = subscription.topics.clone();
+    topics.sort();
+    buffer.put_i32(topics.len() as i32);
+    for topic in &topics {
+        write_string(topic, &mut buffer);
+    }
+
+    write_nullable_bytes(subscription.user_data.as_ref(), &mut buffer);
+
+    if version >= CONSUMER_PROTOCOL_V1 {
+        let mut owned_partitions = subscription.owned_partitions.clone();
+        owned_partitions.sort();
+        write_topic_partitions(&owned_partitions, &mut buffer);
+    }
+    Ok(buffer.freeze())
+}
+
+pub fn deserialize_subscription_with_version(
+    buffer: &mut Bytes,
+    version: i16,
+) -> Result<Subscription> {
+    let version = check_version("subscription", version)?;
+    let parse = |buffer: &mut Bytes| -> Option<Subscription> {
+        let topics_count = read_array_length(buffer)?;
+        let mut topics = Vec::with_capacity(topics_count);
+        for _ in 0..topics_count {
+            topics.push(read_string(buffer)?);
+        }
+        let user_data = read_nullable_bytes(buffer)?;
+        let owned_partitions = if version >= CONSUMER_PROTOCOL_V1 {
+            read_topic_partitions(buffer)?
+        } else {
+            vec![]
+        };
+        Some(Subscription::new(topics, user_data, owned_partitions))
+    };
+    parse(buffer).ok_or_else(|| schema_error("subscription"))
+}
+
+pub fn deserialize_subscription(mut buffer: Bytes) -> Result<Subscription> {
+    let version = deserialize_version(&mut buffer)?;
+    deserialize_subscription_with_version(&mut buffer, version)
+}
+
+pub fn serialize_assignment(assignment: &Assignment) -> Result<Bytes> {
+    serialize_assignment_with_version(assignment, HIGHEST_SUPPORTED_VERSION)
+}
+
+pub fn serialize_assignment_with_version(assignment: &Assignment, version: i16) -> Result<Bytes> {
+    let version = check_version("assignment", version)?;
+    let mut buffer = BytesMut::new();
+    buffer.put_i16(version);
+    write_topic_partitions(&assignment.partitions, &mut buffer);
+    write_nullable_bytes(assignment.user_data.as_ref(), &mut buffer);
+    Ok(buffer.freeze())
+}
+
+pub fn deserialize_assignment_with_version(buffer: &mut Bytes, version: i16) -> Result<Assignment> {
+    check_version("assignment", version)?;
+    let parse = |buffer: &mut Bytes| -> Option<Assignment> {
+        let partitions = read_topic_partitions(buffer)?;
+        let user_data = read_nullable_bytes(buffer)?;
+        Some(Assignment::new(partitions, user_data))
+    };
+    parse(buffer).ok_or_else(|| schema_error("assignment"))
+}
+
+pub fn deserialize_assignment(mut buffer: Bytes) -> Result<Assignment> {
+    let version = deserialize_version(&mut buffer)?;
+    deserialize_assignment_with_version(&mut buffer, version)
+}
+
+fn check_version(kind: &str, version: i16) -> Result<i16> {
+    if version < LOWEST_SUPPORTED_VERSION {
+        Err(KafkaError::Kafka(format!(
+            "Unsupported {} version: {}",
+            kind, version
+        )))
+    } else {
+        Ok(version.min(HIGHEST_SUPPORTED_VERSION))
+    }
+}
+
+fn schema_error(kind: &str) -> KafkaError {
+    KafkaError::Kafka(format!(
+        "Buffer underflow while parsing consumer protocol's {}",
+        kind
+    ))
+}
+
+/// Write partitions grouped by topic, in order of the first appearance of each topic.
+fn write_topic_partitions(partitions: &[TopicPartition], buffer: &mut BytesMut) {
+    let mut partitions_by_topic: IndexMap<&str, Vec<i32>> = IndexMap::new();
+    for tp in partitions {
+        partitions_by_topic
+            .entry(&tp.topic)
+            .or_default()
+            .push(tp.partition);
+    }
+    buffer.put_i32(partitions_by_topic.len() as i32);
+    for (topic, partitions) in partitions_by_topic {
+        write_string(topic, buffer);
+        buffer.put_i32(partitions.len() as i32);
+        for partition in partitions {
+            buffer.put_i32(partition);
+        }
+    }
+}
+
+fn read_topic_partitions(buffer: &mut Bytes) -> Option<Vec<TopicPartition>> {
+    let topics_count = read_array_length(buffer)?;
+    let mut partitions = vec![];
+    for _ in 0..topics_count {
+        let topic = read_string(buffer)?;
+        let partitions_count = read_array_length(buffer)?;
+        for _ in 0..partitions_count {
+            if buffer.remaining() < 4 {
+                return None;
+            }
+            partitions.push(TopicPartition::new(topic.clone(), buffer.get_i32()));
+        }
+    }
+    Some(partitions)
+}
+
+fn write_string(value: &str, buffer: &mut BytesMut) {
+    buffer.put_i16(value.len() as i16);
+    buffer.put_slice(value.as_bytes());
+}
+
+fn read_string(buffer: &mut Bytes) -> Option<String> {
+    if buffer.remaining() < 2 {
+        return None;
+    }
+    let length = buffer.get_i16();
+    if length < 0 || buffer.remaining() < length as usize {
+        return None;
+    }
+    String::from_utf8(buffer.split_to(length as usize).to_vec()).ok()
+}
+
+fn write_nullable_bytes(value: Option<&Bytes>, buffer: &mut BytesMut) {
+    match value {
+        Some(value) => {
+            buffer.put_i32(value.len() as i32);
+            buffer.put_slice(value);
+        }
+        None => buffer.put_i32(-1),
+    }
+}
+
+fn read_nullable_bytes(buffer: &mut Bytes) -> Option<Option<Bytes>> {
+    if buffer.remaining() < 4 {
+        return None;
+    }
+    let length = buffer.get_i32();
+    if length < 0 {
+        return Some(None);
+    }
+    if buffer.remaining() < length as usize {
+        return None;
+    }
+    Some(Some(buffer.split_to(length as usize)))
+}
+
+/// Read the length of an array, treating null arrays as empty.
+fn read_array_length(buffer: &mut Bytes) -> Option<usize> {
+    if buffer.remaining() < 4 {
+        return None;
+    }
+    Some(buffer.get_i32().max(0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{BufMut, Bytes, BytesMut};
+
+    use crate::{
+        clients::consumer::consumer_partition_assignor::{Assignment, Subscription},
+        common::{errors::KafkaError, topic_partition::TopicPartition},
+    };
+
+    use super::{
+        deserialize_assignment, deserialize_subscription, serialize_assignment,
+        serialize_assignment_with_version, serialize_subscription,
+        serialize_subscription_with_version, CONSUMER_PROTOCOL_V0,
+    };
+
+    // The golden bytes below were written by the Java client's ConsumerProtocol for the same
+    // subscriptions and assignments.
+
+    const SUBSCRIPTION_V0: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x62, 0x61, 0x72, 0x00, 0x03, 0x66, 0x6f,
+        0x6f, 0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03,
+    ];
+
+    const SUBSCRIPTION_V1: &[u8] = &[
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x62, 0x61, 0x72, 0x00, 0x03, 0x66, 0x6f,
+        0x6f, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x62, 0x61, 0x72, 0x00,
+        0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x66, 0x6f, 0x6f, 0x00, 0x00, 0x00,
+        0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    const ASSIGNMENT_V0: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03, 0x66, 0x6f, 0x6f, 0x00, 0x00, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x03, 0x62, 0x61, 0x72, 0x00, 0x00,
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x01, 0x02, 0x03,
+    ];
+
+    const ASSIGNMENT_V1: &[u8] = &[
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x03, 0x66, 0x6f, 0x6f, 0x00, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff,
+    ];
+
+    fn user_data() -> Option<Bytes> {
+        Some(Bytes::from_static(&[1, 2, 3]))
+    }
+
+    fn topics(topics: &[&str]) -> Vec<String> {
+        topics.iter().map(|topic| topic.to_string()).collect()
+    }
+
+    fn tp(topic: &str, partition: i32) -> TopicPartition {
+        TopicPartition::new(topic, partition)
+    }
+
+    #[test]
+    fn subscription_v0_matches_the_java_client() {
+        let subscription = Subscription::new(topics(&["foo", "bar"]), user_data(), vec![]);
+        let serialized =
+            serialize_subscription_with_version(&subscription, CONSUMER_PROTOCOL_V0).unwrap();
+        assert_eq!(&serialized[..], SUBSCRIPTION_V0);
+
+        let deserialized = deserialize_subscription(Bytes::from_static(SUBSCRIPTION_V0)).unwrap();
+        assert_eq!(deserialized.topics, topics(&["bar", "foo"]));
+        assert_eq!(deserialized.user_data, user_data());
+        assert!(deserialized.owned_partitions.is_empty());
+    }
+
+    #[test]
+    fn subscription_v1_with_owned_partitions_matches_the_java_client() {
+        let subscription = Subscription::new(
+            topics(&["foo", "bar"]),
+            None,
+            vec![tp("foo", 1), tp("bar", 0), tp("foo", 0)],
+        );
+        let serialized = serialize_subscription(&subscription).unwrap();
+        assert_eq!(&serialized[..], SUBSCRIPTION_V1);
+
+        let deserialized = deserialize_subscription(Bytes::from_static(SUBSCRIPTION_V1)).unwrap();
+        assert_eq!(
+            deserialized,
+            Subscription::new(
+                topics(&["bar", "foo"]),
+                None,
+                vec![tp("bar", 0), tp("foo", 0), tp("foo", 1)],
+            )
+        );
+    }
+
+    #[test]
+    fn assignment_v0_matches_the_java_client() {
+        let assignment =
+            Assignment::new(vec![tp("foo", 0), tp("bar", 2), tp("foo", 1)], user_data());
+        let serialized =
+            serialize_assignment_with_version(&assignment, CONSUMER_PROTOCOL_V0).unwrap();
+        assert_eq!(&serialized[..], ASSIGNMENT_V0);
+
+        let deserialized = deserialize_assignment(Bytes::from_static(ASSIGNMENT_V0)).unwrap();
+        assert_eq!(
+            deserialized,
+            Assignment::new(vec![tp("foo", 0), tp("foo", 1), tp("bar", 2)], user_data())
+        );
+    }
+
+    #[test]
+    fn assignment_v1_matches_the_java_client() {
+        let assignment = Assignment::from_partitions(vec![tp("foo", 0)]);
+        assert_eq!(
+            &serialize_assignment(&assignment).unwrap()[..],
+            ASSIGNMENT_V1
+        );
+        assert_eq!(
+            deserialize_assignment(Bytes::from_static(ASSIGNMENT_V1)).unwrap(),
+            assignment
+        );
+    }
+
+    #[test]
+    fn future_versions_are_parsed_with_the_current_format() {
+        let mut buffer = BytesMut::new();
+        buffer.put_i16(5);
+        buffer.put_slice(&SUBSCRIPTION_V1[2..]);
+        // fields added by newer versions are ignored
+        buffer.put_i32(42);
+        let deserialized = deserialize_subscription(buffer.freeze()).unwrap();
+        assert_eq!(deserialized.topics, topics(&["bar", "foo"]));
+        assert_eq!(deserialized.owned_partitions.len(), 3);
+
+        assert!(serialize_subscription_with_version(&deserialized, 5)
+            .unwrap()
+            .starts_with(&[0x00, 0x01]));
+    }
+
+    #[test]
+    fn truncated_or_negative_versions_are_rejected() {
+        match deserialize_subscription(Bytes::from_static(&SUBSCRIPTION_V1[..20])) {
+            Err(KafkaError::Kafka(message)) => assert_eq!(
+                message,
+                "Buffer underflow while parsing consumer protocol's subscription"
+            ),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(deserialize_assignment(Bytes::from_static(&[0x00])).is_err());
+        match deserialize_assignment(Bytes::from_static(&[0xff, 0xff])) {
+            Err(KafkaError::Kafka(message)) => {
+                assert_eq!(message, "Unsupported assignment version: -1")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,2132 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::{
+    clients::{
+        api_versions::{ApiVersions, NodeApiVersions},
+        client_response::ClientResponse,
+        consumer::{consumer_record::ConsumerRecord, offset_reset_strategy::OffsetResetStrategy},
+        fetch_session_handler::{FetchRequestData, FetchSessionHandler},
+        kafka_client::KafkaClient,
+        metadata::Metadata,
+    },
+    common::{
+        errors::{KafkaError, Result},
+        header::internals::record_headers::RecordHeaders,
+        isolation_level::IsolationLevel,
+        node::Node,
+        protocol::{api_keys::ApiKeys, errors::Errors},
+        record::{
+            control_record_type::ControlRecordType,
+            default_record::DefaultRecord,
+            memory_records::RecordBatchIterator,
+            record_batch::{RecordBatch, NO_PARTITION_LEADER_EPOCH},
+        },
+        requests::{
+            abstract_request::AbstractRequest,
+            abstract_response::AbstractResponse,
+            fetch_request::{FetchRequest, PartitionData, INVALID_LOG_START_OFFSET},
+            fetch_response::{self, AbortedTransaction},
+            list_offsets_request::{
+                ListOffsetsPartition, ListOffsetsRequest, EARLIEST_TIMESTAMP, LATEST_TIMESTAMP,
+            },
+            list_offsets_response::{ListOffsetsResponse, UNKNOWN_OFFSET},
+            offsets_for_leader_epoch_request::{
+                OffsetForLeaderPartition, OffsetsForLeaderEpochRequest,
+            },
+            offsets_for_leader_epoch_response::{EpochEndOffset, OffsetsForLeaderEpochResponse},
+        },
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::Time,
+        },
+    },
+};
+
+use super::subscription_state::{FetchPosition, LogTruncation, SubscriptionState};
+
+/// This class manages the fetching process with the brokers.
+///
+/// Thread-safety:
+/// Requests and responses of the Fetcher may be processed by different threads since heartbeat
+/// thread may process responses. Other operations are single-threaded and invoked only from
+/// the thread polling the consumer.
+///
+/// - If a response handler accesses any shared state of the Fetcher (e.g. FetchSessionHandler),
+///   all access to that state must be synchronized on the Fetcher instance.
+/// - If a response handler accesses any shared state of the coordinator (e.g. SubscriptionState),
+///   it is assumed that all access to that state is synchronized on the coordinator instance by
+///   the caller.
+/// - Responses that collate partial responses from multiple brokers (e.g. to list offsets) are
+///   synchronized on the response future.
+/// - At most one request is pending for each node at any time. Nodes with pending requests are
+///   tracked and updated after processing the response. This ensures that any state (e.g. epoch)
+///   updated while processing responses on one thread are visible while creating the subsequent request
+///   on a different thread.
+pub struct Fetcher {
+    log: Logger,
+    client: Arc<dyn KafkaClient>,
+    metadata: Arc<Metadata>,
+    subscriptions: Arc<SubscriptionState>,
+    api_versions: Arc<ApiVersions>,
+    time: Arc<dyn Time>,
+    min_bytes: i32,
+    max_bytes: i32,
+    max_wait_ms: i32,
+    fetch_size: i32,
+    max_poll_records: usize,
+    check_crcs: bool,
+    client_rack_id: String,
+    isolation_level: IsolationLevel,
+    retry_backoff_ms: u128,
+    request_timeout_ms: u128,
+    state: Mutex<FetcherState>,
+}
+
+#[derive(Default)]
+struct FetcherState {
+    session_handlers: HashMap<i32, FetchSessionHandler>,
+    nodes_with_pending_fetch_requests: HashSet<i32>,
+    completed_fetches: VecDeque<CompletedFetch>,
+    next_in_line_fetch: Option<CompletedFetch>,
+    cached_list_offsets_error: Option<KafkaError>,
+    cached_offset_for_leader_error: Option<KafkaError>,
+    metadata_update_version: i32,
+}
+
+/// Records returned by a poll, grouped by partition.
+pub type FetchedRecords =
+    IndexMap<TopicPartition, Vec<ConsumerRecord<Option<Bytes>, Option<Bytes>>>>;
+
+/// Partitions (with fetched offsets) of a list offsets response, and the partitions which
+/// should be retried.
+#[derive(Debug, Default)]
+struct ListOffsetResult {
+    fetched_offsets: HashMap<TopicPartition, ListOffsetData>,
+    partitions_to_retry: Vec<TopicPartition>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ListOffsetData {
+    offset: i64,
+    leader_epoch: Option<i32>,
+}
+
+/// End offsets of an offsets for leader epoch response, and the partitions which should be
+/// retried.
+#[derive(Debug, Default)]
+struct OffsetForEpochResult {
+    end_offsets: HashMap<TopicPartition, EpochEndOffset>,
+    partitions_to_retry: Vec<TopicPartition>,
+}
+
+/// Whether the node supports a version of the OffsetsForLeaderEpoch API which can be used by
+/// consumers to validate their positions.
+pub fn has_usable_offset_for_leader_epoch_version(node_api_versions: &NodeApiVersions) -> bool {
+    match node_api_versions.api_version(ApiKeys::OffsetForLeaderEpoch) {
+        Some(api_version) => {
+            OffsetsForLeaderEpochRequest::supports_topic_permission(api_version.max_version)
+        }
+        None => false,
+    }
+}
+
+impl Fetcher {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: Arc<dyn KafkaClient>,
+        metadata: Arc<Metadata>,
+        subscriptions: Arc<SubscriptionState>,
+        api_versions: Arc<ApiVersions>,
+        time: Arc<dyn Time>,
+        min_bytes: i32,
+        max_bytes: i32,
+        max_wait_ms: i32,
+        fetch_size: i32,
+        max_poll_records: usize,
+        check_crcs: bool,
+        client_rack_id: impl Into<String>,
+        isolation_level: IsolationLevel,
+        retry_backoff_ms: u128,
+        request_timeout_ms: u128,
+        log_context: &LogContext,
+    ) -> Fetcher {
+        Fetcher {
+            log: log_context.logger(module_path!()),
+            client,
+            metadata,
+            subscriptions,
+            api_versions,
+            time,
+            min_bytes,
+            max_bytes,
+            max_wait_ms,
+            fetch_size,
+            max_poll_records,
+            check_crcs,
+            client_rack_id: client_rack_id.into(),
+            isolation_level,
+            retry_backoff_ms,
+            request_timeout_ms,
+            state: Mutex::new(FetcherState::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FetcherState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Return whether we have any completed fetches pending return to the user. This method is thread-safe. Has
+    /// visibility for testing.
+    pub fn has_completed_fetches(&self) -> bool {
+        !self.lock().completed_fetches.is_empty()
+    }
+
+    /// Return whether we have any completed fetches that are fetchable. This method is thread-safe.
+    pub fn has_available_fetches(&self) -> bool {
+        self.lock()
+            .completed_fetches
+            .iter()
+            .any(|fetch| self.subscriptions.is_fetchable(&fetch.partition))
+    }
+
+    /// Set-up a fetch request for any node that we have assigned partitions for which doesn't already have
+    /// an in-flight fetch or pending fetch data.
+    ///
+    /// Returns number of fetches sent
+    pub fn send_fetches(self: &Arc<Self>) -> Result<usize> {
+        let fetch_request_map = self.prepare_fetch_requests()?;
+        let count = fetch_request_map.len();
+        for (fetch_target, data) in fetch_request_map {
+            let request = FetchRequest::for_consumer(
+                self.max_wait_ms,
+                self.min_bytes,
+                self.max_bytes,
+                self.isolation_level,
+                data.metadata,
+                data.to_send.clone(),
+                data.to_forget.clone(),
+                self.client_rack_id.clone(),
+            );
+            self.log.debug(format_args!(
+                "Sending {} {} to broker {}",
+                self.isolation_level, data, fetch_target
+            ));
+            // We add the node to the set of nodes with pending fetch requests before sending the
+            // request because the response may be handled on another thread (e.g. during a
+            // disconnection being handled by the heartbeat thread).
+            self.lock()
+                .nodes_with_pending_fetch_requests
+                .insert(fetch_target.id);
+            let fetcher = self.clone();
+            let node_id = fetch_target.id;
+            let now = self.time.milliseconds();
+            let client_request = self.client.new_client_request(
+                &fetch_target.id_string(),
+                AbstractRequest::Fetch(request),
+                now,
+                true,
+                self.request_timeout_ms,
+                Some(Box::new(move |response| {
+                    fetcher.handle_fetch_response(node_id, data, response)
+                })),
+            );
+            self.client.send(client_request, now);
+        }
+        Ok(count)
+    }
+
+    fn handle_fetch_response(&self, node_id: i32, data: FetchRequestData, resp: ClientResponse) {
+        let mut state = self.lock();
+        state.nodes_with_pending_fetch_requests.remove(&node_id);
+        let response = match resp.response_body {
+            Some(AbstractResponse::Fetch(response)) if !resp.disconnected => response,
+            _ => {
+                let error = resp.version_mismatch.clone().unwrap_or_else(|| {
+                    KafkaError::Network(format!(
+                        "Disconnected from node {} while awaiting fetch response",
+                        node_id
+                    ))
+                });
+                if let Some(handler) = state.session_handlers.get_mut(&node_id) {
+                    handler.handle_error(&error);
+                }
+                return;
+            }
+        };
+        let handler = match state.session_handlers.get_mut(&node_id) {
+            Some(handler) => handler,
+            None => {
+                self.log.error(format_args!(
+                    "Unable to find FetchSessionHandler for node {}. Ignoring fetch response.",
+                    node_id
+                ));
+                return;
+            }
+        };
+        if !handler.handle_response(&response) {
+            return;
+        }
+
+        for (partition, partition_data) in response.response_data {
+            let request_data = match data.session_partitions.get(&partition) {
+                Some(request_data) => request_data,
+                None => {
+                    // Received fetch response for missing session partition
+                    if data.metadata.is_full() {
+                        self.log.error(format_args!(
+                            "Response for missing full request partition: partition={}; metadata={}",
+                            partition, data.metadata
+                        ));
+                    } else {
+                        self.log.error(format_args!(
+                            "Response for missing session request partition: partition={}; metadata={}; toSend={:?}; toForget={:?}",
+                            partition, data.metadata, data.to_send, data.to_forget
+                        ));
+                    }
+                    continue;
+                }
+            };
+            let fetch_offset = request_data.fetch_offset;
+            self.log.debug(format_args!(
+                "Fetch {} at offset {} for partition {} returned fetch data {:?}",
+                self.isolation_level, fetch_offset, partition, partition_data
+            ));
+            state.completed_fetches.push_back(CompletedFetch::new(
+                partition,
+                partition_data,
+                fetch_offset,
+                self.isolation_level,
+                self.check_crcs,
+                self.log.clone(),
+            ));
+        }
+    }
+
+    /// If we have seen new metadata (as tracked by `Metadata::update_version`), then
+    /// we should check that all of the assignments have a valid position.
+    fn validate_positions_on_metadata_change(&self) -> Result<()> {
+        let new_metadata_update_version = self.metadata.update_version();
+        {
+            let mut state = self.lock();
+            if state.metadata_update_version == new_metadata_update_version {
+                return Ok(());
+            }
+            state.metadata_update_version = new_metadata_update_version;
+        }
+        for tp in self.subscriptions.assigned_partitions_list() {
+            let leader_and_epoch = self.metadata.current_leader(&tp);
+            self.subscriptions
+                .maybe_validate_position_for_current_leader(
+                    &self.api_versions,
+                    &tp,
+                    leader_and_epoch,
+                )?;
+        }
+        Ok(())
+    }
+
+    /// Determine which replica to read from.
+    fn select_read_replica(
+        &self,
+        partition: &TopicPartition,
+        leader_replica: Node,
+        current_time_ms: u128,
+    ) -> Result<Node> {
+        match self
+            .subscriptions
+            .preferred_read_replica(partition, current_time_ms)
+        {
+            Some(node_id) => match self.metadata.fetch().node_if_online(partition, node_id) {
+                Some(node) => Ok(node.clone()),
+                None => {
+                    self.log.trace(format_args!(
+                        "Not fetching from {} for partition {} since it is marked offline or is missing from our metadata, using the leader instead.",
+                        node_id, partition
+                    ));
+                    self.subscriptions.clear_preferred_read_replica(partition)?;
+                    Ok(leader_replica)
+                }
+            },
+            None => Ok(leader_replica),
+        }
+    }
+
+    fn is_unavailable(&self, node: &Node) -> bool {
+        self.client.connection_failed(node)
+            && self.client.connection_delay(node, self.time.milliseconds()) > 0
+    }
+
+    fn fetchable_partitions(&self) -> Vec<TopicPartition> {
+        let exclude: HashSet<TopicPartition> = {
+            let state = self.lock();
+            state
+                .next_in_line_fetch
+                .iter()
+                .filter(|fetch| !fetch.is_consumed)
+                .chain(state.completed_fetches.iter())
+                .map(|fetch| fetch.partition.clone())
+                .collect()
+        };
+        self.subscriptions
+            .fetchable_partitions(|tp| !exclude.contains(tp))
+    }
+
+    /// Create fetch requests for all nodes for which we have assigned partitions
+    /// that have no existing requests in flight.
+    fn prepare_fetch_requests(&self) -> Result<IndexMap<Node, FetchRequestData>> {
+        self.validate_positions_on_metadata_change()?;
+
+        let current_time_ms = self.time.milliseconds();
+        let mut fetchable: IndexMap<Node, Vec<(TopicPartition, PartitionData)>> = IndexMap::new();
+        for partition in self.fetchable_partitions() {
+            let position = self.subscriptions.position(&partition)?.ok_or_else(|| {
+                KafkaError::IllegalState(format!(
+                    "Missing position for fetchable partition {}",
+                    partition
+                ))
+            })?;
+
+            let leader = match &position.current_leader.leader {
+                Some(leader) => leader.clone(),
+                None => {
+                    self.log.debug(format_args!(
+                        "Requesting metadata update for partition {} since the position {} is missing the current leader node",
+                        partition, position
+                    ));
+                    self.metadata.request_update();
+                    continue;
+                }
+            };
+
+            // Use the preferred read replica if set, otherwise the position's leader
+            let node = self.select_read_replica(&partition, leader, current_time_ms)?;
+            if self.is_unavailable(&node) {
+                // If we try to send during the reconnect backoff window, then the request is just
+                // going to be failed anyway before being sent, so skip the send for now
+                self.log.trace(format_args!(
+                    "Skipping fetch for partition {} because node {} is awaiting reconnect backoff",
+                    partition, node
+                ));
+            } else if self
+                .lock()
+                .nodes_with_pending_fetch_requests
+                .contains(&node.id)
+            {
+                self.log.trace(format_args!(
+                    "Skipping fetch for partition {} because previous request to {} has not been processed",
+                    partition, node
+                ));
+            } else {
+                self.log.debug(format_args!(
+                    "Added {} fetch request for partition {} at position {} to node {}",
+                    self.isolation_level, partition, position, node
+                ));
+                // if there is a leader and no in-flight requests, issue a new fetch
+                fetchable.entry(node).or_default().push((
+                    partition,
+                    PartitionData::new(
+                        position.offset,
+                        INVALID_LOG_START_OFFSET,
+                        self.fetch_size,
+                        position.current_leader.epoch,
+                    ),
+                ));
+            }
+        }
+
+        let mut state = self.lock();
+        let mut requests = IndexMap::with_capacity(fetchable.len());
+        for (node, partitions) in fetchable {
+            let handler = state
+                .session_handlers
+                .entry(node.id)
+                .or_insert_with(|| FetchSessionHandler::new(node.id));
+            let mut builder = handler.new_builder();
+            for (partition, data) in partitions {
+                builder.add(partition, data);
+            }
+            requests.insert(node, handler.build(builder));
+        }
+        Ok(requests)
+    }
+
+    /// Return the fetched records, empty the record buffer and update the consumed position.
+    ///
+    /// NOTE: returning empty records guarantees the consumed position are NOT updated.
+    ///
+    /// Returns the fetched records per partition. Fails with `OffsetOutOfRange` if there is an
+    /// out-of-range error in the fetch response and the default reset policy is `None`, and
+    /// with `TopicAuthorization` if there is `TopicAuthorizationFailed` error in the fetch
+    /// response.
+    pub fn fetched_records(&self) -> Result<FetchedRecords> {
+        let mut fetched: FetchedRecords = IndexMap::new();
+        let mut paused_completed_fetches = vec![];
+        let mut records_remaining = self.max_poll_records;
+
+        let mut state = self.lock();
+        let result = self.collect_fetched_records(
+            &mut state,
+            &mut fetched,
+            &mut paused_completed_fetches,
+            &mut records_remaining,
+        );
+        // add any polled completed fetches for paused partitions back to the completed fetches queue to be
+        // re-evaluated in the next poll
+        state.completed_fetches.extend(paused_completed_fetches);
+        match result {
+            Err(error) if fetched.is_empty() => Err(error),
+            _ => Ok(fetched),
+        }
+    }
+
+    fn collect_fetched_records(
+        &self,
+        state: &mut FetcherState,
+        fetched: &mut FetchedRecords,
+        paused_completed_fetches: &mut Vec<CompletedFetch>,
+        records_remaining: &mut usize,
+    ) -> Result<()> {
+        while *records_remaining > 0 {
+            let next_in_line_consumed = state
+                .next_in_line_fetch
+                .as_ref()
+                .map(|fetch| fetch.is_consumed)
+                .unwrap_or(true);
+            if next_in_line_consumed {
+                let completed_fetch = match state.completed_fetches.pop_front() {
+                    Some(completed_fetch) => completed_fetch,
+                    None => break,
+                };
+                if completed_fetch.initialized {
+                    state.next_in_line_fetch = Some(completed_fetch);
+                } else {
+                    let has_records = completed_fetch.partition_data.records.size_in_bytes() > 0;
+                    match self.initialize_completed_fetch(completed_fetch) {
+                        Ok(next_in_line_fetch) => state.next_in_line_fetch = next_in_line_fetch,
+                        Err((error, completed_fetch)) => {
+                            // Remove a completed fetch upon a parse with exception if (1) it contains no records, and
+                            // (2) there are no fetched records with actual content preceding this exception.
+                            // The first condition ensures that the completed fetches is not stuck with the same completed fetch
+                            // in cases such as the TopicAuthorization error, and the second condition ensures that no
+                            // potential data loss due to an exception in a following record.
+                            if !fetched.is_empty() || has_records {
+                                state.completed_fetches.push_front(*completed_fetch);
+                            }
+                            return Err(error);
+                        }
+                    }
+                }
+            } else {
+                let mut next_in_line_fetch =
+                    state.next_in_line_fetch.take().expect("checked above");
+                if self.subscriptions.is_paused(&next_in_line_fetch.partition) {
+                    // when the partition is paused we add the records back to the completed fetches queue instead of draining
+                    // them so that they can be returned on a subsequent poll if the partition is resumed at that time
+                    self.log.debug(format_args!(
+                        "Skipping fetching records for assigned partition {} because it is paused",
+                        next_in_line_fetch.partition
+                    ));
+                    paused_completed_fetches.push(next_in_line_fetch);
+                    continue;
+                }
+                let records = self.fetch_records(&mut next_in_line_fetch, *records_remaining);
+                let partition = next_in_line_fetch.partition.clone();
+                state.next_in_line_fetch = Some(next_in_line_fetch);
+                let records = records?;
+                if !records.is_empty() {
+                    *records_remaining -= records.len();
+                    // appending to existing records shouldn't usually happen because we only send one fetch at a time per
+                    // partition, but it might conceivably happen in some rare cases (such as partition leader changes).
+                    fetched.entry(partition).or_default().extend(records);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fetch_records(
+        &self,
+        completed_fetch: &mut CompletedFetch,
+        max_records: usize,
+    ) -> Result<Vec<ConsumerRecord<Option<Bytes>, Option<Bytes>>>> {
+        let partition = completed_fetch.partition.clone();
+        if !self.subscriptions.is_assigned(&partition) {
+            // this can happen when a rebalance happened before fetched records are returned to the consumer's poll call
+            self.log.debug(format_args!(
+                "Not returning fetched records for partition {} since it is no longer assigned",
+                partition
+            ));
+        } else if !self.subscriptions.is_fetchable(&partition) {
+            // this can happen when a partition is paused before fetched records are returned to the consumer's
+            // poll call or if the offset is being reset
+            self.log.debug(format_args!(
+                "Not returning fetched records for assigned partition {} since it is no longer fetchable",
+                partition
+            ));
+        } else {
+            let position = self.subscriptions.position(&partition)?.ok_or_else(|| {
+                KafkaError::IllegalState(format!(
+                    "Missing position for fetchable partition {}",
+                    partition
+                ))
+            })?;
+            if completed_fetch.next_fetch_offset == position.offset {
+                let part_records =
+                    completed_fetch.fetch_records(max_records, &self.subscriptions)?;
+
+                self.log.trace(format_args!(
+                    "Returning {} fetched records at offset {} for assigned partition {}",
+                    part_records.len(),
+                    position,
+                    partition
+                ));
+
+                if completed_fetch.next_fetch_offset > position.offset {
+                    let next_position = FetchPosition::new(
+                        completed_fetch.next_fetch_offset,
+                        completed_fetch.last_epoch,
+                        position.current_leader,
+                    );
+                    self.log.trace(format_args!(
+                        "Update fetching position to {} for partition {}",
+                        next_position, partition
+                    ));
+                    self.subscriptions.set_position(&partition, next_position)?;
+                }
+
+                return Ok(part_records);
+            } else {
+                // these records aren't next in line based on the last consumed position, ignore them
+                // they must be from an obsolete request
+                self.log.debug(format_args!(
+                    "Ignoring fetched records for {} at offset {} since the current position is {}",
+                    partition, completed_fetch.next_fetch_offset, position
+                ));
+            }
+        }
+
+        self.log.trace(format_args!(
+            "Draining fetched records for partition {}",
+            partition
+        ));
+        completed_fetch.drain(&self.subscriptions);
+
+        Ok(vec![])
+    }
+
+    /// Initialize a CompletedFetch object. On error the completed fetch is handed back, so it
+    /// can be returned to the queue.
+    fn initialize_completed_fetch(
+        &self,
+        mut next_completed_fetch: CompletedFetch,
+    ) -> std::result::Result<Option<CompletedFetch>, (KafkaError, Box<CompletedFetch>)> {
+        let tp = next_completed_fetch.partition.clone();
+        let error = next_completed_fetch.partition_data.error;
+        let result = self.check_completed_fetch(&mut next_completed_fetch);
+        if error != Errors::None {
+            // we move the partition to the end if there was an error. This way, it's more likely that partitions for
+            // the same topic can remain together (allowing for more efficient serialization).
+            self.subscriptions.move_partition_to_end(&tp);
+        }
+        match result {
+            Ok(true) => Ok(Some(next_completed_fetch)),
+            Ok(false) => Ok(None),
+            Err(error) => Err((error, Box::new(next_completed_fetch))),
+        }
+    }
+
+    /// Returns true if the completed fetch holds records which should be returned to the user.
+    fn check_completed_fetch(&self, completed_fetch: &mut CompletedFetch) -> Result<bool> {
+        let tp = completed_fetch.partition.clone();
+        let partition = &completed_fetch.partition_data;
+        let fetch_offset = completed_fetch.next_fetch_offset;
+
+        if !self.subscriptions.has_valid_position(&tp) {
+            // this can happen when a rebalance happened while fetch is still in-flight
+            self.log.debug(format_args!(
+                "Ignoring fetched records for partition {} since it no longer has valid position",
+                tp
+            ));
+            return Ok(false);
+        }
+        match partition.error {
+            Errors::None => {
+                // we are interested in this fetch only if the beginning offset matches the
+                // current consumed position
+                let position = self.subscriptions.position(&tp)?;
+                if position.as_ref().map(|position| position.offset) != Some(fetch_offset) {
+                    self.log.debug(format_args!(
+                        "Discarding stale fetch response for partition {} since its offset {} does not match the expected offset {:?}",
+                        tp, fetch_offset, position
+                    ));
+                    return Ok(false);
+                }
+
+                self.log.trace(format_args!(
+                    "Preparing to read {} bytes of data for partition {} with offset {}",
+                    partition.records.size_in_bytes(),
+                    tp,
+                    fetch_offset
+                ));
+                if partition.records.size_in_bytes() > 0
+                    && partition.records.batches().next().is_none()
+                {
+                    // This should not happen with brokers that support FetchRequest/Response V3 or higher (i.e. KIP-74)
+                    return Err(KafkaError::Kafka(format!(
+                        "Failed to make progress reading messages at {}={}. Received a non-empty fetch response from the server, but no complete records were found.",
+                        tp, fetch_offset
+                    )));
+                }
+
+                if partition.high_watermark >= 0 {
+                    self.log.trace(format_args!(
+                        "Updating high watermark for partition {} to {}",
+                        tp, partition.high_watermark
+                    ));
+                    self.subscriptions
+                        .update_high_watermark(&tp, partition.high_watermark)?;
+                }
+
+                if partition.log_start_offset >= 0 {
+                    self.log.trace(format_args!(
+                        "Updating log start offset for partition {} to {}",
+                        tp, partition.log_start_offset
+                    ));
+                    self.subscriptions
+                        .update_log_start_offset(&tp, partition.log_start_offset)?;
+                }
+
+                if partition.last_stable_offset >= 0 {
+                    self.log.trace(format_args!(
+                        "Updating last stable offset for partition {} to {}",
+                        tp, partition.last_stable_offset
+                    ));
+                    self.subscriptions
+                        .update_last_stable_offset(&tp, partition.last_stable_offset)?;
+                }
+
+                if let Some(preferred_read_replica) = partition
+                    .preferred_read_replica
+                    .filter(|replica| *replica != fetch_response::INVALID_PREFERRED_REPLICA_ID)
+                {
+                    let expire_time_ms =
+                        self.time.milliseconds() + self.metadata.metadata_expire_ms();
+                    self.log.debug(format_args!(
+                        "Updating preferred read replica for partition {} to {}, set to expire at {}",
+                        tp, preferred_read_replica, expire_time_ms
+                    ));
+                    self.subscriptions.update_preferred_read_replica(
+                        &tp,
+                        preferred_read_replica,
+                        expire_time_ms,
+                    )?;
+                }
+
+                completed_fetch.initialize();
+                Ok(true)
+            }
+            Errors::NotLeaderOrFollower
+            | Errors::ReplicaNotAvailable
+            | Errors::KafkaStorageError
+            | Errors::FencedLeaderEpoch
+            | Errors::OffsetNotAvailable => {
+                self.log.debug(format_args!(
+                    "Error in fetch for partition {}: {}",
+                    tp,
+                    partition.error.name()
+                ));
+                self.metadata.request_update();
+                Ok(false)
+            }
+            Errors::UnknownTopicOrPartition => {
+                self.log.warn(format_args!(
+                    "Received unknown topic or partition error in fetch for partition {}",
+                    tp
+                ));
+                self.metadata.request_update();
+                Ok(false)
+            }
+            Errors::OffsetOutOfRange => {
+                match self.subscriptions.clear_preferred_read_replica(&tp)? {
+                    None => {
+                        // If there's no preferred replica to clear, we're fetching from the leader so handle this error normally
+                        match self.subscriptions.position(&tp)? {
+                            Some(position) if position.offset == fetch_offset => {
+                                self.handle_offset_out_of_range(position, &tp)?
+                            }
+                            position => self.log.debug(format_args!(
+                                "Discarding stale fetch response for partition {} since the fetched offset {} does not match the current offset {:?}",
+                                tp, fetch_offset, position
+                            )),
+                        }
+                    }
+                    Some(cleared_replica_id) => self.log.debug(format_args!(
+                        "Unset the preferred read replica {} for partition {} since we got {} when fetching {}",
+                        cleared_replica_id,
+                        tp,
+                        partition.error.name(),
+                        fetch_offset
+                    )),
+                }
+                Ok(false)
+            }
+            Errors::TopicAuthorizationFailed => {
+                //we log the actual partition and not just the topic to help with ACL propagation issues in large clusters
+                self.log.warn(format_args!(
+                    "Not authorized to read from partition {}.",
+                    tp
+                ));
+                Err(KafkaError::TopicAuthorization(format!(
+                    "Not authorized to access topics: [{}]",
+                    tp.topic
+                )))
+            }
+            Errors::UnknownLeaderEpoch => {
+                self.log.debug(format_args!(
+                    "Received unknown leader epoch error in fetch for partition {}",
+                    tp
+                ));
+                Ok(false)
+            }
+            Errors::UnknownServerError => {
+                self.log.warn(format_args!(
+                    "Unknown server error while fetching offset {} for topic-partition {}",
+                    fetch_offset, tp
+                ));
+                Ok(false)
+            }
+            Errors::CorruptMessage => Err(KafkaError::Kafka(format!(
+                "Encountered corrupt message when fetching offset {} for topic-partition {}",
+                fetch_offset, tp
+            ))),
+            error => Err(KafkaError::IllegalState(format!(
+                "Unexpected error code {} while fetching at offset {} from topic-partition {}",
+                error.code(),
+                fetch_offset,
+                tp
+            ))),
+        }
+    }
+
+    fn handle_offset_out_of_range(
+        &self,
+        fetch_position: FetchPosition,
+        topic_partition: &TopicPartition,
+    ) -> Result<()> {
+        let error_message = format!(
+            "Fetch position {} is out of range for partition {}",
+            fetch_position, topic_partition
+        );
+        if self.subscriptions.has_default_offset_reset_policy() {
+            self.log
+                .info(format_args!("{}, resetting offset", error_message));
+            self.subscriptions
+                .request_default_offset_reset(topic_partition)
+        } else {
+            self.log.info(format_args!(
+                "{}, raising error to the application since no reset policy is configured",
+                error_message
+            ));
+            Err(KafkaError::OffsetOutOfRange(error_message))
+        }
+    }
+
+    /// Reset offsets for all assigned partitions that require it.
+    ///
+    /// Fails with `NoOffsetForPartition` if no offset reset strategy is defined and one or more
+    /// partitions aren't awaiting a seek, or with the error of a previous, failed reset attempt.
+    pub fn reset_offsets_if_needed(self: &Arc<Self>) -> Result<()> {
+        // Raise exception from previous offset fetch if there is one
+        if let Some(error) = self.lock().cached_list_offsets_error.take() {
+            return Err(error);
+        }
+
+        let partitions = self
+            .subscriptions
+            .partitions_needing_reset(self.time.milliseconds());
+        if partitions.is_empty() {
+            return Ok(());
+        }
+
+        let mut offset_reset_timestamps = HashMap::new();
+        for partition in partitions {
+            if let Some(timestamp) = self.offset_reset_strategy_timestamp(&partition)? {
+                offset_reset_timestamps.insert(partition, timestamp);
+            }
+        }
+
+        self.reset_offsets_async(offset_reset_timestamps)
+    }
+
+    fn offset_reset_strategy_timestamp(&self, partition: &TopicPartition) -> Result<Option<i64>> {
+        Ok(match self.subscriptions.reset_strategy(partition)? {
+            Some(OffsetResetStrategy::Earliest) => Some(EARLIEST_TIMESTAMP),
+            Some(OffsetResetStrategy::Latest) => Some(LATEST_TIMESTAMP),
+            _ => None,
+        })
+    }
+
+    fn reset_offsets_async(
+        self: &Arc<Self>,
+        partition_reset_timestamps: HashMap<TopicPartition, i64>,
+    ) -> Result<()> {
+        let timestamps_to_search_by_node =
+            self.group_list_offset_requests(partition_reset_timestamps);
+        for (node, reset_timestamps) in timestamps_to_search_by_node {
+            let partitions: Vec<TopicPartition> = reset_timestamps.keys().cloned().collect();
+            let now = self.time.milliseconds();
+            self.subscriptions
+                .set_next_allowed_retry(&partitions, now + self.request_timeout_ms)?;
+
+            let fetcher = self.clone();
+            let request =
+                ListOffsetsRequest::for_consumer(self.isolation_level, reset_timestamps.clone());
+            self.log.debug(format_args!(
+                "Sending ListOffsetRequest {} to broker {}",
+                request, node
+            ));
+            let client_request = self.client.new_client_request(
+                &node.id_string(),
+                AbstractRequest::ListOffsets(request),
+                now,
+                true,
+                self.request_timeout_ms,
+                Some(Box::new(move |response| {
+                    fetcher.handle_reset_offsets_response(reset_timestamps, response)
+                })),
+            );
+            self.client.send(client_request, now);
+        }
+        Ok(())
+    }
+
+    fn handle_reset_offsets_response(
+        &self,
+        reset_timestamps: IndexMap<TopicPartition, ListOffsetsPartition>,
+        response: ClientResponse,
+    ) {
+        let partitions: Vec<TopicPartition> = reset_timestamps.keys().cloned().collect();
+        let result = match response.response_body {
+            Some(AbstractResponse::ListOffsets(list_offsets_response))
+                if !response.disconnected =>
+            {
+                self.handle_list_offset_response(list_offsets_response)
+            }
+            _ => Err(response.version_mismatch.clone().unwrap_or_else(|| {
+                KafkaError::Network(format!(
+                    "Disconnected from node {} while awaiting list offsets response",
+                    response.destination
+                ))
+            })),
+        };
+        let now = self.time.milliseconds();
+        match result {
+            Ok(result) => {
+                if !result.partitions_to_retry.is_empty() {
+                    self.subscriptions
+                        .request_failed(&result.partitions_to_retry, now + self.retry_backoff_ms);
+                    self.metadata.request_update();
+                }
+                for (partition, offset_data) in result.fetched_offsets {
+                    let requested_reset_timestamp = reset_timestamps
+                        .get(&partition)
+                        .map(|reset| reset.timestamp)
+                        .unwrap_or(LATEST_TIMESTAMP);
+                    let strategy = if requested_reset_timestamp == EARLIEST_TIMESTAMP {
+                        OffsetResetStrategy::Earliest
+                    } else {
+                        OffsetResetStrategy::Latest
+                    };
+                    let position = FetchPosition::new(
+                        offset_data.offset,
+                        // This will ensure we skip validation
+                        None,
+                        self.metadata.current_leader(&partition),
+                    );
+                    if let Some(epoch) = offset_data.leader_epoch {
+                        self.metadata
+                            .update_last_seen_epoch_if_newer(&partition, epoch);
+                    }
+                    if let Err(error) = self
+                        .subscriptions
+                        .maybe_seek_unvalidated(&partition, position, strategy)
+                    {
+                        self.log.warn(format_args!(
+                            "Failed to reset offset of partition {}: {}",
+                            partition, error
+                        ));
+                    }
+                }
+            }
+            Err(error) => {
+                self.subscriptions
+                    .request_failed(&partitions, now + self.retry_backoff_ms);
+                self.metadata.request_update();
+
+                if !error.is_retriable() {
+                    let mut state = self.lock();
+                    if state.cached_list_offsets_error.is_none() {
+                        state.cached_list_offsets_error = Some(error);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Validate offsets for all assigned partitions for which a leader change has been detected.
+    ///
+    /// Fails with the error of a previous validation, e.g. `LogTruncation` if truncation was
+    /// detected and there is no reset policy.
+    pub fn validate_offsets_if_needed(self: &Arc<Self>) -> Result<()> {
+        if let Some(error) = self.lock().cached_offset_for_leader_error.take() {
+            return Err(error);
+        }
+
+        // Validate each partition against the current leader and epoch
+        // If we see a new metadata version, check all partitions
+        self.validate_positions_on_metadata_change()?;
+
+        // Collect positions needing validation, with backoff
+        let mut partitions_to_validate = HashMap::new();
+        for tp in self
+            .subscriptions
+            .partitions_needing_validation(self.time.milliseconds())
+        {
+            if let Some(position) = self.subscriptions.position(&tp)? {
+                partitions_to_validate.insert(tp, position);
+            }
+        }
+
+        self.validate_offsets_async(partitions_to_validate)
+    }
+
+    fn validate_offsets_async(
+        self: &Arc<Self>,
+        partitions_to_validate: HashMap<TopicPartition, FetchPosition>,
+    ) -> Result<()> {
+        let next_reset_time_ms = self.time.milliseconds() + self.request_timeout_ms;
+        for (node, fetch_positions) in
+            self.regroup_fetch_positions_by_leader(partitions_to_validate)
+        {
+            let partitions: Vec<TopicPartition> = fetch_positions.keys().cloned().collect();
+            let node_api_versions = match self.api_versions.get(&node.id_string()) {
+                Some(node_api_versions) => node_api_versions,
+                None => {
+                    // initiate the connection, the api versions are known once it is established
+                    self.client.ready(&node, self.time.milliseconds());
+                    continue;
+                }
+            };
+
+            if !has_usable_offset_for_leader_epoch_version(&node_api_versions) {
+                self.log.debug(format_args!(
+                    "Skipping validation of fetch offsets for partitions {:?} since the broker does not support the required protocol version (introduced in Kafka 2.3)",
+                    partitions
+                ));
+                for partition in &partitions {
+                    self.subscriptions.complete_validation(partition)?;
+                }
+                continue;
+            }
+
+            self.subscriptions
+                .set_next_allowed_retry(&partitions, next_reset_time_ms)?;
+
+            let request_partitions = fetch_positions
+                .iter()
+                .filter_map(|(tp, position)| {
+                    position.offset_epoch.map(|fetch_epoch| {
+                        (
+                            tp.clone(),
+                            OffsetForLeaderPartition::new(
+                                fetch_epoch,
+                                position
+                                    .current_leader
+                                    .epoch
+                                    .unwrap_or(NO_PARTITION_LEADER_EPOCH),
+                            ),
+                        )
+                    })
+                })
+                .collect();
+            let request = OffsetsForLeaderEpochRequest::for_consumer(request_partitions);
+            self.log.debug(format_args!(
+                "Sending OffsetsForLeaderEpoch request {} to broker {}",
+                request, node
+            ));
+            let fetcher = self.clone();
+            let now = self.time.milliseconds();
+            let client_request = self.client.new_client_request(
+                &node.id_string(),
+                AbstractRequest::OffsetsForLeaderEpoch(request),
+                now,
+                true,
+                self.request_timeout_ms,
+                Some(Box::new(move |response| {
+                    fetcher.handle_validate_offsets_response(fetch_positions, response)
+                })),
+            );
+            self.client.send(client_request, now);
+        }
+        Ok(())
+    }
+
+    fn handle_validate_offsets_response(
+        &self,
+        fetch_positions: IndexMap<TopicPartition, FetchPosition>,
+        response: ClientResponse,
+    ) {
+        let result = match response.response_body {
+            Some(AbstractResponse::OffsetsForLeaderEpoch(offsets_response))
+                if !response.disconnected =>
+            {
+                self.handle_offsets_for_leader_epoch_response(&fetch_positions, offsets_response)
+            }
+            _ => Err(response.version_mismatch.clone().unwrap_or_else(|| {
+                KafkaError::Network(format!(
+                    "Disconnected from node {} while awaiting offsets for leader epoch response",
+                    response.destination
+                ))
+            })),
+        };
+        let now = self.time.milliseconds();
+        match result {
+            Ok(offsets_result) => {
+                if !offsets_result.partitions_to_retry.is_empty() {
+                    if let Err(error) = self.subscriptions.set_next_allowed_retry(
+                        &offsets_result.partitions_to_retry,
+                        now + self.retry_backoff_ms,
+                    ) {
+                        self.log.warn(format_args!(
+                            "Failed to back off offset validation: {}",
+                            error
+                        ));
+                    }
+                    self.metadata.request_update();
+                }
+
+                // For each OffsetsForLeader response, check if the end-offset is lower than our current offset
+                // for the partition. If so, it means we have experienced log truncation and need to reposition
+                // that partition's offset.
+                //
+                // In addition, check whether the returned offset and epoch are valid. If not, then we should reset
+                // its offset if reset policy is configured, or throw out of range exception.
+                let mut truncations = vec![];
+                for (tp, end_offset) in offsets_result.end_offsets {
+                    let request_position = match fetch_positions.get(&tp) {
+                        Some(request_position) => request_position,
+                        None => continue,
+                    };
+                    match self.subscriptions.maybe_complete_validation(
+                        &tp,
+                        request_position,
+                        &end_offset,
+                    ) {
+                        Ok(Some(truncation)) => truncations.push(truncation),
+                        Ok(None) => {}
+                        Err(error) => self.log.warn(format_args!(
+                            "Failed to complete validation of partition {}: {}",
+                            tp, error
+                        )),
+                    }
+                }
+
+                if !truncations.is_empty() {
+                    self.maybe_set_offset_for_leader_error(build_log_truncation_error(
+                        &truncations,
+                    ));
+                }
+            }
+            Err(error) => {
+                let partitions: Vec<TopicPartition> = fetch_positions.keys().cloned().collect();
+                self.subscriptions
+                    .request_failed(&partitions, now + self.retry_backoff_ms);
+                self.metadata.request_update();
+
+                if !error.is_retriable() {
+                    self.maybe_set_offset_for_leader_error(error);
+                }
+            }
+        }
+    }
+
+    fn maybe_set_offset_for_leader_error(&self, error: KafkaError) {
+        let mut state = self.lock();
+        match &state.cached_offset_for_leader_error {
+            Some(cached) => {
+                self.log.error(format_args!(
+                    "Discarding error {} because another error {} is pending",
+                    error, cached
+                ));
+            }
+            None => state.cached_offset_for_leader_error = Some(error),
+        }
+    }
+
+    /// Callback for the response of the offsets for leader epoch call.
+    ///
+    /// Returns the end offsets and the partitions to retry. Fails with `TopicAuthorization` if
+    /// a partition failed with `TopicAuthorizationFailed`.
+    fn handle_offsets_for_leader_epoch_response(
+        &self,
+        request_data: &IndexMap<TopicPartition, FetchPosition>,
+        response: OffsetsForLeaderEpochResponse,
+    ) -> Result<OffsetForEpochResult> {
+        let mut partitions_to_retry: HashSet<TopicPartition> =
+            request_data.keys().cloned().collect();
+        let mut unauthorized_topics = HashSet::new();
+        let mut end_offsets = HashMap::new();
+
+        for (topic_partition, partition) in response.partitions {
+            if !request_data.contains_key(&topic_partition) {
+                self.log.warn(format_args!(
+                    "Received unrequested topic or partition {} from response, ignoring.",
+                    topic_partition
+                ));
+                continue;
+            }
+
+            match partition.error {
+                Errors::None => {
+                    self.log.debug(format_args!(
+                        "Handling OffsetsForLeaderEpoch response for {}. Got offset {} for epoch {}.",
+                        topic_partition, partition.end_offset, partition.leader_epoch
+                    ));
+                    partitions_to_retry.remove(&topic_partition);
+                    end_offsets.insert(topic_partition, partition);
+                }
+                Errors::NotLeaderOrFollower
+                | Errors::ReplicaNotAvailable
+                | Errors::KafkaStorageError
+                | Errors::OffsetNotAvailable
+                | Errors::LeaderNotAvailable
+                | Errors::FencedLeaderEpoch
+                | Errors::UnknownLeaderEpoch => {
+                    self.log.debug(format_args!(
+                        "Attempt to fetch offsets for partition {} failed due to {}, retrying.",
+                        topic_partition,
+                        partition.error.name()
+                    ));
+                }
+                Errors::UnknownTopicOrPartition => {
+                    self.log.warn(format_args!(
+                        "Received unknown topic or partition error in OffsetsForLeaderEpoch request for partition {}.",
+                        topic_partition
+                    ));
+                }
+                Errors::TopicAuthorizationFailed => {
+                    partitions_to_retry.remove(&topic_partition);
+                    unauthorized_topics.insert(topic_partition.topic.clone());
+                }
+                error => {
+                    self.log.warn(format_args!(
+                        "Attempt to fetch offsets for partition {} failed due to: {}, retrying.",
+                        topic_partition,
+                        error.message()
+                    ));
+                }
+            }
+        }
+
+        if !unauthorized_topics.is_empty() {
+            let mut topics: Vec<_> = unauthorized_topics.into_iter().collect();
+            topics.sort();
+            return Err(KafkaError::TopicAuthorization(format!(
+                "Not authorized to access topics: [{}]",
+                topics.join(", ")
+            )));
+        }
+        Ok(OffsetForEpochResult {
+            end_offsets,
+            partitions_to_retry: partitions_to_retry.into_iter().collect(),
+        })
+    }
+
+    /// Group the fetch positions by the leader of their partition. Positions without a known
+    /// leader trigger a metadata update instead.
+    fn regroup_fetch_positions_by_leader(
+        &self,
+        partition_map: HashMap<TopicPartition, FetchPosition>,
+    ) -> IndexMap<Node, IndexMap<TopicPartition, FetchPosition>> {
+        let mut positions_by_leader: IndexMap<Node, IndexMap<_, _>> = IndexMap::new();
+        for (tp, position) in partition_map {
+            match position.current_leader.leader.clone() {
+                Some(leader) => {
+                    positions_by_leader
+                        .entry(leader)
+                        .or_default()
+                        .insert(tp, position);
+                }
+                None => {
+                    self.metadata.request_update();
+                }
+            }
+        }
+        positions_by_leader
+    }
+
+    /// Search the offsets by target times for the specified partitions, grouped by the leader
+    /// of the partition.
+    fn group_list_offset_requests(
+        &self,
+        timestamps_to_search: HashMap<TopicPartition, i64>,
+    ) -> IndexMap<Node, IndexMap<TopicPartition, ListOffsetsPartition>> {
+        let mut timestamps_to_search_by_node: IndexMap<Node, IndexMap<_, _>> = IndexMap::new();
+        for (tp, timestamp) in timestamps_to_search {
+            let leader_and_epoch = self.metadata.current_leader(&tp);
+            match leader_and_epoch.leader {
+                None => {
+                    self.log.debug(format_args!(
+                        "Leader for partition {} is unknown for fetching offset {}",
+                        tp, timestamp
+                    ));
+                    self.metadata.request_update();
+                }
+                Some(leader) if self.is_unavailable(&leader) => {
+                    // The connection has failed and we need to await the backoff period before we can
+                    // try again. No need to request a metadata update since the disconnect will have
+                    // done so already.
+                    self.log.debug(format_args!(
+                        "Leader {} for partition {} is unavailable for fetching offset until reconnect backoff expires",
+                        leader, tp
+                    ));
+                }
+                Some(leader) => {
+                    timestamps_to_search_by_node
+                        .entry(leader)
+                        .or_default()
+                        .insert(
+                            tp,
+                            ListOffsetsPartition::new(timestamp, leader_and_epoch.epoch),
+                        );
+                }
+            }
+        }
+        timestamps_to_search_by_node
+    }
+
+    /// Callback for the response of the list offset call.
+    ///
+    /// Returns the fetched offsets and the partitions to retry. Fails if a partition can't be
+    /// retried (e.g. authorization failure).
+    fn handle_list_offset_response(
+        &self,
+        list_offsets_response: ListOffsetsResponse,
+    ) -> Result<ListOffsetResult> {
+        let mut result = ListOffsetResult::default();
+        let mut unauthorized_topics = HashSet::new();
+
+        for (topic_partition, partition) in list_offsets_response.partitions {
+            match partition.error {
+                Errors::None => {
+                    if partition.offset != UNKNOWN_OFFSET {
+                        self.log.debug(format_args!(
+                            "Handling ListOffsetResponse response for {}. Fetched offset {}, timestamp {}",
+                            topic_partition, partition.offset, partition.timestamp
+                        ));
+                        result.fetched_offsets.insert(
+                            topic_partition,
+                            ListOffsetData {
+                                offset: partition.offset,
+                                leader_epoch: partition.leader_epoch,
+                            },
+                        );
+                    }
+                }
+                Errors::UnsupportedForMessageFormat => {
+                    // The message format on the broker side is before 0.10.0, which means it does not
+                    // support timestamps. We treat this case the same as if we weren't able to find an
+                    // offset corresponding to the requested timestamp and leave it out of the result.
+                    self.log.debug(format_args!(
+                        "Cannot search by timestamp for partition {} because the message format version is before 0.10.0",
+                        topic_partition
+                    ));
+                }
+                Errors::NotLeaderOrFollower
+                | Errors::ReplicaNotAvailable
+                | Errors::KafkaStorageError
+                | Errors::OffsetNotAvailable
+                | Errors::LeaderNotAvailable
+                | Errors::FencedLeaderEpoch
+                | Errors::UnknownLeaderEpoch => {
+                    self.log.debug(format_args!(
+                        "Attempt to fetch offsets for partition {} failed due to {}, retrying.",
+                        topic_partition,
+                        partition.error.name()
+                    ));
+                    result.partitions_to_retry.push(topic_partition);
+                }
+                Errors::UnknownTopicOrPartition => {
+                    self.log.warn(format_args!(
+                        "Received unknown topic or partition error in ListOffset request for partition {}",
+                        topic_partition
+                    ));
+                    result.partitions_to_retry.push(topic_partition);
+                }
+                Errors::TopicAuthorizationFailed => {
+                    unauthorized_topics.insert(topic_partition.topic.clone());
+                }
+                error => {
+                    self.log.warn(format_args!(
+                        "Attempt to fetch offsets for partition {} failed due to unexpected exception: {}, retrying.",
+                        topic_partition,
+                        error.message()
+                    ));
+                    result.partitions_to_retry.push(topic_partition);
+                }
+            }
+        }
+
+        if !unauthorized_topics.is_empty() {
+            let mut topics: Vec<_> = unauthorized_topics.into_iter().collect();
+            topics.sort();
+            return Err(KafkaError::TopicAuthorization(format!(
+                "Not authorized to access topics: [{}]",
+                topics.join(", ")
+            )));
+        }
+        Ok(result)
+    }
+
+    /// Clear the buffered data which are not a part of newly assigned partitions
+    pub fn clear_buffered_data_for_unassigned_partitions(
+        &self,
+        assigned_partitions: &HashSet<TopicPartition>,
+    ) {
+        let mut state = self.lock();
+        state
+            .completed_fetches
+            .retain(|fetch| assigned_partitions.contains(&fetch.partition));
+        let unassigned = state
+            .next_in_line_fetch
+            .as_ref()
+            .map(|fetch| !assigned_partitions.contains(&fetch.partition))
+            .unwrap_or(false);
+        if unassigned {
+            if let Some(mut fetch) = state.next_in_line_fetch.take() {
+                fetch.drain(&self.subscriptions);
+            }
+        }
+    }
+
+    /// Clear the buffered data which are not a part of newly assigned topics
+    pub fn clear_buffered_data_for_unassigned_topics(&self, assigned_topics: &HashSet<String>) {
+        let current_topic_partitions = self
+            .subscriptions
+            .assigned_partitions()
+            .into_iter()
+            .filter(|tp| assigned_topics.contains(&tp.topic))
+            .collect();
+        self.clear_buffered_data_for_unassigned_partitions(&current_topic_partitions);
+    }
+
+    pub fn close(&self) {
+        let mut state = self.lock();
+        if let Some(mut fetch) = state.next_in_line_fetch.take() {
+            fetch.drain(&self.subscriptions);
+        }
+        state.completed_fetches.clear();
+        state.session_handlers.clear();
+    }
+}
+
+/// Fetched data of a single partition, consumed record by record.
+struct CompletedFetch {
+    log: Logger,
+    partition: TopicPartition,
+    partition_data: fetch_response::PartitionData,
+    isolation_level: IsolationLevel,
+    check_crcs: bool,
+    batches: RecordBatchIterator,
+    aborted_producer_ids: HashSet<i64>,
+    aborted_transactions: VecDeque<AbortedTransaction>,
+
+    records_read: usize,
+    bytes_read: usize,
+    current_batch: Option<RecordBatch>,
+    records: VecDeque<DefaultRecord>,
+    last_record: Option<DefaultRecord>,
+    next_fetch_offset: i64,
+    last_epoch: Option<i32>,
+    is_consumed: bool,
+    cached_record_error: Option<KafkaError>,
+    corrupt_last_record: bool,
+    initialized: bool,
+}
+
+impl CompletedFetch {
+    fn new(
+        partition: TopicPartition,
+        partition_data: fetch_response::PartitionData,
+        fetch_offset: i64,
+        isolation_level: IsolationLevel,
+        check_crcs: bool,
+        log: Logger,
+    ) -> CompletedFetch {
+        let batches = partition_data.records.batches();
+        CompletedFetch {
+            log,
+            partition,
+            partition_data,
+            isolation_level,
+            check_crcs,
+            batches,
+            aborted_producer_ids: HashSet::new(),
+            aborted_transactions: VecDeque::new(),
+            records_read: 0,
+            bytes_read: 0,
+            current_batch: None,
+            records: VecDeque::new(),
+            last_record: None,
+            next_fetch_offset: fetch_offset,
+            last_epoch: None,
+            is_consumed: false,
+            cached_record_error: None,
+            corrupt_last_record: false,
+            initialized: false,
+        }
+    }
+
+    fn initialize(&mut self) {
+        if let Some(aborted_transactions) = &self.partition_data.aborted_transactions {
+            if self.isolation_level == IsolationLevel::ReadCommitted {
+                let mut aborted_transactions = aborted_transactions.clone();
+                aborted_transactions.sort_by_key(|transaction| transaction.first_offset);
+                self.aborted_transactions = aborted_transactions.into();
+            }
+        }
+        self.initialized = true;
+    }
+
+    fn drain(&mut self, subscriptions: &SubscriptionState) {
+        if !self.is_consumed {
+            self.records.clear();
+            self.last_record = None;
+            self.cached_record_error = None;
+            self.is_consumed = true;
+
+            // we move the partition to the end if we received some bytes. This way, it's more likely that partitions
+            // for the same topic can remain together (allowing for more efficient serialization).
+            if self.bytes_read > 0 {
+                subscriptions.move_partition_to_end(&self.partition);
+            }
+        }
+    }
+
+    fn maybe_ensure_valid(&self, batch: &RecordBatch) -> Result<()> {
+        if self.check_crcs {
+            batch.ensure_valid().map_err(|error| {
+                KafkaError::Kafka(format!(
+                    "Record batch for partition {} at offset {} is invalid, cause: {}",
+                    self.partition,
+                    batch.base_offset(),
+                    error
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    fn next_fetched_record(
+        &mut self,
+        subscriptions: &SubscriptionState,
+    ) -> Result<Option<DefaultRecord>> {
+        loop {
+            match self.records.pop_front() {
+                None => {
+                    let batch = match self.batches.next() {
+                        None => {
+                            // Message format v2 preserves the last offset in a batch even if the last record is removed
+                            // through compaction. By using the next offset computed from the last offset in the batch,
+                            // we ensure that the offset of the next fetch will point to the next batch, which avoids
+                            // unnecessary re-fetching of the same batch (in the worst case, the consumer could get stuck
+                            // fetching the same batch repeatedly).
+                            if let Some(current_batch) = &self.current_batch {
+                                self.next_fetch_offset = current_batch.next_offset();
+                            }
+                            self.drain(subscriptions);
+                            return Ok(None);
+                        }
+                        Some(batch) => batch?,
+                    };
+
+                    self.maybe_ensure_valid(&batch)?;
+
+                    if self.isolation_level == IsolationLevel::ReadCommitted
+                        && batch.has_producer_id()
+                    {
+                        // remove from the aborted transaction queue all aborted transactions which have begun
+                        // before the current batch's last offset and add the associated producerIds to the
+                        // aborted producer set
+                        self.consume_aborted_transactions_up_to(batch.last_offset());
+
+                        let producer_id = batch.producer_id();
+                        if contains_abort_marker(&batch)? {
+                            self.aborted_producer_ids.remove(&producer_id);
+                        } else if self.is_batch_aborted(&batch) {
+                            self.log.debug(format_args!(
+                                "Skipping aborted record batch from partition {} with producerId {} and offsets {} to {}",
+                                self.partition,
+                                producer_id,
+                                batch.base_offset(),
+                                batch.last_offset()
+                            ));
+                            self.next_fetch_offset = batch.next_offset();
+                            self.current_batch = Some(batch);
+                            continue;
+                        }
+                    }
+
+                    self.records = batch.records()?.into();
+                    self.current_batch = Some(batch);
+                }
+                Some(record) => {
+                    // skip any records out of range
+                    if record.offset >= self.next_fetch_offset {
+                        let is_control_batch = self
+                            .current_batch
+                            .as_ref()
+                            .map(|batch| batch.is_control_batch())
+                            .unwrap_or(false);
+                        // control records are not returned to the user
+                        if !is_control_batch {
+                            return Ok(Some(record));
+                        } else {
+                            // Increment the next fetch offset when we skip a control batch.
+                            self.next_fetch_offset = record.offset + 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn fetch_records(
+        &mut self,
+        max_records: usize,
+        subscriptions: &SubscriptionState,
+    ) -> Result<Vec<ConsumerRecord<Option<Bytes>, Option<Bytes>>>> {
+        // Error when fetching the next record. If needed, please seek past the record to continue consumption.
+        if self.corrupt_last_record {
+            return Err(KafkaError::Kafka(format!(
+                "Received exception when fetching the next record from {}. If needed, please seek past the record to continue consumption. Cause: {}",
+                self.partition,
+                self.cached_record_error
+                    .as_ref()
+                    .map(|error| error.to_string())
+                    .unwrap_or_default()
+            )));
+        }
+
+        if self.is_consumed {
+            return Ok(vec![]);
+        }
+
+        let mut records = vec![];
+        for _ in 0..max_records {
+            // Only move to next record if there was no exception in the last fetch. Otherwise we should
+            // use the last record to do deserialization again.
+            if self.cached_record_error.is_none() {
+                self.corrupt_last_record = true;
+                match self.next_fetched_record(subscriptions) {
+                    Ok(record) => self.last_record = record,
+                    Err(error) => {
+                        self.cached_record_error = Some(error.clone());
+                        if records.is_empty() {
+                            return Err(KafkaError::Kafka(format!(
+                                "Received exception when fetching the next record from {}. If needed, please seek past the record to continue consumption. Cause: {}",
+                                self.partition, error
+                            )));
+                        }
+                        break;
+                    }
+                }
+                self.corrupt_last_record = false;
+            }
+            let record = match self.last_record.take() {
+                Some(record) => record,
+                None => break,
+            };
+            self.records_read += 1;
+            self.bytes_read += record.size_in_bytes;
+            self.next_fetch_offset = record.offset + 1;
+            let record = self.parse_record(record);
+            // the epoch of the last returned record becomes the epoch of the next fetch
+            // position, which is what the position is validated against after a leader change
+            self.last_epoch = record.leader_epoch;
+            records.push(record);
+            // In some cases, the deserialization may have thrown an exception and the retry may succeed,
+            // we allow user to move forward in this case.
+            self.cached_record_error = None;
+        }
+        Ok(records)
+    }
+
+    /// Convert the record entry to a consumer record. Key and value are deserialized by the consumer.
+    fn parse_record(&self, record: DefaultRecord) -> ConsumerRecord<Option<Bytes>, Option<Bytes>> {
+        let batch = self
+            .current_batch
+            .as_ref()
+            .expect("records are read from the current batch");
+        let mut headers = RecordHeaders::default();
+        headers.extend(record.headers.iter().cloned());
+        ConsumerRecord {
+            topic: self.partition.topic.clone(),
+            partition: self.partition.partition,
+            offset: record.offset,
+            timestamp: record.timestamp,
+            timestamp_type: batch.timestamp_type(),
+            serialized_key_size: record.key_size(),
+            serialized_value_size: record.value_size(),
+            headers,
+            key: record.key,
+            value: record.value,
+            leader_epoch: match batch.partition_leader_epoch() {
+                NO_PARTITION_LEADER_EPOCH => None,
+                epoch => Some(epoch),
+            },
+        }
+    }
+
+    fn consume_aborted_transactions_up_to(&mut self, offset: i64) {
+        while let Some(aborted_transaction) = self.aborted_transactions.front() {
+            if aborted_transaction.first_offset > offset {
+                break;
+            }
+            self.aborted_producer_ids
+                .insert(aborted_transaction.producer_id);
+            self.aborted_transactions.pop_front();
+        }
+    }
+
+    fn is_batch_aborted(&self, batch: &RecordBatch) -> bool {
+        batch.is_transactional() && self.aborted_producer_ids.contains(&batch.producer_id())
+    }
+}
+
+fn build_log_truncation_error(truncations: &[LogTruncation]) -> KafkaError {
+    let truncations: Vec<_> = truncations
+        .iter()
+        .map(|truncation| truncation.to_string())
+        .collect();
+    KafkaError::LogTruncation(format!(
+        "Detected truncated partitions: [{}]",
+        truncations.join(", ")
+    ))
+}
+
+fn contains_abort_marker(batch: &RecordBatch) -> Result<bool> {
+    if !batch.is_control_batch() {
+        return Ok(false);
+    }
+    match batch.records()?.first() {
+        Some(DefaultRecord { key: Some(key), .. }) => {
+            Ok(ControlRecordType::parse(key)? == ControlRecordType::Abort)
+        }
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bytes::{Bytes, BytesMut};
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::{
+            consumer::{
+                internals::subscription_state::SubscriptionState,
+                offset_reset_strategy::OffsetResetStrategy,
+            },
+            kafka_client::KafkaClient,
+            metadata::Metadata,
+            mock_client::MockClient,
+        },
+        common::{
+            errors::KafkaError,
+            isolation_level::IsolationLevel,
+            protocol::errors::Errors,
+            record::{
+                compression_type::CompressionType,
+                legacy_record_batch,
+                memory_records::MemoryRecords,
+                memory_records_builder::MemoryRecordsBuilder,
+                record_batch::{
+                    NO_PARTITION_LEADER_EPOCH, NO_PRODUCER_EPOCH, NO_PRODUCER_ID, NO_SEQUENCE,
+                    NO_TIMESTAMP,
+                },
+                timestamp_type::TimestampType,
+            },
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                fetch_metadata::{FetchMetadata, INVALID_SESSION_ID},
+                fetch_response::{self, FetchResponse},
+                list_offsets_request::EARLIEST_TIMESTAMP,
+                list_offsets_response::{ListOffsetsPartitionResponse, ListOffsetsResponse},
+            },
+            topic_partition::TopicPartition,
+            utils::{log_context::LogContext, mock_time::MockTime, time::Time},
+        },
+        test_utils::MockBroker,
+    };
+
+    use super::Fetcher;
+
+    const TOPIC: &str = "test";
+    const SESSION_ID: i32 = 123;
+    const RETRY_BACKOFF_MS: u128 = 100;
+    const REQUEST_TIMEOUT_MS: u128 = 30_000;
+
+    struct Context {
+        time: Arc<MockTime>,
+        client: Arc<MockClient>,
+        subscriptions: Arc<SubscriptionState>,
+        fetcher: Arc<Fetcher>,
+    }
+
+    impl Context {
+        fn new(reset_strategy: OffsetResetStrategy) -> Context {
+            let mock_broker = MockBroker::new();
+            let cluster = mock_broker.cluster(TOPIC, 2);
+            let MockBroker {
+                time,
+                api_versions,
+                client,
+                ..
+            } = mock_broker;
+
+            let metadata = Arc::new(Metadata::new(RETRY_BACKOFF_MS, 300_000));
+            metadata
+                .update(
+                    metadata.request_version(),
+                    cluster,
+                    false,
+                    time.milliseconds(),
+                )
+                .unwrap();
+            let subscriptions = Arc::new(SubscriptionState::new(reset_strategy));
+            subscriptions
+                .assign_from_user(&HashSet::from_iter(vec![tp0()]))
+                .unwrap();
+            let fetcher = Arc::new(Fetcher::new(
+                client.clone(),
+                metadata,
+                subscriptions.clone(),
+                api_versions,
+                time.clone(),
+                1,
+                i32::MAX,
+                500,
+                1024 * 1024,
+                500,
+                true,
+                "",
+                IsolationLevel::ReadUncommitted,
+                RETRY_BACKOFF_MS,
+                REQUEST_TIMEOUT_MS,
+                &LogContext::default(),
+            ));
+            Context {
+                time,
+                client,
+                subscriptions,
+                fetcher,
+            }
+        }
+
+        /// Send the fetch of the partition, returning the session metadata of the request.
+        fn send_fetch(&self) -> FetchMetadata {
+            assert_eq!(self.fetcher.send_fetches().unwrap(), 1);
+            match self.client.requests().last() {
+                Some(AbstractRequest::Fetch(request)) => request.metadata,
+                request => panic!("Expected a fetch request, got {:?}", request),
+            }
+        }
+
+        /// Answer the oldest request with `response`, running the response handler.
+        fn respond(&self, response: AbstractResponse) {
+            assert!(self.client.respond(response));
+            self.client.poll(0, self.time.milliseconds());
+        }
+
+        /// Send the fetch and answer it with `response`.
+        fn fetch(&self, response: FetchResponse) -> FetchMetadata {
+            let metadata = self.send_fetch();
+            self.respond(AbstractResponse::Fetch(response));
+            metadata
+        }
+
+        fn position(&self) -> Option<i64> {
+            self.subscriptions
+                .position(&tp0())
+                .unwrap()
+                .map(|position| position.offset)
+        }
+    }
+
+    fn tp0() -> TopicPartition {
+        TopicPartition::new(TOPIC, 0)
+    }
+
+    /// Records of the values "v<offset>" at the given offsets.
+    fn records(base_offset: i64, count: i64) -> MemoryRecords {
+        let mut builder = MemoryRecordsBuilder::new(
+            BytesMut::with_capacity(1024),
+            CompressionType::None,
+            TimestampType::CreateTime,
+            base_offset,
+            NO_TIMESTAMP,
+            NO_PRODUCER_ID,
+            NO_PRODUCER_EPOCH,
+            NO_SEQUENCE,
+            false,
+            false,
+            NO_PARTITION_LEADER_EPOCH,
+            1024,
+        )
+        .unwrap();
+        for offset in base_offset..base_offset + count {
+            builder
+                .append(0, None, Some(format!("v{}", offset).as_bytes()), &[])
+                .unwrap();
+        }
+        builder.build().unwrap()
+    }
+
+    fn fetch_response(
+        session_id: i32,
+        error: Errors,
+        records: MemoryRecords,
+        high_watermark: i64,
+    ) -> FetchResponse {
+        let mut partitions = IndexMap::new();
+        partitions.insert(
+            tp0(),
+            fetch_response::PartitionData::new(error, high_watermark, -1, 0, None, records),
+        );
+        FetchResponse::new(Errors::None, session_id, partitions)
+    }
+
+    #[test]
+    fn fetched_records_advance_the_position() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+
+        context.fetch(fetch_response(
+            INVALID_SESSION_ID,
+            Errors::None,
+            records(0, 3),
+            100,
+        ));
+        assert!(context.fetcher.has_completed_fetches());
+        let fetched = context.fetcher.fetched_records().unwrap();
+        let values: Vec<Option<Bytes>> = fetched[&tp0()]
+            .iter()
+            .map(|record| record.value.clone())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                Some(Bytes::from_static(b"v0")),
+                Some(Bytes::from_static(b"v1")),
+                Some(Bytes::from_static(b"v2"))
+            ]
+        );
+        assert_eq!(context.position(), Some(3));
+        assert_eq!(
+            context
+                .subscriptions
+                .partition_lag(&tp0(), IsolationLevel::ReadUncommitted)
+                .unwrap(),
+            Some(97)
+        );
+        assert!(!context.fetcher.has_completed_fetches());
+    }
+
+    #[test]
+    fn legacy_records_are_fetched() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 5).unwrap();
+
+        context.fetch(fetch_response(
+            INVALID_SESSION_ID,
+            Errors::None,
+            MemoryRecords::new(Bytes::from_static(&legacy_record_batch::tests::V1)),
+            7,
+        ));
+        let fetched = context.fetcher.fetched_records().unwrap();
+        let records = &fetched[&tp0()];
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].offset, 5);
+        assert_eq!(records[0].timestamp, 1000);
+        assert_eq!(records[0].key.as_deref(), Some(&b"k"[..]));
+        assert_eq!(records[1].offset, 6);
+        assert_eq!(records[1].value.as_deref(), Some(&b"v1"[..]));
+        assert_eq!(context.position(), Some(7));
+    }
+
+    #[test]
+    fn fetch_session_is_continued_and_reset_when_not_found() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+
+        let metadata = context.fetch(fetch_response(SESSION_ID, Errors::None, records(0, 1), 10));
+        assert_eq!(metadata, FetchMetadata::INITIAL);
+        context.fetcher.fetched_records().unwrap();
+
+        // the session created by the broker is used by the next fetches
+        let metadata = context.fetch(fetch_response(SESSION_ID, Errors::None, records(1, 1), 10));
+        assert_eq!(metadata, FetchMetadata::new(SESSION_ID, 1));
+        context.fetcher.fetched_records().unwrap();
+        assert_eq!(context.send_fetch(), FetchMetadata::new(SESSION_ID, 2));
+        context.respond(AbstractResponse::Fetch(FetchResponse::new(
+            Errors::FetchSessionIdNotFound,
+            INVALID_SESSION_ID,
+            IndexMap::new(),
+        )));
+        assert!(!context.fetcher.has_completed_fetches());
+
+        // the session is gone, the next fetch is a full one
+        assert_eq!(context.send_fetch(), FetchMetadata::INITIAL);
+    }
+
+    #[test]
+    fn only_one_fetch_is_in_flight_per_node() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+        context.send_fetch();
+        assert_eq!(context.fetcher.send_fetches().unwrap(), 0);
+
+        // a disconnection completes the request, the node can be fetched from again
+        context.client.disconnect("0");
+        context.client.poll(0, context.time.milliseconds());
+        assert!(!context.fetcher.has_completed_fetches());
+        assert_eq!(context.fetcher.send_fetches().unwrap(), 1);
+    }
+
+    #[test]
+    fn offset_out_of_range_resets_the_position() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+
+        context.fetch(fetch_response(
+            INVALID_SESSION_ID,
+            Errors::OffsetOutOfRange,
+            MemoryRecords::empty(),
+            100,
+        ));
+        assert!(context.fetcher.fetched_records().unwrap().is_empty());
+        assert!(context
+            .subscriptions
+            .is_offset_reset_needed(&tp0())
+            .unwrap());
+        assert!(!context.subscriptions.has_valid_position(&tp0()));
+
+        // the reset looks up the earliest offset
+        context.fetcher.reset_offsets_if_needed().unwrap();
+        match context.client.requests().last() {
+            Some(AbstractRequest::ListOffsets(request)) => {
+                assert_eq!(request.partitions[&tp0()].timestamp, EARLIEST_TIMESTAMP);
+            }
+            request => panic!("Expected a list offsets request, got {:?}", request),
+        }
+        let mut offsets = IndexMap::new();
+        offsets.insert(
+            tp0(),
+            ListOffsetsPartitionResponse::new(Errors::None, -1, 50, None),
+        );
+        context.respond(AbstractResponse::ListOffsets(ListOffsetsResponse::new(
+            offsets,
+        )));
+        assert!(!context
+            .subscriptions
+            .is_offset_reset_needed(&tp0())
+            .unwrap());
+        assert_eq!(context.position(), Some(50));
+    }
+
+    #[test]
+    fn failed_reset_is_retried_after_the_backoff() {
+        let context = Context::new(OffsetResetStrategy::Latest);
+        context
+            .subscriptions
+            .request_offset_reset(&tp0(), OffsetResetStrategy::Latest)
+            .unwrap();
+        context.fetcher.reset_offsets_if_needed().unwrap();
+        let mut offsets = IndexMap::new();
+        offsets.insert(
+            tp0(),
+            ListOffsetsPartitionResponse::from_error(Errors::NotLeaderOrFollower),
+        );
+        context.respond(AbstractResponse::ListOffsets(ListOffsetsResponse::new(
+            offsets,
+        )));
+        assert!(context
+            .subscriptions
+            .is_offset_reset_needed(&tp0())
+            .unwrap());
+
+        // backing off
+        context.fetcher.reset_offsets_if_needed().unwrap();
+        assert!(context.client.requests().is_empty());
+        context.time.sleep(RETRY_BACKOFF_MS);
+        context.fetcher.reset_offsets_if_needed().unwrap();
+        assert_eq!(context.client.requests().len(), 1);
+    }
+
+    #[test]
+    fn offset_out_of_range_without_reset_policy_is_an_error() {
+        let context = Context::new(OffsetResetStrategy::None);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+
+        context.fetch(fetch_response(
+            INVALID_SESSION_ID,
+            Errors::OffsetOutOfRange,
+            MemoryRecords::empty(),
+            100,
+        ));
+        assert!(matches!(
+            context.fetcher.fetched_records(),
+            Err(KafkaError::OffsetOutOfRange(_))
+        ));
+        // the error is returned once, the position is kept
+        assert!(context.fetcher.fetched_records().unwrap().is_empty());
+        assert_eq!(context.position(), Some(0));
+        assert!(!context
+            .subscriptions
+            .is_offset_reset_needed(&tp0())
+            .unwrap());
+    }
+
+    #[test]
+    fn stale_offset_out_of_range_is_ignored() {
+        let context = Context::new(OffsetResetStrategy::Earliest);
+        context.subscriptions.seek(&tp0(), 0).unwrap();
+        context.send_fetch();
+        // the user seeks while the fetch is in flight
+        context.subscriptions.seek(&tp0(), 10).unwrap();
+        context.respond(AbstractResponse::Fetch(fetch_response(
+            INVALID_SESSION_ID,
+            Errors::OffsetOutOfRange,
+            MemoryRecords::empty(),
+            100,
+        )));
+
+        assert!(context.fetcher.fetched_records().unwrap().is_empty());
+        assert!(!context
+            .subscriptions
+            .is_offset_reset_needed(&tp0())
+            .unwrap());
+        assert_eq!(context.position(), Some(10));
+    }
+}
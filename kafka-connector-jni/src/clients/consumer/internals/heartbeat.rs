@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use log::trace;
+
+use crate::{
+    clients::group_rebalance_config::GroupRebalanceConfig,
+    common::{
+        errors::{KafkaError, Result},
+        utils::{time::Time, timer::Timer},
+    },
+};
+
+/// A helper class for managing the heartbeat to the coordinator
+pub struct Heartbeat {
+    max_poll_interval_ms: u128,
+    rebalance_config: GroupRebalanceConfig,
+    time: Arc<dyn Time>,
+    heartbeat_timer: Timer,
+    session_timer: Timer,
+    poll_timer: Timer,
+    last_heartbeat_send: u128,
+    heartbeat_in_flight: bool,
+}
+
+impl Heartbeat {
+    /// Fails with `IllegalArgument` if the heartbeat interval isn't lower than the session
+    /// timeout.
+    pub fn new(config: GroupRebalanceConfig, time: Arc<dyn Time>) -> Result<Heartbeat> {
+        if config.heartbeat_interval_ms >= config.session_timeout_ms {
+            return Err(KafkaError::IllegalArgument(
+                "Heartbeat must be set lower than the session timeout".into(),
+            ));
+        }
+        Ok(Heartbeat {
+            max_poll_interval_ms: config.rebalance_timeout_ms,
+            heartbeat_timer: Timer::new(time.clone(), config.heartbeat_interval_ms),
+            session_timer: Timer::new(time.clone(), config.session_timeout_ms),
+            poll_timer: Timer::new(time.clone(), config.rebalance_timeout_ms),
+            rebalance_config: config,
+            time,
+            last_heartbeat_send: 0,
+            heartbeat_in_flight: false,
+        })
+    }
+
+    fn update(&mut self, now: u128) {
+        self.heartbeat_timer.update_to(now);
+        self.session_timer.update_to(now);
+        self.poll_timer.update_to(now);
+    }
+
+    pub fn poll(&mut self, now: u128) {
+        self.update(now);
+        self.poll_timer.reset(self.max_poll_interval_ms);
+    }
+
+    pub fn has_inflight(&self) -> bool {
+        self.heartbeat_in_flight
+    }
+
+    pub fn sent_heartbeat(&mut self, now: u128) {
+        self.last_heartbeat_send = now;
+        self.heartbeat_in_flight = true;
+        self.update(now);
+        self.heartbeat_timer
+            .reset(self.rebalance_config.heartbeat_interval_ms);
+        trace!(
+            "[Heartbeat groupID={}] Sending heartbeat request with {}ms remaining on timer",
+            self.rebalance_config.group_id,
+            self.heartbeat_timer.remaining_ms()
+        );
+    }
+
+    pub fn fail_heartbeat(&mut self) {
+        self.update(self.time.milliseconds());
+        self.heartbeat_in_flight = false;
+        self.heartbeat_timer
+            .reset(self.rebalance_config.retry_backoff_ms);
+        trace!(
+            "[Heartbeat groupID={}] Heartbeat failed, reset the timer to {}ms remaining",
+            self.rebalance_config.group_id,
+            self.heartbeat_timer.remaining_ms()
+        );
+    }
+
+    pub fn receive_heartbeat(&mut self) {
+        self.update(self.time.milliseconds());
+        self.heartbeat_in_flight = false;
+        self.session_timer
+            .reset(self.rebalance_config.session_timeout_ms);
+    }
+
+    pub fn should_heartbeat(&mut self, now: u128) -> bool {
+        self.update(now);
+        self.heartbeat_timer.is_expired()
+    }
+
+    pub fn last_heartbeat_send(&self) -> u128 {
+        self.last_heartbeat_send
+    }
+
+    pub fn time_to_next_heartbeat(&mut self, now: u128) -> u128 {
+        self.update(now);
+        self.heartbeat_timer.remaining_ms()
+    }
+
+    pub fn session_timeout_expired(&mut self, now: u128) -> bool {
+        self.update(now);
+        self.session_timer.is_expired()
+    }
+
+    pub fn reset_timeouts(&mut self) {
+        self.update(self.time.milliseconds());
+        self.session_timer
+            .reset(self.rebalance_config.session_timeout_ms);
+        self.poll_timer.reset(self.max_poll_interval_ms);
+        self.heartbeat_timer
+            .reset(self.rebalance_config.heartbeat_interval_ms);
+    }
+
+    pub fn reset_session_timeout(&mut self) {
+        self.update(self.time.milliseconds());
+        self.session_timer
+            .reset(self.rebalance_config.session_timeout_ms);
+    }
+
+    pub fn poll_timeout_expired(&mut self, now: u128) -> bool {
+        self.update(now);
+        self.poll_timer.is_expired()
+    }
+
+    pub fn last_poll_time(&self) -> u128 {
+        self.poll_timer.current_time_ms()
+    }
+}
@@ -0,0 +1,11 @@
+pub mod abstract_coordinator;
+pub mod abstract_partition_assignor;
+pub mod abstract_sticky_assignor;
+pub mod consumer_coordinator;
+pub mod consumer_metadata;
+pub mod consumer_network_client;
+pub mod consumer_protocol;
+pub mod fetcher;
+pub mod heartbeat;
+pub mod request_future;
+pub mod subscription_state;
@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use log::warn;
+
+use crate::common::errors::{KafkaError, Result};
+
+type Listener<T> = Box<dyn FnOnce(&Result<T>) + Send>;
+
+/// Result of an asynchronous request from `ConsumerNetworkClient`. Use `ConsumerNetworkClient::poll_future`
+/// to wait for the request to complete. Once complete, use `succeeded` to check if the request
+/// was successful, and `value` to obtain the result or `exception` to obtain the error.
+///
+/// Listeners are invoked on the thread completing the future, after the result has been set.
+pub struct RequestFuture<T> {
+    state: Mutex<RequestFutureState<T>>,
+}
+
+struct RequestFutureState<T> {
+    result: Option<Result<T>>,
+    listeners: Vec<Listener<T>>,
+}
+
+impl<T: Clone + Send + 'static> RequestFuture<T> {
+    pub fn new() -> Arc<RequestFuture<T>> {
+        Arc::new(RequestFuture {
+            state: Mutex::new(RequestFutureState {
+                result: None,
+                listeners: vec![],
+            }),
+        })
+    }
+
+    /// Convenience method returning an already completed future.
+    pub fn completed(value: T) -> Arc<RequestFuture<T>> {
+        let future = RequestFuture::new();
+        future.complete(value);
+        future
+    }
+
+    /// Convenience method returning an already failed future.
+    pub fn failure(error: KafkaError) -> Arc<RequestFuture<T>> {
+        let future = RequestFuture::new();
+        future.raise(error);
+        future
+    }
+
+    /// A future failed because the coordinator is not available.
+    pub fn coordinator_not_available() -> Arc<RequestFuture<T>> {
+        RequestFuture::failure(KafkaError::CoordinatorNotAvailable(
+            "The coordinator is not available.".into(),
+        ))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RequestFutureState<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Check whether the response is ready to be handled
+    pub fn is_done(&self) -> bool {
+        self.lock().result.is_some()
+    }
+
+    /// Check if the request succeeded;
+    pub fn succeeded(&self) -> bool {
+        matches!(self.lock().result, Some(Ok(_)))
+    }
+
+    /// Check if the request failed.
+    pub fn failed(&self) -> bool {
+        matches!(self.lock().result, Some(Err(_)))
+    }
+
+    /// Check if the request is retriable. Returns false if the request has not failed.
+    pub fn is_retriable(&self) -> bool {
+        matches!(&self.lock().result, Some(Err(error)) if error.is_retriable())
+    }
+
+    /// Get the value corresponding to this request (only available if the request succeeded)
+    pub fn value(&self) -> Option<T> {
+        match &self.lock().result {
+            Some(Ok(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Get the exception from a failed result (only available if the request failed)
+    pub fn exception(&self) -> Option<KafkaError> {
+        match &self.lock().result {
+            Some(Err(error)) => Some(error.clone()),
+            _ => None,
+        }
+    }
+
+    /// The result of the completed request, `None` if it's still pending.
+    pub fn result(&self) -> Option<Result<T>> {
+        self.lock().result.clone()
+    }
+
+    /// Complete the request successfully. After this call, `succeeded` will return true
+    /// and the value can be obtained through `value`.
+    ///
+    /// Completing an already completed future is a programming error; the second result is
+    /// dropped.
+    pub fn complete(&self, value: T) {
+        self.set_result(Ok(value));
+    }
+
+    /// Raise an exception. The request will be marked as failed, and the caller can either
+    /// handle the exception or throw it.
+    pub fn raise(&self, error: KafkaError) {
+        self.set_result(Err(error));
+    }
+
+    fn set_result(&self, result: Result<T>) {
+        let listeners = {
+            let mut state = self.lock();
+            if state.result.is_some() {
+                warn!("Invalid attempt to complete a request future which is already complete");
+                return;
+            }
+            state.result = Some(result.clone());
+            std::mem::take(&mut state.listeners)
+        };
+        for listener in listeners {
+            listener(&result);
+        }
+    }
+
+    /// Add a listener which will be notified when the future completes. If the future is
+    /// already complete, the listener is invoked immediately.
+    pub fn add_listener(&self, listener: impl FnOnce(&Result<T>) + Send + 'static) {
+        let result = {
+            let mut state = self.lock();
+            match &state.result {
+                Some(result) => result.clone(),
+                None => {
+                    state.listeners.push(Box::new(listener));
+                    return;
+                }
+            }
+        };
+        listener(&result);
+    }
+
+    /// Propagate the result of this future to `future` once it completes.
+    pub fn chain(&self, future: Arc<RequestFuture<T>>) {
+        self.add_listener(move |result| match result {
+            Ok(value) => future.complete(value.clone()),
+            Err(error) => future.raise(error.clone()),
+        });
+    }
+
+    /// Convert from a request future of one type to another type. Errors are propagated as is.
+    pub fn compose<S: Clone + Send + 'static>(
+        &self,
+        adapter: impl FnOnce(T, &Arc<RequestFuture<S>>) + Send + 'static,
+    ) -> Arc<RequestFuture<S>> {
+        let adapted = RequestFuture::new();
+        let future = adapted.clone();
+        self.add_listener(move |result| match result {
+            Ok(value) => adapter(value.clone(), &future),
+            Err(error) => future.raise(error.clone()),
+        });
+        adapted
+    }
+}
@@ -0,0 +1,1590 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    sync::{Mutex, MutexGuard},
+};
+
+use indexmap::IndexMap;
+use log::{debug, info, warn};
+use regex::Regex;
+
+use crate::{
+    clients::{
+        api_versions::ApiVersions,
+        consumer::{
+            offset_and_metadata::OffsetAndMetadata, offset_reset_strategy::OffsetResetStrategy,
+        },
+        metadata::LeaderAndEpoch,
+    },
+    common::{
+        errors::{KafkaError, Result},
+        isolation_level::IsolationLevel,
+        requests::offsets_for_leader_epoch_response::EpochEndOffset,
+        topic_partition::TopicPartition,
+    },
+};
+
+use super::fetcher::has_usable_offset_for_leader_epoch_version;
+
+const SUBSCRIPTION_EXCEPTION_MESSAGE: &str =
+    "Subscription to topics, partitions and pattern are mutually exclusive";
+
+/// A class for tracking the topics, partitions, and offsets for the consumer. A partition
+/// is "assigned" either directly with `assign_from_user` (manual assignment)
+/// or with `assign_from_subscribed` (automatic assignment from subscription).
+///
+/// Once assigned, the partition is not considered "fetchable" until its initial position has
+/// been set with `seek_validated`. Fetchable partitions track a fetch
+/// position which is used to set the offset of the next fetch, and a consumed position
+/// which is the last offset that has been returned to the user. You can suspend fetching
+/// from a partition through `pause` without affecting the fetched/consumed
+/// offsets. The partition will remain unfetchable until the `resume` is
+/// used. You can also query the pause state independently with `is_paused`.
+///
+/// Note that pause state as well as fetch/consumed positions are not preserved when partition
+/// assignment is changed whether directly by the user or through a group rebalance.
+///
+/// This class is shared between the application thread and the fetcher, all methods are
+/// synchronized.
+pub struct SubscriptionState {
+    /// Default offset reset strategy
+    default_reset_strategy: OffsetResetStrategy,
+    state: Mutex<SubscriptionStateInner>,
+}
+
+struct SubscriptionStateInner {
+    /// The type of subscription
+    subscription_type: SubscriptionType,
+    /// The pattern user has requested
+    subscribed_pattern: Option<SubscribedPattern>,
+    /// The list of topics the user has requested
+    subscription: HashSet<String>,
+    /// The list of topics the group has subscribed to. This may include some topics which are not part
+    /// of `subscription` for the leader of a group since it is responsible for detecting metadata changes
+    /// which require a group rebalance.
+    group_subscription: HashSet<String>,
+    /// The partitions that are currently assigned, note that the order of partition matters (see
+    /// FetchBuilder for more details)
+    assignment: IndexMap<TopicPartition, TopicPartitionState>,
+    /// Assignment id, bumped on every assignment change
+    assignment_id: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SubscriptionType {
+    None,
+    AutoTopics,
+    AutoPattern,
+    UserAssigned,
+}
+
+/// Pattern of a pattern subscription. Topics have to match the whole pattern, so a copy
+/// anchored at both ends is kept next to the one provided by the user.
+#[derive(Debug, Clone)]
+struct SubscribedPattern {
+    pattern: Regex,
+    anchored: Regex,
+}
+
+impl SubscribedPattern {
+    fn new(pattern: Regex) -> SubscribedPattern {
+        let anchored = Regex::new(&format!("^(?:{})$", pattern.as_str()))
+            .expect("anchoring a valid pattern keeps it valid");
+        SubscribedPattern { pattern, anchored }
+    }
+
+    fn matches(&self, topic: &str) -> bool {
+        self.anchored.is_match(topic)
+    }
+}
+
+/// The fetch state of a partition. Used to determine valid state transitions and expose some of
+/// the behavior of the current fetch state. Actual state variables are stored in the
+/// `TopicPartitionState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FetchState {
+    Initializing,
+    Fetching,
+    AwaitReset,
+    AwaitValidation,
+}
+
+impl FetchState {
+    /// Return the valid states which this state can transition to
+    fn valid_transitions(&self) -> &'static [FetchState] {
+        match self {
+            FetchState::Initializing | FetchState::Fetching | FetchState::AwaitValidation => &[
+                FetchState::Fetching,
+                FetchState::AwaitReset,
+                FetchState::AwaitValidation,
+            ],
+            FetchState::AwaitReset => &[FetchState::Fetching, FetchState::AwaitReset],
+        }
+    }
+
+    fn transition_to(self, new_state: FetchState) -> FetchState {
+        if self.valid_transitions().contains(&new_state) {
+            new_state
+        } else {
+            self
+        }
+    }
+
+    /// Test if this state requires a position to be set
+    pub fn requires_position(&self) -> bool {
+        matches!(self, FetchState::Fetching | FetchState::AwaitValidation)
+    }
+
+    /// Test if this state is considered to have a valid position which can be used for fetching
+    pub fn has_valid_position(&self) -> bool {
+        matches!(self, FetchState::Fetching)
+    }
+}
+
+/// Represents the position of a partition subscription.
+///
+/// This includes the offset and epoch from the last record in
+/// the batch from a FetchResponse. It also includes the leader epoch at the time the batch was consumed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchPosition {
+    pub offset: i64,
+    pub offset_epoch: Option<i32>,
+    pub current_leader: LeaderAndEpoch,
+}
+
+impl FetchPosition {
+    pub fn new(
+        offset: i64,
+        offset_epoch: Option<i32>,
+        current_leader: LeaderAndEpoch,
+    ) -> FetchPosition {
+        FetchPosition {
+            offset,
+            offset_epoch,
+            current_leader,
+        }
+    }
+
+    pub fn from_offset(offset: i64) -> FetchPosition {
+        FetchPosition::new(offset, None, LeaderAndEpoch::no_leader_or_epoch())
+    }
+}
+
+impl Display for FetchPosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FetchPosition{{offset={}, offsetEpoch=", self.offset)?;
+        match self.offset_epoch {
+            Some(epoch) => write!(f, "Optional[{}]", epoch)?,
+            None => f.write_str("Optional.empty")?,
+        }
+        write!(f, ", currentLeader={}}}", self.current_leader)
+    }
+}
+
+/// Log truncation detected while validating the position of a partition, when there is no
+/// reset policy to recover from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogTruncation {
+    pub topic_partition: TopicPartition,
+    pub fetch_position: FetchPosition,
+    pub divergent_offset: Option<OffsetAndMetadata>,
+}
+
+impl LogTruncation {
+    pub fn new(
+        topic_partition: TopicPartition,
+        fetch_position: FetchPosition,
+        divergent_offset: Option<OffsetAndMetadata>,
+    ) -> LogTruncation {
+        LogTruncation {
+            topic_partition,
+            fetch_position,
+            divergent_offset,
+        }
+    }
+}
+
+impl Display for LogTruncation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(partition={}, fetchOffset={}, fetchEpoch={:?}",
+            self.topic_partition, self.fetch_position.offset, self.fetch_position.offset_epoch
+        )?;
+        match &self.divergent_offset {
+            Some(divergent_offset) => write!(
+                f,
+                ", divergentOffset={}, divergentEpoch={:?}",
+                divergent_offset.offset, divergent_offset.leader_epoch
+            )?,
+            None => f.write_str(", divergentOffset=unknown, divergentEpoch=unknown")?,
+        }
+        f.write_str(")")
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TopicPartitionState {
+    fetch_state: FetchState,
+    /// last consumed position
+    position: Option<FetchPosition>,
+    /// the high watermark from last fetch
+    high_watermark: Option<i64>,
+    /// the log start offset
+    log_start_offset: Option<i64>,
+    last_stable_offset: Option<i64>,
+    /// whether this partition has been paused by the user
+    paused: bool,
+    /// the strategy to use if the offset needs resetting
+    reset_strategy: Option<OffsetResetStrategy>,
+    next_retry_time_ms: Option<u128>,
+    preferred_read_replica: Option<i32>,
+    preferred_read_replica_expire_time_ms: Option<u128>,
+    end_offset_requested: bool,
+}
+
+impl TopicPartitionState {
+    fn new() -> TopicPartitionState {
+        TopicPartitionState {
+            fetch_state: FetchState::Initializing,
+            position: None,
+            high_watermark: None,
+            log_start_offset: None,
+            last_stable_offset: None,
+            paused: false,
+            reset_strategy: None,
+            next_retry_time_ms: None,
+            preferred_read_replica: None,
+            preferred_read_replica_expire_time_ms: None,
+            end_offset_requested: false,
+        }
+    }
+
+    fn transition_state(
+        &mut self,
+        new_state: FetchState,
+        run_if_transitioned: impl FnOnce(&mut TopicPartitionState),
+    ) -> Result<()> {
+        let next_state = self.fetch_state.transition_to(new_state);
+        if next_state == new_state {
+            self.fetch_state = next_state;
+            run_if_transitioned(self);
+            if self.position.is_none() && next_state.requires_position() {
+                return Err(KafkaError::IllegalState(format!(
+                    "Transitioned subscription state to {:?}, but position is null",
+                    next_state
+                )));
+            } else if !next_state.requires_position() {
+                self.position = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn preferred_read_replica(&mut self, time_ms: u128) -> Option<i32> {
+        match self.preferred_read_replica_expire_time_ms {
+            Some(expire_time_ms) if time_ms > expire_time_ms => {
+                self.preferred_read_replica = None;
+                None
+            }
+            _ => self.preferred_read_replica,
+        }
+    }
+
+    fn update_preferred_read_replica(&mut self, preferred_read_replica: i32, time_ms: u128) {
+        if self.preferred_read_replica != Some(preferred_read_replica) {
+            self.preferred_read_replica = Some(preferred_read_replica);
+            self.preferred_read_replica_expire_time_ms = Some(time_ms);
+        }
+    }
+
+    fn clear_preferred_read_replica(&mut self) -> Option<i32> {
+        self.preferred_read_replica_expire_time_ms = None;
+        self.preferred_read_replica.take()
+    }
+
+    fn reset(&mut self, strategy: OffsetResetStrategy) -> Result<()> {
+        self.transition_state(FetchState::AwaitReset, |state| {
+            state.reset_strategy = Some(strategy);
+            state.next_retry_time_ms = None;
+        })
+    }
+
+    fn awaiting_retry_backoff(&self, now_ms: u128) -> bool {
+        matches!(self.next_retry_time_ms, Some(next_retry_time_ms) if now_ms < next_retry_time_ms)
+    }
+
+    fn awaiting_reset(&self) -> bool {
+        self.fetch_state == FetchState::AwaitReset
+    }
+
+    fn awaiting_validation(&self) -> bool {
+        self.fetch_state == FetchState::AwaitValidation
+    }
+
+    fn has_valid_position(&self) -> bool {
+        self.fetch_state.has_valid_position()
+    }
+
+    /// Check if the position exists and needs to be validated. If so, enter the AwaitValidation
+    /// state. This method also will update the position with the current leader and epoch.
+    ///
+    /// Returns true if the position is now awaiting validation.
+    fn maybe_validate_position(
+        &mut self,
+        current_leader_and_epoch: LeaderAndEpoch,
+    ) -> Result<bool> {
+        if self.fetch_state == FetchState::AwaitReset {
+            return Ok(false);
+        }
+        if current_leader_and_epoch.leader.is_none() {
+            return Ok(false);
+        }
+        if let Some(position) = self.position.clone() {
+            if position.current_leader != current_leader_and_epoch {
+                self.validate_position(FetchPosition::new(
+                    position.offset,
+                    position.offset_epoch,
+                    current_leader_and_epoch,
+                ))?;
+                self.preferred_read_replica = None;
+            }
+        }
+        Ok(self.awaiting_validation())
+    }
+
+    /// For brokers which do not support offset validation, simply update the leader of the
+    /// position and transition directly to fetching.
+    fn update_position_leader_no_validation(
+        &mut self,
+        current_leader_and_epoch: LeaderAndEpoch,
+    ) -> Result<()> {
+        match self.position.clone() {
+            Some(position) => self.transition_state(FetchState::Fetching, |state| {
+                state.position = Some(FetchPosition::new(
+                    position.offset,
+                    position.offset_epoch,
+                    current_leader_and_epoch,
+                ));
+                state.next_retry_time_ms = None;
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn seek_validated(&mut self, position: FetchPosition) -> Result<()> {
+        self.transition_state(FetchState::Fetching, |state| {
+            state.position = Some(position);
+            state.reset_strategy = None;
+            state.next_retry_time_ms = None;
+        })
+    }
+
+    fn validate_position(&mut self, position: FetchPosition) -> Result<()> {
+        if position.offset_epoch.is_some() && position.current_leader.epoch.is_some() {
+            self.transition_state(FetchState::AwaitValidation, |state| {
+                state.position = Some(position);
+                state.next_retry_time_ms = None;
+            })
+        } else {
+            // If we have no epoch information for the current position, then we can skip validation
+            self.transition_state(FetchState::Fetching, |state| {
+                state.position = Some(position);
+                state.next_retry_time_ms = None;
+            })
+        }
+    }
+
+    /// Clear the awaiting validation state and enter fetching.
+    fn complete_validation(&mut self) -> Result<()> {
+        if self.position.is_some() {
+            self.transition_state(FetchState::Fetching, |state| {
+                state.next_retry_time_ms = None;
+            })?;
+        }
+        Ok(())
+    }
+
+    fn seek_unvalidated(&mut self, position: FetchPosition) -> Result<()> {
+        self.seek_validated(position.clone())?;
+        self.validate_position(position)
+    }
+
+    fn set_position(&mut self, position: FetchPosition) -> Result<()> {
+        if !self.has_valid_position() {
+            return Err(KafkaError::IllegalState(
+                "Cannot set a new position without a valid current position".to_owned(),
+            ));
+        }
+        self.position = Some(position);
+        Ok(())
+    }
+
+    fn is_fetchable(&self) -> bool {
+        !self.paused && self.has_valid_position()
+    }
+}
+
+impl SubscriptionState {
+    pub fn new(default_reset_strategy: OffsetResetStrategy) -> SubscriptionState {
+        SubscriptionState {
+            default_reset_strategy,
+            state: Mutex::new(SubscriptionStateInner::new()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SubscriptionStateInner> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Monotonically increasing id which is incremented after every assignment change. This can
+    /// be used to check when an assignment has changed.
+    pub fn assignment_id(&self) -> i32 {
+        self.lock().assignment_id
+    }
+
+    pub fn subscribe(&self, topics: HashSet<String>) -> Result<bool> {
+        let mut inner = self.lock();
+        inner.set_subscription_type(SubscriptionType::AutoTopics)?;
+        Ok(inner.change_subscription(topics))
+    }
+
+    pub fn subscribe_pattern(&self, pattern: Regex) -> Result<()> {
+        let mut inner = self.lock();
+        inner.set_subscription_type(SubscriptionType::AutoPattern)?;
+        inner.subscribed_pattern = Some(SubscribedPattern::new(pattern));
+        Ok(())
+    }
+
+    pub fn subscribe_from_pattern(&self, topics: HashSet<String>) -> Result<bool> {
+        let mut inner = self.lock();
+        if inner.subscription_type != SubscriptionType::AutoPattern {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Attempt to subscribe from pattern while subscription type set to {:?}",
+                inner.subscription_type
+            )));
+        }
+        Ok(inner.change_subscription(topics))
+    }
+
+    /// Set the current group subscription. This is used by the group leader to ensure
+    /// that it receives metadata updates for all topics that the group is interested in.
+    ///
+    /// Returns true if the group subscription contains topics which are not part of the local subscription
+    pub fn group_subscribe(&self, topics: impl IntoIterator<Item = String>) -> Result<bool> {
+        let mut inner = self.lock();
+        if !inner.has_auto_assigned_partitions() {
+            return Err(KafkaError::IllegalState(
+                SUBSCRIPTION_EXCEPTION_MESSAGE.to_owned(),
+            ));
+        }
+        inner.group_subscription = topics.into_iter().collect();
+        Ok(!inner.group_subscription.is_subset(&inner.subscription))
+    }
+
+    /// Reset the group's subscription to only contain topics subscribed by this consumer.
+    pub fn reset_group_subscription(&self) {
+        self.lock().group_subscription.clear();
+    }
+
+    /// Change the assignment to the specified partitions provided by the user,
+    /// note this is different from `assign_from_subscribed`
+    /// whose input partitions are provided from the subscribed topics.
+    ///
+    /// Returns true if assignment has changed.
+    pub fn assign_from_user(&self, partitions: &HashSet<TopicPartition>) -> Result<bool> {
+        let mut inner = self.lock();
+        inner.set_subscription_type(SubscriptionType::UserAssigned)?;
+
+        let unchanged = inner.assignment.len() == partitions.len()
+            && partitions
+                .iter()
+                .all(|partition| inner.assignment.contains_key(partition));
+        if unchanged {
+            return Ok(false);
+        }
+        // partitions are grouped by topic, which keeps the fetch order stable
+        let mut partitions: Vec<_> = partitions.iter().cloned().collect();
+        partitions.sort();
+        inner.assignment_id += 1;
+
+        // update the subscribed topics
+        let manual_subscribed_topics = partitions
+            .iter()
+            .map(|partition| partition.topic.clone())
+            .collect();
+        inner.set_assignment(partitions.into_iter());
+        Ok(inner.change_subscription(manual_subscribed_topics))
+    }
+
+    /// Returns true if assignments matches subscription, otherwise false
+    pub fn check_assignment_matched_subscription<'a>(
+        &self,
+        assignments: impl IntoIterator<Item = &'a TopicPartition>,
+    ) -> bool {
+        let inner = self.lock();
+        for topic_partition in assignments {
+            match &inner.subscribed_pattern {
+                Some(pattern) => {
+                    if !pattern.matches(&topic_partition.topic) {
+                        info!(
+                            "Assigned partition {} for non-subscribed topic regex pattern; subscription pattern is {}",
+                            topic_partition, pattern.pattern
+                        );
+                        return false;
+                    }
+                }
+                None => {
+                    if !inner.subscription.contains(&topic_partition.topic) {
+                        info!(
+                            "Assigned partition {} for non-subscribed topic; subscription is {:?}",
+                            topic_partition, inner.subscription
+                        );
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Change the assignment to the specified partitions returned from the coordinator, note this is
+    /// different from `assign_from_user` which directly set the assignment from user inputs.
+    pub fn assign_from_subscribed(
+        &self,
+        assignments: impl IntoIterator<Item = TopicPartition>,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        if !inner.has_auto_assigned_partitions() {
+            return Err(KafkaError::IllegalArgument(
+                "Attempt to dynamically assign partitions while manual assignment in use"
+                    .to_owned(),
+            ));
+        }
+        inner.assignment_id += 1;
+        inner.set_assignment(assignments.into_iter());
+        Ok(())
+    }
+
+    /// Check whether pattern subscription is in use.
+    pub fn has_pattern_subscription(&self) -> bool {
+        self.lock().subscription_type == SubscriptionType::AutoPattern
+    }
+
+    pub fn has_no_subscription_or_user_assignment(&self) -> bool {
+        self.lock().subscription_type == SubscriptionType::None
+    }
+
+    pub fn unsubscribe(&self) {
+        let mut inner = self.lock();
+        inner.subscription.clear();
+        inner.group_subscription.clear();
+        inner.assignment.clear();
+        inner.subscribed_pattern = None;
+        inner.subscription_type = SubscriptionType::None;
+        inner.assignment_id += 1;
+    }
+
+    /// Check whether a topic matches a subscribed pattern.
+    ///
+    /// Returns true if pattern subscription is in use and the topic matches the subscribed pattern, false otherwise
+    pub fn matches_subscribed_pattern(&self, topic: &str) -> bool {
+        let inner = self.lock();
+        match &inner.subscribed_pattern {
+            Some(pattern) if inner.subscription_type == SubscriptionType::AutoPattern => {
+                pattern.matches(topic)
+            }
+            _ => false,
+        }
+    }
+
+    /// The topics the consumer subscribed to, empty unless partitions are auto assigned.
+    pub fn subscription(&self) -> HashSet<String> {
+        let inner = self.lock();
+        if inner.has_auto_assigned_partitions() {
+            inner.subscription.clone()
+        } else {
+            HashSet::new()
+        }
+    }
+
+    pub fn paused_partitions(&self) -> HashSet<TopicPartition> {
+        self.lock().collect_partitions(|state| state.paused)
+    }
+
+    /// Get the subscription topics for which metadata is required. For the leader, this will include
+    /// the union of the subscriptions of all group members. For followers, it is just that member's
+    /// subscription. This is used when querying topic metadata to detect the metadata changes which would
+    /// require rebalancing. The leader fetches metadata for all topics in the group so that it
+    /// can do the partition assignment (which requires at least partition counts for all topics
+    /// to be assigned).
+    ///
+    /// Returns the union of all subscribed topics in the group if this member is the leader
+    /// of the current generation; otherwise it returns the same set as `subscription`
+    pub fn metadata_topics(&self) -> HashSet<String> {
+        let inner = self.lock();
+        // When subscription changes `group_subscription` may be outdated, ensure that
+        // new subscription topics are returned.
+        inner
+            .group_subscription
+            .union(&inner.subscription)
+            .cloned()
+            .collect()
+    }
+
+    pub fn needs_metadata(&self, topic: &str) -> bool {
+        let inner = self.lock();
+        inner.subscription.contains(topic) || inner.group_subscription.contains(topic)
+    }
+
+    pub fn has_auto_assigned_partitions(&self) -> bool {
+        self.lock().has_auto_assigned_partitions()
+    }
+
+    /// Short description of the subscription, for logging.
+    pub fn pretty_string(&self) -> String {
+        let inner = self.lock();
+        match inner.subscription_type {
+            SubscriptionType::None => "None".to_owned(),
+            SubscriptionType::AutoTopics => {
+                let mut topics: Vec<_> = inner.subscription.iter().cloned().collect();
+                topics.sort();
+                format!("Subscribe({})", topics.join(","))
+            }
+            SubscriptionType::AutoPattern => format!(
+                "Subscribe({})",
+                inner
+                    .subscribed_pattern
+                    .as_ref()
+                    .map(|pattern| pattern.pattern.as_str())
+                    .unwrap_or_default()
+            ),
+            SubscriptionType::UserAssigned => {
+                let partitions: Vec<_> = inner.assignment.keys().map(|tp| tp.to_string()).collect();
+                format!(
+                    "Assign([{}] , id={})",
+                    partitions.join(", "),
+                    inner.assignment_id
+                )
+            }
+        }
+    }
+
+    /// Returns a copy of the currently assigned partitions
+    pub fn assigned_partitions(&self) -> HashSet<TopicPartition> {
+        self.lock().assignment.keys().cloned().collect()
+    }
+
+    /// Returns a copy of the currently assigned partitions, in fetch order
+    pub fn assigned_partitions_list(&self) -> Vec<TopicPartition> {
+        self.lock().assignment.keys().cloned().collect()
+    }
+
+    /// Provides the number of assigned partitions.
+    pub fn num_assigned_partitions(&self) -> usize {
+        self.lock().assignment.len()
+    }
+
+    pub fn fetchable_partitions(
+        &self,
+        is_available: impl Fn(&TopicPartition) -> bool,
+    ) -> Vec<TopicPartition> {
+        self.lock()
+            .assignment
+            .iter()
+            // Cheap check is first to avoid evaluating the predicate if possible
+            .filter(|(tp, state)| state.is_fetchable() && is_available(tp))
+            .map(|(tp, _)| tp.clone())
+            .collect()
+    }
+
+    pub fn seek_validated(&self, tp: &TopicPartition, position: FetchPosition) -> Result<()> {
+        self.lock().assigned_state(tp)?.seek_validated(position)
+    }
+
+    pub fn seek(&self, tp: &TopicPartition, offset: i64) -> Result<()> {
+        self.seek_validated(tp, FetchPosition::from_offset(offset))
+    }
+
+    pub fn seek_unvalidated(&self, tp: &TopicPartition, position: FetchPosition) -> Result<()> {
+        self.lock().assigned_state(tp)?.seek_unvalidated(position)
+    }
+
+    pub fn maybe_seek_unvalidated(
+        &self,
+        tp: &TopicPartition,
+        position: FetchPosition,
+        requested_reset_strategy: OffsetResetStrategy,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        match inner.assignment.get_mut(tp) {
+            None => {
+                debug!(
+                    "Skipping reset of partition {} since it is no longer assigned",
+                    tp
+                );
+                Ok(())
+            }
+            Some(state) if !state.awaiting_reset() => {
+                debug!(
+                    "Skipping reset of partition {} since reset is no longer needed",
+                    tp
+                );
+                Ok(())
+            }
+            Some(state) if state.reset_strategy != Some(requested_reset_strategy) => {
+                debug!(
+                    "Skipping reset of partition {} since an alternative reset has been requested",
+                    tp
+                );
+                Ok(())
+            }
+            Some(state) => {
+                info!(
+                    "Resetting offset for partition {} to position {}.",
+                    tp, position
+                );
+                state.seek_unvalidated(position)
+            }
+        }
+    }
+
+    /// Enter the offset validation state if the leader for this partition is known to support a usable version of the
+    /// OffsetsForLeaderEpoch API. If the leader node does not support the API, simply complete the offset validation.
+    ///
+    /// Returns true if we enter the offset validation state
+    pub fn maybe_validate_position_for_current_leader(
+        &self,
+        api_versions: &ApiVersions,
+        tp: &TopicPartition,
+        leader_and_epoch: LeaderAndEpoch,
+    ) -> Result<bool> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        let usable = match &leader_and_epoch.leader {
+            Some(leader) => api_versions
+                .get(&leader.id_string())
+                .map(|node_api_versions| {
+                    has_usable_offset_for_leader_epoch_version(&node_api_versions)
+                })
+                .unwrap_or(true),
+            None => true,
+        };
+        if usable {
+            state.maybe_validate_position(leader_and_epoch)
+        } else {
+            // If the broker does not support a newer version of OffsetsForLeaderEpoch, we skip validation
+            state.update_position_leader_no_validation(leader_and_epoch)?;
+            Ok(false)
+        }
+    }
+
+    /// Attempt to complete validation with the end offset returned from the OffsetForLeaderEpoch request.
+    ///
+    /// Returns log truncation details if detected and no reset policy is defined.
+    pub fn maybe_complete_validation(
+        &self,
+        tp: &TopicPartition,
+        request_position: &FetchPosition,
+        epoch_end_offset: &EpochEndOffset,
+    ) -> Result<Option<LogTruncation>> {
+        let mut inner = self.lock();
+        let state = match inner.assignment.get_mut(tp) {
+            Some(state) => state,
+            None => {
+                debug!(
+                    "Skipping completed validation for partition {} which is not currently assigned.",
+                    tp
+                );
+                return Ok(None);
+            }
+        };
+        if !state.awaiting_validation() {
+            debug!(
+                "Skipping completed validation for partition {} which is no longer expecting validation.",
+                tp
+            );
+            return Ok(None);
+        }
+        let current_position = match &state.position {
+            Some(position) => position.clone(),
+            None => return Ok(None),
+        };
+        if &current_position != request_position {
+            debug!(
+                "Skipping completed validation for partition {} since the current position {} no longer matches the position {} when the request was sent",
+                tp, current_position, request_position
+            );
+        } else if epoch_end_offset.is_undefined() {
+            if self.has_default_offset_reset_policy() {
+                info!(
+                    "Truncation detected for partition {} at offset {}, resetting offset",
+                    tp, current_position
+                );
+                state.reset(self.default_reset_strategy)?;
+            } else {
+                warn!(
+                    "Truncation detected for partition {} at offset {}, but no reset policy is set",
+                    tp, current_position
+                );
+                return Ok(Some(LogTruncation::new(
+                    tp.clone(),
+                    request_position.clone(),
+                    None,
+                )));
+            }
+        } else if epoch_end_offset.end_offset < current_position.offset {
+            if self.has_default_offset_reset_policy() {
+                let new_position = FetchPosition::new(
+                    epoch_end_offset.end_offset,
+                    Some(epoch_end_offset.leader_epoch),
+                    current_position.current_leader.clone(),
+                );
+                info!(
+                    "Truncation detected for partition {} at offset {}, resetting offset to the first offset known to diverge {}",
+                    tp, current_position, new_position
+                );
+                state.seek_validated(new_position)?;
+            } else {
+                let divergent_offset = OffsetAndMetadata::new(
+                    epoch_end_offset.end_offset,
+                    Some(epoch_end_offset.leader_epoch),
+                    "",
+                );
+                warn!(
+                    "Truncation detected for partition {} at offset {} (the end offset from the broker is {}), but no reset policy is set",
+                    tp, current_position, divergent_offset
+                );
+                return Ok(Some(LogTruncation::new(
+                    tp.clone(),
+                    request_position.clone(),
+                    Some(divergent_offset),
+                )));
+            }
+        } else {
+            state.complete_validation()?;
+        }
+        Ok(None)
+    }
+
+    pub fn awaiting_validation(&self, tp: &TopicPartition) -> Result<bool> {
+        Ok(self.lock().assigned_state(tp)?.awaiting_validation())
+    }
+
+    pub fn complete_validation(&self, tp: &TopicPartition) -> Result<()> {
+        self.lock().assigned_state(tp)?.complete_validation()
+    }
+
+    /// Set the position of an assigned partition which already has a valid position.
+    pub fn set_position(&self, tp: &TopicPartition, position: FetchPosition) -> Result<()> {
+        self.lock().assigned_state(tp)?.set_position(position)
+    }
+
+    /// The position of an assigned partition, if it has one.
+    pub fn position(&self, tp: &TopicPartition) -> Result<Option<FetchPosition>> {
+        Ok(self.lock().assigned_state(tp)?.position.clone())
+    }
+
+    /// The position of an assigned partition if it is valid for fetching.
+    pub fn valid_position(&self, tp: &TopicPartition) -> Result<Option<FetchPosition>> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        if state.has_valid_position() {
+            Ok(state.position.clone())
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn partition_lag(
+        &self,
+        tp: &TopicPartition,
+        isolation_level: IsolationLevel,
+    ) -> Result<Option<i64>> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        let position = match &state.position {
+            Some(position) => position.offset,
+            None => return Ok(None),
+        };
+        let end_offset = match isolation_level {
+            IsolationLevel::ReadCommitted => state.last_stable_offset,
+            IsolationLevel::ReadUncommitted => state.high_watermark,
+        };
+        Ok(end_offset.map(|end_offset| end_offset - position))
+    }
+
+    pub fn partition_end_offset(
+        &self,
+        tp: &TopicPartition,
+        isolation_level: IsolationLevel,
+    ) -> Result<Option<i64>> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        Ok(match isolation_level {
+            IsolationLevel::ReadCommitted => state.last_stable_offset,
+            IsolationLevel::ReadUncommitted => state.high_watermark,
+        })
+    }
+
+    pub fn request_partition_end_offset(&self, tp: &TopicPartition) -> Result<()> {
+        self.lock().assigned_state(tp)?.end_offset_requested = true;
+        Ok(())
+    }
+
+    pub fn partition_end_offset_requested(&self, tp: &TopicPartition) -> Result<bool> {
+        Ok(self.lock().assigned_state(tp)?.end_offset_requested)
+    }
+
+    pub fn partition_lead(&self, tp: &TopicPartition) -> Result<Option<i64>> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        Ok(match (&state.position, state.log_start_offset) {
+            (Some(position), Some(log_start_offset)) => Some(position.offset - log_start_offset),
+            _ => None,
+        })
+    }
+
+    pub fn update_high_watermark(&self, tp: &TopicPartition, high_watermark: i64) -> Result<()> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        state.high_watermark = Some(high_watermark);
+        state.end_offset_requested = false;
+        Ok(())
+    }
+
+    pub fn update_log_start_offset(
+        &self,
+        tp: &TopicPartition,
+        log_start_offset: i64,
+    ) -> Result<()> {
+        self.lock().assigned_state(tp)?.log_start_offset = Some(log_start_offset);
+        Ok(())
+    }
+
+    pub fn update_last_stable_offset(
+        &self,
+        tp: &TopicPartition,
+        last_stable_offset: i64,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        let state = inner.assigned_state(tp)?;
+        state.last_stable_offset = Some(last_stable_offset);
+        state.end_offset_requested = false;
+        Ok(())
+    }
+
+    /// Set the preferred read replica with a lease timeout. After this time, the replica will no longer be valid and
+    /// `preferred_read_replica` will return `None`.
+    ///
+    /// * `tp` - The topic partition
+    /// * `preferred_read_replica_id` - The preferred read replica
+    /// * `time_ms` - The time at which this preferred replica is no longer valid
+    pub fn update_preferred_read_replica(
+        &self,
+        tp: &TopicPartition,
+        preferred_read_replica_id: i32,
+        time_ms: u128,
+    ) -> Result<()> {
+        self.lock()
+            .assigned_state(tp)?
+            .update_preferred_read_replica(preferred_read_replica_id, time_ms);
+        Ok(())
+    }
+
+    /// Get the preferred read replica. Returns the current preferred read replica, if it has been
+    /// set and if it has not expired.
+    pub fn preferred_read_replica(&self, tp: &TopicPartition, time_ms: u128) -> Option<i32> {
+        self.lock()
+            .assignment
+            .get_mut(tp)
+            .and_then(|state| state.preferred_read_replica(time_ms))
+    }
+
+    /// Unset the preferred read replica. This causes the fetcher to go back to the leader for fetches.
+    /// Returns the replica which was removed, if there was one.
+    pub fn clear_preferred_read_replica(&self, tp: &TopicPartition) -> Result<Option<i32>> {
+        Ok(self
+            .lock()
+            .assigned_state(tp)?
+            .clear_preferred_read_replica())
+    }
+
+    /// Positions of all partitions with a valid position, as offsets to commit.
+    pub fn all_consumed(&self) -> IndexMap<TopicPartition, OffsetAndMetadata> {
+        self.lock()
+            .assignment
+            .iter()
+            .filter(|(_, state)| state.has_valid_position())
+            .filter_map(|(tp, state)| {
+                state.position.as_ref().map(|position| {
+                    (
+                        tp.clone(),
+                        OffsetAndMetadata::new(position.offset, position.offset_epoch, ""),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub fn request_offset_reset(
+        &self,
+        partition: &TopicPartition,
+        offset_reset_strategy: OffsetResetStrategy,
+    ) -> Result<()> {
+        self.lock()
+            .assigned_state(partition)?
+            .reset(offset_reset_strategy)
+    }
+
+    pub fn request_offset_reset_for_partitions(
+        &self,
+        partitions: &[TopicPartition],
+        offset_reset_strategy: OffsetResetStrategy,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        for tp in partitions {
+            info!(
+                "Seeking to {} offset of partition {}",
+                offset_reset_strategy, tp
+            );
+            inner.assigned_state(tp)?.reset(offset_reset_strategy)?;
+        }
+        Ok(())
+    }
+
+    /// Request a reset of the partition using the default reset strategy.
+    pub fn request_default_offset_reset(&self, partition: &TopicPartition) -> Result<()> {
+        self.request_offset_reset(partition, self.default_reset_strategy)
+    }
+
+    pub fn set_next_allowed_retry(
+        &self,
+        partitions: &[TopicPartition],
+        next_allow_reset_time_ms: u128,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        for partition in partitions {
+            inner.assigned_state(partition)?.next_retry_time_ms = Some(next_allow_reset_time_ms);
+        }
+        Ok(())
+    }
+
+    pub fn has_default_offset_reset_policy(&self) -> bool {
+        self.default_reset_strategy != OffsetResetStrategy::None
+    }
+
+    pub fn default_reset_strategy(&self) -> OffsetResetStrategy {
+        self.default_reset_strategy
+    }
+
+    pub fn is_offset_reset_needed(&self, partition: &TopicPartition) -> Result<bool> {
+        Ok(self.lock().assigned_state(partition)?.awaiting_reset())
+    }
+
+    pub fn reset_strategy(
+        &self,
+        partition: &TopicPartition,
+    ) -> Result<Option<OffsetResetStrategy>> {
+        Ok(self.lock().assigned_state(partition)?.reset_strategy)
+    }
+
+    pub fn has_all_fetch_positions(&self) -> bool {
+        self.lock()
+            .assignment
+            .values()
+            .all(|state| state.has_valid_position())
+    }
+
+    pub fn initializing_partitions(&self) -> HashSet<TopicPartition> {
+        self.lock()
+            .collect_partitions(|state| state.fetch_state == FetchState::Initializing)
+    }
+
+    /// Request an offset reset of all partitions without a position, using the default reset
+    /// strategy. Fails with `NoOffsetForPartition` if there is no default reset strategy.
+    pub fn reset_initializing_positions(&self) -> Result<()> {
+        let mut inner = self.lock();
+        let mut partitions_with_no_offsets = vec![];
+        for (tp, state) in inner.assignment.iter_mut() {
+            if state.fetch_state == FetchState::Initializing {
+                if self.default_reset_strategy == OffsetResetStrategy::None {
+                    partitions_with_no_offsets.push(tp.to_string());
+                } else {
+                    state.reset(self.default_reset_strategy)?;
+                }
+            }
+        }
+        if !partitions_with_no_offsets.is_empty() {
+            return Err(KafkaError::NoOffsetForPartition(format!(
+                "Undefined offset with no reset policy for partitions: [{}]",
+                partitions_with_no_offsets.join(", ")
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn partitions_needing_reset(&self, now_ms: u128) -> HashSet<TopicPartition> {
+        self.lock().collect_partitions(|state| {
+            state.awaiting_reset() && !state.awaiting_retry_backoff(now_ms)
+        })
+    }
+
+    pub fn partitions_needing_validation(&self, now_ms: u128) -> HashSet<TopicPartition> {
+        self.lock().collect_partitions(|state| {
+            state.awaiting_validation() && !state.awaiting_retry_backoff(now_ms)
+        })
+    }
+
+    pub fn is_assigned(&self, tp: &TopicPartition) -> bool {
+        self.lock().assignment.contains_key(tp)
+    }
+
+    pub fn is_paused(&self, tp: &TopicPartition) -> bool {
+        self.lock()
+            .assignment
+            .get(tp)
+            .map(|state| state.paused)
+            .unwrap_or(false)
+    }
+
+    pub fn is_fetchable(&self, tp: &TopicPartition) -> bool {
+        self.lock()
+            .assignment
+            .get(tp)
+            .map(|state| state.is_fetchable())
+            .unwrap_or(false)
+    }
+
+    pub fn has_valid_position(&self, tp: &TopicPartition) -> bool {
+        self.lock()
+            .assignment
+            .get(tp)
+            .map(|state| state.has_valid_position())
+            .unwrap_or(false)
+    }
+
+    pub fn pause(&self, tp: &TopicPartition) -> Result<()> {
+        self.lock().assigned_state(tp)?.paused = true;
+        Ok(())
+    }
+
+    pub fn resume(&self, tp: &TopicPartition) -> Result<()> {
+        self.lock().assigned_state(tp)?.paused = false;
+        Ok(())
+    }
+
+    pub fn request_failed(&self, partitions: &[TopicPartition], next_retry_time_ms: u128) {
+        let mut inner = self.lock();
+        for partition in partitions {
+            // by the time the request failed, the assignment may no longer
+            // contain this partition any more, in which case we would just ignore.
+            if let Some(state) = inner.assignment.get_mut(partition) {
+                state.next_retry_time_ms = Some(next_retry_time_ms);
+            }
+        }
+    }
+
+    /// Move the partition to the end of the fetch order, so other partitions get a chance to be
+    /// fetched first.
+    pub fn move_partition_to_end(&self, tp: &TopicPartition) {
+        let mut inner = self.lock();
+        if let Some(state) = inner.assignment.shift_remove(tp) {
+            inner.assignment.insert(tp.clone(), state);
+        }
+    }
+}
+
+impl SubscriptionStateInner {
+    fn new() -> SubscriptionStateInner {
+        SubscriptionStateInner {
+            subscription_type: SubscriptionType::None,
+            subscribed_pattern: None,
+            subscription: HashSet::new(),
+            group_subscription: HashSet::new(),
+            assignment: IndexMap::new(),
+            assignment_id: 0,
+        }
+    }
+
+    /// This method sets the subscription type if it is not already set (i.e. when it is None),
+    /// or verifies that the subscription type is equal to the give type when it is set (i.e.
+    /// when it is not None)
+    fn set_subscription_type(&mut self, subscription_type: SubscriptionType) -> Result<()> {
+        if self.subscription_type == SubscriptionType::None {
+            self.subscription_type = subscription_type;
+        } else if self.subscription_type != subscription_type {
+            return Err(KafkaError::IllegalState(
+                SUBSCRIPTION_EXCEPTION_MESSAGE.to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn change_subscription(&mut self, topics_to_subscribe: HashSet<String>) -> bool {
+        if self.subscription == topics_to_subscribe {
+            return false;
+        }
+        self.subscription = topics_to_subscribe;
+        true
+    }
+
+    fn has_auto_assigned_partitions(&self) -> bool {
+        matches!(
+            self.subscription_type,
+            SubscriptionType::AutoTopics | SubscriptionType::AutoPattern
+        )
+    }
+
+    fn assigned_state(&mut self, tp: &TopicPartition) -> Result<&mut TopicPartitionState> {
+        self.assignment.get_mut(tp).ok_or_else(|| {
+            KafkaError::IllegalState(format!("No current assignment for partition {}", tp))
+        })
+    }
+
+    fn collect_partitions(
+        &self,
+        filter: impl Fn(&TopicPartitionState) -> bool,
+    ) -> HashSet<TopicPartition> {
+        self.assignment
+            .iter()
+            .filter(|(_, state)| filter(state))
+            .map(|(tp, _)| tp.clone())
+            .collect()
+    }
+
+    /// Replace the assignment, keeping the state of partitions which remain assigned.
+    fn set_assignment(&mut self, partitions: impl Iterator<Item = TopicPartition>) {
+        let mut previous = std::mem::take(&mut self.assignment);
+        self.assignment = partitions
+            .map(|tp| {
+                let state = previous
+                    .shift_remove(&tp)
+                    .unwrap_or_else(TopicPartitionState::new);
+                (tp, state)
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use regex::Regex;
+
+    use crate::{
+        clients::{
+            api_versions::ApiVersions, consumer::offset_reset_strategy::OffsetResetStrategy,
+            metadata::LeaderAndEpoch,
+        },
+        common::{
+            errors::KafkaError, node::Node, protocol::errors::Errors,
+            requests::offsets_for_leader_epoch_response::EpochEndOffset,
+            topic_partition::TopicPartition,
+        },
+    };
+
+    use super::{FetchPosition, SubscriptionState};
+
+    const TOPIC: &str = "test";
+
+    fn tp0() -> TopicPartition {
+        TopicPartition::new(TOPIC, 0)
+    }
+
+    fn topics(topics: &[&str]) -> HashSet<String> {
+        topics.iter().map(|topic| topic.to_string()).collect()
+    }
+
+    fn leader(epoch: i32) -> LeaderAndEpoch {
+        LeaderAndEpoch::new(Some(Node::new(0, "localhost", 9092)), Some(epoch))
+    }
+
+    fn assigned(default_reset_strategy: OffsetResetStrategy) -> SubscriptionState {
+        let state = SubscriptionState::new(default_reset_strategy);
+        state
+            .assign_from_user(&[tp0()].into_iter().collect())
+            .unwrap();
+        state
+    }
+
+    #[test]
+    fn pattern_subscription_matches_whole_topic_names() {
+        let state = SubscriptionState::new(OffsetResetStrategy::Earliest);
+        state
+            .subscribe_pattern(Regex::new("test.*").unwrap())
+            .unwrap();
+        assert!(state.has_pattern_subscription());
+        assert!(state.matches_subscribed_pattern("test-1"));
+        assert!(!state.matches_subscribed_pattern("a-test"));
+        assert_eq!(state.pretty_string(), "Subscribe(test.*)");
+
+        assert!(state
+            .subscribe_from_pattern(topics(&["test-1", "test-2"]))
+            .unwrap());
+        assert!(!state
+            .subscribe_from_pattern(topics(&["test-1", "test-2"]))
+            .unwrap());
+        assert_eq!(state.subscription(), topics(&["test-1", "test-2"]));
+        assert!(state.check_assignment_matched_subscription(&[TopicPartition::new("test-3", 0)]));
+        assert!(!state.check_assignment_matched_subscription(&[TopicPartition::new("other", 0)]));
+
+        state.unsubscribe();
+        assert!(!state.matches_subscribed_pattern("test-1"));
+        assert!(state.has_no_subscription_or_user_assignment());
+    }
+
+    #[test]
+    fn subscription_types_are_mutually_exclusive() {
+        let state = SubscriptionState::new(OffsetResetStrategy::Earliest);
+        assert!(matches!(
+            state.subscribe_from_pattern(topics(&[TOPIC])),
+            Err(KafkaError::IllegalArgument(_))
+        ));
+        state.subscribe(topics(&[TOPIC])).unwrap();
+        assert!(matches!(
+            state.subscribe_pattern(Regex::new(".*").unwrap()),
+            Err(KafkaError::IllegalState(_))
+        ));
+        assert!(matches!(
+            state.assign_from_user(&[tp0()].into_iter().collect()),
+            Err(KafkaError::IllegalState(_))
+        ));
+
+        state.unsubscribe();
+        state
+            .assign_from_user(&[tp0()].into_iter().collect())
+            .unwrap();
+        assert!(matches!(
+            state.assign_from_subscribed(vec![tp0()]),
+            Err(KafkaError::IllegalArgument(_))
+        ));
+        assert!(matches!(
+            state.group_subscribe(vec![TOPIC.to_owned()]),
+            Err(KafkaError::IllegalState(_))
+        ));
+    }
+
+    #[test]
+    fn assignment_id_is_bumped_on_assignment_changes() {
+        let state = SubscriptionState::new(OffsetResetStrategy::Earliest);
+        assert_eq!(state.assignment_id(), 0);
+
+        let partitions: HashSet<_> = [tp0()].into_iter().collect();
+        assert!(state.assign_from_user(&partitions).unwrap());
+        assert_eq!(state.assignment_id(), 1);
+        // assigning the same partitions again is not a change
+        assert!(!state.assign_from_user(&partitions).unwrap());
+        assert_eq!(state.assignment_id(), 1);
+        assert_eq!(state.pretty_string(), "Assign([test-0] , id=1)");
+
+        state.unsubscribe();
+        assert_eq!(state.assignment_id(), 2);
+        assert_eq!(state.num_assigned_partitions(), 0);
+
+        state.subscribe(topics(&[TOPIC])).unwrap();
+        state.assign_from_subscribed(vec![tp0()]).unwrap();
+        assert_eq!(state.assignment_id(), 3);
+        assert_eq!(state.assigned_partitions(), partitions);
+    }
+
+    #[test]
+    fn partition_state_survives_reassignment() {
+        let state = SubscriptionState::new(OffsetResetStrategy::Earliest);
+        let tp1 = TopicPartition::new(TOPIC, 1);
+        state.subscribe(topics(&[TOPIC])).unwrap();
+        state
+            .assign_from_subscribed(vec![tp0(), tp1.clone()])
+            .unwrap();
+        state.seek(&tp0(), 10).unwrap();
+        state.pause(&tp0()).unwrap();
+
+        state.assign_from_subscribed(vec![tp1, tp0()]).unwrap();
+        assert_eq!(
+            state.position(&tp0()).unwrap(),
+            Some(FetchPosition::from_offset(10))
+        );
+        assert!(state.is_paused(&tp0()));
+        assert!(!state.is_fetchable(&tp0()));
+        state.resume(&tp0()).unwrap();
+        assert!(state.is_fetchable(&tp0()));
+    }
+
+    #[test]
+    fn seek_makes_the_partition_fetchable() {
+        let state = assigned(OffsetResetStrategy::Earliest);
+        assert_eq!(
+            state.initializing_partitions(),
+            [tp0()].into_iter().collect()
+        );
+        assert!(!state.has_valid_position(&tp0()));
+        assert!(!state.has_all_fetch_positions());
+        assert!(state.fetchable_partitions(|_| true).is_empty());
+        assert!(matches!(
+            state.set_position(&tp0(), FetchPosition::from_offset(5)),
+            Err(KafkaError::IllegalState(_))
+        ));
+
+        state.seek(&tp0(), 5).unwrap();
+        assert!(state.has_all_fetch_positions());
+        assert_eq!(state.fetchable_partitions(|_| true), vec![tp0()]);
+        assert!(state.fetchable_partitions(|_| false).is_empty());
+        state
+            .set_position(&tp0(), FetchPosition::from_offset(8))
+            .unwrap();
+        assert_eq!(state.all_consumed()[&tp0()].offset, 8);
+
+        let unassigned = TopicPartition::new(TOPIC, 1);
+        assert!(matches!(
+            state.seek(&unassigned, 0),
+            Err(KafkaError::IllegalState(_))
+        ));
+    }
+
+    #[test]
+    fn offset_reset_clears_the_position_and_honours_the_backoff() {
+        let state = assigned(OffsetResetStrategy::Latest);
+        state.reset_initializing_positions().unwrap();
+        assert!(state.is_offset_reset_needed(&tp0()).unwrap());
+        assert_eq!(
+            state.reset_strategy(&tp0()).unwrap(),
+            Some(OffsetResetStrategy::Latest)
+        );
+        assert_eq!(
+            state.partitions_needing_reset(0),
+            [tp0()].into_iter().collect()
+        );
+
+        state.set_next_allowed_retry(&[tp0()], 100).unwrap();
+        assert!(state.partitions_needing_reset(99).is_empty());
+        assert_eq!(state.partitions_needing_reset(100).len(), 1);
+
+        // a reset for another strategy than the one requested is dropped
+        state
+            .maybe_seek_unvalidated(
+                &tp0(),
+                FetchPosition::from_offset(3),
+                OffsetResetStrategy::Earliest,
+            )
+            .unwrap();
+        assert!(state.is_offset_reset_needed(&tp0()).unwrap());
+
+        state
+            .maybe_seek_unvalidated(
+                &tp0(),
+                FetchPosition::from_offset(3),
+                OffsetResetStrategy::Latest,
+            )
+            .unwrap();
+        assert!(!state.is_offset_reset_needed(&tp0()).unwrap());
+        assert_eq!(state.reset_strategy(&tp0()).unwrap(), None);
+        assert_eq!(
+            state.valid_position(&tp0()).unwrap(),
+            Some(FetchPosition::from_offset(3))
+        );
+
+        state
+            .request_offset_reset(&tp0(), OffsetResetStrategy::Earliest)
+            .unwrap();
+        assert_eq!(state.position(&tp0()).unwrap(), None);
+        assert!(state.all_consumed().is_empty());
+    }
+
+    #[test]
+    fn initializing_partitions_without_reset_policy_fail() {
+        let state = assigned(OffsetResetStrategy::None);
+        match state.reset_initializing_positions() {
+            Err(KafkaError::NoOffsetForPartition(message)) => assert_eq!(
+                message,
+                "Undefined offset with no reset policy for partitions: [test-0]"
+            ),
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn position_with_epoch_awaits_validation() {
+        let state = assigned(OffsetResetStrategy::Earliest);
+        let position = FetchPosition::new(10, Some(2), leader(2));
+        state.seek_unvalidated(&tp0(), position.clone()).unwrap();
+        assert!(state.awaiting_validation(&tp0()).unwrap());
+        assert!(!state.is_fetchable(&tp0()));
+        assert_eq!(
+            state.partitions_needing_validation(0),
+            [tp0()].into_iter().collect()
+        );
+        assert_eq!(state.valid_position(&tp0()).unwrap(), None);
+
+        // the broker did not truncate past our position
+        let truncation = state
+            .maybe_complete_validation(&tp0(), &position, &EpochEndOffset::new(Errors::None, 2, 12))
+            .unwrap();
+        assert_eq!(truncation, None);
+        assert!(state.is_fetchable(&tp0()));
+
+        // a leader change requires a new validation
+        let api_versions = ApiVersions::new();
+        assert!(state
+            .maybe_validate_position_for_current_leader(&api_versions, &tp0(), leader(3))
+            .unwrap());
+        assert_eq!(
+            state.position(&tp0()).unwrap().unwrap().current_leader,
+            leader(3)
+        );
+    }
+
+    #[test]
+    fn position_without_epoch_skips_validation() {
+        let state = assigned(OffsetResetStrategy::Earliest);
+        state
+            .seek_unvalidated(&tp0(), FetchPosition::new(10, None, leader(2)))
+            .unwrap();
+        assert!(!state.awaiting_validation(&tp0()).unwrap());
+        assert!(state.is_fetchable(&tp0()));
+    }
+
+    #[test]
+    fn truncation_seeks_to_the_divergent_offset_with_reset_policy() {
+        let state = assigned(OffsetResetStrategy::Earliest);
+        let position = FetchPosition::new(10, Some(2), leader(2));
+        state.seek_unvalidated(&tp0(), position.clone()).unwrap();
+
+        let truncation = state
+            .maybe_complete_validation(&tp0(), &position, &EpochEndOffset::new(Errors::None, 1, 7))
+            .unwrap();
+        assert_eq!(truncation, None);
+        assert_eq!(
+            state.valid_position(&tp0()).unwrap(),
+            Some(FetchPosition::new(7, Some(1), leader(2)))
+        );
+    }
+
+    #[test]
+    fn truncation_is_reported_without_reset_policy() {
+        let state = assigned(OffsetResetStrategy::None);
+        let position = FetchPosition::new(10, Some(2), leader(2));
+        state.seek_unvalidated(&tp0(), position.clone()).unwrap();
+
+        // a response for an outdated position is ignored
+        let stale = FetchPosition::new(9, Some(2), leader(2));
+        assert_eq!(
+            state
+                .maybe_complete_validation(&tp0(), &stale, &EpochEndOffset::new(Errors::None, 1, 7))
+                .unwrap(),
+            None
+        );
+        assert!(state.awaiting_validation(&tp0()).unwrap());
+
+        let truncation = state
+            .maybe_complete_validation(&tp0(), &position, &EpochEndOffset::new(Errors::None, 1, 7))
+            .unwrap()
+            .unwrap();
+        assert_eq!(truncation.topic_partition, tp0());
+        assert_eq!(truncation.fetch_position, position);
+        let divergent_offset = truncation.divergent_offset.unwrap();
+        assert_eq!(divergent_offset.offset, 7);
+        assert_eq!(divergent_offset.leader_epoch, Some(1));
+        assert!(state.awaiting_validation(&tp0()).unwrap());
+    }
+}
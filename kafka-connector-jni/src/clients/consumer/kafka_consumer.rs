@@ -0,0 +1,1360 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::{
+    clients::{
+        api_versions::ApiVersions,
+        client_utils,
+        common_client_configs::{
+            BOOTSTRAP_SERVERS_CONFIG, CLIENT_ID_CONFIG, CLIENT_RACK_CONFIG,
+            METADATA_MAX_AGE_CONFIG, REQUEST_TIMEOUT_MS_CONFIG, RETRY_BACKOFF_MS_CONFIG,
+        },
+        group_rebalance_config::GroupRebalanceConfig,
+        kafka_client::KafkaClient,
+        metadata::Metadata,
+    },
+    common::{
+        errors::{KafkaError, Result},
+        isolation_level::IsolationLevel,
+        serialization::deserializer::Deserializer,
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::{SystemTime, Time},
+            timer::Timer,
+        },
+    },
+};
+
+use super::{
+    consumer_config::{
+        ConsumerConfig, ALLOW_AUTO_CREATE_TOPICS_CONFIG, AUTO_COMMIT_INTERVAL_MS_CONFIG,
+        AUTO_OFFSET_RESET_CONFIG, CHECK_CRCS_CONFIG, DEFAULT_API_TIMEOUT_MS_CONFIG,
+        EXCLUDE_INTERNAL_TOPICS_CONFIG, FETCH_MAX_BYTES_CONFIG, FETCH_MAX_WAIT_MS_CONFIG,
+        FETCH_MIN_BYTES_CONFIG, GROUP_ID_CONFIG, GROUP_INSTANCE_ID_CONFIG,
+        HEARTBEAT_INTERVAL_MS_CONFIG, ISOLATION_LEVEL_CONFIG, LEAVE_GROUP_ON_CLOSE_CONFIG,
+        MAX_PARTITION_FETCH_BYTES_CONFIG, MAX_POLL_INTERVAL_MS_CONFIG, MAX_POLL_RECORDS_CONFIG,
+        PARTITION_ASSIGNMENT_STRATEGY_CONFIG, SESSION_TIMEOUT_MS_CONFIG,
+    },
+    consumer_group_metadata::ConsumerGroupMetadata,
+    consumer_partition_assignor::ConsumerPartitionAssignor,
+    consumer_rebalance_listener::{ConsumerRebalanceListener, NoOpConsumerRebalanceListener},
+    consumer_record::ConsumerRecord,
+    consumer_records::ConsumerRecords,
+    consumer_stream::ConsumerStream,
+    cooperative_sticky_assignor::CooperativeStickyAssignor,
+    internals::{
+        abstract_coordinator::GroupProtocolHandler,
+        consumer_coordinator::ConsumerCoordinator,
+        consumer_metadata::ConsumerMetadata,
+        consumer_network_client::ConsumerNetworkClient,
+        fetcher::{FetchedRecords, Fetcher},
+        subscription_state::{FetchPosition, SubscriptionState},
+    },
+    offset_and_metadata::OffsetAndMetadata,
+    offset_commit_callback::OffsetCommitCallback,
+    offset_reset_strategy::OffsetResetStrategy,
+    range_assignor::RangeAssignor,
+    round_robin_assignor::RoundRobinAssignor,
+    sticky_assignor::StickyAssignor,
+};
+
+const NO_CURRENT_THREAD: u64 = 0;
+
+/// The maximum number of in-flight requests per broker connection of the consumer.
+const MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION: usize = 100;
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(NO_CURRENT_THREAD + 1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::SeqCst);
+}
+
+/// A client that consumes records from a Kafka cluster.
+///
+/// This client transparently handles the failure of Kafka brokers, and transparently adapts as topic partitions
+/// it fetches migrate within the cluster. This client also interacts with the broker to allow groups of
+/// consumers to load balance consumption using consumer groups.
+///
+/// The consumer maintains TCP connections to the necessary brokers to fetch data.
+/// Failure to close the consumer after use will leak these connections.
+///
+/// The consumer is not thread-safe: all methods except `wakeup` must be called from the thread
+/// currently using it, concurrent access fails with `ConcurrentModification`.
+pub struct KafkaConsumer<K, V> {
+    log: Logger,
+    client_id: String,
+    group_id: Option<String>,
+    coordinator: Option<Arc<ConsumerCoordinator>>,
+    key_deserializer: Box<dyn Deserializer<K>>,
+    value_deserializer: Box<dyn Deserializer<V>>,
+    fetcher: Arc<Fetcher>,
+    time: Arc<dyn Time>,
+    client: Arc<ConsumerNetworkClient>,
+    subscriptions: Arc<SubscriptionState>,
+    metadata: Arc<Metadata>,
+    retry_backoff_ms: u128,
+    request_timeout_ms: u128,
+    default_api_timeout_ms: u128,
+    closed: AtomicBool,
+    assignors: Vec<Arc<dyn ConsumerPartitionAssignor>>,
+
+    // currentThread holds the id of the thread which is currently using the consumer (which
+    // is used to prevent multi-threaded access)
+    current_thread: AtomicU64,
+    // refcount is used to allow reentrant access by the thread who has acquired currentThread
+    refcount: AtomicUsize,
+
+    // to keep from repeatedly scanning subscriptions in poll(), cache the result during metadata updates
+    cached_subscription_has_all_fetch_positions: AtomicBool,
+}
+
+/// Releases the light lock protecting the consumer from multi-threaded access when dropped.
+struct ConsumerGuard<'a> {
+    current_thread: &'a AtomicU64,
+    refcount: &'a AtomicUsize,
+}
+
+impl Drop for ConsumerGuard<'_> {
+    fn drop(&mut self) {
+        if self.refcount.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.current_thread
+                .store(NO_CURRENT_THREAD, Ordering::SeqCst);
+        }
+    }
+}
+
+impl<K, V> KafkaConsumer<K, V> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client_id: impl Into<String>,
+        group_id: Option<String>,
+        coordinator: Option<Arc<ConsumerCoordinator>>,
+        key_deserializer: Box<dyn Deserializer<K>>,
+        value_deserializer: Box<dyn Deserializer<V>>,
+        fetcher: Arc<Fetcher>,
+        time: Arc<dyn Time>,
+        client: Arc<ConsumerNetworkClient>,
+        subscriptions: Arc<SubscriptionState>,
+        metadata: Arc<Metadata>,
+        retry_backoff_ms: u128,
+        request_timeout_ms: u128,
+        default_api_timeout_ms: u128,
+        assignors: Vec<Arc<dyn ConsumerPartitionAssignor>>,
+    ) -> KafkaConsumer<K, V> {
+        let client_id = client_id.into();
+        let log = KafkaConsumer::<K, V>::create_log_context(&client_id, group_id.as_deref())
+            .logger(module_path!());
+        log.debug(format_args!("Kafka consumer initialized"));
+        KafkaConsumer {
+            log,
+            client_id,
+            group_id,
+            coordinator,
+            key_deserializer,
+            value_deserializer,
+            fetcher,
+            time,
+            client,
+            subscriptions,
+            metadata,
+            retry_backoff_ms,
+            request_timeout_ms,
+            default_api_timeout_ms,
+            closed: AtomicBool::new(false),
+            assignors,
+            current_thread: AtomicU64::new(NO_CURRENT_THREAD),
+            refcount: AtomicUsize::new(0),
+            cached_subscription_has_all_fetch_positions: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a consumer from its configuration, connecting to the brokers in `bootstrap.servers`.
+    ///
+    /// The deserializers are given as instances, the deserializer classes of the configuration are
+    /// not used.
+    pub fn from_config(
+        config: ConsumerConfig,
+        key_deserializer: Box<dyn Deserializer<K>>,
+        value_deserializer: Box<dyn Deserializer<V>>,
+    ) -> Result<KafkaConsumer<K, V>> {
+        let get_ms = |key: &str| -> Result<u128> {
+            Ok(config.get_int(key)?.unwrap_or_default().max(0) as u128)
+        };
+        let client_id = config.get_string(CLIENT_ID_CONFIG)?.unwrap_or_default();
+        let group_id = config.get_string(GROUP_ID_CONFIG)?;
+        let log_context =
+            KafkaConsumer::<K, V>::create_log_context(&client_id, group_id.as_deref());
+        let time: Arc<dyn Time> = Arc::new(SystemTime);
+        let request_timeout_ms = get_ms(REQUEST_TIMEOUT_MS_CONFIG)?;
+        let default_api_timeout_ms = get_ms(DEFAULT_API_TIMEOUT_MS_CONFIG)?;
+        let heartbeat_interval_ms = get_ms(HEARTBEAT_INTERVAL_MS_CONFIG)?;
+        let retry_backoff_ms = config
+            .get_long(RETRY_BACKOFF_MS_CONFIG)?
+            .unwrap_or_default()
+            .max(0) as u128;
+
+        let offset_reset_strategy = match config.get_string(AUTO_OFFSET_RESET_CONFIG)?.as_deref() {
+            Some("earliest") => OffsetResetStrategy::Earliest,
+            Some("none") => OffsetResetStrategy::None,
+            _ => OffsetResetStrategy::Latest,
+        };
+        let subscriptions = Arc::new(SubscriptionState::new(offset_reset_strategy));
+        let metadata = Arc::new(Metadata::new(
+            retry_backoff_ms,
+            config
+                .get_long(METADATA_MAX_AGE_CONFIG)?
+                .unwrap_or_default()
+                .max(0) as u128,
+        ));
+        let addresses = client_utils::parse_and_validate_addresses(
+            &config
+                .get_list(BOOTSTRAP_SERVERS_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        metadata.bootstrap(&addresses);
+        let metadata_updater = Arc::new(ConsumerMetadata::new(
+            metadata.clone(),
+            config.get_boolean(EXCLUDE_INTERNAL_TOPICS_CONFIG)? != Some(true),
+            config.get_boolean(ALLOW_AUTO_CREATE_TOPICS_CONFIG)? == Some(true),
+            subscriptions.clone(),
+            &log_context,
+        ));
+
+        let api_versions = Arc::new(ApiVersions::new());
+        let network_client: Arc<dyn KafkaClient> = Arc::new(client_utils::create_network_client(
+            &config,
+            &log_context,
+            api_versions.clone(),
+            time.clone(),
+            MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
+            metadata_updater,
+        )?);
+        let client = Arc::new(ConsumerNetworkClient::new(
+            network_client.clone(),
+            metadata.clone(),
+            time.clone(),
+            retry_backoff_ms,
+            request_timeout_ms,
+            heartbeat_interval_ms,
+        ));
+
+        let assignors = assignor_instances(
+            &config
+                .get_list(PARTITION_ASSIGNMENT_STRATEGY_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        // no coordinator will be constructed for the default (null) group id
+        let coordinator = match &group_id {
+            Some(group_id) => {
+                let rebalance_config = GroupRebalanceConfig::new(
+                    get_ms(SESSION_TIMEOUT_MS_CONFIG)?,
+                    get_ms(MAX_POLL_INTERVAL_MS_CONFIG)?,
+                    heartbeat_interval_ms,
+                    group_id.clone(),
+                    config.get_string(GROUP_INSTANCE_ID_CONFIG)?,
+                    retry_backoff_ms,
+                    config.get_boolean(LEAVE_GROUP_ON_CLOSE_CONFIG)? == Some(true),
+                )?;
+                Some(Arc::new(ConsumerCoordinator::new(
+                    rebalance_config,
+                    client.clone(),
+                    assignors.clone(),
+                    metadata.clone(),
+                    subscriptions.clone(),
+                    time.clone(),
+                    config.maybe_override_enable_auto_commit()?,
+                    get_ms(AUTO_COMMIT_INTERVAL_MS_CONFIG)?,
+                    &log_context,
+                )?))
+            }
+            None => None,
+        };
+
+        let isolation_level = match config.get_string(ISOLATION_LEVEL_CONFIG)? {
+            Some(isolation_level) => isolation_level.parse()?,
+            None => IsolationLevel::ReadUncommitted,
+        };
+        let fetcher = Arc::new(Fetcher::new(
+            network_client,
+            metadata.clone(),
+            subscriptions.clone(),
+            api_versions,
+            time.clone(),
+            config.get_int(FETCH_MIN_BYTES_CONFIG)?.unwrap_or_default(),
+            config.get_int(FETCH_MAX_BYTES_CONFIG)?.unwrap_or_default(),
+            config
+                .get_int(FETCH_MAX_WAIT_MS_CONFIG)?
+                .unwrap_or_default(),
+            config
+                .get_int(MAX_PARTITION_FETCH_BYTES_CONFIG)?
+                .unwrap_or_default(),
+            config
+                .get_int(MAX_POLL_RECORDS_CONFIG)?
+                .unwrap_or_default()
+                .max(1) as usize,
+            config.get_boolean(CHECK_CRCS_CONFIG)? == Some(true),
+            config.get_string(CLIENT_RACK_CONFIG)?.unwrap_or_default(),
+            isolation_level,
+            retry_backoff_ms,
+            request_timeout_ms,
+            &log_context,
+        ));
+
+        config.log_unused();
+        Ok(KafkaConsumer::new(
+            client_id,
+            group_id,
+            coordinator,
+            key_deserializer,
+            value_deserializer,
+            fetcher,
+            time,
+            client,
+            subscriptions,
+            metadata,
+            retry_backoff_ms,
+            request_timeout_ms,
+            default_api_timeout_ms,
+            assignors,
+        ))
+    }
+
+    fn create_log_context(client_id: &str, group_id: Option<&str>) -> LogContext {
+        match group_id {
+            Some(group_id) => LogContext::new(format!(
+                "[Consumer clientId={}, groupId={}] ",
+                client_id, group_id
+            )),
+            None => LogContext::new(format!("[Consumer clientId={}] ", client_id)),
+        }
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Get the set of partitions currently assigned to this consumer. If subscription happened by directly assigning
+    /// partitions using `assign` then this will simply return the same partitions that
+    /// were assigned. If topic subscription was used, then this will give the set of topic partitions currently assigned
+    /// to the consumer (which may be none if the assignment hasn't happened yet, or the partitions are in the
+    /// process of getting reassigned).
+    pub fn assignment(&self) -> Result<HashSet<TopicPartition>> {
+        let _guard = self.acquire_and_ensure_open()?;
+        Ok(self.subscriptions.assigned_partitions())
+    }
+
+    /// Get the current subscription. Will return the same topics used in the most recent call to
+    /// `subscribe`, or an empty set if no such call has been made.
+    pub fn subscription(&self) -> Result<HashSet<String>> {
+        let _guard = self.acquire_and_ensure_open()?;
+        Ok(self.subscriptions.subscription())
+    }
+
+    /// Subscribe to the given list of topics to get dynamically assigned partitions.
+    /// **Topic subscriptions are not incremental. This list will replace the current
+    /// assignment (if there is one).** Note that it is not possible to combine topic subscription with group management
+    /// with manual partition assignment through `assign`.
+    ///
+    /// If the given list of topics is empty, it is treated the same as `unsubscribe`.
+    ///
+    /// As part of group management, the consumer will keep track of the list of consumers that belong to a particular
+    /// group and will trigger a rebalance operation if any one of the following events are triggered:
+    /// * Number of partitions change for any of the subscribed topics
+    /// * A subscribed topic is created or deleted
+    /// * An existing member of the consumer group is shutdown or fails
+    /// * A new member is added to the consumer group
+    ///
+    /// When any of these events are triggered, the provided listener will be invoked first to indicate that
+    /// the consumer's assignment has been revoked, and then again when the new assignment has been received.
+    /// Note that rebalances will only occur during an active call to `poll`, so callbacks will
+    /// also only be invoked during that time.
+    ///
+    /// Fails with `IllegalArgument` if a topic is empty, with `IllegalState` if `subscribe` is called
+    /// previously with pattern, or assign is called previously (without a subsequent call to `unsubscribe`),
+    /// or if not configured at-least one partition assignment strategy.
+    pub fn subscribe(
+        &self,
+        topics: &[String],
+        listener: Option<Arc<dyn ConsumerRebalanceListener>>,
+    ) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.maybe_throw_invalid_group_id_exception()?;
+        if topics.is_empty() {
+            // treat subscribing to empty topic list as the same as unsubscribing
+            return self.do_unsubscribe();
+        }
+        if topics.iter().any(|topic| topic.trim().is_empty()) {
+            return Err(KafkaError::IllegalArgument(
+                "Topic collection to subscribe to cannot contain null or empty topic".to_owned(),
+            ));
+        }
+
+        self.throw_if_no_assignors_configured()?;
+        let topics: HashSet<String> = topics.iter().cloned().collect();
+        self.fetcher
+            .clear_buffered_data_for_unassigned_topics(&topics);
+        self.log.info(format_args!(
+            "Subscribed to topic(s): {}",
+            topics.iter().cloned().collect::<Vec<_>>().join(", ")
+        ));
+        self.set_rebalance_listener(listener);
+        if self.subscriptions.subscribe(topics)? {
+            self.metadata.request_update_for_new_topics();
+        }
+        Ok(())
+    }
+
+    /// Subscribe to all topics matching specified pattern to get dynamically assigned partitions.
+    /// The pattern matching will be done periodically against all topics existing at the time of check.
+    /// This can be controlled through the `metadata.max.age.ms` configuration: by lowering
+    /// the max metadata age, the consumer will refresh metadata more often and check for matching topics.
+    ///
+    /// See `subscribe` for details on the use of the `ConsumerRebalanceListener`.
+    pub fn subscribe_pattern(
+        &self,
+        pattern: Regex,
+        listener: Option<Arc<dyn ConsumerRebalanceListener>>,
+    ) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.maybe_throw_invalid_group_id_exception()?;
+        self.throw_if_no_assignors_configured()?;
+        self.log
+            .info(format_args!("Subscribed to pattern: '{}'", pattern));
+        self.set_rebalance_listener(listener);
+        self.subscriptions.subscribe_pattern(pattern)?;
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.update_pattern_subscription(&self.metadata.fetch())?;
+        }
+        self.metadata.request_update_for_new_topics();
+        Ok(())
+    }
+
+    /// Unsubscribe from topics currently subscribed with `subscribe` or `subscribe_pattern`.
+    /// This also clears any partitions directly assigned through `assign`.
+    /// If auto-commit is enabled, an async commit (based on the old subscription) will be initiated before
+    /// the rebalance listener is invoked to revoke the partitions.
+    pub fn unsubscribe(&self) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.do_unsubscribe()
+    }
+
+    fn do_unsubscribe(&self) -> Result<()> {
+        self.fetcher
+            .clear_buffered_data_for_unassigned_partitions(&HashSet::new());
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.on_leave_prepare()?;
+            coordinator
+                .coordinator()
+                .maybe_leave_group("the consumer unsubscribed from all topics")?;
+        }
+        self.subscriptions.unsubscribe();
+        self.log.info(format_args!(
+            "Unsubscribed all topics or patterns and assigned partitions"
+        ));
+        Ok(())
+    }
+
+    /// Manually assign a list of partitions to this consumer. This interface does not allow for incremental assignment
+    /// and will replace the previous assignment (if there is one).
+    ///
+    /// If the given list of topic partitions is empty, it is treated the same as `unsubscribe`.
+    ///
+    /// Manual topic assignment through this method does not use the consumer's group management
+    /// functionality. As such, there will be no rebalance operation triggered when group membership or cluster and topic
+    /// metadata change. Note that it is not possible to use both manual partition assignment with `assign`
+    /// and group assignment with `subscribe`.
+    ///
+    /// If auto-commit is enabled, an async commit (based on the old assignment) will be initiated before the new
+    /// assignment replaces the old one.
+    pub fn assign(&self, partitions: &[TopicPartition]) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        if partitions.is_empty() {
+            return self.do_unsubscribe();
+        }
+        if partitions.iter().any(|tp| tp.topic.trim().is_empty()) {
+            return Err(KafkaError::IllegalArgument(
+                "Topic partitions to assign to cannot have null or empty topic".to_owned(),
+            ));
+        }
+        let partitions: HashSet<TopicPartition> = partitions.iter().cloned().collect();
+        self.fetcher
+            .clear_buffered_data_for_unassigned_partitions(&partitions);
+
+        // make sure the offsets of topic partitions the consumer is unsubscribing from
+        // are committed since there will be no following rebalance
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.maybe_auto_commit_offsets_async(self.time.milliseconds())?;
+        }
+
+        self.log.info(format_args!(
+            "Subscribed to partition(s): {}",
+            partitions
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        if self.subscriptions.assign_from_user(&partitions)? {
+            self.metadata.request_update_for_new_topics();
+        }
+        Ok(())
+    }
+
+    /// Fetch data for the topics or partitions specified using one of the subscribe/assign APIs. It is an error to not have
+    /// subscribed to any topics or partitions before polling for data.
+    ///
+    /// On each poll, consumer will try to use the last consumed offset as the starting offset and fetch sequentially. The last
+    /// consumed offset can be manually set through `seek` or automatically set as the last committed
+    /// offset for the subscribed list of partitions
+    ///
+    /// This method returns immediately if there are records available. Otherwise, it will await the passed timeout.
+    /// If the timeout expires, an empty record set will be returned. Note that this method may block beyond the
+    /// timeout in order to execute custom `ConsumerRebalanceListener` callbacks.
+    ///
+    /// Fails with `IllegalState` if the consumer is not subscribed to any topics or manually assigned any
+    /// partitions to consume from, with `Wakeup` if `wakeup` is called before or while this function is called,
+    /// with `Serialization` if a record cannot be deserialized (the position is left at the failed record),
+    /// and with errors of the fetch and group protocols otherwise.
+    pub fn poll(&self, timeout: Duration) -> Result<ConsumerRecords<K, V>> {
+        let _guard = self.acquire_and_ensure_open()?;
+        let mut timer = Timer::new(self.time.clone(), timeout.as_millis());
+
+        if self.subscriptions.has_no_subscription_or_user_assignment() {
+            return Err(KafkaError::IllegalState(
+                "Consumer is not subscribed to any topics or assigned any partitions".to_owned(),
+            ));
+        }
+
+        loop {
+            self.client.maybe_trigger_wakeup()?;
+
+            // try to update assignment metadata BUT do not need to block on the timer for join group
+            self.update_assignment_metadata_if_needed(&mut timer, false)?;
+
+            let records = self.poll_for_fetches(&mut timer)?;
+            if !records.is_empty() {
+                // before returning the fetched records, we can send off the next round of fetches
+                // and avoid block waiting for their responses to enable pipelining while the user
+                // is handling the fetched records.
+                //
+                // NOTE: since the consumed position has already been updated, we must not allow
+                // wakeups or any other errors to be triggered prior to returning the fetched records.
+                let fetches_sent = match self.fetcher.send_fetches() {
+                    Ok(fetches_sent) => fetches_sent,
+                    Err(error) => {
+                        self.log.debug(format_args!(
+                            "Failed to send the next round of fetches: {}",
+                            error
+                        ));
+                        0
+                    }
+                };
+                if fetches_sent > 0 || self.client.has_pending_requests() {
+                    self.client.transmit_sends();
+                }
+                return Ok(ConsumerRecords::new(records));
+            }
+
+            if timer.is_expired() {
+                return Ok(ConsumerRecords::empty());
+            }
+        }
+    }
+
+    /// Visible for testing
+    pub fn update_assignment_metadata_if_needed(
+        &self,
+        timer: &mut Timer,
+        wait_for_join_group: bool,
+    ) -> Result<bool> {
+        if let Some(coordinator) = &self.coordinator {
+            if !coordinator.poll(timer, wait_for_join_group)? {
+                return Ok(false);
+            }
+        }
+        self.update_fetch_positions(timer)
+    }
+
+    fn poll_for_fetches(
+        &self,
+        timer: &mut Timer,
+    ) -> Result<IndexMap<TopicPartition, Vec<ConsumerRecord<K, V>>>> {
+        let mut poll_timeout = match &self.coordinator {
+            Some(coordinator) => coordinator
+                .time_to_next_poll(timer.current_time_ms())
+                .min(timer.remaining_ms()),
+            None => timer.remaining_ms(),
+        };
+
+        // if data is available already, return it immediately
+        let records = self.fetcher.fetched_records()?;
+        if !records.is_empty() {
+            return self.deserialize_records(records);
+        }
+
+        // send any new fetches (won't resend pending fetches)
+        self.fetcher.send_fetches()?;
+
+        // We do not want to be stuck blocking in poll if we are missing some positions
+        // since the offset lookup may be backing off after a failure
+
+        // NOTE: the use of cached_subscription_has_all_fetch_positions means we MUST call
+        // update_assignment_metadata_if_needed before this method.
+        if !self
+            .cached_subscription_has_all_fetch_positions
+            .load(Ordering::SeqCst)
+            && poll_timeout > self.retry_backoff_ms
+        {
+            poll_timeout = self.retry_backoff_ms;
+        }
+
+        self.log.trace(format_args!(
+            "Polling for fetches with timeout {}",
+            poll_timeout
+        ));
+
+        let mut poll_timer = Timer::new(self.time.clone(), poll_timeout);
+        // since a fetch might be completed by the background thread, we need this poll condition
+        // to ensure that we do not block unnecessarily in poll()
+        let fetcher = self.fetcher.clone();
+        self.client.poll_with_condition(
+            &mut poll_timer,
+            Some(&move || !fetcher.has_available_fetches()),
+            false,
+        )?;
+        timer.update_to(poll_timer.current_time_ms());
+
+        let records = self.fetcher.fetched_records()?;
+        self.deserialize_records(records)
+    }
+
+    /// Deserializes the fetched records. Deserialization stops at the first record which fails:
+    /// the position of its partition (and of any partition that was not deserialized yet) is
+    /// moved back so the record is fetched again by the next poll, which then fails right away.
+    fn deserialize_records(
+        &self,
+        fetched: FetchedRecords,
+    ) -> Result<IndexMap<TopicPartition, Vec<ConsumerRecord<K, V>>>> {
+        let mut records = IndexMap::new();
+        let mut first_error = None;
+        for (partition, partition_records) in fetched {
+            if first_error.is_some() {
+                if let Some(first) = partition_records.first() {
+                    self.rewind_position(&partition, first.offset, first.leader_epoch)?;
+                }
+                continue;
+            }
+
+            let mut parsed_records = Vec::with_capacity(partition_records.len());
+            for record in partition_records {
+                let (offset, leader_epoch) = (record.offset, record.leader_epoch);
+                match self.parse_record(&partition, record) {
+                    Ok(parsed_record) => parsed_records.push(parsed_record),
+                    Err(error) => {
+                        self.rewind_position(&partition, offset, leader_epoch)?;
+                        first_error = Some(error);
+                        break;
+                    }
+                }
+            }
+            if !parsed_records.is_empty() {
+                records.insert(partition, parsed_records);
+            }
+        }
+
+        match first_error {
+            Some(error) if records.is_empty() => Err(error),
+            _ => Ok(records),
+        }
+    }
+
+    fn parse_record(
+        &self,
+        partition: &TopicPartition,
+        record: ConsumerRecord<Option<Bytes>, Option<Bytes>>,
+    ) -> Result<ConsumerRecord<K, V>> {
+        let deserialization_error = |error: KafkaError| {
+            KafkaError::Serialization(format!(
+                "Error deserializing key/value for partition {} at offset {}. If needed, please seek past the record to continue consumption. Caused by: {}",
+                partition, record.offset, error
+            ))
+        };
+        let key = self
+            .key_deserializer
+            .deserialize(&record.topic, &record.headers, record.key.as_ref())
+            .map_err(deserialization_error)?;
+        let value = self
+            .value_deserializer
+            .deserialize(&record.topic, &record.headers, record.value.as_ref())
+            .map_err(deserialization_error)?;
+        Ok(ConsumerRecord {
+            topic: record.topic,
+            partition: record.partition,
+            offset: record.offset,
+            timestamp: record.timestamp,
+            timestamp_type: record.timestamp_type,
+            serialized_key_size: record.serialized_key_size,
+            serialized_value_size: record.serialized_value_size,
+            headers: record.headers,
+            key,
+            value,
+            leader_epoch: record.leader_epoch,
+        })
+    }
+
+    fn rewind_position(
+        &self,
+        partition: &TopicPartition,
+        offset: i64,
+        leader_epoch: Option<i32>,
+    ) -> Result<()> {
+        let position = FetchPosition::new(
+            offset,
+            leader_epoch,
+            self.metadata.current_leader(partition),
+        );
+        self.subscriptions.set_position(partition, position)
+    }
+
+    /// Commit offsets returned on the last `poll` for all the subscribed list of topics and
+    /// partitions.
+    ///
+    /// This is a synchronous commit and will block until either the commit succeeds, an unrecoverable error is
+    /// encountered (in which case it is returned to the caller), or the timeout expires.
+    ///
+    /// Fails with `Timeout` if the timeout expires before successful completion of the offset commit, with
+    /// `CommitFailed` if the commit failed and cannot be retried (this can only occur if you are using automatic
+    /// group management with `subscribe`, or if there is an active group with the same group id which is using
+    /// group management).
+    pub fn commit_sync(&self, timeout: Duration) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.do_commit_sync(self.subscriptions.all_consumed(), timeout, || {
+            format!(
+                "Timeout of {}ms expired before successfully committing the current consumed offsets",
+                timeout.as_millis()
+            )
+        })
+    }
+
+    /// Commit the specified offsets for the specified list of topics and partitions.
+    ///
+    /// The committed offset should be the next message your application will consume,
+    /// i.e. lastProcessedMessageOffset + 1.
+    ///
+    /// See `commit_sync` for the errors.
+    pub fn commit_sync_offsets(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        let description = offsets
+            .iter()
+            .map(|(tp, offset)| format!("{}={}", tp, offset))
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.do_commit_sync(offsets, timeout, || {
+            format!(
+                "Timeout of {}ms expired before successfully committing offsets {{{}}}",
+                timeout.as_millis(),
+                description
+            )
+        })
+    }
+
+    fn do_commit_sync<F>(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        timeout: Duration,
+        timeout_message: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> String,
+    {
+        let coordinator = self.maybe_throw_invalid_group_id_exception()?;
+        for (tp, offset_and_metadata) in &offsets {
+            self.update_last_seen_epoch_if_newer(tp, offset_and_metadata);
+        }
+        let mut timer = Timer::new(self.time.clone(), timeout.as_millis());
+        if !coordinator.commit_offsets_sync(offsets, &mut timer)? {
+            return Err(KafkaError::Timeout(timeout_message()));
+        }
+        Ok(())
+    }
+
+    /// Commit offsets returned on the last `poll` for the subscribed list of topics and partitions.
+    ///
+    /// This is an asynchronous call and will not block. Any errors encountered are either passed to the callback
+    /// (if provided) or discarded.
+    ///
+    /// Offsets committed through multiple calls to this API are guaranteed to be sent in the same order as
+    /// the invocations. Corresponding commit callbacks are also invoked in the same order. Additionally note that
+    /// offsets committed through this API are guaranteed to complete before a subsequent call to `commit_sync`
+    /// (and variants) returns.
+    pub fn commit_async(&self, callback: Option<OffsetCommitCallback>) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.do_commit_async(self.subscriptions.all_consumed(), callback)
+    }
+
+    /// Commit the specified offsets for the specified list of topics and partitions to Kafka.
+    ///
+    /// The committed offset should be the next message your application will consume,
+    /// i.e. lastProcessedMessageOffset + 1. See `commit_async` for ordering guarantees.
+    pub fn commit_async_offsets(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        callback: Option<OffsetCommitCallback>,
+    ) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        self.do_commit_async(offsets, callback)
+    }
+
+    fn do_commit_async(
+        &self,
+        offsets: IndexMap<TopicPartition, OffsetAndMetadata>,
+        callback: Option<OffsetCommitCallback>,
+    ) -> Result<()> {
+        let coordinator = self.maybe_throw_invalid_group_id_exception()?;
+        self.log.debug(format_args!(
+            "Committing offsets: {}",
+            offsets
+                .iter()
+                .map(|(tp, offset)| format!("{}={}", tp, offset))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        for (tp, offset_and_metadata) in &offsets {
+            self.update_last_seen_epoch_if_newer(tp, offset_and_metadata);
+        }
+        coordinator.commit_offsets_async(offsets, callback)
+    }
+
+    /// Overrides the fetch offsets that the consumer will use on the next `poll`. If this API
+    /// is invoked for the same partition more than once, the latest offset will be used on the next poll(). Note that
+    /// you may lose data if this API is arbitrarily used in the middle of consumption, to reset the fetch offsets
+    ///
+    /// Fails with `IllegalArgument` if the provided offset is negative and with `IllegalState` if the
+    /// provided partition is not assigned to this consumer.
+    pub fn seek(&self, partition: &TopicPartition, offset: i64) -> Result<()> {
+        if offset < 0 {
+            return Err(KafkaError::IllegalArgument(
+                "seek offset must not be a negative number".to_owned(),
+            ));
+        }
+
+        let _guard = self.acquire_and_ensure_open()?;
+        self.log.info(format_args!(
+            "Seeking to offset {} for partition {}",
+            offset, partition
+        ));
+        let new_position =
+            FetchPosition::new(offset, None, self.metadata.current_leader(partition));
+        self.subscriptions.seek_unvalidated(partition, new_position)
+    }
+
+    /// Overrides the fetch offsets that the consumer will use on the next `poll`. This
+    /// variant also uses the leader epoch of the offset to validate the position.
+    pub fn seek_offset_and_metadata(
+        &self,
+        partition: &TopicPartition,
+        offset_and_metadata: &OffsetAndMetadata,
+    ) -> Result<()> {
+        let offset = offset_and_metadata.offset;
+        if offset < 0 {
+            return Err(KafkaError::IllegalArgument(
+                "seek offset must not be a negative number".to_owned(),
+            ));
+        }
+
+        let _guard = self.acquire_and_ensure_open()?;
+        match offset_and_metadata.leader_epoch {
+            Some(leader_epoch) => {
+                self.log.info(format_args!(
+                    "Seeking to offset {} for partition {} with epoch {}",
+                    offset, partition, leader_epoch
+                ));
+                self.update_last_seen_epoch_if_newer(partition, offset_and_metadata);
+            }
+            None => self.log.info(format_args!(
+                "Seeking to offset {} for partition {}",
+                offset, partition
+            )),
+        }
+        let new_position = FetchPosition::new(
+            offset,
+            offset_and_metadata.leader_epoch,
+            self.metadata.current_leader(partition),
+        );
+        self.subscriptions.seek_unvalidated(partition, new_position)
+    }
+
+    /// Seek to the first offset for each of the given partitions. This function evaluates lazily, seeking to the
+    /// first offset in all partitions only when `poll` or `position` are called.
+    /// If no partitions are provided, seek to the first offset for all of the currently assigned partitions.
+    pub fn seek_to_beginning(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.seek_with_reset_strategy(partitions, OffsetResetStrategy::Earliest)
+    }
+
+    /// Seek to the last offset for each of the given partitions. This function evaluates lazily, seeking to the
+    /// final offset in all partitions only when `poll` or `position` are called.
+    /// If no partitions are provided, seek to the final offset for all of the currently assigned partitions.
+    pub fn seek_to_end(&self, partitions: &[TopicPartition]) -> Result<()> {
+        self.seek_with_reset_strategy(partitions, OffsetResetStrategy::Latest)
+    }
+
+    fn seek_with_reset_strategy(
+        &self,
+        partitions: &[TopicPartition],
+        offset_reset_strategy: OffsetResetStrategy,
+    ) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        if partitions.is_empty() {
+            let assigned_partitions = self.subscriptions.assigned_partitions_list();
+            self.subscriptions
+                .request_offset_reset_for_partitions(&assigned_partitions, offset_reset_strategy)
+        } else {
+            self.subscriptions
+                .request_offset_reset_for_partitions(partitions, offset_reset_strategy)
+        }
+    }
+
+    /// Get the offset of the next record that will be fetched (if a record with that offset exists).
+    /// This method may issue a remote call to the server if there is no current position
+    /// for the given partition.
+    ///
+    /// This call will block until the position can be determined, an unrecoverable error is
+    /// encountered (in which case it is returned to the caller), or the timeout expires.
+    ///
+    /// Fails with `IllegalState` if the provided partition is not assigned to this consumer, with
+    /// `Timeout` if the position cannot be determined before the timeout expires.
+    pub fn position(&self, partition: &TopicPartition, timeout: Duration) -> Result<i64> {
+        let _guard = self.acquire_and_ensure_open()?;
+        if !self.subscriptions.is_assigned(partition) {
+            return Err(KafkaError::IllegalState(
+                "You can only check the position for partitions assigned to this consumer."
+                    .to_owned(),
+            ));
+        }
+
+        let mut timer = Timer::new(self.time.clone(), timeout.as_millis());
+        loop {
+            if let Some(position) = self.subscriptions.valid_position(partition)? {
+                return Ok(position.offset);
+            }
+
+            self.update_fetch_positions(&mut timer)?;
+            self.client.poll(&mut timer)?;
+            if timer.is_expired() {
+                break;
+            }
+        }
+        Err(KafkaError::Timeout(format!(
+            "Timeout of {}ms expired before the position for partition {} could be determined",
+            timeout.as_millis(),
+            partition
+        )))
+    }
+
+    /// Get the last committed offsets for the given partitions (whether the commit happened by this process or
+    /// another). The returned offsets will be used as the position for the consumer in the event of a failure.
+    ///
+    /// Partitions that do not have a committed offset map to `None`.
+    ///
+    /// Fails with `Timeout` if the committed offsets cannot be found before the timeout expires.
+    pub fn committed(
+        &self,
+        partitions: &HashSet<TopicPartition>,
+        timeout: Duration,
+    ) -> Result<IndexMap<TopicPartition, Option<OffsetAndMetadata>>> {
+        let _guard = self.acquire_and_ensure_open()?;
+        let coordinator = self.maybe_throw_invalid_group_id_exception()?;
+        let mut timer = Timer::new(self.time.clone(), timeout.as_millis());
+        match coordinator.fetch_committed_offsets(partitions, &mut timer)? {
+            Some(offsets) => {
+                for (tp, offset_and_metadata) in &offsets {
+                    if let Some(offset_and_metadata) = offset_and_metadata {
+                        self.update_last_seen_epoch_if_newer(tp, offset_and_metadata);
+                    }
+                }
+                Ok(offsets)
+            }
+            None => Err(KafkaError::Timeout(format!(
+                "Timeout of {}ms expired before the last committed offset for partitions {:?} could be determined. \
+                 Try tuning default.api.timeout.ms larger to relax the threshold.",
+                timeout.as_millis(),
+                partitions
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+            ))),
+        }
+    }
+
+    /// Get the last committed offsets for the given partitions, waiting at most `default.api.timeout.ms`.
+    pub fn committed_with_default_timeout(
+        &self,
+        partitions: &HashSet<TopicPartition>,
+    ) -> Result<IndexMap<TopicPartition, Option<OffsetAndMetadata>>> {
+        self.committed(
+            partitions,
+            Duration::from_millis(self.default_api_timeout_ms as u64),
+        )
+    }
+
+    /// Suspend fetching from the requested partitions. Future calls to `poll` will not return
+    /// any records from these partitions until they have been resumed using `resume`.
+    /// Note that this method does not affect partition subscription. In particular, it does not cause a group
+    /// rebalance when automatic assignment is used.
+    pub fn pause(&self, partitions: &[TopicPartition]) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        for partition in partitions {
+            self.log
+                .debug(format_args!("Pausing partition {}", partition));
+            self.subscriptions.pause(partition)?;
+        }
+        Ok(())
+    }
+
+    /// Resume specified partitions which have been paused with `pause`. New calls to
+    /// `poll` will return records from these partitions if there are any to be fetched.
+    /// If the partitions were not previously paused, this method is a no-op.
+    pub fn resume(&self, partitions: &[TopicPartition]) -> Result<()> {
+        let _guard = self.acquire_and_ensure_open()?;
+        for partition in partitions {
+            self.log
+                .debug(format_args!("Resuming partition {}", partition));
+            self.subscriptions.resume(partition)?;
+        }
+        Ok(())
+    }
+
+    /// Get the set of partitions that were previously paused by a call to `pause`.
+    pub fn paused(&self) -> Result<HashSet<TopicPartition>> {
+        let _guard = self.acquire_and_ensure_open()?;
+        Ok(self.subscriptions.paused_partitions())
+    }
+
+    /// Return the current group metadata associated with this consumer.
+    pub fn group_metadata(&self) -> Result<ConsumerGroupMetadata> {
+        let _guard = self.acquire_and_ensure_open()?;
+        Ok(self
+            .maybe_throw_invalid_group_id_exception()?
+            .group_metadata())
+    }
+
+    /// Wakeup the consumer. This method is thread-safe and is useful in particular to abort a long poll.
+    /// The thread which is blocking in an operation will fail with `Wakeup`.
+    /// If no thread is blocking in a method which can fail with `Wakeup`, the next call to such a method will
+    /// fail instead.
+    pub fn wakeup(&self) {
+        self.client.wakeup();
+    }
+
+    /// Adapts the consumer to an async stream of records. See `ConsumerStream`.
+    pub fn stream(&self, poll_interval: Duration) -> ConsumerStream<'_, K, V> {
+        ConsumerStream::new(self, poll_interval)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Tries to close the consumer cleanly within the specified timeout. This method waits up to
+    /// `timeout` for the consumer to complete pending commits and leave the group.
+    /// If auto-commit is enabled, this will commit the current offsets if possible within the
+    /// timeout. If the consumer is unable to complete offset commits and gracefully leave the group
+    /// before the timeout expires, the consumer is force closed. Note that `wakeup` cannot be
+    /// used to interrupt close.
+    pub fn close(&self, timeout: Duration) -> Result<()> {
+        let _guard = self.acquire()?;
+        if self.closed.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        // need to close before setting the flag since the close function
+        // itself may trigger rebalance callback that needs the consumer to be open still
+        let result = self.do_close(timeout.as_millis());
+        self.closed.store(true, Ordering::SeqCst);
+        result
+    }
+
+    fn do_close(&self, timeout_ms: u128) -> Result<()> {
+        self.log.trace(format_args!("Closing the Kafka consumer"));
+        let mut first_exception = None;
+        if let Some(coordinator) = &self.coordinator {
+            let mut timer = Timer::new(self.time.clone(), timeout_ms.min(self.request_timeout_ms));
+            if let Err(error) = coordinator.close(&mut timer) {
+                self.log
+                    .error(format_args!("Failed to close coordinator: {}", error));
+                first_exception = Some(error);
+            }
+        }
+        self.fetcher.close();
+        self.client.close();
+        self.log
+            .debug(format_args!("Kafka consumer has been closed"));
+        match first_exception {
+            Some(error @ KafkaError::Interrupt(_)) => Err(error),
+            Some(error) => Err(KafkaError::Kafka(format!(
+                "Failed to close kafka consumer: {}",
+                error
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the fetch position to the committed position (if there is one)
+    /// or reset it using the offset reset policy the user has configured.
+    ///
+    /// Returns true iff the operation completed without timing out
+    fn update_fetch_positions(&self, timer: &mut Timer) -> Result<bool> {
+        // If any partitions have been truncated due to a leader change, we need to validate the offsets
+        self.fetcher.validate_offsets_if_needed()?;
+
+        let has_all_fetch_positions = self.subscriptions.has_all_fetch_positions();
+        self.cached_subscription_has_all_fetch_positions
+            .store(has_all_fetch_positions, Ordering::SeqCst);
+        if has_all_fetch_positions {
+            return Ok(true);
+        }
+
+        // If there are any partitions which do not have a valid position and are not
+        // awaiting reset, then we need to fetch committed offsets. We will only do a
+        // coordinator lookup if there are partitions which have missing positions, so
+        // a consumer with manually assigned partitions can avoid a coordinator dependence
+        // by always ensuring that assigned partitions have an initial position.
+        if let Some(coordinator) = &self.coordinator {
+            if !coordinator.refresh_committed_offsets_if_needed(timer)? {
+                return Ok(false);
+            }
+        }
+
+        // If there are partitions still needing a position and a reset policy is defined,
+        // request reset using the default policy. If no reset strategy is defined and there
+        // are partitions with a missing position, then we will raise an exception.
+        self.subscriptions.reset_initializing_positions()?;
+
+        // Finally send an asynchronous request to lookup and update the positions of any
+        // partitions which are awaiting reset.
+        self.fetcher.reset_offsets_if_needed()?;
+
+        Ok(true)
+    }
+
+    fn set_rebalance_listener(&self, listener: Option<Arc<dyn ConsumerRebalanceListener>>) {
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.set_rebalance_listener(
+                listener.unwrap_or_else(|| Arc::new(NoOpConsumerRebalanceListener)),
+            );
+        }
+    }
+
+    fn update_last_seen_epoch_if_newer(
+        &self,
+        topic_partition: &TopicPartition,
+        offset_and_metadata: &OffsetAndMetadata,
+    ) {
+        if let Some(epoch) = offset_and_metadata.leader_epoch {
+            self.metadata
+                .update_last_seen_epoch_if_newer(topic_partition, epoch);
+        }
+    }
+
+    fn maybe_throw_invalid_group_id_exception(&self) -> Result<&Arc<ConsumerCoordinator>> {
+        match (&self.group_id, &self.coordinator) {
+            (Some(_), Some(coordinator)) => Ok(coordinator),
+            _ => Err(KafkaError::InvalidGroupId(
+                "To use the group management or offset commit APIs, you must provide a valid \
+                 group.id in the consumer configuration."
+                    .to_owned(),
+            )),
+        }
+    }
+
+    fn throw_if_no_assignors_configured(&self) -> Result<()> {
+        if self.assignors.is_empty() {
+            return Err(KafkaError::IllegalState(
+                "Must configure at least one partition assigner class name to \
+                 partition.assignment.strategy configuration property"
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Acquire the light lock and ensure that the consumer hasn't been closed.
+    fn acquire_and_ensure_open(&self) -> Result<ConsumerGuard<'_>> {
+        let guard = self.acquire()?;
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(KafkaError::IllegalState(
+                "This consumer has already been closed.".to_owned(),
+            ));
+        }
+        Ok(guard)
+    }
+
+    /// Acquire the light lock protecting this consumer from multi-threaded access. Instead of blocking
+    /// when the lock is not available, however, we just fail with `ConcurrentModification`. The
+    /// lock is released when the returned guard is dropped.
+    fn acquire(&self) -> Result<ConsumerGuard<'_>> {
+        let thread_id = THREAD_ID.with(|thread_id| *thread_id);
+        if thread_id != self.current_thread.load(Ordering::SeqCst)
+            && self
+                .current_thread
+                .compare_exchange(
+                    NO_CURRENT_THREAD,
+                    thread_id,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_err()
+        {
+            return Err(KafkaError::ConcurrentModification(
+                "KafkaConsumer is not safe for multi-threaded access".to_owned(),
+            ));
+        }
+        self.refcount.fetch_add(1, Ordering::SeqCst);
+        Ok(ConsumerGuard {
+            current_thread: &self.current_thread,
+            refcount: &self.refcount,
+        })
+    }
+}
+
+/// Instantiates the assignors named in `partition.assignment.strategy`, by class name or by the name
+/// of the assignor.
+fn assignor_instances(names: &[String]) -> Result<Vec<Arc<dyn ConsumerPartitionAssignor>>> {
+    names
+        .iter()
+        .map(|name| -> Result<Arc<dyn ConsumerPartitionAssignor>> {
+            match name.rsplit('.').next().unwrap_or_default() {
+                "RangeAssignor" | "range" => Ok(Arc::new(RangeAssignor)),
+                "RoundRobinAssignor" | "roundrobin" => Ok(Arc::new(RoundRobinAssignor)),
+                "StickyAssignor" | "sticky" => Ok(Arc::new(StickyAssignor::new())),
+                "CooperativeStickyAssignor" | "cooperative-sticky" => {
+                    Ok(Arc::new(CooperativeStickyAssignor::new()))
+                }
+                _ => Err(KafkaError::Kafka(format!(
+                    "{} is not an instance of org.apache.kafka.clients.consumer.ConsumerPartitionAssignor",
+                    name
+                ))),
+            }
+        })
+        .collect()
+}
+
+impl<K, V> Drop for KafkaConsumer<K, V> {
+    fn drop(&mut self) {
+        if !self.closed.load(Ordering::SeqCst) {
+            if let Err(error) = self.do_close(0) {
+                self.log.warn(format_args!(
+                    "Failed to close kafka consumer on drop: {}",
+                    error
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use indexmap::IndexMap;
+
+    use crate::common::{
+        errors::KafkaError, serialization::string_deserializer::StringDeserializer,
+        topic_partition::TopicPartition,
+    };
+
+    use super::{
+        super::consumer_config::{
+            ConsumerConfig, GROUP_ID_CONFIG, KEY_DESERIALIZER_CLASS_CONFIG,
+            PARTITION_ASSIGNMENT_STRATEGY_CONFIG, VALUE_DESERIALIZER_CLASS_CONFIG,
+        },
+        KafkaConsumer, BOOTSTRAP_SERVERS_CONFIG,
+    };
+
+    const STRING_DESERIALIZER: &str = "org.apache.kafka.common.serialization.StringDeserializer";
+
+    fn config(extra: &[(&str, &str)]) -> ConsumerConfig {
+        let mut props: IndexMap<String, String> = [
+            (BOOTSTRAP_SERVERS_CONFIG, "localhost:9092"),
+            (KEY_DESERIALIZER_CLASS_CONFIG, STRING_DESERIALIZER),
+            (VALUE_DESERIALIZER_CLASS_CONFIG, STRING_DESERIALIZER),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        for (key, value) in extra {
+            props.insert(key.to_string(), value.to_string());
+        }
+        ConsumerConfig::new(props).unwrap()
+    }
+
+    fn consumer(
+        config: ConsumerConfig,
+    ) -> Result<KafkaConsumer<Option<String>, Option<String>>, KafkaError> {
+        KafkaConsumer::from_config(
+            config,
+            Box::new(StringDeserializer),
+            Box::new(StringDeserializer),
+        )
+    }
+
+    #[test]
+    fn from_config_with_group_id_creates_a_coordinator() {
+        let consumer = consumer(config(&[
+            (GROUP_ID_CONFIG, "group"),
+            (
+                PARTITION_ASSIGNMENT_STRATEGY_CONFIG,
+                "org.apache.kafka.clients.consumer.RoundRobinAssignor,cooperative-sticky",
+            ),
+        ]))
+        .unwrap();
+        assert!(consumer.client_id().starts_with("consumer-group-"));
+        assert_eq!(consumer.group_metadata().unwrap().group_id, "group");
+        let assignors: Vec<&str> = consumer.assignors.iter().map(|a| a.name()).collect();
+        assert_eq!(assignors, vec!["roundrobin", "cooperative-sticky"]);
+        consumer.close(Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn from_config_without_group_id_has_no_coordinator() {
+        let consumer = consumer(config(&[])).unwrap();
+        assert!(matches!(
+            consumer.group_metadata(),
+            Err(KafkaError::InvalidGroupId(_))
+        ));
+        let partition = TopicPartition::new("topic", 0);
+        consumer.assign(std::slice::from_ref(&partition)).unwrap();
+        consumer.seek(&partition, 5).unwrap();
+        assert_eq!(consumer.position(&partition, Duration::ZERO).unwrap(), 5);
+        consumer.close(Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn from_config_rejects_unknown_assignors() {
+        let result = consumer(config(&[(
+            PARTITION_ASSIGNMENT_STRATEGY_CONFIG,
+            "com.example.CustomAssignor",
+        )]));
+        match result {
+            Err(KafkaError::Kafka(message)) => assert_eq!(
+                message,
+                "com.example.CustomAssignor is not an instance of org.apache.kafka.clients.consumer.ConsumerPartitionAssignor"
+            ),
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+    }
+}
@@ -1 +1,16 @@
+pub mod consumer_config;
+pub mod consumer_group_metadata;
+pub mod consumer_partition_assignor;
+pub mod consumer_rebalance_listener;
 pub mod consumer_record;
+pub mod consumer_records;
+pub mod consumer_stream;
+pub mod cooperative_sticky_assignor;
+pub mod internals;
+pub mod kafka_consumer;
+pub mod offset_and_metadata;
+pub mod offset_commit_callback;
+pub mod offset_reset_strategy;
+pub mod range_assignor;
+pub mod round_robin_assignor;
+pub mod sticky_assignor;
@@ -0,0 +1,45 @@
+use std::fmt::{self, Display};
+
+/// The Kafka offset commit API allows users to provide additional metadata (in the form of a string)
+/// when an offset is committed. This can be useful (for example) to store information about which
+/// node made the commit, what time the commit was made, etc.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OffsetAndMetadata {
+    pub offset: i64,
+    pub metadata: String,
+    // We use None to represent the absence of a leader epoch to simplify serialization.
+    // I.e., older serializations of this class which do not have this field will automatically
+    // initialize its value to None.
+    pub leader_epoch: Option<i32>,
+}
+
+impl OffsetAndMetadata {
+    /// Construct a new OffsetAndMetadata object for committing through a KafkaConsumer.
+    pub fn new(
+        offset: i64,
+        leader_epoch: Option<i32>,
+        metadata: impl Into<String>,
+    ) -> OffsetAndMetadata {
+        OffsetAndMetadata {
+            offset,
+            metadata: metadata.into(),
+            leader_epoch,
+        }
+    }
+
+    /// Construct a new OffsetAndMetadata object for committing through a KafkaConsumer. The
+    /// metadata associated with the commit will be empty.
+    pub fn from_offset(offset: i64) -> OffsetAndMetadata {
+        OffsetAndMetadata::new(offset, None, "")
+    }
+}
+
+impl Display for OffsetAndMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OffsetAndMetadata{{offset={}, leaderEpoch={:?}, metadata='{}'}}",
+            self.offset, self.leader_epoch, self.metadata
+        )
+    }
+}
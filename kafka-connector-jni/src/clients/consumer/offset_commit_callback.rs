@@ -0,0 +1,13 @@
+use indexmap::IndexMap;
+
+use crate::common::{errors::KafkaError, topic_partition::TopicPartition};
+
+use super::offset_and_metadata::OffsetAndMetadata;
+
+/// A callback interface that the user can implement to trigger custom actions when a commit request completes. The callback
+/// may be executed in any thread calling `KafkaConsumer::poll`.
+///
+/// Called with the offsets that were committed, and the error if the commit failed, in which case
+/// retriable failures are reported as `RetriableCommitFailed`.
+pub type OffsetCommitCallback =
+    Box<dyn FnOnce(&IndexMap<TopicPartition, OffsetAndMetadata>, Option<&KafkaError>) + Send>;
@@ -0,0 +1,20 @@
+use std::fmt::{self, Display};
+
+/// What to do when there is no initial offset in Kafka or if the current offset does not exist
+/// any more on the server (`auto.offset.reset`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OffsetResetStrategy {
+    Latest,
+    Earliest,
+    None,
+}
+
+impl Display for OffsetResetStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OffsetResetStrategy::Latest => f.write_str("LATEST"),
+            OffsetResetStrategy::Earliest => f.write_str("EARLIEST"),
+            OffsetResetStrategy::None => f.write_str("NONE"),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use indexmap::IndexMap;
+
+use crate::common::{cluster::Cluster, errors::Result, topic_partition::TopicPartition};
+
+use super::{
+    consumer_partition_assignor::{
+        ConsumerPartitionAssignor, GroupAssignment, GroupSubscription, Subscription,
+    },
+    internals::abstract_partition_assignor::{self, AbstractPartitionAssignor, MemberInfo},
+};
+
+pub const RANGE_ASSIGNOR_NAME: &str = "range";
+
+/// The range assignor works on a per-topic basis. For each topic, we lay out the available partitions in numeric order
+/// and the consumers in lexicographic order. We then divide the number of partitions by the total number of
+/// consumers to determine the number of partitions to assign to each consumer. If it does not evenly
+/// divide, then the first few consumers will have one extra partition.
+///
+/// For example, suppose there are two consumers `C0` and `C1`, two topics `t0` and
+/// `t1`, and each topic has 3 partitions, resulting in partitions `t0p0`, `t0p1`,
+/// `t0p2`, `t1p0`, `t1p1`, and `t1p2`.
+///
+/// The assignment will be:
+/// * `C0: [t0p0, t0p1, t1p0, t1p1]`
+/// * `C1: [t0p2, t1p2]`
+///
+/// Since the introduction of static membership, we could leverage `group_instance_id`
+/// to make the assignment behavior more sticky. Static members are ordered by their instance id ahead of
+/// dynamic members, so the assignment stays the same across restarts of the same static member.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RangeAssignor;
+
+impl RangeAssignor {
+    fn consumers_per_topic(
+        consumer_metadata: &IndexMap<String, Subscription>,
+    ) -> IndexMap<String, Vec<MemberInfo>> {
+        let mut topic_to_consumers: IndexMap<String, Vec<MemberInfo>> = IndexMap::new();
+        for (consumer_id, subscription) in consumer_metadata {
+            let member_info =
+                MemberInfo::new(consumer_id.clone(), subscription.group_instance_id.clone());
+            for topic in &subscription.topics {
+                topic_to_consumers
+                    .entry(topic.clone())
+                    .or_default()
+                    .push(member_info.clone());
+            }
+        }
+        topic_to_consumers
+    }
+}
+
+impl AbstractPartitionAssignor for RangeAssignor {
+    fn assign_partitions(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<IndexMap<String, Vec<TopicPartition>>> {
+        let consumers_per_topic = Self::consumers_per_topic(subscriptions);
+
+        let mut assignment: IndexMap<String, Vec<TopicPartition>> = subscriptions
+            .keys()
+            .map(|member_id| (member_id.clone(), vec![]))
+            .collect();
+
+        for (topic, mut consumers_for_topic) in consumers_per_topic {
+            let num_partitions_for_topic = match partitions_per_topic.get(&topic) {
+                Some(num_partitions) => *num_partitions,
+                None => continue,
+            };
+
+            consumers_for_topic.sort();
+
+            let num_partitions_per_consumer = num_partitions_for_topic / consumers_for_topic.len();
+            let consumers_with_extra_partition =
+                num_partitions_for_topic % consumers_for_topic.len();
+
+            let partitions =
+                abstract_partition_assignor::partitions(&topic, num_partitions_for_topic);
+            for (i, member) in consumers_for_topic.iter().enumerate() {
+                let start = num_partitions_per_consumer * i + i.min(consumers_with_extra_partition);
+                let length = num_partitions_per_consumer
+                    + if i + 1 > consumers_with_extra_partition {
+                        0
+                    } else {
+                        1
+                    };
+                if let Some(member_assignment) = assignment.get_mut(&member.member_id) {
+                    member_assignment.extend_from_slice(&partitions[start..start + length]);
+                }
+            }
+        }
+        Ok(assignment)
+    }
+}
+
+impl ConsumerPartitionAssignor for RangeAssignor {
+    fn assign(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment> {
+        self.assign_group(metadata, group_subscription)
+    }
+
+    fn name(&self) -> &str {
+        RANGE_ASSIGNOR_NAME
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeSet;
+
+use indexmap::IndexMap;
+
+use crate::common::{cluster::Cluster, errors::Result, topic_partition::TopicPartition};
+
+use super::{
+    consumer_partition_assignor::{
+        ConsumerPartitionAssignor, GroupAssignment, GroupSubscription, Subscription,
+    },
+    internals::abstract_partition_assignor::{self, AbstractPartitionAssignor, MemberInfo},
+};
+
+pub const ROUNDROBIN_ASSIGNOR_NAME: &str = "roundrobin";
+
+/// The round robin assignor lays out all the available partitions and all the available consumers. It
+/// then proceeds to do a round robin assignment from partition to consumer. If the subscriptions of all consumer
+/// instances are identical, then the partitions will be uniformly distributed. (i.e., the partition ownership counts
+/// will be within a delta of exactly one across all consumers.)
+///
+/// For example, suppose there are two consumers `C0` and `C1`, two topics `t0` and `t1`,
+/// and each topic has 3 partitions, resulting in partitions `t0p0`, `t0p1`, `t0p2`,
+/// `t1p0`, `t1p1`, and `t1p2`.
+///
+/// The assignment will be:
+/// * `C0: [t0p0, t0p2, t1p1]`
+/// * `C1: [t0p1, t1p0, t1p2]`
+///
+/// When subscriptions differ across consumer instances, the assignment process still considers each
+/// consumer instance in round robin fashion but skips over an instance if it is not subscribed to
+/// the topic. Unlike the case when subscriptions are identical, this can result in imbalanced
+/// assignments.
+///
+/// Members are ordered the same way as in the range assignor: static members by their group instance id first,
+/// followed by dynamic members by member id.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinAssignor;
+
+impl RoundRobinAssignor {
+    fn all_partitions_sorted(
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Vec<TopicPartition> {
+        let topics: BTreeSet<&String> = subscriptions
+            .values()
+            .flat_map(|subscription| subscription.topics.iter())
+            .collect();
+        let mut all_partitions = vec![];
+        for topic in topics {
+            if let Some(num_partitions_for_topic) = partitions_per_topic.get(topic) {
+                all_partitions.extend(abstract_partition_assignor::partitions(
+                    topic,
+                    *num_partitions_for_topic,
+                ));
+            }
+        }
+        all_partitions
+    }
+}
+
+impl AbstractPartitionAssignor for RoundRobinAssignor {
+    fn assign_partitions(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<IndexMap<String, Vec<TopicPartition>>> {
+        let mut assignment: IndexMap<String, Vec<TopicPartition>> = IndexMap::new();
+        let mut member_info_list = vec![];
+        for (member_id, subscription) in subscriptions {
+            assignment.insert(member_id.clone(), vec![]);
+            member_info_list.push(MemberInfo::new(
+                member_id.clone(),
+                subscription.group_instance_id.clone(),
+            ));
+        }
+        member_info_list.sort();
+
+        let mut assigner = member_info_list.iter().cycle().peekable();
+        for partition in Self::all_partitions_sorted(partitions_per_topic, subscriptions) {
+            // every partition comes from a topic at least one member is subscribed to
+            while let Some(member) = assigner.peek() {
+                if subscriptions[&member.member_id]
+                    .topics
+                    .contains(&partition.topic)
+                {
+                    break;
+                }
+                assigner.next();
+            }
+            if let Some(member) = assigner.next() {
+                if let Some(member_assignment) = assignment.get_mut(&member.member_id) {
+                    member_assignment.push(partition);
+                }
+            }
+        }
+        Ok(assignment)
+    }
+}
+
+impl ConsumerPartitionAssignor for RoundRobinAssignor {
+    fn assign(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment> {
+        self.assign_group(metadata, group_subscription)
+    }
+
+    fn name(&self) -> &str {
+        ROUNDROBIN_ASSIGNOR_NAME
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, MutexGuard},
+};
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{cluster::Cluster, errors::Result, topic_partition::TopicPartition};
+
+use super::{
+    consumer_group_metadata::ConsumerGroupMetadata,
+    consumer_partition_assignor::{
+        Assignment, ConsumerPartitionAssignor, GroupAssignment, GroupSubscription, Subscription,
+    },
+    internals::{
+        abstract_partition_assignor::AbstractPartitionAssignor,
+        abstract_sticky_assignor::{AbstractStickyAssignor, MemberData, DEFAULT_GENERATION},
+    },
+};
+
+pub const STICKY_ASSIGNOR_NAME: &str = "sticky";
+
+/// The sticky assignor serves two purposes. First, it guarantees an assignment that is as balanced as possible, meaning either:
+/// * the numbers of topic partitions assigned to consumers differ by at most one; or
+/// * each consumer that has 2+ fewer topic partitions than some other consumer cannot get any of those topic partitions transferred to it.
+///
+/// Second, it preserved as many existing assignment as possible when a reassignment occurs. This helps in saving some of the
+/// overhead processing when topic partitions move from one consumer to another.
+///
+/// Starting fresh it would work by distributing the partitions over consumers as evenly as possible. Even though this may sound similar to
+/// how round robin assignor works, the second example below shows that it is not.
+/// During a reassignment it would perform the reassignment in such a way that in the new assignment
+/// 1. topic partitions are still distributed as evenly as possible, and
+/// 2. topic partitions stay with their previously assigned consumers as much as possible.
+///
+/// Of course, the first goal above takes precedence over the second one.
+///
+/// The previous assignment of each member is sent to the leader as subscription user data, using the
+/// same schema as the Java client, so Rust and Java members of the same group stay sticky.
+///
+/// Note that this assignor follows the eager rebalance protocol, so all partitions are revoked
+/// before a rebalance. The `CooperativeStickyAssignor` provides the same assignment logic with
+/// cooperative rebalancing.
+#[derive(Debug, Default)]
+pub struct StickyAssignor {
+    state: Mutex<StickyAssignorState>,
+}
+
+#[derive(Debug)]
+struct StickyAssignorState {
+    member_assignment: Option<Vec<TopicPartition>>,
+    /// consumer group generation
+    generation: i32,
+}
+
+impl Default for StickyAssignorState {
+    fn default() -> Self {
+        StickyAssignorState {
+            member_assignment: None,
+            generation: DEFAULT_GENERATION,
+        }
+    }
+}
+
+impl StickyAssignor {
+    pub fn new() -> StickyAssignor {
+        StickyAssignor::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, StickyAssignorState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serializes previous assignment and generation as the version 1 sticky assignor user data.
+    pub fn serialize_topic_partition_assignment(member_data: &MemberData) -> Bytes {
+        let mut partitions_by_topic: IndexMap<&str, Vec<i32>> = IndexMap::new();
+        for tp in &member_data.partitions {
+            partitions_by_topic
+                .entry(tp.topic.as_str())
+                .or_default()
+                .push(tp.partition);
+        }
+
+        let mut buffer = BytesMut::new();
+        buffer.put_i32(partitions_by_topic.len() as i32);
+        for (topic, partitions) in partitions_by_topic {
+            buffer.put_i16(topic.len() as i16);
+            buffer.put_slice(topic.as_bytes());
+            buffer.put_i32(partitions.len() as i32);
+            for partition in partitions {
+                buffer.put_i32(partition);
+            }
+        }
+        buffer.put_i32(member_data.generation.unwrap_or(DEFAULT_GENERATION));
+        buffer.freeze()
+    }
+
+    /// Deserializes sticky assignor user data, falling back to the version 0 schema (without generation).
+    /// User data which cannot be parsed is ignored.
+    pub fn deserialize_topic_partition_assignment(buffer: &Bytes) -> MemberData {
+        let mut buffer = buffer.clone();
+        let partitions = match read_topic_partitions(&mut buffer) {
+            Some(partitions) => partitions,
+            // ignore the consumer's previous assignment if it cannot be parsed
+            None => return MemberData::new(vec![], Some(DEFAULT_GENERATION)),
+        };
+        // make sure this is backward compatible
+        let generation = if buffer.remaining() >= 4 {
+            Some(buffer.get_i32())
+        } else {
+            None
+        };
+        MemberData::new(partitions, generation)
+    }
+}
+
+fn read_topic_partitions(buffer: &mut Bytes) -> Option<Vec<TopicPartition>> {
+    let mut partitions = vec![];
+    let topic_count = read_i32(buffer)?;
+    for _ in 0..topic_count {
+        if buffer.remaining() < 2 {
+            return None;
+        }
+        let topic_length = buffer.get_i16();
+        if topic_length < 0 || buffer.remaining() < topic_length as usize {
+            return None;
+        }
+        let topic = String::from_utf8(buffer.split_to(topic_length as usize).to_vec()).ok()?;
+        let partition_count = read_i32(buffer)?;
+        for _ in 0..partition_count {
+            partitions.push(TopicPartition::new(topic.as_str(), read_i32(buffer)?));
+        }
+    }
+    Some(partitions)
+}
+
+fn read_i32(buffer: &mut Bytes) -> Option<i32> {
+    if buffer.remaining() < 4 {
+        None
+    } else {
+        Some(buffer.get_i32())
+    }
+}
+
+impl AbstractStickyAssignor for StickyAssignor {
+    fn member_data(&self, subscription: &Subscription) -> MemberData {
+        match &subscription.user_data {
+            Some(user_data) if !user_data.is_empty() => {
+                StickyAssignor::deserialize_topic_partition_assignment(user_data)
+            }
+            _ => MemberData::new(vec![], None),
+        }
+    }
+}
+
+impl AbstractPartitionAssignor for StickyAssignor {
+    fn assign_partitions(
+        &self,
+        partitions_per_topic: &IndexMap<String, usize>,
+        subscriptions: &IndexMap<String, Subscription>,
+    ) -> Result<IndexMap<String, Vec<TopicPartition>>> {
+        Ok(self
+            .sticky_assign(partitions_per_topic, subscriptions)?
+            .assignment)
+    }
+}
+
+impl ConsumerPartitionAssignor for StickyAssignor {
+    fn subscription_user_data(&self, _topics: &HashSet<String>) -> Option<Bytes> {
+        let state = self.lock();
+        state.member_assignment.as_ref().map(|member_assignment| {
+            StickyAssignor::serialize_topic_partition_assignment(&MemberData::new(
+                member_assignment.clone(),
+                Some(state.generation),
+            ))
+        })
+    }
+
+    fn assign(
+        &self,
+        metadata: &Cluster,
+        group_subscription: &GroupSubscription,
+    ) -> Result<GroupAssignment> {
+        self.assign_group(metadata, group_subscription)
+    }
+
+    fn on_assignment(&self, assignment: &Assignment, metadata: Option<&ConsumerGroupMetadata>) {
+        let mut state = self.lock();
+        state.member_assignment = Some(assignment.partitions.clone());
+        if let Some(metadata) = metadata {
+            state.generation = metadata.generation_id;
+        }
+    }
+
+    fn name(&self) -> &str {
+        STICKY_ASSIGNOR_NAME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::consumer::{
+            consumer_partition_assignor::Subscription,
+            internals::abstract_sticky_assignor::{
+                tests::{
+                    assert_balanced, assert_valid, assign, random_group, topics, Rng, LAYOUTS,
+                },
+                MemberData,
+            },
+        },
+        common::topic_partition::TopicPartition,
+    };
+
+    use super::StickyAssignor;
+
+    /// Subscriptions of `members` to all topics, carrying the partitions each owned in `previous`.
+    fn subscriptions(
+        topics: &[String],
+        members: &[String],
+        previous: &IndexMap<String, Vec<TopicPartition>>,
+        generation: i32,
+    ) -> IndexMap<String, Subscription> {
+        members
+            .iter()
+            .map(|member| {
+                let user_data = previous.get(member).map(|partitions| {
+                    StickyAssignor::serialize_topic_partition_assignment(&MemberData::new(
+                        partitions.clone(),
+                        Some(generation),
+                    ))
+                });
+                let subscription = Subscription::new(topics.to_vec(), user_data, vec![]);
+                (member.clone(), subscription)
+            })
+            .collect()
+    }
+
+    /// Number of partitions which moved from one of the previous owners to another member.
+    fn moved_partitions(
+        previous: &IndexMap<String, Vec<TopicPartition>>,
+        current: &IndexMap<String, Vec<TopicPartition>>,
+    ) -> usize {
+        previous
+            .iter()
+            .filter(|(member, _)| current.contains_key(*member))
+            .map(|(member, partitions)| {
+                let kept: HashSet<&TopicPartition> = current[member].iter().collect();
+                partitions.iter().filter(|tp| !kept.contains(tp)).count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn assignment_is_balanced() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, members) = random_group(&mut Rng::new(seed));
+            let subscriptions = subscriptions(
+                &topics(&partitions_per_topic),
+                &members,
+                &IndexMap::new(),
+                1,
+            );
+            let assignment = assign(
+                &StickyAssignor::new(),
+                &partitions_per_topic,
+                &subscriptions,
+            );
+            assert_valid(&assignment, &subscriptions);
+            assert_balanced(&assignment, &partitions_per_topic);
+        }
+    }
+
+    #[test]
+    fn adding_a_member_only_moves_partitions_to_it() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, mut members) = random_group(&mut Rng::new(seed));
+            let topics = topics(&partitions_per_topic);
+            let assignor = StickyAssignor::new();
+            let previous = assign(
+                &assignor,
+                &partitions_per_topic,
+                &subscriptions(&topics, &members, &IndexMap::new(), 1),
+            );
+
+            members.push("new-consumer".to_owned());
+            let subscriptions = subscriptions(&topics, &members, &previous, 1);
+            let assignment = assign(&assignor, &partitions_per_topic, &subscriptions);
+            assert_valid(&assignment, &subscriptions);
+            assert_balanced(&assignment, &partitions_per_topic);
+            // the existing members only give up partitions, and only those the new member takes
+            for (member, partitions) in &previous {
+                assert!(
+                    assignment[member].iter().all(|tp| partitions.contains(tp)),
+                    "seed {}: {} got partitions it didn't own before",
+                    seed,
+                    member
+                );
+            }
+            assert_eq!(
+                moved_partitions(&previous, &assignment),
+                assignment["new-consumer"].len(),
+                "seed {}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn removing_a_member_keeps_the_other_assignments() {
+        for seed in 0..LAYOUTS {
+            let (partitions_per_topic, mut members) = random_group(&mut Rng::new(seed));
+            if members.len() < 2 {
+                continue;
+            }
+            let topics = topics(&partitions_per_topic);
+            let assignor = StickyAssignor::new();
+            let previous = assign(
+                &assignor,
+                &partitions_per_topic,
+                &subscriptions(&topics, &members, &IndexMap::new(), 1),
+            );
+
+            members.remove(0);
+            let subscriptions = subscriptions(&topics, &members, &previous, 1);
+            let assignment = assign(&assignor, &partitions_per_topic, &subscriptions);
+            assert_valid(&assignment, &subscriptions);
+            assert_balanced(&assignment, &partitions_per_topic);
+            assert_eq!(moved_partitions(&previous, &assignment), 0, "seed {}", seed);
+        }
+    }
+}
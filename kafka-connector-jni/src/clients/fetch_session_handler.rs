@@ -0,0 +1,591 @@
+use std::fmt::{self, Display};
+
+use indexmap::IndexMap;
+use log::{debug, info, log_enabled, Level};
+
+use crate::common::{
+    errors::KafkaError,
+    protocol::errors::Errors,
+    requests::{
+        fetch_metadata::{FetchMetadata, INVALID_SESSION_ID},
+        fetch_request::PartitionData,
+        fetch_response::FetchResponse,
+    },
+    topic_partition::TopicPartition,
+};
+
+/// FetchSessionHandler maintains the fetch session state for connecting to a broker.
+///
+/// Using the protocol outlined by KIP-227, clients can create incremental fetch sessions.
+/// These sessions allow the client to fetch information about a set of partition over
+/// and over, without explicitly enumerating all the partitions in the request and the
+/// response.
+///
+/// FetchSessionHandler tracks the partitions which are in the session. It also
+/// determines which partitions need to be included in each fetch request, and what
+/// the attached fetch session metadata should be for each request. The corresponding
+/// class on the receiving broker side is FetchManager.
+pub struct FetchSessionHandler {
+    node: i32,
+    /// The metadata for the next fetch request.
+    next_metadata: FetchMetadata,
+    /// All of the partitions which exist in the fetch request session.
+    session_partitions: IndexMap<TopicPartition, PartitionData>,
+}
+
+/// Partitions and session metadata of a fetch request prepared by `FetchSessionHandler`.
+#[derive(Debug, Clone)]
+pub struct FetchRequestData {
+    /// The partitions to send in the fetch request.
+    pub to_send: IndexMap<TopicPartition, PartitionData>,
+    /// The partitions to send in the request's "forget" list.
+    pub to_forget: Vec<TopicPartition>,
+    /// All of the partitions which exist in the fetch request session.
+    pub session_partitions: IndexMap<TopicPartition, PartitionData>,
+    /// The metadata to use in this fetch request.
+    pub metadata: FetchMetadata,
+}
+
+impl Display for FetchRequestData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.metadata.is_full() {
+            write!(f, "FullFetchRequest({})", join(self.to_send.keys()))
+        } else {
+            write!(
+                f,
+                "IncrementalFetchRequest(toSend=({}), toForget=({}), implied=({}))",
+                join(self.to_send.keys()),
+                join(self.to_forget.iter()),
+                join(
+                    self.session_partitions
+                        .keys()
+                        .filter(|partition| !self.to_send.contains_key(*partition))
+                )
+            )
+        }
+    }
+}
+
+/// Collects the partitions which should be fetched in the upcoming request.
+#[derive(Debug, Default)]
+pub struct Builder {
+    /// The next partitions which we want to fetch.
+    ///
+    /// It is important to maintain the insertion order of this list by using an IndexMap rather
+    /// than a regular HashMap.
+    ///
+    /// One reason is that when dealing with FULL fetch requests, if there is not enough response
+    /// space to return data from all partitions, the server will only return data from partitions
+    /// early in this list.
+    ///
+    /// Another reason is because we make use of the list ordering to optimize the preparation of
+    /// incremental fetch requests (see below).
+    next: IndexMap<TopicPartition, PartitionData>,
+}
+
+impl Builder {
+    /// Mark that we want data from this partition in the upcoming fetch.
+    pub fn add(&mut self, topic_partition: TopicPartition, data: PartitionData) {
+        self.next.insert(topic_partition, data);
+    }
+}
+
+impl FetchSessionHandler {
+    pub fn new(node: i32) -> FetchSessionHandler {
+        FetchSessionHandler {
+            node,
+            next_metadata: FetchMetadata::INITIAL,
+            session_partitions: IndexMap::new(),
+        }
+    }
+
+    pub fn new_builder(&self) -> Builder {
+        Builder::default()
+    }
+
+    /// The partitions which exist in the fetch request session.
+    pub fn session_partitions(&self) -> &IndexMap<TopicPartition, PartitionData> {
+        &self.session_partitions
+    }
+
+    /// Prepare the next fetch request from the partitions collected by `builder`.
+    pub fn build(&mut self, builder: Builder) -> FetchRequestData {
+        let mut next = builder.next;
+        if self.next_metadata.is_full() {
+            debug!(
+                "Built full fetch {} for node {} with {}.",
+                self.next_metadata,
+                self.node,
+                partitions_to_log_string(next.keys())
+            );
+            self.session_partitions = next;
+            let to_send = self.session_partitions.clone();
+            return FetchRequestData {
+                session_partitions: to_send.clone(),
+                to_send,
+                to_forget: vec![],
+                metadata: self.next_metadata,
+            };
+        }
+
+        let mut added = vec![];
+        let mut removed = vec![];
+        let mut altered = vec![];
+        let mut retained = IndexMap::with_capacity(self.session_partitions.len());
+        for (topic_partition, prev_data) in self.session_partitions.drain(..) {
+            match next.shift_remove(&topic_partition) {
+                Some(next_data) => {
+                    if prev_data != next_data {
+                        // Re-add the altered partition to the end of 'next'
+                        next.insert(topic_partition.clone(), next_data.clone());
+                        altered.push(topic_partition.clone());
+                        retained.insert(topic_partition, next_data);
+                    } else {
+                        retained.insert(topic_partition, prev_data);
+                    }
+                }
+                None => {
+                    // Indicate that we no longer want to listen to this partition.
+                    removed.push(topic_partition);
+                }
+            }
+        }
+        self.session_partitions = retained;
+        // Add any new partitions to the session.
+        for (topic_partition, next_data) in next.iter() {
+            if self.session_partitions.contains_key(topic_partition) {
+                // In the previous loop, all the partitions which existed in both session_partitions
+                // and next were moved to the end of next, or removed from next. Therefore,
+                // once we hit one of them, we know there are no more unseen entries to look
+                // at in next.
+                break;
+            }
+            self.session_partitions
+                .insert(topic_partition.clone(), next_data.clone());
+            added.push(topic_partition.clone());
+        }
+        debug!(
+            "Built incremental fetch {} for node {}. Added {}, altered {}, removed {} out of {}",
+            self.next_metadata,
+            self.node,
+            partitions_to_log_string(added.iter()),
+            partitions_to_log_string(altered.iter()),
+            partitions_to_log_string(removed.iter()),
+            partitions_to_log_string(self.session_partitions.keys())
+        );
+        FetchRequestData {
+            to_send: next,
+            to_forget: removed,
+            session_partitions: self.session_partitions.clone(),
+            metadata: self.next_metadata,
+        }
+    }
+
+    /// Verify that a full fetch response contains all the partitions in the fetch session.
+    fn verify_full_fetch_response_partitions(&self, response: &FetchResponse) -> Option<String> {
+        let extra = find_missing(response.response_data.keys(), |partition| {
+            self.session_partitions.contains_key(partition)
+        });
+        let omitted = find_missing(self.session_partitions.keys(), |partition| {
+            response.response_data.contains_key(partition)
+        });
+        if omitted.is_empty() && extra.is_empty() {
+            return None;
+        }
+        let mut problem = String::new();
+        if !omitted.is_empty() {
+            problem.push_str(&format!("omitted=({}, ", join(omitted.iter())));
+        }
+        if !extra.is_empty() {
+            problem.push_str(&format!("extra=({}, ", join(extra.iter())));
+        }
+        problem.push_str(&format!(
+            "response=({})",
+            join(response.response_data.keys())
+        ));
+        Some(problem)
+    }
+
+    /// Verify that the partitions in an incremental fetch response are contained in the session.
+    fn verify_incremental_fetch_response_partitions(
+        &self,
+        response: &FetchResponse,
+    ) -> Option<String> {
+        let extra = find_missing(response.response_data.keys(), |partition| {
+            self.session_partitions.contains_key(partition)
+        });
+        if extra.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "extra=({}), response=({}), ",
+            join(extra.iter()),
+            join(response.response_data.keys())
+        ))
+    }
+
+    /// Create a string describing the partitions in a FetchResponse.
+    fn response_data_to_log_string(&self, response: &FetchResponse) -> String {
+        if !log_enabled!(Level::Trace) {
+            let implied =
+                self.session_partitions.len() as i64 - response.response_data.len() as i64;
+            return if implied > 0 {
+                format!(
+                    " with {} response partition(s), {} implied partition(s)",
+                    response.response_data.len(),
+                    implied
+                )
+            } else {
+                format!(
+                    " with {} response partition(s)",
+                    response.response_data.len()
+                )
+            };
+        }
+        let mut log_string = format!(" with response=({})", join(response.response_data.keys()));
+        let implied = find_missing(self.session_partitions.keys(), |partition| {
+            response.response_data.contains_key(partition)
+        });
+        if !implied.is_empty() {
+            log_string.push_str(&format!(", implied=({})", join(implied.iter())));
+        }
+        log_string
+    }
+
+    /// Handle the fetch response.
+    ///
+    /// Returns true if the response is well-formed; false if it can't be processed
+    /// because of missing or unexpected partitions.
+    pub fn handle_response(&mut self, response: &FetchResponse) -> bool {
+        if response.error != Errors::None {
+            info!(
+                "Node {} was unable to process the fetch request with {}: {}.",
+                self.node,
+                self.next_metadata,
+                response.error.name()
+            );
+            self.next_metadata = if response.error == Errors::FetchSessionIdNotFound {
+                FetchMetadata::INITIAL
+            } else {
+                self.next_metadata.next_close_existing()
+            };
+            return false;
+        }
+        if self.next_metadata.is_full() {
+            if response.response_data.is_empty() && response.throttle_time_ms > 0 {
+                // Normally, an empty full fetch response would be invalid. However, KIP-219
+                // specifies that if the broker wants to throttle the client, it will respond
+                // to a full fetch request with an empty response and a throttle_time_ms
+                // value set. We don't want to log this with a warning, since it's not an error.
+                // However, the empty full fetch response can't be processed, so it's still appropriate
+                // to return false here.
+                debug!(
+                    "Node {} sent a empty full fetch response to indicate that this client should be throttled for {} ms.",
+                    self.node, response.throttle_time_ms
+                );
+                self.next_metadata = FetchMetadata::INITIAL;
+                return false;
+            }
+            if let Some(problem) = self.verify_full_fetch_response_partitions(response) {
+                info!(
+                    "Node {} sent an invalid full fetch response with {}",
+                    self.node, problem
+                );
+                self.next_metadata = FetchMetadata::INITIAL;
+                false
+            } else if response.session_id == INVALID_SESSION_ID {
+                debug!(
+                    "Node {} sent a full fetch response{}",
+                    self.node,
+                    self.response_data_to_log_string(response)
+                );
+                self.next_metadata = FetchMetadata::INITIAL;
+                true
+            } else {
+                // The server created a new incremental fetch session.
+                debug!(
+                    "Node {} sent a full fetch response that created a new incremental fetch session {}{}",
+                    self.node,
+                    response.session_id,
+                    self.response_data_to_log_string(response)
+                );
+                self.next_metadata = FetchMetadata::new_incremental(response.session_id);
+                true
+            }
+        } else if let Some(problem) = self.verify_incremental_fetch_response_partitions(response) {
+            info!(
+                "Node {} sent an invalid incremental fetch response with {}",
+                self.node, problem
+            );
+            self.next_metadata = self.next_metadata.next_close_existing();
+            false
+        } else if response.session_id == INVALID_SESSION_ID {
+            // The incremental fetch session was closed by the server.
+            debug!(
+                "Node {} sent an incremental fetch response closing session {}{}",
+                self.node,
+                self.next_metadata.session_id,
+                self.response_data_to_log_string(response)
+            );
+            self.next_metadata = FetchMetadata::INITIAL;
+            true
+        } else {
+            // The incremental fetch session was continued by the server.
+            // We don't have to do anything special here to support KIP-219, since an empty incremental
+            // fetch request is perfectly valid.
+            debug!(
+                "Node {} sent an incremental fetch response with throttleTimeMs = {} for session {}{}",
+                self.node,
+                response.throttle_time_ms,
+                response.session_id,
+                self.response_data_to_log_string(response)
+            );
+            self.next_metadata = self.next_metadata.next_incremental();
+            true
+        }
+    }
+
+    /// Handle an error sending the prepared request.
+    ///
+    /// When a network error occurs, we close any existing fetch session on our next request,
+    /// and try to create a new session.
+    pub fn handle_error(&mut self, error: &KafkaError) {
+        info!(
+            "Error sending fetch request {} to node {}: {}",
+            self.next_metadata, self.node, error
+        );
+        self.next_metadata = self.next_metadata.next_close_existing();
+    }
+}
+
+/// Return the partitions from `to_find` which are not contained in the searched set.
+fn find_missing<'a>(
+    to_find: impl Iterator<Item = &'a TopicPartition>,
+    contains: impl Fn(&TopicPartition) -> bool,
+) -> Vec<TopicPartition> {
+    to_find
+        .filter(|partition| !contains(partition))
+        .cloned()
+        .collect()
+}
+
+fn partitions_to_log_string<'a>(
+    partitions: impl ExactSizeIterator<Item = &'a TopicPartition>,
+) -> String {
+    if !log_enabled!(Level::Trace) {
+        return format!("{} partition(s)", partitions.len());
+    }
+    format!("({})", join(partitions))
+}
+
+fn join<'a>(partitions: impl Iterator<Item = &'a TopicPartition>) -> String {
+    partitions
+        .map(|partition| partition.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use crate::common::{
+        errors::KafkaError,
+        protocol::errors::Errors,
+        requests::{
+            fetch_metadata::{FetchMetadata, INVALID_SESSION_ID},
+            fetch_request::PartitionData,
+            fetch_response::{self, FetchResponse},
+        },
+        topic_partition::TopicPartition,
+    };
+
+    use super::{Builder, FetchRequestData, FetchSessionHandler};
+
+    const SESSION_ID: i32 = 123;
+
+    fn tp(topic: &str, partition: i32) -> TopicPartition {
+        TopicPartition::new(topic, partition)
+    }
+
+    fn data(fetch_offset: i64) -> PartitionData {
+        PartitionData::new(fetch_offset, 0, 100, None)
+    }
+
+    fn build(
+        handler: &mut FetchSessionHandler,
+        partitions: &[(&TopicPartition, i64)],
+    ) -> FetchRequestData {
+        let mut builder: Builder = handler.new_builder();
+        for (topic_partition, fetch_offset) in partitions {
+            builder.add((*topic_partition).clone(), data(*fetch_offset));
+        }
+        handler.build(builder)
+    }
+
+    fn response(error: Errors, session_id: i32, partitions: &[&TopicPartition]) -> FetchResponse {
+        FetchResponse::new(
+            error,
+            session_id,
+            partitions
+                .iter()
+                .map(|topic_partition| {
+                    (
+                        (*topic_partition).clone(),
+                        fetch_response::PartitionData::from_error(Errors::None),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn keys(partitions: &IndexMap<TopicPartition, PartitionData>) -> Vec<TopicPartition> {
+        partitions.keys().cloned().collect()
+    }
+
+    #[test]
+    fn sessionless_fetches_are_always_full() {
+        let (foo0, foo1) = (tp("foo", 0), tp("foo", 1));
+        let mut handler = FetchSessionHandler::new(1);
+        let request = build(&mut handler, &[(&foo0, 0), (&foo1, 10)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+        assert_eq!(keys(&request.to_send), vec![foo0.clone(), foo1.clone()]);
+        assert!(request.to_forget.is_empty());
+        assert!(handler.handle_response(&response(
+            Errors::None,
+            INVALID_SESSION_ID,
+            &[&foo0, &foo1]
+        )));
+
+        let request = build(&mut handler, &[(&foo0, 5)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+        assert_eq!(keys(&request.to_send), vec![foo0]);
+        assert!(request.to_forget.is_empty());
+    }
+
+    #[test]
+    fn incremental_fetches_send_added_altered_and_removed_partitions() {
+        let (foo0, foo1, bar0) = (tp("foo", 0), tp("foo", 1), tp("bar", 0));
+        let mut handler = FetchSessionHandler::new(1);
+        let request = build(&mut handler, &[(&foo0, 0), (&foo1, 10)]);
+        assert!(request.metadata.is_full());
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo0, &foo1])));
+
+        // nothing changed, every partition is implied
+        let request = build(&mut handler, &[(&foo0, 0), (&foo1, 10)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 1));
+        assert!(request.to_send.is_empty());
+        assert!(request.to_forget.is_empty());
+        assert_eq!(
+            keys(&request.session_partitions),
+            vec![foo0.clone(), foo1.clone()]
+        );
+        assert!(request.to_string().starts_with("IncrementalFetchRequest("));
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[])));
+
+        // foo0 is altered, foo1 removed and bar0 added
+        let request = build(&mut handler, &[(&foo0, 20), (&bar0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 2));
+        assert_eq!(keys(&request.to_send), vec![bar0.clone(), foo0.clone()]);
+        assert_eq!(request.to_send[&foo0].fetch_offset, 20);
+        assert_eq!(request.to_forget, vec![foo1.clone()]);
+        assert_eq!(
+            keys(&request.session_partitions),
+            vec![foo0.clone(), bar0.clone()]
+        );
+        assert_eq!(handler.session_partitions()[&foo0].fetch_offset, 20);
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[&bar0])));
+
+        // a response with partitions outside of the session is invalid
+        let request = build(&mut handler, &[(&foo0, 20), (&bar0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 3));
+        assert!(!handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo1])));
+        let request = build(&mut handler, &[(&foo0, 20), (&bar0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 0));
+        assert_eq!(keys(&request.to_send), vec![foo0, bar0]);
+    }
+
+    #[test]
+    fn session_closed_by_the_server_starts_over() {
+        let foo0 = tp("foo", 0);
+        let mut handler = FetchSessionHandler::new(1);
+        build(&mut handler, &[(&foo0, 0)]);
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo0])));
+        let request = build(&mut handler, &[(&foo0, 0)]);
+        assert_eq!(request.metadata.session_id, SESSION_ID);
+
+        assert!(handler.handle_response(&response(Errors::None, INVALID_SESSION_ID, &[])));
+        let request = build(&mut handler, &[(&foo0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+        assert_eq!(keys(&request.to_send), vec![foo0]);
+    }
+
+    #[test]
+    fn fetch_session_id_not_found_resets_the_session() {
+        let foo0 = tp("foo", 0);
+        let mut handler = FetchSessionHandler::new(1);
+        build(&mut handler, &[(&foo0, 0)]);
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo0])));
+        build(&mut handler, &[(&foo0, 0)]);
+
+        assert!(!handler.handle_response(&response(
+            Errors::FetchSessionIdNotFound,
+            INVALID_SESSION_ID,
+            &[]
+        )));
+        let request = build(&mut handler, &[(&foo0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+        assert_eq!(keys(&request.to_send), vec![foo0]);
+    }
+
+    #[test]
+    fn other_errors_close_the_existing_session() {
+        let foo0 = tp("foo", 0);
+        let mut handler = FetchSessionHandler::new(1);
+        build(&mut handler, &[(&foo0, 0)]);
+        assert!(handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo0])));
+        build(&mut handler, &[(&foo0, 0)]);
+
+        assert!(!handler.handle_response(&response(
+            Errors::InvalidFetchSessionEpoch,
+            INVALID_SESSION_ID,
+            &[]
+        )));
+        let request = build(&mut handler, &[(&foo0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 0));
+        assert!(request.metadata.is_full());
+
+        handler.handle_error(&KafkaError::Network("disconnected".to_owned()));
+        let request = build(&mut handler, &[(&foo0, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::new(SESSION_ID, 0));
+    }
+
+    #[test]
+    fn invalid_full_responses_are_rejected() {
+        let (foo0, foo1) = (tp("foo", 0), tp("foo", 1));
+        let mut handler = FetchSessionHandler::new(1);
+        build(&mut handler, &[(&foo0, 0), (&foo1, 0)]);
+        // foo1 is omitted
+        assert!(!handler.handle_response(&response(Errors::None, SESSION_ID, &[&foo0])));
+        let request = build(&mut handler, &[(&foo0, 0), (&foo1, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+
+        // an empty throttled response is not processed either
+        let mut throttled = response(Errors::None, SESSION_ID, &[]);
+        throttled.throttle_time_ms = 100;
+        assert!(!handler.handle_response(&throttled));
+        let request = build(&mut handler, &[(&foo0, 0), (&foo1, 0)]);
+        assert_eq!(request.metadata, FetchMetadata::INITIAL);
+    }
+
+    #[test]
+    fn session_epoch_wraps_to_one() {
+        assert_eq!(FetchMetadata::next_epoch(0), 1);
+        assert_eq!(FetchMetadata::next_epoch(i32::MAX), 1);
+        assert_eq!(FetchMetadata::next_epoch(-1), -1);
+        assert_eq!(
+            FetchMetadata::new(SESSION_ID, i32::MAX).next_incremental(),
+            FetchMetadata::new(SESSION_ID, 1)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use crate::common::{errors::Result, requests::join_group_request::validate_group_instance_id};
+
+/// Class to extract group rebalance related configs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupRebalanceConfig {
+    pub session_timeout_ms: u128,
+    pub rebalance_timeout_ms: u128,
+    pub heartbeat_interval_ms: u128,
+    pub group_id: String,
+    pub group_instance_id: Option<String>,
+    pub retry_backoff_ms: u128,
+    pub leave_group_on_close: bool,
+}
+
+impl GroupRebalanceConfig {
+    /// Fails with `InvalidConfiguration` if `group_instance_id` isn't a legal group instance id.
+    pub fn new(
+        session_timeout_ms: u128,
+        rebalance_timeout_ms: u128,
+        heartbeat_interval_ms: u128,
+        group_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        retry_backoff_ms: u128,
+        leave_group_on_close: bool,
+    ) -> Result<GroupRebalanceConfig> {
+        if let Some(group_instance_id) = &group_instance_id {
+            validate_group_instance_id(group_instance_id)?;
+        }
+        Ok(GroupRebalanceConfig {
+            session_timeout_ms,
+            rebalance_timeout_ms,
+            heartbeat_interval_ms,
+            group_id: group_id.into(),
+            group_instance_id,
+            retry_backoff_ms,
+            leave_group_on_close,
+        })
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::common::{
+    errors::KafkaError,
+    requests::{abstract_response::AbstractResponse, request_header::RequestHeader},
+};
+
+use super::{client_request::RequestCompletionHandler, client_response::ClientResponse};
+
+/// A request sent by the network client, awaiting its response.
+pub struct InFlightRequest {
+    pub header: RequestHeader,
+    pub destination: String,
+    pub callback: Option<RequestCompletionHandler>,
+    pub expect_response: bool,
+    /// Whether the request was sent by the network client itself, e.g. for metadata or api versions.
+    pub is_internal_request: bool,
+    pub send_completed: bool,
+    pub created_time_ms: u128,
+    pub send_time_ms: u128,
+    pub request_timeout_ms: u128,
+}
+
+impl InFlightRequest {
+    /// The response to complete this request with, together with the handler to invoke.
+    pub fn completed(
+        self,
+        response: Option<AbstractResponse>,
+        time_ms: u128,
+    ) -> (ClientResponse, Option<RequestCompletionHandler>) {
+        (
+            ClientResponse::new(
+                self.header,
+                self.destination,
+                self.created_time_ms,
+                time_ms,
+                false,
+                None,
+                response,
+            ),
+            self.callback,
+        )
+    }
+
+    pub fn disconnected(
+        self,
+        time_ms: u128,
+        error: Option<KafkaError>,
+    ) -> (ClientResponse, Option<RequestCompletionHandler>) {
+        (
+            ClientResponse::new(
+                self.header,
+                self.destination,
+                self.created_time_ms,
+                time_ms,
+                true,
+                error,
+                None,
+            ),
+            self.callback,
+        )
+    }
+}
+
+/// The set of requests which have been sent or are being sent but haven't yet received a response
+pub struct InFlightRequests {
+    max_in_flight_requests_per_connection: usize,
+    requests: HashMap<String, VecDeque<InFlightRequest>>,
+    in_flight_request_count: usize,
+}
+
+impl InFlightRequests {
+    pub fn new(max_in_flight_requests_per_connection: usize) -> InFlightRequests {
+        InFlightRequests {
+            max_in_flight_requests_per_connection,
+            requests: HashMap::new(),
+            in_flight_request_count: 0,
+        }
+    }
+
+    /// Add the given request to the queue for the connection it was directed to
+    pub fn add(&mut self, request: InFlightRequest) {
+        self.requests
+            .entry(request.destination.clone())
+            .or_default()
+            .push_back(request);
+        self.in_flight_request_count += 1;
+    }
+
+    /// Get the oldest request (the one that will be completed next) for the given node
+    pub fn complete_next(&mut self, node: &str) -> Option<InFlightRequest> {
+        let request = self.requests.get_mut(node)?.pop_front()?;
+        self.in_flight_request_count -= 1;
+        Some(request)
+    }
+
+    /// Get the last request we sent to the given node (but don't remove it from the queue)
+    pub fn last_sent(&mut self, node: &str) -> Option<&mut InFlightRequest> {
+        self.requests.get_mut(node)?.back_mut()
+    }
+
+    /// Complete the last request that was sent to a particular node.
+    pub fn complete_last_sent(&mut self, node: &str) -> Option<InFlightRequest> {
+        let request = self.requests.get_mut(node)?.pop_back()?;
+        self.in_flight_request_count -= 1;
+        Some(request)
+    }
+
+    /// Can we send more requests to this node?
+    pub fn can_send_more(&self, node: &str) -> bool {
+        match self.requests.get(node) {
+            None => true,
+            Some(queue) => match queue.back() {
+                None => true,
+                Some(last) => {
+                    last.send_completed && queue.len() < self.max_in_flight_requests_per_connection
+                }
+            },
+        }
+    }
+
+    /// Return the number of in-flight requests directed at the given node
+    pub fn count(&self, node: &str) -> usize {
+        self.requests.get(node).map_or(0, VecDeque::len)
+    }
+
+    /// Return true if there is no in-flight request directed at the given node and false otherwise
+    pub fn is_empty(&self, node: &str) -> bool {
+        self.count(node) == 0
+    }
+
+    /// Count all in-flight requests for all nodes. This method is O(1).
+    pub fn total(&self) -> usize {
+        self.in_flight_request_count
+    }
+
+    /// Clear out all the in-flight requests for the given node and return them, oldest first
+    pub fn clear_all(&mut self, node: &str) -> Vec<InFlightRequest> {
+        match self.requests.remove(node) {
+            Some(requests) => {
+                self.in_flight_request_count -= requests.len();
+                requests.into_iter().collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Returns the nodes which have a request whose response did not arrive in time.
+    pub fn nodes_with_timed_out_requests(&self, now: u128) -> Vec<String> {
+        self.requests
+            .iter()
+            .filter(|(_, requests)| {
+                requests.iter().any(|request| {
+                    now.saturating_sub(request.send_time_ms) > request.request_timeout_ms
+                })
+            })
+            .map(|(node, _)| node.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{protocol::api_keys::ApiKeys, requests::request_header::RequestHeader};
+
+    use super::{InFlightRequest, InFlightRequests};
+
+    const DEST: &str = "dest";
+
+    fn add_request(
+        requests: &mut InFlightRequests,
+        correlation_id: i32,
+        send_time_ms: u128,
+        request_timeout_ms: u128,
+    ) {
+        requests.add(InFlightRequest {
+            header: RequestHeader::new(ApiKeys::Metadata, 8, "clientId", correlation_id),
+            destination: DEST.to_owned(),
+            callback: None,
+            expect_response: true,
+            is_internal_request: false,
+            send_completed: false,
+            created_time_ms: send_time_ms,
+            send_time_ms,
+            request_timeout_ms,
+        });
+    }
+
+    #[test]
+    fn complete_next_returns_the_oldest_request() {
+        let mut requests = InFlightRequests::new(5);
+        add_request(&mut requests, 1, 0, 100);
+        add_request(&mut requests, 2, 0, 100);
+        assert_eq!(requests.total(), 2);
+        assert_eq!(requests.count(DEST), 2);
+        assert_eq!(
+            requests.complete_next(DEST).unwrap().header.correlation_id,
+            1
+        );
+        assert_eq!(
+            requests
+                .complete_last_sent(DEST)
+                .unwrap()
+                .header
+                .correlation_id,
+            2
+        );
+        assert!(requests.is_empty(DEST));
+        assert!(requests.complete_next(DEST).is_none());
+        assert_eq!(requests.total(), 0);
+    }
+
+    #[test]
+    fn can_send_more_once_the_last_send_completed() {
+        let mut requests = InFlightRequests::new(2);
+        assert!(requests.can_send_more(DEST));
+        add_request(&mut requests, 1, 0, 100);
+        assert!(!requests.can_send_more(DEST));
+        requests.last_sent(DEST).unwrap().send_completed = true;
+        assert!(requests.can_send_more(DEST));
+        add_request(&mut requests, 2, 0, 100);
+        requests.last_sent(DEST).unwrap().send_completed = true;
+        assert!(!requests.can_send_more(DEST));
+    }
+
+    #[test]
+    fn timed_out_requests_and_clear_all() {
+        let mut requests = InFlightRequests::new(5);
+        add_request(&mut requests, 1, 0, 100);
+        add_request(&mut requests, 2, 50, 100);
+        assert!(requests.nodes_with_timed_out_requests(100).is_empty());
+        assert_eq!(requests.nodes_with_timed_out_requests(101), vec![DEST]);
+
+        let cleared = requests.clear_all(DEST);
+        assert_eq!(
+            cleared
+                .iter()
+                .map(|r| r.header.correlation_id)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(requests.total(), 0);
+        assert!(requests.clear_all(DEST).is_empty());
+    }
+}
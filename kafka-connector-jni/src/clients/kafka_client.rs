@@ -0,0 +1,109 @@
+use crate::common::{node::Node, requests::abstract_request::AbstractRequest};
+
+use super::{
+    client_request::{ClientRequest, RequestCompletionHandler},
+    client_response::ClientResponse,
+};
+
+/// The interface for the network client used by the producer and consumer internals.
+///
+/// Implementations are shared between the application and background threads, so all methods
+/// take `&self` and rely on interior synchronization.
+pub trait KafkaClient: Send + Sync {
+    /// Check if we are currently ready to send another request to the given node but don't attempt to connect if we
+    /// aren't.
+    fn is_ready(&self, node: &Node, now: u128) -> bool;
+
+    /// Initiate a connection to the given node (if necessary), and return true if already connected. The readiness of a
+    /// node will change only when poll is invoked.
+    fn ready(&self, node: &Node, now: u128) -> bool;
+
+    /// Return the number of milliseconds to wait, based on the connection state, before attempting to send data. When
+    /// disconnected, this respects the reconnect backoff time. When connecting or connected, this handles slow/stalled
+    /// connections.
+    fn connection_delay(&self, node: &Node, now: u128) -> u128;
+
+    /// Return the number of milliseconds to wait, based on the connection state and the throttle time, before
+    /// attempting to send data. If the connection has been established but being throttled, return throttle delay.
+    /// Otherwise, return connection delay.
+    fn poll_delay_ms(&self, node: &Node, now: u128) -> u128;
+
+    /// Check if the connection of the node has failed, based on the connection state. Such connection failure are
+    /// usually transient and can be resumed in the next `ready` call, but there are cases where transient failures
+    /// needs to be caught and re-acted upon.
+    fn connection_failed(&self, node: &Node) -> bool;
+
+    /// Queue up the given request for sending. Requests can only be sent on ready connections.
+    fn send(&self, request: ClientRequest, now: u128);
+
+    /// Do actual reads and writes from sockets. Completion handlers of finished requests are invoked
+    /// before this method returns.
+    ///
+    /// Returns the list of responses received.
+    fn poll(&self, timeout: u128, now: u128) -> Vec<ClientResponse>;
+
+    /// Disconnects the connection to a particular node, if there is one.
+    /// Any pending ClientRequests for this connection will receive disconnections.
+    fn disconnect(&self, node_id: &str);
+
+    /// Choose the node with the fewest outstanding requests. This method will prefer a node with an existing connection,
+    /// but will potentially choose a node for which we don't yet have a connection if all existing connections are in
+    /// use.
+    fn least_loaded_node(&self, now: u128) -> Option<Node>;
+
+    /// The number of currently in-flight requests for which we have not yet returned a response
+    fn in_flight_request_count(&self) -> usize;
+
+    /// Return true if there is at least one in-flight request and false otherwise.
+    fn has_in_flight_requests(&self) -> bool {
+        self.in_flight_request_count() > 0
+    }
+
+    /// Get the total in-flight requests for a particular node
+    fn in_flight_request_count_for(&self, node_id: &str) -> usize;
+
+    /// Return true if there is at least one in-flight request for a particular node and false otherwise.
+    fn has_in_flight_requests_for(&self, node_id: &str) -> bool {
+        self.in_flight_request_count_for(node_id) > 0
+    }
+
+    /// Return true if there is at least one node with connection in the READY state and not throttled. Returns false
+    /// otherwise.
+    fn has_ready_nodes(&self, now: u128) -> bool;
+
+    /// Wake up the client if it is currently blocked waiting for I/O
+    fn wakeup(&self);
+
+    /// Create a new ClientRequest.
+    ///
+    /// * `node_id` - the node to send to
+    /// * `request` - the request to send
+    /// * `created_time_ms` - the time in milliseconds to use as the creation time of the request
+    /// * `expect_response` - true iff we expect a response
+    /// * `request_timeout_ms` - Upper bound time in milliseconds to await a response before disconnecting the socket and
+    ///   cancelling the request. The request may get cancelled sooner if the socket disconnects for any reason
+    ///   including if another pending request to the same node timed out first.
+    /// * `callback` - the callback to invoke when we get a response
+    fn new_client_request(
+        &self,
+        node_id: &str,
+        request: AbstractRequest,
+        created_time_ms: u128,
+        expect_response: bool,
+        request_timeout_ms: u128,
+        callback: Option<RequestCompletionHandler>,
+    ) -> ClientRequest;
+
+    /// Initiates shutdown of this client. This method may be invoked from another thread while this
+    /// client is being polled. No further requests may be sent using the client. The current poll()
+    /// will be terminated using wakeup(). The client should be explicitly shutdown using `close`
+    /// after poll returns.
+    fn initiate_close(&self);
+
+    /// Returns true if the client is still active. Returns false if `initiate_close` or `close`
+    /// was invoked for this client.
+    fn active(&self) -> bool;
+
+    /// Close the client and release all resources
+    fn close(&self);
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::common::{
+    cluster::Cluster,
+    errors::{KafkaError, Result},
+    node::Node,
+    topic_partition::TopicPartition,
+};
+
+/// A class encapsulating some of the logic around metadata.
+///
+/// This class is shared by the client thread (for partitioning) and the background sender thread.
+///
+/// Metadata is maintained for only a subset of topics, which can be added to over time. When we
+/// request metadata for a topic we don't have any metadata for it will trigger a metadata update.
+pub struct Metadata {
+    refresh_backoff_ms: u128,
+    metadata_expire_ms: u128,
+    state: Mutex<MetadataState>,
+}
+
+struct MetadataState {
+    update_version: i32,
+    request_version: i32,
+    last_refresh_ms: u128,
+    last_successful_refresh_ms: u128,
+    need_full_update: bool,
+    need_partial_update: bool,
+    cluster: Arc<Cluster>,
+    last_seen_leader_epochs: HashMap<TopicPartition, i32>,
+    fatal_error: Option<KafkaError>,
+    is_closed: bool,
+}
+
+/// Leader of a partition together with the leader epoch, if known.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LeaderAndEpoch {
+    pub leader: Option<Node>,
+    pub epoch: Option<i32>,
+}
+
+impl LeaderAndEpoch {
+    pub fn new(leader: Option<Node>, epoch: Option<i32>) -> LeaderAndEpoch {
+        LeaderAndEpoch { leader, epoch }
+    }
+
+    pub fn no_leader_or_epoch() -> LeaderAndEpoch {
+        LeaderAndEpoch::default()
+    }
+}
+
+impl Display for LeaderAndEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LeaderAndEpoch(leader=")?;
+        match &self.leader {
+            Some(leader) => write!(f, "{}", leader)?,
+            None => f.write_str("null")?,
+        }
+        match self.epoch {
+            Some(epoch) => write!(f, ", epoch={})", epoch),
+            None => f.write_str(", epoch=absent)"),
+        }
+    }
+}
+
+impl Metadata {
+    /// Create a new Metadata instance
+    ///
+    /// * `refresh_backoff_ms` - The minimum amount of time that must expire between metadata refreshes to avoid busy
+    ///   polling
+    /// * `metadata_expire_ms` - The maximum amount of time that metadata can be retained without refresh
+    pub fn new(refresh_backoff_ms: u128, metadata_expire_ms: u128) -> Metadata {
+        Metadata {
+            refresh_backoff_ms,
+            metadata_expire_ms,
+            state: Mutex::new(MetadataState {
+                update_version: 0,
+                request_version: 0,
+                last_refresh_ms: 0,
+                last_successful_refresh_ms: 0,
+                need_full_update: false,
+                need_partial_update: false,
+                cluster: Arc::new(Cluster::empty()),
+                last_seen_leader_epochs: HashMap::new(),
+                fatal_error: None,
+                is_closed: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MetadataState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get the current cluster info without blocking
+    pub fn fetch(&self) -> Arc<Cluster> {
+        self.lock().cluster.clone()
+    }
+
+    /// The current leader of the partition and the last seen leader epoch.
+    pub fn current_leader(&self, topic_partition: &TopicPartition) -> LeaderAndEpoch {
+        let state = self.lock();
+        LeaderAndEpoch::new(
+            state.cluster.leader_for(topic_partition).cloned(),
+            state.last_seen_leader_epochs.get(topic_partition).copied(),
+        )
+    }
+
+    /// The last leader epoch seen for the partition, either in a metadata response or in fetched
+    /// records.
+    pub fn last_seen_leader_epoch(&self, topic_partition: &TopicPartition) -> Option<i32> {
+        self.lock()
+            .last_seen_leader_epochs
+            .get(topic_partition)
+            .copied()
+    }
+
+    /// Request an update if the given leader epoch is newer than the one we have seen so far.
+    /// Returns true if the epoch was updated.
+    pub fn update_last_seen_epoch_if_newer(
+        &self,
+        topic_partition: &TopicPartition,
+        leader_epoch: i32,
+    ) -> bool {
+        if leader_epoch < 0 {
+            return false;
+        }
+        let mut state = self.lock();
+        let updated = match state.last_seen_leader_epochs.get(topic_partition) {
+            Some(old_epoch) => leader_epoch > *old_epoch,
+            None => true,
+        };
+        if updated {
+            state
+                .last_seen_leader_epochs
+                .insert(topic_partition.clone(), leader_epoch);
+            state.need_full_update = true;
+        }
+        updated
+    }
+
+    /// Return the next time when the current cluster info can be updated (i.e., backoff time has elapsed).
+    pub fn time_to_allow_update(&self, now_ms: u128) -> u128 {
+        let state = self.lock();
+        (state.last_refresh_ms + self.refresh_backoff_ms).saturating_sub(now_ms)
+    }
+
+    /// The next time to update the cluster info is the maximum of the time the current info will expire and the time the
+    /// current info can be updated (i.e. backoff time has elapsed); If an update has been request then the expiry time
+    /// is now
+    pub fn time_to_next_update(&self, now_ms: u128) -> u128 {
+        let time_to_expire = {
+            let state = self.lock();
+            if state.need_full_update || state.need_partial_update {
+                0
+            } else {
+                (state.last_successful_refresh_ms + self.metadata_expire_ms).saturating_sub(now_ms)
+            }
+        };
+        time_to_expire.max(self.time_to_allow_update(now_ms))
+    }
+
+    pub fn metadata_expire_ms(&self) -> u128 {
+        self.metadata_expire_ms
+    }
+
+    /// Request an update of the current cluster metadata info, return the current update_version before the update
+    pub fn request_update(&self) -> i32 {
+        let mut state = self.lock();
+        state.need_full_update = true;
+        state.update_version
+    }
+
+    pub fn request_update_for_new_topics(&self) -> i32 {
+        let mut state = self.lock();
+        // Override the timestamp of last refresh to let immediate update.
+        state.last_refresh_ms = 0;
+        state.need_partial_update = true;
+        state.request_version += 1;
+        state.update_version
+    }
+
+    /// Whether the next update can be a partial one, for new topics only. That is the case as long as
+    /// no full update was requested and the last successful full update has not expired.
+    pub fn partial_update_allowed(&self, now_ms: u128) -> bool {
+        let state = self.lock();
+        !state.need_full_update
+            && state.last_successful_refresh_ms + self.metadata_expire_ms > now_ms
+    }
+
+    /// Check whether an update has been explicitly requested.
+    pub fn update_requested(&self) -> bool {
+        let state = self.lock();
+        state.need_full_update || state.need_partial_update
+    }
+
+    /// Use the given bootstrap cluster until the first real metadata update arrives.
+    pub fn bootstrap(&self, addresses: &[(String, i32)]) {
+        let mut state = self.lock();
+        state.need_full_update = true;
+        state.update_version += 1;
+        state.cluster = Arc::new(Cluster::bootstrap(addresses));
+    }
+
+    /// The version of the update request. Pass it back to `update` so an outdated partial update
+    /// does not clear a newer request.
+    pub fn request_version(&self) -> i32 {
+        self.lock().request_version
+    }
+
+    /// Updates the cluster metadata. If topic expiry is enabled, expiry time is set for topics if
+    /// required and expired topics are removed from the metadata.
+    ///
+    /// * `request_version` - The request version corresponding to the update response, as provided by
+    ///   `request_version`.
+    /// * `cluster` - Metadata response from which to build the new cluster
+    /// * `is_partial_update` - Whether the metadata request was for a subset of the active topics
+    /// * `now_ms` - The current time in milliseconds
+    pub fn update(
+        &self,
+        request_version: i32,
+        cluster: Cluster,
+        is_partial_update: bool,
+        now_ms: u128,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        if state.is_closed {
+            return Err(KafkaError::IllegalState(
+                "Update requested after metadata close".to_owned(),
+            ));
+        }
+        state.need_partial_update = request_version < state.request_version;
+        state.last_refresh_ms = now_ms;
+        state.update_version += 1;
+        if !is_partial_update {
+            state.need_full_update = false;
+            state.last_successful_refresh_ms = now_ms;
+        }
+        state.cluster = Arc::new(cluster);
+        Ok(())
+    }
+
+    /// Record an attempt to update the metadata that failed. We need to keep track of this
+    /// to avoid retrying immediately.
+    pub fn failed_update(&self, now: u128) {
+        self.lock().last_refresh_ms = now;
+    }
+
+    /// Propagate a fatal error which affects the ability to fetch metadata for the cluster.
+    /// Two examples are authentication and unsupported version exceptions.
+    pub fn fatal_error(&self, error: KafkaError) {
+        self.lock().fatal_error = Some(error);
+    }
+
+    /// If any fatal exceptions were encountered during metadata update, return the exception.
+    /// The exception is cleared, so it is returned only once.
+    pub fn maybe_throw_fatal_exception(&self) -> Result<()> {
+        match self.lock().fatal_error.take() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// The current metadata update_version
+    pub fn update_version(&self) -> i32 {
+        self.lock().update_version
+    }
+
+    /// The last time metadata was successfully updated.
+    pub fn last_successful_update(&self) -> u128 {
+        self.lock().last_successful_refresh_ms
+    }
+
+    /// Close this metadata instance to indicate that metadata updates are no longer possible.
+    pub fn close(&self) {
+        self.lock().is_closed = true;
+    }
+
+    /// Check if this metadata instance has been closed. See `close` for more information.
+    pub fn is_closed(&self) -> bool {
+        self.lock().is_closed
+    }
+}
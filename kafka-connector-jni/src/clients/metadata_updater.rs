@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+
+use log::{trace, warn};
+
+use crate::common::{
+    errors::KafkaError,
+    node::Node,
+    requests::{metadata_request::MetadataRequest, metadata_response::MetadataResponse},
+};
+
+use super::{metadata::Metadata, producer::internals::producer_metadata::ProducerMetadata};
+
+/// A metadata request together with the version of the metadata update it was built for.
+#[derive(Debug, Clone)]
+pub struct MetadataRequestAndVersion {
+    pub request: MetadataRequest,
+    pub request_version: i32,
+    pub is_partial_update: bool,
+}
+
+/// The interface used by `NetworkClient` to request cluster metadata info to be updated and to retrieve the cluster nodes
+/// from such metadata. This is an internal class.
+///
+/// The network client decides when and to which node a metadata request is sent, implementations
+/// only build the requests and handle their outcome.
+pub trait MetadataUpdater: Send + Sync {
+    /// Gets the current cluster info without blocking.
+    fn fetch_nodes(&self) -> Vec<Node>;
+
+    /// The number of milliseconds until the next metadata update is due, 0 if it is due now.
+    fn time_to_next_update(&self, now: u128) -> u128;
+
+    /// The request for the next metadata update, `None` if this updater does not send any.
+    fn new_metadata_request(&self, now: u128) -> Option<MetadataRequestAndVersion>;
+
+    /// Request an update of the cluster metadata, e.g. after losing the connection to a node.
+    fn request_update(&self);
+
+    /// Handle a server disconnect.
+    ///
+    /// This provides a mechanism for the `MetadataUpdater` implementation to use the NetworkClient instance for its own
+    /// requests with special handling for disconnections of such requests.
+    fn handle_server_disconnect(&self, _now: u128, _node_id: &str) {}
+
+    /// Handle a failure of the metadata request sent by `new_metadata_request`, `error` being
+    /// the reason the request could not be sent if there is one, e.g. an unsupported version.
+    fn handle_failed_request(&self, now: u128, error: Option<KafkaError>);
+
+    /// Handle responses for metadata requests.
+    fn handle_successful_response(
+        &self,
+        request: &MetadataRequestAndVersion,
+        response: &MetadataResponse,
+        now: u128,
+    );
+
+    /// Close this updater.
+    fn close(&self);
+}
+
+/// A simple implementation of `MetadataUpdater` that returns the cluster nodes set via the constructor or via
+/// `set_nodes`.
+///
+/// This is useful in cases where automatic metadata updates are not required. An example is controller/broker
+/// communication.
+#[derive(Default)]
+pub struct ManualMetadataUpdater {
+    nodes: Mutex<Vec<Node>>,
+}
+
+impl ManualMetadataUpdater {
+    pub fn new(nodes: Vec<Node>) -> ManualMetadataUpdater {
+        ManualMetadataUpdater {
+            nodes: Mutex::new(nodes),
+        }
+    }
+
+    pub fn set_nodes(&self, nodes: Vec<Node>) {
+        *self.nodes.lock().unwrap_or_else(|e| e.into_inner()) = nodes;
+    }
+}
+
+impl MetadataUpdater for ManualMetadataUpdater {
+    fn fetch_nodes(&self) -> Vec<Node> {
+        self.nodes.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn time_to_next_update(&self, _now: u128) -> u128 {
+        u128::MAX
+    }
+
+    fn new_metadata_request(&self, _now: u128) -> Option<MetadataRequestAndVersion> {
+        None
+    }
+
+    fn request_update(&self) {}
+
+    fn handle_failed_request(&self, _now: u128, _error: Option<KafkaError>) {
+        // Do nothing
+    }
+
+    fn handle_successful_response(
+        &self,
+        _request: &MetadataRequestAndVersion,
+        _response: &MetadataResponse,
+        _now: u128,
+    ) {
+        // Do nothing
+    }
+
+    fn close(&self) {}
+}
+
+impl MetadataUpdater for ProducerMetadata {
+    fn fetch_nodes(&self) -> Vec<Node> {
+        self.fetch().nodes.clone()
+    }
+
+    fn time_to_next_update(&self, now: u128) -> u128 {
+        Metadata::time_to_next_update(self, now)
+    }
+
+    fn new_metadata_request(&self, now: u128) -> Option<MetadataRequestAndVersion> {
+        let is_partial_update = self.partial_update_allowed(now);
+        let topics = if is_partial_update {
+            self.new_topics()
+        } else {
+            self.topics()
+        };
+        Some(MetadataRequestAndVersion {
+            request: MetadataRequest::new(topics.into_iter().collect(), true),
+            request_version: self.request_version(),
+            is_partial_update,
+        })
+    }
+
+    fn request_update(&self) {
+        Metadata::request_update(self);
+    }
+
+    fn handle_failed_request(&self, now: u128, error: Option<KafkaError>) {
+        if let Some(error @ KafkaError::UnsupportedVersion(_)) = error {
+            ProducerMetadata::fatal_error(self, error);
+        }
+        self.failed_update(now);
+    }
+
+    fn handle_successful_response(
+        &self,
+        request: &MetadataRequestAndVersion,
+        response: &MetadataResponse,
+        now: u128,
+    ) {
+        let errors = response.errors();
+        if !errors.is_empty() {
+            warn!("Error while fetching metadata: {:?}", errors);
+        }
+        // Don't update the cluster if there are no valid nodes...the topic we want may still be in the process of being
+        // created which means we will get errors and no nodes until it exists
+        if response.brokers.is_empty() {
+            trace!("Ignoring empty metadata response.");
+            self.failed_update(now);
+            return;
+        }
+        if let Err(e) = ProducerMetadata::update(
+            self,
+            request.request_version,
+            response.build_cluster(),
+            request.is_partial_update,
+            now,
+        ) {
+            warn!("Failed to update the metadata: {}", e);
+        }
+    }
+
+    fn close(&self) {
+        ProducerMetadata::close(self);
+    }
+}
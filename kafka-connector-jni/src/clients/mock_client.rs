@@ -0,0 +1,422 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use log::{debug, trace};
+
+use crate::common::{
+    node::Node,
+    requests::{abstract_request::AbstractRequest, abstract_response::AbstractResponse},
+    utils::time::Time,
+};
+
+use super::{
+    api_versions::{ApiVersions, NodeApiVersions},
+    client_request::{ClientRequest, RequestCompletionHandler},
+    client_response::ClientResponse,
+    kafka_client::KafkaClient,
+};
+
+/// Predicate deciding whether a prepared response applies to a request.
+pub type RequestMatcher = Box<dyn Fn(&AbstractRequest) -> bool + Send>;
+
+/// A mock network client acting as an in-process broker. Responses are prepared up front (or
+/// given for already sent requests) and completion handlers are invoked from `poll`, the same
+/// way a real network client would do it.
+pub struct MockClient {
+    time: Arc<dyn Time>,
+    api_versions: Arc<ApiVersions>,
+    active: AtomicBool,
+    state: Mutex<MockClientState>,
+}
+
+struct FutureResponse {
+    node: Option<Node>,
+    response_body: AbstractResponse,
+    disconnected: bool,
+    request_matcher: Option<RequestMatcher>,
+}
+
+#[derive(Default)]
+struct MockClientState {
+    correlation: i32,
+    nodes: Vec<Node>,
+    connected: HashSet<String>,
+    blackedout: HashMap<String, u128>,
+    requests: VecDeque<ClientRequest>,
+    responses: VecDeque<(ClientResponse, Option<RequestCompletionHandler>)>,
+    future_responses: VecDeque<FutureResponse>,
+}
+
+impl MockClient {
+    pub fn new(
+        time: Arc<dyn Time>,
+        nodes: Vec<Node>,
+        api_versions: Arc<ApiVersions>,
+    ) -> MockClient {
+        MockClient {
+            time,
+            api_versions,
+            active: AtomicBool::new(true),
+            state: Mutex::new(MockClientState {
+                nodes,
+                ..MockClientState::default()
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockClientState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Replace the nodes known to the client.
+    pub fn update_nodes(&self, nodes: Vec<Node>) {
+        self.lock().nodes = nodes;
+    }
+
+    /// Set the api versions the node reports, as the network client would do once connected.
+    pub fn set_node_api_versions(&self, node: &Node, node_api_versions: NodeApiVersions) {
+        self.api_versions
+            .update(node.id_string(), node_api_versions);
+    }
+
+    /// Make the node unavailable for `duration_ms`. The connection to it is dropped.
+    pub fn blackout(&self, node: &Node, duration_ms: u128) {
+        let until = self.time.milliseconds() + duration_ms;
+        self.lock().blackedout.insert(node.id_string(), until);
+        self.disconnect(&node.id_string());
+    }
+
+    /// Make the node available again.
+    pub fn unblackout(&self, node: &Node) {
+        self.lock().blackedout.remove(&node.id_string());
+    }
+
+    fn is_blacked_out(state: &mut MockClientState, node_id: &str, now: u128) -> bool {
+        match state.blackedout.get(node_id) {
+            Some(until) if *until > now => true,
+            Some(_) => {
+                state.blackedout.remove(node_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Prepare a response for the next request sent to any node.
+    pub fn prepare_response(&self, response_body: AbstractResponse) {
+        self.prepare_response_full(None, None, response_body, false);
+    }
+
+    /// Prepare a response for the next request sent to `node`.
+    pub fn prepare_response_from(&self, node: &Node, response_body: AbstractResponse) {
+        self.prepare_response_full(Some(node.clone()), None, response_body, false);
+    }
+
+    /// Prepare a response for the next request, which must be accepted by `matcher`.
+    pub fn prepare_response_matching(
+        &self,
+        matcher: impl Fn(&AbstractRequest) -> bool + Send + 'static,
+        response_body: AbstractResponse,
+    ) {
+        self.prepare_response_full(None, Some(Box::new(matcher)), response_body, false);
+    }
+
+    /// Prepare a response. Requests are matched against prepared responses in order; a request
+    /// sent to a node other than the one the response is prepared for skips it. The next in line
+    /// response for the node must be accepted by its matcher.
+    pub fn prepare_response_full(
+        &self,
+        node: Option<Node>,
+        request_matcher: Option<RequestMatcher>,
+        response_body: AbstractResponse,
+        disconnected: bool,
+    ) {
+        self.lock().future_responses.push_back(FutureResponse {
+            node,
+            response_body,
+            disconnected,
+            request_matcher,
+        });
+    }
+
+    /// Respond to the oldest in-flight request.
+    pub fn respond(&self, response_body: AbstractResponse) -> bool {
+        let mut state = self.lock();
+        match state.requests.pop_front() {
+            Some(request) => {
+                let response = self.client_response(request, Some(response_body), false);
+                state.responses.push_back(response);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Respond to the oldest in-flight request sent to `node`.
+    pub fn respond_from(&self, response_body: AbstractResponse, node: &Node) -> bool {
+        let mut state = self.lock();
+        let node_id = node.id_string();
+        let index = match state
+            .requests
+            .iter()
+            .position(|request| request.destination == node_id)
+        {
+            Some(index) => index,
+            None => return false,
+        };
+        let request = state.requests.remove(index).expect("index is in range");
+        let response = self.client_response(request, Some(response_body), false);
+        state.responses.push_back(response);
+        true
+    }
+
+    /// The in-flight requests which were not answered yet.
+    pub fn requests(&self) -> Vec<AbstractRequest> {
+        self.lock()
+            .requests
+            .iter()
+            .map(|request| request.request.clone())
+            .collect()
+    }
+
+    pub fn has_pending_responses(&self) -> bool {
+        let state = self.lock();
+        !state.responses.is_empty() || !state.future_responses.is_empty()
+    }
+
+    /// Number of prepared responses which were not matched to a request yet.
+    pub fn future_response_count(&self) -> usize {
+        self.lock().future_responses.len()
+    }
+
+    /// Drop all in-flight requests and prepared responses.
+    pub fn reset(&self) {
+        let mut state = self.lock();
+        state.connected.clear();
+        state.requests.clear();
+        state.responses.clear();
+        state.future_responses.clear();
+    }
+
+    fn client_response(
+        &self,
+        mut request: ClientRequest,
+        response_body: Option<AbstractResponse>,
+        disconnected: bool,
+    ) -> (ClientResponse, Option<RequestCompletionHandler>) {
+        let version = self
+            .api_versions
+            .get(&request.destination)
+            .and_then(|versions| {
+                versions
+                    .api_version(request.request.api_key())
+                    .map(|v| v.max_version)
+            })
+            .unwrap_or(0);
+        let response = ClientResponse::new(
+            request.make_header(version),
+            request.destination.clone(),
+            request.created_time_ms,
+            self.time.milliseconds(),
+            disconnected,
+            None,
+            response_body,
+        );
+        (response, request.callback.take())
+    }
+}
+
+impl KafkaClient for MockClient {
+    fn is_ready(&self, node: &Node, now: u128) -> bool {
+        let mut state = self.lock();
+        let node_id = node.id_string();
+        !Self::is_blacked_out(&mut state, &node_id, now) && state.connected.contains(&node_id)
+    }
+
+    fn ready(&self, node: &Node, now: u128) -> bool {
+        let mut state = self.lock();
+        let node_id = node.id_string();
+        if Self::is_blacked_out(&mut state, &node_id, now) {
+            return false;
+        }
+        state.connected.insert(node_id);
+        true
+    }
+
+    fn connection_delay(&self, node: &Node, now: u128) -> u128 {
+        self.lock()
+            .blackedout
+            .get(&node.id_string())
+            .map(|until| until.saturating_sub(now))
+            .unwrap_or(0)
+    }
+
+    fn poll_delay_ms(&self, node: &Node, now: u128) -> u128 {
+        self.connection_delay(node, now)
+    }
+
+    fn connection_failed(&self, node: &Node) -> bool {
+        let now = self.time.milliseconds();
+        Self::is_blacked_out(&mut self.lock(), &node.id_string(), now)
+    }
+
+    fn send(&self, request: ClientRequest, now: u128) {
+        let mut state = self.lock();
+        if !self.active.load(Ordering::SeqCst)
+            || Self::is_blacked_out(&mut state, &request.destination, now)
+        {
+            debug!("Cannot send {} since the destination is not ready", request);
+            let response = self.client_response(request, None, true);
+            state.responses.push_back(response);
+            return;
+        }
+
+        if !request.expect_response {
+            let response = self.client_response(request, None, false);
+            state.responses.push_back(response);
+            return;
+        }
+
+        let position = state.future_responses.iter().position(|future_response| {
+            future_response
+                .node
+                .as_ref()
+                .map(|node| node.id_string() == request.destination)
+                .unwrap_or(true)
+        });
+        match position {
+            Some(position) => {
+                let future_response = state
+                    .future_responses
+                    .remove(position)
+                    .expect("position is in range");
+                if let Some(request_matcher) = &future_response.request_matcher {
+                    if !request_matcher(&request.request) {
+                        panic!(
+                            "Request matcher did not match next-in-line request {} with prepared response {:?}",
+                            request.request, future_response.response_body
+                        );
+                    }
+                }
+                trace!("Responding to {} with a prepared response", request);
+                let response = self.client_response(
+                    request,
+                    Some(future_response.response_body),
+                    future_response.disconnected,
+                );
+                state.responses.push_back(response);
+            }
+            None => state.requests.push_back(request),
+        }
+    }
+
+    fn poll(&self, _timeout: u128, _now: u128) -> Vec<ClientResponse> {
+        let responses: Vec<_> = self.lock().responses.drain(..).collect();
+        let mut copy = Vec::with_capacity(responses.len());
+        for (response, callback) in responses {
+            if let Some(callback) = callback {
+                callback(response.clone());
+            }
+            copy.push(response);
+        }
+        copy
+    }
+
+    fn disconnect(&self, node_id: &str) {
+        let mut state = self.lock();
+        state.connected.remove(node_id);
+        let (disconnected, remaining): (VecDeque<_>, VecDeque<_>) = state
+            .requests
+            .drain(..)
+            .partition(|request| request.destination == node_id);
+        state.requests = remaining;
+        for request in disconnected {
+            let response = self.client_response(request, None, true);
+            state.responses.push_back(response);
+        }
+    }
+
+    fn least_loaded_node(&self, now: u128) -> Option<Node> {
+        let mut state = self.lock();
+        let nodes: Vec<Node> = state
+            .nodes
+            .clone()
+            .into_iter()
+            .filter(|node| !Self::is_blacked_out(&mut state, &node.id_string(), now))
+            .collect();
+        nodes.into_iter().min_by_key(|node| {
+            let node_id = node.id_string();
+            state
+                .requests
+                .iter()
+                .filter(|request| request.destination == node_id)
+                .count()
+        })
+    }
+
+    fn in_flight_request_count(&self) -> usize {
+        let state = self.lock();
+        state.requests.len() + state.responses.len()
+    }
+
+    fn in_flight_request_count_for(&self, node_id: &str) -> usize {
+        let state = self.lock();
+        state
+            .requests
+            .iter()
+            .filter(|request| request.destination == node_id)
+            .count()
+            + state
+                .responses
+                .iter()
+                .filter(|(response, _)| response.destination == node_id)
+                .count()
+    }
+
+    fn has_ready_nodes(&self, _now: u128) -> bool {
+        !self.lock().connected.is_empty()
+    }
+
+    fn wakeup(&self) {}
+
+    fn new_client_request(
+        &self,
+        node_id: &str,
+        request: AbstractRequest,
+        created_time_ms: u128,
+        expect_response: bool,
+        request_timeout_ms: u128,
+        callback: Option<RequestCompletionHandler>,
+    ) -> ClientRequest {
+        let mut state = self.lock();
+        let correlation_id = state.correlation;
+        state.correlation += 1;
+        ClientRequest::new(
+            node_id,
+            request,
+            correlation_id,
+            "mockClientId",
+            created_time_ms,
+            expect_response,
+            request_timeout_ms,
+            callback,
+        )
+    }
+
+    fn initiate_close(&self) {
+        self.active.store(false, Ordering::SeqCst);
+    }
+
+    fn active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn close(&self) {
+        self.active.store(false, Ordering::SeqCst);
+    }
+}
@@ -1 +1,19 @@
+pub mod admin;
+pub mod api_versions;
+pub mod client_request;
+pub mod client_response;
+pub mod client_utils;
+pub mod cluster_connection_states;
+pub mod common_client_configs;
 pub mod consumer;
+pub mod fetch_session_handler;
+pub mod group_rebalance_config;
+pub mod in_flight_requests;
+pub mod kafka_client;
+pub mod metadata;
+pub mod metadata_updater;
+#[cfg(test)]
+pub mod mock_client;
+pub mod network_client;
+pub mod network_client_utils;
+pub mod producer;
@@ -0,0 +1,1430 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicI32, AtomicU8, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use mio::Waker;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    network::{network_send::NetworkSend, selector::Selector},
+    node::Node,
+    protocol::{api_keys::ApiKeys, errors::Errors},
+    requests::{
+        abstract_request::AbstractRequest, abstract_response::AbstractResponse,
+        api_versions_request::ApiVersionsRequest, api_versions_response::ApiVersionsResponse,
+        response_header::ResponseHeader,
+    },
+    utils::{
+        log_context::{LogContext, Logger},
+        time::Time,
+    },
+};
+
+use super::{
+    api_versions::{ApiVersions, NodeApiVersions},
+    client_request::{ClientRequest, RequestCompletionHandler},
+    client_response::ClientResponse,
+    cluster_connection_states::ClusterConnectionStates,
+    in_flight_requests::{InFlightRequest, InFlightRequests},
+    kafka_client::KafkaClient,
+    metadata_updater::{MetadataRequestAndVersion, MetadataUpdater},
+};
+
+const ACTIVE: u8 = 0;
+const CLOSING: u8 = 1;
+const CLOSED: u8 = 2;
+
+/// A response and the completion handler of its request, invoked once the client lock is released.
+type Completion = (ClientResponse, Option<RequestCompletionHandler>);
+
+/// A network client for asynchronous request/response network i/o. This is an internal class used to implement the
+/// user-facing producer and consumer clients.
+///
+/// The connections, the in-flight requests and the selector are guarded by a single lock which
+/// `poll` holds while it waits for I/O, so other threads should `wakeup` the client before using
+/// it, as the consumer network client does. The completion handlers of the requests are invoked
+/// without holding the lock.
+pub struct NetworkClient {
+    metadata_updater: Arc<dyn MetadataUpdater>,
+    client_id: String,
+    reconnect_backoff_ms: u128,
+    // default timeout for individual requests to await acknowledgement from servers
+    default_request_timeout_ms: u128,
+    // True if we should send an ApiVersionRequest when first connecting to a broker.
+    discover_broker_versions: bool,
+    api_versions: Arc<ApiVersions>,
+    time: Arc<dyn Time>,
+    waker: Arc<Waker>,
+    client_state: AtomicU8,
+    correlation: AtomicI32,
+    state: Mutex<NetworkClientState>,
+    log: Logger,
+}
+
+struct NetworkClientState {
+    // the selector used to perform network i/o
+    selector: Selector,
+    // the state of each node's connection
+    connection_states: ClusterConnectionStates,
+    // the set of requests currently being sent or awaiting a response
+    in_flight_requests: InFlightRequests,
+    // the version of the ApiVersionsRequest to send to the nodes which need one
+    nodes_needing_api_versions_fetch: HashMap<String, i16>,
+    // the responses of the requests which could not be sent, returned by the next poll
+    aborted_sends: Vec<Completion>,
+    // the metadata request in flight, if any
+    metadata_in_progress: Option<MetadataRequestAndVersion>,
+}
+
+impl NetworkClient {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        selector: Selector,
+        metadata_updater: Arc<dyn MetadataUpdater>,
+        client_id: impl Into<String>,
+        max_in_flight_requests_per_connection: usize,
+        reconnect_backoff_ms: u128,
+        reconnect_backoff_max_ms: u128,
+        default_request_timeout_ms: u128,
+        connection_setup_timeout_ms: u128,
+        connection_setup_timeout_max_ms: u128,
+        time: Arc<dyn Time>,
+        discover_broker_versions: bool,
+        api_versions: Arc<ApiVersions>,
+        log_context: &LogContext,
+    ) -> NetworkClient {
+        NetworkClient {
+            metadata_updater,
+            client_id: client_id.into(),
+            reconnect_backoff_ms,
+            default_request_timeout_ms,
+            discover_broker_versions,
+            api_versions,
+            time,
+            waker: selector.waker(),
+            client_state: AtomicU8::new(ACTIVE),
+            correlation: AtomicI32::new(0),
+            state: Mutex::new(NetworkClientState {
+                selector,
+                connection_states: ClusterConnectionStates::new(
+                    reconnect_backoff_ms,
+                    reconnect_backoff_max_ms,
+                    connection_setup_timeout_ms,
+                    connection_setup_timeout_max_ms,
+                ),
+                in_flight_requests: InFlightRequests::new(max_in_flight_requests_per_connection),
+                nodes_needing_api_versions_fetch: HashMap::new(),
+                aborted_sends: vec![],
+                metadata_in_progress: None,
+            }),
+            log: log_context.logger(module_path!()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NetworkClientState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn next_correlation_id(&self) -> i32 {
+        self.correlation.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// Are we connected and ready and able to send more requests to the given connection?
+    fn can_send_request(state: &NetworkClientState, node: &str, now: u128) -> bool {
+        state.connection_states.is_ready(node, now)
+            && state.selector.is_channel_ready(node)
+            && state.in_flight_requests.can_send_more(node)
+    }
+
+    fn is_metadata_update_due(&self, state: &NetworkClientState, now: u128) -> bool {
+        state.metadata_in_progress.is_none() && self.metadata_updater.time_to_next_update(now) == 0
+    }
+
+    /// Initiate a connection to the given node
+    fn initiate_connect(&self, state: &mut NetworkClientState, node: &Node, now: u128) {
+        let node_id = node.id_string();
+        state
+            .connection_states
+            .connecting(&node_id, now, &node.host, node.port);
+        let result = state
+            .connection_states
+            .current_address(&node_id)
+            .and_then(|address| {
+                self.log.debug(format_args!(
+                    "Initiating connection to node {} using address {}",
+                    node, address
+                ));
+                state.selector.connect(&node_id, address)
+            });
+        if let Err(e) = result {
+            self.log
+                .warn(format_args!("Error connecting to node {}: {}", node, e));
+            // Attempt failed, we'll try again after the backoff
+            state.connection_states.disconnected(&node_id, now);
+            // Notify metadata updater of the connection failure
+            self.metadata_updater
+                .handle_server_disconnect(now, &node_id);
+        }
+    }
+
+    fn do_send(
+        &self,
+        state: &mut NetworkClientState,
+        request: ClientRequest,
+        is_internal_request: bool,
+        now: u128,
+    ) {
+        let node_id = request.destination.clone();
+        if !self.active() {
+            self.abort_send(state, request, is_internal_request, now, None, true);
+            return;
+        }
+        // If this request came from outside the NetworkClient, validate that we can send data.
+        if !is_internal_request && !Self::can_send_request(state, &node_id, now) {
+            self.log.warn(format_args!(
+                "Attempt to send a request to node {} which is not ready.",
+                node_id
+            ));
+            self.abort_send(state, request, is_internal_request, now, None, true);
+            return;
+        }
+        let api_key = request.request.api_key();
+        let version = match self.api_versions.get(&node_id) {
+            // The node is not known to support any version, e.g. when the broker versions are not
+            // discovered, use the latest version of the client.
+            None => api_key.latest_version().ok_or_else(|| {
+                KafkaError::UnsupportedVersion(format!("The client does not support {}", api_key))
+            }),
+            Some(version_info) => version_info.latest_usable_version(api_key),
+        };
+        match version {
+            Ok(version) => {
+                self.send_with_version(state, request, is_internal_request, now, version)
+            }
+            Err(e) => {
+                self.log.debug(format_args!(
+                    "Version mismatch when attempting to send {} with correlation id {} to {}: {}",
+                    request.request, request.correlation_id, request.destination, e
+                ));
+                self.abort_send(state, request, is_internal_request, now, Some(e), false);
+            }
+        }
+    }
+
+    fn send_with_version(
+        &self,
+        state: &mut NetworkClientState,
+        request: ClientRequest,
+        is_internal_request: bool,
+        now: u128,
+        version: i16,
+    ) {
+        let header = request.make_header(version);
+        let payload = match request.request.serialize_with_header(&header) {
+            Ok(payload) => payload,
+            Err(e) => {
+                self.log.debug(format_args!(
+                    "Version mismatch when attempting to send {} with correlation id {} to {}: {}",
+                    request.request, request.correlation_id, request.destination, e
+                ));
+                self.abort_send(state, request, is_internal_request, now, Some(e), false);
+                return;
+            }
+        };
+        self.log.debug(format_args!(
+            "Sending {} request with header {} and timeout {} to node {}: {}",
+            header.api_key,
+            header,
+            request.request_timeout_ms,
+            request.destination,
+            request.request
+        ));
+        let send = NetworkSend::size_delimited(request.destination.clone(), &payload);
+        state.in_flight_requests.add(InFlightRequest {
+            header,
+            destination: request.destination,
+            callback: request.callback,
+            expect_response: request.expect_response,
+            is_internal_request,
+            send_completed: false,
+            created_time_ms: request.created_time_ms,
+            send_time_ms: now,
+            request_timeout_ms: request.request_timeout_ms,
+        });
+        state.selector.send(send);
+    }
+
+    /// Complete a request which could not be sent in the next poll, or fail the metadata update
+    /// if it was the metadata request of the client.
+    fn abort_send(
+        &self,
+        state: &mut NetworkClientState,
+        request: ClientRequest,
+        is_internal_request: bool,
+        now: u128,
+        version_mismatch: Option<KafkaError>,
+        disconnected: bool,
+    ) {
+        let api_key = request.request.api_key();
+        if is_internal_request {
+            if api_key == ApiKeys::Metadata {
+                state.metadata_in_progress = None;
+                self.metadata_updater
+                    .handle_failed_request(now, version_mismatch);
+            }
+            return;
+        }
+        let header = request.make_header(api_key.latest_version().unwrap_or_default());
+        let response = ClientResponse::new(
+            header,
+            request.destination,
+            now,
+            now,
+            disconnected,
+            version_mismatch,
+            None,
+        );
+        state.aborted_sends.push((response, request.callback));
+    }
+
+    /// Choose the node with the fewest outstanding requests which is at least eligible for connection.
+    fn least_loaded_node_locked(&self, state: &NetworkClientState, now: u128) -> Option<Node> {
+        let nodes = self.metadata_updater.fetch_nodes();
+        if nodes.is_empty() {
+            self.log
+                .debug(format_args!("There are no nodes in the Kafka cluster"));
+            return None;
+        }
+        let mut in_flight = usize::MAX;
+        let mut found_connecting = None;
+        let mut found_can_connect: Option<&Node> = None;
+        let mut found_ready = None;
+
+        let offset = (RandomState::new().build_hasher().finish() % nodes.len() as u64) as usize;
+        for i in 0..nodes.len() {
+            let node = &nodes[(offset + i) % nodes.len()];
+            let node_id = node.id_string();
+            // Return null if initial connections have not been established yet
+            if Self::can_send_request(state, &node_id, now) {
+                let curr_in_flight = state.in_flight_requests.count(&node_id);
+                if curr_in_flight == 0 {
+                    // if we find an established connection with no in-flight requests we can stop right away
+                    self.log.trace(format_args!(
+                        "Found least loaded node {} connected with no in-flight requests",
+                        node
+                    ));
+                    return Some(node.clone());
+                } else if curr_in_flight < in_flight {
+                    // otherwise if this is the best we have found so far, record that
+                    in_flight = curr_in_flight;
+                    found_ready = Some(node);
+                }
+            } else if state.connection_states.is_preparing_connection(&node_id) {
+                // If we find a connection that is still being established, then we should prefer it
+                found_connecting = Some(node);
+            } else if state.connection_states.can_connect(&node_id, now) {
+                let attempted_earlier = found_can_connect.is_none_or(|found| {
+                    state
+                        .connection_states
+                        .last_connect_attempt_ms(&found.id_string())
+                        > state.connection_states.last_connect_attempt_ms(&node_id)
+                });
+                if attempted_earlier {
+                    found_can_connect = Some(node);
+                }
+            } else {
+                self.log.trace(format_args!(
+                    "Removing node {} from least loaded node selection since it is neither ready for sending or connecting",
+                    node
+                ));
+            }
+        }
+
+        // We prefer established connections if possible. Otherwise, we will wait for connections
+        // which are being established before connecting to new nodes.
+        found_ready
+            .or(found_connecting)
+            .or(found_can_connect)
+            .cloned()
+            .or_else(|| {
+                self.log.trace(format_args!(
+                    "Least loaded node selection failed to find an available node"
+                ));
+                None
+            })
+    }
+
+    /// Start a metadata update if one is due, returning the time to wait for the next one.
+    fn maybe_update_metadata(&self, state: &mut NetworkClientState, now: u128) -> u128 {
+        // Beware that the behavior of this method and the computation of timeouts for poll() are
+        // highly dependent on the behavior of least_loaded_node.
+        let time_to_next_metadata_update = self.metadata_updater.time_to_next_update(now);
+        let wait_for_metadata_fetch = if state.metadata_in_progress.is_some() {
+            self.default_request_timeout_ms
+        } else {
+            0
+        };
+        let metadata_timeout = time_to_next_metadata_update.max(wait_for_metadata_fetch);
+        if metadata_timeout > 0 {
+            return metadata_timeout;
+        }
+
+        let node = match self.least_loaded_node_locked(state, now) {
+            Some(node) => node,
+            None => {
+                self.log.debug(format_args!(
+                    "Give up sending metadata request since no node is available"
+                ));
+                return self.reconnect_backoff_ms;
+            }
+        };
+        let node_id = node.id_string();
+        if Self::can_send_request(state, &node_id, now) {
+            let request = match self.metadata_updater.new_metadata_request(now) {
+                Some(request) => request,
+                None => return u128::MAX,
+            };
+            self.log.debug(format_args!(
+                "Sending metadata request {} to node {}",
+                request.request, node
+            ));
+            let client_request = self.new_client_request(
+                &node_id,
+                AbstractRequest::Metadata(request.request.clone()),
+                now,
+                true,
+                self.default_request_timeout_ms,
+                None,
+            );
+            state.metadata_in_progress = Some(request);
+            self.do_send(state, client_request, true, now);
+            return self.default_request_timeout_ms;
+        }
+
+        // If there's any connection establishment underway, wait until it completes. This prevents
+        // the client from unnecessarily connecting to additional nodes while a previous connection
+        // attempt has not been completed.
+        if state.connection_states.is_any_node_connecting() {
+            // Strictly the timeout we should return here is "connect timeout", but as we don't
+            // have such application level configuration, using reconnect backoff instead.
+            return self.reconnect_backoff_ms;
+        }
+
+        if state.connection_states.can_connect(&node_id, now) {
+            // We don't have a connection to this node right now, make one
+            self.log.debug(format_args!(
+                "Initialize connection to node {} for sending metadata request",
+                node
+            ));
+            self.initiate_connect(state, &node, now);
+            return self.reconnect_backoff_ms;
+        }
+
+        // connected, but can't send more OR connecting
+        // In either case, we just need to wait for a network event to let us know the selected
+        // connection might be usable again.
+        u128::MAX
+    }
+
+    /// Post process disconnection of a node
+    fn process_disconnection(
+        &self,
+        state: &mut NetworkClientState,
+        node_id: &str,
+        now: u128,
+        responses: &mut Vec<Completion>,
+    ) {
+        if state.connection_states.is_connecting(node_id) {
+            self.log.warn(format_args!(
+                "Connection to node {} could not be established. Broker may not be available.",
+                node_id
+            ));
+        } else {
+            self.log
+                .debug(format_args!("Node {} disconnected.", node_id));
+        }
+        state.connection_states.disconnected(node_id, now);
+        self.api_versions.remove(node_id);
+        state.nodes_needing_api_versions_fetch.remove(node_id);
+        self.cancel_in_flight_requests(state, node_id, now, responses);
+        self.metadata_updater.handle_server_disconnect(now, node_id);
+    }
+
+    fn cancel_in_flight_requests(
+        &self,
+        state: &mut NetworkClientState,
+        node_id: &str,
+        now: u128,
+        responses: &mut Vec<Completion>,
+    ) {
+        for request in state.in_flight_requests.clear_all(node_id) {
+            self.log.trace(format_args!(
+                "Cancelled request {} due to node {} being disconnected",
+                request.header, node_id
+            ));
+            self.cancel_request(state, request, now, responses);
+        }
+    }
+
+    fn cancel_request(
+        &self,
+        state: &mut NetworkClientState,
+        request: InFlightRequest,
+        now: u128,
+        responses: &mut Vec<Completion>,
+    ) {
+        if !request.is_internal_request {
+            responses.push(request.disconnected(now, None));
+        } else if request.header.api_key == ApiKeys::Metadata {
+            state.metadata_in_progress = None;
+            self.metadata_updater.handle_failed_request(now, None);
+        }
+    }
+
+    /// Handle any completed request send. In particular if no response is expected consider the request complete.
+    fn handle_completed_sends(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        now: u128,
+    ) {
+        // if no response is expected then when the send is completed, return it
+        for send in state.selector.drain_completed_sends() {
+            let node = send.destination();
+            let expect_response = match state.in_flight_requests.last_sent(node) {
+                Some(request) => {
+                    request.send_completed = true;
+                    request.expect_response
+                }
+                None => continue,
+            };
+            if !expect_response {
+                if let Some(request) = state.in_flight_requests.complete_last_sent(node) {
+                    responses.push(request.completed(None, now));
+                }
+            }
+        }
+    }
+
+    /// Handle any completed receives and update the response list with the responses received.
+    fn handle_completed_receives(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        now: u128,
+    ) {
+        for receive in state.selector.drain_completed_receives() {
+            let source = receive.source().to_owned();
+            let request = match state.in_flight_requests.complete_next(&source) {
+                Some(request) => request,
+                None => {
+                    if state.selector.is_channel_ready(&source) {
+                        self.log.error(format_args!(
+                            "Received a response from node {} without any request in flight, disconnecting",
+                            source
+                        ));
+                        state.selector.close(&source);
+                        self.process_disconnection(state, &source, now, responses);
+                    }
+                    continue;
+                }
+            };
+            let mut payload = receive.payload();
+            let response = Self::parse_response(&mut payload, &request);
+            let response = match response {
+                Ok(response) => response,
+                Err(e) => {
+                    self.log.error(format_args!(
+                        "Failed to read the response from node {} for request with header {}, disconnecting: {}",
+                        source, request.header, e
+                    ));
+                    self.cancel_request(state, request, now, responses);
+                    state.selector.close(&source);
+                    self.process_disconnection(state, &source, now, responses);
+                    continue;
+                }
+            };
+            self.log.debug(format_args!(
+                "Received {} response from node {} for request with header {}: {:?}",
+                request.header.api_key, request.destination, request.header, response
+            ));
+
+            // If the received response includes a throttle delay, throttle the connection.
+            self.maybe_throttle(
+                state,
+                &response,
+                request.header.api_version,
+                &request.destination,
+                now,
+            );
+            match response {
+                AbstractResponse::Metadata(response) if request.is_internal_request => {
+                    if let Some(in_progress) = state.metadata_in_progress.take() {
+                        self.metadata_updater.handle_successful_response(
+                            &in_progress,
+                            &response,
+                            now,
+                        );
+                    }
+                }
+                AbstractResponse::ApiVersions(response) if request.is_internal_request => {
+                    self.handle_api_versions_response(state, responses, request, now, response)
+                }
+                response => responses.push(request.completed(Some(response), now)),
+            }
+        }
+    }
+
+    fn parse_response(
+        payload: &mut bytes::Bytes,
+        request: &InFlightRequest,
+    ) -> Result<AbstractResponse> {
+        let header = &request.header;
+        let response_header =
+            ResponseHeader::read_from(payload, header.api_key, header.api_version)?;
+        // Always expect the response version id to be the same as the request version id
+        if response_header.correlation_id != header.correlation_id {
+            return Err(KafkaError::IllegalState(format!(
+                "Correlation id for response ({}) does not match request ({}), request header: {}",
+                response_header.correlation_id, header.correlation_id, header
+            )));
+        }
+        AbstractResponse::parse(header.api_key, payload, header.api_version)
+    }
+
+    fn maybe_throttle(
+        &self,
+        state: &mut NetworkClientState,
+        response: &AbstractResponse,
+        api_version: i16,
+        node_id: &str,
+        now: u128,
+    ) {
+        let throttle_time_ms = response.throttle_time_ms();
+        if throttle_time_ms > 0 && response.should_client_throttle(api_version) {
+            let throttle_until_time_ms = now + throttle_time_ms as u128;
+            state
+                .connection_states
+                .throttle(node_id, throttle_until_time_ms);
+            self.log.trace(format_args!(
+                "Connection to node {} is throttled for {} ms until timestamp {}",
+                node_id, throttle_time_ms, throttle_until_time_ms
+            ));
+        }
+    }
+
+    fn handle_api_versions_response(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        request: InFlightRequest,
+        now: u128,
+        response: ApiVersionsResponse,
+    ) {
+        let node = request.destination;
+        if response.error != Errors::None {
+            if request.header.api_version == 0 || response.error != Errors::UnsupportedVersion {
+                self.log.warn(format_args!(
+                    "Received error {} from node {} when making an ApiVersionsRequest with correlation id {}. Disconnecting.",
+                    response.error, node, request.header.correlation_id
+                ));
+                state.selector.close(&node);
+                self.process_disconnection(state, &node, now, responses);
+            } else {
+                // The ApiKeys field is populated with the supported versions of the
+                // ApiVersionsRequest when an UNSUPPORTED_VERSION error is returned.
+                // If not provided, the client falls back to version 0.
+                let max_api_version = response
+                    .api_version(ApiKeys::ApiVersions)
+                    .map_or(0, |version| version.max_version)
+                    .min(request.header.api_version - 1);
+                state
+                    .nodes_needing_api_versions_fetch
+                    .insert(node, max_api_version);
+            }
+            return;
+        }
+        let node_version_info = NodeApiVersions::new(response.api_keys);
+        self.log.debug(format_args!(
+            "Recorded API versions for node {}: {:?}",
+            node, node_version_info
+        ));
+        self.api_versions.update(node.clone(), node_version_info);
+        state.connection_states.ready(&node);
+    }
+
+    /// Handle any disconnected connections
+    fn handle_disconnections(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        now: u128,
+    ) {
+        let disconnected = state.selector.drain_disconnected();
+        for node in &disconnected {
+            self.process_disconnection(state, node, now, responses);
+        }
+        if !disconnected.is_empty() {
+            self.metadata_updater.request_update();
+        }
+    }
+
+    /// Record any newly completed connections
+    fn handle_connections(&self, state: &mut NetworkClientState) {
+        for node in state.selector.drain_connected() {
+            // We are now connected.  Note that we might not still be able to send requests. For instance,
+            // if SSL is enabled, the SSL handshake happens after the connection is established.
+            // Therefore, it is still necessary to check isChannelReady before attempting to send on this
+            // connection.
+            if self.discover_broker_versions {
+                let version = ApiKeys::ApiVersions.latest_version().unwrap_or_default();
+                state.connection_states.checking_api_versions(&node);
+                self.log.debug(format_args!(
+                    "Completed connection to node {}. Fetching API versions.",
+                    node
+                ));
+                state.nodes_needing_api_versions_fetch.insert(node, version);
+            } else {
+                state.connection_states.ready(&node);
+                self.log.debug(format_args!(
+                    "Completed connection to node {}. Ready.",
+                    node
+                ));
+            }
+        }
+    }
+
+    fn handle_initiate_api_version_requests(&self, state: &mut NetworkClientState, now: u128) {
+        let nodes: Vec<(String, i16)> = state
+            .nodes_needing_api_versions_fetch
+            .iter()
+            .map(|(node, version)| (node.clone(), *version))
+            .collect();
+        for (node, version) in nodes {
+            if state.selector.is_channel_ready(&node)
+                && state.in_flight_requests.can_send_more(&node)
+            {
+                self.log.debug(format_args!(
+                    "Initiating API versions fetch from node {}.",
+                    node
+                ));
+                state.nodes_needing_api_versions_fetch.remove(&node);
+                let request = self.new_client_request(
+                    &node,
+                    AbstractRequest::ApiVersions(ApiVersionsRequest::new()),
+                    now,
+                    true,
+                    self.default_request_timeout_ms,
+                    None,
+                );
+                self.send_with_version(state, request, true, now, version);
+            }
+        }
+    }
+
+    /// Handle socket channel connection timeout. The timeout will hit iff a connection
+    /// stays at the ConnectionState.CONNECTING state longer than the timeout value,
+    /// as indicated by ClusterConnectionStates.NodeConnectionState.
+    fn handle_timed_out_connections(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        now: u128,
+    ) {
+        for node_id in state
+            .connection_states
+            .nodes_with_connection_setup_timeout(now)
+        {
+            state.selector.close(&node_id);
+            self.log.info(format_args!(
+                "Disconnecting from node {} due to socket connection setup timeout. The timeout value is {} ms.",
+                node_id,
+                state.connection_states.connection_setup_timeout_ms(&node_id)
+            ));
+            self.process_disconnection(state, &node_id, now, responses);
+        }
+    }
+
+    /// Iterate over all the inflight requests and expire any requests that have exceeded the configured requestTimeout.
+    /// The connection to the node associated with the request will be terminated and will be treated as a disconnection.
+    fn handle_timed_out_requests(
+        &self,
+        state: &mut NetworkClientState,
+        responses: &mut Vec<Completion>,
+        now: u128,
+    ) {
+        let node_ids = state.in_flight_requests.nodes_with_timed_out_requests(now);
+        for node_id in &node_ids {
+            // close connection to the node
+            state.selector.close(node_id);
+            self.log.info(format_args!(
+                "Disconnecting from node {} due to request timeout.",
+                node_id
+            ));
+            self.process_disconnection(state, node_id, now, responses);
+        }
+        if !node_ids.is_empty() {
+            self.metadata_updater.request_update();
+        }
+    }
+
+    /// Invoke the completion handlers of the responses, which must not be done holding the lock.
+    fn complete_responses(&self, completions: Vec<Completion>) -> Vec<ClientResponse> {
+        let mut responses = Vec::with_capacity(completions.len());
+        for (response, callback) in completions {
+            if let Some(callback) = callback {
+                let copy = response.clone();
+                if panic::catch_unwind(AssertUnwindSafe(|| callback(copy))).is_err() {
+                    self.log.error(format_args!(
+                        "Uncaught error in request completion of {}",
+                        response.request_header
+                    ));
+                }
+            }
+            responses.push(response);
+        }
+        responses
+    }
+}
+
+impl KafkaClient for NetworkClient {
+    fn is_ready(&self, node: &Node, now: u128) -> bool {
+        let state = self.lock();
+        // if we need to update our metadata now declare all requests unready to make metadata requests first
+        // priority
+        !self.is_metadata_update_due(&state, now)
+            && Self::can_send_request(&state, &node.id_string(), now)
+    }
+
+    fn ready(&self, node: &Node, now: u128) -> bool {
+        if node.is_empty() {
+            self.log
+                .error(format_args!("Cannot connect to empty node {}", node));
+            return false;
+        }
+        let mut state = self.lock();
+        if !self.is_metadata_update_due(&state, now)
+            && Self::can_send_request(&state, &node.id_string(), now)
+        {
+            return true;
+        }
+        // if we are interested in sending to a node and we don't have a connection to it, initiate one
+        if state.connection_states.can_connect(&node.id_string(), now) {
+            self.initiate_connect(&mut state, node, now);
+        }
+        false
+    }
+
+    fn connection_delay(&self, node: &Node, now: u128) -> u128 {
+        self.lock()
+            .connection_states
+            .connection_delay(&node.id_string(), now)
+    }
+
+    fn poll_delay_ms(&self, node: &Node, now: u128) -> u128 {
+        self.lock()
+            .connection_states
+            .poll_delay_ms(&node.id_string(), now)
+    }
+
+    fn connection_failed(&self, node: &Node) -> bool {
+        self.lock()
+            .connection_states
+            .is_disconnected(&node.id_string())
+    }
+
+    fn send(&self, request: ClientRequest, now: u128) {
+        let mut state = self.lock();
+        self.do_send(&mut state, request, false, now);
+    }
+
+    fn poll(&self, timeout: u128, now: u128) -> Vec<ClientResponse> {
+        if !self.active() {
+            self.log.debug(format_args!(
+                "Ignoring poll since the NetworkClient is no longer active"
+            ));
+            return vec![];
+        }
+        let completions = {
+            let mut state = self.lock();
+            if !state.aborted_sends.is_empty() {
+                // If there are aborted sends because of unsupported version exceptions or disconnects,
+                // handle them immediately without waiting for Selector#poll.
+                std::mem::take(&mut state.aborted_sends)
+            } else {
+                let metadata_timeout = self.maybe_update_metadata(&mut state, now);
+                let poll_timeout = timeout
+                    .min(metadata_timeout)
+                    .min(self.default_request_timeout_ms);
+                if let Err(e) = state.selector.poll(poll_timeout) {
+                    self.log
+                        .error(format_args!("Unexpected error during I/O: {}", e));
+                }
+
+                // process completed actions
+                let updated_now = self.time.milliseconds();
+                let mut responses = vec![];
+                self.handle_completed_sends(&mut state, &mut responses, updated_now);
+                self.handle_completed_receives(&mut state, &mut responses, updated_now);
+                self.handle_disconnections(&mut state, &mut responses, updated_now);
+                self.handle_connections(&mut state);
+                self.handle_initiate_api_version_requests(&mut state, updated_now);
+                self.handle_timed_out_connections(&mut state, &mut responses, updated_now);
+                self.handle_timed_out_requests(&mut state, &mut responses, updated_now);
+                responses
+            }
+        };
+        self.complete_responses(completions)
+    }
+
+    fn disconnect(&self, node_id: &str) {
+        let mut state = self.lock();
+        if state.connection_states.is_disconnected(node_id) {
+            return;
+        }
+        state.selector.close(node_id);
+        let now = self.time.milliseconds();
+        let mut responses = vec![];
+        self.cancel_in_flight_requests(&mut state, node_id, now, &mut responses);
+        state.aborted_sends.append(&mut responses);
+        state.connection_states.disconnected(node_id, now);
+    }
+
+    fn least_loaded_node(&self, now: u128) -> Option<Node> {
+        let state = self.lock();
+        self.least_loaded_node_locked(&state, now)
+    }
+
+    fn in_flight_request_count(&self) -> usize {
+        self.lock().in_flight_requests.total()
+    }
+
+    fn in_flight_request_count_for(&self, node_id: &str) -> usize {
+        self.lock().in_flight_requests.count(node_id)
+    }
+
+    fn has_ready_nodes(&self, now: u128) -> bool {
+        self.lock().connection_states.has_ready_nodes(now)
+    }
+
+    fn wakeup(&self) {
+        if let Err(e) = self.waker.wake() {
+            self.log
+                .error(format_args!("Failed to wake up the network client: {}", e));
+        }
+    }
+
+    fn new_client_request(
+        &self,
+        node_id: &str,
+        request: AbstractRequest,
+        created_time_ms: u128,
+        expect_response: bool,
+        request_timeout_ms: u128,
+        callback: Option<RequestCompletionHandler>,
+    ) -> ClientRequest {
+        ClientRequest::new(
+            node_id,
+            request,
+            self.next_correlation_id(),
+            self.client_id.clone(),
+            created_time_ms,
+            expect_response,
+            request_timeout_ms,
+            callback,
+        )
+    }
+
+    fn initiate_close(&self) {
+        if self
+            .client_state
+            .compare_exchange(ACTIVE, CLOSING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            self.wakeup();
+        }
+    }
+
+    fn active(&self) -> bool {
+        self.client_state.load(Ordering::SeqCst) == ACTIVE
+    }
+
+    fn close(&self) {
+        if self.client_state.swap(CLOSED, Ordering::SeqCst) == CLOSED {
+            self.log.warn(format_args!(
+                "Attempting to close NetworkClient that has already been closed."
+            ));
+            return;
+        }
+        self.lock().selector.close_all();
+        self.metadata_updater.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        net::{Shutdown, TcpListener, TcpStream},
+        sync::{Arc, Mutex},
+        thread::{self, JoinHandle},
+    };
+
+    use bytes::{BufMut, Bytes, BytesMut};
+
+    use crate::{
+        clients::{
+            api_versions::ApiVersions,
+            client_response::ClientResponse,
+            kafka_client::KafkaClient,
+            metadata_updater::{ManualMetadataUpdater, MetadataUpdater},
+            producer::internals::producer_metadata::ProducerMetadata,
+        },
+        common::{
+            errors::KafkaError,
+            network::{network_receive::NetworkReceive, selector::Selector},
+            node::Node,
+            protocol::{api_keys::ApiKeys, types},
+            requests::{
+                abstract_request::AbstractRequest, abstract_response::AbstractResponse,
+                heartbeat_request::HeartbeatRequest, request_header::RequestHeader,
+            },
+            topic_partition::TopicPartition,
+            utils::{
+                log_context::LogContext,
+                time::{SystemTime, Time},
+            },
+        },
+    };
+
+    use super::NetworkClient;
+
+    const REQUEST_TIMEOUT_MS: u128 = 1000;
+
+    /// What the fake broker does with a request.
+    enum Reply {
+        Body(BytesMut),
+        Ignore,
+        Close,
+    }
+
+    type Handler = Box<dyn FnMut(&RequestHeader) -> Reply + Send>;
+
+    /// A broker on a local socket, answering the requests of a single connection.
+    struct FakeBroker {
+        node: Node,
+        requests: Arc<Mutex<Vec<RequestHeader>>>,
+        handle: Option<JoinHandle<()>>,
+    }
+
+    impl FakeBroker {
+        fn start(mut handler: Handler) -> FakeBroker {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let requests = Arc::new(Mutex::new(vec![]));
+            let received = requests.clone();
+            let handle = thread::spawn(move || {
+                let (mut stream, _) = listener.accept().unwrap();
+                while let Some(mut payload) = read_frame(&mut stream) {
+                    let api_key = ApiKeys::for_id(types::read_i16(&mut payload).unwrap()).unwrap();
+                    let api_version = types::read_i16(&mut payload).unwrap();
+                    let correlation_id = types::read_i32(&mut payload).unwrap();
+                    let client_id = types::read_nullable_string(&mut payload).unwrap();
+                    let header = RequestHeader::new(
+                        api_key,
+                        api_version,
+                        client_id.unwrap_or_default(),
+                        correlation_id,
+                    );
+                    received.lock().unwrap().push(header.clone());
+                    match handler(&header) {
+                        Reply::Body(body) => {
+                            let mut response = BytesMut::new();
+                            response.put_i32(4 + body.len() as i32);
+                            response.put_i32(correlation_id);
+                            response.put_slice(&body);
+                            stream.write_all(&response).unwrap();
+                        }
+                        Reply::Ignore => {}
+                        Reply::Close => {
+                            let _ = stream.shutdown(Shutdown::Both);
+                            return;
+                        }
+                    }
+                }
+            });
+            FakeBroker {
+                node: Node::new(0, "127.0.0.1", port as i32),
+                requests,
+                handle: Some(handle),
+            }
+        }
+
+        fn requests(&self) -> Vec<RequestHeader> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl Drop for FakeBroker {
+        fn drop(&mut self) {
+            if let Some(handle) = self.handle.take() {
+                if !thread::panicking() {
+                    handle.join().unwrap();
+                }
+            }
+        }
+    }
+
+    fn read_frame(stream: &mut TcpStream) -> Option<Bytes> {
+        let mut receive = NetworkReceive::new("client", NetworkReceive::UNLIMITED);
+        while !receive.complete() {
+            receive.read_from(stream).ok()?;
+        }
+        Some(receive.payload())
+    }
+
+    /// The body of an `ApiVersionsResponse` in version 1 and above.
+    fn api_versions_body(error: i16, versions: &[(ApiKeys, i16, i16)]) -> BytesMut {
+        let mut body = BytesMut::new();
+        body.put_i16(error);
+        types::write_array(&mut body, versions, |body, (api_key, min, max)| {
+            body.put_i16(api_key.id());
+            body.put_i16(*min);
+            body.put_i16(*max);
+        });
+        body.put_i32(0);
+        body
+    }
+
+    fn heartbeat_body(throttle_time_ms: i32) -> BytesMut {
+        let mut body = BytesMut::new();
+        body.put_i32(throttle_time_ms);
+        body.put_i16(0);
+        body
+    }
+
+    /// Answers the api versions request, then the heartbeats.
+    fn heartbeat_broker(heartbeat_versions: (i16, i16)) -> FakeBroker {
+        FakeBroker::start(Box::new(move |header| match header.api_key {
+            ApiKeys::ApiVersions => Reply::Body(api_versions_body(
+                0,
+                &[
+                    (ApiKeys::ApiVersions, 0, 3),
+                    (
+                        ApiKeys::Heartbeat,
+                        heartbeat_versions.0,
+                        heartbeat_versions.1,
+                    ),
+                ],
+            )),
+            ApiKeys::Heartbeat => Reply::Body(heartbeat_body(0)),
+            _ => Reply::Close,
+        }))
+    }
+
+    fn network_client(
+        updater: Arc<dyn MetadataUpdater>,
+        request_timeout_ms: u128,
+    ) -> NetworkClient {
+        let time: Arc<dyn Time> = Arc::new(SystemTime);
+        let log_context = LogContext::new("[Test clientId=test] ");
+        NetworkClient::new(
+            Selector::new(None, NetworkReceive::UNLIMITED, time.clone(), &log_context).unwrap(),
+            updater,
+            "test",
+            5,
+            50,
+            1000,
+            request_timeout_ms,
+            10_000,
+            30_000,
+            time,
+            true,
+            Arc::new(ApiVersions::new()),
+            &log_context,
+        )
+    }
+
+    fn manual_client(node: &Node, request_timeout_ms: u128) -> NetworkClient {
+        network_client(
+            Arc::new(ManualMetadataUpdater::new(vec![node.clone()])),
+            request_timeout_ms,
+        )
+    }
+
+    /// Poll the client until `done` holds for the responses received so far.
+    fn poll_until(
+        client: &NetworkClient,
+        mut done: impl FnMut(&NetworkClient, &[ClientResponse]) -> bool,
+    ) -> Vec<ClientResponse> {
+        let deadline = SystemTime.milliseconds() + 5000;
+        let mut responses = vec![];
+        while !done(client, &responses) {
+            assert!(SystemTime.milliseconds() < deadline, "Timed out polling");
+            responses.extend(client.poll(50, SystemTime.milliseconds()));
+        }
+        responses
+    }
+
+    fn await_ready(client: &NetworkClient, node: &Node) {
+        poll_until(client, |client, _| {
+            client.ready(node, SystemTime.milliseconds())
+        });
+    }
+
+    fn heartbeat(client: &NetworkClient, node: &Node, callback: Arc<Mutex<Vec<ClientResponse>>>) {
+        let now = SystemTime.milliseconds();
+        let request = client.new_client_request(
+            &node.id_string(),
+            AbstractRequest::Heartbeat(HeartbeatRequest::new("group", 1, "member", None)),
+            now,
+            true,
+            REQUEST_TIMEOUT_MS,
+            Some(Box::new(move |response| {
+                callback.lock().unwrap().push(response)
+            })),
+        );
+        client.send(request, now);
+    }
+
+    #[test]
+    fn send_after_fetching_api_versions() {
+        let broker = heartbeat_broker((0, 4));
+        let client = manual_client(&broker.node, REQUEST_TIMEOUT_MS);
+        assert!(!client.ready(&broker.node, SystemTime.milliseconds()));
+        await_ready(&client, &broker.node);
+
+        let callback = Arc::new(Mutex::new(vec![]));
+        heartbeat(&client, &broker.node, callback.clone());
+        assert_eq!(client.in_flight_request_count(), 1);
+        let responses = poll_until(&client, |_, responses| !responses.is_empty());
+
+        assert_eq!(responses.len(), 1);
+        let response = &responses[0];
+        assert!(!response.disconnected);
+        assert_eq!(response.request_header.api_key, ApiKeys::Heartbeat);
+        // the latest version usable with both the client and the broker
+        assert_eq!(response.request_header.api_version, 3);
+        assert!(matches!(
+            response.response_body,
+            Some(AbstractResponse::Heartbeat(_))
+        ));
+        assert_eq!(callback.lock().unwrap().len(), 1);
+        assert_eq!(client.in_flight_request_count(), 0);
+
+        let requests = broker.requests();
+        assert_eq!(requests[0].api_key, ApiKeys::ApiVersions);
+        assert_eq!(requests[0].api_version, 2);
+        assert_eq!(requests[0].client_id, "test");
+        assert_eq!(requests[1].api_key, ApiKeys::Heartbeat);
+        assert_eq!(
+            requests[1].correlation_id,
+            response.request_header.correlation_id
+        );
+        client.close();
+    }
+
+    #[test]
+    fn unsupported_api_versions_version_is_retried_with_the_broker_version() {
+        let broker = FakeBroker::start(Box::new(|header| match header.api_version {
+            // a broker only supporting the version 0 answers with the version 0 of the response
+            2 => {
+                let mut body = BytesMut::new();
+                body.put_i16(35);
+                types::write_array(&mut body, [(ApiKeys::ApiVersions.id(), 0, 0)], |body, v| {
+                    body.put_i16(v.0);
+                    body.put_i16(v.1);
+                    body.put_i16(v.2);
+                });
+                Reply::Body(body)
+            }
+            0 => {
+                let mut body = api_versions_body(0, &[(ApiKeys::ApiVersions, 0, 0)]);
+                body.truncate(body.len() - 4);
+                Reply::Body(body)
+            }
+            _ => Reply::Close,
+        }));
+        let client = manual_client(&broker.node, REQUEST_TIMEOUT_MS);
+        client.ready(&broker.node, SystemTime.milliseconds());
+        await_ready(&client, &broker.node);
+
+        let versions: Vec<i16> = broker.requests().iter().map(|r| r.api_version).collect();
+        assert_eq!(versions, vec![2, 0]);
+        client.close();
+    }
+
+    #[test]
+    fn version_mismatch_is_returned_by_the_next_poll() {
+        let broker = heartbeat_broker((5, 6));
+        let client = manual_client(&broker.node, REQUEST_TIMEOUT_MS);
+        await_ready(&client, &broker.node);
+
+        let callback = Arc::new(Mutex::new(vec![]));
+        heartbeat(&client, &broker.node, callback.clone());
+        assert_eq!(client.in_flight_request_count(), 0);
+        let responses = client.poll(0, SystemTime.milliseconds());
+
+        assert_eq!(responses.len(), 1);
+        assert!(matches!(
+            responses[0].version_mismatch,
+            Some(KafkaError::UnsupportedVersion(_))
+        ));
+        assert!(responses[0].response_body.is_none());
+        assert_eq!(callback.lock().unwrap().len(), 1);
+        client.close();
+    }
+
+    #[test]
+    fn requests_without_response_disconnect_after_the_timeout() {
+        let broker = FakeBroker::start(Box::new(|header| match header.api_key {
+            ApiKeys::ApiVersions => {
+                Reply::Body(api_versions_body(0, &[(ApiKeys::Heartbeat, 0, 3)]))
+            }
+            ApiKeys::Heartbeat => Reply::Ignore,
+            _ => Reply::Close,
+        }));
+        let client = manual_client(&broker.node, 200);
+        await_ready(&client, &broker.node);
+
+        let callback = Arc::new(Mutex::new(vec![]));
+        heartbeat(&client, &broker.node, callback);
+        // the timeout of the request applies, not the default one of the client
+        let start = SystemTime.milliseconds();
+        let responses = poll_until(&client, |_, responses| !responses.is_empty());
+        assert!(responses[0].disconnected);
+        assert!(SystemTime.milliseconds() - start >= REQUEST_TIMEOUT_MS);
+        assert!(client.connection_failed(&broker.node));
+        assert_eq!(client.in_flight_request_count(), 0);
+        client.close();
+    }
+
+    #[test]
+    fn in_flight_requests_are_cancelled_when_the_broker_disconnects() {
+        let broker = FakeBroker::start(Box::new(|header| match header.api_key {
+            ApiKeys::ApiVersions => {
+                Reply::Body(api_versions_body(0, &[(ApiKeys::Heartbeat, 0, 3)]))
+            }
+            _ => Reply::Close,
+        }));
+        let client = manual_client(&broker.node, REQUEST_TIMEOUT_MS);
+        await_ready(&client, &broker.node);
+
+        let callback = Arc::new(Mutex::new(vec![]));
+        heartbeat(&client, &broker.node, callback.clone());
+        let responses = poll_until(&client, |_, responses| !responses.is_empty());
+
+        assert!(responses[0].disconnected);
+        assert_eq!(callback.lock().unwrap().len(), 1);
+        assert!(client.connection_failed(&broker.node));
+        // the reconnect backoff applies
+        assert!(!client.ready(&broker.node, SystemTime.milliseconds()));
+        assert!(client.connection_delay(&broker.node, SystemTime.milliseconds()) > 0);
+        client.close();
+    }
+
+    #[test]
+    fn failed_connection_is_backed_off() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let node = Node::new(0, "127.0.0.1", port as i32);
+        let client = manual_client(&node, REQUEST_TIMEOUT_MS);
+        assert!(!client.ready(&node, SystemTime.milliseconds()));
+        poll_until(&client, |client, _| client.connection_failed(&node));
+        assert!(client
+            .least_loaded_node(SystemTime.milliseconds())
+            .is_none());
+        client.close();
+    }
+
+    #[test]
+    fn wakeup_interrupts_poll() {
+        let node = Node::new(0, "127.0.0.1", 9092);
+        let client = Arc::new(manual_client(&node, REQUEST_TIMEOUT_MS));
+        let waker = client.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(50));
+            waker.wakeup();
+        });
+        let start = SystemTime.milliseconds();
+        client.poll(60_000, start);
+        assert!(SystemTime.milliseconds() - start < 5000);
+        handle.join().unwrap();
+
+        client.initiate_close();
+        assert!(!client.active());
+        client.close();
+    }
+
+    #[test]
+    fn metadata_is_updated_from_the_bootstrap_node() {
+        let broker = FakeBroker::start(Box::new(move |header| match header.api_key {
+            ApiKeys::ApiVersions => Reply::Body(api_versions_body(0, &[(ApiKeys::Metadata, 0, 8)])),
+            ApiKeys::Metadata => {
+                let mut body = BytesMut::new();
+                body.put_i32(0);
+                // brokers
+                types::write_array(&mut body, [0], |body, id| {
+                    body.put_i32(id);
+                    types::write_string(body, "127.0.0.1");
+                    body.put_i32(9092);
+                    types::write_nullable_string(body, None);
+                });
+                types::write_nullable_string(&mut body, Some("cluster"));
+                body.put_i32(0);
+                // topics
+                types::write_array(&mut body, ["topic"], |body, topic| {
+                    body.put_i16(0);
+                    types::write_string(body, topic);
+                    types::write_bool(body, false);
+                    types::write_array(body, [0], |body, partition| {
+                        body.put_i16(0);
+                        body.put_i32(partition);
+                        body.put_i32(0);
+                        body.put_i32(3);
+                        types::write_i32_array(body, &[0]);
+                        types::write_i32_array(body, &[0]);
+                        types::write_i32_array(body, &[]);
+                    });
+                    body.put_i32(0);
+                });
+                body.put_i32(0);
+                Reply::Body(body)
+            }
+            _ => Reply::Close,
+        }));
+        let metadata = Arc::new(ProducerMetadata::new(
+            100,
+            60_000,
+            60_000,
+            Arc::new(SystemTime),
+        ));
+        metadata.bootstrap(&[("127.0.0.1".to_owned(), broker.node.port)]);
+        metadata.add("topic", SystemTime.milliseconds());
+        let version = metadata.update_version();
+        let client = network_client(metadata.clone(), REQUEST_TIMEOUT_MS);
+
+        poll_until(&client, |_, _| metadata.update_version() > version);
+
+        let cluster = metadata.fetch();
+        assert!(!cluster.is_bootstrap_configured);
+        assert_eq!(cluster.cluster_id.as_deref(), Some("cluster"));
+        let leader = cluster
+            .leader_for(&TopicPartition::new("topic", 0))
+            .unwrap();
+        assert_eq!(leader.id, 0);
+        assert!(metadata.new_topics().is_empty());
+        let requests = broker.requests();
+        assert_eq!(requests[1].api_key, ApiKeys::Metadata);
+        assert_eq!(requests[1].api_version, 8);
+        client.close();
+    }
+}
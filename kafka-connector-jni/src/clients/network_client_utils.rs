@@ -0,0 +1,54 @@
+use crate::common::{
+    errors::{KafkaError, Result},
+    node::Node,
+    utils::time::Time,
+};
+
+use super::kafka_client::KafkaClient;
+
+/// Checks whether the node is currently connected, first calling `client.poll` to ensure that any pending
+/// disconnects have been processed.
+///
+/// This method can be used to check the status of a connection prior to calling the blocking version to be able
+/// to tell whether the latter completed a new connection.
+pub fn is_ready(client: &dyn KafkaClient, node: &Node, current_time: u128) -> bool {
+    client.poll(0, current_time);
+    client.is_ready(node, current_time)
+}
+
+/// Invokes `client.poll` to discard pending disconnects, followed by `client.ready` and 0 or more `client.poll`
+/// invocations until the connection to `node` is ready, the timeout expires or the connection fails.
+///
+/// It returns `true` if the call completes normally or `false` if the timeout expires. If the connection fails,
+/// a `Network` error is returned.
+///
+/// This method is useful for implementing blocking behaviour on top of the non-blocking `KafkaClient`, use it with
+/// care.
+pub fn await_ready(
+    client: &dyn KafkaClient,
+    node: &Node,
+    time: &dyn Time,
+    timeout_ms: u128,
+) -> Result<bool> {
+    let start_time = time.milliseconds();
+
+    if is_ready(client, node, start_time) || client.ready(node, start_time) {
+        return Ok(true);
+    }
+
+    let mut attempt_start_time = time.milliseconds();
+    while !client.is_ready(node, attempt_start_time)
+        && attempt_start_time.saturating_sub(start_time) < timeout_ms
+    {
+        if client.connection_failed(node) {
+            return Err(KafkaError::Network(format!(
+                "Connection to {} failed.",
+                node
+            )));
+        }
+        let poll_timeout = timeout_ms - attempt_start_time.saturating_sub(start_time);
+        client.poll(poll_timeout, attempt_start_time);
+        attempt_start_time = time.milliseconds();
+    }
+    Ok(client.is_ready(node, attempt_start_time))
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use jni::objects::{GlobalRef, JObject, JValue};
+
+use crate::{
+    clone_to_java::CloneToJava, common::errors::KafkaError, java_vm::CallbackDispatcher, jni_cache,
+};
+
+use super::record_metadata::RecordMetadata;
+
+/// A callback interface that the user can implement to allow code to execute when the request is
+/// complete. This callback will generally execute in the background I/O thread so it should be fast.
+///
+/// Exactly one of metadata or error is provided: metadata of the record that was sent, or the
+/// error thrown during processing of this record.
+pub type Callback = Box<dyn FnOnce(Result<RecordMetadata, KafkaError>) + Send>;
+
+/// Callback calling `onCompletion` of the java `org.apache.kafka.clients.producer.Callback`
+/// `callback` on the `dispatcher` thread. Exceptions thrown by it are logged, like
+/// `KafkaProducer` does.
+pub fn java_callback(dispatcher: Arc<CallbackDispatcher>, callback: GlobalRef) -> Callback {
+    Box::new(move |result| {
+        dispatcher.execute(move |env| {
+            let (metadata, exception) = match &result {
+                Ok(metadata) => (metadata.clone_to_java(env)?, JObject::null()),
+                Err(error) => (
+                    JValue::Object(JObject::null()),
+                    error.to_java_exception(env)?,
+                ),
+            };
+            jni_cache::call_method(
+                env,
+                callback.as_obj(),
+                "org/apache/kafka/clients/producer/Callback",
+                "onCompletion",
+                "(Lorg/apache/kafka/clients/producer/RecordMetadata;Ljava/lang/Exception;)V",
+                &[metadata, exception.into()],
+            )?;
+            Ok(())
+        })
+    })
+}
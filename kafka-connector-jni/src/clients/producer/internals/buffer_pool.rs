@@ -0,0 +1,370 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use bytes::BytesMut;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    utils::time::Time,
+};
+
+/// A pool of byte buffers kept under a given memory limit. This class is fairly specific to the
+/// needs of the producer. In particular it has the following properties:
+///
+/// 1. There is a special "poolable size" and buffers of this size are kept in a free list and recycled
+/// 2. It is fair. That is all memory is given to the longest waiting thread until it has sufficient
+///    memory. This prevents starvation or deadlock when a thread asks for a large chunk of memory
+///    and needs to block until multiple buffers are deallocated.
+pub struct BufferPool {
+    total_memory: usize,
+    poolable_size: usize,
+    state: Mutex<BufferPoolState>,
+    time: Arc<dyn Time>,
+}
+
+struct BufferPoolState {
+    free: VecDeque<BytesMut>,
+    waiters: VecDeque<Arc<Condvar>>,
+    /// Total available memory is the sum of non_pooled_available_memory and the number of byte
+    /// buffers in free * poolable_size.
+    non_pooled_available_memory: usize,
+    closed: bool,
+}
+
+impl BufferPoolState {
+    /// Attempt to ensure we have at least the requested number of bytes of memory for allocation
+    /// by deallocating pooled buffers (if needed)
+    fn free_up(&mut self, size: usize, poolable_size: usize) {
+        while !self.free.is_empty() && self.non_pooled_available_memory < size {
+            self.free.pop_back();
+            self.non_pooled_available_memory += poolable_size;
+        }
+    }
+
+    fn signal_first_waiter(&self) {
+        if let Some(waiter) = self.waiters.front() {
+            waiter.notify_one();
+        }
+    }
+}
+
+impl BufferPool {
+    /// Create a new buffer pool
+    ///
+    /// * `memory` - The maximum amount of memory that this buffer pool can allocate
+    /// * `poolable_size` - The buffer size to cache in the free list rather than deallocating
+    pub fn new(memory: usize, poolable_size: usize, time: Arc<dyn Time>) -> BufferPool {
+        BufferPool {
+            total_memory: memory,
+            poolable_size,
+            state: Mutex::new(BufferPoolState {
+                free: VecDeque::new(),
+                waiters: VecDeque::new(),
+                non_pooled_available_memory: memory,
+                closed: false,
+            }),
+            time,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BufferPoolState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Allocate a buffer of the given size. This method blocks if there is not enough memory and
+    /// the buffer pool is configured with blocking mode.
+    pub fn allocate(&self, size: usize, max_time_to_block_ms: u128) -> Result<BytesMut> {
+        if size > self.total_memory {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Attempt to allocate {} bytes, but there is a hard limit of {} on memory allocations.",
+                size, self.total_memory
+            )));
+        }
+
+        let state = self.lock();
+        if state.closed {
+            return Err(KafkaError::Kafka(
+                "Producer closed while allocating memory".to_owned(),
+            ));
+        }
+
+        let (state, result) = self.allocate_locked(state, size, max_time_to_block_ms);
+
+        // signal any additional waiters if there is more memory left over for them
+        if !(state.non_pooled_available_memory == 0 && state.free.is_empty()) {
+            state.signal_first_waiter();
+        }
+        drop(state);
+
+        match result? {
+            Some(buffer) => Ok(buffer),
+            None => Ok(BytesMut::with_capacity(size)),
+        }
+    }
+
+    fn allocate_locked<'a>(
+        &self,
+        mut state: MutexGuard<'a, BufferPoolState>,
+        size: usize,
+        max_time_to_block_ms: u128,
+    ) -> (MutexGuard<'a, BufferPoolState>, Result<Option<BytesMut>>) {
+        // check if we have a free buffer of the right size pooled
+        if size == self.poolable_size {
+            if let Some(buffer) = state.free.pop_front() {
+                return (state, Ok(Some(buffer)));
+            }
+        }
+
+        // now check if the request is immediately satisfiable with the
+        // memory on hand or if we need to block
+        let free_list_size = state.free.len() * self.poolable_size;
+        if state.non_pooled_available_memory + free_list_size >= size {
+            // we have enough unallocated or pooled memory to immediately
+            // satisfy the request, but need to allocate the buffer
+            state.free_up(size, self.poolable_size);
+            state.non_pooled_available_memory -= size;
+            return (state, Ok(None));
+        }
+
+        // we are out of memory and will have to block
+        let more_memory = Arc::new(Condvar::new());
+        state.waiters.push_back(more_memory.clone());
+        let mut accumulated = 0;
+        let mut buffer = None;
+        let mut remaining_time_to_block_ns = max_time_to_block_ms * 1_000_000;
+        let result = loop {
+            // loop over and over until we have a buffer or have reserved
+            // enough memory to allocate one
+            if accumulated >= size {
+                break Ok(buffer.take());
+            }
+            let start_wait_ns = self.time.nanoseconds();
+            let (guard, wait_result) = more_memory
+                .wait_timeout(
+                    state,
+                    Duration::from_nanos(remaining_time_to_block_ns.min(u64::MAX as u128) as u64),
+                )
+                .unwrap_or_else(|e| e.into_inner());
+            state = guard;
+            let time_ns = self.time.nanoseconds().saturating_sub(start_wait_ns);
+
+            if state.closed {
+                break Err(KafkaError::Kafka(
+                    "Producer closed while allocating memory".to_owned(),
+                ));
+            }
+
+            if wait_result.timed_out() {
+                break Err(KafkaError::BufferExhausted(format!(
+                    "Failed to allocate memory within the configured max blocking time {} ms.",
+                    max_time_to_block_ms
+                )));
+            }
+
+            remaining_time_to_block_ns = remaining_time_to_block_ns.saturating_sub(time_ns);
+
+            // check if we can satisfy this request from the free list,
+            // otherwise allocate memory
+            if accumulated == 0 && size == self.poolable_size && !state.free.is_empty() {
+                // just grab a buffer from the free list
+                buffer = state.free.pop_front();
+                accumulated = size;
+            } else {
+                // we'll need to allocate memory, but we may only get
+                // part of what we need on this iteration
+                state.free_up(size - accumulated, self.poolable_size);
+                let got = (size - accumulated).min(state.non_pooled_available_memory);
+                state.non_pooled_available_memory -= got;
+                accumulated += got;
+            }
+        };
+
+        if result.is_err() {
+            // When this loop was not able to successfully terminate don't loose available memory
+            state.non_pooled_available_memory += accumulated;
+        }
+        state
+            .waiters
+            .retain(|waiter| !Arc::ptr_eq(waiter, &more_memory));
+        (state, result)
+    }
+
+    /// Return buffers to the pool. If they are of the poolable size add them to the free list,
+    /// otherwise just mark the memory as free.
+    ///
+    /// * `buffer` - The buffer to return
+    /// * `size` - The size of the buffer to mark as deallocated, note that this may be smaller
+    ///   than buffer.capacity since the buffer may re-allocate itself during in-place compression
+    pub fn deallocate(&self, mut buffer: BytesMut, size: usize) {
+        let mut state = self.lock();
+        if size == self.poolable_size && size == buffer.capacity() {
+            buffer.clear();
+            state.free.push_back(buffer);
+        } else {
+            state.non_pooled_available_memory += size;
+        }
+        state.signal_first_waiter();
+    }
+
+    /// the total free memory both unallocated and in the free list
+    pub fn available_memory(&self) -> usize {
+        let state = self.lock();
+        state.non_pooled_available_memory + state.free.len() * self.poolable_size
+    }
+
+    /// Get the unallocated memory (not in the free list or in use)
+    pub fn unallocated_memory(&self) -> usize {
+        self.lock().non_pooled_available_memory
+    }
+
+    /// The number of threads blocked waiting on memory
+    pub fn queued(&self) -> usize {
+        self.lock().waiters.len()
+    }
+
+    /// The buffer size that will be retained in the free list after use
+    pub fn poolable_size(&self) -> usize {
+        self.poolable_size
+    }
+
+    /// The total memory managed by this pool
+    pub fn total_memory(&self) -> usize {
+        self.total_memory
+    }
+
+    /// Closes the buffer pool. Memory will be prevented from being allocated, but may be
+    /// deallocated. All allocations awaiting available memory will be notified to abort.
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        for waiter in &state.waiters {
+            waiter.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use crate::common::{errors::KafkaError, utils::mock_time::MockTime};
+
+    use super::BufferPool;
+
+    const TOTAL_MEMORY: usize = 64 * 1024;
+    const MAX_BLOCK_TIME_MS: u128 = 2_000;
+
+    fn buffer_pool(total_memory: usize, poolable_size: usize) -> Arc<BufferPool> {
+        Arc::new(BufferPool::new(
+            total_memory,
+            poolable_size,
+            Arc::new(MockTime::default()),
+        ))
+    }
+
+    fn await_queued(pool: &BufferPool, queued: usize) {
+        while pool.queued() != queued {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn poolable_buffers_are_recycled() {
+        let size = 512;
+        let pool = buffer_pool(TOTAL_MEMORY, size);
+        let buffer = pool.allocate(size, MAX_BLOCK_TIME_MS).unwrap();
+        assert_eq!(size, buffer.capacity());
+        assert_eq!(TOTAL_MEMORY - size, pool.unallocated_memory());
+        assert_eq!(TOTAL_MEMORY - size, pool.available_memory());
+
+        pool.deallocate(buffer, size);
+        assert_eq!(TOTAL_MEMORY, pool.available_memory());
+        assert_eq!(TOTAL_MEMORY - size, pool.unallocated_memory());
+
+        let buffer = pool.allocate(size, MAX_BLOCK_TIME_MS).unwrap();
+        assert_eq!(TOTAL_MEMORY - size, pool.unallocated_memory());
+        assert_eq!(TOTAL_MEMORY - size, pool.available_memory());
+        pool.deallocate(buffer, size);
+
+        // buffers of other sizes are not pooled, the pooled one stays in the free list
+        let buffer = pool.allocate(2 * size, MAX_BLOCK_TIME_MS).unwrap();
+        assert_eq!(TOTAL_MEMORY - 3 * size, pool.unallocated_memory());
+        pool.deallocate(buffer, 2 * size);
+        assert_eq!(TOTAL_MEMORY - size, pool.unallocated_memory());
+        assert_eq!(TOTAL_MEMORY, pool.available_memory());
+    }
+
+    #[test]
+    fn cant_allocate_more_memory_than_the_pool_has() {
+        let pool = buffer_pool(1024, 512);
+        assert!(matches!(
+            pool.allocate(1025, MAX_BLOCK_TIME_MS),
+            Err(KafkaError::IllegalArgument(_))
+        ));
+    }
+
+    #[test]
+    fn allocation_waits_until_memory_is_deallocated() {
+        let pool = buffer_pool(1024, 1024);
+        let buffer = pool.allocate(1024, MAX_BLOCK_TIME_MS).unwrap();
+
+        let waiting_pool = pool.clone();
+        let waiting = thread::spawn(move || waiting_pool.allocate(1024, 60_000));
+        await_queued(&pool, 1);
+        pool.deallocate(buffer, 1024);
+
+        assert_eq!(1024, waiting.join().unwrap().unwrap().capacity());
+        assert_eq!(0, pool.queued());
+        assert_eq!(0, pool.available_memory());
+    }
+
+    #[test]
+    fn allocation_gathers_memory_from_several_deallocations() {
+        let pool = buffer_pool(1024, 256);
+        let first = pool.allocate(512, MAX_BLOCK_TIME_MS).unwrap();
+        let second = pool.allocate(512, MAX_BLOCK_TIME_MS).unwrap();
+
+        let waiting_pool = pool.clone();
+        let waiting = thread::spawn(move || waiting_pool.allocate(1024, 60_000));
+        await_queued(&pool, 1);
+        pool.deallocate(first, 512);
+        pool.deallocate(second, 512);
+
+        assert_eq!(1024, waiting.join().unwrap().unwrap().capacity());
+        assert_eq!(0, pool.available_memory());
+    }
+
+    #[test]
+    fn allocation_times_out_without_losing_memory() {
+        let pool = buffer_pool(1024, 256);
+        let _first = pool.allocate(512, MAX_BLOCK_TIME_MS).unwrap();
+        let _second = pool.allocate(256, MAX_BLOCK_TIME_MS).unwrap();
+
+        assert!(matches!(
+            pool.allocate(512, 10),
+            Err(KafkaError::BufferExhausted(_))
+        ));
+        assert_eq!(0, pool.queued());
+        assert_eq!(256, pool.available_memory());
+    }
+
+    #[test]
+    fn close_aborts_waiting_allocations() {
+        let pool = buffer_pool(1024, 1024);
+        let _buffer = pool.allocate(1024, MAX_BLOCK_TIME_MS).unwrap();
+
+        let waiting_pool = pool.clone();
+        let waiting = thread::spawn(move || waiting_pool.allocate(1024, 60_000));
+        await_queued(&pool, 1);
+        pool.close();
+
+        assert!(matches!(waiting.join().unwrap(), Err(KafkaError::Kafka(_))));
+        assert!(matches!(
+            pool.allocate(1, MAX_BLOCK_TIME_MS),
+            Err(KafkaError::Kafka(_))
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{
+    clients::producer::partitioner::Partitioner,
+    common::{
+        cluster::Cluster,
+        errors::{KafkaError, Result},
+        utils,
+    },
+};
+
+use super::sticky_partition_cache::StickyPartitionCache;
+
+/// The default partitioning strategy:
+///  - If a partition is specified in the record, use it
+///  - If no partition is specified but a key is present choose a partition based on a hash of the key
+///  - If no partition or key is present choose the sticky partition that changes when the batch is full.
+///
+/// See KIP-480 for details about sticky partitioning.
+#[derive(Default)]
+pub struct DefaultPartitioner {
+    sticky_partition_cache: StickyPartitionCache,
+}
+
+impl DefaultPartitioner {
+    pub fn new() -> DefaultPartitioner {
+        DefaultPartitioner::default()
+    }
+
+    /// Compute the partition for the given record.
+    ///
+    /// * `topic` - The topic name
+    /// * `key` - The serialized key to partition on (or `None` if no key)
+    /// * `cluster` - The current cluster metadata
+    /// * `num_partitions` - The number of partitions of the given topic
+    pub fn partition_with_count(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        cluster: &Cluster,
+        num_partitions: usize,
+    ) -> Result<i32> {
+        match key {
+            None => self.sticky_partition_cache.partition(topic, cluster),
+            Some(_) if num_partitions == 0 => Err(KafkaError::IllegalArgument(format!(
+                "Topic {} has no partitions",
+                topic
+            ))),
+            // hash the key to choose a partition
+            Some(key) => Ok(utils::to_positive(utils::murmur2(key)) % num_partitions as i32),
+        }
+    }
+}
+
+impl Partitioner for DefaultPartitioner {
+    fn partition(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        _value: Option<&[u8]>,
+        cluster: &Cluster,
+    ) -> Result<i32> {
+        self.partition_with_count(
+            topic,
+            key,
+            cluster,
+            cluster.partitions_for_topic(topic).len(),
+        )
+    }
+
+    /// If a batch completed for the current sticky partition, change the sticky partition.
+    /// Alternately, if no sticky partition has been determined, set one.
+    fn on_new_batch(&self, topic: &str, cluster: &Cluster, prev_partition: i32) {
+        // Without partitions there is no sticky partition to move, the next call to
+        // `partition` reports the error.
+        let _ = self
+            .sticky_partition_cache
+            .next_partition(topic, cluster, prev_partition);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        clients::producer::partitioner::Partitioner, common::node::Node,
+        test_utils::cluster_with_leaders,
+    };
+
+    use super::DefaultPartitioner;
+
+    const TOPIC: &str = "test";
+
+    #[test]
+    fn keyed_records_are_partitioned_by_the_murmur2_hash_of_the_key() {
+        let node = Node::new(0, "localhost", 9092);
+        // every partition counts, available or not
+        let cluster = cluster_with_leaders(
+            TOPIC,
+            &[
+                Some(node.clone()),
+                None,
+                Some(node.clone()),
+                None,
+                Some(node.clone()),
+                Some(node),
+            ],
+        );
+        let partitioner = DefaultPartitioner::new();
+        // partitions computed by the Java client for a topic of 6 partitions
+        let cases: [(&[u8], i32); 5] = [
+            (b"21", 0),
+            (b"key-1", 0),
+            (b"", 3),
+            (&[0xff], 3),
+            (&[0xde, 0xad, 0xbe, 0xef, 0x9a], 5),
+        ];
+        for (key, expected) in cases {
+            for _ in 0..3 {
+                assert_eq!(
+                    partitioner
+                        .partition(TOPIC, Some(key), None, &cluster)
+                        .unwrap(),
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn keyed_record_of_a_topic_without_partitions_is_an_error() {
+        let cluster = cluster_with_leaders(TOPIC, &[]);
+        assert!(DefaultPartitioner::new()
+            .partition(TOPIC, Some(b"key"), None, &cluster)
+            .is_err());
+    }
+
+    #[test]
+    fn records_without_key_stick_to_a_partition_until_a_new_batch() {
+        let node = Node::new(0, "localhost", 9092);
+        let cluster = cluster_with_leaders(TOPIC, &[Some(node.clone()), None, Some(node)]);
+        let partitioner = DefaultPartitioner::new();
+        let sticky = partitioner.partition(TOPIC, None, None, &cluster).unwrap();
+        // only available partitions are chosen
+        assert!(sticky == 0 || sticky == 2);
+        assert_eq!(
+            partitioner.partition(TOPIC, None, None, &cluster).unwrap(),
+            sticky
+        );
+
+        // a new batch of another partition does not move the sticky partition
+        partitioner.on_new_batch(TOPIC, &cluster, 1);
+        assert_eq!(
+            partitioner.partition(TOPIC, None, None, &cluster).unwrap(),
+            sticky
+        );
+        // a new batch of the sticky partition moves it to the other available one
+        partitioner.on_new_batch(TOPIC, &cluster, sticky);
+        assert_eq!(
+            partitioner.partition(TOPIC, None, None, &cluster).unwrap(),
+            2 - sticky
+        );
+    }
+}
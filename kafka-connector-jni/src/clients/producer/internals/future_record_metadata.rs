@@ -0,0 +1,154 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    clients::producer::record_metadata::RecordMetadata,
+    common::errors::{KafkaError, Result},
+};
+
+use super::produce_request_result::ProduceRequestResult;
+
+/// The future result of a record send.
+///
+/// It can be awaited from async code or blocked on with `get`/`get_timeout`. Clones share the
+/// same underlying state.
+#[derive(Clone)]
+pub struct FutureRecordMetadata {
+    inner: Arc<FutureRecordMetadataInner>,
+}
+
+struct FutureRecordMetadataInner {
+    result: Arc<ProduceRequestResult>,
+    batch_index: usize,
+    create_timestamp: i64,
+    serialized_key_size: i32,
+    serialized_value_size: i32,
+    next_record_metadata: Mutex<Option<FutureRecordMetadata>>,
+}
+
+impl FutureRecordMetadata {
+    pub fn new(
+        result: Arc<ProduceRequestResult>,
+        batch_index: usize,
+        create_timestamp: i64,
+        serialized_key_size: i32,
+        serialized_value_size: i32,
+    ) -> FutureRecordMetadata {
+        FutureRecordMetadata {
+            inner: Arc::new(FutureRecordMetadataInner {
+                result,
+                batch_index,
+                create_timestamp,
+                serialized_key_size,
+                serialized_value_size,
+                next_record_metadata: Mutex::new(None),
+            }),
+        }
+    }
+
+    fn next_record_metadata(&self) -> Option<FutureRecordMetadata> {
+        self.inner
+            .next_record_metadata
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Block until the record is acknowledged (or failed).
+    pub fn get(&self) -> Result<RecordMetadata> {
+        self.inner.result.await_completion();
+        if let Some(next_record_metadata) = self.next_record_metadata() {
+            return next_record_metadata.get();
+        }
+        self.value_or_error()
+    }
+
+    /// Block until the record is acknowledged (or failed), waiting at most `timeout`.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<RecordMetadata> {
+        let deadline = Instant::now().checked_add(timeout);
+        let occurred = self.inner.result.await_timeout(timeout);
+        if !occurred {
+            return Err(KafkaError::Timeout(format!(
+                "Timeout after waiting for {} ms.",
+                timeout.as_millis()
+            )));
+        }
+        if let Some(next_record_metadata) = self.next_record_metadata() {
+            let remaining = deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or(timeout);
+            return next_record_metadata.get_timeout(remaining);
+        }
+        self.value_or_error()
+    }
+
+    /// This method is used when we have to split a large batch in smaller ones. A chained metadata will allow the
+    /// future that has already returned to the users to wait on the newly created split batches even after the
+    /// old big batch has been deemed as done.
+    pub fn chain(&self, future_record_metadata: FutureRecordMetadata) {
+        let mut next_record_metadata = self
+            .inner
+            .next_record_metadata
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        match next_record_metadata.as_ref() {
+            None => *next_record_metadata = Some(future_record_metadata),
+            Some(next) => next.chain(future_record_metadata),
+        }
+    }
+
+    pub fn value_or_error(&self) -> Result<RecordMetadata> {
+        match self.inner.result.error(self.inner.batch_index) {
+            Some(error) => Err(error),
+            None => Ok(self.value()),
+        }
+    }
+
+    pub fn value(&self) -> RecordMetadata {
+        if let Some(next_record_metadata) = self.next_record_metadata() {
+            return next_record_metadata.value();
+        }
+        RecordMetadata::new(
+            self.inner.result.topic_partition().clone(),
+            self.inner.result.base_offset(),
+            self.inner.batch_index as i32,
+            self.timestamp(),
+            self.inner.serialized_key_size,
+            self.inner.serialized_value_size,
+        )
+    }
+
+    fn timestamp(&self) -> i64 {
+        if self.inner.result.has_log_append_time() {
+            self.inner.result.log_append_time()
+        } else {
+            self.inner.create_timestamp
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        if let Some(next_record_metadata) = self.next_record_metadata() {
+            return next_record_metadata.is_done();
+        }
+        self.inner.result.completed()
+    }
+}
+
+impl Future for FutureRecordMetadata {
+    type Output = Result<RecordMetadata>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if !self.inner.result.register_waker(cx.waker()) {
+            return Poll::Pending;
+        }
+        match self.next_record_metadata() {
+            Some(mut next_record_metadata) => Pin::new(&mut next_record_metadata).poll(cx),
+            None => Poll::Ready(self.value_or_error()),
+        }
+    }
+}
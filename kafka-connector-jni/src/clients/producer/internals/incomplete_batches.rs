@@ -0,0 +1,52 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use indexmap::IndexMap;
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{produce_request_result::ProduceRequestResult, producer_batch::ProducerBatch};
+
+/// A thread-safe helper class to hold batches that haven't been acknowledged yet (including
+/// those which have been sent but not yet acknowledged).
+#[derive(Default)]
+pub struct IncompleteBatches {
+    incomplete: Mutex<IndexMap<u64, Arc<ProducerBatch>>>,
+}
+
+impl IncompleteBatches {
+    pub fn new() -> IncompleteBatches {
+        IncompleteBatches::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, IndexMap<u64, Arc<ProducerBatch>>> {
+        self.incomplete.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn add(&self, batch: Arc<ProducerBatch>) {
+        self.lock().insert(batch.id(), batch);
+    }
+
+    pub fn remove(&self, batch: &ProducerBatch) -> Result<()> {
+        match self.lock().shift_remove(&batch.id()) {
+            Some(_) => Ok(()),
+            None => Err(KafkaError::IllegalState(
+                "Remove from the incomplete set failed. This should be impossible.".to_owned(),
+            )),
+        }
+    }
+
+    pub fn copy_all(&self) -> Vec<Arc<ProducerBatch>> {
+        self.lock().values().cloned().collect()
+    }
+
+    pub fn request_results(&self) -> Vec<Arc<ProduceRequestResult>> {
+        self.lock()
+            .values()
+            .map(|batch| batch.produce_future.clone())
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+}
@@ -0,0 +1,12 @@
+pub mod buffer_pool;
+pub mod default_partitioner;
+pub mod future_record_metadata;
+pub mod incomplete_batches;
+pub mod produce_request_result;
+pub mod producer_batch;
+pub mod producer_metadata;
+pub mod record_accumulator;
+pub mod sender;
+pub mod sticky_partition_cache;
+pub mod transaction_manager;
+pub mod transactional_request_result;
@@ -0,0 +1,156 @@
+use std::{
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::Waker,
+    time::{Duration, Instant},
+};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    record::record_batch::NO_TIMESTAMP,
+    topic_partition::TopicPartition,
+};
+
+/// Function mapping the index of a record in the batch to the error it failed with.
+pub type ErrorsByIndex = Arc<dyn Fn(usize) -> KafkaError + Send + Sync>;
+
+/// A class that models the future completion of a produce request for a single partition. There
+/// is one of these per partition in a produce request and it is shared by all the `RecordMetadata`
+/// instances that are batched together for the same partition in the request.
+pub struct ProduceRequestResult {
+    topic_partition: TopicPartition,
+    state: Mutex<ProduceRequestResultState>,
+    completed: Condvar,
+}
+
+#[derive(Default)]
+struct ProduceRequestResultState {
+    base_offset: Option<i64>,
+    log_append_time: Option<i64>,
+    errors_by_index: Option<ErrorsByIndex>,
+    completed: bool,
+    wakers: Vec<Waker>,
+}
+
+impl ProduceRequestResult {
+    /// Create an instance of this class.
+    pub fn new(topic_partition: TopicPartition) -> ProduceRequestResult {
+        ProduceRequestResult {
+            topic_partition,
+            state: Mutex::new(ProduceRequestResultState::default()),
+            completed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ProduceRequestResultState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Set the result of the produce request.
+    ///
+    /// * `base_offset` - The base offset assigned to the record
+    /// * `log_append_time` - The log append time or -1 if CreateTime is being used
+    /// * `errors_by_index` - Function mapping the batch index to the exception, or `None` if the response was successful
+    pub fn set(
+        &self,
+        base_offset: i64,
+        log_append_time: i64,
+        errors_by_index: Option<ErrorsByIndex>,
+    ) {
+        let mut state = self.lock();
+        state.base_offset = Some(base_offset);
+        state.log_append_time = Some(log_append_time).filter(|time| *time != NO_TIMESTAMP);
+        state.errors_by_index = errors_by_index;
+    }
+
+    /// Mark this request as complete and unblock any threads waiting on its completion.
+    pub fn done(&self) -> Result<()> {
+        let mut state = self.lock();
+        if state.base_offset.is_none() {
+            return Err(KafkaError::IllegalState(
+                "The method `set` must be invoked before this method.".to_owned(),
+            ));
+        }
+        state.completed = true;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        self.completed.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Await the completion of this request
+    pub fn await_completion(&self) {
+        let mut state = self.lock();
+        while !state.completed {
+            state = self
+                .completed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Await the completion of this request (up to the given time interval)
+    ///
+    /// Returns true if the request completed, false if we timed out
+    pub fn await_timeout(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        while !state.completed {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self
+                .completed
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        true
+    }
+
+    /// Register a waker notified once the request completes. Returns true if already completed.
+    pub fn register_waker(&self, waker: &Waker) -> bool {
+        let mut state = self.lock();
+        if state.completed {
+            return true;
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        false
+    }
+
+    /// The base offset for the request (the first offset in the record set)
+    pub fn base_offset(&self) -> i64 {
+        self.lock().base_offset.unwrap_or(-1)
+    }
+
+    /// Return true if log append time is being used for this topic
+    pub fn has_log_append_time(&self) -> bool {
+        self.lock().log_append_time.is_some()
+    }
+
+    /// The log append time or -1 if CreateTime is being used
+    pub fn log_append_time(&self) -> i64 {
+        self.lock().log_append_time.unwrap_or(NO_TIMESTAMP)
+    }
+
+    /// The error thrown (generally on the server) while processing this request
+    pub fn error(&self, batch_index: usize) -> Option<KafkaError> {
+        let errors_by_index = self.lock().errors_by_index.clone();
+        errors_by_index.map(|errors_by_index| errors_by_index(batch_index))
+    }
+
+    /// The topic and partition to which the record was appended
+    pub fn topic_partition(&self) -> &TopicPartition {
+        &self.topic_partition
+    }
+
+    /// Has the request completed?
+    pub fn completed(&self) -> bool {
+        self.lock().completed
+    }
+}
@@ -0,0 +1,835 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use bytes::BytesMut;
+use jni::{
+    objects::JObject,
+    sys::{jboolean, jint, jlong, jobject, jshort, jstring},
+    JNIEnv,
+};
+use log::{debug, error, info, trace};
+
+use crate::{
+    clients::producer::callback::Callback,
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::{
+        errors::{JniResult, KafkaError, Result},
+        header::internals::record_header::RecordHeader,
+        protocol::errors::Errors,
+        record::{
+            default_record::DefaultRecord,
+            default_record_batch::RECORD_BATCH_OVERHEAD,
+            memory_records::MemoryRecords,
+            memory_records_builder::MemoryRecordsBuilder,
+            record_batch::{NO_SEQUENCE, NO_TIMESTAMP},
+        },
+        topic_partition::TopicPartition,
+        utils::producer_id_and_epoch::ProducerIdAndEpoch,
+    },
+    direct_byte_buffer::DirectByteBuffer,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
+};
+
+use super::{
+    future_record_metadata::FutureRecordMetadata,
+    produce_request_result::{ErrorsByIndex, ProduceRequestResult},
+};
+
+const INVALID_OFFSET: i64 = -1;
+
+static NEXT_BATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalState {
+    Aborted,
+    Failed,
+    Succeeded,
+}
+
+/// A batch of records that is or will be sent.
+///
+/// Batches are shared between the accumulator, the sender and the list of incomplete batches, so
+/// mutable state is kept behind a lock and all methods take `&self`.
+pub struct ProducerBatch {
+    id: u64,
+    pub created_ms: u128,
+    pub topic_partition: TopicPartition,
+    pub produce_future: Arc<ProduceRequestResult>,
+    is_split_batch: bool,
+    inner: Mutex<ProducerBatchInner>,
+}
+
+struct ProducerBatchInner {
+    thunks: Vec<Thunk>,
+    records_builder: MemoryRecordsBuilder,
+    attempts: i32,
+    final_state: Option<FinalState>,
+    record_count: i32,
+    max_record_size: usize,
+    last_attempt_ms: u128,
+    last_append_time: u128,
+    drained_ms: u128,
+    retry: bool,
+    reopened: bool,
+}
+
+struct Thunk {
+    callback: Option<Callback>,
+    future: FutureRecordMetadata,
+}
+
+impl ProducerBatch {
+    pub fn new(
+        tp: TopicPartition,
+        records_builder: MemoryRecordsBuilder,
+        created_ms: u128,
+    ) -> ProducerBatch {
+        ProducerBatch::with_split_flag(tp, records_builder, created_ms, false)
+    }
+
+    pub fn with_split_flag(
+        tp: TopicPartition,
+        records_builder: MemoryRecordsBuilder,
+        created_ms: u128,
+        is_split_batch: bool,
+    ) -> ProducerBatch {
+        ProducerBatch {
+            id: NEXT_BATCH_ID.fetch_add(1, Ordering::Relaxed),
+            created_ms,
+            produce_future: Arc::new(ProduceRequestResult::new(tp.clone())),
+            topic_partition: tp,
+            is_split_batch,
+            inner: Mutex::new(ProducerBatchInner {
+                thunks: vec![],
+                records_builder,
+                attempts: 0,
+                final_state: None,
+                record_count: 0,
+                max_record_size: 0,
+                last_attempt_ms: created_ms,
+                last_append_time: created_ms,
+                drained_ms: 0,
+                retry: false,
+                reopened: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ProducerBatchInner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Unique identifier of the batch within the process.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Append the record to the current record set and return the relative offset within that
+    /// record set. The callback is taken only when the record was appended.
+    pub fn try_append(
+        &self,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+        callback: &mut Option<Callback>,
+        now: u128,
+    ) -> Result<Option<FutureRecordMetadata>> {
+        let mut inner = self.lock();
+        if !inner
+            .records_builder
+            .has_room_for(timestamp, key, value, headers)
+        {
+            return Ok(None);
+        }
+        inner
+            .records_builder
+            .append(timestamp, key, value, headers)?;
+        inner.max_record_size = inner.max_record_size.max(
+            RECORD_BATCH_OVERHEAD + DefaultRecord::record_size_upper_bound(key, value, headers),
+        );
+        inner.last_append_time = now;
+        let future = FutureRecordMetadata::new(
+            self.produce_future.clone(),
+            inner.record_count as usize,
+            timestamp,
+            key.map(|key| key.len() as i32).unwrap_or(-1),
+            value.map(|value| value.len() as i32).unwrap_or(-1),
+        );
+        // we have to keep every future returned to the users in case the batch needs to be
+        // split to several new batches and resent.
+        inner.thunks.push(Thunk {
+            callback: callback.take(),
+            future: future.clone(),
+        });
+        inner.record_count += 1;
+        Ok(Some(future))
+    }
+
+    /// This method is only used by `split` when splitting a large batch to smaller ones.
+    fn try_append_for_split(
+        &self,
+        record: &DefaultRecord,
+        thunk: &mut Option<Thunk>,
+    ) -> Result<bool> {
+        let mut inner = self.lock();
+        let key = record.key.as_deref();
+        let value = record.value.as_deref();
+        if !inner
+            .records_builder
+            .has_room_for(record.timestamp, key, value, &record.headers)
+        {
+            return Ok(false);
+        }
+        // No need to get the CRC.
+        inner
+            .records_builder
+            .append(record.timestamp, key, value, &record.headers)?;
+        inner.max_record_size = inner.max_record_size.max(
+            RECORD_BATCH_OVERHEAD
+                + DefaultRecord::record_size_upper_bound(key, value, &record.headers),
+        );
+        let future = FutureRecordMetadata::new(
+            self.produce_future.clone(),
+            inner.record_count as usize,
+            record.timestamp,
+            record.key_size(),
+            record.value_size(),
+        );
+        // Chain the future to the original thunk.
+        if let Some(thunk) = thunk.take() {
+            thunk.future.chain(future);
+            inner.thunks.push(thunk);
+        }
+        inner.record_count += 1;
+        Ok(true)
+    }
+
+    /// Abort the batch and complete the future and callbacks.
+    pub fn abort(&self, error: KafkaError) -> Result<()> {
+        {
+            let mut inner = self.lock();
+            if let Some(final_state) = inner.final_state {
+                return Err(KafkaError::IllegalState(format!(
+                    "Batch has already been completed in final state {:?}",
+                    final_state
+                )));
+            }
+            inner.final_state = Some(FinalState::Aborted);
+        }
+
+        trace!(
+            "Aborting batch for partition {}: {}",
+            self.topic_partition,
+            error
+        );
+        self.complete_future_and_fire_callbacks(
+            INVALID_OFFSET,
+            NO_TIMESTAMP,
+            Some(Arc::new(move |_| error.clone())),
+        )
+    }
+
+    /// Check if the batch has been completed (either successfully or exceptionally).
+    pub fn is_done(&self) -> bool {
+        self.final_state().is_some()
+    }
+
+    /// Complete the batch successfully.
+    ///
+    /// Returns true if the batch was completed as a result of this call, and false if it had
+    /// been completed previously.
+    pub fn complete(&self, base_offset: i64, log_append_time: i64) -> Result<bool> {
+        self.done(base_offset, log_append_time, None, None)
+    }
+
+    /// Complete the batch exceptionally. The provided top-level error will be used for each
+    /// record future contained in the batch unless `record_errors` provides a different one.
+    pub fn complete_exceptionally(
+        &self,
+        top_level_error: KafkaError,
+        record_errors: ErrorsByIndex,
+    ) -> Result<bool> {
+        self.done(
+            INVALID_OFFSET,
+            NO_TIMESTAMP,
+            Some(top_level_error),
+            Some(record_errors),
+        )
+    }
+
+    fn done(
+        &self,
+        base_offset: i64,
+        log_append_time: i64,
+        top_level_error: Option<KafkaError>,
+        record_errors: Option<ErrorsByIndex>,
+    ) -> Result<bool> {
+        let try_final_state = match top_level_error {
+            None => FinalState::Succeeded,
+            Some(_) => FinalState::Failed,
+        };
+        match &top_level_error {
+            None => trace!(
+                "Successfully produced messages to {} with base offset {}.",
+                self.topic_partition,
+                base_offset
+            ),
+            Some(error) => trace!(
+                "Failed to produce messages to {} with base offset {}: {}",
+                self.topic_partition,
+                base_offset,
+                error
+            ),
+        }
+
+        let final_state = {
+            let mut inner = self.lock();
+            let final_state = inner.final_state;
+            if final_state.is_none() {
+                inner.final_state = Some(try_final_state);
+            }
+            final_state
+        };
+        match final_state {
+            None => {
+                self.complete_future_and_fire_callbacks(
+                    base_offset,
+                    log_append_time,
+                    record_errors,
+                )?;
+                Ok(true)
+            }
+            Some(FinalState::Succeeded) => {
+                // A SUCCESSFUL batch must not attempt another state change.
+                Err(KafkaError::IllegalState(format!(
+                    "A {:?} batch must not attempt another state change to {:?}",
+                    FinalState::Succeeded,
+                    try_final_state
+                )))
+            }
+            Some(final_state) => {
+                if try_final_state == FinalState::Succeeded {
+                    // Log if a previously unsuccessful batch succeeded later on.
+                    debug!(
+                        "ProduceResponse returned {:?} for {} after batch with base offset {} had already been {:?}.",
+                        try_final_state, self.topic_partition, base_offset, final_state
+                    );
+                } else {
+                    // FAILED --> FAILED and ABORTED --> FAILED transitions are ignored.
+                    debug!(
+                        "Ignored state transition {:?} -> {:?} for {} batch with base offset {}",
+                        final_state, try_final_state, self.topic_partition, base_offset
+                    );
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    fn complete_future_and_fire_callbacks(
+        &self,
+        base_offset: i64,
+        log_append_time: i64,
+        record_errors: Option<ErrorsByIndex>,
+    ) -> Result<()> {
+        // Set the future before invoking the callbacks as we rely on its state for the `on_completion` call
+        self.produce_future
+            .set(base_offset, log_append_time, record_errors.clone());
+
+        let thunks = std::mem::take(&mut self.lock().thunks);
+
+        // execute callbacks
+        for (i, thunk) in thunks.into_iter().enumerate() {
+            if let Some(callback) = thunk.callback {
+                let result = match &record_errors {
+                    None => Ok(thunk.future.value()),
+                    Some(record_errors) => Err(record_errors(i)),
+                };
+                if panic::catch_unwind(AssertUnwindSafe(|| callback(result))).is_err() {
+                    error!(
+                        "Error executing user-provided callback on message for topic-partition '{}'",
+                        self.topic_partition
+                    );
+                }
+            }
+        }
+
+        self.produce_future.done()
+    }
+
+    /// Split the batch into batches no larger than `split_batch_size`, completing the original
+    /// future with `RecordBatchTooLarge` error. Record futures returned to users are chained to
+    /// the new batches.
+    pub fn split(&self, split_batch_size: usize) -> Result<VecDeque<ProducerBatch>> {
+        let mut batches = VecDeque::new();
+        let (memory_records, thunks, producer_id_and_epoch, base_sequence, is_transactional) = {
+            let mut inner = self.lock();
+            let memory_records = inner.records_builder.build()?;
+            (
+                memory_records,
+                std::mem::take(&mut inner.thunks),
+                ProducerIdAndEpoch::new(
+                    inner.records_builder.producer_id(),
+                    inner.records_builder.producer_epoch(),
+                ),
+                inner.records_builder.base_sequence(),
+                inner.records_builder.is_transactional(),
+            )
+        };
+
+        let mut record_batches = memory_records.batches();
+        let record_batch = match record_batches.next() {
+            Some(record_batch) => record_batch?,
+            None => {
+                return Err(KafkaError::IllegalState(
+                    "Cannot split an empty producer batch.".to_owned(),
+                ))
+            }
+        };
+        if record_batches.next().is_some() {
+            return Err(KafkaError::IllegalArgument(
+                "A producer batch should only have one record batch.".to_owned(),
+            ));
+        }
+
+        let mut thunks = thunks.into_iter();
+        // We always allocate batch size because we are already splitting a big batch.
+        // And we also Retain the create time of the original batch.
+        let mut batch: Option<ProducerBatch> = None;
+
+        for record in record_batch.records()? {
+            let mut thunk = thunks.next();
+            let current = match batch.take() {
+                Some(current) => current,
+                None => self.create_batch_off_accumulator_for_record(&record, split_batch_size)?,
+            };
+
+            // A newly created batch can always host the first message.
+            if current.try_append_for_split(&record, &mut thunk)? {
+                batch = Some(current);
+            } else {
+                current.close_for_record_appends();
+                batches.push_back(current);
+                let next =
+                    self.create_batch_off_accumulator_for_record(&record, split_batch_size)?;
+                next.try_append_for_split(&record, &mut thunk)?;
+                batch = Some(next);
+            }
+        }
+
+        // Close the last batch and add it to the batch list after split.
+        if let Some(batch) = batch {
+            batch.close_for_record_appends();
+            batches.push_back(batch);
+        }
+
+        self.produce_future.set(
+            INVALID_OFFSET,
+            NO_TIMESTAMP,
+            Some(Arc::new(|_| {
+                KafkaError::RecordBatchTooLarge(
+                    "The request included message batch larger than the configured segment size on the server.".to_owned(),
+                )
+            })),
+        );
+        self.produce_future.done()?;
+
+        if base_sequence != NO_SEQUENCE {
+            let mut sequence = base_sequence;
+            for new_batch in &batches {
+                new_batch.set_producer_state(producer_id_and_epoch, sequence, is_transactional)?;
+                sequence += new_batch.record_count();
+            }
+        }
+        Ok(batches)
+    }
+
+    fn create_batch_off_accumulator_for_record(
+        &self,
+        record: &DefaultRecord,
+        batch_size: usize,
+    ) -> Result<ProducerBatch> {
+        let initial_size = batch_size.max(
+            RECORD_BATCH_OVERHEAD
+                + DefaultRecord::record_size_upper_bound(
+                    record.key.as_deref(),
+                    record.value.as_deref(),
+                    &record.headers,
+                ),
+        );
+        let buffer = BytesMut::with_capacity(initial_size);
+
+        // Note that we intentionally do not set producer state (producerId, epoch, sequence, and isTransactional)
+        // for the newly created batch. This will be set when the batch is dequeued for sending (which is consistent
+        // with how normal batches are handled).
+        let compression_type = self.lock().records_builder.compression_type();
+        let builder = MemoryRecordsBuilder::for_producer(buffer, compression_type, batch_size)?;
+        Ok(ProducerBatch::with_split_flag(
+            self.topic_partition.clone(),
+            builder,
+            self.created_ms,
+            true,
+        ))
+    }
+
+    pub fn has_reached_delivery_timeout(&self, delivery_timeout_ms: u128, now: u128) -> bool {
+        delivery_timeout_ms <= now.saturating_sub(self.created_ms)
+    }
+
+    pub fn final_state(&self) -> Option<FinalState> {
+        self.lock().final_state
+    }
+
+    pub fn attempts(&self) -> i32 {
+        self.lock().attempts
+    }
+
+    pub fn reenqueued(&self, now: u128) {
+        let mut inner = self.lock();
+        inner.attempts += 1;
+        inner.last_attempt_ms = inner.last_append_time.max(now);
+        inner.last_append_time = inner.last_append_time.max(now);
+        inner.retry = true;
+    }
+
+    pub fn queue_time_ms(&self) -> u128 {
+        self.lock().drained_ms.saturating_sub(self.created_ms)
+    }
+
+    pub fn waited_time_ms(&self, now_ms: u128) -> u128 {
+        now_ms.saturating_sub(self.lock().last_attempt_ms)
+    }
+
+    pub fn drained(&self, now_ms: u128) {
+        let mut inner = self.lock();
+        inner.drained_ms = inner.drained_ms.max(now_ms);
+    }
+
+    pub fn is_split_batch(&self) -> bool {
+        self.is_split_batch
+    }
+
+    pub fn in_retry(&self) -> bool {
+        self.lock().retry
+    }
+
+    pub fn records(&self) -> Result<MemoryRecords> {
+        self.lock().records_builder.build()
+    }
+
+    pub fn record_count(&self) -> i32 {
+        self.lock().record_count
+    }
+
+    pub fn max_record_size(&self) -> usize {
+        self.lock().max_record_size
+    }
+
+    pub fn estimated_size_in_bytes(&self) -> usize {
+        self.lock().records_builder.estimated_size_in_bytes()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.lock().records_builder.is_full()
+    }
+
+    pub fn set_producer_state(
+        &self,
+        producer_id_and_epoch: ProducerIdAndEpoch,
+        base_sequence: i32,
+        is_transactional: bool,
+    ) -> Result<()> {
+        self.lock().records_builder.set_producer_state(
+            producer_id_and_epoch.producer_id,
+            producer_id_and_epoch.epoch,
+            base_sequence,
+            is_transactional,
+        )
+    }
+
+    pub fn reset_producer_state(
+        &self,
+        producer_id_and_epoch: ProducerIdAndEpoch,
+        base_sequence: i32,
+        is_transactional: bool,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        info!(
+            "Resetting sequence number of batch with current sequence {} for partition {} to {}",
+            inner.records_builder.base_sequence(),
+            self.topic_partition,
+            base_sequence
+        );
+        inner.reopened = true;
+        inner.records_builder.reopen_and_rewrite_producer_state(
+            producer_id_and_epoch.producer_id,
+            producer_id_and_epoch.epoch,
+            base_sequence,
+            is_transactional,
+        )
+    }
+
+    /// Release resources required for record appends (e.g. compression buffers). Once this
+    /// method is called, it's only possible to update the RecordBatch header.
+    pub fn close_for_record_appends(&self) {
+        self.lock().records_builder.close_for_record_appends();
+    }
+
+    pub fn close(&self) -> Result<()> {
+        let mut inner = self.lock();
+        inner.records_builder.close()?;
+        inner.reopened = false;
+        Ok(())
+    }
+
+    /// Abort the record builder and reset the state of the underlying buffer. This is used prior
+    /// to aborting the batch with `abort` and ensures that no record previously appended can be
+    /// read. This is used in scenarios where we want to ensure a batch ultimately gets aborted,
+    /// but in which it is not safe to invoke the completion callbacks (e.g. because we are
+    /// holding a lock, such as when aborting batches in `RecordAccumulator`).
+    pub fn abort_record_appends(&self) {
+        self.lock().records_builder.abort();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().records_builder.is_closed()
+    }
+
+    /// Take the buffer backing this batch, so it can be returned to the buffer pool.
+    pub fn take_buffer(&self) -> BytesMut {
+        self.lock().records_builder.take_buffer()
+    }
+
+    pub fn initial_capacity(&self) -> usize {
+        self.lock().records_builder.initial_capacity()
+    }
+
+    pub fn is_writable(&self) -> bool {
+        !self.is_closed()
+    }
+
+    pub fn magic(&self) -> i8 {
+        self.lock().records_builder.magic()
+    }
+
+    pub fn producer_id(&self) -> i64 {
+        self.lock().records_builder.producer_id()
+    }
+
+    pub fn producer_epoch(&self) -> i16 {
+        self.lock().records_builder.producer_epoch()
+    }
+
+    pub fn base_sequence(&self) -> i32 {
+        self.lock().records_builder.base_sequence()
+    }
+
+    pub fn last_sequence(&self) -> i32 {
+        let inner = self.lock();
+        inner.records_builder.base_sequence() + inner.records_builder.num_records() - 1
+    }
+
+    pub fn has_sequence(&self) -> bool {
+        self.base_sequence() != NO_SEQUENCE
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.lock().records_builder.is_transactional()
+    }
+
+    pub fn sequence_has_been_reset(&self) -> bool {
+        self.lock().reopened
+    }
+}
+
+impl fmt::Display for ProducerBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProducerBatch(topicPartition={}, recordCount={})",
+            self.topic_partition,
+            self.record_count()
+        )
+    }
+}
+
+impl fmt::Debug for ProducerBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// `ProducerBatch` backing a `RustProducerBatch`, shared with the accumulator which drained it.
+#[derive(Clone)]
+pub struct JavaProducerBatch(pub Arc<ProducerBatch>);
+java_struct_standard_impl!(
+    JavaProducerBatch,
+    "org/apache/kafka/clients/producer/internals/RustProducerBatch"
+);
+
+impl JavaProducerBatch {
+    /// The batch backing `obj`, cloned out of the lock: completing it runs callbacks.
+    fn batch(env: JNIEnv, obj: JObject) -> JniResult<Arc<ProducerBatch>> {
+        Ok(JavaProducerBatch::from_jobject(env, obj)?.lock().0.clone())
+    }
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    topicPartition
+ * Signature: ()Lorg/apache/kafka/common/TopicPartition;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_topicPartition(
+    env: JNIEnv,
+    obj: JObject,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let batch = JavaProducerBatch::batch(env, obj)?;
+        Ok(batch.topic_partition.clone_to_java(env)?.l()?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    recordCount
+ * Signature: ()I
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_recordCount(
+    env: JNIEnv,
+    obj: JObject,
+) -> jint {
+    jni_guard(env, || -> JniResult<_> {
+        Ok(JavaProducerBatch::batch(env, obj)?.record_count())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    attempts
+ * Signature: ()I
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_attempts(
+    env: JNIEnv,
+    obj: JObject,
+) -> jint {
+    jni_guard(env, || -> JniResult<_> {
+        Ok(JavaProducerBatch::batch(env, obj)?.attempts())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    records
+ * Signature: ()Ljava/nio/ByteBuffer;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_records(
+    env: JNIEnv,
+    obj: JObject,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let records = JavaProducerBatch::batch(env, obj)?.records()?;
+        let buffer = DirectByteBuffer(records.buffer().clone());
+        Ok(buffer.clone_to_java(env)?.l()?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    complete
+ * Signature: (JJ)Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_complete(
+    env: JNIEnv,
+    obj: JObject,
+    base_offset: jlong,
+    log_append_time: jlong,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        let batch = JavaProducerBatch::batch(env, obj)?;
+        Ok(batch.complete(base_offset, log_append_time)? as jboolean)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    completeExceptionally
+ * Signature: (SLjava/lang/String;)Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_completeExceptionally(
+    env: JNIEnv,
+    obj: JObject,
+    error_code: jshort,
+    error_message: jstring,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        let error_message = Option::<String>::clone_from_java(env, error_message.into())?;
+        let error = Errors::for_code(error_code)
+            .exception(error_message.as_deref())
+            .ok_or_else(|| {
+                KafkaError::IllegalArgument(format!("Error code {} is not an error", error_code))
+            })?;
+        let batch = JavaProducerBatch::batch(env, obj)?;
+        let record_error = error.clone();
+        Ok(
+            batch.complete_exceptionally(error, Arc::new(move |_| record_error.clone()))?
+                as jboolean,
+        )
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    isDone
+ * Signature: ()Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_isDone(
+    env: JNIEnv,
+    obj: JObject,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        Ok(JavaProducerBatch::batch(env, obj)?.is_done() as jboolean)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustProducerBatch
+ * Method:    rustDestructor
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustProducerBatch_rustDestructor(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<JavaProducerBatch>::destroy(env, obj)?;
+
+        Ok(())
+    })
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use log::debug;
+
+use crate::{
+    clients::metadata::Metadata,
+    common::{
+        cluster::Cluster,
+        errors::{KafkaError, Result},
+        utils::time::Time,
+    },
+};
+
+/// Producer specific metadata. Keeps track of topics the producer sends to and expires topics
+/// which weren't used for `metadata_idle_ms`.
+pub struct ProducerMetadata {
+    metadata: Metadata,
+    // If a topic hasn't been accessed for this many milliseconds, it is removed from the cache.
+    metadata_idle_ms: u128,
+    topics: Mutex<ProducerTopics>,
+    updated: Condvar,
+    time: Arc<dyn Time>,
+}
+
+#[derive(Default)]
+struct ProducerTopics {
+    /* Topics with expiry time */
+    topics: HashMap<String, u128>,
+    new_topics: HashSet<String>,
+}
+
+impl ProducerMetadata {
+    pub fn new(
+        refresh_backoff_ms: u128,
+        metadata_expire_ms: u128,
+        metadata_idle_ms: u128,
+        time: Arc<dyn Time>,
+    ) -> ProducerMetadata {
+        ProducerMetadata {
+            metadata: Metadata::new(refresh_backoff_ms, metadata_expire_ms),
+            metadata_idle_ms,
+            topics: Mutex::new(ProducerTopics::default()),
+            updated: Condvar::new(),
+            time,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ProducerTopics> {
+        self.topics.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn add(&self, topic: &str, now_ms: u128) {
+        let mut topics = self.lock();
+        let expiry = now_ms.saturating_add(self.metadata_idle_ms);
+        if topics.topics.insert(topic.to_owned(), expiry).is_none() {
+            topics.new_topics.insert(topic.to_owned());
+            self.metadata.request_update_for_new_topics();
+        }
+    }
+
+    pub fn request_update_for_topic(&self, topic: &str) -> i32 {
+        if self.lock().new_topics.contains(topic) {
+            self.metadata.request_update_for_new_topics()
+        } else {
+            self.metadata.request_update()
+        }
+    }
+
+    pub fn topics(&self) -> HashSet<String> {
+        self.lock().topics.keys().cloned().collect()
+    }
+
+    pub fn new_topics(&self) -> HashSet<String> {
+        self.lock().new_topics.clone()
+    }
+
+    pub fn contains_topic(&self, topic: &str) -> bool {
+        self.lock().topics.contains_key(topic)
+    }
+
+    pub fn retain_topic(&self, topic: &str, now_ms: u128) -> bool {
+        let mut topics = self.lock();
+        Self::retain_topic_locked(&mut topics, topic, now_ms)
+    }
+
+    fn retain_topic_locked(topics: &mut ProducerTopics, topic: &str, now_ms: u128) -> bool {
+        match topics.topics.get(topic) {
+            None => false,
+            Some(_) if topics.new_topics.contains(topic) => true,
+            Some(&expire_ms) if expire_ms <= now_ms => {
+                debug!(
+                    "Removing unused topic {} from the metadata list, expiryMs {} now {}",
+                    topic, expire_ms, now_ms
+                );
+                topics.topics.remove(topic);
+                false
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Wait for metadata update until the current version is larger than the last version we know of
+    pub fn await_update(&self, last_version: i32, timeout_ms: u128) -> Result<()> {
+        let deadline_ms = self.time.milliseconds().saturating_add(timeout_ms);
+        let mut topics = self.lock();
+        loop {
+            // Throw fatal exceptions, if there are any. Recoverable topic errors will be handled by the caller.
+            self.metadata.maybe_throw_fatal_exception()?;
+            if self.metadata.update_version() > last_version || self.metadata.is_closed() {
+                break;
+            }
+            let now_ms = self.time.milliseconds();
+            if now_ms >= deadline_ms {
+                return Err(KafkaError::Timeout(format!(
+                    "Failed to update metadata after {} ms.",
+                    timeout_ms
+                )));
+            }
+            let remaining =
+                Duration::from_millis((deadline_ms - now_ms).min(u64::MAX as u128) as u64);
+            topics = self
+                .updated
+                .wait_timeout(topics, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        if self.metadata.is_closed() {
+            return Err(KafkaError::Kafka(
+                "Requested metadata update after close".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Updates the cluster metadata and wakes up threads waiting in `await_update`. Unused topics
+    /// are expired and topics present in the new cluster are no longer considered new.
+    pub fn update(
+        &self,
+        request_version: i32,
+        cluster: Cluster,
+        is_partial_update: bool,
+        now_ms: u128,
+    ) -> Result<()> {
+        let mut topics = self.lock();
+        let known_topics: Vec<String> = topics.topics.keys().cloned().collect();
+        for topic in known_topics {
+            Self::retain_topic_locked(&mut topics, &topic, now_ms);
+        }
+        // Remove all topics in the response that are in the new topic set. Note that if an error was encountered for a
+        // new topic's metadata, then any work to resolve the error will include the topic in a full metadata update.
+        for topic in cluster.topics() {
+            topics.new_topics.remove(topic);
+        }
+        let result = self
+            .metadata
+            .update(request_version, cluster, is_partial_update, now_ms);
+        self.updated.notify_all();
+        result
+    }
+
+    pub fn fatal_error(&self, error: KafkaError) {
+        let _topics = self.lock();
+        self.metadata.fatal_error(error);
+        self.updated.notify_all();
+    }
+
+    /// Close this instance and notify any awaiting threads.
+    pub fn close(&self) {
+        let _topics = self.lock();
+        self.metadata.close();
+        self.updated.notify_all();
+    }
+}
+
+impl Deref for ProducerMetadata {
+    type Target = Metadata;
+
+    fn deref(&self) -> &Metadata {
+        &self.metadata
+    }
+}
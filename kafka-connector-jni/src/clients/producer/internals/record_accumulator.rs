@@ -0,0 +1,1847 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, RwLock,
+    },
+};
+
+use indexmap::IndexMap;
+use jni::{
+    objects::{JObject, JValue},
+    sys::{jboolean, jint, jlong, jobject},
+    JNIEnv,
+};
+
+use crate::{
+    clients::producer::callback::{java_callback, Callback},
+    clone_from_java::CloneFromJava,
+    clone_to_java::{kind, CloneToJava},
+    common::{
+        cluster::Cluster,
+        errors::{JniResult, KafkaError, Result},
+        header::internals::{record_header::RecordHeader, record_headers::RecordHeaders},
+        node::Node,
+        record::{
+            compression_type::CompressionType, default_record::DefaultRecord,
+            default_record_batch::RECORD_BATCH_OVERHEAD,
+            memory_records_builder::MemoryRecordsBuilder, record_batch::NO_SEQUENCE,
+        },
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::{SystemTime, Time},
+        },
+    },
+    direct_byte_buffer::DirectByteBuffer,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_vm::CallbackDispatcher,
+    jni_cache,
+    jni_guard::jni_guard,
+};
+
+use super::{
+    buffer_pool::BufferPool,
+    future_record_metadata::FutureRecordMetadata,
+    incomplete_batches::IncompleteBatches,
+    producer_batch::{JavaProducerBatch, ProducerBatch},
+    transaction_manager::TransactionManager,
+};
+
+type Deque = Arc<Mutex<VecDeque<Arc<ProducerBatch>>>>;
+
+/// This class acts as a queue that accumulates records into `MemoryRecords` instances to be sent
+/// to the server.
+///
+/// The accumulator uses a bounded amount of memory and append calls will block when that memory
+/// is exhausted, unless this behavior is explicitly disabled.
+///
+/// Java uses it through `RustRecordAccumulator`, which drains `RustProducerBatch`es.
+pub struct RecordAccumulator {
+    log: Logger,
+    closed: AtomicBool,
+    flushes_in_progress: AtomicUsize,
+    appends_in_progress: AtomicUsize,
+    batch_size: usize,
+    compression: CompressionType,
+    linger_ms: u128,
+    retry_backoff_ms: u128,
+    delivery_timeout_ms: u128,
+    free: Arc<BufferPool>,
+    time: Arc<dyn Time>,
+    batches: RwLock<IndexMap<TopicPartition, Deque>>,
+    incomplete: IncompleteBatches,
+    transaction_manager: Option<Arc<TransactionManager>>,
+    // The following variables are only accessed by the sender thread
+    muted: Mutex<HashSet<TopicPartition>>,
+    drain_index: AtomicUsize,
+    // the earliest time (absolute) a batch will expire.
+    next_batch_expiry_time_ms: Mutex<u128>,
+}
+
+/// Metadata about a record just appended to the record accumulator
+pub struct RecordAppendResult {
+    pub future: Option<FutureRecordMetadata>,
+    pub batch_is_full: bool,
+    pub new_batch_created: bool,
+    pub abort_for_new_batch: bool,
+}
+
+/// The set of nodes that have at least one complete record batch in the accumulator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadyCheckResult {
+    pub ready_nodes: HashSet<Node>,
+    pub next_ready_check_delay_ms: u128,
+    pub unknown_leader_topics: HashSet<String>,
+}
+
+impl RecordAccumulator {
+    /// Create a new record accumulator
+    ///
+    /// * `batch_size` - The size to use when allocating `MemoryRecords` instances
+    /// * `compression` - The compression codec for the records
+    /// * `linger_ms` - An artificial delay time to add before declaring a records instance that isn't full ready for
+    ///   sending. This allows time for more records to arrive. Setting a non-zero linger_ms will trade off some
+    ///   latency for potentially better throughput due to more batching (and hence fewer, larger requests).
+    /// * `retry_backoff_ms` - An artificial delay time to retry the produce request upon receiving an error. This avoids
+    ///   exhausting all retries in a short period of time.
+    /// * `delivery_timeout_ms` - An upper bound on the time to report success or failure on record delivery
+    /// * `time` - The time instance to use
+    /// * `transaction_manager` - The shared transaction state object which tracks producer IDs, epochs, and sequence
+    ///   numbers per partition.
+    /// * `buffer_pool` - The buffer pool
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        batch_size: usize,
+        compression: CompressionType,
+        linger_ms: u128,
+        retry_backoff_ms: u128,
+        delivery_timeout_ms: u128,
+        time: Arc<dyn Time>,
+        transaction_manager: Option<Arc<TransactionManager>>,
+        buffer_pool: Arc<BufferPool>,
+        log_context: &LogContext,
+    ) -> Result<RecordAccumulator> {
+        Ok(RecordAccumulator {
+            log: log_context.logger(module_path!()),
+            closed: AtomicBool::new(false),
+            flushes_in_progress: AtomicUsize::new(0),
+            appends_in_progress: AtomicUsize::new(0),
+            batch_size,
+            compression,
+            linger_ms,
+            retry_backoff_ms,
+            delivery_timeout_ms,
+            free: buffer_pool,
+            time,
+            batches: RwLock::new(IndexMap::new()),
+            incomplete: IncompleteBatches::new(),
+            transaction_manager,
+            muted: Mutex::new(HashSet::new()),
+            drain_index: AtomicUsize::new(0),
+            next_batch_expiry_time_ms: Mutex::new(u128::MAX),
+        })
+    }
+
+    /// Add a record to the accumulator, return the append result
+    ///
+    /// The append result will contain the future metadata, and flag for whether the appended batch is full or a new batch is created
+    ///
+    /// * `tp` - The topic/partition to which this record is being sent
+    /// * `timestamp` - The timestamp of the record
+    /// * `key` - The key for the record
+    /// * `value` - The value for the record
+    /// * `headers` - the Headers for the record
+    /// * `callback` - The user-supplied callback to execute when the request is complete. It is taken only if the
+    ///   record was appended.
+    /// * `max_time_to_block` - The maximum time in milliseconds to block for buffer memory to be available
+    /// * `abort_on_new_batch` - A boolean that indicates returning before a new batch is created and
+    ///   running the partitioner's on_new_batch method before trying to append again
+    /// * `now_ms` - The current time, in milliseconds
+    #[allow(clippy::too_many_arguments)]
+    pub fn append(
+        &self,
+        tp: &TopicPartition,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+        callback: &mut Option<Callback>,
+        max_time_to_block: u128,
+        abort_on_new_batch: bool,
+        now_ms: u128,
+    ) -> Result<RecordAppendResult> {
+        // We keep track of the number of appending thread to make sure we do not miss batches in
+        // abort_incomplete_batches().
+        self.appends_in_progress.fetch_add(1, Ordering::SeqCst);
+        let result = self.append_in_progress(
+            tp,
+            timestamp,
+            key,
+            value,
+            headers,
+            callback,
+            max_time_to_block,
+            abort_on_new_batch,
+            now_ms,
+        );
+        self.appends_in_progress.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn append_in_progress(
+        &self,
+        tp: &TopicPartition,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+        callback: &mut Option<Callback>,
+        max_time_to_block: u128,
+        abort_on_new_batch: bool,
+        now_ms: u128,
+    ) -> Result<RecordAppendResult> {
+        // check if we have an in-progress batch
+        let dq = self.get_or_create_deque(tp);
+        {
+            let mut dq = lock_deque(&dq);
+            if self.closed.load(Ordering::SeqCst) {
+                return Err(KafkaError::Kafka(
+                    "Producer closed while send in progress".to_owned(),
+                ));
+            }
+            if let Some(append_result) =
+                self.try_append(timestamp, key, value, headers, callback, &mut dq, now_ms)?
+            {
+                return Ok(append_result);
+            }
+        }
+
+        // we don't have an in-progress record batch try to allocate a new batch
+        if abort_on_new_batch {
+            // Return a result that will cause another call to append.
+            return Ok(RecordAppendResult {
+                future: None,
+                batch_is_full: false,
+                new_batch_created: false,
+                abort_for_new_batch: true,
+            });
+        }
+
+        let size = self.batch_size.max(
+            RECORD_BATCH_OVERHEAD + DefaultRecord::record_size_upper_bound(key, value, headers),
+        );
+        self.log.trace(format_args!(
+            "Allocating a new {} byte message buffer for topic {} partition {} with remaining timeout {}ms",
+            size,
+            tp.topic,
+            tp.partition,
+            max_time_to_block
+        ));
+        let buffer = self.free.allocate(size, max_time_to_block)?;
+
+        // Update the current time in case the buffer allocation blocked above.
+        let now_ms = self.time.milliseconds();
+        let mut dq = lock_deque(&dq);
+        // Need to check if producer is closed again after grabbing the dequeue lock.
+        if self.closed.load(Ordering::SeqCst) {
+            drop(dq);
+            self.free.deallocate(buffer, size);
+            return Err(KafkaError::Kafka(
+                "Producer closed while send in progress".to_owned(),
+            ));
+        }
+
+        match self.try_append(timestamp, key, value, headers, callback, &mut dq, now_ms) {
+            Ok(None) => {}
+            Ok(Some(append_result)) => {
+                // Somebody else found us a batch, return the one we waited for! Hopefully this doesn't happen often...
+                drop(dq);
+                self.free.deallocate(buffer, size);
+                return Ok(append_result);
+            }
+            Err(error) => {
+                drop(dq);
+                self.free.deallocate(buffer, size);
+                return Err(error);
+            }
+        }
+
+        let records_builder =
+            MemoryRecordsBuilder::for_producer(buffer, self.compression, self.batch_size)?;
+        let batch = Arc::new(ProducerBatch::new(tp.clone(), records_builder, now_ms));
+        let future = match batch.try_append(timestamp, key, value, headers, callback, now_ms) {
+            Ok(Some(future)) => future,
+            result => {
+                // Don't keep the buffer if the record could not be appended to the new batch
+                drop(dq);
+                self.free
+                    .deallocate(batch.take_buffer(), batch.initial_capacity());
+                return Err(result.err().unwrap_or_else(|| {
+                    KafkaError::IllegalState(
+                        "Newly created batch could not fit the first record".to_owned(),
+                    )
+                }));
+            }
+        };
+
+        dq.push_back(batch.clone());
+        self.incomplete.add(batch.clone());
+
+        Ok(RecordAppendResult {
+            future: Some(future),
+            batch_is_full: dq.len() > 1 || batch.is_full(),
+            new_batch_created: true,
+            abort_for_new_batch: false,
+        })
+    }
+
+    /// Try to append to a ProducerBatch.
+    ///
+    /// If it is full, we return `None` and a new batch is created. We also close the batch for record appends to free up
+    /// resources like compression buffers. The batch will be fully closed (ie. the record batch headers will be written
+    /// and memory records built) in one of the following cases (whichever comes first): right before send,
+    /// if it is expired, or when the producer is closed.
+    #[allow(clippy::too_many_arguments)]
+    fn try_append(
+        &self,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+        callback: &mut Option<Callback>,
+        deque: &mut VecDeque<Arc<ProducerBatch>>,
+        now_ms: u128,
+    ) -> Result<Option<RecordAppendResult>> {
+        if let Some(last) = deque.back() {
+            match last.try_append(timestamp, key, value, headers, callback, now_ms)? {
+                None => last.close_for_record_appends(),
+                Some(future) => {
+                    return Ok(Some(RecordAppendResult {
+                        future: Some(future),
+                        batch_is_full: deque.len() > 1 || last.is_full(),
+                        new_batch_created: false,
+                        abort_for_new_batch: false,
+                    }))
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_muted(&self, tp: &TopicPartition) -> bool {
+        lock(&self.muted).contains(tp)
+    }
+
+    pub fn reset_next_batch_expiry_time(&self) {
+        *lock(&self.next_batch_expiry_time_ms) = u128::MAX;
+    }
+
+    pub fn maybe_update_next_batch_expiry_time(&self, batch: &ProducerBatch) {
+        match batch.created_ms.checked_add(self.delivery_timeout_ms) {
+            Some(expiry_time_ms) => {
+                let mut next_batch_expiry_time_ms = lock(&self.next_batch_expiry_time_ms);
+                *next_batch_expiry_time_ms = (*next_batch_expiry_time_ms).min(expiry_time_ms);
+            }
+            None => self.log.warn(format_args!(
+                "Skipping next batch expiry time update due to addition overflow: batch.createMs={}, deliveryTimeoutMs={}",
+                batch.created_ms, self.delivery_timeout_ms
+            )),
+        }
+    }
+
+    /// Get a list of batches which have been sitting in the accumulator too long and need to be expired.
+    pub fn expired_batches(&self, now: u128) -> Vec<Arc<ProducerBatch>> {
+        let mut expired_batches = vec![];
+        for deque in self.deques() {
+            // expire the batches in the order of sending
+            let mut deque = lock_deque(&deque);
+            while let Some(batch) = deque.front() {
+                if batch.has_reached_delivery_timeout(self.delivery_timeout_ms, now) {
+                    let batch = deque.pop_front().expect("deque is not empty");
+                    batch.abort_record_appends();
+                    expired_batches.push(batch);
+                } else {
+                    self.maybe_update_next_batch_expiry_time(batch);
+                    break;
+                }
+            }
+        }
+        expired_batches
+    }
+
+    pub fn delivery_timeout_ms(&self) -> u128 {
+        self.delivery_timeout_ms
+    }
+
+    /// Re-enqueue the given record batch in the accumulator. In Sender.completeBatch method, we check
+    /// whether the batch has reached delivery_timeout_ms or not. Hence we do not do the delivery timeout check here.
+    pub fn reenqueue(&self, batch: Arc<ProducerBatch>, now: u128) {
+        batch.reenqueued(now);
+        let deque = self.get_or_create_deque(&batch.topic_partition);
+        let mut deque = lock_deque(&deque);
+        if batch.has_sequence() {
+            self.insert_in_sequence_order(&mut deque, batch);
+        } else {
+            deque.push_front(batch);
+        }
+    }
+
+    /// Split the big batch that has been rejected and reenqueue the split batches in to the accumulator.
+    ///
+    /// Returns the number of split batches.
+    pub fn split_and_reenqueue(&self, big_batch: &ProducerBatch) -> Result<usize> {
+        let mut dq = big_batch.split(self.batch_size)?;
+        let num_split_batches = dq.len();
+        let partition_dequeue = self.get_or_create_deque(&big_batch.topic_partition);
+        while let Some(batch) = dq.pop_back() {
+            let batch = Arc::new(batch);
+            self.incomplete.add(batch.clone());
+            // We treat the newly split batches as if they are not even tried.
+            lock_deque(&partition_dequeue).push_front(batch);
+        }
+        Ok(num_split_batches)
+    }
+
+    /// Get a list of nodes whose partitions are ready to be sent, and the earliest time at which any non-sendable
+    /// partition will be ready; Also return the flag for whether there are any unknown leaders for the accumulated
+    /// partition batches.
+    ///
+    /// A destination node is ready to send data if:
+    ///  1. There is at least one partition that is not backing off its send
+    ///  2. and those partitions are not muted (to prevent reordering if "max.in.flight.requests.per.connection" is set to one)
+    ///  3. and any of the following are true
+    ///     - The record set is full
+    ///     - The record set has sat in the accumulator for at least linger_ms milliseconds
+    ///     - The accumulator is out of memory and threads are blocking waiting for data (in this case all partitions
+    ///       are immediately considered ready).
+    ///     - The accumulator has been closed
+    pub fn ready(&self, cluster: &Cluster, now_ms: u128) -> ReadyCheckResult {
+        let mut ready_nodes = HashSet::new();
+        let mut next_ready_check_delay_ms = u128::MAX;
+        let mut unknown_leader_topics = HashSet::new();
+
+        let exhausted = self.free.queued() > 0;
+        for (part, deque) in self.entries() {
+            let deque = lock_deque(&deque);
+            // When producing to a large number of partitions, this path is hot and deques are often empty.
+            // We check whether a batch exists first to avoid the more expensive checks whenever possible.
+            let batch = match deque.front() {
+                Some(batch) => batch,
+                None => continue,
+            };
+            match cluster.leader_for(&part) {
+                None => {
+                    // This is a partition for which leader is not known, but messages are available to send.
+                    // Note that entries are currently not removed from batches when deque is empty.
+                    unknown_leader_topics.insert(part.topic.clone());
+                }
+                Some(leader) if !ready_nodes.contains(leader) && !self.is_muted(&part) => {
+                    let waited_time_ms = batch.waited_time_ms(now_ms);
+                    let backing_off =
+                        batch.attempts() > 0 && waited_time_ms < self.retry_backoff_ms;
+                    let time_to_wait_ms = if backing_off {
+                        self.retry_backoff_ms
+                    } else {
+                        self.linger_ms
+                    };
+                    let full = deque.len() > 1 || batch.is_full();
+                    let expired = waited_time_ms >= time_to_wait_ms;
+                    let sendable = full
+                        || expired
+                        || exhausted
+                        || self.closed.load(Ordering::SeqCst)
+                        || self.flush_in_progress();
+                    if sendable && !backing_off {
+                        ready_nodes.insert(leader.clone());
+                    } else {
+                        let time_left_ms = time_to_wait_ms.saturating_sub(waited_time_ms);
+                        // Note that this results in a conservative estimate since an un-sendable partition may have
+                        // a leader that will later be found to have sendable data. However, this is good enough
+                        // since we'll just wake up and then sleep again for the remaining time.
+                        next_ready_check_delay_ms = next_ready_check_delay_ms.min(time_left_ms);
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+        ReadyCheckResult {
+            ready_nodes,
+            next_ready_check_delay_ms,
+            unknown_leader_topics,
+        }
+    }
+
+    /// Check whether there are any batches which haven't been drained
+    pub fn has_undrained(&self) -> bool {
+        self.deques()
+            .iter()
+            .any(|deque| !lock_deque(deque).is_empty())
+    }
+
+    fn drain_batches_for_one_node(
+        &self,
+        cluster: &Cluster,
+        node: &Node,
+        max_size: usize,
+        now: u128,
+    ) -> Vec<Arc<ProducerBatch>> {
+        let mut size = 0;
+        let parts = cluster.partitions_for_node(node.id);
+        let mut ready = vec![];
+        if parts.is_empty() {
+            return ready;
+        }
+        /* to make starvation less likely this loop doesn't start at 0 */
+        let start = self.drain_index.load(Ordering::SeqCst) % parts.len();
+        let mut drain_index = start;
+        loop {
+            let part = &parts[drain_index];
+            let tp = TopicPartition::new(part.topic.clone(), part.partition);
+            drain_index = (drain_index + 1) % parts.len();
+            self.drain_index.store(drain_index, Ordering::SeqCst);
+
+            if let Some(deque) = self.get_deque(&tp).filter(|_| !self.is_muted(&tp)) {
+                let mut deque = lock_deque(&deque);
+                if let Some(first) = deque.front() {
+                    let backoff =
+                        first.attempts() > 0 && first.waited_time_ms(now) < self.retry_backoff_ms;
+                    // Only drain the batch if it is not during backoff period.
+                    if !backoff {
+                        if size + first.estimated_size_in_bytes() > max_size && !ready.is_empty() {
+                            // there is a rare case that a single batch size is larger than the request size due to
+                            // compression; in this case we will still eventually send this batch in a single request
+                            break;
+                        }
+                        if self.should_stop_drain_batches_for_partition(first, &tp) {
+                            break;
+                        }
+
+                        let batch = deque.pop_front().expect("deque is not empty");
+                        match self.close_for_drain(&batch) {
+                            Ok(batch_size) => {
+                                size += batch_size;
+                                batch.drained(now);
+                                ready.push(batch);
+                            }
+                            Err(error) => {
+                                // A batch which can't be closed would fail again on every drain, so it is failed
+                                // right away instead of blocking the partition. The completion callbacks must not
+                                // run while the deque lock is held.
+                                drop(deque);
+                                self.log.error(format_args!(
+                                    "Failed to drain batch {}: {}",
+                                    batch, error
+                                ));
+                                self.abort_batch(&batch, error);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if drain_index == start {
+                break;
+            }
+        }
+        ready
+    }
+
+    /// Assign the producer state to a batch about to be drained and close it, returning its size in bytes. The
+    /// transaction manager only tracks the batch once it has been closed, so a batch failing here leaves the
+    /// partition's sequence numbers untouched.
+    fn close_for_drain(&self, batch: &Arc<ProducerBatch>) -> Result<usize> {
+        let transaction_manager = match &self.transaction_manager {
+            Some(transaction_manager) if !batch.has_sequence() => transaction_manager,
+            _ => {
+                batch.close()?;
+                return Ok(batch.records()?.size_in_bytes());
+            }
+        };
+        let producer_id_and_epoch = transaction_manager.producer_id_and_epoch();
+        // If the producer id/epoch of the partition do not match the latest one
+        // of the producer, we update it and reset the sequence. This should be
+        // only done when all its in-flight batches have completed. This is guarantee
+        // in `should_stop_drain_batches_for_partition`.
+        transaction_manager.maybe_update_producer_id_and_epoch(&batch.topic_partition)?;
+
+        // If the batch already has an assigned sequence, then we should not change the producer id and
+        // sequence number, since this may introduce duplicates. In particular, the previous attempt
+        // may actually have been accepted, and if we change the producer id and sequence here, this
+        // attempt will also be accepted, causing a duplicate.
+        //
+        // Additionally, we update the next sequence number bound for the partition, and also have
+        // the transaction manager track the batch so as to ensure that sequence ordering is maintained
+        // even if we receive out of order responses.
+        batch.set_producer_state(
+            producer_id_and_epoch,
+            transaction_manager.sequence_number(&batch.topic_partition),
+            transaction_manager.is_transactional(),
+        )?;
+        batch.close()?;
+        let size = batch.records()?.size_in_bytes();
+        transaction_manager.add_in_flight_batch(batch)?;
+        transaction_manager
+            .increment_sequence_number(&batch.topic_partition, batch.record_count())?;
+        self.log.debug(format_args!(
+            "Assigned producerId {} and producerEpoch {} to batch with base sequence {} being sent to partition {}",
+            producer_id_and_epoch.producer_id,
+            producer_id_and_epoch.epoch,
+            batch.base_sequence(),
+            batch.topic_partition
+        ));
+        Ok(size)
+    }
+
+    fn should_stop_drain_batches_for_partition(
+        &self,
+        first: &ProducerBatch,
+        tp: &TopicPartition,
+    ) -> bool {
+        if let Some(transaction_manager) = &self.transaction_manager {
+            if !transaction_manager.is_send_to_partition_allowed(tp) {
+                return true;
+            }
+
+            if !transaction_manager.producer_id_and_epoch().is_valid() {
+                // we cannot send the batch until we have refreshed the producer id
+                return true;
+            }
+
+            if !first.has_sequence() {
+                if transaction_manager.has_inflight_batches(tp)
+                    && transaction_manager.has_stale_producer_id_and_epoch(tp)
+                {
+                    // Don't drain any new batches while the partition has in-flight batches with a different epoch
+                    // and/or producer ID. Otherwise, a batch with a new epoch and sequence number
+                    // 0 could be written before earlier batches complete, which would cause out of sequence errors
+                    return true;
+                }
+
+                if transaction_manager.has_unresolved_sequence(&first.topic_partition) {
+                    // Don't drain any new batches while the state of previous sequence numbers
+                    // is unknown. The previous batches would be unknown if they were aborted
+                    // on the client after being sent to the broker at least once.
+                    return true;
+                }
+            }
+
+            let first_in_flight_sequence =
+                transaction_manager.first_in_flight_sequence(&first.topic_partition);
+            if first_in_flight_sequence != NO_SEQUENCE
+                && first.has_sequence()
+                && first.base_sequence() != first_in_flight_sequence
+            {
+                // If the queued batch already has an assigned sequence, then it is being retried.
+                // In this case, we wait until the next immediate batch is ready and drain that.
+                // We only move on when the next in line batch is complete (either successfully or due to
+                // a fatal broker error). This effectively reduces our in flight request count to 1.
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Drain all the data for the given nodes and collate them into a list of batches that will fit within the specified
+    /// size on a per-node basis. This method attempts to avoid choosing the same topic-node over and over.
+    ///
+    /// Batches which fail to be closed are aborted with the error rather than drained.
+    ///
+    /// Returns a map from node id to a list of `ProducerBatch` for each node
+    pub fn drain(
+        &self,
+        cluster: &Cluster,
+        nodes: &HashSet<Node>,
+        max_size: usize,
+        now: u128,
+    ) -> HashMap<i32, Vec<Arc<ProducerBatch>>> {
+        let mut batches = HashMap::with_capacity(nodes.len());
+        for node in nodes {
+            let ready = self.drain_batches_for_one_node(cluster, node, max_size, now);
+            batches.insert(node.id, ready);
+        }
+        batches
+    }
+
+    /// The earliest absolute time a batch will expire (in milliseconds)
+    pub fn next_expiry_time_ms(&self) -> u128 {
+        *lock(&self.next_batch_expiry_time_ms)
+    }
+
+    fn get_deque(&self, tp: &TopicPartition) -> Option<Deque> {
+        self.batches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(tp)
+            .cloned()
+    }
+
+    /// Get the deque for the given topic-partition, creating it if necessary.
+    fn get_or_create_deque(&self, tp: &TopicPartition) -> Deque {
+        if let Some(deque) = self.get_deque(tp) {
+            return deque;
+        }
+        self.batches
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(tp.clone())
+            .or_default()
+            .clone()
+    }
+
+    fn entries(&self) -> Vec<(TopicPartition, Deque)> {
+        self.batches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(tp, deque)| (tp.clone(), deque.clone()))
+            .collect()
+    }
+
+    fn deques(&self) -> Vec<Deque> {
+        self.batches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Deallocate the record batch
+    pub fn deallocate(&self, batch: &ProducerBatch) -> Result<()> {
+        self.incomplete.remove(batch)?;
+        // Only deallocate the batch if it is not a split batch because split batch are allocated outside the
+        // buffer pool.
+        if !batch.is_split_batch() {
+            self.free
+                .deallocate(batch.take_buffer(), batch.initial_capacity());
+        }
+        Ok(())
+    }
+
+    /// Package private for unit test. Get the buffer pool remaining size in bytes.
+    pub fn buffer_pool_available_memory(&self) -> usize {
+        self.free.available_memory()
+    }
+
+    /// Are there any threads currently waiting on a flush?
+    pub fn flush_in_progress(&self) -> bool {
+        self.flushes_in_progress.load(Ordering::SeqCst) > 0
+    }
+
+    /// Initiate the flushing of data from the accumulator...this makes all requests immediately ready
+    pub fn begin_flush(&self) {
+        self.flushes_in_progress.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Are there any threads currently appending messages?
+    fn appends_in_progress(&self) -> bool {
+        self.appends_in_progress.load(Ordering::SeqCst) > 0
+    }
+
+    /// Mark all partitions as ready to send and block until the send is complete
+    pub fn await_flush_completion(&self) {
+        // Obtain a copy of all of the incomplete ProduceRequestResult(s) at the time of the flush.
+        // We must be careful not to hold a reference to the ProduceBatch(s) so that memory
+        // can be freed.
+        // The sender will remove ProducerBatch(s) from the original incomplete collection.
+        for result in self.incomplete.request_results() {
+            result.await_completion();
+        }
+        self.flushes_in_progress.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Check whether there are any pending batches (whether sent or unsent).
+    pub fn has_incomplete(&self) -> bool {
+        !self.incomplete.is_empty()
+    }
+
+    /// This function is only called when sender is closed forcefully. It will fail all the
+    /// incomplete batches and return.
+    pub fn abort_incomplete_batches(&self) {
+        // We need to keep aborting the incomplete batch until no thread is trying to append to
+        // 1. Avoid losing batches.
+        // 2. Free up memory in case appending threads are blocked on buffer full.
+        // This is a tight loop but should be able to get through very quickly.
+        loop {
+            self.abort_batches(KafkaError::Kafka(
+                "Producer is closed forcefully.".to_owned(),
+            ));
+            if !self.appends_in_progress() {
+                break;
+            }
+        }
+        // After this point, no thread will append any messages because they will see the close
+        // flag set. We need to do the last abort after no thread was appending in case there was a new
+        // batch appended by the last appending thread.
+        self.abort_batches(KafkaError::Kafka(
+            "Producer is closed forcefully.".to_owned(),
+        ));
+        self.batches
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Go through incomplete batches and abort them.
+    pub fn abort_batches(&self, reason: KafkaError) {
+        for batch in self.incomplete.copy_all() {
+            if let Some(dq) = self.get_deque(&batch.topic_partition) {
+                let mut dq = lock_deque(&dq);
+                batch.abort_record_appends();
+                dq.retain(|b| !Arc::ptr_eq(b, &batch));
+            }
+            self.abort_batch(&batch, reason.clone());
+        }
+    }
+
+    /// Abort any batches which have not been drained
+    pub fn abort_undrained_batches(&self, reason: KafkaError) {
+        for batch in self.incomplete.copy_all() {
+            let mut aborted = false;
+            if let Some(dq) = self.get_deque(&batch.topic_partition) {
+                let mut dq = lock_deque(&dq);
+                if !batch.is_closed() {
+                    aborted = true;
+                    batch.abort_record_appends();
+                    dq.retain(|b| !Arc::ptr_eq(b, &batch));
+                }
+            }
+            if aborted {
+                self.abort_batch(&batch, reason.clone());
+            }
+        }
+    }
+
+    fn abort_batch(&self, batch: &ProducerBatch, reason: KafkaError) {
+        if let Err(error) = batch.abort(reason).and_then(|_| self.deallocate(batch)) {
+            self.log
+                .warn(format_args!("Failed to abort batch {}: {}", batch, error));
+        }
+    }
+
+    pub fn mute_partition(&self, tp: TopicPartition) {
+        lock(&self.muted).insert(tp);
+    }
+
+    pub fn unmute_partition(&self, tp: &TopicPartition) {
+        lock(&self.muted).remove(tp);
+    }
+
+    /// We order the batches in the deque by sequence number, so that retried batches of an idempotent producer are
+    /// sent in the same order as they were originally, even with more than one request in flight.
+    ///
+    /// Batches which already have a sequence assigned are always at the front of the deque, followed by batches
+    /// which were never drained.
+    fn insert_in_sequence_order(
+        &self,
+        deque: &mut VecDeque<Arc<ProducerBatch>>,
+        batch: Arc<ProducerBatch>,
+    ) {
+        let position = deque
+            .iter()
+            .position(|b| !b.has_sequence() || b.base_sequence() > batch.base_sequence())
+            .unwrap_or(deque.len());
+        self.log.trace(format_args!(
+            "Reordered incoming batch with sequence {} for partition {}. It was placed in the queue at position {}",
+            batch.base_sequence(),
+            batch.topic_partition,
+            position
+        ));
+        deque.insert(position, batch);
+    }
+
+    /// Close this accumulator and force all the record buffers to be drained
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.free.close();
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock_deque(deque: &Deque) -> MutexGuard<'_, VecDeque<Arc<ProducerBatch>>> {
+    lock(deque)
+}
+
+/// `org.apache.kafka.clients.producer.internals.RustRecordAccumulator.ReadyCheckResult`
+impl CloneToJava for ReadyCheckResult {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let ready_nodes = self.ready_nodes.clone_to_java(env)?;
+        let unknown_leader_topics = self.unknown_leader_topics.clone_to_java(env)?;
+        jni_cache::new_object(
+            env,
+            "org/apache/kafka/clients/producer/internals/RustRecordAccumulator$ReadyCheckResult",
+            "(Ljava/util/Set;JLjava/util/Set;)V",
+            &[
+                ready_nodes,
+                self.next_ready_check_delay_ms.clone_to_java(env)?,
+                unknown_leader_topics,
+            ],
+        )
+        .map(JValue::Object)
+    }
+}
+
+/// `RecordAccumulator` backing a `RustRecordAccumulator`. Callbacks of the records appended by
+/// java run on the thread of `dispatcher`.
+#[derive(Clone)]
+struct JavaRecordAccumulator {
+    accumulator: Arc<RecordAccumulator>,
+    dispatcher: Arc<CallbackDispatcher>,
+}
+from_jobject!(
+    JavaRecordAccumulator,
+    "org/apache/kafka/clients/producer/internals/RustRecordAccumulator"
+);
+
+impl JavaRecordAccumulator {
+    /// The accumulator backing `obj`, cloned out of the lock: appends and flushes block.
+    fn get(env: JNIEnv, obj: JObject) -> JniResult<JavaRecordAccumulator> {
+        Ok(JavaRecordAccumulator::from_jobject(env, obj)?
+            .lock()
+            .clone())
+    }
+}
+
+fn java_batches(batches: Vec<Arc<ProducerBatch>>) -> Vec<JavaProducerBatch> {
+    batches.into_iter().map(JavaProducerBatch).collect()
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    rustConstructor
+ * Signature: (IJLorg/apache/kafka/common/record/CompressionType;IJI)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_rustConstructor(
+    env: JNIEnv,
+    obj: JObject,
+    batch_size: jint,
+    total_memory: jlong,
+    compression: JObject,
+    linger_ms: jint,
+    retry_backoff_ms: jlong,
+    delivery_timeout_ms: jint,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        let compression = CompressionType::clone_from_java(env, compression.into())?;
+        let time: Arc<dyn Time> = Arc::new(SystemTime);
+        let buffer_pool = BufferPool::new(total_memory as usize, batch_size as usize, time.clone());
+        let accumulator = RecordAccumulator::new(
+            batch_size as usize,
+            compression,
+            linger_ms as u128,
+            retry_backoff_ms as u128,
+            delivery_timeout_ms as u128,
+            time,
+            None,
+            Arc::new(buffer_pool),
+            &LogContext::default(),
+        )?;
+        let dispatcher = CallbackDispatcher::new("kafka-producer-callbacks").map_err(|e| {
+            KafkaError::Kafka(format!("Failed to start the callback thread: {}", e))
+        })?;
+        let accumulator = JavaRecordAccumulator {
+            accumulator: Arc::new(accumulator),
+            dispatcher: Arc::new(dispatcher),
+        };
+        JavaStoredObject::store(env, obj, accumulator)?;
+
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    rustDestructor
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_rustDestructor(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<JavaRecordAccumulator>::destroy(env, obj)?;
+
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    append
+ * Signature: (Lorg/apache/kafka/common/TopicPartition;JLjava/nio/ByteBuffer;Ljava/nio/ByteBuffer;Lorg/apache/kafka/common/header/internals/RecordHeaders;Lorg/apache/kafka/clients/producer/Callback;JJ)Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_append(
+    env: JNIEnv,
+    obj: JObject,
+    tp: JObject,
+    timestamp: jlong,
+    key: JObject,
+    value: JObject,
+    headers: JObject,
+    callback: JObject,
+    max_time_to_block_ms: jlong,
+    now_ms: jlong,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        let tp = TopicPartition::clone_from_java(env, tp.into())?;
+        let key = Option::<DirectByteBuffer>::clone_from_java(env, key.into())?;
+        let value = Option::<DirectByteBuffer>::clone_from_java(env, value.into())?;
+        let headers = if headers.is_null() {
+            vec![]
+        } else {
+            RecordHeaders::from_jobject(env, headers)?.lock().to_vec()
+        };
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        let mut callback = if callback.is_null() {
+            None
+        } else {
+            let callback = env.new_global_ref(callback)?;
+            Some(java_callback(java.dispatcher.clone(), callback))
+        };
+        let result = java.accumulator.append(
+            &tp,
+            timestamp,
+            key.as_ref().map(|key| &key.0[..]),
+            value.as_ref().map(|value| &value.0[..]),
+            &headers,
+            &mut callback,
+            max_time_to_block_ms as u128,
+            false,
+            now_ms as u128,
+        )?;
+        Ok((result.batch_is_full || result.new_batch_created) as jboolean)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    ready
+ * Signature: (Lorg/apache/kafka/common/Cluster;J)Lorg/apache/kafka/clients/producer/internals/RustRecordAccumulator$ReadyCheckResult;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_ready(
+    env: JNIEnv,
+    obj: JObject,
+    cluster: JObject,
+    now_ms: jlong,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let cluster = Cluster::clone_from_java(env, cluster.into())?;
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        let result = java.accumulator.ready(&cluster, now_ms as u128);
+        Ok(result.clone_to_java(env)?.l()?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    drain
+ * Signature: (Lorg/apache/kafka/common/Cluster;Ljava/util/Set;IJ)Ljava/util/Map;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_drain(
+    env: JNIEnv,
+    obj: JObject,
+    cluster: JObject,
+    nodes: JObject,
+    max_size: jint,
+    now_ms: jlong,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let cluster = Cluster::clone_from_java(env, cluster.into())?;
+        let nodes = HashSet::<Node>::clone_from_java(env, nodes.into())?;
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        let drained: HashMap<_, _> = java
+            .accumulator
+            .drain(&cluster, &nodes, max_size as usize, now_ms as u128)
+            .into_iter()
+            .map(|(node, batches)| (node, java_batches(batches)))
+            .collect();
+        Ok(drained.clone_to_java(env)?.l()?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    expiredBatches
+ * Signature: (J)Ljava/util/List;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_expiredBatches(
+    env: JNIEnv,
+    obj: JObject,
+    now: jlong,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        let expired = java_batches(java.accumulator.expired_batches(now as u128));
+        Ok(expired.clone_to_java(env)?.l()?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    reenqueue
+ * Signature: (Lorg/apache/kafka/clients/producer/internals/RustProducerBatch;J)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_reenqueue(
+    env: JNIEnv,
+    obj: JObject,
+    batch: JObject,
+    now: jlong,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        let batch = JavaProducerBatch::clone_from_java(env, batch.into())?;
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        java.accumulator.reenqueue(batch.0, now as u128);
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    splitAndReenqueue
+ * Signature: (Lorg/apache/kafka/clients/producer/internals/RustProducerBatch;)I
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_splitAndReenqueue(
+    env: JNIEnv,
+    obj: JObject,
+    big_batch: JObject,
+) -> jint {
+    jni_guard(env, || -> JniResult<_> {
+        let big_batch = JavaProducerBatch::clone_from_java(env, big_batch.into())?;
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        Ok(java.accumulator.split_and_reenqueue(&big_batch.0)? as jint)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    deallocate
+ * Signature: (Lorg/apache/kafka/clients/producer/internals/RustProducerBatch;)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_deallocate(
+    env: JNIEnv,
+    obj: JObject,
+    batch: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        let batch = JavaProducerBatch::clone_from_java(env, batch.into())?;
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        java.accumulator.deallocate(&batch.0)?;
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    mutePartition
+ * Signature: (Lorg/apache/kafka/common/TopicPartition;)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_mutePartition(
+    env: JNIEnv,
+    obj: JObject,
+    tp: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        let tp = TopicPartition::clone_from_java(env, tp.into())?;
+        JavaRecordAccumulator::get(env, obj)?
+            .accumulator
+            .mute_partition(tp);
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    unmutePartition
+ * Signature: (Lorg/apache/kafka/common/TopicPartition;)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_unmutePartition(
+    env: JNIEnv,
+    obj: JObject,
+    tp: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        let tp = TopicPartition::clone_from_java(env, tp.into())?;
+        JavaRecordAccumulator::get(env, obj)?
+            .accumulator
+            .unmute_partition(&tp);
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    hasUndrained
+ * Signature: ()Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_hasUndrained(
+    env: JNIEnv,
+    obj: JObject,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        Ok(java.accumulator.has_undrained() as jboolean)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    hasIncomplete
+ * Signature: ()Z
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_hasIncomplete(
+    env: JNIEnv,
+    obj: JObject,
+) -> jboolean {
+    jni_guard(env, || -> JniResult<_> {
+        let java = JavaRecordAccumulator::get(env, obj)?;
+        Ok(java.accumulator.has_incomplete() as jboolean)
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    beginFlush
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_beginFlush(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaRecordAccumulator::get(env, obj)?
+            .accumulator
+            .begin_flush();
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    awaitFlushCompletion
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_awaitFlushCompletion(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaRecordAccumulator::get(env, obj)?
+            .accumulator
+            .await_flush_completion();
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    abortIncompleteBatches
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_abortIncompleteBatches(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaRecordAccumulator::get(env, obj)?
+            .accumulator
+            .abort_incomplete_batches();
+        Ok(())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_clients_producer_internals_RustRecordAccumulator
+ * Method:    close
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_clients_producer_internals_RustRecordAccumulator_close(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaRecordAccumulator::get(env, obj)?.accumulator.close();
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, sync::Arc};
+
+    use bytes::BytesMut;
+    use jni::{
+        objects::{JObject, JValue},
+        JNIEnv,
+    };
+
+    use crate::{
+        clients::producer::internals::{
+            buffer_pool::BufferPool, future_record_metadata::FutureRecordMetadata,
+            producer_batch::ProducerBatch,
+        },
+        clone_from_java::CloneFromJava,
+        clone_to_java::CloneToJava,
+        common::{
+            cluster::Cluster,
+            errors::KafkaError,
+            node::Node,
+            protocol::errors::Errors,
+            record::{
+                compression_type::CompressionType, memory_records::MemoryRecords,
+                memory_records_builder::MemoryRecordsBuilder,
+            },
+            topic_partition::TopicPartition,
+            utils::{log_context::LogContext, mock_time::MockTime, time::Time},
+        },
+        direct_byte_buffer::DirectByteBuffer,
+        jvm,
+        test_utils::MockBroker,
+    };
+
+    use super::{RecordAccumulator, RecordAppendResult};
+
+    const TOPIC: &str = "test";
+    const BATCH_SIZE: usize = 1024;
+    const TOTAL_MEMORY: usize = 10 * BATCH_SIZE;
+    const LINGER_MS: u128 = 10;
+    const RETRY_BACKOFF_MS: u128 = 100;
+    const DELIVERY_TIMEOUT_MS: u128 = 3_200;
+    const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+    struct Context {
+        time: Arc<MockTime>,
+        broker: MockBroker,
+        cluster: Cluster,
+        accumulator: RecordAccumulator,
+    }
+
+    impl Context {
+        fn new() -> Context {
+            let broker = MockBroker::new();
+            let time = broker.time.clone();
+            let cluster = broker.cluster(TOPIC, 2);
+            let accumulator = RecordAccumulator::new(
+                BATCH_SIZE,
+                CompressionType::None,
+                LINGER_MS,
+                RETRY_BACKOFF_MS,
+                DELIVERY_TIMEOUT_MS,
+                time.clone(),
+                None,
+                Arc::new(BufferPool::new(TOTAL_MEMORY, BATCH_SIZE, time.clone())),
+                &LogContext::default(),
+            )
+            .unwrap();
+            Context {
+                time,
+                broker,
+                cluster,
+                accumulator,
+            }
+        }
+
+        fn append_result(&self, tp: &TopicPartition, value: &[u8]) -> RecordAppendResult {
+            let now = self.time.milliseconds();
+            self.accumulator
+                .append(
+                    tp,
+                    now as i64,
+                    Some(b"key"),
+                    Some(value),
+                    &[],
+                    &mut None,
+                    0,
+                    false,
+                    now,
+                )
+                .unwrap()
+        }
+
+        fn append(&self, tp: &TopicPartition, value: &[u8]) -> FutureRecordMetadata {
+            self.append_result(tp, value).future.unwrap()
+        }
+
+        /// Append records to the partition until its batch is full.
+        fn fill_batch(&self, tp: &TopicPartition) {
+            while !self.append_result(tp, &[0; 100]).batch_is_full {}
+        }
+
+        fn ready_nodes(&self) -> HashSet<Node> {
+            self.accumulator
+                .ready(&self.cluster, self.time.milliseconds())
+                .ready_nodes
+        }
+
+        fn drain(&self, max_size: usize) -> Vec<Arc<ProducerBatch>> {
+            let now = self.time.milliseconds();
+            self.accumulator
+                .drain(&self.cluster, &self.nodes(), max_size, now)
+                .remove(&self.broker.node.id)
+                .unwrap()
+        }
+
+        fn nodes(&self) -> HashSet<Node> {
+            HashSet::from_iter(vec![self.broker.node.clone()])
+        }
+    }
+
+    fn tp(partition: i32) -> TopicPartition {
+        TopicPartition::new(TOPIC.to_owned(), partition)
+    }
+
+    #[test]
+    fn batch_failing_to_close_is_aborted_instead_of_drained() {
+        let ctx = Context::new();
+        let future = ctx.append(&tp(0), b"value");
+        ctx.accumulator
+            .get_deque(&tp(0))
+            .unwrap()
+            .lock()
+            .unwrap()
+            .front()
+            .unwrap()
+            .abort_record_appends();
+        ctx.append(&tp(1), b"value");
+
+        let now = ctx.time.milliseconds();
+        let drained = ctx
+            .accumulator
+            .drain(&ctx.cluster, &ctx.nodes(), MAX_REQUEST_SIZE, now);
+
+        let batches = &drained[&ctx.broker.node.id];
+        assert_eq!(1, batches.len());
+        assert_eq!(tp(1), batches[0].topic_partition);
+        assert!(future.is_done());
+        assert!(matches!(future.get(), Err(KafkaError::IllegalState(_))));
+        assert!(!ctx.accumulator.has_undrained());
+        assert_eq!(
+            TOTAL_MEMORY - BATCH_SIZE,
+            ctx.accumulator.buffer_pool_available_memory()
+        );
+    }
+
+    #[test]
+    fn full_batch_is_ready_before_linger_expires() {
+        let ctx = Context::new();
+        let mut appends = 0;
+        let records_in_full_batch = loop {
+            appends += 1;
+            let result = ctx.append_result(&tp(0), &[0; 100]);
+            if result.batch_is_full {
+                // the record which did not fit anymore starts the next batch
+                break if result.new_batch_created {
+                    appends - 1
+                } else {
+                    appends
+                };
+            }
+            assert!(
+                ctx.ready_nodes().is_empty(),
+                "No partitions should be ready"
+            );
+        };
+
+        assert_eq!(
+            HashSet::from_iter(vec![ctx.broker.node.clone()]),
+            ctx.ready_nodes()
+        );
+        let batches = ctx.drain(MAX_REQUEST_SIZE);
+        assert_eq!(1, batches.len());
+        assert_eq!(records_in_full_batch, batches[0].record_count());
+    }
+
+    #[test]
+    fn batch_is_ready_once_linger_expires() {
+        let ctx = Context::new();
+        ctx.append(&tp(0), b"value");
+        let result = ctx.accumulator.ready(&ctx.cluster, ctx.time.milliseconds());
+        assert!(result.ready_nodes.is_empty());
+        assert_eq!(LINGER_MS, result.next_ready_check_delay_ms);
+
+        ctx.time.sleep(LINGER_MS);
+        assert_eq!(
+            HashSet::from_iter(vec![ctx.broker.node.clone()]),
+            ctx.ready_nodes()
+        );
+        let batches = ctx.drain(MAX_REQUEST_SIZE);
+        assert_eq!(1, batches.len());
+        assert_eq!(1, batches[0].record_count());
+        assert!(!ctx.accumulator.has_undrained());
+    }
+
+    #[test]
+    fn batch_with_unknown_leader_reports_its_topic() {
+        let ctx = Context::new();
+        ctx.append(&TopicPartition::new("unknown".to_owned(), 0), b"value");
+        ctx.time.sleep(LINGER_MS);
+        let result = ctx.accumulator.ready(&ctx.cluster, ctx.time.milliseconds());
+        assert!(result.ready_nodes.is_empty());
+        assert_eq!(
+            HashSet::from_iter(vec!["unknown".to_owned()]),
+            result.unknown_leader_topics
+        );
+    }
+
+    #[test]
+    fn drain_stops_at_max_size() {
+        let ctx = Context::new();
+        ctx.fill_batch(&tp(0));
+        ctx.fill_batch(&tp(1));
+
+        assert_eq!(1, ctx.drain(BATCH_SIZE).len());
+        let batches = ctx.drain(MAX_REQUEST_SIZE);
+        assert_eq!(2, batches.len());
+        assert_ne!(batches[0].topic_partition, batches[1].topic_partition);
+    }
+
+    #[test]
+    fn muted_partition_is_neither_ready_nor_drained() {
+        let ctx = Context::new();
+        ctx.append(&tp(0), b"value");
+        ctx.time.sleep(LINGER_MS);
+        ctx.accumulator.mute_partition(tp(0));
+        assert!(ctx.ready_nodes().is_empty());
+        assert!(ctx.drain(MAX_REQUEST_SIZE).is_empty());
+
+        ctx.accumulator.unmute_partition(&tp(0));
+        assert_eq!(1, ctx.drain(MAX_REQUEST_SIZE).len());
+    }
+
+    #[test]
+    fn reenqueued_batch_backs_off_before_retrying() {
+        let ctx = Context::new();
+        ctx.append(&tp(0), b"value");
+        ctx.time.sleep(LINGER_MS);
+        let batch = ctx.drain(MAX_REQUEST_SIZE).remove(0);
+
+        ctx.accumulator.reenqueue(batch, ctx.time.milliseconds());
+        let result = ctx.accumulator.ready(&ctx.cluster, ctx.time.milliseconds());
+        assert!(result.ready_nodes.is_empty());
+        assert_eq!(RETRY_BACKOFF_MS, result.next_ready_check_delay_ms);
+        assert!(ctx.drain(MAX_REQUEST_SIZE).is_empty());
+
+        ctx.time.sleep(RETRY_BACKOFF_MS);
+        assert_eq!(
+            HashSet::from_iter(vec![ctx.broker.node.clone()]),
+            ctx.ready_nodes()
+        );
+        let batches = ctx.drain(MAX_REQUEST_SIZE);
+        assert_eq!(1, batches.len());
+        assert_eq!(1, batches[0].attempts());
+    }
+
+    #[test]
+    fn batches_expire_after_delivery_timeout() {
+        let ctx = Context::new();
+        ctx.append(&tp(0), b"value");
+        ctx.append(&tp(1), b"value");
+        assert!(ctx
+            .accumulator
+            .expired_batches(ctx.time.milliseconds())
+            .is_empty());
+        assert_eq!(
+            ctx.time.milliseconds() + DELIVERY_TIMEOUT_MS,
+            ctx.accumulator.next_expiry_time_ms()
+        );
+
+        ctx.time.sleep(DELIVERY_TIMEOUT_MS);
+        let expired = ctx.accumulator.expired_batches(ctx.time.milliseconds());
+        assert_eq!(2, expired.len());
+        assert!(!ctx.accumulator.has_undrained());
+    }
+
+    #[test]
+    fn split_batches_are_reenqueued_in_front() {
+        let ctx = Context::new();
+        let now = ctx.time.milliseconds();
+        let records_builder = MemoryRecordsBuilder::for_producer(
+            BytesMut::new(),
+            CompressionType::None,
+            4 * BATCH_SIZE,
+        )
+        .unwrap();
+        let big_batch = ProducerBatch::new(tp(0), records_builder, now);
+        let mut futures = vec![];
+        while let Some(future) = big_batch
+            .try_append(now as i64, None, Some(&[0; 100]), &[], &mut None, now)
+            .unwrap()
+        {
+            futures.push(future);
+        }
+        ctx.append(&tp(0), b"value");
+
+        let num_split_batches = ctx.accumulator.split_and_reenqueue(&big_batch).unwrap();
+        assert!(num_split_batches > 1);
+
+        let mut drained = vec![];
+        while drained.len() < num_split_batches {
+            let batches = ctx.drain(MAX_REQUEST_SIZE);
+            assert!(!batches.is_empty());
+            drained.extend(batches);
+        }
+        let mut offset = 0;
+        for batch in &drained {
+            assert!(batch.is_split_batch());
+            assert!(batch.records().unwrap().size_in_bytes() <= BATCH_SIZE);
+            batch.complete(offset, -1).unwrap();
+            offset += batch.record_count() as i64;
+        }
+        for (index, future) in futures.iter().enumerate() {
+            assert_eq!(index as i64, future.get().unwrap().offset);
+        }
+        assert_eq!(futures.len() as i64, offset);
+        // The record appended before splitting stays queued behind the split batches
+        assert!(ctx.accumulator.has_undrained());
+    }
+
+    /// `RustRecordAccumulator` without linger, so batches are ready as soon as they are created.
+    fn java_accumulator(env: JNIEnv) -> jni::errors::Result<JObject> {
+        let compression = CompressionType::None.clone_to_java(env)?;
+        env.new_object(
+            "org/apache/kafka/clients/producer/internals/RustRecordAccumulator",
+            "(IJLorg/apache/kafka/common/record/CompressionType;IJI)V",
+            &[
+                JValue::Int(BATCH_SIZE as i32),
+                JValue::Long(TOTAL_MEMORY as i64),
+                compression,
+                JValue::Int(0),
+                JValue::Long(RETRY_BACKOFF_MS as i64),
+                JValue::Int(DELIVERY_TIMEOUT_MS as i32),
+            ],
+        )
+    }
+
+    /// Java `Cluster` with a single partition of `TOPIC` led by `leader`.
+    fn java_cluster<'a>(env: JNIEnv<'a>, leader: &Node) -> jni::errors::Result<JObject<'a>> {
+        let node = leader.clone_to_java(env)?.l()?;
+        let replicas = env.new_object_array(1, "org/apache/kafka/common/Node", node)?;
+        let partition = env.new_object(
+            "org/apache/kafka/common/PartitionInfo",
+            "(Ljava/lang/String;ILorg/apache/kafka/common/Node;[Lorg/apache/kafka/common/Node;[Lorg/apache/kafka/common/Node;)V",
+            &[
+                TOPIC.to_string().clone_to_java(env)?,
+                JValue::Int(0),
+                node.into(),
+                JObject::from(replicas).into(),
+                JObject::from(replicas).into(),
+            ],
+        )?;
+        let nodes = vec![leader.clone()].clone_to_java(env)?;
+        let partitions = env.new_object("java/util/ArrayList", "()V", &[])?;
+        env.call_method(
+            partitions,
+            "add",
+            "(Ljava/lang/Object;)Z",
+            &[partition.into()],
+        )?;
+        let empty = HashSet::<String>::new().clone_to_java(env)?;
+        env.new_object(
+            "org/apache/kafka/common/Cluster",
+            "(Ljava/lang/String;Ljava/util/Collection;Ljava/util/Collection;Ljava/util/Set;Ljava/util/Set;)V",
+            &[
+                "cluster".to_string().clone_to_java(env)?,
+                nodes,
+                partitions.into(),
+                empty,
+                empty,
+            ],
+        )
+    }
+
+    /// Appends a record to `accumulator` and drains the batch holding it.
+    fn append_and_drain<'a>(
+        env: JNIEnv<'a>,
+        accumulator: JObject<'a>,
+    ) -> jni::errors::Result<JObject<'a>> {
+        let leader = Node::new(0, "localhost", 9092);
+        let cluster = java_cluster(env, &leader)?;
+        let tp = TopicPartition::new(TOPIC, 0).clone_to_java(env)?;
+        let wakeup = env.call_method(
+            accumulator,
+            "append",
+            "(Lorg/apache/kafka/common/TopicPartition;JLjava/nio/ByteBuffer;Ljava/nio/ByteBuffer;Lorg/apache/kafka/common/header/internals/RecordHeaders;Lorg/apache/kafka/clients/producer/Callback;JJ)Z",
+            &[
+                tp,
+                JValue::Long(0),
+                jvm::direct_buffer(env, b"key")?,
+                jvm::direct_buffer(env, b"value")?,
+                JObject::null().into(),
+                JObject::null().into(),
+                JValue::Long(0),
+                JValue::Long(0),
+            ],
+        )?;
+        assert!(wakeup.z()?, "a new batch was created");
+
+        let ready = env.call_method(
+            accumulator,
+            "ready",
+            "(Lorg/apache/kafka/common/Cluster;J)Lorg/apache/kafka/clients/producer/internals/RustRecordAccumulator$ReadyCheckResult;",
+            &[cluster.into(), JValue::Long(0)],
+        )?.l()?;
+        let ready_nodes = env.get_field(ready, "readyNodes", "Ljava/util/Set;")?;
+        assert_eq!(
+            HashSet::from([leader.clone()]),
+            HashSet::<Node>::clone_from_java(env, ready_nodes)?
+        );
+
+        let drained = env
+            .call_method(
+                accumulator,
+                "drain",
+                "(Lorg/apache/kafka/common/Cluster;Ljava/util/Set;IJ)Ljava/util/Map;",
+                &[
+                    cluster.into(),
+                    ready_nodes,
+                    JValue::Int(MAX_REQUEST_SIZE as i32),
+                    JValue::Long(0),
+                ],
+            )?
+            .l()?;
+        let node_id = 0.clone_to_java_object(env)?;
+        let batches = env
+            .call_method(
+                drained,
+                "get",
+                "(Ljava/lang/Object;)Ljava/lang/Object;",
+                &[node_id.into()],
+            )?
+            .l()?;
+        assert_eq!(1, env.call_method(batches, "size", "()I", &[])?.i()?);
+        env.call_method(batches, "get", "(I)Ljava/lang/Object;", &[JValue::Int(0)])?
+            .l()
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn java_accumulator_drains_and_completes_batches() {
+        jvm::run(|env| {
+            let accumulator = java_accumulator(env)?;
+            let batch = append_and_drain(env, accumulator)?;
+
+            let tp = env.call_method(
+                batch,
+                "topicPartition",
+                "()Lorg/apache/kafka/common/TopicPartition;",
+                &[],
+            )?;
+            assert_eq!(
+                TopicPartition::new(TOPIC, 0),
+                TopicPartition::clone_from_java(env, tp)?
+            );
+            assert_eq!(1, env.call_method(batch, "recordCount", "()I", &[])?.i()?);
+            let records = env.call_method(batch, "records", "()Ljava/nio/ByteBuffer;", &[])?;
+            let records = DirectByteBuffer::clone_from_java(env, records)?;
+            let batches: Vec<_> = MemoryRecords::new(records.0)
+                .batches()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(1, batches.len());
+            let record = batches[0].records().unwrap().remove(0);
+            assert_eq!(Some(&b"value"[..]), record.value.as_deref());
+
+            assert!(env
+                .call_method(
+                    batch,
+                    "complete",
+                    "(JJ)Z",
+                    &[JValue::Long(5), JValue::Long(-1)]
+                )?
+                .z()?);
+            assert!(env.call_method(batch, "isDone", "()Z", &[])?.z()?);
+            let exception = jvm::expect_exception(env, || {
+                env.call_method(
+                    batch,
+                    "complete",
+                    "(JJ)Z",
+                    &[JValue::Long(5), JValue::Long(-1)],
+                )
+            })?;
+            assert_eq!("java.lang.IllegalStateException", exception);
+            env.call_method(
+                accumulator,
+                "deallocate",
+                "(Lorg/apache/kafka/clients/producer/internals/RustProducerBatch;)V",
+                &[batch.into()],
+            )?;
+            assert!(!env
+                .call_method(accumulator, "hasIncomplete", "()Z", &[])?
+                .z()?);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn java_batches_complete_with_the_error_of_the_code() {
+        jvm::run(|env| {
+            let accumulator = java_accumulator(env)?;
+            let batch = append_and_drain(env, accumulator)?;
+            let message = "leader moved".to_string().clone_to_java(env)?;
+
+            let exception = jvm::expect_exception(env, || {
+                env.call_method(
+                    batch,
+                    "completeExceptionally",
+                    "(SLjava/lang/String;)Z",
+                    &[JValue::Short(0), message],
+                )
+            })?;
+            assert_eq!("java.lang.IllegalArgumentException", exception);
+            assert!(!env.call_method(batch, "isDone", "()Z", &[])?.z()?);
+
+            let not_leader = Errors::NotLeaderOrFollower.code();
+            assert!(env
+                .call_method(
+                    batch,
+                    "completeExceptionally",
+                    "(SLjava/lang/String;)Z",
+                    &[JValue::Short(not_leader), message]
+                )?
+                .z()?);
+            assert!(env.call_method(batch, "isDone", "()Z", &[])?.z()?);
+            Ok(())
+        });
+    }
+}
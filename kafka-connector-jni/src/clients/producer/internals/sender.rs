@@ -0,0 +1,1318 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard, Weak,
+    },
+};
+
+use crate::{
+    clients::{client_response::ClientResponse, kafka_client::KafkaClient, network_client_utils},
+    common::{
+        errors::{KafkaError, Result},
+        node::Node,
+        protocol::errors::Errors,
+        record::record_batch::MAGIC_VALUE_V2,
+        requests::{
+            abstract_request::AbstractRequest,
+            abstract_response::AbstractResponse,
+            find_coordinator_request::CoordinatorType,
+            produce_request::ProduceRequest,
+            produce_response::{PartitionResponse, RecordError},
+        },
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::Time,
+        },
+    },
+};
+use indexmap::IndexMap;
+
+use super::{
+    produce_request_result::ErrorsByIndex,
+    producer_batch::ProducerBatch,
+    producer_metadata::ProducerMetadata,
+    record_accumulator::RecordAccumulator,
+    transaction_manager::{TransactionManager, TxnRequestHandler},
+};
+
+/// The background thread that handles the sending of produce requests to the Kafka cluster. This thread makes metadata
+/// requests to renew its view of the cluster and then sends produce requests to the appropriate nodes.
+pub struct Sender {
+    log: Logger,
+    /* the state of each nodes connection */
+    client: Arc<dyn KafkaClient>,
+    /* the record accumulator that batches records */
+    accumulator: Arc<RecordAccumulator>,
+    /* the metadata for the client */
+    metadata: Arc<ProducerMetadata>,
+    /* the flag indicating whether the producer should guarantee the message order on the broker or not. */
+    guarantee_message_order: bool,
+    /* the maximum request size to attempt to send to the server */
+    max_request_size: usize,
+    /* the number of acknowledgements to request from the server */
+    acks: i16,
+    /* the number of times to retry a failed request before giving up */
+    retries: i32,
+    /* the clock instance used for getting the time */
+    time: Arc<dyn Time>,
+    /* true while the sender thread is still running */
+    running: AtomicBool,
+    /* true when the caller wants to ignore all unsent/inflight messages and force close.  */
+    force_close: AtomicBool,
+    /* the max time to wait for the server to respond to the request*/
+    request_timeout_ms: u128,
+    /* The max time to wait before retrying a request which has failed */
+    retry_backoff_ms: u128,
+    /* the transaction manager tracking producer ids, sequence numbers and the transaction state */
+    transaction_manager: Option<Arc<TransactionManager>>,
+    // A per-partition queue of batches ordered by creation time for tracking the in-flight batches
+    in_flight_batches: Mutex<HashMap<TopicPartition, Vec<Arc<ProducerBatch>>>>,
+}
+
+impl Sender {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client: Arc<dyn KafkaClient>,
+        metadata: Arc<ProducerMetadata>,
+        accumulator: Arc<RecordAccumulator>,
+        guarantee_message_order: bool,
+        max_request_size: usize,
+        acks: i16,
+        retries: i32,
+        time: Arc<dyn Time>,
+        request_timeout_ms: u128,
+        retry_backoff_ms: u128,
+        transaction_manager: Option<Arc<TransactionManager>>,
+        log_context: &LogContext,
+    ) -> Sender {
+        Sender {
+            log: log_context.logger(module_path!()),
+            client,
+            accumulator,
+            metadata,
+            guarantee_message_order,
+            max_request_size,
+            acks,
+            retries,
+            time,
+            running: AtomicBool::new(true),
+            force_close: AtomicBool::new(false),
+            request_timeout_ms,
+            retry_backoff_ms,
+            transaction_manager,
+            in_flight_batches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn lock_in_flight_batches(
+        &self,
+    ) -> MutexGuard<'_, HashMap<TopicPartition, Vec<Arc<ProducerBatch>>>> {
+        self.in_flight_batches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn in_flight_batches(&self, tp: &TopicPartition) -> Vec<Arc<ProducerBatch>> {
+        self.lock_in_flight_batches()
+            .get(tp)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn maybe_remove_from_inflight_batches(&self, batch: &ProducerBatch) {
+        let mut in_flight_batches = self.lock_in_flight_batches();
+        if let Some(batches) = in_flight_batches.get_mut(&batch.topic_partition) {
+            batches.retain(|b| b.id() != batch.id());
+            if batches.is_empty() {
+                in_flight_batches.remove(&batch.topic_partition);
+            }
+        }
+    }
+
+    fn maybe_remove_and_deallocate_batch(&self, batch: &ProducerBatch) -> Result<()> {
+        self.maybe_remove_from_inflight_batches(batch);
+        self.accumulator.deallocate(batch)
+    }
+
+    /// Get the in-flight batches that has reached delivery timeout.
+    fn get_expired_inflight_batches(&self, now: u128) -> Result<Vec<Arc<ProducerBatch>>> {
+        let mut expired_batches = vec![];
+        let mut in_flight_batches = self.lock_in_flight_batches();
+        for partition_in_flight_batches in in_flight_batches.values_mut() {
+            while let Some(batch) = partition_in_flight_batches.first() {
+                if batch.has_reached_delivery_timeout(self.accumulator.delivery_timeout_ms(), now) {
+                    let batch = partition_in_flight_batches.remove(0);
+                    // expire_batches is called in Sender::send_producer_data, before client.poll.
+                    // The !batch.is_done() invariant should always hold. An IllegalState error
+                    // will be returned if the invariant is violated.
+                    if batch.is_done() {
+                        return Err(KafkaError::IllegalState(format!(
+                            "{} batch created at {} gets unexpected final state {:?}",
+                            batch.topic_partition,
+                            batch.created_ms,
+                            batch.final_state()
+                        )));
+                    }
+                    expired_batches.push(batch);
+                } else {
+                    self.accumulator.maybe_update_next_batch_expiry_time(batch);
+                    break;
+                }
+            }
+        }
+        in_flight_batches.retain(|_, batches| !batches.is_empty());
+        Ok(expired_batches)
+    }
+
+    pub fn add_to_inflight_batches(&self, batches: &HashMap<i32, Vec<Arc<ProducerBatch>>>) {
+        let mut in_flight_batches = self.lock_in_flight_batches();
+        for batch in batches.values().flatten() {
+            in_flight_batches
+                .entry(batch.topic_partition.clone())
+                .or_default()
+                .push(batch.clone());
+        }
+    }
+
+    /// The main run loop for the sender thread
+    pub fn run(self: &Arc<Self>) {
+        self.log
+            .debug(format_args!("Starting Kafka producer I/O thread."));
+
+        // main loop, runs until close is called
+        while self.running.load(Ordering::SeqCst) {
+            self.run_once_logging_errors();
+        }
+
+        self.log.debug(format_args!(
+            "Beginning shutdown of Kafka producer I/O thread, sending remaining records."
+        ));
+
+        // okay we stopped accepting requests but there may still be
+        // requests in the accumulator or waiting for acknowledgment,
+        // wait until these are completed.
+        while !self.force_close.load(Ordering::SeqCst)
+            && (self.accumulator.has_undrained()
+                || self.client.in_flight_request_count() > 0
+                || self.has_pending_transactional_requests())
+        {
+            self.run_once_logging_errors();
+        }
+
+        // Abort the transaction if any commit or abort didn't go through the transaction manager's queue
+        if let Some(transaction_manager) = &self.transaction_manager {
+            while !self.force_close.load(Ordering::SeqCst)
+                && transaction_manager.has_ongoing_transaction()
+            {
+                if !transaction_manager.is_completing() {
+                    self.log.info(format_args!(
+                        "Aborting incomplete transaction due to shutdown"
+                    ));
+                    if let Err(e) = transaction_manager.begin_abort() {
+                        self.log.error(format_args!(
+                            "Failed to abort incomplete transaction on shutdown: {}",
+                            e
+                        ));
+                        break;
+                    }
+                }
+                self.run_once_logging_errors();
+            }
+        }
+
+        if self.force_close.load(Ordering::SeqCst) {
+            // We need to fail all the incomplete batches and wake up the threads waiting on
+            // the futures.
+            if let Some(transaction_manager) = &self.transaction_manager {
+                self.log.debug(format_args!(
+                    "Aborting incomplete transactional requests due to forced shutdown"
+                ));
+                transaction_manager.close();
+            }
+            self.log.debug(format_args!(
+                "Aborting incomplete batches due to forced shutdown"
+            ));
+            self.accumulator.abort_incomplete_batches();
+        }
+        self.client.close();
+
+        self.log.debug(format_args!(
+            "Shutdown of Kafka producer I/O thread has completed."
+        ));
+    }
+
+    fn run_once_logging_errors(self: &Arc<Self>) {
+        if let Err(e) = self.run_once() {
+            self.log.error(format_args!(
+                "Uncaught error in kafka producer I/O thread: {}",
+                e
+            ));
+        }
+    }
+
+    fn has_pending_transactional_requests(&self) -> bool {
+        self.transaction_manager
+            .as_ref()
+            .map(|transaction_manager| {
+                transaction_manager.has_pending_requests()
+                    && transaction_manager.has_ongoing_transaction()
+            })
+            .unwrap_or(false)
+    }
+
+    /// Run a single iteration of sending
+    pub fn run_once(self: &Arc<Self>) -> Result<()> {
+        if let Some(transaction_manager) = &self.transaction_manager {
+            transaction_manager.maybe_resolve_sequences()?;
+
+            // do not continue sending if the transaction manager is in a failed state
+            if transaction_manager.has_fatal_error() {
+                if let Some(last_error) = transaction_manager.last_error() {
+                    self.maybe_abort_batches(last_error);
+                }
+                self.client
+                    .poll(self.retry_backoff_ms, self.time.milliseconds());
+                return Ok(());
+            }
+
+            // Check whether we need a new producerId. If so, we will enqueue an InitProducerId
+            // request which will be sent below
+            transaction_manager.bump_idempotent_epoch_and_reset_id_if_needed()?;
+
+            if self.maybe_send_and_poll_transactional_request(transaction_manager)? {
+                return Ok(());
+            }
+        }
+
+        let current_time_ms = self.time.milliseconds();
+        let poll_timeout = self.send_producer_data(current_time_ms)?;
+        self.client.poll(poll_timeout, current_time_ms);
+        Ok(())
+    }
+
+    fn send_producer_data(self: &Arc<Self>, now: u128) -> Result<u128> {
+        let cluster = self.metadata.fetch();
+        // get the list of partitions with data ready to send
+        let mut result = self.accumulator.ready(&cluster, now);
+
+        // if there are any partitions whose leaders are not known yet, force metadata update
+        if !result.unknown_leader_topics.is_empty() {
+            // The set of topics with unknown leader contains topics with leader election pending as well as
+            // topics which may have expired. Add the topic again to metadata to ensure it is included
+            // and request metadata update, since there are messages to send to the topic.
+            for topic in &result.unknown_leader_topics {
+                self.metadata.add(topic, now);
+            }
+            self.log.debug(format_args!(
+                "Requesting metadata update due to unknown leader topics from the batched records: {:?}",
+                result.unknown_leader_topics
+            ));
+            self.metadata.request_update();
+        }
+
+        // remove any nodes we aren't ready to send to
+        let mut not_ready_timeout = u128::MAX;
+        result.ready_nodes.retain(|node| {
+            if self.client.ready(node, now) {
+                true
+            } else {
+                not_ready_timeout = not_ready_timeout.min(self.client.poll_delay_ms(node, now));
+                false
+            }
+        });
+
+        // create produce requests
+        let batches =
+            self.accumulator
+                .drain(&cluster, &result.ready_nodes, self.max_request_size, now);
+        self.add_to_inflight_batches(&batches);
+        if self.guarantee_message_order {
+            // Mute all the partitions drained
+            for batch in batches.values().flatten() {
+                self.accumulator
+                    .mute_partition(batch.topic_partition.clone());
+            }
+        }
+
+        self.accumulator.reset_next_batch_expiry_time();
+        let expired_inflight_batches = self.get_expired_inflight_batches(now)?;
+        let mut expired_batches = self.accumulator.expired_batches(now);
+        expired_batches.extend(expired_inflight_batches);
+
+        if !expired_batches.is_empty() {
+            self.log.trace(format_args!(
+                "Expired {} batches in accumulator",
+                expired_batches.len()
+            ));
+        }
+        for expired_batch in expired_batches {
+            let error_message = format!(
+                "Expiring {} record(s) for {}:{} ms has passed since batch creation",
+                expired_batch.record_count(),
+                expired_batch.topic_partition,
+                now.saturating_sub(expired_batch.created_ms)
+            );
+            self.fail_batch(&expired_batch, KafkaError::Timeout(error_message), false)?;
+            if let Some(transaction_manager) = &self.transaction_manager {
+                if expired_batch.in_retry() {
+                    // This ensures that no new batches are drained until the current in flight batches are fully resolved.
+                    transaction_manager.mark_sequence_unresolved(&expired_batch);
+                }
+            }
+        }
+
+        // If we have any nodes that are ready to send + have sendable data, poll with 0 timeout so this can immediately
+        // loop and try sending more data. Otherwise, the timeout will be the smaller value between next batch expiry
+        // time, and the delay time for checking data availability. Note that the nodes may have data that isn't yet
+        // sendable due to lingering, backing off, etc. This specifically does not include nodes with sendable data
+        // that aren't ready to send since they would cause busy looping.
+        let mut poll_timeout = result.next_ready_check_delay_ms.min(not_ready_timeout);
+        poll_timeout = poll_timeout.min(self.accumulator.next_expiry_time_ms().saturating_sub(now));
+        if !result.ready_nodes.is_empty() {
+            self.log.trace(format_args!(
+                "Nodes with data ready to send: {:?}",
+                result.ready_nodes
+            ));
+            // if some partitions are already ready to be sent, the select time would be 0;
+            // otherwise if some partition already has some data accumulated but not ready yet,
+            // the select time will be the time difference between now and its linger expiry time;
+            // otherwise the select time will be the time difference between now and the metadata expiry time;
+            poll_timeout = 0;
+        }
+        self.send_produce_requests(batches, now);
+        Ok(poll_timeout)
+    }
+
+    /// Returns true if a transactional request is sent or polled, or if a FindCoordinator request is enqueued
+    fn maybe_send_and_poll_transactional_request(
+        &self,
+        transaction_manager: &Arc<TransactionManager>,
+    ) -> Result<bool> {
+        if transaction_manager.has_in_flight_request() {
+            // as long as there are outstanding transactional requests, we simply wait for them to return
+            self.client
+                .poll(self.retry_backoff_ms, self.time.milliseconds());
+            return Ok(true);
+        }
+
+        if (transaction_manager.has_abortable_error() || transaction_manager.is_aborting())
+            && self.accumulator.has_incomplete()
+        {
+            // Attempt to get the last error that caused this abort.
+            // If there was no error, but we are still aborting,
+            // then this is most likely a case where there was no fatal error.
+            let error = transaction_manager.last_error().unwrap_or_else(|| {
+                KafkaError::TransactionAborted(
+                    "Failing batch since transaction was aborted".to_owned(),
+                )
+            });
+            self.accumulator.abort_undrained_batches(error);
+        }
+
+        let next_request_handler =
+            match transaction_manager.next_request(self.accumulator.has_incomplete())? {
+                Some(next_request_handler) => next_request_handler,
+                None => return Ok(false),
+            };
+
+        let coordinator_type = next_request_handler.coordinator_type();
+        let target_node = match coordinator_type {
+            Some(coordinator_type) => transaction_manager.coordinator(coordinator_type),
+            None => self.client.least_loaded_node(self.time.milliseconds()),
+        };
+        let target_node = match target_node {
+            Some(target_node) => target_node,
+            None => {
+                match coordinator_type {
+                    Some(coordinator_type) => {
+                        self.log.trace(format_args!(
+                            "Coordinator not known for {}, will retry {} after finding coordinator.",
+                            coordinator_type,
+                            next_request_handler.request().api_key()
+                        ));
+                        self.maybe_find_coordinator_and_retry(
+                            transaction_manager,
+                            next_request_handler,
+                        );
+                    }
+                    None => {
+                        self.log.trace(format_args!("No nodes available to send requests, will poll and retry when until a node is ready."));
+                        transaction_manager.retry(next_request_handler);
+                        self.client
+                            .poll(self.retry_backoff_ms, self.time.milliseconds());
+                    }
+                }
+                return Ok(true);
+            }
+        };
+
+        match self.await_node_ready(transaction_manager, &target_node, coordinator_type) {
+            Ok(true) => {}
+            Ok(false) => {
+                self.log.trace(format_args!(
+                    "Target node {} not ready within request timeout, will retry when node is ready.",
+                    target_node
+                ));
+                self.maybe_find_coordinator_and_retry(transaction_manager, next_request_handler);
+                return Ok(true);
+            }
+            Err(e) => {
+                self.log.debug(format_args!(
+                    "Disconnect from {} while trying to send request {}. Going to back off and retry. {}",
+                    target_node, next_request_handler, e
+                ));
+                // We break here so that we pick up the FindCoordinator request immediately.
+                self.maybe_find_coordinator_and_retry(transaction_manager, next_request_handler);
+                return Ok(true);
+            }
+        }
+
+        if next_request_handler.is_retry() {
+            self.time.sleep(next_request_handler.retry_backoff_ms());
+        }
+
+        let current_time_ms = self.time.milliseconds();
+        let request = next_request_handler.request();
+        let request_description = request.to_string();
+        let weak_transaction_manager: Weak<TransactionManager> =
+            Arc::downgrade(transaction_manager);
+        let log = self.log.clone();
+        let callback = Box::new(move |response: ClientResponse| {
+            if let Some(transaction_manager) = weak_transaction_manager.upgrade() {
+                if let Err(e) = transaction_manager.handle_response(next_request_handler, response)
+                {
+                    log.error(format_args!(
+                        "Failed to handle transactional response: {}",
+                        e
+                    ));
+                }
+            }
+        });
+        let client_request = self.client.new_client_request(
+            &target_node.id_string(),
+            request,
+            current_time_ms,
+            true,
+            self.request_timeout_ms,
+            Some(callback),
+        );
+        let correlation_id = client_request.correlation_id;
+        self.log.debug(format_args!(
+            "Sending transactional request {} to node {} with correlation ID {}",
+            request_description, target_node, correlation_id
+        ));
+        // The correlation id must be registered before the request can complete, as the
+        // completion handler checks it.
+        transaction_manager.set_in_flight_correlation_id(correlation_id);
+        self.client.send(client_request, current_time_ms);
+        self.client
+            .poll(self.retry_backoff_ms, self.time.milliseconds());
+        Ok(true)
+    }
+
+    fn maybe_find_coordinator_and_retry(
+        &self,
+        transaction_manager: &TransactionManager,
+        next_request_handler: TxnRequestHandler,
+    ) {
+        if next_request_handler.needs_coordinator() {
+            transaction_manager.lookup_coordinator(&next_request_handler);
+        } else {
+            // For non-coordinator requests, sleep here to prevent a tight loop when no node is available
+            self.time.sleep(self.retry_backoff_ms);
+            self.metadata.request_update();
+        }
+
+        transaction_manager.retry(next_request_handler);
+    }
+
+    fn maybe_abort_batches(&self, error: KafkaError) {
+        if self.accumulator.has_incomplete() {
+            self.log.error(format_args!(
+                "Aborting producer batches due to fatal error: {}",
+                error
+            ));
+            self.accumulator.abort_batches(error);
+        }
+    }
+
+    fn await_node_ready(
+        &self,
+        transaction_manager: &TransactionManager,
+        node: &Node,
+        coordinator_type: Option<CoordinatorType>,
+    ) -> Result<bool> {
+        if network_client_utils::await_ready(
+            self.client.as_ref(),
+            node,
+            self.time.as_ref(),
+            self.request_timeout_ms,
+        )? {
+            if coordinator_type == Some(CoordinatorType::Transaction) {
+                // Indicate to the transaction manager that the coordinator is ready, allowing it to check ApiVersions
+                // This allows us to bump transactional epochs even if the coordinator is temporarily unavailable at
+                // the time when the abortable error is handled
+                transaction_manager.handle_coordinator_ready();
+            }
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Start closing the sender (won't actually complete until all data is sent out)
+    pub fn initiate_close(&self) {
+        // Ensure accumulator is closed first to guarantee that no more appends are accepted after
+        // breaking from the sender loop. Otherwise, we may miss some callbacks when shutting down.
+        self.accumulator.close();
+        self.running.store(false, Ordering::SeqCst);
+        self.wakeup();
+    }
+
+    /// Closes the sender without sending out any pending messages.
+    pub fn force_close(&self) {
+        self.force_close.store(true, Ordering::SeqCst);
+        self.initiate_close();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Handle a produce response
+    fn handle_produce_response(
+        &self,
+        response: ClientResponse,
+        batches: &HashMap<TopicPartition, Arc<ProducerBatch>>,
+        now: u128,
+    ) -> Result<()> {
+        let request_header = &response.request_header;
+        let correlation_id = request_header.correlation_id;
+        if response.disconnected {
+            self.log.trace(format_args!(
+                "Cancelled request with header {} due to node {} being disconnected",
+                request_header, response.destination
+            ));
+            for batch in batches.values() {
+                self.complete_batch(
+                    batch,
+                    PartitionResponse::from_error_with_message(
+                        Errors::NetworkException,
+                        Some(format!("Disconnected from node {}", response.destination)),
+                    ),
+                    correlation_id,
+                    now,
+                )?;
+            }
+        } else if let Some(version_mismatch) = &response.version_mismatch {
+            self.log.warn(format_args!(
+                "Cancelled request {} due to a version mismatch with node {}: {}",
+                response, response.destination, version_mismatch
+            ));
+            for batch in batches.values() {
+                self.complete_batch(
+                    batch,
+                    PartitionResponse::from_error(Errors::UnsupportedVersion),
+                    correlation_id,
+                    now,
+                )?;
+            }
+        } else {
+            self.log.trace(format_args!(
+                "Received produce response from node {} with correlation id {}",
+                response.destination, correlation_id
+            ));
+            // if we have a response, parse it
+            match response.response_body {
+                Some(AbstractResponse::Produce(produce_response)) => {
+                    for (tp, partition_response) in produce_response.responses {
+                        match batches.get(&tp) {
+                            Some(batch) => self.complete_batch(
+                                batch,
+                                partition_response,
+                                correlation_id,
+                                now,
+                            )?,
+                            None => self.log.warn(format_args!(
+                                "Received produce response for partition {} which was not part of the request with correlation id {}",
+                                tp, correlation_id
+                            )),
+                        }
+                    }
+                }
+                Some(other) => {
+                    return Err(KafkaError::IllegalState(format!(
+                        "Unexpected response type {} for produce request",
+                        other.api_key()
+                    )))
+                }
+                None => {
+                    // this is the acks = 0 case, just complete all requests
+                    for batch in batches.values() {
+                        self.complete_batch(
+                            batch,
+                            PartitionResponse::from_error(Errors::None),
+                            correlation_id,
+                            now,
+                        )?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Complete or retry the given batch of records.
+    ///
+    /// * `batch` - The record batch
+    /// * `response` - The produce response
+    /// * `correlation_id` - The correlation id for the request
+    /// * `now` - The current POSIX timestamp in milliseconds
+    fn complete_batch(
+        &self,
+        batch: &Arc<ProducerBatch>,
+        response: PartitionResponse,
+        correlation_id: i32,
+        now: u128,
+    ) -> Result<()> {
+        let error = response.error;
+
+        if error == Errors::MessageTooLarge
+            && batch.record_count() > 1
+            && !batch.is_done()
+            && batch.magic() >= MAGIC_VALUE_V2
+        {
+            // If the batch is too large, we split the batch and send the split batches again. We do not decrement
+            // the retry attempts in this case.
+            self.log.warn(format_args!(
+                "Got error produce response in correlation id {} on topic-partition {}, splitting and retrying ({} attempts left). Error: {}",
+                correlation_id,
+                batch.topic_partition,
+                self.retries - batch.attempts(),
+                format_err_msg(&response)
+            ));
+            if let Some(transaction_manager) = &self.transaction_manager {
+                transaction_manager.remove_in_flight_batch(batch);
+            }
+            self.accumulator.split_and_reenqueue(batch)?;
+            self.maybe_remove_and_deallocate_batch(batch)?;
+        } else if error != Errors::None {
+            if self.can_retry(batch, &response, now)? {
+                self.log.warn(format_args!(
+                    "Got error produce response with correlation id {} on topic-partition {}, retrying ({} attempts left). Error: {}",
+                    correlation_id,
+                    batch.topic_partition,
+                    self.retries - batch.attempts() - 1,
+                    format_err_msg(&response)
+                ));
+                self.reenqueue_batch(batch, now);
+            } else if error == Errors::DuplicateSequenceNumber {
+                // If we have received a duplicate sequence error, it means that the sequence number has advanced beyond
+                // the sequence of the current batch, and we haven't retained batch metadata on the broker to return
+                // the correct offset and timestamp.
+                //
+                // The only thing we can do is to return success to the user and not return a valid offset and timestamp.
+                self.complete_batch_successfully(batch, &response)?;
+            } else {
+                // tell the user the result of their request. We only adjust sequence numbers if the batch didn't exhaust
+                // its retries -- if it did, we don't know whether the sequence number was accepted or not, and
+                // thus it is not safe to reassign the sequence.
+                self.fail_batch_with_response(batch, &response, batch.attempts() < self.retries)?;
+            }
+            if let Some(exception) = error.exception(response.error_message.as_deref()) {
+                if exception.is_invalid_metadata() {
+                    if let KafkaError::UnknownTopicOrPartition(_) = exception {
+                        self.log.warn(format_args!(
+                            "Received unknown topic or partition error in produce request on partition {}. The topic-partition may not exist or the user may not have Describe access to it",
+                            batch.topic_partition
+                        ));
+                    } else {
+                        self.log.warn(format_args!(
+                            "Received invalid metadata error in produce request on partition {} due to {}. Going to request metadata update now",
+                            batch.topic_partition, exception
+                        ));
+                    }
+                    self.metadata.request_update();
+                }
+            }
+        } else {
+            self.complete_batch_successfully(batch, &response)?;
+        }
+
+        // Unmute the completed partition.
+        if self.guarantee_message_order {
+            self.accumulator.unmute_partition(&batch.topic_partition);
+        }
+        Ok(())
+    }
+
+    fn reenqueue_batch(&self, batch: &Arc<ProducerBatch>, current_time_ms: u128) {
+        self.accumulator.reenqueue(batch.clone(), current_time_ms);
+        self.maybe_remove_from_inflight_batches(batch);
+    }
+
+    fn complete_batch_successfully(
+        &self,
+        batch: &ProducerBatch,
+        response: &PartitionResponse,
+    ) -> Result<()> {
+        if let Some(transaction_manager) = &self.transaction_manager {
+            transaction_manager.handle_completed_batch(batch, response)?;
+        }
+
+        if batch.complete(response.base_offset, response.log_append_time)? {
+            self.maybe_remove_and_deallocate_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    fn fail_batch_with_response(
+        &self,
+        batch: &ProducerBatch,
+        response: &PartitionResponse,
+        adjust_sequence_numbers: bool,
+    ) -> Result<()> {
+        let top_level_error = match response.error {
+            Errors::TopicAuthorizationFailed => KafkaError::TopicAuthorization(format!(
+                "Not authorized to access topics: [{}]",
+                batch.topic_partition.topic
+            )),
+            Errors::ClusterAuthorizationFailed => KafkaError::ClusterAuthorization(
+                "The producer is not authorized to do idempotent sends".to_owned(),
+            ),
+            error => error
+                .exception(response.error_message.as_deref())
+                .unwrap_or_else(|| KafkaError::UnknownServer(error.message().to_owned())),
+        };
+
+        if response.record_errors.is_empty() {
+            return self.fail_batch(batch, top_level_error, adjust_sequence_numbers);
+        }
+
+        let record_error_map = record_error_map(response);
+        let record_errors: ErrorsByIndex = Arc::new(move |batch_index| {
+            match record_error_map.get(&batch_index) {
+                Some(error) => error.clone(),
+                // If the response contains record errors, then the records which failed validation
+                // will be present in the response. To avoid confusion for the remaining records, we
+                // return a generic exception.
+                None => KafkaError::Kafka(
+                    "Failed to append record because it was part of a batch which had one more more invalid records"
+                        .to_owned(),
+                ),
+            }
+        });
+        self.fail_batch_with_record_errors(
+            batch,
+            top_level_error,
+            record_errors,
+            adjust_sequence_numbers,
+        )
+    }
+
+    fn fail_batch(
+        &self,
+        batch: &ProducerBatch,
+        top_level_error: KafkaError,
+        adjust_sequence_numbers: bool,
+    ) -> Result<()> {
+        let record_error = top_level_error.clone();
+        self.fail_batch_with_record_errors(
+            batch,
+            top_level_error,
+            Arc::new(move |_| record_error.clone()),
+            adjust_sequence_numbers,
+        )
+    }
+
+    fn fail_batch_with_record_errors(
+        &self,
+        batch: &ProducerBatch,
+        top_level_error: KafkaError,
+        record_errors: ErrorsByIndex,
+        adjust_sequence_numbers: bool,
+    ) -> Result<()> {
+        if let Some(transaction_manager) = &self.transaction_manager {
+            transaction_manager.handle_failed_batch(
+                batch,
+                &top_level_error,
+                adjust_sequence_numbers,
+            )?;
+        }
+
+        if batch.complete_exceptionally(top_level_error, record_errors)? {
+            self.maybe_remove_and_deallocate_batch(batch)?;
+        }
+        Ok(())
+    }
+
+    /// We can retry a send if the error is transient and the number of attempts taken is fewer than the maximum allowed.
+    fn can_retry(
+        &self,
+        batch: &ProducerBatch,
+        response: &PartitionResponse,
+        now: u128,
+    ) -> Result<bool> {
+        if batch.has_reached_delivery_timeout(self.accumulator.delivery_timeout_ms(), now)
+            || batch.attempts() >= self.retries
+            || batch.is_done()
+        {
+            return Ok(false);
+        }
+        match &self.transaction_manager {
+            Some(transaction_manager) => transaction_manager.can_retry(response, batch),
+            None => Ok(response
+                .error
+                .exception(None)
+                .map(|e| e.is_retriable())
+                .unwrap_or(false)),
+        }
+    }
+
+    /// Transfer the record batches into a list of produce requests on a per-node basis
+    fn send_produce_requests(
+        self: &Arc<Self>,
+        collated: HashMap<i32, Vec<Arc<ProducerBatch>>>,
+        now: u128,
+    ) {
+        for (destination, batches) in collated {
+            if let Err(e) = self.send_produce_request(now, destination, batches) {
+                self.log.error(format_args!(
+                    "Failed to send produce request to node {}: {}",
+                    destination, e
+                ));
+            }
+        }
+    }
+
+    /// Create a produce request from the given record batches
+    fn send_produce_request(
+        self: &Arc<Self>,
+        now: u128,
+        destination: i32,
+        batches: Vec<Arc<ProducerBatch>>,
+    ) -> Result<()> {
+        if batches.is_empty() {
+            return Ok(());
+        }
+
+        let mut partition_records = IndexMap::with_capacity(batches.len());
+        let mut records_by_partition = HashMap::with_capacity(batches.len());
+        for batch in batches {
+            let tp = batch.topic_partition.clone();
+            partition_records.insert(tp.clone(), batch.records()?);
+            records_by_partition.insert(tp, batch);
+        }
+
+        let transactional_id = self
+            .transaction_manager
+            .as_ref()
+            .filter(|transaction_manager| transaction_manager.is_transactional())
+            .and_then(|transaction_manager| transaction_manager.transactional_id())
+            .map(str::to_owned);
+        let request = ProduceRequest::new(
+            self.acks,
+            self.request_timeout_ms.min(i32::MAX as u128) as i32,
+            transactional_id,
+            partition_records,
+        );
+        let request_description = request.to_string();
+        let sender: Weak<Sender> = Arc::downgrade(self);
+        let callback = Box::new(move |response: ClientResponse| {
+            if let Some(sender) = sender.upgrade() {
+                let now = sender.time.milliseconds();
+                if let Err(e) = sender.handle_produce_response(response, &records_by_partition, now)
+                {
+                    sender
+                        .log
+                        .error(format_args!("Failed to handle produce response: {}", e));
+                }
+            }
+        });
+
+        let node_id = destination.to_string();
+        let client_request = self.client.new_client_request(
+            &node_id,
+            AbstractRequest::Produce(request),
+            now,
+            self.acks != 0,
+            self.request_timeout_ms,
+            Some(callback),
+        );
+        self.client.send(client_request, now);
+        self.log.trace(format_args!(
+            "Sent produce request to {}: {}",
+            node_id, request_description
+        ));
+        Ok(())
+    }
+
+    /// Wake up the selector associated with this send thread
+    pub fn wakeup(&self) {
+        self.client.wakeup();
+    }
+}
+
+/// Format the error from a `PartitionResponse` in a user-friendly string
+/// e.g "NETWORK_EXCEPTION. Error Message: Disconnected from node 0"
+fn format_err_msg(response: &PartitionResponse) -> String {
+    match response.error_message.as_deref() {
+        None | Some("") => response.error.to_string(),
+        Some(message) => format!("{}. Error Message: {}", response.error, message),
+    }
+}
+
+fn record_error_map(response: &PartitionResponse) -> HashMap<usize, KafkaError> {
+    let mut record_error_map = HashMap::with_capacity(response.record_errors.len());
+    for RecordError {
+        batch_index,
+        message,
+    } in &response.record_errors
+    {
+        // The API leaves us with some awkwardness interpreting the errors in the response.
+        // We cannot differentiate between different error cases (such as INVALID_TIMESTAMP)
+        // from the single error code at the partition level, so instead we use INVALID_RECORD
+        // for all failed records and rely on the message to distinguish the cases.
+        let error_message = message
+            .as_deref()
+            .or(response.error_message.as_deref())
+            .unwrap_or_else(|| response.error.message())
+            .to_owned();
+
+        // If the batch contained only a single record error, then we can unambiguously
+        // use the exception type corresponding to the partition-level error code.
+        let error = if response.record_errors.len() == 1 {
+            response
+                .error
+                .exception(Some(&error_message))
+                .unwrap_or(KafkaError::InvalidRecord(error_message))
+        } else {
+            KafkaError::InvalidRecord(error_message)
+        };
+        record_error_map.insert(*batch_index, error);
+    }
+    record_error_map
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::{
+            mock_client::MockClient,
+            producer::internals::{
+                buffer_pool::BufferPool, future_record_metadata::FutureRecordMetadata,
+                producer_metadata::ProducerMetadata, record_accumulator::RecordAccumulator,
+            },
+        },
+        common::{
+            errors::KafkaError,
+            protocol::errors::Errors,
+            record::{compression_type::CompressionType, record_batch::RecordBatch},
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                produce_response::{PartitionResponse, ProduceResponse},
+            },
+            topic_partition::TopicPartition,
+            utils::{log_context::LogContext, mock_time::MockTime, time::Time},
+        },
+        test_utils::MockBroker,
+    };
+
+    use super::Sender;
+
+    const TOPIC: &str = "test";
+    const RETRY_BACKOFF_MS: u128 = 100;
+    const DELIVERY_TIMEOUT_MS: u128 = 1500;
+    const REQUEST_TIMEOUT_MS: u128 = 1000;
+
+    /// A sender without transaction manager talking to a `MockClient` broker.
+    struct Context {
+        time: Arc<MockTime>,
+        client: Arc<MockClient>,
+        metadata: Arc<ProducerMetadata>,
+        accumulator: Arc<RecordAccumulator>,
+        sender: Arc<Sender>,
+    }
+
+    impl Context {
+        fn new(guarantee_message_order: bool, retries: i32) -> Context {
+            let broker = MockBroker::new();
+            let cluster = broker.cluster(TOPIC, 2);
+            let MockBroker { time, client, .. } = broker;
+
+            let now = time.milliseconds();
+            let metadata = Arc::new(ProducerMetadata::new(
+                RETRY_BACKOFF_MS,
+                300_000,
+                300_000,
+                time.clone(),
+            ));
+            metadata.add(TOPIC, now);
+            metadata
+                .update(metadata.request_version(), cluster, false, now)
+                .unwrap();
+
+            let accumulator = Arc::new(
+                RecordAccumulator::new(
+                    16384,
+                    CompressionType::None,
+                    0,
+                    RETRY_BACKOFF_MS,
+                    DELIVERY_TIMEOUT_MS,
+                    time.clone(),
+                    None,
+                    Arc::new(BufferPool::new(1024 * 1024, 16384, time.clone())),
+                    &LogContext::default(),
+                )
+                .unwrap(),
+            );
+            let sender = Arc::new(Sender::new(
+                client.clone(),
+                metadata.clone(),
+                accumulator.clone(),
+                guarantee_message_order,
+                1024 * 1024,
+                -1,
+                retries,
+                time.clone(),
+                REQUEST_TIMEOUT_MS,
+                RETRY_BACKOFF_MS,
+                None,
+                &LogContext::new("[Producer clientId=test] "),
+            ));
+            Context {
+                time,
+                client,
+                metadata,
+                accumulator,
+                sender,
+            }
+        }
+
+        fn append(&self, tp: &TopicPartition) -> FutureRecordMetadata {
+            let now = self.time.milliseconds();
+            self.accumulator
+                .append(
+                    tp,
+                    now as i64,
+                    Some(b"key"),
+                    Some(b"value"),
+                    &[],
+                    &mut None,
+                    0,
+                    false,
+                    now,
+                )
+                .unwrap()
+                .future
+                .unwrap()
+        }
+
+        fn run_once(&self) {
+            self.sender.run_once().unwrap();
+        }
+
+        /// The records of `tp` in the produce request, panics if it is not a produce request.
+        fn sent_batch(&self, index: usize, tp: &TopicPartition) -> RecordBatch {
+            match &self.client.requests()[index] {
+                AbstractRequest::Produce(request) => request
+                    .partition_records
+                    .get(tp)
+                    .and_then(|records| records.batches().next())
+                    .map(|batch| batch.unwrap())
+                    .unwrap(),
+                request => panic!("Unexpected request {}", request),
+            }
+        }
+    }
+
+    fn tp(partition: i32) -> TopicPartition {
+        TopicPartition::new(TOPIC, partition)
+    }
+
+    fn produce_response(tp: &TopicPartition, error: Errors, base_offset: i64) -> AbstractResponse {
+        let response = if error == Errors::None {
+            PartitionResponse::new(error, base_offset, -1, 0, vec![], None)
+        } else {
+            PartitionResponse::from_error(error)
+        };
+        AbstractResponse::Produce(ProduceResponse::new(
+            IndexMap::from_iter(vec![(tp.clone(), response)]),
+            0,
+        ))
+    }
+
+    #[test]
+    fn retriable_error_is_retried_after_the_backoff() {
+        let context = Context::new(false, 1);
+        let future = context.append(&tp(0));
+        context.run_once();
+        assert_eq!(context.client.requests().len(), 1);
+
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::NotLeaderOrFollower, -1)));
+        context.run_once();
+        assert!(!future.is_done());
+        // the error invalidates the metadata of the partition
+        assert!(context.metadata.update_requested());
+        // the batch is backing off
+        context.run_once();
+        assert!(context.client.requests().is_empty());
+
+        context.time.sleep(RETRY_BACKOFF_MS);
+        context.run_once();
+        assert_eq!(context.client.requests().len(), 1);
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 42)));
+        context.run_once();
+        assert_eq!(future.value_or_error().unwrap().offset, 42);
+    }
+
+    #[test]
+    fn batch_fails_once_retries_are_exhausted() {
+        let context = Context::new(false, 1);
+        let future = context.append(&tp(0));
+        for _ in 0..2 {
+            context.run_once();
+            assert_eq!(context.client.requests().len(), 1);
+            assert!(context.client.respond(produce_response(
+                &tp(0),
+                Errors::NotEnoughReplicas,
+                -1
+            )));
+            context.run_once();
+            context.time.sleep(RETRY_BACKOFF_MS);
+        }
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::NotEnoughReplicas(_))
+        ));
+        assert!(context.sender.in_flight_batches(&tp(0)).is_empty());
+    }
+
+    #[test]
+    fn non_retriable_error_fails_the_batch() {
+        let context = Context::new(false, i32::MAX);
+        let future = context.append(&tp(0));
+        context.run_once();
+        assert!(context.client.respond(produce_response(
+            &tp(0),
+            Errors::TopicAuthorizationFailed,
+            -1
+        )));
+        context.run_once();
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::TopicAuthorization(_))
+        ));
+    }
+
+    #[test]
+    fn message_too_large_splits_the_batch_without_using_a_retry() {
+        let context = Context::new(false, 0);
+        let first = context.append(&tp(0));
+        let second = context.append(&tp(0));
+        context.run_once();
+        assert_eq!(context.sent_batch(0, &tp(0)).records().unwrap().len(), 2);
+
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::MessageTooLarge, -1)));
+        context.run_once();
+        assert!(!first.is_done());
+        assert!(!second.is_done());
+
+        // the split batches are sent right away, they were never tried
+        while context.client.requests().is_empty() {
+            context.run_once();
+        }
+        let batch = context.sent_batch(0, &tp(0));
+        assert_eq!(batch.records().unwrap().len(), 2);
+        let in_flight = context.sender.in_flight_batches(&tp(0));
+        assert_eq!(in_flight.len(), 1);
+        assert!(in_flight[0].is_split_batch());
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 10)));
+        context.run_once();
+        // the futures returned by the appends are chained to the split batches
+        assert_eq!(first.get().unwrap().offset, 10);
+        assert_eq!(second.get().unwrap().offset, 11);
+    }
+
+    #[test]
+    fn in_flight_batch_expires_at_the_delivery_timeout() {
+        let context = Context::new(false, i32::MAX);
+        let future = context.append(&tp(0));
+        context.run_once();
+        assert_eq!(context.sender.in_flight_batches(&tp(0)).len(), 1);
+
+        context.time.sleep(DELIVERY_TIMEOUT_MS - 1);
+        context.run_once();
+        assert!(!future.is_done());
+
+        context.time.sleep(1);
+        context.run_once();
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::Timeout(_))
+        ));
+        assert!(context.sender.in_flight_batches(&tp(0)).is_empty());
+
+        // a late response for the expired batch is ignored
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 0)));
+        context.run_once();
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::Timeout(_))
+        ));
+    }
+
+    #[test]
+    fn partition_is_muted_while_a_batch_is_in_flight() {
+        let context = Context::new(true, i32::MAX);
+        let first = context.append(&tp(0));
+        context.run_once();
+        assert_eq!(context.client.requests().len(), 1);
+
+        // the next batch of the partition waits for the in-flight one, other partitions don't
+        let second = context.append(&tp(0));
+        let other = context.append(&tp(1));
+        context.run_once();
+        let requests = context.client.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(matches!(&requests[1], AbstractRequest::Produce(request)
+                if request.partition_records.keys().eq([&tp(1)])));
+
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 0)));
+        context.run_once();
+        assert!(first.is_done());
+        assert!(!second.is_done());
+        // unmuted once the first batch completed
+        context.run_once();
+        assert_eq!(context.client.requests().len(), 2);
+        let batch = context.sent_batch(1, &tp(0));
+        assert_eq!(batch.records().unwrap().len(), 1);
+
+        for (tp, offset) in [(tp(1), 0), (tp(0), 1)] {
+            assert!(context
+                .client
+                .respond(produce_response(&tp, Errors::None, offset)));
+        }
+        context.run_once();
+        assert_eq!(other.value_or_error().unwrap().offset, 0);
+        assert_eq!(second.value_or_error().unwrap().offset, 1);
+    }
+}
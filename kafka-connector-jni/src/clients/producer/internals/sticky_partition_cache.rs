@@ -0,0 +1,135 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::common::{
+    cluster::Cluster,
+    errors::{KafkaError, Result},
+    utils,
+};
+
+/// An internal class that implements a cache used for sticky partitioning behavior. The cache tracks the current sticky
+/// partition for any given topic. This class should not be used externally.
+#[derive(Default)]
+pub struct StickyPartitionCache {
+    index_cache: Mutex<HashMap<String, i32>>,
+}
+
+impl StickyPartitionCache {
+    pub fn new() -> StickyPartitionCache {
+        StickyPartitionCache::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, i32>> {
+        self.index_cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn partition(&self, topic: &str, cluster: &Cluster) -> Result<i32> {
+        let part = self.lock().get(topic).copied();
+        match part {
+            Some(part) => Ok(part),
+            None => self.next_partition(topic, cluster, -1),
+        }
+    }
+
+    pub fn next_partition(
+        &self,
+        topic: &str,
+        cluster: &Cluster,
+        prev_partition: i32,
+    ) -> Result<i32> {
+        let mut index_cache = self.lock();
+        let old_part = index_cache.get(topic).copied();
+        // Check that the current sticky partition for the topic is either not set or that the partition that
+        // triggered the new batch matches the sticky partition that needs to be changed.
+        if old_part.is_none() || old_part == Some(prev_partition) {
+            let available_partitions = cluster.available_partitions_for_topic(topic);
+            let new_part = if available_partitions.is_empty() {
+                let partitions = cluster.partitions_for_topic(topic);
+                if partitions.is_empty() {
+                    return Err(KafkaError::IllegalArgument(format!(
+                        "Topic {} has no partitions",
+                        topic
+                    )));
+                }
+                let random = utils::to_positive(random_int());
+                random % partitions.len() as i32
+            } else if available_partitions.len() == 1 {
+                available_partitions[0].partition
+            } else {
+                loop {
+                    let random = utils::to_positive(random_int());
+                    let new_part = available_partitions
+                        [(random % available_partitions.len() as i32) as usize]
+                        .partition;
+                    if Some(new_part) != old_part {
+                        break new_part;
+                    }
+                }
+            };
+            // Only change the sticky partition if it is not set or prev_partition matches the current sticky partition.
+            index_cache.insert(topic.to_owned(), new_part);
+            return Ok(new_part);
+        }
+        Ok(old_part.expect("sticky partition is set"))
+    }
+}
+
+/// A random integer, the per instance random keys of the standard library hasher are the source
+/// of randomness.
+fn random_int() -> i32 {
+    RandomState::new().build_hasher().finish() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::node::Node, test_utils::cluster_with_leaders};
+
+    use super::StickyPartitionCache;
+
+    const TOPIC: &str = "test";
+
+    #[test]
+    fn next_partition_changes_only_for_the_sticky_partition() {
+        let node = Node::new(0, "localhost", 9092);
+        let cluster =
+            cluster_with_leaders(TOPIC, &[Some(node.clone()), Some(node.clone()), Some(node)]);
+        let cache = StickyPartitionCache::new();
+        let sticky = cache.partition(TOPIC, &cluster).unwrap();
+        assert_eq!(cache.partition(TOPIC, &cluster).unwrap(), sticky);
+
+        let other = (sticky + 1) % 3;
+        assert_eq!(
+            cache.next_partition(TOPIC, &cluster, other).unwrap(),
+            sticky
+        );
+        for _ in 0..10 {
+            let previous = cache.partition(TOPIC, &cluster).unwrap();
+            let next = cache.next_partition(TOPIC, &cluster, previous).unwrap();
+            assert_ne!(next, previous);
+            assert_eq!(cache.partition(TOPIC, &cluster).unwrap(), next);
+        }
+    }
+
+    #[test]
+    fn single_available_partition_is_always_chosen() {
+        let node = Node::new(0, "localhost", 9092);
+        let cluster = cluster_with_leaders(TOPIC, &[None, Some(node), None]);
+        let cache = StickyPartitionCache::new();
+        assert_eq!(cache.partition(TOPIC, &cluster).unwrap(), 1);
+        assert_eq!(cache.next_partition(TOPIC, &cluster, 1).unwrap(), 1);
+    }
+
+    #[test]
+    fn unavailable_partitions_are_used_when_none_is_available() {
+        let cluster = cluster_with_leaders(TOPIC, &[None, None]);
+        let cache = StickyPartitionCache::new();
+        let partition = cache.partition(TOPIC, &cluster).unwrap();
+        assert!((0..2).contains(&partition));
+
+        let empty = cluster_with_leaders("empty", &[]);
+        assert!(cache.partition("empty", &empty).is_err());
+    }
+}
@@ -0,0 +1,2981 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::{
+        api_versions::ApiVersions,
+        client_response::ClientResponse,
+        consumer::{
+            consumer_group_metadata::ConsumerGroupMetadata, offset_and_metadata::OffsetAndMetadata,
+        },
+    },
+    common::{
+        errors::{KafkaError, Result},
+        node::Node,
+        protocol::{api_keys::ApiKeys, errors::Errors},
+        record::{default_record_batch, record_batch::NO_SEQUENCE},
+        requests::{
+            abstract_request::AbstractRequest,
+            abstract_response::AbstractResponse,
+            add_offsets_to_txn_request::AddOffsetsToTxnRequest,
+            add_partitions_to_txn_request::AddPartitionsToTxnRequest,
+            end_txn_request::{EndTxnRequest, TransactionResult},
+            find_coordinator_request::{CoordinatorType, FindCoordinatorRequest},
+            init_producer_id_request::InitProducerIdRequest,
+            produce_response::{PartitionResponse, INVALID_OFFSET},
+            txn_offset_commit_request::{CommittedOffset, TxnOffsetCommitRequest},
+        },
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            producer_id_and_epoch::ProducerIdAndEpoch,
+        },
+    },
+};
+
+use super::{
+    producer_batch::ProducerBatch, transactional_request_result::TransactionalRequestResult,
+};
+
+const NO_INFLIGHT_REQUEST_CORRELATION_ID: i32 = -1;
+const NO_LAST_ACKED_SEQUENCE_NUMBER: i32 = -1;
+
+// The retryBackoff is overridden to the following value if the first AddPartitions receives a
+// CONCURRENT_TRANSACTIONS error.
+const ADD_PARTITIONS_RETRY_BACKOFF_MS: u128 = 20;
+
+/// A class which maintains state for transactions. Also keeps the state necessary to ensure idempotent production.
+pub struct TransactionManager {
+    log: Logger,
+    transactional_id: Option<String>,
+    transaction_timeout_ms: i32,
+    // This is used by the TxnRequestHandlers to control how long to back off before a given request is retried.
+    // For instance, this value is lowered by the AddPartitionsToTxnHandler when it receives a CONCURRENT_TRANSACTIONS
+    // error for the first AddPartitionsRequest in a transaction.
+    retry_backoff_ms: u128,
+    api_versions: Arc<ApiVersions>,
+    state: Mutex<TransactionManagerState>,
+}
+
+struct TransactionManagerState {
+    topic_partition_bookkeeper: TopicPartitionBookkeeper,
+    pending_txn_offset_commits: IndexMap<TopicPartition, CommittedOffset>,
+    // If a batch bound for a partition expired locally after being sent at least once, the partition has is considered
+    // to have an unresolved state. We keep track fo such partitions here, and cannot assign any more sequence numbers
+    // for this partition until the unresolved state gets cleared. This may happen if other inflight batches returned
+    // successfully (indicating that the expired batch actually made it to the broker). If we don't get any successful
+    // responses for the partition once the inflight request count falls to zero, we reset the producer id and
+    // consequently clear this data structure as well.
+    // The value of the map is the sequence number of the batch following the expired one, computed by adding its
+    // record count to its sequence number. This is used to tell if a subsequent batch is the one immediately following
+    // the expired one.
+    partitions_with_unresolved_sequences: HashMap<TopicPartition, i32>,
+    // The partitions that have received an error that triggers an epoch bump. When the epoch is bumped, these
+    // partitions will have the sequences of their in-flight batches rewritten
+    partitions_to_rewrite_sequences: HashSet<TopicPartition>,
+    pending_requests: Vec<TxnRequestHandler>,
+    new_partitions_in_transaction: HashSet<TopicPartition>,
+    pending_partitions_in_transaction: HashSet<TopicPartition>,
+    partitions_in_transaction: HashSet<TopicPartition>,
+    pending_result: Option<Arc<TransactionalRequestResult>>,
+    in_flight_request_correlation_id: i32,
+    transaction_coordinator: Option<Node>,
+    consumer_group_coordinator: Option<Node>,
+    coordinator_supports_bumping_epoch: bool,
+    current_state: State,
+    last_error: Option<KafkaError>,
+    producer_id_and_epoch: ProducerIdAndEpoch,
+    transaction_started: bool,
+    epoch_bump_required: bool,
+}
+
+#[derive(Default)]
+struct TopicPartitionBookkeeper {
+    topic_partitions: HashMap<TopicPartition, TopicPartitionEntry>,
+}
+
+impl TopicPartitionBookkeeper {
+    fn get_partition(
+        &mut self,
+        topic_partition: &TopicPartition,
+    ) -> Result<&mut TopicPartitionEntry> {
+        self.topic_partitions.get_mut(topic_partition).ok_or_else(|| {
+            KafkaError::IllegalState(format!(
+                "Trying to get the sequence number for {}, but the sequence number was never set for this partition.",
+                topic_partition
+            ))
+        })
+    }
+
+    fn get_or_create_partition(
+        &mut self,
+        topic_partition: &TopicPartition,
+    ) -> &mut TopicPartitionEntry {
+        self.topic_partitions
+            .entry(topic_partition.clone())
+            .or_insert_with(TopicPartitionEntry::new)
+    }
+
+    fn add_partition(&mut self, topic_partition: &TopicPartition) {
+        self.get_or_create_partition(topic_partition);
+    }
+
+    fn contains(&self, topic_partition: &TopicPartition) -> bool {
+        self.topic_partitions.contains_key(topic_partition)
+    }
+
+    fn reset(&mut self) {
+        self.topic_partitions.clear();
+    }
+
+    fn last_acked_offset(&self, topic_partition: &TopicPartition) -> Option<i64> {
+        self.topic_partitions
+            .get(topic_partition)
+            .map(|entry| entry.last_acked_offset)
+            .filter(|offset| *offset != INVALID_OFFSET)
+    }
+
+    fn last_acked_sequence(&self, topic_partition: &TopicPartition) -> Option<i32> {
+        self.topic_partitions
+            .get(topic_partition)
+            .map(|entry| entry.last_acked_sequence)
+            .filter(|sequence| *sequence != NO_LAST_ACKED_SEQUENCE_NUMBER)
+    }
+
+    fn start_sequences_at_beginning(
+        &mut self,
+        topic_partition: &TopicPartition,
+        new_producer_id_and_epoch: ProducerIdAndEpoch,
+    ) -> Result<()> {
+        let mut sequence = 0;
+        let topic_partition_entry = self.get_partition(topic_partition)?;
+        topic_partition_entry.reset_sequence_numbers(|in_flight_batch| {
+            in_flight_batch.reset_producer_state(
+                new_producer_id_and_epoch,
+                sequence,
+                in_flight_batch.is_transactional(),
+            )?;
+            sequence += in_flight_batch.record_count();
+            Ok(())
+        })?;
+        topic_partition_entry.producer_id_and_epoch = new_producer_id_and_epoch;
+        topic_partition_entry.next_sequence = sequence;
+        topic_partition_entry.last_acked_sequence = NO_LAST_ACKED_SEQUENCE_NUMBER;
+        Ok(())
+    }
+}
+
+struct TopicPartitionEntry {
+    // The producer id/epoch being used for a given partition.
+    producer_id_and_epoch: ProducerIdAndEpoch,
+    // The base sequence of the next batch bound for a given partition.
+    next_sequence: i32,
+    // The sequence number of the last record of the last ack'd batch from the given partition. When there are no
+    // in flight requests for a partition, the lastAckedSequence(topicPartition) == nextSequence(topicPartition) - 1.
+    last_acked_sequence: i32,
+    // Keep track of the in flight batches bound for a partition, ordered by sequence. This helps us to ensure that
+    // we continue to order batches by the sequence numbers even when the responses come back out of order during
+    // leader failover. We add a batch to the queue when it is drained, and remove it when the batch completes
+    // (either successfully or through a fatal failure).
+    inflight_batches_by_sequence: Vec<Arc<ProducerBatch>>,
+    // We keep track of the last acknowledged offset on a per partition basis in order to disambiguate UnknownProducer
+    // responses which are due to the retention period elapsing, and those which are due to actual lost data.
+    last_acked_offset: i64,
+}
+
+impl TopicPartitionEntry {
+    fn new() -> TopicPartitionEntry {
+        TopicPartitionEntry {
+            producer_id_and_epoch: ProducerIdAndEpoch::NONE,
+            next_sequence: 0,
+            last_acked_sequence: NO_LAST_ACKED_SEQUENCE_NUMBER,
+            inflight_batches_by_sequence: Vec::new(),
+            last_acked_offset: INVALID_OFFSET,
+        }
+    }
+
+    /// Adds the batch keeping the in flight batches ordered by base sequence. A batch with an
+    /// already tracked base sequence is not added twice.
+    fn add_in_flight_batch(&mut self, batch: Arc<ProducerBatch>) {
+        let base_sequence = batch.base_sequence();
+        if let Err(index) = self
+            .inflight_batches_by_sequence
+            .binary_search_by_key(&base_sequence, |b| b.base_sequence())
+        {
+            self.inflight_batches_by_sequence.insert(index, batch);
+        }
+    }
+
+    fn reset_sequence_numbers(
+        &mut self,
+        mut reset_sequence: impl FnMut(&ProducerBatch) -> Result<()>,
+    ) -> Result<()> {
+        for inflight_batch in &self.inflight_batches_by_sequence {
+            reset_sequence(inflight_batch)?;
+        }
+        self.inflight_batches_by_sequence
+            .sort_by_key(|batch| batch.base_sequence());
+        self.inflight_batches_by_sequence
+            .dedup_by_key(|batch| batch.base_sequence());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Uninitialized,
+    Initializing,
+    Ready,
+    InTransaction,
+    CommittingTransaction,
+    AbortingTransaction,
+    AbortableError,
+    FatalError,
+}
+
+impl State {
+    fn is_transition_valid(source: State, target: State) -> bool {
+        match target {
+            State::Uninitialized => source == State::Ready,
+            State::Initializing => {
+                source == State::Uninitialized || source == State::AbortingTransaction
+            }
+            State::Ready => {
+                source == State::Initializing
+                    || source == State::CommittingTransaction
+                    || source == State::AbortingTransaction
+            }
+            State::InTransaction => source == State::Ready,
+            State::CommittingTransaction => source == State::InTransaction,
+            State::AbortingTransaction => {
+                source == State::InTransaction || source == State::AbortableError
+            }
+            State::AbortableError => {
+                source == State::InTransaction
+                    || source == State::CommittingTransaction
+                    || source == State::AbortableError
+            }
+            // We can transition to FATAL_ERROR unconditionally.
+            // FATAL_ERROR is never a valid starting state for any transition. So the only option is to close the
+            // producer or do purely non transactional requests.
+            State::FatalError => true,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            State::Uninitialized => "UNINITIALIZED",
+            State::Initializing => "INITIALIZING",
+            State::Ready => "READY",
+            State::InTransaction => "IN_TRANSACTION",
+            State::CommittingTransaction => "COMMITTING_TRANSACTION",
+            State::AbortingTransaction => "ABORTING_TRANSACTION",
+            State::AbortableError => "ABORTABLE_ERROR",
+            State::FatalError => "FATAL_ERROR",
+        }
+    }
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// We use the priority to determine the order in which requests need to be sent out. For instance, if we have
+// a pending FindCoordinator request, that must always go first. Next, If we need a producer id, that must go second.
+// The endTxn request must always go last, unless we are bumping the epoch (a special case of InitProducerId) as
+// part of ending the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    FindCoordinator = 0,
+    InitProducerId = 1,
+    AddPartitionsOrOffsets = 2,
+    EndTxn = 3,
+    EpochBump = 4,
+}
+
+enum TxnRequestKind {
+    InitProducerId {
+        request: InitProducerIdRequest,
+        is_epoch_bump: bool,
+    },
+    AddPartitionsToTxn(AddPartitionsToTxnRequest),
+    FindCoordinator(FindCoordinatorRequest),
+    EndTxn(EndTxnRequest),
+    AddOffsetsToTxn {
+        request: AddOffsetsToTxnRequest,
+        offsets: HashMap<TopicPartition, OffsetAndMetadata>,
+        group_metadata: ConsumerGroupMetadata,
+    },
+    TxnOffsetCommit(TxnOffsetCommitRequest),
+}
+
+/// A pending transactional request together with the result it completes. Handlers are taken
+/// from the `TransactionManager` by the sender using `next_request` and handed back through
+/// `handle_response` (or `retry`) once the request completes.
+pub struct TxnRequestHandler {
+    result: Arc<TransactionalRequestResult>,
+    is_retry: bool,
+    retry_backoff_ms: u128,
+    coordinator_type: Option<CoordinatorType>,
+    coordinator_key: Option<String>,
+    kind: TxnRequestKind,
+}
+
+impl TxnRequestHandler {
+    fn new(
+        result: Arc<TransactionalRequestResult>,
+        retry_backoff_ms: u128,
+        coordinator_type: Option<CoordinatorType>,
+        coordinator_key: Option<String>,
+        kind: TxnRequestKind,
+    ) -> TxnRequestHandler {
+        TxnRequestHandler {
+            result,
+            is_retry: false,
+            retry_backoff_ms,
+            coordinator_type,
+            coordinator_key,
+            kind,
+        }
+    }
+
+    /// The request to send to the broker.
+    pub fn request(&self) -> AbstractRequest {
+        match &self.kind {
+            TxnRequestKind::InitProducerId { request, .. } => {
+                AbstractRequest::InitProducerId(request.clone())
+            }
+            TxnRequestKind::AddPartitionsToTxn(request) => {
+                AbstractRequest::AddPartitionsToTxn(request.clone())
+            }
+            TxnRequestKind::FindCoordinator(request) => {
+                AbstractRequest::FindCoordinator(request.clone())
+            }
+            TxnRequestKind::EndTxn(request) => AbstractRequest::EndTxn(request.clone()),
+            TxnRequestKind::AddOffsetsToTxn { request, .. } => {
+                AbstractRequest::AddOffsetsToTxn(request.clone())
+            }
+            TxnRequestKind::TxnOffsetCommit(request) => {
+                AbstractRequest::TxnOffsetCommit(request.clone())
+            }
+        }
+    }
+
+    pub fn priority(&self) -> Priority {
+        match &self.kind {
+            TxnRequestKind::InitProducerId { is_epoch_bump, .. } => {
+                if *is_epoch_bump {
+                    Priority::EpochBump
+                } else {
+                    Priority::InitProducerId
+                }
+            }
+            TxnRequestKind::FindCoordinator(_) => Priority::FindCoordinator,
+            TxnRequestKind::EndTxn(_) => Priority::EndTxn,
+            TxnRequestKind::AddPartitionsToTxn(_)
+            | TxnRequestKind::AddOffsetsToTxn { .. }
+            | TxnRequestKind::TxnOffsetCommit(_) => Priority::AddPartitionsOrOffsets,
+        }
+    }
+
+    pub fn result(&self) -> &Arc<TransactionalRequestResult> {
+        &self.result
+    }
+
+    pub fn retry_backoff_ms(&self) -> u128 {
+        self.retry_backoff_ms
+    }
+
+    pub fn needs_coordinator(&self) -> bool {
+        self.coordinator_type.is_some()
+    }
+
+    pub fn coordinator_type(&self) -> Option<CoordinatorType> {
+        self.coordinator_type
+    }
+
+    pub fn is_retry(&self) -> bool {
+        self.is_retry
+    }
+
+    pub fn is_end_txn(&self) -> bool {
+        matches!(self.kind, TxnRequestKind::EndTxn(_))
+    }
+
+    fn set_retry(&mut self) {
+        self.is_retry = true;
+    }
+
+    fn fail(&self, error: KafkaError) {
+        self.result.fail(error);
+    }
+}
+
+impl Display for TxnRequestHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.request().fmt(f)
+    }
+}
+
+impl TransactionManager {
+    pub fn new(
+        transactional_id: Option<String>,
+        transaction_timeout_ms: i32,
+        retry_backoff_ms: u128,
+        api_versions: Arc<ApiVersions>,
+        log_context: &LogContext,
+    ) -> TransactionManager {
+        TransactionManager {
+            log: log_context.logger(module_path!()),
+            transactional_id,
+            transaction_timeout_ms,
+            retry_backoff_ms,
+            api_versions,
+            state: Mutex::new(TransactionManagerState {
+                topic_partition_bookkeeper: TopicPartitionBookkeeper::default(),
+                pending_txn_offset_commits: IndexMap::new(),
+                partitions_with_unresolved_sequences: HashMap::new(),
+                partitions_to_rewrite_sequences: HashSet::new(),
+                pending_requests: Vec::new(),
+                new_partitions_in_transaction: HashSet::new(),
+                pending_partitions_in_transaction: HashSet::new(),
+                partitions_in_transaction: HashSet::new(),
+                pending_result: None,
+                in_flight_request_correlation_id: NO_INFLIGHT_REQUEST_CORRELATION_ID,
+                transaction_coordinator: None,
+                consumer_group_coordinator: None,
+                coordinator_supports_bumping_epoch: false,
+                current_state: State::Uninitialized,
+                last_error: None,
+                producer_id_and_epoch: ProducerIdAndEpoch::NONE,
+                transaction_started: false,
+                epoch_bump_required: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TransactionManagerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn initialize_transactions(&self) -> Result<Arc<TransactionalRequestResult>> {
+        let mut state = self.lock();
+        self.initialize_transactions_with(&mut state, ProducerIdAndEpoch::NONE)
+    }
+
+    fn initialize_transactions_with(
+        &self,
+        state: &mut TransactionManagerState,
+        producer_id_and_epoch: ProducerIdAndEpoch,
+    ) -> Result<Arc<TransactionalRequestResult>> {
+        let is_epoch_bump = producer_id_and_epoch != ProducerIdAndEpoch::NONE;
+        self.handle_cached_transaction_request_result(
+            state,
+            |state| {
+                // If this is an epoch bump, we will transition the state as part of handling the EndTxnRequest
+                if !is_epoch_bump {
+                    self.transition_to(state, State::Initializing, None)?;
+                    self.log.info(format_args!("Invoking InitProducerId for the first time in order to acquire a producer ID"));
+                } else {
+                    self.log.info(format_args!(
+                        "Invoking InitProducerId with current producer ID and epoch {} in order to bump the epoch",
+                        producer_id_and_epoch
+                    ));
+                }
+                let request = InitProducerIdRequest::new(
+                    self.transactional_id.clone(),
+                    self.transaction_timeout_ms,
+                    producer_id_and_epoch.producer_id,
+                    producer_id_and_epoch.epoch,
+                );
+                let handler = self.init_producer_id_handler(request, is_epoch_bump);
+                let result = handler.result.clone();
+                self.enqueue_request(state, handler);
+                Ok(result)
+            },
+            State::Initializing,
+        )
+    }
+
+    pub fn begin_transaction(&self) -> Result<()> {
+        self.ensure_transactional()?;
+        let mut state = self.lock();
+        self.maybe_fail_with_error(&state)?;
+        self.transition_to(&mut state, State::InTransaction, None)
+    }
+
+    pub fn begin_commit(&self) -> Result<Arc<TransactionalRequestResult>> {
+        let mut state = self.lock();
+        self.handle_cached_transaction_request_result(
+            &mut state,
+            |state| {
+                self.maybe_fail_with_error(state)?;
+                self.transition_to(state, State::CommittingTransaction, None)?;
+                self.begin_completing_transaction(state, TransactionResult::Commit)
+            },
+            State::CommittingTransaction,
+        )
+    }
+
+    pub fn begin_abort(&self) -> Result<Arc<TransactionalRequestResult>> {
+        let mut state = self.lock();
+        self.handle_cached_transaction_request_result(
+            &mut state,
+            |state| {
+                if state.current_state != State::AbortableError {
+                    self.maybe_fail_with_error(state)?;
+                }
+                self.transition_to(state, State::AbortingTransaction, None)?;
+
+                // We're aborting the transaction, so there should be no need to add new partitions
+                state.new_partitions_in_transaction.clear();
+                self.begin_completing_transaction(state, TransactionResult::Abort)
+            },
+            State::AbortingTransaction,
+        )
+    }
+
+    fn begin_completing_transaction(
+        &self,
+        state: &mut TransactionManagerState,
+        transaction_result: TransactionResult,
+    ) -> Result<Arc<TransactionalRequestResult>> {
+        if !state.new_partitions_in_transaction.is_empty() {
+            let handler = self.add_partitions_to_transaction_handler(state);
+            self.enqueue_request(state, handler);
+        }
+
+        // If the error is an INVALID_PRODUCER_ID_MAPPING error, the server will not accept an EndTxnRequest, so skip
+        // directly to InitProducerId. Otherwise, we must first abort the transaction, because the producer will be
+        // fenced if we directly call InitProducerId.
+        if !matches!(state.last_error, Some(KafkaError::InvalidPidMapping(_))) {
+            let request = EndTxnRequest::new(
+                self.transactional_id.clone().unwrap_or_default(),
+                state.producer_id_and_epoch.producer_id,
+                state.producer_id_and_epoch.epoch,
+                transaction_result,
+            );
+            let handler = self.new_handler(
+                format!("EndTxn({})", transaction_result.id()),
+                TxnRequestKind::EndTxn(request),
+            );
+            let result = handler.result.clone();
+            self.enqueue_request(state, handler);
+            if !state.epoch_bump_required {
+                return Ok(result);
+            }
+        }
+
+        let producer_id_and_epoch = state.producer_id_and_epoch;
+        self.initialize_transactions_with(state, producer_id_and_epoch)
+    }
+
+    pub fn send_offsets_to_transaction(
+        &self,
+        offsets: HashMap<TopicPartition, OffsetAndMetadata>,
+        group_metadata: ConsumerGroupMetadata,
+    ) -> Result<Arc<TransactionalRequestResult>> {
+        self.ensure_transactional()?;
+        let mut state = self.lock();
+        self.maybe_fail_with_error(&state)?;
+        if state.current_state != State::InTransaction {
+            return Err(KafkaError::Kafka(
+                "Cannot send offsets to transaction either because the producer is not in an active transaction"
+                    .to_owned(),
+            ));
+        }
+
+        self.log.debug(format_args!(
+            "Begin adding offsets {:?} for consumer group {} to transaction",
+            offsets, group_metadata
+        ));
+        let request = AddOffsetsToTxnRequest::new(
+            self.transactional_id.clone().unwrap_or_default(),
+            state.producer_id_and_epoch.producer_id,
+            state.producer_id_and_epoch.epoch,
+            group_metadata.group_id.clone(),
+        );
+        let handler = self.new_handler(
+            "AddOffsetsToTxn",
+            TxnRequestKind::AddOffsetsToTxn {
+                request,
+                offsets,
+                group_metadata,
+            },
+        );
+        let result = handler.result.clone();
+        self.enqueue_request(&mut state, handler);
+        Ok(result)
+    }
+
+    pub fn maybe_add_partition_to_transaction(&self, topic_partition: &TopicPartition) {
+        let mut state = self.lock();
+        if state.partitions_in_transaction.contains(topic_partition)
+            || Self::is_partition_pending_add_locked(&state, topic_partition)
+        {
+            return;
+        }
+
+        self.log.debug(format_args!(
+            "Begin adding new partition {} to transaction",
+            topic_partition
+        ));
+        state
+            .topic_partition_bookkeeper
+            .add_partition(topic_partition);
+        state
+            .new_partitions_in_transaction
+            .insert(topic_partition.clone());
+    }
+
+    pub fn last_error(&self) -> Option<KafkaError> {
+        self.lock().last_error.clone()
+    }
+
+    pub fn fail_if_not_ready_for_send(&self) -> Result<()> {
+        let state = self.lock();
+        if Self::has_error_locked(&state) {
+            return Err(KafkaError::Kafka(format!(
+                "Cannot perform send because at least one previous transactional or idempotent request has failed with errors: {}",
+                state.last_error.as_ref().map(|e| e.to_string()).unwrap_or_default()
+            )));
+        }
+
+        if self.is_transactional() {
+            if !state.producer_id_and_epoch.is_valid() {
+                return Err(KafkaError::IllegalState(
+                    "Cannot perform a 'send' before completing a call to initTransactions when transactions are enabled."
+                        .to_owned(),
+                ));
+            }
+
+            if state.current_state != State::InTransaction {
+                return Err(KafkaError::IllegalState(format!(
+                    "Cannot call send in state {}",
+                    state.current_state
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_send_to_partition_allowed(&self, tp: &TopicPartition) -> bool {
+        let state = self.lock();
+        if state.current_state == State::FatalError {
+            return false;
+        }
+        !self.is_transactional() || state.partitions_in_transaction.contains(tp)
+    }
+
+    pub fn transactional_id(&self) -> Option<&str> {
+        self.transactional_id.as_deref()
+    }
+
+    pub fn has_producer_id(&self) -> bool {
+        self.lock().producer_id_and_epoch.is_valid()
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.transactional_id.is_some()
+    }
+
+    pub fn has_partitions_to_add(&self) -> bool {
+        let state = self.lock();
+        !state.new_partitions_in_transaction.is_empty()
+            || !state.pending_partitions_in_transaction.is_empty()
+    }
+
+    pub fn is_completing(&self) -> bool {
+        Self::is_completing_locked(&self.lock())
+    }
+
+    fn is_completing_locked(state: &TransactionManagerState) -> bool {
+        state.current_state == State::CommittingTransaction
+            || state.current_state == State::AbortingTransaction
+    }
+
+    pub fn has_error(&self) -> bool {
+        Self::has_error_locked(&self.lock())
+    }
+
+    fn has_error_locked(state: &TransactionManagerState) -> bool {
+        state.current_state == State::AbortableError || state.current_state == State::FatalError
+    }
+
+    pub fn is_aborting(&self) -> bool {
+        self.lock().current_state == State::AbortingTransaction
+    }
+
+    pub fn transition_to_abortable_error(&self, error: KafkaError) -> Result<()> {
+        let mut state = self.lock();
+        self.transition_to_abortable_error_locked(&mut state, error)
+    }
+
+    fn transition_to_abortable_error_locked(
+        &self,
+        state: &mut TransactionManagerState,
+        error: KafkaError,
+    ) -> Result<()> {
+        if state.current_state == State::AbortingTransaction {
+            self.log.debug(format_args!(
+                "Skipping transition to abortable error state since the transaction is already being aborted. Underlying exception: {}",
+                error
+            ));
+            return Ok(());
+        }
+
+        self.log.info(format_args!(
+            "Transiting to abortable error state due to {}",
+            error
+        ));
+        self.transition_to(state, State::AbortableError, Some(error))
+    }
+
+    pub fn transition_to_fatal_error(&self, error: KafkaError) {
+        let mut state = self.lock();
+        self.transition_to_fatal_error_locked(&mut state, error);
+    }
+
+    fn transition_to_fatal_error_locked(
+        &self,
+        state: &mut TransactionManagerState,
+        error: KafkaError,
+    ) {
+        self.log.info(format_args!(
+            "Transiting to fatal error state due to {}",
+            error
+        ));
+        // Transition to FATAL_ERROR is always valid and the error is always present.
+        let _ = self.transition_to(state, State::FatalError, Some(error.clone()));
+
+        if let Some(pending_result) = &state.pending_result {
+            pending_result.fail(error);
+        }
+    }
+
+    pub fn is_partition_added(&self, partition: &TopicPartition) -> bool {
+        self.lock().partitions_in_transaction.contains(partition)
+    }
+
+    pub fn is_partition_pending_add(&self, partition: &TopicPartition) -> bool {
+        Self::is_partition_pending_add_locked(&self.lock(), partition)
+    }
+
+    fn is_partition_pending_add_locked(
+        state: &TransactionManagerState,
+        partition: &TopicPartition,
+    ) -> bool {
+        state.new_partitions_in_transaction.contains(partition)
+            || state.pending_partitions_in_transaction.contains(partition)
+    }
+
+    /// Get the current producer id and epoch without blocking. Callers must use
+    /// `ProducerIdAndEpoch::is_valid` to verify that the result is valid.
+    pub fn producer_id_and_epoch(&self) -> ProducerIdAndEpoch {
+        self.lock().producer_id_and_epoch
+    }
+
+    pub fn maybe_update_producer_id_and_epoch(
+        &self,
+        topic_partition: &TopicPartition,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let producer_id_and_epoch = state.producer_id_and_epoch;
+        let entry = state
+            .topic_partition_bookkeeper
+            .get_or_create_partition(topic_partition);
+        if entry.producer_id_and_epoch != producer_id_and_epoch
+            && entry.inflight_batches_by_sequence.is_empty()
+        {
+            // If the batch was on a different ID and/or epoch (due to an epoch bump) and all its in-flight batches
+            // have completed, reset the partition sequence so that the next batch (with the new epoch) starts from 0
+            state
+                .topic_partition_bookkeeper
+                .start_sequences_at_beginning(topic_partition, producer_id_and_epoch)?;
+            self.log.debug(format_args!(
+                "ProducerId of partition {} set to {} with epoch {}. Reinitialize sequence at beginning.",
+                topic_partition, producer_id_and_epoch.producer_id, producer_id_and_epoch.epoch
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the producer id and epoch atomically.
+    fn set_producer_id_and_epoch(
+        &self,
+        state: &mut TransactionManagerState,
+        producer_id_and_epoch: ProducerIdAndEpoch,
+    ) {
+        self.log.info(format_args!(
+            "ProducerId set to {} with epoch {}",
+            producer_id_and_epoch.producer_id, producer_id_and_epoch.epoch
+        ));
+        state.producer_id_and_epoch = producer_id_and_epoch;
+    }
+
+    /// This method resets the producer ID and epoch and sets the state to UNINITIALIZED, which will trigger a new
+    /// InitProducerId request. This method is only called when the producer epoch is exhausted; we will bump the epoch
+    /// instead.
+    fn reset_idempotent_producer_id(&self, state: &mut TransactionManagerState) -> Result<()> {
+        if self.is_transactional() {
+            return Err(KafkaError::IllegalState(
+                "Cannot reset producer state for a transactional producer. You must either abort the ongoing transaction or reinitialize the transactional producer instead"
+                    .to_owned(),
+            ));
+        }
+        self.log.debug(format_args!(
+            "Resetting idempotent producer ID. ID and epoch before reset are {}",
+            state.producer_id_and_epoch
+        ));
+        self.set_producer_id_and_epoch(state, ProducerIdAndEpoch::NONE);
+        self.transition_to(state, State::Uninitialized, None)
+    }
+
+    fn reset_sequence_for_partition(
+        state: &mut TransactionManagerState,
+        topic_partition: &TopicPartition,
+    ) {
+        state
+            .topic_partition_bookkeeper
+            .topic_partitions
+            .remove(topic_partition);
+        state
+            .partitions_with_unresolved_sequences
+            .remove(topic_partition);
+    }
+
+    fn reset_sequence_numbers(state: &mut TransactionManagerState) {
+        state.topic_partition_bookkeeper.reset();
+        state.partitions_with_unresolved_sequences.clear();
+    }
+
+    pub fn request_epoch_bump_for_partition(&self, tp: &TopicPartition) {
+        Self::request_epoch_bump_for_partition_locked(&mut self.lock(), tp);
+    }
+
+    fn request_epoch_bump_for_partition_locked(
+        state: &mut TransactionManagerState,
+        tp: &TopicPartition,
+    ) {
+        state.epoch_bump_required = true;
+        state.partitions_to_rewrite_sequences.insert(tp.clone());
+    }
+
+    fn bump_idempotent_producer_epoch(&self, state: &mut TransactionManagerState) -> Result<()> {
+        if state.producer_id_and_epoch.epoch == i16::MAX {
+            self.reset_idempotent_producer_id(state)?;
+        } else {
+            let producer_id_and_epoch = ProducerIdAndEpoch::new(
+                state.producer_id_and_epoch.producer_id,
+                state.producer_id_and_epoch.epoch + 1,
+            );
+            self.set_producer_id_and_epoch(state, producer_id_and_epoch);
+            self.log.debug(format_args!(
+                "Incremented producer epoch, current producer ID and epoch are now {}",
+                state.producer_id_and_epoch
+            ));
+        }
+
+        // When the epoch is bumped, rewrite all in-flight sequences for the partition(s) that triggered the epoch bump
+        let partitions_to_rewrite_sequences =
+            std::mem::take(&mut state.partitions_to_rewrite_sequences);
+        for topic_partition in &partitions_to_rewrite_sequences {
+            let producer_id_and_epoch = state.producer_id_and_epoch;
+            state
+                .topic_partition_bookkeeper
+                .start_sequences_at_beginning(topic_partition, producer_id_and_epoch)?;
+            state
+                .partitions_with_unresolved_sequences
+                .remove(topic_partition);
+        }
+        state.epoch_bump_required = false;
+        Ok(())
+    }
+
+    pub fn bump_idempotent_epoch_and_reset_id_if_needed(&self) -> Result<()> {
+        if self.is_transactional() {
+            return Ok(());
+        }
+        let mut state = self.lock();
+        if state.epoch_bump_required {
+            self.bump_idempotent_producer_epoch(&mut state)?;
+        }
+        if state.current_state != State::Initializing && !state.producer_id_and_epoch.is_valid() {
+            self.transition_to(&mut state, State::Initializing, None)?;
+            let request = InitProducerIdRequest::new(
+                None,
+                i32::MAX,
+                ProducerIdAndEpoch::NONE.producer_id,
+                ProducerIdAndEpoch::NONE.epoch,
+            );
+            let handler = self.init_producer_id_handler(request, false);
+            self.enqueue_request(&mut state, handler);
+        }
+        Ok(())
+    }
+
+    /// Returns the next sequence number to be written to the given TopicPartition.
+    pub fn sequence_number(&self, topic_partition: &TopicPartition) -> i32 {
+        Self::sequence_number_locked(&mut self.lock(), topic_partition)
+    }
+
+    fn sequence_number_locked(
+        state: &mut TransactionManagerState,
+        topic_partition: &TopicPartition,
+    ) -> i32 {
+        state
+            .topic_partition_bookkeeper
+            .get_or_create_partition(topic_partition)
+            .next_sequence
+    }
+
+    /// Returns the current producer id/epoch of the given TopicPartition.
+    pub fn producer_id_and_epoch_for(
+        &self,
+        topic_partition: &TopicPartition,
+    ) -> ProducerIdAndEpoch {
+        self.lock()
+            .topic_partition_bookkeeper
+            .get_or_create_partition(topic_partition)
+            .producer_id_and_epoch
+    }
+
+    pub fn increment_sequence_number(
+        &self,
+        topic_partition: &TopicPartition,
+        increment: i32,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let current_sequence = Self::sequence_number_locked(&mut state, topic_partition);
+        state
+            .topic_partition_bookkeeper
+            .get_partition(topic_partition)?
+            .next_sequence = default_record_batch::increment_sequence(current_sequence, increment);
+        Ok(())
+    }
+
+    pub fn add_in_flight_batch(&self, batch: &Arc<ProducerBatch>) -> Result<()> {
+        if !batch.has_sequence() {
+            return Err(KafkaError::IllegalState(format!(
+                "Can't track batch for partition {} when sequence is not set.",
+                batch.topic_partition
+            )));
+        }
+        self.lock()
+            .topic_partition_bookkeeper
+            .get_partition(&batch.topic_partition)?
+            .add_in_flight_batch(batch.clone());
+        Ok(())
+    }
+
+    /// Returns the first inflight sequence for a given partition. This is the base sequence of an inflight batch with
+    /// the lowest sequence number.
+    ///
+    /// Returns the lowest inflight sequence if the transaction manager is tracking inflight requests for this
+    /// partition. If there are no inflight requests being tracked for this partition, this method will return
+    /// `NO_SEQUENCE`.
+    pub fn first_in_flight_sequence(&self, topic_partition: &TopicPartition) -> i32 {
+        self.lock()
+            .topic_partition_bookkeeper
+            .get_or_create_partition(topic_partition)
+            .inflight_batches_by_sequence
+            .first()
+            .map(|batch| batch.base_sequence())
+            .unwrap_or(NO_SEQUENCE)
+    }
+
+    pub fn next_batch_by_sequence(
+        &self,
+        topic_partition: &TopicPartition,
+    ) -> Result<Option<Arc<ProducerBatch>>> {
+        Ok(self
+            .lock()
+            .topic_partition_bookkeeper
+            .get_partition(topic_partition)?
+            .inflight_batches_by_sequence
+            .first()
+            .cloned())
+    }
+
+    pub fn remove_in_flight_batch(&self, batch: &ProducerBatch) {
+        Self::remove_in_flight_batch_locked(&mut self.lock(), batch);
+    }
+
+    fn remove_in_flight_batch_locked(state: &mut TransactionManagerState, batch: &ProducerBatch) {
+        if let Some(entry) = state
+            .topic_partition_bookkeeper
+            .topic_partitions
+            .get_mut(&batch.topic_partition)
+        {
+            entry
+                .inflight_batches_by_sequence
+                .retain(|b| b.id() != batch.id());
+        }
+    }
+
+    fn maybe_update_last_acked_sequence(
+        state: &mut TransactionManagerState,
+        topic_partition: &TopicPartition,
+        sequence: i32,
+    ) -> Result<i32> {
+        let last_acked_sequence = state
+            .topic_partition_bookkeeper
+            .last_acked_sequence(topic_partition)
+            .unwrap_or(NO_LAST_ACKED_SEQUENCE_NUMBER);
+        if sequence > last_acked_sequence {
+            state
+                .topic_partition_bookkeeper
+                .get_partition(topic_partition)?
+                .last_acked_sequence = sequence;
+            return Ok(sequence);
+        }
+        Ok(last_acked_sequence)
+    }
+
+    pub fn last_acked_sequence(&self, topic_partition: &TopicPartition) -> Option<i32> {
+        self.lock()
+            .topic_partition_bookkeeper
+            .last_acked_sequence(topic_partition)
+    }
+
+    pub fn last_acked_offset(&self, topic_partition: &TopicPartition) -> Option<i64> {
+        self.lock()
+            .topic_partition_bookkeeper
+            .last_acked_offset(topic_partition)
+    }
+
+    fn update_last_acked_offset(
+        &self,
+        state: &mut TransactionManagerState,
+        response: &PartitionResponse,
+        batch: &ProducerBatch,
+    ) -> Result<()> {
+        if response.base_offset == INVALID_OFFSET {
+            return Ok(());
+        }
+        let last_offset = response.base_offset + batch.record_count() as i64 - 1;
+        let last_acked_offset = state
+            .topic_partition_bookkeeper
+            .last_acked_offset(&batch.topic_partition);
+        // It might happen that the TransactionManager has been reset while a request was reenqueued and got a valid
+        // response for this. This can happen only if the producer is only idempotent (not transactional) and in
+        // this case there will be no tracked bookkeeper entry about it, so we have to insert one.
+        if last_acked_offset.is_none() && !self.is_transactional() {
+            state
+                .topic_partition_bookkeeper
+                .add_partition(&batch.topic_partition);
+        }
+        if last_offset > last_acked_offset.unwrap_or(INVALID_OFFSET) {
+            state
+                .topic_partition_bookkeeper
+                .get_partition(&batch.topic_partition)?
+                .last_acked_offset = last_offset;
+        } else {
+            self.log.trace(format_args!(
+                "Partition {} keeps lastOffset at {}",
+                batch.topic_partition, last_offset
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn handle_completed_batch(
+        &self,
+        batch: &ProducerBatch,
+        response: &PartitionResponse,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let last_acked_sequence = Self::maybe_update_last_acked_sequence(
+            &mut state,
+            &batch.topic_partition,
+            batch.last_sequence(),
+        )?;
+        self.log.debug(format_args!(
+            "ProducerId: {}; Set last ack'd sequence number for topic-partition {} to {}",
+            batch.producer_id(),
+            batch.topic_partition,
+            last_acked_sequence
+        ));
+
+        self.update_last_acked_offset(&mut state, response, batch)?;
+        Self::remove_in_flight_batch_locked(&mut state, batch);
+        Ok(())
+    }
+
+    fn maybe_transition_to_error_state(
+        &self,
+        state: &mut TransactionManagerState,
+        error: &KafkaError,
+    ) -> Result<()> {
+        match error {
+            KafkaError::ClusterAuthorization(_)
+            | KafkaError::TransactionalIdAuthorization(_)
+            | KafkaError::ProducerFenced(_)
+            | KafkaError::UnsupportedVersion(_) => {
+                self.transition_to_fatal_error_locked(state, error.clone());
+                Ok(())
+            }
+            _ if self.is_transactional() => {
+                if self.can_bump_epoch_locked(state) && !Self::is_completing_locked(state) {
+                    state.epoch_bump_required = true;
+                }
+                self.transition_to_abortable_error_locked(state, error.clone())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn handle_failed_batch(
+        &self,
+        batch: &ProducerBatch,
+        error: &KafkaError,
+        adjust_sequence_numbers: bool,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        self.maybe_transition_to_error_state(&mut state, error)?;
+        Self::remove_in_flight_batch_locked(&mut state, batch);
+
+        if state.current_state == State::FatalError {
+            self.log.debug(format_args!(
+                "Ignoring batch {} with producer id {}, epoch {}, and sequence number {} since the producer is already in fatal error state: {}",
+                batch,
+                batch.producer_id(),
+                batch.producer_epoch(),
+                batch.base_sequence(),
+                error
+            ));
+            return Ok(());
+        }
+
+        match error {
+            KafkaError::OutOfOrderSequence(_) if !self.is_transactional() => {
+                self.log.error(format_args!(
+                    "The broker returned {} for topic-partition {} with producerId {}, epoch {}, and sequence number {}",
+                    error,
+                    batch.topic_partition,
+                    batch.producer_id(),
+                    batch.producer_epoch(),
+                    batch.base_sequence()
+                ));
+
+                // If we fail with an OutOfOrderSequenceException, we have a gap in the log. Bump the epoch for this
+                // partition, which will reset the sequence number to 0 and allow us to continue
+                Self::request_epoch_bump_for_partition_locked(&mut state, &batch.topic_partition);
+            }
+            KafkaError::UnknownProducerId(_) => {
+                // If we get an UnknownProducerId for a partition, then the broker has no state for that producer. It will
+                // therefore accept a write with sequence number 0. We reset the sequence number for the partition here so
+                // that the producer can continue after aborting the transaction. All inflight-requests to this partition
+                // will also fail with an UnknownProducerId error, so the sequence will remain at 0. Note that if the
+                // broker supports bumping the epoch, we will later reset all sequence numbers after calling InitProducerId
+                Self::reset_sequence_for_partition(&mut state, &batch.topic_partition);
+            }
+            _ if adjust_sequence_numbers => {
+                if !self.is_transactional() {
+                    Self::request_epoch_bump_for_partition_locked(
+                        &mut state,
+                        &batch.topic_partition,
+                    );
+                } else {
+                    self.adjust_sequences_due_to_failed_batch(&mut state, batch)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // If a batch is failed fatally, the sequence numbers for future batches bound for the partition must be adjusted
+    // so that they don't fail with the OutOfOrderSequenceException.
+    //
+    // This method must only be called when we know that the batch is question has been unequivocally failed by the broker,
+    // ie. it has received a confirmed fatal status code like 'Message Too Large' or something similar.
+    fn adjust_sequences_due_to_failed_batch(
+        &self,
+        state: &mut TransactionManagerState,
+        batch: &ProducerBatch,
+    ) -> Result<()> {
+        if !state
+            .topic_partition_bookkeeper
+            .contains(&batch.topic_partition)
+        {
+            // Sequence numbers are not being tracked for this partition. This could happen if the producer id was just
+            // reset due to a previous OutOfOrderSequenceException.
+            return Ok(());
+        }
+        self.log.debug(format_args!(
+            "producerId: {}, send to partition {} failed fatally. Reducing future sequence numbers by {}",
+            batch.producer_id(),
+            batch.topic_partition,
+            batch.record_count()
+        ));
+        let mut current_sequence = Self::sequence_number_locked(state, &batch.topic_partition);
+        current_sequence -= batch.record_count();
+        if current_sequence < 0 {
+            return Err(KafkaError::IllegalState(format!(
+                "Sequence number for partition {} is going to become negative: {}",
+                batch.topic_partition, current_sequence
+            )));
+        }
+
+        let entry = state
+            .topic_partition_bookkeeper
+            .get_partition(&batch.topic_partition)?;
+        entry.next_sequence = current_sequence;
+        let failed_base_sequence = batch.base_sequence();
+        let failed_record_count = batch.record_count();
+        entry.reset_sequence_numbers(|in_flight_batch| {
+            if in_flight_batch.base_sequence() < failed_base_sequence {
+                return Ok(());
+            }
+
+            let new_sequence = in_flight_batch.base_sequence() - failed_record_count;
+            if new_sequence < 0 {
+                return Err(KafkaError::IllegalState(format!(
+                    "Sequence number for batch with sequence {} for partition {} is going to become negative: {}",
+                    in_flight_batch.base_sequence(),
+                    batch.topic_partition,
+                    new_sequence
+                )));
+            }
+
+            in_flight_batch.reset_producer_state(
+                ProducerIdAndEpoch::new(
+                    in_flight_batch.producer_id(),
+                    in_flight_batch.producer_epoch(),
+                ),
+                new_sequence,
+                in_flight_batch.is_transactional(),
+            )
+        })
+    }
+
+    pub fn has_inflight_batches(&self, topic_partition: &TopicPartition) -> bool {
+        Self::has_inflight_batches_locked(&mut self.lock(), topic_partition)
+    }
+
+    fn has_inflight_batches_locked(
+        state: &mut TransactionManagerState,
+        topic_partition: &TopicPartition,
+    ) -> bool {
+        !state
+            .topic_partition_bookkeeper
+            .get_or_create_partition(topic_partition)
+            .inflight_batches_by_sequence
+            .is_empty()
+    }
+
+    pub fn has_stale_producer_id_and_epoch(&self, topic_partition: &TopicPartition) -> bool {
+        let mut state = self.lock();
+        let producer_id_and_epoch = state.producer_id_and_epoch;
+        producer_id_and_epoch
+            != state
+                .topic_partition_bookkeeper
+                .get_or_create_partition(topic_partition)
+                .producer_id_and_epoch
+    }
+
+    pub fn has_unresolved_sequences(&self) -> bool {
+        !self.lock().partitions_with_unresolved_sequences.is_empty()
+    }
+
+    pub fn has_unresolved_sequence(&self, topic_partition: &TopicPartition) -> bool {
+        self.lock()
+            .partitions_with_unresolved_sequences
+            .contains_key(topic_partition)
+    }
+
+    pub fn mark_sequence_unresolved(&self, batch: &ProducerBatch) {
+        let mut state = self.lock();
+        let next_sequence = batch.last_sequence() + 1;
+        let unresolved = state
+            .partitions_with_unresolved_sequences
+            .entry(batch.topic_partition.clone())
+            .or_insert(next_sequence);
+        *unresolved = (*unresolved).max(next_sequence);
+        self.log.debug(format_args!(
+            "Marking partition {} unresolved with next sequence number {}",
+            batch.topic_partition, *unresolved
+        ));
+    }
+
+    // Attempts to resolve unresolved sequences. If all in-flight requests are complete and some partitions are still
+    // unresolved, either bump the epoch if possible, or transition to a fatal error
+    pub fn maybe_resolve_sequences(&self) -> Result<()> {
+        let mut state = self.lock();
+        let unresolved: Vec<TopicPartition> = state
+            .partitions_with_unresolved_sequences
+            .keys()
+            .cloned()
+            .collect();
+        for topic_partition in unresolved {
+            if Self::has_inflight_batches_locked(&mut state, &topic_partition) {
+                continue;
+            }
+            // The partition has been fully drained. At this point, the last ack'd sequence should be one less than
+            // next sequence destined for the partition. If so, the partition is fully resolved. If not, we should
+            // reset the sequence number if necessary.
+            let next_sequence = Self::sequence_number_locked(&mut state, &topic_partition);
+            if !Self::is_next_sequence(&state, &topic_partition, next_sequence) {
+                // We would enter this branch if all in flight batches were ultimately expired in the producer.
+                if self.is_transactional() {
+                    // For the transactional producer, we bump the epoch if possible, otherwise we transition to a fatal error
+                    let unacked_messages_err = "The client hasn't received acknowledgment for some previously sent messages and can no longer retry them. ";
+                    if self.can_bump_epoch_locked(&state) {
+                        state.epoch_bump_required = true;
+                        let error = KafkaError::Kafka(format!(
+                            "{}It is safe to abort the transaction and continue.",
+                            unacked_messages_err
+                        ));
+                        self.transition_to_abortable_error_locked(&mut state, error)?;
+                    } else {
+                        let error = KafkaError::Kafka(format!(
+                            "{}It isn't safe to continue.",
+                            unacked_messages_err
+                        ));
+                        self.transition_to_fatal_error_locked(&mut state, error);
+                    }
+                } else {
+                    // For the idempotent producer, bump the epoch
+                    self.log.info(format_args!(
+                        "No inflight batches remaining for {}, last ack'd sequence for partition is {}, next sequence is {}. Going to bump epoch and reset sequence numbers.",
+                        topic_partition,
+                        state
+                            .topic_partition_bookkeeper
+                            .last_acked_sequence(&topic_partition)
+                            .unwrap_or(NO_LAST_ACKED_SEQUENCE_NUMBER),
+                        next_sequence
+                    ));
+                    Self::request_epoch_bump_for_partition_locked(&mut state, &topic_partition);
+                }
+            }
+            // Either this would happen when a batch was expired, but subsequent batches succeeded, or the partition
+            // is resolved by bumping the epoch.
+            state
+                .partitions_with_unresolved_sequences
+                .remove(&topic_partition);
+        }
+        Ok(())
+    }
+
+    fn is_next_sequence(
+        state: &TransactionManagerState,
+        topic_partition: &TopicPartition,
+        sequence: i32,
+    ) -> bool {
+        sequence
+            - state
+                .topic_partition_bookkeeper
+                .last_acked_sequence(topic_partition)
+                .unwrap_or(NO_LAST_ACKED_SEQUENCE_NUMBER)
+            == 1
+    }
+
+    fn is_next_sequence_for_unresolved_partition(
+        state: &TransactionManagerState,
+        topic_partition: &TopicPartition,
+        sequence: i32,
+    ) -> bool {
+        state
+            .partitions_with_unresolved_sequences
+            .get(topic_partition)
+            == Some(&sequence)
+    }
+
+    /// Take the next transactional request which should be sent, if any. The handler must be
+    /// returned using `handle_response` once the request completes, or using `retry` if it could
+    /// not be sent.
+    pub fn next_request(&self, has_incomplete_batches: bool) -> Result<Option<TxnRequestHandler>> {
+        let mut state = self.lock();
+        if !state.new_partitions_in_transaction.is_empty() {
+            let handler = self.add_partitions_to_transaction_handler(&mut state);
+            self.enqueue_request(&mut state, handler);
+        }
+
+        let index = match Self::peek_request(&state) {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+
+        // Do not send the EndTxn until all batches have been flushed
+        if state.pending_requests[index].is_end_txn() && has_incomplete_batches {
+            return Ok(None);
+        }
+
+        let next_request_handler = state.pending_requests.remove(index);
+        if self.maybe_terminate_request_with_error(&state, &next_request_handler) {
+            self.log.trace(format_args!(
+                "Not sending transactional request {} because we are in an error state",
+                next_request_handler
+            ));
+            return Ok(None);
+        }
+
+        let mut next_request_handler = Some(next_request_handler);
+        if let Some(handler) = &next_request_handler {
+            if handler.is_end_txn() && !state.transaction_started {
+                handler.result.done();
+                if state.current_state != State::FatalError {
+                    self.log.debug(format_args!("Not sending EndTxn for completed transaction since no partitions or offsets were successfully added"));
+                    self.complete_transaction(&mut state)?;
+                }
+                next_request_handler =
+                    Self::peek_request(&state).map(|index| state.pending_requests.remove(index));
+            }
+        }
+
+        if let Some(handler) = &next_request_handler {
+            self.log
+                .trace(format_args!("Request {} dequeued for sending", handler));
+        }
+        Ok(next_request_handler)
+    }
+
+    /// Index of the pending request with the highest priority, requests of the same priority are
+    /// sent in the order they were enqueued.
+    fn peek_request(state: &TransactionManagerState) -> Option<usize> {
+        state
+            .pending_requests
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, handler)| handler.priority())
+            .map(|(index, _)| index)
+    }
+
+    pub fn retry(&self, mut request: TxnRequestHandler) {
+        request.set_retry();
+        self.enqueue_request(&mut self.lock(), request);
+    }
+
+    pub fn close(&self) {
+        let mut state = self.lock();
+        let shutdown_exception = KafkaError::Kafka("The producer closed forcefully".to_owned());
+        let pending_requests = std::mem::take(&mut state.pending_requests);
+        for handler in &pending_requests {
+            handler.fail(shutdown_exception.clone());
+            self.transition_to_fatal_error_locked(&mut state, shutdown_exception.clone());
+        }
+        if let Some(pending_result) = &state.pending_result {
+            pending_result.fail(shutdown_exception);
+        }
+    }
+
+    pub fn coordinator(&self, coordinator_type: CoordinatorType) -> Option<Node> {
+        let state = self.lock();
+        match coordinator_type {
+            CoordinatorType::Group => state.consumer_group_coordinator.clone(),
+            CoordinatorType::Transaction => state.transaction_coordinator.clone(),
+        }
+    }
+
+    pub fn lookup_coordinator(&self, request: &TxnRequestHandler) {
+        if let Some(coordinator_type) = request.coordinator_type {
+            let coordinator_key = request.coordinator_key.clone().unwrap_or_default();
+            self.lookup_coordinator_locked(&mut self.lock(), coordinator_type, coordinator_key);
+        }
+    }
+
+    pub fn set_in_flight_correlation_id(&self, correlation_id: i32) {
+        self.lock().in_flight_request_correlation_id = correlation_id;
+    }
+
+    pub fn has_in_flight_request(&self) -> bool {
+        self.lock().in_flight_request_correlation_id != NO_INFLIGHT_REQUEST_CORRELATION_ID
+    }
+
+    pub fn has_fatal_error(&self) -> bool {
+        self.lock().current_state == State::FatalError
+    }
+
+    pub fn has_abortable_error(&self) -> bool {
+        self.lock().current_state == State::AbortableError
+    }
+
+    pub fn transaction_contains_partition(&self, topic_partition: &TopicPartition) -> bool {
+        self.lock()
+            .partitions_in_transaction
+            .contains(topic_partition)
+    }
+
+    pub fn has_pending_offset_commits(&self) -> bool {
+        !self.lock().pending_txn_offset_commits.is_empty()
+    }
+
+    pub fn has_pending_requests(&self) -> bool {
+        !self.lock().pending_requests.is_empty()
+    }
+
+    pub fn has_ongoing_transaction(&self) -> bool {
+        let state = self.lock();
+        // transactions are considered ongoing once started until completion or a fatal error
+        state.current_state == State::InTransaction
+            || Self::is_completing_locked(&state)
+            || state.current_state == State::AbortableError
+    }
+
+    pub fn can_retry(&self, response: &PartitionResponse, batch: &ProducerBatch) -> Result<bool> {
+        let mut state = self.lock();
+        let error = response.error;
+
+        // An UNKNOWN_PRODUCER_ID means that we have lost the producer state on the broker. Depending on the log start
+        // offset, we may want to retry these, as described for each case below. If none of those apply, then for the
+        // idempotent producer, we will locally bump the epoch and reset the sequence numbers of in-flight batches from
+        // sequence 0, then retry the failed batch, which should now succeed. For the transactional producer, allow the
+        // batch to fail. When processing the failed batch, we will transition to an abortable error and set a flag
+        // indicating that we need to bump the epoch (if supported by the broker).
+        if error == Errors::UnknownProducerId {
+            if response.log_start_offset == -1 {
+                // We don't know the log start offset with this response. We should just retry the request until we get it.
+                // The UNKNOWN_PRODUCER_ID error code was added along with the new ProduceResponse which includes the
+                // logStartOffset. So the '-1' sentinel is not for backward compatibility. Instead, it is possible for
+                // a broker to not know the logStartOffset at when it is returning the response because the partition
+                // may have moved away from the broker from the time the error was initially raised to the time the
+                // response was being constructed. In these cases, we should just retry the request: we are guaranteed
+                // to eventually get a logStartOffset once things settle down.
+                return Ok(true);
+            }
+
+            if batch.sequence_has_been_reset() {
+                // When the first inflight batch fails due to the truncation case, then the sequences of all the other
+                // in flight batches would have been restarted from the beginning. However, when those responses
+                // come back from the broker, they would also come with an UNKNOWN_PRODUCER_ID error. In this case, we should not
+                // reset the sequence numbers to the beginning.
+                return Ok(true);
+            } else if state
+                .topic_partition_bookkeeper
+                .last_acked_offset(&batch.topic_partition)
+                .unwrap_or(NO_LAST_ACKED_SEQUENCE_NUMBER as i64)
+                < response.log_start_offset
+            {
+                // The head of the log has been removed, probably due to the retention time elapsing. In this case,
+                // we expect to lose the producer state. For the transactional producer, reset the sequences of all
+                // inflight batches to be from the beginning and retry them, so that the transaction does not need to
+                // be aborted. For the idempotent producer, bump the epoch to avoid reusing (sequence, epoch) pairs
+                if self.is_transactional() {
+                    let producer_id_and_epoch = state.producer_id_and_epoch;
+                    state
+                        .topic_partition_bookkeeper
+                        .start_sequences_at_beginning(
+                            &batch.topic_partition,
+                            producer_id_and_epoch,
+                        )?;
+                } else {
+                    Self::request_epoch_bump_for_partition_locked(
+                        &mut state,
+                        &batch.topic_partition,
+                    );
+                }
+                return Ok(true);
+            }
+
+            if !self.is_transactional() {
+                // For the idempotent producer, always retry UNKNOWN_PRODUCER_ID errors. If the batch has the current
+                // producer ID and epoch, request a bump of the epoch. Otherwise just retry the produce.
+                Self::request_epoch_bump_for_partition_locked(&mut state, &batch.topic_partition);
+                return Ok(true);
+            }
+        } else if error == Errors::OutOfOrderSequenceNumber {
+            let has_unresolved_sequence = state
+                .partitions_with_unresolved_sequences
+                .contains_key(&batch.topic_partition);
+            if !has_unresolved_sequence
+                && (batch.sequence_has_been_reset()
+                    || !Self::is_next_sequence(
+                        &state,
+                        &batch.topic_partition,
+                        batch.base_sequence(),
+                    ))
+            {
+                // We should retry the OutOfOrderSequenceException if the batch is _not_ the next batch, ie. its base
+                // sequence isn't the lastAckedSequence + 1.
+                return Ok(true);
+            } else if !self.is_transactional() {
+                // For the idempotent producer, retry all OUT_OF_ORDER_SEQUENCE_NUMBER errors. If there are no
+                // unresolved sequences, or this batch is the one immediately following an unresolved sequence, we know
+                // there is actually a gap in the sequences, and we bump the epoch. Otherwise, retry without bumping
+                // and wait to see if the sequence resolves
+                if !has_unresolved_sequence
+                    || Self::is_next_sequence_for_unresolved_partition(
+                        &state,
+                        &batch.topic_partition,
+                        batch.base_sequence(),
+                    )
+                {
+                    Self::request_epoch_bump_for_partition_locked(
+                        &mut state,
+                        &batch.topic_partition,
+                    );
+                }
+                return Ok(true);
+            }
+        }
+
+        // If neither of the above cases are true, retry if the exception is retriable
+        Ok(error
+            .exception(None)
+            .map(|e| e.is_retriable())
+            .unwrap_or(false))
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.is_transactional() && self.lock().current_state == State::Ready
+    }
+
+    pub fn handle_coordinator_ready(&self) {
+        let mut state = self.lock();
+        let init_producer_id_version = state
+            .transaction_coordinator
+            .as_ref()
+            .and_then(|coordinator| self.api_versions.get(&coordinator.id_string()))
+            .and_then(|versions| versions.latest_usable_version(ApiKeys::InitProducerId).ok());
+        state.coordinator_supports_bumping_epoch = init_producer_id_version
+            .map(|version| version >= 3)
+            .unwrap_or(false);
+    }
+
+    fn transition_to(
+        &self,
+        state: &mut TransactionManagerState,
+        target: State,
+        error: Option<KafkaError>,
+    ) -> Result<()> {
+        if !State::is_transition_valid(state.current_state, target) {
+            let id_string = match &self.transactional_id {
+                Some(transactional_id) => format!("TransactionalId {}: ", transactional_id),
+                None => String::new(),
+            };
+            return Err(KafkaError::Kafka(format!(
+                "{}Invalid transition attempted from state {} to state {}",
+                id_string, state.current_state, target
+            )));
+        }
+
+        if target == State::FatalError || target == State::AbortableError {
+            if error.is_none() {
+                return Err(KafkaError::IllegalArgument(format!(
+                    "Cannot transition to {} with a null exception",
+                    target
+                )));
+            }
+            state.last_error = error;
+        } else {
+            state.last_error = None;
+        }
+
+        match &state.last_error {
+            Some(last_error) => self.log.debug(format_args!(
+                "Transition from state {} to error state {}: {}",
+                state.current_state, target, last_error
+            )),
+            None => self.log.debug(format_args!(
+                "Transition from state {} to {}",
+                state.current_state, target
+            )),
+        }
+        state.current_state = target;
+        Ok(())
+    }
+
+    fn ensure_transactional(&self) -> Result<()> {
+        if !self.is_transactional() {
+            return Err(KafkaError::IllegalState(
+                "Transactional method invoked on a non-transactional producer.".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn maybe_fail_with_error(&self, state: &TransactionManagerState) -> Result<()> {
+        if !Self::has_error_locked(state) {
+            return Ok(());
+        }
+        // for ProducerFencedException, do not wrap it as a KafkaException
+        // but create a new instance without the call trace since it was not thrown because of the current call
+        match &state.last_error {
+            Some(KafkaError::ProducerFenced(_)) => Err(KafkaError::ProducerFenced(
+                "The producer has been rejected from the broker because it tried to use an old epoch with the transactionalId"
+                    .to_owned(),
+            )),
+            Some(KafkaError::InvalidProducerEpoch(_)) => {
+                Err(KafkaError::InvalidProducerEpoch(format!(
+                    "Producer attempted to produce with an old epoch {}",
+                    state.producer_id_and_epoch
+                )))
+            }
+            last_error => Err(KafkaError::Kafka(format!(
+                "Cannot execute transactional method because we are in an error state: {}",
+                last_error.as_ref().map(|e| e.to_string()).unwrap_or_default()
+            ))),
+        }
+    }
+
+    fn maybe_terminate_request_with_error(
+        &self,
+        state: &TransactionManagerState,
+        request_handler: &TxnRequestHandler,
+    ) -> bool {
+        if Self::has_error_locked(state) {
+            if state.current_state == State::AbortableError
+                && matches!(request_handler.kind, TxnRequestKind::FindCoordinator(_))
+            {
+                // No harm letting the FindCoordinator request go through if we're expecting to abort
+                return false;
+            }
+
+            if let Some(last_error) = &state.last_error {
+                request_handler.fail(last_error.clone());
+            }
+            return true;
+        }
+        false
+    }
+
+    fn enqueue_request(
+        &self,
+        state: &mut TransactionManagerState,
+        request_handler: TxnRequestHandler,
+    ) {
+        self.log.debug(format_args!(
+            "Enqueuing transactional request {}",
+            request_handler
+        ));
+        state.pending_requests.push(request_handler);
+    }
+
+    fn lookup_coordinator_locked(
+        &self,
+        state: &mut TransactionManagerState,
+        coordinator_type: CoordinatorType,
+        coordinator_key: String,
+    ) {
+        match coordinator_type {
+            CoordinatorType::Group => state.consumer_group_coordinator = None,
+            CoordinatorType::Transaction => state.transaction_coordinator = None,
+        }
+
+        let request = FindCoordinatorRequest::new(coordinator_key, coordinator_type);
+        let handler = TxnRequestHandler::new(
+            Arc::new(TransactionalRequestResult::new("FindCoordinator")),
+            self.retry_backoff_ms,
+            None,
+            None,
+            TxnRequestKind::FindCoordinator(request),
+        );
+        self.enqueue_request(state, handler);
+    }
+
+    fn new_handler(&self, operation: impl Into<String>, kind: TxnRequestKind) -> TxnRequestHandler {
+        TxnRequestHandler::new(
+            Arc::new(TransactionalRequestResult::new(operation)),
+            self.retry_backoff_ms,
+            Some(CoordinatorType::Transaction),
+            self.transactional_id.clone(),
+            kind,
+        )
+    }
+
+    fn init_producer_id_handler(
+        &self,
+        request: InitProducerIdRequest,
+        is_epoch_bump: bool,
+    ) -> TxnRequestHandler {
+        let mut handler = self.new_handler(
+            "InitProducerId",
+            TxnRequestKind::InitProducerId {
+                request,
+                is_epoch_bump,
+            },
+        );
+        if !self.is_transactional() {
+            handler.coordinator_type = None;
+        }
+        handler
+    }
+
+    fn add_partitions_to_transaction_handler(
+        &self,
+        state: &mut TransactionManagerState,
+    ) -> TxnRequestHandler {
+        let new_partitions = std::mem::take(&mut state.new_partitions_in_transaction);
+        state
+            .pending_partitions_in_transaction
+            .extend(new_partitions);
+        let mut partitions: Vec<TopicPartition> = state
+            .pending_partitions_in_transaction
+            .iter()
+            .cloned()
+            .collect();
+        partitions.sort();
+        let request = AddPartitionsToTxnRequest::new(
+            self.transactional_id.clone().unwrap_or_default(),
+            state.producer_id_and_epoch.producer_id,
+            state.producer_id_and_epoch.epoch,
+            partitions,
+        );
+        self.new_handler(
+            "AddPartitionsToTxn",
+            TxnRequestKind::AddPartitionsToTxn(request),
+        )
+    }
+
+    fn txn_offset_commit_handler(
+        &self,
+        state: &mut TransactionManagerState,
+        result: Arc<TransactionalRequestResult>,
+        offsets: &HashMap<TopicPartition, OffsetAndMetadata>,
+        group_metadata: &ConsumerGroupMetadata,
+    ) -> TxnRequestHandler {
+        for (topic_partition, offset_and_metadata) in offsets {
+            let committed_offset = CommittedOffset::new(
+                offset_and_metadata.offset,
+                offset_and_metadata.metadata.clone(),
+                offset_and_metadata.leader_epoch,
+            );
+            state
+                .pending_txn_offset_commits
+                .insert(topic_partition.clone(), committed_offset);
+        }
+
+        let request = TxnOffsetCommitRequest::new(
+            self.transactional_id.clone().unwrap_or_default(),
+            group_metadata.group_id.clone(),
+            state.producer_id_and_epoch.producer_id,
+            state.producer_id_and_epoch.epoch,
+            group_metadata.member_id.clone(),
+            group_metadata.generation_id,
+            group_metadata.group_instance_id.clone(),
+            state.pending_txn_offset_commits.clone(),
+        );
+        TxnRequestHandler::new(
+            result,
+            self.retry_backoff_ms,
+            Some(CoordinatorType::Group),
+            Some(group_metadata.group_id.clone()),
+            TxnRequestKind::TxnOffsetCommit(request),
+        )
+    }
+
+    fn handle_cached_transaction_request_result(
+        &self,
+        state: &mut TransactionManagerState,
+        transactional_request_result_supplier: impl FnOnce(
+            &mut TransactionManagerState,
+        )
+            -> Result<Arc<TransactionalRequestResult>>,
+        target_state: State,
+    ) -> Result<Arc<TransactionalRequestResult>> {
+        self.ensure_transactional()?;
+
+        if let Some(result) = state.pending_result.clone() {
+            if state.current_state == target_state {
+                if result.is_completed() {
+                    state.pending_result = None;
+                }
+                return Ok(result);
+            }
+        }
+
+        let pending_result = transactional_request_result_supplier(state)?;
+        state.pending_result = Some(pending_result.clone());
+        Ok(pending_result)
+    }
+
+    pub fn can_bump_epoch(&self) -> bool {
+        self.can_bump_epoch_locked(&self.lock())
+    }
+
+    fn can_bump_epoch_locked(&self, state: &TransactionManagerState) -> bool {
+        if !self.is_transactional() {
+            return true;
+        }
+        state.coordinator_supports_bumping_epoch
+    }
+
+    fn complete_transaction(&self, state: &mut TransactionManagerState) -> Result<()> {
+        if state.epoch_bump_required {
+            self.transition_to(state, State::Initializing, None)?;
+        } else {
+            self.transition_to(state, State::Ready, None)?;
+        }
+        state.last_error = None;
+        state.epoch_bump_required = false;
+        state.transaction_started = false;
+        state.new_partitions_in_transaction.clear();
+        state.pending_partitions_in_transaction.clear();
+        state.partitions_in_transaction.clear();
+        Ok(())
+    }
+
+    fn fatal_error(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: &TxnRequestHandler,
+        error: KafkaError,
+    ) {
+        handler.result.fail(error.clone());
+        self.transition_to_fatal_error_locked(state, error);
+    }
+
+    fn abortable_error(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: &TxnRequestHandler,
+        error: KafkaError,
+    ) -> Result<()> {
+        handler.result.fail(error.clone());
+        self.transition_to_abortable_error_locked(state, error)
+    }
+
+    fn abortable_error_if_possible(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: &TxnRequestHandler,
+        error: KafkaError,
+    ) -> Result<()> {
+        if self.can_bump_epoch_locked(state) {
+            state.epoch_bump_required = true;
+            self.abortable_error(state, handler, error)
+        } else {
+            self.fatal_error(state, handler, error);
+            Ok(())
+        }
+    }
+
+    fn reenqueue(&self, state: &mut TransactionManagerState, mut handler: TxnRequestHandler) {
+        handler.set_retry();
+        self.enqueue_request(state, handler);
+    }
+
+    /// Completion handler of a transactional request previously returned by `next_request`.
+    pub fn handle_response(
+        &self,
+        handler: TxnRequestHandler,
+        response: ClientResponse,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        if response.request_header.correlation_id != state.in_flight_request_correlation_id {
+            self.fatal_error(
+                &mut state,
+                &handler,
+                KafkaError::Kafka(
+                    "Detected more than one in-flight transactional request.".to_owned(),
+                ),
+            );
+            return Ok(());
+        }
+
+        state.in_flight_request_correlation_id = NO_INFLIGHT_REQUEST_CORRELATION_ID;
+        if response.disconnected {
+            self.log.debug(format_args!(
+                "Disconnected from {}. Will retry.",
+                response.destination
+            ));
+            if let Some(coordinator_type) = handler.coordinator_type {
+                let coordinator_key = handler.coordinator_key.clone().unwrap_or_default();
+                self.lookup_coordinator_locked(&mut state, coordinator_type, coordinator_key);
+            }
+            self.reenqueue(&mut state, handler);
+            Ok(())
+        } else if let Some(version_mismatch) = response.version_mismatch {
+            self.fatal_error(&mut state, &handler, version_mismatch);
+            Ok(())
+        } else if let Some(response_body) = response.response_body {
+            self.log.trace(format_args!(
+                "Received transactional response {:?} for request {}",
+                response_body, handler
+            ));
+            self.handle_response_body(&mut state, handler, response_body)
+        } else {
+            self.fatal_error(
+                &mut state,
+                &handler,
+                KafkaError::Kafka(
+                    "Could not execute transactional request for unknown reasons".to_owned(),
+                ),
+            );
+            Ok(())
+        }
+    }
+
+    fn handle_response_body(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: TxnRequestHandler,
+        response: AbstractResponse,
+    ) -> Result<()> {
+        match (&handler.kind, response) {
+            (TxnRequestKind::InitProducerId { .. }, AbstractResponse::InitProducerId(response)) => {
+                self.handle_init_producer_id_response(
+                    state,
+                    handler,
+                    response.error,
+                    ProducerIdAndEpoch::new(response.producer_id, response.producer_epoch),
+                )
+            }
+            (
+                TxnRequestKind::AddPartitionsToTxn(_),
+                AbstractResponse::AddPartitionsToTxn(response),
+            ) => self.handle_add_partitions_to_txn_response(state, handler, response.errors),
+            (TxnRequestKind::FindCoordinator(_), AbstractResponse::FindCoordinator(response)) => {
+                self.handle_find_coordinator_response(
+                    state,
+                    handler,
+                    response.error,
+                    response.error_message,
+                    response.node,
+                )
+            }
+            (TxnRequestKind::EndTxn(_), AbstractResponse::EndTxn(response)) => {
+                self.handle_end_txn_response(state, handler, response.error)
+            }
+            (
+                TxnRequestKind::AddOffsetsToTxn { .. },
+                AbstractResponse::AddOffsetsToTxn(response),
+            ) => self.handle_add_offsets_to_txn_response(state, handler, response.error),
+            (TxnRequestKind::TxnOffsetCommit(_), AbstractResponse::TxnOffsetCommit(response)) => {
+                self.handle_txn_offset_commit_response(state, handler, response.errors)
+            }
+            (_, response) => {
+                let error = KafkaError::Kafka(format!(
+                    "Unexpected response type {} for transactional request {}",
+                    response.api_key(),
+                    handler
+                ));
+                self.fatal_error(state, &handler, error);
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_init_producer_id_response(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: TxnRequestHandler,
+        error: Errors,
+        producer_id_and_epoch: ProducerIdAndEpoch,
+    ) -> Result<()> {
+        let is_epoch_bump = matches!(
+            handler.kind,
+            TxnRequestKind::InitProducerId {
+                is_epoch_bump: true,
+                ..
+            }
+        );
+        match error {
+            Errors::None => {
+                self.set_producer_id_and_epoch(state, producer_id_and_epoch);
+                self.transition_to(state, State::Ready, None)?;
+                state.last_error = None;
+                if is_epoch_bump {
+                    Self::reset_sequence_numbers(state);
+                }
+                handler.result.done();
+            }
+            Errors::NotCoordinator | Errors::CoordinatorNotAvailable => {
+                self.lookup_coordinator_locked(
+                    state,
+                    CoordinatorType::Transaction,
+                    self.transactional_id.clone().unwrap_or_default(),
+                );
+                self.reenqueue(state, handler);
+            }
+            Errors::CoordinatorLoadInProgress | Errors::ConcurrentTransactions => {
+                self.reenqueue(state, handler);
+            }
+            Errors::TransactionalIdAuthorizationFailed | Errors::ClusterAuthorizationFailed => {
+                self.fatal_error(state, &handler, exception(error));
+            }
+            Errors::InvalidProducerEpoch | Errors::ProducerFenced => {
+                // We could still receive INVALID_PRODUCER_EPOCH from old versioned transaction coordinator,
+                // just treat it the same as PRODUCE_FENCED.
+                self.fatal_error(state, &handler, exception(Errors::ProducerFenced));
+            }
+            _ => {
+                let error = KafkaError::Kafka(format!(
+                    "Unexpected error in InitProducerIdResponse; {}",
+                    error.message()
+                ));
+                self.fatal_error(state, &handler, error);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_add_partitions_to_txn_response(
+        &self,
+        state: &mut TransactionManagerState,
+        mut handler: TxnRequestHandler,
+        errors: IndexMap<TopicPartition, Errors>,
+    ) -> Result<()> {
+        let mut has_partition_errors = false;
+        let mut unauthorized_topics = Vec::new();
+        handler.retry_backoff_ms = self.retry_backoff_ms;
+
+        for (topic_partition, error) in &errors {
+            match error {
+                Errors::None => continue,
+                Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                    self.lookup_coordinator_locked(
+                        state,
+                        CoordinatorType::Transaction,
+                        self.transactional_id.clone().unwrap_or_default(),
+                    );
+                    self.reenqueue(state, handler);
+                    return Ok(());
+                }
+                Errors::ConcurrentTransactions => {
+                    // We only want to reduce the backoff when retrying the first AddPartition which errored out due to a
+                    // CONCURRENT_TRANSACTIONS error since this means that the previous transaction is still completing and
+                    // we don't want to wait too long before trying to start the new one.
+                    //
+                    // This is only a temporary fix, the long term solution is being tracked in
+                    // https://issues.apache.org/jira/browse/KAFKA-5482
+                    if state.partitions_in_transaction.is_empty() {
+                        handler.retry_backoff_ms =
+                            self.retry_backoff_ms.min(ADD_PARTITIONS_RETRY_BACKOFF_MS);
+                    }
+                    self.reenqueue(state, handler);
+                    return Ok(());
+                }
+                Errors::CoordinatorLoadInProgress | Errors::UnknownTopicOrPartition => {
+                    self.reenqueue(state, handler);
+                    return Ok(());
+                }
+                Errors::InvalidProducerEpoch | Errors::ProducerFenced => {
+                    // We could still receive INVALID_PRODUCER_EPOCH from old versioned transaction coordinator,
+                    // just treat it the same as PRODUCE_FENCED.
+                    self.fatal_error(state, &handler, exception(Errors::ProducerFenced));
+                    return Ok(());
+                }
+                Errors::TransactionalIdAuthorizationFailed => {
+                    self.fatal_error(state, &handler, exception(*error));
+                    return Ok(());
+                }
+                Errors::InvalidTxnState => {
+                    self.fatal_error(
+                        state,
+                        &handler,
+                        KafkaError::Kafka(error.message().to_owned()),
+                    );
+                    return Ok(());
+                }
+                Errors::TopicAuthorizationFailed => {
+                    unauthorized_topics.push(topic_partition.topic.clone());
+                }
+                Errors::OperationNotAttempted => {
+                    self.log.debug(format_args!(
+                        "Did not attempt to add partition {} to transaction because other partitions in the batch had errors.",
+                        topic_partition
+                    ));
+                    has_partition_errors = true;
+                }
+                Errors::UnknownProducerId | Errors::InvalidProducerIdMapping => {
+                    self.abortable_error_if_possible(state, &handler, exception(*error))?;
+                    return Ok(());
+                }
+                _ => {
+                    self.log.error(format_args!(
+                        "Could not add partition {} due to unexpected error {}",
+                        topic_partition, error
+                    ));
+                    has_partition_errors = true;
+                }
+            }
+        }
+
+        // Remove the partitions from the pending set regardless of the result. We use the presence
+        // of partitions in the pending set to know when it is not safe to send batches. However, if
+        // the partitions failed to be added and we enter an error state, we expect the batches to be
+        // aborted anyway. In this case, we must be able to continue sending the batches which are in
+        // retry for partitions that were successfully added.
+        for partition in errors.keys() {
+            state.pending_partitions_in_transaction.remove(partition);
+        }
+
+        if !unauthorized_topics.is_empty() {
+            unauthorized_topics.sort();
+            unauthorized_topics.dedup();
+            let error = KafkaError::TopicAuthorization(format!(
+                "Not authorized to access topics: [{}]",
+                unauthorized_topics.join(", ")
+            ));
+            self.abortable_error(state, &handler, error)?;
+        } else if has_partition_errors {
+            let error = KafkaError::Kafka(format!(
+                "Could not add partitions to transaction due to errors: {:?}",
+                errors
+            ));
+            self.abortable_error(state, &handler, error)?;
+        } else {
+            self.log.debug(format_args!(
+                "Successfully added partitions {:?} to transaction",
+                errors.keys().collect::<Vec<_>>()
+            ));
+            state
+                .partitions_in_transaction
+                .extend(errors.into_iter().map(|(partition, _)| partition));
+            state.transaction_started = true;
+            handler.result.done();
+        }
+        Ok(())
+    }
+
+    fn handle_find_coordinator_response(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: TxnRequestHandler,
+        error: Errors,
+        error_message: Option<String>,
+        node: Node,
+    ) -> Result<()> {
+        let (coordinator_type, key) = match &handler.kind {
+            TxnRequestKind::FindCoordinator(request) => (request.key_type, request.key.clone()),
+            _ => unreachable!("FindCoordinator response handled by another handler"),
+        };
+        match error {
+            Errors::None => {
+                self.log.info(format_args!(
+                    "Discovered {} coordinator {}",
+                    coordinator_type.to_string().to_lowercase(),
+                    node
+                ));
+                match coordinator_type {
+                    CoordinatorType::Group => state.consumer_group_coordinator = Some(node),
+                    CoordinatorType::Transaction => state.transaction_coordinator = Some(node),
+                }
+                handler.result.done();
+            }
+            Errors::CoordinatorNotAvailable => {
+                self.reenqueue(state, handler);
+            }
+            Errors::TransactionalIdAuthorizationFailed => {
+                self.fatal_error(state, &handler, exception(error));
+            }
+            Errors::GroupAuthorizationFailed => {
+                let error = group_authorization_exception(&key);
+                self.abortable_error(state, &handler, error)?;
+            }
+            _ => {
+                let error = KafkaError::Kafka(format!(
+                    "Could not find a coordinator with type {} with key {} due to unexpected error: {}",
+                    coordinator_type,
+                    key,
+                    error_message.as_deref().unwrap_or_else(|| error.message())
+                ));
+                self.fatal_error(state, &handler, error);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_end_txn_response(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: TxnRequestHandler,
+        error: Errors,
+    ) -> Result<()> {
+        match error {
+            Errors::None => {
+                self.complete_transaction(state)?;
+                handler.result.done();
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                self.lookup_coordinator_locked(
+                    state,
+                    CoordinatorType::Transaction,
+                    self.transactional_id.clone().unwrap_or_default(),
+                );
+                self.reenqueue(state, handler);
+            }
+            Errors::CoordinatorLoadInProgress | Errors::ConcurrentTransactions => {
+                self.reenqueue(state, handler);
+            }
+            Errors::InvalidProducerEpoch | Errors::ProducerFenced => {
+                // We could still receive INVALID_PRODUCER_EPOCH from old versioned transaction coordinator,
+                // just treat it the same as PRODUCE_FENCED.
+                self.fatal_error(state, &handler, exception(Errors::ProducerFenced));
+            }
+            Errors::TransactionalIdAuthorizationFailed | Errors::InvalidTxnState => {
+                self.fatal_error(state, &handler, exception(error));
+            }
+            Errors::UnknownProducerId | Errors::InvalidProducerIdMapping => {
+                self.abortable_error_if_possible(state, &handler, exception(error))?;
+            }
+            _ => {
+                let error = KafkaError::Kafka(format!(
+                    "Unhandled error in EndTxnResponse: {}",
+                    error.message()
+                ));
+                self.fatal_error(state, &handler, error);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_add_offsets_to_txn_response(
+        &self,
+        state: &mut TransactionManagerState,
+        handler: TxnRequestHandler,
+        error: Errors,
+    ) -> Result<()> {
+        let group_id = match &handler.kind {
+            TxnRequestKind::AddOffsetsToTxn { request, .. } => request.group_id.clone(),
+            _ => unreachable!("AddOffsetsToTxn response handled by another handler"),
+        };
+        match error {
+            Errors::None => {
+                self.log.debug(format_args!(
+                    "Successfully added partition for consumer group {} to transaction",
+                    group_id
+                ));
+
+                // note the result is not completed until the TxnOffsetCommit returns
+                if let TxnRequestKind::AddOffsetsToTxn {
+                    offsets,
+                    group_metadata,
+                    ..
+                } = &handler.kind
+                {
+                    let commit_handler = self.txn_offset_commit_handler(
+                        state,
+                        handler.result.clone(),
+                        offsets,
+                        group_metadata,
+                    );
+                    state.pending_requests.push(commit_handler);
+                }
+                state.transaction_started = true;
+            }
+            Errors::CoordinatorNotAvailable | Errors::NotCoordinator => {
+                self.lookup_coordinator_locked(
+                    state,
+                    CoordinatorType::Transaction,
+                    self.transactional_id.clone().unwrap_or_default(),
+                );
+                self.reenqueue(state, handler);
+            }
+            Errors::CoordinatorLoadInProgress | Errors::ConcurrentTransactions => {
+                self.reenqueue(state, handler);
+            }
+            Errors::UnknownProducerId | Errors::InvalidProducerIdMapping => {
+                self.abortable_error_if_possible(state, &handler, exception(error))?;
+            }
+            Errors::InvalidProducerEpoch | Errors::ProducerFenced => {
+                // We could still receive INVALID_PRODUCER_EPOCH from old versioned transaction coordinator,
+                // just treat it the same as PRODUCE_FENCED.
+                self.fatal_error(state, &handler, exception(Errors::ProducerFenced));
+            }
+            Errors::TransactionalIdAuthorizationFailed => {
+                self.fatal_error(state, &handler, exception(error));
+            }
+            Errors::GroupAuthorizationFailed => {
+                let error = group_authorization_exception(&group_id);
+                self.abortable_error(state, &handler, error)?;
+            }
+            _ => {
+                let error = KafkaError::Kafka(format!(
+                    "Unexpected error in AddOffsetsToTxnResponse: {}",
+                    error.message()
+                ));
+                self.fatal_error(state, &handler, error);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_txn_offset_commit_response(
+        &self,
+        state: &mut TransactionManagerState,
+        mut handler: TxnRequestHandler,
+        errors: IndexMap<TopicPartition, Errors>,
+    ) -> Result<()> {
+        let group_id = match &handler.kind {
+            TxnRequestKind::TxnOffsetCommit(request) => request.group_id.clone(),
+            _ => unreachable!("TxnOffsetCommit response handled by another handler"),
+        };
+        let mut coordinator_reloaded = false;
+
+        self.log.debug(format_args!(
+            "Received TxnOffsetCommit response for consumer group {}: {:?}",
+            group_id, errors
+        ));
+
+        for (topic_partition, error) in &errors {
+            match error {
+                Errors::None => {
+                    state.pending_txn_offset_commits.remove(topic_partition);
+                }
+                Errors::CoordinatorNotAvailable
+                | Errors::NotCoordinator
+                | Errors::RequestTimedOut => {
+                    if !coordinator_reloaded {
+                        coordinator_reloaded = true;
+                        self.lookup_coordinator_locked(
+                            state,
+                            CoordinatorType::Group,
+                            group_id.clone(),
+                        );
+                    }
+                }
+                Errors::UnknownTopicOrPartition | Errors::CoordinatorLoadInProgress => {
+                    // If the topic is unknown or the coordinator is loading, retry with the current coordinator
+                    continue;
+                }
+                Errors::GroupAuthorizationFailed => {
+                    let error = group_authorization_exception(&group_id);
+                    self.abortable_error(state, &handler, error)?;
+                    break;
+                }
+                Errors::FencedInstanceId => {
+                    self.abortable_error(state, &handler, exception(*error))?;
+                    break;
+                }
+                Errors::UnknownMemberId | Errors::IllegalGeneration => {
+                    let error = KafkaError::CommitFailed(format!(
+                        "Transaction offset Commit failed due to consumer group metadata mismatch: {}",
+                        exception(*error)
+                    ));
+                    self.abortable_error(state, &handler, error)?;
+                    break;
+                }
+                error if is_fatal_exception(*error) => {
+                    self.fatal_error(state, &handler, exception(*error));
+                    break;
+                }
+                _ => {
+                    let error = KafkaError::Kafka(format!(
+                        "Unexpected error in TxnOffsetCommitResponse: {}",
+                        error.message()
+                    ));
+                    self.fatal_error(state, &handler, error);
+                    break;
+                }
+            }
+        }
+
+        if handler.result.is_completed() {
+            state.pending_txn_offset_commits.clear();
+        } else if state.pending_txn_offset_commits.is_empty() {
+            handler.result.done();
+        } else {
+            // Retry the commits which failed with a retriable error
+            if let TxnRequestKind::TxnOffsetCommit(request) = &mut handler.kind {
+                request.offsets = state.pending_txn_offset_commits.clone();
+            }
+            self.reenqueue(state, handler);
+        }
+        Ok(())
+    }
+}
+
+fn exception(error: Errors) -> KafkaError {
+    error
+        .exception(None)
+        .unwrap_or_else(|| KafkaError::UnknownServer(error.message().to_owned()))
+}
+
+fn group_authorization_exception(group_id: &str) -> KafkaError {
+    KafkaError::GroupAuthorization(format!("Not authorized to access group: {}", group_id))
+}
+
+fn is_fatal_exception(error: Errors) -> bool {
+    error == Errors::TransactionalIdAuthorizationFailed
+        || error == Errors::InvalidProducerEpoch
+        || error == Errors::ProducerFenced
+        || error == Errors::UnsupportedForMessageFormat
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use indexmap::IndexMap;
+
+    use crate::{
+        clients::{
+            api_versions::{ApiVersion, NodeApiVersions},
+            mock_client::MockClient,
+            producer::internals::{
+                buffer_pool::BufferPool, future_record_metadata::FutureRecordMetadata,
+                producer_metadata::ProducerMetadata, record_accumulator::RecordAccumulator,
+                sender::Sender,
+            },
+        },
+        common::{
+            errors::KafkaError,
+            node::Node,
+            protocol::{api_keys::ApiKeys, errors::Errors},
+            record::{compression_type::CompressionType, record_batch::RecordBatch},
+            requests::{
+                abstract_request::AbstractRequest,
+                abstract_response::AbstractResponse,
+                add_partitions_to_txn_response::AddPartitionsToTxnResponse,
+                end_txn_request::TransactionResult,
+                end_txn_response::EndTxnResponse,
+                find_coordinator_request::CoordinatorType,
+                find_coordinator_response::FindCoordinatorResponse,
+                init_producer_id_response::InitProducerIdResponse,
+                produce_response::{PartitionResponse, ProduceResponse},
+            },
+            topic_partition::TopicPartition,
+            utils::{
+                log_context::LogContext, mock_time::MockTime,
+                producer_id_and_epoch::ProducerIdAndEpoch, time::Time,
+            },
+        },
+        test_utils::MockBroker,
+    };
+
+    use super::{State, TransactionManager};
+
+    const TOPIC: &str = "test";
+    const TRANSACTIONAL_ID: &str = "foobar";
+    const PRODUCER_ID: i64 = 13131;
+    const EPOCH: i16 = 1;
+    const RETRY_BACKOFF_MS: u128 = 100;
+
+    /// A sender and transaction manager talking to a `MockClient` broker.
+    struct Context {
+        time: Arc<MockTime>,
+        broker: Node,
+        client: Arc<MockClient>,
+        accumulator: Arc<RecordAccumulator>,
+        sender: Arc<Sender>,
+        transaction_manager: Arc<TransactionManager>,
+    }
+
+    impl Context {
+        fn new(transactional_id: Option<&str>) -> Context {
+            let mock_broker = MockBroker::new();
+            let cluster = mock_broker.cluster(TOPIC, 2);
+            let MockBroker {
+                time,
+                node: broker,
+                api_versions,
+                client,
+            } = mock_broker;
+            // InitProducerId v3 lets transactional producers bump the epoch on abortable errors
+            api_versions.update(
+                broker.id_string(),
+                NodeApiVersions::new(vec![ApiVersion::new(ApiKeys::InitProducerId, 0, 4)]),
+            );
+            let transaction_manager = Arc::new(TransactionManager::new(
+                transactional_id.map(str::to_owned),
+                60_000,
+                RETRY_BACKOFF_MS,
+                api_versions,
+                &LogContext::default(),
+            ));
+
+            let now = time.milliseconds();
+            let metadata = Arc::new(ProducerMetadata::new(
+                RETRY_BACKOFF_MS,
+                300_000,
+                300_000,
+                time.clone(),
+            ));
+            metadata.add(TOPIC, now);
+            metadata
+                .update(metadata.request_version(), cluster, false, now)
+                .unwrap();
+
+            let accumulator = Arc::new(
+                RecordAccumulator::new(
+                    16384,
+                    CompressionType::None,
+                    0,
+                    RETRY_BACKOFF_MS,
+                    120_000,
+                    time.clone(),
+                    Some(transaction_manager.clone()),
+                    Arc::new(BufferPool::new(1024 * 1024, 16384, time.clone())),
+                    &LogContext::default(),
+                )
+                .unwrap(),
+            );
+            let sender = Arc::new(Sender::new(
+                client.clone(),
+                metadata,
+                accumulator.clone(),
+                false,
+                1024 * 1024,
+                -1,
+                i32::MAX,
+                time.clone(),
+                30_000,
+                RETRY_BACKOFF_MS,
+                Some(transaction_manager.clone()),
+                &LogContext::default(),
+            ));
+            Context {
+                time,
+                broker,
+                client,
+                accumulator,
+                sender,
+                transaction_manager,
+            }
+        }
+
+        fn state(&self) -> State {
+            self.transaction_manager.lock().current_state
+        }
+
+        fn append(&self, tp: &TopicPartition) -> FutureRecordMetadata {
+            let now = self.time.milliseconds();
+            self.accumulator
+                .append(
+                    tp,
+                    now as i64,
+                    Some(b"key"),
+                    Some(b"value"),
+                    &[],
+                    &mut None,
+                    0,
+                    false,
+                    now,
+                )
+                .unwrap()
+                .future
+                .unwrap()
+        }
+
+        fn run_once(&self) {
+            self.sender.run_once().unwrap();
+        }
+
+        fn run_until(&self, condition: impl Fn() -> bool) {
+            for _ in 0..1000 {
+                if condition() {
+                    return;
+                }
+                self.run_once();
+            }
+            panic!("Condition not met after 1000 iterations of the sender");
+        }
+
+        fn prepare_init_producer_id(&self, current_epoch: i16, epoch: i16) {
+            self.client.prepare_response_matching(
+                move |request| {
+                    matches!(request, AbstractRequest::InitProducerId(request)
+                        if request.producer_epoch == current_epoch)
+                },
+                AbstractResponse::InitProducerId(InitProducerIdResponse::new(
+                    Errors::None,
+                    PRODUCER_ID,
+                    epoch,
+                )),
+            );
+        }
+
+        fn prepare_add_partitions(&self, tp: &TopicPartition, error: Errors) {
+            let expected = tp.clone();
+            self.client.prepare_response_matching(
+                move |request| {
+                    matches!(request, AbstractRequest::AddPartitionsToTxn(request)
+                        if request.partitions == [expected.clone()])
+                },
+                AbstractResponse::AddPartitionsToTxn(AddPartitionsToTxnResponse::new(
+                    IndexMap::from_iter(vec![(tp.clone(), error)]),
+                )),
+            );
+        }
+
+        fn prepare_produce(&self, tp: &TopicPartition, error: Errors, base_offset: i64) {
+            let expected = tp.clone();
+            self.client.prepare_response_matching(
+                move |request| produced_batch(request, &expected).is_some(),
+                produce_response(tp, error, base_offset),
+            );
+        }
+
+        fn prepare_end_txn(&self, result: TransactionResult) {
+            self.client.prepare_response_matching(
+                move |request| {
+                    matches!(request, AbstractRequest::EndTxn(request) if request.result == result)
+                },
+                AbstractResponse::EndTxn(EndTxnResponse::new(Errors::None)),
+            );
+        }
+
+        fn initialize_idempotent_producer(&self) {
+            self.prepare_init_producer_id(-1, EPOCH);
+            self.run_until(|| self.transaction_manager.has_producer_id());
+            assert_eq!(
+                self.transaction_manager.producer_id_and_epoch(),
+                ProducerIdAndEpoch::new(PRODUCER_ID, EPOCH)
+            );
+        }
+
+        fn initialize_transactions(&self) {
+            let result = self.transaction_manager.initialize_transactions().unwrap();
+            assert_eq!(self.state(), State::Initializing);
+            self.client.prepare_response_matching(
+                |request| {
+                    matches!(request, AbstractRequest::FindCoordinator(request)
+                        if request.key == TRANSACTIONAL_ID
+                            && request.key_type == CoordinatorType::Transaction)
+                },
+                AbstractResponse::FindCoordinator(FindCoordinatorResponse::new(
+                    Errors::None,
+                    self.broker.clone(),
+                )),
+            );
+            self.prepare_init_producer_id(-1, EPOCH);
+            self.run_until(|| result.is_completed());
+            assert!(result.is_successful());
+            assert!(self.transaction_manager.is_ready());
+            assert_eq!(
+                self.transaction_manager
+                    .coordinator(CoordinatorType::Transaction),
+                Some(self.broker.clone())
+            );
+        }
+    }
+
+    fn tp(partition: i32) -> TopicPartition {
+        TopicPartition::new(TOPIC, partition)
+    }
+
+    fn produced_batch(request: &AbstractRequest, tp: &TopicPartition) -> Option<RecordBatch> {
+        match request {
+            AbstractRequest::Produce(request) => request
+                .partition_records
+                .get(tp)
+                .and_then(|records| records.batches().next())
+                .map(|batch| batch.unwrap()),
+            _ => None,
+        }
+    }
+
+    fn produce_response(tp: &TopicPartition, error: Errors, base_offset: i64) -> AbstractResponse {
+        let response = if error == Errors::None {
+            PartitionResponse::new(error, base_offset, -1, 0, vec![], None)
+        } else {
+            PartitionResponse::from_error(error)
+        };
+        AbstractResponse::Produce(ProduceResponse::new(
+            IndexMap::from_iter(vec![(tp.clone(), response)]),
+            0,
+        ))
+    }
+
+    #[test]
+    fn sequence_numbers_are_tracked_per_partition() {
+        let context = Context::new(None);
+        context.initialize_idempotent_producer();
+        let transaction_manager = &context.transaction_manager;
+
+        let first = context.append(&tp(0));
+        let second = context.append(&tp(1));
+        context.run_once();
+        let requests = context.client.requests();
+        assert_eq!(requests.len(), 1);
+        for tp in &[tp(0), tp(1)] {
+            let batch = produced_batch(&requests[0], tp).unwrap();
+            assert_eq!(batch.producer_id(), PRODUCER_ID);
+            assert_eq!(batch.producer_epoch(), EPOCH);
+            assert_eq!(batch.base_sequence(), 0);
+            assert_eq!(transaction_manager.sequence_number(tp), 1);
+        }
+        assert!(context
+            .client
+            .respond(AbstractResponse::Produce(ProduceResponse::new(
+                IndexMap::from_iter(vec![
+                    (
+                        tp(0),
+                        PartitionResponse::new(Errors::None, 0, -1, 0, vec![], None)
+                    ),
+                    (
+                        tp(1),
+                        PartitionResponse::new(Errors::None, 0, -1, 0, vec![], None)
+                    ),
+                ]),
+                0,
+            ))));
+        context.run_until(|| first.is_done() && second.is_done());
+        assert_eq!(transaction_manager.last_acked_sequence(&tp(0)), Some(0));
+        assert_eq!(transaction_manager.last_acked_sequence(&tp(1)), Some(0));
+
+        // Both records go to the same batch, which takes the next two sequences of the partition
+        let third = context.append(&tp(0));
+        let fourth = context.append(&tp(0));
+        context.run_once();
+        let requests = context.client.requests();
+        assert_eq!(requests.len(), 1);
+        let batch = produced_batch(&requests[0], &tp(0)).unwrap();
+        assert_eq!(batch.base_sequence(), 1);
+        assert_eq!(batch.last_sequence(), 2);
+        assert_eq!(transaction_manager.sequence_number(&tp(0)), 3);
+        assert_eq!(transaction_manager.sequence_number(&tp(1)), 1);
+
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 1)));
+        context.run_until(|| third.is_done() && fourth.is_done());
+        assert_eq!(fourth.value_or_error().unwrap().offset, 2);
+        assert_eq!(transaction_manager.last_acked_sequence(&tp(0)), Some(2));
+        assert_eq!(transaction_manager.last_acked_offset(&tp(0)), Some(2));
+    }
+
+    #[test]
+    fn out_of_order_sequence_bumps_the_epoch() {
+        let context = Context::new(None);
+        context.initialize_idempotent_producer();
+        let transaction_manager = &context.transaction_manager;
+
+        let future = context.append(&tp(0));
+        context.prepare_produce(&tp(0), Errors::OutOfOrderSequenceNumber, -1);
+        context.run_once();
+        assert!(!future.is_done());
+        assert_eq!(
+            transaction_manager.producer_id_and_epoch(),
+            ProducerIdAndEpoch::new(PRODUCER_ID, EPOCH)
+        );
+
+        // The batch is retried with sequence 0 of the bumped epoch, without a new producer id
+        context.time.sleep(RETRY_BACKOFF_MS);
+        context.run_until(|| !context.client.requests().is_empty());
+        assert_eq!(
+            transaction_manager.producer_id_and_epoch(),
+            ProducerIdAndEpoch::new(PRODUCER_ID, EPOCH + 1)
+        );
+        let batch = produced_batch(&context.client.requests()[0], &tp(0)).unwrap();
+        assert_eq!(batch.producer_id(), PRODUCER_ID);
+        assert_eq!(batch.producer_epoch(), EPOCH + 1);
+        assert_eq!(batch.base_sequence(), 0);
+
+        assert!(context
+            .client
+            .respond(produce_response(&tp(0), Errors::None, 0)));
+        context.run_until(|| future.is_done());
+        assert!(future.value_or_error().is_ok());
+        assert_eq!(transaction_manager.sequence_number(&tp(0)), 1);
+        assert_eq!(transaction_manager.last_acked_sequence(&tp(0)), Some(0));
+    }
+
+    #[test]
+    fn committing_a_transaction() {
+        let context = Context::new(Some(TRANSACTIONAL_ID));
+        context.initialize_transactions();
+        let transaction_manager = &context.transaction_manager;
+
+        transaction_manager.begin_transaction().unwrap();
+        assert_eq!(context.state(), State::InTransaction);
+        transaction_manager.maybe_add_partition_to_transaction(&tp(0));
+        let future = context.append(&tp(0));
+
+        context.prepare_add_partitions(&tp(0), Errors::None);
+        context.prepare_produce(&tp(0), Errors::None, 0);
+        let commit = transaction_manager.begin_commit().unwrap();
+        assert_eq!(context.state(), State::CommittingTransaction);
+        context.prepare_end_txn(TransactionResult::Commit);
+        context.run_until(|| commit.is_completed());
+
+        assert!(commit.is_successful());
+        assert!(future.value_or_error().is_ok());
+        assert_eq!(context.client.future_response_count(), 0);
+        assert_eq!(context.state(), State::Ready);
+        assert!(!transaction_manager.has_ongoing_transaction());
+        assert!(!transaction_manager.transaction_contains_partition(&tp(0)));
+    }
+
+    #[test]
+    fn aborting_a_transaction() {
+        let context = Context::new(Some(TRANSACTIONAL_ID));
+        context.initialize_transactions();
+        let transaction_manager = &context.transaction_manager;
+
+        transaction_manager.begin_transaction().unwrap();
+        transaction_manager.maybe_add_partition_to_transaction(&tp(0));
+        let future = context.append(&tp(0));
+        context.prepare_add_partitions(&tp(0), Errors::None);
+        context.prepare_produce(&tp(0), Errors::None, 0);
+        context.run_until(|| future.is_done());
+        assert!(transaction_manager.transaction_contains_partition(&tp(0)));
+
+        let abort = transaction_manager.begin_abort().unwrap();
+        assert_eq!(context.state(), State::AbortingTransaction);
+        context.prepare_end_txn(TransactionResult::Abort);
+        context.run_until(|| abort.is_completed());
+
+        assert!(abort.is_successful());
+        assert_eq!(context.state(), State::Ready);
+        assert_eq!(
+            transaction_manager.producer_id_and_epoch(),
+            ProducerIdAndEpoch::new(PRODUCER_ID, EPOCH)
+        );
+        transaction_manager.begin_transaction().unwrap();
+        assert_eq!(context.state(), State::InTransaction);
+    }
+
+    #[test]
+    fn failed_batch_is_an_abortable_error() {
+        let context = Context::new(Some(TRANSACTIONAL_ID));
+        context.initialize_transactions();
+        let transaction_manager = &context.transaction_manager;
+
+        transaction_manager.begin_transaction().unwrap();
+        transaction_manager.maybe_add_partition_to_transaction(&tp(0));
+        let future = context.append(&tp(0));
+        context.prepare_add_partitions(&tp(0), Errors::None);
+        context.prepare_produce(&tp(0), Errors::OutOfOrderSequenceNumber, -1);
+        context.run_until(|| future.is_done());
+
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::OutOfOrderSequence(_))
+        ));
+        assert!(transaction_manager.has_abortable_error());
+        assert!(!transaction_manager.has_fatal_error());
+        assert!(transaction_manager.begin_commit().is_err());
+
+        // Aborting recovers, bumping the epoch since the broker supports it
+        let abort = transaction_manager.begin_abort().unwrap();
+        context.prepare_end_txn(TransactionResult::Abort);
+        context.prepare_init_producer_id(EPOCH, EPOCH + 1);
+        context.run_until(|| abort.is_completed());
+
+        assert!(abort.is_successful());
+        assert!(transaction_manager.is_ready());
+        assert_eq!(transaction_manager.last_error(), None);
+        assert_eq!(
+            transaction_manager.producer_id_and_epoch(),
+            ProducerIdAndEpoch::new(PRODUCER_ID, EPOCH + 1)
+        );
+    }
+
+    #[test]
+    fn fenced_producer_is_a_fatal_error() {
+        let context = Context::new(Some(TRANSACTIONAL_ID));
+        context.initialize_transactions();
+        let transaction_manager = &context.transaction_manager;
+
+        transaction_manager.begin_transaction().unwrap();
+        transaction_manager.maybe_add_partition_to_transaction(&tp(0));
+        let future = context.append(&tp(0));
+        context.prepare_add_partitions(&tp(0), Errors::ProducerFenced);
+        context.run_until(|| future.is_done());
+
+        assert!(transaction_manager.has_fatal_error());
+        assert!(matches!(
+            transaction_manager.last_error(),
+            Some(KafkaError::ProducerFenced(_))
+        ));
+        assert!(matches!(
+            future.value_or_error(),
+            Err(KafkaError::ProducerFenced(_))
+        ));
+        assert!(transaction_manager.begin_abort().is_err());
+        assert!(transaction_manager.begin_commit().is_err());
+        assert!(transaction_manager.begin_transaction().is_err());
+        assert!(!transaction_manager.is_send_to_partition_allowed(&tp(0)));
+    }
+}
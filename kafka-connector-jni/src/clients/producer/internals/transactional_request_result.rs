@@ -0,0 +1,128 @@
+use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    task::Waker,
+    time::{Duration, Instant},
+};
+
+use crate::common::errors::{KafkaError, Result};
+
+/// The result of a transactional request (`InitProducerId`, `AddOffsetsToTxn`, `EndTxn`, ...).
+/// Shared between the application thread awaiting it and the sender thread completing it.
+pub struct TransactionalRequestResult {
+    operation: String,
+    state: Mutex<TransactionalRequestResultState>,
+    completed: Condvar,
+}
+
+#[derive(Default)]
+struct TransactionalRequestResultState {
+    error: Option<KafkaError>,
+    completed: bool,
+    wakers: Vec<Waker>,
+}
+
+impl TransactionalRequestResult {
+    pub fn new(operation: impl Into<String>) -> TransactionalRequestResult {
+        TransactionalRequestResult {
+            operation: operation.into(),
+            state: Mutex::new(TransactionalRequestResultState::default()),
+            completed: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, TransactionalRequestResultState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn complete(&self, error: Option<KafkaError>) {
+        let mut state = self.lock();
+        if state.completed {
+            return;
+        }
+        state.error = error;
+        state.completed = true;
+        let wakers = std::mem::take(&mut state.wakers);
+        drop(state);
+        self.completed.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn fail(&self, error: KafkaError) {
+        self.complete(Some(error));
+    }
+
+    pub fn done(&self) {
+        self.complete(None);
+    }
+
+    /// Wait until the request completes, returning its error if it failed.
+    pub fn await_completion(&self) -> Result<()> {
+        let mut state = self.lock();
+        while !state.completed {
+            state = self
+                .completed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+        match &state.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait up to `timeout` for the request to complete, returning its error if it failed or a
+    /// `Timeout` error if it did not complete in time.
+    pub fn await_timeout(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        while !state.completed {
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(KafkaError::Timeout(format!(
+                    "Timeout expired after {} milliseconds while awaiting {}",
+                    timeout.as_millis(),
+                    self.operation
+                )));
+            }
+            state = self
+                .completed
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        match &state.error {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Register a waker notified once the request completes. Returns true if already completed.
+    pub fn register_waker(&self, waker: &Waker) -> bool {
+        let mut state = self.lock();
+        if state.completed {
+            return true;
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(waker)) {
+            state.wakers.push(waker.clone());
+        }
+        false
+    }
+
+    pub fn error(&self) -> Option<KafkaError> {
+        self.lock().error.clone()
+    }
+
+    pub fn is_successful(&self) -> bool {
+        self.lock().error.is_none()
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.lock().completed
+    }
+
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+}
@@ -0,0 +1,1077 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    clients::{
+        api_versions::ApiVersions,
+        client_utils,
+        common_client_configs::{
+            BOOTSTRAP_SERVERS_CONFIG, CLIENT_ID_CONFIG, METADATA_MAX_AGE_CONFIG,
+            REQUEST_TIMEOUT_MS_CONFIG, RETRIES_CONFIG, RETRY_BACKOFF_MS_CONFIG,
+        },
+        consumer::{
+            consumer_group_metadata::{ConsumerGroupMetadata, UNKNOWN_MEMBER_ID},
+            offset_and_metadata::OffsetAndMetadata,
+        },
+    },
+    common::{
+        cluster::Cluster,
+        errors::{KafkaError, Result},
+        partition_info::PartitionInfo,
+        record::{
+            compression_type::CompressionType, default_record::DefaultRecord,
+            default_record_batch::RECORD_BATCH_OVERHEAD, record_batch::NO_TIMESTAMP,
+        },
+        serialization::serializer::Serializer,
+        topic_partition::TopicPartition,
+        utils::{
+            log_context::{LogContext, Logger},
+            time::{SystemTime, Time},
+        },
+    },
+};
+
+use super::{
+    callback::Callback,
+    internals::{
+        buffer_pool::BufferPool,
+        default_partitioner::DefaultPartitioner,
+        future_record_metadata::FutureRecordMetadata,
+        produce_request_result::{ErrorsByIndex, ProduceRequestResult},
+        producer_metadata::ProducerMetadata,
+        record_accumulator::RecordAccumulator,
+        sender::Sender,
+        transaction_manager::TransactionManager,
+    },
+    partitioner::Partitioner,
+    producer_config::{
+        ProducerConfig, ACKS_CONFIG, BATCH_SIZE_CONFIG, BUFFER_MEMORY_CONFIG,
+        COMPRESSION_TYPE_CONFIG, DELIVERY_TIMEOUT_MS_CONFIG, ENABLE_IDEMPOTENCE_CONFIG,
+        LINGER_MS_CONFIG, MAX_BLOCK_MS_CONFIG, MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
+        MAX_REQUEST_SIZE_CONFIG, METADATA_MAX_IDLE_CONFIG, PARTITIONER_CLASS_CONFIG,
+        TRANSACTIONAL_ID_CONFIG, TRANSACTION_TIMEOUT_CONFIG,
+    },
+    producer_record::ProducerRecord,
+    round_robin_partitioner::RoundRobinPartitioner,
+    uniform_sticky_partitioner::UniformStickyPartitioner,
+};
+
+const NETWORK_THREAD_PREFIX: &str = "kafka-producer-network-thread";
+
+/// The sender thread together with a channel disconnected once the thread exits, which allows
+/// waiting for it with a timeout.
+struct IoThread {
+    handle: JoinHandle<()>,
+    terminated: Receiver<()>,
+}
+
+/// A Kafka client that publishes records to the Kafka cluster.
+///
+/// The producer is thread safe and sharing a single producer instance across threads will generally be faster than
+/// having multiple instances.
+///
+/// The producer consists of a pool of buffer space that holds records that haven't yet been transmitted to the server
+/// as well as a background I/O thread that is responsible for turning these records into requests and transmitting them
+/// to the cluster. Failure to close the producer after use will leak these resources.
+///
+/// The `send` method is asynchronous. When called it adds the record to a buffer of pending record sends
+/// and immediately returns. This allows the producer to batch together individual records for efficiency.
+pub struct KafkaProducer<K, V> {
+    log: Logger,
+    client_id: String,
+    partitioner: Arc<dyn Partitioner>,
+    max_request_size: usize,
+    total_memory_size: usize,
+    metadata: Arc<ProducerMetadata>,
+    accumulator: Arc<RecordAccumulator>,
+    sender: Arc<Sender>,
+    io_thread: Mutex<Option<IoThread>>,
+    time: Arc<dyn Time>,
+    key_serializer: Box<dyn Serializer<K>>,
+    value_serializer: Box<dyn Serializer<V>>,
+    max_block_time_ms: u128,
+    transaction_manager: Option<Arc<TransactionManager>>,
+}
+
+impl<K, V> KafkaProducer<K, V> {
+    /// Creates the producer and starts its I/O thread running the `sender`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        client_id: impl Into<String>,
+        key_serializer: Box<dyn Serializer<K>>,
+        value_serializer: Box<dyn Serializer<V>>,
+        partitioner: Arc<dyn Partitioner>,
+        metadata: Arc<ProducerMetadata>,
+        accumulator: Arc<RecordAccumulator>,
+        sender: Arc<Sender>,
+        transaction_manager: Option<Arc<TransactionManager>>,
+        time: Arc<dyn Time>,
+        max_request_size: usize,
+        total_memory_size: usize,
+        max_block_time_ms: u128,
+    ) -> Result<KafkaProducer<K, V>> {
+        let client_id = client_id.into();
+        let transactional_id = transaction_manager
+            .as_ref()
+            .and_then(|manager| manager.transactional_id());
+        let log_context = KafkaProducer::<K, V>::create_log_context(&client_id, transactional_id);
+        let log = log_context.logger(module_path!());
+        let name = format!("{} | {}", NETWORK_THREAD_PREFIX, client_id);
+        let (terminated_sender, terminated) = mpsc::channel();
+        let io_sender = sender.clone();
+        let handle = thread::Builder::new()
+            .name(name)
+            .spawn(move || {
+                // dropped when the thread exits, even by panicking
+                let _terminated_sender = terminated_sender;
+                io_sender.run();
+            })
+            .map_err(|e| {
+                KafkaError::Kafka(format!("Failed to start the producer I/O thread: {}", e))
+            })?;
+        log.debug(format_args!("Kafka producer started"));
+        Ok(KafkaProducer {
+            log,
+            client_id,
+            partitioner,
+            max_request_size,
+            total_memory_size,
+            metadata,
+            accumulator,
+            sender,
+            io_thread: Mutex::new(Some(IoThread { handle, terminated })),
+            time,
+            key_serializer,
+            value_serializer,
+            max_block_time_ms,
+            transaction_manager,
+        })
+    }
+
+    /// Creates the producer from `config`, with the network client, metadata, accumulator, sender
+    /// and partitioner it configures. The serializers are passed in directly, so the configured
+    /// serializer classes are ignored.
+    pub fn from_config(
+        config: ProducerConfig,
+        key_serializer: Box<dyn Serializer<K>>,
+        value_serializer: Box<dyn Serializer<V>>,
+    ) -> Result<KafkaProducer<K, V>> {
+        let get_ms = |key: &str| -> Result<u128> {
+            Ok(config.get_long(key)?.unwrap_or_default().max(0) as u128)
+        };
+        let client_id = config.get_string(CLIENT_ID_CONFIG)?.unwrap_or_default();
+        let transactional_id = config.get_string(TRANSACTIONAL_ID_CONFIG)?;
+        let log_context =
+            KafkaProducer::<K, V>::create_log_context(&client_id, transactional_id.as_deref());
+        let log = log_context.logger(module_path!());
+        log.trace(format_args!("Starting the Kafka producer"));
+        let time: Arc<dyn Time> = Arc::new(SystemTime);
+
+        let partitioner = partitioner_instance(
+            &config
+                .get_class(PARTITIONER_CLASS_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        let retry_backoff_ms = get_ms(RETRY_BACKOFF_MS_CONFIG)?;
+        let max_request_size = config
+            .get_int(MAX_REQUEST_SIZE_CONFIG)?
+            .unwrap_or_default()
+            .max(0) as usize;
+        let total_memory_size = config
+            .get_long(BUFFER_MEMORY_CONFIG)?
+            .unwrap_or_default()
+            .max(0) as usize;
+        let compression = CompressionType::for_name(
+            &config
+                .get_string(COMPRESSION_TYPE_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        let max_block_time_ms = get_ms(MAX_BLOCK_MS_CONFIG)?;
+        let delivery_timeout_ms = configure_delivery_timeout(&config, &log)?;
+
+        let api_versions = Arc::new(ApiVersions::new());
+        let transaction_manager = configure_transaction_state(
+            &config,
+            &log_context,
+            retry_backoff_ms,
+            api_versions.clone(),
+        )?;
+        let batch_size = config
+            .get_int(BATCH_SIZE_CONFIG)?
+            .unwrap_or_default()
+            .max(0) as usize;
+        let accumulator = Arc::new(RecordAccumulator::new(
+            batch_size,
+            compression,
+            linger_ms(&config)?,
+            retry_backoff_ms,
+            delivery_timeout_ms,
+            time.clone(),
+            transaction_manager.clone(),
+            Arc::new(BufferPool::new(total_memory_size, batch_size, time.clone())),
+            &log_context,
+        )?);
+
+        let addresses = client_utils::parse_and_validate_addresses(
+            &config
+                .get_list(BOOTSTRAP_SERVERS_CONFIG)?
+                .unwrap_or_default(),
+        )?;
+        let metadata = Arc::new(ProducerMetadata::new(
+            retry_backoff_ms,
+            get_ms(METADATA_MAX_AGE_CONFIG)?,
+            get_ms(METADATA_MAX_IDLE_CONFIG)?,
+            time.clone(),
+        ));
+        metadata.bootstrap(&addresses);
+
+        let max_in_flight_requests = configure_inflight_requests(&config)?;
+        let client = Arc::new(client_utils::create_network_client(
+            &config,
+            &log_context,
+            api_versions,
+            time.clone(),
+            max_in_flight_requests,
+            metadata.clone(),
+        )?);
+        let acks = configure_acks(&config, &log)?;
+        let sender = Arc::new(Sender::new(
+            client,
+            metadata.clone(),
+            accumulator.clone(),
+            max_in_flight_requests == 1,
+            max_request_size,
+            acks,
+            config.get_int(RETRIES_CONFIG)?.unwrap_or_default(),
+            time.clone(),
+            config
+                .get_int(REQUEST_TIMEOUT_MS_CONFIG)?
+                .unwrap_or_default()
+                .max(0) as u128,
+            retry_backoff_ms,
+            transaction_manager.clone(),
+            &log_context,
+        ));
+
+        let producer = KafkaProducer::new(
+            client_id,
+            key_serializer,
+            value_serializer,
+            partitioner,
+            metadata,
+            accumulator,
+            sender,
+            transaction_manager,
+            time,
+            max_request_size,
+            total_memory_size,
+            max_block_time_ms,
+        )?;
+        config.log_unused();
+        Ok(producer)
+    }
+
+    fn create_log_context(client_id: &str, transactional_id: Option<&str>) -> LogContext {
+        match transactional_id {
+            Some(transactional_id) => LogContext::new(format!(
+                "[Producer clientId={}, transactionalId={}] ",
+                client_id, transactional_id
+            )),
+            None => LogContext::new(format!("[Producer clientId={}] ", client_id)),
+        }
+    }
+
+    fn lock_io_thread(&self) -> MutexGuard<'_, Option<IoThread>> {
+        self.io_thread.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Needs to be called before any other methods when the transactional.id is set in the configuration.
+    ///
+    /// This method does the following:
+    /// 1. Ensures any transactions initiated by previous instances of the producer with the same
+    ///    transactional.id are completed. If the previous instance had failed with a transaction in
+    ///    progress, it will be aborted. If the last transaction had begun completion,
+    ///    but not yet finished, this method awaits its completion.
+    /// 2. Gets the internal producer id and epoch, used in all future transactional
+    ///    messages issued by the producer.
+    ///
+    /// Note that this method will fail with `Timeout` if the transaction state cannot be initialized before
+    /// expiration of `max.block.ms`. Retrying after a timeout will continue to wait for the prior request.
+    pub fn init_transactions(&self) -> Result<()> {
+        let transaction_manager = self.throw_if_no_transaction_manager()?;
+        self.throw_if_producer_closed()?;
+        let result = transaction_manager.initialize_transactions()?;
+        self.sender.wakeup();
+        result.await_timeout(self.max_block_duration())
+    }
+
+    /// Should be called before the start of each new transaction. Note that prior to the first invocation
+    /// of this method, you must invoke `init_transactions` exactly one time.
+    pub fn begin_transaction(&self) -> Result<()> {
+        let transaction_manager = self.throw_if_no_transaction_manager()?;
+        self.throw_if_producer_closed()?;
+        transaction_manager.begin_transaction()
+    }
+
+    /// Sends a list of specified offsets to the consumer group coordinator, and also marks
+    /// those offsets as part of the current transaction. These offsets will be considered
+    /// committed only if the transaction is committed successfully. The committed offset should
+    /// be the next message your application will consume, i.e. lastProcessedMessageOffset + 1.
+    ///
+    /// This method should be used when you need to batch consumed and produced messages
+    /// together, typically in a consume-transform-produce pattern. Thus, the specified
+    /// `group_metadata` should be extracted from the used consumer via `KafkaConsumer::group_metadata`
+    /// to leverage consumer group metadata for stronger fencing than the group id alone.
+    pub fn send_offsets_to_transaction(
+        &self,
+        offsets: HashMap<TopicPartition, OffsetAndMetadata>,
+        group_metadata: ConsumerGroupMetadata,
+    ) -> Result<()> {
+        throw_if_invalid_group_metadata(&group_metadata)?;
+        let transaction_manager = self.throw_if_no_transaction_manager()?;
+        self.throw_if_producer_closed()?;
+        let result = transaction_manager.send_offsets_to_transaction(offsets, group_metadata)?;
+        self.sender.wakeup();
+        result.await_timeout(self.max_block_duration())
+    }
+
+    /// Commits the ongoing transaction. This method will flush any unsent records before actually committing the
+    /// transaction.
+    ///
+    /// Further, if any of the `send` calls which were part of the transaction hit irrecoverable
+    /// errors, this method will return the last received error immediately and the transaction will not be
+    /// committed. So all `send` calls in a transaction must succeed in order for this method to succeed.
+    ///
+    /// Note that this method will fail with `Timeout` if the transaction cannot be committed before expiration
+    /// of `max.block.ms`. Additionally, it will fail with `Interrupt` if interrupted.
+    /// It is safe to retry in either case, but it is not possible to attempt a different operation
+    /// (such as abort_transaction) since the commit may already be in the progress of completing.
+    /// If not retrying, the only option is to close the producer.
+    pub fn commit_transaction(&self) -> Result<()> {
+        let transaction_manager = self.throw_if_no_transaction_manager()?;
+        self.throw_if_producer_closed()?;
+        let result = transaction_manager.begin_commit()?;
+        self.sender.wakeup();
+        result.await_timeout(self.max_block_duration())
+    }
+
+    /// Aborts the ongoing transaction. Any unflushed produce messages will be aborted when this call is made.
+    /// This call will fail immediately if any prior `send` calls failed with a `ProducerFenced` error
+    /// or an instance of `Authorization` errors.
+    ///
+    /// Note that this method will fail with `Timeout` if the transaction cannot be aborted before expiration
+    /// of `max.block.ms`. It is safe to retry, but it is not possible to attempt a different operation
+    /// (such as commit_transaction) since the abort may already be in the progress of completing.
+    /// If not retrying, the only option is to close the producer.
+    pub fn abort_transaction(&self) -> Result<()> {
+        let transaction_manager = self.throw_if_no_transaction_manager()?;
+        self.throw_if_producer_closed()?;
+        self.log
+            .info(format_args!("Aborting incomplete transaction"));
+        let result = transaction_manager.begin_abort()?;
+        self.sender.wakeup();
+        result.await_timeout(self.max_block_duration())
+    }
+
+    /// Asynchronously send a record to a topic and invoke the provided callback when the send has been acknowledged.
+    ///
+    /// The send is asynchronous and this method will return immediately once the record has been stored in the buffer of
+    /// records waiting to be sent. This allows sending many records in parallel without blocking to wait for the
+    /// response after each one.
+    ///
+    /// The result of the send is a `RecordMetadata` specifying the partition the record was sent to, the offset
+    /// it was assigned and the timestamp of the record. The returned `FutureRecordMetadata` can be awaited
+    /// or blocked on with `get`.
+    ///
+    /// Callbacks for records being sent to the same partition are guaranteed to execute in order. They will generally
+    /// execute in the background I/O thread so they should be reasonably fast.
+    ///
+    /// Errors of the send itself (e.g. a record too large, or metadata not available within `max.block.ms`) are
+    /// passed to the callback and the returned future. Only errors caused by misuse of the producer, such as
+    /// sending after close or a failing serializer, are returned directly.
+    pub fn send(
+        &self,
+        record: ProducerRecord<K, V>,
+        callback: Option<Callback>,
+    ) -> Result<FutureRecordMetadata> {
+        let mut callback = callback;
+        match self.do_send(record, &mut callback) {
+            Ok(future) => Ok(future),
+            Err(error) if is_api_error(&error) => {
+                self.log.debug(format_args!(
+                    "Exception occurred during message send: {}",
+                    error
+                ));
+                if let Some(callback) = callback.take() {
+                    callback(Err(error.clone()));
+                }
+                Ok(failed_future(error))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Implementation of asynchronously send a record to a topic.
+    fn do_send(
+        &self,
+        record: ProducerRecord<K, V>,
+        callback: &mut Option<Callback>,
+    ) -> Result<FutureRecordMetadata> {
+        self.throw_if_producer_closed()?;
+        // first make sure the metadata for the topic is available
+        let mut now_ms = self.time.milliseconds();
+        let (cluster, waited_on_metadata_ms) = self.wait_on_metadata(
+            &record.topic,
+            record.partition,
+            now_ms,
+            self.max_block_time_ms,
+        )?;
+        now_ms += waited_on_metadata_ms;
+        let remaining_wait_ms = self.max_block_time_ms.saturating_sub(waited_on_metadata_ms);
+
+        let serialized_key = self
+            .key_serializer
+            .serialize(&record.topic, &record.headers, &record.key)
+            .map_err(|e| {
+                KafkaError::Serialization(format!(
+                    "Can't convert key of record to topic {}: {}",
+                    record.topic, e
+                ))
+            })?;
+        let serialized_value = self
+            .value_serializer
+            .serialize(&record.topic, &record.headers, &record.value)
+            .map_err(|e| {
+                KafkaError::Serialization(format!(
+                    "Can't convert value of record to topic {}: {}",
+                    record.topic, e
+                ))
+            })?;
+        let key = serialized_key.as_deref();
+        let value = serialized_value.as_deref();
+
+        let mut partition = self.partition(&record, key, value, &cluster)?;
+        let mut tp = TopicPartition::new(record.topic.clone(), partition);
+
+        let serialized_size = RECORD_BATCH_OVERHEAD
+            + DefaultRecord::record_size_upper_bound(key, value, &record.headers);
+        self.ensure_valid_record_size(serialized_size)?;
+        let timestamp = record.timestamp.unwrap_or(now_ms as i64);
+        self.log.trace(format_args!(
+            "Attempting to append record to topic {} partition {}",
+            record.topic, partition
+        ));
+        if let Some(transaction_manager) = self.transactional_manager() {
+            transaction_manager.fail_if_not_ready_for_send()?;
+        }
+        let mut result = self.accumulator.append(
+            &tp,
+            timestamp,
+            key,
+            value,
+            &record.headers,
+            callback,
+            remaining_wait_ms,
+            true,
+            now_ms,
+        )?;
+
+        if result.abort_for_new_batch {
+            let prev_partition = partition;
+            self.partitioner
+                .on_new_batch(&record.topic, &cluster, prev_partition);
+            partition = self.partition(&record, key, value, &cluster)?;
+            tp = TopicPartition::new(record.topic.clone(), partition);
+            self.log.trace(format_args!(
+                "Retrying append due to new batch creation for topic {} partition {}. The old partition was {}",
+                record.topic,
+                partition,
+                prev_partition
+            ));
+            result = self.accumulator.append(
+                &tp,
+                timestamp,
+                key,
+                value,
+                &record.headers,
+                callback,
+                remaining_wait_ms,
+                false,
+                now_ms,
+            )?;
+        }
+
+        if let Some(transaction_manager) = self.transactional_manager() {
+            transaction_manager.maybe_add_partition_to_transaction(&tp);
+        }
+
+        if result.batch_is_full || result.new_batch_created {
+            self.log.trace(format_args!(
+                "Waking up the sender since topic {} partition {} is either full or getting a new batch",
+                record.topic,
+                partition
+            ));
+            self.sender.wakeup();
+        }
+        result.future.ok_or_else(|| {
+            KafkaError::IllegalState("Record was not appended to any batch".to_owned())
+        })
+    }
+
+    /// Wait for cluster metadata including partitions for the given topic to be available.
+    ///
+    /// Returns the cluster containing topic metadata and the amount of time we waited in ms
+    fn wait_on_metadata(
+        &self,
+        topic: &str,
+        partition: Option<i32>,
+        now_ms: u128,
+        max_wait_ms: u128,
+    ) -> Result<(Arc<Cluster>, u128)> {
+        // add topic to metadata topic list if it is not there already and reset expiry
+        let mut cluster = self.metadata.fetch();
+        self.metadata.add(topic, now_ms);
+
+        let mut partitions_count = cluster.partition_count_for_topic(topic);
+        // Return cached metadata if we have it, and if the record's partition is either undefined
+        // or within the known partition range
+        if is_partition_known(partitions_count, partition) {
+            return Ok((cluster, 0));
+        }
+
+        let mut remaining_wait_ms = max_wait_ms;
+        let mut elapsed = 0;
+        // Issue metadata requests until we have metadata for the topic and the requested partition,
+        // or until max_wait_ms is exceeded. This is necessary in case the metadata
+        // is stale and the number of partitions for this topic has increased in the meantime.
+        loop {
+            match partition {
+                Some(partition) => self.log.trace(format_args!(
+                    "Requesting metadata update for partition {} of topic {}.",
+                    partition, topic
+                )),
+                None => self.log.trace(format_args!(
+                    "Requesting metadata update for topic {}.",
+                    topic
+                )),
+            }
+            self.metadata.add(topic, now_ms + elapsed);
+            let version = self.metadata.request_update_for_topic(topic);
+            self.sender.wakeup();
+            if let Err(error) = self.metadata.await_update(version, remaining_wait_ms) {
+                return Err(match error {
+                    // Rethrow with original max_wait_ms to prevent logging exception with remaining_wait_ms
+                    KafkaError::Timeout(_) => KafkaError::Timeout(format!(
+                        "Topic {} not present in metadata after {} ms.",
+                        topic, max_wait_ms
+                    )),
+                    error => error,
+                });
+            }
+            cluster = self.metadata.fetch();
+            elapsed = self.time.milliseconds().saturating_sub(now_ms);
+            if elapsed >= max_wait_ms {
+                return Err(KafkaError::Timeout(match partitions_count {
+                    None => format!(
+                        "Topic {} not present in metadata after {} ms.",
+                        topic, max_wait_ms
+                    ),
+                    Some(partitions_count) => format!(
+                        "Partition {} of topic {} with partition count {} is not present in metadata after {} ms.",
+                        partition.unwrap_or_default(),
+                        topic,
+                        partitions_count,
+                        max_wait_ms
+                    ),
+                }));
+            }
+            remaining_wait_ms = max_wait_ms - elapsed;
+            partitions_count = cluster.partition_count_for_topic(topic);
+            if is_partition_known(partitions_count, partition) {
+                return Ok((cluster, elapsed));
+            }
+        }
+    }
+
+    /// Validate that the record size isn't too large
+    fn ensure_valid_record_size(&self, size: usize) -> Result<()> {
+        if size > self.max_request_size {
+            return Err(KafkaError::RecordTooLarge(format!(
+                "The message is {} bytes when serialized which is larger than {}, which is the value of the max.request.size configuration.",
+                size, self.max_request_size
+            )));
+        }
+        if size > self.total_memory_size {
+            return Err(KafkaError::RecordTooLarge(format!(
+                "The message is {} bytes when serialized which is larger than the total memory buffer you have configured with the buffer.memory configuration.",
+                size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Invoking this method makes all buffered records immediately available to send (even if `linger.ms` is
+    /// greater than 0) and blocks on the completion of the requests associated with these records. The post-condition
+    /// of `flush` is that any previously sent record will have completed. A request is considered completed when it
+    /// is successfully acknowledged according to the `acks` configuration you have specified or else it results in
+    /// an error.
+    ///
+    /// Other threads can continue sending records while one thread is blocked waiting for a flush call to complete,
+    /// however no guarantee is made about the completion of records sent after the flush call begins.
+    pub fn flush(&self) {
+        self.log
+            .trace(format_args!("Flushing accumulated records in producer."));
+        self.accumulator.begin_flush();
+        self.sender.wakeup();
+        self.accumulator.await_flush_completion();
+    }
+
+    /// Get the partition metadata for the given topic. This can be used for custom partitioning.
+    ///
+    /// Fails with `Timeout` if metadata could not be refreshed within `max.block.ms`.
+    pub fn partitions_for(&self, topic: &str) -> Result<Vec<PartitionInfo>> {
+        self.throw_if_producer_closed()?;
+        let (cluster, _) = self.wait_on_metadata(
+            topic,
+            None,
+            self.time.milliseconds(),
+            self.max_block_time_ms,
+        )?;
+        Ok(cluster.partitions_for_topic(topic).to_vec())
+    }
+
+    /// This method waits up to `timeout` for the producer to complete the sending of all incomplete requests.
+    ///
+    /// If the producer is unable to complete all requests before the timeout expires, this method will fail
+    /// any unsent and unacknowledged records immediately. It will also abort the ongoing transaction if it's not
+    /// already completing.
+    ///
+    /// If invoked from within a `Callback` this method will not block and will be equivalent to
+    /// `close(Duration::ZERO)`. This is done since no further sending will happen while
+    /// blocking the I/O thread of the producer.
+    pub fn close(&self, timeout: Duration) {
+        let timeout_ms = timeout.as_millis();
+        self.log.info(format_args!(
+            "Closing the Kafka producer with timeoutMillis = {} ms.",
+            timeout_ms
+        ));
+
+        let io_thread = match self.lock_io_thread().take() {
+            Some(io_thread) => io_thread,
+            None => {
+                self.log
+                    .debug(format_args!("Kafka producer has already been closed"));
+                return;
+            }
+        };
+
+        let invoked_from_callback = thread::current().id() == io_thread.handle.thread().id();
+        let mut terminated = false;
+        if timeout_ms > 0 {
+            if invoked_from_callback {
+                self.log.warn(format_args!(
+                    "Overriding close timeout {} ms to 0 ms in order to prevent useless blocking due to self-join. \
+                     This means you have incorrectly invoked close with a non-zero timeout from the producer call-back.",
+                    timeout_ms
+                ));
+            } else {
+                // Try to close gracefully.
+                self.sender.initiate_close();
+                terminated = !matches!(
+                    io_thread.terminated.recv_timeout(timeout),
+                    Err(RecvTimeoutError::Timeout)
+                );
+            }
+        }
+
+        if !terminated {
+            self.log.info(format_args!(
+                "Proceeding to force close the producer since pending requests could not be completed within timeout {} ms.",
+                timeout_ms
+            ));
+            self.sender.force_close();
+        }
+        // Only join the sender thread when not calling from callback.
+        if !invoked_from_callback && io_thread.handle.join().is_err() {
+            self.log.warn(format_args!(
+                "Producer I/O thread panicked before it was closed"
+            ));
+        }
+
+        self.partitioner.close();
+        self.log
+            .debug(format_args!("Kafka producer has been closed"));
+    }
+
+    /// Computes partition for given record.
+    /// if the record has partition returns the value otherwise
+    /// calls configured partitioner class to compute the partition.
+    fn partition(
+        &self,
+        record: &ProducerRecord<K, V>,
+        serialized_key: Option<&[u8]>,
+        serialized_value: Option<&[u8]>,
+        cluster: &Cluster,
+    ) -> Result<i32> {
+        match record.partition {
+            Some(partition) => Ok(partition),
+            None => {
+                self.partitioner
+                    .partition(&record.topic, serialized_key, serialized_value, cluster)
+            }
+        }
+    }
+
+    fn max_block_duration(&self) -> Duration {
+        Duration::from_millis(self.max_block_time_ms.min(u64::MAX as u128) as u64)
+    }
+
+    fn transactional_manager(&self) -> Option<&Arc<TransactionManager>> {
+        self.transaction_manager
+            .as_ref()
+            .filter(|transaction_manager| transaction_manager.is_transactional())
+    }
+
+    fn throw_if_no_transaction_manager(&self) -> Result<&Arc<TransactionManager>> {
+        self.transaction_manager.as_ref().ok_or_else(|| {
+            KafkaError::IllegalState(
+                "Cannot use transactional methods without enabling transactions by setting the transactional.id configuration property"
+                    .to_owned(),
+            )
+        })
+    }
+
+    /// Verify that this producer instance has not been closed.
+    fn throw_if_producer_closed(&self) -> Result<()> {
+        if !self.sender.is_running() {
+            return Err(KafkaError::IllegalState(
+                "Cannot perform operation after producer has been closed".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<K, V> Drop for KafkaProducer<K, V> {
+    fn drop(&mut self) {
+        if self.lock_io_thread().is_some() {
+            self.log.warn(format_args!(
+                "Kafka producer dropped without being closed, unsent records are aborted"
+            ));
+            self.close(Duration::ZERO);
+        }
+    }
+}
+
+/// Instantiates the partitioner named in `partitioner.class`.
+fn partitioner_instance(class_name: &str) -> Result<Arc<dyn Partitioner>> {
+    match class_name.rsplit('.').next().unwrap_or_default() {
+        "DefaultPartitioner" => Ok(Arc::new(DefaultPartitioner::new())),
+        "RoundRobinPartitioner" => Ok(Arc::new(RoundRobinPartitioner::new())),
+        "UniformStickyPartitioner" => Ok(Arc::new(UniformStickyPartitioner::new())),
+        _ => Err(KafkaError::Kafka(format!(
+            "{} is not an instance of org.apache.kafka.clients.producer.Partitioner",
+            class_name
+        ))),
+    }
+}
+
+fn linger_ms(config: &ProducerConfig) -> Result<u128> {
+    Ok(config
+        .get_long(LINGER_MS_CONFIG)?
+        .unwrap_or_default()
+        .clamp(0, i32::MAX as i64) as u128)
+}
+
+fn configure_delivery_timeout(config: &ProducerConfig, log: &Logger) -> Result<u128> {
+    let delivery_timeout_ms = config
+        .get_int(DELIVERY_TIMEOUT_MS_CONFIG)?
+        .unwrap_or_default()
+        .max(0) as u128;
+    let request_timeout_ms = config
+        .get_int(REQUEST_TIMEOUT_MS_CONFIG)?
+        .unwrap_or_default()
+        .max(0) as u128;
+    let linger_and_request_timeout_ms =
+        (linger_ms(config)? + request_timeout_ms).min(i32::MAX as u128);
+
+    if delivery_timeout_ms >= linger_and_request_timeout_ms {
+        return Ok(delivery_timeout_ms);
+    }
+    if config.originals().contains_key(DELIVERY_TIMEOUT_MS_CONFIG) {
+        // fail if the user explicitly set an inconsistent value
+        return Err(KafkaError::Config(format!(
+            "{} should be equal to or larger than {} + {}",
+            DELIVERY_TIMEOUT_MS_CONFIG, LINGER_MS_CONFIG, REQUEST_TIMEOUT_MS_CONFIG
+        )));
+    }
+    // override the default to linger.ms + request.timeout.ms for backward compatibility
+    log.warn(format_args!(
+        "{} should be equal to or larger than {} + {}. Setting it to {}.",
+        DELIVERY_TIMEOUT_MS_CONFIG,
+        LINGER_MS_CONFIG,
+        REQUEST_TIMEOUT_MS_CONFIG,
+        linger_and_request_timeout_ms
+    ));
+    Ok(linger_and_request_timeout_ms)
+}
+
+fn configure_transaction_state(
+    config: &ProducerConfig,
+    log_context: &LogContext,
+    retry_backoff_ms: u128,
+    api_versions: Arc<ApiVersions>,
+) -> Result<Option<Arc<TransactionManager>>> {
+    let log = log_context.logger(module_path!());
+    let user_configured_idempotence = config.originals().contains_key(ENABLE_IDEMPOTENCE_CONFIG);
+    let user_configured_transactions = config.originals().contains_key(TRANSACTIONAL_ID_CONFIG);
+    if user_configured_transactions && !user_configured_idempotence {
+        log.info(format_args!(
+            "Overriding the default {} to true since {} is specified.",
+            ENABLE_IDEMPOTENCE_CONFIG, TRANSACTIONAL_ID_CONFIG
+        ));
+    }
+    if !config.idempotence_enabled()? {
+        return Ok(None);
+    }
+    let transaction_manager = TransactionManager::new(
+        config.get_string(TRANSACTIONAL_ID_CONFIG)?,
+        config
+            .get_int(TRANSACTION_TIMEOUT_CONFIG)?
+            .unwrap_or_default(),
+        retry_backoff_ms,
+        api_versions,
+        log_context,
+    );
+    if transaction_manager.is_transactional() {
+        log.info(format_args!("Instantiated a transactional producer."));
+    } else {
+        log.info(format_args!("Instantiated an idempotent producer."));
+    }
+    Ok(Some(Arc::new(transaction_manager)))
+}
+
+fn configure_inflight_requests(config: &ProducerConfig) -> Result<usize> {
+    let max_in_flight_requests = config
+        .get_int(MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION)?
+        .unwrap_or_default();
+    if config.idempotence_enabled()? && max_in_flight_requests > 5 {
+        return Err(KafkaError::Config(format!(
+            "Must set {} to at most 5 to use the idempotent producer.",
+            MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION
+        )));
+    }
+    Ok(max_in_flight_requests.max(1) as usize)
+}
+
+fn configure_acks(config: &ProducerConfig, log: &Logger) -> Result<i16> {
+    let user_configured_acks = config.originals().contains_key(ACKS_CONFIG);
+    let acks = match config.get_string(ACKS_CONFIG)?.as_deref() {
+        Some("all") | Some("-1") => -1,
+        Some("0") => 0,
+        _ => 1,
+    };
+    if config.idempotence_enabled()? {
+        if !user_configured_acks {
+            log.info(format_args!(
+                "Overriding the default {} to all since idempotence is enabled.",
+                ACKS_CONFIG
+            ));
+            return Ok(-1);
+        }
+        if acks != -1 {
+            return Err(KafkaError::Config(format!(
+                "Must set {} to all in order to use the idempotent producer. Otherwise we cannot guarantee idempotence.",
+                ACKS_CONFIG
+            )));
+        }
+    }
+    Ok(acks)
+}
+
+fn is_partition_known(partitions_count: Option<usize>, partition: Option<i32>) -> bool {
+    match (partitions_count, partition) {
+        (Some(_), None) => true,
+        (Some(partitions_count), Some(partition)) => (partition as usize) < partitions_count,
+        (None, _) => false,
+    }
+}
+
+fn throw_if_invalid_group_metadata(group_metadata: &ConsumerGroupMetadata) -> Result<()> {
+    if group_metadata.generation_id > 0 && group_metadata.member_id == UNKNOWN_MEMBER_ID {
+        return Err(KafkaError::IllegalArgument(format!(
+            "Passed in group metadata {} has generationId > 0 but member.id ",
+            group_metadata
+        )));
+    }
+    Ok(())
+}
+
+/// Errors of a single send which are reported through the callback and the returned future, rather
+/// than returned from `send` (`ApiException` subclasses).
+fn is_api_error(error: &KafkaError) -> bool {
+    !matches!(
+        error,
+        KafkaError::Kafka(_)
+            | KafkaError::ConcurrentModification(_)
+            | KafkaError::IllegalArgument(_)
+            | KafkaError::IllegalState(_)
+            | KafkaError::Interrupt(_)
+            | KafkaError::Serialization(_)
+    )
+}
+
+/// A future which is already completed with the given error.
+fn failed_future(error: KafkaError) -> FutureRecordMetadata {
+    let result = Arc::new(ProduceRequestResult::new(TopicPartition::new(
+        String::new(),
+        -1,
+    )));
+    let errors_by_index: ErrorsByIndex = Arc::new(move |_| error.clone());
+    result.set(-1, NO_TIMESTAMP, Some(errors_by_index));
+    // set was invoked above, so done cannot fail
+    let _ = result.done();
+    FutureRecordMetadata::new(result, 0, NO_TIMESTAMP, -1, -1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use indexmap::IndexMap;
+
+    use crate::common::{
+        errors::KafkaError, serialization::string_serializer::StringSerializer,
+        utils::log_context::LogContext,
+    };
+
+    use super::{
+        super::producer_config::{
+            ProducerConfig, ACKS_CONFIG, DELIVERY_TIMEOUT_MS_CONFIG, ENABLE_IDEMPOTENCE_CONFIG,
+            KEY_SERIALIZER_CLASS_CONFIG, LINGER_MS_CONFIG, MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
+            PARTITIONER_CLASS_CONFIG, TRANSACTIONAL_ID_CONFIG, VALUE_SERIALIZER_CLASS_CONFIG,
+        },
+        configure_delivery_timeout, KafkaProducer, BOOTSTRAP_SERVERS_CONFIG,
+    };
+
+    const STRING_SERIALIZER: &str = "org.apache.kafka.common.serialization.StringSerializer";
+
+    fn config(extra: &[(&str, &str)]) -> ProducerConfig {
+        let mut props: IndexMap<String, String> = [
+            (BOOTSTRAP_SERVERS_CONFIG, "localhost:9092"),
+            (KEY_SERIALIZER_CLASS_CONFIG, STRING_SERIALIZER),
+            (VALUE_SERIALIZER_CLASS_CONFIG, STRING_SERIALIZER),
+        ]
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        for (key, value) in extra {
+            props.insert(key.to_string(), value.to_string());
+        }
+        ProducerConfig::new(props).unwrap()
+    }
+
+    fn producer(
+        config: ProducerConfig,
+    ) -> Result<KafkaProducer<Option<String>, Option<String>>, KafkaError> {
+        KafkaProducer::from_config(
+            config,
+            Box::new(StringSerializer),
+            Box::new(StringSerializer),
+        )
+    }
+
+    fn assert_config_error(
+        result: Result<KafkaProducer<Option<String>, Option<String>>, KafkaError>,
+        expected: &str,
+    ) {
+        match result {
+            Err(KafkaError::Config(message)) => assert_eq!(message, expected),
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn from_config_without_transactional_id_has_no_transaction_manager() {
+        let producer = producer(config(&[(
+            PARTITIONER_CLASS_CONFIG,
+            "org.apache.kafka.clients.producer.RoundRobinPartitioner",
+        )]))
+        .unwrap();
+        assert!(producer.client_id().starts_with("producer-"));
+        assert!(producer.transaction_manager.is_none());
+        assert!(matches!(
+            producer.begin_transaction(),
+            Err(KafkaError::IllegalState(_))
+        ));
+        producer.close(Duration::ZERO);
+    }
+
+    #[test]
+    fn from_config_with_transactional_id_creates_a_transaction_manager() {
+        let producer = producer(config(&[(TRANSACTIONAL_ID_CONFIG, "txn")])).unwrap();
+        assert_eq!(producer.client_id(), "producer-txn");
+        let transaction_manager = producer.transaction_manager.as_ref().unwrap();
+        assert_eq!(transaction_manager.transactional_id(), Some("txn"));
+        producer.close(Duration::ZERO);
+    }
+
+    #[test]
+    fn from_config_rejects_unknown_partitioners() {
+        let result = producer(config(&[(
+            PARTITIONER_CLASS_CONFIG,
+            "com.example.CustomPartitioner",
+        )]));
+        match result {
+            Err(KafkaError::Kafka(message)) => assert_eq!(
+                message,
+                "com.example.CustomPartitioner is not an instance of org.apache.kafka.clients.producer.Partitioner"
+            ),
+            other => panic!("unexpected result {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn idempotence_requires_acks_all_and_at_most_five_requests_in_flight() {
+        assert_config_error(
+            producer(config(&[(ENABLE_IDEMPOTENCE_CONFIG, "true"), (ACKS_CONFIG, "1")])),
+            "Must set acks to all in order to use the idempotent producer. Otherwise we cannot guarantee idempotence.",
+        );
+        assert_config_error(
+            producer(config(&[
+                (ENABLE_IDEMPOTENCE_CONFIG, "true"),
+                (MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION, "6"),
+            ])),
+            "Must set max.in.flight.requests.per.connection to at most 5 to use the idempotent producer.",
+        );
+    }
+
+    #[test]
+    fn delivery_timeout_is_raised_to_linger_plus_request_timeout_unless_configured() {
+        let log = LogContext::new("").logger(module_path!());
+        let defaulted = config(&[(LINGER_MS_CONFIG, "120000")]);
+        assert_eq!(
+            configure_delivery_timeout(&defaulted, &log).unwrap(),
+            150_000
+        );
+
+        assert_config_error(
+            producer(config(&[
+                (LINGER_MS_CONFIG, "120000"),
+                (DELIVERY_TIMEOUT_MS_CONFIG, "120000"),
+            ])),
+            "delivery.timeout.ms should be equal to or larger than linger.ms + request.timeout.ms",
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod callback;
+pub mod internals;
+pub mod kafka_producer;
+pub mod partitioner;
+pub mod producer_config;
+pub mod producer_record;
+pub mod record_metadata;
+pub mod round_robin_partitioner;
+pub mod uniform_sticky_partitioner;
@@ -0,0 +1,32 @@
+use crate::common::{cluster::Cluster, errors::Result};
+
+/// Partitioner Interface
+///
+/// Unlike the Java interface only the serialized key and value are passed in, as the native
+/// producer never sees the objects they were serialized from.
+pub trait Partitioner: Send + Sync {
+    /// Compute the partition for the given record.
+    ///
+    /// * `topic` - The topic name
+    /// * `key` - The serialized key to partition on (or `None` if no key)
+    /// * `value` - The serialized value to partition on or `None`
+    /// * `cluster` - The current cluster metadata
+    fn partition(
+        &self,
+        topic: &str,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        cluster: &Cluster,
+    ) -> Result<i32>;
+
+    /// This is called when partitioner is closed.
+    fn close(&self) {}
+
+    /// Notifies the partitioner a new batch is about to be created. When using the sticky partitioner,
+    /// this method can change the chosen sticky partition for the new batch.
+    ///
+    /// * `topic` - The topic name
+    /// * `cluster` - The current cluster metadata
+    /// * `prev_partition` - The partition previously selected for the record that triggered a new batch
+    fn on_new_batch(&self, _topic: &str, _cluster: &Cluster, _prev_partition: i32) {}
+}
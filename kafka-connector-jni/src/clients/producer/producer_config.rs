@@ -0,0 +1,514 @@
+use std::{
+    collections::HashSet,
+    ops::Deref,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    clients::common_client_configs::{self, *},
+    common::{
+        config::{
+            abstract_config::AbstractConfig,
+            config_def::{
+                CaseInsensitiveValidString, ConfigDef, ConfigValue, DefaultValue, Importance,
+                NonEmptyString, NonNullValidator, Range, Type, ValidString,
+            },
+        },
+        errors::{KafkaError, Result},
+    },
+};
+
+pub const BATCH_SIZE_CONFIG: &str = "batch.size";
+const BATCH_SIZE_DOC: &str = "The producer will attempt to batch records together into fewer requests whenever multiple records are being sent \
+    to the same partition. This helps performance on both the client and the server. This configuration controls the \
+    default batch size in bytes. \
+    No attempt will be made to batch records larger than this size. \
+    Requests sent to brokers will contain multiple batches, one for each partition with data available to be sent. \
+    A small batch size will make batching less common and may reduce throughput (a batch size of zero will disable \
+    batching entirely). A very large batch size may use memory a bit more wastefully as we will always allocate a \
+    buffer of the specified batch size in anticipation of additional records.";
+
+pub const ACKS_CONFIG: &str = "acks";
+const ACKS_DOC: &str = "The number of acknowledgments the producer requires the leader to have received before considering a request complete. \
+    This controls the durability of records that are sent. The following settings are allowed: \
+    <code>acks=0</code> the producer will not wait for any acknowledgment from the server at all. \
+    <code>acks=1</code> the leader will write the record to its local log but will respond without awaiting full acknowledgement from all followers. \
+    <code>acks=all</code> the leader will wait for the full set of in-sync replicas to acknowledge the record. \
+    This is equivalent to the acks=-1 setting.";
+
+pub const LINGER_MS_CONFIG: &str = "linger.ms";
+const LINGER_MS_DOC: &str = "The producer groups together any records that arrive in between request transmissions into a single batched request. \
+    This setting accomplishes this by adding a small amount of artificial delay&mdash;that is, rather than immediately sending out a record, \
+    the producer will wait for up to the given delay to allow other records to be sent so that the sends can be batched together. \
+    This setting gives the upper bound on the delay for batching: once we get <code>batch.size</code> worth of records for a partition \
+    it will be sent immediately regardless of this setting, however if we have fewer than this many bytes accumulated for this partition \
+    we will 'linger' for the specified time waiting for more records to show up. This setting defaults to 0 (i.e. no delay).";
+
+pub const DELIVERY_TIMEOUT_MS_CONFIG: &str = "delivery.timeout.ms";
+const DELIVERY_TIMEOUT_MS_DOC: &str = "An upper bound on the time to report success or failure after a call to <code>send()</code> returns. \
+    This limits the total time that a record will be delayed prior to sending, the time to await acknowledgement from the broker (if expected), \
+    and the time allowed for retriable send failures. The value of this config should be greater than or equal to the sum of \
+    <code>request.timeout.ms</code> and <code>linger.ms</code>.";
+
+pub const MAX_REQUEST_SIZE_CONFIG: &str = "max.request.size";
+const MAX_REQUEST_SIZE_DOC: &str =
+    "The maximum size of a request in bytes. This setting will limit the number of record \
+    batches the producer will send in a single request to avoid sending huge requests. \
+    This is also effectively a cap on the maximum uncompressed record batch size.";
+
+pub const MAX_BLOCK_MS_CONFIG: &str = "max.block.ms";
+const MAX_BLOCK_MS_DOC: &str = "The configuration controls how long the <code>send()</code>, <code>partitionsFor()</code>, \
+    <code>initTransactions()</code>, <code>sendOffsetsToTransaction()</code>, <code>commitTransaction()</code> \
+    and <code>abortTransaction()</code> methods will block. \
+    For <code>send()</code> this timeout bounds the total time waiting for both metadata fetch and buffer allocation \
+    (blocking in the user-supplied serializers or partitioner is not counted against this timeout).";
+
+pub const BUFFER_MEMORY_CONFIG: &str = "buffer.memory";
+const BUFFER_MEMORY_DOC: &str = "The total bytes of memory the producer can use to buffer records waiting to be sent to the server. \
+    If records are sent faster than they can be delivered to the server the producer will block for <code>max.block.ms</code> \
+    after which it will throw an exception.";
+
+pub const COMPRESSION_TYPE_CONFIG: &str = "compression.type";
+const COMPRESSION_TYPE_DOC: &str = "The compression type for all data generated by the producer. The default is none (i.e. no compression). \
+    Valid  values are <code>none</code>, <code>gzip</code>, <code>snappy</code>, <code>lz4</code>, or <code>zstd</code>. \
+    Compression is of full batches of data, so the efficacy of batching will also impact the compression ratio \
+    (more batching means better compression).";
+
+pub const METADATA_MAX_IDLE_CONFIG: &str = "metadata.max.idle.ms";
+const METADATA_MAX_IDLE_DOC: &str = "Controls how long the producer will cache metadata for a topic that's idle. If the elapsed \
+    time since a topic was last produced to exceeds the metadata idle duration, then the topic's metadata is forgotten \
+    and the next access to it will force a metadata fetch request.";
+
+pub const MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION: &str = "max.in.flight.requests.per.connection";
+const MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION_DOC: &str = "The maximum number of unacknowledged requests the client will send on a single connection before blocking. \
+    Note that if this setting is set to be greater than 1 and there are failed sends, there is a risk of message re-ordering due to retries \
+    (i.e., if retries are enabled).";
+
+pub const KEY_SERIALIZER_CLASS_CONFIG: &str = "key.serializer";
+pub const KEY_SERIALIZER_CLASS_DOC: &str = "Serializer class for key that implements the <code>org.apache.kafka.common.serialization.Serializer</code> interface.";
+
+pub const VALUE_SERIALIZER_CLASS_CONFIG: &str = "value.serializer";
+pub const VALUE_SERIALIZER_CLASS_DOC: &str = "Serializer class for value that implements the <code>org.apache.kafka.common.serialization.Serializer</code> interface.";
+
+pub const PARTITIONER_CLASS_CONFIG: &str = "partitioner.class";
+const PARTITIONER_CLASS_DOC: &str = "Partitioner class that implements the <code>org.apache.kafka.clients.producer.Partitioner</code> interface.";
+
+pub const INTERCEPTOR_CLASSES_CONFIG: &str = "interceptor.classes";
+pub const INTERCEPTOR_CLASSES_DOC: &str = "A list of classes to use as interceptors. \
+    Implementing the <code>org.apache.kafka.clients.producer.ProducerInterceptor</code> interface allows you to intercept (and possibly mutate) the records \
+    received by the producer before they are published to the Kafka cluster. By default, there are no interceptors.";
+
+pub const ENABLE_IDEMPOTENCE_CONFIG: &str = "enable.idempotence";
+pub const ENABLE_IDEMPOTENCE_DOC: &str = "When set to 'true', the producer will ensure that exactly one copy of each message is written in the stream. If 'false', producer \
+    retries due to broker failures, etc., may write duplicates of the retried message in the stream. \
+    Note that enabling idempotence requires <code>max.in.flight.requests.per.connection</code> to be less than or equal to 5, \
+    <code>retries</code> to be greater than 0 and <code>acks</code> must be 'all'. If these values \
+    are not explicitly set by the user, suitable values will be chosen. If incompatible values are set, \
+    a <code>ConfigException</code> will be thrown.";
+
+pub const TRANSACTION_TIMEOUT_CONFIG: &str = "transaction.timeout.ms";
+pub const TRANSACTION_TIMEOUT_DOC: &str = "The maximum amount of time in ms that the transaction coordinator will wait for a transaction status update from the producer before proactively aborting the ongoing transaction.\
+    If this value is larger than the transaction.max.timeout.ms setting in the broker, the request will fail with a <code>InvalidTxnTimeoutException</code> error.";
+
+pub const TRANSACTIONAL_ID_CONFIG: &str = "transactional.id";
+pub const TRANSACTIONAL_ID_DOC: &str = "The TransactionalId to use for transactional delivery. This enables reliability semantics which span multiple producer sessions since it allows the client to guarantee that transactions using the same TransactionalId have been completed prior to starting any new transactions. If no TransactionalId is provided, then the producer is limited to idempotent delivery. \
+    If a TransactionalId is configured, <code>enable.idempotence</code> is implied. \
+    By default the TransactionId is not configured, which means transactions cannot be used. \
+    Note that, by default, transactions require a cluster of at least three brokers which is the recommended setting for production; for development you can change this, by adjusting broker setting <code>transaction.state.log.replication.factor</code>.";
+
+/// Internal config to allow the transactional producer to downgrade the `TxnOffsetCommitRequest`
+/// when the broker doesn't support the group metadata.
+pub const AUTO_DOWNGRADE_TXN_COMMIT: &str = "internal.auto.downgrade.txn.commit";
+
+const DEFAULT_PARTITIONER_CLASS: &str =
+    "org.apache.kafka.clients.producer.internals.DefaultPartitioner";
+
+static PRODUCER_CLIENT_ID_SEQUENCE: AtomicUsize = AtomicUsize::new(1);
+
+/// Configuration for the Kafka Producer.
+pub struct ProducerConfig {
+    config: AbstractConfig,
+}
+
+impl ProducerConfig {
+    /// Parses and validates `props`, filling in defaults and the generated `client.id`.
+    pub fn new(props: IndexMap<String, String>) -> Result<ProducerConfig> {
+        let config = AbstractConfig::new(
+            &ProducerConfig::config_def()?,
+            props,
+            "ProducerConfig",
+            true,
+            post_process_parsed_config,
+        )?;
+        Ok(ProducerConfig { config })
+    }
+
+    pub fn config_def() -> Result<ConfigDef> {
+        ConfigDef::new()
+            .define_with_validator(
+                BOOTSTRAP_SERVERS_CONFIG,
+                Type::List,
+                Vec::<String>::new(),
+                NonNullValidator,
+                Importance::High,
+                BOOTSTRAP_SERVERS_DOC,
+            )?
+            .define_with_validator(
+                CLIENT_DNS_LOOKUP_CONFIG,
+                Type::String,
+                USE_ALL_DNS_IPS,
+                ValidString::one_of(&[USE_ALL_DNS_IPS, RESOLVE_CANONICAL_BOOTSTRAP_SERVERS_ONLY]),
+                Importance::Medium,
+                CLIENT_DNS_LOOKUP_DOC,
+            )?
+            .define_with_validator(
+                BUFFER_MEMORY_CONFIG,
+                Type::Long,
+                32i64 * 1024 * 1024,
+                Range::at_least(0i64),
+                Importance::High,
+                BUFFER_MEMORY_DOC,
+            )?
+            .define_with_validator(
+                RETRIES_CONFIG,
+                Type::Int,
+                i32::MAX,
+                Range::between(0, i32::MAX),
+                Importance::High,
+                RETRIES_DOC,
+            )?
+            .define_with_validator(
+                ACKS_CONFIG,
+                Type::String,
+                "1",
+                ValidString::one_of(&["all", "-1", "0", "1"]),
+                Importance::High,
+                ACKS_DOC,
+            )?
+            .define(
+                COMPRESSION_TYPE_CONFIG,
+                Type::String,
+                "none",
+                Importance::High,
+                COMPRESSION_TYPE_DOC,
+            )?
+            .define_with_validator(
+                BATCH_SIZE_CONFIG,
+                Type::Int,
+                16384,
+                Range::at_least(0),
+                Importance::Medium,
+                BATCH_SIZE_DOC,
+            )?
+            .define_with_validator(
+                LINGER_MS_CONFIG,
+                Type::Long,
+                0i64,
+                Range::at_least(0),
+                Importance::Medium,
+                LINGER_MS_DOC,
+            )?
+            .define_with_validator(
+                DELIVERY_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                120 * 1000,
+                Range::at_least(0),
+                Importance::Medium,
+                DELIVERY_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                CLIENT_ID_CONFIG,
+                Type::String,
+                "",
+                Importance::Medium,
+                CLIENT_ID_DOC,
+            )?
+            .define_with_validator(
+                SEND_BUFFER_CONFIG,
+                Type::Int,
+                128 * 1024,
+                Range::at_least(SEND_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                SEND_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                RECEIVE_BUFFER_CONFIG,
+                Type::Int,
+                32 * 1024,
+                Range::at_least(RECEIVE_BUFFER_LOWER_BOUND),
+                Importance::Medium,
+                RECEIVE_BUFFER_DOC,
+            )?
+            .define_with_validator(
+                MAX_REQUEST_SIZE_CONFIG,
+                Type::Int,
+                1024 * 1024,
+                Range::at_least(0),
+                Importance::Medium,
+                MAX_REQUEST_SIZE_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MS_CONFIG,
+                Type::Long,
+                50i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                RECONNECT_BACKOFF_MAX_MS_CONFIG,
+                Type::Long,
+                1000i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RECONNECT_BACKOFF_MAX_MS_DOC,
+            )?
+            .define_with_validator(
+                RETRY_BACKOFF_MS_CONFIG,
+                Type::Long,
+                100i64,
+                Range::at_least(0i64),
+                Importance::Low,
+                RETRY_BACKOFF_MS_DOC,
+            )?
+            .define_with_validator(
+                MAX_BLOCK_MS_CONFIG,
+                Type::Long,
+                60 * 1000i64,
+                Range::at_least(0),
+                Importance::Medium,
+                MAX_BLOCK_MS_DOC,
+            )?
+            .define_with_validator(
+                REQUEST_TIMEOUT_MS_CONFIG,
+                Type::Int,
+                30 * 1000,
+                Range::at_least(0),
+                Importance::Medium,
+                REQUEST_TIMEOUT_MS_DOC,
+            )?
+            .define_with_validator(
+                METADATA_MAX_AGE_CONFIG,
+                Type::Long,
+                5 * 60 * 1000i64,
+                Range::at_least(0),
+                Importance::Low,
+                METADATA_MAX_AGE_DOC,
+            )?
+            .define_with_validator(
+                METADATA_MAX_IDLE_CONFIG,
+                Type::Long,
+                5 * 60 * 1000i64,
+                Range::at_least(5000),
+                Importance::Low,
+                METADATA_MAX_IDLE_DOC,
+            )?
+            .define_with_validator(
+                METRICS_SAMPLE_WINDOW_MS_CONFIG,
+                Type::Long,
+                30000i64,
+                Range::at_least(0),
+                Importance::Low,
+                METRICS_SAMPLE_WINDOW_MS_DOC,
+            )?
+            .define_with_validator(
+                METRICS_NUM_SAMPLES_CONFIG,
+                Type::Int,
+                2,
+                Range::at_least(1),
+                Importance::Low,
+                METRICS_NUM_SAMPLES_DOC,
+            )?
+            .define_with_validator(
+                METRICS_RECORDING_LEVEL_CONFIG,
+                Type::String,
+                "INFO",
+                ValidString::one_of(&["INFO", "DEBUG", "TRACE"]),
+                Importance::Low,
+                METRICS_RECORDING_LEVEL_DOC,
+            )?
+            .define(
+                METRIC_REPORTER_CLASSES_CONFIG,
+                Type::List,
+                "",
+                Importance::Low,
+                METRIC_REPORTER_CLASSES_DOC,
+            )?
+            .define_with_validator(
+                MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION,
+                Type::Int,
+                5,
+                Range::at_least(1),
+                Importance::Low,
+                MAX_IN_FLIGHT_REQUESTS_PER_CONNECTION_DOC,
+            )?
+            .define(
+                KEY_SERIALIZER_CLASS_CONFIG,
+                Type::Class,
+                DefaultValue::NoDefault,
+                Importance::High,
+                KEY_SERIALIZER_CLASS_DOC,
+            )?
+            .define(
+                VALUE_SERIALIZER_CLASS_CONFIG,
+                Type::Class,
+                DefaultValue::NoDefault,
+                Importance::High,
+                VALUE_SERIALIZER_CLASS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MS_DOC,
+            )?
+            .define(
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_CONFIG,
+                Type::Long,
+                DEFAULT_SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS,
+                Importance::Medium,
+                SOCKET_CONNECTION_SETUP_TIMEOUT_MAX_MS_DOC,
+            )?
+            // default is set to be a bit lower than the server default (10 min), to avoid both client and server closing connection at same time
+            .define(
+                CONNECTIONS_MAX_IDLE_MS_CONFIG,
+                Type::Long,
+                9 * 60 * 1000i64,
+                Importance::Medium,
+                CONNECTIONS_MAX_IDLE_MS_DOC,
+            )?
+            .define(
+                PARTITIONER_CLASS_CONFIG,
+                Type::Class,
+                ConfigValue::Class(DEFAULT_PARTITIONER_CLASS.to_owned()),
+                Importance::Medium,
+                PARTITIONER_CLASS_DOC,
+            )?
+            .define(
+                INTERCEPTOR_CLASSES_CONFIG,
+                Type::List,
+                "",
+                Importance::Low,
+                INTERCEPTOR_CLASSES_DOC,
+            )?
+            .define_with_validator(
+                SECURITY_PROTOCOL_CONFIG,
+                Type::String,
+                DEFAULT_SECURITY_PROTOCOL,
+                CaseInsensitiveValidString::one_of(&SECURITY_PROTOCOLS),
+                Importance::Medium,
+                SECURITY_PROTOCOL_DOC,
+            )?
+            .with_client_ssl_support()?
+            .with_client_sasl_support()?
+            .define(
+                ENABLE_IDEMPOTENCE_CONFIG,
+                Type::Boolean,
+                false,
+                Importance::Low,
+                ENABLE_IDEMPOTENCE_DOC,
+            )?
+            .define_with_validator(
+                TRANSACTION_TIMEOUT_CONFIG,
+                Type::Int,
+                60000,
+                Range::at_least(1),
+                Importance::Low,
+                TRANSACTION_TIMEOUT_DOC,
+            )?
+            .define_with_validator(
+                TRANSACTIONAL_ID_CONFIG,
+                Type::String,
+                DefaultValue::Null,
+                NonEmptyString,
+                Importance::Low,
+                TRANSACTIONAL_ID_DOC,
+            )?
+            .define_internal(
+                AUTO_DOWNGRADE_TXN_COMMIT,
+                Type::Boolean,
+                false,
+                Importance::Low,
+            )
+    }
+
+    pub fn config_names() -> Result<HashSet<String>> {
+        Ok(ProducerConfig::config_def()?.names())
+    }
+
+    /// Whether the producer is idempotent, which is implied by setting `transactional.id`.
+    ///
+    /// Fails with `Config` if idempotence is explicitly disabled for a transactional producer.
+    pub fn idempotence_enabled(&self) -> Result<bool> {
+        let user_configured_idempotence = self.originals().contains_key(ENABLE_IDEMPOTENCE_CONFIG);
+        let user_configured_transactions = self.originals().contains_key(TRANSACTIONAL_ID_CONFIG);
+        let idempotence_enabled = user_configured_idempotence
+            && self.get_boolean(ENABLE_IDEMPOTENCE_CONFIG)? == Some(true);
+
+        if !idempotence_enabled && user_configured_idempotence && user_configured_transactions {
+            return Err(KafkaError::Config(format!(
+                "Cannot set a {} without also enabling idempotence.",
+                TRANSACTIONAL_ID_CONFIG
+            )));
+        }
+        Ok(user_configured_transactions || idempotence_enabled)
+    }
+}
+
+impl Deref for ProducerConfig {
+    type Target = AbstractConfig;
+
+    fn deref(&self) -> &Self::Target {
+        &self.config
+    }
+}
+
+fn post_process_parsed_config(
+    config: &AbstractConfig,
+) -> Result<IndexMap<String, Option<ConfigValue>>> {
+    let mut refined_configs =
+        common_client_configs::post_process_reconnect_backoff_configs(config)?;
+    maybe_override_enable_idempotence(config, &mut refined_configs);
+    maybe_override_client_id(config, &mut refined_configs)?;
+    Ok(refined_configs)
+}
+
+fn maybe_override_enable_idempotence(
+    config: &AbstractConfig,
+    configs: &mut IndexMap<String, Option<ConfigValue>>,
+) {
+    let user_configured_idempotence = config.originals().contains_key(ENABLE_IDEMPOTENCE_CONFIG);
+    let user_configured_transactions = config.originals().contains_key(TRANSACTIONAL_ID_CONFIG);
+
+    if !user_configured_idempotence && user_configured_transactions {
+        configs.insert(
+            ENABLE_IDEMPOTENCE_CONFIG.to_owned(),
+            Some(ConfigValue::Boolean(true)),
+        );
+    }
+}
+
+fn maybe_override_client_id(
+    config: &AbstractConfig,
+    configs: &mut IndexMap<String, Option<ConfigValue>>,
+) -> Result<()> {
+    let refined_client_id = if config.originals().contains_key(CLIENT_ID_CONFIG) {
+        config.get_string(CLIENT_ID_CONFIG)?.unwrap_or_default()
+    } else {
+        match config.get_string(TRANSACTIONAL_ID_CONFIG)? {
+            Some(transactional_id) => format!("producer-{}", transactional_id),
+            None => format!(
+                "producer-{}",
+                PRODUCER_CLIENT_ID_SEQUENCE.fetch_add(1, Ordering::SeqCst)
+            ),
+        }
+    };
+    configs.insert(
+        CLIENT_ID_CONFIG.to_owned(),
+        Some(ConfigValue::String(refined_client_id)),
+    );
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::common::{
+    errors::{KafkaError, Result},
+    header::internals::record_headers::RecordHeaders,
+};
+
+/// A key/value pair to be sent to Kafka. This consists of a topic name to which the record is being sent, an optional
+/// partition number, and an optional key and value.
+///
+/// If a valid partition number is specified that partition will be used when sending the record. If no partition is
+/// specified but a key is present a partition will be chosen using a hash of the key. If neither key nor partition is
+/// present a partition will be assigned in a round-robin fashion.
+///
+/// The record also has an associated timestamp. If the user did not provide a timestamp, the producer will stamp the
+/// record with its current time. The timestamp eventually used by Kafka depends on the timestamp type configured for
+/// the topic.
+#[derive(Debug, Clone)]
+pub struct ProducerRecord<K, V> {
+    pub topic: String,
+    pub partition: Option<i32>,
+    pub headers: RecordHeaders,
+    pub key: K,
+    pub value: V,
+    pub timestamp: Option<i64>,
+}
+
+impl<K, V> ProducerRecord<K, V> {
+    /// Creates a record with a specified timestamp to be sent to a specified topic and partition
+    ///
+    /// * `topic` - The topic the record will be appended to
+    /// * `partition` - The partition to which the record should be sent
+    /// * `timestamp` - The timestamp of the record, in milliseconds since epoch. If `None`, the producer will assign
+    ///   the timestamp using the current time.
+    /// * `key` - The key that will be included in the record
+    /// * `value` - The record contents
+    /// * `headers` - the headers that will be included in the record
+    pub fn new(
+        topic: impl Into<String>,
+        partition: Option<i32>,
+        timestamp: Option<i64>,
+        key: K,
+        value: V,
+        headers: Option<RecordHeaders>,
+    ) -> Result<ProducerRecord<K, V>> {
+        if let Some(timestamp) = timestamp.filter(|timestamp| *timestamp < 0) {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Invalid timestamp: {}. Timestamp should always be non-negative or null.",
+                timestamp
+            )));
+        }
+        if let Some(partition) = partition.filter(|partition| *partition < 0) {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Invalid partition: {}. Partition number should always be non-negative or null.",
+                partition
+            )));
+        }
+        Ok(ProducerRecord {
+            topic: topic.into(),
+            partition,
+            headers: headers.unwrap_or_default(),
+            key,
+            value,
+            timestamp,
+        })
+    }
+
+    /// Create a record to be sent to Kafka
+    pub fn with_key(topic: impl Into<String>, key: K, value: V) -> ProducerRecord<K, V> {
+        ProducerRecord {
+            topic: topic.into(),
+            partition: None,
+            headers: RecordHeaders::default(),
+            key,
+            value,
+            timestamp: None,
+        }
+    }
+}
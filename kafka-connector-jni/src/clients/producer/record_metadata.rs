@@ -0,0 +1,93 @@
+use jni::{objects::JValue, JNIEnv};
+
+use crate::{
+    clone_to_java::{kind, CloneToJava},
+    common::{record::record_batch::NO_TIMESTAMP, topic_partition::TopicPartition},
+    jni_cache,
+};
+
+/// Partition value for record without partition assigned
+pub const UNKNOWN_PARTITION: i32 = -1;
+
+const INVALID_OFFSET: i64 = -1;
+
+/// The metadata for a record that has been acknowledged by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordMetadata {
+    pub offset: i64,
+    /// The timestamp of the message.
+    /// If LogAppendTime is used for the topic, the timestamp will be the timestamp returned by the broker.
+    /// If CreateTime is used for the topic, the timestamp is the timestamp in the corresponding ProducerRecord if the
+    /// user provided one. Otherwise, it will be the producer local time when the producer record was handed to the
+    /// producer.
+    pub timestamp: i64,
+    pub serialized_key_size: i32,
+    pub serialized_value_size: i32,
+    pub topic_partition: TopicPartition,
+}
+
+impl RecordMetadata {
+    pub fn new(
+        topic_partition: TopicPartition,
+        base_offset: i64,
+        batch_index: i32,
+        timestamp: i64,
+        serialized_key_size: i32,
+        serialized_value_size: i32,
+    ) -> RecordMetadata {
+        // ignore the batchIndex if the base offset is -1, since this indicates the offset is unknown
+        let offset = if base_offset == INVALID_OFFSET {
+            base_offset
+        } else {
+            base_offset + batch_index as i64
+        };
+        RecordMetadata {
+            offset,
+            timestamp,
+            serialized_key_size,
+            serialized_value_size,
+            topic_partition,
+        }
+    }
+
+    /// Indicates whether the record metadata includes the offset.
+    pub fn has_offset(&self) -> bool {
+        self.offset != INVALID_OFFSET
+    }
+
+    /// Indicates whether the record metadata includes the timestamp.
+    pub fn has_timestamp(&self) -> bool {
+        self.timestamp != NO_TIMESTAMP
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic_partition.topic
+    }
+
+    pub fn partition(&self) -> i32 {
+        self.topic_partition.partition
+    }
+}
+
+/// `org.apache.kafka.clients.producer.RecordMetadata`, passed to java `Callback`s.
+impl CloneToJava for RecordMetadata {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let topic_partition = self.topic_partition.clone_to_java(env)?;
+        jni_cache::new_object(
+            env,
+            "org/apache/kafka/clients/producer/RecordMetadata",
+            "(Lorg/apache/kafka/common/TopicPartition;JIJII)V",
+            &[
+                topic_partition,
+                JValue::Long(self.offset),
+                JValue::Int(0),
+                JValue::Long(self.timestamp),
+                JValue::Int(self.serialized_key_size),
+                JValue::Int(self.serialized_value_size),
+            ],
+        )
+        .map(JValue::Object)
+    }
+}
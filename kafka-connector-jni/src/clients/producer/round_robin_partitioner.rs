@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::common::{
+    cluster::Cluster,
+    errors::{KafkaError, Result},
+    utils,
+};
+
+use super::partitioner::Partitioner;
+
+/// The "Round-Robin" partitioner
+///
+/// This partitioning strategy can be used when user wants
+/// to distribute the writes to all partitions equally. This
+/// is the behaviour regardless of record key hash.
+#[derive(Default)]
+pub struct RoundRobinPartitioner {
+    topic_counter_map: Mutex<HashMap<String, i32>>,
+}
+
+impl RoundRobinPartitioner {
+    pub fn new() -> RoundRobinPartitioner {
+        RoundRobinPartitioner::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, i32>> {
+        self.topic_counter_map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn next_value(&self, topic: &str) -> i32 {
+        let mut topic_counter_map = self.lock();
+        let counter = topic_counter_map.entry(topic.to_owned()).or_insert(0);
+        let value = *counter;
+        *counter = counter.wrapping_add(1);
+        value
+    }
+}
+
+impl Partitioner for RoundRobinPartitioner {
+    fn partition(
+        &self,
+        topic: &str,
+        _key: Option<&[u8]>,
+        _value: Option<&[u8]>,
+        cluster: &Cluster,
+    ) -> Result<i32> {
+        let num_partitions = cluster.partitions_for_topic(topic).len();
+        let next_value = self.next_value(topic);
+        let available_partitions = cluster.available_partitions_for_topic(topic);
+        if !available_partitions.is_empty() {
+            let part = utils::to_positive(next_value) % available_partitions.len() as i32;
+            Ok(available_partitions[part as usize].partition)
+        } else if num_partitions > 0 {
+            // no partitions are available, give a non-available partition
+            Ok(utils::to_positive(next_value) % num_partitions as i32)
+        } else {
+            Err(KafkaError::IllegalArgument(format!(
+                "Topic {} has no partitions",
+                topic
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{common::node::Node, test_utils::cluster_with_leaders};
+
+    use super::{Partitioner, RoundRobinPartitioner};
+
+    #[test]
+    fn records_are_spread_over_the_available_partitions() {
+        let node = Node::new(0, "localhost", 9092);
+        let cluster = cluster_with_leaders(
+            "test",
+            &[Some(node.clone()), None, Some(node.clone()), Some(node)],
+        );
+        let partitioner = RoundRobinPartitioner::new();
+        let partitions: Vec<i32> = (0..6)
+            .map(|_| {
+                partitioner
+                    .partition("test", Some(b"key"), None, &cluster)
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(partitions, vec![0, 2, 3, 0, 2, 3]);
+    }
+
+    #[test]
+    fn topics_have_their_own_counter() {
+        let node = Node::new(0, "localhost", 9092);
+        let leaders = [Some(node.clone()), Some(node)];
+        let first = cluster_with_leaders("first", &leaders);
+        let second = cluster_with_leaders("second", &leaders);
+        let partitioner = RoundRobinPartitioner::new();
+        assert_eq!(
+            partitioner.partition("first", None, None, &first).unwrap(),
+            0
+        );
+        assert_eq!(
+            partitioner.partition("first", None, None, &first).unwrap(),
+            1
+        );
+        assert_eq!(
+            partitioner
+                .partition("second", None, None, &second)
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn unavailable_partitions_are_used_when_none_is_available() {
+        let cluster = cluster_with_leaders("test", &[None, None, None]);
+        let partitioner = RoundRobinPartitioner::new();
+        let partitions: Vec<i32> = (0..4)
+            .map(|_| partitioner.partition("test", None, None, &cluster).unwrap())
+            .collect();
+        assert_eq!(partitions, vec![0, 1, 2, 0]);
+
+        let empty = cluster_with_leaders("empty", &[]);
+        assert!(partitioner.partition("empty", None, None, &empty).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use crate::common::{cluster::Cluster, errors::Result};
+
+use super::{internals::sticky_partition_cache::StickyPartitionCache, partitioner::Partitioner};
+
+/// The partitioning strategy:
+///  - If a partition is specified in the record, use it
+///  - Otherwise choose the sticky partition that changes when the batch is full.
+///
+/// NOTE: In contrast to the DefaultPartitioner, the record key is NOT used as part of the partitioning strategy in this
+///       partitioner. Records with the same key are not guaranteed to be sent to the same partition.
+///
+/// See KIP-480 for details about sticky partitioning.
+#[derive(Default)]
+pub struct UniformStickyPartitioner {
+    sticky_partition_cache: StickyPartitionCache,
+}
+
+impl UniformStickyPartitioner {
+    pub fn new() -> UniformStickyPartitioner {
+        UniformStickyPartitioner::default()
+    }
+}
+
+impl Partitioner for UniformStickyPartitioner {
+    fn partition(
+        &self,
+        topic: &str,
+        _key: Option<&[u8]>,
+        _value: Option<&[u8]>,
+        cluster: &Cluster,
+    ) -> Result<i32> {
+        self.sticky_partition_cache.partition(topic, cluster)
+    }
+
+    /// If a batch completed for the current sticky partition, change the sticky partition.
+    /// Alternately, if no sticky partition has been determined, set one.
+    fn on_new_batch(&self, topic: &str, cluster: &Cluster, prev_partition: i32) {
+        // Without partitions there is no sticky partition to move, the next call to
+        // `partition` reports the error.
+        let _ = self
+            .sticky_partition_cache
+            .next_partition(topic, cluster, prev_partition);
+    }
+}
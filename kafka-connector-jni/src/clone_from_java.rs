@@ -1,17 +1,36 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    any,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use bytes::Bytes;
 use indexmap::{IndexMap, IndexSet};
 use jni::{
-    objects::{GlobalRef, JValue},
+    objects::{GlobalRef, JObject, JValue},
+    sys::{jboolean, jobjectArray},
     JNIEnv,
 };
 
+use crate::jni_cache;
+
+/// Null policy: java `null` is accepted only by `Option<T>`, where it becomes `None`, every
+/// other type throws `NullPointerException`.
 pub trait CloneFromJava {
     /// May be slower, but have to clone if we want to avoid Arc<T> on each structure in rust
     fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized;
+
+    /// Clones from a java reference, unboxing primitives (e.g. `java.lang.Integer` to `i32`).
+    /// Used for elements of collections and `Option` values.
+    fn clone_from_java_object(env: JNIEnv, obj: JObject) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::clone_from_java(env, JValue::Object(obj))
+    }
 }
 
 macro_rules! clone_from_java {
@@ -21,132 +40,196 @@ macro_rules! clone_from_java {
                 env: jni::JNIEnv,
                 obj: jni::objects::JValue,
             ) -> jni::errors::Result<Self> {
-                let obj = obj.l()?;
-                let class = env.find_class($class_name)?;
-                if !env.is_instance_of(obj, class)? {
-                    env.throw_new("java/lang/Exception", "Wrong object class")?;
-                    return Err(jni::errors::Error::JavaException);
-                }
-                let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-                let this = unsafe { Box::from_raw(ptr as *mut Self) };
-                let clone = this.as_ref().clone();
-                let _ptr = Box::into_raw(this);
+                let obj = crate::clone_from_java::non_null::<Self>(env, obj.l()?)?;
+                let stored = crate::java_stored_object::JavaStoredObject::<Self>::new(
+                    env,
+                    obj,
+                    $class_name,
+                )?;
+                let clone = stored.lock().clone();
                 Ok(clone)
             }
         }
     };
 }
 
-impl CloneFromJava for String {
-    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
-    where
-        Self: Sized,
-    {
-        let obj = obj.l()?;
-        if obj.is_null() {
-            return Ok("".to_owned());
-        }
-        env.get_string(obj.into()).map(Into::into)
+/// Throws `NullPointerException` when `obj` is `null`, `T` is the rust type it was converted to.
+pub fn non_null<'a, T>(env: JNIEnv, obj: JObject<'a>) -> jni::errors::Result<JObject<'a>> {
+    if obj.is_null() {
+        let message = format!("Cannot convert null to {}", any::type_name::<T>());
+        return throw(env, "java/lang/NullPointerException", &message);
     }
+    Ok(obj)
 }
 
-impl CloneFromJava for i32 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
-    where
-        Self: Sized,
-    {
-        obj.i()
-    }
-}
-impl CloneFromJava for u32 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
-    where
-        Self: Sized,
-    {
-        Ok(obj.i()? as u32)
-    }
+fn throw<T>(env: JNIEnv, class: &str, message: &str) -> jni::errors::Result<T> {
+    env.throw_new(class, message)?;
+    Err(jni::errors::Error::JavaException)
 }
 
-impl CloneFromJava for i64 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+impl CloneFromJava for String {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        obj.j()
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        env.get_string(obj.into()).map(Into::into)
     }
 }
-impl CloneFromJava for u64 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
-    where
-        Self: Sized,
-    {
-        Ok(obj.j()? as u64)
-    }
+
+/// Primitives are passed by value, or as their boxed class in collections and `Option`.
+macro_rules! clone_primitive_from_java {
+    ($type:ty, $getter:ident, $class_name:literal, $method:literal, $sig:literal) => {
+        impl CloneFromJava for $type {
+            #[allow(clippy::unnecessary_cast)]
+            fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+            where
+                Self: Sized,
+            {
+                Ok(obj.$getter()? as $type)
+            }
+
+            fn clone_from_java_object(env: JNIEnv, obj: JObject) -> jni::errors::Result<Self>
+            where
+                Self: Sized,
+            {
+                let obj = non_null::<Self>(env, obj)?;
+                let value = jni_cache::call_method(env, obj, $class_name, $method, $sig, &[])?;
+                Self::clone_from_java(env, value)
+            }
+        }
+    };
 }
-impl CloneFromJava for u128 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+clone_primitive_from_java!(i32, i, "java/lang/Number", "intValue", "()I");
+clone_primitive_from_java!(u32, i, "java/lang/Number", "intValue", "()I");
+clone_primitive_from_java!(i64, j, "java/lang/Number", "longValue", "()J");
+clone_primitive_from_java!(u64, j, "java/lang/Number", "longValue", "()J");
+clone_primitive_from_java!(u128, j, "java/lang/Number", "longValue", "()J");
+clone_primitive_from_java!(f64, d, "java/lang/Number", "doubleValue", "()D");
+clone_primitive_from_java!(bool, z, "java/lang/Boolean", "booleanValue", "()Z");
+
+/// Nullable reference, `null` is `None`. `java.util.Optional` is converted by
+/// `crate::java_optional::JavaOptional`.
+impl<T> CloneFromJava for Option<T>
+where
+    T: CloneFromJava,
+{
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        Ok(obj.j()? as u128)
+        let obj = obj.l()?;
+        if obj.is_null() {
+            return Ok(None);
+        }
+        T::clone_from_java_object(env, obj).map(Some)
     }
 }
 
-impl CloneFromJava for bool {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+impl CloneFromJava for GlobalRef {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        obj.z()
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        env.new_global_ref(obj)
     }
 }
 
-impl CloneFromJava for f64 {
-    fn clone_from_java(_env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+impl CloneFromJava for Bytes {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        obj.d()
+        let o = non_null::<Self>(env, obj.l()?)?.into_inner();
+        env.convert_byte_array(o).map(Bytes::from)
     }
 }
 
-impl CloneFromJava for Option<i32> {
+/// `java.time.Duration`, negative durations throw `IllegalArgumentException`.
+impl CloneFromJava for Duration {
     fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        let obj = obj.l()?;
-        let is_present = env.call_method(obj, "isPresent", "()Z", &[])?.z()?;
-        match is_present {
-            true => {
-                let integer_obj = env
-                    .call_method(obj, "get", "()Ljava/lang/Object;", &[])?
-                    .l()?;
-                let value = env.call_method(integer_obj, "intValue", "()I", &[])?.i()?;
-                Ok(Some(value))
-            }
-            false => Ok(None),
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let seconds =
+            jni_cache::call_method(env, obj, "java/time/Duration", "getSeconds", "()J", &[])?
+                .j()?;
+        let nanos =
+            jni_cache::call_method(env, obj, "java/time/Duration", "getNano", "()I", &[])?.i()?;
+        if seconds < 0 {
+            return throw(
+                env,
+                "java/lang/IllegalArgumentException",
+                "Negative durations are not supported",
+            );
         }
+        Ok(Duration::new(seconds as u64, nanos as u32))
     }
 }
 
-impl CloneFromJava for GlobalRef {
+/// `java.time.Instant`, instants out of the range of `SystemTime` throw
+/// `IllegalArgumentException`.
+impl CloneFromJava for SystemTime {
     fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        let obj = obj.l()?;
-        env.new_global_ref(obj)
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let seconds =
+            jni_cache::call_method(env, obj, "java/time/Instant", "getEpochSecond", "()J", &[])?
+                .j()?;
+        let nanos =
+            jni_cache::call_method(env, obj, "java/time/Instant", "getNano", "()I", &[])?.i()?;
+        // Nanos are always a positive adjustment of the seconds, also before the epoch.
+        let time = if seconds >= 0 {
+            UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64))
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+        };
+        match time.and_then(|time| time.checked_add(Duration::from_nanos(nanos as u64))) {
+            Some(time) => Ok(time),
+            None => throw(
+                env,
+                "java/lang/IllegalArgumentException",
+                "Instant is out of the supported range",
+            ),
+        }
     }
 }
 
-impl CloneFromJava for Bytes {
+/// Primitive arrays, e.g. `Box<[i64]>` is `long[]`.
+macro_rules! clone_array_from_java {
+    ($type:ty, $getter:ident) => {
+        impl CloneFromJava for Box<[$type]> {
+            fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+            where
+                Self: Sized,
+            {
+                let array = non_null::<Self>(env, obj.l()?)?.into_inner();
+                let length = env.get_array_length(array)?;
+                let mut buf: Vec<$type> = vec![Default::default(); length as usize];
+                env.$getter(array, 0, &mut buf)?;
+                Ok(buf.into_boxed_slice())
+            }
+        }
+    };
+}
+clone_array_from_java!(i32, get_int_array_region);
+clone_array_from_java!(i64, get_long_array_region);
+clone_array_from_java!(f64, get_double_array_region);
+
+impl CloneFromJava for Box<[bool]> {
     fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
-        let o = obj.l()?.into_inner();
-        env.convert_byte_array(o).map(Bytes::from)
+        let array = non_null::<Self>(env, obj.l()?)?.into_inner();
+        let length = env.get_array_length(array)?;
+        let mut buf: Vec<jboolean> = vec![0; length as usize];
+        env.get_boolean_array_region(array, 0, &mut buf)?;
+        Ok(buf.into_iter().map(|value| value != 0).collect())
     }
 }
 
@@ -159,24 +242,11 @@ where
     where
         Self: Sized,
     {
+        let obj = non_null::<Self>(env, obj.l()?)?;
         let mut hash_map = HashMap::new();
-        let entry_set = env
-            .call_method(obj.l()?, "entrySet", "()Ljava/util/Set;", &[])?
-            .l()?;
-        let array = env
-            .call_method(entry_set, "toArray", "()[Ljava/lang/Object;", &[])?
-            .l()?
-            .into_inner();
-        let length = env.get_array_length(array)?;
-        for i in 0..length {
-            let entry = env.get_object_array_element(array, i)?;
-            let key = env.call_method(entry, "getKey", "()Ljava/lang/Object;", &[])?;
-            let key = K::clone_from_java(env, key)?;
-            let value = env.call_method(entry, "getValue", "()Ljava/lang/Object;", &[])?;
-            let value = V::clone_from_java(env, value)?;
+        for_each_entry(env, obj, |key, value| {
             hash_map.insert(key, value);
-        }
-
+        })?;
         Ok(hash_map)
     }
 }
@@ -190,28 +260,32 @@ where
     where
         Self: Sized,
     {
+        let obj = non_null::<Self>(env, obj.l()?)?;
         let mut hash_map = IndexMap::new();
-        let entry_set = env
-            .call_method(obj.l()?, "entrySet", "()Ljava/util/Set;", &[])?
-            .l()?;
-        let array = env
-            .call_method(entry_set, "toArray", "()[Ljava/lang/Object;", &[])?
-            .l()?
-            .into_inner();
-        let length = env.get_array_length(array)?;
-        for i in 0..length {
-            let entry = env.get_object_array_element(array, i)?;
-            let key = env.call_method(entry, "getKey", "()Ljava/lang/Object;", &[])?;
-            let key = K::clone_from_java(env, key)?;
-            let value = env.call_method(entry, "getValue", "()Ljava/lang/Object;", &[])?;
-            let value = V::clone_from_java(env, value)?;
+        for_each_entry(env, obj, |key, value| {
             hash_map.insert(key, value);
-        }
-
+        })?;
         Ok(hash_map)
     }
 }
 
+impl<K> CloneFromJava for HashSet<K>
+where
+    K: CloneFromJava + Eq + Hash,
+{
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let mut hash_set = HashSet::new();
+        for_each_element(env, obj, |key| {
+            hash_set.insert(key);
+        })?;
+        Ok(hash_set)
+    }
+}
+
 impl<K> CloneFromJava for IndexSet<K>
 where
     K: CloneFromJava + Eq + Hash,
@@ -220,19 +294,11 @@ where
     where
         Self: Sized,
     {
+        let obj = non_null::<Self>(env, obj.l()?)?;
         let mut hash_set = IndexSet::new();
-
-        let array = env
-            .call_method(obj.l()?, "toArray", "()[Ljava/lang/Object;", &[])?
-            .l()?
-            .into_inner();
-        let length = env.get_array_length(array)?;
-        for i in 0..length {
-            let key = env.get_object_array_element(array, i)?;
-            let key = K::clone_from_java(env, JValue::Object(key))?;
+        for_each_element(env, obj, |key| {
             hash_set.insert(key);
-        }
-
+        })?;
         Ok(hash_set)
     }
 }
@@ -245,19 +311,249 @@ where
     where
         Self: Sized,
     {
+        let obj = non_null::<Self>(env, obj.l()?)?;
         let mut vec = Vec::new();
+        for_each_element(env, obj, |item| vec.push(item))?;
+        Ok(vec)
+    }
+}
 
-        let array = env
-            .call_method(obj.l()?, "toArray", "()[Ljava/lang/Object;", &[])?
-            .l()?
-            .into_inner();
-        let length = env.get_array_length(array)?;
-        for i in 0..length {
-            let key = env.get_object_array_element(array, i)?;
-            let item = T::clone_from_java(env, JValue::Object(key))?;
-            vec.push(item);
-        }
+/// Calls `f` with every entry of the `java.util.Map` `map`.
+fn for_each_entry<K, V, F>(env: JNIEnv, map: JObject, mut f: F) -> jni::errors::Result<()>
+where
+    K: CloneFromJava,
+    V: CloneFromJava,
+    F: FnMut(K, V),
+{
+    let entry_set = jni_cache::call_method(
+        env,
+        map,
+        "java/util/Map",
+        "entrySet",
+        "()Ljava/util/Set;",
+        &[],
+    )?
+    .l()?;
+    let array = to_array(env, entry_set)?;
+    let length = env.get_array_length(array)?;
+    for i in 0..length {
+        let (key, value) = in_local_frame(env, || {
+            let entry = env.get_object_array_element(array, i)?;
+            let key = jni_cache::call_method(
+                env,
+                entry,
+                "java/util/Map$Entry",
+                "getKey",
+                "()Ljava/lang/Object;",
+                &[],
+            )?
+            .l()?;
+            let key = K::clone_from_java_object(env, key)?;
+            let value = jni_cache::call_method(
+                env,
+                entry,
+                "java/util/Map$Entry",
+                "getValue",
+                "()Ljava/lang/Object;",
+                &[],
+            )?
+            .l()?;
+            let value = V::clone_from_java_object(env, value)?;
+            Ok((key, value))
+        })?;
+        f(key, value);
+    }
+    Ok(())
+}
 
-        Ok(vec)
+/// Calls `f` with every element of the `java.util.Collection` `collection`.
+fn for_each_element<T, F>(env: JNIEnv, collection: JObject, mut f: F) -> jni::errors::Result<()>
+where
+    T: CloneFromJava,
+    F: FnMut(T),
+{
+    let array = to_array(env, collection)?;
+    let length = env.get_array_length(array)?;
+    for i in 0..length {
+        let element = in_local_frame(env, || {
+            T::clone_from_java_object(env, env.get_object_array_element(array, i)?)
+        })?;
+        f(element);
+    }
+    Ok(())
+}
+
+/// Local references a conversion may create before the JVM grows its frame.
+const LOCAL_FRAME_CAPACITY: i32 = 16;
+
+/// Runs the conversion `f` in its own frame of local references, which are all freed once it
+/// returns, so converting large collections doesn't exhaust the frame of the native method.
+pub fn in_local_frame<T, F>(env: JNIEnv, f: F) -> jni::errors::Result<T>
+where
+    F: FnOnce() -> jni::errors::Result<T>,
+{
+    env.push_local_frame(LOCAL_FRAME_CAPACITY)?;
+    let result = f();
+    env.pop_local_frame(JObject::null())?;
+    result
+}
+
+/// Elements of the `java.util.Collection` `collection`.
+fn to_array(env: JNIEnv, collection: JObject) -> jni::errors::Result<jobjectArray> {
+    let array = jni_cache::call_method(
+        env,
+        collection,
+        "java/util/Collection",
+        "toArray",
+        "()[Ljava/lang/Object;",
+        &[],
+    )?
+    .l()?;
+    Ok(array.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{HashMap, HashSet},
+        fmt::Debug,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
+
+    use indexmap::{IndexMap, IndexSet};
+    use jni::{objects::JObject, JNIEnv};
+
+    use super::CloneFromJava;
+    use crate::{clone_to_java::CloneToJava, jvm};
+
+    /// Clones `value` to java and back, returns `String.valueOf` of the java value.
+    fn round_trip<T>(env: JNIEnv, value: T) -> jni::errors::Result<String>
+    where
+        T: CloneToJava + CloneFromJava + PartialEq + Debug,
+    {
+        let java = value.clone_to_java(env)?;
+        assert_eq!(T::clone_from_java(env, java)?, value);
+        jvm::to_string(env, value.clone_to_java_object(env)?)
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn option_is_nullable() {
+        jvm::run(|env| {
+            assert_eq!(round_trip(env, Some(7i32))?, "7");
+            assert_eq!(round_trip(env, Some(-7i64))?, "-7");
+            assert_eq!(round_trip(env, Some(true))?, "true");
+            assert_eq!(round_trip(env, Some(1.5f64))?, "1.5");
+            assert_eq!(round_trip(env, Some("a".to_string()))?, "a");
+            assert_eq!(round_trip(env, None::<i32>)?, "null");
+            assert_eq!(round_trip(env, None::<String>)?, "null");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn null_is_rejected_without_option() {
+        jvm::run(|env| {
+            let npe = "java.lang.NullPointerException";
+            let null = || JObject::null();
+            assert_eq!(
+                jvm::expect_exception(env, || i32::clone_from_java_object(env, null()))?,
+                npe
+            );
+            assert_eq!(
+                jvm::expect_exception(env, || String::clone_from_java_object(env, null()))?,
+                npe
+            );
+            assert_eq!(
+                jvm::expect_exception(env, || Vec::<i32>::clone_from_java_object(env, null()))?,
+                npe
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn vec_is_array_list() {
+        jvm::run(|env| {
+            assert_eq!(round_trip(env, vec![1i32, 2, 3])?, "[1, 2, 3]");
+            assert_eq!(round_trip(env, Vec::<String>::new())?, "[]");
+            assert_eq!(
+                round_trip(env, vec![Some("a".to_string()), None])?,
+                "[a, null]"
+            );
+            assert_eq!(round_trip(env, vec![vec![1i64], vec![]])?, "[[1], []]");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn maps_keep_entries() {
+        jvm::run(|env| {
+            let ordered: IndexMap<String, i32> = vec![("b".to_string(), 2), ("a".to_string(), 1)]
+                .into_iter()
+                .collect();
+            assert_eq!(round_trip(env, ordered)?, "{b=2, a=1}");
+            let hashed: HashMap<i64, Option<String>> =
+                vec![(1, Some("one".to_string())), (2, None)]
+                    .into_iter()
+                    .collect();
+            round_trip(env, hashed)?;
+            assert_eq!(round_trip(env, HashMap::<String, i32>::new())?, "{}");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn sets_keep_elements() {
+        jvm::run(|env| {
+            let ordered: IndexSet<String> =
+                vec!["b".to_string(), "a".to_string()].into_iter().collect();
+            assert_eq!(round_trip(env, ordered)?, "[b, a]");
+            let hashed: HashSet<i32> = vec![3, 1, 2].into_iter().collect();
+            round_trip(env, hashed)?;
+            assert_eq!(round_trip(env, HashSet::<String>::new())?, "[]");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn duration_is_java_duration() {
+        jvm::run(|env| {
+            assert_eq!(round_trip(env, Duration::from_millis(1500))?, "PT1.5S");
+            assert_eq!(round_trip(env, Duration::new(2, 5))?, "PT2.000000005S");
+            assert_eq!(round_trip(env, Duration::default())?, "PT0S");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn system_time_is_instant() {
+        jvm::run(|env| {
+            let after = UNIX_EPOCH + Duration::new(1_600_000_000, 123);
+            assert_eq!(round_trip(env, after)?, "2020-09-13T12:26:40.000000123Z");
+            let before = UNIX_EPOCH - Duration::from_millis(1500);
+            assert_eq!(round_trip(env, before)?, "1969-12-31T23:59:58.500Z");
+            round_trip(env, SystemTime::now())?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn boxed_slices_are_primitive_arrays() {
+        jvm::run(|env| {
+            assert!(round_trip(env, Box::<[i32]>::from([1, -2]))?.starts_with("[I@"));
+            assert!(round_trip(env, Box::<[i64]>::from([i64::MAX]))?.starts_with("[J@"));
+            assert!(round_trip(env, Box::<[f64]>::from([0.5]))?.starts_with("[D@"));
+            assert!(round_trip(env, Box::<[bool]>::from([true, false]))?.starts_with("[Z@"));
+            round_trip(env, Box::<[i32]>::from([]))?;
+            Ok(())
+        });
     }
 }
@@ -1,34 +1,57 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use bytes::Bytes;
 use indexmap::{IndexMap, IndexSet};
 use jni::{
-    objects::{GlobalRef, JValue},
+    objects::{GlobalRef, JObject, JValue},
+    sys::jboolean,
     JNIEnv,
 };
+
+use crate::jni_cache;
+
+/// Kinds of java values, `CloneToJava::Kind` lets macros check JNI signatures at compile time.
+pub mod kind {
+    pub struct Boolean;
+    pub struct Double;
+    pub struct Int;
+    pub struct Long;
+    pub struct Object;
+}
+
+/// `None` is cloned to java `null`, see `CloneFromJava` for the null policy of the reverse
+/// direction.
 pub trait CloneToJava {
+    /// Kind of the java value produced, e.g. `kind::Long` for values returned as `J`.
+    type Kind;
+
     /// Clones object to java - making a clone readonly in most cases(java changes won't affect a real state)
     /// May produce errors in java (testing) logic, however needed if we want to avoid Arc<T> on rust structures
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>>;
+
+    /// Clones to a java reference, boxing primitives (e.g. `i32` to `java.lang.Integer`). Used
+    /// for elements of collections and `Option` values.
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        self.clone_to_java(env)?.l()
+    }
 }
 
 macro_rules! clone_to_java {
     ($struct_name:ty, $class_name:literal) => {
         impl crate::clone_to_java::CloneToJava for $struct_name {
+            type Kind = crate::clone_to_java::kind::Object;
+
             fn clone_to_java<'a>(
                 &self,
                 env: jni::JNIEnv<'a>,
             ) -> jni::errors::Result<jni::objects::JValue<'a>> {
-                let class = env.find_class($class_name)?;
-                let obj = env.alloc_object(class)?;
-                let copy = Box::new(self.clone());
-                let ptr = Box::into_raw(copy);
-                env.set_field(
-                    obj,
-                    "rustPointer",
-                    "J",
-                    jni::objects::JValue::Long(ptr as i64),
-                )?;
+                let class = crate::jni_cache::class(env, $class_name)?;
+                let obj = env.alloc_object(&class)?;
+                crate::java_stored_object::JavaStoredObject::store(env, obj, self.clone())?;
                 Ok(obj.into())
             }
         }
@@ -36,16 +59,26 @@ macro_rules! clone_to_java {
 }
 
 impl CloneToJava for String {
+    type Kind = kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         env.new_string(self).map(Into::into).map(JValue::Object)
     }
 }
 impl CloneToJava for i32 {
+    type Kind = kind::Int;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(JValue::Int(*self))
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Integer", self.clone_to_java(env)?)
+    }
 }
 impl CloneToJava for u32 {
+    type Kind = kind::Int;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(match *self {
             u32::MAX => JValue::Int(i32::MAX),
@@ -53,13 +86,25 @@ impl CloneToJava for u32 {
             v => JValue::Int(v as i32),
         })
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Integer", self.clone_to_java(env)?)
+    }
 }
 impl CloneToJava for i64 {
+    type Kind = kind::Long;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(JValue::Long(*self))
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Long", self.clone_to_java(env)?)
+    }
 }
 impl CloneToJava for u64 {
+    type Kind = kind::Long;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(match *self {
             u64::MAX => JValue::Long(i64::MAX),
@@ -67,8 +112,14 @@ impl CloneToJava for u64 {
             v => JValue::Long(v as i64),
         })
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Long", self.clone_to_java(env)?)
+    }
 }
 impl CloneToJava for u128 {
+    type Kind = kind::Long;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(match *self {
             u128::MAX => JValue::Long(i64::MAX),
@@ -76,78 +127,167 @@ impl CloneToJava for u128 {
             v => JValue::Long(v as i64),
         })
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Long", self.clone_to_java(env)?)
+    }
 }
 
 impl CloneToJava for bool {
+    type Kind = kind::Boolean;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(JValue::Bool((*self) as u8))
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Boolean", self.clone_to_java(env)?)
+    }
 }
 impl CloneToJava for f64 {
+    type Kind = kind::Double;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         Ok(JValue::Double(*self))
     }
+
+    fn clone_to_java_object<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        box_value(env, "java/lang/Double", self.clone_to_java(env)?)
+    }
+}
+
+/// `valueOf` of the boxed class `class_name` for the primitive `value`.
+fn box_value<'a>(
+    env: JNIEnv<'a>,
+    class_name: &'static str,
+    value: JValue<'a>,
+) -> jni::errors::Result<JObject<'a>> {
+    let sig = match value {
+        JValue::Bool(_) => "(Z)Ljava/lang/Boolean;",
+        JValue::Int(_) => "(I)Ljava/lang/Integer;",
+        JValue::Long(_) => "(J)Ljava/lang/Long;",
+        JValue::Double(_) => "(D)Ljava/lang/Double;",
+        _ => return value.l(),
+    };
+    jni_cache::call_static_method(env, class_name, "valueOf", sig, &[value])?.l()
 }
 
-impl CloneToJava for Option<i32> {
+/// Nullable reference, `None` is `null`. `java.util.Optional` is produced by
+/// `crate::java_optional::JavaOptional`.
+impl<T> CloneToJava for Option<T>
+where
+    T: CloneToJava,
+{
+    type Kind = kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
-        let optional_class = env
-            .find_class("java/util/Optional")
-            .expect("Failed to load the target class");
-        let value = match self {
-            Some(value) => {
-                let integer_class = env
-                    .find_class("java/lang/Integer")
-                    .expect("Failed to load the target class");
-
-                let obj = env.new_object(integer_class, "(I)V", &[JValue::Int(*value)])?;
-                env.call_static_method(
-                    optional_class,
-                    "of",
-                    "(Ljava/lang/Object;)Ljava/util/Optional;",
-                    &[obj.into()],
-                )?
-            }
-            None => {
-                env.call_static_method(optional_class, "empty", "()Ljava/util/Optional;", &[])?
-            }
+        let obj = match self {
+            Some(value) => value.clone_to_java_object(env)?,
+            None => JObject::null(),
         };
-        Ok(value)
+        Ok(JValue::Object(obj))
     }
 }
 
 impl CloneToJava for GlobalRef {
+    type Kind = kind::Object;
+
     fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         let o = self.clone().as_obj().into_inner();
         Ok(JValue::Object(o.into()))
     }
 }
 impl CloneToJava for Bytes {
+    type Kind = kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         let o = env.byte_array_from_slice(self)?;
         Ok(JValue::Object(o.into()))
     }
 }
 
+/// `java.time.Duration`.
+impl CloneToJava for Duration {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        // Longer than java supports, saturated as the unsigned integers are.
+        let seconds = self.as_secs().min(i64::MAX as u64) as i64;
+        jni_cache::call_static_method(
+            env,
+            "java/time/Duration",
+            "ofSeconds",
+            "(JJ)Ljava/time/Duration;",
+            &[
+                JValue::Long(seconds),
+                JValue::Long(self.subsec_nanos() as i64),
+            ],
+        )
+    }
+}
+
+/// `java.time.Instant`.
+impl CloneToJava for SystemTime {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        // `ofEpochSecond` normalizes negative nanos of times before the epoch.
+        let (seconds, nanos) = match self.duration_since(UNIX_EPOCH) {
+            Ok(since) => (since.as_secs() as i64, since.subsec_nanos() as i64),
+            Err(e) => {
+                let before = e.duration();
+                (-(before.as_secs() as i64), -(before.subsec_nanos() as i64))
+            }
+        };
+        jni_cache::call_static_method(
+            env,
+            "java/time/Instant",
+            "ofEpochSecond",
+            "(JJ)Ljava/time/Instant;",
+            &[JValue::Long(seconds), JValue::Long(nanos)],
+        )
+    }
+}
+
+/// Primitive arrays, e.g. `Box<[i64]>` is `long[]`.
+macro_rules! clone_array_to_java {
+    ($type:ty, $new_array:ident, $setter:ident) => {
+        impl CloneToJava for Box<[$type]> {
+            type Kind = kind::Object;
+
+            fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+                let array = env.$new_array(self.len() as i32)?;
+                env.$setter(array, 0, self)?;
+                Ok(JValue::Object(array.into()))
+            }
+        }
+    };
+}
+clone_array_to_java!(i32, new_int_array, set_int_array_region);
+clone_array_to_java!(i64, new_long_array, set_long_array_region);
+clone_array_to_java!(f64, new_double_array, set_double_array_region);
+
+impl CloneToJava for Box<[bool]> {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let array = env.new_boolean_array(self.len() as i32)?;
+        let values: Vec<jboolean> = self.iter().map(|value| *value as jboolean).collect();
+        env.set_boolean_array_region(array, 0, &values)?;
+        Ok(JValue::Object(array.into()))
+    }
+}
+
 impl<K, V> CloneToJava for HashMap<K, V>
 where
     K: CloneToJava + Eq + Hash,
     V: CloneToJava,
 {
-    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
-        let class = env.find_class("java/util/HashMap")?;
-        let hash_map = env.new_object(class, "()V", &[])?;
-        for entry in self {
-            let key = K::clone_to_java(entry.0, env)?;
-            let value = V::clone_to_java(entry.1, env)?;
-            env.call_method(
-                hash_map,
-                "put",
-                "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
-                &[key, value],
-            )?;
-        }
+    type Kind = kind::Object;
 
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let hash_map = jni_cache::new_object(env, "java/util/HashMap", "()V", &[])?;
+        put_all(env, hash_map, self)?;
         Ok(JValue::Object(hash_map))
     }
 }
@@ -157,36 +297,37 @@ where
     K: CloneToJava + Eq + Hash,
     V: CloneToJava,
 {
-    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
-        let class = env.find_class("java/util/LinkedHashMap")?;
-        let hash_map = env.new_object(class, "()V", &[])?;
-        for entry in self {
-            let key = K::clone_to_java(entry.0, env)?;
-            let value = V::clone_to_java(entry.1, env)?;
-            env.call_method(
-                hash_map,
-                "put",
-                "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
-                &[key, value],
-            )?;
-        }
+    type Kind = kind::Object;
 
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let hash_map = jni_cache::new_object(env, "java/util/LinkedHashMap", "()V", &[])?;
+        put_all(env, hash_map, self)?;
         Ok(JValue::Object(hash_map))
     }
 }
 
-impl<K> CloneToJava for IndexSet<K>
+impl<K> CloneToJava for HashSet<K>
 where
     K: CloneToJava + Eq + Hash,
 {
+    type Kind = kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
-        let class = env.find_class("java/util/LinkedHashSet")?;
-        let hash_set = env.new_object(class, "()V", &[])?;
-        for key in self {
-            let key = K::clone_to_java(key, env)?;
-            env.call_method(hash_set, "add", "(Ljava/lang/Object;)Z", &[key])?;
-        }
+        let hash_set = jni_cache::new_object(env, "java/util/HashSet", "()V", &[])?;
+        add_all(env, hash_set, self)?;
+        Ok(JValue::Object(hash_set))
+    }
+}
+
+impl<K> CloneToJava for IndexSet<K>
+where
+    K: CloneToJava + Eq + Hash,
+{
+    type Kind = kind::Object;
 
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let hash_set = jni_cache::new_object(env, "java/util/LinkedHashSet", "()V", &[])?;
+        add_all(env, hash_set, self)?;
         Ok(JValue::Object(hash_set))
     }
 }
@@ -195,14 +336,70 @@ impl<T> CloneToJava for Vec<T>
 where
     T: CloneToJava,
 {
-    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
-        let class = env.find_class("java/util/List")?;
-        let list = env.new_object(class, "()V", &[])?;
-        for item in self {
-            let key = T::clone_to_java(item, env)?;
-            env.call_method(list, "add", "(Ljava/lang/Object;)Z", &[key])?;
-        }
+    type Kind = kind::Object;
 
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let list = jni_cache::new_object(
+            env,
+            "java/util/ArrayList",
+            "(I)V",
+            &[JValue::Int(self.len() as i32)],
+        )?;
+        add_all(env, list, self)?;
         Ok(JValue::Object(list))
     }
 }
+
+/// Puts every entry of `entries` to the `java.util.Map` `map`.
+fn put_all<'a, 'b, K, V, I>(
+    env: JNIEnv<'a>,
+    map: JObject<'a>,
+    entries: I,
+) -> jni::errors::Result<()>
+where
+    K: CloneToJava + 'b,
+    V: CloneToJava + 'b,
+    I: IntoIterator<Item = (&'b K, &'b V)>,
+{
+    for (key, value) in entries {
+        let key = key.clone_to_java_object(env)?;
+        let value = value.clone_to_java_object(env)?;
+        jni_cache::call_method(
+            env,
+            map,
+            "java/util/Map",
+            "put",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+            &[key.into(), value.into()],
+        )?;
+        // Freed right away, the frame of the native method only holds a few references.
+        env.delete_local_ref(key)?;
+        env.delete_local_ref(value)?;
+    }
+    Ok(())
+}
+
+/// Adds every element of `elements` to the `java.util.Collection` `collection`.
+fn add_all<'a, 'b, T, I>(
+    env: JNIEnv<'a>,
+    collection: JObject<'a>,
+    elements: I,
+) -> jni::errors::Result<()>
+where
+    T: CloneToJava + 'b,
+    I: IntoIterator<Item = &'b T>,
+{
+    for element in elements {
+        let element = element.clone_to_java_object(env)?;
+        jni_cache::call_method(
+            env,
+            collection,
+            "java/util/Collection",
+            "add",
+            "(Ljava/lang/Object;)Z",
+            &[element.into()],
+        )?;
+        env.delete_local_ref(element)?;
+    }
+    Ok(())
+}
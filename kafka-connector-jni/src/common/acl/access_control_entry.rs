@@ -0,0 +1,91 @@
+use std::fmt::{self, Display};
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{
+    access_control_entry_filter::AccessControlEntryFilter, acl_operation::AclOperation,
+    acl_permission_type::AclPermissionType,
+};
+
+/// Represents an access control entry. ACEs are a tuple of principal, host, operation, and
+/// permissionType.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessControlEntry {
+    principal: String,
+    host: String,
+    operation: AclOperation,
+    permission_type: AclPermissionType,
+}
+
+impl AccessControlEntry {
+    /// Create an instance of an access control entry with the provided parameters.
+    ///
+    /// * `principal` - the principal
+    /// * `host` - the host
+    /// * `operation` - the operation, which can not be `Any`
+    /// * `permission_type` - the permission type, which can not be `Any`
+    pub fn new(
+        principal: impl Into<String>,
+        host: impl Into<String>,
+        operation: AclOperation,
+        permission_type: AclPermissionType,
+    ) -> Result<AccessControlEntry> {
+        if operation == AclOperation::Any {
+            return Err(KafkaError::IllegalArgument(
+                "operation must not be ANY".to_owned(),
+            ));
+        }
+        if permission_type == AclPermissionType::Any {
+            return Err(KafkaError::IllegalArgument(
+                "permissionType must not be ANY".to_owned(),
+            ));
+        }
+        Ok(AccessControlEntry {
+            principal: principal.into(),
+            host: host.into(),
+            operation,
+            permission_type,
+        })
+    }
+
+    pub fn principal(&self) -> &str {
+        &self.principal
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn operation(&self) -> AclOperation {
+        self.operation
+    }
+
+    pub fn permission_type(&self) -> AclPermissionType {
+        self.permission_type
+    }
+
+    /// Create a filter which matches only this AccessControlEntry.
+    pub fn to_filter(&self) -> AccessControlEntryFilter {
+        AccessControlEntryFilter::new(
+            Some(self.principal.clone()),
+            Some(self.host.clone()),
+            self.operation,
+            self.permission_type,
+        )
+    }
+
+    /// Return true if this AclResource has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.operation.is_unknown() || self.permission_type.is_unknown()
+    }
+}
+
+impl Display for AccessControlEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(principal={}, host={}, operation={}, permissionType={})",
+            self.principal, self.host, self.operation, self.permission_type
+        )
+    }
+}
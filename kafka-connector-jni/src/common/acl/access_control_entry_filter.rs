@@ -0,0 +1,109 @@
+use std::fmt::{self, Display};
+
+use super::{
+    access_control_entry::AccessControlEntry, acl_operation::AclOperation,
+    acl_permission_type::AclPermissionType,
+};
+
+/// Represents a filter which matches access control entries.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AccessControlEntryFilter {
+    /// The principal or `None`, which matches any principal
+    pub principal: Option<String>,
+    /// The host or `None`, which matches any host
+    pub host: Option<String>,
+    pub operation: AclOperation,
+    pub permission_type: AclPermissionType,
+}
+
+impl AccessControlEntryFilter {
+    /// Matches any access control entry.
+    pub const ANY: AccessControlEntryFilter = AccessControlEntryFilter {
+        principal: None,
+        host: None,
+        operation: AclOperation::Any,
+        permission_type: AclPermissionType::Any,
+    };
+
+    /// Create an instance of an access control entry filter with the provided parameters.
+    ///
+    /// * `principal` - the principal or `None`
+    /// * `host` - the host or `None`
+    /// * `operation` - the operation
+    /// * `permission_type` - the permission type
+    pub fn new(
+        principal: Option<String>,
+        host: Option<String>,
+        operation: AclOperation,
+        permission_type: AclPermissionType,
+    ) -> AccessControlEntryFilter {
+        AccessControlEntryFilter {
+            principal,
+            host,
+            operation,
+            permission_type,
+        }
+    }
+
+    /// Returns true if this filter has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.operation.is_unknown() || self.permission_type.is_unknown()
+    }
+
+    /// Returns true if this filter matches the given AccessControlEntry.
+    pub fn matches(&self, other: &AccessControlEntry) -> bool {
+        if matches!(&self.principal, Some(principal) if principal != other.principal()) {
+            return false;
+        }
+        if matches!(&self.host, Some(host) if host != other.host()) {
+            return false;
+        }
+        if self.operation != AclOperation::Any && self.operation != other.operation() {
+            return false;
+        }
+        self.permission_type == AclPermissionType::Any
+            || self.permission_type == other.permission_type()
+    }
+
+    /// Returns true if this filter could only match one ACE -- in other words, if there are no
+    /// ANY or UNKNOWN fields.
+    pub fn matches_at_most_one(&self) -> bool {
+        self.find_indefinite_field().is_none()
+    }
+
+    /// Returns a string describing an ANY or UNKNOWN field, or `None` if there is no such field.
+    pub fn find_indefinite_field(&self) -> Option<&'static str> {
+        if self.principal.is_none() {
+            return Some("Principal is NULL");
+        }
+        if self.host.is_none() {
+            return Some("Host is NULL");
+        }
+        if self.operation == AclOperation::Any {
+            return Some("Operation is ANY");
+        }
+        if self.operation == AclOperation::Unknown {
+            return Some("Operation is UNKNOWN");
+        }
+        if self.permission_type == AclPermissionType::Any {
+            return Some("Permission type is ANY");
+        }
+        if self.permission_type == AclPermissionType::Unknown {
+            return Some("Permission type is UNKNOWN");
+        }
+        None
+    }
+}
+
+impl Display for AccessControlEntryFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(principal={}, host={}, operation={}, permissionType={})",
+            self.principal.as_deref().unwrap_or("<any>"),
+            self.host.as_deref().unwrap_or("<any>"),
+            self.operation,
+            self.permission_type
+        )
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::{self, Display};
+
+use crate::common::resource::resource_pattern::ResourcePattern;
+
+use super::{access_control_entry::AccessControlEntry, acl_binding_filter::AclBindingFilter};
+
+/// Represents a binding between a resource pattern and an access control entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AclBinding {
+    pub pattern: ResourcePattern,
+    pub entry: AccessControlEntry,
+}
+
+impl AclBinding {
+    /// Create an instance of this class with the provided parameters.
+    ///
+    /// * `pattern` - the resource pattern
+    /// * `entry` - the access control entry
+    pub fn new(pattern: ResourcePattern, entry: AccessControlEntry) -> AclBinding {
+        AclBinding { pattern, entry }
+    }
+
+    /// Returns true if this binding has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.pattern.is_unknown() || self.entry.is_unknown()
+    }
+
+    /// Create a filter which matches only this AclBinding.
+    pub fn to_filter(&self) -> AclBindingFilter {
+        AclBindingFilter::new(self.pattern.to_filter(), self.entry.to_filter())
+    }
+}
+
+impl Display for AclBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(pattern={}, entry={})", self.pattern, self.entry)
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::{self, Display};
+
+use crate::common::resource::resource_pattern_filter::ResourcePatternFilter;
+
+use super::{access_control_entry_filter::AccessControlEntryFilter, acl_binding::AclBinding};
+
+/// A filter which can match AclBinding objects.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AclBindingFilter {
+    pub pattern_filter: ResourcePatternFilter,
+    pub entry_filter: AccessControlEntryFilter,
+}
+
+impl AclBindingFilter {
+    /// A filter which matches any ACL binding.
+    pub const ANY: AclBindingFilter = AclBindingFilter {
+        pattern_filter: ResourcePatternFilter::ANY,
+        entry_filter: AccessControlEntryFilter::ANY,
+    };
+
+    /// Create an instance of this filter with the provided parameters.
+    ///
+    /// * `pattern_filter` - the resource pattern filter
+    /// * `entry_filter` - the access control entry filter
+    pub fn new(
+        pattern_filter: ResourcePatternFilter,
+        entry_filter: AccessControlEntryFilter,
+    ) -> AclBindingFilter {
+        AclBindingFilter {
+            pattern_filter,
+            entry_filter,
+        }
+    }
+
+    /// Returns true if this filter has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.pattern_filter.is_unknown() || self.entry_filter.is_unknown()
+    }
+
+    /// Return true if the resource and entry filters can only match one ACE. In other words, if
+    /// there are no ANY or UNKNOWN fields.
+    pub fn matches_at_most_one(&self) -> bool {
+        self.pattern_filter.matches_at_most_one() && self.entry_filter.matches_at_most_one()
+    }
+
+    /// Return a string describing an ANY or UNKNOWN field, or `None` if there is no such field.
+    pub fn find_indefinite_field(&self) -> Option<&'static str> {
+        self.pattern_filter
+            .find_indefinite_field()
+            .or_else(|| self.entry_filter.find_indefinite_field())
+    }
+
+    /// Return true if the resource filter matches the binding's resource and the entry filter
+    /// matches binding's entry.
+    pub fn matches(&self, binding: &AclBinding) -> bool {
+        self.pattern_filter.matches(&binding.pattern) && self.entry_filter.matches(&binding.entry)
+    }
+}
+
+impl Display for AclBindingFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(patternFilter={}, entryFilter={})",
+            self.pattern_filter, self.entry_filter
+        )
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt::{self, Display};
+
+/// Represents an operation which an ACL grants or denies permission to perform.
+///
+/// Some operations imply other operations:
+/// - `Allow All` implies `Allow` everything
+/// - `Deny All` implies `Deny` everything
+/// - `Allow Read` implies `Allow Describe`
+/// - `Allow Write` implies `Allow Describe`
+/// - `Allow Delete` implies `Allow Describe`
+/// - `Allow Alter` implies `Allow Describe`
+/// - `Allow AlterConfigs` implies `Allow DescribeConfigs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AclOperation {
+    /// Represents any AclOperation which this client cannot understand, perhaps because this
+    /// client is too old.
+    Unknown,
+    /// In a filter, matches any AclOperation.
+    Any,
+    /// ALL operation.
+    All,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Alter,
+    Describe,
+    ClusterAction,
+    DescribeConfigs,
+    AlterConfigs,
+    IdempotentWrite,
+}
+
+impl AclOperation {
+    pub fn code(&self) -> i8 {
+        match self {
+            AclOperation::Unknown => 0,
+            AclOperation::Any => 1,
+            AclOperation::All => 2,
+            AclOperation::Read => 3,
+            AclOperation::Write => 4,
+            AclOperation::Create => 5,
+            AclOperation::Delete => 6,
+            AclOperation::Alter => 7,
+            AclOperation::Describe => 8,
+            AclOperation::ClusterAction => 9,
+            AclOperation::DescribeConfigs => 10,
+            AclOperation::AlterConfigs => 11,
+            AclOperation::IdempotentWrite => 12,
+        }
+    }
+
+    /// Return the AclOperation with the provided code or `Unknown` if one cannot be found.
+    pub fn from_code(code: i8) -> AclOperation {
+        match code {
+            1 => AclOperation::Any,
+            2 => AclOperation::All,
+            3 => AclOperation::Read,
+            4 => AclOperation::Write,
+            5 => AclOperation::Create,
+            6 => AclOperation::Delete,
+            7 => AclOperation::Alter,
+            8 => AclOperation::Describe,
+            9 => AclOperation::ClusterAction,
+            10 => AclOperation::DescribeConfigs,
+            11 => AclOperation::AlterConfigs,
+            12 => AclOperation::IdempotentWrite,
+            _ => AclOperation::Unknown,
+        }
+    }
+
+    /// Return true if this operation is `Unknown`.
+    pub fn is_unknown(&self) -> bool {
+        *self == AclOperation::Unknown
+    }
+}
+
+impl Display for AclOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AclOperation::Unknown => "UNKNOWN",
+            AclOperation::Any => "ANY",
+            AclOperation::All => "ALL",
+            AclOperation::Read => "READ",
+            AclOperation::Write => "WRITE",
+            AclOperation::Create => "CREATE",
+            AclOperation::Delete => "DELETE",
+            AclOperation::Alter => "ALTER",
+            AclOperation::Describe => "DESCRIBE",
+            AclOperation::ClusterAction => "CLUSTER_ACTION",
+            AclOperation::DescribeConfigs => "DESCRIBE_CONFIGS",
+            AclOperation::AlterConfigs => "ALTER_CONFIGS",
+            AclOperation::IdempotentWrite => "IDEMPOTENT_WRITE",
+        })
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::{self, Display};
+
+/// Represents whether an ACL grants or denies permissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AclPermissionType {
+    /// Represents any AclPermissionType which this client cannot understand, perhaps because
+    /// this client is too old.
+    Unknown,
+    /// In a filter, matches any AclPermissionType.
+    Any,
+    /// Disallows access.
+    Deny,
+    /// Grants access.
+    Allow,
+}
+
+impl AclPermissionType {
+    pub fn code(&self) -> i8 {
+        match self {
+            AclPermissionType::Unknown => 0,
+            AclPermissionType::Any => 1,
+            AclPermissionType::Deny => 2,
+            AclPermissionType::Allow => 3,
+        }
+    }
+
+    /// Return the AclPermissionType with the provided code or `Unknown` if one cannot be found.
+    pub fn from_code(code: i8) -> AclPermissionType {
+        match code {
+            1 => AclPermissionType::Any,
+            2 => AclPermissionType::Deny,
+            3 => AclPermissionType::Allow,
+            _ => AclPermissionType::Unknown,
+        }
+    }
+
+    /// Return true if this permission type is `Unknown`.
+    pub fn is_unknown(&self) -> bool {
+        *self == AclPermissionType::Unknown
+    }
+}
+
+impl Display for AclPermissionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AclPermissionType::Unknown => "UNKNOWN",
+            AclPermissionType::Any => "ANY",
+            AclPermissionType::Deny => "DENY",
+            AclPermissionType::Allow => "ALLOW",
+        })
+    }
+}
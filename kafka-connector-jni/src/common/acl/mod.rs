@@ -0,0 +1,6 @@
+pub mod access_control_entry;
+pub mod access_control_entry_filter;
+pub mod acl_binding;
+pub mod acl_binding_filter;
+pub mod acl_operation;
+pub mod acl_permission_type;
@@ -0,0 +1,262 @@
+use std::collections::{HashMap, HashSet};
+
+use indexmap::IndexMap;
+use jni::{objects::JValue, JNIEnv};
+
+use crate::{
+    clone_from_java::{in_local_frame, non_null, CloneFromJava},
+    clone_to_java::CloneToJava,
+    jni_cache,
+};
+
+use super::{node::Node, partition_info::PartitionInfo, topic_partition::TopicPartition};
+
+/// An immutable representation of a subset of the nodes, topics, and partitions in the Kafka cluster.
+#[derive(Debug, Clone, Default)]
+pub struct Cluster {
+    pub cluster_id: Option<String>,
+    pub is_bootstrap_configured: bool,
+    pub nodes: Vec<Node>,
+    pub unauthorized_topics: HashSet<String>,
+    pub invalid_topics: HashSet<String>,
+    pub internal_topics: HashSet<String>,
+    pub controller: Option<Node>,
+    partitions_by_topic_partition: HashMap<TopicPartition, PartitionInfo>,
+    partitions_by_topic: IndexMap<String, Vec<PartitionInfo>>,
+    available_partitions_by_topic: HashMap<String, Vec<PartitionInfo>>,
+    partitions_by_node: HashMap<i32, Vec<PartitionInfo>>,
+    nodes_by_id: HashMap<i32, Node>,
+}
+
+impl Cluster {
+    pub fn new(
+        cluster_id: Option<String>,
+        nodes: Vec<Node>,
+        partitions: Vec<PartitionInfo>,
+        unauthorized_topics: HashSet<String>,
+        invalid_topics: HashSet<String>,
+        internal_topics: HashSet<String>,
+        controller: Option<Node>,
+    ) -> Cluster {
+        let mut nodes_by_id = HashMap::with_capacity(nodes.len());
+        let mut partitions_by_node: HashMap<i32, Vec<PartitionInfo>> =
+            HashMap::with_capacity(nodes.len());
+        for node in &nodes {
+            nodes_by_id.insert(node.id, node.clone());
+            partitions_by_node.insert(node.id, vec![]);
+        }
+
+        let mut partitions_by_topic_partition = HashMap::with_capacity(partitions.len());
+        let mut partitions_by_topic: IndexMap<String, Vec<PartitionInfo>> = IndexMap::new();
+        for partition in partitions {
+            if let Some(leader) = partition
+                .leader
+                .as_ref()
+                .filter(|leader| !leader.is_empty())
+            {
+                partitions_by_node
+                    .entry(leader.id)
+                    .or_default()
+                    .push(partition.clone());
+            }
+            partitions_by_topic
+                .entry(partition.topic.clone())
+                .or_default()
+                .push(partition.clone());
+            partitions_by_topic_partition.insert(
+                TopicPartition::new(partition.topic.clone(), partition.partition),
+                partition,
+            );
+        }
+
+        let available_partitions_by_topic = partitions_by_topic
+            .iter()
+            .map(|(topic, partitions)| {
+                let available = partitions
+                    .iter()
+                    .filter(|p| p.leader.is_some())
+                    .cloned()
+                    .collect();
+                (topic.clone(), available)
+            })
+            .collect();
+
+        Cluster {
+            cluster_id,
+            is_bootstrap_configured: false,
+            nodes,
+            unauthorized_topics,
+            invalid_topics,
+            internal_topics,
+            controller,
+            partitions_by_topic_partition,
+            partitions_by_topic,
+            available_partitions_by_topic,
+            partitions_by_node,
+            nodes_by_id,
+        }
+    }
+
+    /// Create an empty cluster instance with no nodes and no topic-partitions.
+    pub fn empty() -> Cluster {
+        Cluster::default()
+    }
+
+    /// Create a "bootstrap" cluster using the given list of host/ports
+    pub fn bootstrap(addresses: &[(String, i32)]) -> Cluster {
+        let nodes = addresses
+            .iter()
+            .enumerate()
+            .map(|(i, (host, port))| Node::new(-(i as i32) - 1, host.clone(), *port))
+            .collect();
+        Cluster {
+            is_bootstrap_configured: true,
+            ..Cluster::new(
+                None,
+                nodes,
+                vec![],
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+                None,
+            )
+        }
+    }
+
+    /// Return a copy of this cluster combined with `partitions`.
+    pub fn with_partitions(&self, partitions: HashMap<TopicPartition, PartitionInfo>) -> Cluster {
+        let mut combined = self.partitions_by_topic_partition.clone();
+        combined.extend(partitions);
+        Cluster::new(
+            self.cluster_id.clone(),
+            self.nodes.clone(),
+            combined.into_values().collect(),
+            self.unauthorized_topics.clone(),
+            self.invalid_topics.clone(),
+            self.internal_topics.clone(),
+            self.controller.clone(),
+        )
+    }
+
+    /// Get the node by the node id (or None if the node is not online or does not exist)
+    pub fn node_by_id(&self, id: i32) -> Option<&Node> {
+        self.nodes_by_id.get(&id)
+    }
+
+    /// Get the node by node id if the replica for the given partition is online
+    pub fn node_if_online(&self, partition: &TopicPartition, id: i32) -> Option<&Node> {
+        let node = self.node_by_id(id)?;
+        let partition_info = self.partition(partition)?;
+        if !partition_info.offline_replicas.contains(node) && partition_info.replicas.contains(node)
+        {
+            Some(node)
+        } else {
+            None
+        }
+    }
+
+    /// Get the current leader for the given topic-partition
+    pub fn leader_for(&self, topic_partition: &TopicPartition) -> Option<&Node> {
+        self.partitions_by_topic_partition
+            .get(topic_partition)
+            .and_then(|info| info.leader.as_ref())
+    }
+
+    /// Get the metadata for the specified partition
+    pub fn partition(&self, topic_partition: &TopicPartition) -> Option<&PartitionInfo> {
+        self.partitions_by_topic_partition.get(topic_partition)
+    }
+
+    /// Get the list of partitions for this topic
+    pub fn partitions_for_topic(&self, topic: &str) -> &[PartitionInfo] {
+        self.partitions_by_topic
+            .get(topic)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the number of partitions for the given topic.
+    pub fn partition_count_for_topic(&self, topic: &str) -> Option<usize> {
+        self.partitions_by_topic.get(topic).map(Vec::len)
+    }
+
+    /// Get the list of available partitions for this topic
+    pub fn available_partitions_for_topic(&self, topic: &str) -> &[PartitionInfo] {
+        self.available_partitions_by_topic
+            .get(topic)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get the list of partitions whose leader is this node
+    pub fn partitions_for_node(&self, node_id: i32) -> &[PartitionInfo] {
+        self.partitions_by_node
+            .get(&node_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Get all topics.
+    pub fn topics(&self) -> impl Iterator<Item = &String> {
+        self.partitions_by_topic.keys()
+    }
+}
+
+/// `org.apache.kafka.common.Cluster`, e.g. the one of the java producer metadata.
+impl CloneFromJava for Cluster {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let call = |name, sig| {
+            jni_cache::call_method(env, obj, "org/apache/kafka/common/Cluster", name, sig, &[])
+        };
+        let topics =
+            |name| HashSet::<String>::clone_from_java(env, call(name, "()Ljava/util/Set;")?);
+
+        let mut partitions = vec![];
+        for topic in topics("topics")? {
+            partitions.extend(in_local_frame(env, || {
+                let topic = topic.clone_to_java(env)?;
+                let topic_partitions = jni_cache::call_method(
+                    env,
+                    obj,
+                    "org/apache/kafka/common/Cluster",
+                    "partitionsForTopic",
+                    "(Ljava/lang/String;)Ljava/util/List;",
+                    &[topic],
+                )?;
+                Vec::<PartitionInfo>::clone_from_java(env, topic_partitions)
+            })?);
+        }
+        let cluster_resource = call(
+            "clusterResource",
+            "()Lorg/apache/kafka/common/ClusterResource;",
+        )?
+        .l()?;
+        let cluster_id = jni_cache::call_method(
+            env,
+            cluster_resource,
+            "org/apache/kafka/common/ClusterResource",
+            "clusterId",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+
+        let mut cluster = Cluster::new(
+            Option::<String>::clone_from_java(env, cluster_id)?,
+            Vec::<Node>::clone_from_java(env, call("nodes", "()Ljava/util/List;")?)?,
+            partitions,
+            topics("unauthorizedTopics")?,
+            topics("invalidTopics")?,
+            topics("internalTopics")?,
+            Option::<Node>::clone_from_java(
+                env,
+                call("controller", "()Lorg/apache/kafka/common/Node;")?,
+            )?,
+        );
+        cluster.is_bootstrap_configured = call("isBootstrapConfigured", "()Z")?.z()?;
+        Ok(cluster)
+    }
+}
@@ -0,0 +1,210 @@
+//! The LZ4 frame format as written by `KafkaLZ4BlockOutputStream` and read by
+//! `KafkaLZ4BlockInputStream`: independent blocks of at most 64KB, without block or content
+//! checksums.
+
+use bytes::{Buf, BufMut};
+use twox_hash::XxHash32;
+
+use crate::common::errors::{KafkaError, Result};
+
+pub const MAGIC: u32 = 0x184D_2204;
+pub const LZ4_FRAME_INCOMPRESSIBLE_MASK: u32 = 0x8000_0000;
+pub const BLOCKSIZE_64KB: u8 = 4;
+
+const FLG_VERSION: u8 = 1;
+const FLG_BLOCK_INDEPENDENCE: u8 = 1 << 5;
+const FLG_BLOCK_CHECKSUM: u8 = 1 << 4;
+const FLG_CONTENT_SIZE: u8 = 1 << 3;
+const FLG_CONTENT_CHECKSUM: u8 = 1 << 2;
+const FLG_RESERVED: u8 = 3;
+
+const PREMATURE_EOS: &str = "Stream ended prematurely";
+const NOT_SUPPORTED: &str = "Stream unsupported (invalid magic bytes)";
+const BLOCK_HASH_MISMATCH: &str = "Block checksum mismatch";
+const DESCRIPTOR_HASH_MISMATCH: &str = "Stream frame descriptor corrupted";
+
+/// Compress `data` into a single LZ4 frame.
+///
+/// * `use_broken_flag_descriptor_checksum` - Compute the header checksum over the magic bytes
+///   too, like clients writing magic v0 messages did
+pub fn compress(data: &[u8], use_broken_flag_descriptor_checksum: bool, out: &mut impl BufMut) {
+    let flg = FLG_VERSION << 6 | FLG_BLOCK_INDEPENDENCE;
+    let bd = BLOCKSIZE_64KB << 4;
+    let mut header = Vec::with_capacity(6);
+    header.put_u32_le(MAGIC);
+    header.put_u8(flg);
+    header.put_u8(bd);
+    let descriptor = if use_broken_flag_descriptor_checksum {
+        &header[..]
+    } else {
+        &header[4..]
+    };
+    let hash = (XxHash32::oneshot(0, descriptor) >> 8) as u8;
+    out.put_slice(&header);
+    out.put_u8(hash);
+
+    let max_block_size = block_maximum_size(BLOCKSIZE_64KB);
+    let mut compressed = vec![0; lz4_flex::block::get_maximum_output_size(max_block_size)];
+    for block in data.chunks(max_block_size) {
+        match lz4_flex::block::compress_into(block, &mut compressed) {
+            // Store block uncompressed if compressed length is greater (incompressible)
+            Ok(compressed_length) if compressed_length < block.len() => {
+                out.put_u32_le(compressed_length as u32);
+                out.put_slice(&compressed[..compressed_length]);
+            }
+            _ => {
+                out.put_u32_le(block.len() as u32 | LZ4_FRAME_INCOMPRESSIBLE_MASK);
+                out.put_slice(block);
+            }
+        }
+    }
+    // end mark
+    out.put_u32_le(0);
+}
+
+/// Decompress a single LZ4 frame.
+///
+/// * `ignore_flag_descriptor_checksum` - For compatibility with old kafka clients, ignore
+///   incorrect HC byte
+pub fn decompress(mut input: &[u8], ignore_flag_descriptor_checksum: bool) -> Result<Vec<u8>> {
+    if input.remaining() < 6 {
+        return Err(error(PREMATURE_EOS));
+    }
+    if input.get_u32_le() != MAGIC {
+        return Err(error(NOT_SUPPORTED));
+    }
+    let descriptor_start = input;
+    let flg = input.get_u8();
+    validate_flg(flg)?;
+    let bd = input.get_u8();
+    let block_size_value = (bd >> 4) & 7;
+    if bd & 0x8f != 0 || block_size_value < BLOCKSIZE_64KB {
+        return Err(error("Reserved bits of the block descriptor must be 0"));
+    }
+    let max_block_size = block_maximum_size(block_size_value);
+    if flg & FLG_CONTENT_SIZE != 0 {
+        if input.remaining() < 8 {
+            return Err(error(PREMATURE_EOS));
+        }
+        input.advance(8);
+    }
+    if !input.has_remaining() {
+        return Err(error(PREMATURE_EOS));
+    }
+    // Final byte of Frame Descriptor is HC checksum
+    let descriptor = &descriptor_start[..descriptor_start.len() - input.len()];
+    let hash = input.get_u8();
+    if !ignore_flag_descriptor_checksum && hash != (XxHash32::oneshot(0, descriptor) >> 8) as u8 {
+        return Err(error(DESCRIPTOR_HASH_MISMATCH));
+    }
+
+    let mut out = Vec::new();
+    let mut decompression_buffer = vec![0; max_block_size];
+    loop {
+        if input.remaining() < 4 {
+            return Err(error(PREMATURE_EOS));
+        }
+        let block_size = input.get_u32_le();
+        let compressed = block_size & LZ4_FRAME_INCOMPRESSIBLE_MASK == 0;
+        let block_size = (block_size & !LZ4_FRAME_INCOMPRESSIBLE_MASK) as usize;
+        // Check for EndMark
+        if block_size == 0 {
+            if flg & FLG_CONTENT_CHECKSUM != 0 {
+                if input.remaining() < 4 {
+                    return Err(error(PREMATURE_EOS));
+                }
+                input.advance(4);
+            }
+            return Ok(out);
+        } else if block_size > max_block_size {
+            return Err(error(&format!(
+                "Block size {} exceeded max: {}",
+                block_size, max_block_size
+            )));
+        }
+        if input.remaining() < block_size {
+            return Err(error(PREMATURE_EOS));
+        }
+        let block = &input[..block_size];
+        if compressed {
+            let size = lz4_flex::block::decompress_into(block, &mut decompression_buffer)
+                .map_err(|e| error(&e.to_string()))?;
+            out.extend_from_slice(&decompression_buffer[..size]);
+        } else {
+            out.extend_from_slice(block);
+        }
+        input.advance(block_size);
+        // verify checksum
+        if flg & FLG_BLOCK_CHECKSUM != 0 {
+            if input.remaining() < 4 {
+                return Err(error(PREMATURE_EOS));
+            }
+            if XxHash32::oneshot(0, block) != input.get_u32_le() {
+                return Err(error(BLOCK_HASH_MISMATCH));
+            }
+        }
+    }
+}
+
+fn validate_flg(flg: u8) -> Result<()> {
+    if flg & FLG_RESERVED != 0 {
+        return Err(error("Reserved bits must be 0"));
+    }
+    if flg & FLG_BLOCK_INDEPENDENCE == 0 {
+        return Err(error(
+            "Dependent block stream is unsupported (BLOCK_INDEPENDENCE must be set)",
+        ));
+    }
+    if flg >> 6 != FLG_VERSION {
+        return Err(error(&format!("Version {} is unsupported", flg >> 6)));
+    }
+    Ok(())
+}
+
+fn block_maximum_size(block_size_value: u8) -> usize {
+    1 << (2 * block_size_value + 8)
+}
+
+fn error(message: &str) -> KafkaError {
+    KafkaError::Kafka(message.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress};
+
+    #[test]
+    fn compressed_data_round_trips() {
+        let text: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| format!("record-{} ", i % 977).into_bytes())
+            .collect();
+        let noise: Vec<u8> = (0..70_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        for data in [&b""[..], b"a", &text, &noise] {
+            let mut out = Vec::new();
+            compress(data, false, &mut out);
+            assert_eq!(data, &decompress(&out, false).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn broken_header_checksum_is_only_read_when_ignored() {
+        let mut out = Vec::new();
+        compress(b"value", true, &mut out);
+        assert!(decompress(&out, false).is_err());
+        assert_eq!(b"value", &decompress(&out, true).unwrap()[..]);
+    }
+
+    #[test]
+    fn reads_frame_header_of_the_java_client() {
+        // KafkaLZ4BlockOutputStream of an empty input: magic, FLG 0x60, BD 0x40, HC 0x82, end mark
+        let frame = [
+            0x04, 0x22, 0x4d, 0x18, 0x60, 0x40, 0x82, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert!(decompress(&frame, false).unwrap().is_empty());
+        let mut out = Vec::new();
+        compress(b"", false, &mut out);
+        assert_eq!(&frame[..], &out[..]);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod kafka_lz4;
+pub mod snappy;
@@ -0,0 +1,294 @@
+//! Snappy compression in the framing of xerial's `SnappyOutputStream`, which the java client
+//! uses for `CompressionType.SNAPPY`. The framed stream starts with a magic header followed by
+//! chunks, each prefixed with its compressed length and holding one raw snappy block.
+
+use bytes::{Buf, BufMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    utils::byte_utils::{read_unsigned_varint, write_unsigned_varint},
+};
+
+const MAGIC_HEADER: [u8; 8] = [0x82, b'S', b'N', b'A', b'P', b'P', b'Y', 0];
+const DEFAULT_VERSION: i32 = 1;
+const MINIMUM_COMPATIBLE_VERSION: i32 = 1;
+const HEADER_SIZE: usize = MAGIC_HEADER.len() + 8;
+
+/// Size of the chunks written by `SnappyOutputStream`
+const DEFAULT_CHUNK_SIZE: usize = 32 * 1024;
+
+/// Raw snappy compresses independent blocks of at most 64KB, so copy offsets fit in two bytes
+const MAX_BLOCK_SIZE: usize = 1 << 16;
+const HASH_TABLE_BITS: u32 = 14;
+const MIN_MATCH: usize = 4;
+
+const TAG_LITERAL: u8 = 0;
+const TAG_COPY_1: u8 = 1;
+const TAG_COPY_2: u8 = 2;
+
+/// Compress `data` into a framed snappy stream.
+pub fn compress(data: &[u8], out: &mut impl BufMut) {
+    out.put_slice(&MAGIC_HEADER);
+    out.put_i32(DEFAULT_VERSION);
+    out.put_i32(MINIMUM_COMPATIBLE_VERSION);
+    let mut compressed = Vec::new();
+    for chunk in data.chunks(DEFAULT_CHUNK_SIZE) {
+        compressed.clear();
+        compress_raw(chunk, &mut compressed);
+        out.put_i32(compressed.len() as i32);
+        out.put_slice(&compressed);
+    }
+}
+
+/// Decompress a framed snappy stream. Like `SnappyInputStream`, data without the magic header
+/// is read as a single raw snappy block.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    if data.len() < HEADER_SIZE || data[..MAGIC_HEADER.len()] != MAGIC_HEADER {
+        let mut out = Vec::new();
+        decompress_raw(data, &mut out)?;
+        return Ok(out);
+    }
+    let mut input = &data[MAGIC_HEADER.len()..];
+    input.advance(4);
+    let compatible_version = input.get_i32();
+    if compatible_version > DEFAULT_VERSION {
+        return Err(KafkaError::Kafka(format!(
+            "Incompatible snappy stream version {}",
+            compatible_version
+        )));
+    }
+    let mut out = Vec::new();
+    while input.has_remaining() {
+        if input.remaining() < 4 {
+            return Err(premature_end());
+        }
+        let chunk_size = input.get_i32();
+        if chunk_size < 0 || input.remaining() < chunk_size as usize {
+            return Err(premature_end());
+        }
+        let (chunk, rest) = input.split_at(chunk_size as usize);
+        decompress_raw(chunk, &mut out)?;
+        input = rest;
+    }
+    Ok(out)
+}
+
+/// Compress `data` into a raw snappy block, appending it to `out`.
+pub fn compress_raw(data: &[u8], out: &mut Vec<u8>) {
+    write_unsigned_varint(data.len() as u32, out);
+    for block in data.chunks(MAX_BLOCK_SIZE) {
+        compress_block(block, out);
+    }
+}
+
+fn compress_block(input: &[u8], out: &mut Vec<u8>) {
+    // Positions are relative to the block, which is at most 64KB. An empty slot reads as position
+    // zero, candidates are always verified against the input.
+    let mut table = vec![0u16; 1 << HASH_TABLE_BITS];
+    let mut literal_start = 0;
+    let mut position = 1;
+    // Incompressible data is skipped faster the longer no match is found
+    let mut skip = 32;
+    while position + MIN_MATCH <= input.len() {
+        let word = load_u32(input, position);
+        let slot = hash(word);
+        let candidate = table[slot] as usize;
+        table[slot] = position as u16;
+        if load_u32(input, candidate) != word {
+            position += skip >> 5;
+            skip += 1;
+            continue;
+        }
+        let mut length = MIN_MATCH;
+        while position + length < input.len()
+            && input[candidate + length] == input[position + length]
+        {
+            length += 1;
+        }
+        emit_literal(&input[literal_start..position], out);
+        emit_copy(position - candidate, length, out);
+        position += length;
+        literal_start = position;
+        skip = 32;
+    }
+    if literal_start < input.len() {
+        emit_literal(&input[literal_start..], out);
+    }
+}
+
+fn load_u32(input: &[u8], position: usize) -> u32 {
+    (&input[position..]).get_u32_le()
+}
+
+fn hash(word: u32) -> usize {
+    (word.wrapping_mul(0x1e35_a7bd) >> (32 - HASH_TABLE_BITS)) as usize
+}
+
+fn emit_literal(literal: &[u8], out: &mut Vec<u8>) {
+    if literal.is_empty() {
+        return;
+    }
+    let n = literal.len() - 1;
+    if n < 60 {
+        out.push((n as u8) << 2 | TAG_LITERAL);
+    } else {
+        let bytes = (4 - (n as u32).leading_zeros() as usize / 8).max(1);
+        out.push(((59 + bytes) as u8) << 2 | TAG_LITERAL);
+        out.extend_from_slice(&(n as u32).to_le_bytes()[..bytes]);
+    }
+    out.extend_from_slice(literal);
+}
+
+fn emit_copy(offset: usize, mut length: usize, out: &mut Vec<u8>) {
+    // Copies are at most 64 bytes long, leave at least 4 bytes for the last one
+    while length >= 68 {
+        emit_copy_2(offset, 64, out);
+        length -= 64;
+    }
+    if length > 64 {
+        emit_copy_2(offset, 60, out);
+        length -= 60;
+    }
+    if length < 12 && offset < 2048 {
+        out.push(((offset >> 8) as u8) << 5 | ((length - 4) as u8) << 2 | TAG_COPY_1);
+        out.push(offset as u8);
+    } else {
+        emit_copy_2(offset, length, out);
+    }
+}
+
+fn emit_copy_2(offset: usize, length: usize, out: &mut Vec<u8>) {
+    out.push(((length - 1) as u8) << 2 | TAG_COPY_2);
+    out.extend_from_slice(&(offset as u16).to_le_bytes());
+}
+
+/// Decompress a raw snappy block, appending the result to `out`.
+pub fn decompress_raw(mut input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    let uncompressed_length = read_unsigned_varint(&mut input)
+        .map_err(|_| corrupt("invalid uncompressed length"))?
+        as usize;
+    let start = out.len();
+    let end = start + uncompressed_length;
+    out.reserve(uncompressed_length);
+    while input.has_remaining() {
+        let tag = input.get_u8();
+        let (offset, length) = match tag & 3 {
+            TAG_LITERAL => {
+                let mut length = (tag >> 2) as usize;
+                if length >= 60 {
+                    let bytes = length - 59;
+                    if input.remaining() < bytes {
+                        return Err(corrupt("literal length is truncated"));
+                    }
+                    length = input.get_uint_le(bytes) as usize;
+                }
+                length += 1;
+                if input.remaining() < length || out.len() + length > end {
+                    return Err(corrupt("literal exceeds the block"));
+                }
+                out.extend_from_slice(&input[..length]);
+                input.advance(length);
+                continue;
+            }
+            TAG_COPY_1 => {
+                if !input.has_remaining() {
+                    return Err(corrupt("copy offset is truncated"));
+                }
+                let offset = ((tag as usize >> 5) << 8) | input.get_u8() as usize;
+                (offset, 4 + ((tag >> 2) & 7) as usize)
+            }
+            TAG_COPY_2 => {
+                if input.remaining() < 2 {
+                    return Err(corrupt("copy offset is truncated"));
+                }
+                (input.get_u16_le() as usize, (tag >> 2) as usize + 1)
+            }
+            // four byte offset copy
+            _ => {
+                if input.remaining() < 4 {
+                    return Err(corrupt("copy offset is truncated"));
+                }
+                (input.get_u32_le() as usize, (tag >> 2) as usize + 1)
+            }
+        };
+        if offset == 0 || offset > out.len() - start || out.len() + length > end {
+            return Err(corrupt("copy exceeds the block"));
+        }
+        // copies may overlap the bytes they produce, so they are appended one at a time
+        for _ in 0..length {
+            out.push(out[out.len() - offset]);
+        }
+    }
+    if out.len() != end {
+        return Err(corrupt("block is shorter than its uncompressed length"));
+    }
+    Ok(())
+}
+
+fn premature_end() -> KafkaError {
+    KafkaError::Kafka("Snappy stream ended prematurely".to_owned())
+}
+
+fn corrupt(reason: &str) -> KafkaError {
+    KafkaError::Kafka(format!("Corrupt snappy block: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, compress_raw, decompress, decompress_raw};
+
+    fn round_trip(data: &[u8]) {
+        let mut raw = Vec::new();
+        compress_raw(data, &mut raw);
+        let mut decompressed = Vec::new();
+        decompress_raw(&raw, &mut decompressed).unwrap();
+        assert_eq!(data, &decompressed[..]);
+
+        let mut framed = Vec::new();
+        compress(data, &mut framed);
+        assert_eq!(data, &decompress(&framed).unwrap()[..]);
+    }
+
+    #[test]
+    fn compressed_data_round_trips() {
+        round_trip(b"");
+        round_trip(b"a");
+        round_trip(b"abcabcabcabcabcabcabcabcabcabcabcabcabcabcabc");
+        round_trip(&vec![7; 200_000]);
+        let text: Vec<u8> = (0..100_000u32)
+            .flat_map(|i| format!("record-{} ", i % 977).into_bytes())
+            .collect();
+        round_trip(&text);
+        let noise: Vec<u8> = (0..70_000u32)
+            .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect();
+        round_trip(&noise);
+    }
+
+    #[test]
+    fn repeated_data_is_compressed() {
+        let mut raw = Vec::new();
+        compress_raw(&[1; 10_000], &mut raw);
+        assert!(raw.len() < 500);
+    }
+
+    #[test]
+    fn reads_literals_and_overlapping_copies() {
+        // length 23, the literal "Kafka " and a 17 byte copy at offset 6
+        let block = [
+            0x17, 0x14, b'K', b'a', b'f', b'k', b'a', b' ', 0x42, 0x06, 0x00,
+        ];
+        let mut out = Vec::new();
+        decompress_raw(&block, &mut out).unwrap();
+        assert_eq!(b"Kafka Kafka Kafka Kafka", &out[..]);
+    }
+
+    #[test]
+    fn corrupt_blocks_are_rejected() {
+        let mut out = Vec::new();
+        // a copy pointing before the start of the block
+        assert!(decompress_raw(&[0x08, 0x01, 0x10], &mut out).is_err());
+        // a block shorter than its uncompressed length
+        assert!(decompress_raw(&[0x05, 0x00, b'a'], &mut out).is_err());
+    }
+}
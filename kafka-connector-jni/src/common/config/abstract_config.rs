@@ -0,0 +1,200 @@
+use std::{
+    collections::HashSet,
+    sync::{Mutex, MutexGuard},
+};
+
+use indexmap::IndexMap;
+use log::{info, warn};
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{
+    config_def::{ConfigDef, ConfigValue},
+    types::password::Password,
+};
+
+/// A convenient base class for configurations to extend.
+///
+/// This class holds both the original configuration that was provided as well as the parsed values.
+pub struct AbstractConfig {
+    /// Configs for which values have been requested, used to detect unused configs
+    used: Mutex<HashSet<String>>,
+    /// The original configs that the user specified
+    originals: IndexMap<String, String>,
+    /// The parsed values
+    values: IndexMap<String, Option<ConfigValue>>,
+}
+
+impl AbstractConfig {
+    /// Construct a configuration with a ConfigDef and the configuration properties.
+    ///
+    /// * `definition` - the definition of the configurations
+    /// * `originals` - the configuration properties
+    /// * `config_name` - name used in the log entry listing all the values
+    /// * `do_log` - whether the configurations should be logged
+    /// * `post_process` - computes values which depend on other configs, the returned values override the parsed
+    ///   ones and are validated against `definition`
+    pub fn new<F>(
+        definition: &ConfigDef,
+        originals: IndexMap<String, String>,
+        config_name: &str,
+        do_log: bool,
+        post_process: F,
+    ) -> Result<AbstractConfig>
+    where
+        F: FnOnce(&AbstractConfig) -> Result<IndexMap<String, Option<ConfigValue>>>,
+    {
+        let values = definition.parse(&originals)?;
+        let mut config = AbstractConfig {
+            used: Mutex::new(HashSet::new()),
+            originals,
+            values,
+        };
+        let updates = post_process(&config)?;
+        for (name, value) in updates {
+            let value = definition.parse_typed_value(&name, value.as_ref())?;
+            config.values.insert(name, value);
+        }
+        if do_log {
+            config.log_all(config_name);
+        }
+        Ok(config)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.used.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Get the parsed value of `key`, `None` for configs set to null.
+    ///
+    /// Fails with `Config` when `key` isn't defined.
+    pub fn get(&self, key: &str) -> Result<Option<&ConfigValue>> {
+        let value = self
+            .values
+            .get(key)
+            .ok_or_else(|| KafkaError::Config(format!("Unknown configuration '{}'", key)))?;
+        self.lock().insert(key.to_owned());
+        Ok(value.as_ref())
+    }
+
+    pub fn get_boolean(&self, key: &str) -> Result<Option<bool>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Boolean(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<Option<i32>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Int(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_short(&self, key: &str) -> Result<Option<i16>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Short(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_long(&self, key: &str) -> Result<Option<i64>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Long(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_double(&self, key: &str) -> Result<Option<f64>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Double(value)) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_string(&self, key: &str) -> Result<Option<String>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::String(value)) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn get_list(&self, key: &str) -> Result<Option<Vec<String>>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::List(value)) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Returns the fully qualified name of the configured class.
+    pub fn get_class(&self, key: &str) -> Result<Option<String>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Class(value)) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    pub fn get_password(&self, key: &str) -> Result<Option<Password>> {
+        Ok(match self.get(key)? {
+            Some(ConfigValue::Password(value)) => Some(value.clone()),
+            _ => None,
+        })
+    }
+
+    /// Names of the original configs which were never requested.
+    pub fn unused(&self) -> HashSet<String> {
+        let used = self.lock();
+        self.originals
+            .keys()
+            .filter(|key| !used.contains(*key))
+            .cloned()
+            .collect()
+    }
+
+    pub fn originals(&self) -> &IndexMap<String, String> {
+        &self.originals
+    }
+
+    /// Gets all original settings with the given prefix, stripping the prefix before adding it to the output.
+    pub fn originals_with_prefix(&self, prefix: &str) -> IndexMap<String, String> {
+        self.originals
+            .iter()
+            .filter_map(|(key, value)| {
+                key.strip_prefix(prefix)
+                    .map(|key| (key.to_owned(), value.clone()))
+            })
+            .collect()
+    }
+
+    /// All parsed values, `None` for configs set to null.
+    pub fn values(&self) -> &IndexMap<String, Option<ConfigValue>> {
+        &self.values
+    }
+
+    fn log_all(&self, config_name: &str) {
+        let mut names: Vec<&String> = self.values.keys().collect();
+        names.sort();
+        let mut b = format!("{} values: \n", config_name);
+        for name in names {
+            b.push('\t');
+            b.push_str(name);
+            b.push_str(" = ");
+            match &self.values[name] {
+                Some(value) => b.push_str(&value.to_string()),
+                None => b.push_str("null"),
+            }
+            b.push('\n');
+        }
+        info!("{}", b);
+    }
+
+    /// Log warnings for any unused configurations
+    pub fn log_unused(&self) {
+        for key in self.unused() {
+            warn!(
+                "The configuration '{}' was supplied but isn't a known config.",
+                key
+            );
+        }
+    }
+}
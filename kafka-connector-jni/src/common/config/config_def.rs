@@ -0,0 +1,983 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    sync::Arc,
+};
+
+use indexmap::IndexMap;
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{sasl_configs, ssl_configs, types::password::Password};
+
+/// The config types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Type {
+    Boolean,
+    String,
+    Int,
+    Short,
+    Long,
+    Double,
+    List,
+    Class,
+    Password,
+}
+
+impl Type {
+    pub fn is_sensitive(&self) -> bool {
+        *self == Type::Password
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Type::Boolean => "boolean",
+            Type::String => "string",
+            Type::Int => "int",
+            Type::Short => "short",
+            Type::Long => "long",
+            Type::Double => "double",
+            Type::List => "list",
+            Type::Class => "class",
+            Type::Password => "password",
+        })
+    }
+}
+
+/// The importance level for a configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Importance {
+    High,
+    Medium,
+    Low,
+}
+
+/// A parsed configuration value, one variant per config `Type`.
+///
+/// `Class` values hold the fully qualified name of the Java class, as classes are loaded on the
+/// Java side.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Boolean(bool),
+    String(String),
+    Int(i32),
+    Short(i16),
+    Long(i64),
+    Double(f64),
+    List(Vec<String>),
+    Class(String),
+    Password(Password),
+}
+
+impl ConfigValue {
+    /// Name of the Java class the value is represented with, used in error messages.
+    fn java_class_name(&self) -> &'static str {
+        match self {
+            ConfigValue::Boolean(_) => "java.lang.Boolean",
+            ConfigValue::String(_) => "java.lang.String",
+            ConfigValue::Int(_) => "java.lang.Integer",
+            ConfigValue::Short(_) => "java.lang.Short",
+            ConfigValue::Long(_) => "java.lang.Long",
+            ConfigValue::Double(_) => "java.lang.Double",
+            ConfigValue::List(_) => "java.util.List",
+            ConfigValue::Class(_) => "java.lang.Class",
+            ConfigValue::Password(_) => "org.apache.kafka.common.config.types.Password",
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            ConfigValue::Int(value) => Some(*value as f64),
+            ConfigValue::Short(value) => Some(*value as f64),
+            ConfigValue::Long(value) => Some(*value as f64),
+            ConfigValue::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ConfigValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigValue::Boolean(value) => write!(f, "{}", value),
+            ConfigValue::String(value) | ConfigValue::Class(value) => f.write_str(value),
+            ConfigValue::Int(value) => write!(f, "{}", value),
+            ConfigValue::Short(value) => write!(f, "{}", value),
+            ConfigValue::Long(value) => write!(f, "{}", value),
+            // Debug keeps the fractional part of whole numbers (1.0), like Java does
+            ConfigValue::Double(value) => write!(f, "{:?}", value),
+            ConfigValue::List(value) => write!(f, "[{}]", value.join(", ")),
+            ConfigValue::Password(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for ConfigValue {
+    fn from(value: bool) -> Self {
+        ConfigValue::Boolean(value)
+    }
+}
+
+impl From<&str> for ConfigValue {
+    fn from(value: &str) -> Self {
+        ConfigValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for ConfigValue {
+    fn from(value: String) -> Self {
+        ConfigValue::String(value)
+    }
+}
+
+impl From<i32> for ConfigValue {
+    fn from(value: i32) -> Self {
+        ConfigValue::Int(value)
+    }
+}
+
+impl From<i16> for ConfigValue {
+    fn from(value: i16) -> Self {
+        ConfigValue::Short(value)
+    }
+}
+
+impl From<i64> for ConfigValue {
+    fn from(value: i64) -> Self {
+        ConfigValue::Long(value)
+    }
+}
+
+impl From<f64> for ConfigValue {
+    fn from(value: f64) -> Self {
+        ConfigValue::Double(value)
+    }
+}
+
+impl From<Vec<String>> for ConfigValue {
+    fn from(value: Vec<String>) -> Self {
+        ConfigValue::List(value)
+    }
+}
+
+impl From<Password> for ConfigValue {
+    fn from(value: Password) -> Self {
+        ConfigValue::Password(value)
+    }
+}
+
+/// Default value of a config key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultValue {
+    /// The config is required, parsing fails when it is not set.
+    NoDefault,
+    /// The config is optional and defaults to null.
+    Null,
+    Value(ConfigValue),
+}
+
+impl<T: Into<ConfigValue>> From<T> for DefaultValue {
+    fn from(value: T) -> Self {
+        DefaultValue::Value(value.into())
+    }
+}
+
+/// Creates the error returned for an invalid config value (`ConfigException`).
+pub fn config_exception(name: &str, value: impl Display, message: &str) -> KafkaError {
+    KafkaError::Config(format!(
+        "Invalid value {} for configuration {}: {}",
+        value, name, message
+    ))
+}
+
+fn display_or_null(value: Option<&ConfigValue>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_owned(),
+    }
+}
+
+/// Validation logic the user may provide to perform single configuration validation.
+///
+/// The `Display` implementation describes the valid values in the documentation.
+pub trait Validator: Send + Sync + Display {
+    /// Perform single configuration validation.
+    ///
+    /// * `name` - The name of the configuration
+    /// * `value` - The value of the configuration, `None` for null
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()>;
+}
+
+/// Validation logic for numeric ranges
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    min: Option<ConfigValue>,
+    max: Option<ConfigValue>,
+}
+
+impl Range {
+    /// A numeric range that checks only the lower bound
+    pub fn at_least(min: impl Into<ConfigValue>) -> Range {
+        Range {
+            min: Some(min.into()),
+            max: None,
+        }
+    }
+
+    /// A numeric range that checks both the upper (inclusive) and lower bound
+    pub fn between(min: impl Into<ConfigValue>, max: impl Into<ConfigValue>) -> Range {
+        Range {
+            min: Some(min.into()),
+            max: Some(max.into()),
+        }
+    }
+}
+
+impl Validator for Range {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        let value = match value {
+            Some(value) => value,
+            None => return Err(config_exception(name, "null", "Value must be non-null")),
+        };
+        let number = value.as_f64().unwrap_or(f64::NAN);
+        if let Some(min) = &self.min {
+            if matches!(min.as_f64(), Some(min) if number < min) {
+                return Err(config_exception(
+                    name,
+                    value,
+                    &format!("Value must be at least {}", min),
+                ));
+            }
+        }
+        if let Some(max) = &self.max {
+            if matches!(max.as_f64(), Some(max) if number > max) {
+                return Err(config_exception(
+                    name,
+                    value,
+                    &format!("Value must be no more than {}", max),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.min, &self.max) {
+            (None, None) => f.write_str("[...]"),
+            (None, Some(max)) => write!(f, "[...,{}]", max),
+            (Some(min), None) => write!(f, "[{},...]", min),
+            (Some(min), Some(max)) => write!(f, "[{},...,{}]", min, max),
+        }
+    }
+}
+
+/// Validation logic for string values limited to a fixed set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidString {
+    valid_strings: Vec<String>,
+}
+
+impl ValidString {
+    pub fn one_of(valid_strings: &[&str]) -> ValidString {
+        ValidString {
+            valid_strings: valid_strings.iter().map(|s| (*s).to_owned()).collect(),
+        }
+    }
+
+    fn ensure_valid_string(&self, name: &str, value: &str) -> Result<()> {
+        if !self.valid_strings.iter().any(|s| s == value) {
+            return Err(config_exception(
+                name,
+                value,
+                &format!("String must be one of: {}", self.valid_strings.join(", ")),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validator for ValidString {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        match value {
+            Some(ConfigValue::String(value)) => self.ensure_valid_string(name, value),
+            value => self.ensure_valid_string(name, &display_or_null(value)),
+        }
+    }
+}
+
+impl Display for ValidString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.valid_strings.join(", "))
+    }
+}
+
+/// Validation logic for string values limited to a fixed set, ignoring the case
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseInsensitiveValidString {
+    valid_strings: Vec<String>,
+}
+
+impl CaseInsensitiveValidString {
+    pub fn one_of(valid_strings: &[&str]) -> CaseInsensitiveValidString {
+        CaseInsensitiveValidString {
+            valid_strings: valid_strings.iter().map(|s| s.to_uppercase()).collect(),
+        }
+    }
+}
+
+impl Validator for CaseInsensitiveValidString {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        let valid = match value {
+            Some(ConfigValue::String(value)) => self.valid_strings.contains(&value.to_uppercase()),
+            _ => false,
+        };
+        if !valid {
+            return Err(config_exception(
+                name,
+                display_or_null(value),
+                &format!(
+                    "String must be one of (case insensitive): {}",
+                    self.valid_strings.join(", ")
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Display for CaseInsensitiveValidString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(case insensitive) [{}]", self.valid_strings.join(", "))
+    }
+}
+
+/// Validation logic for lists whose every element is limited to a fixed set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidList {
+    valid_string: ValidString,
+}
+
+impl ValidList {
+    pub fn one_of(valid_strings: &[&str]) -> ValidList {
+        ValidList {
+            valid_string: ValidString::one_of(valid_strings),
+        }
+    }
+}
+
+impl Validator for ValidList {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        if let Some(ConfigValue::List(values)) = value {
+            for value in values {
+                self.valid_string.ensure_valid_string(name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ValidList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.valid_string.fmt(f)
+    }
+}
+
+/// Rejects empty strings, null is allowed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NonEmptyString;
+
+impl Validator for NonEmptyString {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        if let Some(ConfigValue::String(value)) = value {
+            if value.is_empty() {
+                return Err(config_exception(name, value, "String must be non-empty"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for NonEmptyString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("non-empty string")
+    }
+}
+
+/// Rejects null values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NonNullValidator;
+
+impl Validator for NonNullValidator {
+    fn ensure_valid(&self, name: &str, value: Option<&ConfigValue>) -> Result<()> {
+        if value.is_none() {
+            // Pass in the string null to avoid the spotbugs warning
+            return Err(config_exception(name, "null", "entry must be non null"));
+        }
+        Ok(())
+    }
+}
+
+impl Display for NonNullValidator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("non-null string")
+    }
+}
+
+/// Definition of a single config
+#[derive(Clone)]
+pub struct ConfigKey {
+    pub name: String,
+    pub type_: Type,
+    pub documentation: String,
+    pub default_value: DefaultValue,
+    pub validator: Option<Arc<dyn Validator>>,
+    pub importance: Importance,
+    pub internal_config: bool,
+}
+
+impl ConfigKey {
+    /// Fails with `Config` if the default value can't be parsed as `type_` or isn't accepted by the validator.
+    pub fn new(
+        name: impl Into<String>,
+        type_: Type,
+        default_value: DefaultValue,
+        validator: Option<Arc<dyn Validator>>,
+        importance: Importance,
+        documentation: impl Into<String>,
+        internal_config: bool,
+    ) -> Result<ConfigKey> {
+        let name = name.into();
+        let default_value = match default_value {
+            DefaultValue::Value(value) => DefaultValue::Value(parse_type(&name, &value, type_)?),
+            default_value => default_value,
+        };
+        if let Some(validator) = &validator {
+            match &default_value {
+                DefaultValue::NoDefault => {}
+                DefaultValue::Null => validator.ensure_valid(&name, None)?,
+                DefaultValue::Value(value) => validator.ensure_valid(&name, Some(value))?,
+            }
+        }
+        Ok(ConfigKey {
+            name,
+            type_,
+            documentation: documentation.into(),
+            default_value,
+            validator,
+            importance,
+            internal_config,
+        })
+    }
+
+    pub fn has_default(&self) -> bool {
+        self.default_value != DefaultValue::NoDefault
+    }
+}
+
+/// This struct is used for specifying the set of expected configurations. For each configuration, you can specify
+/// the name, the type, the default value, the documentation, the importance and the validator of the configuration.
+///
+/// This struct can be used as follows:
+///
+/// ```ignore
+/// let def = ConfigDef::new()
+///     .define("config_with_default", Type::String, "default string value", Importance::High, "Configuration with default value.")?
+///     .define_with_validator("config_with_validator", Type::Int, 42, Range::at_least(0), Importance::High, "Configuration with user provided validator.")?;
+///
+/// let configs = def.parse(&props)?;
+///
+/// let an_int = configs["config_with_validator"];
+/// let a_string = configs["config_with_default"];
+/// ```
+///
+/// Defining the same name twice or using a default value which doesn't match the type or validator fails with
+/// `Config`.
+#[derive(Clone, Default)]
+pub struct ConfigDef {
+    config_keys: IndexMap<String, ConfigKey>,
+}
+
+impl ConfigDef {
+    pub fn new() -> ConfigDef {
+        ConfigDef::default()
+    }
+
+    /// Returns unmodifiable set of properties names defined in this ConfigDef
+    pub fn names(&self) -> HashSet<String> {
+        self.config_keys.keys().cloned().collect()
+    }
+
+    /// Get the configuration keys
+    pub fn config_keys(&self) -> &IndexMap<String, ConfigKey> {
+        &self.config_keys
+    }
+
+    /// Default values of the configs which have one.
+    pub fn default_values(&self) -> IndexMap<String, Option<ConfigValue>> {
+        self.config_keys
+            .values()
+            .filter_map(|key| match &key.default_value {
+                DefaultValue::NoDefault => None,
+                DefaultValue::Null => Some((key.name.clone(), None)),
+                DefaultValue::Value(value) => Some((key.name.clone(), Some(value.clone()))),
+            })
+            .collect()
+    }
+
+    /// Define a new configuration
+    ///
+    /// Fails with `Config` if the key is already defined.
+    pub fn define_key(mut self, key: ConfigKey) -> Result<ConfigDef> {
+        if self.config_keys.contains_key(&key.name) {
+            return Err(KafkaError::Config(format!(
+                "Configuration {} is defined twice.",
+                key.name
+            )));
+        }
+        self.config_keys.insert(key.name.clone(), key);
+        Ok(self)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn define_checked(
+        self,
+        name: &str,
+        type_: Type,
+        default_value: DefaultValue,
+        validator: Option<Arc<dyn Validator>>,
+        importance: Importance,
+        documentation: &str,
+        internal_config: bool,
+    ) -> Result<ConfigDef> {
+        self.define_key(ConfigKey::new(
+            name,
+            type_,
+            default_value,
+            validator,
+            importance,
+            documentation,
+            internal_config,
+        )?)
+    }
+
+    /// Define a new configuration with no special validation logic
+    ///
+    /// * `name` - The name of the config parameter
+    /// * `type_` - The type of the config
+    /// * `default_value` - The default value to use if this config isn't present, `DefaultValue::NoDefault` for
+    ///   required configs
+    /// * `importance` - The importance of this config: is this something you will likely need to change.
+    /// * `documentation` - The documentation string for the config
+    ///
+    /// Fails with `Config` if the name is already defined or the default value is invalid.
+    pub fn define(
+        self,
+        name: &str,
+        type_: Type,
+        default_value: impl Into<DefaultValue>,
+        importance: Importance,
+        documentation: &str,
+    ) -> Result<ConfigDef> {
+        self.define_checked(
+            name,
+            type_,
+            default_value.into(),
+            None,
+            importance,
+            documentation,
+            false,
+        )
+    }
+
+    /// Define a new configuration validated with `validator`
+    pub fn define_with_validator(
+        self,
+        name: &str,
+        type_: Type,
+        default_value: impl Into<DefaultValue>,
+        validator: impl Validator + 'static,
+        importance: Importance,
+        documentation: &str,
+    ) -> Result<ConfigDef> {
+        self.define_checked(
+            name,
+            type_,
+            default_value.into(),
+            Some(Arc::new(validator)),
+            importance,
+            documentation,
+            false,
+        )
+    }
+
+    /// Define a new internal configuration. Internal configuration won't show up in the docs and aren't
+    /// intended for general use.
+    pub fn define_internal(
+        self,
+        name: &str,
+        type_: Type,
+        default_value: impl Into<DefaultValue>,
+        importance: Importance,
+    ) -> Result<ConfigDef> {
+        self.define_checked(
+            name,
+            type_,
+            default_value.into(),
+            None,
+            importance,
+            "",
+            true,
+        )
+    }
+
+    /// Add standard SSL client configuration options.
+    pub fn with_client_ssl_support(self) -> Result<ConfigDef> {
+        ssl_configs::add_client_ssl_support(self)
+    }
+
+    /// Add standard SASL client configuration options.
+    pub fn with_client_sasl_support(self) -> Result<ConfigDef> {
+        sasl_configs::add_client_sasl_support(self)
+    }
+
+    /// Parse and validate configs against this configuration definition. The input is a map of configs with
+    /// string values, as read from a properties file.
+    ///
+    /// Returns the parsed configs with `None` for null values.
+    pub fn parse(
+        &self,
+        props: &IndexMap<String, String>,
+    ) -> Result<IndexMap<String, Option<ConfigValue>>> {
+        let mut values = IndexMap::with_capacity(self.config_keys.len());
+        for key in self.config_keys.values() {
+            values.insert(
+                key.name.clone(),
+                self.parse_value(key, props.get(&key.name).map(String::as_str))?,
+            );
+        }
+        Ok(values)
+    }
+
+    fn parse_value(&self, key: &ConfigKey, value: Option<&str>) -> Result<Option<ConfigValue>> {
+        let parsed_value = match (value, &key.default_value) {
+            (Some(value), _) => Some(parse_string(&key.name, value, key.type_)?),
+            // props map doesn't contain setting, the key is required because no default value specified - its an error
+            (None, DefaultValue::NoDefault) => {
+                return Err(KafkaError::Config(format!(
+                    "Missing required configuration \"{}\" which has no default value.",
+                    key.name
+                )))
+            }
+            // otherwise assign setting its default value
+            (None, DefaultValue::Null) => None,
+            (None, DefaultValue::Value(default_value)) => Some(default_value.clone()),
+        };
+        if let Some(validator) = &key.validator {
+            validator.ensure_valid(&key.name, parsed_value.as_ref())?;
+        }
+        Ok(parsed_value)
+    }
+
+    /// Parse an already typed value of a defined config and validate it.
+    pub fn parse_typed_value(
+        &self,
+        name: &str,
+        value: Option<&ConfigValue>,
+    ) -> Result<Option<ConfigValue>> {
+        let key = self
+            .config_keys
+            .get(name)
+            .ok_or_else(|| KafkaError::Config(format!("Unknown configuration '{}'", name)))?;
+        let parsed_value = match value {
+            Some(value) => Some(parse_type(name, value, key.type_)?),
+            None => None,
+        };
+        if let Some(validator) = &key.validator {
+            validator.ensure_valid(name, parsed_value.as_ref())?;
+        }
+        Ok(parsed_value)
+    }
+}
+
+/// Parse a value according to its expected type.
+///
+/// * `name` - The config name
+/// * `value` - The config value
+/// * `type_` - The expected type
+pub fn parse_type(name: &str, value: &ConfigValue, type_: Type) -> Result<ConfigValue> {
+    let mismatch = |message: String| Err(config_exception(name, value, &message));
+    match (type_, value) {
+        (_, ConfigValue::String(value)) => parse_string(name, value, type_),
+        (Type::Boolean, ConfigValue::Boolean(_))
+        | (Type::Int, ConfigValue::Int(_))
+        | (Type::Short, ConfigValue::Short(_))
+        | (Type::Long, ConfigValue::Long(_))
+        | (Type::Double, ConfigValue::Double(_))
+        | (Type::List, ConfigValue::List(_))
+        | (Type::Class, ConfigValue::Class(_))
+        | (Type::Password, ConfigValue::Password(_)) => Ok(value.clone()),
+        (Type::Long, ConfigValue::Int(value)) => Ok(ConfigValue::Long(*value as i64)),
+        (Type::Boolean, _) => mismatch("Expected value to be either true or false".to_owned()),
+        (Type::Password, _) | (Type::String, _) => mismatch(format!(
+            "Expected value to be a string, but it was a {}",
+            value.java_class_name()
+        )),
+        (Type::Int, _) => mismatch(format!(
+            "Expected value to be a 32-bit integer, but it was a {}",
+            value.java_class_name()
+        )),
+        (Type::Short, _) => mismatch(format!(
+            "Expected value to be a 16-bit integer (short), but it was a {}",
+            value.java_class_name()
+        )),
+        (Type::Long, _) => mismatch(format!(
+            "Expected value to be a 64-bit integer (long), but it was a {}",
+            value.java_class_name()
+        )),
+        (Type::Double, _) => mismatch(format!(
+            "Expected value to be a double, but it was a {}",
+            value.java_class_name()
+        )),
+        (Type::List, _) => mismatch("Expected a comma separated list.".to_owned()),
+        (Type::Class, _) => mismatch("Expected a Class instance or class name.".to_owned()),
+    }
+}
+
+fn parse_string(name: &str, value: &str, type_: Type) -> Result<ConfigValue> {
+    let trimmed = value.trim();
+    let not_a_number = || {
+        config_exception(
+            name,
+            value,
+            // the name of the Java enum constant, e.g. INT
+            &format!("Not a number of type {}", type_.to_string().to_uppercase()),
+        )
+    };
+    Ok(match type_ {
+        Type::Boolean => {
+            if trimmed.eq_ignore_ascii_case("true") {
+                ConfigValue::Boolean(true)
+            } else if trimmed.eq_ignore_ascii_case("false") {
+                ConfigValue::Boolean(false)
+            } else {
+                return Err(config_exception(
+                    name,
+                    value,
+                    "Expected value to be either true or false",
+                ));
+            }
+        }
+        Type::Password => ConfigValue::Password(Password::new(trimmed)),
+        Type::String => ConfigValue::String(trimmed.to_owned()),
+        Type::Int => ConfigValue::Int(trimmed.parse().map_err(|_| not_a_number())?),
+        Type::Short => ConfigValue::Short(trimmed.parse().map_err(|_| not_a_number())?),
+        Type::Long => ConfigValue::Long(trimmed.parse().map_err(|_| not_a_number())?),
+        Type::Double => ConfigValue::Double(trimmed.parse().map_err(|_| not_a_number())?),
+        Type::List => {
+            if trimmed.is_empty() {
+                ConfigValue::List(vec![])
+            } else {
+                ConfigValue::List(trimmed.split(',').map(|s| s.trim().to_owned()).collect())
+            }
+        }
+        Type::Class => ConfigValue::Class(trimmed.to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::{
+        CaseInsensitiveValidString, ConfigDef, ConfigValue, DefaultValue, Importance,
+        NonEmptyString, NonNullValidator, Range, Type, ValidList, ValidString, Validator,
+    };
+    use crate::{
+        clients::{
+            admin::admin_client_config::AdminClientConfig,
+            consumer::consumer_config::ConsumerConfig, producer::producer_config::ProducerConfig,
+        },
+        common::errors::KafkaError,
+    };
+
+    fn config_error<T>(result: Result<T, KafkaError>) -> String {
+        match result {
+            Err(KafkaError::Config(message)) => message,
+            Err(error) => panic!("Expected a config error, got {:?}", error),
+            Ok(_) => panic!("Expected a config error"),
+        }
+    }
+
+    #[test]
+    fn defining_a_name_twice_fails() {
+        let def = ConfigDef::new()
+            .define("a", Type::Int, 1, Importance::High, "")
+            .expect("first definition");
+        let error = config_error(def.define("a", Type::String, "1", Importance::Low, ""));
+        assert_eq!(error, "Configuration a is defined twice.");
+    }
+
+    #[test]
+    fn invalid_defaults_fail() {
+        let mistyped = ConfigDef::new().define("a", Type::Int, "one", Importance::High, "");
+        config_error(mistyped);
+        let out_of_range = ConfigDef::new().define_with_validator(
+            "a",
+            Type::Int,
+            -1,
+            Range::at_least(0),
+            Importance::High,
+            "",
+        );
+        config_error(out_of_range);
+    }
+
+    #[test]
+    fn defaults_are_parsed() -> Result<(), KafkaError> {
+        let def = ConfigDef::new()
+            .define("a", Type::Long, 1, Importance::High, "")?
+            .define("b", Type::String, DefaultValue::Null, Importance::High, "")?
+            .define_internal("c", Type::Int, DefaultValue::NoDefault, Importance::Low)?;
+        let values = def.parse(&IndexMap::from_iter(vec![(
+            "c".to_string(),
+            "3".to_string(),
+        )]))?;
+        assert_eq!(values["a"], Some(ConfigValue::Long(1)));
+        assert_eq!(values["b"], None);
+        assert_eq!(values["c"], Some(ConfigValue::Int(3)));
+        Ok(())
+    }
+
+    #[test]
+    fn client_configs_are_defined() -> Result<(), KafkaError> {
+        ProducerConfig::config_def()?;
+        ConsumerConfig::config_def()?;
+        AdminClientConfig::config_def()?;
+        Ok(())
+    }
+
+    fn string(value: &str) -> ConfigValue {
+        ConfigValue::String(value.to_owned())
+    }
+
+    #[test]
+    fn range_messages() {
+        let range = Range::between(1, 10);
+        assert_eq!(range.to_string(), "[1,...,10]");
+        range
+            .ensure_valid("a", Some(&ConfigValue::Int(10)))
+            .unwrap();
+        assert_eq!(
+            config_error(range.ensure_valid("a", Some(&ConfigValue::Int(0)))),
+            "Invalid value 0 for configuration a: Value must be at least 1"
+        );
+        assert_eq!(
+            config_error(range.ensure_valid("a", Some(&ConfigValue::Int(11)))),
+            "Invalid value 11 for configuration a: Value must be no more than 10"
+        );
+        assert_eq!(
+            config_error(Range::at_least(0).ensure_valid("a", None)),
+            "Invalid value null for configuration a: Value must be non-null"
+        );
+    }
+
+    #[test]
+    fn valid_string_messages() {
+        let valid_string = ValidString::one_of(&["all", "-1", "0", "1"]);
+        valid_string
+            .ensure_valid("acks", Some(&string("all")))
+            .unwrap();
+        assert_eq!(
+            config_error(valid_string.ensure_valid("acks", Some(&string("2")))),
+            "Invalid value 2 for configuration acks: String must be one of: all, -1, 0, 1"
+        );
+
+        let case_insensitive = CaseInsensitiveValidString::one_of(&["plaintext", "ssl"]);
+        case_insensitive
+            .ensure_valid("security.protocol", Some(&string("Ssl")))
+            .unwrap();
+        assert_eq!(
+            config_error(case_insensitive.ensure_valid("security.protocol", Some(&string("tls")))),
+            "Invalid value tls for configuration security.protocol: String must be one of (case insensitive): PLAINTEXT, SSL"
+        );
+
+        let valid_list = ValidList::one_of(&["a", "b"]);
+        valid_list
+            .ensure_valid("l", Some(&ConfigValue::List(vec!["a".to_owned()])))
+            .unwrap();
+        assert_eq!(
+            config_error(valid_list.ensure_valid(
+                "l",
+                Some(&ConfigValue::List(vec!["a".to_owned(), "c".to_owned()]))
+            )),
+            "Invalid value c for configuration l: String must be one of: a, b"
+        );
+    }
+
+    #[test]
+    fn non_empty_and_non_null_messages() {
+        NonEmptyString.ensure_valid("a", None).unwrap();
+        assert_eq!(
+            config_error(NonEmptyString.ensure_valid("a", Some(&string("")))),
+            "Invalid value  for configuration a: String must be non-empty"
+        );
+        NonNullValidator
+            .ensure_valid("a", Some(&string("")))
+            .unwrap();
+        assert_eq!(
+            config_error(NonNullValidator.ensure_valid("a", None)),
+            "Invalid value null for configuration a: entry must be non null"
+        );
+    }
+
+    #[test]
+    fn parse_messages() -> Result<(), KafkaError> {
+        let def = ConfigDef::new()
+            .define("int", Type::Int, 1, Importance::High, "")?
+            .define("bool", Type::Boolean, true, Importance::High, "")?
+            .define_with_validator(
+                "ranged",
+                Type::Long,
+                1,
+                Range::at_least(1),
+                Importance::High,
+                "",
+            )?
+            .define(
+                "required",
+                Type::String,
+                DefaultValue::NoDefault,
+                Importance::High,
+                "",
+            )?;
+        let parse = |props: &[(&str, &str)]| {
+            let mut map: IndexMap<String, String> = IndexMap::new();
+            map.insert("required".to_owned(), "x".to_owned());
+            for (key, value) in props {
+                map.insert(key.to_string(), value.to_string());
+            }
+            def.parse(&map)
+        };
+        assert_eq!(
+            config_error(parse(&[("int", "one")])),
+            "Invalid value one for configuration int: Not a number of type INT"
+        );
+        assert_eq!(
+            config_error(parse(&[("bool", "yes")])),
+            "Invalid value yes for configuration bool: Expected value to be either true or false"
+        );
+        assert_eq!(
+            config_error(parse(&[("ranged", "0")])),
+            "Invalid value 0 for configuration ranged: Value must be at least 1"
+        );
+        assert_eq!(
+            config_error(def.parse(&IndexMap::new())),
+            "Missing required configuration \"required\" which has no default value."
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::{self, Display};
+
+/// Type of resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigResourceType {
+    BrokerLogger,
+    Broker,
+    Topic,
+    Unknown,
+}
+
+impl ConfigResourceType {
+    pub fn id(&self) -> i8 {
+        match self {
+            ConfigResourceType::BrokerLogger => 8,
+            ConfigResourceType::Broker => 4,
+            ConfigResourceType::Topic => 2,
+            ConfigResourceType::Unknown => 0,
+        }
+    }
+
+    /// Unknown ids are mapped to `Unknown`.
+    pub fn for_id(id: i8) -> ConfigResourceType {
+        match id {
+            8 => ConfigResourceType::BrokerLogger,
+            4 => ConfigResourceType::Broker,
+            2 => ConfigResourceType::Topic,
+            _ => ConfigResourceType::Unknown,
+        }
+    }
+}
+
+impl Display for ConfigResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigResourceType::BrokerLogger => f.write_str("BROKER_LOGGER"),
+            ConfigResourceType::Broker => f.write_str("BROKER"),
+            ConfigResourceType::Topic => f.write_str("TOPIC"),
+            ConfigResourceType::Unknown => f.write_str("UNKNOWN"),
+        }
+    }
+}
+
+/// A class representing resources that have configs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConfigResource {
+    pub type_: ConfigResourceType,
+    pub name: String,
+}
+
+impl ConfigResource {
+    /// Create an instance of this class with the provided parameters.
+    ///
+    /// * `type_` - a non-null resource type
+    /// * `name` - a non-null resource name
+    pub fn new(type_: ConfigResourceType, name: impl Into<String>) -> ConfigResource {
+        ConfigResource {
+            type_,
+            name: name.into(),
+        }
+    }
+
+    /// Returns true if this is the default resource of a resource type.
+    ///
+    /// Resource name is empty for the default resource.
+    pub fn is_default(&self) -> bool {
+        self.name.is_empty()
+    }
+}
+
+impl Display for ConfigResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ConfigResource(type={}, name='{}')",
+            self.type_, self.name
+        )
+    }
+}
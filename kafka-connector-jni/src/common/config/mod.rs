@@ -0,0 +1,6 @@
+pub mod abstract_config;
+pub mod config_def;
+pub mod config_resource;
+pub mod sasl_configs;
+pub mod ssl_configs;
+pub mod types;
@@ -0,0 +1,191 @@
+use crate::common::errors::Result;
+
+use super::config_def::{ConfigDef, DefaultValue, Importance, Range, Type};
+
+pub const SASL_MECHANISM: &str = "sasl.mechanism";
+pub const SASL_MECHANISM_DOC: &str = "SASL mechanism used for client connections. This may be any mechanism for which a security provider is available. GSSAPI is the default mechanism.";
+pub const GSSAPI_MECHANISM: &str = "GSSAPI";
+pub const DEFAULT_SASL_MECHANISM: &str = GSSAPI_MECHANISM;
+
+pub const SASL_JAAS_CONFIG: &str = "sasl.jaas.config";
+pub const SASL_JAAS_CONFIG_DOC: &str = "JAAS login context parameters for SASL connections in the format used by JAAS configuration files. \
+    JAAS configuration file format is described in the Oracle JAAS documentation. \
+    The format for the value is: 'loginModuleClass controlFlag (optionName=optionValue)*;'. For brokers, \
+    the config must be prefixed with listener prefix and SASL mechanism name in lower-case. For example, \
+    listener.name.sasl_ssl.scram-sha-256.sasl.jaas.config=com.example.ScramLoginModule required;";
+
+pub const SASL_CLIENT_CALLBACK_HANDLER_CLASS: &str = "sasl.client.callback.handler.class";
+pub const SASL_CLIENT_CALLBACK_HANDLER_CLASS_DOC: &str =
+    "The fully qualified name of a SASL client callback handler class \
+    that implements the AuthenticateCallbackHandler interface.";
+
+pub const SASL_LOGIN_CALLBACK_HANDLER_CLASS: &str = "sasl.login.callback.handler.class";
+pub const SASL_LOGIN_CALLBACK_HANDLER_CLASS_DOC: &str = "The fully qualified name of a SASL login callback handler class \
+    that implements the AuthenticateCallbackHandler interface. For brokers, login callback handler config must be prefixed with \
+    listener prefix and SASL mechanism name in lower-case. For example, \
+    listener.name.sasl_ssl.scram-sha-256.sasl.login.callback.handler.class=com.example.CustomScramLoginCallbackHandler";
+
+pub const SASL_LOGIN_CLASS: &str = "sasl.login.class";
+pub const SASL_LOGIN_CLASS_DOC: &str = "The fully qualified name of a class that implements the Login interface. \
+    For brokers, login config must be prefixed with listener prefix and SASL mechanism name in lower-case. For example, \
+    listener.name.sasl_ssl.scram-sha-256.sasl.login.class=com.example.CustomScramLogin";
+
+pub const SASL_KERBEROS_SERVICE_NAME: &str = "sasl.kerberos.service.name";
+pub const SASL_KERBEROS_SERVICE_NAME_DOC: &str = "The Kerberos principal name that Kafka runs as. \
+    This can be defined either in Kafka's JAAS config or in Kafka's config.";
+
+pub const SASL_KERBEROS_KINIT_CMD: &str = "sasl.kerberos.kinit.cmd";
+pub const SASL_KERBEROS_KINIT_CMD_DOC: &str = "Kerberos kinit command path.";
+pub const DEFAULT_KERBEROS_KINIT_CMD: &str = "/usr/bin/kinit";
+
+pub const SASL_KERBEROS_TICKET_RENEW_WINDOW_FACTOR: &str =
+    "sasl.kerberos.ticket.renew.window.factor";
+pub const SASL_KERBEROS_TICKET_RENEW_WINDOW_FACTOR_DOC: &str =
+    "Login thread will sleep until the specified window factor of time from last refresh \
+    to ticket's expiry has been reached, at which time it will try to renew the ticket.";
+pub const DEFAULT_KERBEROS_TICKET_RENEW_WINDOW_FACTOR: f64 = 0.80;
+
+pub const SASL_KERBEROS_TICKET_RENEW_JITTER: &str = "sasl.kerberos.ticket.renew.jitter";
+pub const SASL_KERBEROS_TICKET_RENEW_JITTER_DOC: &str =
+    "Percentage of random jitter added to the renewal time.";
+pub const DEFAULT_KERBEROS_TICKET_RENEW_JITTER: f64 = 0.05;
+
+pub const SASL_KERBEROS_MIN_TIME_BEFORE_RELOGIN: &str = "sasl.kerberos.min.time.before.relogin";
+pub const SASL_KERBEROS_MIN_TIME_BEFORE_RELOGIN_DOC: &str =
+    "Login thread sleep time between refresh attempts.";
+pub const DEFAULT_KERBEROS_MIN_TIME_BEFORE_RELOGIN: i64 = 60 * 1000;
+
+pub const SASL_LOGIN_REFRESH_WINDOW_FACTOR: &str = "sasl.login.refresh.window.factor";
+pub const SASL_LOGIN_REFRESH_WINDOW_FACTOR_DOC: &str = "Login refresh thread will sleep until the specified window factor relative to the \
+    credential's lifetime has been reached, at which time it will try to refresh the credential. \
+    Legal values are between 0.5 (50%) and 1.0 (100%) inclusive; a default value of 0.8 (80%) is used \
+    if no value is specified. Currently applies only to OAUTHBEARER.";
+pub const DEFAULT_LOGIN_REFRESH_WINDOW_FACTOR: f64 = 0.80;
+
+pub const SASL_LOGIN_REFRESH_WINDOW_JITTER: &str = "sasl.login.refresh.window.jitter";
+pub const SASL_LOGIN_REFRESH_WINDOW_JITTER_DOC: &str = "The maximum amount of random jitter relative to the credential's lifetime \
+    that is added to the login refresh thread's sleep time. Legal values are between 0 and 0.25 (25%) inclusive; \
+    a default value of 0.05 (5%) is used if no value is specified. Currently applies only to OAUTHBEARER.";
+pub const DEFAULT_LOGIN_REFRESH_WINDOW_JITTER: f64 = 0.05;
+
+pub const SASL_LOGIN_REFRESH_MIN_PERIOD_SECONDS: &str = "sasl.login.refresh.min.period.seconds";
+pub const SASL_LOGIN_REFRESH_MIN_PERIOD_SECONDS_DOC: &str = "The desired minimum time for the login refresh thread to wait before refreshing a credential, \
+    in seconds. Legal values are between 0 and 900 (15 minutes); a default value of 60 (1 minute) is used if no value is specified. \
+    This value and sasl.login.refresh.buffer.seconds are both ignored if their sum exceeds the remaining lifetime of a credential. \
+    Currently applies only to OAUTHBEARER.";
+pub const DEFAULT_LOGIN_REFRESH_MIN_PERIOD_SECONDS: i16 = 60;
+
+pub const SASL_LOGIN_REFRESH_BUFFER_SECONDS: &str = "sasl.login.refresh.buffer.seconds";
+pub const SASL_LOGIN_REFRESH_BUFFER_SECONDS_DOC: &str = "The amount of buffer time before credential expiration to maintain when refreshing a credential, \
+    in seconds. If a refresh would otherwise occur closer to expiration than the number of buffer seconds then the refresh will be moved up to maintain \
+    as much of the buffer time as possible. Legal values are between 0 and 3600 (1 hour); a default value of  300 (5 minutes) is used if no value is specified. \
+    This value and sasl.login.refresh.min.period.seconds are both ignored if their sum exceeds the remaining lifetime of a credential. \
+    Currently applies only to OAUTHBEARER.";
+pub const DEFAULT_LOGIN_REFRESH_BUFFER_SECONDS: i16 = 300;
+
+/// Add standard SASL client configuration options.
+pub fn add_client_sasl_support(config: ConfigDef) -> Result<ConfigDef> {
+    config
+        .define(
+            SASL_KERBEROS_SERVICE_NAME,
+            Type::String,
+            DefaultValue::Null,
+            Importance::Medium,
+            SASL_KERBEROS_SERVICE_NAME_DOC,
+        )?
+        .define(
+            SASL_KERBEROS_KINIT_CMD,
+            Type::String,
+            DEFAULT_KERBEROS_KINIT_CMD,
+            Importance::Low,
+            SASL_KERBEROS_KINIT_CMD_DOC,
+        )?
+        .define(
+            SASL_KERBEROS_TICKET_RENEW_WINDOW_FACTOR,
+            Type::Double,
+            DEFAULT_KERBEROS_TICKET_RENEW_WINDOW_FACTOR,
+            Importance::Low,
+            SASL_KERBEROS_TICKET_RENEW_WINDOW_FACTOR_DOC,
+        )?
+        .define(
+            SASL_KERBEROS_TICKET_RENEW_JITTER,
+            Type::Double,
+            DEFAULT_KERBEROS_TICKET_RENEW_JITTER,
+            Importance::Low,
+            SASL_KERBEROS_TICKET_RENEW_JITTER_DOC,
+        )?
+        .define(
+            SASL_KERBEROS_MIN_TIME_BEFORE_RELOGIN,
+            Type::Long,
+            DEFAULT_KERBEROS_MIN_TIME_BEFORE_RELOGIN,
+            Importance::Low,
+            SASL_KERBEROS_MIN_TIME_BEFORE_RELOGIN_DOC,
+        )?
+        .define_with_validator(
+            SASL_LOGIN_REFRESH_WINDOW_FACTOR,
+            Type::Double,
+            DEFAULT_LOGIN_REFRESH_WINDOW_FACTOR,
+            Range::between(0.5, 1.0),
+            Importance::Low,
+            SASL_LOGIN_REFRESH_WINDOW_FACTOR_DOC,
+        )?
+        .define_with_validator(
+            SASL_LOGIN_REFRESH_WINDOW_JITTER,
+            Type::Double,
+            DEFAULT_LOGIN_REFRESH_WINDOW_JITTER,
+            Range::between(0.0, 0.25),
+            Importance::Low,
+            SASL_LOGIN_REFRESH_WINDOW_JITTER_DOC,
+        )?
+        .define_with_validator(
+            SASL_LOGIN_REFRESH_MIN_PERIOD_SECONDS,
+            Type::Short,
+            DEFAULT_LOGIN_REFRESH_MIN_PERIOD_SECONDS,
+            Range::between(0, 900),
+            Importance::Low,
+            SASL_LOGIN_REFRESH_MIN_PERIOD_SECONDS_DOC,
+        )?
+        .define_with_validator(
+            SASL_LOGIN_REFRESH_BUFFER_SECONDS,
+            Type::Short,
+            DEFAULT_LOGIN_REFRESH_BUFFER_SECONDS,
+            Range::between(0, 3600),
+            Importance::Low,
+            SASL_LOGIN_REFRESH_BUFFER_SECONDS_DOC,
+        )?
+        .define(
+            SASL_MECHANISM,
+            Type::String,
+            DEFAULT_SASL_MECHANISM,
+            Importance::Medium,
+            SASL_MECHANISM_DOC,
+        )?
+        .define(
+            SASL_JAAS_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::Medium,
+            SASL_JAAS_CONFIG_DOC,
+        )?
+        .define(
+            SASL_CLIENT_CALLBACK_HANDLER_CLASS,
+            Type::Class,
+            DefaultValue::Null,
+            Importance::Medium,
+            SASL_CLIENT_CALLBACK_HANDLER_CLASS_DOC,
+        )?
+        .define(
+            SASL_LOGIN_CALLBACK_HANDLER_CLASS,
+            Type::Class,
+            DefaultValue::Null,
+            Importance::Medium,
+            SASL_LOGIN_CALLBACK_HANDLER_CLASS_DOC,
+        )?
+        .define(
+            SASL_LOGIN_CLASS,
+            Type::Class,
+            DefaultValue::Null,
+            Importance::Medium,
+            SASL_LOGIN_CLASS_DOC,
+        )
+}
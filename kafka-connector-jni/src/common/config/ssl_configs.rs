@@ -0,0 +1,238 @@
+use crate::common::errors::Result;
+
+use super::config_def::{ConfigDef, DefaultValue, Importance, Type};
+
+pub const SSL_PROTOCOL_CONFIG: &str = "ssl.protocol";
+pub const SSL_PROTOCOL_DOC: &str = "The SSL protocol used to generate the SSLContext. \
+    The default is 'TLSv1.3' when running with Java 11 or newer, 'TLSv1.2' otherwise. \
+    This value should be fine for most use cases. \
+    Allowed values in recent JVMs are 'TLSv1.2' and 'TLSv1.3'. 'TLS', 'TLSv1.1', 'SSL', 'SSLv2' and 'SSLv3' \
+    may be supported in older JVMs, but their usage is discouraged due to known security vulnerabilities. \
+    With the default value for this config and 'ssl.enabled.protocols', clients will downgrade to 'TLSv1.2' if \
+    the server does not support 'TLSv1.3'. If this config is set to 'TLSv1.2', clients will not use 'TLSv1.3' even \
+    if it is one of the values in ssl.enabled.protocols and the server only supports 'TLSv1.3'.";
+pub const DEFAULT_SSL_PROTOCOL: &str = "TLSv1.3";
+
+pub const SSL_PROVIDER_CONFIG: &str = "ssl.provider";
+pub const SSL_PROVIDER_DOC: &str = "The name of the security provider used for SSL connections. Default value is the default security provider of the JVM.";
+
+pub const SSL_CIPHER_SUITES_CONFIG: &str = "ssl.cipher.suites";
+pub const SSL_CIPHER_SUITES_DOC: &str = "A list of cipher suites. This is a named combination of authentication, encryption, MAC and key exchange algorithm used to negotiate the security settings for a network connection using TLS or SSL network protocol. \
+    By default all the available cipher suites are supported.";
+
+pub const SSL_ENABLED_PROTOCOLS_CONFIG: &str = "ssl.enabled.protocols";
+pub const SSL_ENABLED_PROTOCOLS_DOC: &str = "The list of protocols enabled for SSL connections. \
+    The default is 'TLSv1.2,TLSv1.3' when running with Java 11 or newer, 'TLSv1.2' otherwise. With the \
+    default value for Java 11, clients and servers will prefer TLSv1.3 if both support it and fallback \
+    to TLSv1.2 otherwise (assuming both support at least TLSv1.2). This default should be fine for most \
+    cases. Also see the config documentation for `ssl.protocol`.";
+pub const DEFAULT_SSL_ENABLED_PROTOCOLS: &str = "TLSv1.2,TLSv1.3";
+
+pub const SSL_KEYSTORE_TYPE_CONFIG: &str = "ssl.keystore.type";
+pub const SSL_KEYSTORE_TYPE_DOC: &str = "The file format of the key store file. \
+    This is optional for client.";
+pub const DEFAULT_SSL_KEYSTORE_TYPE: &str = "JKS";
+
+pub const SSL_KEYSTORE_KEY_CONFIG: &str = "ssl.keystore.key";
+pub const SSL_KEYSTORE_KEY_DOC: &str =
+    "Private key in the format specified by 'ssl.keystore.type'. \
+    Default SSL engine factory supports only PEM format with PKCS#8 keys. If the key is encrypted, \
+    key password must be specified using 'ssl.key.password'";
+
+pub const SSL_KEYSTORE_CERTIFICATE_CHAIN_CONFIG: &str = "ssl.keystore.certificate.chain";
+pub const SSL_KEYSTORE_CERTIFICATE_CHAIN_DOC: &str =
+    "Certificate chain in the format specified by 'ssl.keystore.type'. \
+    Default SSL engine factory supports only PEM format with a list of X.509 certificates";
+
+pub const SSL_TRUSTSTORE_CERTIFICATES_CONFIG: &str = "ssl.truststore.certificates";
+pub const SSL_TRUSTSTORE_CERTIFICATES_DOC: &str =
+    "Trusted certificates in the format specified by 'ssl.truststore.type'. \
+    Default SSL engine factory supports only PEM format with X.509 certificates.";
+
+pub const SSL_KEYSTORE_LOCATION_CONFIG: &str = "ssl.keystore.location";
+pub const SSL_KEYSTORE_LOCATION_DOC: &str = "The location of the key store file. \
+    This is optional for client and can be used for two-way authentication for client.";
+
+pub const SSL_KEYSTORE_PASSWORD_CONFIG: &str = "ssl.keystore.password";
+pub const SSL_KEYSTORE_PASSWORD_DOC: &str = "The store password for the key store file. \
+    This is optional for client and only needed if 'ssl.keystore.location' is configured. \
+    Key store password is not supported for PEM format.";
+
+pub const SSL_KEY_PASSWORD_CONFIG: &str = "ssl.key.password";
+pub const SSL_KEY_PASSWORD_DOC: &str = "The password of the private key in the key store file or \
+    the PEM key specified in `ssl.keystore.key'. This is required for clients only if two-way authentication is configured.";
+
+pub const SSL_TRUSTSTORE_TYPE_CONFIG: &str = "ssl.truststore.type";
+pub const SSL_TRUSTSTORE_TYPE_DOC: &str = "The file format of the trust store file.";
+pub const DEFAULT_SSL_TRUSTSTORE_TYPE: &str = "JKS";
+
+pub const SSL_TRUSTSTORE_LOCATION_CONFIG: &str = "ssl.truststore.location";
+pub const SSL_TRUSTSTORE_LOCATION_DOC: &str = "The location of the trust store file. ";
+
+pub const SSL_TRUSTSTORE_PASSWORD_CONFIG: &str = "ssl.truststore.password";
+pub const SSL_TRUSTSTORE_PASSWORD_DOC: &str = "The password for the trust store file. \
+    If a password is not set, trust store file configured will still be used, but integrity checking is disabled. \
+    Trust store password is not supported for PEM format.";
+
+pub const SSL_KEYMANAGER_ALGORITHM_CONFIG: &str = "ssl.keymanager.algorithm";
+pub const SSL_KEYMANAGER_ALGORITHM_DOC: &str =
+    "The algorithm used by key manager factory for SSL connections. \
+    Default value is the key manager factory algorithm configured for the Java Virtual Machine.";
+pub const DEFAULT_SSL_KEYMANGER_ALGORITHM: &str = "SunX509";
+
+pub const SSL_TRUSTMANAGER_ALGORITHM_CONFIG: &str = "ssl.trustmanager.algorithm";
+pub const SSL_TRUSTMANAGER_ALGORITHM_DOC: &str =
+    "The algorithm used by trust manager factory for SSL connections. \
+    Default value is the trust manager factory algorithm configured for the Java Virtual Machine.";
+pub const DEFAULT_SSL_TRUSTMANAGER_ALGORITHM: &str = "PKIX";
+
+pub const SSL_ENDPOINT_IDENTIFICATION_ALGORITHM_CONFIG: &str =
+    "ssl.endpoint.identification.algorithm";
+pub const SSL_ENDPOINT_IDENTIFICATION_ALGORITHM_DOC: &str =
+    "The endpoint identification algorithm to validate server hostname using server certificate. ";
+pub const DEFAULT_SSL_ENDPOINT_IDENTIFICATION_ALGORITHM: &str = "https";
+
+pub const SSL_SECURE_RANDOM_IMPLEMENTATION_CONFIG: &str = "ssl.secure.random.implementation";
+pub const SSL_SECURE_RANDOM_IMPLEMENTATION_DOC: &str =
+    "The SecureRandom PRNG implementation to use for SSL cryptography operations. ";
+
+pub const SSL_ENGINE_FACTORY_CLASS_CONFIG: &str = "ssl.engine.factory.class";
+pub const SSL_ENGINE_FACTORY_CLASS_DOC: &str = "The class of type org.apache.kafka.common.security.auth.SslEngineFactory to provide SSLEngine objects. Default value is org.apache.kafka.common.security.ssl.DefaultSslEngineFactory";
+
+/// Add standard SSL client configuration options.
+pub fn add_client_ssl_support(config: ConfigDef) -> Result<ConfigDef> {
+    config
+        .define(
+            SSL_PROTOCOL_CONFIG,
+            Type::String,
+            DEFAULT_SSL_PROTOCOL,
+            Importance::Medium,
+            SSL_PROTOCOL_DOC,
+        )?
+        .define(
+            SSL_PROVIDER_CONFIG,
+            Type::String,
+            DefaultValue::Null,
+            Importance::Medium,
+            SSL_PROVIDER_DOC,
+        )?
+        .define(
+            SSL_CIPHER_SUITES_CONFIG,
+            Type::List,
+            DefaultValue::Null,
+            Importance::Low,
+            SSL_CIPHER_SUITES_DOC,
+        )?
+        .define(
+            SSL_ENABLED_PROTOCOLS_CONFIG,
+            Type::List,
+            DEFAULT_SSL_ENABLED_PROTOCOLS,
+            Importance::Medium,
+            SSL_ENABLED_PROTOCOLS_DOC,
+        )?
+        .define(
+            SSL_KEYSTORE_TYPE_CONFIG,
+            Type::String,
+            DEFAULT_SSL_KEYSTORE_TYPE,
+            Importance::Medium,
+            SSL_KEYSTORE_TYPE_DOC,
+        )?
+        .define(
+            SSL_KEYSTORE_LOCATION_CONFIG,
+            Type::String,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_KEYSTORE_LOCATION_DOC,
+        )?
+        .define(
+            SSL_KEYSTORE_PASSWORD_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_KEYSTORE_PASSWORD_DOC,
+        )?
+        .define(
+            SSL_KEY_PASSWORD_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_KEY_PASSWORD_DOC,
+        )?
+        .define(
+            SSL_KEYSTORE_KEY_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_KEYSTORE_KEY_DOC,
+        )?
+        .define(
+            SSL_KEYSTORE_CERTIFICATE_CHAIN_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_KEYSTORE_CERTIFICATE_CHAIN_DOC,
+        )?
+        .define(
+            SSL_TRUSTSTORE_CERTIFICATES_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_TRUSTSTORE_CERTIFICATES_DOC,
+        )?
+        .define(
+            SSL_TRUSTSTORE_TYPE_CONFIG,
+            Type::String,
+            DEFAULT_SSL_TRUSTSTORE_TYPE,
+            Importance::Medium,
+            SSL_TRUSTSTORE_TYPE_DOC,
+        )?
+        .define(
+            SSL_TRUSTSTORE_LOCATION_CONFIG,
+            Type::String,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_TRUSTSTORE_LOCATION_DOC,
+        )?
+        .define(
+            SSL_TRUSTSTORE_PASSWORD_CONFIG,
+            Type::Password,
+            DefaultValue::Null,
+            Importance::High,
+            SSL_TRUSTSTORE_PASSWORD_DOC,
+        )?
+        .define(
+            SSL_KEYMANAGER_ALGORITHM_CONFIG,
+            Type::String,
+            DEFAULT_SSL_KEYMANGER_ALGORITHM,
+            Importance::Low,
+            SSL_KEYMANAGER_ALGORITHM_DOC,
+        )?
+        .define(
+            SSL_TRUSTMANAGER_ALGORITHM_CONFIG,
+            Type::String,
+            DEFAULT_SSL_TRUSTMANAGER_ALGORITHM,
+            Importance::Low,
+            SSL_TRUSTMANAGER_ALGORITHM_DOC,
+        )?
+        .define(
+            SSL_ENDPOINT_IDENTIFICATION_ALGORITHM_CONFIG,
+            Type::String,
+            DEFAULT_SSL_ENDPOINT_IDENTIFICATION_ALGORITHM,
+            Importance::Low,
+            SSL_ENDPOINT_IDENTIFICATION_ALGORITHM_DOC,
+        )?
+        .define(
+            SSL_SECURE_RANDOM_IMPLEMENTATION_CONFIG,
+            Type::String,
+            DefaultValue::Null,
+            Importance::Low,
+            SSL_SECURE_RANDOM_IMPLEMENTATION_DOC,
+        )?
+        .define(
+            SSL_ENGINE_FACTORY_CLASS_CONFIG,
+            Type::Class,
+            DefaultValue::Null,
+            Importance::Low,
+            SSL_ENGINE_FACTORY_CLASS_DOC,
+        )
+}
@@ -0,0 +1,36 @@
+use std::fmt::{self, Display};
+
+pub const HIDDEN: &str = "[hidden]";
+
+/// A wrapper class for passwords to hide them while logging a config
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Password {
+    value: String,
+}
+
+impl Password {
+    /// Construct a new Password object
+    pub fn new(value: impl Into<String>) -> Password {
+        Password {
+            value: value.into(),
+        }
+    }
+
+    /// Returns real password string
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+/// Returns hidden password string
+impl Display for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(HIDDEN)
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(HIDDEN)
+    }
+}
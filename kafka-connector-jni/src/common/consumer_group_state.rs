@@ -0,0 +1,43 @@
+use std::fmt::{self, Display};
+
+/// The consumer group state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConsumerGroupState {
+    Unknown,
+    PreparingRebalance,
+    CompletingRebalance,
+    Stable,
+    Dead,
+    Empty,
+}
+
+impl ConsumerGroupState {
+    /// Parse a string into a consumer group state, unknown names are mapped to `Unknown`.
+    pub fn parse(name: &str) -> ConsumerGroupState {
+        match name {
+            "PreparingRebalance" => ConsumerGroupState::PreparingRebalance,
+            "CompletingRebalance" => ConsumerGroupState::CompletingRebalance,
+            "Stable" => ConsumerGroupState::Stable,
+            "Dead" => ConsumerGroupState::Dead,
+            "Empty" => ConsumerGroupState::Empty,
+            _ => ConsumerGroupState::Unknown,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConsumerGroupState::Unknown => "Unknown",
+            ConsumerGroupState::PreparingRebalance => "PreparingRebalance",
+            ConsumerGroupState::CompletingRebalance => "CompletingRebalance",
+            ConsumerGroupState::Stable => "Stable",
+            ConsumerGroupState::Dead => "Dead",
+            ConsumerGroupState::Empty => "Empty",
+        }
+    }
+}
+
+impl Display for ConsumerGroupState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,979 @@
+use std::fmt;
+
+use jni::{
+    objects::{GlobalRef, JObject, JThrowable},
+    JNIEnv,
+};
+use log::error;
+use thiserror::Error;
+
+use crate::{clone_from_java::CloneFromJava, jni_cache};
+
+/// Rust counterpart of exceptions from `org.apache.kafka.common.errors` package.
+/// Variants are named after java exception classes without `Exception` suffix.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum KafkaError {
+    /// Generic `KafkaException`
+    #[error("{0}")]
+    Kafka(String),
+    #[error("{0}")]
+    BrokerIdNotRegistered(String),
+    #[error("{0}")]
+    BrokerNotAvailable(String),
+    #[error("{0}")]
+    BufferExhausted(String),
+    #[error("{0}")]
+    ClusterAuthorization(String),
+    #[error("{0}")]
+    CommitFailed(String),
+    /// `java.util.ConcurrentModificationException`
+    #[error("{0}")]
+    ConcurrentModification(String),
+    #[error("{0}")]
+    ConcurrentTransactions(String),
+    /// `ConfigException`
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    ControllerMoved(String),
+    #[error("{0}")]
+    CoordinatorLoadInProgress(String),
+    #[error("{0}")]
+    CoordinatorNotAvailable(String),
+    #[error("{0}")]
+    CorruptRecord(String),
+    #[error("{0}")]
+    DelegationTokenAuthorization(String),
+    #[error("{0}")]
+    DelegationTokenDisabled(String),
+    #[error("{0}")]
+    DelegationTokenExpired(String),
+    #[error("{0}")]
+    DelegationTokenNotFound(String),
+    #[error("{0}")]
+    DelegationTokenOwnerMismatch(String),
+    #[error("{0}")]
+    DuplicateBrokerRegistration(String),
+    #[error("{0}")]
+    DuplicateResource(String),
+    #[error("{0}")]
+    DuplicateSequence(String),
+    #[error("{0}")]
+    ElectionNotNeeded(String),
+    #[error("{0}")]
+    EligibleLeadersNotAvailable(String),
+    #[error("{0}")]
+    FeatureUpdateFailed(String),
+    #[error("{0}")]
+    FencedInstanceId(String),
+    #[error("{0}")]
+    FencedLeaderEpoch(String),
+    #[error("{0}")]
+    FetchSessionIdNotFound(String),
+    #[error("{0}")]
+    GroupAuthorization(String),
+    #[error("{0}")]
+    GroupIdNotFound(String),
+    #[error("{0}")]
+    GroupMaxSizeReached(String),
+    #[error("{0}")]
+    GroupNotEmpty(String),
+    #[error("{0}")]
+    GroupSubscribedToTopic(String),
+    #[error("{0}")]
+    IllegalArgument(String),
+    #[error("{0}")]
+    IllegalGeneration(String),
+    #[error("{0}")]
+    IllegalSaslState(String),
+    #[error("{0}")]
+    IllegalState(String),
+    #[error("{0}")]
+    InconsistentClusterId(String),
+    #[error("{0}")]
+    InconsistentGroupProtocol(String),
+    #[error("{0}")]
+    InconsistentTopicId(String),
+    #[error("{0}")]
+    InconsistentVoterSet(String),
+    #[error("{0}")]
+    Interrupt(String),
+    #[error("{0}")]
+    InvalidCommitOffsetSize(String),
+    #[error("{0}")]
+    InvalidConfiguration(String),
+    #[error("{0}")]
+    InvalidFetchSessionEpoch(String),
+    #[error("{0}")]
+    InvalidFetchSize(String),
+    #[error("{0}")]
+    InvalidGroupId(String),
+    #[error("{0}")]
+    InvalidPartitions(String),
+    #[error("{0}")]
+    InvalidPidMapping(String),
+    #[error("{0}")]
+    InvalidPrincipalType(String),
+    #[error("{0}")]
+    InvalidProducerEpoch(String),
+    #[error("{0}")]
+    InvalidRecord(String),
+    #[error("{0}")]
+    InvalidReplicaAssignment(String),
+    #[error("{0}")]
+    InvalidReplicationFactor(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error("{0}")]
+    InvalidRequiredAcks(String),
+    #[error("{0}")]
+    InvalidSessionTimeout(String),
+    #[error("{0}")]
+    InvalidTimestamp(String),
+    #[error("{0}")]
+    InvalidTopic(String),
+    #[error("{0}")]
+    InvalidTxnState(String),
+    #[error("{0}")]
+    InvalidTxnTimeout(String),
+    #[error("{0}")]
+    InvalidUpdateVersion(String),
+    #[error("{0}")]
+    KafkaStorage(String),
+    #[error("{0}")]
+    LeaderNotAvailable(String),
+    #[error("{0}")]
+    ListenerNotFound(String),
+    #[error("{0}")]
+    LogDirNotFound(String),
+    #[error("{0}")]
+    LogTruncation(String),
+    #[error("{0}")]
+    MemberIdRequired(String),
+    #[error("{0}")]
+    Network(String),
+    #[error("{0}")]
+    NoOffsetForPartition(String),
+    #[error("{0}")]
+    NoReassignmentInProgress(String),
+    #[error("{0}")]
+    NotController(String),
+    #[error("{0}")]
+    NotCoordinator(String),
+    #[error("{0}")]
+    NotEnoughReplicas(String),
+    #[error("{0}")]
+    NotEnoughReplicasAfterAppend(String),
+    #[error("{0}")]
+    NotLeaderOrFollower(String),
+    #[error("{0}")]
+    OffsetMetadataTooLarge(String),
+    #[error("{0}")]
+    OffsetNotAvailable(String),
+    #[error("{0}")]
+    OffsetOutOfRange(String),
+    #[error("{0}")]
+    OperationNotAttempted(String),
+    #[error("{0}")]
+    OutOfOrderSequence(String),
+    #[error("{0}")]
+    PolicyViolation(String),
+    #[error("{0}")]
+    PositionOutOfRange(String),
+    #[error("{0}")]
+    PreferredLeaderNotAvailable(String),
+    #[error("{0}")]
+    PrincipalDeserialization(String),
+    #[error("{0}")]
+    ProducerFenced(String),
+    #[error("{0}")]
+    ReassignmentInProgress(String),
+    #[error("{0}")]
+    RebalanceInProgress(String),
+    #[error("{0}")]
+    RecordBatchTooLarge(String),
+    #[error("{0}")]
+    RecordTooLarge(String),
+    #[error("{0}")]
+    ReplicaNotAvailable(String),
+    #[error("{0}")]
+    ResourceNotFound(String),
+    #[error("{0}")]
+    RetriableCommitFailed(String),
+    #[error("{0}")]
+    SaslAuthentication(String),
+    #[error("{0}")]
+    SecurityDisabled(String),
+    #[error("{0}")]
+    Serialization(String),
+    #[error("{0}")]
+    SnapshotNotFound(String),
+    #[error("{0}")]
+    StaleBrokerEpoch(String),
+    #[error("{0}")]
+    ThrottlingQuotaExceeded(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    TopicAuthorization(String),
+    #[error("{0}")]
+    TopicDeletionDisabled(String),
+    #[error("{0}")]
+    TopicExists(String),
+    #[error("{0}")]
+    TransactionAborted(String),
+    #[error("{0}")]
+    TransactionCoordinatorFenced(String),
+    #[error("{0}")]
+    TransactionalIdAuthorization(String),
+    #[error("{0}")]
+    TransactionalIdNotFound(String),
+    #[error("{0}")]
+    UnacceptableCredential(String),
+    /// The consumer coordinator is not part of a group yet, it has no public java counterpart
+    #[error("{0}")]
+    UnjoinedGroup(String),
+    #[error("{0}")]
+    UnknownLeaderEpoch(String),
+    #[error("{0}")]
+    UnknownMemberId(String),
+    #[error("{0}")]
+    UnknownProducerId(String),
+    #[error("{0}")]
+    UnknownServer(String),
+    #[error("{0}")]
+    UnknownTopicId(String),
+    #[error("{0}")]
+    UnknownTopicOrPartition(String),
+    #[error("{0}")]
+    UnstableOffsetCommit(String),
+    #[error("{0}")]
+    UnsupportedByAuthentication(String),
+    #[error("{0}")]
+    UnsupportedCompressionType(String),
+    #[error("{0}")]
+    UnsupportedForMessageFormat(String),
+    #[error("{0}")]
+    UnsupportedSaslMechanism(String),
+    #[error("{0}")]
+    UnsupportedVersion(String),
+    #[error("{0}")]
+    Wakeup(String),
+}
+
+impl KafkaError {
+    /// A retriable exception is a transient exception that if retried may succeed
+    /// (`RetriableException` subclasses).
+    pub fn is_retriable(&self) -> bool {
+        self.is_invalid_metadata()
+            || matches!(
+                self,
+                KafkaError::BufferExhausted(_)
+                    | KafkaError::CoordinatorLoadInProgress(_)
+                    | KafkaError::CoordinatorNotAvailable(_)
+                    | KafkaError::CorruptRecord(_)
+                    | KafkaError::FetchSessionIdNotFound(_)
+                    | KafkaError::InvalidFetchSessionEpoch(_)
+                    | KafkaError::NotController(_)
+                    | KafkaError::NotCoordinator(_)
+                    | KafkaError::NotEnoughReplicas(_)
+                    | KafkaError::NotEnoughReplicasAfterAppend(_)
+                    | KafkaError::OffsetNotAvailable(_)
+                    | KafkaError::RetriableCommitFailed(_)
+                    | KafkaError::ThrottlingQuotaExceeded(_)
+                    | KafkaError::Timeout(_)
+                    | KafkaError::UnjoinedGroup(_)
+                    | KafkaError::UnknownLeaderEpoch(_)
+                    | KafkaError::UnstableOffsetCommit(_)
+            )
+    }
+
+    /// An exception that may indicate the client's metadata is out of date
+    /// (`InvalidMetadataException` subclasses).
+    pub fn is_invalid_metadata(&self) -> bool {
+        matches!(
+            self,
+            KafkaError::ElectionNotNeeded(_)
+                | KafkaError::EligibleLeadersNotAvailable(_)
+                | KafkaError::FencedLeaderEpoch(_)
+                | KafkaError::InconsistentTopicId(_)
+                | KafkaError::KafkaStorage(_)
+                | KafkaError::LeaderNotAvailable(_)
+                | KafkaError::ListenerNotFound(_)
+                | KafkaError::Network(_)
+                | KafkaError::NotLeaderOrFollower(_)
+                | KafkaError::PreferredLeaderNotAvailable(_)
+                | KafkaError::ReplicaNotAvailable(_)
+                | KafkaError::UnknownTopicId(_)
+                | KafkaError::UnknownTopicOrPartition(_)
+        )
+    }
+
+    /// JNI name of the java exception class the error corresponds to.
+    pub fn java_class(&self) -> &'static str {
+        match self {
+            KafkaError::Kafka(_) => "org/apache/kafka/common/KafkaException",
+            KafkaError::BrokerIdNotRegistered(_) => {
+                "org/apache/kafka/common/errors/BrokerIdNotRegisteredException"
+            }
+            KafkaError::BrokerNotAvailable(_) => {
+                "org/apache/kafka/common/errors/BrokerNotAvailableException"
+            }
+            KafkaError::BufferExhausted(_) => {
+                "org/apache/kafka/clients/producer/BufferExhaustedException"
+            }
+            KafkaError::ClusterAuthorization(_) => {
+                "org/apache/kafka/common/errors/ClusterAuthorizationException"
+            }
+            KafkaError::CommitFailed(_) => {
+                "org/apache/kafka/clients/consumer/CommitFailedException"
+            }
+            KafkaError::ConcurrentModification(_) => "java/util/ConcurrentModificationException",
+            KafkaError::ConcurrentTransactions(_) => {
+                "org/apache/kafka/common/errors/ConcurrentTransactionsException"
+            }
+            KafkaError::Config(_) => "org/apache/kafka/common/config/ConfigException",
+            KafkaError::ControllerMoved(_) => {
+                "org/apache/kafka/common/errors/ControllerMovedException"
+            }
+            KafkaError::CoordinatorLoadInProgress(_) => {
+                "org/apache/kafka/common/errors/CoordinatorLoadInProgressException"
+            }
+            KafkaError::CoordinatorNotAvailable(_) => {
+                "org/apache/kafka/common/errors/CoordinatorNotAvailableException"
+            }
+            KafkaError::CorruptRecord(_) => "org/apache/kafka/common/errors/CorruptRecordException",
+            KafkaError::DelegationTokenAuthorization(_) => {
+                "org/apache/kafka/common/errors/DelegationTokenAuthorizationException"
+            }
+            KafkaError::DelegationTokenDisabled(_) => {
+                "org/apache/kafka/common/errors/DelegationTokenDisabledException"
+            }
+            KafkaError::DelegationTokenExpired(_) => {
+                "org/apache/kafka/common/errors/DelegationTokenExpiredException"
+            }
+            KafkaError::DelegationTokenNotFound(_) => {
+                "org/apache/kafka/common/errors/DelegationTokenNotFoundException"
+            }
+            KafkaError::DelegationTokenOwnerMismatch(_) => {
+                "org/apache/kafka/common/errors/DelegationTokenOwnerMismatchException"
+            }
+            KafkaError::DuplicateBrokerRegistration(_) => {
+                "org/apache/kafka/common/errors/DuplicateBrokerRegistrationException"
+            }
+            KafkaError::DuplicateResource(_) => {
+                "org/apache/kafka/common/errors/DuplicateResourceException"
+            }
+            KafkaError::DuplicateSequence(_) => {
+                "org/apache/kafka/common/errors/DuplicateSequenceException"
+            }
+            KafkaError::ElectionNotNeeded(_) => {
+                "org/apache/kafka/common/errors/ElectionNotNeededException"
+            }
+            KafkaError::EligibleLeadersNotAvailable(_) => {
+                "org/apache/kafka/common/errors/EligibleLeadersNotAvailableException"
+            }
+            KafkaError::FeatureUpdateFailed(_) => {
+                "org/apache/kafka/common/errors/FeatureUpdateFailedException"
+            }
+            KafkaError::FencedInstanceId(_) => {
+                "org/apache/kafka/common/errors/FencedInstanceIdException"
+            }
+            KafkaError::FencedLeaderEpoch(_) => {
+                "org/apache/kafka/common/errors/FencedLeaderEpochException"
+            }
+            KafkaError::FetchSessionIdNotFound(_) => {
+                "org/apache/kafka/common/errors/FetchSessionIdNotFoundException"
+            }
+            KafkaError::GroupAuthorization(_) => {
+                "org/apache/kafka/common/errors/GroupAuthorizationException"
+            }
+            KafkaError::GroupIdNotFound(_) => {
+                "org/apache/kafka/common/errors/GroupIdNotFoundException"
+            }
+            KafkaError::GroupMaxSizeReached(_) => {
+                "org/apache/kafka/common/errors/GroupMaxSizeReachedException"
+            }
+            KafkaError::GroupNotEmpty(_) => "org/apache/kafka/common/errors/GroupNotEmptyException",
+            KafkaError::GroupSubscribedToTopic(_) => {
+                "org/apache/kafka/common/errors/GroupSubscribedToTopicException"
+            }
+            KafkaError::IllegalArgument(_) => "java/lang/IllegalArgumentException",
+            KafkaError::IllegalGeneration(_) => {
+                "org/apache/kafka/common/errors/IllegalGenerationException"
+            }
+            KafkaError::IllegalSaslState(_) => {
+                "org/apache/kafka/common/errors/IllegalSaslStateException"
+            }
+            KafkaError::IllegalState(_) => "java/lang/IllegalStateException",
+            KafkaError::InconsistentClusterId(_) => {
+                "org/apache/kafka/common/errors/InconsistentClusterIdException"
+            }
+            KafkaError::InconsistentGroupProtocol(_) => {
+                "org/apache/kafka/common/errors/InconsistentGroupProtocolException"
+            }
+            KafkaError::InconsistentTopicId(_) => {
+                "org/apache/kafka/common/errors/InconsistentTopicIdException"
+            }
+            KafkaError::InconsistentVoterSet(_) => {
+                "org/apache/kafka/common/errors/InconsistentVoterSetException"
+            }
+            KafkaError::Interrupt(_) => "org/apache/kafka/common/errors/InterruptException",
+            KafkaError::InvalidCommitOffsetSize(_) => {
+                "org/apache/kafka/common/errors/InvalidCommitOffsetSizeException"
+            }
+            KafkaError::InvalidConfiguration(_) => {
+                "org/apache/kafka/common/errors/InvalidConfigurationException"
+            }
+            KafkaError::InvalidFetchSessionEpoch(_) => {
+                "org/apache/kafka/common/errors/InvalidFetchSessionEpochException"
+            }
+            KafkaError::InvalidFetchSize(_) => {
+                "org/apache/kafka/common/errors/InvalidFetchSizeException"
+            }
+            KafkaError::InvalidGroupId(_) => {
+                "org/apache/kafka/common/errors/InvalidGroupIdException"
+            }
+            KafkaError::InvalidPartitions(_) => {
+                "org/apache/kafka/common/errors/InvalidPartitionsException"
+            }
+            KafkaError::InvalidPidMapping(_) => {
+                "org/apache/kafka/common/errors/InvalidPidMappingException"
+            }
+            KafkaError::InvalidPrincipalType(_) => {
+                "org/apache/kafka/common/errors/InvalidPrincipalTypeException"
+            }
+            KafkaError::InvalidProducerEpoch(_) => {
+                "org/apache/kafka/common/errors/InvalidProducerEpochException"
+            }
+            KafkaError::InvalidRecord(_) => "org/apache/kafka/common/InvalidRecordException",
+            KafkaError::InvalidReplicaAssignment(_) => {
+                "org/apache/kafka/common/errors/InvalidReplicaAssignmentException"
+            }
+            KafkaError::InvalidReplicationFactor(_) => {
+                "org/apache/kafka/common/errors/InvalidReplicationFactorException"
+            }
+            KafkaError::InvalidRequest(_) => {
+                "org/apache/kafka/common/errors/InvalidRequestException"
+            }
+            KafkaError::InvalidRequiredAcks(_) => {
+                "org/apache/kafka/common/errors/InvalidRequiredAcksException"
+            }
+            KafkaError::InvalidSessionTimeout(_) => {
+                "org/apache/kafka/common/errors/InvalidSessionTimeoutException"
+            }
+            KafkaError::InvalidTimestamp(_) => {
+                "org/apache/kafka/common/errors/InvalidTimestampException"
+            }
+            KafkaError::InvalidTopic(_) => "org/apache/kafka/common/errors/InvalidTopicException",
+            KafkaError::InvalidTxnState(_) => {
+                "org/apache/kafka/common/errors/InvalidTxnStateException"
+            }
+            KafkaError::InvalidTxnTimeout(_) => {
+                "org/apache/kafka/common/errors/InvalidTxnTimeoutException"
+            }
+            KafkaError::InvalidUpdateVersion(_) => {
+                "org/apache/kafka/common/errors/InvalidUpdateVersionException"
+            }
+            KafkaError::KafkaStorage(_) => "org/apache/kafka/common/errors/KafkaStorageException",
+            KafkaError::LeaderNotAvailable(_) => {
+                "org/apache/kafka/common/errors/LeaderNotAvailableException"
+            }
+            KafkaError::ListenerNotFound(_) => {
+                "org/apache/kafka/common/errors/ListenerNotFoundException"
+            }
+            KafkaError::LogDirNotFound(_) => {
+                "org/apache/kafka/common/errors/LogDirNotFoundException"
+            }
+            KafkaError::LogTruncation(_) => {
+                "org/apache/kafka/clients/consumer/LogTruncationException"
+            }
+            KafkaError::MemberIdRequired(_) => {
+                "org/apache/kafka/common/errors/MemberIdRequiredException"
+            }
+            KafkaError::Network(_) => "org/apache/kafka/common/errors/NetworkException",
+            // Only built from the partitions, which the error doesn't keep.
+            KafkaError::NoOffsetForPartition(_) => "org/apache/kafka/common/KafkaException",
+            KafkaError::NoReassignmentInProgress(_) => {
+                "org/apache/kafka/common/errors/NoReassignmentInProgressException"
+            }
+            KafkaError::NotController(_) => "org/apache/kafka/common/errors/NotControllerException",
+            KafkaError::NotCoordinator(_) => {
+                "org/apache/kafka/common/errors/NotCoordinatorException"
+            }
+            KafkaError::NotEnoughReplicas(_) => {
+                "org/apache/kafka/common/errors/NotEnoughReplicasException"
+            }
+            KafkaError::NotEnoughReplicasAfterAppend(_) => {
+                "org/apache/kafka/common/errors/NotEnoughReplicasAfterAppendException"
+            }
+            KafkaError::NotLeaderOrFollower(_) => {
+                "org/apache/kafka/common/errors/NotLeaderOrFollowerException"
+            }
+            KafkaError::OffsetMetadataTooLarge(_) => {
+                "org/apache/kafka/common/errors/OffsetMetadataTooLarge"
+            }
+            KafkaError::OffsetNotAvailable(_) => {
+                "org/apache/kafka/common/errors/OffsetNotAvailableException"
+            }
+            KafkaError::OffsetOutOfRange(_) => {
+                "org/apache/kafka/common/errors/OffsetOutOfRangeException"
+            }
+            KafkaError::OperationNotAttempted(_) => {
+                "org/apache/kafka/common/errors/OperationNotAttemptedException"
+            }
+            KafkaError::OutOfOrderSequence(_) => {
+                "org/apache/kafka/common/errors/OutOfOrderSequenceException"
+            }
+            KafkaError::PolicyViolation(_) => {
+                "org/apache/kafka/common/errors/PolicyViolationException"
+            }
+            KafkaError::PositionOutOfRange(_) => {
+                "org/apache/kafka/common/errors/PositionOutOfRangeException"
+            }
+            KafkaError::PreferredLeaderNotAvailable(_) => {
+                "org/apache/kafka/common/errors/PreferredLeaderNotAvailableException"
+            }
+            KafkaError::PrincipalDeserialization(_) => {
+                "org/apache/kafka/common/errors/PrincipalDeserializationException"
+            }
+            KafkaError::ProducerFenced(_) => {
+                "org/apache/kafka/common/errors/ProducerFencedException"
+            }
+            KafkaError::ReassignmentInProgress(_) => {
+                "org/apache/kafka/common/errors/ReassignmentInProgressException"
+            }
+            KafkaError::RebalanceInProgress(_) => {
+                "org/apache/kafka/common/errors/RebalanceInProgressException"
+            }
+            KafkaError::RecordBatchTooLarge(_) => {
+                "org/apache/kafka/common/errors/RecordBatchTooLargeException"
+            }
+            KafkaError::RecordTooLarge(_) => {
+                "org/apache/kafka/common/errors/RecordTooLargeException"
+            }
+            KafkaError::ReplicaNotAvailable(_) => {
+                "org/apache/kafka/common/errors/ReplicaNotAvailableException"
+            }
+            KafkaError::ResourceNotFound(_) => {
+                "org/apache/kafka/common/errors/ResourceNotFoundException"
+            }
+            KafkaError::RetriableCommitFailed(_) => {
+                "org/apache/kafka/clients/consumer/RetriableCommitFailedException"
+            }
+            KafkaError::SaslAuthentication(_) => {
+                "org/apache/kafka/common/errors/SaslAuthenticationException"
+            }
+            KafkaError::SecurityDisabled(_) => {
+                "org/apache/kafka/common/errors/SecurityDisabledException"
+            }
+            KafkaError::Serialization(_) => "org/apache/kafka/common/errors/SerializationException",
+            KafkaError::SnapshotNotFound(_) => {
+                "org/apache/kafka/common/errors/SnapshotNotFoundException"
+            }
+            KafkaError::StaleBrokerEpoch(_) => {
+                "org/apache/kafka/common/errors/StaleBrokerEpochException"
+            }
+            KafkaError::ThrottlingQuotaExceeded(_) => {
+                "org/apache/kafka/common/errors/ThrottlingQuotaExceededException"
+            }
+            KafkaError::Timeout(_) => "org/apache/kafka/common/errors/TimeoutException",
+            KafkaError::TopicAuthorization(_) => {
+                "org/apache/kafka/common/errors/TopicAuthorizationException"
+            }
+            KafkaError::TopicDeletionDisabled(_) => {
+                "org/apache/kafka/common/errors/TopicDeletionDisabledException"
+            }
+            KafkaError::TopicExists(_) => "org/apache/kafka/common/errors/TopicExistsException",
+            KafkaError::TransactionAborted(_) => {
+                "org/apache/kafka/common/errors/TransactionAbortedException"
+            }
+            KafkaError::TransactionCoordinatorFenced(_) => {
+                "org/apache/kafka/common/errors/TransactionCoordinatorFencedException"
+            }
+            KafkaError::TransactionalIdAuthorization(_) => {
+                "org/apache/kafka/common/errors/TransactionalIdAuthorizationException"
+            }
+            KafkaError::TransactionalIdNotFound(_) => {
+                "org/apache/kafka/common/errors/TransactionalIdNotFoundException"
+            }
+            KafkaError::UnacceptableCredential(_) => {
+                "org/apache/kafka/common/errors/UnacceptableCredentialException"
+            }
+            KafkaError::UnjoinedGroup(_) => "org/apache/kafka/common/KafkaException",
+            KafkaError::UnknownLeaderEpoch(_) => {
+                "org/apache/kafka/common/errors/UnknownLeaderEpochException"
+            }
+            KafkaError::UnknownMemberId(_) => {
+                "org/apache/kafka/common/errors/UnknownMemberIdException"
+            }
+            KafkaError::UnknownProducerId(_) => {
+                "org/apache/kafka/common/errors/UnknownProducerIdException"
+            }
+            KafkaError::UnknownServer(_) => "org/apache/kafka/common/errors/UnknownServerException",
+            KafkaError::UnknownTopicId(_) => {
+                "org/apache/kafka/common/errors/UnknownTopicIdException"
+            }
+            KafkaError::UnknownTopicOrPartition(_) => {
+                "org/apache/kafka/common/errors/UnknownTopicOrPartitionException"
+            }
+            KafkaError::UnstableOffsetCommit(_) => {
+                "org/apache/kafka/common/errors/UnstableOffsetCommitException"
+            }
+            KafkaError::UnsupportedByAuthentication(_) => {
+                "org/apache/kafka/common/errors/UnsupportedByAuthenticationException"
+            }
+            KafkaError::UnsupportedCompressionType(_) => {
+                "org/apache/kafka/common/errors/UnsupportedCompressionTypeException"
+            }
+            KafkaError::UnsupportedForMessageFormat(_) => {
+                "org/apache/kafka/common/errors/UnsupportedForMessageFormatException"
+            }
+            KafkaError::UnsupportedSaslMechanism(_) => {
+                "org/apache/kafka/common/errors/UnsupportedSaslMechanismException"
+            }
+            KafkaError::UnsupportedVersion(_) => {
+                "org/apache/kafka/common/errors/UnsupportedVersionException"
+            }
+            KafkaError::Wakeup(_) => "org/apache/kafka/common/errors/WakeupException",
+        }
+    }
+
+    /// Throws the java exception matching the error in the calling java thread. The exception
+    /// becomes pending and is raised once the native method returns.
+    pub fn throw(&self, env: JNIEnv) -> jni::errors::Result<()> {
+        let exception = self.to_java_exception(env)?;
+        env.throw(JThrowable::from(exception))
+    }
+
+    /// New instance of the java exception matching the error, for java callbacks expecting one.
+    pub fn to_java_exception<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+        if let KafkaError::Wakeup(_) = self {
+            // Has no message.
+            return jni_cache::new_object(env, self.java_class(), "()V", &[]);
+        }
+        let message = env.new_string(self.to_string())?;
+        match self {
+            // The offsets are not kept by the error, the exception gets empty maps.
+            KafkaError::LogTruncation(_) => {
+                let offsets = jni_cache::call_static_method(
+                    env,
+                    "java/util/Collections",
+                    "emptyMap",
+                    "()Ljava/util/Map;",
+                    &[],
+                )?;
+                jni_cache::new_object(
+                    env,
+                    self.java_class(),
+                    "(Ljava/lang/String;Ljava/util/Map;Ljava/util/Map;)V",
+                    &[message.into(), offsets, offsets],
+                )
+            }
+            _ => jni_cache::new_object(
+                env,
+                self.java_class(),
+                "(Ljava/lang/String;)V",
+                &[message.into()],
+            ),
+        }
+    }
+}
+
+impl From<JavaException> for KafkaError {
+    fn from(exception: JavaException) -> Self {
+        KafkaError::Kafka(exception.to_string())
+    }
+}
+
+impl From<JniError> for KafkaError {
+    fn from(error: JniError) -> Self {
+        match error {
+            JniError::Jni(error) => KafkaError::Kafka(error.to_string()),
+            JniError::Java(exception) => exception.into(),
+            JniError::Kafka(error) => error,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, KafkaError>;
+
+/// Failure of the native side of a JNI entry point.
+#[derive(Error, Debug)]
+pub enum JniError {
+    /// A JNI call failed, `JavaException` means the java exception is already pending.
+    #[error(transparent)]
+    Jni(#[from] jni::errors::Error),
+    /// Java code called from rust threw, the exception is no longer pending.
+    #[error(transparent)]
+    Java(#[from] JavaException),
+    #[error(transparent)]
+    Kafka(#[from] KafkaError),
+}
+
+impl JniError {
+    /// Raises the error in the calling java thread: Kafka errors throw the matching java
+    /// exception, failed JNI calls throw `IllegalStateException` unless an exception is already
+    /// pending.
+    pub fn throw(&self, env: JNIEnv) {
+        let result = match self {
+            JniError::Jni(jni::errors::Error::JavaException) => Ok(()),
+            JniError::Jni(error) => {
+                env.throw_new("java/lang/IllegalStateException", error.to_string())
+            }
+            JniError::Java(exception) => {
+                env.throw(JThrowable::from(exception.exception.as_obj().into_inner()))
+            }
+            JniError::Kafka(error) => error.throw(env),
+        };
+        if let Err(error) = result {
+            error!("Failed to throw java exception for {:?}: {}", self, error);
+        }
+    }
+}
+
+pub type JniResult<T> = std::result::Result<T, JniError>;
+
+/// Exception thrown by java code called from rust, taken out of the thread it was pending in.
+#[derive(Clone)]
+pub struct JavaException {
+    /// Binary name of the exception class, e.g. `java.lang.IllegalStateException`.
+    pub class_name: String,
+    pub message: Option<String>,
+    pub exception: GlobalRef,
+}
+
+impl JavaException {
+    /// Clears the exception pending in the current thread and returns it.
+    pub fn take(env: JNIEnv) -> jni::errors::Result<Option<JavaException>> {
+        if !env.exception_check()? {
+            return Ok(None);
+        }
+        let throwable = env.exception_occurred()?;
+        env.exception_clear()?;
+        let class = env.get_object_class(throwable)?;
+        let class_name = jni_cache::call_method(
+            env,
+            class.into(),
+            "java/lang/Class",
+            "getName",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        let message = jni_cache::call_method(
+            env,
+            throwable.into(),
+            "java/lang/Throwable",
+            "getMessage",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        Ok(Some(JavaException {
+            class_name: String::clone_from_java(env, class_name)?,
+            message: Option::<String>::clone_from_java(env, message)?,
+            exception: env.new_global_ref(throwable)?,
+        }))
+    }
+}
+
+/// Formatted as `Throwable.toString`.
+impl fmt::Display for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", self.class_name, message),
+            None => write!(f, "{}", self.class_name),
+        }
+    }
+}
+
+impl fmt::Debug for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JavaException")
+            .field("class_name", &self.class_name)
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl std::error::Error for JavaException {}
+
+#[cfg(test)]
+mod tests {
+    use jni::objects::JObject;
+
+    use super::KafkaError;
+    use crate::{jni_cache, jvm};
+
+    macro_rules! errors {
+        ($($variant:ident),* $(,)?) => {
+            vec![$(KafkaError::$variant(stringify!($variant).to_string())),*]
+        };
+    }
+
+    fn all_errors() -> Vec<KafkaError> {
+        errors![
+            Kafka,
+            BrokerIdNotRegistered,
+            BrokerNotAvailable,
+            BufferExhausted,
+            ClusterAuthorization,
+            CommitFailed,
+            ConcurrentModification,
+            ConcurrentTransactions,
+            Config,
+            ControllerMoved,
+            CoordinatorLoadInProgress,
+            CoordinatorNotAvailable,
+            CorruptRecord,
+            DelegationTokenAuthorization,
+            DelegationTokenDisabled,
+            DelegationTokenExpired,
+            DelegationTokenNotFound,
+            DelegationTokenOwnerMismatch,
+            DuplicateBrokerRegistration,
+            DuplicateResource,
+            DuplicateSequence,
+            ElectionNotNeeded,
+            EligibleLeadersNotAvailable,
+            FeatureUpdateFailed,
+            FencedInstanceId,
+            FencedLeaderEpoch,
+            FetchSessionIdNotFound,
+            GroupAuthorization,
+            GroupIdNotFound,
+            GroupMaxSizeReached,
+            GroupNotEmpty,
+            GroupSubscribedToTopic,
+            IllegalArgument,
+            IllegalGeneration,
+            IllegalSaslState,
+            IllegalState,
+            InconsistentClusterId,
+            InconsistentGroupProtocol,
+            InconsistentTopicId,
+            InconsistentVoterSet,
+            Interrupt,
+            InvalidCommitOffsetSize,
+            InvalidConfiguration,
+            InvalidFetchSessionEpoch,
+            InvalidFetchSize,
+            InvalidGroupId,
+            InvalidPartitions,
+            InvalidPidMapping,
+            InvalidPrincipalType,
+            InvalidProducerEpoch,
+            InvalidRecord,
+            InvalidReplicaAssignment,
+            InvalidReplicationFactor,
+            InvalidRequest,
+            InvalidRequiredAcks,
+            InvalidSessionTimeout,
+            InvalidTimestamp,
+            InvalidTopic,
+            InvalidTxnState,
+            InvalidTxnTimeout,
+            InvalidUpdateVersion,
+            KafkaStorage,
+            LeaderNotAvailable,
+            ListenerNotFound,
+            LogDirNotFound,
+            LogTruncation,
+            MemberIdRequired,
+            Network,
+            NoOffsetForPartition,
+            NoReassignmentInProgress,
+            NotController,
+            NotCoordinator,
+            NotEnoughReplicas,
+            NotEnoughReplicasAfterAppend,
+            NotLeaderOrFollower,
+            OffsetMetadataTooLarge,
+            OffsetNotAvailable,
+            OffsetOutOfRange,
+            OperationNotAttempted,
+            OutOfOrderSequence,
+            PolicyViolation,
+            PositionOutOfRange,
+            PreferredLeaderNotAvailable,
+            PrincipalDeserialization,
+            ProducerFenced,
+            ReassignmentInProgress,
+            RebalanceInProgress,
+            RecordBatchTooLarge,
+            RecordTooLarge,
+            ReplicaNotAvailable,
+            ResourceNotFound,
+            RetriableCommitFailed,
+            SaslAuthentication,
+            SecurityDisabled,
+            Serialization,
+            SnapshotNotFound,
+            StaleBrokerEpoch,
+            ThrottlingQuotaExceeded,
+            Timeout,
+            TopicAuthorization,
+            TopicDeletionDisabled,
+            TopicExists,
+            TransactionAborted,
+            TransactionCoordinatorFenced,
+            TransactionalIdAuthorization,
+            TransactionalIdNotFound,
+            UnacceptableCredential,
+            UnjoinedGroup,
+            UnknownLeaderEpoch,
+            UnknownMemberId,
+            UnknownProducerId,
+            UnknownServer,
+            UnknownTopicId,
+            UnknownTopicOrPartition,
+            UnstableOffsetCommit,
+            UnsupportedByAuthentication,
+            UnsupportedCompressionType,
+            UnsupportedForMessageFormat,
+            UnsupportedSaslMechanism,
+            UnsupportedVersion,
+            Wakeup
+        ]
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn every_error_has_a_java_exception() {
+        jvm::run(|env| {
+            for error in all_errors() {
+                env.with_local_frame(8, || {
+                    let exception = error.to_java_exception(env)?;
+                    let class = jni_cache::class(env, error.java_class())?;
+                    assert!(env.is_instance_of(exception, &class)?, "{:?}", error);
+                    let message =
+                        env.call_method(exception, "getMessage", "()Ljava/lang/String;", &[])?;
+                    let message = message.l()?;
+                    match error {
+                        KafkaError::Wakeup(_) => assert!(message.is_null()),
+                        _ => assert_eq!(jvm::string(env, message)?, error.to_string()),
+                    }
+                    Ok(JObject::null())
+                })?;
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn throw_raises_the_java_exception() {
+        jvm::run(|env| {
+            let error = KafkaError::LogTruncation("truncated".to_string());
+            assert_eq!(
+                jvm::expect_exception(env, || error.throw(env))?,
+                "org.apache.kafka.clients.consumer.LogTruncationException"
+            );
+            let error = KafkaError::Wakeup(String::new());
+            assert_eq!(
+                jvm::expect_exception(env, || error.throw(env))?,
+                "org.apache.kafka.common.errors.WakeupException"
+            );
+            Ok(())
+        });
+    }
+}
@@ -1,15 +1,22 @@
-use crate::java_struct_standard_impl;
+use crate::{
+    clone_from_java::CloneFromJava, clone_to_java::CloneToJava, common::errors::JniResult,
+    direct_byte_buffer::DirectByteBuffer, java_stored_object::JavaStoredObject,
+    jni_guard::jni_guard,
+};
 use bytes::Bytes;
 use jni::{
-    objects::{JObject, JValue},
-    sys::{jbyteArray, jstring},
+    objects::JObject,
+    sys::{jbyteArray, jobject, jstring},
     JNIEnv,
 };
-use kafka_connector_macros::rust_property_getter;
+use kafka_connector_macros::JavaStruct;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, JavaStruct)]
+#[java_class = "org/apache/kafka/common/header/internals/RecordHeader"]
 pub struct RecordHeader {
+    #[java_getter]
     pub key: String,
+    #[java_getter]
     pub value: Bytes,
 }
 impl RecordHeader {
@@ -19,11 +26,6 @@ impl RecordHeader {
     }
 }
 
-java_struct_standard_impl!(
-    RecordHeader,
-    "org/apache/kafka/common/header/internals/RecordHeader"
-);
-
 /*
  * Class:     org_apache_kafka_common_header_internals_RecordHeader
  * Method:    rustConstructor
@@ -31,64 +33,62 @@ java_struct_standard_impl!(
  */
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeader_rustConstructor(
+pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeader_rustConstructor__Ljava_lang_String_2_3B(
     env: JNIEnv,
     obj: JObject,
     key: jstring,
     value: jbyteArray,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let key: String = env.get_string(key.into())?.into();
         let value = if value.is_null() {
             vec![]
         } else {
-            env.convert_byte_array(value).unwrap()
+            env.convert_byte_array(value)?
         };
-        let record_header = Box::new(RecordHeader::new(key, value));
-        let ptr = Box::into_raw(record_header);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(env, obj, RecordHeader::new(key, value))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
  * Class:     org_apache_kafka_common_header_internals_RecordHeader
- * Method:    rustDestructor
- * Signature: ()V
+ * Method:    rustConstructor
+ * Signature: (Ljava/lang/String;Ljava/nio/ByteBuffer;)V
  */
 #[no_mangle]
 #[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeader_rustDestructor(
+pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeader_rustConstructor__Ljava_lang_String_2Ljava_nio_ByteBuffer_2(
     env: JNIEnv,
     obj: JObject,
+    key: jstring,
+    value: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut RecordHeader) };
+    jni_guard(env, || -> JniResult<_> {
+        let key: String = env.get_string(key.into())?.into();
+        let value = Option::<DirectByteBuffer>::clone_from_java(env, value.into())?;
+        let value = value.map(Bytes::from).unwrap_or_default();
+        JavaStoredObject::store(env, obj, RecordHeader::new(key, value))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
-rust_property_getter!(
- * Struct:    RecordHeader
- * Class:     org_apache_kafka_common_header_internals_RecordHeader
- * Method:    key
- * Signature: ()Ljava/lang/String;
-);
-
-rust_property_getter!(
- * Struct:    RecordHeader
+/*
  * Class:     org_apache_kafka_common_header_internals_RecordHeader
- * Method:    value
- * Signature: ()[]B
-);
+ * Method:    valueBuffer
+ * Signature: ()Ljava/nio/ByteBuffer;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeader_valueBuffer__(
+    env: JNIEnv,
+    obj: JObject,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let header = RecordHeader::clone_from_java(env, obj.into())?;
+        let value = DirectByteBuffer(header.value).clone_to_java(env)?;
+        Ok(value.l()?.into_inner())
+    })
+}
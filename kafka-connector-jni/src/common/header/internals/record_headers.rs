@@ -1,10 +1,12 @@
 use crate::{
-    clone_from_java::CloneFromJava, clone_to_java::CloneToJava, java_struct_standard_impl,
-};
-use std::{
-    ops::{Deref, DerefMut},
-    panic,
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::errors::JniResult,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl, jni_cache,
+    jni_guard::jni_guard,
 };
+use std::ops::{Deref, DerefMut};
 
 use jni::{
     objects::{JObject, JValue},
@@ -46,17 +48,11 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let record_headers = Box::new(RecordHeaders::default());
-        let ptr = Box::into_raw(record_headers);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, RecordHeaders::default())?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_header_internals_RecordHeaders
@@ -69,16 +65,11 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<RecordHeaders>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -93,14 +84,11 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     obj: JObject,
     header: JObject,
 ) -> jobject {
-    let result = || -> jni::errors::Result<()> {
-        let class = env
-            .find_class("java/util/Objects")
-            .expect("Failed to load the target class");
-
-        let error_msg = env.new_string("Header cannot be null.").unwrap();
-        env.call_static_method(
-            class,
+    jni_guard(env, || -> JniResult<jobject> {
+        let error_msg = env.new_string("Header cannot be null.")?;
+        jni_cache::call_static_method(
+            env,
+            "java/util/Objects",
             "requireNonNull",
             "(Ljava/lang/Object;Ljava/lang/String;)Ljava/lang/Object;",
             &[JValue::Object(header), JValue::Object(error_msg.into())],
@@ -108,19 +96,9 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
 
         let header = RecordHeader::clone_from_java(env, header.into())?;
 
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let mut headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
-        headers.push(header);
-        let _ptr = Box::into_raw(headers);
-        Ok(())
-    }();
-
-    match result {
-        Ok(()) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!(),
-    }
-
-    obj.into_inner()
+        RecordHeaders::from_jobject(env, obj)?.lock().push(header);
+        Ok(obj.into_inner())
+    })
 }
 
 /*
@@ -136,7 +114,7 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     key: jstring,
     value: jbyteArray,
 ) -> jobject {
-    let result = || -> jni::errors::Result<()> {
+    jni_guard(env, || -> JniResult<jobject> {
         let key: String = env.get_string(key.into())?.into();
         let value = if value.is_null() {
             vec![]
@@ -145,20 +123,10 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
         };
         let header = RecordHeader::new(key, value);
 
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let mut headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
-        headers.push(header);
-        let _ptr = Box::into_raw(headers);
-
-        Ok(())
-    }();
+        RecordHeaders::from_jobject(env, obj)?.lock().push(header);
 
-    match result {
-        Ok(()) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!(),
-    }
-
-    obj.into_inner()
+        Ok(obj.into_inner())
+    })
 }
 
 /*
@@ -173,34 +141,23 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     obj: JObject,
     key: jstring,
 ) -> jobject {
-    let result = || -> jni::errors::Result<()> {
-        env.call_method(
+    jni_guard(env, || -> JniResult<jobject> {
+        jni_cache::call_method(
+            env,
             obj,
+            "org/apache/kafka/common/header/internals/RecordHeaders",
             "checkKey",
             "(Ljava/lang/String;)V",
             &[JValue::Object(key.into())],
         )?;
         let key: String = env.get_string(key.into())?.into();
 
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
-        let filtered = headers
-            .0
-            .into_iter()
-            .filter(|header| header.key != key)
-            .collect::<Vec<_>>();
-        let headers = RecordHeaders(filtered);
-        let ptr = Box::into_raw(Box::new(headers));
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
-
-        Ok(())
-    }();
-    match result {
-        Ok(()) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!(),
-    }
+        RecordHeaders::from_jobject(env, obj)?
+            .lock()
+            .retain(|header| header.key != key);
 
-    obj.into_inner()
+        Ok(obj.into_inner())
+    })
 }
 
 /*
@@ -215,18 +172,20 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     obj: JObject,
     key: jstring,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        env.call_method(
+    jni_guard(env, || -> JniResult<_> {
+        jni_cache::call_method(
+            env,
             obj,
+            "org/apache/kafka/common/header/internals/RecordHeaders",
             "checkKey",
             "(Ljava/lang/String;)V",
             &[JValue::Object(key.into())],
         )?;
 
         let key: String = env.get_string(key.into())?.into();
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
+        let headers = RecordHeaders::from_jobject(env, obj)?;
         let result = headers
+            .lock()
             .iter()
             .rev()
             .find(|x| x.key == key)
@@ -234,15 +193,9 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
             .transpose()?
             .map(JValue::l)
             .transpose();
-        let _ptr = Box::into_raw(headers);
 
-        Ok(result?.unwrap_or_else(JObject::null))
-    }();
-    match result {
-        Ok(val) => val.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+        Ok(result?.unwrap_or_else(JObject::null).into_inner())
+    })
 }
 
 /*
@@ -257,44 +210,48 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     obj: JObject,
     key: jstring,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        env.call_method(
+    jni_guard(env, || -> JniResult<_> {
+        jni_cache::call_method(
+            env,
             obj,
+            "org/apache/kafka/common/header/internals/RecordHeaders",
             "checkKey",
             "(Ljava/lang/String;)V",
             &[JValue::Object(key.into())],
         )?;
 
         let key: String = env.get_string(key.into())?.into();
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
+        let headers = RecordHeaders::from_jobject(env, obj)?;
         let filtered = headers
+            .lock()
             .iter()
             .filter(|x| x.key == key)
             .map(|header| header.clone_to_java(env))
             .collect::<jni::errors::Result<Vec<_>>>();
-        let _ptr = Box::into_raw(headers);
         let result = filtered?
             .into_iter()
             .map(JValue::l)
-            .collect::<jni::errors::Result<Vec<_>>>()?
-            .into_iter()
-            .map(Into::into)
-            .collect::<Vec<JObject>>();
+            .collect::<jni::errors::Result<Vec<JObject>>>()?;
 
-        let array_list_class = env.find_class("java/util/ArrayList")?;
-        let array = env.new_object(array_list_class, "(I)V", &[(result.len() as i32).into()])?;
+        let array = jni_cache::new_object(
+            env,
+            "java/util/ArrayList",
+            "(I)V",
+            &[(result.len() as i32).into()],
+        )?;
         for object in result {
-            env.call_method(array, "add", "(Ljava/lang/Object;)Z", &[object.into()])?;
+            jni_cache::call_method(
+                env,
+                array,
+                "java/util/Collection",
+                "add",
+                "(Ljava/lang/Object;)Z",
+                &[object.into()],
+            )?;
         }
 
-        Ok(array)
-    }();
-    match result {
-        Ok(val) => val.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+        Ok(array.into_inner())
+    })
 }
 
 /*
@@ -308,38 +265,46 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     env: JNIEnv,
     obj: JObject,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let record_headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
+    jni_guard(env, || -> JniResult<_> {
+        let record_headers = RecordHeaders::from_jobject(env, obj)?;
         let filtered = record_headers
+            .lock()
             .iter()
             .map(|header| header.clone_to_java(env))
             .collect::<jni::errors::Result<Vec<_>>>();
-        let _ptr = Box::into_raw(record_headers);
         let result = filtered?
             .into_iter()
             .map(JValue::l)
-            .collect::<jni::errors::Result<Vec<_>>>()?
-            .into_iter()
-            .map(Into::into)
-            .collect::<Vec<JObject>>();
+            .collect::<jni::errors::Result<Vec<JObject>>>()?;
 
-        let array_list_class = env.find_class("java/util/ArrayList")?;
-        let array = env.new_object(array_list_class, "(I)V", &[(result.len() as i32).into()])?;
+        let array = jni_cache::new_object(
+            env,
+            "java/util/ArrayList",
+            "(I)V",
+            &[(result.len() as i32).into()],
+        )?;
         for object in result {
-            env.call_method(array, "add", "(Ljava/lang/Object;)Z", &[object.into()])?;
+            jni_cache::call_method(
+                env,
+                array,
+                "java/util/Collection",
+                "add",
+                "(Ljava/lang/Object;)Z",
+                &[object.into()],
+            )?;
         }
-        let iterator = env
-            .call_method(array, "iterator", "()Ljava/util/Iterator;", &[])?
-            .l()?;
-
-        Ok(iterator)
-    }();
-    match result {
-        Ok(val) => val.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+        let iterator = jni_cache::call_method(
+            env,
+            array,
+            "java/util/Collection",
+            "iterator",
+            "()Ljava/util/Iterator;",
+            &[],
+        )?
+        .l()?;
+
+        Ok(iterator.into_inner())
+    })
 }
 
 /*
@@ -353,33 +318,24 @@ pub extern "system" fn Java_org_apache_kafka_common_header_internals_RecordHeade
     env: JNIEnv,
     obj: JObject,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let record_headers = unsafe { Box::from_raw(ptr as *mut RecordHeaders) };
+    jni_guard(env, || -> JniResult<_> {
+        let record_headers = RecordHeaders::from_jobject(env, obj)?;
         let filtered = record_headers
+            .lock()
             .iter()
             .map(|header| header.clone_to_java(env))
             .collect::<jni::errors::Result<Vec<_>>>();
-        let _ptr = Box::into_raw(record_headers);
         let result = filtered?
             .into_iter()
             .map(JValue::l)
-            .collect::<jni::errors::Result<Vec<_>>>()?
-            .into_iter()
-            .map(Into::into)
-            .collect::<Vec<JObject>>();
+            .collect::<jni::errors::Result<Vec<JObject>>>()?;
 
-        let element_class = env.find_class("org/apache/kafka/common/header/Header")?;
-        let array = env.new_object_array(result.len() as i32, element_class, JObject::null())?;
+        let element_class = jni_cache::class(env, "org/apache/kafka/common/header/Header")?;
+        let array = env.new_object_array(result.len() as i32, &element_class, JObject::null())?;
         for object in result.into_iter().enumerate() {
             env.set_object_array_element(array, object.0 as i32, object.1)?;
         }
 
         Ok(array)
-    }();
-    match result {
-        Ok(val) => val,
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
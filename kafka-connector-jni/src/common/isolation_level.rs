@@ -0,0 +1,132 @@
+use kafka_connector_macros::JavaEnum;
+
+/// Controls how to read messages written transactionally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, JavaEnum)]
+#[java_class = "org/apache/kafka/common/IsolationLevel"]
+#[java_id_type = "i8"]
+pub enum IsolationLevel {
+    /// Read all messages, including messages of aborted and ongoing transactions.
+    #[java_variant = "READ_UNCOMMITTED"]
+    #[java_id = 0]
+    #[java_name = "read_uncommitted"]
+    ReadUncommitted,
+    /// Read only messages of committed transactions (and non-transactional messages).
+    #[java_variant = "READ_COMMITTED"]
+    #[java_id = 1]
+    #[java_name = "read_committed"]
+    ReadCommitted,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use jni::{
+        objects::{JObject, JValue},
+        JNIEnv,
+    };
+    use kafka_connector_macros::JavaEnum;
+
+    use super::IsolationLevel;
+    use crate::{
+        clone_from_java::CloneFromJava, clone_to_java::CloneToJava, common::errors::JavaException,
+        jvm,
+    };
+
+    const CLASS: &str = "org/apache/kafka/common/IsolationLevel";
+
+    /// Binds only one of the java constants.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, JavaEnum)]
+    #[java_class = "org/apache/kafka/common/IsolationLevel"]
+    enum ReadCommittedOnly {
+        #[java_variant = "READ_COMMITTED"]
+        ReadCommitted,
+    }
+
+    fn java_constant<'a>(env: JNIEnv<'a>, name: &str) -> jni::errors::Result<JObject<'a>> {
+        env.get_static_field(CLASS, name, format!("L{};", CLASS))?
+            .l()
+    }
+
+    #[test]
+    fn ids_map_to_variants() {
+        assert_eq!(
+            IsolationLevel::values(),
+            [
+                IsolationLevel::ReadUncommitted,
+                IsolationLevel::ReadCommitted
+            ]
+        );
+        for level in IsolationLevel::values() {
+            assert_eq!(IsolationLevel::from_id(level.id()), Some(*level));
+        }
+        assert_eq!(IsolationLevel::ReadUncommitted.id(), 0);
+        assert_eq!(IsolationLevel::ReadCommitted.id(), 1);
+        assert_eq!(IsolationLevel::from_id(2), None);
+        assert_eq!(IsolationLevel::from_id(-1), None);
+    }
+
+    #[test]
+    fn names_map_to_variants() {
+        for level in IsolationLevel::values() {
+            assert_eq!(IsolationLevel::from_str(level.name()), Ok(*level));
+            assert_eq!(level.to_string(), level.name());
+        }
+        assert_eq!(IsolationLevel::ReadCommitted.name(), "read_committed");
+        assert_eq!(
+            "READ_COMMITTED"
+                .parse::<IsolationLevel>()
+                .unwrap_err()
+                .to_string(),
+            "Unknown IsolationLevel name: READ_COMMITTED"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn variants_are_the_java_constants() {
+        jvm::run(|env| {
+            for (level, name) in [
+                (IsolationLevel::ReadUncommitted, "READ_UNCOMMITTED"),
+                (IsolationLevel::ReadCommitted, "READ_COMMITTED"),
+            ] {
+                let obj = level.clone_to_java(env)?.l()?;
+                assert!(env.is_same_object(obj, java_constant(env, name)?)?);
+                assert_eq!(env.call_method(obj, "id", "()B", &[])?.b()?, level.id());
+                assert_eq!(IsolationLevel::clone_from_java(env, obj.into())?, level);
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn unknown_constants_throw_without_fallback() {
+        jvm::run(|env| {
+            let obj = java_constant(env, "READ_COMMITTED")?;
+            assert_eq!(
+                ReadCommittedOnly::clone_from_java(env, obj.into())?,
+                ReadCommittedOnly::ReadCommitted
+            );
+
+            let obj = java_constant(env, "READ_UNCOMMITTED")?;
+            assert!(ReadCommittedOnly::clone_from_java(env, obj.into()).is_err());
+            let exception = JavaException::take(env)?.expect("unknown enum value");
+            assert_eq!(exception.class_name, "java.lang.Exception");
+            assert_eq!(exception.message.as_deref(), Some("Unknown enum value"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn objects_of_other_classes_throw() {
+        jvm::run(|env| {
+            let obj = env.new_string("READ_COMMITTED")?;
+            assert!(IsolationLevel::clone_from_java(env, JValue::Object(obj.into())).is_err());
+            let exception = JavaException::take(env)?.expect("wrong object class");
+            assert_eq!(exception.message.as_deref(), Some("Wrong object class"));
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,254 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::common::errors::{KafkaError, Result};
+
+type Callback<T> = Box<dyn FnOnce(&Result<T>) + Send>;
+
+/// A flexible future which supports call chaining and other asynchronous programming patterns.
+///
+/// It can be awaited from async code or blocked on with `get`/`get_timeout`. Clones share the
+/// same underlying state, the side which completes the future usually keeps one clone and hands
+/// out the others.
+pub struct KafkaFuture<T> {
+    inner: Arc<KafkaFutureInner<T>>,
+}
+
+struct KafkaFutureInner<T> {
+    state: Mutex<KafkaFutureState<T>>,
+    completed: Condvar,
+}
+
+struct KafkaFutureState<T> {
+    result: Option<Result<T>>,
+    wakers: Vec<Waker>,
+    callbacks: Vec<Callback<T>>,
+}
+
+impl<T> Clone for KafkaFuture<T> {
+    fn clone(&self) -> Self {
+        KafkaFuture {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Clone + Send + 'static> Default for KafkaFuture<T> {
+    fn default() -> Self {
+        KafkaFuture::new()
+    }
+}
+
+impl<T: Clone + Send + 'static> KafkaFuture<T> {
+    /// Create an incomplete future.
+    pub fn new() -> KafkaFuture<T> {
+        KafkaFuture {
+            inner: Arc::new(KafkaFutureInner {
+                state: Mutex::new(KafkaFutureState {
+                    result: None,
+                    wakers: Vec::new(),
+                    callbacks: Vec::new(),
+                }),
+                completed: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Returns a new future that is already completed with the given value.
+    pub fn completed_future(value: T) -> KafkaFuture<T> {
+        let future = KafkaFuture::new();
+        future.complete(value);
+        future
+    }
+
+    /// Returns a new future that is already completed exceptionally with the given error.
+    pub fn failed_future(error: KafkaError) -> KafkaFuture<T> {
+        let future = KafkaFuture::new();
+        future.complete_exceptionally(error);
+        future
+    }
+
+    /// Returns a new future that is completed when all the given futures have completed. If any
+    /// future fails, the returned future fails with the first error encountered.
+    pub fn all_of(futures: Vec<KafkaFuture<T>>) -> KafkaFuture<()> {
+        let all = KafkaFuture::new();
+        let remaining = Arc::new(Mutex::new(futures.len()));
+        if futures.is_empty() {
+            all.complete(());
+        }
+        for future in futures {
+            let all = all.clone();
+            let remaining = remaining.clone();
+            future.when_complete(move |result| {
+                if let Err(error) = result {
+                    all.complete_exceptionally(error.clone());
+                    return;
+                }
+                let mut remaining = remaining.lock().unwrap_or_else(|e| e.into_inner());
+                *remaining -= 1;
+                if *remaining == 0 {
+                    all.complete(());
+                }
+            });
+        }
+        all
+    }
+
+    fn lock(&self) -> MutexGuard<'_, KafkaFutureState<T>> {
+        self.inner.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_result(&self, result: Result<T>) -> bool {
+        let mut state = self.lock();
+        if state.result.is_some() {
+            return false;
+        }
+        state.result = Some(result.clone());
+        let wakers = std::mem::take(&mut state.wakers);
+        let callbacks = std::mem::take(&mut state.callbacks);
+        drop(state);
+        self.inner.completed.notify_all();
+        for waker in wakers {
+            waker.wake();
+        }
+        for callback in callbacks {
+            callback(&result);
+        }
+        true
+    }
+
+    /// If not already completed, sets the value returned by `get` and related methods to the
+    /// given value. Returns true if this invocation caused the future to transition to a
+    /// completed state.
+    pub fn complete(&self, value: T) -> bool {
+        self.set_result(Ok(value))
+    }
+
+    /// If not already completed, causes invocations of `get` and related methods to return the
+    /// given error. Returns true if this invocation caused the future to transition to a
+    /// completed state.
+    pub fn complete_exceptionally(&self, error: KafkaError) -> bool {
+        self.set_result(Err(error))
+    }
+
+    /// Waits if necessary for this future to complete, and then returns its result.
+    pub fn get(&self) -> Result<T> {
+        let mut state = self.lock();
+        loop {
+            if let Some(result) = &state.result {
+                return result.clone();
+            }
+            state = self
+                .inner
+                .completed
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Waits if necessary for at most the given time for this future to complete, and then
+    /// returns its result, if available.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<T> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock();
+        loop {
+            if let Some(result) = &state.result {
+                return result.clone();
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(KafkaError::Timeout(format!(
+                    "Timeout after waiting for {} ms.",
+                    timeout.as_millis()
+                )));
+            }
+            state = self
+                .inner
+                .completed
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Returns the result value (or fails with any encountered error) if completed, else returns
+    /// the given `value_if_absent`.
+    pub fn get_now(&self, value_if_absent: T) -> Result<T> {
+        match &self.lock().result {
+            Some(result) => result.clone(),
+            None => Ok(value_if_absent),
+        }
+    }
+
+    /// Returns true if completed in any fashion: normally, exceptionally, or via cancellation.
+    pub fn is_done(&self) -> bool {
+        self.lock().result.is_some()
+    }
+
+    /// Returns true if this future completed exceptionally, in any way.
+    pub fn is_completed_exceptionally(&self) -> bool {
+        matches!(self.lock().result, Some(Err(_)))
+    }
+
+    /// Invokes `action` with the result once this future completes. The action runs immediately
+    /// on the calling thread if the future is already done, otherwise on the completing thread.
+    pub fn when_complete<F>(&self, action: F)
+    where
+        F: FnOnce(&Result<T>) + Send + 'static,
+    {
+        let mut state = self.lock();
+        match &state.result {
+            Some(result) => {
+                let result = result.clone();
+                drop(state);
+                action(&result);
+            }
+            None => state.callbacks.push(Box::new(action)),
+        }
+    }
+
+    /// Returns a new future that, when this future completes normally, is completed with this
+    /// future's result as the argument to the supplied function. Errors are propagated unchanged.
+    pub fn then_apply<R, F>(&self, function: F) -> KafkaFuture<R>
+    where
+        R: Clone + Send + 'static,
+        F: FnOnce(T) -> Result<R> + Send + 'static,
+    {
+        let future = KafkaFuture::new();
+        let result = future.clone();
+        self.when_complete(move |value| match value {
+            Ok(value) => match function(value.clone()) {
+                Ok(value) => {
+                    result.complete(value);
+                }
+                Err(error) => {
+                    result.complete_exceptionally(error);
+                }
+            },
+            Err(error) => {
+                result.complete_exceptionally(error.clone());
+            }
+        });
+        future
+    }
+}
+
+impl<T: Clone + Send + 'static> Future for KafkaFuture<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.lock();
+        if let Some(result) = &state.result {
+            return Poll::Ready(result.clone());
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
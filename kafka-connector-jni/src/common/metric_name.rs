@@ -1,19 +1,17 @@
-use crate::java_struct_standard_impl;
 use indexmap::IndexMap;
-use jni::{
-    objects::{JObject, JValue},
-    sys::jstring,
-    JNIEnv,
-};
-use kafka_connector_macros::rust_property_getter;
+use kafka_connector_macros::JavaStruct;
 
-use crate::clone_from_java::CloneFromJava;
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JavaStruct)]
+#[java_class = "org/apache/kafka/common/MetricName"]
+#[java_constructor(name, group, description, tags)]
 pub struct MetricName {
+    #[java_getter]
     pub name: String,
+    #[java_getter]
     pub group: String,
+    #[java_getter]
     pub description: String,
+    #[java_getter]
     pub tags: IndexMap<String, String>,
 }
 impl MetricName {
@@ -31,89 +29,3 @@ impl MetricName {
         }
     }
 }
-
-java_struct_standard_impl!(MetricName, "org/apache/kafka/common/MetricName");
-
-/*
- * Class:     org_apache_kafka_common_MetricName
- * Method:    rustConstructor
- * Signature: (Ljava/lang/String;Ljava/lang/String;Ljava/lang/String;Ljava/util/Map;)V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_MetricName_rustConstructor(
-    env: JNIEnv,
-    obj: JObject,
-    name: jstring,
-    group: jstring,
-    description: jstring,
-    tags: JObject,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let name: String = env.get_string(name.into())?.into();
-        let group: String = env.get_string(group.into())?.into();
-        let description: String = env.get_string(description.into())?.into();
-        let tags = CloneFromJava::clone_from_java(env, JValue::Object(tags))?;
-
-        let record_header = Box::new(MetricName::new(name, group, description, tags));
-        let ptr = Box::into_raw(record_header);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-/*
- * Class:     org_apache_kafka_common_MetricName
- * Method:    rustDestructor
- * Signature: ()V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_MetricName_rustDestructor(
-    env: JNIEnv,
-    obj: JObject,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut MetricName) };
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-rust_property_getter!(
- * Struct:    MetricName
- * Class:     org_apache_kafka_common_MetricName
- * Method:    name
- * Signature: ()Ljava/lang/String;
-);
-
-rust_property_getter!(
- * Struct:    MetricName
- * Class:     org_apache_kafka_common_MetricName
- * Method:    group
- * Signature: ()Ljava/lang/String;
-);
-
-rust_property_getter!(
- * Struct:    MetricName
- * Class:     org_apache_kafka_common_MetricName
- * Method:    tags
- * Signature: ()Ljava/util/Map;
-);
-
-rust_property_getter!(
- * Struct:    MetricName
- * Class:     org_apache_kafka_common_MetricName
- * Method:    description
- * Signature: ()Ljava/lang/String;
-);
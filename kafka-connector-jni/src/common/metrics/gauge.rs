@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use jni::{
+    objects::{JObject, JValue},
+    sys::{jlong, jobject},
+    JNIEnv,
+};
+
+use crate::{
+    clone_from_java::CloneFromJava,
+    clone_to_java::{kind, CloneToJava},
+    common::errors::JniResult,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    jni_cache,
+    jni_guard::jni_guard,
+};
+
+use super::metric_config::MetricConfig;
+
+type ValueFn<T> = dyn Fn(&MetricConfig, u64) -> T + Send + Sync;
+
+/// Gauge computed in rust, e.g. the state of a client as a string or the size of a pool as an
+/// int. Java reads it through a `RustGauge`, `KafkaMetric.metricValue()` returns the value
+/// cloned to java and boxed.
+pub struct NativeGauge<T> {
+    value_fn: Arc<ValueFn<T>>,
+}
+
+impl<T> NativeGauge<T> {
+    pub fn new<F>(value_fn: F) -> NativeGauge<T>
+    where
+        F: Fn(&MetricConfig, u64) -> T + Send + Sync + 'static,
+    {
+        NativeGauge {
+            value_fn: Arc::new(value_fn),
+        }
+    }
+
+    pub fn value(&self, config: &MetricConfig, now: u64) -> T {
+        (self.value_fn)(config, now)
+    }
+}
+
+impl<T> Clone for NativeGauge<T> {
+    fn clone(&self) -> Self {
+        NativeGauge {
+            value_fn: self.value_fn.clone(),
+        }
+    }
+}
+
+impl<T> CloneToJava for NativeGauge<T>
+where
+    T: CloneToJava + 'static,
+{
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let value_fn = self.value_fn.clone();
+        let gauge = JavaGauge {
+            value_fn: Arc::new(move |env, config, now| {
+                value_fn(config, now).clone_to_java_object(env)
+            }),
+        };
+        let class = jni_cache::class(env, "org/apache/kafka/common/metrics/RustGauge")?;
+        let obj = env.alloc_object(&class)?;
+        JavaStoredObject::store(env, obj, gauge)?;
+        Ok(obj.into())
+    }
+}
+
+type JavaValueFn = dyn for<'a> Fn(JNIEnv<'a>, &MetricConfig, u64) -> jni::errors::Result<JObject<'a>>
+    + Send
+    + Sync;
+
+/// `NativeGauge` backing a `RustGauge`, its values are cloned to java objects.
+#[derive(Clone)]
+struct JavaGauge {
+    value_fn: Arc<JavaValueFn>,
+}
+from_jobject!(JavaGauge, "org/apache/kafka/common/metrics/RustGauge");
+
+/*
+ * Class:     org_apache_kafka_common_metrics_RustGauge
+ * Method:    value
+ * Signature: (Lorg/apache/kafka/common/metrics/MetricConfig;J)Ljava/lang/Object;
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_common_metrics_RustGauge_value(
+    env: JNIEnv,
+    obj: JObject,
+    config: JObject,
+    now: jlong,
+) -> jobject {
+    jni_guard(env, || -> JniResult<_> {
+        let config = MetricConfig::clone_from_java(env, config.into())?;
+        // Evaluated outside of the lock, the closure may take a while.
+        let gauge = JavaGauge::from_jobject(env, obj)?.lock().clone();
+        Ok((gauge.value_fn)(env, &config, now as u64)?.into_inner())
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_common_metrics_RustGauge
+ * Method:    rustDestructor
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_common_metrics_RustGauge_rustDestructor(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<JavaGauge>::destroy(env, obj)?;
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    };
+
+    use indexmap::IndexMap;
+    use jni::{objects::JObject, JNIEnv};
+
+    use super::NativeGauge;
+    use crate::{
+        clone_to_java::CloneToJava,
+        common::{metric_name::MetricName, metrics::metric_config::MetricConfig},
+        jvm,
+    };
+
+    /// `KafkaMetric` reading `gauge`, as registered by `Metrics.addMetric`.
+    fn kafka_metric<'a, T>(
+        env: JNIEnv<'a>,
+        gauge: &NativeGauge<T>,
+    ) -> jni::errors::Result<JObject<'a>>
+    where
+        T: CloneToJava + 'static,
+    {
+        let lock = env.new_object("java/lang/Object", "()V", &[])?;
+        let name = MetricName::new(
+            "state".to_string(),
+            "test-metrics".to_string(),
+            String::new(),
+            IndexMap::new(),
+        );
+        let time = env.get_static_field(
+            "org/apache/kafka/common/utils/Time",
+            "SYSTEM",
+            "Lorg/apache/kafka/common/utils/Time;",
+        )?;
+        env.new_object(
+            "org/apache/kafka/common/metrics/KafkaMetric",
+            "(Ljava/lang/Object;Lorg/apache/kafka/common/MetricName;Lorg/apache/kafka/common/metrics/MetricValueProvider;Lorg/apache/kafka/common/metrics/MetricConfig;Lorg/apache/kafka/common/utils/Time;)V",
+            &[
+                lock.into(),
+                name.clone_to_java(env)?,
+                gauge.clone_to_java(env)?,
+                MetricConfig::default().clone_to_java(env)?,
+                time,
+            ],
+        )
+    }
+
+    /// Class name and `String.valueOf` of `KafkaMetric.metricValue()`.
+    fn metric_value(env: JNIEnv, metric: JObject) -> jni::errors::Result<(String, String)> {
+        let value = env
+            .call_method(metric, "metricValue", "()Ljava/lang/Object;", &[])?
+            .l()?;
+        let class = env.call_method(
+            env.get_object_class(value)?,
+            "getName",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        Ok((jvm::string(env, class.l()?)?, jvm::to_string(env, value)?))
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn kafka_metric_reads_the_current_value() {
+        jvm::run(|env| {
+            let size = Arc::new(AtomicI64::new(3));
+            let read = size.clone();
+            let metric = kafka_metric(
+                env,
+                &NativeGauge::new(move |_, _| read.load(Ordering::Relaxed)),
+            )?;
+            assert_eq!(
+                metric_value(env, metric)?,
+                ("java.lang.Long".to_string(), "3".to_string())
+            );
+            size.store(-5, Ordering::Relaxed);
+            assert_eq!(metric_value(env, metric)?.1, "-5");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn kafka_metric_reads_objects() {
+        jvm::run(|env| {
+            let metric = kafka_metric(env, &NativeGauge::new(|_, _| "RUNNING".to_string()))?;
+            assert_eq!(
+                metric_value(env, metric)?,
+                ("java.lang.String".to_string(), "RUNNING".to_string())
+            );
+            let metric = kafka_metric(env, &NativeGauge::new(|_, _| None::<i32>))?;
+            let value = env
+                .call_method(metric, "metricValue", "()Ljava/lang/Object;", &[])?
+                .l()?;
+            assert!(value.is_null());
+            Ok(())
+        });
+    }
+}
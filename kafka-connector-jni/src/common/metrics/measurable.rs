@@ -1,30 +1,80 @@
+use std::sync::{
+    atomic::{AtomicI64, AtomicU64, Ordering},
+    Arc,
+};
+
 use jni::{
     objects::{GlobalRef, JObject, JValue},
+    sys::jlong,
     JNIEnv,
 };
 
-use crate::{clone_from_java::CloneFromJava, clone_to_java::CloneToJava};
+use crate::{
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::errors::JniResult,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    java_vm::call_java,
+    jni_cache,
+    jni_guard::jni_guard,
+};
 
 use super::metric_config::MetricConfig;
 
+const RUST_MEASURABLE: &str = "org/apache/kafka/common/metrics/RustMeasurable";
+
 #[derive(Clone)]
 pub enum Measurable {
     Java(JavaMeasurable),
+    Native(NativeMeasurable),
+}
+
+impl Measurable {
+    /// Measures the value, native measurables don't call java.
+    pub fn measure(&self, config: &MetricConfig, now: u64) -> JniResult<f64> {
+        match self {
+            Measurable::Java(m) => m.measure(config, now),
+            Measurable::Native(m) => Ok(m.measure(config, now)),
+        }
+    }
+}
+
+impl From<JavaMeasurable> for Measurable {
+    fn from(measurable: JavaMeasurable) -> Self {
+        Measurable::Java(measurable)
+    }
+}
+
+impl From<NativeMeasurable> for Measurable {
+    fn from(measurable: NativeMeasurable) -> Self {
+        Measurable::Native(measurable)
+    }
 }
 
 impl CloneToJava for Measurable {
+    type Kind = crate::clone_to_java::kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<jni::objects::JValue<'a>> {
         match self {
             Measurable::Java(m) => m.clone_to_java(env),
+            Measurable::Native(m) => m.clone_to_java(env),
         }
     }
 }
 
 impl CloneFromJava for Measurable {
+    /// A `RustMeasurable` is unwrapped to its native measurable, so measuring it doesn't go
+    /// through java.
     fn clone_from_java(env: JNIEnv, obj: jni::objects::JValue) -> jni::errors::Result<Self>
     where
         Self: Sized,
     {
+        let measurable = obj.l()?;
+        let class = jni_cache::class(env, RUST_MEASURABLE)?;
+        if !measurable.is_null() && env.is_instance_of(measurable, &class)? {
+            return NativeMeasurable::clone_from_java(env, obj).map(Measurable::Native);
+        }
         JavaMeasurable::clone_from_java(env, obj).map(Measurable::Java)
     }
 }
@@ -38,20 +88,25 @@ impl JavaMeasurable {
             measure_fn: env.new_global_ref(measure_fn)?,
         })
     }
-    pub fn measure(&self, env: JNIEnv, config: MetricConfig, now: u64) -> jni::errors::Result<f64> {
-        let config = config.clone_to_java(env)?;
-        let ret = env
-            .call_method(
+    /// Calls the java `Measurable`, from any thread.
+    pub fn measure(&self, config: &MetricConfig, now: u64) -> JniResult<f64> {
+        call_java(|env| {
+            let config = config.clone_to_java(env)?;
+            jni_cache::call_method(
+                env,
                 self.measure_fn.as_obj(),
+                "org/apache/kafka/common/metrics/Measurable",
                 "measure",
                 "(Lorg/apache/kafka/common/metrics/MetricConfig;J)D",
                 &[config, JValue::Long(now as i64)],
             )?
-            .d()?;
-        Ok(ret)
+            .d()
+        })
     }
 }
 impl CloneToJava for JavaMeasurable {
+    type Kind = crate::clone_to_java::kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         self.measure_fn.clone_to_java(env)
     }
@@ -62,7 +117,83 @@ impl CloneFromJava for JavaMeasurable {
         Self: Sized,
     {
         Ok(JavaMeasurable {
-            measure_fn: env.new_global_ref(obj.l()?)?,
+            measure_fn: GlobalRef::clone_from_java(env, obj)?,
         })
     }
 }
+
+type MeasureFn = dyn Fn(&MetricConfig, u64) -> f64 + Send + Sync;
+
+/// Measurable computed in rust, so rust code can register metrics without implementing a java
+/// class. Java reads it through a `RustMeasurable`.
+#[derive(Clone)]
+pub struct NativeMeasurable {
+    measure_fn: Arc<MeasureFn>,
+}
+impl NativeMeasurable {
+    pub fn new<F>(measure_fn: F) -> NativeMeasurable
+    where
+        F: Fn(&MetricConfig, u64) -> f64 + Send + Sync + 'static,
+    {
+        NativeMeasurable {
+            measure_fn: Arc::new(measure_fn),
+        }
+    }
+
+    /// Current value of a counter, e.g. of the bytes sent, incremented by its owner.
+    pub fn counter(counter: Arc<AtomicU64>) -> NativeMeasurable {
+        NativeMeasurable::new(move |_, _| counter.load(Ordering::Relaxed) as f64)
+    }
+
+    /// Current value of a gauge, e.g. of the requests in flight, set by its owner.
+    pub fn gauge(gauge: Arc<AtomicI64>) -> NativeMeasurable {
+        NativeMeasurable::new(move |_, _| gauge.load(Ordering::Relaxed) as f64)
+    }
+
+    pub fn measure(&self, config: &MetricConfig, now: u64) -> f64 {
+        (self.measure_fn)(config, now)
+    }
+}
+java_struct_standard_impl!(
+    NativeMeasurable,
+    "org/apache/kafka/common/metrics/RustMeasurable"
+);
+
+/*
+ * Class:     org_apache_kafka_common_metrics_RustMeasurable
+ * Method:    measure
+ * Signature: (Lorg/apache/kafka/common/metrics/MetricConfig;J)D
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_common_metrics_RustMeasurable_measure(
+    env: JNIEnv,
+    obj: JObject,
+    config: JObject,
+    now: jlong,
+) -> f64 {
+    jni_guard(env, || -> JniResult<_> {
+        let config = MetricConfig::clone_from_java(env, config.into())?;
+        // Measured outside of the lock, the closure may take a while.
+        let measurable = NativeMeasurable::from_jobject(env, obj)?.lock().clone();
+        Ok(measurable.measure(&config, now as u64))
+    })
+}
+
+/*
+ * Class:     org_apache_kafka_common_metrics_RustMeasurable
+ * Method:    rustDestructor
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_common_metrics_RustMeasurable_rustDestructor(
+    env: JNIEnv,
+    obj: JObject,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<NativeMeasurable>::destroy(env, obj)?;
+
+        Ok(())
+    })
+}
@@ -1,6 +1,7 @@
 pub mod internals;
 pub mod stats;
 
+pub mod gauge;
 pub mod measurable;
 pub mod metric_config;
 pub mod named_measurable;
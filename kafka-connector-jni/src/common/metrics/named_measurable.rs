@@ -1,27 +1,25 @@
 use crate::{
     clone_from_java::CloneFromJava,
-    common::{metric_name::MetricName, metrics::measurable::JavaMeasurable},
-    java_struct_standard_impl,
+    common::{errors::JniResult, metric_name::MetricName},
+    java_stored_object::JavaStoredObject,
+    jni_guard::jni_guard,
 };
-use jni::{
-    objects::{JObject, JValue},
-    JNIEnv,
-};
-use kafka_connector_macros::rust_property_getter;
+use jni::{objects::JObject, JNIEnv};
+use kafka_connector_macros::JavaStruct;
 
 use super::measurable::Measurable;
 
-#[derive(Clone)]
+#[derive(Clone, JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/NamedMeasurable"]
 pub struct NamedMeasurable {
+    #[java_getter]
+    #[java_type = "Lorg/apache/kafka/common/MetricName;"]
     pub name: MetricName,
+    #[java_getter]
+    #[java_type = "Lorg/apache/kafka/common/metrics/Measurable;"]
     pub stat: Measurable,
 }
 
-java_struct_standard_impl!(
-    NamedMeasurable,
-    "org/apache/kafka/common/metrics/NamedMeasurable"
-);
-
 /*
  * Class:     org_apache_kafka_common_metrics_NamedMeasurable
  * Method:    rustConstructor
@@ -35,57 +33,18 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_NamedMeasurable_rust
     metric_name: JObject,
     measurable: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let metric_name = MetricName::clone_from_java(env, metric_name.into())?;
-        let measurable = JavaMeasurable::new(env, measurable)?;
-        let metrics_config = Box::new(NamedMeasurable {
-            name: metric_name,
-            stat: Measurable::Java(measurable),
-        });
-        let ptr = Box::into_raw(metrics_config);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-/*
- * Class:     org_apache_kafka_common_metrics_NamedMeasurable
- * Method:    rustDestructor
- * Signature: ()V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_metrics_NamedMeasurable_rustDestructor(
-    env: JNIEnv,
-    obj: JObject,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut NamedMeasurable) };
+        let measurable = Measurable::clone_from_java(env, measurable.into())?;
+        JavaStoredObject::store(
+            env,
+            obj,
+            NamedMeasurable {
+                name: metric_name,
+                stat: measurable,
+            },
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
-
-rust_property_getter!(
- * Struct:    NamedMeasurable
- * Class:     org_apache_kafka_common_metrics_NamedMeasurable
- * Method:    name
- * Signature: ()Lorg/apache/kafka/common/MetricName;
-);
-
-rust_property_getter!(
- * Struct:    NamedMeasurable
- * Class:     org_apache_kafka_common_metrics_NamedMeasurable
- * Method:    stat
- * Signature: ()Lorg/apache/kafka/common/metrics/Measurable;
-);
@@ -1,14 +1,14 @@
-use crate::java_struct_standard_impl;
-use jni::{
-    objects::{JObject, JValue},
-    sys::{jboolean, jdouble},
-    JNIEnv,
-};
-use kafka_connector_macros::rust_property_getter;
+use crate::{common::errors::JniResult, java_stored_object::FromJObject, jni_guard::jni_guard};
+use jni::sys::jdouble;
+use kafka_connector_macros::JavaStruct;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/Quota"]
+#[java_constructor(bound, is_upper_bound)]
 pub struct Quota {
+    #[java_getter = "isUpperBound"]
     pub is_upper_bound: bool,
+    #[java_getter]
     pub bound: f64,
 }
 impl Quota {
@@ -26,71 +26,6 @@ impl Quota {
     }
 }
 
-java_struct_standard_impl!(Quota, "org/apache/kafka/common/metrics/Quota");
-
-/*
- * Class:     org_apache_kafka_common_metrics_Quota
- * Method:    rustConstructor
- * Signature: (DZ)V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_metrics_Quota_rustConstructor(
-    env: JNIEnv,
-    obj: JObject,
-    bound: jdouble,
-    is_upper: jboolean,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let quota = Box::new(Quota::new(is_upper != 0, bound));
-        let ptr = Box::into_raw(quota);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-/*
- * Class:     org_apache_kafka_common_metrics_Quota
- * Method:    rustDestructor
- * Signature: ()V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_metrics_Quota_rustDestructor(
-    env: JNIEnv,
-    obj: JObject,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Quota) };
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-rust_property_getter!(
- * Struct:    Quota
- * Class:     org_apache_kafka_common_metrics_Quota
- * Method:    isUpperBound
- * Signature: ()Z
-);
-
-rust_property_getter!(
- * Struct:    Quota
- * Class:     org_apache_kafka_common_metrics_Quota
- * Method:    bound
- * Signature: ()D
-);
-
 /*
  * Class:     org_apache_kafka_common_metrics_Quota
  * Method:    acceptable
@@ -103,17 +38,10 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_Quota_acceptable(
     obj: jni::objects::JObject,
     value: jdouble,
 ) -> jni::sys::jboolean {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let rust_struct = unsafe { Box::from_raw(ptr as *mut Quota) };
-        let ret = rust_struct.is_acceptable(value);
-        let _ptr = Box::into_raw(rust_struct);
+    jni_guard(env, || -> JniResult<_> {
+        let quota = Quota::from_jobject(env, obj)?;
+        let ret = quota.lock().is_acceptable(value);
 
         Ok(ret as u8)
-    };
-    match result() {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!(),
-    }
+    })
 }
@@ -1,7 +1,7 @@
 use jni::{objects::JObject, sys::jobject, JNIEnv};
 use kafka_connector_macros::JavaEnum;
 
-use crate::clone_from_java::CloneFromJava;
+use crate::{clone_from_java::CloneFromJava, common::errors::JniResult, jni_guard::jni_guard};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, JavaEnum)]
 #[java_class = "org/apache/kafka/common/metrics/SensorRecordingLevel"]
@@ -38,14 +38,9 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_SensorRecordingLevel
     obj: JObject,
     val: jobject,
 ) -> bool {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let this = SensorRecordingLevel::clone_from_java(env, obj.into())?;
         let val = SensorRecordingLevel::clone_from_java(env, val.into())?;
         Ok(this.should_record(val))
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => true,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
@@ -1,12 +1,16 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jlong},
     JNIEnv,
 };
 
 use crate::{
-    clone_from_java::CloneFromJava, common::metrics::metric_config::MetricConfig,
-    java_stored_object::FromJObject, java_struct_standard_impl,
+    clone_from_java::CloneFromJava,
+    common::errors::JniResult,
+    common::metrics::metric_config::MetricConfig,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 
 #[derive(Debug, Clone)]
@@ -50,18 +54,15 @@ fn java_cumulative_stat_record(
     value: jdouble,
     time_ms: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let config = MetricConfig::clone_from_java(env, config.into()).unwrap_or_default();
+    jni_guard(env, || -> JniResult<_> {
+        let config =
+            Option::<MetricConfig>::clone_from_java(env, config.into())?.unwrap_or_default();
         let mut stat = CumulativeStat::from_jobject(env, obj)?;
         stat.modify(|stat| {
             stat.record(&config, value, time_ms as u128);
         });
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_CumulativeSum
@@ -109,17 +110,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_CumulativeSum_
     config: JObject,
     now: jlong,
 ) -> f64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut stat = CumulativeStat::from_jobject(env, obj)?;
         let ret = stat.modify(|stat| stat.measure(&config, now as u128));
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -134,17 +130,15 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_CumulativeSum_
     obj: JObject,
     value: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let cumulative_stat = Box::new(CumulativeStat::new(value, StatType::CumulativeSum));
-        let ptr = Box::into_raw(cumulative_stat);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(
+            env,
+            obj,
+            CumulativeStat::new(value, StatType::CumulativeSum),
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -159,17 +153,15 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_CumulativeCoun
     obj: JObject,
     value: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let cumulative_stat = Box::new(CumulativeStat::new(value, StatType::CumulativeCount));
-        let ptr = Box::into_raw(cumulative_stat);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(
+            env,
+            obj,
+            CumulativeStat::new(value, StatType::CumulativeCount),
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -183,14 +175,9 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_CumulativeSum_
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut CumulativeStat) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<CumulativeStat>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
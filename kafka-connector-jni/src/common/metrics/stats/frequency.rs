@@ -1,12 +1,17 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jobject},
     JNIEnv,
 };
 
 use crate::{
-    clone_from_java::CloneFromJava, clone_to_java::CloneToJava, common::metric_name::MetricName,
-    java_stored_object::FromJObject, java_struct_standard_impl,
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::errors::JniResult,
+    common::metric_name::MetricName,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 
 #[derive(Debug, Clone)]
@@ -30,21 +35,19 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Frequency_rust
     metric_name: jobject,
     center_value: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let metric_name = MetricName::clone_from_java(env, metric_name.into())?;
-        let histogram = Box::new(Frequency {
-            metric_name,
-            center_value,
-        });
-        let ptr = Box::into_raw(histogram);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(
+            env,
+            obj,
+            Frequency {
+                metric_name,
+                center_value,
+            },
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -58,16 +61,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Frequency_rust
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Frequency) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<Frequency>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -81,15 +79,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Frequency_name
     env: JNIEnv,
     obj: JObject,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        let frequency = Frequency::from_jobject(env, obj)?;
-        MetricName::clone_to_java(&frequency.metric_name, env)?.l()
-    }();
-    match result {
-        Ok(val) => val.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+    jni_guard(env, || -> JniResult<_> {
+        let stored = Frequency::from_jobject(env, obj)?;
+        let frequency = stored.lock();
+        Ok(MetricName::clone_to_java(&frequency.metric_name, env)?
+            .l()?
+            .into_inner())
+    })
 }
 
 /*
@@ -103,13 +99,9 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Frequency_cent
     env: JNIEnv,
     obj: JObject,
 ) -> jdouble {
-    let result = || -> jni::errors::Result<_> {
-        let frequency = Frequency::from_jobject(env, obj)?;
+    jni_guard(env, || -> JniResult<_> {
+        let stored = Frequency::from_jobject(env, obj)?;
+        let frequency = stored.lock();
         Ok(frequency.center_value)
-    }();
-    match result {
-        Ok(val) => val,
-        Err(jni::errors::Error::JavaException) => 0.0,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
@@ -1,10 +1,15 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jarray, jdouble, jint},
     JNIEnv,
 };
 
-use crate::{java_stored_object::FromJObject, java_struct_standard_impl};
+use crate::{
+    common::errors::JniResult,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
+};
 
 #[derive(Debug, Clone)]
 pub struct Histogram {
@@ -18,7 +23,7 @@ impl Histogram {
         let bins = match bin_scheme {
             BinScheme::Constant { bins, .. } | BinScheme::Linear { bins, .. } => bins,
         };
-        let hist = (0..bins).into_iter().map(|_| 0_f32).collect();
+        let hist = (0..bins).map(|_| 0_f32).collect();
         Self {
             bin_scheme,
             count: 0.0,
@@ -53,10 +58,10 @@ impl Histogram {
         for (i, val) in self.hist.iter().enumerate() {
             sum += val;
             if sum > min_count {
-                return self.bin_scheme.from_bin(i);
+                return self.bin_scheme.value_of_bin(i);
             }
         }
-        self.bin_scheme.from_bin(self.hist.len() - 1)
+        self.bin_scheme.value_of_bin(self.hist.len() - 1)
     }
 }
 
@@ -113,7 +118,7 @@ impl BinScheme {
         }
     }
 
-    fn from_bin(&self, b: usize) -> f64 {
+    fn value_of_bin(&self, b: usize) -> f64 {
         match *self {
             BinScheme::Constant {
                 max_bin_number,
@@ -153,19 +158,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_rust
     obj: JObject,
     bin_scheme: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let bin_scheme = BinScheme::from_jobject(env, bin_scheme)?;
-        let scheme = bin_scheme.obj.clone().unwrap().as_ref().clone();
-        let histogram = Box::new(Histogram::new(scheme));
-        let ptr = Box::into_raw(histogram);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        let scheme = bin_scheme.lock().clone();
+        JavaStoredObject::store(env, obj, Histogram::new(scheme))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -179,16 +178,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_rust
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Histogram) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<Histogram>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -203,15 +197,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_reco
     obj: JObject,
     val: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut histogram = Histogram::from_jobject(env, obj)?;
         histogram.modify(|histogram| histogram.record(val));
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -226,15 +216,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_valu
     obj: JObject,
     val: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut histogram = Histogram::from_jobject(env, obj)?;
         histogram.modify(|histogram| histogram.value(val));
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 // /*
@@ -248,18 +234,14 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_coun
     env: JNIEnv,
     obj: JObject,
 ) -> jarray {
-    let result = || -> jni::errors::Result<_> {
-        let histogram = Histogram::from_jobject(env, obj)?;
+    jni_guard(env, || -> JniResult<_> {
+        let stored = Histogram::from_jobject(env, obj)?;
+        let histogram = stored.lock();
         let counts = histogram.counts();
         let arr = env.new_float_array(counts.len() as i32)?;
         env.set_float_array_region(arr, 0, counts)?;
         Ok(arr)
-    }();
-    match result {
-        Ok(val) => val,
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Histogram
@@ -272,15 +254,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_clea
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut histogram = Histogram::from_jobject(env, obj)?;
         histogram.modify(|histogram| histogram.clear());
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -297,17 +275,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
     min: jdouble,
     max: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let bin_scheme = Box::new(BinScheme::new_constant(bins as usize, min, max));
-        let ptr = Box::into_raw(bin_scheme);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, BinScheme::new_constant(bins as usize, min, max))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -321,16 +293,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut BinScheme) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<BinScheme>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -348,19 +315,14 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
 }
 
 fn java_bin_scheme_bins(env: JNIEnv, obj: JObject) -> i32 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut bin_scheme = BinScheme::from_jobject(env, obj)?;
         let bins = bin_scheme.modify(|bin_scheme| match bin_scheme {
             BinScheme::Constant { bins, .. } => *bins,
             BinScheme::Linear { bins, .. } => *bins,
         });
-        Ok(bins)
-    }();
-    match result {
-        Ok(val) => val as i32,
-        Err(jni::errors::Error::JavaException) => 0,
-        _ => panic!("{:?}", result),
-    }
+        Ok(bins as i32)
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Histogram_ConstantBinScheme
@@ -378,16 +340,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
 }
 
 fn java_bin_scheme_from_bin(env: JNIEnv, obj: JObject, b: jint) -> f64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let bin_scheme = BinScheme::from_jobject(env, obj)?;
-        let ret = bin_scheme.from_bin(b as usize);
-        Ok(ret)
-    }();
-    match result {
-        Ok(val) => val as jdouble,
-        Err(jni::errors::Error::JavaException) => 0.0,
-        _ => panic!("{:?}", result),
-    }
+        let ret = bin_scheme.lock().value_of_bin(b as usize);
+        Ok(ret as jdouble)
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Histogram_ConstantBinScheme
@@ -405,16 +362,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
 }
 
 fn java_bin_scheme_to_bin(env: JNIEnv, obj: JObject, x: jdouble) -> i32 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let bin_scheme = BinScheme::from_jobject(env, obj)?;
-        let ret = bin_scheme.to_bin(x);
-        Ok(ret)
-    }();
-    match result {
-        Ok(val) => val as jint,
-        Err(jni::errors::Error::JavaException) => 0,
-        _ => panic!("{:?}", result),
-    }
+        let ret = bin_scheme.lock().to_bin(x);
+        Ok(ret as jint)
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Histogram_LinearBinScheme
@@ -429,17 +381,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
     bins: jint,
     max: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let bin_scheme = Box::new(BinScheme::new_linear(bins as usize, max));
-        let ptr = Box::into_raw(bin_scheme);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, BinScheme::new_linear(bins as usize, max))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -453,16 +399,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Histogram_0002
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut BinScheme) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<BinScheme>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -1,12 +1,17 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jobject},
     JNIEnv,
 };
 
 use crate::{
-    clone_from_java::CloneFromJava, clone_to_java::CloneToJava, common::metric_name::MetricName,
-    java_stored_object::FromJObject, java_struct_standard_impl,
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::errors::JniResult,
+    common::metric_name::MetricName,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 
 #[derive(Debug, Clone)]
@@ -33,21 +38,19 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Percentile_rus
     metric_name: jobject,
     percentile: jdouble,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let metric_name = MetricName::clone_from_java(env, metric_name.into())?;
-        let percentile = Box::new(Percentile {
-            metric_name,
-            percentile,
-        });
-        let ptr = Box::into_raw(percentile);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(
+            env,
+            obj,
+            Percentile {
+                metric_name,
+                percentile,
+            },
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -61,16 +64,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Percentile_rus
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Percentile) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<Percentile>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -84,15 +82,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Percentile_nam
     env: JNIEnv,
     obj: JObject,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
-        let Percentile = Percentile::from_jobject(env, obj)?;
-        MetricName::clone_to_java(&Percentile.metric_name, env)?.l()
-    }();
-    match result {
-        Ok(val) => val.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+    jni_guard(env, || -> JniResult<_> {
+        let stored = Percentile::from_jobject(env, obj)?;
+        let Percentile = stored.lock();
+        Ok(MetricName::clone_to_java(&Percentile.metric_name, env)?
+            .l()?
+            .into_inner())
+    })
 }
 
 /*
@@ -106,13 +102,9 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Percentile_per
     env: JNIEnv,
     obj: JObject,
 ) -> jdouble {
-    let result = || -> jni::errors::Result<_> {
-        let Percentile = Percentile::from_jobject(env, obj)?;
+    jni_guard(env, || -> JniResult<_> {
+        let stored = Percentile::from_jobject(env, obj)?;
+        let Percentile = stored.lock();
         Ok(Percentile.percentile)
-    }();
-    match result {
-        Ok(val) => val,
-        Err(jni::errors::Error::JavaException) => 0.0,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
@@ -1,5 +1,5 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jlong},
     JNIEnv,
 };
@@ -7,9 +7,11 @@ use kafka_connector_macros::rust_property_getter;
 
 use crate::{
     clone_from_java::CloneFromJava,
+    common::errors::JniResult,
     common::metrics::{internals::metric_utils::TimeUnit, metric_config::MetricConfig},
-    java_stored_object::FromJObject,
+    java_stored_object::{FromJObject, JavaStoredObject},
     java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 
 use super::sampled_stat::SampledStat;
@@ -44,18 +46,18 @@ impl Rate {
     }
     pub fn window_size(&mut self, config: &MetricConfig, now: u128) -> u128 {
         self.stat.purge_obsolete_samples(config, now);
-        let mut elapsed = u128::saturating_sub(now, self.stat.oldest(now).last_window_ms);
+        let mut elapsed = u128::saturating_sub(now, self.stat.oldest(now).last_window_ms as u128);
+        let time_window_ms = config.time_window_ms as u128;
         match self.rate_type {
             RateType::Standard => {
-                let num_full_windows = (elapsed / config.time_window_ms) as u32;
+                let num_full_windows = (elapsed / time_window_ms) as i32;
                 let min_full_windows = config.samples - 1;
                 if num_full_windows < min_full_windows {
-                    elapsed +=
-                        ((min_full_windows - num_full_windows) as u128) * config.time_window_ms;
+                    elapsed += ((min_full_windows - num_full_windows) as u128) * time_window_ms;
                 }
                 elapsed
             }
-            RateType::Simple => u128::max(elapsed, config.time_window_ms),
+            RateType::Simple => u128::max(elapsed, time_window_ms),
         }
     }
 }
@@ -75,19 +77,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SimpleRate_rus
     time_unit: JObject,
     stat: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let time_unit = TimeUnit::clone_from_java(env, time_unit.into())?;
         let stat = SampledStat::clone_from_java(env, stat.into())?;
-        let rate = Box::new(Rate::new(time_unit, stat, RateType::Simple));
-        let ptr = Box::into_raw(rate);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(env, obj, Rate::new(time_unit, stat, RateType::Simple))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Rate
@@ -102,19 +98,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Rate_rustConst
     time_unit: JObject,
     stat: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let time_unit = TimeUnit::clone_from_java(env, time_unit.into())?;
         let stat = SampledStat::clone_from_java(env, stat.into())?;
-        let rate = Box::new(Rate::new(time_unit, stat, RateType::Standard));
-        let ptr = Box::into_raw(rate);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(env, obj, Rate::new(time_unit, stat, RateType::Standard))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Rate
@@ -127,16 +117,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Rate_rustDestr
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Rate) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<Rate>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 rust_property_getter!(
@@ -160,18 +145,15 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Rate_record(
     value: jdouble,
     time_ms: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let config = MetricConfig::clone_from_java(env, config.into()).unwrap_or_default();
+    jni_guard(env, || -> JniResult<_> {
+        let config =
+            Option::<MetricConfig>::clone_from_java(env, config.into())?.unwrap_or_default();
         let mut rate = Rate::from_jobject(env, obj)?;
         rate.modify(|rate| {
             rate.record(&config, value, time_ms as u128);
         });
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -187,17 +169,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Rate_measure(
     config: JObject,
     now: jlong,
 ) -> f64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut rate = Rate::from_jobject(env, obj)?;
         let ret = rate.modify(|rate| rate.measure(&config, now as u128));
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -213,17 +190,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Rate_windowSiz
     config: JObject,
     now: jlong,
 ) -> i64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut rate = Rate::from_jobject(env, obj)?;
         let ret = rate.modify(|rate| rate.window_size(&config, now as u128)) as i64;
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -1,40 +1,47 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jlong},
     JNIEnv,
 };
-use kafka_connector_macros::{rust_property_chain_setter, rust_property_getter};
+use kafka_connector_macros::JavaStruct;
 
 use crate::{
-    clone_from_java::CloneFromJava, common::metrics::metric_config::MetricConfig,
-    java_stored_object::FromJObject, java_struct_standard_impl,
+    clone_from_java::CloneFromJava,
+    common::errors::JniResult,
+    common::metrics::metric_config::MetricConfig,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    jni_guard::jni_guard,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/stats/Sample"]
 pub struct Sample {
     pub initial_value: f64,
-    pub event_count: u64,
-    pub last_window_ms: u128,
+    #[java_getter]
+    #[java_setter]
+    pub event_count: i64,
+    #[java_getter]
+    pub last_window_ms: i64,
+    #[java_getter]
     pub value: f64,
 }
-java_struct_standard_impl!(Sample, "org/apache/kafka/common/metrics/stats/Sample");
 
 impl Sample {
     pub fn new(initial_value: f64, now: u128) -> Sample {
         Sample {
             event_count: 0,
             initial_value,
-            last_window_ms: now,
+            last_window_ms: now as i64,
             value: initial_value,
         }
     }
     pub fn reset(&mut self, now: u128) {
         self.event_count = 0;
-        self.last_window_ms = now;
+        self.last_window_ms = now as i64;
         self.value = self.initial_value;
     }
     pub fn is_complete(&self, time_ms: u128, config: &MetricConfig) -> bool {
-        u128::saturating_sub(time_ms, self.last_window_ms) >= config.time_window_ms
+        u128::saturating_sub(time_ms, self.last_window_ms as u128) >= config.time_window_ms as u128
             || self.event_count >= config.event_window
     }
 }
@@ -52,72 +59,13 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Sample_rustCon
     initial_value: jdouble,
     now: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let token_bucket = Box::new(Sample::new(initial_value, now as u128));
-        let ptr = Box::into_raw(token_bucket);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, Sample::new(initial_value, now as u128))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
-}
-
-/*
- * Class:     org_apache_kafka_common_metrics_stats_Sample
- * Method:    rustDestructor
- * Signature: ()V
- */
-#[no_mangle]
-#[allow(non_snake_case)]
-pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Sample_rustDestructor(
-    env: JNIEnv,
-    obj: JObject,
-) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Sample) };
-
-        Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
-rust_property_getter!(
- * Function:  Java_org_apache_kafka_common_metrics_stats_Sample_eventCount__
- * Struct:    Sample
- * Class:     org_apache_kafka_common_metrics_stats_Sample
- * Method:    eventCount
- * Signature: ()J
-);
-
-rust_property_chain_setter!(
- * Function:  Java_org_apache_kafka_common_metrics_stats_Sample_eventCount__J
- * Struct:    Sample
- * Class:     org_apache_kafka_common_metrics_stats_Sample
- * Method:    eventCount
- * Signature: (J)Lorg/apache/kafka/common/metrics/stats/Sample;
-);
-
-rust_property_getter!(
- * Struct:    Sample
- * Class:     org_apache_kafka_common_metrics_stats_Sample
- * Method:    lastWindowMs
- * Signature: ()J
-);
-
-rust_property_getter!(
- * Struct:    Sample
- * Class:     org_apache_kafka_common_metrics_stats_Sample
- * Method:    value
- * Signature: ()D
-);
-
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Sample
  * Method:    reset
@@ -130,15 +78,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Sample_reset(
     obj: JObject,
     now: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut sample = Sample::from_jobject(env, obj)?;
         sample.modify(|stat| stat.reset(now as u128));
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -154,15 +98,10 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Sample_isCompl
     time_ms: jlong,
     config: JObject,
 ) -> bool {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let sample = Sample::from_jobject(env, obj)?;
-        let ret = sample.is_complete(time_ms as u128, &config);
+        let ret = sample.lock().is_complete(time_ms as u128, &config);
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => false,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
@@ -5,8 +5,13 @@ use jni::{
 };
 
 use crate::{
-    clone_from_java::CloneFromJava, clone_to_java::CloneToJava,
-    common::metrics::metric_config::MetricConfig, java_stored_object::FromJObject,
+    clone_from_java::CloneFromJava,
+    clone_to_java::CloneToJava,
+    common::errors::JniResult,
+    common::metrics::metric_config::MetricConfig,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    jni_cache,
+    jni_guard::jni_guard,
 };
 
 use super::sample::Sample;
@@ -46,7 +51,7 @@ impl SampledStat {
         let mut sample = self.current(time_ms);
         if sample.is_complete(time_ms, config) {
             self.current = (self.current + 1) % config.samples as usize;
-            if self.current as usize >= self.samples.len() {
+            if self.current >= self.samples.len() {
                 let new_sample = Sample::new(self.initial_value, time_ms);
                 self.samples.push(new_sample);
                 sample = self.samples.last_mut().unwrap();
@@ -92,7 +97,7 @@ impl SampledStat {
             StatType::Avg => {
                 let (total, count) = samples
                     .iter()
-                    .fold((0_f64, 0_u64), |(total, count), sample| {
+                    .fold((0_f64, 0_i64), |(total, count), sample| {
                         (total + sample.value, count + sample.event_count)
                     });
                 if count == 0 {
@@ -105,7 +110,7 @@ impl SampledStat {
                 let (min, count) =
                     samples
                         .iter()
-                        .fold((f64::MAX, 0_u64), |(total, count), sample| {
+                        .fold((f64::MAX, 0_i64), |(total, count), sample| {
                             (f64::min(total, sample.value), count + sample.event_count)
                         });
                 if count == 0 {
@@ -118,7 +123,7 @@ impl SampledStat {
                 let (max, count) =
                     samples
                         .iter()
-                        .fold((f64::MIN, 0_u64), |(total, count), sample| {
+                        .fold((f64::MIN, 0_i64), |(total, count), sample| {
                             (f64::max(total, sample.value), count + sample.event_count)
                         });
                 if count == 0 {
@@ -134,39 +139,31 @@ impl SampledStat {
     }
     pub(super) fn purge_obsolete_samples(&mut self, config: &MetricConfig, now: u128) {
         let (max_window_ms, overflow) =
-            u128::overflowing_sub(now, config.samples as u128 * config.time_window_ms);
+            u128::overflowing_sub(now, config.samples as u128 * config.time_window_ms as u128);
         if !overflow {
             self.samples
                 .iter_mut()
-                .filter(|s| s.last_window_ms <= max_window_ms)
+                .filter(|s| s.last_window_ms as u128 <= max_window_ms)
                 .for_each(|s| s.reset(now));
         }
     }
 }
 
 impl CloneToJava for SampledStat {
+    type Kind = crate::clone_to_java::kind::Object;
+
     fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
         let class_name = match self.stat_type {
-            StatType::Avg => "Avg",
-            StatType::Min => "Min",
-            StatType::Max => "Max",
-            StatType::WindowedSum => "WindowedSum",
-            StatType::WindowedCount => "WindowedCount",
+            StatType::Avg => "org/apache/kafka/common/metrics/stats/Avg",
+            StatType::Min => "org/apache/kafka/common/metrics/stats/Min",
+            StatType::Max => "org/apache/kafka/common/metrics/stats/Max",
+            StatType::WindowedSum => "org/apache/kafka/common/metrics/stats/WindowedSum",
+            StatType::WindowedCount => "org/apache/kafka/common/metrics/stats/WindowedCount",
         };
-        let class = env.find_class(&format!(
-            "org/apache/kafka/common/metrics/stats/{}",
-            class_name
-        ))?;
-        let obj = env.alloc_object(class)?;
+        let class = jni_cache::class(env, class_name)?;
+        let obj = env.alloc_object(&class)?;
 
-        let copy = Box::new(self.clone());
-        let ptr = Box::into_raw(copy);
-        env.set_field(
-            obj,
-            "rustPointer",
-            "J",
-            jni::objects::JValue::Long(ptr as i64),
-        )?;
+        JavaStoredObject::store(env, obj, self.clone())?;
         Ok(obj.into())
     }
 }
@@ -193,18 +190,14 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SampledStat_re
     value: jdouble,
     time_ms: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut stat = SampledStat::from_jobject(env, obj)?;
         stat.modify(|stat| {
             stat.record(&config, value, time_ms as u128);
         });
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -220,17 +213,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SampledStat_me
     config: JObject,
     now: jlong,
 ) -> f64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut stat = SampledStat::from_jobject(env, obj)?;
         let ret = stat.modify(|stat| stat.measure(&config, now as u128));
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -245,17 +233,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SampledStat_cu
     obj: JObject,
     time_ms: jlong,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut stat = SampledStat::from_jobject(env, obj)?;
         let ret =
             stat.modify(|stat| CloneToJava::clone_to_java(stat.current(time_ms as u128), env));
-        ret?.l()
-    }();
-    match result {
-        Ok(v) => v.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+        Ok(ret?.l()?.into_inner())
+    })
 }
 
 /*
@@ -270,16 +253,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SampledStat_ol
     obj: JObject,
     time_ms: jlong,
 ) -> jobject {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let mut stat = SampledStat::from_jobject(env, obj)?;
         let ret = stat.modify(|stat| CloneToJava::clone_to_java(stat.oldest(time_ms as u128), env));
-        ret?.l()
-    }();
-    match result {
-        Ok(v) => v.into_inner(),
-        Err(jni::errors::Error::JavaException) => JObject::null().into_inner(),
-        _ => panic!("{:?}", result),
-    }
+        Ok(ret?.l()?.into_inner())
+    })
 }
 
 /*
@@ -295,56 +273,36 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_SampledStat_pu
     config: JObject,
     now: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut stat = SampledStat::from_jobject(env, obj)?;
         stat.modify(|stat| stat.purge_obsolete_samples(&config, now as u128));
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 fn java_sampled_stat_constructor(env: JNIEnv, obj: JObject, type_: StatType) {
-    let result = || -> jni::errors::Result<_> {
-        let sampled_stat = Box::new(SampledStat::new(type_));
-        let ptr = Box::into_raw(sampled_stat);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, SampledStat::new(type_))?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 fn java_sampled_stat_destructor(env: JNIEnv, obj: JObject) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut SampledStat) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<SampledStat>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 fn java_sampled_stat_combine(env: JNIEnv, obj: JObject, samples: JObject) -> jdouble {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let stat = SampledStat::from_jobject(env, obj)?;
         let samples: Vec<Sample> = CloneFromJava::clone_from_java(env, samples.into())?;
-        let ret = stat.combine(&samples);
+        let ret = stat.lock().combine(&samples);
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => 0_f64,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_Avg
@@ -1,13 +1,15 @@
 use crate::{
     clone_from_java::CloneFromJava,
+    common::errors::JniResult,
     common::metrics::{
         internals::metric_utils::TimeUnit, metric_config::MetricConfig, quota::Quota,
     },
-    java_stored_object::FromJObject,
+    java_stored_object::{FromJObject, JavaStoredObject},
     java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jlong},
     JNIEnv,
 };
@@ -48,7 +50,7 @@ impl TokenBucket {
     }
     fn burst(&self, config: &MetricConfig) -> f64 {
         (config.samples as f64)
-            * self.unit.convert(config.time_window_ms)
+            * self.unit.convert(config.time_window_ms as u128)
             * config.quota.as_ref().unwrap().bound
     }
 }
@@ -70,22 +72,20 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_TokenBucket_ru
     obj: JObject,
     time_unit: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let time_unit = TimeUnit::clone_from_java(env, time_unit.into())?;
-        let token_bucket = Box::new(TokenBucket {
-            unit: time_unit,
-            tokens: 0f64,
-            last_update_ms: 0,
-        });
-        let ptr = Box::into_raw(token_bucket);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+        JavaStoredObject::store(
+            env,
+            obj,
+            TokenBucket {
+                unit: time_unit,
+                tokens: 0f64,
+                last_update_ms: 0,
+            },
+        )?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -99,16 +99,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_TokenBucket_ru
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut TokenBucket) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<TokenBucket>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 rust_property_getter!(
@@ -145,16 +140,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_TokenBucket_me
     config: JObject,
     time_ms: jlong,
 ) -> jdouble {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut token_bucket = TokenBucket::from_jobject(env, obj)?;
         Ok(token_bucket.modify(|bucket| bucket.measure(&config, time_ms as u128)))
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => 0f64,
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 /*
  * Class:     org_apache_kafka_common_metrics_stats_TokenBucket
@@ -170,14 +160,10 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_TokenBucket_re
     value: jdouble,
     time_ms: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut token_bucket = TokenBucket::from_jobject(env, obj)?;
         token_bucket.modify(|bucket| bucket.record(&config, value, time_ms as u128));
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
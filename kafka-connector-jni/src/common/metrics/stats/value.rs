@@ -1,12 +1,16 @@
 use jni::{
-    objects::{JObject, JValue},
+    objects::JObject,
     sys::{jdouble, jlong},
     JNIEnv,
 };
 
 use crate::{
-    clone_from_java::CloneFromJava, common::metrics::metric_config::MetricConfig,
-    java_stored_object::FromJObject, java_struct_standard_impl,
+    clone_from_java::CloneFromJava,
+    common::errors::JniResult,
+    common::metrics::metric_config::MetricConfig,
+    java_stored_object::{FromJObject, JavaStoredObject},
+    java_struct_standard_impl,
+    jni_guard::jni_guard,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -39,18 +43,15 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Value_record(
     value: jdouble,
     time_ms: jlong,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let config = MetricConfig::clone_from_java(env, config.into()).unwrap_or_default();
+    jni_guard(env, || -> JniResult<_> {
+        let config =
+            Option::<MetricConfig>::clone_from_java(env, config.into())?.unwrap_or_default();
         let mut stat = Value::from_jobject(env, obj)?;
         stat.modify(|stat| {
             stat.record(&config, value, time_ms as u128);
         });
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -66,17 +67,12 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Value_measure(
     config: JObject,
     now: jlong,
 ) -> f64 {
-    let result = || -> jni::errors::Result<_> {
+    jni_guard(env, || -> JniResult<_> {
         let config = MetricConfig::clone_from_java(env, config.into())?;
         let mut stat = Value::from_jobject(env, obj)?;
         let ret = stat.modify(|stat| stat.measure(&config, now as u128));
         Ok(ret)
-    }();
-    match result {
-        Ok(v) => v,
-        Err(jni::errors::Error::JavaException) => Default::default(),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -90,17 +86,11 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Value_rustCons
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let value = Box::new(Value::default());
-        let ptr = Box::into_raw(value);
-        env.set_field(obj, "rustPointer", "J", JValue::Long(ptr as i64))?;
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::store(env, obj, Value::default())?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
 
 /*
@@ -114,14 +104,9 @@ pub extern "system" fn Java_org_apache_kafka_common_metrics_stats_Value_rustDest
     env: JNIEnv,
     obj: JObject,
 ) {
-    let result = || -> jni::errors::Result<_> {
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let _obj = unsafe { Box::from_raw(ptr as *mut Value) };
+    jni_guard(env, || -> JniResult<_> {
+        JavaStoredObject::<Value>::destroy(env, obj)?;
 
         Ok(())
-    }();
-    match result {
-        Ok(_) | Err(jni::errors::Error::JavaException) => (),
-        _ => panic!("{:?}", result),
-    }
+    })
 }
@@ -1,6 +1,24 @@
+pub mod acl;
+pub mod cluster;
+pub mod compress;
+pub mod config;
+pub mod consumer_group_state;
+pub mod errors;
 pub mod header;
+pub mod isolation_level;
+pub mod kafka_future;
 pub mod metrics;
+pub mod network;
+pub mod node;
+pub mod partition_info;
+pub mod protocol;
 pub mod record;
+pub mod requests;
+pub mod resource;
+pub mod serialization;
+pub mod topic_partition;
+pub mod topic_partition_info;
+pub mod utils;
 
 pub mod metric_name;
 pub mod metric_name_template;
@@ -0,0 +1,100 @@
+use std::io::{self, ErrorKind};
+
+use mio::net::TcpStream;
+
+use super::{network_receive::NetworkReceive, network_send::NetworkSend};
+
+/// A connection to a node, holding the send in progress and the receive being read.
+#[derive(Debug)]
+pub struct KafkaChannel {
+    id: String,
+    stream: TcpStream,
+    connected: bool,
+    max_receive_size: usize,
+    send: Option<NetworkSend>,
+    receive: Option<NetworkReceive>,
+}
+
+impl KafkaChannel {
+    pub fn new(id: impl Into<String>, stream: TcpStream, max_receive_size: usize) -> KafkaChannel {
+        KafkaChannel {
+            id: id.into(),
+            stream,
+            connected: false,
+            max_receive_size,
+            send: None,
+            receive: None,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn stream(&mut self) -> &mut TcpStream {
+        &mut self.stream
+    }
+
+    /// Whether the connection is established and the channel can be used for sends.
+    pub fn ready(&self) -> bool {
+        self.connected
+    }
+
+    /// Complete the non-blocking connect, returning false if it is still in progress.
+    pub fn finish_connect(&mut self) -> io::Result<bool> {
+        if self.connected {
+            return Ok(true);
+        }
+        if let Some(e) = self.stream.take_error()? {
+            return Err(e);
+        }
+        match self.stream.peer_addr() {
+            Ok(_) => {
+                self.connected = true;
+                Ok(true)
+            }
+            Err(e) if e.kind() == ErrorKind::NotConnected => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn has_send(&self) -> bool {
+        self.send.is_some()
+    }
+
+    /// Set the send to write, a channel has at most one send in progress.
+    pub fn set_send(&mut self, send: NetworkSend) -> Result<(), NetworkSend> {
+        if self.send.is_some() {
+            return Err(send);
+        }
+        self.send = Some(send);
+        Ok(())
+    }
+
+    /// Write the send in progress, returning it once completely written.
+    pub fn write(&mut self) -> io::Result<Option<NetworkSend>> {
+        match &mut self.send {
+            Some(send) if self.connected => {
+                send.write_to(&mut self.stream)?;
+                if send.completed() {
+                    return Ok(self.send.take());
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Read the next receive, returning `None` if it is not complete yet because the stream
+    /// would block.
+    pub fn read(&mut self) -> io::Result<Option<NetworkReceive>> {
+        let receive = self
+            .receive
+            .get_or_insert_with(|| NetworkReceive::new(self.id.clone(), self.max_receive_size));
+        receive.read_from(&mut self.stream)?;
+        if receive.complete() {
+            return Ok(self.receive.take());
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,4 @@
+pub mod kafka_channel;
+pub mod network_receive;
+pub mod network_send;
+pub mod selector;
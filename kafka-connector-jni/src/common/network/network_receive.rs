@@ -0,0 +1,102 @@
+use std::io::{self, ErrorKind, Read};
+
+use bytes::{Bytes, BytesMut};
+
+/// A size delimited receive, consisting of a 4 byte network-ordered size N followed by N bytes of
+/// content.
+#[derive(Debug)]
+pub struct NetworkReceive {
+    source: String,
+    max_size: usize,
+    size: [u8; 4],
+    size_read: usize,
+    payload_size: Option<usize>,
+    buffer: BytesMut,
+}
+
+impl NetworkReceive {
+    pub const UNLIMITED: usize = usize::MAX;
+
+    pub fn new(source: impl Into<String>, max_size: usize) -> NetworkReceive {
+        NetworkReceive {
+            source: source.into(),
+            max_size,
+            size: [0; 4],
+            size_read: 0,
+            payload_size: None,
+            buffer: BytesMut::new(),
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn complete(&self) -> bool {
+        self.payload_size == Some(self.buffer.len())
+    }
+
+    /// Read from `channel` until the receive is complete or the channel would block. Returns the
+    /// number of bytes read, the end of the stream is an `UnexpectedEof` error.
+    pub fn read_from(&mut self, channel: &mut impl Read) -> io::Result<usize> {
+        let mut read = 0;
+        while self.size_read < self.size.len() {
+            let n = read_some(channel, &mut self.size[self.size_read..])?;
+            if n == 0 {
+                return Ok(read);
+            }
+            self.size_read += n;
+            read += n;
+        }
+        if self.payload_size.is_none() {
+            let size = i32::from_be_bytes(self.size);
+            if size < 0 {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid receive (size = {})", size),
+                ));
+            }
+            if size as usize > self.max_size {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Invalid receive (size = {} larger than {})",
+                        size, self.max_size
+                    ),
+                ));
+            }
+            self.payload_size = Some(size as usize);
+            self.buffer.reserve(size as usize);
+        }
+        let payload_size = self.payload_size.unwrap_or_default();
+        let mut chunk = [0; 8192];
+        while self.buffer.len() < payload_size {
+            let remaining = (payload_size - self.buffer.len()).min(chunk.len());
+            let n = read_some(channel, &mut chunk[..remaining])?;
+            if n == 0 {
+                break;
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+            read += n;
+        }
+        Ok(read)
+    }
+
+    /// The content of a complete receive, without the size.
+    pub fn payload(self) -> Bytes {
+        self.buffer.freeze()
+    }
+}
+
+/// Read into `buf`, 0 meaning that the channel would block.
+fn read_some(channel: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match channel.read(buf) {
+            Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+            Ok(n) => return Ok(n),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(0),
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
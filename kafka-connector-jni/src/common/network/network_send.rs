@@ -0,0 +1,55 @@
+use std::io::{self, ErrorKind, Write};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// A size delimited send, the 4 byte network-ordered size of the payload followed by the payload.
+#[derive(Debug)]
+pub struct NetworkSend {
+    destination: String,
+    buffer: Bytes,
+    written: usize,
+}
+
+impl NetworkSend {
+    pub fn size_delimited(destination: impl Into<String>, payload: &[u8]) -> NetworkSend {
+        let mut buffer = BytesMut::with_capacity(4 + payload.len());
+        buffer.put_i32(payload.len() as i32);
+        buffer.put_slice(payload);
+        NetworkSend {
+            destination: destination.into(),
+            buffer: buffer.freeze(),
+            written: 0,
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        &self.destination
+    }
+
+    pub fn completed(&self) -> bool {
+        self.written == self.buffer.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Write to `channel` until the send is complete or the channel would block. Returns the
+    /// number of bytes written.
+    pub fn write_to(&mut self, channel: &mut impl Write) -> io::Result<usize> {
+        let mut written = 0;
+        while !self.completed() {
+            match channel.write(&self.buffer[self.written..]) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.written += n;
+                    written += n;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    collections::HashMap,
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use mio::{net::TcpStream, Events, Interest, Poll, Token, Waker};
+
+use crate::common::utils::{
+    log_context::{LogContext, Logger},
+    time::Time,
+};
+
+use super::{
+    kafka_channel::KafkaChannel, network_receive::NetworkReceive, network_send::NetworkSend,
+};
+
+const WAKER_TOKEN: Token = Token(0);
+
+/// A nioSelector interface for doing non-blocking multi-connection network I/O.
+///
+/// Connections are identified by the id of their node. A connection is added with `connect`,
+/// sends are queued with `send` and `poll` does the reads and writes, after which the connections
+/// completed, the sends written, the responses read and the connections lost during the poll can
+/// be drained. A channel has at most one send in progress.
+pub struct Selector {
+    poll: Poll,
+    events: Events,
+    waker: Arc<Waker>,
+    next_token: usize,
+    tokens: HashMap<Token, String>,
+    channels: HashMap<String, (Token, KafkaChannel)>,
+    last_activity_ms: HashMap<String, u128>,
+    connected: Vec<String>,
+    completed_sends: Vec<NetworkSend>,
+    completed_receives: Vec<NetworkReceive>,
+    disconnected: Vec<String>,
+    failed_sends: Vec<String>,
+    max_receive_size: usize,
+    connections_max_idle_ms: Option<u128>,
+    time: Arc<dyn Time>,
+    log: Logger,
+}
+
+impl Selector {
+    /// * `connections_max_idle_ms` - Close idle connections after this many milliseconds, `None`
+    ///   to keep them open
+    /// * `max_receive_size` - The maximum size of a receive, larger ones are considered corrupt
+    pub fn new(
+        connections_max_idle_ms: Option<u128>,
+        max_receive_size: usize,
+        time: Arc<dyn Time>,
+        log_context: &LogContext,
+    ) -> io::Result<Selector> {
+        let poll = Poll::new()?;
+        let waker = Arc::new(Waker::new(poll.registry(), WAKER_TOKEN)?);
+        Ok(Selector {
+            poll,
+            events: Events::with_capacity(256),
+            waker,
+            next_token: WAKER_TOKEN.0 + 1,
+            tokens: HashMap::new(),
+            channels: HashMap::new(),
+            last_activity_ms: HashMap::new(),
+            connected: vec![],
+            completed_sends: vec![],
+            completed_receives: vec![],
+            disconnected: vec![],
+            failed_sends: vec![],
+            max_receive_size,
+            connections_max_idle_ms,
+            time,
+            log: log_context.logger(module_path!()),
+        })
+    }
+
+    /// Interrupts a blocking `poll` from any thread.
+    pub fn waker(&self) -> Arc<Waker> {
+        self.waker.clone()
+    }
+
+    /// Begin connecting to the given address and add the connection to this selector under the
+    /// given id. The connect is non-blocking, its completion is reported by `drain_connected`
+    /// after a `poll`.
+    pub fn connect(&mut self, id: &str, address: SocketAddr) -> io::Result<()> {
+        if self.channels.contains_key(id) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("There is already a connection for id {}", id),
+            ));
+        }
+        let mut stream = TcpStream::connect(address)?;
+        let token = Token(self.next_token);
+        self.next_token += 1;
+        self.poll.registry().register(
+            &mut stream,
+            token,
+            Interest::READABLE | Interest::WRITABLE,
+        )?;
+        self.tokens.insert(token, id.to_owned());
+        self.channels.insert(
+            id.to_owned(),
+            (token, KafkaChannel::new(id, stream, self.max_receive_size)),
+        );
+        self.last_activity_ms
+            .insert(id.to_owned(), self.time.milliseconds());
+        Ok(())
+    }
+
+    /// Queue the given request for sending in the subsequent `poll` calls. A send to a closed
+    /// connection is reported as a disconnection by the next `poll`.
+    pub fn send(&mut self, send: NetworkSend) {
+        let destination = send.destination().to_owned();
+        let result = match self.channels.get_mut(&destination) {
+            Some((_, channel)) => channel.set_send(send).map_err(|_| {
+                self.log.error(format_args!(
+                    "Attempt to begin a send operation with prior send operation still in progress, connection id is {}",
+                    destination
+                ))
+            }),
+            None => Err(()),
+        };
+        if result.is_err() {
+            self.close(&destination);
+            self.failed_sends.push(destination);
+        }
+    }
+
+    /// Do whatever I/O can be done on each connection without blocking, waiting up to
+    /// `timeout_ms` for I/O when there is none. Completed connections, sends and receives as well
+    /// as disconnections are collected, replacing the ones of the previous poll.
+    pub fn poll(&mut self, timeout_ms: u128) -> io::Result<()> {
+        self.connected.clear();
+        self.completed_sends.clear();
+        self.completed_receives.clear();
+        self.disconnected.clear();
+        self.disconnected.append(&mut self.failed_sends);
+
+        let ids: Vec<String> = self.channels.keys().cloned().collect();
+        for id in &ids {
+            self.write(id);
+        }
+
+        let timeout_ms = if self.completed_sends.is_empty() && self.disconnected.is_empty() {
+            timeout_ms
+        } else {
+            0
+        };
+        let timeout = Duration::from_millis(timeout_ms.min(u64::MAX as u128) as u64);
+        if let Err(e) = self.poll.poll(&mut self.events, Some(timeout)) {
+            if e.kind() != ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+
+        let ready: Vec<String> = self
+            .events
+            .iter()
+            .filter_map(|event| self.tokens.get(&event.token()).cloned())
+            .collect();
+        for id in ready {
+            self.poll_channel(&id);
+        }
+        self.close_idle_connections();
+        Ok(())
+    }
+
+    fn poll_channel(&mut self, id: &str) {
+        let channel = match self.channels.get_mut(id) {
+            Some((_, channel)) => channel,
+            None => return,
+        };
+        if !channel.ready() {
+            match channel.finish_connect() {
+                Ok(true) => {
+                    if let Err(e) = channel.stream().set_nodelay(true) {
+                        self.log.debug(format_args!(
+                            "Failed to disable Nagle's algorithm for {}: {}",
+                            id, e
+                        ));
+                    }
+                    self.log
+                        .debug(format_args!("Created socket for node {}", id));
+                    self.connected.push(id.to_owned());
+                }
+                Ok(false) => return,
+                Err(e) => {
+                    self.log.debug(format_args!(
+                        "Connection with node {} disconnected: {}",
+                        id, e
+                    ));
+                    self.disconnect(id);
+                    return;
+                }
+            }
+        }
+        self.write(id);
+        self.read(id);
+    }
+
+    fn write(&mut self, id: &str) {
+        let channel = match self.channels.get_mut(id) {
+            Some((_, channel)) => channel,
+            None => return,
+        };
+        if !channel.has_send() {
+            return;
+        }
+        match channel.write() {
+            Ok(Some(send)) => {
+                self.completed_sends.push(send);
+                self.touch(id);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                self.log.debug(format_args!(
+                    "Connection with node {} disconnected while writing: {}",
+                    id, e
+                ));
+                self.disconnect(id);
+            }
+        }
+    }
+
+    /// Read every complete receive, the events of the connections are edge triggered so the
+    /// stream is read until it would block.
+    fn read(&mut self, id: &str) {
+        loop {
+            let channel = match self.channels.get_mut(id) {
+                Some((_, channel)) => channel,
+                None => return,
+            };
+            if !channel.ready() {
+                return;
+            }
+            match channel.read() {
+                Ok(Some(receive)) => {
+                    self.completed_receives.push(receive);
+                    self.touch(id);
+                }
+                Ok(None) => return,
+                Err(e) => {
+                    if e.kind() == ErrorKind::UnexpectedEof {
+                        self.log.debug(format_args!(
+                            "Connection with node {} disconnected by the peer",
+                            id
+                        ));
+                    } else {
+                        self.log.warn(format_args!(
+                            "Unexpected error from node {}; closing connection: {}",
+                            id, e
+                        ));
+                    }
+                    self.disconnect(id);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn touch(&mut self, id: &str) {
+        self.last_activity_ms
+            .insert(id.to_owned(), self.time.milliseconds());
+    }
+
+    fn close_idle_connections(&mut self) {
+        let max_idle_ms = match self.connections_max_idle_ms {
+            Some(max_idle_ms) => max_idle_ms,
+            None => return,
+        };
+        let now = self.time.milliseconds();
+        let expired: Vec<String> = self
+            .last_activity_ms
+            .iter()
+            .filter(|(_, &last_activity_ms)| now.saturating_sub(last_activity_ms) > max_idle_ms)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            self.log.trace(format_args!(
+                "About to close the idle connection from {} due to being idle for {} millis",
+                id, max_idle_ms
+            ));
+            self.disconnect(&id);
+        }
+    }
+
+    fn disconnect(&mut self, id: &str) {
+        self.close(id);
+        self.disconnected.push(id.to_owned());
+    }
+
+    /// Close the connection identified by the given id, without reporting it as disconnected.
+    pub fn close(&mut self, id: &str) {
+        if let Some((token, mut channel)) = self.channels.remove(id) {
+            if let Err(e) = self.poll.registry().deregister(channel.stream()) {
+                self.log
+                    .debug(format_args!("Failed to deregister {}: {}", id, e));
+            }
+            self.tokens.remove(&token);
+        }
+        self.last_activity_ms.remove(id);
+    }
+
+    /// Close every connection.
+    pub fn close_all(&mut self) {
+        let ids: Vec<String> = self.channels.keys().cloned().collect();
+        for id in ids {
+            self.close(&id);
+        }
+    }
+
+    /// Whether the connection is established and can be used for sends.
+    pub fn is_channel_ready(&self, id: &str) -> bool {
+        matches!(self.channels.get(id), Some((_, channel)) if channel.ready())
+    }
+
+    /// The ids of the connections established during the last poll.
+    pub fn drain_connected(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.connected)
+    }
+
+    /// The sends written during the last poll.
+    pub fn drain_completed_sends(&mut self) -> Vec<NetworkSend> {
+        std::mem::take(&mut self.completed_sends)
+    }
+
+    /// The receives read during the last poll.
+    pub fn drain_completed_receives(&mut self) -> Vec<NetworkReceive> {
+        std::mem::take(&mut self.completed_receives)
+    }
+
+    /// The ids of the connections lost during the last poll, either by the peer, by an I/O error
+    /// or because they were idle too long.
+    pub fn drain_disconnected(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.disconnected)
+    }
+}
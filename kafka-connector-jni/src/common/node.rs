@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+use jni::{objects::JValue, JNIEnv};
+
+use crate::{
+    clone_from_java::{non_null, CloneFromJava},
+    clone_to_java::{kind, CloneToJava},
+    jni_cache,
+};
+
+/// Information about a Kafka node
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Node {
+    pub id: i32,
+    pub host: String,
+    pub port: i32,
+    pub rack: Option<String>,
+}
+impl Node {
+    pub fn new(id: i32, host: impl Into<String>, port: i32) -> Node {
+        Node {
+            id,
+            host: host.into(),
+            port,
+            rack: None,
+        }
+    }
+    pub fn with_rack(id: i32, host: impl Into<String>, port: i32, rack: impl Into<String>) -> Node {
+        Node {
+            rack: Some(rack.into()),
+            ..Node::new(id, host, port)
+        }
+    }
+    pub fn no_node() -> Node {
+        Node::new(-1, "", -1)
+    }
+    /// Check whether this node is empty, which may be the case if noNode() is used as a placeholder
+    /// in a response payload with an error.
+    pub fn is_empty(&self) -> bool {
+        self.host.is_empty() || self.port < 0
+    }
+    pub fn id_string(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{} (id: {} rack: {})",
+            self.host,
+            self.port,
+            self.id,
+            self.rack.as_deref().unwrap_or("null")
+        )
+    }
+}
+
+/// `org.apache.kafka.common.Node`, a plain java value class.
+impl CloneToJava for Node {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let host = self.host.clone_to_java(env)?;
+        let rack = self.rack.clone_to_java(env)?;
+        jni_cache::new_object(
+            env,
+            "org/apache/kafka/common/Node",
+            "(ILjava/lang/String;ILjava/lang/String;)V",
+            &[JValue::Int(self.id), host, JValue::Int(self.port), rack],
+        )
+        .map(JValue::Object)
+    }
+}
+
+impl CloneFromJava for Node {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let call = |name, sig| {
+            jni_cache::call_method(env, obj, "org/apache/kafka/common/Node", name, sig, &[])
+        };
+        Ok(Node {
+            id: call("id", "()I")?.i()?,
+            host: String::clone_from_java(env, call("host", "()Ljava/lang/String;")?)?,
+            port: call("port", "()I")?.i()?,
+            rack: Option::<String>::clone_from_java(env, call("rack", "()Ljava/lang/String;")?)?,
+        })
+    }
+}
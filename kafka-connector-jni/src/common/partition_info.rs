@@ -0,0 +1,89 @@
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+
+use crate::{
+    clone_from_java::{in_local_frame, non_null, CloneFromJava},
+    jni_cache,
+};
+
+use super::node::Node;
+
+/// This is used to describe per-partition state in the MetadataResponse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionInfo {
+    pub topic: String,
+    pub partition: i32,
+    pub leader: Option<Node>,
+    pub replicas: Vec<Node>,
+    pub in_sync_replicas: Vec<Node>,
+    pub offline_replicas: Vec<Node>,
+}
+impl PartitionInfo {
+    pub fn new(
+        topic: impl Into<String>,
+        partition: i32,
+        leader: Option<Node>,
+        replicas: Vec<Node>,
+        in_sync_replicas: Vec<Node>,
+    ) -> PartitionInfo {
+        PartitionInfo {
+            topic: topic.into(),
+            partition,
+            leader,
+            replicas,
+            in_sync_replicas,
+            offline_replicas: vec![],
+        }
+    }
+}
+
+/// `org.apache.kafka.common.PartitionInfo`, a plain java value class.
+impl CloneFromJava for PartitionInfo {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let call = |name, sig| {
+            jni_cache::call_method(
+                env,
+                obj,
+                "org/apache/kafka/common/PartitionInfo",
+                name,
+                sig,
+                &[],
+            )
+        };
+        let nodes = |name| -> jni::errors::Result<Vec<Node>> {
+            nodes_from_array(env, call(name, "()[Lorg/apache/kafka/common/Node;")?.l()?)
+        };
+        Ok(PartitionInfo {
+            topic: String::clone_from_java(env, call("topic", "()Ljava/lang/String;")?)?,
+            partition: call("partition", "()I")?.i()?,
+            leader: Option::<Node>::clone_from_java(
+                env,
+                call("leader", "()Lorg/apache/kafka/common/Node;")?,
+            )?,
+            replicas: nodes("replicas")?,
+            in_sync_replicas: nodes("inSyncReplicas")?,
+            offline_replicas: nodes("offlineReplicas")?,
+        })
+    }
+}
+
+/// Nodes of a `Node[]`, which java leaves `null` for partitions without replica information.
+fn nodes_from_array(env: JNIEnv, array: JObject) -> jni::errors::Result<Vec<Node>> {
+    if array.is_null() {
+        return Ok(vec![]);
+    }
+    let array = array.into_inner();
+    (0..env.get_array_length(array)?)
+        .map(|i| {
+            in_local_frame(env, || {
+                Node::clone_from_java_object(env, env.get_object_array_element(array, i)?)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,178 @@
+use std::fmt::{self, Display};
+
+use crate::common::errors::{KafkaError, Result};
+
+macro_rules! api_keys {
+    ($($variant:ident = $id:literal, $name:literal;)*) => {
+        /// Identifiers for all the Kafka APIs
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum ApiKeys {
+            $($variant,)*
+        }
+
+        impl ApiKeys {
+            /// The permanent and immutable id of an API - this can't change ever
+            pub fn id(&self) -> i16 {
+                match self {
+                    $(ApiKeys::$variant => $id,)*
+                }
+            }
+            /// An english description of the api - used for debugging and metric names, this can change
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ApiKeys::$variant => $name,)*
+                }
+            }
+            pub fn for_id(id: i16) -> Result<ApiKeys> {
+                match id {
+                    $($id => Ok(ApiKeys::$variant),)*
+                    _ => Err(KafkaError::IllegalArgument(format!(
+                        "Unexpected api key: {}",
+                        id
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+api_keys! {
+    Produce = 0, "Produce";
+    Fetch = 1, "Fetch";
+    ListOffsets = 2, "ListOffsets";
+    Metadata = 3, "Metadata";
+    LeaderAndIsr = 4, "LeaderAndIsr";
+    StopReplica = 5, "StopReplica";
+    UpdateMetadata = 6, "UpdateMetadata";
+    ControlledShutdown = 7, "ControlledShutdown";
+    OffsetCommit = 8, "OffsetCommit";
+    OffsetFetch = 9, "OffsetFetch";
+    FindCoordinator = 10, "FindCoordinator";
+    JoinGroup = 11, "JoinGroup";
+    Heartbeat = 12, "Heartbeat";
+    LeaveGroup = 13, "LeaveGroup";
+    SyncGroup = 14, "SyncGroup";
+    DescribeGroups = 15, "DescribeGroups";
+    ListGroups = 16, "ListGroups";
+    SaslHandshake = 17, "SaslHandshake";
+    ApiVersions = 18, "ApiVersions";
+    CreateTopics = 19, "CreateTopics";
+    DeleteTopics = 20, "DeleteTopics";
+    DeleteRecords = 21, "DeleteRecords";
+    InitProducerId = 22, "InitProducerId";
+    OffsetForLeaderEpoch = 23, "OffsetForLeaderEpoch";
+    AddPartitionsToTxn = 24, "AddPartitionsToTxn";
+    AddOffsetsToTxn = 25, "AddOffsetsToTxn";
+    EndTxn = 26, "EndTxn";
+    WriteTxnMarkers = 27, "WriteTxnMarkers";
+    TxnOffsetCommit = 28, "TxnOffsetCommit";
+    DescribeAcls = 29, "DescribeAcls";
+    CreateAcls = 30, "CreateAcls";
+    DeleteAcls = 31, "DeleteAcls";
+    DescribeConfigs = 32, "DescribeConfigs";
+    AlterConfigs = 33, "AlterConfigs";
+    AlterReplicaLogDirs = 34, "AlterReplicaLogDirs";
+    DescribeLogDirs = 35, "DescribeLogDirs";
+    SaslAuthenticate = 36, "SaslAuthenticate";
+    CreatePartitions = 37, "CreatePartitions";
+    CreateDelegationToken = 38, "CreateDelegationToken";
+    RenewDelegationToken = 39, "RenewDelegationToken";
+    ExpireDelegationToken = 40, "ExpireDelegationToken";
+    DescribeDelegationToken = 41, "DescribeDelegationToken";
+    DeleteGroups = 42, "DeleteGroups";
+    ElectLeaders = 43, "ElectLeaders";
+    IncrementalAlterConfigs = 44, "IncrementalAlterConfigs";
+    AlterPartitionReassignments = 45, "AlterPartitionReassignments";
+    ListPartitionReassignments = 46, "ListPartitionReassignments";
+    OffsetDelete = 47, "OffsetDelete";
+    DescribeClientQuotas = 48, "DescribeClientQuotas";
+    AlterClientQuotas = 49, "AlterClientQuotas";
+    DescribeUserScramCredentials = 50, "DescribeUserScramCredentials";
+    AlterUserScramCredentials = 51, "AlterUserScramCredentials";
+    Vote = 52, "Vote";
+    BeginQuorumEpoch = 53, "BeginQuorumEpoch";
+    EndQuorumEpoch = 54, "EndQuorumEpoch";
+    DescribeQuorum = 55, "DescribeQuorum";
+    AlterIsr = 56, "AlterIsr";
+    UpdateFeatures = 57, "UpdateFeatures";
+    Envelope = 58, "Envelope";
+    FetchSnapshot = 59, "FetchSnapshot";
+    DescribeCluster = 60, "DescribeCluster";
+    DescribeProducers = 61, "DescribeProducers";
+    BrokerRegistration = 62, "BrokerRegistration";
+    BrokerHeartbeat = 63, "BrokerHeartbeat";
+    UnregisterBroker = 64, "UnregisterBroker";
+    DescribeTransactions = 65, "DescribeTransactions";
+    ListTransactions = 66, "ListTransactions";
+    AllocateProducerIds = 67, "AllocateProducerIds";
+}
+
+impl ApiKeys {
+    /// The range of versions this client can send, `None` for the apis it never sends. Most
+    /// ranges stop before the first flexible version, see `first_flexible_version`.
+    pub fn supported_versions(&self) -> Option<(i16, i16)> {
+        let versions = match self {
+            ApiKeys::Produce => (3, 8),
+            ApiKeys::Fetch => (4, 11),
+            ApiKeys::ListOffsets => (1, 5),
+            ApiKeys::Metadata => (4, 8),
+            ApiKeys::OffsetCommit => (2, 7),
+            ApiKeys::OffsetFetch => (1, 5),
+            ApiKeys::FindCoordinator => (0, 2),
+            ApiKeys::JoinGroup => (2, 5),
+            ApiKeys::Heartbeat => (0, 3),
+            ApiKeys::LeaveGroup => (0, 3),
+            ApiKeys::SyncGroup => (0, 3),
+            ApiKeys::DescribeGroups => (0, 4),
+            ApiKeys::ListGroups => (0, 2),
+            ApiKeys::ApiVersions => (0, 2),
+            ApiKeys::CreateTopics => (2, 4),
+            ApiKeys::DeleteTopics => (1, 3),
+            ApiKeys::InitProducerId => (0, 4),
+            ApiKeys::OffsetForLeaderEpoch => (2, 3),
+            ApiKeys::AddPartitionsToTxn => (0, 2),
+            ApiKeys::AddOffsetsToTxn => (0, 2),
+            ApiKeys::EndTxn => (0, 2),
+            ApiKeys::TxnOffsetCommit => (0, 2),
+            ApiKeys::DescribeAcls => (1, 1),
+            ApiKeys::CreateAcls => (1, 1),
+            ApiKeys::DeleteAcls => (1, 1),
+            ApiKeys::DescribeConfigs => (1, 3),
+            ApiKeys::CreatePartitions => (0, 1),
+            ApiKeys::DeleteGroups => (0, 1),
+            ApiKeys::IncrementalAlterConfigs => (0, 0),
+            ApiKeys::OffsetDelete => (0, 0),
+            _ => return None,
+        };
+        Some(versions)
+    }
+
+    /// The first version using compact types and tagged fields, for the apis this client sends
+    /// in flexible versions. InitProducerId needs version 3 to bump the epoch of a producer.
+    pub fn first_flexible_version(&self) -> Option<i16> {
+        match self {
+            ApiKeys::InitProducerId => Some(2),
+            _ => None,
+        }
+    }
+
+    /// Whether `version` of the api is flexible, its headers then end with tagged fields.
+    pub fn is_flexible(&self, version: i16) -> bool {
+        self.first_flexible_version()
+            .is_some_and(|flexible| version >= flexible)
+    }
+
+    pub fn oldest_version(&self) -> Option<i16> {
+        self.supported_versions().map(|(oldest, _)| oldest)
+    }
+
+    pub fn latest_version(&self) -> Option<i16> {
+        self.supported_versions().map(|(_, latest)| latest)
+    }
+}
+
+impl Display for ApiKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
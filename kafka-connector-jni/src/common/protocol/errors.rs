@@ -0,0 +1,406 @@
+use std::fmt::{self, Display};
+
+use log::warn;
+
+use crate::common::errors::KafkaError;
+
+macro_rules! errors {
+    ($($variant:ident = $code:literal, $name:literal, $message:expr, $builder:expr;)*) => {
+        /// This enum is used to describe error codes returned by the server and map them to the
+        /// corresponding `KafkaError` variant.
+        ///
+        /// It covers every error code of the protocol, codes unknown to this client (e.g. added
+        /// by newer brokers) map to `UnknownServerError`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Errors {
+            $($variant,)*
+        }
+
+        impl Errors {
+            /// The error code for the exception
+            pub fn code(&self) -> i16 {
+                match self {
+                    $(Errors::$variant => $code,)*
+                }
+            }
+
+            /// Name of the error as used by the Java client
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Errors::$variant => $name,)*
+                }
+            }
+
+            fn default_message(&self) -> Option<&'static str> {
+                match self {
+                    $(Errors::$variant => $message,)*
+                }
+            }
+
+            fn builder(&self) -> Option<fn(String) -> KafkaError> {
+                match self {
+                    $(Errors::$variant => $builder,)*
+                }
+            }
+
+            /// Get the error for the given code, unknown codes are mapped to `UnknownServerError`
+            pub fn for_code(code: i16) -> Errors {
+                match code {
+                    $($code => Errors::$variant,)*
+                    _ => {
+                        warn!("Unexpected error code: {}.", code);
+                        Errors::UnknownServerError
+                    }
+                }
+            }
+        }
+    };
+}
+
+errors! {
+    UnknownServerError = -1, "UNKNOWN_SERVER_ERROR",
+        Some("The server experienced an unexpected error when processing the request."),
+        Some(KafkaError::UnknownServer);
+    None = 0, "NONE", None, None;
+    OffsetOutOfRange = 1, "OFFSET_OUT_OF_RANGE",
+        Some("The requested offset is not within the range of offsets maintained by the server."),
+        Some(KafkaError::OffsetOutOfRange);
+    CorruptMessage = 2, "CORRUPT_MESSAGE",
+        Some("This message has failed its CRC checksum, exceeds the valid size, has a null key for a compacted topic, or is otherwise corrupt."),
+        Some(KafkaError::CorruptRecord);
+    UnknownTopicOrPartition = 3, "UNKNOWN_TOPIC_OR_PARTITION",
+        Some("This server does not host this topic-partition."),
+        Some(KafkaError::UnknownTopicOrPartition);
+    InvalidFetchSize = 4, "INVALID_FETCH_SIZE",
+        Some("The requested fetch size is invalid."),
+        Some(KafkaError::InvalidFetchSize);
+    LeaderNotAvailable = 5, "LEADER_NOT_AVAILABLE",
+        Some("There is no leader for this topic-partition as we are in the middle of a leadership election."),
+        Some(KafkaError::LeaderNotAvailable);
+    NotLeaderOrFollower = 6, "NOT_LEADER_OR_FOLLOWER",
+        Some("For requests intended only for the leader, this error indicates that the broker is not the current leader. For requests intended for any replica, this error indicates that the broker is not a replica of the topic partition."),
+        Some(KafkaError::NotLeaderOrFollower);
+    RequestTimedOut = 7, "REQUEST_TIMED_OUT",
+        Some("The request timed out."),
+        Some(KafkaError::Timeout);
+    BrokerNotAvailable = 8, "BROKER_NOT_AVAILABLE",
+        Some("The broker is not available."),
+        Some(KafkaError::BrokerNotAvailable);
+    ReplicaNotAvailable = 9, "REPLICA_NOT_AVAILABLE",
+        Some("The replica is not available for the requested topic-partition. Produce/Fetch requests and other requests intended only for the leader or follower return NOT_LEADER_OR_FOLLOWER if the broker is not a replica of the topic-partition."),
+        Some(KafkaError::ReplicaNotAvailable);
+    MessageTooLarge = 10, "MESSAGE_TOO_LARGE",
+        Some("The request included a message larger than the max message size the server will accept."),
+        Some(KafkaError::RecordTooLarge);
+    StaleControllerEpoch = 11, "STALE_CONTROLLER_EPOCH",
+        Some("The controller moved to another broker."),
+        Some(KafkaError::ControllerMoved);
+    OffsetMetadataTooLarge = 12, "OFFSET_METADATA_TOO_LARGE",
+        Some("The metadata field of the offset request was too large."),
+        Some(KafkaError::OffsetMetadataTooLarge);
+    NetworkException = 13, "NETWORK_EXCEPTION",
+        Some("The server disconnected before a response was received."),
+        Some(KafkaError::Network);
+    CoordinatorLoadInProgress = 14, "COORDINATOR_LOAD_IN_PROGRESS",
+        Some("The coordinator is loading and hence can't process requests."),
+        Some(KafkaError::CoordinatorLoadInProgress);
+    CoordinatorNotAvailable = 15, "COORDINATOR_NOT_AVAILABLE",
+        Some("The coordinator is not available."),
+        Some(KafkaError::CoordinatorNotAvailable);
+    NotCoordinator = 16, "NOT_COORDINATOR",
+        Some("This is not the correct coordinator."),
+        Some(KafkaError::NotCoordinator);
+    InvalidTopicException = 17, "INVALID_TOPIC_EXCEPTION",
+        Some("The request attempted to perform an operation on an invalid topic."),
+        Some(KafkaError::InvalidTopic);
+    RecordListTooLarge = 18, "RECORD_LIST_TOO_LARGE",
+        Some("The request included message batch larger than the configured segment size on the server."),
+        Some(KafkaError::RecordBatchTooLarge);
+    NotEnoughReplicas = 19, "NOT_ENOUGH_REPLICAS",
+        Some("Messages are rejected since there are fewer in-sync replicas than required."),
+        Some(KafkaError::NotEnoughReplicas);
+    NotEnoughReplicasAfterAppend = 20, "NOT_ENOUGH_REPLICAS_AFTER_APPEND",
+        Some("Messages are written to the log, but to fewer in-sync replicas than required."),
+        Some(KafkaError::NotEnoughReplicasAfterAppend);
+    InvalidRequiredAcks = 21, "INVALID_REQUIRED_ACKS",
+        Some("Produce request specified an invalid value for required acks."),
+        Some(KafkaError::InvalidRequiredAcks);
+    IllegalGeneration = 22, "ILLEGAL_GENERATION",
+        Some("Specified group generation id is not valid."),
+        Some(KafkaError::IllegalGeneration);
+    InconsistentGroupProtocol = 23, "INCONSISTENT_GROUP_PROTOCOL",
+        Some("The group member's supported protocols are incompatible with those of existing members or first group member tried to join with empty protocol type or empty protocol list."),
+        Some(KafkaError::InconsistentGroupProtocol);
+    InvalidGroupId = 24, "INVALID_GROUP_ID",
+        Some("The configured groupId is invalid."),
+        Some(KafkaError::InvalidGroupId);
+    UnknownMemberId = 25, "UNKNOWN_MEMBER_ID",
+        Some("The coordinator is not aware of this member."),
+        Some(KafkaError::UnknownMemberId);
+    InvalidSessionTimeout = 26, "INVALID_SESSION_TIMEOUT",
+        Some("The session timeout is not within the range allowed by the broker (as configured by group.min.session.timeout.ms and group.max.session.timeout.ms)."),
+        Some(KafkaError::InvalidSessionTimeout);
+    RebalanceInProgress = 27, "REBALANCE_IN_PROGRESS",
+        Some("The group is rebalancing, so a rejoin is needed."),
+        Some(KafkaError::RebalanceInProgress);
+    InvalidCommitOffsetSize = 28, "INVALID_COMMIT_OFFSET_SIZE",
+        Some("The committing offset data size is not valid."),
+        Some(KafkaError::InvalidCommitOffsetSize);
+    TopicAuthorizationFailed = 29, "TOPIC_AUTHORIZATION_FAILED",
+        Some("Topic authorization failed."),
+        Some(KafkaError::TopicAuthorization);
+    GroupAuthorizationFailed = 30, "GROUP_AUTHORIZATION_FAILED",
+        Some("Group authorization failed."),
+        Some(KafkaError::GroupAuthorization);
+    ClusterAuthorizationFailed = 31, "CLUSTER_AUTHORIZATION_FAILED",
+        Some("Cluster authorization failed."),
+        Some(KafkaError::ClusterAuthorization);
+    InvalidTimestamp = 32, "INVALID_TIMESTAMP",
+        Some("The timestamp of the message is out of acceptable range."),
+        Some(KafkaError::InvalidTimestamp);
+    UnsupportedSaslMechanism = 33, "UNSUPPORTED_SASL_MECHANISM",
+        Some("The broker does not support the requested SASL mechanism."),
+        Some(KafkaError::UnsupportedSaslMechanism);
+    IllegalSaslState = 34, "ILLEGAL_SASL_STATE",
+        Some("Request is not valid given the current SASL state."),
+        Some(KafkaError::IllegalSaslState);
+    UnsupportedVersion = 35, "UNSUPPORTED_VERSION",
+        Some("The version of API is not supported."),
+        Some(KafkaError::UnsupportedVersion);
+    TopicAlreadyExists = 36, "TOPIC_ALREADY_EXISTS",
+        Some("Topic with this name already exists."),
+        Some(KafkaError::TopicExists);
+    InvalidPartitions = 37, "INVALID_PARTITIONS",
+        Some("Number of partitions is below 1."),
+        Some(KafkaError::InvalidPartitions);
+    InvalidReplicationFactor = 38, "INVALID_REPLICATION_FACTOR",
+        Some("Replication factor is below 1 or larger than the number of available brokers."),
+        Some(KafkaError::InvalidReplicationFactor);
+    InvalidReplicaAssignment = 39, "INVALID_REPLICA_ASSIGNMENT",
+        Some("Replica assignment is invalid."),
+        Some(KafkaError::InvalidReplicaAssignment);
+    InvalidConfig = 40, "INVALID_CONFIG",
+        Some("Configuration is invalid."),
+        Some(KafkaError::InvalidConfiguration);
+    NotController = 41, "NOT_CONTROLLER",
+        Some("This is not the correct controller for this cluster."),
+        Some(KafkaError::NotController);
+    InvalidRequest = 42, "INVALID_REQUEST",
+        Some("This most likely occurs because of a request being malformed by the client library or the message was sent to an incompatible broker. See the broker logs for more details."),
+        Some(KafkaError::InvalidRequest);
+    UnsupportedForMessageFormat = 43, "UNSUPPORTED_FOR_MESSAGE_FORMAT",
+        Some("The message format version on the broker does not support the request."),
+        Some(KafkaError::UnsupportedForMessageFormat);
+    PolicyViolation = 44, "POLICY_VIOLATION",
+        Some("Request parameters do not satisfy the configured policy."),
+        Some(KafkaError::PolicyViolation);
+    OutOfOrderSequenceNumber = 45, "OUT_OF_ORDER_SEQUENCE_NUMBER",
+        Some("The broker received an out of order sequence number."),
+        Some(KafkaError::OutOfOrderSequence);
+    DuplicateSequenceNumber = 46, "DUPLICATE_SEQUENCE_NUMBER",
+        Some("The broker received a duplicate sequence number."),
+        Some(KafkaError::DuplicateSequence);
+    InvalidProducerEpoch = 47, "INVALID_PRODUCER_EPOCH",
+        Some("Producer attempted to produce with an old epoch."),
+        Some(KafkaError::InvalidProducerEpoch);
+    InvalidTxnState = 48, "INVALID_TXN_STATE",
+        Some("The producer attempted a transactional operation in an invalid state."),
+        Some(KafkaError::InvalidTxnState);
+    InvalidProducerIdMapping = 49, "INVALID_PRODUCER_ID_MAPPING",
+        Some("The producer attempted to use a producer id which is not currently assigned to its transactional id."),
+        Some(KafkaError::InvalidPidMapping);
+    InvalidTransactionTimeout = 50, "INVALID_TRANSACTION_TIMEOUT",
+        Some("The transaction timeout is larger than the maximum value allowed by the broker (as configured by transaction.max.timeout.ms)."),
+        Some(KafkaError::InvalidTxnTimeout);
+    ConcurrentTransactions = 51, "CONCURRENT_TRANSACTIONS",
+        Some("The producer attempted to update a transaction while another concurrent operation on the same transaction was ongoing."),
+        Some(KafkaError::ConcurrentTransactions);
+    TransactionCoordinatorFenced = 52, "TRANSACTION_COORDINATOR_FENCED",
+        Some("Indicates that the transaction coordinator sending a WriteTxnMarker is no longer the current coordinator for a given producer."),
+        Some(KafkaError::TransactionCoordinatorFenced);
+    TransactionalIdAuthorizationFailed = 53, "TRANSACTIONAL_ID_AUTHORIZATION_FAILED",
+        Some("Transactional Id authorization failed."),
+        Some(KafkaError::TransactionalIdAuthorization);
+    SecurityDisabled = 54, "SECURITY_DISABLED",
+        Some("Security features are disabled."),
+        Some(KafkaError::SecurityDisabled);
+    OperationNotAttempted = 55, "OPERATION_NOT_ATTEMPTED",
+        Some("The broker did not attempt to execute this operation. This may happen for batched RPCs where some operations in the batch failed, causing the broker to respond without trying the rest."),
+        Some(KafkaError::OperationNotAttempted);
+    KafkaStorageError = 56, "KAFKA_STORAGE_ERROR",
+        Some("Disk error when trying to access log file on the disk."),
+        Some(KafkaError::KafkaStorage);
+    LogDirNotFound = 57, "LOG_DIR_NOT_FOUND",
+        Some("The user-specified log directory is not found in the broker config."),
+        Some(KafkaError::LogDirNotFound);
+    SaslAuthenticationFailed = 58, "SASL_AUTHENTICATION_FAILED",
+        Some("SASL Authentication failed."),
+        Some(KafkaError::SaslAuthentication);
+    UnknownProducerId = 59, "UNKNOWN_PRODUCER_ID",
+        Some("This exception is raised by the broker if it could not locate the producer metadata associated with the producerId in question. This could happen if, for instance, the producer's records were deleted because their retention time had elapsed. Once the last records of the producerId are removed, the producer's metadata is removed from the broker, and future appends by the producer will return this exception."),
+        Some(KafkaError::UnknownProducerId);
+    ReassignmentInProgress = 60, "REASSIGNMENT_IN_PROGRESS",
+        Some("A partition reassignment is in progress."),
+        Some(KafkaError::ReassignmentInProgress);
+    DelegationTokenAuthDisabled = 61, "DELEGATION_TOKEN_AUTH_DISABLED",
+        Some("Delegation Token feature is not enabled."),
+        Some(KafkaError::DelegationTokenDisabled);
+    DelegationTokenNotFound = 62, "DELEGATION_TOKEN_NOT_FOUND",
+        Some("Delegation Token is not found on server."),
+        Some(KafkaError::DelegationTokenNotFound);
+    DelegationTokenOwnerMismatch = 63, "DELEGATION_TOKEN_OWNER_MISMATCH",
+        Some("Specified Principal is not valid Owner/Renewer."),
+        Some(KafkaError::DelegationTokenOwnerMismatch);
+    DelegationTokenRequestNotAllowed = 64, "DELEGATION_TOKEN_REQUEST_NOT_ALLOWED",
+        Some("Delegation Token requests are not allowed on PLAINTEXT/1-way SSL channels and on delegation token authenticated channels."),
+        Some(KafkaError::UnsupportedByAuthentication);
+    DelegationTokenAuthorizationFailed = 65, "DELEGATION_TOKEN_AUTHORIZATION_FAILED",
+        Some("Delegation Token authorization failed."),
+        Some(KafkaError::DelegationTokenAuthorization);
+    DelegationTokenExpired = 66, "DELEGATION_TOKEN_EXPIRED",
+        Some("Delegation Token is expired."),
+        Some(KafkaError::DelegationTokenExpired);
+    InvalidPrincipalType = 67, "INVALID_PRINCIPAL_TYPE",
+        Some("Supplied principalType is not supported."),
+        Some(KafkaError::InvalidPrincipalType);
+    NonEmptyGroup = 68, "NON_EMPTY_GROUP",
+        Some("The group is not empty."),
+        Some(KafkaError::GroupNotEmpty);
+    GroupIdNotFound = 69, "GROUP_ID_NOT_FOUND",
+        Some("The group id does not exist."),
+        Some(KafkaError::GroupIdNotFound);
+    FetchSessionIdNotFound = 70, "FETCH_SESSION_ID_NOT_FOUND",
+        Some("The fetch session ID was not found."),
+        Some(KafkaError::FetchSessionIdNotFound);
+    InvalidFetchSessionEpoch = 71, "INVALID_FETCH_SESSION_EPOCH",
+        Some("The fetch session epoch is invalid."),
+        Some(KafkaError::InvalidFetchSessionEpoch);
+    ListenerNotFound = 72, "LISTENER_NOT_FOUND",
+        Some("There is no listener on the leader broker that matches the listener on which metadata request was processed."),
+        Some(KafkaError::ListenerNotFound);
+    TopicDeletionDisabled = 73, "TOPIC_DELETION_DISABLED",
+        Some("Topic deletion is disabled."),
+        Some(KafkaError::TopicDeletionDisabled);
+    FencedLeaderEpoch = 74, "FENCED_LEADER_EPOCH",
+        Some("The leader epoch in the request is older than the epoch on the broker."),
+        Some(KafkaError::FencedLeaderEpoch);
+    UnknownLeaderEpoch = 75, "UNKNOWN_LEADER_EPOCH",
+        Some("The leader epoch in the request is newer than the epoch on the broker."),
+        Some(KafkaError::UnknownLeaderEpoch);
+    UnsupportedCompressionType = 76, "UNSUPPORTED_COMPRESSION_TYPE",
+        Some("The requesting client does not support the compression type of given partition."),
+        Some(KafkaError::UnsupportedCompressionType);
+    StaleBrokerEpoch = 77, "STALE_BROKER_EPOCH",
+        Some("Broker epoch has changed."),
+        Some(KafkaError::StaleBrokerEpoch);
+    OffsetNotAvailable = 78, "OFFSET_NOT_AVAILABLE",
+        Some("The leader high watermark has not caught up from a recent leader election so the offsets cannot be guaranteed to be monotonically increasing."),
+        Some(KafkaError::OffsetNotAvailable);
+    MemberIdRequired = 79, "MEMBER_ID_REQUIRED",
+        Some("The group member needs to have a valid member id before actually entering a consumer group."),
+        Some(KafkaError::MemberIdRequired);
+    PreferredLeaderNotAvailable = 80, "PREFERRED_LEADER_NOT_AVAILABLE",
+        Some("The preferred leader was not available."),
+        Some(KafkaError::PreferredLeaderNotAvailable);
+    GroupMaxSizeReached = 81, "GROUP_MAX_SIZE_REACHED",
+        Some("The consumer group has reached its max size."),
+        Some(KafkaError::GroupMaxSizeReached);
+    FencedInstanceId = 82, "FENCED_INSTANCE_ID",
+        Some("The broker rejected this static consumer since another consumer with the same group.instance.id has registered with a different member.id."),
+        Some(KafkaError::FencedInstanceId);
+    EligibleLeadersNotAvailable = 83, "ELIGIBLE_LEADERS_NOT_AVAILABLE",
+        Some("Eligible topic partition leaders are not available."),
+        Some(KafkaError::EligibleLeadersNotAvailable);
+    ElectionNotNeeded = 84, "ELECTION_NOT_NEEDED",
+        Some("Leader election not needed for topic partition."),
+        Some(KafkaError::ElectionNotNeeded);
+    NoReassignmentInProgress = 85, "NO_REASSIGNMENT_IN_PROGRESS",
+        Some("No partition reassignment is in progress."),
+        Some(KafkaError::NoReassignmentInProgress);
+    GroupSubscribedToTopic = 86, "GROUP_SUBSCRIBED_TO_TOPIC",
+        Some("Deleting offsets of a topic is forbidden while the consumer group is actively subscribed to it."),
+        Some(KafkaError::GroupSubscribedToTopic);
+    InvalidRecord = 87, "INVALID_RECORD",
+        Some("This record has failed the validation on broker and hence will be rejected."),
+        Some(KafkaError::InvalidRecord);
+    UnstableOffsetCommit = 88, "UNSTABLE_OFFSET_COMMIT",
+        Some("There are unstable offsets that need to be cleared."),
+        Some(KafkaError::UnstableOffsetCommit);
+    ThrottlingQuotaExceeded = 89, "THROTTLING_QUOTA_EXCEEDED",
+        Some("The throttling quota has been exceeded."),
+        Some(KafkaError::ThrottlingQuotaExceeded);
+    ProducerFenced = 90, "PRODUCER_FENCED",
+        Some("There is a newer producer with the same transactionalId which fences the current one."),
+        Some(KafkaError::ProducerFenced);
+    ResourceNotFound = 91, "RESOURCE_NOT_FOUND",
+        Some("A request illegally referred to a resource that does not exist."),
+        Some(KafkaError::ResourceNotFound);
+    DuplicateResource = 92, "DUPLICATE_RESOURCE",
+        Some("A request illegally referred to the same resource twice."),
+        Some(KafkaError::DuplicateResource);
+    UnacceptableCredential = 93, "UNACCEPTABLE_CREDENTIAL",
+        Some("Requested credential would not meet criteria for acceptability."),
+        Some(KafkaError::UnacceptableCredential);
+    InconsistentVoterSet = 94, "INCONSISTENT_VOTER_SET",
+        Some("Indicates that the either the sender or recipient of a voter-only request is not one of the expected voters"),
+        Some(KafkaError::InconsistentVoterSet);
+    InvalidUpdateVersion = 95, "INVALID_UPDATE_VERSION",
+        Some("The given update version was invalid."),
+        Some(KafkaError::InvalidUpdateVersion);
+    FeatureUpdateFailed = 96, "FEATURE_UPDATE_FAILED",
+        Some("Unable to update finalized features due to an unexpected server error."),
+        Some(KafkaError::FeatureUpdateFailed);
+    PrincipalDeserializationFailure = 97, "PRINCIPAL_DESERIALIZATION_FAILURE",
+        Some("Request principal deserialization failed during forwarding. This indicates an internal error on the broker cluster security setup."),
+        Some(KafkaError::PrincipalDeserialization);
+    SnapshotNotFound = 98, "SNAPSHOT_NOT_FOUND",
+        Some("Requested snapshot was not found"),
+        Some(KafkaError::SnapshotNotFound);
+    PositionOutOfRange = 99, "POSITION_OUT_OF_RANGE",
+        Some("Requested position is not greater than or equal to zero, and less than the size of the snapshot."),
+        Some(KafkaError::PositionOutOfRange);
+    UnknownTopicId = 100, "UNKNOWN_TOPIC_ID",
+        Some("This server does not host this topic ID."),
+        Some(KafkaError::UnknownTopicId);
+    DuplicateBrokerRegistration = 101, "DUPLICATE_BROKER_REGISTRATION",
+        Some("This broker ID is already in use."),
+        Some(KafkaError::DuplicateBrokerRegistration);
+    BrokerIdNotRegistered = 102, "BROKER_ID_NOT_REGISTERED",
+        Some("The given broker ID was not registered."),
+        Some(KafkaError::BrokerIdNotRegistered);
+    InconsistentTopicId = 103, "INCONSISTENT_TOPIC_ID",
+        Some("The log's topic ID did not match the topic ID in the request"),
+        Some(KafkaError::InconsistentTopicId);
+    InconsistentClusterId = 104, "INCONSISTENT_CLUSTER_ID",
+        Some("The clusterId in the request does not match that found on the server"),
+        Some(KafkaError::InconsistentClusterId);
+    TransactionalIdNotFound = 105, "TRANSACTIONAL_ID_NOT_FOUND",
+        Some("The transactionalId could not be found"),
+        Some(KafkaError::TransactionalIdNotFound);
+}
+
+impl Errors {
+    /// Create an instance of the error that contains the given message, or the default message
+    /// if `None` is passed. Returns `None` for `Errors::None`.
+    pub fn exception(&self, message: Option<&str>) -> Option<KafkaError> {
+        let builder = self.builder()?;
+        match message {
+            Some(message) => Some(builder(message.to_owned())),
+            // If no error message was specified, return an exception with the default error message.
+            None => Some(builder(
+                self.default_message().unwrap_or_default().to_owned(),
+            )),
+        }
+    }
+
+    /// Get a friendly description of the error (if one is available).
+    pub fn message(&self) -> &'static str {
+        self.default_message().unwrap_or_else(|| self.name())
+    }
+}
+
+impl Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
@@ -0,0 +1,3 @@
+pub mod api_keys;
+pub mod errors;
+pub mod types;
@@ -0,0 +1,413 @@
+//! Readers and writers of the primitive types of the Kafka protocol. Readers fail with a schema
+//! error instead of panicking when the buffer is too short, the broker may be speaking another
+//! version than the one we expect.
+//!
+//! Only the few flexible versions used by the clients need the compact types and tagged fields.
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::errors::Errors,
+    topic_partition::TopicPartition,
+};
+
+fn schema_error(type_name: &str, message: impl std::fmt::Display) -> KafkaError {
+    KafkaError::Kafka(format!("Error reading {}: {}", type_name, message))
+}
+
+fn ensure_remaining(buffer: &Bytes, size: usize, type_name: &str) -> Result<()> {
+    if buffer.remaining() < size {
+        return Err(schema_error(
+            type_name,
+            format!(
+                "{} bytes needed, only {} bytes available",
+                size,
+                buffer.remaining()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub fn read_i8(buffer: &mut Bytes) -> Result<i8> {
+    ensure_remaining(buffer, 1, "INT8")?;
+    Ok(buffer.get_i8())
+}
+
+pub fn read_i16(buffer: &mut Bytes) -> Result<i16> {
+    ensure_remaining(buffer, 2, "INT16")?;
+    Ok(buffer.get_i16())
+}
+
+pub fn read_i32(buffer: &mut Bytes) -> Result<i32> {
+    ensure_remaining(buffer, 4, "INT32")?;
+    Ok(buffer.get_i32())
+}
+
+pub fn read_i64(buffer: &mut Bytes) -> Result<i64> {
+    ensure_remaining(buffer, 8, "INT64")?;
+    Ok(buffer.get_i64())
+}
+
+pub fn read_bool(buffer: &mut Bytes) -> Result<bool> {
+    Ok(read_i8(buffer)? != 0)
+}
+
+/// Error code, codes unknown to this client map to `UnknownServerError`.
+pub fn read_error(buffer: &mut Bytes) -> Result<Errors> {
+    Ok(Errors::for_code(read_i16(buffer)?))
+}
+
+pub fn read_nullable_string(buffer: &mut Bytes) -> Result<Option<String>> {
+    let length = read_i16(buffer)?;
+    if length < 0 {
+        return Ok(None);
+    }
+    ensure_remaining(buffer, length as usize, "STRING")?;
+    let bytes = buffer.split_to(length as usize);
+    String::from_utf8(bytes.to_vec())
+        .map(Some)
+        .map_err(|e| schema_error("STRING", e))
+}
+
+pub fn read_string(buffer: &mut Bytes) -> Result<String> {
+    read_nullable_string(buffer)?
+        .ok_or_else(|| schema_error("STRING", "null value for non-nullable field"))
+}
+
+pub fn read_nullable_bytes(buffer: &mut Bytes) -> Result<Option<Bytes>> {
+    let length = read_i32(buffer)?;
+    if length < 0 {
+        return Ok(None);
+    }
+    ensure_remaining(buffer, length as usize, "BYTES")?;
+    Ok(Some(buffer.split_to(length as usize)))
+}
+
+pub fn read_bytes(buffer: &mut Bytes) -> Result<Bytes> {
+    read_nullable_bytes(buffer)?
+        .ok_or_else(|| schema_error("BYTES", "null value for non-nullable field"))
+}
+
+/// Read an array, each element with `read`.
+pub fn read_nullable_array<T, F>(buffer: &mut Bytes, mut read: F) -> Result<Option<Vec<T>>>
+where
+    F: FnMut(&mut Bytes) -> Result<T>,
+{
+    let size = read_i32(buffer)?;
+    if size < 0 {
+        return Ok(None);
+    }
+    // every element takes at least a byte, don't trust the size for the allocation
+    if size as usize > buffer.remaining() {
+        return Err(schema_error(
+            "ARRAY",
+            format!(
+                "array of size {} cannot be read from {} bytes",
+                size,
+                buffer.remaining()
+            ),
+        ));
+    }
+    let mut items = Vec::with_capacity(size as usize);
+    for _ in 0..size {
+        items.push(read(buffer)?);
+    }
+    Ok(Some(items))
+}
+
+pub fn read_array<T, F>(buffer: &mut Bytes, read: F) -> Result<Vec<T>>
+where
+    F: FnMut(&mut Bytes) -> Result<T>,
+{
+    read_nullable_array(buffer, read)?
+        .ok_or_else(|| schema_error("ARRAY", "null value for non-nullable field"))
+}
+
+pub fn read_i32_array(buffer: &mut Bytes) -> Result<Vec<i32>> {
+    read_array(buffer, read_i32)
+}
+
+/// Read topics with their partitions, flattening them with `read_partition`.
+pub fn read_topic_partitions<T, F>(
+    buffer: &mut Bytes,
+    mut read_partition: F,
+) -> Result<IndexMap<TopicPartition, T>>
+where
+    F: FnMut(&mut Bytes) -> Result<(i32, T)>,
+{
+    let mut result = IndexMap::new();
+    for (topic, partitions) in read_array(buffer, |buffer| {
+        let topic = read_string(buffer)?;
+        Ok((topic, read_array(buffer, &mut read_partition)?))
+    })? {
+        for (partition, value) in partitions {
+            result.insert(TopicPartition::new(topic.clone(), partition), value);
+        }
+    }
+    Ok(result)
+}
+
+/// Read the error code of each partition, grouped by topic.
+pub fn read_partition_errors(buffer: &mut Bytes) -> Result<IndexMap<TopicPartition, Errors>> {
+    read_topic_partitions(buffer, |buffer| {
+        Ok((read_i32(buffer)?, read_error(buffer)?))
+    })
+}
+
+pub fn read_unsigned_varint(buffer: &mut Bytes) -> Result<u32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let byte = read_i8(buffer)? as u8;
+        value |= ((byte & 0x7f) as u32) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(schema_error("UNSIGNED_VARINT", "varint is too long"))
+}
+
+/// Skip the tagged fields ending each structure of a flexible version, none of them is used.
+pub fn skip_tagged_fields(buffer: &mut Bytes) -> Result<()> {
+    let count = read_unsigned_varint(buffer)?;
+    for _ in 0..count {
+        read_unsigned_varint(buffer)?;
+        let size = read_unsigned_varint(buffer)? as usize;
+        ensure_remaining(buffer, size, "TAGGED_FIELDS")?;
+        buffer.advance(size);
+    }
+    Ok(())
+}
+
+pub fn write_bool(buffer: &mut BytesMut, value: bool) {
+    buffer.put_i8(value as i8);
+}
+
+pub fn write_string(buffer: &mut BytesMut, value: &str) {
+    buffer.put_i16(value.len() as i16);
+    buffer.put_slice(value.as_bytes());
+}
+
+pub fn write_nullable_string(buffer: &mut BytesMut, value: Option<&str>) {
+    match value {
+        Some(value) => write_string(buffer, value),
+        None => buffer.put_i16(-1),
+    }
+}
+
+pub fn write_unsigned_varint(buffer: &mut BytesMut, mut value: u32) {
+    while value >= 0x80 {
+        buffer.put_u8((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.put_u8(value as u8);
+}
+
+/// Compact strings are prefixed with their length plus one, zero being null.
+pub fn write_compact_nullable_string(buffer: &mut BytesMut, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            write_unsigned_varint(buffer, value.len() as u32 + 1);
+            buffer.put_slice(value.as_bytes());
+        }
+        None => write_unsigned_varint(buffer, 0),
+    }
+}
+
+/// Write an empty set of tagged fields.
+pub fn write_empty_tagged_fields(buffer: &mut BytesMut) {
+    write_unsigned_varint(buffer, 0);
+}
+
+pub fn write_bytes(buffer: &mut BytesMut, value: &[u8]) {
+    buffer.put_i32(value.len() as i32);
+    buffer.put_slice(value);
+}
+
+pub fn write_nullable_bytes(buffer: &mut BytesMut, value: Option<&[u8]>) {
+    match value {
+        Some(value) => write_bytes(buffer, value),
+        None => buffer.put_i32(-1),
+    }
+}
+
+/// Write the elements of `items`, each with `write`.
+pub fn write_array<I, F>(buffer: &mut BytesMut, items: I, mut write: F)
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    F: FnMut(&mut BytesMut, I::Item),
+{
+    let items = items.into_iter();
+    buffer.put_i32(items.len() as i32);
+    for item in items {
+        write(buffer, item);
+    }
+}
+
+pub fn write_nullable_array<I, F>(buffer: &mut BytesMut, items: Option<I>, write: F)
+where
+    I: IntoIterator,
+    I::IntoIter: ExactSizeIterator,
+    F: FnMut(&mut BytesMut, I::Item),
+{
+    match items {
+        Some(items) => write_array(buffer, items, write),
+        None => buffer.put_i32(-1),
+    }
+}
+
+pub fn write_i32_array(buffer: &mut BytesMut, items: &[i32]) {
+    write_array(buffer, items, |buffer, item| buffer.put_i32(*item));
+}
+
+/// Group partitions by topic, in order of the first appearance of each topic.
+pub fn group_by_topic<'a, T: 'a>(
+    partitions: impl IntoIterator<Item = (&'a TopicPartition, T)>,
+) -> IndexMap<&'a str, Vec<(i32, T)>> {
+    let mut by_topic: IndexMap<&str, Vec<(i32, T)>> = IndexMap::new();
+    for (tp, value) in partitions {
+        by_topic
+            .entry(&tp.topic)
+            .or_default()
+            .push((tp.partition, value));
+    }
+    by_topic
+}
+
+/// Write partitions grouped by topic, the fields of each partition with `write_partition`.
+pub fn write_topic_partitions<'a, T: 'a, F>(
+    buffer: &mut BytesMut,
+    partitions: impl IntoIterator<Item = (&'a TopicPartition, T)>,
+    mut write_partition: F,
+) where
+    F: FnMut(&mut BytesMut, i32, T),
+{
+    write_array(
+        buffer,
+        group_by_topic(partitions),
+        |buffer, (topic, partitions)| {
+            write_string(buffer, topic);
+            write_array(buffer, partitions, |buffer, (partition, value)| {
+                write_partition(buffer, partition, value)
+            });
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+    use super::{
+        read_array, read_i32, read_nullable_bytes, read_nullable_string, read_string,
+        read_topic_partitions, read_unsigned_varint, skip_tagged_fields, write_array,
+        write_compact_nullable_string, write_empty_tagged_fields, write_nullable_bytes,
+        write_nullable_string, write_string, write_topic_partitions, write_unsigned_varint,
+    };
+    use crate::common::topic_partition::TopicPartition;
+
+    #[test]
+    fn strings_and_bytes_are_size_prefixed() {
+        let mut buffer = BytesMut::new();
+        write_string(&mut buffer, "abc");
+        write_nullable_string(&mut buffer, None);
+        write_nullable_bytes(&mut buffer, Some(&[1, 2]));
+        write_nullable_bytes(&mut buffer, None);
+        assert_eq!(
+            &buffer[..],
+            &[0, 3, b'a', b'b', b'c', 0xff, 0xff, 0, 0, 0, 2, 1, 2, 0xff, 0xff, 0xff, 0xff]
+        );
+
+        let mut buffer = buffer.freeze();
+        assert_eq!(read_string(&mut buffer).unwrap(), "abc");
+        assert_eq!(read_nullable_string(&mut buffer).unwrap(), None);
+        assert_eq!(
+            read_nullable_bytes(&mut buffer).unwrap(),
+            Some(Bytes::from_static(&[1, 2]))
+        );
+        assert_eq!(read_nullable_bytes(&mut buffer).unwrap(), None);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn short_buffers_are_schema_errors() {
+        let mut buffer = Bytes::from_static(&[0, 5, b'a']);
+        assert_eq!(
+            read_string(&mut buffer).unwrap_err().to_string(),
+            "Error reading STRING: 5 bytes needed, only 1 bytes available"
+        );
+        let mut buffer = Bytes::from_static(&[0xff, 0xff]);
+        assert!(read_string(&mut buffer).is_err());
+        let mut buffer = Bytes::from_static(&[0x7f, 0xff, 0xff, 0xff, 0]);
+        assert!(read_array(&mut buffer, read_i32).is_err());
+    }
+
+    #[test]
+    fn varints_and_tagged_fields() {
+        let mut buffer = BytesMut::new();
+        write_unsigned_varint(&mut buffer, 300);
+        write_compact_nullable_string(&mut buffer, Some("ab"));
+        write_compact_nullable_string(&mut buffer, None);
+        // one tagged field of tag 5 and 2 bytes, then an empty set
+        buffer.put_slice(&[1, 5, 2, 0xaa, 0xbb]);
+        write_empty_tagged_fields(&mut buffer);
+        assert_eq!(
+            &buffer[..],
+            &[0xac, 0x02, 3, b'a', b'b', 0, 1, 5, 2, 0xaa, 0xbb, 0]
+        );
+
+        let mut buffer = buffer.freeze();
+        assert_eq!(read_unsigned_varint(&mut buffer).unwrap(), 300);
+        buffer.advance(4);
+        skip_tagged_fields(&mut buffer).unwrap();
+        skip_tagged_fields(&mut buffer).unwrap();
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn topic_partitions_are_grouped_by_topic() {
+        let partitions = [
+            (TopicPartition::new("a", 0), 10),
+            (TopicPartition::new("b", 0), 20),
+            (TopicPartition::new("a", 1), 11),
+        ];
+        let mut buffer = BytesMut::new();
+        write_topic_partitions(
+            &mut buffer,
+            partitions.iter().map(|(tp, value)| (tp, *value)),
+            |buffer, partition, value| {
+                buffer.put_i32(partition);
+                buffer.put_i32(value);
+            },
+        );
+        let mut expected = BytesMut::new();
+        write_array(
+            &mut expected,
+            [("a", [(0, 10), (1, 11)].as_slice()), ("b", &[(0, 20)])],
+            |buffer, (topic, partitions)| {
+                write_string(buffer, topic);
+                write_array(buffer, partitions, |buffer, (partition, value)| {
+                    buffer.put_i32(*partition);
+                    buffer.put_i32(*value);
+                });
+            },
+        );
+        assert_eq!(buffer, expected);
+
+        let read = read_topic_partitions(&mut buffer.freeze(), |buffer| {
+            Ok((read_i32(buffer)?, read_i32(buffer)?))
+        })
+        .unwrap();
+        assert_eq!(
+            read.into_iter().collect::<Vec<_>>(),
+            vec![
+                (TopicPartition::new("a", 0), 10),
+                (TopicPartition::new("a", 1), 11),
+                (TopicPartition::new("b", 0), 20),
+            ]
+        );
+    }
+}
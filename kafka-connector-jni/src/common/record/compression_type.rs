@@ -0,0 +1,148 @@
+use std::io::{Read, Write};
+
+use bytes::{BufMut, Bytes, BytesMut};
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use kafka_connector_macros::JavaEnum;
+use ruzstd::{decoding::StreamingDecoder, encoding::CompressionLevel};
+
+use crate::common::{
+    compress::{kafka_lz4, snappy},
+    errors::{KafkaError, Result},
+};
+
+use super::record_batch::MAGIC_VALUE_V0;
+
+/// The compression type to use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, JavaEnum)]
+#[java_class = "org/apache/kafka/common/record/CompressionType"]
+#[java_id_type = "i16"]
+pub enum CompressionType {
+    #[java_variant = "NONE"]
+    #[java_id = 0]
+    #[java_name = "none"]
+    None,
+    #[java_variant = "GZIP"]
+    #[java_id = 1]
+    #[java_name = "gzip"]
+    Gzip,
+    #[java_variant = "SNAPPY"]
+    #[java_id = 2]
+    #[java_name = "snappy"]
+    Snappy,
+    #[java_variant = "LZ4"]
+    #[java_id = 3]
+    #[java_name = "lz4"]
+    Lz4,
+    #[java_variant = "ZSTD"]
+    #[java_id = 4]
+    #[java_name = "zstd"]
+    Zstd,
+}
+
+impl CompressionType {
+    pub fn for_id(id: i16) -> Result<CompressionType> {
+        CompressionType::from_id(id).ok_or_else(|| {
+            KafkaError::IllegalArgument(format!("Unknown compression type id: {}", id))
+        })
+    }
+    pub fn for_name(name: &str) -> Result<CompressionType> {
+        name.parse()
+            .map_err(|_| KafkaError::IllegalArgument(format!("Unknown compression name: {}", name)))
+    }
+}
+
+impl CompressionType {
+    /// Compress `data` and append it to `out`, in the same stream format the java client writes
+    /// for this compression type.
+    ///
+    /// * `magic` - The magic of the batch, LZ4 frames of magic v0 have a broken header checksum
+    pub fn compress(&self, data: &[u8], magic: i8, out: &mut BytesMut) -> Result<()> {
+        match self {
+            CompressionType::None => out.put_slice(data),
+            CompressionType::Gzip => {
+                let mut encoder = GzEncoder::new(out.writer(), Compression::default());
+                encoder.write_all(data).map_err(compression_error)?;
+                encoder.finish().map_err(compression_error)?;
+            }
+            CompressionType::Snappy => snappy::compress(data, out),
+            CompressionType::Lz4 => kafka_lz4::compress(data, magic == MAGIC_VALUE_V0, out),
+            // the java client uses zstd level 3, the fastest level is the one implemented in rust
+            CompressionType::Zstd => {
+                ruzstd::encoding::compress(data, out.writer(), CompressionLevel::Fastest)
+            }
+        }
+        Ok(())
+    }
+
+    /// Decompress `data` written with this compression type.
+    ///
+    /// * `magic` - The magic of the batch, LZ4 frames of magic v0 have a broken header checksum
+    pub fn decompress(&self, data: &[u8], magic: i8) -> Result<Bytes> {
+        let decompressed = match self {
+            CompressionType::None => return Ok(Bytes::copy_from_slice(data)),
+            CompressionType::Gzip => {
+                let mut decompressed = Vec::new();
+                MultiGzDecoder::new(data)
+                    .read_to_end(&mut decompressed)
+                    .map_err(compression_error)?;
+                decompressed
+            }
+            CompressionType::Snappy => snappy::decompress(data)?,
+            CompressionType::Lz4 => kafka_lz4::decompress(data, magic == MAGIC_VALUE_V0)?,
+            CompressionType::Zstd => {
+                let mut input = data;
+                let mut decompressed = Vec::new();
+                // a stream may consist of several frames
+                while !input.is_empty() {
+                    StreamingDecoder::new(&mut input)
+                        .map_err(|e| KafkaError::Kafka(e.to_string()))?
+                        .read_to_end(&mut decompressed)
+                        .map_err(compression_error)?;
+                }
+                decompressed
+            }
+        };
+        Ok(decompressed.into())
+    }
+}
+
+fn compression_error(error: std::io::Error) -> KafkaError {
+    KafkaError::Kafka(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::common::record::record_batch::MAGIC_VALUE_V2;
+
+    use super::CompressionType;
+
+    #[test]
+    fn every_compression_type_round_trips() {
+        let data: Vec<u8> = (0..10_000u32)
+            .flat_map(|i| format!("key-{} value-{} ", i % 10, i).into_bytes())
+            .collect();
+        for compression_type in [
+            CompressionType::None,
+            CompressionType::Gzip,
+            CompressionType::Snappy,
+            CompressionType::Lz4,
+            CompressionType::Zstd,
+        ] {
+            let mut compressed = BytesMut::new();
+            compression_type
+                .compress(&data, MAGIC_VALUE_V2, &mut compressed)
+                .unwrap();
+            if compression_type != CompressionType::None {
+                assert!(compressed.len() < data.len() / 2, "{}", compression_type);
+            }
+            assert_eq!(
+                data,
+                compression_type
+                    .decompress(&compressed, MAGIC_VALUE_V2)
+                    .unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use bytes::Buf;
+use kafka_connector_macros::JavaEnum;
+use log::warn;
+
+use crate::common::errors::{KafkaError, Result};
+
+/// Control records specify a schema for the record key which includes a version and type:
+///
+/// ```text
+/// Key => Version Type
+///   Version => Int16
+///   Type => Int16
+/// ```
+///
+/// In the future, the version can be bumped to indicate a new schema, but it must be backwards compatible
+/// with the current schema. In general, this means we can add new fields, but we cannot remove old ones.
+///
+/// Note that control records are not considered for compaction by the log cleaner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, JavaEnum)]
+#[java_class = "org/apache/kafka/common/record/ControlRecordType"]
+#[java_id_type = "i16"]
+pub enum ControlRecordType {
+    #[java_variant = "ABORT"]
+    #[java_id = 0]
+    Abort,
+    #[java_variant = "COMMIT"]
+    #[java_id = 1]
+    Commit,
+    /// Raft Leader Change Message
+    #[java_variant = "LEADER_CHANGE"]
+    #[java_id = 2]
+    LeaderChange,
+    /// UNKNOWN is used to indicate a control type which the client is not aware of and should be ignored
+    #[java_variant = "UNKNOWN"]
+    #[java_id(-1)]
+    #[java_fallback]
+    Unknown,
+}
+
+const CURRENT_CONTROL_RECORD_KEY_VERSION: i16 = 0;
+const CURRENT_CONTROL_RECORD_KEY_SIZE: usize = 4;
+
+impl ControlRecordType {
+    pub fn record_key(&self) -> Result<Vec<u8>> {
+        if *self == ControlRecordType::Unknown {
+            return Err(KafkaError::IllegalArgument(
+                "Cannot serialize UNKNOWN control record type".to_owned(),
+            ));
+        }
+        let mut key = Vec::with_capacity(CURRENT_CONTROL_RECORD_KEY_SIZE);
+        key.extend_from_slice(&CURRENT_CONTROL_RECORD_KEY_VERSION.to_be_bytes());
+        key.extend_from_slice(&self.id().to_be_bytes());
+        Ok(key)
+    }
+
+    pub fn parse(key: &[u8]) -> Result<ControlRecordType> {
+        if key.len() < CURRENT_CONTROL_RECORD_KEY_SIZE {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Invalid value size found for end control record key. Must have at least {} bytes, but found only {}",
+                CURRENT_CONTROL_RECORD_KEY_SIZE,
+                key.len()
+            )));
+        }
+        let mut key = key;
+        let version = key.get_i16();
+        if version < 0 {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Invalid version found for control record: {}. May indicate data corruption",
+                version
+            )));
+        }
+        if version != CURRENT_CONTROL_RECORD_KEY_VERSION {
+            warn!(
+                "Received unknown control record key version {}. Parsing as version {}",
+                version, CURRENT_CONTROL_RECORD_KEY_VERSION
+            );
+        }
+        Ok(ControlRecordType::from_id(key.get_i16()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControlRecordType;
+    use crate::{clone_from_java::CloneFromJava, clone_to_java::CloneToJava, jvm};
+
+    const CLASS: &str = "org/apache/kafka/common/record/ControlRecordType";
+
+    #[test]
+    fn unknown_ids_are_the_fallback_variant() {
+        for control_type in ControlRecordType::values() {
+            assert_eq!(ControlRecordType::from_id(control_type.id()), *control_type);
+        }
+        assert_eq!(ControlRecordType::Unknown.id(), -1);
+        assert_eq!(ControlRecordType::from_id(3), ControlRecordType::Unknown);
+        assert_eq!(
+            ControlRecordType::from_id(i16::MIN),
+            ControlRecordType::Unknown
+        );
+    }
+
+    #[test]
+    fn record_keys_round_trip() {
+        for control_type in [
+            ControlRecordType::Abort,
+            ControlRecordType::Commit,
+            ControlRecordType::LeaderChange,
+        ] {
+            let key = control_type.record_key().unwrap();
+            assert_eq!(ControlRecordType::parse(&key).unwrap(), control_type);
+        }
+        assert!(ControlRecordType::Unknown.record_key().is_err());
+        assert_eq!(
+            ControlRecordType::parse(&[0, 0, 0, 3]).unwrap(),
+            ControlRecordType::Unknown
+        );
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn variants_are_the_java_constants_with_their_id() {
+        jvm::run(|env| {
+            for control_type in ControlRecordType::values() {
+                let obj = control_type.clone_to_java(env)?.l()?;
+                assert_eq!(env.get_field(obj, "type", "S")?.s()?, control_type.id());
+                assert_eq!(
+                    ControlRecordType::clone_from_java(env, obj.into())?,
+                    *control_type
+                );
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn java_constants_without_variant_are_the_fallback_variant() {
+        jvm::run(|env| {
+            for name in ["SNAPSHOT_HEADER", "SNAPSHOT_FOOTER"] {
+                let obj = env
+                    .get_static_field(CLASS, name, format!("L{};", CLASS))?
+                    .l()?;
+                assert_eq!(
+                    ControlRecordType::clone_from_java(env, obj.into())?,
+                    ControlRecordType::Unknown
+                );
+            }
+            Ok(())
+        });
+    }
+}
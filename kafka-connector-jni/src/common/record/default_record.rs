@@ -0,0 +1,249 @@
+use bytes::{Buf, BufMut, Bytes};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    header::internals::record_header::RecordHeader,
+    record::record_batch::NO_SEQUENCE,
+    utils::byte_utils::{
+        read_varint, read_varlong, size_of_varint, size_of_varlong, write_varint, write_varlong,
+    },
+};
+
+use super::default_record_batch::increment_sequence;
+
+/// Maximum size of the record overhead: body size + attributes + timestamp delta + offset delta
+/// + key length + value length + headers count
+pub const MAX_RECORD_OVERHEAD: usize = 21;
+
+const NULL_VARINT_SIZE_BYTES: usize = 1;
+
+/// This class implements the inner record format for magic 2 and above.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultRecord {
+    pub size_in_bytes: usize,
+    pub attributes: i8,
+    pub offset: i64,
+    pub timestamp: i64,
+    pub sequence: i32,
+    pub key: Option<Bytes>,
+    pub value: Option<Bytes>,
+    pub headers: Vec<RecordHeader>,
+}
+
+impl DefaultRecord {
+    pub fn key_size(&self) -> i32 {
+        self.key.as_ref().map(|key| key.len() as i32).unwrap_or(-1)
+    }
+    pub fn value_size(&self) -> i32 {
+        self.value
+            .as_ref()
+            .map(|value| value.len() as i32)
+            .unwrap_or(-1)
+    }
+
+    /// Write the record to `out` and return its size.
+    pub fn write_to(
+        out: &mut impl BufMut,
+        offset_delta: i32,
+        timestamp_delta: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> usize {
+        let size_in_bytes = DefaultRecord::size_of_body_in_bytes(
+            offset_delta,
+            timestamp_delta,
+            key,
+            value,
+            headers,
+        );
+        write_varint(size_in_bytes as i32, out);
+
+        // there are no used record attributes at the moment
+        out.put_i8(0);
+
+        write_varlong(timestamp_delta, out);
+        write_varint(offset_delta, out);
+
+        write_nullable_bytes(key, out);
+        write_nullable_bytes(value, out);
+
+        write_varint(headers.len() as i32, out);
+        for header in headers {
+            write_varint(header.key.len() as i32, out);
+            out.put_slice(header.key.as_bytes());
+            write_varint(header.value.len() as i32, out);
+            out.put_slice(&header.value);
+        }
+
+        size_of_varint(size_in_bytes as i32) + size_in_bytes
+    }
+
+    /// Read a record from `buffer`, advancing it past the record.
+    pub fn read_from(
+        buffer: &mut Bytes,
+        base_offset: i64,
+        base_timestamp: i64,
+        base_sequence: i32,
+        log_append_time: Option<i64>,
+    ) -> Result<DefaultRecord> {
+        let size_of_body_in_bytes = read_varint(buffer).map_err(invalid_record_structure)?;
+        if size_of_body_in_bytes < 0 || buffer.remaining() < size_of_body_in_bytes as usize {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Invalid record size: expected {} bytes in record payload, but instead the buffer has only {} remaining bytes.",
+                size_of_body_in_bytes,
+                buffer.remaining()
+            )));
+        }
+        let size_of_body_in_bytes = size_of_body_in_bytes as usize;
+        let size_in_bytes = size_of_varint(size_of_body_in_bytes as i32) + size_of_body_in_bytes;
+        let mut body = buffer.split_to(size_of_body_in_bytes);
+
+        let attributes = body.get_i8();
+        let timestamp_delta = read_varlong(&mut body).map_err(invalid_record_structure)?;
+        let timestamp = log_append_time.unwrap_or(base_timestamp + timestamp_delta);
+
+        let offset_delta = read_varint(&mut body).map_err(invalid_record_structure)?;
+        let offset = base_offset + offset_delta as i64;
+        let sequence = if base_sequence >= 0 {
+            increment_sequence(base_sequence, offset_delta)
+        } else {
+            NO_SEQUENCE
+        };
+
+        let key = read_nullable_bytes(&mut body)?;
+        let value = read_nullable_bytes(&mut body)?;
+
+        let num_headers = read_varint(&mut body).map_err(invalid_record_structure)?;
+        if num_headers < 0 {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Found invalid number of record headers {}",
+                num_headers
+            )));
+        }
+        let mut headers = Vec::with_capacity(num_headers as usize);
+        for _ in 0..num_headers {
+            let header_key_size = read_varint(&mut body).map_err(invalid_record_structure)?;
+            if header_key_size < 0 {
+                return Err(KafkaError::InvalidRecord(format!(
+                    "Invalid negative header key size {}",
+                    header_key_size
+                )));
+            }
+            if body.remaining() < header_key_size as usize {
+                return Err(invalid_record_structure(KafkaError::InvalidRecord(
+                    "Header key is longer than the record".to_owned(),
+                )));
+            }
+            let header_key = body.split_to(header_key_size as usize);
+            let header_key = String::from_utf8(header_key.to_vec()).map_err(|_| {
+                KafkaError::InvalidRecord("Header key is not valid UTF-8".to_owned())
+            })?;
+            let header_value = read_nullable_bytes(&mut body)?.unwrap_or_default();
+            headers.push(RecordHeader::new(header_key, header_value));
+        }
+
+        // validate whether we have read all header bytes in the current record
+        if body.has_remaining() {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Invalid record size: expected to read {} bytes in record payload, but instead read {}",
+                size_of_body_in_bytes,
+                size_of_body_in_bytes - body.remaining()
+            )));
+        }
+
+        Ok(DefaultRecord {
+            size_in_bytes,
+            attributes,
+            offset,
+            timestamp,
+            sequence,
+            key,
+            value,
+            headers,
+        })
+    }
+
+    pub fn size_in_bytes(
+        offset_delta: i32,
+        timestamp_delta: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> usize {
+        let body_size = DefaultRecord::size_of_body_in_bytes(
+            offset_delta,
+            timestamp_delta,
+            key,
+            value,
+            headers,
+        );
+        body_size + size_of_varint(body_size as i32)
+    }
+
+    pub fn size_of_body_in_bytes(
+        offset_delta: i32,
+        timestamp_delta: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> usize {
+        // always one byte for attributes
+        1 + size_of_varint(offset_delta)
+            + size_of_varlong(timestamp_delta)
+            + size_of(key, value, headers)
+    }
+
+    /// Get an upper bound on the size of a record with the given key, value and headers.
+    pub fn record_size_upper_bound(
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> usize {
+        MAX_RECORD_OVERHEAD + size_of(key, value, headers)
+    }
+}
+
+fn size_of(key: Option<&[u8]>, value: Option<&[u8]>, headers: &[RecordHeader]) -> usize {
+    let mut size = size_of_nullable_bytes(key) + size_of_nullable_bytes(value);
+    size += size_of_varint(headers.len() as i32);
+    for header in headers {
+        size += size_of_varint(header.key.len() as i32) + header.key.len();
+        size += size_of_varint(header.value.len() as i32) + header.value.len();
+    }
+    size
+}
+
+fn size_of_nullable_bytes(bytes: Option<&[u8]>) -> usize {
+    match bytes {
+        Some(bytes) => size_of_varint(bytes.len() as i32) + bytes.len(),
+        None => NULL_VARINT_SIZE_BYTES,
+    }
+}
+
+fn write_nullable_bytes(bytes: Option<&[u8]>, out: &mut impl BufMut) {
+    match bytes {
+        Some(bytes) => {
+            write_varint(bytes.len() as i32, out);
+            out.put_slice(bytes);
+        }
+        None => write_varint(-1, out),
+    }
+}
+
+fn read_nullable_bytes(buffer: &mut Bytes) -> Result<Option<Bytes>> {
+    let size = read_varint(buffer).map_err(invalid_record_structure)?;
+    if size < 0 {
+        return Ok(None);
+    }
+    if buffer.remaining() < size as usize {
+        return Err(KafkaError::InvalidRecord(
+            "Found invalid record structure".to_owned(),
+        ));
+    }
+    Ok(Some(buffer.split_to(size as usize)))
+}
+
+fn invalid_record_structure(cause: KafkaError) -> KafkaError {
+    KafkaError::InvalidRecord(format!("Found invalid record structure: {}", cause))
+}
@@ -0,0 +1,285 @@
+use bytes::{Buf, Bytes};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    utils::crc32c,
+};
+
+use super::{
+    compression_type::CompressionType, default_record::DefaultRecord, record_batch::MAGIC_VALUE_V2,
+    timestamp_type::TimestampType,
+};
+
+pub const BASE_OFFSET_OFFSET: usize = 0;
+pub const BASE_OFFSET_LENGTH: usize = 8;
+pub const LENGTH_OFFSET: usize = BASE_OFFSET_OFFSET + BASE_OFFSET_LENGTH;
+pub const LENGTH_LENGTH: usize = 4;
+pub const PARTITION_LEADER_EPOCH_OFFSET: usize = LENGTH_OFFSET + LENGTH_LENGTH;
+pub const PARTITION_LEADER_EPOCH_LENGTH: usize = 4;
+pub const MAGIC_OFFSET: usize = PARTITION_LEADER_EPOCH_OFFSET + PARTITION_LEADER_EPOCH_LENGTH;
+pub const MAGIC_LENGTH: usize = 1;
+pub const CRC_OFFSET: usize = MAGIC_OFFSET + MAGIC_LENGTH;
+pub const CRC_LENGTH: usize = 4;
+pub const ATTRIBUTES_OFFSET: usize = CRC_OFFSET + CRC_LENGTH;
+pub const ATTRIBUTE_LENGTH: usize = 2;
+pub const LAST_OFFSET_DELTA_OFFSET: usize = ATTRIBUTES_OFFSET + ATTRIBUTE_LENGTH;
+pub const LAST_OFFSET_DELTA_LENGTH: usize = 4;
+pub const BASE_TIMESTAMP_OFFSET: usize = LAST_OFFSET_DELTA_OFFSET + LAST_OFFSET_DELTA_LENGTH;
+pub const BASE_TIMESTAMP_LENGTH: usize = 8;
+pub const MAX_TIMESTAMP_OFFSET: usize = BASE_TIMESTAMP_OFFSET + BASE_TIMESTAMP_LENGTH;
+pub const MAX_TIMESTAMP_LENGTH: usize = 8;
+pub const PRODUCER_ID_OFFSET: usize = MAX_TIMESTAMP_OFFSET + MAX_TIMESTAMP_LENGTH;
+pub const PRODUCER_ID_LENGTH: usize = 8;
+pub const PRODUCER_EPOCH_OFFSET: usize = PRODUCER_ID_OFFSET + PRODUCER_ID_LENGTH;
+pub const PRODUCER_EPOCH_LENGTH: usize = 2;
+pub const BASE_SEQUENCE_OFFSET: usize = PRODUCER_EPOCH_OFFSET + PRODUCER_EPOCH_LENGTH;
+pub const BASE_SEQUENCE_LENGTH: usize = 4;
+pub const RECORDS_COUNT_OFFSET: usize = BASE_SEQUENCE_OFFSET + BASE_SEQUENCE_LENGTH;
+pub const RECORDS_COUNT_LENGTH: usize = 4;
+pub const RECORDS_OFFSET: usize = RECORDS_COUNT_OFFSET + RECORDS_COUNT_LENGTH;
+pub const RECORD_BATCH_OVERHEAD: usize = RECORDS_OFFSET;
+pub const LOG_OVERHEAD: usize = LENGTH_OFFSET + LENGTH_LENGTH;
+
+const COMPRESSION_CODEC_MASK: i16 = 0x07;
+const TRANSACTIONAL_FLAG_MASK: i16 = 0x10;
+const CONTROL_FLAG_MASK: i16 = 0x20;
+const TIMESTAMP_TYPE_MASK: i16 = 0x08;
+
+/// Record batch implementation for magic 2 and above, backed by a shared byte buffer.
+///
+/// Accessors read directly from the underlying buffer, so constructing a batch is cheap and
+/// record payloads returned from `records()` share memory with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefaultRecordBatch {
+    buffer: Bytes,
+}
+
+impl DefaultRecordBatch {
+    pub fn new(buffer: Bytes) -> DefaultRecordBatch {
+        DefaultRecordBatch { buffer }
+    }
+
+    pub fn buffer(&self) -> &Bytes {
+        &self.buffer
+    }
+
+    pub fn magic(&self) -> i8 {
+        self.buffer[MAGIC_OFFSET] as i8
+    }
+
+    pub fn base_offset(&self) -> i64 {
+        self.get_i64(BASE_OFFSET_OFFSET)
+    }
+
+    pub fn last_offset(&self) -> i64 {
+        self.base_offset() + self.last_offset_delta() as i64
+    }
+
+    pub fn next_offset(&self) -> i64 {
+        self.last_offset() + 1
+    }
+
+    pub fn last_offset_delta(&self) -> i32 {
+        self.get_i32(LAST_OFFSET_DELTA_OFFSET)
+    }
+
+    pub fn base_timestamp(&self) -> i64 {
+        self.get_i64(BASE_TIMESTAMP_OFFSET)
+    }
+
+    pub fn max_timestamp(&self) -> i64 {
+        self.get_i64(MAX_TIMESTAMP_OFFSET)
+    }
+
+    pub fn partition_leader_epoch(&self) -> i32 {
+        self.get_i32(PARTITION_LEADER_EPOCH_OFFSET)
+    }
+
+    pub fn producer_id(&self) -> i64 {
+        self.get_i64(PRODUCER_ID_OFFSET)
+    }
+
+    pub fn producer_epoch(&self) -> i16 {
+        self.get_i16(PRODUCER_EPOCH_OFFSET)
+    }
+
+    pub fn base_sequence(&self) -> i32 {
+        self.get_i32(BASE_SEQUENCE_OFFSET)
+    }
+
+    pub fn last_sequence(&self) -> i32 {
+        let base_sequence = self.base_sequence();
+        if base_sequence < 0 {
+            return base_sequence;
+        }
+        increment_sequence(base_sequence, self.last_offset_delta())
+    }
+
+    pub fn count_or_null(&self) -> i32 {
+        self.get_i32(RECORDS_COUNT_OFFSET)
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        LOG_OVERHEAD + self.get_i32(LENGTH_OFFSET) as usize
+    }
+
+    pub fn checksum(&self) -> u32 {
+        self.get_u32(CRC_OFFSET)
+    }
+
+    pub fn compute_checksum(&self) -> u32 {
+        crc32c::compute(&self.buffer[ATTRIBUTES_OFFSET..])
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.size_in_bytes() >= RECORD_BATCH_OVERHEAD && self.checksum() == self.compute_checksum()
+    }
+
+    pub fn ensure_valid(&self) -> Result<()> {
+        if self.size_in_bytes() < RECORD_BATCH_OVERHEAD {
+            return Err(KafkaError::CorruptRecord(format!(
+                "Record batch is corrupt (the size {} is smaller than the minimum allowed overhead {})",
+                self.size_in_bytes(),
+                RECORD_BATCH_OVERHEAD
+            )));
+        }
+        if !self.is_valid() {
+            return Err(KafkaError::CorruptRecord(format!(
+                "Record is corrupt (stored crc = {}, computed crc = {})",
+                self.checksum(),
+                self.compute_checksum()
+            )));
+        }
+        Ok(())
+    }
+
+    fn attributes(&self) -> i16 {
+        self.get_i16(ATTRIBUTES_OFFSET)
+    }
+
+    pub fn compression_type(&self) -> Result<CompressionType> {
+        CompressionType::for_id(self.attributes() & COMPRESSION_CODEC_MASK)
+    }
+
+    pub fn timestamp_type(&self) -> TimestampType {
+        if self.attributes() & TIMESTAMP_TYPE_MASK == 0 {
+            TimestampType::CreateTime
+        } else {
+            TimestampType::LogAppendTime
+        }
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.attributes() & TRANSACTIONAL_FLAG_MASK > 0
+    }
+
+    pub fn is_control_batch(&self) -> bool {
+        self.attributes() & CONTROL_FLAG_MASK > 0
+    }
+
+    pub fn has_producer_id(&self) -> bool {
+        self.producer_id() >= 0
+    }
+
+    /// Decode all records of this batch. Records of a compressed batch are decompressed first, so
+    /// they don't share memory with the batch.
+    pub fn records(&self) -> Result<Vec<DefaultRecord>> {
+        if self.magic() != MAGIC_VALUE_V2 {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Unsupported magic {} for record batch",
+                self.magic()
+            )));
+        }
+        let compression_type = self.compression_type()?;
+        let count = self.count_or_null();
+        if count < 0 {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Found invalid record count {} in magic v{} batch",
+                count,
+                self.magic()
+            )));
+        }
+        let log_append_time = match self.timestamp_type() {
+            TimestampType::LogAppendTime => Some(self.max_timestamp()),
+            _ => None,
+        };
+        let base_offset = self.base_offset();
+        let base_timestamp = self.base_timestamp();
+        let base_sequence = self.base_sequence();
+
+        let mut buffer = self.buffer.slice(RECORDS_OFFSET..self.size_in_bytes());
+        if compression_type != CompressionType::None {
+            buffer = compression_type.decompress(&buffer, MAGIC_VALUE_V2)?;
+        }
+        let mut records = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            records.push(DefaultRecord::read_from(
+                &mut buffer,
+                base_offset,
+                base_timestamp,
+                base_sequence,
+                log_append_time,
+            )?);
+        }
+        if buffer.has_remaining() {
+            return Err(KafkaError::InvalidRecord(format!(
+                "Incorrect declared batch size, {} records but {} bytes left",
+                count,
+                buffer.remaining()
+            )));
+        }
+        Ok(records)
+    }
+
+    fn get_i64(&self, offset: usize) -> i64 {
+        (&self.buffer[offset..]).get_i64()
+    }
+
+    fn get_i32(&self, offset: usize) -> i32 {
+        (&self.buffer[offset..]).get_i32()
+    }
+
+    fn get_u32(&self, offset: usize) -> u32 {
+        (&self.buffer[offset..]).get_u32()
+    }
+
+    fn get_i16(&self, offset: usize) -> i16 {
+        (&self.buffer[offset..]).get_i16()
+    }
+}
+
+/// Compute the attributes field of a batch header.
+pub fn compute_attributes(
+    compression_type: CompressionType,
+    timestamp_type: TimestampType,
+    is_transactional: bool,
+    is_control: bool,
+) -> i16 {
+    let mut attributes = compression_type.id() & COMPRESSION_CODEC_MASK;
+    if is_transactional {
+        attributes |= TRANSACTIONAL_FLAG_MASK;
+    }
+    if is_control {
+        attributes |= CONTROL_FLAG_MASK;
+    }
+    if timestamp_type == TimestampType::LogAppendTime {
+        attributes |= TIMESTAMP_TYPE_MASK;
+    }
+    attributes
+}
+
+/// Increment sequence number, wrapping around on overflow like the broker does.
+pub fn increment_sequence(sequence: i32, increment: i32) -> i32 {
+    if sequence > i32::MAX - increment {
+        return increment - (i32::MAX - sequence) - 1;
+    }
+    sequence + increment
+}
+
+/// Decrement sequence number, wrapping around on underflow.
+pub fn decrement_sequence(sequence: i32, decrement: i32) -> i32 {
+    if sequence < decrement {
+        return i32::MAX - (decrement - sequence) + 1;
+    }
+    sequence - decrement
+}
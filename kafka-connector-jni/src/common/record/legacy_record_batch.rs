@@ -0,0 +1,425 @@
+use bytes::{Buf, Bytes};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    utils::crc32,
+};
+
+use super::{
+    compression_type::CompressionType,
+    default_record::DefaultRecord,
+    record_batch::{MAGIC_VALUE_V0, MAGIC_VALUE_V1, NO_SEQUENCE, NO_TIMESTAMP},
+    timestamp_type::TimestampType,
+};
+
+pub const OFFSET_OFFSET: usize = 0;
+pub const OFFSET_LENGTH: usize = 8;
+pub const SIZE_OFFSET: usize = OFFSET_OFFSET + OFFSET_LENGTH;
+pub const SIZE_LENGTH: usize = 4;
+pub const LOG_OVERHEAD: usize = SIZE_OFFSET + SIZE_LENGTH;
+
+/// The current offset and size for all the fixed-length fields of a message, relative to the
+/// start of its log entry
+pub const CRC_OFFSET: usize = LOG_OVERHEAD;
+pub const CRC_LENGTH: usize = 4;
+pub const MAGIC_OFFSET: usize = CRC_OFFSET + CRC_LENGTH;
+pub const MAGIC_LENGTH: usize = 1;
+pub const ATTRIBUTES_OFFSET: usize = MAGIC_OFFSET + MAGIC_LENGTH;
+pub const ATTRIBUTES_LENGTH: usize = 1;
+pub const TIMESTAMP_OFFSET: usize = ATTRIBUTES_OFFSET + ATTRIBUTES_LENGTH;
+pub const TIMESTAMP_LENGTH: usize = 8;
+pub const KEY_SIZE_OFFSET_V0: usize = ATTRIBUTES_OFFSET + ATTRIBUTES_LENGTH;
+pub const KEY_SIZE_OFFSET_V1: usize = TIMESTAMP_OFFSET + TIMESTAMP_LENGTH;
+pub const KEY_SIZE_LENGTH: usize = 4;
+pub const VALUE_SIZE_LENGTH: usize = 4;
+
+/// The size for the message header, excluding the log overhead
+pub const HEADER_SIZE_V0: usize = CRC_LENGTH + MAGIC_LENGTH + ATTRIBUTES_LENGTH;
+pub const HEADER_SIZE_V1: usize = HEADER_SIZE_V0 + TIMESTAMP_LENGTH;
+
+/// The amount of overhead bytes in a message, excluding the log overhead
+pub const RECORD_OVERHEAD_V0: usize = HEADER_SIZE_V0 + KEY_SIZE_LENGTH + VALUE_SIZE_LENGTH;
+pub const RECORD_OVERHEAD_V1: usize = HEADER_SIZE_V1 + KEY_SIZE_LENGTH + VALUE_SIZE_LENGTH;
+
+const COMPRESSION_CODEC_MASK: i8 = 0x07;
+const TIMESTAMP_TYPE_MASK: i8 = 0x08;
+
+/// A log entry of magic v0 or v1, which holds a single message. The value of a compressed
+/// message wraps a whole set of uncompressed inner log entries, which are returned by
+/// `records()` in its place.
+///
+/// Legacy messages have no producer state, leader epoch or headers, they are read into
+/// `DefaultRecord` so the consumer handles records of every magic the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyRecordBatch {
+    buffer: Bytes,
+}
+
+impl LegacyRecordBatch {
+    pub fn new(buffer: Bytes) -> LegacyRecordBatch {
+        LegacyRecordBatch { buffer }
+    }
+
+    pub fn buffer(&self) -> &Bytes {
+        &self.buffer
+    }
+
+    pub fn magic(&self) -> i8 {
+        self.buffer[MAGIC_OFFSET] as i8
+    }
+
+    /// The offset of the first inner message of a compressed message, which needs to be
+    /// decompressed for it.
+    pub fn base_offset(&self) -> i64 {
+        if matches!(self.compression_type(), Ok(CompressionType::None)) {
+            return self.last_offset();
+        }
+        self.records()
+            .ok()
+            .and_then(|records| records.first().map(|record| record.offset))
+            .unwrap_or_else(|| self.last_offset())
+    }
+
+    /// The offset of the log entry, which is the offset of the last inner message for a
+    /// compressed message.
+    pub fn last_offset(&self) -> i64 {
+        (&self.buffer[OFFSET_OFFSET..]).get_i64()
+    }
+
+    pub fn next_offset(&self) -> i64 {
+        self.last_offset() + 1
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        LOG_OVERHEAD + self.record_size()
+    }
+
+    fn record_size(&self) -> usize {
+        (&self.buffer[SIZE_OFFSET..]).get_i32() as usize
+    }
+
+    fn attributes(&self) -> i8 {
+        self.buffer[ATTRIBUTES_OFFSET] as i8
+    }
+
+    pub fn compression_type(&self) -> Result<CompressionType> {
+        CompressionType::for_id((self.attributes() & COMPRESSION_CODEC_MASK) as i16)
+    }
+
+    pub fn timestamp_type(&self) -> TimestampType {
+        if self.magic() == MAGIC_VALUE_V0 {
+            TimestampType::NoTimestampType
+        } else if self.attributes() & TIMESTAMP_TYPE_MASK == 0 {
+            TimestampType::CreateTime
+        } else {
+            TimestampType::LogAppendTime
+        }
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        if self.magic() == MAGIC_VALUE_V0 {
+            NO_TIMESTAMP
+        } else {
+            (&self.buffer[TIMESTAMP_OFFSET..]).get_i64()
+        }
+    }
+
+    pub fn checksum(&self) -> u32 {
+        (&self.buffer[CRC_OFFSET..]).get_u32()
+    }
+
+    pub fn compute_checksum(&self) -> u32 {
+        crc32::compute(&self.buffer[MAGIC_OFFSET..self.size_in_bytes()])
+    }
+
+    fn record_overhead(&self) -> usize {
+        if self.magic() == MAGIC_VALUE_V0 {
+            RECORD_OVERHEAD_V0
+        } else {
+            RECORD_OVERHEAD_V1
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.record_size() >= self.record_overhead() && self.checksum() == self.compute_checksum()
+    }
+
+    pub fn ensure_valid(&self) -> Result<()> {
+        if self.record_size() < self.record_overhead() {
+            return Err(KafkaError::CorruptRecord(format!(
+                "Record is corrupt (the size {} is smaller than the minimum allowed overhead {})",
+                self.record_size(),
+                self.record_overhead()
+            )));
+        }
+        if !self.is_valid() {
+            return Err(KafkaError::CorruptRecord(format!(
+                "Record is corrupt (stored crc = {}, computed crc = {})",
+                self.checksum(),
+                self.compute_checksum()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Decode the message of this log entry, or the inner messages of a compressed message.
+    pub fn records(&self) -> Result<Vec<DefaultRecord>> {
+        let compression_type = self.compression_type()?;
+        let (key, value) = self.key_and_value()?;
+        if compression_type == CompressionType::None {
+            return Ok(vec![self.to_record(
+                self.last_offset(),
+                self.timestamp(),
+                key,
+                value,
+            )]);
+        }
+
+        let wrapper_magic = self.magic();
+        let value = value.ok_or_else(|| {
+            KafkaError::InvalidRecord(
+                "Found invalid compressed record set with null value".to_owned(),
+            )
+        })?;
+        let mut inner_entries = compression_type.decompress(&value, wrapper_magic)?;
+        let mut inner_batches = vec![];
+        while inner_entries.len() >= LOG_OVERHEAD {
+            let size = (&inner_entries[SIZE_OFFSET..]).get_i32();
+            if size < RECORD_OVERHEAD_V0 as i32
+                || inner_entries.len() < LOG_OVERHEAD + size as usize
+            {
+                return Err(KafkaError::InvalidRecord(format!(
+                    "Found invalid inner record of size {} in compressed record set",
+                    size
+                )));
+            }
+            let inner =
+                LegacyRecordBatch::new(inner_entries.split_to(LOG_OVERHEAD + size as usize));
+            if inner.magic() != wrapper_magic {
+                return Err(KafkaError::InvalidRecord(format!(
+                    "Compressed message magic {} does not match wrapper magic {}",
+                    inner.magic(),
+                    wrapper_magic
+                )));
+            }
+            if inner.compression_type()? != CompressionType::None {
+                return Err(KafkaError::InvalidRecord(
+                    "Compressed outer record should not have an inner record with a compression attribute set".to_owned(),
+                ));
+            }
+            inner_batches.push(inner);
+        }
+        if inner_entries.has_remaining() {
+            return Err(KafkaError::InvalidRecord(
+                "Found truncated inner record in compressed record set".to_owned(),
+            ));
+        }
+
+        // Inner offsets of magic v1 are relative, the wrapper holds the offset of the last one
+        let absolute_base_offset = match inner_batches.last() {
+            Some(last) if wrapper_magic == MAGIC_VALUE_V1 => {
+                self.last_offset() - last.last_offset()
+            }
+            Some(_) => 0,
+            None => {
+                return Err(KafkaError::InvalidRecord(
+                    "Found invalid compressed record set with no inner records".to_owned(),
+                ))
+            }
+        };
+        let log_append_time = match self.timestamp_type() {
+            TimestampType::LogAppendTime => Some(self.timestamp()),
+            _ => None,
+        };
+        inner_batches
+            .iter()
+            .map(|inner| {
+                let (key, value) = inner.key_and_value()?;
+                Ok(inner.to_record(
+                    absolute_base_offset + inner.last_offset(),
+                    log_append_time.unwrap_or_else(|| inner.timestamp()),
+                    key,
+                    value,
+                ))
+            })
+            .collect()
+    }
+
+    fn key_and_value(&self) -> Result<(Option<Bytes>, Option<Bytes>)> {
+        let key_size_offset = if self.magic() == MAGIC_VALUE_V0 {
+            KEY_SIZE_OFFSET_V0
+        } else {
+            KEY_SIZE_OFFSET_V1
+        };
+        let mut buffer = self.buffer.slice(key_size_offset..self.size_in_bytes());
+        let key = read_nullable_bytes(&mut buffer)?;
+        let value = read_nullable_bytes(&mut buffer)?;
+        Ok((key, value))
+    }
+
+    fn to_record(
+        &self,
+        offset: i64,
+        timestamp: i64,
+        key: Option<Bytes>,
+        value: Option<Bytes>,
+    ) -> DefaultRecord {
+        DefaultRecord {
+            size_in_bytes: self.size_in_bytes(),
+            attributes: self.attributes(),
+            offset,
+            timestamp,
+            sequence: NO_SEQUENCE,
+            key,
+            value,
+            headers: vec![],
+        }
+    }
+}
+
+fn read_nullable_bytes(buffer: &mut Bytes) -> Result<Option<Bytes>> {
+    if buffer.remaining() < 4 {
+        return Err(invalid_record_structure());
+    }
+    let size = buffer.get_i32();
+    if size < 0 {
+        return Ok(None);
+    }
+    if buffer.remaining() < size as usize {
+        return Err(invalid_record_structure());
+    }
+    Ok(Some(buffer.split_to(size as usize)))
+}
+
+fn invalid_record_structure() -> KafkaError {
+    KafkaError::InvalidRecord("Found invalid record structure".to_owned())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bytes::Bytes;
+
+    use crate::common::record::{
+        compression_type::CompressionType, default_record::DefaultRecord,
+        record_batch::NO_TIMESTAMP, timestamp_type::TimestampType,
+    };
+
+    use super::LegacyRecordBatch;
+
+    // Log entries written by `LegacyRecord.write` of the java client: the key "k" with the value
+    // "v0" at offset 5 and a null key with the value "v1" at offset 6, timestamps 1000 and 1001
+    // for magic v1. Compressed messages wrap both with gzip in a single entry at offset 6.
+    const V0: [u8; 57] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x11, 0x16, 0x57, 0x64,
+        0xb1, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x6b, 0x00, 0x00, 0x00, 0x02, 0x76, 0x30, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x10, 0x4c, 0x9f, 0x5b, 0xc2,
+        0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x02, 0x76, 0x31,
+    ];
+    pub(crate) const V1: [u8; 73] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x19, 0x34, 0xae, 0x7d,
+        0xfe, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x00, 0x01,
+        0x6b, 0x00, 0x00, 0x00, 0x02, 0x76, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06,
+        0x00, 0x00, 0x00, 0x18, 0x76, 0x1e, 0x72, 0xb4, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x03, 0xe9, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x02, 0x76, 0x31,
+    ];
+    const V0_GZIP: [u8; 83] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x47, 0xa6, 0x5c, 0x69,
+        0x2e, 0x00, 0x01, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x39, 0x1f, 0x8b, 0x08, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x63, 0x60, 0x00, 0x03, 0x56, 0x20, 0x16, 0x14, 0x0b,
+        0x4f, 0xd9, 0x08, 0xe6, 0x31, 0x66, 0x03, 0x09, 0xa6, 0x32, 0x03, 0x88, 0x1c, 0x03, 0x1b,
+        0x10, 0x0b, 0xf8, 0xcc, 0x8f, 0x3e, 0xc4, 0xc0, 0xf0, 0x1f, 0x08, 0xc0, 0x72, 0x86, 0x00,
+        0x76, 0x79, 0x0f, 0x69, 0x39, 0x00, 0x00, 0x00,
+    ];
+    const V1_GZIP: [u8; 93] = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x51, 0x67, 0x25, 0xed,
+        0x2b, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0xe9, 0xff, 0xff, 0xff, 0xff,
+        0x00, 0x00, 0x00, 0x3b, 0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x63,
+        0x60, 0x80, 0x03, 0x49, 0x93, 0x75, 0xb5, 0xff, 0x18, 0xa1, 0x1c, 0xe6, 0x17, 0x40, 0x82,
+        0x31, 0x1b, 0x48, 0x30, 0x95, 0x19, 0x40, 0xc5, 0x40, 0x72, 0x12, 0x65, 0x72, 0x45, 0x5b,
+        0xe0, 0x8a, 0x5e, 0xfe, 0x07, 0x02, 0xb0, 0x1a, 0x43, 0x00, 0xb9, 0xe4, 0x6f, 0x9e, 0x49,
+        0x00, 0x00, 0x00,
+    ];
+
+    fn batches(entries: &'static [u8]) -> Vec<LegacyRecordBatch> {
+        let mut entries = Bytes::from_static(entries);
+        let mut batches = vec![];
+        while !entries.is_empty() {
+            let batch = LegacyRecordBatch::new(entries.clone());
+            batches.push(LegacyRecordBatch::new(
+                entries.split_to(batch.size_in_bytes()),
+            ));
+        }
+        batches
+    }
+
+    fn records(entries: &'static [u8]) -> Vec<DefaultRecord> {
+        batches(entries)
+            .iter()
+            .flat_map(|batch| {
+                batch.ensure_valid().unwrap();
+                batch.records().unwrap()
+            })
+            .collect()
+    }
+
+    fn assert_records(records: &[DefaultRecord], timestamps: [i64; 2]) {
+        assert_eq!(2, records.len());
+        assert_eq!(5, records[0].offset);
+        assert_eq!(timestamps[0], records[0].timestamp);
+        assert_eq!(Some(&b"k"[..]), records[0].key.as_deref());
+        assert_eq!(Some(&b"v0"[..]), records[0].value.as_deref());
+        assert_eq!(6, records[1].offset);
+        assert_eq!(timestamps[1], records[1].timestamp);
+        assert_eq!(None, records[1].key);
+        assert_eq!(Some(&b"v1"[..]), records[1].value.as_deref());
+    }
+
+    #[test]
+    fn reads_uncompressed_messages() {
+        assert_records(&records(&V0), [NO_TIMESTAMP; 2]);
+        assert_records(&records(&V1), [1000, 1001]);
+
+        let batches = batches(&V1);
+        assert_eq!(TimestampType::CreateTime, batches[0].timestamp_type());
+        assert_eq!(5, batches[0].base_offset());
+        assert_eq!(7, batches[1].next_offset());
+    }
+
+    #[test]
+    fn reads_inner_messages_of_compressed_messages() {
+        // inner offsets are absolute in magic v0 and relative in magic v1
+        assert_records(&records(&V0_GZIP), [NO_TIMESTAMP; 2]);
+        assert_records(&records(&V1_GZIP), [1000, 1001]);
+
+        let batches = batches(&V1_GZIP);
+        assert_eq!(1, batches.len());
+        assert_eq!(
+            CompressionType::Gzip,
+            batches[0].compression_type().unwrap()
+        );
+        assert_eq!(5, batches[0].base_offset());
+        assert_eq!(6, batches[0].last_offset());
+    }
+
+    #[test]
+    fn inner_messages_take_the_log_append_time_of_the_wrapper() {
+        let mut entry = V1_GZIP.to_vec();
+        // set the timestamp type attribute, then fix the crc
+        entry[17] |= 0x08;
+        let crc = crate::common::utils::crc32::compute(&entry[16..]);
+        entry[12..16].copy_from_slice(&crc.to_be_bytes());
+        let batch = LegacyRecordBatch::new(entry.into());
+        batch.ensure_valid().unwrap();
+        assert_eq!(TimestampType::LogAppendTime, batch.timestamp_type());
+        assert_records(&batch.records().unwrap(), [1001, 1001]);
+    }
+
+    #[test]
+    fn corrupt_message_is_invalid() {
+        let mut entry = V1[..37].to_vec();
+        entry[36] ^= 1;
+        let batch = LegacyRecordBatch::new(entry.into());
+        assert!(!batch.is_valid());
+        assert!(batch.ensure_valid().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use bytes::{Buf, Bytes};
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{
+    default_record_batch::{DefaultRecordBatch, LENGTH_OFFSET, LOG_OVERHEAD, MAGIC_OFFSET},
+    legacy_record_batch::{LegacyRecordBatch, RECORD_OVERHEAD_V0},
+    record_batch::{RecordBatch, MAGIC_VALUE_V0, MAGIC_VALUE_V1, MAGIC_VALUE_V2},
+};
+
+/// A `Records` implementation backed by a byte buffer. Batches share the underlying memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryRecords {
+    buffer: Bytes,
+}
+
+impl MemoryRecords {
+    pub fn new(buffer: Bytes) -> MemoryRecords {
+        MemoryRecords { buffer }
+    }
+
+    pub fn empty() -> MemoryRecords {
+        MemoryRecords::default()
+    }
+
+    pub fn buffer(&self) -> &Bytes {
+        &self.buffer
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Iterate over record batches stored in this buffer. Incomplete trailing batch is ignored,
+    /// just like partial batches returned by the broker when fetch size limit is reached.
+    /// Messages of magic v0 and v1 are returned as one batch per log entry.
+    pub fn batches(&self) -> RecordBatchIterator {
+        RecordBatchIterator {
+            buffer: self.buffer.clone(),
+        }
+    }
+}
+
+impl From<DefaultRecordBatch> for MemoryRecords {
+    fn from(batch: DefaultRecordBatch) -> Self {
+        MemoryRecords::new(batch.buffer().clone())
+    }
+}
+
+pub struct RecordBatchIterator {
+    buffer: Bytes,
+}
+
+impl Iterator for RecordBatchIterator {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.len() < LOG_OVERHEAD {
+            return None;
+        }
+        let record_size = (&self.buffer[LENGTH_OFFSET..]).get_i32();
+        if record_size < RECORD_OVERHEAD_V0 as i32 {
+            self.buffer.clear();
+            return Some(Err(KafkaError::CorruptRecord(format!(
+                "Record size {} is less than the minimum record overhead ({})",
+                record_size, RECORD_OVERHEAD_V0
+            ))));
+        }
+        let batch_size = record_size as usize + LOG_OVERHEAD;
+        if self.buffer.len() < batch_size || self.buffer.len() <= MAGIC_OFFSET {
+            return None;
+        }
+        let magic = self.buffer[MAGIC_OFFSET] as i8;
+        let batch = self.buffer.split_to(batch_size);
+        match magic {
+            MAGIC_VALUE_V2 => Some(Ok(RecordBatch::Default(DefaultRecordBatch::new(batch)))),
+            MAGIC_VALUE_V0 | MAGIC_VALUE_V1 => {
+                Some(Ok(RecordBatch::Legacy(LegacyRecordBatch::new(batch))))
+            }
+            _ => {
+                self.buffer.clear();
+                Some(Err(KafkaError::CorruptRecord(format!(
+                    "Invalid magic found in record: {}",
+                    magic
+                ))))
+            }
+        }
+    }
+}
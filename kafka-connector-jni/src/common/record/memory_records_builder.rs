@@ -0,0 +1,550 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    header::internals::record_header::RecordHeader,
+    utils::crc32c,
+};
+
+use super::{
+    compression_type::CompressionType,
+    default_record::DefaultRecord,
+    default_record_batch::{
+        compute_attributes, ATTRIBUTES_OFFSET, CRC_OFFSET, LOG_OVERHEAD, RECORDS_OFFSET,
+    },
+    memory_records::MemoryRecords,
+    record_batch::{MAGIC_VALUE_V2, NO_PARTITION_LEADER_EPOCH, NO_TIMESTAMP},
+    timestamp_type::TimestampType,
+};
+
+const COMPRESSION_RATE_ESTIMATION_FACTOR: f32 = 1.05;
+
+/// This class is used to write new log data in memory, i.e. this is the write path for
+/// `MemoryRecords`. Only magic v2 batches are written.
+///
+/// The batch header is reserved up front and written once the builder is closed. Records are
+/// written uncompressed and compressed as a whole on close, after which the written bytes are
+/// frozen into `MemoryRecords`.
+#[derive(Debug)]
+pub struct MemoryRecordsBuilder {
+    buffer: BytesMut,
+    initial_capacity: usize,
+    write_limit: usize,
+    compression_type: CompressionType,
+    timestamp_type: TimestampType,
+    base_offset: i64,
+    log_append_time: i64,
+    is_transactional: bool,
+    is_control_batch: bool,
+    partition_leader_epoch: i32,
+    producer_id: i64,
+    producer_epoch: i16,
+    base_sequence: i32,
+    uncompressed_records_size_in_bytes: usize,
+    num_records: i32,
+    max_timestamp: i64,
+    offset_of_max_timestamp: i64,
+    last_offset: Option<i64>,
+    first_timestamp: Option<i64>,
+    appends_closed: bool,
+    aborted: bool,
+    records_compressed: bool,
+    built_records: Option<MemoryRecords>,
+}
+
+impl MemoryRecordsBuilder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        mut buffer: BytesMut,
+        compression_type: CompressionType,
+        timestamp_type: TimestampType,
+        base_offset: i64,
+        log_append_time: i64,
+        producer_id: i64,
+        producer_epoch: i16,
+        base_sequence: i32,
+        is_transactional: bool,
+        is_control_batch: bool,
+        partition_leader_epoch: i32,
+        write_limit: usize,
+    ) -> Result<MemoryRecordsBuilder> {
+        if is_control_batch && !is_transactional {
+            return Err(KafkaError::IllegalArgument(
+                "Control records can only be appended to transactional batches".to_owned(),
+            ));
+        }
+        if timestamp_type == TimestampType::NoTimestampType {
+            return Err(KafkaError::IllegalArgument(
+                "TimestampType must be set for magic >= 0".to_owned(),
+            ));
+        }
+        let initial_capacity = buffer.capacity();
+        buffer.clear();
+        buffer.resize(RECORDS_OFFSET, 0);
+        Ok(MemoryRecordsBuilder {
+            buffer,
+            initial_capacity,
+            write_limit,
+            compression_type,
+            timestamp_type,
+            base_offset,
+            log_append_time,
+            is_transactional,
+            is_control_batch,
+            partition_leader_epoch,
+            producer_id,
+            producer_epoch,
+            base_sequence,
+            uncompressed_records_size_in_bytes: 0,
+            num_records: 0,
+            max_timestamp: NO_TIMESTAMP,
+            offset_of_max_timestamp: -1,
+            last_offset: None,
+            first_timestamp: None,
+            appends_closed: false,
+            aborted: false,
+            records_compressed: false,
+            built_records: None,
+        })
+    }
+
+    /// Builder with defaults used by the producer for a new batch.
+    pub fn for_producer(
+        buffer: BytesMut,
+        compression_type: CompressionType,
+        write_limit: usize,
+    ) -> Result<MemoryRecordsBuilder> {
+        MemoryRecordsBuilder::new(
+            buffer,
+            compression_type,
+            TimestampType::CreateTime,
+            0,
+            NO_TIMESTAMP,
+            super::record_batch::NO_PRODUCER_ID,
+            super::record_batch::NO_PRODUCER_EPOCH,
+            super::record_batch::NO_SEQUENCE,
+            false,
+            false,
+            NO_PARTITION_LEADER_EPOCH,
+            write_limit,
+        )
+    }
+
+    pub fn initial_capacity(&self) -> usize {
+        self.initial_capacity
+    }
+
+    /// Take the underlying buffer, so it can be returned to the buffer pool.
+    pub fn take_buffer(&mut self) -> BytesMut {
+        std::mem::take(&mut self.buffer)
+    }
+
+    pub fn compression_type(&self) -> CompressionType {
+        self.compression_type
+    }
+
+    pub fn magic(&self) -> i8 {
+        MAGIC_VALUE_V2
+    }
+
+    pub fn is_control_batch(&self) -> bool {
+        self.is_control_batch
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        self.is_transactional
+    }
+
+    pub fn producer_id(&self) -> i64 {
+        self.producer_id
+    }
+
+    pub fn producer_epoch(&self) -> i16 {
+        self.producer_epoch
+    }
+
+    pub fn base_sequence(&self) -> i32 {
+        self.base_sequence
+    }
+
+    pub fn num_records(&self) -> i32 {
+        self.num_records
+    }
+
+    pub fn max_timestamp(&self) -> i64 {
+        self.max_timestamp
+    }
+
+    pub fn offset_of_max_timestamp(&self) -> i64 {
+        self.offset_of_max_timestamp
+    }
+
+    /// Get the records built by the builder. `close` must be called first.
+    pub fn build(&mut self) -> Result<MemoryRecords> {
+        if self.aborted {
+            return Err(KafkaError::IllegalState(
+                "Attempting to build an aborted record batch".to_owned(),
+            ));
+        }
+        self.close()?;
+        Ok(self.built_records.clone().unwrap_or_default())
+    }
+
+    pub fn set_producer_state(
+        &mut self,
+        producer_id: i64,
+        producer_epoch: i16,
+        base_sequence: i32,
+        is_transactional: bool,
+    ) -> Result<()> {
+        if self.is_closed() {
+            // Sequence numbers are assigned when the batch is closed while the accumulator is being drained.
+            // If the resulting ProduceRequest to the partition leader failed for a retriable error, the batch will
+            // be re queued. In this case, we should not attempt to set the state again, since changing the producerId and sequence
+            // once a batch has been sent to the broker risks introducing duplicates.
+            return Err(KafkaError::IllegalState(
+                "Trying to set producer state of an already closed batch. This indicates a bug on the client.".to_owned(),
+            ));
+        }
+        self.producer_id = producer_id;
+        self.producer_epoch = producer_epoch;
+        self.base_sequence = base_sequence;
+        self.is_transactional = is_transactional;
+        Ok(())
+    }
+
+    /// Reopen a closed builder and rewrite producer state in the batch header on the next close.
+    pub fn reopen_and_rewrite_producer_state(
+        &mut self,
+        producer_id: i64,
+        producer_epoch: i16,
+        base_sequence: i32,
+        is_transactional: bool,
+    ) -> Result<()> {
+        if self.aborted {
+            return Err(KafkaError::IllegalState(
+                "Should not reopen a batch which is already aborted.".to_owned(),
+            ));
+        }
+        if let Some(built_records) = self.built_records.take() {
+            self.buffer = BytesMut::from(built_records.buffer().as_ref());
+        }
+        self.producer_id = producer_id;
+        self.producer_epoch = producer_epoch;
+        self.base_sequence = base_sequence;
+        self.is_transactional = is_transactional;
+        Ok(())
+    }
+
+    /// Release resources required for record appends. Further appends are rejected.
+    pub fn close_for_record_appends(&mut self) {
+        self.appends_closed = true;
+    }
+
+    pub fn abort(&mut self) {
+        self.close_for_record_appends();
+        self.buffer.clear();
+        self.aborted = true;
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+
+    pub fn close(&mut self) -> Result<()> {
+        if self.aborted {
+            return Err(KafkaError::IllegalState(
+                "Cannot close MemoryRecordsBuilder as it has already been aborted".to_owned(),
+            ));
+        }
+        if self.built_records.is_some() {
+            return Ok(());
+        }
+        self.close_for_record_appends();
+        if self.num_records == 0 {
+            self.buffer.clear();
+            self.built_records = Some(MemoryRecords::empty());
+        } else {
+            // a reopened batch keeps its records as they were compressed on the first close
+            if self.compression_type != CompressionType::None && !self.records_compressed {
+                let mut compressed = BytesMut::new();
+                self.compression_type.compress(
+                    &self.buffer[RECORDS_OFFSET..],
+                    MAGIC_VALUE_V2,
+                    &mut compressed,
+                )?;
+                // the pooled buffer is reused for the compressed records
+                self.buffer.truncate(RECORDS_OFFSET);
+                self.buffer.extend_from_slice(&compressed);
+                self.records_compressed = true;
+            }
+            self.write_default_batch_header();
+            self.built_records = Some(MemoryRecords::new(self.buffer.split().freeze()));
+        }
+        Ok(())
+    }
+
+    fn write_default_batch_header(&mut self) {
+        let size = self.buffer.len();
+        let last_offset_delta =
+            (self.last_offset.unwrap_or(self.base_offset) - self.base_offset) as i32;
+        let max_timestamp = if self.timestamp_type == TimestampType::LogAppendTime {
+            self.log_append_time
+        } else {
+            self.max_timestamp
+        };
+        let attributes = compute_attributes(
+            self.compression_type,
+            self.timestamp_type,
+            self.is_transactional,
+            self.is_control_batch,
+        );
+
+        let mut header = &mut self.buffer[..RECORDS_OFFSET];
+        header.put_i64(self.base_offset);
+        header.put_i32((size - LOG_OVERHEAD) as i32);
+        header.put_i32(self.partition_leader_epoch);
+        header.put_i8(MAGIC_VALUE_V2);
+        header.put_u32(0);
+        header.put_i16(attributes);
+        header.put_i32(last_offset_delta);
+        header.put_i64(self.first_timestamp.unwrap_or(NO_TIMESTAMP));
+        header.put_i64(max_timestamp);
+        header.put_i64(self.producer_id);
+        header.put_i16(self.producer_epoch);
+        header.put_i32(self.base_sequence);
+        header.put_i32(self.num_records);
+
+        let crc = crc32c::compute(&self.buffer[ATTRIBUTES_OFFSET..]);
+        (&mut self.buffer[CRC_OFFSET..]).put_u32(crc);
+    }
+
+    /// Append a record at the given offset.
+    pub fn append_with_offset(
+        &mut self,
+        offset: i64,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> Result<()> {
+        if self.is_control_batch {
+            return Err(KafkaError::IllegalArgument(
+                "Control records can only be appended to control batches".to_owned(),
+            ));
+        }
+        if let Some(last_offset) = self.last_offset {
+            if offset <= last_offset {
+                return Err(KafkaError::IllegalArgument(format!(
+                    "Illegal offset {} following previous offset {} (Offsets must increase monotonically).",
+                    offset, last_offset
+                )));
+            }
+        }
+        if timestamp < 0 && timestamp != NO_TIMESTAMP {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Invalid negative timestamp {}",
+                timestamp
+            )));
+        }
+        if self.appends_closed {
+            return Err(KafkaError::IllegalState(
+                "Tried to append a record, but MemoryRecordsBuilder is closed for record appends"
+                    .to_owned(),
+            ));
+        }
+
+        let first_timestamp = *self.first_timestamp.get_or_insert(timestamp);
+        let offset_delta = (offset - self.base_offset) as i32;
+        let timestamp_delta = timestamp - first_timestamp;
+        let size_in_bytes = DefaultRecord::write_to(
+            &mut self.buffer,
+            offset_delta,
+            timestamp_delta,
+            key,
+            value,
+            headers,
+        );
+
+        self.num_records += 1;
+        self.uncompressed_records_size_in_bytes += size_in_bytes;
+        self.last_offset = Some(offset);
+        if timestamp > self.max_timestamp {
+            self.max_timestamp = timestamp;
+            self.offset_of_max_timestamp = offset;
+        }
+        Ok(())
+    }
+
+    /// Append a new record at the next sequential offset.
+    pub fn append(
+        &mut self,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> Result<()> {
+        let offset = self.next_sequential_offset();
+        self.append_with_offset(offset, timestamp, key, value, headers)
+    }
+
+    fn next_sequential_offset(&self) -> i64 {
+        self.last_offset
+            .map(|last_offset| last_offset + 1)
+            .unwrap_or(self.base_offset)
+    }
+
+    /// Get an estimate of the number of bytes written (based on the estimation factor hard-coded
+    /// in `CompressionType`).
+    pub fn estimated_bytes_written(&self) -> usize {
+        if self.compression_type == CompressionType::None {
+            RECORDS_OFFSET + self.uncompressed_records_size_in_bytes
+        } else {
+            // estimate the written bytes to the underlying byte buffer based on uncompressed written bytes
+            RECORDS_OFFSET
+                + (self.uncompressed_records_size_in_bytes as f32
+                    * COMPRESSION_RATE_ESTIMATION_FACTOR) as usize
+        }
+    }
+
+    /// Check if we have room for a new record containing the given key/value pair. If no records
+    /// have been appended, then this returns true.
+    pub fn has_room_for(
+        &self,
+        timestamp: i64,
+        key: Option<&[u8]>,
+        value: Option<&[u8]>,
+        headers: &[RecordHeader],
+    ) -> bool {
+        if self.is_full() {
+            return false;
+        }
+
+        // We always allow at least one record to be appended (the buffer will grow as needed)
+        if self.num_records == 0 {
+            return true;
+        }
+
+        let next_offset_delta = self
+            .last_offset
+            .map(|last_offset| (last_offset - self.base_offset + 1) as i32)
+            .unwrap_or(0);
+        let timestamp_delta = self
+            .first_timestamp
+            .map(|first_timestamp| timestamp - first_timestamp)
+            .unwrap_or(0);
+        let record_size =
+            DefaultRecord::size_in_bytes(next_offset_delta, timestamp_delta, key, value, headers);
+        self.write_limit >= self.estimated_bytes_written() + record_size
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.built_records.is_some()
+    }
+
+    pub fn is_full(&self) -> bool {
+        // note that the write limit is respected only after the first record is added which ensures we can always
+        // create non-empty batches (this is used to disable batching when the producer's batch size is set to 0).
+        self.appends_closed
+            || (self.num_records > 0 && self.write_limit <= self.estimated_bytes_written())
+    }
+
+    /// Get an estimate of the number of bytes written to the underlying buffer. The returned
+    /// value is exactly correct if the record set is not compressed or if the builder has been
+    /// closed.
+    pub fn estimated_size_in_bytes(&self) -> usize {
+        match &self.built_records {
+            Some(built_records) => built_records.size_in_bytes(),
+            None => self.estimated_bytes_written(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use crate::common::record::{
+        compression_type::CompressionType, default_record_batch::RECORDS_OFFSET,
+    };
+
+    use super::MemoryRecordsBuilder;
+
+    const COMPRESSION_TYPES: [CompressionType; 5] = [
+        CompressionType::None,
+        CompressionType::Gzip,
+        CompressionType::Snappy,
+        CompressionType::Lz4,
+        CompressionType::Zstd,
+    ];
+
+    fn build(compression_type: CompressionType) -> MemoryRecordsBuilder {
+        let mut builder = MemoryRecordsBuilder::for_producer(
+            BytesMut::with_capacity(1024),
+            compression_type,
+            1 << 20,
+        )
+        .unwrap();
+        for i in 0..100 {
+            let key = format!("key-{}", i % 3);
+            builder
+                .append(1000 + i, Some(key.as_bytes()), Some(b"value"), &[])
+                .unwrap();
+        }
+        builder
+    }
+
+    #[test]
+    fn compressed_batches_round_trip() {
+        for compression_type in COMPRESSION_TYPES {
+            let records = build(compression_type).build().unwrap();
+            let batches = records.batches().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(1, batches.len());
+            let batch = &batches[0];
+            batch.ensure_valid().unwrap();
+            assert_eq!(compression_type, batch.compression_type().unwrap());
+            assert_eq!(records.size_in_bytes(), batch.size_in_bytes());
+
+            let decoded = batch.records().unwrap();
+            assert_eq!(100, decoded.len());
+            for (i, record) in decoded.iter().enumerate() {
+                assert_eq!(i as i64, record.offset);
+                assert_eq!(1000 + i as i64, record.timestamp);
+                assert_eq!(
+                    format!("key-{}", i % 3).as_bytes(),
+                    record.key.as_deref().unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn compressed_batches_are_smaller_than_estimated() {
+        for compression_type in COMPRESSION_TYPES {
+            let mut builder = build(compression_type);
+            let estimated = builder.estimated_size_in_bytes();
+            assert!(estimated > RECORDS_OFFSET);
+            builder.close().unwrap();
+            assert!(builder.estimated_size_in_bytes() <= estimated);
+        }
+    }
+
+    #[test]
+    fn reopened_batch_is_not_compressed_twice() {
+        for compression_type in COMPRESSION_TYPES {
+            let mut builder = build(compression_type);
+            builder.close().unwrap();
+            let size = builder.estimated_size_in_bytes();
+            builder
+                .reopen_and_rewrite_producer_state(7, 1, 42, false)
+                .unwrap();
+            let records = builder.build().unwrap();
+            assert_eq!(size, records.size_in_bytes());
+            let batch = records.batches().next().unwrap().unwrap();
+            batch.ensure_valid().unwrap();
+            assert_eq!(7, batch.producer_id());
+            assert_eq!(42, batch.base_sequence());
+            assert_eq!(100, batch.records().unwrap().len());
+        }
+    }
+}
@@ -1 +1,9 @@
+pub mod compression_type;
+pub mod control_record_type;
+pub mod default_record;
+pub mod default_record_batch;
+pub mod legacy_record_batch;
+pub mod memory_records;
+pub mod memory_records_builder;
+pub mod record_batch;
 pub mod timestamp_type;
@@ -0,0 +1,154 @@
+//! Constants shared by all record batch versions - `org.apache.kafka.common.record.RecordBatch`
+
+use crate::common::errors::Result;
+
+use super::{
+    compression_type::CompressionType, default_record::DefaultRecord,
+    default_record_batch::DefaultRecordBatch, legacy_record_batch::LegacyRecordBatch,
+    timestamp_type::TimestampType,
+};
+
+pub const MAGIC_VALUE_V0: i8 = 0;
+pub const MAGIC_VALUE_V1: i8 = 1;
+pub const MAGIC_VALUE_V2: i8 = 2;
+
+/// The current "magic" value
+pub const CURRENT_MAGIC_VALUE: i8 = MAGIC_VALUE_V2;
+
+/// Timestamp value for records without a timestamp
+pub const NO_TIMESTAMP: i64 = -1;
+
+/// Values used in the v2 record format by non-idempotent/non-transactional producers or when
+/// up-converting from an older format.
+pub const NO_PRODUCER_ID: i64 = -1;
+pub const NO_PRODUCER_EPOCH: i16 = -1;
+pub const NO_SEQUENCE: i32 = -1;
+
+/// Used to indicate an unknown leader epoch, which will be the case when the record set is
+/// first created by the producer.
+pub const NO_PARTITION_LEADER_EPOCH: i32 = -1;
+
+/// A record batch of any magic, as read from `MemoryRecords`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordBatch {
+    /// A batch of magic v2
+    Default(DefaultRecordBatch),
+    /// A single message of magic v0 or v1, which may wrap compressed inner messages
+    Legacy(LegacyRecordBatch),
+}
+
+impl RecordBatch {
+    pub fn magic(&self) -> i8 {
+        match self {
+            RecordBatch::Default(batch) => batch.magic(),
+            RecordBatch::Legacy(batch) => batch.magic(),
+        }
+    }
+
+    pub fn base_offset(&self) -> i64 {
+        match self {
+            RecordBatch::Default(batch) => batch.base_offset(),
+            RecordBatch::Legacy(batch) => batch.base_offset(),
+        }
+    }
+
+    pub fn last_offset(&self) -> i64 {
+        match self {
+            RecordBatch::Default(batch) => batch.last_offset(),
+            RecordBatch::Legacy(batch) => batch.last_offset(),
+        }
+    }
+
+    pub fn next_offset(&self) -> i64 {
+        self.last_offset() + 1
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            RecordBatch::Default(batch) => batch.size_in_bytes(),
+            RecordBatch::Legacy(batch) => batch.size_in_bytes(),
+        }
+    }
+
+    pub fn compression_type(&self) -> Result<CompressionType> {
+        match self {
+            RecordBatch::Default(batch) => batch.compression_type(),
+            RecordBatch::Legacy(batch) => batch.compression_type(),
+        }
+    }
+
+    pub fn timestamp_type(&self) -> TimestampType {
+        match self {
+            RecordBatch::Default(batch) => batch.timestamp_type(),
+            RecordBatch::Legacy(batch) => batch.timestamp_type(),
+        }
+    }
+
+    pub fn partition_leader_epoch(&self) -> i32 {
+        match self {
+            RecordBatch::Default(batch) => batch.partition_leader_epoch(),
+            RecordBatch::Legacy(_) => NO_PARTITION_LEADER_EPOCH,
+        }
+    }
+
+    pub fn producer_id(&self) -> i64 {
+        match self {
+            RecordBatch::Default(batch) => batch.producer_id(),
+            RecordBatch::Legacy(_) => NO_PRODUCER_ID,
+        }
+    }
+
+    pub fn producer_epoch(&self) -> i16 {
+        match self {
+            RecordBatch::Default(batch) => batch.producer_epoch(),
+            RecordBatch::Legacy(_) => NO_PRODUCER_EPOCH,
+        }
+    }
+
+    pub fn base_sequence(&self) -> i32 {
+        match self {
+            RecordBatch::Default(batch) => batch.base_sequence(),
+            RecordBatch::Legacy(_) => NO_SEQUENCE,
+        }
+    }
+
+    pub fn last_sequence(&self) -> i32 {
+        match self {
+            RecordBatch::Default(batch) => batch.last_sequence(),
+            RecordBatch::Legacy(_) => NO_SEQUENCE,
+        }
+    }
+
+    pub fn has_producer_id(&self) -> bool {
+        self.producer_id() >= 0
+    }
+
+    pub fn is_transactional(&self) -> bool {
+        match self {
+            RecordBatch::Default(batch) => batch.is_transactional(),
+            RecordBatch::Legacy(_) => false,
+        }
+    }
+
+    pub fn is_control_batch(&self) -> bool {
+        match self {
+            RecordBatch::Default(batch) => batch.is_control_batch(),
+            RecordBatch::Legacy(_) => false,
+        }
+    }
+
+    pub fn ensure_valid(&self) -> Result<()> {
+        match self {
+            RecordBatch::Default(batch) => batch.ensure_valid(),
+            RecordBatch::Legacy(batch) => batch.ensure_valid(),
+        }
+    }
+
+    /// Decode all records of this batch.
+    pub fn records(&self) -> Result<Vec<DefaultRecord>> {
+        match self {
+            RecordBatch::Default(batch) => batch.records(),
+            RecordBatch::Legacy(batch) => batch.records(),
+        }
+    }
+}
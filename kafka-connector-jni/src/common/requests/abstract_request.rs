@@ -0,0 +1,277 @@
+use std::fmt::{self, Display};
+
+use bytes::{Bytes, BytesMut};
+
+use crate::common::{errors::Result, protocol::api_keys::ApiKeys};
+
+use super::{
+    add_offsets_to_txn_request::AddOffsetsToTxnRequest,
+    add_partitions_to_txn_request::AddPartitionsToTxnRequest,
+    api_versions_request::ApiVersionsRequest, create_acls_request::CreateAclsRequest,
+    create_partitions_request::CreatePartitionsRequest, create_topics_request::CreateTopicsRequest,
+    delete_acls_request::DeleteAclsRequest, delete_groups_request::DeleteGroupsRequest,
+    delete_topics_request::DeleteTopicsRequest, describe_acls_request::DescribeAclsRequest,
+    describe_configs_request::DescribeConfigsRequest,
+    describe_groups_request::DescribeGroupsRequest, end_txn_request::EndTxnRequest,
+    fetch_request::FetchRequest, find_coordinator_request::FindCoordinatorRequest,
+    heartbeat_request::HeartbeatRequest,
+    incremental_alter_configs_request::IncrementalAlterConfigsRequest,
+    init_producer_id_request::InitProducerIdRequest, join_group_request::JoinGroupRequest,
+    leave_group_request::LeaveGroupRequest, list_groups_request::ListGroupsRequest,
+    list_offsets_request::ListOffsetsRequest, metadata_request::MetadataRequest,
+    offset_commit_request::OffsetCommitRequest, offset_delete_request::OffsetDeleteRequest,
+    offset_fetch_request::OffsetFetchRequest,
+    offsets_for_leader_epoch_request::OffsetsForLeaderEpochRequest,
+    produce_request::ProduceRequest, request_header::RequestHeader,
+    sync_group_request::SyncGroupRequest, txn_offset_commit_request::TxnOffsetCommitRequest,
+};
+
+/// A request which can be sent to a broker through a `KafkaClient`.
+#[derive(Debug, Clone)]
+pub enum AbstractRequest {
+    AddOffsetsToTxn(AddOffsetsToTxnRequest),
+    AddPartitionsToTxn(AddPartitionsToTxnRequest),
+    ApiVersions(ApiVersionsRequest),
+    CreateAcls(CreateAclsRequest),
+    CreatePartitions(CreatePartitionsRequest),
+    CreateTopics(CreateTopicsRequest),
+    DeleteAcls(DeleteAclsRequest),
+    DeleteGroups(DeleteGroupsRequest),
+    DeleteTopics(DeleteTopicsRequest),
+    DescribeAcls(DescribeAclsRequest),
+    DescribeConfigs(DescribeConfigsRequest),
+    DescribeGroups(DescribeGroupsRequest),
+    EndTxn(EndTxnRequest),
+    Fetch(FetchRequest),
+    FindCoordinator(FindCoordinatorRequest),
+    Heartbeat(HeartbeatRequest),
+    IncrementalAlterConfigs(IncrementalAlterConfigsRequest),
+    InitProducerId(InitProducerIdRequest),
+    JoinGroup(JoinGroupRequest),
+    LeaveGroup(LeaveGroupRequest),
+    ListGroups(ListGroupsRequest),
+    ListOffsets(ListOffsetsRequest),
+    Metadata(MetadataRequest),
+    OffsetCommit(OffsetCommitRequest),
+    OffsetDelete(OffsetDeleteRequest),
+    OffsetFetch(OffsetFetchRequest),
+    OffsetsForLeaderEpoch(OffsetsForLeaderEpochRequest),
+    Produce(ProduceRequest),
+    SyncGroup(SyncGroupRequest),
+    TxnOffsetCommit(TxnOffsetCommitRequest),
+}
+
+impl AbstractRequest {
+    pub fn api_key(&self) -> ApiKeys {
+        match self {
+            AbstractRequest::AddOffsetsToTxn(_) => ApiKeys::AddOffsetsToTxn,
+            AbstractRequest::AddPartitionsToTxn(_) => ApiKeys::AddPartitionsToTxn,
+            AbstractRequest::ApiVersions(_) => ApiKeys::ApiVersions,
+            AbstractRequest::CreateAcls(_) => ApiKeys::CreateAcls,
+            AbstractRequest::CreatePartitions(_) => ApiKeys::CreatePartitions,
+            AbstractRequest::CreateTopics(_) => ApiKeys::CreateTopics,
+            AbstractRequest::DeleteAcls(_) => ApiKeys::DeleteAcls,
+            AbstractRequest::DeleteGroups(_) => ApiKeys::DeleteGroups,
+            AbstractRequest::DeleteTopics(_) => ApiKeys::DeleteTopics,
+            AbstractRequest::DescribeAcls(_) => ApiKeys::DescribeAcls,
+            AbstractRequest::DescribeConfigs(_) => ApiKeys::DescribeConfigs,
+            AbstractRequest::DescribeGroups(_) => ApiKeys::DescribeGroups,
+            AbstractRequest::EndTxn(_) => ApiKeys::EndTxn,
+            AbstractRequest::Fetch(_) => ApiKeys::Fetch,
+            AbstractRequest::FindCoordinator(_) => ApiKeys::FindCoordinator,
+            AbstractRequest::Heartbeat(_) => ApiKeys::Heartbeat,
+            AbstractRequest::IncrementalAlterConfigs(_) => ApiKeys::IncrementalAlterConfigs,
+            AbstractRequest::InitProducerId(_) => ApiKeys::InitProducerId,
+            AbstractRequest::JoinGroup(_) => ApiKeys::JoinGroup,
+            AbstractRequest::LeaveGroup(_) => ApiKeys::LeaveGroup,
+            AbstractRequest::ListGroups(_) => ApiKeys::ListGroups,
+            AbstractRequest::ListOffsets(_) => ApiKeys::ListOffsets,
+            AbstractRequest::Metadata(_) => ApiKeys::Metadata,
+            AbstractRequest::OffsetCommit(_) => ApiKeys::OffsetCommit,
+            AbstractRequest::OffsetDelete(_) => ApiKeys::OffsetDelete,
+            AbstractRequest::OffsetFetch(_) => ApiKeys::OffsetFetch,
+            AbstractRequest::OffsetsForLeaderEpoch(_) => ApiKeys::OffsetForLeaderEpoch,
+            AbstractRequest::Produce(_) => ApiKeys::Produce,
+            AbstractRequest::SyncGroup(_) => ApiKeys::SyncGroup,
+            AbstractRequest::TxnOffsetCommit(_) => ApiKeys::TxnOffsetCommit,
+        }
+    }
+
+    /// Write the body of the request in `version`, failing with `UnsupportedVersion` if the
+    /// request uses a feature `version` does not have.
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        match self {
+            AbstractRequest::AddOffsetsToTxn(request) => request.write_to(buffer, version),
+            AbstractRequest::AddPartitionsToTxn(request) => request.write_to(buffer, version),
+            AbstractRequest::ApiVersions(request) => request.write_to(buffer, version),
+            AbstractRequest::CreateAcls(request) => request.write_to(buffer, version),
+            AbstractRequest::CreatePartitions(request) => request.write_to(buffer, version),
+            AbstractRequest::CreateTopics(request) => request.write_to(buffer, version),
+            AbstractRequest::DeleteAcls(request) => request.write_to(buffer, version),
+            AbstractRequest::DeleteGroups(request) => request.write_to(buffer, version),
+            AbstractRequest::DeleteTopics(request) => request.write_to(buffer, version),
+            AbstractRequest::DescribeAcls(request) => request.write_to(buffer, version),
+            AbstractRequest::DescribeConfigs(request) => request.write_to(buffer, version),
+            AbstractRequest::DescribeGroups(request) => request.write_to(buffer, version),
+            AbstractRequest::EndTxn(request) => request.write_to(buffer, version),
+            AbstractRequest::Fetch(request) => request.write_to(buffer, version),
+            AbstractRequest::FindCoordinator(request) => request.write_to(buffer, version)?,
+            AbstractRequest::Heartbeat(request) => request.write_to(buffer, version)?,
+            AbstractRequest::IncrementalAlterConfigs(request) => request.write_to(buffer, version),
+            AbstractRequest::InitProducerId(request) => request.write_to(buffer, version)?,
+            AbstractRequest::JoinGroup(request) => request.write_to(buffer, version)?,
+            AbstractRequest::LeaveGroup(request) => request.write_to(buffer, version)?,
+            AbstractRequest::ListGroups(request) => request.validate(version)?,
+            AbstractRequest::ListOffsets(request) => request.write_to(buffer, version)?,
+            AbstractRequest::Metadata(request) => request.write_to(buffer, version),
+            AbstractRequest::OffsetCommit(request) => request.write_to(buffer, version)?,
+            AbstractRequest::OffsetDelete(request) => request.write_to(buffer, version),
+            AbstractRequest::OffsetFetch(request) => request.write_to(buffer, version)?,
+            AbstractRequest::OffsetsForLeaderEpoch(request) => request.write_to(buffer, version),
+            AbstractRequest::Produce(request) => request.write_to(buffer, version),
+            AbstractRequest::SyncGroup(request) => request.write_to(buffer, version)?,
+            AbstractRequest::TxnOffsetCommit(request) => request.write_to(buffer, version)?,
+        }
+        Ok(())
+    }
+
+    /// The header followed by the body, as sent after the size of the frame.
+    pub fn serialize_with_header(&self, header: &RequestHeader) -> Result<Bytes> {
+        let mut buffer = BytesMut::new();
+        header.write_to(&mut buffer);
+        self.write_to(&mut buffer, header.api_version)?;
+        Ok(buffer.freeze())
+    }
+}
+
+impl Display for AbstractRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbstractRequest::AddOffsetsToTxn(request) => request.fmt(f),
+            AbstractRequest::AddPartitionsToTxn(request) => request.fmt(f),
+            AbstractRequest::ApiVersions(request) => request.fmt(f),
+            AbstractRequest::CreateAcls(request) => request.fmt(f),
+            AbstractRequest::CreatePartitions(request) => request.fmt(f),
+            AbstractRequest::CreateTopics(request) => request.fmt(f),
+            AbstractRequest::DeleteAcls(request) => request.fmt(f),
+            AbstractRequest::DeleteGroups(request) => request.fmt(f),
+            AbstractRequest::DeleteTopics(request) => request.fmt(f),
+            AbstractRequest::DescribeAcls(request) => request.fmt(f),
+            AbstractRequest::DescribeConfigs(request) => request.fmt(f),
+            AbstractRequest::DescribeGroups(request) => request.fmt(f),
+            AbstractRequest::EndTxn(request) => request.fmt(f),
+            AbstractRequest::Fetch(request) => request.fmt(f),
+            AbstractRequest::FindCoordinator(request) => request.fmt(f),
+            AbstractRequest::Heartbeat(request) => request.fmt(f),
+            AbstractRequest::IncrementalAlterConfigs(request) => request.fmt(f),
+            AbstractRequest::InitProducerId(request) => request.fmt(f),
+            AbstractRequest::JoinGroup(request) => request.fmt(f),
+            AbstractRequest::LeaveGroup(request) => request.fmt(f),
+            AbstractRequest::ListGroups(request) => request.fmt(f),
+            AbstractRequest::ListOffsets(request) => request.fmt(f),
+            AbstractRequest::Metadata(request) => request.fmt(f),
+            AbstractRequest::OffsetCommit(request) => request.fmt(f),
+            AbstractRequest::OffsetDelete(request) => request.fmt(f),
+            AbstractRequest::OffsetFetch(request) => request.fmt(f),
+            AbstractRequest::OffsetsForLeaderEpoch(request) => request.fmt(f),
+            AbstractRequest::Produce(request) => request.fmt(f),
+            AbstractRequest::SyncGroup(request) => request.fmt(f),
+            AbstractRequest::TxnOffsetCommit(request) => request.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::{
+        errors::KafkaError,
+        protocol::api_keys::ApiKeys,
+        requests::{
+            heartbeat_request::HeartbeatRequest, init_producer_id_request::InitProducerIdRequest,
+            metadata_request::MetadataRequest, request_header::RequestHeader,
+        },
+    };
+
+    use super::AbstractRequest;
+
+    fn serialize(request: &AbstractRequest, version: i16) -> Vec<u8> {
+        let header = RequestHeader::new(request.api_key(), version, "c", 7);
+        request.serialize_with_header(&header).unwrap().to_vec()
+    }
+
+    #[test]
+    fn heartbeat_request_bytes() {
+        let request = AbstractRequest::Heartbeat(HeartbeatRequest::new("g", 1, "m", None));
+        assert_eq!(
+            serialize(&request, 3),
+            [
+                0, 12, 0, 3, 0, 0, 0, 7, 0, 1, b'c', // header
+                0, 1, b'g', 0, 0, 0, 1, 0, 1, b'm', 0xff, 0xff,
+            ]
+        );
+        assert_eq!(
+            serialize(&request, 0),
+            [0, 12, 0, 0, 0, 0, 0, 7, 0, 1, b'c', 0, 1, b'g', 0, 0, 0, 1, 0, 1, b'm']
+        );
+
+        let request =
+            AbstractRequest::Heartbeat(HeartbeatRequest::new("g", 1, "m", Some("i".to_owned())));
+        let header = RequestHeader::new(ApiKeys::Heartbeat, 2, "c", 7);
+        assert!(matches!(
+            request.serialize_with_header(&header),
+            Err(KafkaError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn metadata_request_bytes() {
+        let request = AbstractRequest::Metadata(MetadataRequest::new(vec!["t".to_owned()], true));
+        assert_eq!(
+            serialize(&request, 8),
+            [0, 3, 0, 8, 0, 0, 0, 7, 0, 1, b'c', 0, 0, 0, 1, 0, 1, b't', 1, 0, 0]
+        );
+        let request = AbstractRequest::Metadata(MetadataRequest::all_topics());
+        assert_eq!(
+            serialize(&request, 4),
+            [0, 3, 0, 4, 0, 0, 0, 7, 0, 1, b'c', 0xff, 0xff, 0xff, 0xff, 1]
+        );
+    }
+
+    #[test]
+    fn flexible_init_producer_id_request_bytes() {
+        let request = AbstractRequest::InitProducerId(InitProducerIdRequest::new(
+            Some("t".to_owned()),
+            60_000,
+            -1,
+            -1,
+        ));
+        // the header version 2 and the compact string of the flexible versions
+        assert_eq!(
+            serialize(&request, 2),
+            [0, 22, 0, 2, 0, 0, 0, 7, 0, 1, b'c', 0, 2, b't', 0, 0, 0xea, 0x60, 0]
+        );
+        assert_eq!(
+            serialize(&request, 1),
+            [0, 22, 0, 1, 0, 0, 0, 7, 0, 1, b'c', 0, 1, b't', 0, 0, 0xea, 0x60]
+        );
+
+        let request = AbstractRequest::InitProducerId(InitProducerIdRequest::new(
+            Some("t".to_owned()),
+            60_000,
+            5,
+            1,
+        ));
+        assert_eq!(
+            serialize(&request, 3),
+            [
+                0, 22, 0, 3, 0, 0, 0, 7, 0, 1, b'c', 0, 2, b't', 0, 0, 0xea, 0x60, 0, 0, 0, 0, 0,
+                0, 0, 5, 0, 1, 0,
+            ]
+        );
+        let header = RequestHeader::new(ApiKeys::InitProducerId, 2, "c", 7);
+        assert!(matches!(
+            request.serialize_with_header(&header),
+            Err(KafkaError::UnsupportedVersion(_))
+        ));
+    }
+}
@@ -0,0 +1,315 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::api_keys::ApiKeys,
+};
+
+use super::{
+    add_offsets_to_txn_response::AddOffsetsToTxnResponse,
+    add_partitions_to_txn_response::AddPartitionsToTxnResponse,
+    api_versions_response::ApiVersionsResponse, create_acls_response::CreateAclsResponse,
+    create_partitions_response::CreatePartitionsResponse,
+    create_topics_response::CreateTopicsResponse, delete_acls_response::DeleteAclsResponse,
+    delete_groups_response::DeleteGroupsResponse, delete_topics_response::DeleteTopicsResponse,
+    describe_acls_response::DescribeAclsResponse,
+    describe_configs_response::DescribeConfigsResponse,
+    describe_groups_response::DescribeGroupsResponse, end_txn_response::EndTxnResponse,
+    fetch_response::FetchResponse, find_coordinator_response::FindCoordinatorResponse,
+    heartbeat_response::HeartbeatResponse,
+    incremental_alter_configs_response::IncrementalAlterConfigsResponse,
+    init_producer_id_response::InitProducerIdResponse, join_group_response::JoinGroupResponse,
+    leave_group_response::LeaveGroupResponse, list_groups_response::ListGroupsResponse,
+    list_offsets_response::ListOffsetsResponse, metadata_response::MetadataResponse,
+    offset_commit_response::OffsetCommitResponse, offset_delete_response::OffsetDeleteResponse,
+    offset_fetch_response::OffsetFetchResponse,
+    offsets_for_leader_epoch_response::OffsetsForLeaderEpochResponse,
+    produce_response::ProduceResponse, sync_group_response::SyncGroupResponse,
+    txn_offset_commit_response::TxnOffsetCommitResponse,
+};
+
+/// A response received from a broker through a `KafkaClient`.
+#[derive(Debug, Clone)]
+pub enum AbstractResponse {
+    AddOffsetsToTxn(AddOffsetsToTxnResponse),
+    AddPartitionsToTxn(AddPartitionsToTxnResponse),
+    ApiVersions(ApiVersionsResponse),
+    CreateAcls(CreateAclsResponse),
+    CreatePartitions(CreatePartitionsResponse),
+    CreateTopics(CreateTopicsResponse),
+    DeleteAcls(DeleteAclsResponse),
+    DeleteGroups(DeleteGroupsResponse),
+    DeleteTopics(DeleteTopicsResponse),
+    DescribeAcls(DescribeAclsResponse),
+    DescribeConfigs(DescribeConfigsResponse),
+    DescribeGroups(DescribeGroupsResponse),
+    EndTxn(EndTxnResponse),
+    Fetch(FetchResponse),
+    FindCoordinator(FindCoordinatorResponse),
+    Heartbeat(HeartbeatResponse),
+    IncrementalAlterConfigs(IncrementalAlterConfigsResponse),
+    InitProducerId(InitProducerIdResponse),
+    JoinGroup(JoinGroupResponse),
+    LeaveGroup(LeaveGroupResponse),
+    ListGroups(ListGroupsResponse),
+    ListOffsets(ListOffsetsResponse),
+    Metadata(MetadataResponse),
+    OffsetCommit(OffsetCommitResponse),
+    OffsetDelete(OffsetDeleteResponse),
+    OffsetFetch(OffsetFetchResponse),
+    OffsetsForLeaderEpoch(OffsetsForLeaderEpochResponse),
+    Produce(ProduceResponse),
+    SyncGroup(SyncGroupResponse),
+    TxnOffsetCommit(TxnOffsetCommitResponse),
+}
+
+impl AbstractResponse {
+    /// Read the body of a response to a request of `api_key` in `version`.
+    pub fn parse(api_key: ApiKeys, buffer: &mut Bytes, version: i16) -> Result<AbstractResponse> {
+        let response = match api_key {
+            ApiKeys::AddOffsetsToTxn => AbstractResponse::AddOffsetsToTxn(
+                AddOffsetsToTxnResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::AddPartitionsToTxn => AbstractResponse::AddPartitionsToTxn(
+                AddPartitionsToTxnResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::ApiVersions => {
+                AbstractResponse::ApiVersions(ApiVersionsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::CreateAcls => {
+                AbstractResponse::CreateAcls(CreateAclsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::CreatePartitions => AbstractResponse::CreatePartitions(
+                CreatePartitionsResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::CreateTopics => {
+                AbstractResponse::CreateTopics(CreateTopicsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::DeleteAcls => {
+                AbstractResponse::DeleteAcls(DeleteAclsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::DeleteGroups => {
+                AbstractResponse::DeleteGroups(DeleteGroupsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::DeleteTopics => {
+                AbstractResponse::DeleteTopics(DeleteTopicsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::DescribeAcls => {
+                AbstractResponse::DescribeAcls(DescribeAclsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::DescribeConfigs => AbstractResponse::DescribeConfigs(
+                DescribeConfigsResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::DescribeGroups => AbstractResponse::DescribeGroups(
+                DescribeGroupsResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::EndTxn => {
+                AbstractResponse::EndTxn(EndTxnResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::Fetch => AbstractResponse::Fetch(FetchResponse::read_from(buffer, version)?),
+            ApiKeys::FindCoordinator => AbstractResponse::FindCoordinator(
+                FindCoordinatorResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::Heartbeat => {
+                AbstractResponse::Heartbeat(HeartbeatResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::IncrementalAlterConfigs => AbstractResponse::IncrementalAlterConfigs(
+                IncrementalAlterConfigsResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::InitProducerId => AbstractResponse::InitProducerId(
+                InitProducerIdResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::JoinGroup => {
+                AbstractResponse::JoinGroup(JoinGroupResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::LeaveGroup => {
+                AbstractResponse::LeaveGroup(LeaveGroupResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::ListGroups => {
+                AbstractResponse::ListGroups(ListGroupsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::ListOffsets => {
+                AbstractResponse::ListOffsets(ListOffsetsResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::Metadata => {
+                AbstractResponse::Metadata(MetadataResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::OffsetCommit => {
+                AbstractResponse::OffsetCommit(OffsetCommitResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::OffsetDelete => {
+                AbstractResponse::OffsetDelete(OffsetDeleteResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::OffsetFetch => {
+                AbstractResponse::OffsetFetch(OffsetFetchResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::OffsetForLeaderEpoch => AbstractResponse::OffsetsForLeaderEpoch(
+                OffsetsForLeaderEpochResponse::read_from(buffer, version)?,
+            ),
+            ApiKeys::Produce => {
+                AbstractResponse::Produce(ProduceResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::SyncGroup => {
+                AbstractResponse::SyncGroup(SyncGroupResponse::read_from(buffer, version)?)
+            }
+            ApiKeys::TxnOffsetCommit => AbstractResponse::TxnOffsetCommit(
+                TxnOffsetCommitResponse::read_from(buffer, version)?,
+            ),
+            _ => {
+                return Err(KafkaError::IllegalArgument(format!(
+                    "Unsupported response type {}",
+                    api_key
+                )))
+            }
+        };
+        Ok(response)
+    }
+
+    pub fn api_key(&self) -> ApiKeys {
+        match self {
+            AbstractResponse::AddOffsetsToTxn(_) => ApiKeys::AddOffsetsToTxn,
+            AbstractResponse::AddPartitionsToTxn(_) => ApiKeys::AddPartitionsToTxn,
+            AbstractResponse::ApiVersions(_) => ApiKeys::ApiVersions,
+            AbstractResponse::CreateAcls(_) => ApiKeys::CreateAcls,
+            AbstractResponse::CreatePartitions(_) => ApiKeys::CreatePartitions,
+            AbstractResponse::CreateTopics(_) => ApiKeys::CreateTopics,
+            AbstractResponse::DeleteAcls(_) => ApiKeys::DeleteAcls,
+            AbstractResponse::DeleteGroups(_) => ApiKeys::DeleteGroups,
+            AbstractResponse::DeleteTopics(_) => ApiKeys::DeleteTopics,
+            AbstractResponse::DescribeAcls(_) => ApiKeys::DescribeAcls,
+            AbstractResponse::DescribeConfigs(_) => ApiKeys::DescribeConfigs,
+            AbstractResponse::DescribeGroups(_) => ApiKeys::DescribeGroups,
+            AbstractResponse::EndTxn(_) => ApiKeys::EndTxn,
+            AbstractResponse::Fetch(_) => ApiKeys::Fetch,
+            AbstractResponse::FindCoordinator(_) => ApiKeys::FindCoordinator,
+            AbstractResponse::Heartbeat(_) => ApiKeys::Heartbeat,
+            AbstractResponse::IncrementalAlterConfigs(_) => ApiKeys::IncrementalAlterConfigs,
+            AbstractResponse::InitProducerId(_) => ApiKeys::InitProducerId,
+            AbstractResponse::JoinGroup(_) => ApiKeys::JoinGroup,
+            AbstractResponse::LeaveGroup(_) => ApiKeys::LeaveGroup,
+            AbstractResponse::ListGroups(_) => ApiKeys::ListGroups,
+            AbstractResponse::ListOffsets(_) => ApiKeys::ListOffsets,
+            AbstractResponse::Metadata(_) => ApiKeys::Metadata,
+            AbstractResponse::OffsetCommit(_) => ApiKeys::OffsetCommit,
+            AbstractResponse::OffsetDelete(_) => ApiKeys::OffsetDelete,
+            AbstractResponse::OffsetFetch(_) => ApiKeys::OffsetFetch,
+            AbstractResponse::OffsetsForLeaderEpoch(_) => ApiKeys::OffsetForLeaderEpoch,
+            AbstractResponse::Produce(_) => ApiKeys::Produce,
+            AbstractResponse::SyncGroup(_) => ApiKeys::SyncGroup,
+            AbstractResponse::TxnOffsetCommit(_) => ApiKeys::TxnOffsetCommit,
+        }
+    }
+
+    /// Whether the client should honor the throttle time of the response, older versions are
+    /// throttled by the broker before the response is sent.
+    pub fn should_client_throttle(&self, version: i16) -> bool {
+        let first_version = match self.api_key() {
+            ApiKeys::Fetch => 8,
+            ApiKeys::Metadata => 6,
+            ApiKeys::Produce => 6,
+            ApiKeys::ListOffsets | ApiKeys::CreateTopics | ApiKeys::JoinGroup => 3,
+            ApiKeys::OffsetCommit | ApiKeys::OffsetFetch => 4,
+            ApiKeys::ApiVersions
+            | ApiKeys::DeleteTopics
+            | ApiKeys::DescribeConfigs
+            | ApiKeys::DescribeGroups
+            | ApiKeys::FindCoordinator
+            | ApiKeys::Heartbeat
+            | ApiKeys::LeaveGroup
+            | ApiKeys::ListGroups
+            | ApiKeys::SyncGroup => 2,
+            ApiKeys::IncrementalAlterConfigs | ApiKeys::OffsetDelete => 0,
+            ApiKeys::OffsetForLeaderEpoch => return false,
+            _ => 1,
+        };
+        version >= first_version
+    }
+
+    pub fn throttle_time_ms(&self) -> i32 {
+        match self {
+            AbstractResponse::AddOffsetsToTxn(response) => response.throttle_time_ms,
+            AbstractResponse::AddPartitionsToTxn(response) => response.throttle_time_ms,
+            AbstractResponse::ApiVersions(response) => response.throttle_time_ms,
+            AbstractResponse::CreateAcls(response) => response.throttle_time_ms,
+            AbstractResponse::CreatePartitions(response) => response.throttle_time_ms,
+            AbstractResponse::CreateTopics(response) => response.throttle_time_ms,
+            AbstractResponse::DeleteAcls(response) => response.throttle_time_ms,
+            AbstractResponse::DeleteGroups(response) => response.throttle_time_ms,
+            AbstractResponse::DeleteTopics(response) => response.throttle_time_ms,
+            AbstractResponse::DescribeAcls(response) => response.throttle_time_ms,
+            AbstractResponse::DescribeConfigs(response) => response.throttle_time_ms,
+            AbstractResponse::DescribeGroups(response) => response.throttle_time_ms,
+            AbstractResponse::EndTxn(response) => response.throttle_time_ms,
+            AbstractResponse::Fetch(response) => response.throttle_time_ms,
+            AbstractResponse::FindCoordinator(response) => response.throttle_time_ms,
+            AbstractResponse::Heartbeat(response) => response.throttle_time_ms,
+            AbstractResponse::IncrementalAlterConfigs(response) => response.throttle_time_ms,
+            AbstractResponse::InitProducerId(response) => response.throttle_time_ms,
+            AbstractResponse::JoinGroup(response) => response.throttle_time_ms,
+            AbstractResponse::LeaveGroup(response) => response.throttle_time_ms,
+            AbstractResponse::ListGroups(response) => response.throttle_time_ms,
+            AbstractResponse::ListOffsets(response) => response.throttle_time_ms,
+            AbstractResponse::Metadata(response) => response.throttle_time_ms,
+            AbstractResponse::OffsetCommit(response) => response.throttle_time_ms,
+            AbstractResponse::OffsetDelete(response) => response.throttle_time_ms,
+            AbstractResponse::OffsetFetch(response) => response.throttle_time_ms,
+            AbstractResponse::OffsetsForLeaderEpoch(response) => response.throttle_time_ms,
+            AbstractResponse::Produce(response) => response.throttle_time_ms,
+            AbstractResponse::SyncGroup(response) => response.throttle_time_ms,
+            AbstractResponse::TxnOffsetCommit(response) => response.throttle_time_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use crate::common::{
+        protocol::{api_keys::ApiKeys, errors::Errors},
+        requests::response_header::ResponseHeader,
+    };
+
+    use super::AbstractResponse;
+
+    #[test]
+    fn parse_heartbeat_response() {
+        let mut buffer = Bytes::from_static(&[0, 0, 0, 7, 0, 0, 0, 100, 0, 27]);
+        let header = ResponseHeader::read_from(&mut buffer, ApiKeys::Heartbeat, 1).unwrap();
+        assert_eq!(header.correlation_id, 7);
+        let response = AbstractResponse::parse(ApiKeys::Heartbeat, &mut buffer, 1).unwrap();
+        match &response {
+            AbstractResponse::Heartbeat(response) => {
+                assert_eq!(response.error, Errors::RebalanceInProgress)
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+        assert_eq!(response.throttle_time_ms(), 100);
+        assert!(response.should_client_throttle(2));
+        assert!(!response.should_client_throttle(1));
+    }
+
+    #[test]
+    fn parse_flexible_init_producer_id_response() {
+        let mut buffer = Bytes::from_static(&[
+            0, 0, 0, 7, 0, // header with tagged fields
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5, 0, 1, 0,
+        ]);
+        ResponseHeader::read_from(&mut buffer, ApiKeys::InitProducerId, 2).unwrap();
+        match AbstractResponse::parse(ApiKeys::InitProducerId, &mut buffer, 2).unwrap() {
+            AbstractResponse::InitProducerId(response) => {
+                assert_eq!(response.error, Errors::None);
+                assert_eq!(response.producer_id, 5);
+                assert_eq!(response.producer_epoch, 1);
+            }
+            response => panic!("Unexpected response {:?}", response),
+        }
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn short_responses_are_errors() {
+        let mut buffer = Bytes::from_static(&[0, 0]);
+        assert!(AbstractResponse::parse(ApiKeys::Heartbeat, &mut buffer, 1).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::protocol::types;
+
+#[derive(Debug, Clone)]
+pub struct AddOffsetsToTxnRequest {
+    pub transactional_id: String,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub group_id: String,
+}
+
+impl AddOffsetsToTxnRequest {
+    pub fn new(
+        transactional_id: impl Into<String>,
+        producer_id: i64,
+        producer_epoch: i16,
+        group_id: impl Into<String>,
+    ) -> AddOffsetsToTxnRequest {
+        AddOffsetsToTxnRequest {
+            transactional_id: transactional_id.into(),
+            producer_id,
+            producer_epoch,
+            group_id: group_id.into(),
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_string(buffer, &self.transactional_id);
+        buffer.put_i64(self.producer_id);
+        buffer.put_i16(self.producer_epoch);
+        types::write_string(buffer, &self.group_id);
+    }
+}
+
+impl Display for AddOffsetsToTxnRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AddOffsetsToTxnRequest(transactionalId={}, producerId={}, producerEpoch={}, groupId={})",
+            self.transactional_id, self.producer_id, self.producer_epoch, self.group_id
+        )
+    }
+}
@@ -0,0 +1,31 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `NotCoordinator`, `CoordinatorNotAvailable`,
+/// `CoordinatorLoadInProgress`, `InvalidProducerIdMapping`, `InvalidProducerEpoch`,
+/// `InvalidTxnState`, `GroupAuthorizationFailed` and `TransactionalIdAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct AddOffsetsToTxnResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+}
+
+impl AddOffsetsToTxnResponse {
+    pub fn new(error: Errors) -> AddOffsetsToTxnResponse {
+        AddOffsetsToTxnResponse {
+            throttle_time_ms: 0,
+            error,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<AddOffsetsToTxnResponse> {
+        Ok(AddOffsetsToTxnResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            error: types::read_error(buffer)?,
+        })
+    }
+}
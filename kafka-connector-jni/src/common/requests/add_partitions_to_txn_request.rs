@@ -0,0 +1,59 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{protocol::types, topic_partition::TopicPartition};
+
+#[derive(Debug, Clone)]
+pub struct AddPartitionsToTxnRequest {
+    pub transactional_id: String,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub partitions: Vec<TopicPartition>,
+}
+
+impl AddPartitionsToTxnRequest {
+    pub fn new(
+        transactional_id: impl Into<String>,
+        producer_id: i64,
+        producer_epoch: i16,
+        partitions: Vec<TopicPartition>,
+    ) -> AddPartitionsToTxnRequest {
+        AddPartitionsToTxnRequest {
+            transactional_id: transactional_id.into(),
+            producer_id,
+            producer_epoch,
+            partitions,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_string(buffer, &self.transactional_id);
+        buffer.put_i64(self.producer_id);
+        buffer.put_i16(self.producer_epoch);
+        let partitions = types::group_by_topic(self.partitions.iter().map(|tp| (tp, ())));
+        types::write_array(buffer, partitions, |buffer, (topic, partitions)| {
+            types::write_string(buffer, topic);
+            types::write_array(buffer, partitions, |buffer, (partition, ())| {
+                buffer.put_i32(partition)
+            });
+        });
+    }
+}
+
+impl Display for AddPartitionsToTxnRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "AddPartitionsToTxnRequest(transactionalId={}, producerId={}, producerEpoch={}, partitions=[",
+            self.transactional_id, self.producer_id, self.producer_epoch
+        )?;
+        for (i, tp) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            tp.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
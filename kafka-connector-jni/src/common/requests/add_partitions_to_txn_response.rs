@@ -0,0 +1,34 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+/// Possible error codes include `NotCoordinator`, `CoordinatorNotAvailable`,
+/// `CoordinatorLoadInProgress`, `InvalidTxnState`, `InvalidProducerIdMapping`,
+/// `TopicAuthorizationFailed`, `TransactionalIdAuthorizationFailed`,
+/// `UnknownTopicOrPartition` and `OperationNotAttempted`.
+#[derive(Debug, Clone, Default)]
+pub struct AddPartitionsToTxnResponse {
+    pub throttle_time_ms: i32,
+    pub errors: IndexMap<TopicPartition, Errors>,
+}
+
+impl AddPartitionsToTxnResponse {
+    pub fn new(errors: IndexMap<TopicPartition, Errors>) -> AddPartitionsToTxnResponse {
+        AddPartitionsToTxnResponse {
+            throttle_time_ms: 0,
+            errors,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<AddPartitionsToTxnResponse> {
+        Ok(AddPartitionsToTxnResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            errors: types::read_partition_errors(buffer)?,
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+/// Asks a broker for the versions of each api it supports, the first request sent on each
+/// connection.
+#[derive(Debug, Clone, Default)]
+pub struct ApiVersionsRequest {}
+
+impl ApiVersionsRequest {
+    pub fn new() -> ApiVersionsRequest {
+        ApiVersionsRequest {}
+    }
+
+    /// The body is empty in all the non flexible versions.
+    pub fn write_to(&self, _buffer: &mut BytesMut, _version: i16) {}
+}
+
+impl Display for ApiVersionsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ApiVersionsRequest()")
+    }
+}
@@ -0,0 +1,72 @@
+use bytes::Bytes;
+
+use crate::{
+    clients::api_versions::ApiVersion,
+    common::{
+        errors::Result,
+        protocol::{api_keys::ApiKeys, errors::Errors, types},
+    },
+};
+
+/// Possible error codes include `UnsupportedVersion`, in which case the broker answers with the
+/// version 0 of the response and only lists its own range of `ApiVersions` versions.
+#[derive(Debug, Clone)]
+pub struct ApiVersionsResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    /// The versions supported by the broker, apis unknown to this client are left out.
+    pub api_keys: Vec<ApiVersion>,
+}
+
+impl ApiVersionsResponse {
+    pub fn new(error: Errors, api_keys: Vec<ApiVersion>) -> ApiVersionsResponse {
+        ApiVersionsResponse {
+            throttle_time_ms: 0,
+            error,
+            api_keys,
+        }
+    }
+
+    /// Read the response to a request of `version`, falling back to version 0 which is what a
+    /// broker which does not support `version` answers with.
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<ApiVersionsResponse> {
+        let mut copy = buffer.clone();
+        match Self::read_version(&mut copy, version) {
+            Ok(response) => {
+                *buffer = copy;
+                Ok(response)
+            }
+            Err(_) if version > 0 => Self::read_version(buffer, 0),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_version(buffer: &mut Bytes, version: i16) -> Result<ApiVersionsResponse> {
+        let error = types::read_error(buffer)?;
+        let api_keys = types::read_array(buffer, |buffer| {
+            let api_key = types::read_i16(buffer)?;
+            let min_version = types::read_i16(buffer)?;
+            let max_version = types::read_i16(buffer)?;
+            Ok(ApiKeys::for_id(api_key)
+                .ok()
+                .map(|api_key| ApiVersion::new(api_key, min_version, max_version)))
+        })?;
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        Ok(ApiVersionsResponse {
+            throttle_time_ms,
+            error,
+            api_keys: api_keys.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The range of `ApiVersions` versions supported by the broker.
+    pub fn api_version(&self, api_key: ApiKeys) -> Option<&ApiVersion> {
+        self.api_keys
+            .iter()
+            .find(|version| version.api_key == api_key)
+    }
+}
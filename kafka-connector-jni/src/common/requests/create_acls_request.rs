@@ -0,0 +1,43 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{acl::acl_binding::AclBinding, protocol::types};
+
+#[derive(Debug, Clone)]
+pub struct CreateAclsRequest {
+    pub creations: Vec<AclBinding>,
+}
+
+impl CreateAclsRequest {
+    pub fn new(creations: Vec<AclBinding>) -> CreateAclsRequest {
+        CreateAclsRequest { creations }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.creations, |buffer, creation| {
+            let pattern = &creation.pattern;
+            let entry = &creation.entry;
+            buffer.put_i8(pattern.resource_type().code());
+            types::write_string(buffer, pattern.name());
+            buffer.put_i8(pattern.pattern_type().code());
+            types::write_string(buffer, entry.principal());
+            types::write_string(buffer, entry.host());
+            buffer.put_i8(entry.operation().code());
+            buffer.put_i8(entry.permission_type().code());
+        });
+    }
+}
+
+impl Display for CreateAclsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CreateAclsRequest(creations=[")?;
+        for (i, creation) in self.creations.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            creation.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
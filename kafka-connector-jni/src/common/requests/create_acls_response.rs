@@ -0,0 +1,51 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `InvalidRequest`, `SecurityDisabled` and
+/// `ClusterAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct AclCreationResult {
+    pub error: Errors,
+    pub error_message: Option<String>,
+}
+
+impl AclCreationResult {
+    pub fn new(error: Errors) -> AclCreationResult {
+        AclCreationResult {
+            error,
+            error_message: None,
+        }
+    }
+}
+
+/// The results are in the same order as the creations of the request.
+#[derive(Debug, Clone)]
+pub struct CreateAclsResponse {
+    pub throttle_time_ms: i32,
+    pub results: Vec<AclCreationResult>,
+}
+
+impl CreateAclsResponse {
+    pub fn new(results: Vec<AclCreationResult>) -> CreateAclsResponse {
+        CreateAclsResponse {
+            throttle_time_ms: 0,
+            results,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<CreateAclsResponse> {
+        Ok(CreateAclsResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            results: types::read_array(buffer, |buffer| {
+                Ok(AclCreationResult {
+                    error: types::read_error(buffer)?,
+                    error_message: types::read_nullable_string(buffer)?,
+                })
+            })?,
+        })
+    }
+}
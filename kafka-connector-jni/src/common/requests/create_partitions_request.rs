@@ -0,0 +1,67 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::protocol::types;
+
+/// The new partition count of a topic.
+#[derive(Debug, Clone)]
+pub struct CreatePartitionsTopic {
+    pub name: String,
+    pub count: i32,
+    /// The broker ids of the replicas of each new partition, `None` to let the controller
+    /// assign them.
+    pub assignments: Option<Vec<Vec<i32>>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatePartitionsRequest {
+    pub topics: Vec<CreatePartitionsTopic>,
+    pub timeout_ms: i32,
+    pub validate_only: bool,
+}
+
+impl CreatePartitionsRequest {
+    pub fn new(
+        topics: Vec<CreatePartitionsTopic>,
+        timeout_ms: i32,
+        validate_only: bool,
+    ) -> CreatePartitionsRequest {
+        CreatePartitionsRequest {
+            topics,
+            timeout_ms,
+            validate_only,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.topics, |buffer, topic| {
+            types::write_string(buffer, &topic.name);
+            buffer.put_i32(topic.count);
+            types::write_nullable_array(
+                buffer,
+                topic.assignments.as_ref(),
+                |buffer, broker_ids| types::write_i32_array(buffer, broker_ids),
+            );
+        });
+        buffer.put_i32(self.timeout_ms);
+        types::write_bool(buffer, self.validate_only);
+    }
+}
+
+impl Display for CreatePartitionsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CreatePartitionsRequest(topics=[")?;
+        for (i, topic) in self.topics.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", topic.name, topic.count)?;
+        }
+        write!(
+            f,
+            "], timeoutMs={}, validateOnly={})",
+            self.timeout_ms, self.validate_only
+        )
+    }
+}
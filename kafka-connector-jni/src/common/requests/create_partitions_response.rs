@@ -0,0 +1,58 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `InvalidPartitions`, `InvalidReplicaAssignment`,
+/// `UnknownTopicOrPartition`, `NotController`, `PolicyViolation`, `ThrottlingQuotaExceeded` and
+/// `TopicAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct CreatePartitionsTopicResult {
+    pub name: String,
+    pub error: Errors,
+    pub error_message: Option<String>,
+}
+
+impl CreatePartitionsTopicResult {
+    pub fn new(name: impl Into<String>, error: Errors) -> CreatePartitionsTopicResult {
+        CreatePartitionsTopicResult {
+            name: name.into(),
+            error,
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatePartitionsResponse {
+    pub throttle_time_ms: i32,
+    pub results: Vec<CreatePartitionsTopicResult>,
+}
+
+impl CreatePartitionsResponse {
+    pub fn new(results: Vec<CreatePartitionsTopicResult>) -> CreatePartitionsResponse {
+        CreatePartitionsResponse {
+            throttle_time_ms: 0,
+            results,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<CreatePartitionsResponse> {
+        Ok(CreatePartitionsResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            results: types::read_array(buffer, |buffer| {
+                Ok(CreatePartitionsTopicResult {
+                    name: types::read_string(buffer)?,
+                    error: types::read_error(buffer)?,
+                    error_message: types::read_nullable_string(buffer)?,
+                })
+            })?,
+        })
+    }
+
+    pub fn has_error(&self, error: Errors) -> bool {
+        self.results.iter().any(|topic| topic.error == error)
+    }
+}
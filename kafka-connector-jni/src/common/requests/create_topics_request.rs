@@ -0,0 +1,84 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::protocol::types;
+
+pub const NO_NUM_PARTITIONS: i32 = -1;
+pub const NO_REPLICATION_FACTOR: i16 = -1;
+
+/// A topic to create.
+#[derive(Debug, Clone)]
+pub struct CreatableTopic {
+    pub name: String,
+    /// The number of partitions, or -1 if a replica assignment is given or the broker default
+    /// should be used.
+    pub num_partitions: i32,
+    /// The replication factor, or -1 if a replica assignment is given or the broker default
+    /// should be used.
+    pub replication_factor: i16,
+    /// The broker ids of the replicas of each partition.
+    pub assignments: IndexMap<i32, Vec<i32>>,
+    pub configs: IndexMap<String, Option<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTopicsRequest {
+    pub topics: Vec<CreatableTopic>,
+    pub timeout_ms: i32,
+    pub validate_only: bool,
+}
+
+impl CreateTopicsRequest {
+    pub fn new(
+        topics: Vec<CreatableTopic>,
+        timeout_ms: i32,
+        validate_only: bool,
+    ) -> CreateTopicsRequest {
+        CreateTopicsRequest {
+            topics,
+            timeout_ms,
+            validate_only,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.topics, |buffer, topic| {
+            types::write_string(buffer, &topic.name);
+            buffer.put_i32(topic.num_partitions);
+            buffer.put_i16(topic.replication_factor);
+            types::write_array(
+                buffer,
+                &topic.assignments,
+                |buffer, (partition, broker_ids)| {
+                    buffer.put_i32(*partition);
+                    types::write_i32_array(buffer, broker_ids);
+                },
+            );
+            types::write_array(buffer, &topic.configs, |buffer, (name, value)| {
+                types::write_string(buffer, name);
+                types::write_nullable_string(buffer, value.as_deref());
+            });
+        });
+        buffer.put_i32(self.timeout_ms);
+        types::write_bool(buffer, self.validate_only);
+    }
+}
+
+impl Display for CreateTopicsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CreateTopicsRequest(topics=[")?;
+        for (i, topic) in self.topics.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(&topic.name)?;
+        }
+        write!(
+            f,
+            "], timeoutMs={}, validateOnly={})",
+            self.timeout_ms, self.validate_only
+        )
+    }
+}
@@ -0,0 +1,75 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// A config of a created topic, as returned by the controller.
+#[derive(Debug, Clone)]
+pub struct CreatableTopicConfigs {
+    pub name: String,
+    pub value: Option<String>,
+    pub read_only: bool,
+    pub config_source: i8,
+    pub is_sensitive: bool,
+}
+
+/// Possible error codes include `InvalidTopicException`, `InvalidPartitions`,
+/// `InvalidReplicationFactor`, `InvalidReplicaAssignment`, `InvalidConfig`, `TopicAlreadyExists`,
+/// `NotController`, `PolicyViolation`, `ThrottlingQuotaExceeded` and `TopicAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct CreatableTopicResult {
+    pub name: String,
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub num_partitions: i32,
+    pub replication_factor: i16,
+    pub configs: Vec<CreatableTopicConfigs>,
+}
+
+impl CreatableTopicResult {
+    pub fn new(name: impl Into<String>, error: Errors) -> CreatableTopicResult {
+        CreatableTopicResult {
+            name: name.into(),
+            error,
+            error_message: None,
+            num_partitions: -1,
+            replication_factor: -1,
+            configs: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateTopicsResponse {
+    pub throttle_time_ms: i32,
+    pub topics: Vec<CreatableTopicResult>,
+}
+
+impl CreateTopicsResponse {
+    pub fn new(topics: Vec<CreatableTopicResult>) -> CreateTopicsResponse {
+        CreateTopicsResponse {
+            throttle_time_ms: 0,
+            topics,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<CreateTopicsResponse> {
+        Ok(CreateTopicsResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            topics: types::read_array(buffer, |buffer| {
+                let mut result = CreatableTopicResult::new(
+                    types::read_string(buffer)?,
+                    types::read_error(buffer)?,
+                );
+                result.error_message = types::read_nullable_string(buffer)?;
+                Ok(result)
+            })?,
+        })
+    }
+
+    pub fn has_error(&self, error: Errors) -> bool {
+        self.topics.iter().any(|topic| topic.error == error)
+    }
+}
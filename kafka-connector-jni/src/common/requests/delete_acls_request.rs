@@ -0,0 +1,37 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+use crate::common::{acl::acl_binding_filter::AclBindingFilter, protocol::types};
+
+use super::describe_acls_request::write_acl_binding_filter;
+
+#[derive(Debug, Clone)]
+pub struct DeleteAclsRequest {
+    pub filters: Vec<AclBindingFilter>,
+}
+
+impl DeleteAclsRequest {
+    pub fn new(filters: Vec<AclBindingFilter>) -> DeleteAclsRequest {
+        DeleteAclsRequest { filters }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.filters, |buffer, filter| {
+            write_acl_binding_filter(buffer, filter)
+        });
+    }
+}
+
+impl Display for DeleteAclsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DeleteAclsRequest(filters=[")?;
+        for (i, filter) in self.filters.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            filter.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
@@ -0,0 +1,76 @@
+use bytes::Bytes;
+
+use crate::common::{
+    acl::acl_binding::AclBinding,
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+use super::describe_acls_response::{read_access_control_entry, read_resource_pattern};
+
+/// An ACL deleted by a filter, or which failed to be deleted.
+#[derive(Debug, Clone)]
+pub struct DeleteAclsMatchingAcl {
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub acl: AclBinding,
+}
+
+/// Possible error codes include `InvalidRequest`, `SecurityDisabled` and
+/// `ClusterAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct DeleteAclsFilterResult {
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub matching_acls: Vec<DeleteAclsMatchingAcl>,
+}
+
+impl DeleteAclsFilterResult {
+    pub fn new(error: Errors, matching_acls: Vec<DeleteAclsMatchingAcl>) -> DeleteAclsFilterResult {
+        DeleteAclsFilterResult {
+            error,
+            error_message: None,
+            matching_acls,
+        }
+    }
+}
+
+/// The filter results are in the same order as the filters of the request.
+#[derive(Debug, Clone)]
+pub struct DeleteAclsResponse {
+    pub throttle_time_ms: i32,
+    pub filter_results: Vec<DeleteAclsFilterResult>,
+}
+
+impl DeleteAclsResponse {
+    pub fn new(filter_results: Vec<DeleteAclsFilterResult>) -> DeleteAclsResponse {
+        DeleteAclsResponse {
+            throttle_time_ms: 0,
+            filter_results,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<DeleteAclsResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let filter_results = types::read_array(buffer, |buffer| {
+            Ok(DeleteAclsFilterResult {
+                error: types::read_error(buffer)?,
+                error_message: types::read_nullable_string(buffer)?,
+                matching_acls: types::read_array(buffer, |buffer| {
+                    Ok(DeleteAclsMatchingAcl {
+                        error: types::read_error(buffer)?,
+                        error_message: types::read_nullable_string(buffer)?,
+                        acl: AclBinding::new(
+                            read_resource_pattern(buffer)?,
+                            read_access_control_entry(buffer)?,
+                        ),
+                    })
+                })?,
+            })
+        })?;
+        Ok(DeleteAclsResponse {
+            throttle_time_ms,
+            filter_results,
+        })
+    }
+}
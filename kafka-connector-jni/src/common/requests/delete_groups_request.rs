@@ -0,0 +1,32 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+use crate::common::protocol::types;
+
+#[derive(Debug, Clone)]
+pub struct DeleteGroupsRequest {
+    pub groups_names: Vec<String>,
+}
+
+impl DeleteGroupsRequest {
+    pub fn new(groups_names: Vec<String>) -> DeleteGroupsRequest {
+        DeleteGroupsRequest { groups_names }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.groups_names, |buffer, name| {
+            types::write_string(buffer, name)
+        });
+    }
+}
+
+impl Display for DeleteGroupsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DeleteGroupsRequest(groupsNames=[{}])",
+            self.groups_names.join(", ")
+        )
+    }
+}
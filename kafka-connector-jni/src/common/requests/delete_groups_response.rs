@@ -0,0 +1,36 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `CoordinatorNotAvailable`,
+/// `NotCoordinator`, `InvalidGroupId`, `GroupIdNotFound`, `NonEmptyGroup` and
+/// `GroupAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct DeleteGroupsResponse {
+    pub throttle_time_ms: i32,
+    pub results: IndexMap<String, Errors>,
+}
+
+impl DeleteGroupsResponse {
+    pub fn new(results: IndexMap<String, Errors>) -> DeleteGroupsResponse {
+        DeleteGroupsResponse {
+            throttle_time_ms: 0,
+            results,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<DeleteGroupsResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let results = types::read_array(buffer, |buffer| {
+            Ok((types::read_string(buffer)?, types::read_error(buffer)?))
+        })?;
+        Ok(DeleteGroupsResponse {
+            throttle_time_ms,
+            results: results.into_iter().collect(),
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::protocol::types;
+
+#[derive(Debug, Clone)]
+pub struct DeleteTopicsRequest {
+    pub topic_names: Vec<String>,
+    pub timeout_ms: i32,
+}
+
+impl DeleteTopicsRequest {
+    pub fn new(topic_names: Vec<String>, timeout_ms: i32) -> DeleteTopicsRequest {
+        DeleteTopicsRequest {
+            topic_names,
+            timeout_ms,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.topic_names, |buffer, name| {
+            types::write_string(buffer, name)
+        });
+        buffer.put_i32(self.timeout_ms);
+    }
+}
+
+impl Display for DeleteTopicsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DeleteTopicsRequest(topicNames=[{}], timeoutMs={})",
+            self.topic_names.join(", "),
+            self.timeout_ms
+        )
+    }
+}
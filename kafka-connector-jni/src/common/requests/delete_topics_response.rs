@@ -0,0 +1,57 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `RequestTimedOut`, `InvalidTopicException`,
+/// `TopicAuthorizationFailed`, `NotController`, `UnknownTopicOrPartition`,
+/// `TopicDeletionDisabled` and `ThrottlingQuotaExceeded`.
+#[derive(Debug, Clone)]
+pub struct DeletableTopicResult {
+    pub name: String,
+    pub error: Errors,
+    pub error_message: Option<String>,
+}
+
+impl DeletableTopicResult {
+    pub fn new(name: impl Into<String>, error: Errors) -> DeletableTopicResult {
+        DeletableTopicResult {
+            name: name.into(),
+            error,
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeleteTopicsResponse {
+    pub throttle_time_ms: i32,
+    pub responses: Vec<DeletableTopicResult>,
+}
+
+impl DeleteTopicsResponse {
+    pub fn new(responses: Vec<DeletableTopicResult>) -> DeleteTopicsResponse {
+        DeleteTopicsResponse {
+            throttle_time_ms: 0,
+            responses,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<DeleteTopicsResponse> {
+        Ok(DeleteTopicsResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            responses: types::read_array(buffer, |buffer| {
+                Ok(DeletableTopicResult::new(
+                    types::read_string(buffer)?,
+                    types::read_error(buffer)?,
+                ))
+            })?,
+        })
+    }
+
+    pub fn has_error(&self, error: Errors) -> bool {
+        self.responses.iter().any(|topic| topic.error == error)
+    }
+}
@@ -0,0 +1,39 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{acl::acl_binding_filter::AclBindingFilter, protocol::types};
+
+/// Write the fields of `filter`, as shared by describe and delete ACL requests.
+pub(crate) fn write_acl_binding_filter(buffer: &mut BytesMut, filter: &AclBindingFilter) {
+    let pattern = &filter.pattern_filter;
+    let entry = &filter.entry_filter;
+    buffer.put_i8(pattern.resource_type.code());
+    types::write_nullable_string(buffer, pattern.name.as_deref());
+    buffer.put_i8(pattern.pattern_type.code());
+    types::write_nullable_string(buffer, entry.principal.as_deref());
+    types::write_nullable_string(buffer, entry.host.as_deref());
+    buffer.put_i8(entry.operation.code());
+    buffer.put_i8(entry.permission_type.code());
+}
+
+#[derive(Debug, Clone)]
+pub struct DescribeAclsRequest {
+    pub filter: AclBindingFilter,
+}
+
+impl DescribeAclsRequest {
+    pub fn new(filter: AclBindingFilter) -> DescribeAclsRequest {
+        DescribeAclsRequest { filter }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        write_acl_binding_filter(buffer, &self.filter);
+    }
+}
+
+impl Display for DescribeAclsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DescribeAclsRequest(filter={})", self.filter)
+    }
+}
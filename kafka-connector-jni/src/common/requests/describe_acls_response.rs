@@ -0,0 +1,76 @@
+use bytes::Bytes;
+
+use crate::common::{
+    acl::{
+        access_control_entry::AccessControlEntry, acl_binding::AclBinding,
+        acl_operation::AclOperation, acl_permission_type::AclPermissionType,
+    },
+    errors::Result,
+    protocol::{errors::Errors, types},
+    resource::{
+        pattern_type::PatternType, resource_pattern::ResourcePattern, resource_type::ResourceType,
+    },
+};
+
+/// Read a resource pattern, as returned by describe and delete ACL responses.
+pub(crate) fn read_resource_pattern(buffer: &mut Bytes) -> Result<ResourcePattern> {
+    let resource_type = ResourceType::from_code(types::read_i8(buffer)?);
+    let name = types::read_string(buffer)?;
+    let pattern_type = PatternType::from_code(types::read_i8(buffer)?);
+    ResourcePattern::new(resource_type, name, pattern_type)
+}
+
+/// Read an access control entry, as returned by describe and delete ACL responses.
+pub(crate) fn read_access_control_entry(buffer: &mut Bytes) -> Result<AccessControlEntry> {
+    let principal = types::read_string(buffer)?;
+    let host = types::read_string(buffer)?;
+    let operation = AclOperation::from_code(types::read_i8(buffer)?);
+    let permission_type = AclPermissionType::from_code(types::read_i8(buffer)?);
+    AccessControlEntry::new(principal, host, operation, permission_type)
+}
+
+/// Possible error codes include `InvalidRequest`, `SecurityDisabled` and
+/// `ClusterAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct DescribeAclsResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub acls: Vec<AclBinding>,
+}
+
+impl DescribeAclsResponse {
+    pub fn new(error: Errors, acls: Vec<AclBinding>) -> DescribeAclsResponse {
+        DescribeAclsResponse {
+            throttle_time_ms: 0,
+            error,
+            error_message: None,
+            acls,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<DescribeAclsResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let error = types::read_error(buffer)?;
+        let error_message = types::read_nullable_string(buffer)?;
+        let resources = types::read_array(buffer, |buffer| {
+            let pattern = read_resource_pattern(buffer)?;
+            let entries = types::read_array(buffer, read_access_control_entry)?;
+            Ok((pattern, entries))
+        })?;
+        let acls = resources
+            .into_iter()
+            .flat_map(|(pattern, entries)| {
+                entries
+                    .into_iter()
+                    .map(move |entry| AclBinding::new(pattern.clone(), entry))
+            })
+            .collect();
+        Ok(DescribeAclsResponse {
+            throttle_time_ms,
+            error,
+            error_message,
+            acls,
+        })
+    }
+}
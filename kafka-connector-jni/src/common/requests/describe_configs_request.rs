@@ -0,0 +1,76 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{config::config_resource::ConfigResource, protocol::types};
+
+/// A resource to describe the configs of.
+#[derive(Debug, Clone)]
+pub struct DescribeConfigsResource {
+    pub resource: ConfigResource,
+    /// The configuration keys to list, `None` for all keys.
+    pub configuration_keys: Option<Vec<String>>,
+}
+
+impl DescribeConfigsResource {
+    pub fn new(resource: ConfigResource) -> DescribeConfigsResource {
+        DescribeConfigsResource {
+            resource,
+            configuration_keys: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DescribeConfigsRequest {
+    pub resources: Vec<DescribeConfigsResource>,
+    pub include_synonyms: bool,
+    pub include_documentation: bool,
+}
+
+impl DescribeConfigsRequest {
+    pub fn new(
+        resources: Vec<DescribeConfigsResource>,
+        include_synonyms: bool,
+        include_documentation: bool,
+    ) -> DescribeConfigsRequest {
+        DescribeConfigsRequest {
+            resources,
+            include_synonyms,
+            include_documentation,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) {
+        types::write_array(buffer, &self.resources, |buffer, resource| {
+            buffer.put_i8(resource.resource.type_.id());
+            types::write_string(buffer, &resource.resource.name);
+            types::write_nullable_array(
+                buffer,
+                resource.configuration_keys.as_ref(),
+                |buffer, key| types::write_string(buffer, key),
+            );
+        });
+        types::write_bool(buffer, self.include_synonyms);
+        if version >= 3 {
+            types::write_bool(buffer, self.include_documentation);
+        }
+    }
+}
+
+impl Display for DescribeConfigsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DescribeConfigsRequest(resources=[")?;
+        for (i, resource) in self.resources.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            resource.resource.fmt(f)?;
+        }
+        write!(
+            f,
+            "], includeSynonyms={}, includeDocumentation={})",
+            self.include_synonyms, self.include_documentation
+        )
+    }
+}
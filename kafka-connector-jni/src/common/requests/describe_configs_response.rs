@@ -0,0 +1,120 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    config::config_resource::{ConfigResource, ConfigResourceType},
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Read the type and name of a resource, as returned by the config responses.
+pub(crate) fn read_config_resource(buffer: &mut Bytes) -> Result<ConfigResource> {
+    let type_ = ConfigResourceType::for_id(types::read_i8(buffer)?);
+    Ok(ConfigResource::new(type_, types::read_string(buffer)?))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescribeConfigsSynonym {
+    pub name: String,
+    pub value: Option<String>,
+    pub source: i8,
+}
+
+/// A single config of a described resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescribeConfigsResourceResult {
+    pub name: String,
+    pub value: Option<String>,
+    pub read_only: bool,
+    pub config_source: i8,
+    pub is_sensitive: bool,
+    pub synonyms: Vec<DescribeConfigsSynonym>,
+    pub config_type: i8,
+    pub documentation: Option<String>,
+}
+
+/// Possible error codes include `InvalidRequest`, `UnknownTopicOrPartition`,
+/// `TopicAuthorizationFailed` and `ClusterAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct DescribeConfigsResult {
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub configs: Vec<DescribeConfigsResourceResult>,
+}
+
+impl DescribeConfigsResult {
+    pub fn new(
+        error: Errors,
+        configs: Vec<DescribeConfigsResourceResult>,
+    ) -> DescribeConfigsResult {
+        DescribeConfigsResult {
+            error,
+            error_message: None,
+            configs,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DescribeConfigsResponse {
+    pub throttle_time_ms: i32,
+    pub results: IndexMap<ConfigResource, DescribeConfigsResult>,
+}
+
+impl DescribeConfigsResponse {
+    pub fn new(
+        results: IndexMap<ConfigResource, DescribeConfigsResult>,
+    ) -> DescribeConfigsResponse {
+        DescribeConfigsResponse {
+            throttle_time_ms: 0,
+            results,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<DescribeConfigsResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let results = types::read_array(buffer, |buffer| {
+            let error = types::read_error(buffer)?;
+            let error_message = types::read_nullable_string(buffer)?;
+            let resource = read_config_resource(buffer)?;
+            let configs = types::read_array(buffer, |buffer| {
+                Ok(DescribeConfigsResourceResult {
+                    name: types::read_string(buffer)?,
+                    value: types::read_nullable_string(buffer)?,
+                    read_only: types::read_bool(buffer)?,
+                    config_source: types::read_i8(buffer)?,
+                    is_sensitive: types::read_bool(buffer)?,
+                    synonyms: types::read_array(buffer, |buffer| {
+                        Ok(DescribeConfigsSynonym {
+                            name: types::read_string(buffer)?,
+                            value: types::read_nullable_string(buffer)?,
+                            source: types::read_i8(buffer)?,
+                        })
+                    })?,
+                    config_type: if version >= 3 {
+                        types::read_i8(buffer)?
+                    } else {
+                        0
+                    },
+                    documentation: if version >= 3 {
+                        types::read_nullable_string(buffer)?
+                    } else {
+                        None
+                    },
+                })
+            })?;
+            Ok((
+                resource,
+                DescribeConfigsResult {
+                    error,
+                    error_message,
+                    configs,
+                },
+            ))
+        })?;
+        Ok(DescribeConfigsResponse {
+            throttle_time_ms,
+            results: results.into_iter().collect(),
+        })
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+use crate::common::protocol::types;
+
+#[derive(Debug, Clone)]
+pub struct DescribeGroupsRequest {
+    pub groups: Vec<String>,
+    pub include_authorized_operations: bool,
+}
+
+impl DescribeGroupsRequest {
+    pub fn new(groups: Vec<String>, include_authorized_operations: bool) -> DescribeGroupsRequest {
+        DescribeGroupsRequest {
+            groups,
+            include_authorized_operations,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) {
+        types::write_array(buffer, &self.groups, |buffer, group| {
+            types::write_string(buffer, group)
+        });
+        if version >= 3 {
+            types::write_bool(buffer, self.include_authorized_operations);
+        }
+    }
+}
+
+impl Display for DescribeGroupsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DescribeGroupsRequest(groups=[{}], includeAuthorizedOperations={})",
+            self.groups.join(", "),
+            self.include_authorized_operations
+        )
+    }
+}
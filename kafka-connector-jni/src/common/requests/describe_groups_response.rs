@@ -0,0 +1,97 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescribedGroupMember {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub client_id: String,
+    pub client_host: String,
+    pub member_metadata: Bytes,
+    pub member_assignment: Bytes,
+}
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `CoordinatorNotAvailable`,
+/// `NotCoordinator` and `AuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct DescribedGroup {
+    pub error: Errors,
+    pub group_id: String,
+    pub group_state: String,
+    pub protocol_type: String,
+    pub protocol_data: String,
+    pub members: Vec<DescribedGroupMember>,
+}
+
+impl DescribedGroup {
+    /// Used for groups which failed to be described.
+    pub fn for_error(group_id: impl Into<String>, error: Errors) -> DescribedGroup {
+        DescribedGroup {
+            error,
+            group_id: group_id.into(),
+            group_state: String::new(),
+            protocol_type: String::new(),
+            protocol_data: String::new(),
+            members: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DescribeGroupsResponse {
+    pub throttle_time_ms: i32,
+    pub groups: Vec<DescribedGroup>,
+}
+
+impl DescribeGroupsResponse {
+    pub fn new(groups: Vec<DescribedGroup>) -> DescribeGroupsResponse {
+        DescribeGroupsResponse {
+            throttle_time_ms: 0,
+            groups,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<DescribeGroupsResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        let groups = types::read_array(buffer, |buffer| {
+            let group = DescribedGroup {
+                error: types::read_error(buffer)?,
+                group_id: types::read_string(buffer)?,
+                group_state: types::read_string(buffer)?,
+                protocol_type: types::read_string(buffer)?,
+                protocol_data: types::read_string(buffer)?,
+                members: types::read_array(buffer, |buffer| {
+                    Ok(DescribedGroupMember {
+                        member_id: types::read_string(buffer)?,
+                        group_instance_id: if version >= 4 {
+                            types::read_nullable_string(buffer)?
+                        } else {
+                            None
+                        },
+                        client_id: types::read_string(buffer)?,
+                        client_host: types::read_string(buffer)?,
+                        member_metadata: types::read_bytes(buffer)?,
+                        member_assignment: types::read_bytes(buffer)?,
+                    })
+                })?,
+            };
+            if version >= 3 {
+                // authorized operations, never requested
+                types::read_i32(buffer)?;
+            }
+            Ok(group)
+        })?;
+        Ok(DescribeGroupsResponse {
+            throttle_time_ms,
+            groups,
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::protocol::types;
+
+/// Result of a transaction sent with an `EndTxnRequest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionResult {
+    Abort,
+    Commit,
+}
+
+impl TransactionResult {
+    pub fn id(&self) -> bool {
+        matches!(self, TransactionResult::Commit)
+    }
+}
+
+impl Display for TransactionResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionResult::Abort => f.write_str("ABORT"),
+            TransactionResult::Commit => f.write_str("COMMIT"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EndTxnRequest {
+    pub transactional_id: String,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub result: TransactionResult,
+}
+
+impl EndTxnRequest {
+    pub fn new(
+        transactional_id: impl Into<String>,
+        producer_id: i64,
+        producer_epoch: i16,
+        result: TransactionResult,
+    ) -> EndTxnRequest {
+        EndTxnRequest {
+            transactional_id: transactional_id.into(),
+            producer_id,
+            producer_epoch,
+            result,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_string(buffer, &self.transactional_id);
+        buffer.put_i64(self.producer_id);
+        buffer.put_i16(self.producer_epoch);
+        types::write_bool(buffer, self.result.id());
+    }
+}
+
+impl Display for EndTxnRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "EndTxnRequest(transactionalId={}, producerId={}, producerEpoch={}, result={})",
+            self.transactional_id, self.producer_id, self.producer_epoch, self.result
+        )
+    }
+}
@@ -0,0 +1,31 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `NotCoordinator`, `CoordinatorNotAvailable`,
+/// `CoordinatorLoadInProgress`, `InvalidTxnState`, `InvalidProducerIdMapping`,
+/// `InvalidProducerEpoch`, `ProducerFenced` and `TransactionalIdAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct EndTxnResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+}
+
+impl EndTxnResponse {
+    pub fn new(error: Errors) -> EndTxnResponse {
+        EndTxnResponse {
+            throttle_time_ms: 0,
+            error,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<EndTxnResponse> {
+        Ok(EndTxnResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            error: types::read_error(buffer)?,
+        })
+    }
+}
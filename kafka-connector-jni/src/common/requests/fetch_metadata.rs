@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+/// The session ID used by clients with no session.
+pub const INVALID_SESSION_ID: i32 = 0;
+
+/// The first epoch. When used in a fetch request, indicates that the client wants to create or
+/// recreate a session.
+pub const INITIAL_EPOCH: i32 = 0;
+
+/// An invalid epoch. When used in a fetch request, indicates that the client wants to close any
+/// existing session, and not create a new one.
+pub const FINAL_EPOCH: i32 = -1;
+
+/// Fetch session metadata sent along with a fetch request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FetchMetadata {
+    /// The fetch session ID.
+    pub session_id: i32,
+    /// The fetch session epoch.
+    pub epoch: i32,
+}
+
+impl FetchMetadata {
+    /// The FetchMetadata that is used when initializing a new FetchSessionHandler.
+    pub const INITIAL: FetchMetadata = FetchMetadata {
+        session_id: INVALID_SESSION_ID,
+        epoch: INITIAL_EPOCH,
+    };
+
+    /// The FetchMetadata that is implicitly used for handling older FetchRequests that
+    /// don't include fetch metadata.
+    pub const LEGACY: FetchMetadata = FetchMetadata {
+        session_id: INVALID_SESSION_ID,
+        epoch: FINAL_EPOCH,
+    };
+
+    pub fn new(session_id: i32, epoch: i32) -> FetchMetadata {
+        FetchMetadata { session_id, epoch }
+    }
+
+    /// Returns the next epoch.
+    pub fn next_epoch(prev_epoch: i32) -> i32 {
+        if prev_epoch < 0 {
+            // The next epoch after FINAL_EPOCH is always FINAL_EPOCH itself.
+            FINAL_EPOCH
+        } else if prev_epoch == i32::MAX {
+            1
+        } else {
+            prev_epoch + 1
+        }
+    }
+
+    /// Returns true if this is a full fetch request.
+    pub fn is_full(&self) -> bool {
+        self.epoch == INITIAL_EPOCH || self.epoch == FINAL_EPOCH
+    }
+
+    /// Return the metadata for the next incremental response.
+    pub fn new_incremental(session_id: i32) -> FetchMetadata {
+        FetchMetadata::new(session_id, FetchMetadata::next_epoch(INITIAL_EPOCH))
+    }
+
+    /// Return the metadata for the next error response.
+    pub fn next_close_existing(&self) -> FetchMetadata {
+        FetchMetadata::new(self.session_id, INITIAL_EPOCH)
+    }
+
+    /// Return the metadata for the next full fetch request.
+    pub fn next_close_existing_attempt_new(&self) -> FetchMetadata {
+        FetchMetadata::new(INVALID_SESSION_ID, INITIAL_EPOCH)
+    }
+
+    /// Return the metadata for the next incremental fetch request.
+    pub fn next_incremental(&self) -> FetchMetadata {
+        FetchMetadata::new(self.session_id, FetchMetadata::next_epoch(self.epoch))
+    }
+}
+
+impl Display for FetchMetadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(sessionId=")?;
+        if self.session_id == INVALID_SESSION_ID {
+            f.write_str("INVALID")?;
+        } else {
+            write!(f, "{}", self.session_id)?;
+        }
+        match self.epoch {
+            INITIAL_EPOCH => f.write_str(", epoch=INITIAL)"),
+            FINAL_EPOCH => f.write_str(", epoch=FINAL)"),
+            epoch => write!(f, ", epoch={})", epoch),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    isolation_level::IsolationLevel, protocol::types, topic_partition::TopicPartition,
+};
+
+use super::fetch_metadata::FetchMetadata;
+
+pub const CONSUMER_REPLICA_ID: i32 = -1;
+pub const DEFAULT_RESPONSE_MAX_BYTES: i32 = i32::MAX;
+pub const INVALID_LOG_START_OFFSET: i64 = -1;
+
+/// Fetch position and limits of a single partition.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PartitionData {
+    pub fetch_offset: i64,
+    pub log_start_offset: i64,
+    pub max_bytes: i32,
+    pub current_leader_epoch: Option<i32>,
+    pub last_fetched_epoch: Option<i32>,
+}
+
+impl PartitionData {
+    pub fn new(
+        fetch_offset: i64,
+        log_start_offset: i64,
+        max_bytes: i32,
+        current_leader_epoch: Option<i32>,
+    ) -> PartitionData {
+        PartitionData {
+            fetch_offset,
+            log_start_offset,
+            max_bytes,
+            current_leader_epoch,
+            last_fetched_epoch: None,
+        }
+    }
+}
+
+impl Display for PartitionData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PartitionData(fetchOffset={}, logStartOffset={}, maxBytes={}, currentLeaderEpoch={:?}, lastFetchedEpoch={:?})",
+            self.fetch_offset,
+            self.log_start_offset,
+            self.max_bytes,
+            self.current_leader_epoch,
+            self.last_fetched_epoch
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FetchRequest {
+    pub replica_id: i32,
+    pub max_wait_ms: i32,
+    pub min_bytes: i32,
+    pub max_bytes: i32,
+    pub isolation_level: IsolationLevel,
+    pub metadata: FetchMetadata,
+    /// Partitions to fetch. For an incremental fetch request only added or altered partitions.
+    pub fetch_data: IndexMap<TopicPartition, PartitionData>,
+    /// Partitions to remove from the fetch session.
+    pub to_forget: Vec<TopicPartition>,
+    pub rack_id: String,
+}
+
+impl FetchRequest {
+    /// Create a consumer fetch request.
+    #[allow(clippy::too_many_arguments)]
+    pub fn for_consumer(
+        max_wait_ms: i32,
+        min_bytes: i32,
+        max_bytes: i32,
+        isolation_level: IsolationLevel,
+        metadata: FetchMetadata,
+        fetch_data: IndexMap<TopicPartition, PartitionData>,
+        to_forget: Vec<TopicPartition>,
+        rack_id: impl Into<String>,
+    ) -> FetchRequest {
+        FetchRequest {
+            replica_id: CONSUMER_REPLICA_ID,
+            max_wait_ms,
+            min_bytes,
+            max_bytes,
+            isolation_level,
+            metadata,
+            fetch_data,
+            to_forget,
+            rack_id: rack_id.into(),
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) {
+        buffer.put_i32(self.replica_id);
+        buffer.put_i32(self.max_wait_ms);
+        buffer.put_i32(self.min_bytes);
+        buffer.put_i32(self.max_bytes);
+        buffer.put_i8(self.isolation_level.id());
+        if version >= 7 {
+            buffer.put_i32(self.metadata.session_id);
+            buffer.put_i32(self.metadata.epoch);
+        }
+        types::write_topic_partitions(buffer, &self.fetch_data, |buffer, partition, data| {
+            buffer.put_i32(partition);
+            if version >= 9 {
+                buffer.put_i32(data.current_leader_epoch.unwrap_or(-1));
+            }
+            buffer.put_i64(data.fetch_offset);
+            if version >= 5 {
+                buffer.put_i64(data.log_start_offset);
+            }
+            buffer.put_i32(data.max_bytes);
+        });
+        if version >= 7 {
+            types::write_topic_partitions(
+                buffer,
+                self.to_forget.iter().map(|tp| (tp, ())),
+                |buffer, partition, _| buffer.put_i32(partition),
+            );
+        }
+        if version >= 11 {
+            types::write_string(buffer, &self.rack_id);
+        }
+    }
+}
+
+impl Display for FetchRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FetchRequest(replicaId={}, maxWait={}, minBytes={}, maxBytes={}, fetchData={{",
+            self.replica_id, self.max_wait_ms, self.min_bytes, self.max_bytes
+        )?;
+        for (i, (tp, data)) in self.fetch_data.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", tp, data)?;
+        }
+        write!(f, "}}, isolationLevel={}, toForget=", self.isolation_level)?;
+        for (i, tp) in self.to_forget.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            tp.fmt(f)?;
+        }
+        write!(f, ", metadata={}, rackId={})", self.metadata, self.rack_id)
+    }
+}
@@ -0,0 +1,163 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    record::memory_records::MemoryRecords,
+    topic_partition::TopicPartition,
+};
+
+use super::fetch_metadata::INVALID_SESSION_ID;
+
+pub const INVALID_HIGH_WATERMARK: i64 = -1;
+pub const INVALID_LAST_STABLE_OFFSET: i64 = -1;
+pub const INVALID_LOG_START_OFFSET: i64 = -1;
+pub const INVALID_PREFERRED_REPLICA_ID: i32 = -1;
+
+/// A transaction which was aborted within the fetched range of offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AbortedTransaction {
+    pub producer_id: i64,
+    pub first_offset: i64,
+}
+
+impl AbortedTransaction {
+    pub fn new(producer_id: i64, first_offset: i64) -> AbortedTransaction {
+        AbortedTransaction {
+            producer_id,
+            first_offset,
+        }
+    }
+}
+
+/// Fetch result of a single partition.
+///
+/// Possible error codes include `OffsetOutOfRange`, `UnknownTopicOrPartition`,
+/// `NotLeaderOrFollower`, `ReplicaNotAvailable`, `KafkaStorageError`, `UnknownLeaderEpoch`,
+/// `FencedLeaderEpoch`, `TopicAuthorizationFailed` and `CorruptMessage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionData {
+    pub error: Errors,
+    pub high_watermark: i64,
+    pub last_stable_offset: i64,
+    pub log_start_offset: i64,
+    pub preferred_read_replica: Option<i32>,
+    /// Only returned for `read_committed` fetches.
+    pub aborted_transactions: Option<Vec<AbortedTransaction>>,
+    pub records: MemoryRecords,
+}
+
+impl PartitionData {
+    pub fn new(
+        error: Errors,
+        high_watermark: i64,
+        last_stable_offset: i64,
+        log_start_offset: i64,
+        aborted_transactions: Option<Vec<AbortedTransaction>>,
+        records: MemoryRecords,
+    ) -> PartitionData {
+        PartitionData {
+            error,
+            high_watermark,
+            last_stable_offset,
+            log_start_offset,
+            preferred_read_replica: None,
+            aborted_transactions,
+            records,
+        }
+    }
+
+    pub fn from_error(error: Errors) -> PartitionData {
+        PartitionData::new(
+            error,
+            INVALID_HIGH_WATERMARK,
+            INVALID_LAST_STABLE_OFFSET,
+            INVALID_LOG_START_OFFSET,
+            None,
+            MemoryRecords::empty(),
+        )
+    }
+}
+
+/// Possible top level error codes include `FetchSessionIdNotFound` and
+/// `InvalidFetchSessionEpoch`. Incremental fetch responses only contain partitions which changed
+/// since the previous response.
+#[derive(Debug, Clone)]
+pub struct FetchResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub session_id: i32,
+    pub response_data: IndexMap<TopicPartition, PartitionData>,
+}
+
+impl FetchResponse {
+    pub fn new(
+        error: Errors,
+        session_id: i32,
+        response_data: IndexMap<TopicPartition, PartitionData>,
+    ) -> FetchResponse {
+        FetchResponse {
+            throttle_time_ms: 0,
+            error,
+            session_id,
+            response_data,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<FetchResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let (error, session_id) = if version >= 7 {
+            (types::read_error(buffer)?, types::read_i32(buffer)?)
+        } else {
+            (Errors::None, INVALID_SESSION_ID)
+        };
+        let response_data = types::read_topic_partitions(buffer, |buffer| {
+            let partition = types::read_i32(buffer)?;
+            let error = types::read_error(buffer)?;
+            let high_watermark = types::read_i64(buffer)?;
+            let last_stable_offset = types::read_i64(buffer)?;
+            let log_start_offset = if version >= 5 {
+                types::read_i64(buffer)?
+            } else {
+                INVALID_LOG_START_OFFSET
+            };
+            let aborted_transactions = types::read_nullable_array(buffer, |buffer| {
+                Ok(AbortedTransaction::new(
+                    types::read_i64(buffer)?,
+                    types::read_i64(buffer)?,
+                ))
+            })?;
+            let preferred_read_replica = if version >= 11 {
+                Some(types::read_i32(buffer)?)
+                    .filter(|replica| *replica != INVALID_PREFERRED_REPLICA_ID)
+            } else {
+                None
+            };
+            let records = types::read_nullable_bytes(buffer)?.unwrap_or_default();
+            Ok((
+                partition,
+                PartitionData {
+                    preferred_read_replica,
+                    ..PartitionData::new(
+                        error,
+                        high_watermark,
+                        last_stable_offset,
+                        log_start_offset,
+                        aborted_transactions,
+                        MemoryRecords::new(records),
+                    )
+                },
+            ))
+        })?;
+        Ok(FetchResponse {
+            throttle_time_ms,
+            ..FetchResponse::new(error, session_id, response_data)
+        })
+    }
+
+    /// A response which is not a part of any fetch session.
+    pub fn sessionless(response_data: IndexMap<TopicPartition, PartitionData>) -> FetchResponse {
+        FetchResponse::new(Errors::None, INVALID_SESSION_ID, response_data)
+    }
+}
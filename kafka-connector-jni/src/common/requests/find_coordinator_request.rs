@@ -0,0 +1,72 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+};
+
+/// Type of the coordinator looked up by a `FindCoordinatorRequest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoordinatorType {
+    Group,
+    Transaction,
+}
+
+impl CoordinatorType {
+    pub fn id(&self) -> i8 {
+        match self {
+            CoordinatorType::Group => 0,
+            CoordinatorType::Transaction => 1,
+        }
+    }
+}
+
+impl Display for CoordinatorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoordinatorType::Group => f.write_str("GROUP"),
+            CoordinatorType::Transaction => f.write_str("TRANSACTION"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FindCoordinatorRequest {
+    pub key: String,
+    pub key_type: CoordinatorType,
+}
+
+impl FindCoordinatorRequest {
+    pub fn new(key: impl Into<String>, key_type: CoordinatorType) -> FindCoordinatorRequest {
+        FindCoordinatorRequest {
+            key: key.into(),
+            key_type,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 1 && self.key_type != CoordinatorType::Group {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "Cannot create a v{} FindCoordinator request because we require features supported only in 1 or later.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.key);
+        if version >= 1 {
+            buffer.put_i8(self.key_type.id());
+        }
+        Ok(())
+    }
+}
+
+impl Display for FindCoordinatorRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FindCoordinatorRequest(key={}, keyType={})",
+            self.key, self.key_type
+        )
+    }
+}
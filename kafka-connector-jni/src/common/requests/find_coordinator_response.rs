@@ -0,0 +1,55 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    node::Node,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `CoordinatorNotAvailable`,
+/// `GroupAuthorizationFailed`, `TransactionalIdAuthorizationFailed` and `InvalidRequest`.
+#[derive(Debug, Clone)]
+pub struct FindCoordinatorResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub error_message: Option<String>,
+    pub node: Node,
+}
+
+impl FindCoordinatorResponse {
+    pub fn new(error: Errors, node: Node) -> FindCoordinatorResponse {
+        FindCoordinatorResponse {
+            throttle_time_ms: 0,
+            error,
+            error_message: None,
+            node,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<FindCoordinatorResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        let error = types::read_error(buffer)?;
+        let error_message = if version >= 1 {
+            types::read_nullable_string(buffer)?
+        } else {
+            None
+        };
+        let node_id = types::read_i32(buffer)?;
+        let host = types::read_string(buffer)?;
+        let port = types::read_i32(buffer)?;
+        Ok(FindCoordinatorResponse {
+            throttle_time_ms,
+            error,
+            error_message,
+            node: Node::new(node_id, host, port),
+        })
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error != Errors::None
+    }
+}
@@ -0,0 +1,61 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+};
+
+#[derive(Debug, Clone)]
+pub struct HeartbeatRequest {
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+impl HeartbeatRequest {
+    pub fn new(
+        group_id: impl Into<String>,
+        generation_id: i32,
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+    ) -> HeartbeatRequest {
+        HeartbeatRequest {
+            group_id: group_id.into(),
+            generation_id,
+            member_id: member_id.into(),
+            group_instance_id,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 3 && self.group_instance_id.is_some() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker heartbeat protocol version {} does not support usage of config group.instance.id.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.group_id);
+        buffer.put_i32(self.generation_id);
+        types::write_string(buffer, &self.member_id);
+        if version >= 3 {
+            types::write_nullable_string(buffer, self.group_instance_id.as_deref());
+        }
+        Ok(())
+    }
+}
+
+impl Display for HeartbeatRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HeartbeatRequest(groupId={}, generationId={}, memberId={}, groupInstanceId={})",
+            self.group_id,
+            self.generation_id,
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null")
+        )
+    }
+}
@@ -0,0 +1,36 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `GroupCoordinatorNotAvailable`, `NotCoordinator`,
+/// `IllegalGeneration`, `UnknownMemberId`, `RebalanceInProgress`, `GroupAuthorizationFailed`
+/// and `FencedInstanceId`.
+#[derive(Debug, Clone)]
+pub struct HeartbeatResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+}
+
+impl HeartbeatResponse {
+    pub fn new(error: Errors) -> HeartbeatResponse {
+        HeartbeatResponse {
+            throttle_time_ms: 0,
+            error,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<HeartbeatResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        Ok(HeartbeatResponse {
+            throttle_time_ms,
+            error: types::read_error(buffer)?,
+        })
+    }
+}
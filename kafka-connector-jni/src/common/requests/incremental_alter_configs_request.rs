@@ -0,0 +1,58 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{config::config_resource::ConfigResource, protocol::types};
+
+/// A config update, `config_operation` is the id of an `AlterConfigOp` operation type.
+#[derive(Debug, Clone)]
+pub struct AlterableConfig {
+    pub name: String,
+    pub config_operation: i8,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalAlterConfigsRequest {
+    pub resources: IndexMap<ConfigResource, Vec<AlterableConfig>>,
+    pub validate_only: bool,
+}
+
+impl IncrementalAlterConfigsRequest {
+    pub fn new(
+        resources: IndexMap<ConfigResource, Vec<AlterableConfig>>,
+        validate_only: bool,
+    ) -> IncrementalAlterConfigsRequest {
+        IncrementalAlterConfigsRequest {
+            resources,
+            validate_only,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_array(buffer, &self.resources, |buffer, (resource, configs)| {
+            buffer.put_i8(resource.type_.id());
+            types::write_string(buffer, &resource.name);
+            types::write_array(buffer, configs, |buffer, config| {
+                types::write_string(buffer, &config.name);
+                buffer.put_i8(config.config_operation);
+                types::write_nullable_string(buffer, config.value.as_deref());
+            });
+        });
+        types::write_bool(buffer, self.validate_only);
+    }
+}
+
+impl Display for IncrementalAlterConfigsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IncrementalAlterConfigsRequest(resources=[")?;
+        for (i, resource) in self.resources.keys().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            resource.fmt(f)?;
+        }
+        write!(f, "], validateOnly={})", self.validate_only)
+    }
+}
@@ -0,0 +1,59 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    config::config_resource::ConfigResource,
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+use super::describe_configs_response::read_config_resource;
+
+/// Possible error codes include `InvalidConfig`, `InvalidRequest`, `PolicyViolation`,
+/// `UnknownTopicOrPartition`, `TopicAuthorizationFailed` and `ClusterAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct AlterConfigsResourceResponse {
+    pub error: Errors,
+    pub error_message: Option<String>,
+}
+
+impl AlterConfigsResourceResponse {
+    pub fn new(error: Errors) -> AlterConfigsResourceResponse {
+        AlterConfigsResourceResponse {
+            error,
+            error_message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IncrementalAlterConfigsResponse {
+    pub throttle_time_ms: i32,
+    pub responses: IndexMap<ConfigResource, AlterConfigsResourceResponse>,
+}
+
+impl IncrementalAlterConfigsResponse {
+    pub fn new(
+        responses: IndexMap<ConfigResource, AlterConfigsResourceResponse>,
+    ) -> IncrementalAlterConfigsResponse {
+        IncrementalAlterConfigsResponse {
+            throttle_time_ms: 0,
+            responses,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<IncrementalAlterConfigsResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let responses = types::read_array(buffer, |buffer| {
+            let response = AlterConfigsResourceResponse {
+                error: types::read_error(buffer)?,
+                error_message: types::read_nullable_string(buffer)?,
+            };
+            Ok((read_config_resource(buffer)?, response))
+        })?;
+        Ok(IncrementalAlterConfigsResponse {
+            throttle_time_ms,
+            responses: responses.into_iter().collect(),
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+    record::record_batch::{NO_PRODUCER_EPOCH, NO_PRODUCER_ID},
+};
+
+#[derive(Debug, Clone)]
+pub struct InitProducerIdRequest {
+    pub transactional_id: Option<String>,
+    pub transaction_timeout_ms: i32,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+}
+
+impl InitProducerIdRequest {
+    pub fn new(
+        transactional_id: Option<String>,
+        transaction_timeout_ms: i32,
+        producer_id: i64,
+        producer_epoch: i16,
+    ) -> InitProducerIdRequest {
+        InitProducerIdRequest {
+            transactional_id,
+            transaction_timeout_ms,
+            producer_id,
+            producer_epoch,
+        }
+    }
+
+    /// Versions from 2 are flexible, the producer id and epoch to bump are only sent from 3.
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 3
+            && (self.producer_id != NO_PRODUCER_ID || self.producer_epoch != NO_PRODUCER_EPOCH)
+        {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "Attempted to write a non-default producer id at version {}",
+                version
+            )));
+        }
+        if version >= 2 {
+            types::write_compact_nullable_string(buffer, self.transactional_id.as_deref());
+        } else {
+            types::write_nullable_string(buffer, self.transactional_id.as_deref());
+        }
+        buffer.put_i32(self.transaction_timeout_ms);
+        if version >= 3 {
+            buffer.put_i64(self.producer_id);
+            buffer.put_i16(self.producer_epoch);
+        }
+        if version >= 2 {
+            types::write_empty_tagged_fields(buffer);
+        }
+        Ok(())
+    }
+}
+
+impl Display for InitProducerIdRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "InitProducerIdRequest(transactionalId={}, transactionTimeoutMs={}, producerId={}, producerEpoch={})",
+            self.transactional_id.as_deref().unwrap_or("null"),
+            self.transaction_timeout_ms,
+            self.producer_id,
+            self.producer_epoch
+        )
+    }
+}
@@ -0,0 +1,46 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    record::record_batch::{NO_PRODUCER_EPOCH, NO_PRODUCER_ID},
+};
+
+/// Possible error codes include `NotCoordinator`, `CoordinatorNotAvailable`,
+/// `CoordinatorLoadInProgress`, `TransactionalIdAuthorizationFailed`,
+/// `ClusterAuthorizationFailed`, `InvalidProducerEpoch` and `ProducerFenced`.
+#[derive(Debug, Clone)]
+pub struct InitProducerIdResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+}
+
+impl InitProducerIdResponse {
+    pub fn new(error: Errors, producer_id: i64, producer_epoch: i16) -> InitProducerIdResponse {
+        InitProducerIdResponse {
+            throttle_time_ms: 0,
+            error,
+            producer_id,
+            producer_epoch,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<InitProducerIdResponse> {
+        let response = InitProducerIdResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            error: types::read_error(buffer)?,
+            producer_id: types::read_i64(buffer)?,
+            producer_epoch: types::read_i16(buffer)?,
+        };
+        if version >= 2 {
+            types::skip_tagged_fields(buffer)?;
+        }
+        Ok(response)
+    }
+
+    pub fn from_error(error: Errors) -> InitProducerIdResponse {
+        InitProducerIdResponse::new(error, NO_PRODUCER_ID, NO_PRODUCER_EPOCH)
+    }
+}
@@ -0,0 +1,139 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+};
+
+pub const UNKNOWN_MEMBER_ID: &str = "";
+pub const UNKNOWN_GENERATION_ID: i32 = -1;
+pub const UNKNOWN_PROTOCOL_NAME: &str = "";
+
+// Note: if change this, make sure to update the same value in the broker's GroupMetadata.
+const MAX_GROUP_INSTANCE_ID_LENGTH: usize = 249;
+
+/// Ensures that the provided `id` is a valid group instance id.
+pub fn validate_group_instance_id(id: &str) -> Result<()> {
+    if id.is_empty() {
+        return Err(KafkaError::InvalidConfiguration(
+            "Group instance id must be non-empty string".to_owned(),
+        ));
+    }
+    if id == "." || id == ".." {
+        return Err(KafkaError::InvalidConfiguration(
+            "Group instance id cannot be \".\" or \"..\"".to_owned(),
+        ));
+    }
+    if id.len() > MAX_GROUP_INSTANCE_ID_LENGTH {
+        return Err(KafkaError::InvalidConfiguration(format!(
+            "Group instance id can't be longer than {} characters: {}",
+            MAX_GROUP_INSTANCE_ID_LENGTH, id
+        )));
+    }
+    if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-')
+    {
+        return Err(KafkaError::InvalidConfiguration(format!(
+            "Group instance id \"{}\" is illegal, it contains a character other than ASCII alphanumerics, '.', '_' and '-'",
+            id
+        )));
+    }
+    Ok(())
+}
+
+/// A protocol supported by the joining member, with its serialized metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinGroupRequestProtocol {
+    pub name: String,
+    pub metadata: Bytes,
+}
+
+impl JoinGroupRequestProtocol {
+    pub fn new(name: impl Into<String>, metadata: Bytes) -> JoinGroupRequestProtocol {
+        JoinGroupRequestProtocol {
+            name: name.into(),
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JoinGroupRequest {
+    pub group_id: String,
+    pub session_timeout_ms: i32,
+    pub rebalance_timeout_ms: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub protocol_type: String,
+    pub protocols: Vec<JoinGroupRequestProtocol>,
+}
+
+impl JoinGroupRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        group_id: impl Into<String>,
+        session_timeout_ms: i32,
+        rebalance_timeout_ms: i32,
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        protocol_type: impl Into<String>,
+        protocols: Vec<JoinGroupRequestProtocol>,
+    ) -> JoinGroupRequest {
+        JoinGroupRequest {
+            group_id: group_id.into(),
+            session_timeout_ms,
+            rebalance_timeout_ms,
+            member_id: member_id.into(),
+            group_instance_id,
+            protocol_type: protocol_type.into(),
+            protocols,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 5 && self.group_instance_id.is_some() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker join group protocol version {} does not support usage of config group.instance.id.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.group_id);
+        buffer.put_i32(self.session_timeout_ms);
+        buffer.put_i32(self.rebalance_timeout_ms);
+        types::write_string(buffer, &self.member_id);
+        if version >= 5 {
+            types::write_nullable_string(buffer, self.group_instance_id.as_deref());
+        }
+        types::write_string(buffer, &self.protocol_type);
+        types::write_array(buffer, &self.protocols, |buffer, protocol| {
+            types::write_string(buffer, &protocol.name);
+            types::write_bytes(buffer, &protocol.metadata);
+        });
+        Ok(())
+    }
+}
+
+impl Display for JoinGroupRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "JoinGroupRequest(groupId={}, sessionTimeoutMs={}, rebalanceTimeoutMs={}, memberId={}, groupInstanceId={}, protocolType={}, protocols=[",
+            self.group_id,
+            self.session_timeout_ms,
+            self.rebalance_timeout_ms,
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null"),
+            self.protocol_type
+        )?;
+        for (i, protocol) in self.protocols.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(&protocol.name)?;
+        }
+        f.write_str("])")
+    }
+}
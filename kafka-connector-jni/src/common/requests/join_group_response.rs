@@ -0,0 +1,110 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+use super::join_group_request::{UNKNOWN_GENERATION_ID, UNKNOWN_MEMBER_ID};
+
+/// A member of the group, as reported to the leader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinGroupResponseMember {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub metadata: Bytes,
+}
+
+impl JoinGroupResponseMember {
+    pub fn new(
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        metadata: Bytes,
+    ) -> JoinGroupResponseMember {
+        JoinGroupResponseMember {
+            member_id: member_id.into(),
+            group_instance_id,
+            metadata,
+        }
+    }
+}
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `GroupCoordinatorNotAvailable`,
+/// `NotCoordinator`, `InconsistentGroupProtocol`, `UnknownMemberId`, `InvalidSessionTimeout`,
+/// `GroupAuthorizationFailed`, `GroupMaxSizeReached`, `MemberIdRequired` and
+/// `FencedInstanceId`.
+#[derive(Debug, Clone)]
+pub struct JoinGroupResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub generation_id: i32,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub leader: String,
+    pub member_id: String,
+    /// Members of the group, only sent to the leader.
+    pub members: Vec<JoinGroupResponseMember>,
+}
+
+impl JoinGroupResponse {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        error: Errors,
+        generation_id: i32,
+        protocol_type: Option<String>,
+        protocol_name: Option<String>,
+        leader: impl Into<String>,
+        member_id: impl Into<String>,
+        members: Vec<JoinGroupResponseMember>,
+    ) -> JoinGroupResponse {
+        JoinGroupResponse {
+            throttle_time_ms: 0,
+            error,
+            generation_id,
+            protocol_type,
+            protocol_name,
+            leader: leader.into(),
+            member_id: member_id.into(),
+            members,
+        }
+    }
+
+    pub fn from_error(error: Errors) -> JoinGroupResponse {
+        JoinGroupResponse::new(
+            error,
+            UNKNOWN_GENERATION_ID,
+            None,
+            None,
+            UNKNOWN_MEMBER_ID,
+            UNKNOWN_MEMBER_ID,
+            vec![],
+        )
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<JoinGroupResponse> {
+        Ok(JoinGroupResponse {
+            throttle_time_ms: types::read_i32(buffer)?,
+            error: types::read_error(buffer)?,
+            generation_id: types::read_i32(buffer)?,
+            protocol_type: None,
+            protocol_name: Some(types::read_string(buffer)?),
+            leader: types::read_string(buffer)?,
+            member_id: types::read_string(buffer)?,
+            members: types::read_array(buffer, |buffer| {
+                Ok(JoinGroupResponseMember {
+                    member_id: types::read_string(buffer)?,
+                    group_instance_id: if version >= 5 {
+                        types::read_nullable_string(buffer)?
+                    } else {
+                        None
+                    },
+                    metadata: types::read_bytes(buffer)?,
+                })
+            })?,
+        })
+    }
+
+    pub fn is_leader(&self) -> bool {
+        self.member_id == self.leader
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+};
+
+/// Identity of a member leaving the group.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemberIdentity {
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+}
+
+impl MemberIdentity {
+    pub fn new(member_id: impl Into<String>, group_instance_id: Option<String>) -> MemberIdentity {
+        MemberIdentity {
+            member_id: member_id.into(),
+            group_instance_id,
+        }
+    }
+}
+
+impl Display for MemberIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "MemberIdentity(memberId={}, groupInstanceId={})",
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaveGroupRequest {
+    pub group_id: String,
+    pub members: Vec<MemberIdentity>,
+}
+
+impl LeaveGroupRequest {
+    pub fn new(group_id: impl Into<String>, members: Vec<MemberIdentity>) -> LeaveGroupRequest {
+        LeaveGroupRequest {
+            group_id: group_id.into(),
+            members,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        types::write_string(buffer, &self.group_id);
+        if version >= 3 {
+            types::write_array(buffer, &self.members, |buffer, member| {
+                types::write_string(buffer, &member.member_id);
+                types::write_nullable_string(buffer, member.group_instance_id.as_deref());
+            });
+            return Ok(());
+        }
+        match self.members.as_slice() {
+            [member] if member.group_instance_id.is_none() => {
+                types::write_string(buffer, &member.member_id);
+                Ok(())
+            }
+            [_] => Err(KafkaError::UnsupportedVersion(format!(
+                "Version {} leave group request doesn't support group instance id (the field of static membership)",
+                version
+            ))),
+            members => Err(KafkaError::UnsupportedVersion(format!(
+                "Version {} leave group request only supports single member instance than {} members",
+                version,
+                members.len()
+            ))),
+        }
+    }
+}
+
+impl Display for LeaveGroupRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LeaveGroupRequest(groupId={}, members=[", self.group_id)?;
+        for (i, member) in self.members.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            member.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
@@ -0,0 +1,65 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+use super::leave_group_request::MemberIdentity;
+
+/// Possible error codes include `GroupCoordinatorNotAvailable`, `NotCoordinator`,
+/// `CoordinatorLoadInProgress`, `GroupAuthorizationFailed`, `UnknownMemberId` and
+/// `FencedInstanceId` (the last two at member level).
+#[derive(Debug, Clone)]
+pub struct LeaveGroupResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub members: Vec<(MemberIdentity, Errors)>,
+}
+
+impl LeaveGroupResponse {
+    pub fn new(error: Errors, members: Vec<(MemberIdentity, Errors)>) -> LeaveGroupResponse {
+        LeaveGroupResponse {
+            throttle_time_ms: 0,
+            error,
+            members,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<LeaveGroupResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        let error = types::read_error(buffer)?;
+        let members = if version >= 3 {
+            types::read_array(buffer, |buffer| {
+                let member = MemberIdentity::new(
+                    types::read_string(buffer)?,
+                    types::read_nullable_string(buffer)?,
+                );
+                Ok((member, types::read_error(buffer)?))
+            })?
+        } else {
+            vec![]
+        };
+        Ok(LeaveGroupResponse {
+            throttle_time_ms,
+            error,
+            members,
+        })
+    }
+
+    /// The top level error, or the first member level error if there is no top level one.
+    pub fn top_level_error(&self) -> Errors {
+        if self.error != Errors::None {
+            return self.error;
+        }
+        self.members
+            .iter()
+            .map(|(_, error)| *error)
+            .find(|error| *error != Errors::None)
+            .unwrap_or(Errors::None)
+    }
+}
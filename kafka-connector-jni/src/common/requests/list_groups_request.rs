@@ -0,0 +1,36 @@
+use std::fmt::{self, Display};
+
+use crate::common::errors::{KafkaError, Result};
+
+#[derive(Debug, Clone, Default)]
+pub struct ListGroupsRequest {
+    /// The states of the groups to list, empty to list all groups.
+    pub states_filter: Vec<String>,
+}
+
+impl ListGroupsRequest {
+    pub fn new(states_filter: Vec<String>) -> ListGroupsRequest {
+        ListGroupsRequest { states_filter }
+    }
+
+    /// The request has no field below version 4, which is the first one filtering by state.
+    pub fn validate(&self, version: i16) -> Result<()> {
+        if !self.states_filter.is_empty() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker only supports ListGroups v{}, but we need v4 or newer to request groups by states.",
+                version
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Display for ListGroupsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ListGroupsRequest(statesFilter=[{}])",
+            self.states_filter.join(", ")
+        )
+    }
+}
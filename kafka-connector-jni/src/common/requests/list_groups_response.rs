@@ -0,0 +1,66 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedGroup {
+    pub group_id: String,
+    pub protocol_type: String,
+    /// The group state, `None` for brokers which do not report it.
+    pub group_state: Option<String>,
+}
+
+impl ListedGroup {
+    pub fn new(
+        group_id: impl Into<String>,
+        protocol_type: impl Into<String>,
+        group_state: Option<String>,
+    ) -> ListedGroup {
+        ListedGroup {
+            group_id: group_id.into(),
+            protocol_type: protocol_type.into(),
+            group_state,
+        }
+    }
+}
+
+/// Possible error codes include `CoordinatorNotAvailable`, `CoordinatorLoadInProgress` and
+/// `AuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct ListGroupsResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub groups: Vec<ListedGroup>,
+}
+
+impl ListGroupsResponse {
+    pub fn new(error: Errors, groups: Vec<ListedGroup>) -> ListGroupsResponse {
+        ListGroupsResponse {
+            throttle_time_ms: 0,
+            error,
+            groups,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<ListGroupsResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        Ok(ListGroupsResponse {
+            throttle_time_ms,
+            error: types::read_error(buffer)?,
+            groups: types::read_array(buffer, |buffer| {
+                Ok(ListedGroup::new(
+                    types::read_string(buffer)?,
+                    types::read_string(buffer)?,
+                    None,
+                ))
+            })?,
+        })
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    isolation_level::IsolationLevel,
+    protocol::types,
+    topic_partition::TopicPartition,
+};
+
+pub const EARLIEST_TIMESTAMP: i64 = -2;
+pub const LATEST_TIMESTAMP: i64 = -1;
+pub const MAX_TIMESTAMP: i64 = -3;
+
+pub const CONSUMER_REPLICA_ID: i32 = -1;
+pub const DEBUGGING_REPLICA_ID: i32 = -2;
+
+/// Target timestamp of a single partition lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListOffsetsPartition {
+    pub timestamp: i64,
+    pub current_leader_epoch: Option<i32>,
+}
+
+impl ListOffsetsPartition {
+    pub fn new(timestamp: i64, current_leader_epoch: Option<i32>) -> ListOffsetsPartition {
+        ListOffsetsPartition {
+            timestamp,
+            current_leader_epoch,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListOffsetsRequest {
+    pub replica_id: i32,
+    pub isolation_level: IsolationLevel,
+    pub partitions: IndexMap<TopicPartition, ListOffsetsPartition>,
+}
+
+impl ListOffsetsRequest {
+    pub fn for_consumer(
+        isolation_level: IsolationLevel,
+        partitions: IndexMap<TopicPartition, ListOffsetsPartition>,
+    ) -> ListOffsetsRequest {
+        ListOffsetsRequest {
+            replica_id: CONSUMER_REPLICA_ID,
+            isolation_level,
+            partitions,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 2 && self.isolation_level == IsolationLevel::ReadCommitted {
+            return Err(KafkaError::UnsupportedVersion(
+                "The broker does not support READ_COMMITTED offset lookups".to_owned(),
+            ));
+        }
+        if self
+            .partitions
+            .values()
+            .any(|partition| partition.timestamp == MAX_TIMESTAMP)
+        {
+            return Err(KafkaError::UnsupportedVersion(
+                "The broker does not support max timestamp offset lookups".to_owned(),
+            ));
+        }
+        buffer.put_i32(self.replica_id);
+        if version >= 2 {
+            buffer.put_i8(self.isolation_level.id());
+        }
+        types::write_topic_partitions(buffer, &self.partitions, |buffer, index, partition| {
+            buffer.put_i32(index);
+            if version >= 4 {
+                buffer.put_i32(partition.current_leader_epoch.unwrap_or(-1));
+            }
+            buffer.put_i64(partition.timestamp);
+        });
+        Ok(())
+    }
+}
+
+impl Display for ListOffsetsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ListOffsetsRequest(replicaId={}, isolationLevel={}, partitions=[",
+            self.replica_id, self.isolation_level
+        )?;
+        for (i, (tp, partition)) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", tp, partition.timestamp)?;
+        }
+        f.write_str("])")
+    }
+}
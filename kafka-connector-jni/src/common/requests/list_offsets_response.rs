@@ -0,0 +1,89 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+pub const UNKNOWN_TIMESTAMP: i64 = -1;
+pub const UNKNOWN_OFFSET: i64 = -1;
+pub const UNKNOWN_EPOCH: i32 = -1;
+
+/// Offset lookup result of a single partition.
+///
+/// Possible error codes include `UnsupportedForMessageFormat`, `UnknownTopicOrPartition`,
+/// `NotLeaderOrFollower`, `FencedLeaderEpoch`, `UnknownLeaderEpoch`, `KafkaStorageError`,
+/// `OffsetNotAvailable`, `LeaderNotAvailable`, `TopicAuthorizationFailed` and `UnknownServerError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListOffsetsPartitionResponse {
+    pub error: Errors,
+    pub timestamp: i64,
+    pub offset: i64,
+    pub leader_epoch: Option<i32>,
+}
+
+impl ListOffsetsPartitionResponse {
+    pub fn new(
+        error: Errors,
+        timestamp: i64,
+        offset: i64,
+        leader_epoch: Option<i32>,
+    ) -> ListOffsetsPartitionResponse {
+        ListOffsetsPartitionResponse {
+            error,
+            timestamp,
+            offset,
+            leader_epoch,
+        }
+    }
+
+    pub fn from_error(error: Errors) -> ListOffsetsPartitionResponse {
+        ListOffsetsPartitionResponse::new(error, UNKNOWN_TIMESTAMP, UNKNOWN_OFFSET, None)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ListOffsetsResponse {
+    pub throttle_time_ms: i32,
+    pub partitions: IndexMap<TopicPartition, ListOffsetsPartitionResponse>,
+}
+
+impl ListOffsetsResponse {
+    pub fn new(
+        partitions: IndexMap<TopicPartition, ListOffsetsPartitionResponse>,
+    ) -> ListOffsetsResponse {
+        ListOffsetsResponse {
+            throttle_time_ms: 0,
+            partitions,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<ListOffsetsResponse> {
+        let throttle_time_ms = if version >= 2 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        let partitions = types::read_topic_partitions(buffer, |buffer| {
+            let index = types::read_i32(buffer)?;
+            let error = types::read_error(buffer)?;
+            let timestamp = types::read_i64(buffer)?;
+            let offset = types::read_i64(buffer)?;
+            let leader_epoch = if version >= 4 {
+                Some(types::read_i32(buffer)?).filter(|epoch| *epoch != UNKNOWN_EPOCH)
+            } else {
+                None
+            };
+            Ok((
+                index,
+                ListOffsetsPartitionResponse::new(error, timestamp, offset, leader_epoch),
+            ))
+        })?;
+        Ok(ListOffsetsResponse {
+            throttle_time_ms,
+            partitions,
+        })
+    }
+}
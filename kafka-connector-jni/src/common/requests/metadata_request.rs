@@ -0,0 +1,59 @@
+use std::fmt::{self, Display};
+
+use bytes::BytesMut;
+
+use crate::common::protocol::types;
+
+#[derive(Debug, Clone)]
+pub struct MetadataRequest {
+    /// The topics to fetch metadata for, `None` for all topics.
+    pub topics: Option<Vec<String>>,
+    pub allow_auto_topic_creation: bool,
+}
+
+impl MetadataRequest {
+    pub fn new(topics: Vec<String>, allow_auto_topic_creation: bool) -> MetadataRequest {
+        MetadataRequest {
+            topics: Some(topics),
+            allow_auto_topic_creation,
+        }
+    }
+
+    pub fn all_topics() -> MetadataRequest {
+        MetadataRequest {
+            topics: None,
+            allow_auto_topic_creation: true,
+        }
+    }
+
+    pub fn is_all_topics(&self) -> bool {
+        self.topics.is_none()
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) {
+        types::write_nullable_array(buffer, self.topics.as_ref(), |buffer, topic| {
+            types::write_string(buffer, topic)
+        });
+        types::write_bool(buffer, self.allow_auto_topic_creation);
+        if version >= 8 {
+            // include_cluster_authorized_operations and include_topic_authorized_operations
+            types::write_bool(buffer, false);
+            types::write_bool(buffer, false);
+        }
+    }
+}
+
+impl Display for MetadataRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetadataRequest(topics=")?;
+        match &self.topics {
+            Some(topics) => write!(f, "[{}]", topics.join(", "))?,
+            None => f.write_str("null")?,
+        }
+        write!(
+            f,
+            ", allowAutoTopicCreation={})",
+            self.allow_auto_topic_creation
+        )
+    }
+}
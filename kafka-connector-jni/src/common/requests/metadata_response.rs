@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    cluster::Cluster,
+    errors::Result,
+    node::Node,
+    partition_info::PartitionInfo,
+    protocol::{errors::Errors, types},
+};
+
+pub const NO_CONTROLLER_ID: i32 = -1;
+
+/// Possible error codes include `UnknownTopicOrPartition`, `LeaderNotAvailable`,
+/// `ReplicaNotAvailable` and `TopicAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct MetadataResponsePartition {
+    pub error: Errors,
+    pub partition_index: i32,
+    pub leader_id: i32,
+    pub leader_epoch: Option<i32>,
+    pub replica_nodes: Vec<i32>,
+    pub isr_nodes: Vec<i32>,
+    pub offline_replicas: Vec<i32>,
+}
+
+impl MetadataResponsePartition {
+    pub fn new(
+        error: Errors,
+        partition_index: i32,
+        leader_id: i32,
+        replica_nodes: Vec<i32>,
+        isr_nodes: Vec<i32>,
+    ) -> MetadataResponsePartition {
+        MetadataResponsePartition {
+            error,
+            partition_index,
+            leader_id,
+            leader_epoch: None,
+            replica_nodes,
+            isr_nodes,
+            offline_replicas: vec![],
+        }
+    }
+}
+
+/// Possible error codes include `UnknownTopicOrPartition`, `InvalidTopicException`,
+/// `LeaderNotAvailable` and `TopicAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct MetadataResponseTopic {
+    pub error: Errors,
+    pub name: String,
+    pub is_internal: bool,
+    pub partitions: Vec<MetadataResponsePartition>,
+}
+
+impl MetadataResponseTopic {
+    pub fn new(
+        error: Errors,
+        name: impl Into<String>,
+        is_internal: bool,
+        partitions: Vec<MetadataResponsePartition>,
+    ) -> MetadataResponseTopic {
+        MetadataResponseTopic {
+            error,
+            name: name.into(),
+            is_internal,
+            partitions,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetadataResponse {
+    pub throttle_time_ms: i32,
+    pub brokers: Vec<Node>,
+    pub cluster_id: Option<String>,
+    pub controller_id: i32,
+    pub topics: Vec<MetadataResponseTopic>,
+}
+
+impl MetadataResponse {
+    pub fn new(
+        brokers: Vec<Node>,
+        cluster_id: Option<String>,
+        controller_id: i32,
+        topics: Vec<MetadataResponseTopic>,
+    ) -> MetadataResponse {
+        MetadataResponse {
+            throttle_time_ms: 0,
+            brokers,
+            cluster_id,
+            controller_id,
+            topics,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<MetadataResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let brokers = types::read_array(buffer, |buffer| {
+            let id = types::read_i32(buffer)?;
+            let host = types::read_string(buffer)?;
+            let port = types::read_i32(buffer)?;
+            Ok(Node {
+                rack: types::read_nullable_string(buffer)?,
+                ..Node::new(id, host, port)
+            })
+        })?;
+        let cluster_id = types::read_nullable_string(buffer)?;
+        let controller_id = types::read_i32(buffer)?;
+        let topics = types::read_array(buffer, |buffer| {
+            let error = types::read_error(buffer)?;
+            let name = types::read_string(buffer)?;
+            let is_internal = types::read_bool(buffer)?;
+            let partitions = types::read_array(buffer, |buffer| {
+                let error = types::read_error(buffer)?;
+                let partition_index = types::read_i32(buffer)?;
+                let leader_id = types::read_i32(buffer)?;
+                let leader_epoch = if version >= 7 {
+                    Some(types::read_i32(buffer)?).filter(|epoch| *epoch >= 0)
+                } else {
+                    None
+                };
+                let replica_nodes = types::read_i32_array(buffer)?;
+                let isr_nodes = types::read_i32_array(buffer)?;
+                let offline_replicas = if version >= 5 {
+                    types::read_i32_array(buffer)?
+                } else {
+                    vec![]
+                };
+                Ok(MetadataResponsePartition {
+                    leader_epoch,
+                    offline_replicas,
+                    ..MetadataResponsePartition::new(
+                        error,
+                        partition_index,
+                        leader_id,
+                        replica_nodes,
+                        isr_nodes,
+                    )
+                })
+            })?;
+            if version >= 8 {
+                // topic_authorized_operations
+                types::read_i32(buffer)?;
+            }
+            Ok(MetadataResponseTopic::new(
+                error,
+                name,
+                is_internal,
+                partitions,
+            ))
+        })?;
+        if version >= 8 {
+            // cluster_authorized_operations
+            types::read_i32(buffer)?;
+        }
+        Ok(MetadataResponse {
+            throttle_time_ms,
+            ..MetadataResponse::new(brokers, cluster_id, controller_id, topics)
+        })
+    }
+
+    /// Get a map of the topics which had metadata errors
+    pub fn errors(&self) -> IndexMap<String, Errors> {
+        self.topics
+            .iter()
+            .filter(|topic| topic.error != Errors::None)
+            .map(|topic| (topic.name.clone(), topic.error))
+            .collect()
+    }
+
+    /// Get a set of the topics which had the given error
+    pub fn topics_by_error(&self, error: Errors) -> HashSet<String> {
+        self.topics
+            .iter()
+            .filter(|topic| topic.error == error)
+            .map(|topic| topic.name.clone())
+            .collect()
+    }
+
+    /// The controller node, `None` if it is unknown.
+    pub fn controller(&self) -> Option<Node> {
+        self.brokers
+            .iter()
+            .find(|broker| broker.id == self.controller_id)
+            .cloned()
+    }
+
+    /// Get a snapshot of the cluster metadata from this response
+    pub fn build_cluster(&self) -> Cluster {
+        let nodes_by_id: HashMap<i32, &Node> =
+            self.brokers.iter().map(|node| (node.id, node)).collect();
+        let convert_to_nodes = |ids: &[i32]| -> Vec<Node> {
+            ids.iter()
+                .map(|id| {
+                    nodes_by_id
+                        .get(id)
+                        .map(|node| (*node).clone())
+                        .unwrap_or_else(|| Node::new(*id, "", -1))
+                })
+                .collect()
+        };
+        let mut internal_topics = HashSet::new();
+        let mut partitions = vec![];
+        for topic in &self.topics {
+            if topic.error != Errors::None {
+                continue;
+            }
+            if topic.is_internal {
+                internal_topics.insert(topic.name.clone());
+            }
+            for partition in &topic.partitions {
+                partitions.push(PartitionInfo {
+                    offline_replicas: convert_to_nodes(&partition.offline_replicas),
+                    ..PartitionInfo::new(
+                        topic.name.clone(),
+                        partition.partition_index,
+                        nodes_by_id
+                            .get(&partition.leader_id)
+                            .map(|node| (*node).clone()),
+                        convert_to_nodes(&partition.replica_nodes),
+                        convert_to_nodes(&partition.isr_nodes),
+                    )
+                });
+            }
+        }
+        Cluster::new(
+            self.cluster_id.clone(),
+            self.brokers.clone(),
+            partitions,
+            self.topics_by_error(Errors::TopicAuthorizationFailed),
+            self.topics_by_error(Errors::InvalidTopicException),
+            internal_topics,
+            self.controller(),
+        )
+    }
+}
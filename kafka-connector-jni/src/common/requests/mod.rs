@@ -0,0 +1,65 @@
+pub mod abstract_request;
+pub mod abstract_response;
+pub mod add_offsets_to_txn_request;
+pub mod add_offsets_to_txn_response;
+pub mod add_partitions_to_txn_request;
+pub mod add_partitions_to_txn_response;
+pub mod api_versions_request;
+pub mod api_versions_response;
+pub mod create_acls_request;
+pub mod create_acls_response;
+pub mod create_partitions_request;
+pub mod create_partitions_response;
+pub mod create_topics_request;
+pub mod create_topics_response;
+pub mod delete_acls_request;
+pub mod delete_acls_response;
+pub mod delete_groups_request;
+pub mod delete_groups_response;
+pub mod delete_topics_request;
+pub mod delete_topics_response;
+pub mod describe_acls_request;
+pub mod describe_acls_response;
+pub mod describe_configs_request;
+pub mod describe_configs_response;
+pub mod describe_groups_request;
+pub mod describe_groups_response;
+pub mod end_txn_request;
+pub mod end_txn_response;
+pub mod fetch_metadata;
+pub mod fetch_request;
+pub mod fetch_response;
+pub mod find_coordinator_request;
+pub mod find_coordinator_response;
+pub mod heartbeat_request;
+pub mod heartbeat_response;
+pub mod incremental_alter_configs_request;
+pub mod incremental_alter_configs_response;
+pub mod init_producer_id_request;
+pub mod init_producer_id_response;
+pub mod join_group_request;
+pub mod join_group_response;
+pub mod leave_group_request;
+pub mod leave_group_response;
+pub mod list_groups_request;
+pub mod list_groups_response;
+pub mod list_offsets_request;
+pub mod list_offsets_response;
+pub mod metadata_request;
+pub mod metadata_response;
+pub mod offset_commit_request;
+pub mod offset_commit_response;
+pub mod offset_delete_request;
+pub mod offset_delete_response;
+pub mod offset_fetch_request;
+pub mod offset_fetch_response;
+pub mod offsets_for_leader_epoch_request;
+pub mod offsets_for_leader_epoch_response;
+pub mod produce_request;
+pub mod produce_response;
+pub mod request_header;
+pub mod response_header;
+pub mod sync_group_request;
+pub mod sync_group_response;
+pub mod txn_offset_commit_request;
+pub mod txn_offset_commit_response;
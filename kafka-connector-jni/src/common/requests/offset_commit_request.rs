@@ -0,0 +1,91 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+    topic_partition::TopicPartition,
+};
+
+use super::txn_offset_commit_request::CommittedOffset;
+
+/// Default retention time of committed offsets, let the broker decide.
+pub const DEFAULT_RETENTION_TIME: i64 = -1;
+
+#[derive(Debug, Clone)]
+pub struct OffsetCommitRequest {
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub retention_time_ms: i64,
+    pub offsets: IndexMap<TopicPartition, CommittedOffset>,
+}
+
+impl OffsetCommitRequest {
+    pub fn new(
+        group_id: impl Into<String>,
+        generation_id: i32,
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        offsets: IndexMap<TopicPartition, CommittedOffset>,
+    ) -> OffsetCommitRequest {
+        OffsetCommitRequest {
+            group_id: group_id.into(),
+            generation_id,
+            member_id: member_id.into(),
+            group_instance_id,
+            retention_time_ms: DEFAULT_RETENTION_TIME,
+            offsets,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 7 && self.group_instance_id.is_some() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker offset commit protocol version {} does not support usage of config group.instance.id.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.group_id);
+        buffer.put_i32(self.generation_id);
+        types::write_string(buffer, &self.member_id);
+        if version >= 7 {
+            types::write_nullable_string(buffer, self.group_instance_id.as_deref());
+        }
+        if version <= 4 {
+            buffer.put_i64(self.retention_time_ms);
+        }
+        types::write_topic_partitions(buffer, &self.offsets, |buffer, partition, offset| {
+            buffer.put_i32(partition);
+            buffer.put_i64(offset.offset);
+            if version >= 6 {
+                buffer.put_i32(offset.leader_epoch.unwrap_or(-1));
+            }
+            types::write_nullable_string(buffer, Some(&offset.metadata));
+        });
+        Ok(())
+    }
+}
+
+impl Display for OffsetCommitRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OffsetCommitRequest(groupId={}, generationId={}, memberId={}, groupInstanceId={}, offsets=[",
+            self.group_id,
+            self.generation_id,
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null")
+        )?;
+        for (i, (tp, offset)) in self.offsets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", tp, offset.offset)?;
+        }
+        f.write_str("])")
+    }
+}
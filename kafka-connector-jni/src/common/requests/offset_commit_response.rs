@@ -0,0 +1,40 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+/// Possible error codes include `UnknownTopicOrPartition`, `RequestTimedOut`,
+/// `OffsetMetadataTooLarge`, `CoordinatorLoadInProgress`, `GroupCoordinatorNotAvailable`,
+/// `NotCoordinator`, `IllegalGeneration`, `UnknownMemberId`, `RebalanceInProgress`,
+/// `InvalidCommitOffsetSize`, `TopicAuthorizationFailed`, `GroupAuthorizationFailed` and
+/// `FencedInstanceId`.
+#[derive(Debug, Clone, Default)]
+pub struct OffsetCommitResponse {
+    pub throttle_time_ms: i32,
+    pub errors: IndexMap<TopicPartition, Errors>,
+}
+
+impl OffsetCommitResponse {
+    pub fn new(errors: IndexMap<TopicPartition, Errors>) -> OffsetCommitResponse {
+        OffsetCommitResponse {
+            throttle_time_ms: 0,
+            errors,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<OffsetCommitResponse> {
+        let throttle_time_ms = if version >= 3 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        Ok(OffsetCommitResponse {
+            throttle_time_ms,
+            errors: types::read_partition_errors(buffer)?,
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{protocol::types, topic_partition::TopicPartition};
+
+#[derive(Debug, Clone)]
+pub struct OffsetDeleteRequest {
+    pub group_id: String,
+    pub partitions: Vec<TopicPartition>,
+}
+
+impl OffsetDeleteRequest {
+    pub fn new(
+        group_id: impl Into<String>,
+        partitions: Vec<TopicPartition>,
+    ) -> OffsetDeleteRequest {
+        OffsetDeleteRequest {
+            group_id: group_id.into(),
+            partitions,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_string(buffer, &self.group_id);
+        types::write_topic_partitions(
+            buffer,
+            self.partitions.iter().map(|tp| (tp, ())),
+            |buffer, partition, _| buffer.put_i32(partition),
+        );
+    }
+}
+
+impl Display for OffsetDeleteRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OffsetDeleteRequest(groupId={}, partitions=[",
+            self.group_id
+        )?;
+        for (i, tp) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            tp.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
@@ -0,0 +1,40 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+/// Possible error codes include `NonEmptyGroup`, `GroupIdNotFound`, `GroupSubscribedToTopic`,
+/// `CoordinatorLoadInProgress`, `CoordinatorNotAvailable`, `NotCoordinator`,
+/// `GroupAuthorizationFailed` and `TopicAuthorizationFailed`.
+#[derive(Debug, Clone)]
+pub struct OffsetDeleteResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub partitions: IndexMap<TopicPartition, Errors>,
+}
+
+impl OffsetDeleteResponse {
+    pub fn new(
+        error: Errors,
+        partitions: IndexMap<TopicPartition, Errors>,
+    ) -> OffsetDeleteResponse {
+        OffsetDeleteResponse {
+            throttle_time_ms: 0,
+            error,
+            partitions,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<OffsetDeleteResponse> {
+        let error = types::read_error(buffer)?;
+        let throttle_time_ms = types::read_i32(buffer)?;
+        Ok(OffsetDeleteResponse {
+            throttle_time_ms,
+            ..OffsetDeleteResponse::new(error, types::read_partition_errors(buffer)?)
+        })
+    }
+}
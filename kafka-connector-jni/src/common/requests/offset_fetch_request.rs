@@ -0,0 +1,83 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+    topic_partition::TopicPartition,
+};
+
+#[derive(Debug, Clone)]
+pub struct OffsetFetchRequest {
+    pub group_id: String,
+    pub require_stable: bool,
+    /// The partitions to fetch committed offsets for, `None` for all partitions of the group.
+    pub partitions: Option<Vec<TopicPartition>>,
+}
+
+impl OffsetFetchRequest {
+    pub fn new(
+        group_id: impl Into<String>,
+        require_stable: bool,
+        partitions: Option<Vec<TopicPartition>>,
+    ) -> OffsetFetchRequest {
+        OffsetFetchRequest {
+            group_id: group_id.into(),
+            require_stable,
+            partitions,
+        }
+    }
+
+    pub fn is_all_partitions(&self) -> bool {
+        self.partitions.is_none()
+    }
+
+    /// `require_stable` is only sent by the flexible versions, without it the broker returns the
+    /// offsets committed by pending transactions too, as the consumer does it for older brokers.
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 2 && self.is_all_partitions() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker only supports OffsetFetchRequest v{}, but we need v2 or newer to request all topic partitions.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.group_id);
+        types::write_nullable_array(
+            buffer,
+            self.partitions
+                .as_ref()
+                .map(|partitions| types::group_by_topic(partitions.iter().map(|tp| (tp, ())))),
+            |buffer, (topic, partitions)| {
+                types::write_string(buffer, topic);
+                types::write_array(buffer, partitions, |buffer, (partition, _)| {
+                    buffer.put_i32(partition)
+                });
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Display for OffsetFetchRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OffsetFetchRequest(groupId={}, requireStable={}, partitions=",
+            self.group_id, self.require_stable
+        )?;
+        match &self.partitions {
+            Some(partitions) => {
+                f.write_str("[")?;
+                for (i, tp) in partitions.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    tp.fmt(f)?;
+                }
+                f.write_str("])")
+            }
+            None => f.write_str("null)"),
+        }
+    }
+}
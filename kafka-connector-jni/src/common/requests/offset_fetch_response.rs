@@ -0,0 +1,101 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+pub const INVALID_OFFSET: i64 = -1;
+pub const NO_METADATA: &str = "";
+
+/// Committed offset of a single partition.
+///
+/// Possible error codes include `UnknownTopicOrPartition`, `UnstableOffsetCommit` and
+/// `TopicAuthorizationFailed`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetFetchPartitionData {
+    pub offset: i64,
+    pub leader_epoch: Option<i32>,
+    pub metadata: Option<String>,
+    pub error: Errors,
+}
+
+impl OffsetFetchPartitionData {
+    pub fn new(
+        offset: i64,
+        leader_epoch: Option<i32>,
+        metadata: Option<String>,
+        error: Errors,
+    ) -> OffsetFetchPartitionData {
+        OffsetFetchPartitionData {
+            offset,
+            leader_epoch,
+            metadata,
+            error,
+        }
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error != Errors::None
+    }
+}
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `GroupCoordinatorNotAvailable`,
+/// `NotCoordinator` and `GroupAuthorizationFailed` at top level.
+#[derive(Debug, Clone)]
+pub struct OffsetFetchResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub response_data: IndexMap<TopicPartition, OffsetFetchPartitionData>,
+}
+
+impl OffsetFetchResponse {
+    pub fn new(
+        error: Errors,
+        response_data: IndexMap<TopicPartition, OffsetFetchPartitionData>,
+    ) -> OffsetFetchResponse {
+        OffsetFetchResponse {
+            throttle_time_ms: 0,
+            error,
+            response_data,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<OffsetFetchResponse> {
+        let throttle_time_ms = if version >= 3 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        let response_data = types::read_topic_partitions(buffer, |buffer| {
+            let partition = types::read_i32(buffer)?;
+            let offset = types::read_i64(buffer)?;
+            let leader_epoch = if version >= 5 {
+                Some(types::read_i32(buffer)?).filter(|epoch| *epoch >= 0)
+            } else {
+                None
+            };
+            let metadata = types::read_nullable_string(buffer)?;
+            let error = types::read_error(buffer)?;
+            Ok((
+                partition,
+                OffsetFetchPartitionData::new(offset, leader_epoch, metadata, error),
+            ))
+        })?;
+        let error = if version >= 2 {
+            types::read_error(buffer)?
+        } else {
+            Errors::None
+        };
+        Ok(OffsetFetchResponse {
+            throttle_time_ms,
+            ..OffsetFetchResponse::new(error, response_data)
+        })
+    }
+
+    pub fn has_error(&self) -> bool {
+        self.error != Errors::None
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{protocol::types, topic_partition::TopicPartition};
+
+pub const CONSUMER_REPLICA_ID: i32 = -1;
+pub const DEBUGGING_REPLICA_ID: i32 = -2;
+
+/// Epoch lookup of a single partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OffsetForLeaderPartition {
+    /// The epoch to look up an end offset for.
+    pub leader_epoch: i32,
+    /// The current leader epoch known by the client, used for fencing.
+    pub current_leader_epoch: i32,
+}
+
+impl OffsetForLeaderPartition {
+    pub fn new(leader_epoch: i32, current_leader_epoch: i32) -> OffsetForLeaderPartition {
+        OffsetForLeaderPartition {
+            leader_epoch,
+            current_leader_epoch,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OffsetsForLeaderEpochRequest {
+    pub replica_id: i32,
+    pub partitions: IndexMap<TopicPartition, OffsetForLeaderPartition>,
+}
+
+impl OffsetsForLeaderEpochRequest {
+    pub fn for_consumer(
+        partitions: IndexMap<TopicPartition, OffsetForLeaderPartition>,
+    ) -> OffsetsForLeaderEpochRequest {
+        OffsetsForLeaderEpochRequest {
+            replica_id: CONSUMER_REPLICA_ID,
+            partitions,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) {
+        if version >= 3 {
+            buffer.put_i32(self.replica_id);
+        }
+        types::write_topic_partitions(buffer, &self.partitions, |buffer, partition, epoch| {
+            buffer.put_i32(partition);
+            buffer.put_i32(epoch.current_leader_epoch);
+            buffer.put_i32(epoch.leader_epoch);
+        });
+    }
+
+    /// Check whether a broker allows Topic-level permissions in order to use the
+    /// OffsetForLeaderEpoch API. Old versions require Cluster permission.
+    pub fn supports_topic_permission(latest_usable_version: i16) -> bool {
+        latest_usable_version >= 3
+    }
+}
+
+impl Display for OffsetsForLeaderEpochRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "OffsetsForLeaderEpochRequest(replicaId={}, partitions=[",
+            self.replica_id
+        )?;
+        for (i, (tp, partition)) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(
+                f,
+                "{}(leaderEpoch={}, currentLeaderEpoch={})",
+                tp, partition.leader_epoch, partition.current_leader_epoch
+            )?;
+        }
+        f.write_str("])")
+    }
+}
@@ -0,0 +1,79 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    record::record_batch::NO_PARTITION_LEADER_EPOCH,
+    topic_partition::TopicPartition,
+};
+
+pub const UNDEFINED_EPOCH_OFFSET: i64 = NO_PARTITION_LEADER_EPOCH as i64;
+pub const UNDEFINED_EPOCH: i32 = NO_PARTITION_LEADER_EPOCH;
+
+/// End offset of the requested epoch (or of the largest epoch smaller than the requested one)
+/// for a single partition.
+///
+/// Possible error codes include `NotLeaderOrFollower`, `UnknownTopicOrPartition`,
+/// `FencedLeaderEpoch`, `UnknownLeaderEpoch`, `KafkaStorageError`, `TopicAuthorizationFailed`
+/// and `UnknownServerError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpochEndOffset {
+    pub error: Errors,
+    pub leader_epoch: i32,
+    pub end_offset: i64,
+}
+
+impl EpochEndOffset {
+    pub fn new(error: Errors, leader_epoch: i32, end_offset: i64) -> EpochEndOffset {
+        EpochEndOffset {
+            error,
+            leader_epoch,
+            end_offset,
+        }
+    }
+
+    pub fn from_error(error: Errors) -> EpochEndOffset {
+        EpochEndOffset::new(error, UNDEFINED_EPOCH, UNDEFINED_EPOCH_OFFSET)
+    }
+
+    /// Whether the broker could not find any epoch at or below the requested one.
+    pub fn is_undefined(&self) -> bool {
+        self.end_offset == UNDEFINED_EPOCH_OFFSET || self.leader_epoch == UNDEFINED_EPOCH
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OffsetsForLeaderEpochResponse {
+    pub throttle_time_ms: i32,
+    pub partitions: IndexMap<TopicPartition, EpochEndOffset>,
+}
+
+impl OffsetsForLeaderEpochResponse {
+    pub fn new(
+        partitions: IndexMap<TopicPartition, EpochEndOffset>,
+    ) -> OffsetsForLeaderEpochResponse {
+        OffsetsForLeaderEpochResponse {
+            throttle_time_ms: 0,
+            partitions,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<OffsetsForLeaderEpochResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        let partitions = types::read_topic_partitions(buffer, |buffer| {
+            let error = types::read_error(buffer)?;
+            let partition = types::read_i32(buffer)?;
+            let leader_epoch = types::read_i32(buffer)?;
+            let end_offset = types::read_i64(buffer)?;
+            Ok((
+                partition,
+                EpochEndOffset::new(error, leader_epoch, end_offset),
+            ))
+        })?;
+        Ok(OffsetsForLeaderEpochResponse {
+            throttle_time_ms,
+            partitions,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    protocol::types, record::memory_records::MemoryRecords, topic_partition::TopicPartition,
+};
+
+#[derive(Debug, Clone)]
+pub struct ProduceRequest {
+    pub acks: i16,
+    pub timeout_ms: i32,
+    pub transactional_id: Option<String>,
+    pub partition_records: IndexMap<TopicPartition, MemoryRecords>,
+}
+
+impl ProduceRequest {
+    pub fn new(
+        acks: i16,
+        timeout_ms: i32,
+        transactional_id: Option<String>,
+        partition_records: IndexMap<TopicPartition, MemoryRecords>,
+    ) -> ProduceRequest {
+        ProduceRequest {
+            acks,
+            timeout_ms,
+            transactional_id,
+            partition_records,
+        }
+    }
+
+    pub fn has_transactional_records(&self) -> bool {
+        self.transactional_id.is_some()
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, _version: i16) {
+        types::write_nullable_string(buffer, self.transactional_id.as_deref());
+        buffer.put_i16(self.acks);
+        buffer.put_i32(self.timeout_ms);
+        types::write_topic_partitions(
+            buffer,
+            &self.partition_records,
+            |buffer, partition, records| {
+                buffer.put_i32(partition);
+                types::write_bytes(buffer, records.buffer());
+            },
+        );
+    }
+}
+
+impl Display for ProduceRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProduceRequest(acks={}, timeoutMs={}, transactionalId={}, partitions=[",
+            self.acks,
+            self.timeout_ms,
+            self.transactional_id.as_deref().unwrap_or("null")
+        )?;
+        for (i, tp) in self.partition_records.keys().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            tp.fmt(f)?;
+        }
+        f.write_str("])")
+    }
+}
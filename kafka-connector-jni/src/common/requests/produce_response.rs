@@ -0,0 +1,137 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    record::record_batch::NO_TIMESTAMP,
+    topic_partition::TopicPartition,
+};
+
+pub const INVALID_OFFSET: i64 = -1;
+
+/// Per partition result of a produce request.
+///
+/// Possible error codes include `CorruptMessage`, `UnknownTopicOrPartition`, `NotLeaderOrFollower`,
+/// `MessageTooLarge`, `InvalidTopic`, `RecordListTooLarge`, `NotEnoughReplicas`,
+/// `NotEnoughReplicasAfterAppend`, `InvalidRequiredAcks`, `TopicAuthorizationFailed`,
+/// `UnsupportedForMessageFormat`, `InvalidProducerEpoch`, `ClusterAuthorizationFailed`,
+/// `TransactionalIdAuthorizationFailed` and `InvalidRecord`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionResponse {
+    pub error: Errors,
+    pub base_offset: i64,
+    pub log_append_time: i64,
+    pub log_start_offset: i64,
+    pub record_errors: Vec<RecordError>,
+    pub error_message: Option<String>,
+}
+
+impl PartitionResponse {
+    pub fn new(
+        error: Errors,
+        base_offset: i64,
+        log_append_time: i64,
+        log_start_offset: i64,
+        record_errors: Vec<RecordError>,
+        error_message: Option<String>,
+    ) -> PartitionResponse {
+        PartitionResponse {
+            error,
+            base_offset,
+            log_append_time,
+            log_start_offset,
+            record_errors,
+            error_message,
+        }
+    }
+
+    pub fn from_error(error: Errors) -> PartitionResponse {
+        PartitionResponse::from_error_with_message(error, None)
+    }
+
+    pub fn from_error_with_message(
+        error: Errors,
+        error_message: Option<String>,
+    ) -> PartitionResponse {
+        PartitionResponse::new(
+            error,
+            INVALID_OFFSET,
+            NO_TIMESTAMP,
+            INVALID_OFFSET,
+            vec![],
+            error_message,
+        )
+    }
+}
+
+/// Error of a single record from a batch rejected by the broker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordError {
+    pub batch_index: usize,
+    pub message: Option<String>,
+}
+
+impl RecordError {
+    pub fn new(batch_index: usize, message: Option<String>) -> RecordError {
+        RecordError {
+            batch_index,
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProduceResponse {
+    pub responses: IndexMap<TopicPartition, PartitionResponse>,
+    pub throttle_time_ms: i32,
+}
+
+impl ProduceResponse {
+    pub fn new(
+        responses: IndexMap<TopicPartition, PartitionResponse>,
+        throttle_time_ms: i32,
+    ) -> ProduceResponse {
+        ProduceResponse {
+            responses,
+            throttle_time_ms,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<ProduceResponse> {
+        let responses = types::read_topic_partitions(buffer, |buffer| {
+            let partition = types::read_i32(buffer)?;
+            let error = types::read_error(buffer)?;
+            let base_offset = types::read_i64(buffer)?;
+            let log_append_time = types::read_i64(buffer)?;
+            let log_start_offset = if version >= 5 {
+                types::read_i64(buffer)?
+            } else {
+                INVALID_OFFSET
+            };
+            let (record_errors, error_message) = if version >= 8 {
+                let record_errors = types::read_array(buffer, |buffer| {
+                    Ok(RecordError::new(
+                        types::read_i32(buffer)? as usize,
+                        types::read_nullable_string(buffer)?,
+                    ))
+                })?;
+                (record_errors, types::read_nullable_string(buffer)?)
+            } else {
+                (vec![], None)
+            };
+            Ok((
+                partition,
+                PartitionResponse::new(
+                    error,
+                    base_offset,
+                    log_append_time,
+                    log_start_offset,
+                    record_errors,
+                    error_message,
+                ),
+            ))
+        })?;
+        Ok(ProduceResponse::new(responses, types::read_i32(buffer)?))
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+
+use crate::common::protocol::{api_keys::ApiKeys, types};
+
+/// The header for a request in the Kafka protocol
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestHeader {
+    pub api_key: ApiKeys,
+    pub api_version: i16,
+    pub client_id: String,
+    pub correlation_id: i32,
+}
+
+impl RequestHeader {
+    pub fn new(
+        api_key: ApiKeys,
+        api_version: i16,
+        client_id: impl Into<String>,
+        correlation_id: i32,
+    ) -> RequestHeader {
+        RequestHeader {
+            api_key,
+            api_version,
+            client_id: client_id.into(),
+            correlation_id,
+        }
+    }
+
+    /// Write the version 1 of the header, or the version 2 for flexible requests which adds
+    /// tagged fields.
+    pub fn write_to(&self, buffer: &mut BytesMut) {
+        buffer.put_i16(self.api_key.id());
+        buffer.put_i16(self.api_version);
+        buffer.put_i32(self.correlation_id);
+        types::write_nullable_string(buffer, Some(&self.client_id));
+        if self.api_key.is_flexible(self.api_version) {
+            types::write_empty_tagged_fields(buffer);
+        }
+    }
+}
+
+impl Display for RequestHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RequestHeader(apiKey={}, apiVersion={}, clientId={}, correlationId={})",
+            self.api_key, self.api_version, self.client_id, self.correlation_id
+        )
+    }
+}
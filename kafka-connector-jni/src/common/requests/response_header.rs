@@ -0,0 +1,28 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{api_keys::ApiKeys, types},
+};
+
+/// The header of a response in the Kafka protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseHeader {
+    pub correlation_id: i32,
+}
+
+impl ResponseHeader {
+    pub fn new(correlation_id: i32) -> ResponseHeader {
+        ResponseHeader { correlation_id }
+    }
+
+    /// Read the header of a response to `api_key` in `version`, the version 1 of the header of
+    /// flexible responses adds tagged fields.
+    pub fn read_from(buffer: &mut Bytes, api_key: ApiKeys, version: i16) -> Result<ResponseHeader> {
+        let header = ResponseHeader::new(types::read_i32(buffer)?);
+        if api_key.is_flexible(version) {
+            types::skip_tagged_fields(buffer)?;
+        }
+        Ok(header)
+    }
+}
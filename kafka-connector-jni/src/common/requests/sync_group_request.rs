@@ -0,0 +1,95 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+};
+
+/// The assignment computed by the leader for a single member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncGroupRequestAssignment {
+    pub member_id: String,
+    pub assignment: Bytes,
+}
+
+impl SyncGroupRequestAssignment {
+    pub fn new(member_id: impl Into<String>, assignment: Bytes) -> SyncGroupRequestAssignment {
+        SyncGroupRequestAssignment {
+            member_id: member_id.into(),
+            assignment,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncGroupRequest {
+    pub group_id: String,
+    pub generation_id: i32,
+    pub member_id: String,
+    pub group_instance_id: Option<String>,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    /// Assignments of all members, only sent by the leader.
+    pub assignments: Vec<SyncGroupRequestAssignment>,
+}
+
+impl SyncGroupRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        group_id: impl Into<String>,
+        generation_id: i32,
+        member_id: impl Into<String>,
+        group_instance_id: Option<String>,
+        protocol_type: Option<String>,
+        protocol_name: Option<String>,
+        assignments: Vec<SyncGroupRequestAssignment>,
+    ) -> SyncGroupRequest {
+        SyncGroupRequest {
+            group_id: group_id.into(),
+            generation_id,
+            member_id: member_id.into(),
+            group_instance_id,
+            protocol_type,
+            protocol_name,
+            assignments,
+        }
+    }
+
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if version < 3 && self.group_instance_id.is_some() {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "The broker sync group protocol version {} does not support usage of config group.instance.id.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.group_id);
+        buffer.put_i32(self.generation_id);
+        types::write_string(buffer, &self.member_id);
+        if version >= 3 {
+            types::write_nullable_string(buffer, self.group_instance_id.as_deref());
+        }
+        types::write_array(buffer, &self.assignments, |buffer, assignment| {
+            types::write_string(buffer, &assignment.member_id);
+            types::write_bytes(buffer, &assignment.assignment);
+        });
+        Ok(())
+    }
+}
+
+impl Display for SyncGroupRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "SyncGroupRequest(groupId={}, generationId={}, memberId={}, groupInstanceId={}, protocolType={}, protocolName={}, assignments={})",
+            self.group_id,
+            self.generation_id,
+            self.member_id,
+            self.group_instance_id.as_deref().unwrap_or("null"),
+            self.protocol_type.as_deref().unwrap_or("null"),
+            self.protocol_name.as_deref().unwrap_or("null"),
+            self.assignments.len()
+        )
+    }
+}
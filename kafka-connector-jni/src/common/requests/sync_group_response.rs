@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+};
+
+/// Possible error codes include `CoordinatorLoadInProgress`, `GroupCoordinatorNotAvailable`,
+/// `NotCoordinator`, `IllegalGeneration`, `UnknownMemberId`, `RebalanceInProgress`,
+/// `GroupAuthorizationFailed`, `InconsistentGroupProtocol` and `FencedInstanceId`.
+#[derive(Debug, Clone)]
+pub struct SyncGroupResponse {
+    pub throttle_time_ms: i32,
+    pub error: Errors,
+    pub protocol_type: Option<String>,
+    pub protocol_name: Option<String>,
+    pub assignment: Bytes,
+}
+
+impl SyncGroupResponse {
+    pub fn new(
+        error: Errors,
+        protocol_type: Option<String>,
+        protocol_name: Option<String>,
+        assignment: Bytes,
+    ) -> SyncGroupResponse {
+        SyncGroupResponse {
+            throttle_time_ms: 0,
+            error,
+            protocol_type,
+            protocol_name,
+            assignment,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, version: i16) -> Result<SyncGroupResponse> {
+        let throttle_time_ms = if version >= 1 {
+            types::read_i32(buffer)?
+        } else {
+            0
+        };
+        Ok(SyncGroupResponse {
+            throttle_time_ms,
+            error: types::read_error(buffer)?,
+            protocol_type: None,
+            protocol_name: None,
+            assignment: types::read_bytes(buffer)?,
+        })
+    }
+
+    pub fn from_error(error: Errors) -> SyncGroupResponse {
+        SyncGroupResponse::new(error, None, None, Bytes::new())
+    }
+}
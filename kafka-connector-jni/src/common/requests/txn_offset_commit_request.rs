@@ -0,0 +1,119 @@
+use std::fmt::{self, Display};
+
+use bytes::{BufMut, BytesMut};
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    protocol::types,
+    topic_partition::TopicPartition,
+};
+
+use super::join_group_request::{UNKNOWN_GENERATION_ID, UNKNOWN_MEMBER_ID};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommittedOffset {
+    pub offset: i64,
+    pub metadata: String,
+    pub leader_epoch: Option<i32>,
+}
+
+impl CommittedOffset {
+    pub fn new(
+        offset: i64,
+        metadata: impl Into<String>,
+        leader_epoch: Option<i32>,
+    ) -> CommittedOffset {
+        CommittedOffset {
+            offset,
+            metadata: metadata.into(),
+            leader_epoch,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TxnOffsetCommitRequest {
+    pub transactional_id: String,
+    pub group_id: String,
+    pub producer_id: i64,
+    pub producer_epoch: i16,
+    pub member_id: String,
+    pub generation_id: i32,
+    pub group_instance_id: Option<String>,
+    pub offsets: IndexMap<TopicPartition, CommittedOffset>,
+}
+
+impl TxnOffsetCommitRequest {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        transactional_id: impl Into<String>,
+        group_id: impl Into<String>,
+        producer_id: i64,
+        producer_epoch: i16,
+        member_id: impl Into<String>,
+        generation_id: i32,
+        group_instance_id: Option<String>,
+        offsets: IndexMap<TopicPartition, CommittedOffset>,
+    ) -> TxnOffsetCommitRequest {
+        TxnOffsetCommitRequest {
+            transactional_id: transactional_id.into(),
+            group_id: group_id.into(),
+            producer_id,
+            producer_epoch,
+            member_id: member_id.into(),
+            generation_id,
+            group_instance_id,
+            offsets,
+        }
+    }
+
+    /// The group metadata (generation, member and instance ids) is only sent by the flexible
+    /// versions, it can't be used with the versions written here.
+    pub fn write_to(&self, buffer: &mut BytesMut, version: i16) -> Result<()> {
+        if self.generation_id != UNKNOWN_GENERATION_ID
+            || self.member_id != UNKNOWN_MEMBER_ID
+            || self.group_instance_id.is_some()
+        {
+            return Err(KafkaError::UnsupportedVersion(format!(
+                "Broker doesn't support group metadata commit API on version {}, minimum supported request version is 3 which requires brokers to be on version 2.5 or above.",
+                version
+            )));
+        }
+        types::write_string(buffer, &self.transactional_id);
+        types::write_string(buffer, &self.group_id);
+        buffer.put_i64(self.producer_id);
+        buffer.put_i16(self.producer_epoch);
+        types::write_topic_partitions(buffer, &self.offsets, |buffer, partition, offset| {
+            buffer.put_i32(partition);
+            buffer.put_i64(offset.offset);
+            if version >= 2 {
+                buffer.put_i32(offset.leader_epoch.unwrap_or(-1));
+            }
+            types::write_nullable_string(buffer, Some(&offset.metadata));
+        });
+        Ok(())
+    }
+}
+
+impl Display for TxnOffsetCommitRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "TxnOffsetCommitRequest(transactionalId={}, groupId={}, producerId={}, producerEpoch={}, generationId={}, memberId={}, offsets=[",
+            self.transactional_id,
+            self.group_id,
+            self.producer_id,
+            self.producer_epoch,
+            self.generation_id,
+            self.member_id
+        )?;
+        for (i, (tp, offset)) in self.offsets.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}={}", tp, offset.offset)?;
+        }
+        f.write_str("])")
+    }
+}
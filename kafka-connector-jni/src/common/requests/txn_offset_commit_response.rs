@@ -0,0 +1,35 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::common::{
+    errors::Result,
+    protocol::{errors::Errors, types},
+    topic_partition::TopicPartition,
+};
+
+/// Possible error codes include `InvalidProducerEpoch`, `NotCoordinator`,
+/// `CoordinatorNotAvailable`, `CoordinatorLoadInProgress`, `OffsetMetadataTooLarge`,
+/// `GroupAuthorizationFailed`, `InvalidCommitOffsetSize`, `TransactionalIdAuthorizationFailed`,
+/// `UnknownMemberId`, `IllegalGeneration` and `FencedInstanceId`.
+#[derive(Debug, Clone, Default)]
+pub struct TxnOffsetCommitResponse {
+    pub throttle_time_ms: i32,
+    pub errors: IndexMap<TopicPartition, Errors>,
+}
+
+impl TxnOffsetCommitResponse {
+    pub fn new(errors: IndexMap<TopicPartition, Errors>) -> TxnOffsetCommitResponse {
+        TxnOffsetCommitResponse {
+            throttle_time_ms: 0,
+            errors,
+        }
+    }
+
+    pub fn read_from(buffer: &mut Bytes, _version: i16) -> Result<TxnOffsetCommitResponse> {
+        let throttle_time_ms = types::read_i32(buffer)?;
+        Ok(TxnOffsetCommitResponse {
+            throttle_time_ms,
+            errors: types::read_partition_errors(buffer)?,
+        })
+    }
+}
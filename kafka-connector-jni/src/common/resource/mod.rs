@@ -0,0 +1,4 @@
+pub mod pattern_type;
+pub mod resource_pattern;
+pub mod resource_pattern_filter;
+pub mod resource_type;
@@ -0,0 +1,80 @@
+use std::fmt::{self, Display};
+
+/// Resource pattern type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PatternType {
+    /// Represents any PatternType which this client cannot understand, perhaps because this
+    /// client is too old.
+    Unknown,
+    /// In a filter, matches any resource pattern type.
+    Any,
+    /// In a filter, will perform pattern matching.
+    ///
+    /// e.g. Given a filter of `ResourcePatternFilter(TOPIC, "payments.received", MATCH)`, the
+    /// filter match any `ResourcePattern` that matches topic 'payments.received'. This might
+    /// include:
+    /// - A Literal pattern with the same type and name, e.g. `ResourcePattern(TOPIC, "payments.received", LITERAL)`
+    /// - A Wildcard pattern with the same type, e.g. `ResourcePattern(TOPIC, "*", LITERAL)`
+    /// - A Prefixed pattern with the same type and where the name is a matching prefix, e.g. `ResourcePattern(TOPIC, "payments.", PREFIXED)`
+    Match,
+    /// A literal resource name.
+    ///
+    /// A literal name defines the full name of a resource, e.g. topic with name 'foo', or group
+    /// with name 'bob'.
+    ///
+    /// The special wildcard character `*` can be used to represent a resource with any name.
+    Literal,
+    /// A prefixed resource name.
+    ///
+    /// A prefixed name defines a prefix for a resource, e.g. topics with names that start with
+    /// 'foo'.
+    Prefixed,
+}
+
+impl PatternType {
+    pub fn code(&self) -> i8 {
+        match self {
+            PatternType::Unknown => 0,
+            PatternType::Any => 1,
+            PatternType::Match => 2,
+            PatternType::Literal => 3,
+            PatternType::Prefixed => 4,
+        }
+    }
+
+    /// Return the PatternType with the provided code or `Unknown` if one cannot be found.
+    pub fn from_code(code: i8) -> PatternType {
+        match code {
+            1 => PatternType::Any,
+            2 => PatternType::Match,
+            3 => PatternType::Literal,
+            4 => PatternType::Prefixed,
+            _ => PatternType::Unknown,
+        }
+    }
+
+    /// Returns true if this PatternType is `Unknown`.
+    pub fn is_unknown(&self) -> bool {
+        *self == PatternType::Unknown
+    }
+
+    /// Returns true if the pattern type is a concrete type, i.e. not `Any`, `Match` or `Unknown`.
+    pub fn is_specific(&self) -> bool {
+        !matches!(
+            self,
+            PatternType::Unknown | PatternType::Any | PatternType::Match
+        )
+    }
+}
+
+impl Display for PatternType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PatternType::Unknown => "UNKNOWN",
+            PatternType::Any => "ANY",
+            PatternType::Match => "MATCH",
+            PatternType::Literal => "LITERAL",
+            PatternType::Prefixed => "PREFIXED",
+        })
+    }
+}
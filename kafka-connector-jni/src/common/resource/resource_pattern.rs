@@ -0,0 +1,85 @@
+use std::fmt::{self, Display};
+
+use crate::common::errors::{KafkaError, Result};
+
+use super::{
+    pattern_type::PatternType, resource_pattern_filter::ResourcePatternFilter,
+    resource_type::ResourceType,
+};
+
+/// A special literal resource name that corresponds to 'all resources of a certain type'.
+pub const WILDCARD_RESOURCE: &str = "*";
+
+/// Represents a pattern that is used by ACLs to match zero or more `Resources`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResourcePattern {
+    resource_type: ResourceType,
+    name: String,
+    pattern_type: PatternType,
+}
+
+impl ResourcePattern {
+    /// Create a pattern using the supplied parameters.
+    ///
+    /// * `resource_type` - resource type, which can not be `Any`
+    /// * `name` - resource name
+    /// * `pattern_type` - resource pattern type, which can not be `Any` or `Match`
+    pub fn new(
+        resource_type: ResourceType,
+        name: impl Into<String>,
+        pattern_type: PatternType,
+    ) -> Result<ResourcePattern> {
+        if resource_type == ResourceType::Any {
+            return Err(KafkaError::IllegalArgument(
+                "resourceType must not be ANY".to_owned(),
+            ));
+        }
+        if pattern_type == PatternType::Match || pattern_type == PatternType::Any {
+            return Err(KafkaError::IllegalArgument(format!(
+                "patternType must not be {}",
+                pattern_type
+            )));
+        }
+        Ok(ResourcePattern {
+            resource_type,
+            name: name.into(),
+            pattern_type,
+        })
+    }
+
+    pub fn resource_type(&self) -> ResourceType {
+        self.resource_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn pattern_type(&self) -> PatternType {
+        self.pattern_type
+    }
+
+    /// Returns a filter which matches only this pattern.
+    pub fn to_filter(&self) -> ResourcePatternFilter {
+        ResourcePatternFilter::new(
+            self.resource_type,
+            Some(self.name.clone()),
+            self.pattern_type,
+        )
+    }
+
+    /// Returns true if this Resource has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.resource_type.is_unknown() || self.pattern_type.is_unknown()
+    }
+}
+
+impl Display for ResourcePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ResourcePattern(resourceType={}, name={}, patternType={})",
+            self.resource_type, self.name, self.pattern_type
+        )
+    }
+}
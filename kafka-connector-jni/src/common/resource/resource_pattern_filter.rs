@@ -0,0 +1,117 @@
+use std::fmt::{self, Display};
+
+use super::{
+    pattern_type::PatternType,
+    resource_pattern::{ResourcePattern, WILDCARD_RESOURCE},
+    resource_type::ResourceType,
+};
+
+/// Represents a filter that can match `ResourcePattern`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResourcePatternFilter {
+    pub resource_type: ResourceType,
+    /// The resource name or `None`, which matches any name
+    pub name: Option<String>,
+    pub pattern_type: PatternType,
+}
+
+impl ResourcePatternFilter {
+    /// Matches any resource pattern.
+    pub const ANY: ResourcePatternFilter = ResourcePatternFilter {
+        resource_type: ResourceType::Any,
+        name: None,
+        pattern_type: PatternType::Any,
+    };
+
+    /// Create a filter using the supplied parameters.
+    ///
+    /// * `resource_type` - resource type. If `Any`, the filter will ignore the resource type of
+    ///   the pattern. If any other resource type, the filter will match only patterns with the
+    ///   same type.
+    /// * `name` - resource name or `None`. If `None`, the filter will ignore the name of
+    ///   resources. If `*`, will match only wildcard patterns.
+    /// * `pattern_type` - resource pattern type. If `Any`, the filter will match patterns
+    ///   regardless of pattern type. If `Match`, the filter will match patterns that would match
+    ///   the supplied `name`, including a matching prefixed and wildcards patterns. If any other
+    ///   resource pattern type, the filter will match only patterns with the same type.
+    pub fn new(
+        resource_type: ResourceType,
+        name: Option<String>,
+        pattern_type: PatternType,
+    ) -> ResourcePatternFilter {
+        ResourcePatternFilter {
+            resource_type,
+            name,
+            pattern_type,
+        }
+    }
+
+    /// Returns true if this filter has any UNKNOWN components.
+    pub fn is_unknown(&self) -> bool {
+        self.resource_type.is_unknown() || self.pattern_type.is_unknown()
+    }
+
+    /// Returns true if this filter matches the given pattern.
+    pub fn matches(&self, pattern: &ResourcePattern) -> bool {
+        if self.resource_type != ResourceType::Any && self.resource_type != pattern.resource_type()
+        {
+            return false;
+        }
+        if self.pattern_type != PatternType::Any
+            && self.pattern_type != PatternType::Match
+            && self.pattern_type != pattern.pattern_type()
+        {
+            return false;
+        }
+        let name = match &self.name {
+            Some(name) => name,
+            None => return true,
+        };
+        if self.pattern_type == PatternType::Any || self.pattern_type == pattern.pattern_type() {
+            return name == pattern.name();
+        }
+        match pattern.pattern_type() {
+            PatternType::Literal => name == pattern.name() || pattern.name() == WILDCARD_RESOURCE,
+            PatternType::Prefixed => name.starts_with(pattern.name()),
+            _ => false,
+        }
+    }
+
+    /// Returns true if this filter could only match one pattern. In other words, if there are
+    /// no ANY or UNKNOWN fields.
+    pub fn matches_at_most_one(&self) -> bool {
+        self.find_indefinite_field().is_none()
+    }
+
+    /// Returns a string describing an ANY or UNKNOWN field, or `None` if there is no such field.
+    pub fn find_indefinite_field(&self) -> Option<&'static str> {
+        if self.resource_type == ResourceType::Any {
+            return Some("Resource type is ANY.");
+        }
+        if self.resource_type == ResourceType::Unknown {
+            return Some("Resource type is UNKNOWN.");
+        }
+        if self.name.is_none() {
+            return Some("Resource name is NULL.");
+        }
+        if self.pattern_type == PatternType::Match {
+            return Some("Resource pattern type is MATCH.");
+        }
+        if self.pattern_type == PatternType::Unknown {
+            return Some("Resource pattern type is UNKNOWN.");
+        }
+        None
+    }
+}
+
+impl Display for ResourcePatternFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ResourcePattern(resourceType={}, name={}, patternType={})",
+            self.resource_type,
+            self.name.as_deref().unwrap_or("<any>"),
+            self.pattern_type
+        )
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt::{self, Display};
+
+/// Represents a type of resource which an ACL can be applied to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceType {
+    /// Represents any ResourceType which this client cannot understand, perhaps because this
+    /// client is too old.
+    Unknown,
+    /// In a filter, matches any ResourceType.
+    Any,
+    /// A Kafka topic.
+    Topic,
+    /// A consumer group.
+    Group,
+    /// The cluster as a whole.
+    Cluster,
+    /// A transactional ID.
+    TransactionalId,
+    /// A token ID.
+    DelegationToken,
+}
+
+impl ResourceType {
+    pub fn code(&self) -> i8 {
+        match self {
+            ResourceType::Unknown => 0,
+            ResourceType::Any => 1,
+            ResourceType::Topic => 2,
+            ResourceType::Group => 3,
+            ResourceType::Cluster => 4,
+            ResourceType::TransactionalId => 5,
+            ResourceType::DelegationToken => 6,
+        }
+    }
+
+    /// Return the ResourceType with the provided code or `Unknown` if one cannot be found.
+    pub fn from_code(code: i8) -> ResourceType {
+        match code {
+            1 => ResourceType::Any,
+            2 => ResourceType::Topic,
+            3 => ResourceType::Group,
+            4 => ResourceType::Cluster,
+            5 => ResourceType::TransactionalId,
+            6 => ResourceType::DelegationToken,
+            _ => ResourceType::Unknown,
+        }
+    }
+
+    /// Return whether this resource type is `Unknown`.
+    pub fn is_unknown(&self) -> bool {
+        *self == ResourceType::Unknown
+    }
+}
+
+impl Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResourceType::Unknown => "UNKNOWN",
+            ResourceType::Any => "ANY",
+            ResourceType::Topic => "TOPIC",
+            ResourceType::Group => "GROUP",
+            ResourceType::Cluster => "CLUSTER",
+            ResourceType::TransactionalId => "TRANSACTIONAL_ID",
+            ResourceType::DelegationToken => "DELEGATION_TOKEN",
+        })
+    }
+}
@@ -0,0 +1,19 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+use super::deserializer::Deserializer;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByteArrayDeserializer;
+
+impl Deserializer<Option<Vec<u8>>> for ByteArrayDeserializer {
+    fn deserialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: Option<&Bytes>,
+    ) -> Result<Option<Vec<u8>>> {
+        Ok(data.map(|data| data.to_vec()))
+    }
+}
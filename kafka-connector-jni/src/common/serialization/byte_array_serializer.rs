@@ -0,0 +1,19 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+use super::serializer::Serializer;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ByteArraySerializer;
+
+impl Serializer<Option<Vec<u8>>> for ByteArraySerializer {
+    fn serialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: &Option<Vec<u8>>,
+    ) -> Result<Option<Bytes>> {
+        Ok(data.as_ref().map(|data| Bytes::copy_from_slice(data)))
+    }
+}
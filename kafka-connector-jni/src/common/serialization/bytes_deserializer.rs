@@ -0,0 +1,20 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+use super::deserializer::Deserializer;
+
+/// Passes the fetched buffers through without copying.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytesDeserializer;
+
+impl Deserializer<Option<Bytes>> for BytesDeserializer {
+    fn deserialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: Option<&Bytes>,
+    ) -> Result<Option<Bytes>> {
+        Ok(data.cloned())
+    }
+}
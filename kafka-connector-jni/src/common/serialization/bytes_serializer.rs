@@ -0,0 +1,20 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+use super::serializer::Serializer;
+
+/// Passes the buffers through without copying.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BytesSerializer;
+
+impl Serializer<Option<Bytes>> for BytesSerializer {
+    fn serialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: &Option<Bytes>,
+    ) -> Result<Option<Bytes>> {
+        Ok(data.clone())
+    }
+}
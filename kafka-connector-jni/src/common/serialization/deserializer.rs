@@ -0,0 +1,16 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+/// An interface for converting bytes to objects.
+///
+/// `data` is `None` for records with a null key or value, which implementations are expected to
+/// map to their "null" representation rather than fail.
+pub trait Deserializer<T>: Send + Sync {
+    /// Deserialize a record value from a byte array into a value or object.
+    ///
+    /// * `topic` - topic associated with the data
+    /// * `headers` - headers associated with the record; may be empty.
+    /// * `data` - serialized bytes; may be `None`
+    fn deserialize(&self, topic: &str, headers: &RecordHeaders, data: Option<&Bytes>) -> Result<T>;
+}
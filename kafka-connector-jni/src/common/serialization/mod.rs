@@ -0,0 +1,8 @@
+pub mod byte_array_deserializer;
+pub mod byte_array_serializer;
+pub mod bytes_deserializer;
+pub mod bytes_serializer;
+pub mod deserializer;
+pub mod serializer;
+pub mod string_deserializer;
+pub mod string_serializer;
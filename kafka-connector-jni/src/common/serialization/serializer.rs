@@ -0,0 +1,15 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+/// An interface for converting objects to bytes.
+///
+/// Returning `None` produces a record with a null key or value.
+pub trait Serializer<T>: Send + Sync {
+    /// Convert `data` into a byte array.
+    ///
+    /// * `topic` - topic associated with data
+    /// * `headers` - headers associated with the record
+    /// * `data` - typed data
+    fn serialize(&self, topic: &str, headers: &RecordHeaders, data: &T) -> Result<Option<Bytes>>;
+}
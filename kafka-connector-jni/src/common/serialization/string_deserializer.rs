@@ -0,0 +1,31 @@
+use bytes::Bytes;
+
+use crate::common::{
+    errors::{KafkaError, Result},
+    header::internals::record_headers::RecordHeaders,
+};
+
+use super::deserializer::Deserializer;
+
+/// String encoding defaults to UTF8, which is the only encoding supported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StringDeserializer;
+
+impl Deserializer<Option<String>> for StringDeserializer {
+    fn deserialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: Option<&Bytes>,
+    ) -> Result<Option<String>> {
+        match data {
+            Some(data) => String::from_utf8(data.to_vec()).map(Some).map_err(|_| {
+                KafkaError::Serialization(
+                    "Error when deserializing byte[] to string due to unsupported encoding UTF8"
+                        .to_owned(),
+                )
+            }),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use bytes::Bytes;
+
+use crate::common::{errors::Result, header::internals::record_headers::RecordHeaders};
+
+use super::serializer::Serializer;
+
+/// String encoding defaults to UTF8, which is the only encoding supported.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StringSerializer;
+
+impl Serializer<Option<String>> for StringSerializer {
+    fn serialize(
+        &self,
+        _topic: &str,
+        _headers: &RecordHeaders,
+        data: &Option<String>,
+    ) -> Result<Option<Bytes>> {
+        Ok(data
+            .as_ref()
+            .map(|data| Bytes::copy_from_slice(data.as_bytes())))
+    }
+}
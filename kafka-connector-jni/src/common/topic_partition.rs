@@ -0,0 +1,75 @@
+use std::fmt::{self, Display};
+
+use jni::{objects::JValue, JNIEnv};
+
+use crate::{
+    clone_from_java::{non_null, CloneFromJava},
+    clone_to_java::{kind, CloneToJava},
+    jni_cache,
+};
+
+/// A topic name and partition number
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TopicPartition {
+    pub topic: String,
+    pub partition: i32,
+}
+impl TopicPartition {
+    pub fn new(topic: impl Into<String>, partition: i32) -> TopicPartition {
+        TopicPartition {
+            topic: topic.into(),
+            partition,
+        }
+    }
+}
+
+impl Display for TopicPartition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.topic, self.partition)
+    }
+}
+
+/// `org.apache.kafka.common.TopicPartition`, a plain java value class.
+impl CloneToJava for TopicPartition {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        let topic = self.topic.clone_to_java(env)?;
+        jni_cache::new_object(
+            env,
+            "org/apache/kafka/common/TopicPartition",
+            "(Ljava/lang/String;I)V",
+            &[topic, JValue::Int(self.partition)],
+        )
+        .map(JValue::Object)
+    }
+}
+
+impl CloneFromJava for TopicPartition {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        let topic = jni_cache::call_method(
+            env,
+            obj,
+            "org/apache/kafka/common/TopicPartition",
+            "topic",
+            "()Ljava/lang/String;",
+            &[],
+        )?;
+        let partition = jni_cache::call_method(
+            env,
+            obj,
+            "org/apache/kafka/common/TopicPartition",
+            "partition",
+            "()I",
+            &[],
+        )?;
+        Ok(TopicPartition::new(
+            String::clone_from_java(env, topic)?,
+            partition.i()?,
+        ))
+    }
+}
@@ -0,0 +1,57 @@
+use std::fmt::{self, Display};
+
+use super::node::Node;
+
+/// A class containing leadership, replicas and ISR information for a topic partition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicPartitionInfo {
+    pub partition: i32,
+    /// The leader of the partition, `None` if there is none
+    pub leader: Option<Node>,
+    /// The replicas of the partition, the first one is the preferred leader
+    pub replicas: Vec<Node>,
+    /// The in-sync replicas of the partition
+    pub isr: Vec<Node>,
+}
+
+impl TopicPartitionInfo {
+    pub fn new(
+        partition: i32,
+        leader: Option<Node>,
+        replicas: Vec<Node>,
+        isr: Vec<Node>,
+    ) -> TopicPartitionInfo {
+        TopicPartitionInfo {
+            partition,
+            leader,
+            replicas,
+            isr,
+        }
+    }
+}
+
+fn write_nodes(f: &mut fmt::Formatter<'_>, nodes: &[Node]) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        node.fmt(f)?;
+    }
+    f.write_str("]")
+}
+
+impl Display for TopicPartitionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(partition={}, leader=", self.partition)?;
+        match &self.leader {
+            Some(leader) => leader.fmt(f)?,
+            None => f.write_str("none")?,
+        }
+        f.write_str(", replicas=")?;
+        write_nodes(f, &self.replicas)?;
+        f.write_str(", isr=")?;
+        write_nodes(f, &self.isr)?;
+        f.write_str(")")
+    }
+}
@@ -0,0 +1,115 @@
+use bytes::{Buf, BufMut};
+
+use crate::common::errors::{KafkaError, Result};
+
+/// Read an integer stored in variable-length format using unsigned decoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn read_unsigned_varint(buffer: &mut impl Buf) -> Result<u32> {
+    let mut value = 0_u32;
+    let mut i = 0;
+    loop {
+        let b = read_u8(buffer)? as u32;
+        if b & 0x80 == 0 {
+            return Ok(value | (b << i));
+        }
+        value |= (b & 0x7f) << i;
+        i += 7;
+        if i > 28 {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Varint is too long, the most significant bit in the 5th byte is set, converted value: {:x}",
+                value
+            )));
+        }
+    }
+}
+
+/// Read an integer stored in variable-length format using zig-zag decoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn read_varint(buffer: &mut impl Buf) -> Result<i32> {
+    let value = read_unsigned_varint(buffer)?;
+    Ok(((value >> 1) as i32) ^ -((value & 1) as i32))
+}
+
+/// Read a long stored in variable-length format using zig-zag decoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn read_varlong(buffer: &mut impl Buf) -> Result<i64> {
+    let mut value = 0_u64;
+    let mut i = 0;
+    loop {
+        let b = read_u8(buffer)? as u64;
+        if b & 0x80 == 0 {
+            let value = value | (b << i);
+            return Ok(((value >> 1) as i64) ^ -((value & 1) as i64));
+        }
+        value |= (b & 0x7f) << i;
+        i += 7;
+        if i > 63 {
+            return Err(KafkaError::IllegalArgument(format!(
+                "Varlong is too long, most significant bit in the 10th byte is set, converted value: {:x}",
+                value
+            )));
+        }
+    }
+}
+
+/// Write the given integer following the variable-length unsigned encoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn write_unsigned_varint(mut value: u32, buffer: &mut impl BufMut) {
+    while value & 0xffffff80 != 0 {
+        buffer.put_u8(((value & 0x7f) | 0x80) as u8);
+        value >>= 7;
+    }
+    buffer.put_u8(value as u8);
+}
+
+/// Write the given integer following the variable-length zig-zag encoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn write_varint(value: i32, buffer: &mut impl BufMut) {
+    write_unsigned_varint(((value << 1) ^ (value >> 31)) as u32, buffer)
+}
+
+/// Write the given long following the variable-length zig-zag encoding from
+/// [Google Protocol Buffers](http://code.google.com/apis/protocolbuffers/docs/encoding.html).
+pub fn write_varlong(value: i64, buffer: &mut impl BufMut) {
+    let mut v = ((value << 1) ^ (value >> 63)) as u64;
+    while v & 0xffffffffffffff80 != 0 {
+        buffer.put_u8(((v & 0x7f) | 0x80) as u8);
+        v >>= 7;
+    }
+    buffer.put_u8(v as u8);
+}
+
+/// Number of bytes needed to encode an integer in unsigned variable-length format.
+pub fn size_of_unsigned_varint(mut value: u32) -> usize {
+    let mut bytes = 1;
+    while value & 0xffffff80 != 0 {
+        bytes += 1;
+        value >>= 7;
+    }
+    bytes
+}
+
+/// Number of bytes needed to encode an integer in variable-length format.
+pub fn size_of_varint(value: i32) -> usize {
+    size_of_unsigned_varint(((value << 1) ^ (value >> 31)) as u32)
+}
+
+/// Number of bytes needed to encode a long in variable-length format.
+pub fn size_of_varlong(value: i64) -> usize {
+    let mut v = ((value << 1) ^ (value >> 63)) as u64;
+    let mut bytes = 1;
+    while v & 0xffffffffffffff80 != 0 {
+        bytes += 1;
+        v >>= 7;
+    }
+    bytes
+}
+
+fn read_u8(buffer: &mut impl Buf) -> Result<u8> {
+    if !buffer.has_remaining() {
+        return Err(KafkaError::Kafka(
+            "Reached end of buffer while reading varint".to_owned(),
+        ));
+    }
+    Ok(buffer.get_u8())
+}
@@ -0,0 +1,91 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// An utility class for keeping the parameters and providing the value of exponential
+/// retry backoff, exponential reconnect backoff, exponential timeout, etc.
+///
+/// The formula is:
+/// ```text
+/// Backoff(attempts) = random(1 - jitter, 1 + jitter) * initial_interval * multiplier ^ attempts
+/// ```
+/// If `initial_interval` is greater than or equal to `max_interval`, a constant backoff of
+/// `initial_interval` will be provided. This class is thread-safe.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialBackoff {
+    initial_interval: u128,
+    multiplier: u32,
+    jitter: f64,
+    exp_max: f64,
+}
+
+impl ExponentialBackoff {
+    pub fn new(
+        initial_interval: u128,
+        multiplier: u32,
+        max_interval: u128,
+        jitter: f64,
+    ) -> ExponentialBackoff {
+        let exp_max = if max_interval > initial_interval {
+            (max_interval as f64 / initial_interval.max(1) as f64).ln() / (multiplier as f64).ln()
+        } else {
+            0.0
+        };
+        ExponentialBackoff {
+            initial_interval,
+            multiplier,
+            jitter,
+            exp_max,
+        }
+    }
+
+    pub fn backoff(&self, attempts: u32) -> u128 {
+        if self.exp_max == 0.0 {
+            return self.initial_interval;
+        }
+        let exp = (attempts as f64).min(self.exp_max);
+        let term = self.initial_interval as f64 * (self.multiplier as f64).powf(exp);
+        let random_factor = if self.jitter < f64::MIN_POSITIVE {
+            1.0
+        } else {
+            1.0 - self.jitter + 2.0 * self.jitter * random_fraction()
+        };
+        (random_factor * term) as u128
+    }
+}
+
+/// A random number in `[0, 1)`, the per instance random keys of the standard library hasher are
+/// the source of randomness.
+fn random_fraction() -> f64 {
+    (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExponentialBackoff;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_the_max() {
+        let backoff = ExponentialBackoff::new(100, 2, 400, 0.2);
+        for attempts in 0..10 {
+            let expected = 100.0 * 2f64.powi(attempts.min(2) as i32);
+            let value = backoff.backoff(attempts) as f64;
+            assert!(
+                value >= expected * 0.8 - 1.0 && value <= expected * 1.2,
+                "backoff {} of attempt {}",
+                value,
+                attempts
+            );
+        }
+    }
+
+    #[test]
+    fn backoff_is_constant_without_max() {
+        let backoff = ExponentialBackoff::new(100, 2, 100, 0.2);
+        assert_eq!(backoff.backoff(0), 100);
+        assert_eq!(backoff.backoff(5), 100);
+        let backoff = ExponentialBackoff::new(100, 2, 1000, 0.0);
+        assert_eq!(backoff.backoff(1), 200);
+    }
+}
@@ -0,0 +1,86 @@
+use std::{fmt, sync::Arc};
+
+use log::{Level, Record};
+
+/// Provides loggers adding a common prefix to their messages, e.g. the client id of a consumer,
+/// so the messages of every component of a client can be told apart from the ones of other
+/// clients without repeating it in each message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LogContext {
+    log_prefix: Arc<str>,
+}
+
+impl LogContext {
+    pub fn new(log_prefix: impl Into<String>) -> LogContext {
+        LogContext {
+            log_prefix: log_prefix.into().into(),
+        }
+    }
+
+    /// Logger for `target`, usually `module_path!()`.
+    pub fn logger(&self, target: &'static str) -> Logger {
+        Logger {
+            target,
+            prefix: self.log_prefix.clone(),
+        }
+    }
+
+    pub fn log_prefix(&self) -> &str {
+        &self.log_prefix
+    }
+}
+
+/// Logger created by a `LogContext`, messages are formatted only for enabled levels.
+///
+/// ```ignore
+/// let log = LogContext::new("[Consumer clientId=consumer-1] ").logger(module_path!());
+/// log.info(format_args!("Subscribed to topic(s): {}", topics));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Logger {
+    target: &'static str,
+    prefix: Arc<str>,
+}
+
+impl Logger {
+    pub fn target(&self) -> &'static str {
+        self.target
+    }
+
+    pub fn is_enabled(&self, level: Level) -> bool {
+        log::log_enabled!(target: self.target, level)
+    }
+
+    pub fn log(&self, level: Level, args: fmt::Arguments) {
+        if !self.is_enabled(level) {
+            return;
+        }
+        log::logger().log(
+            &Record::builder()
+                .args(format_args!("{}{}", self.prefix, args))
+                .level(level)
+                .target(self.target)
+                .build(),
+        );
+    }
+
+    pub fn error(&self, args: fmt::Arguments) {
+        self.log(Level::Error, args)
+    }
+
+    pub fn warn(&self, args: fmt::Arguments) {
+        self.log(Level::Warn, args)
+    }
+
+    pub fn info(&self, args: fmt::Arguments) {
+        self.log(Level::Info, args)
+    }
+
+    pub fn debug(&self, args: fmt::Arguments) {
+        self.log(Level::Debug, args)
+    }
+
+    pub fn trace(&self, args: fmt::Arguments) {
+        self.log(Level::Trace, args)
+    }
+}
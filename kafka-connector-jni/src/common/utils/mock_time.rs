@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use super::time::Time;
+
+/// A clock that you can manually advance by calling `sleep`.
+///
+/// Every read of the clock advances it by `auto_tick_ms`, which lets code waiting on a timeout
+/// make progress without a second thread moving the time forward.
+#[derive(Debug)]
+pub struct MockTime {
+    time_ms: AtomicU64,
+    high_res_time_ns: AtomicU64,
+    auto_tick_ms: u64,
+}
+
+impl MockTime {
+    pub fn new(auto_tick_ms: u64, current_time_ms: u64, current_high_res_time_ns: u64) -> MockTime {
+        MockTime {
+            time_ms: AtomicU64::new(current_time_ms),
+            high_res_time_ns: AtomicU64::new(current_high_res_time_ns),
+            auto_tick_ms,
+        }
+    }
+
+    /// A clock starting at the current system time, which only moves when it's told to.
+    pub fn with_auto_tick(auto_tick_ms: u64) -> MockTime {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        MockTime::new(auto_tick_ms, now.as_millis() as u64, now.as_nanos() as u64)
+    }
+
+    fn maybe_sleep(&self, ms: u64) {
+        if ms != 0 {
+            self.sleep(ms as u128);
+        }
+    }
+
+    /// Set the wall clock to `new_ms`. Moving the clock backwards is an error.
+    pub fn set_current_time_ms(&self, new_ms: u64) {
+        let old_ms = self.time_ms.load(Ordering::SeqCst);
+        assert!(
+            new_ms >= old_ms,
+            "Setting the time to {} while current time {} is newer; this is not allowed",
+            new_ms,
+            old_ms
+        );
+        self.time_ms.store(new_ms, Ordering::SeqCst);
+        self.high_res_time_ns
+            .fetch_add((new_ms - old_ms) * 1_000_000, Ordering::SeqCst);
+    }
+}
+
+impl Default for MockTime {
+    fn default() -> MockTime {
+        MockTime::with_auto_tick(0)
+    }
+}
+
+impl Time for MockTime {
+    fn milliseconds(&self) -> u128 {
+        self.maybe_sleep(self.auto_tick_ms);
+        self.time_ms.load(Ordering::SeqCst) as u128
+    }
+
+    fn nanoseconds(&self) -> u128 {
+        self.maybe_sleep(self.auto_tick_ms);
+        self.high_res_time_ns.load(Ordering::SeqCst) as u128
+    }
+
+    fn sleep(&self, ms: u128) {
+        let ms = ms as u64;
+        self.time_ms.fetch_add(ms, Ordering::SeqCst);
+        self.high_res_time_ns
+            .fetch_add(ms * 1_000_000, Ordering::SeqCst);
+    }
+}
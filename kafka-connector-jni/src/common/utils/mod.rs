@@ -0,0 +1,108 @@
+pub mod byte_utils;
+pub mod crc32;
+pub mod crc32c;
+pub mod exponential_backoff;
+pub mod log_context;
+#[cfg(test)]
+pub mod mock_time;
+pub mod producer_id_and_epoch;
+pub mod properties;
+pub mod time;
+pub mod timer;
+
+/// Generates 32 bit murmur2 hash from byte array
+pub fn murmur2(data: &[u8]) -> i32 {
+    let length = data.len();
+    let seed = 0x9747b28c_u32;
+    // 'm' and 'r' are mixing constants generated offline.
+    // They're not really 'magic', they just happen to work well.
+    let m = 0x5bd1e995_u32;
+    let r = 24;
+
+    // Initialize the hash to a random value
+    let mut h = seed ^ length as u32;
+    let length4 = length / 4;
+
+    for i in 0..length4 {
+        let i4 = i * 4;
+        let mut k = (data[i4] as u32)
+            | ((data[i4 + 1] as u32) << 8)
+            | ((data[i4 + 2] as u32) << 16)
+            | ((data[i4 + 3] as u32) << 24);
+        k = k.wrapping_mul(m);
+        k ^= k >> r;
+        k = k.wrapping_mul(m);
+        h = h.wrapping_mul(m);
+        h ^= k;
+    }
+
+    // Handle the last few bytes of the input array
+    let tail = length & !3;
+    let remaining = length % 4;
+    if remaining == 3 {
+        h ^= (data[tail + 2] as u32) << 16;
+    }
+    if remaining >= 2 {
+        h ^= (data[tail + 1] as u32) << 8;
+    }
+    if remaining >= 1 {
+        h ^= data[tail] as u32;
+        h = h.wrapping_mul(m);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(m);
+    h ^= h >> 15;
+
+    h as i32
+}
+
+/// A cheap way to deterministically convert a number to a positive value. When the input is
+/// positive, the original value is returned. When the input number is negative, the returned
+/// positive value is the original value bit AND against 0x7fffffff which is not its absolute
+/// value.
+///
+/// Note: changing this method in the future will possibly cause partition selection not to be
+/// compatible with the existing messages already placed on a partition since it is used
+/// in producer's partition selection logic.
+pub fn to_positive(number: i32) -> i32 {
+    number & 0x7fffffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{murmur2, to_positive};
+
+    #[test]
+    fn murmur2_matches_the_java_client() {
+        let cases: [(&[u8], i32); 11] = [
+            (b"21", -973932308),
+            (b"foobar", -790332482),
+            (b"a-little-bit-long-string", -985981536),
+            (b"a-little-bit-longer-string", -1486304829),
+            (
+                b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58897971,
+            ),
+            (b"abc", 479470107),
+            (b"key-1", 193331640),
+            (b"", 275646681),
+            // bytes above 0x7f are negative in Java, they must hash as unsigned
+            (&[0xff], -311467685),
+            (&[0x80, 0xfe], 1316928818),
+            (&[0xde, 0xad, 0xbe, 0xef, 0x9a], 492644201),
+        ];
+        for (data, expected) in cases {
+            assert_eq!(murmur2(data), expected, "murmur2 of {:?}", data);
+        }
+    }
+
+    #[test]
+    fn to_positive_masks_the_sign_bit() {
+        assert_eq!(to_positive(0), 0);
+        assert_eq!(to_positive(1), 1);
+        assert_eq!(to_positive(-1), i32::MAX);
+        assert_eq!(to_positive(i32::MIN), 0);
+        assert_eq!(to_positive(-973932308), 1173551340);
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt::{self, Display};
+
+use crate::common::record::record_batch::{NO_PRODUCER_EPOCH, NO_PRODUCER_ID};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProducerIdAndEpoch {
+    pub producer_id: i64,
+    pub epoch: i16,
+}
+
+impl ProducerIdAndEpoch {
+    pub const NONE: ProducerIdAndEpoch = ProducerIdAndEpoch {
+        producer_id: NO_PRODUCER_ID,
+        epoch: NO_PRODUCER_EPOCH,
+    };
+
+    pub fn new(producer_id: i64, epoch: i16) -> ProducerIdAndEpoch {
+        ProducerIdAndEpoch { producer_id, epoch }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.producer_id != NO_PRODUCER_ID
+    }
+}
+
+impl Default for ProducerIdAndEpoch {
+    fn default() -> Self {
+        ProducerIdAndEpoch::NONE
+    }
+}
+
+impl Display for ProducerIdAndEpoch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProducerIdAndEpoch(producerId={}, epoch={})",
+            self.producer_id, self.epoch
+        )
+    }
+}
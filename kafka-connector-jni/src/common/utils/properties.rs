@@ -0,0 +1,194 @@
+use std::{fs, path::Path};
+
+use indexmap::IndexMap;
+
+use crate::common::errors::{KafkaError, Result};
+
+/// Read a properties file from the given path
+pub fn load_props(path: impl AsRef<Path>) -> Result<IndexMap<String, String>> {
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(|e| {
+        KafkaError::Kafka(format!(
+            "Failed to read properties file {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    parse_properties(&content)
+}
+
+/// Parses text in the `java.util.Properties` format.
+///
+/// Lines starting with `#` or `!` are comments, keys are separated from values by `=`, `:` or whitespace,
+/// a line ending with an odd number of backslashes continues on the next line and `\t`, `\n`, `\r`, `\f`
+/// and `\uXXXX` escapes are supported. Later entries override earlier ones.
+pub fn parse_properties(content: &str) -> Result<IndexMap<String, String>> {
+    let mut props = IndexMap::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim_start_matches(is_whitespace);
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let mut logical_line = String::from(line);
+        while ends_with_continuation(&logical_line) {
+            logical_line.pop();
+            match lines.next() {
+                Some(next) => logical_line.push_str(next.trim_start_matches(is_whitespace)),
+                None => break,
+            }
+        }
+        let (key, value) = split_key_value(&logical_line);
+        props.insert(unescape(key)?, unescape(value)?);
+    }
+    Ok(props)
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\u{c}'
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+fn split_key_value(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || is_whitespace(c) {
+            let key = &line[..i];
+            // whitespace may surround a single '=' or ':' separator
+            let mut rest = line[i..].trim_start_matches(is_whitespace);
+            if rest.starts_with('=') || rest.starts_with(':') {
+                rest = rest[1..].trim_start_matches(is_whitespace);
+            }
+            return (key, rest);
+        }
+    }
+    (line, "")
+}
+
+fn unescape(value: &str) -> Result<String> {
+    let mut result = String::with_capacity(value.len());
+    // `\uxxxx` escapes are UTF-16 code units, a surrogate pair is escaped as two of them
+    let mut code_units: Vec<u16> = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.as_str().starts_with('u') {
+            chars.next();
+            let hex: String = chars.by_ref().take(4).collect();
+            if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(KafkaError::IllegalArgument(
+                    "Malformed \\uxxxx encoding.".to_owned(),
+                ));
+            }
+            code_units.push(u16::from_str_radix(&hex, 16).expect("hex digits"));
+            continue;
+        }
+        push_code_units(&mut result, &mut code_units);
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    push_code_units(&mut result, &mut code_units);
+    Ok(result)
+}
+
+/// Decodes the pending UTF-16 code units into `result`, unpaired surrogates are replaced by
+/// U+FFFD.
+fn push_code_units(result: &mut String, code_units: &mut Vec<u16>) {
+    result.extend(
+        char::decode_utf16(code_units.drain(..)).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::common::errors::KafkaError;
+
+    use super::parse_properties;
+
+    fn parse(content: &str) -> Vec<(String, String)> {
+        parse_properties(content).unwrap().into_iter().collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn separators_and_comments() {
+        let content = "# comment\n! also a comment\n\na=1\nb : 2\nc 3\n  d=\ne\n";
+        assert_eq!(
+            parse(content),
+            pairs(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", ""), ("e", "")])
+        );
+    }
+
+    #[test]
+    fn later_entries_override_earlier_ones() {
+        assert_eq!(parse("a=1\nb=2\na=3\n"), pairs(&[("a", "3"), ("b", "2")]));
+    }
+
+    #[test]
+    fn continuation_lines() {
+        let content = "list=a,\\\n    b,\\\n    c\nescaped=ends with \\\\\nnext=1";
+        assert_eq!(
+            parse(content),
+            pairs(&[
+                ("list", "a,b,c"),
+                ("escaped", "ends with \\"),
+                ("next", "1")
+            ])
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let content =
+            "key\\=with\\:separators\\ x=tab\\tnew\\nline\\r\\f\\q\nunicode=\\u00e9t\\u00C9";
+        assert_eq!(
+            parse(content),
+            pairs(&[
+                ("key=with:separators x", "tab\tnew\nline\r\u{c}q"),
+                ("unicode", "\u{e9}t\u{c9}")
+            ])
+        );
+    }
+
+    #[test]
+    fn surrogate_pairs_are_combined() {
+        assert_eq!(
+            parse("emoji=\\uD83D\\uDE00!\nlone=\\uD83Dx\\uDE00"),
+            pairs(&[("emoji", "\u{1F600}!"), ("lone", "\u{FFFD}x\u{FFFD}")])
+        );
+    }
+
+    #[test]
+    fn malformed_unicode_escapes_fail() {
+        for content in ["a=\\u12", "a=\\u12g4", "a=\\u+123"] {
+            match parse_properties(content) {
+                Err(KafkaError::IllegalArgument(message)) => {
+                    assert_eq!(message, "Malformed \\uxxxx encoding.")
+                }
+                other => panic!("unexpected result {:?} for {}", other, content),
+            }
+        }
+    }
+}
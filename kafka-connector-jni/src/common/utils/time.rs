@@ -0,0 +1,45 @@
+use std::{
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+
+/// An interface abstracting the clock to use in unit testing classes that make use of clock time.
+pub trait Time: Send + Sync {
+    /// Returns the current time in milliseconds.
+    fn milliseconds(&self) -> u128;
+
+    /// Returns the value returned by `nanoseconds` converted into milliseconds.
+    fn hi_res_clock_ms(&self) -> u128 {
+        self.nanoseconds() / 1_000_000
+    }
+
+    /// Returns the current value of the running JVM's high-resolution time source, in nanoseconds.
+    fn nanoseconds(&self) -> u128;
+
+    /// Sleep for the given number of milliseconds
+    fn sleep(&self, ms: u128);
+}
+
+/// A time implementation that uses the system clock and sleep call.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemTime;
+
+impl Time for SystemTime {
+    fn milliseconds(&self) -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+    }
+
+    fn nanoseconds(&self) -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    }
+
+    fn sleep(&self, ms: u128) {
+        thread::sleep(Duration::from_millis(ms as u64));
+    }
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use super::time::Time;
+
+/// This is a helper class which makes blocking methods with a timeout easier to implement.
+/// In particular it enables use cases where a high-level blocking call with a timeout is
+/// composed of several lower level calls, each of which has their own respective timeouts.
+///
+/// This class is not thread safe.
+#[derive(Clone)]
+pub struct Timer {
+    time: Arc<dyn Time>,
+    start_ms: u128,
+    current_time_ms: u128,
+    deadline_ms: u128,
+    timeout_ms: u128,
+}
+
+impl Timer {
+    pub fn new(time: Arc<dyn Time>, timeout_ms: u128) -> Timer {
+        let current_time_ms = time.milliseconds();
+        let mut timer = Timer {
+            time,
+            start_ms: current_time_ms,
+            current_time_ms,
+            deadline_ms: current_time_ms,
+            timeout_ms: 0,
+        };
+        timer.reset(timeout_ms);
+        timer
+    }
+
+    /// Check timer expiration. Like `remaining_ms`, this depends on the current cached
+    /// time in milliseconds, which is only updated through one of the `update` methods
+    /// or with `sleep`.
+    pub fn is_expired(&self) -> bool {
+        self.current_time_ms >= self.deadline_ms
+    }
+
+    /// Check whether the timer has not yet expired.
+    pub fn not_expired(&self) -> bool {
+        !self.is_expired()
+    }
+
+    /// Reset the timer to the specific timeout. This will update the current cached time in
+    /// milliseconds and it will set a new timer deadline.
+    pub fn update_and_reset(&mut self, timeout_ms: u128) {
+        self.update();
+        self.reset(timeout_ms);
+    }
+
+    /// Reset the timer using a new timeout. Note that this does not update the cached current
+    /// time in milliseconds, so it typically must be accompanied with a separate call to
+    /// `update`. Typically, you can just use `update_and_reset`.
+    pub fn reset(&mut self, timeout_ms: u128) {
+        self.timeout_ms = timeout_ms;
+        self.start_ms = self.current_time_ms;
+        self.deadline_ms = self.current_time_ms.saturating_add(timeout_ms);
+    }
+
+    /// Reset the timer's deadline directly.
+    pub fn reset_deadline(&mut self, deadline_ms: u128) {
+        self.timeout_ms = deadline_ms.saturating_sub(self.current_time_ms);
+        self.start_ms = self.current_time_ms;
+        self.deadline_ms = deadline_ms;
+    }
+
+    /// Use the underlying `Time` implementation to update the current cached time. If
+    /// the underlying time returns a value which is smaller than the current cached time,
+    /// the update will be ignored.
+    pub fn update(&mut self) {
+        let now = self.time.milliseconds();
+        self.update_to(now);
+    }
+
+    /// Update the cached current time to a specific value. In some contexts, the caller may
+    /// already have an accurate time, so this avoids unnecessary calls to system time.
+    ///
+    /// Note that if the updated current time is smaller than the cached time, then the update
+    /// is ignored.
+    pub fn update_to(&mut self, current_time_ms: u128) {
+        self.current_time_ms = self.current_time_ms.max(current_time_ms);
+    }
+
+    /// Get the remaining time in milliseconds until the timer expires. Like `current_time_ms`,
+    /// this depends on the cached current time, so the returned value will not change until the
+    /// timer has been updated using one of the `update` methods or `sleep`.
+    pub fn remaining_ms(&self) -> u128 {
+        self.deadline_ms.saturating_sub(self.current_time_ms)
+    }
+
+    /// Get the current time in milliseconds. This will return the same cached value until the
+    /// timer has been updated using one of the `update` methods or `sleep` is used.
+    pub fn current_time_ms(&self) -> u128 {
+        self.current_time_ms
+    }
+
+    /// Get the amount of time that has elapsed since the timer began. If the timer was reset,
+    /// this will be the amount of time since the last reset.
+    pub fn elapsed_ms(&self) -> u128 {
+        self.current_time_ms - self.start_ms
+    }
+
+    /// Get the current timeout value specified through `reset` or `reset_deadline`.
+    pub fn timeout_ms(&self) -> u128 {
+        self.timeout_ms
+    }
+
+    /// Sleep for the requested duration and update the timer. Return when either the duration
+    /// has elapsed or the timer has expired.
+    pub fn sleep(&mut self, duration_ms: u128) {
+        let sleep_duration_ms = duration_ms.min(self.remaining_ms());
+        self.time.sleep(sleep_duration_ms);
+        self.update();
+    }
+}
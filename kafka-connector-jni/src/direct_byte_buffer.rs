@@ -0,0 +1,427 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::c_void,
+    slice,
+    sync::{Mutex, MutexGuard},
+};
+
+use bytes::Bytes;
+use jni::{
+    errors::{Error, Result},
+    objects::{GlobalRef, JClass, JObject, JValue},
+    sys::jlong,
+    JNIEnv,
+};
+use once_cell::sync::Lazy;
+
+use crate::{
+    clone_from_java::{non_null, CloneFromJava},
+    clone_to_java::{kind, CloneToJava},
+    common::errors::JniResult,
+    jni_cache,
+    jni_guard::jni_guard,
+};
+
+/// `Bytes` exchanged with java as a read-only direct `java.nio.ByteBuffer` instead of a copied
+/// `byte[]`. The buffer points to the memory of the `Bytes`, which is retained until java
+/// collects the buffer.
+///
+/// The remaining bytes of direct buffers are read without copying. Memory retained this way is
+/// shared with the `Bytes` holding it, other direct memory is borrowed by a `Bytes` owning a
+/// global reference to the buffer, as java frees it once the buffer is collected. Java must not
+/// modify the remaining bytes while rust holds them. Heap buffers are copied.
+///
+/// Header values and the keys and values of records appended to `RustRecordAccumulator` are
+/// read this way.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirectByteBuffer(pub Bytes);
+
+impl From<Bytes> for DirectByteBuffer {
+    fn from(value: Bytes) -> Self {
+        DirectByteBuffer(value)
+    }
+}
+
+impl From<DirectByteBuffer> for Bytes {
+    fn from(value: DirectByteBuffer) -> Self {
+        value.0
+    }
+}
+
+/// `Bytes` referenced by java buffers, by the handle passed to `RustByteBuffers.wrap`.
+#[derive(Default)]
+struct RetainedBytes {
+    last_handle: jlong,
+    by_handle: HashMap<jlong, Bytes>,
+    /// Handles by the address of the first byte, to find the `Bytes` of buffers passed back.
+    by_address: BTreeMap<usize, Vec<jlong>>,
+    /// Number of retained `Bytes` by length, the longest bounds the search by address.
+    lengths: BTreeMap<usize, usize>,
+}
+
+impl RetainedBytes {
+    fn insert(&mut self, bytes: Bytes) -> jlong {
+        self.last_handle += 1;
+        let handle = self.last_handle;
+        *self.lengths.entry(bytes.len()).or_default() += 1;
+        self.by_address
+            .entry(bytes.as_ptr() as usize)
+            .or_default()
+            .push(handle);
+        self.by_handle.insert(handle, bytes);
+        handle
+    }
+
+    fn remove(&mut self, handle: jlong) -> Option<Bytes> {
+        let bytes = self.by_handle.remove(&handle)?;
+        let address = bytes.as_ptr() as usize;
+        if let Some(handles) = self.by_address.get_mut(&address) {
+            handles.retain(|h| *h != handle);
+            if handles.is_empty() {
+                self.by_address.remove(&address);
+            }
+        }
+        if let Some(count) = self.lengths.get_mut(&bytes.len()) {
+            *count -= 1;
+            if *count == 0 {
+                self.lengths.remove(&bytes.len());
+            }
+        }
+        Some(bytes)
+    }
+
+    /// Length of the longest retained `Bytes`.
+    fn max_len(&self) -> usize {
+        self.lengths.keys().next_back().copied().unwrap_or(0)
+    }
+
+    /// Retained memory from `address` to `address + len`, sharing the `Bytes` containing it.
+    fn slice(&self, address: usize, len: usize) -> Option<Bytes> {
+        let lowest = address.saturating_sub(self.max_len());
+        self.by_address
+            .range(lowest..=address)
+            .rev()
+            .flat_map(|(start, handles)| handles.iter().map(move |handle| (*start, handle)))
+            .filter_map(|(start, handle)| Some((start, self.by_handle.get(handle)?)))
+            .find(|(start, bytes)| address + len <= start + bytes.len())
+            .map(|(start, bytes)| bytes.slice(address - start..address - start + len))
+    }
+}
+
+/// Never dropped, java may release buffers until the library is unloaded.
+static RETAINED: Lazy<Mutex<RetainedBytes>> = Lazy::new(Mutex::default);
+
+fn retained() -> MutexGuard<'static, RetainedBytes> {
+    RETAINED.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl CloneToJava for DirectByteBuffer {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> Result<JValue<'a>> {
+        let buffer = new_direct_byte_buffer(env, &self.0)?;
+        let handle = retained().insert(self.0.clone());
+        let wrapped = jni_cache::call_static_method(
+            env,
+            "org/apache/kafka/RustByteBuffers",
+            "wrap",
+            "(Ljava/nio/ByteBuffer;J)Ljava/nio/ByteBuffer;",
+            &[buffer.into(), JValue::Long(handle)],
+        );
+        if wrapped.is_err() {
+            retained().remove(handle);
+        }
+        // Only reachable through the read-only view from now on.
+        env.delete_local_ref(buffer)?;
+        wrapped
+    }
+}
+
+impl CloneFromJava for DirectByteBuffer {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let buffer = non_null::<Self>(env, obj.l()?)?;
+        let position =
+            jni_cache::call_method(env, buffer, "java/nio/Buffer", "position", "()I", &[])?.i()?;
+        let remaining =
+            jni_cache::call_method(env, buffer, "java/nio/Buffer", "remaining", "()I", &[])?.i()?;
+        let address = direct_buffer_address(env, buffer)?;
+        if address.is_null() {
+            return heap_buffer_bytes(env, buffer, remaining).map(DirectByteBuffer);
+        }
+        let address = address as usize + position as usize;
+        let len = remaining as usize;
+        if let Some(bytes) = retained().slice(address, len) {
+            return Ok(DirectByteBuffer(bytes));
+        }
+        if len == 0 {
+            return Ok(DirectByteBuffer(Bytes::new()));
+        }
+        let memory = JavaDirectMemory {
+            _buffer: env.new_global_ref(buffer)?,
+            address,
+            len,
+        };
+        Ok(DirectByteBuffer(Bytes::from_owner(memory)))
+    }
+}
+
+/// Memory of a java direct buffer, valid as long as the buffer is reachable. Buffers derived
+/// from another one reference it, so the memory of slices stays valid as well.
+struct JavaDirectMemory {
+    _buffer: GlobalRef,
+    address: usize,
+    len: usize,
+}
+
+impl AsRef<[u8]> for JavaDirectMemory {
+    fn as_ref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.address as *const u8, self.len) }
+    }
+}
+
+/// `NewDirectByteBuffer` over `data`, which java must not modify.
+fn new_direct_byte_buffer<'a>(env: JNIEnv<'a>, data: &[u8]) -> Result<JObject<'a>> {
+    let raw = env.get_native_interface();
+    let buffer = unsafe {
+        let new_direct_byte_buffer = (**raw)
+            .NewDirectByteBuffer
+            .ok_or(Error::JNIEnvMethodNotFound("NewDirectByteBuffer"))?;
+        new_direct_byte_buffer(raw, data.as_ptr() as *mut c_void, data.len() as jlong)
+    };
+    if buffer.is_null() {
+        return Err(Error::NullPtr("NewDirectByteBuffer result"));
+    }
+    Ok(JObject::from(buffer))
+}
+
+/// `GetDirectBufferAddress` of `buffer`, null for heap buffers.
+fn direct_buffer_address(env: JNIEnv, buffer: JObject) -> Result<*mut c_void> {
+    let raw = env.get_native_interface();
+    unsafe {
+        let get_direct_buffer_address = (**raw)
+            .GetDirectBufferAddress
+            .ok_or(Error::JNIEnvMethodNotFound("GetDirectBufferAddress"))?;
+        Ok(get_direct_buffer_address(raw, buffer.into_inner()))
+    }
+}
+
+/// Copies the `remaining` bytes of a heap buffer, leaving its position unchanged.
+fn heap_buffer_bytes(env: JNIEnv, buffer: JObject, remaining: i32) -> Result<Bytes> {
+    let duplicate = jni_cache::call_method(
+        env,
+        buffer,
+        "java/nio/ByteBuffer",
+        "duplicate",
+        "()Ljava/nio/ByteBuffer;",
+        &[],
+    )?
+    .l()?;
+    let array = env.new_byte_array(remaining)?;
+    jni_cache::call_method(
+        env,
+        duplicate,
+        "java/nio/ByteBuffer",
+        "get",
+        "([B)Ljava/nio/ByteBuffer;",
+        &[JObject::from(array).into()],
+    )?;
+    env.convert_byte_array(array).map(Bytes::from)
+}
+
+/*
+ * Class:     org_apache_kafka_RustByteBuffers
+ * Method:    release
+ * Signature: (J)V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_RustByteBuffers_release(
+    env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        // Dropped outside of the lock.
+        let bytes = retained().remove(handle);
+        drop(bytes);
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use jni::{
+        objects::{JObject, JValue},
+        JNIEnv,
+    };
+
+    use super::{direct_buffer_address, DirectByteBuffer, RetainedBytes};
+    use crate::{clone_from_java::CloneFromJava, clone_to_java::CloneToJava, jvm};
+
+    fn address(bytes: &Bytes) -> usize {
+        bytes.as_ptr() as usize
+    }
+
+    /// `buffer.position(position)`, declared by `Buffer` on java 8.
+    fn set_position(env: JNIEnv, buffer: JObject, position: i32) -> jni::errors::Result<()> {
+        env.call_method(
+            buffer,
+            "position",
+            "(I)Ljava/nio/Buffer;",
+            &[JValue::Int(position)],
+        )?;
+        Ok(())
+    }
+
+    /// Java buffer holding `bytes`, positioned at `position`.
+    fn java_buffer<'a>(
+        env: JNIEnv<'a>,
+        allocate: &str,
+        bytes: &[u8],
+        position: i32,
+    ) -> jni::errors::Result<JObject<'a>> {
+        let buffer = env
+            .call_static_method(
+                "java/nio/ByteBuffer",
+                allocate,
+                "(I)Ljava/nio/ByteBuffer;",
+                &[JValue::Int(bytes.len() as i32)],
+            )?
+            .l()?;
+        env.call_method(
+            buffer,
+            "put",
+            "([B)Ljava/nio/ByteBuffer;",
+            &[jvm::byte_array(env, bytes)?],
+        )?;
+        set_position(env, buffer, position)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn slice_shares_retained_memory() {
+        let mut retained = RetainedBytes::default();
+        let bytes = Bytes::from(vec![1, 2, 3, 4, 5]);
+        retained.insert(bytes.clone());
+
+        let slice = retained.slice(address(&bytes) + 1, 3).expect("retained");
+        assert_eq!(slice, [2, 3, 4][..]);
+        assert_eq!(address(&slice), address(&bytes) + 1);
+        assert_eq!(retained.slice(address(&bytes), 5).expect("retained"), bytes);
+        assert_eq!(
+            retained.slice(address(&bytes) + 5, 0).expect("retained"),
+            Bytes::new()
+        );
+    }
+
+    #[test]
+    fn slice_outside_of_retained_memory_is_none() {
+        let mut retained = RetainedBytes::default();
+        let bytes = Bytes::from(vec![1, 2, 3, 4, 5]);
+        retained.insert(bytes.clone());
+
+        assert_eq!(retained.slice(address(&bytes) + 3, 3), None);
+        assert_eq!(retained.slice(address(&bytes) - 1, 2), None);
+        assert_eq!(retained.slice(address(&bytes) + 6, 0), None);
+    }
+
+    #[test]
+    fn slice_finds_the_bytes_containing_it() {
+        let mut retained = RetainedBytes::default();
+        let whole = Bytes::from(vec![0; 100]);
+        let long = whole.slice(10..90);
+        let short = whole.slice(20..30);
+        retained.insert(short.clone());
+        let handle = retained.insert(long.clone());
+
+        // Starts in `short` but ends after it, only `long` contains it.
+        let slice = retained.slice(address(&short) + 5, 20).expect("retained");
+        assert_eq!(address(&slice), address(&short) + 5);
+        assert_eq!(slice.len(), 20);
+
+        retained.remove(handle);
+        assert_eq!(retained.slice(address(&short) + 5, 20), None);
+        assert!(retained.slice(address(&short) + 5, 5).is_some());
+    }
+
+    #[test]
+    fn max_len_follows_the_longest_retained_bytes() {
+        let mut retained = RetainedBytes::default();
+        assert_eq!(retained.max_len(), 0);
+        let first = retained.insert(Bytes::from(vec![0; 100]));
+        let second = retained.insert(Bytes::from(vec![0; 100]));
+        let short = retained.insert(Bytes::from(vec![0; 10]));
+        assert_eq!(retained.max_len(), 100);
+
+        retained.remove(first);
+        assert_eq!(retained.max_len(), 100);
+        retained.remove(second);
+        assert_eq!(retained.max_len(), 10);
+        retained.remove(short);
+        assert_eq!(retained.max_len(), 0);
+    }
+
+    #[test]
+    fn removed_bytes_are_not_sliced() {
+        let mut retained = RetainedBytes::default();
+        let bytes = Bytes::from(vec![1, 2, 3]);
+        let first = retained.insert(bytes.clone());
+        let second = retained.insert(bytes.clone());
+
+        assert_eq!(retained.remove(first), Some(bytes.clone()));
+        assert!(retained.slice(address(&bytes), 3).is_some());
+        assert_eq!(retained.remove(second), Some(bytes.clone()));
+        assert_eq!(retained.slice(address(&bytes), 3), None);
+        assert_eq!(retained.remove(second), None);
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn rust_bytes_are_passed_back_without_copying() {
+        jvm::run(|env| {
+            let bytes = Bytes::from(vec![1, 2, 3, 4, 5]);
+            let buffer = DirectByteBuffer(bytes.clone()).clone_to_java(env)?.l()?;
+            set_position(env, buffer, 1)?;
+
+            let DirectByteBuffer(read) = CloneFromJava::clone_from_java(env, buffer.into())?;
+            assert_eq!(read, [2, 3, 4, 5][..]);
+            assert_eq!(address(&read), address(&bytes) + 1);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn java_direct_memory_is_borrowed() {
+        jvm::run(|env| {
+            let buffer = java_buffer(env, "allocateDirect", &[1, 2, 3, 4, 5], 2)?;
+            let memory = direct_buffer_address(env, buffer)? as usize;
+
+            let DirectByteBuffer(read) = CloneFromJava::clone_from_java(env, buffer.into())?;
+            assert_eq!(address(&read), memory + 2);
+            // The bytes keep the buffer reachable.
+            env.delete_local_ref(buffer)?;
+            env.call_static_method("java/lang/System", "gc", "()V", &[])?;
+            assert_eq!(read, [3, 4, 5][..]);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn heap_buffers_are_copied_from_their_position() {
+        jvm::run(|env| {
+            let buffer = java_buffer(env, "allocate", &[1, 2, 3], 1)?;
+
+            let DirectByteBuffer(read) = CloneFromJava::clone_from_java(env, buffer.into())?;
+            assert_eq!(read, [2, 3][..]);
+            let position = env.call_method(buffer, "position", "()I", &[])?.i()?;
+            assert_eq!(position, 1);
+            Ok(())
+        });
+    }
+}
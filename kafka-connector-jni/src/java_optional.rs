@@ -0,0 +1,259 @@
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+
+use crate::{
+    clone_from_java::{non_null, CloneFromJava},
+    clone_to_java::{kind, CloneToJava},
+    jni_cache,
+};
+
+/// `Option` converted to `java.util.Optional` instead of a nullable reference.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JavaOptional<T>(pub Option<T>);
+
+/// `Option<i32>` converted to `java.util.OptionalInt`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JavaOptionalInt(pub Option<i32>);
+
+/// `Option<i64>` converted to `java.util.OptionalLong`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JavaOptionalLong(pub Option<i64>);
+
+impl<T> From<Option<T>> for JavaOptional<T> {
+    fn from(value: Option<T>) -> Self {
+        JavaOptional(value)
+    }
+}
+
+impl From<Option<i32>> for JavaOptionalInt {
+    fn from(value: Option<i32>) -> Self {
+        JavaOptionalInt(value)
+    }
+}
+
+impl From<Option<i64>> for JavaOptionalLong {
+    fn from(value: Option<i64>) -> Self {
+        JavaOptionalLong(value)
+    }
+}
+
+impl<T> CloneToJava for JavaOptional<T>
+where
+    T: CloneToJava,
+{
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        match &self.0 {
+            Some(value) => {
+                let value = value.clone_to_java_object(env)?;
+                jni_cache::call_static_method(
+                    env,
+                    "java/util/Optional",
+                    "of",
+                    "(Ljava/lang/Object;)Ljava/util/Optional;",
+                    &[value.into()],
+                )
+            }
+            None => jni_cache::call_static_method(
+                env,
+                "java/util/Optional",
+                "empty",
+                "()Ljava/util/Optional;",
+                &[],
+            ),
+        }
+    }
+}
+
+impl<T> CloneFromJava for JavaOptional<T>
+where
+    T: CloneFromJava,
+{
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        if !is_present(env, obj, "java/util/Optional")? {
+            return Ok(JavaOptional(None));
+        }
+        let value = jni_cache::call_method(
+            env,
+            obj,
+            "java/util/Optional",
+            "get",
+            "()Ljava/lang/Object;",
+            &[],
+        )?
+        .l()?;
+        T::clone_from_java_object(env, value).map(|value| JavaOptional(Some(value)))
+    }
+}
+
+impl CloneToJava for JavaOptionalInt {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        match self.0 {
+            Some(value) => jni_cache::call_static_method(
+                env,
+                "java/util/OptionalInt",
+                "of",
+                "(I)Ljava/util/OptionalInt;",
+                &[JValue::Int(value)],
+            ),
+            None => jni_cache::call_static_method(
+                env,
+                "java/util/OptionalInt",
+                "empty",
+                "()Ljava/util/OptionalInt;",
+                &[],
+            ),
+        }
+    }
+}
+
+impl CloneFromJava for JavaOptionalInt {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        if !is_present(env, obj, "java/util/OptionalInt")? {
+            return Ok(JavaOptionalInt(None));
+        }
+        let value =
+            jni_cache::call_method(env, obj, "java/util/OptionalInt", "getAsInt", "()I", &[])?
+                .i()?;
+        Ok(JavaOptionalInt(Some(value)))
+    }
+}
+
+impl CloneToJava for JavaOptionalLong {
+    type Kind = kind::Object;
+
+    fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> jni::errors::Result<JValue<'a>> {
+        match self.0 {
+            Some(value) => jni_cache::call_static_method(
+                env,
+                "java/util/OptionalLong",
+                "of",
+                "(J)Ljava/util/OptionalLong;",
+                &[JValue::Long(value)],
+            ),
+            None => jni_cache::call_static_method(
+                env,
+                "java/util/OptionalLong",
+                "empty",
+                "()Ljava/util/OptionalLong;",
+                &[],
+            ),
+        }
+    }
+}
+
+impl CloneFromJava for JavaOptionalLong {
+    fn clone_from_java(env: JNIEnv, obj: JValue) -> jni::errors::Result<Self>
+    where
+        Self: Sized,
+    {
+        let obj = non_null::<Self>(env, obj.l()?)?;
+        if !is_present(env, obj, "java/util/OptionalLong")? {
+            return Ok(JavaOptionalLong(None));
+        }
+        let value =
+            jni_cache::call_method(env, obj, "java/util/OptionalLong", "getAsLong", "()J", &[])?
+                .j()?;
+        Ok(JavaOptionalLong(Some(value)))
+    }
+}
+
+fn is_present(env: JNIEnv, obj: JObject, class_name: &'static str) -> jni::errors::Result<bool> {
+    jni_cache::call_method(env, obj, class_name, "isPresent", "()Z", &[])?.z()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use jni::{objects::JObject, JNIEnv};
+
+    use super::{JavaOptional, JavaOptionalInt, JavaOptionalLong};
+    use crate::{clone_from_java::CloneFromJava, clone_to_java::CloneToJava, jvm};
+
+    /// Clones `value` to java and back, returns `String.valueOf` of the java value.
+    fn round_trip<T>(env: JNIEnv, value: T) -> jni::errors::Result<String>
+    where
+        T: CloneToJava + CloneFromJava + PartialEq + Debug,
+    {
+        let java = value.clone_to_java(env)?;
+        assert_eq!(T::clone_from_java(env, java)?, value);
+        jvm::to_string(env, java.l()?)
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn optional() {
+        jvm::run(|env| {
+            assert_eq!(round_trip(env, JavaOptional(Some(7i32)))?, "Optional[7]");
+            assert_eq!(
+                round_trip(env, JavaOptional(Some("a".to_string())))?,
+                "Optional[a]"
+            );
+            assert_eq!(
+                round_trip(env, JavaOptional(Some(vec![1i64])))?,
+                "Optional[[1]]"
+            );
+            assert_eq!(
+                round_trip(env, JavaOptional::<i32>(None))?,
+                "Optional.empty"
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn optional_int() {
+        jvm::run(|env| {
+            assert_eq!(
+                round_trip(env, JavaOptionalInt(Some(-3)))?,
+                "OptionalInt[-3]"
+            );
+            assert_eq!(round_trip(env, JavaOptionalInt(None))?, "OptionalInt.empty");
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn optional_long() {
+        jvm::run(|env| {
+            assert_eq!(
+                round_trip(env, JavaOptionalLong(Some(i64::MIN)))?,
+                "OptionalLong[-9223372036854775808]"
+            );
+            assert_eq!(
+                round_trip(env, JavaOptionalLong(None))?,
+                "OptionalLong.empty"
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn null_optional_is_rejected() {
+        jvm::run(|env| {
+            let clone = || JavaOptionalInt::clone_from_java_object(env, JObject::null());
+            assert_eq!(
+                jvm::expect_exception(env, clone)?,
+                "java.lang.NullPointerException"
+            );
+            Ok(())
+        });
+    }
+}
@@ -1,6 +1,15 @@
-use std::ops::Deref;
+use std::{
+    any::{type_name, Any},
+    sync::{Arc, Mutex, MutexGuard},
+};
 
-use jni::{objects::JObject, JNIEnv};
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+use once_cell::sync::Lazy;
+
+use crate::jni_cache;
 
 macro_rules! from_jobject {
     ($struct_name:ty, $class_name:literal) => {
@@ -21,44 +30,324 @@ pub trait FromJObject {
         Self: Sized;
 }
 
+type StoredValue = Arc<dyn Any + Send + Sync>;
+
+struct Slot {
+    generation: u32,
+    value: Option<StoredValue>,
+}
+
+/// Owns every rust value backing a java object. Java only keeps a handle in its `rustPointer`
+/// field: the slot index in the lower 32 bits and the slot generation in the upper ones. The
+/// generation is bumped whenever a slot is released, so handles of destroyed objects never
+/// resolve again, even once their slot is reused.
+#[derive(Default)]
+struct HandleRegistry {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl HandleRegistry {
+    fn insert(&mut self, value: StoredValue) -> i64 {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot {
+                    generation: 1,
+                    value: None,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        let slot = &mut self.slots[index as usize];
+        slot.value = Some(value);
+        ((slot.generation as i64) << 32) | index as i64
+    }
+
+    fn slot(&mut self, handle: i64) -> Option<&mut Slot> {
+        let index = (handle & 0xffff_ffff) as usize;
+        let generation = (handle >> 32) as u32;
+        self.slots
+            .get_mut(index)
+            .filter(|slot| slot.generation == generation && slot.value.is_some())
+    }
+
+    fn get(&mut self, handle: i64) -> Option<StoredValue> {
+        self.slot(handle).and_then(|slot| slot.value.clone())
+    }
+
+    fn remove(&mut self, handle: i64) -> Option<StoredValue> {
+        let slot = self.slot(handle)?;
+        let value = slot.value.take();
+        slot.generation = slot.generation.wrapping_add(1).max(1);
+        self.free.push((handle & 0xffff_ffff) as u32);
+        value
+    }
+}
+
+/// Never dropped, the registry lives as long as the library.
+static REGISTRY: Lazy<Mutex<HandleRegistry>> = Lazy::new(Mutex::default);
+
+fn registry() -> MutexGuard<'static, HandleRegistry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Rust value owned by a java object. Values are shared between the threads calling into the
+/// object and guarded by a mutex, they are dropped once java calls `rustDestructor` and the
+/// last native call using them returns.
 pub struct JavaStoredObject<T> {
-    pub obj: Option<Box<T>>,
+    obj: Arc<Mutex<T>>,
 }
 
-impl<T> JavaStoredObject<T> {
+impl<T: Send + 'static> JavaStoredObject<T> {
     pub fn new(
         env: JNIEnv,
         obj: JObject,
-        class_name: &str,
+        class_name: &'static str,
     ) -> jni::errors::Result<JavaStoredObject<T>> {
         if !class_name.is_empty() {
-            let class = env.find_class(class_name)?;
-            if !env.is_instance_of(obj, class)? {
+            let class = jni_cache::class(env, class_name)?;
+            if !env.is_instance_of(obj, &class)? {
                 env.throw_new("java/lang/Exception", "Wrong object class")?;
                 return Err(jni::errors::Error::JavaException);
             }
         }
-        let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-        let this = unsafe { Box::from_raw(ptr as *mut T) };
-        Ok(JavaStoredObject { obj: Some(this) })
+        let handle = env.get_field(obj, "rustPointer", "J")?.j()?;
+        if handle == 0 {
+            return throw_illegal_state::<T, _>(env, "is not initialized");
+        }
+        let value = registry().get(handle);
+        let value = match value {
+            Some(value) => value,
+            None => return throw_illegal_state::<T, _>(env, "was used after being destroyed"),
+        };
+        match value.downcast::<Mutex<T>>() {
+            Ok(obj) => Ok(JavaStoredObject { obj }),
+            Err(_) => throw_illegal_state::<T, _>(env, "is backed by a value of another type"),
+        }
+    }
+
+    /// Moves `value` to the registry and stores its handle in the `rustPointer` field of `obj`.
+    pub fn store(env: JNIEnv, obj: JObject, value: T) -> jni::errors::Result<()> {
+        let handle = registry().insert(Arc::new(Mutex::new(value)));
+        let result = env.set_field(obj, "rustPointer", "J", JValue::Long(handle));
+        if result.is_err() {
+            registry().remove(handle);
+        }
+        result
     }
+
+    /// Releases the value backing `obj`, later calls on `obj` throw `IllegalStateException`.
+    pub fn destroy(env: JNIEnv, obj: JObject) -> jni::errors::Result<()> {
+        let handle = env.get_field(obj, "rustPointer", "J")?.j()?;
+        if handle == 0 {
+            return throw_illegal_state::<T, _>(env, "is not initialized");
+        }
+        // Dropped outside of the registry lock, values may hold global references.
+        let value = registry().remove(handle);
+        match value {
+            Some(_) => Ok(()),
+            None => throw_illegal_state::<T, _>(env, "was destroyed twice"),
+        }
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.obj.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn modify<F, R>(&mut self, mut func: F) -> R
     where
         F: FnMut(&mut T) -> R,
     {
-        func(&mut self.obj.as_mut().unwrap())
+        func(&mut self.lock())
     }
 }
-impl<T> Drop for JavaStoredObject<T> {
-    fn drop(&mut self) {
-        let _ptr = Box::into_raw(self.obj.take().unwrap());
-    }
+
+fn throw_illegal_state<T, R>(env: JNIEnv, reason: &str) -> jni::errors::Result<R> {
+    let message = format!("{} {}", type_name::<T>(), reason);
+    env.throw_new("java/lang/IllegalStateException", message)?;
+    Err(jni::errors::Error::JavaException)
 }
 
-impl<T> Deref for JavaStoredObject<T> {
-    type Target = T;
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use jni::{errors::Error, objects::JObject, JNIEnv};
+
+    use super::{HandleRegistry, JavaStoredObject};
+    use crate::{common::errors::JavaException, jvm};
+
+    /// Any class with a `rustPointer` field, its native constructor is not called.
+    const VALUE: &str = "org/apache/kafka/common/metrics/stats/Value";
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    fn uninitialized(env: JNIEnv) -> jni::errors::Result<JObject> {
+        env.alloc_object(VALUE)
+    }
+
+    /// Message of the `IllegalStateException` thrown by `f`.
+    fn illegal_state<R>(
+        env: JNIEnv,
+        f: impl FnOnce() -> jni::errors::Result<R>,
+    ) -> jni::errors::Result<String> {
+        assert!(matches!(f(), Err(Error::JavaException)));
+        let exception = JavaException::take(env)?.expect("Expected an exception");
+        assert_eq!(exception.class_name, "java.lang.IllegalStateException");
+        Ok(exception.message.unwrap_or_default())
+    }
+
+    #[test]
+    fn handles_resolve_until_removed() {
+        let mut registry = HandleRegistry::default();
+        let handle = registry.insert(Arc::new(1u32));
+        assert_ne!(handle, 0);
+        assert!(registry.get(handle).is_some());
+        assert!(registry.remove(handle).is_some());
+        assert!(registry.get(handle).is_none());
+        assert!(registry.remove(handle).is_none());
+    }
+
+    #[test]
+    fn reused_slots_get_a_new_generation() {
+        let mut registry = HandleRegistry::default();
+        let first = registry.insert(Arc::new(1u32));
+        registry.remove(first);
+        let second = registry.insert(Arc::new(2u32));
+        assert_eq!(first & 0xffff_ffff, second & 0xffff_ffff);
+        assert_ne!(first, second);
+        assert!(registry.get(first).is_none());
+        assert!(registry.remove(first).is_none());
+        let value = registry.get(second).expect("second value");
+        assert_eq!(value.downcast_ref::<u32>(), Some(&2));
+    }
+
+    #[test]
+    fn generations_skip_zero_when_wrapping() {
+        let mut registry = HandleRegistry::default();
+        let handle = registry.insert(Arc::new(1u32));
+        registry.slots[0].generation = u32::MAX;
+        let handle = (handle & 0xffff_ffff) | ((u32::MAX as i64) << 32);
+        registry.remove(handle);
+        assert_eq!(registry.slots[0].generation, 1);
+        assert_ne!(registry.insert(Arc::new(2u32)), 0);
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn stored_values_are_shared_by_every_call() {
+        jvm::run(|env| {
+            let obj = uninitialized(env)?;
+            JavaStoredObject::store(env, obj, Counter(1))?;
+            JavaStoredObject::<Counter>::new(env, obj, VALUE)?.modify(|counter| counter.0 += 1);
+            let stored = JavaStoredObject::<Counter>::new(env, obj, VALUE)?;
+            assert_eq!(*stored.lock(), Counter(2));
+            JavaStoredObject::<Counter>::destroy(env, obj)
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn uninitialized_objects_throw_illegal_state() {
+        jvm::run(|env| {
+            let obj = uninitialized(env)?;
+            let message = illegal_state(env, || JavaStoredObject::<Counter>::new(env, obj, VALUE))?;
+            assert!(
+                message.ends_with("Counter is not initialized"),
+                "{}",
+                message
+            );
+            let message = illegal_state(env, || JavaStoredObject::<Counter>::destroy(env, obj))?;
+            assert!(
+                message.ends_with("Counter is not initialized"),
+                "{}",
+                message
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn destroyed_objects_throw_illegal_state() {
+        jvm::run(|env| {
+            let obj = uninitialized(env)?;
+            JavaStoredObject::store(env, obj, Counter(1))?;
+            let kept = JavaStoredObject::<Counter>::new(env, obj, VALUE)?;
+            JavaStoredObject::<Counter>::destroy(env, obj)?;
+            // Calls running while the object is destroyed keep their value.
+            assert_eq!(*kept.lock(), Counter(1));
+
+            let message = illegal_state(env, || JavaStoredObject::<Counter>::new(env, obj, VALUE))?;
+            assert!(
+                message.ends_with("Counter was used after being destroyed"),
+                "{}",
+                message
+            );
+            let message = illegal_state(env, || JavaStoredObject::<Counter>::destroy(env, obj))?;
+            assert!(
+                message.ends_with("Counter was destroyed twice"),
+                "{}",
+                message
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn handles_of_destroyed_objects_never_resolve_again() {
+        jvm::run(|env| {
+            let destroyed = uninitialized(env)?;
+            JavaStoredObject::store(env, destroyed, Counter(1))?;
+            let handle = env.get_field(destroyed, "rustPointer", "J")?.j()?;
+            JavaStoredObject::<Counter>::destroy(env, destroyed)?;
+
+            // Likely reuses the slot of the destroyed object.
+            let live = uninitialized(env)?;
+            JavaStoredObject::store(env, live, Counter(2))?;
+            assert_ne!(env.get_field(live, "rustPointer", "J")?.j()?, handle);
+
+            let message = illegal_state(env, || {
+                JavaStoredObject::<Counter>::new(env, destroyed, VALUE)
+            })?;
+            assert!(
+                message.ends_with("was used after being destroyed"),
+                "{}",
+                message
+            );
+            let stored = JavaStoredObject::<Counter>::new(env, live, VALUE)?;
+            assert_eq!(*stored.lock(), Counter(2));
+            JavaStoredObject::<Counter>::destroy(env, live)
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn values_of_another_type_are_rejected() {
+        jvm::run(|env| {
+            let obj = uninitialized(env)?;
+            JavaStoredObject::store(env, obj, Counter(1))?;
+            let message = illegal_state(env, || JavaStoredObject::<String>::new(env, obj, VALUE))?;
+            assert_eq!(
+                message,
+                "alloc::string::String is backed by a value of another type"
+            );
+            JavaStoredObject::<Counter>::destroy(env, obj)
+        });
+    }
 
-    fn deref(&self) -> &Self::Target {
-        self.obj.as_ref().unwrap()
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn objects_of_another_class_are_rejected() {
+        jvm::run(|env| {
+            let obj = env.new_string("not a Value")?.into();
+            let class =
+                jvm::expect_exception(env, || JavaStoredObject::<Counter>::new(env, obj, VALUE))?;
+            assert_eq!(class, "java.lang.Exception");
+            Ok(())
+        });
     }
 }
@@ -0,0 +1,289 @@
+use std::{
+    io,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        mpsc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use jni::{objects::JObject, JNIEnv, JavaVM};
+use log::{error, warn};
+
+use crate::common::errors::{JavaException, JniResult};
+
+/// Local references a callback may create before the JVM grows its frame.
+const LOCAL_FRAME_CAPACITY: i32 = 32;
+
+static JAVA_VM: AtomicPtr<jni::sys::JavaVM> = AtomicPtr::new(ptr::null_mut());
+
+/// Remembers the JVM the library was loaded by, called from `JNI_OnLoad`.
+pub(crate) fn set_java_vm(vm: &JavaVM) {
+    JAVA_VM.store(vm.get_java_vm_pointer(), Ordering::Release);
+}
+
+/// Forgets the JVM, called from `JNI_OnUnload`.
+pub(crate) fn clear_java_vm() {
+    JAVA_VM.store(ptr::null_mut(), Ordering::Release);
+}
+
+/// The JVM the library was loaded by.
+pub fn java_vm() -> jni::errors::Result<JavaVM> {
+    let vm = JAVA_VM.load(Ordering::Acquire);
+    if vm.is_null() {
+        return Err(jni::errors::Error::NullPtr(
+            "JavaVM, library not loaded by a JVM",
+        ));
+    }
+    unsafe { JavaVM::from_raw(vm) }
+}
+
+/// Runs `f` with the `JNIEnv` of the current thread, from java threads as well as threads
+/// owned by rust. Threads unknown to the JVM are attached as daemons on first use, so they
+/// don't prevent it from exiting, and detached when they exit.
+///
+/// Local references created by `f` are released when it returns, threads owned by rust never
+/// return to java to release them. An exception `f` leaves pending is cleared and returned as
+/// `JniError::Java`.
+pub fn call_java<F, R>(f: F) -> JniResult<R>
+where
+    F: for<'a> FnOnce(JNIEnv<'a>) -> jni::errors::Result<R>,
+{
+    let vm = java_vm()?;
+    let env = vm.attach_current_thread_as_daemon()?;
+    env.push_local_frame(LOCAL_FRAME_CAPACITY)?;
+    let result = f(env);
+    let exception = JavaException::take(env);
+    env.pop_local_frame(JObject::null())?;
+    match exception? {
+        Some(exception) => Err(exception.into()),
+        None => Ok(result?),
+    }
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// Runs java callbacks one at a time, in the order they were dispatched, on a dedicated thread
+/// attached to the JVM. Threads of the clients hand callbacks over instead of calling user
+/// code themselves, so a slow callback does not stall them.
+pub struct CallbackDispatcher {
+    sender: Option<Mutex<mpsc::Sender<Task>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl CallbackDispatcher {
+    /// Starts the dispatcher thread named `name`.
+    pub fn new(name: impl Into<String>) -> io::Result<CallbackDispatcher> {
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let thread = thread::Builder::new().name(name.into()).spawn(move || {
+            for task in receiver {
+                if panic::catch_unwind(AssertUnwindSafe(task)).is_err() {
+                    error!("Callback panicked, dispatching the next one");
+                }
+            }
+        })?;
+        Ok(CallbackDispatcher {
+            sender: Some(Mutex::new(sender)),
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues `callback` and returns a receiver of its result, including exceptions thrown by
+    /// java as `JniError::Java`. The receiver is disconnected without a result when the
+    /// callback panicked.
+    pub fn dispatch<F, R>(&self, callback: F) -> mpsc::Receiver<JniResult<R>>
+    where
+        F: for<'a> FnOnce(JNIEnv<'a>) -> jni::errors::Result<R> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result_sender, result) = mpsc::sync_channel(1);
+        self.send(Box::new(move || {
+            let _ = result_sender.send(call_java(callback));
+        }));
+        result
+    }
+
+    /// Queues `callback` without waiting for it, its failures are logged.
+    pub fn execute<F>(&self, callback: F)
+    where
+        F: for<'a> FnOnce(JNIEnv<'a>) -> jni::errors::Result<()> + Send + 'static,
+    {
+        self.send(Box::new(move || {
+            if let Err(e) = call_java(callback) {
+                error!("Error executing user-provided callback: {}", e);
+            }
+        }));
+    }
+
+    fn send(&self, task: Task) {
+        let sent = self.sender.as_ref().map(|sender| {
+            let sender = sender.lock().unwrap_or_else(|e| e.into_inner());
+            sender.send(task).is_ok()
+        });
+        if sent != Some(true) {
+            warn!("Callback dispatched after the dispatcher stopped, it is dropped");
+        }
+    }
+}
+
+impl Drop for CallbackDispatcher {
+    /// Runs the callbacks already queued, unless dropped from one of them.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            if thread.thread().id() != thread::current().id() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc::{self, RecvTimeoutError},
+        thread,
+        time::Duration,
+    };
+
+    use jni::JNIEnv;
+
+    use super::{call_java, set_java_vm, CallbackDispatcher};
+    use crate::{
+        common::errors::{JniError, JniResult},
+        jvm,
+    };
+
+    /// Longest wait for a callback, so a lost one fails the test instead of hanging it.
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn init() {
+        set_java_vm(&jvm::jvm());
+    }
+
+    fn parse_int(env: JNIEnv, value: &str) -> jni::errors::Result<i32> {
+        let value = env.new_string(value)?;
+        env.call_static_method(
+            "java/lang/Integer",
+            "parseInt",
+            "(Ljava/lang/String;)I",
+            &[value.into()],
+        )?
+        .i()
+    }
+
+    fn is_daemon(env: JNIEnv) -> jni::errors::Result<bool> {
+        let thread = env
+            .call_static_method(
+                "java/lang/Thread",
+                "currentThread",
+                "()Ljava/lang/Thread;",
+                &[],
+            )?
+            .l()?;
+        env.call_method(thread, "isDaemon", "()Z", &[])?.z()
+    }
+
+    fn assert_number_format_exception<R: std::fmt::Debug>(result: JniResult<R>) {
+        match result {
+            Err(JniError::Java(exception)) => {
+                assert_eq!(exception.class_name, "java.lang.NumberFormatException")
+            }
+            other => panic!("Expected NumberFormatException, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn call_java_attaches_rust_threads_as_daemons() {
+        init();
+        let result =
+            thread::spawn(|| call_java(|env| Ok((parse_int(env, "42")?, is_daemon(env)?))))
+                .join()
+                .expect("rust thread");
+        assert_eq!(result.expect("call_java"), (42, true));
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn call_java_returns_and_clears_exceptions() {
+        init();
+        let (thrown, next) = thread::spawn(|| {
+            let thrown = call_java(|env| parse_int(env, "x"));
+            (thrown, call_java(|env| parse_int(env, "7")))
+        })
+        .join()
+        .expect("rust thread");
+        assert_number_format_exception(thrown);
+        assert_eq!(next.expect("call_java after an exception"), 7);
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn dispatcher_runs_callbacks_in_order() {
+        init();
+        let list = call_java(|env| {
+            let list = env.new_object("java/util/ArrayList", "()V", &[])?;
+            env.new_global_ref(list)
+        })
+        .expect("list");
+        let dispatched = list.clone();
+        thread::spawn(move || {
+            let dispatcher = CallbackDispatcher::new("test-dispatcher").expect("dispatcher");
+            for i in 0..10 {
+                let list = dispatched.clone();
+                dispatcher.execute(move |env| {
+                    let value = env.new_string(i.to_string())?;
+                    env.call_method(
+                        list.as_obj(),
+                        "add",
+                        "(Ljava/lang/Object;)Z",
+                        &[value.into()],
+                    )?;
+                    Ok(())
+                });
+            }
+            // Dropping the dispatcher runs the queued callbacks.
+        })
+        .join()
+        .expect("rust thread");
+        let list = call_java(move |env| jvm::to_string(env, list.as_obj())).expect("list");
+        assert_eq!(list, "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn dispatcher_returns_exceptions() {
+        init();
+        let dispatcher = CallbackDispatcher::new("test-dispatcher").expect("dispatcher");
+        let (thrown, next) = thread::spawn(move || {
+            let thrown = dispatcher.dispatch(|env| parse_int(env, "x"));
+            let next = dispatcher.dispatch(|env| Ok((parse_int(env, "3")?, is_daemon(env)?)));
+            (thrown.recv_timeout(TIMEOUT), next.recv_timeout(TIMEOUT))
+        })
+        .join()
+        .expect("rust thread");
+        assert_number_format_exception(thrown.expect("callback result"));
+        assert_eq!(next.expect("callback result").expect("callback"), (3, true));
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn dispatcher_survives_panicking_callbacks() {
+        init();
+        let dispatcher = CallbackDispatcher::new("test-dispatcher").expect("dispatcher");
+        let panicked = dispatcher.dispatch(|_| -> jni::errors::Result<()> { panic!("callback") });
+        assert_eq!(
+            panicked.recv_timeout(TIMEOUT).map(|_| ()),
+            Err(RecvTimeoutError::Disconnected)
+        );
+        let (sender, receiver) = mpsc::channel();
+        dispatcher.execute(move |env| {
+            let _ = sender.send(parse_int(env, "5")?);
+            Ok(())
+        });
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(5));
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    mem,
+    str::FromStr,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use jni::{
+    objects::{GlobalRef, JFieldID, JMethodID, JObject, JStaticFieldID, JStaticMethodID, JValue},
+    signature::{JavaType, Primitive, TypeSignature},
+    sys::{jfieldID, jint, jmethodID, JNI_VERSION_1_8},
+    JNIEnv, JavaVM,
+};
+use once_cell::sync::Lazy;
+
+use crate::{java_vm, slf4j_logger};
+
+/// Classes loaded with the class loader of the library: JDK classes used by conversions, and
+/// the callbacks called from threads owned by rust with the classes of their arguments, as
+/// `FindClass` from these threads only sees the system class loader.
+const PRELOADED_CLASSES: &[&str] = &[
+    "java/lang/Boolean",
+    "java/lang/Double",
+    "java/lang/Integer",
+    "java/lang/Long",
+    "java/lang/Number",
+    "java/time/Duration",
+    "java/time/Instant",
+    "java/util/ArrayList",
+    "java/util/Collection",
+    "java/util/HashMap",
+    "java/util/HashSet",
+    "java/util/LinkedHashMap",
+    "java/util/LinkedHashSet",
+    "java/util/List",
+    "java/util/Map",
+    "java/util/Map$Entry",
+    "java/util/Objects",
+    "java/util/Optional",
+    "java/util/OptionalInt",
+    "java/util/OptionalLong",
+    "java/util/Set",
+    "org/apache/kafka/clients/consumer/ConsumerRebalanceListener",
+    "org/apache/kafka/clients/producer/Callback",
+    "org/apache/kafka/clients/producer/RecordMetadata",
+    "org/apache/kafka/common/KafkaException",
+    "org/apache/kafka/common/TopicPartition",
+    "org/apache/kafka/common/metrics/Measurable",
+];
+
+/// Class name, member name and JNI signature.
+type MemberKey = (&'static str, &'static str, &'static str);
+
+/// Method and field IDs stay valid as long as their class is loaded, the cache keeps a global
+/// reference to the class of every ID it holds.
+#[derive(Clone, Copy)]
+struct MethodId(jmethodID);
+unsafe impl Send for MethodId {}
+unsafe impl Sync for MethodId {}
+
+#[derive(Clone, Copy)]
+struct FieldId(jfieldID);
+unsafe impl Send for FieldId {}
+unsafe impl Sync for FieldId {}
+
+/// Kind of a returned or read value, all the unchecked JNI calls need to know. Unlike
+/// `JavaType` it holds no class name, so cache hits don't allocate.
+#[derive(Clone, Copy)]
+enum ValueKind {
+    Object,
+    Primitive(Primitive),
+}
+
+impl ValueKind {
+    fn of(ty: &JavaType) -> ValueKind {
+        match ty {
+            JavaType::Primitive(primitive) => ValueKind::Primitive(*primitive),
+            _ => ValueKind::Object,
+        }
+    }
+
+    fn java_type(self) -> JavaType {
+        match self {
+            // The class is not read by the unchecked calls, an empty name doesn't allocate.
+            ValueKind::Object => JavaType::Object(String::new()),
+            ValueKind::Primitive(primitive) => JavaType::Primitive(primitive),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Method {
+    id: MethodId,
+    ret: ValueKind,
+    arg_count: usize,
+}
+
+impl Method {
+    fn new(id: MethodId, sig: &str) -> jni::errors::Result<Method> {
+        let signature = TypeSignature::from_str(sig)?;
+        Ok(Method {
+            id,
+            ret: ValueKind::of(&signature.ret),
+            arg_count: signature.args.len(),
+        })
+    }
+
+    fn check_arguments(&self, sig: &str, args: &[JValue]) -> jni::errors::Result<()> {
+        if self.arg_count != args.len() {
+            return Err(jni::errors::Error::InvalidArgList(TypeSignature::from_str(
+                sig,
+            )?));
+        }
+        Ok(())
+    }
+}
+
+/// Classes and member IDs resolved by name once and reused by every conversion, so hot paths
+/// don't go through `FindClass`/`GetMethodID` and signature parsing on each call.
+#[derive(Default)]
+struct JniCache {
+    classes: HashMap<&'static str, GlobalRef>,
+    methods: HashMap<MemberKey, Method>,
+    static_methods: HashMap<MemberKey, Method>,
+    fields: HashMap<MemberKey, (FieldId, ValueKind)>,
+    static_fields: HashMap<MemberKey, (FieldId, ValueKind)>,
+}
+
+/// Never dropped, `JNI_OnUnload` only empties it.
+static CACHE: Lazy<RwLock<JniCache>> = Lazy::new(RwLock::default);
+
+fn read() -> RwLockReadGuard<'static, JniCache> {
+    CACHE.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write() -> RwLockWriteGuard<'static, JniCache> {
+    CACHE.write().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Stores the JVM loading the library, fills the cache with `PRELOADED_CLASSES` and forwards
+/// logging to SLF4J.
+///
+/// # Safety
+///
+/// Called by the JVM with a valid `vm` pointer.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "system" fn JNI_OnLoad(
+    vm: *mut jni::sys::JavaVM,
+    _reserved: *mut c_void,
+) -> jint {
+    let vm = match JavaVM::from_raw(vm) {
+        Ok(vm) => vm,
+        Err(_) => return JNI_VERSION_1_8,
+    };
+    java_vm::set_java_vm(&vm);
+    if let Ok(env) = vm.get_env() {
+        for name in PRELOADED_CLASSES {
+            // Missing classes are looked up again on first use.
+            if class(env, name).is_err() && env.exception_check().unwrap_or(false) {
+                let _ = env.exception_clear();
+            }
+        }
+        slf4j_logger::init(env);
+    }
+    JNI_VERSION_1_8
+}
+
+/// `JNI_OnLoad` of the library linked into the unit test executable. The JVM of the tests then
+/// binds natives to the executable instead of loading `libkafka_connector_jni.so`, which would
+/// be another copy of the library, not sharing the values stored by the tests.
+///
+/// # Safety
+///
+/// Called by the JVM with a valid `vm` pointer.
+#[cfg(test)]
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "system" fn JNI_OnLoad_kafka_connector_jni(
+    vm: *mut jni::sys::JavaVM,
+    reserved: *mut c_void,
+) -> jint {
+    JNI_OnLoad(vm, reserved)
+}
+
+/// Releases cached classes, IDs of unloaded classes must not be used anymore.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn JNI_OnUnload(_vm: *mut jni::sys::JavaVM, _reserved: *mut c_void) {
+    java_vm::clear_java_vm();
+    slf4j_logger::clear();
+    // Global references are released outside of the lock.
+    let cache = mem::take(&mut *write());
+    drop(cache);
+}
+
+/// Global reference to the class `name`, e.g. `java/util/HashMap`.
+pub fn class(env: JNIEnv, name: &'static str) -> jni::errors::Result<GlobalRef> {
+    if let Some(class) = read().classes.get(name) {
+        return Ok(class.clone());
+    }
+    let class = env.new_global_ref(env.find_class(name)?)?;
+    let class = write().classes.entry(name).or_insert(class).clone();
+    Ok(class)
+}
+
+fn method(
+    env: JNIEnv,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<Method> {
+    let key = (class_name, name, sig);
+    if let Some(method) = read().methods.get(&key) {
+        return Ok(*method);
+    }
+    let class = class(env, class_name)?;
+    let id = MethodId(env.get_method_id(&class, name, sig)?.into_inner());
+    let method = Method::new(id, sig)?;
+    write().methods.insert(key, method);
+    Ok(method)
+}
+
+fn static_method(
+    env: JNIEnv,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<Method> {
+    let key = (class_name, name, sig);
+    if let Some(method) = read().static_methods.get(&key) {
+        return Ok(*method);
+    }
+    let class = class(env, class_name)?;
+    let id = MethodId(env.get_static_method_id(&class, name, sig)?.into_inner());
+    let method = Method::new(id, sig)?;
+    write().static_methods.insert(key, method);
+    Ok(method)
+}
+
+fn field(
+    env: JNIEnv,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<(FieldId, ValueKind)> {
+    let key = (class_name, name, sig);
+    if let Some(field) = read().fields.get(&key) {
+        return Ok(*field);
+    }
+    let class = class(env, class_name)?;
+    let id = FieldId(env.get_field_id(&class, name, sig)?.into_inner());
+    let field = (id, ValueKind::of(&JavaType::from_str(sig)?));
+    write().fields.insert(key, field);
+    Ok(field)
+}
+
+fn static_field(
+    env: JNIEnv,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<(FieldId, ValueKind)> {
+    let key = (class_name, name, sig);
+    if let Some(field) = read().static_fields.get(&key) {
+        return Ok(*field);
+    }
+    let class = class(env, class_name)?;
+    let id = FieldId(env.get_static_field_id(&class, name, sig)?.into_inner());
+    let field = (id, ValueKind::of(&JavaType::from_str(sig)?));
+    write().static_fields.insert(key, field);
+    Ok(field)
+}
+
+/// `obj` has to be an instance of `class_name` for the unchecked calls, checked in debug builds
+/// where it fails with `WrongJValueType` instead of crashing the JVM.
+fn check_instance(env: JNIEnv, obj: JObject, class_name: &'static str) -> jni::errors::Result<()> {
+    if obj.is_null() {
+        return Err(jni::errors::Error::NullPtr(
+            "unchecked call on a null object",
+        ));
+    }
+    if cfg!(debug_assertions) && !env.is_instance_of(obj, &class(env, class_name)?)? {
+        return Err(jni::errors::Error::WrongJValueType(
+            class_name,
+            "an instance of another class",
+        ));
+    }
+    Ok(())
+}
+
+/// `JNIEnv::new_object` of the class `class_name`.
+pub fn new_object<'a>(
+    env: JNIEnv<'a>,
+    class_name: &'static str,
+    ctor_sig: &'static str,
+    ctor_args: &[JValue],
+) -> jni::errors::Result<JObject<'a>> {
+    let class = class(env, class_name)?;
+    let method = method(env, class_name, "<init>", ctor_sig)?;
+    method.check_arguments(ctor_sig, ctor_args)?;
+    env.new_object_unchecked(&class, JMethodID::from(method.id.0), ctor_args)
+}
+
+/// `JNIEnv::call_method` of a method declared by `class_name`, `obj` has to be an instance of
+/// it.
+pub fn call_method<'a>(
+    env: JNIEnv<'a>,
+    obj: JObject<'a>,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+    args: &[JValue],
+) -> jni::errors::Result<JValue<'a>> {
+    check_instance(env, obj, class_name)?;
+    let method = method(env, class_name, name, sig)?;
+    method.check_arguments(sig, args)?;
+    env.call_method_unchecked(
+        obj,
+        JMethodID::from(method.id.0),
+        method.ret.java_type(),
+        args,
+    )
+}
+
+/// `JNIEnv::call_static_method` of the class `class_name`.
+pub fn call_static_method<'a>(
+    env: JNIEnv<'a>,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+    args: &[JValue],
+) -> jni::errors::Result<JValue<'a>> {
+    let class = class(env, class_name)?;
+    let method = static_method(env, class_name, name, sig)?;
+    method.check_arguments(sig, args)?;
+    let id = JStaticMethodID::from(method.id.0);
+    env.call_static_method_unchecked(&class, id, method.ret.java_type(), args)
+}
+
+/// `JNIEnv::get_field` of a field declared by `class_name`, `obj` has to be an instance of it.
+pub fn get_field<'a>(
+    env: JNIEnv<'a>,
+    obj: JObject<'a>,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<JValue<'a>> {
+    check_instance(env, obj, class_name)?;
+    let (id, kind) = field(env, class_name, name, sig)?;
+    env.get_field_unchecked(obj, JFieldID::from(id.0), kind.java_type())
+}
+
+/// `JNIEnv::get_static_field` of the class `class_name`.
+pub fn get_static_field<'a>(
+    env: JNIEnv<'a>,
+    class_name: &'static str,
+    name: &'static str,
+    sig: &'static str,
+) -> jni::errors::Result<JValue<'a>> {
+    let class = class(env, class_name)?;
+    let (id, kind) = static_field(env, class_name, name, sig)?;
+    env.get_static_field_unchecked(&class, JStaticFieldID::from(id.0), kind.java_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use jni::{
+        errors::Error,
+        objects::{JObject, JValue},
+        JNIEnv,
+    };
+
+    use super::{call_method, call_static_method, get_field, new_object};
+    use crate::jvm;
+
+    const TOPIC_PARTITION: &str = "org/apache/kafka/common/TopicPartition";
+
+    fn topic_partition(env: JNIEnv) -> jni::errors::Result<JObject> {
+        let topic = env.new_string("topic")?;
+        env.new_object(
+            TOPIC_PARTITION,
+            "(Ljava/lang/String;I)V",
+            &[topic.into(), JValue::Int(3)],
+        )
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn members_of_instances_are_accessed() {
+        jvm::run(|env| {
+            let partition = topic_partition(env)?;
+            let value = call_method(env, partition, TOPIC_PARTITION, "partition", "()I", &[])?;
+            assert_eq!(value.i()?, 3);
+            let value = get_field(env, partition, TOPIC_PARTITION, "partition", "I")?;
+            assert_eq!(value.i()?, 3);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn null_objects_are_rejected() {
+        jvm::run(|env| {
+            let null = JObject::null();
+            let called = call_method(env, null, TOPIC_PARTITION, "partition", "()I", &[]);
+            assert!(matches!(called, Err(Error::NullPtr(_))));
+            let read = get_field(env, null, TOPIC_PARTITION, "partition", "I");
+            assert!(matches!(read, Err(Error::NullPtr(_))));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn instances_of_other_classes_are_rejected() {
+        jvm::run(|env| {
+            let other = env.new_string("not a TopicPartition")?.into();
+            let called = call_method(env, other, TOPIC_PARTITION, "partition", "()I", &[]);
+            assert!(matches!(
+                called,
+                Err(Error::WrongJValueType(TOPIC_PARTITION, _))
+            ));
+            let read = get_field(env, other, TOPIC_PARTITION, "partition", "I");
+            assert!(matches!(
+                read,
+                Err(Error::WrongJValueType(TOPIC_PARTITION, _))
+            ));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn calls_with_the_wrong_number_of_arguments_are_rejected() {
+        jvm::run(|env| {
+            let partition = topic_partition(env)?;
+            // Once from the lookup and once from the cache.
+            for _ in 0..2 {
+                let called = call_method(
+                    env,
+                    partition,
+                    TOPIC_PARTITION,
+                    "partition",
+                    "()I",
+                    &[JValue::Int(1)],
+                );
+                assert!(matches!(called, Err(Error::InvalidArgList(_))));
+            }
+            let created = new_object(
+                env,
+                TOPIC_PARTITION,
+                "(Ljava/lang/String;I)V",
+                &[JValue::Int(1)],
+            );
+            assert!(matches!(created, Err(Error::InvalidArgList(_))));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn objects_are_returned_by_cached_calls() {
+        jvm::run(|env| {
+            let partition = topic_partition(env)?;
+            for _ in 0..2 {
+                let topic = call_method(
+                    env,
+                    partition,
+                    TOPIC_PARTITION,
+                    "topic",
+                    "()Ljava/lang/String;",
+                    &[],
+                )?;
+                assert_eq!(jvm::string(env, topic.l()?)?, "topic");
+                let empty = call_static_method(
+                    env,
+                    "java/util/Collections",
+                    "emptyList",
+                    "()Ljava/util/List;",
+                    &[],
+                )?;
+                assert_eq!(jvm::to_string(env, empty.l()?)?, "[]");
+            }
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+use backtrace::Backtrace;
+use jni::{
+    sys::{jboolean, jbyte, jchar, jdouble, jfloat, jint, jlong, jobject, jshort},
+    JNIEnv,
+};
+use log::error;
+
+use crate::common::errors::JniResult;
+
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+thread_local! {
+    /// Number of guarded entry points on the stack of the current thread.
+    static GUARD_DEPTH: Cell<usize> = Cell::default();
+    /// Description of the last panic raised inside a guarded entry point, with its backtrace.
+    static PANIC_REPORT: RefCell<Option<String>> = RefCell::default();
+}
+
+/// Value returned to java when the native side of an entry point failed. It is never observed
+/// by java code since an exception is pending at that point.
+pub trait NullValue {
+    fn null_value() -> Self;
+}
+
+impl NullValue for () {
+    fn null_value() -> Self {}
+}
+
+impl NullValue for jobject {
+    fn null_value() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+macro_rules! zero_null_value {
+    ($($type:ty),*) => {
+        $(
+            impl NullValue for $type {
+                fn null_value() -> Self {
+                    Default::default()
+                }
+            }
+        )*
+    };
+}
+zero_null_value!(bool, jboolean, jbyte, jchar, jdouble, jfloat, jint, jlong, jshort);
+
+/// Runs the body of a JNI entry point and converts its failures into java exceptions, so that
+/// neither errors nor panics unwind across the FFI boundary and abort the JVM:
+/// - `KafkaError`s throw the matching java exception,
+/// - failed JNI calls throw `IllegalStateException` (unless an exception is already pending),
+/// - panics throw `KafkaException` carrying the panic message and the Rust backtrace.
+pub fn jni_guard<T, F>(env: JNIEnv, body: F) -> T
+where
+    T: NullValue,
+    F: FnOnce() -> JniResult<T>,
+{
+    install_panic_hook();
+    GUARD_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(body));
+    GUARD_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    match result {
+        Ok(Ok(value)) => value,
+        Ok(Err(error)) => {
+            error.throw(env);
+            T::null_value()
+        }
+        Err(payload) => {
+            let report = PANIC_REPORT
+                .with(|report| report.borrow_mut().take())
+                .unwrap_or_else(|| panic_message(payload.as_ref()));
+            // Discard the exception the panicking code may have left pending, the panic is what
+            // the caller has to see.
+            if env.exception_check().unwrap_or(false) {
+                let _ = env.exception_clear();
+            }
+            if let Err(e) = env.throw_new("org/apache/kafka/common/KafkaException", &report) {
+                error!("Failed to throw java exception for {}: {}", report, e);
+            }
+            T::null_value()
+        }
+    }
+}
+
+/// Captures the backtrace of panics raised inside guarded entry points, it is no longer
+/// available once the stack is unwound. Panics raised elsewhere are reported by the previous
+/// hook.
+fn install_panic_hook() {
+    INSTALL_PANIC_HOOK.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if GUARD_DEPTH.with(Cell::get) == 0 {
+                previous_hook(info);
+                return;
+            }
+            let report = format!("Rust {}\n{:?}", info, Backtrace::new());
+            PANIC_REPORT.with(|last| *last.borrow_mut() = Some(report));
+        }));
+    });
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    format!("Rust panicked at '{}'", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use jni::{
+        errors::Error,
+        sys::{jint, jobject},
+        JNIEnv,
+    };
+
+    use super::{jni_guard, panic_message};
+    use crate::{
+        common::errors::{JavaException, JniError, JniResult, KafkaError},
+        jvm,
+    };
+
+    /// The exception left pending by a guarded call, cleared.
+    fn thrown(env: JNIEnv) -> jni::errors::Result<JavaException> {
+        Ok(JavaException::take(env)?.expect("Expected an exception, none was thrown"))
+    }
+
+    #[test]
+    fn panic_messages_are_taken_from_the_payload() {
+        assert_eq!(panic_message(&"static"), "Rust panicked at 'static'");
+        assert_eq!(
+            panic_message(&"owned".to_string()),
+            "Rust panicked at 'owned'"
+        );
+        assert_eq!(panic_message(&42), "Rust panicked at 'Box<dyn Any>'");
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn values_are_returned_when_the_body_succeeds() {
+        jvm::run(|env| {
+            let value: jint = jni_guard(env, || Ok(42));
+            assert_eq!(value, 42);
+            assert!(!env.exception_check()?);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn kafka_errors_throw_the_matching_exception() {
+        jvm::run(|env| {
+            let value: jobject = jni_guard(env, || {
+                Err(KafkaError::InvalidTopic("bad topic".to_string()).into())
+            });
+            assert!(value.is_null());
+            let exception = thrown(env)?;
+            assert_eq!(
+                exception.class_name,
+                "org.apache.kafka.common.errors.InvalidTopicException"
+            );
+            assert_eq!(exception.message.as_deref(), Some("bad topic"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn failed_jni_calls_throw_illegal_state() {
+        jvm::run(|env| {
+            let value: jint = jni_guard(env, || Err(Error::NullPtr("call_method obj").into()));
+            assert_eq!(value, 0);
+            let exception = thrown(env)?;
+            assert_eq!(exception.class_name, "java.lang.IllegalStateException");
+            assert_eq!(
+                exception.message,
+                Some(Error::NullPtr("call_method obj").to_string())
+            );
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn pending_exceptions_of_failed_jni_calls_are_kept() {
+        jvm::run(|env| {
+            jni_guard(env, || {
+                env.throw_new("java/lang/ArithmeticException", "/ by zero")?;
+                Err::<(), JniError>(Error::JavaException.into())
+            });
+            let exception = thrown(env)?;
+            assert_eq!(exception.class_name, "java.lang.ArithmeticException");
+            assert_eq!(exception.message.as_deref(), Some("/ by zero"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn panics_throw_kafka_exception_with_the_message_and_backtrace() {
+        jvm::run(|env| {
+            let value: jint = jni_guard(env, || panic!("guarded panic"));
+            assert_eq!(value, 0);
+            let exception = thrown(env)?;
+            assert_eq!(
+                exception.class_name,
+                "org.apache.kafka.common.KafkaException"
+            );
+            let message = exception.message.unwrap_or_default();
+            assert!(message.starts_with("Rust panicked at"), "{}", message);
+            assert!(message.contains("guarded panic"), "{}", message);
+            assert!(message.contains("jni_guard::tests"), "{}", message);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn panics_replace_pending_exceptions() {
+        jvm::run(|env| {
+            jni_guard(env, || -> JniResult<()> {
+                env.throw_new("java/lang/ArithmeticException", "/ by zero")?;
+                panic!("after an exception");
+            });
+            let exception = thrown(env)?;
+            assert_eq!(
+                exception.class_name,
+                "org.apache.kafka.common.KafkaException"
+            );
+            assert!(exception
+                .message
+                .unwrap_or_default()
+                .contains("after an exception"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn nested_guards_report_their_own_panics() {
+        jvm::run(|env| {
+            let value: jint = jni_guard(env, || {
+                let inner: jint = jni_guard(env, || panic!("inner panic"));
+                let exception = thrown(env)?;
+                assert!(exception
+                    .message
+                    .unwrap_or_default()
+                    .contains("inner panic"));
+                Ok(inner + 1)
+            });
+            assert_eq!(value, 1);
+            assert!(!env.exception_check()?);
+            Ok(())
+        });
+    }
+}
@@ -1,12 +1,17 @@
-pub mod clients;
-
 #[macro_use]
 pub mod clone_from_java;
 #[macro_use]
 pub mod clone_to_java;
+pub mod direct_byte_buffer;
+pub mod java_optional;
 #[macro_use]
 pub mod java_stored_object;
+pub mod java_vm;
+pub mod jni_cache;
+pub mod jni_guard;
+pub mod slf4j_logger;
 
+pub mod clients;
 pub mod common;
 
 macro_rules! java_struct_standard_impl {
@@ -17,3 +22,12 @@ macro_rules! java_struct_standard_impl {
     };
 }
 pub(crate) use java_struct_standard_impl;
+
+/// The JVM of the integration tests, for unit tests needing a `JNIEnv`.
+#[cfg(test)]
+#[path = "../tests/jvm/mod.rs"]
+mod jvm;
+
+/// Test doubles shared by the unit tests of several modules.
+#[cfg(test)]
+mod test_utils;
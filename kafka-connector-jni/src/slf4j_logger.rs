@@ -0,0 +1,408 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    mem,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use jni::{
+    objects::{GlobalRef, JClass},
+    JNIEnv,
+};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+
+use crate::{
+    common::errors::{JniError, JniResult},
+    java_vm, jni_cache,
+    jni_guard::jni_guard,
+};
+
+const LOGGER_FACTORY: &str = "org/slf4j/LoggerFactory";
+const LOGGER: &str = "org/slf4j/Logger";
+
+/// Methods checking if a level is enabled, from the most verbose level.
+const LEVEL_CHECKS: [(Level, &str); 5] = [
+    (Level::Trace, "isTraceEnabled"),
+    (Level::Debug, "isDebugEnabled"),
+    (Level::Info, "isInfoEnabled"),
+    (Level::Warn, "isWarnEnabled"),
+    (Level::Error, "isErrorEnabled"),
+];
+
+/// Backend of the `log` crate forwarding records to SLF4J loggers named after the rust module
+/// they come from, e.g. `kafka_connector_jni.clients.admin.kafka_admin_client`. Records of the
+/// `jni` crate are not, it logs the calls forwarding needs, down to detaching threads while
+/// they exit.
+///
+/// The level of a SLF4J logger is read the first time its module logs, records of disabled
+/// levels are dropped without calling java. Levels changed at runtime are picked up after
+/// `RustLogging.refreshLevels()`.
+///
+/// There is no bridge for `tracing`: the crate logs through `log`, and the events of
+/// dependencies instrumented with `tracing` only reach this backend when they enable its `log`
+/// feature. A subscriber would forward spans and fields SLF4J has no place for.
+pub struct Slf4jLogger;
+
+static SLF4J_LOGGER: Slf4jLogger = Slf4jLogger;
+
+/// SLF4J logger of a rust module, `None` if it couldn't be created.
+struct JavaLogger {
+    logger: Option<GlobalRef>,
+    max_level: LevelFilter,
+}
+
+thread_local! {
+    /// Set while the thread calls java to forward a record.
+    // Const initializers need a newer toolchain than the one of the crate.
+    #[allow(unknown_lints, clippy::missing_const_for_thread_local)]
+    static FORWARDING: Cell<bool> = Cell::new(false);
+}
+
+/// Never dropped, records may be logged until the process exits.
+static LOGGERS: Lazy<RwLock<HashMap<String, JavaLogger>>> = Lazy::new(RwLock::default);
+
+fn read() -> RwLockReadGuard<'static, HashMap<String, JavaLogger>> {
+    LOGGERS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write() -> RwLockWriteGuard<'static, HashMap<String, JavaLogger>> {
+    LOGGERS.write().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Installs `Slf4jLogger` as the backend of the `log` crate, unless SLF4J is not on the
+/// classpath or the process already installed another one. Called from `JNI_OnLoad`.
+pub(crate) fn init(env: JNIEnv) {
+    if jni_cache::class(env, LOGGER_FACTORY).is_err() {
+        let _ = env.exception_clear();
+        return;
+    }
+    if log::set_logger(&SLF4J_LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+/// Forgets the SLF4J loggers, called from `JNI_OnUnload`.
+pub(crate) fn clear() {
+    // Global references are released outside of the lock.
+    let loggers = mem::take(&mut *write());
+    drop(loggers);
+}
+
+impl Slf4jLogger {
+    fn max_level(&self, target: &str) -> LevelFilter {
+        if let Some(logger) = read().get(target) {
+            return logger.max_level;
+        }
+        match java_logger(target) {
+            Ok(logger) => {
+                write()
+                    .entry(target.to_string())
+                    .or_insert(logger)
+                    .max_level
+            }
+            // Not cached, the JVM may be unavailable only for now.
+            Err(_) => LevelFilter::Off,
+        }
+    }
+}
+
+impl Log for Slf4jLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+        if target == "jni" || target.starts_with("jni::") {
+            return false;
+        }
+        forwarding(|| metadata.level() <= self.max_level(target)).unwrap_or(false)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        forwarding(|| self.forward(record));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Runs `f` unless the thread is already forwarding a record, records logged by java calling
+/// back into rust are dropped instead of being forwarded recursively.
+fn forwarding<R>(f: impl FnOnce() -> R) -> Option<R> {
+    let entered = FORWARDING
+        .try_with(|forwarding| !forwarding.replace(true))
+        .unwrap_or(false);
+    if !entered {
+        return None;
+    }
+    let result = f();
+    let _ = FORWARDING.try_with(|forwarding| forwarding.set(false));
+    Some(result)
+}
+
+impl Slf4jLogger {
+    fn forward(&self, record: &Record) {
+        let logger = match read()
+            .get(record.target())
+            .and_then(|logger| logger.logger.clone())
+        {
+            Some(logger) => logger,
+            None => return,
+        };
+        let method = log_method(record.level());
+        let message = record.args().to_string();
+        // Failures can't be logged, there is nowhere left to report them.
+        let _ = call_java_aside_exception(|env| {
+            let message = env.new_string(message)?;
+            jni_cache::call_method(
+                env,
+                logger.as_obj(),
+                LOGGER,
+                method,
+                "(Ljava/lang/String;)V",
+                &[message.into()],
+            )?;
+            Ok(())
+        });
+    }
+}
+
+/// Method of `org.slf4j.Logger` logging a message at `level`.
+fn log_method(level: Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warn => "warn",
+        Level::Info => "info",
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+    }
+}
+
+/// Most verbose level `is_enabled` accepts the check method of, `Off` if none.
+fn max_level<E>(
+    mut is_enabled: impl FnMut(&'static str) -> Result<bool, E>,
+) -> Result<LevelFilter, E> {
+    for (level, method) in LEVEL_CHECKS {
+        if is_enabled(method)? {
+            return Ok(level.to_level_filter());
+        }
+    }
+    Ok(LevelFilter::Off)
+}
+
+/// Creates the SLF4J logger of `target`, a logger which can't be created logs nothing.
+fn java_logger(target: &str) -> JniResult<JavaLogger> {
+    let name = target.replace("::", ".");
+    let logger = call_java_aside_exception(|env| {
+        let name = env.new_string(name)?;
+        let logger = jni_cache::call_static_method(
+            env,
+            LOGGER_FACTORY,
+            "getLogger",
+            "(Ljava/lang/String;)Lorg/slf4j/Logger;",
+            &[name.into()],
+        )?
+        .l()?;
+        let max_level = max_level(|method| {
+            jni_cache::call_method(env, logger, LOGGER, method, "()Z", &[])?.z()
+        })?;
+        Ok(JavaLogger {
+            logger: Some(env.new_global_ref(logger)?),
+            max_level,
+        })
+    });
+    match logger {
+        Err(JniError::Java(_)) => Ok(JavaLogger {
+            logger: None,
+            max_level: LevelFilter::Off,
+        }),
+        logger => logger,
+    }
+}
+
+/// `java_vm::call_java` for code which may run while an exception is pending, e.g. logging from
+/// a native method which already threw. The pending exception is set aside during the call.
+fn call_java_aside_exception<F, R>(f: F) -> JniResult<R>
+where
+    F: for<'a> FnOnce(JNIEnv<'a>) -> jni::errors::Result<R>,
+{
+    let vm = java_vm::java_vm()?;
+    let env = vm.attach_current_thread_as_daemon()?;
+    if !env.exception_check()? {
+        return java_vm::call_java(f);
+    }
+    let pending = env.exception_occurred()?;
+    env.exception_clear()?;
+    let result = java_vm::call_java(f);
+    env.throw(pending)?;
+    result
+}
+
+/*
+ * Class:     org_apache_kafka_RustLogging
+ * Method:    refreshLevels
+ * Signature: ()V
+ */
+#[no_mangle]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_org_apache_kafka_RustLogging_refreshLevels(
+    env: JNIEnv,
+    _class: JClass,
+) {
+    jni_guard(env, || -> JniResult<_> {
+        clear();
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jni::{objects::JObject, JNIEnv};
+    use log::{Level, LevelFilter, Log, Metadata};
+
+    use super::{
+        call_java_aside_exception, forwarding, java_logger, log_method, max_level, Slf4jLogger,
+        LEVEL_CHECKS,
+    };
+    use crate::{
+        common::errors::{JavaException, JniError},
+        java_vm::set_java_vm,
+        jvm,
+    };
+
+    fn slf4j_logger<'a>(env: JNIEnv<'a>, name: &str) -> jni::errors::Result<JObject<'a>> {
+        let name = env.new_string(name)?;
+        env.call_static_method(
+            "org/slf4j/LoggerFactory",
+            "getLogger",
+            "(Ljava/lang/String;)Lorg/slf4j/Logger;",
+            &[name.into()],
+        )?
+        .l()
+    }
+
+    #[test]
+    fn records_are_logged_with_the_method_of_their_level() {
+        assert_eq!(log_method(Level::Error), "error");
+        assert_eq!(log_method(Level::Warn), "warn");
+        assert_eq!(log_method(Level::Info), "info");
+        assert_eq!(log_method(Level::Debug), "debug");
+        assert_eq!(log_method(Level::Trace), "trace");
+    }
+
+    #[test]
+    fn max_level_is_the_most_verbose_enabled_level() {
+        // SLF4J bindings enable a level together with the less verbose ones.
+        for (most_verbose, (level, _)) in LEVEL_CHECKS.iter().enumerate() {
+            let enabled: Vec<_> = LEVEL_CHECKS[most_verbose..]
+                .iter()
+                .map(|(_, method)| *method)
+                .collect();
+            let max_level = max_level(|method| Ok::<_, ()>(enabled.contains(&method)));
+            assert_eq!(max_level, Ok(level.to_level_filter()));
+        }
+        assert_eq!(max_level(|_| Ok::<_, ()>(false)), Ok(LevelFilter::Off));
+    }
+
+    #[test]
+    fn max_level_stops_at_the_first_failed_check() {
+        let mut checks = Vec::new();
+        let result = max_level(|method| {
+            checks.push(method.to_string());
+            Err("JVM gone")
+        });
+        assert_eq!(result, Err("JVM gone"));
+        assert_eq!(checks, ["isTraceEnabled"]);
+    }
+
+    #[test]
+    fn nested_records_are_not_forwarded() {
+        let nested = forwarding(|| forwarding(|| ()));
+        assert_eq!(nested, Some(None));
+        assert_eq!(forwarding(|| 42), Some(42));
+    }
+
+    #[test]
+    fn records_of_the_jni_crate_are_not_forwarded() {
+        for target in ["jni", "jni::wrapper::java_vm::vm"] {
+            let metadata = Metadata::builder()
+                .level(Level::Error)
+                .target(target)
+                .build();
+            assert!(!Slf4jLogger.enabled(&metadata), "{}", target);
+        }
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn loggers_are_named_after_the_module_with_the_level_of_slf4j() {
+        jvm::run(|env| {
+            set_java_vm(&env.get_java_vm()?);
+            let logger =
+                java_logger("kafka_connector_jni::slf4j_logger::tests").expect("SLF4J logger");
+            let expected = slf4j_logger(env, "kafka_connector_jni.slf4j_logger.tests")?;
+            let expected_level =
+                max_level(|method| env.call_method(expected, method, "()Z", &[])?.z())?;
+            assert_eq!(logger.max_level, expected_level);
+
+            let actual = logger.logger.expect("SLF4J logger reference");
+            let name = |logger| -> jni::errors::Result<String> {
+                let name = env.call_method(logger, "getName", "()Ljava/lang/String;", &[])?;
+                jvm::string(env, name.l()?)
+            };
+            assert_eq!(name(actual.as_obj())?, name(expected)?);
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn pending_exceptions_are_kept_while_forwarding() {
+        jvm::run(|env| {
+            set_java_vm(&env.get_java_vm()?);
+            env.throw_new("java/lang/ArithmeticException", "/ by zero")?;
+
+            let length = call_java_aside_exception(|env| {
+                let message = env.new_string("forwarded")?;
+                env.call_method(message, "length", "()I", &[])?.i()
+            })
+            .expect("call with an exception pending");
+            assert_eq!(length, 9);
+
+            let exception = JavaException::take(env)?.expect("pending exception");
+            assert_eq!(exception.class_name, "java.lang.ArithmeticException");
+            assert_eq!(exception.message.as_deref(), Some("/ by zero"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+    fn exceptions_thrown_while_forwarding_are_returned() {
+        jvm::run(|env| {
+            set_java_vm(&env.get_java_vm()?);
+            env.throw_new("java/lang/ArithmeticException", "/ by zero")?;
+
+            let result = call_java_aside_exception(|env| {
+                let value = env.new_string("x")?;
+                env.call_static_method(
+                    "java/lang/Integer",
+                    "parseInt",
+                    "(Ljava/lang/String;)I",
+                    &[value.into()],
+                )?
+                .i()
+            });
+            match result {
+                Err(JniError::Java(exception)) => {
+                    assert_eq!(exception.class_name, "java.lang.NumberFormatException")
+                }
+                other => panic!("Expected NumberFormatException, got {:?}", other),
+            }
+
+            let exception = JavaException::take(env)?.expect("pending exception");
+            assert_eq!(exception.class_name, "java.lang.ArithmeticException");
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,75 @@
+use std::{collections::HashSet, sync::Arc};
+
+use crate::{
+    clients::{api_versions::ApiVersions, mock_client::MockClient},
+    common::{
+        cluster::Cluster, node::Node, partition_info::PartitionInfo, utils::mock_time::MockTime,
+    },
+};
+
+/// A single broker served by a `MockClient`, and the clock both run on.
+pub struct MockBroker {
+    pub time: Arc<MockTime>,
+    pub node: Node,
+    pub api_versions: Arc<ApiVersions>,
+    pub client: Arc<MockClient>,
+}
+
+impl MockBroker {
+    pub fn new() -> MockBroker {
+        let time = Arc::new(MockTime::default());
+        let node = Node::new(0, "localhost", 9092);
+        let api_versions = Arc::new(ApiVersions::new());
+        let client = Arc::new(MockClient::new(
+            time.clone(),
+            vec![node.clone()],
+            api_versions.clone(),
+        ));
+        MockBroker {
+            time,
+            node,
+            api_versions,
+            client,
+        }
+    }
+
+    /// A cluster of this broker alone, leading every partition of the given topic.
+    pub fn cluster(&self, topic: &str, partitions: i32) -> Cluster {
+        let leaders = vec![Some(self.node.clone()); partitions as usize];
+        cluster_with_leaders(topic, &leaders)
+    }
+}
+
+/// A cluster of the given topic, whose partitions are led by the given nodes in order. The
+/// partitions without leader are unavailable.
+pub fn cluster_with_leaders(topic: &str, leaders: &[Option<Node>]) -> Cluster {
+    let mut nodes: Vec<Node> = vec![];
+    for node in leaders.iter().flatten() {
+        if !nodes.contains(node) {
+            nodes.push(node.clone());
+        }
+    }
+    let partitions = leaders
+        .iter()
+        .enumerate()
+        .map(|(partition, leader)| {
+            let replicas: Vec<Node> = leader.iter().cloned().collect();
+            PartitionInfo::new(
+                topic,
+                partition as i32,
+                leader.clone(),
+                replicas.clone(),
+                replicas,
+            )
+        })
+        .collect();
+    Cluster::new(
+        None,
+        nodes,
+        partitions,
+        HashSet::new(),
+        HashSet::new(),
+        HashSet::new(),
+        None,
+    )
+}
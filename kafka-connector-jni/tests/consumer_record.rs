@@ -0,0 +1,109 @@
+mod jvm;
+
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+
+const CONSUMER_RECORD: &str = "org/apache/kafka/clients/consumer/ConsumerRecord";
+const TIMESTAMP_TYPE: &str = "Lorg/apache/kafka/common/record/TimestampType;";
+
+fn new_record<'a>(env: JNIEnv<'a>, leader_epoch: JObject<'a>) -> jni::errors::Result<JObject<'a>> {
+    let topic = env.new_string("topic")?;
+    let timestamp_type = env
+        .get_static_field(
+            "org/apache/kafka/common/record/TimestampType",
+            "CREATE_TIME",
+            TIMESTAMP_TYPE,
+        )?
+        .l()?;
+    let key = env.new_string("key")?;
+    let value = env.new_string("value")?;
+    let headers = env.new_object(
+        "org/apache/kafka/common/header/internals/RecordHeaders",
+        "()V",
+        &[],
+    )?;
+    env.new_object(
+        CONSUMER_RECORD,
+        format!(
+            "(Ljava/lang/String;IJJ{}IILjava/lang/Object;Ljava/lang/Object;Lorg/apache/kafka/common/header/Headers;Ljava/util/Optional;)V",
+            TIMESTAMP_TYPE
+        ),
+        &[
+            topic.into(),
+            JValue::Int(3),
+            JValue::Long(42),
+            JValue::Long(1_000),
+            timestamp_type.into(),
+            JValue::Int(3),
+            JValue::Int(5),
+            key.into(),
+            value.into(),
+            headers.into(),
+            leader_epoch.into(),
+        ],
+    )
+}
+
+fn leader_epoch(env: JNIEnv, record: JObject) -> jni::errors::Result<String> {
+    let leader_epoch = env
+        .call_method(record, "leaderEpoch", "()Ljava/util/Optional;", &[])?
+        .l()?;
+    jvm::to_string(env, leader_epoch)
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn leader_epoch_is_optional() {
+    jvm::run(|env| {
+        let epoch = env
+            .call_static_method(
+                "java/lang/Integer",
+                "valueOf",
+                "(I)Ljava/lang/Integer;",
+                &[JValue::Int(7)],
+            )?
+            .l()?;
+        let present = env
+            .call_static_method(
+                "java/util/Optional",
+                "of",
+                "(Ljava/lang/Object;)Ljava/util/Optional;",
+                &[epoch.into()],
+            )?
+            .l()?;
+        let empty = env
+            .call_static_method("java/util/Optional", "empty", "()Ljava/util/Optional;", &[])?
+            .l()?;
+
+        let record = new_record(env, present)?;
+        assert_eq!(leader_epoch(env, record)?, "Optional[7]");
+        let record = new_record(env, empty)?;
+        assert_eq!(leader_epoch(env, record)?, "Optional.empty");
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn fields_are_kept() {
+    jvm::run(|env| {
+        let empty = env
+            .call_static_method("java/util/Optional", "empty", "()Ljava/util/Optional;", &[])?
+            .l()?;
+        let record = new_record(env, empty)?;
+
+        let topic = env
+            .call_method(record, "topic", "()Ljava/lang/String;", &[])?
+            .l()?;
+        assert_eq!(jvm::string(env, topic)?, "topic");
+        assert_eq!(env.call_method(record, "partition", "()I", &[])?.i()?, 3);
+        assert_eq!(env.call_method(record, "offset", "()J", &[])?.j()?, 42);
+        let value = env
+            .call_method(record, "value", "()Ljava/lang/Object;", &[])?
+            .l()?;
+        assert_eq!(jvm::to_string(env, value)?, "value");
+        Ok(())
+    })
+}
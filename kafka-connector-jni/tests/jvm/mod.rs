@@ -0,0 +1,346 @@
+//! JVM embedded in the test process, running the java classes of `kafka/clients` against the
+//! library built by `cargo test`, so bindings are tested through the java API they implement.
+//!
+//! The classpath is taken from `KAFKA_CLIENTS_CLASSPATH`, or from the file written by
+//! `./gradlew :clients:rustTestClasspath` inside the `kafka` directory. The JVM is loaded from
+//! `JAVA_HOME`, or from the installation of the `java` found on `PATH`. The library is not
+//! rebuilt by `cargo test` as the crate is only a `cdylib`, so integration tests fail when it is
+//! older than the sources instead of running the previous build: run `cargo build` first.
+//!
+//! Tests running on the JVM run when `JAVA_HOME` or `CI` is set at build time, and fail when the
+//! classpath is missing or the JVM can't start. Elsewhere they are `#[ignore]`d, so a plain
+//! `cargo test` lists them as ignored instead of passing without running them, and they run
+//! with `cargo test -- --ignored`.
+//!
+//! Unit tests of the library use it as well, as `crate::jvm`. The library is linked into their
+//! executable, which exports `JNI_OnLoad_kafka_connector_jni`: the JVM takes it for a library
+//! linked statically and binds natives to the executable instead of loading the `cdylib`, so
+//! java and the tests share the values stored by the library.
+#![allow(dead_code)]
+
+use std::{
+    env,
+    ffi::{c_void, CString},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    ptr,
+    time::SystemTime,
+};
+
+use jni::{
+    objects::{JObject, JThrowable, JValue},
+    sys::{jint, JavaVMInitArgs, JavaVMOption, JNI_OK, JNI_TRUE, JNI_VERSION_1_8},
+    JNIEnv, JavaVM,
+};
+use once_cell::sync::Lazy;
+
+/// Local references a test may create before the JVM grows its frame.
+const LOCAL_FRAME_CAPACITY: i32 = 64;
+
+/// File name of the library, the JVM looks for `JNI_OnLoad_<name>` in the process first.
+const LIBRARY: &str = "libkafka_connector_jni.so";
+
+/// `libjvm.so` relative to the java home, of current JDKs and of java 8 JREs.
+const LIBJVM_PATHS: &[&str] = &[
+    "lib/server/libjvm.so",
+    "lib/amd64/server/libjvm.so",
+    "jre/lib/amd64/server/libjvm.so",
+];
+
+type CreateJavaVm =
+    unsafe extern "system" fn(*mut *mut jni::sys::JavaVM, *mut *mut c_void, *mut c_void) -> jint;
+
+enum Jvm {
+    Started(*mut jni::sys::JavaVM),
+    Failed(String),
+}
+
+// The JavaVM is shared by every thread of the process.
+unsafe impl Send for Jvm {}
+unsafe impl Sync for Jvm {}
+
+/// Never dropped, the JVM lives as long as the process.
+static JVM: Lazy<Jvm> = Lazy::new(|| {
+    kafka_clients_classpath()
+        .and_then(|classpath| create_jvm(&classpath))
+        .map_or_else(Jvm::Failed, Jvm::Started)
+});
+
+/// The JVM shared by the tests of this process, panics if it can't be started.
+pub fn jvm() -> JavaVM {
+    match &*JVM {
+        Jvm::Started(vm) => unsafe { JavaVM::from_raw(*vm) }.expect("JavaVM pointer"),
+        Jvm::Failed(reason) => panic!("Failed to start the JVM: {}", reason),
+    }
+}
+
+/// Runs `test` on the current thread attached to the JVM.
+///
+/// Fails the test when `test` returns an error or leaves an exception pending, with the
+/// exception and its stack trace in the panic message.
+pub fn run<F>(test: F)
+where
+    F: for<'a> FnOnce(JNIEnv<'a>) -> jni::errors::Result<()>,
+{
+    let vm = jvm();
+    let env = vm
+        .attach_current_thread()
+        .expect("attach thread to the JVM");
+    env.push_local_frame(LOCAL_FRAME_CAPACITY)
+        .expect("push local frame");
+    let result = test(*env);
+    let exception = take_exception(*env);
+    env.pop_local_frame(JObject::null())
+        .expect("pop local frame");
+    if let Some(exception) = exception {
+        panic!("Exception thrown: {}", exception);
+    }
+    if let Err(e) = result {
+        panic!("JNI call failed: {}", e);
+    }
+}
+
+/// Runs `f` expecting it to throw, returns the name of the class of the exception thrown.
+pub fn expect_exception<'a, F, R>(env: JNIEnv<'a>, f: F) -> jni::errors::Result<String>
+where
+    F: FnOnce() -> jni::errors::Result<R>,
+{
+    let result = f();
+    if !env.exception_check()? {
+        result?;
+        panic!("Expected an exception, none was thrown");
+    }
+    let exception = env.exception_occurred()?;
+    env.exception_clear()?;
+    let class = env.get_object_class(exception)?;
+    let name = env.call_method(class, "getName", "()Ljava/lang/String;", &[])?;
+    string(env, name.l()?)
+}
+
+/// Rust copy of a `java.lang.String`.
+pub fn string(env: JNIEnv, obj: JObject) -> jni::errors::Result<String> {
+    Ok(env.get_string(obj.into())?.into())
+}
+
+/// Result of `String.valueOf(obj)`.
+pub fn to_string(env: JNIEnv, obj: JObject) -> jni::errors::Result<String> {
+    let value = env.call_static_method(
+        "java/lang/String",
+        "valueOf",
+        "(Ljava/lang/Object;)Ljava/lang/String;",
+        &[obj.into()],
+    )?;
+    string(env, value.l()?)
+}
+
+/// New `byte[]` holding `bytes`.
+pub fn byte_array<'a>(env: JNIEnv<'a>, bytes: &[u8]) -> jni::errors::Result<JValue<'a>> {
+    Ok(JObject::from(env.byte_array_from_slice(bytes)?).into())
+}
+
+/// New direct `java.nio.ByteBuffer` holding `bytes`, positioned at the first one.
+pub fn direct_buffer<'a>(env: JNIEnv<'a>, bytes: &[u8]) -> jni::errors::Result<JValue<'a>> {
+    let buffer = env
+        .call_static_method(
+            "java/nio/ByteBuffer",
+            "allocateDirect",
+            "(I)Ljava/nio/ByteBuffer;",
+            &[JValue::Int(bytes.len() as jint)],
+        )?
+        .l()?;
+    env.call_method(
+        buffer,
+        "put",
+        "([B)Ljava/nio/ByteBuffer;",
+        &[byte_array(env, bytes)?],
+    )?;
+    // Declared by `Buffer` on java 8.
+    env.call_method(buffer, "rewind", "()Ljava/nio/Buffer;", &[])?;
+    Ok(buffer.into())
+}
+
+/// Rust copy of a `byte[]`, `None` for null.
+pub fn bytes(env: JNIEnv, obj: JObject) -> jni::errors::Result<Option<Vec<u8>>> {
+    if obj.is_null() {
+        return Ok(None);
+    }
+    env.convert_byte_array(obj.into_inner()).map(Some)
+}
+
+/// Clears the pending exception, describing it with its stack trace.
+fn take_exception(env: JNIEnv) -> Option<String> {
+    if !env.exception_check().unwrap_or(false) {
+        return None;
+    }
+    let exception = env.exception_occurred().ok()?;
+    env.exception_clear().ok()?;
+    Some(stack_trace(env, exception).unwrap_or_else(|e| format!("<not printable: {}>", e)))
+}
+
+fn stack_trace(env: JNIEnv, exception: JThrowable) -> jni::errors::Result<String> {
+    let writer = env.new_object("java/io/StringWriter", "()V", &[])?;
+    let printer = env.new_object(
+        "java/io/PrintWriter",
+        "(Ljava/io/Writer;)V",
+        &[writer.into()],
+    )?;
+    env.call_method(
+        exception,
+        "printStackTrace",
+        "(Ljava/io/PrintWriter;)V",
+        &[printer.into()],
+    )?;
+    to_string(env, writer)
+}
+
+fn create_jvm(classpath: &str) -> Result<*mut jni::sys::JavaVM, String> {
+    let library = jni_library()?;
+    let create_java_vm = load_libjvm(&java_home()?)?;
+
+    let options = [
+        format!("-Djava.class.path={}", classpath),
+        format!("-Dkafka.connector.jni.library={}", library.display()),
+        "-Xcheck:jni".to_string(),
+    ];
+    let options = options
+        .iter()
+        .map(|option| CString::new(option.as_str()).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut options = options
+        .iter()
+        .map(|option| JavaVMOption {
+            optionString: option.as_ptr() as *mut _,
+            extraInfo: ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    let mut args = JavaVMInitArgs {
+        version: JNI_VERSION_1_8,
+        nOptions: options.len() as jint,
+        options: options.as_mut_ptr(),
+        ignoreUnrecognized: JNI_TRUE,
+    };
+
+    let mut vm = ptr::null_mut();
+    let mut env = ptr::null_mut();
+    let status = unsafe { create_java_vm(&mut vm, &mut env, &mut args as *mut _ as *mut c_void) };
+    if status != JNI_OK {
+        return Err(format!("JNI_CreateJavaVM failed with {}", status));
+    }
+    Ok(vm)
+}
+
+/// The library built with the test executable, in `target/<profile>/deps`, or only its file
+/// name when the library is linked into the executable.
+fn jni_library() -> Result<PathBuf, String> {
+    if library_linked() {
+        return Ok(PathBuf::from(LIBRARY));
+    }
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let library = exe
+        .ancestors()
+        .skip(1)
+        .take(2)
+        .map(|dir| dir.join(LIBRARY))
+        .find(|library| library.exists())
+        .ok_or(
+            "libkafka_connector_jni.so not found next to the test executable, run `cargo build`",
+        )?;
+    let built = modified(&library).ok_or("libkafka_connector_jni.so has no modification time")?;
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let sources = [
+        manifest_dir.join("src"),
+        manifest_dir.join("../kafka-connector-macros/src"),
+    ];
+    if let Some(source) = sources
+        .iter()
+        .filter_map(|dir| newer_source(dir, built))
+        .next()
+    {
+        return Err(format!(
+            "{} is older than {}, run `cargo build`",
+            library.display(),
+            source.display()
+        ));
+    }
+    Ok(library)
+}
+
+/// Whether the library is linked into the test executable, as for unit tests of the library.
+fn library_linked() -> bool {
+    let name = CString::new("JNI_OnLoad_kafka_connector_jni").expect("symbol name");
+    unsafe { !libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr()).is_null() }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// A file under `dir` modified after `time`.
+fn newer_source(dir: &Path, time: SystemTime) -> Option<PathBuf> {
+    fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            newer_source(&path, time)
+        } else {
+            modified(&path)
+                .filter(|modified| *modified > time)
+                .map(|_| path)
+        }
+    })
+}
+
+fn kafka_clients_classpath() -> Result<String, String> {
+    if let Ok(classpath) = env::var("KAFKA_CLIENTS_CLASSPATH") {
+        return Ok(classpath);
+    }
+    let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../kafka/clients/build/rust-test-classpath.txt");
+    fs::read_to_string(&file)
+        .map(|classpath| classpath.trim().to_string())
+        .map_err(|_| {
+            "KAFKA_CLIENTS_CLASSPATH not set and kafka/clients classpath not generated, \
+             run `./gradlew :clients:rustTestClasspath` inside the kafka directory"
+                .to_string()
+        })
+}
+
+fn java_home() -> Result<PathBuf, String> {
+    if let Ok(java_home) = env::var("JAVA_HOME") {
+        return Ok(PathBuf::from(java_home));
+    }
+    let output = Command::new("java")
+        .args(["-XshowSettings:properties", "-version"])
+        .output()
+        .map_err(|_| "JAVA_HOME not set and no java found on PATH".to_string())?;
+    // The settings are printed to stderr.
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("java.home = "))
+        .map(PathBuf::from)
+        .ok_or_else(|| "java.home not reported by java".to_string())
+}
+
+fn load_libjvm(java_home: &Path) -> Result<CreateJavaVm, String> {
+    let libjvm = LIBJVM_PATHS
+        .iter()
+        .map(|path| java_home.join(path))
+        .find(|path| path.exists())
+        .ok_or_else(|| format!("libjvm.so not found in {}", java_home.display()))?;
+    let libjvm = CString::new(libjvm.to_string_lossy().as_bytes()).map_err(|e| e.to_string())?;
+    unsafe {
+        // Never closed, the JVM can't be destroyed and created again in the same process.
+        let handle = libc::dlopen(libjvm.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL);
+        if handle.is_null() {
+            return Err(format!("Failed to load {:?}", libjvm));
+        }
+        let name = CString::new("JNI_CreateJavaVM").expect("symbol name");
+        let symbol = libc::dlsym(handle, name.as_ptr());
+        if symbol.is_null() {
+            return Err("JNI_CreateJavaVM not found in libjvm.so".to_string());
+        }
+        Ok(std::mem::transmute::<*mut c_void, CreateJavaVm>(symbol))
+    }
+}
@@ -0,0 +1,187 @@
+mod jvm;
+
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+
+const METRIC_CONFIG: &str = "org/apache/kafka/common/metrics/MetricConfig";
+const CONFIG: &str = "Lorg/apache/kafka/common/metrics/MetricConfig;";
+
+fn new_config<'a>(
+    env: JNIEnv<'a>,
+    samples: i32,
+    window_ms: i64,
+) -> jni::errors::Result<JObject<'a>> {
+    let config = env.new_object(METRIC_CONFIG, "()V", &[])?;
+    env.call_method(
+        config,
+        "samples",
+        format!("(I){}", CONFIG),
+        &[JValue::Int(samples)],
+    )?;
+    env.call_method(
+        config,
+        "timeWindowMs",
+        format!("(J){}", CONFIG),
+        &[JValue::Long(window_ms)],
+    )?;
+    Ok(config)
+}
+
+fn record(
+    env: JNIEnv,
+    stat: JObject,
+    config: JObject,
+    value: f64,
+    now: i64,
+) -> jni::errors::Result<()> {
+    env.call_method(
+        stat,
+        "record",
+        format!("({}DJ)V", CONFIG),
+        &[config.into(), JValue::Double(value), JValue::Long(now)],
+    )?;
+    Ok(())
+}
+
+fn measure(env: JNIEnv, stat: JObject, config: JObject, now: i64) -> jni::errors::Result<f64> {
+    env.call_method(
+        stat,
+        "measure",
+        format!("({}J)D", CONFIG),
+        &[config.into(), JValue::Long(now)],
+    )?
+    .d()
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn metric_config_defaults() {
+    jvm::run(|env| {
+        let config = env.new_object(METRIC_CONFIG, "()V", &[])?;
+
+        assert_eq!(env.call_method(config, "samples", "()I", &[])?.i()?, 2);
+        assert_eq!(
+            env.call_method(config, "timeWindowMs", "()J", &[])?.j()?,
+            30_000
+        );
+        assert_eq!(
+            env.call_method(config, "eventWindow", "()J", &[])?.j()?,
+            i64::MAX
+        );
+        let tags = env
+            .call_method(config, "tags", "()Ljava/util/Map;", &[])?
+            .l()?;
+        assert_eq!(jvm::to_string(env, tags)?, "{}");
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn metric_config_keeps_bounds_of_java_integers() {
+    jvm::run(|env| {
+        let config = env.new_object(METRIC_CONFIG, "()V", &[])?;
+        for (event_window, samples) in [(0, 0), (i64::MIN, i32::MIN), (i64::MAX, i32::MAX)] {
+            env.call_method(
+                config,
+                "eventWindow",
+                format!("(J){}", CONFIG),
+                &[JValue::Long(event_window)],
+            )?;
+            env.call_method(
+                config,
+                "samples",
+                format!("(I){}", CONFIG),
+                &[JValue::Int(samples)],
+            )?;
+            assert_eq!(
+                env.call_method(config, "eventWindow", "()J", &[])?.j()?,
+                event_window
+            );
+            assert_eq!(
+                env.call_method(config, "samples", "()I", &[])?.i()?,
+                samples
+            );
+        }
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn metric_config_setters_chain() {
+    jvm::run(|env| {
+        let config = env.new_object(METRIC_CONFIG, "()V", &[])?;
+        let returned = env
+            .call_method(
+                config,
+                "eventWindow",
+                format!("(J){}", CONFIG),
+                &[JValue::Long(10)],
+            )?
+            .l()?;
+        assert!(env.is_same_object(config, returned)?);
+
+        let tags = env.new_object("java/util/LinkedHashMap", "()V", &[])?;
+        for (key, value) in [("client-id", "test"), ("topic", "events")] {
+            let key = env.new_string(key)?;
+            let value = env.new_string(value)?;
+            env.call_method(
+                tags,
+                "put",
+                "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+                &[key.into(), value.into()],
+            )?;
+        }
+        env.call_method(
+            config,
+            "tags",
+            format!("(Ljava/util/Map;){}", CONFIG),
+            &[tags.into()],
+        )?;
+
+        assert_eq!(env.call_method(config, "eventWindow", "()J", &[])?.j()?, 10);
+        let tags = env
+            .call_method(config, "tags", "()Ljava/util/Map;", &[])?
+            .l()?;
+        assert_eq!(jvm::to_string(env, tags)?, "{client-id=test, topic=events}");
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn max_and_avg_combine_samples() {
+    jvm::run(|env| {
+        let config = new_config(env, 2, 1_000)?;
+        let max = env.new_object("org/apache/kafka/common/metrics/stats/Max", "()V", &[])?;
+        let avg = env.new_object("org/apache/kafka/common/metrics/stats/Avg", "()V", &[])?;
+        assert!(measure(env, max, config, 0)?.is_nan());
+
+        for (value, now) in [(5.0, 0), (1.0, 500), (3.0, 1_500)] {
+            record(env, max, config, value, now)?;
+            record(env, avg, config, value, now)?;
+        }
+
+        assert_eq!(measure(env, max, config, 1_500)?, 5.0);
+        assert_eq!(measure(env, avg, config, 1_500)?, 3.0);
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn sampled_stat_purges_expired_samples() {
+    jvm::run(|env| {
+        let config = new_config(env, 2, 10)?;
+        let max = env.new_object("org/apache/kafka/common/metrics/stats/Max", "()V", &[])?;
+        record(env, max, config, 10.0, 0)?;
+        record(env, max, config, 1.0, 100)?;
+
+        assert_eq!(measure(env, max, config, 100)?, 1.0);
+        assert!(measure(env, max, config, 1_000)?.is_nan());
+        Ok(())
+    })
+}
@@ -0,0 +1,185 @@
+mod jvm;
+
+use jni::{
+    objects::{JObject, JValue},
+    JNIEnv,
+};
+
+const RECORD_HEADERS: &str = "org/apache/kafka/common/header/internals/RecordHeaders";
+const HEADERS: &str = "Lorg/apache/kafka/common/header/Headers;";
+const HEADER: &str = "Lorg/apache/kafka/common/header/Header;";
+
+fn new_headers<'a>(env: JNIEnv<'a>) -> jni::errors::Result<JObject<'a>> {
+    env.new_object(RECORD_HEADERS, "()V", &[])
+}
+
+fn add<'a>(
+    env: JNIEnv<'a>,
+    headers: JObject<'a>,
+    key: &str,
+    value: &[u8],
+) -> jni::errors::Result<JObject<'a>> {
+    let key = env.new_string(key)?;
+    let value = jvm::byte_array(env, value)?;
+    env.call_method(
+        headers,
+        "add",
+        format!("(Ljava/lang/String;[B){}", HEADERS),
+        &[key.into(), value],
+    )?
+    .l()
+}
+
+fn last_value(
+    env: JNIEnv,
+    headers: JObject,
+    key: &str,
+) -> jni::errors::Result<Option<Option<Vec<u8>>>> {
+    let key = env.new_string(key)?;
+    let header = env
+        .call_method(
+            headers,
+            "lastHeader",
+            format!("(Ljava/lang/String;){}", HEADER),
+            &[key.into()],
+        )?
+        .l()?;
+    if header.is_null() {
+        return Ok(None);
+    }
+    let value = env.call_method(header, "value", "()[B", &[])?.l()?;
+    jvm::bytes(env, value).map(Some)
+}
+
+fn len(env: JNIEnv, headers: JObject) -> jni::errors::Result<i32> {
+    let array = env
+        .call_method(headers, "toArray", format!("()[{}", HEADER), &[])?
+        .l()?;
+    env.get_array_length(array.into_inner())
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn add_returns_same_headers_and_last_header_wins() {
+    jvm::run(|env| {
+        let headers = new_headers(env)?;
+        let returned = add(env, headers, "key", b"first")?;
+        assert!(env.is_same_object(headers, returned)?);
+        add(env, headers, "key", b"second")?;
+        add(env, headers, "other", b"value")?;
+
+        assert_eq!(len(env, headers)?, 3);
+        assert_eq!(
+            last_value(env, headers, "key")?,
+            Some(Some(b"second".to_vec()))
+        );
+        assert_eq!(last_value(env, headers, "missing")?, None);
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn remove_drops_every_header_with_key() {
+    jvm::run(|env| {
+        let headers = new_headers(env)?;
+        add(env, headers, "key", b"first")?;
+        add(env, headers, "other", b"value")?;
+        add(env, headers, "key", b"second")?;
+
+        let key = env.new_string("key")?;
+        env.call_method(
+            headers,
+            "remove",
+            format!("(Ljava/lang/String;){}", HEADERS),
+            &[key.into()],
+        )?;
+
+        assert_eq!(len(env, headers)?, 1);
+        assert_eq!(last_value(env, headers, "key")?, None);
+        assert_eq!(
+            last_value(env, headers, "other")?,
+            Some(Some(b"value".to_vec()))
+        );
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn headers_by_key_are_iterable() {
+    jvm::run(|env| {
+        let headers = new_headers(env)?;
+        add(env, headers, "key", b"first")?;
+        add(env, headers, "other", b"value")?;
+        add(env, headers, "key", b"second")?;
+
+        let key = env.new_string("key")?;
+        let iterable = env
+            .call_method(
+                headers,
+                "headers",
+                "(Ljava/lang/String;)Ljava/lang/Iterable;",
+                &[key.into()],
+            )?
+            .l()?;
+        let iterator = env
+            .call_method(iterable, "iterator", "()Ljava/util/Iterator;", &[])?
+            .l()?;
+        let mut values = Vec::new();
+        while env.call_method(iterator, "hasNext", "()Z", &[])?.z()? {
+            let header = env
+                .call_method(iterator, "next", "()Ljava/lang/Object;", &[])?
+                .l()?;
+            let value = env.call_method(header, "value", "()[B", &[])?.l()?;
+            values.push(jvm::bytes(env, value)?);
+        }
+
+        assert_eq!(
+            values,
+            vec![Some(b"first".to_vec()), Some(b"second".to_vec())]
+        );
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn equal_headers_are_equal_in_java() {
+    jvm::run(|env| {
+        let first = new_headers(env)?;
+        let second = new_headers(env)?;
+        add(env, first, "key", b"value")?;
+        add(env, second, "key", b"value")?;
+
+        let equals = env
+            .call_method(first, "equals", "(Ljava/lang/Object;)Z", &[second.into()])?
+            .z()?;
+        assert!(equals);
+        assert_eq!(
+            jvm::to_string(env, first)?,
+            "RecordHeaders(headers = [RecordHeader(key = key, value = [118, 97, 108, 117, 101])])"
+        );
+        Ok(())
+    })
+}
+
+#[test]
+#[cfg_attr(not(jvm_tests), ignore = "runs on the JVM, see tests/jvm/mod.rs")]
+fn null_header_throws() {
+    jvm::run(|env| {
+        let headers = new_headers(env)?;
+        let exception = jvm::expect_exception(env, || {
+            env.call_method(
+                headers,
+                "add",
+                format!("({}){}", HEADER, HEADERS),
+                &[JValue::Object(JObject::null())],
+            )
+        })?;
+
+        assert_eq!(exception, "java.lang.NullPointerException");
+        assert_eq!(len(env, headers)?, 0);
+        Ok(())
+    })
+}
@@ -1,29 +1,115 @@
+use std::collections::HashMap;
+
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
 use quote::{quote, quote_spanned};
-use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    spanned::Spanned,
+    Data, DataEnum, DeriveInput, Fields, LitInt, LitStr, Token,
+};
+
+use crate::utils::{find_attribute, parse_ident, string_value};
+
+/// Variant bound to the java constant `java_variant`.
+struct JavaVariant<'a> {
+    variant: &'a syn::Variant,
+    java_variant: LitStr,
+    id: Option<JavaId>,
+    name: Option<LitStr>,
+    fallback: bool,
+}
+
+/// Value of `#[java_id = N]`, or `#[java_id(N)]` as negative ids can't follow `=`.
+struct JavaId {
+    tokens: proc_macro2::TokenStream,
+    value: i128,
+}
+
+impl Parse for JavaId {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let id = JavaId::parse_value(&content)?;
+            if !content.is_empty() {
+                return Err(content.error("Expected a single integer"));
+            }
+            return Ok(id);
+        }
+        input.parse::<Token![=]>()?;
+        JavaId::parse_value(input)
+    }
+}
+
+impl JavaId {
+    fn parse_value(input: ParseStream) -> syn::Result<Self> {
+        let minus: Option<Token![-]> = input.parse()?;
+        let literal: LitInt = input.parse()?;
+        let value = literal.base10_parse::<i128>()?;
+        Ok(JavaId {
+            value: if minus.is_some() { -value } else { value },
+            tokens: quote! { #minus #literal },
+        })
+    }
+}
 
 pub fn java_enum_impl(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let enum_ident = input.ident;
+    match expand(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
 
-    let class_name = input
-        .attrs
-        .iter()
-        .find(|attr| attr.path.segments.last().unwrap().ident == "java_class")
-        .map(|attr| attr.tokens.clone().into_iter().last().unwrap().to_string())
-        .map(remove_first_and_last_characters)
-        .expect("No java_class attribute found");
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let enum_ident = &input.ident;
+
+    let class_name = find_attribute(&input.attrs, "java_class")
+        .ok_or_else(|| syn::Error::new(input.span(), "No java_class attribute found"))
+        .and_then(string_value)?
+        .value();
+
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "JavaEnum can only be derived for enums",
+            ))
+        }
+    };
+    let variants = java_variants(data)?;
+    let fallback = fallback_variant(&variants)?;
 
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let fields_from_jobject = generate_fields_from_jobject(&input.data, &enum_ident, &class_name);
-    let fields_to_jobject = generate_fields_to_jobject(&input.data, &enum_ident, &class_name);
+    let fields_from_jobject = generate_fields_from_jobject(&variants, enum_ident, &class_name);
+    let fields_to_jobject = generate_fields_to_jobject(&variants, enum_ident, &class_name);
+    let unknown_value = match fallback {
+        Some(fallback) => quote! {
+            return Ok(#enum_ident::#fallback);
+        },
+        None => quote! {
+            env.throw_new("java/lang/Exception", "Unknown enum value")?;
+            return Err(jni::errors::Error::JavaException);
+        },
+    };
+    let values = generate_values(&variants, enum_ident);
+    let ids = generate_ids(input, &variants, enum_ident, fallback)?;
+    let names = generate_names(input, &variants, enum_ident)?;
 
-    let expanded = quote! {
+    Ok(quote! {
+        impl #impl_generics #enum_ident #ty_generics #where_clause {
+            #values
+            #ids
+        }
+        #names
         impl #impl_generics crate::clone_to_java::CloneToJava for #enum_ident #ty_generics #where_clause {
+            type Kind = crate::clone_to_java::kind::Object;
+
             fn clone_to_java<'a>(&self, env: jni::JNIEnv<'a>) -> jni::errors::Result<jni::objects::JValue<'a>> {
-                let class = env.find_class(#class_name)?;
                 let obj = match self {
                     #fields_to_jobject
                 };
@@ -33,96 +119,291 @@ pub fn java_enum_impl(input: TokenStream) -> TokenStream {
         impl #impl_generics crate::clone_from_java::CloneFromJava for #enum_ident #ty_generics #where_clause {
 
             fn clone_from_java(env: jni::JNIEnv, obj: jni::objects::JValue)-> jni::errors::Result<Self> {
-                let obj = obj.l()?;
-                let class = env.find_class(#class_name)?;
-                assert!(env.is_instance_of(obj, class)?, "Wrong object class");
+                let obj = crate::clone_from_java::non_null::<Self>(env, obj.l()?)?;
+                let class = crate::jni_cache::class(env, #class_name)?;
+                if !env.is_instance_of(obj, &class)? {
+                    env.throw_new("java/lang/Exception", "Wrong object class")?;
+                    return Err(jni::errors::Error::JavaException);
+                }
                 #fields_from_jobject
-                env.throw_new("java/lang/Exception", "Unknown enum value")?;
-                return Err(jni::errors::Error::JavaException);
+                #unknown_value
+            }
+        }
+    })
+}
+
+/// Variants with the name of their java constant from `#[java_variant = "NAME"]` and their
+/// optional `#[java_id = N]`, `#[java_name = "name"]` and `#[java_fallback]` attributes.
+fn java_variants(data: &DataEnum) -> syn::Result<Vec<JavaVariant<'_>>> {
+    data.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(syn::Error::new(
+                    variant.fields.span(),
+                    "JavaEnum variants can't have fields",
+                ));
             }
+            let java_variant = find_attribute(&variant.attrs, "java_variant")
+                .ok_or_else(|| {
+                    syn::Error::new(
+                        variant.span(),
+                        format!("No java_variant attribute on variant {}", variant.ident),
+                    )
+                })
+                .and_then(string_value)?;
+            let id = find_attribute(&variant.attrs, "java_id")
+                .map(|attribute| syn::parse2::<JavaId>(attribute.tokens.clone()))
+                .transpose()?;
+            let name = find_attribute(&variant.attrs, "java_name")
+                .map(string_value)
+                .transpose()?;
+            let fallback = match find_attribute(&variant.attrs, "java_fallback") {
+                Some(attribute) if !attribute.tokens.is_empty() => {
+                    return Err(syn::Error::new(
+                        attribute.tokens.span(),
+                        "Expected #[java_fallback] without a value",
+                    ))
+                }
+                Some(_) => true,
+                None => false,
+            };
+            Ok(JavaVariant {
+                variant,
+                java_variant,
+                id,
+                name,
+                fallback,
+            })
+        })
+        .collect()
+}
+
+/// The `#[java_fallback]` variant, at most one.
+fn fallback_variant<'a>(variants: &[JavaVariant<'a>]) -> syn::Result<Option<&'a Ident>> {
+    let mut fallbacks = variants.iter().filter(|variant| variant.fallback);
+    let fallback = fallbacks.next().map(|variant| &variant.variant.ident);
+    if let Some(duplicate) = fallbacks.next() {
+        return Err(syn::Error::new(
+            duplicate.variant.span(),
+            "Only one variant can be #[java_fallback]",
+        ));
+    }
+    Ok(fallback)
+}
+
+/// Checks `attribute` is set on every variant or none of them, returns if it is set.
+fn on_all_or_none(
+    variants: &[JavaVariant],
+    attribute: &str,
+    is_set: impl Fn(&JavaVariant) -> bool,
+) -> syn::Result<bool> {
+    let set = variants.iter().any(&is_set);
+    match variants.iter().find(|variant| is_set(variant) != set) {
+        Some(variant) if set => Err(syn::Error::new(
+            variant.variant.span(),
+            format!(
+                "No {} attribute on variant {}, required once another variant has one",
+                attribute, variant.variant.ident
+            ),
+        )),
+        _ => Ok(set),
+    }
+}
+
+fn generate_values(variants: &[JavaVariant], enum_name: &Ident) -> proc_macro2::TokenStream {
+    let names = variants.iter().map(|variant| &variant.variant.ident);
+    quote! {
+        /// Every variant, in declaration order.
+        pub fn values() -> &'static [#enum_name] {
+            &[#(#enum_name::#names),*]
         }
+    }
+}
+
+/// `id()` and `from_id` of variants with `#[java_id = N]`, of type `#[java_id_type = "i16"]`.
+fn generate_ids(
+    input: &DeriveInput,
+    variants: &[JavaVariant],
+    enum_name: &Ident,
+    fallback: Option<&Ident>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let id_type = find_attribute(&input.attrs, "java_id_type")
+        .map(string_value)
+        .transpose()?;
+    let has_ids = on_all_or_none(variants, "java_id", |variant| variant.id.is_some())?;
+    let id_type = match (id_type, has_ids) {
+        (Some(id_type), true) => parse_ident(&id_type.value(), id_type.span())?,
+        (None, false) => return Ok(quote! {}),
+        (Some(id_type), false) => {
+            return Err(syn::Error::new(
+                id_type.span(),
+                "java_id_type without #[java_id = N] on the variants",
+            ))
+        }
+        (None, true) => {
+            return Err(syn::Error::new(
+                input.span(),
+                "No java_id_type attribute found, required by #[java_id = N]",
+            ))
+        }
+    };
+
+    let mut seen = HashMap::new();
+    for variant in variants {
+        let id = variant.id.as_ref().expect("checked by on_all_or_none");
+        if let Some(other) = seen.insert(id.value, &variant.variant.ident) {
+            return Err(syn::Error::new(
+                id.tokens.span(),
+                format!("Duplicate java_id {}, already used by {}", id.value, other),
+            ));
+        }
+    }
+
+    let to_id = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let id = &variant
+            .id
+            .as_ref()
+            .expect("checked by on_all_or_none")
+            .tokens;
+        quote_spanned! { variant.variant.span() =>
+            #enum_name::#name => #id,
+        }
+    });
+    let from_id = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let id = &variant
+            .id
+            .as_ref()
+            .expect("checked by on_all_or_none")
+            .tokens;
+        match fallback {
+            Some(_) => quote! { #id => #enum_name::#name, },
+            None => quote! { #id => Some(#enum_name::#name), },
+        }
+    });
+    let from_id = match fallback {
+        Some(fallback) => quote! {
+            /// Variant with `id`, the fallback variant for unknown ids.
+            pub fn from_id(id: #id_type) -> #enum_name {
+                match id {
+                    #(#from_id)*
+                    _ => #enum_name::#fallback,
+                }
+            }
+        },
+        None => quote! {
+            /// Variant with `id`, `None` for unknown ids.
+            pub fn from_id(id: #id_type) -> Option<#enum_name> {
+                match id {
+                    #(#from_id)*
+                    _ => None,
+                }
+            }
+        },
     };
-    TokenStream::from(expanded)
+    Ok(quote! {
+        /// The id identifying the variant, e.g. in the kafka protocol.
+        pub fn id(&self) -> #id_type {
+            match self {
+                #(#to_id)*
+            }
+        }
+
+        #from_id
+    })
+}
+
+/// `name()`, `Display` and `FromStr` of variants with `#[java_name = "name"]`.
+fn generate_names(
+    input: &DeriveInput,
+    variants: &[JavaVariant],
+    enum_name: &Ident,
+) -> syn::Result<proc_macro2::TokenStream> {
+    if !on_all_or_none(variants, "java_name", |variant| variant.name.is_some())? {
+        return Ok(quote! {});
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let to_name = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let java_name = variant.name.as_ref().expect("checked by on_all_or_none");
+        quote_spanned! { variant.variant.span() =>
+            #enum_name::#name => #java_name,
+        }
+    });
+    let from_name = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let java_name = variant.name.as_ref().expect("checked by on_all_or_none");
+        quote! { #java_name => Ok(#enum_name::#name), }
+    });
+    let unknown_name = format!("Unknown {} name: {{}}", enum_name);
+    Ok(quote! {
+        impl #impl_generics #enum_name #ty_generics #where_clause {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(#to_name)*
+                }
+            }
+        }
+        impl #impl_generics std::fmt::Display for #enum_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+        impl #impl_generics std::str::FromStr for #enum_name #ty_generics #where_clause {
+            type Err = crate::common::errors::KafkaError;
+
+            fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+                match name {
+                    #(#from_name)*
+                    _ => Err(crate::common::errors::KafkaError::IllegalArgument(format!(
+                        #unknown_name,
+                        name
+                    ))),
+                }
+            }
+        }
+    })
 }
 
 fn generate_fields_from_jobject(
-    data: &syn::Data,
+    variants: &[JavaVariant],
     enum_name: &Ident,
     class_name: &str,
-) -> quote::__private::TokenStream {
+) -> proc_macro2::TokenStream {
     let class = format!("L{};", class_name);
-    match *data {
-        Data::Enum(ref data) => {
-            let recurse = data.variants.iter().map(|variant| {
-                let name = &variant.ident;
-                let v_name = name.to_string();
-                let java_variant = variant
-                    .attrs
-                    .iter()
-                    .find(|attr| attr.path.segments.last().unwrap().ident == "java_variant")
-                    .map(|attr| attr.tokens.clone().into_iter().last().unwrap().to_string())
-                    .map(remove_first_and_last_characters)
-                    .unwrap_or_else(|| panic!("No java_variant attribute on variant {}", v_name));
-                quote_spanned! { variant.span() =>
-                    let variant = env
-                        .get_static_field(
-                            class,
-                            #java_variant,
-                            #class,
-                        )?
-                        .l()?;
-                    if env.is_same_object(obj, variant)? {
-                        return Ok(#enum_name::#name);
-                    }
-                }
-            });
-            quote! {
-                #(#recurse)*
+    let recurse = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let java_variant = &variant.java_variant;
+        quote_spanned! { variant.variant.span() =>
+            let variant = crate::jni_cache::get_static_field(env, #class_name, #java_variant, #class)?
+                .l()?;
+            if env.is_same_object(obj, variant)? {
+                return Ok(#enum_name::#name);
             }
         }
-        Data::Struct(_) | Data::Union(_) => unimplemented!(),
+    });
+    quote! {
+        #(#recurse)*
     }
 }
 
 fn generate_fields_to_jobject(
-    data: &syn::Data,
+    variants: &[JavaVariant],
     enum_name: &Ident,
     class_name: &str,
-) -> quote::__private::TokenStream {
+) -> proc_macro2::TokenStream {
     let class = format!("L{};", class_name);
-    match *data {
-        Data::Enum(ref data) => {
-            let recurse = data.variants.iter().map(|variant| {
-                let name = &variant.ident;
-                let v_name = name.to_string();
-                let java_variant = variant
-                    .attrs
-                    .iter()
-                    .find(|a| a.path.segments.last().unwrap().ident == "java_variant")
-                    .map(|x| x.tokens.clone().into_iter().last().unwrap().to_string())
-                    .map(remove_first_and_last_characters)
-                    .unwrap_or_else(|| panic!("No java_variant attribute on variant {}", v_name));
-                quote_spanned! { variant.span() =>
-                    #enum_name::#name => env
-                        .get_static_field(
-                            class,
-                            #java_variant,
-                            #class,
-                        )?
-                        .l()?,
-                }
-            });
-            quote! {
-                #(#recurse)*
-            }
+    let recurse = variants.iter().map(|variant| {
+        let name = &variant.variant.ident;
+        let java_variant = &variant.java_variant;
+        quote_spanned! { variant.variant.span() =>
+            #enum_name::#name => crate::jni_cache::get_static_field(env, #class_name, #java_variant, #class)?
+                .l()?,
         }
-        Data::Struct(_) | Data::Union(_) => unimplemented!(),
+    });
+    quote! {
+        #(#recurse)*
     }
 }
-
-fn remove_first_and_last_characters(input: String) -> String {
-    let mut chars = input.chars();
-    chars.next();
-    chars.next_back();
-    chars.collect()
-}
@@ -0,0 +1,383 @@
+use std::str::FromStr;
+
+use convert_case::{Case, Casing};
+use jni::signature::JavaType;
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::{quote, quote_spanned};
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Field, Fields,
+    GenericArgument, Meta, NestedMeta, PathArguments, Type,
+};
+
+use crate::{
+    rust_property_chain_setter::{property_chain_setter, value_from_java},
+    rust_property_getter::property_getter,
+    utils::{find_attribute, java_kind, jni_mangle, rust_type, string_value, value_getter},
+};
+
+pub fn java_struct_impl(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+/// Field exposed to java.
+struct JavaField<'a> {
+    ident: &'a Ident,
+    /// Type converted by `CloneToJava`/`CloneFromJava`, `T` of nullable `Option<T>` fields.
+    ty: &'a Type,
+    /// JNI type signature of the field, e.g. `J` or `Ljava/lang/String;`.
+    signature: String,
+    /// `Option` field mapped to a java reference which may be `null`.
+    nullable: bool,
+    getter: Option<String>,
+    setter: Option<String>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "JavaStruct cannot be derived for generic structs",
+        ));
+    }
+    let struct_ident = &input.ident;
+    let struct_type: Type = syn::parse_quote!(#struct_ident);
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .map(java_field)
+                .collect::<syn::Result<Vec<_>>>()?,
+            _ => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    "JavaStruct can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "JavaStruct can only be derived for structs",
+            ))
+        }
+    };
+
+    let class_name = find_attribute(&input.attrs, "java_class")
+        .ok_or_else(|| syn::Error::new(input.span(), "No java_class attribute found"))
+        .and_then(string_value)?;
+    let class = jni_mangle(&class_name.value());
+
+    let constructor = match find_attribute(&input.attrs, "java_constructor") {
+        Some(attribute) => constructor(attribute, &class, struct_ident, &fields)?,
+        None => quote! {},
+    };
+
+    let destructor_name = Ident::new(
+        &format!("Java_{}_rustDestructor__", class),
+        Span::call_site(),
+    );
+
+    let mut accessors = vec![];
+    for field in &fields {
+        let rust_type = rust_type(&field.signature, Span::call_site())?;
+        if let Some(method) = &field.getter {
+            let function_name = Ident::new(
+                &format!("Java_{}_{}__", class, jni_mangle(method)),
+                Span::call_site(),
+            );
+            let ty = field.ty;
+            accessors.push(quote_spanned! { ty.span() =>
+                const _: fn() = || {
+                    fn assert_clone_to_java<T: crate::clone_to_java::CloneToJava>() {}
+                    assert_clone_to_java::<#ty>();
+                };
+            });
+            accessors.push(property_getter(
+                &function_name,
+                &struct_type,
+                field.ident,
+                &rust_type,
+                &java_kind(&field.signature, field.ty.span())?,
+                &value_getter(&field.signature),
+                field.nullable,
+            ));
+        }
+        if let Some(method) = &field.setter {
+            let function_name = Ident::new(
+                &format!(
+                    "Java_{}_{}__{}",
+                    class,
+                    jni_mangle(method),
+                    jni_mangle(&field.signature)
+                ),
+                Span::call_site(),
+            );
+            let ty = field.ty;
+            accessors.push(quote_spanned! { ty.span() =>
+                const _: fn() = || {
+                    fn assert_clone_from_java<T: crate::clone_from_java::CloneFromJava>() {}
+                    assert_clone_from_java::<#ty>();
+                };
+            });
+            accessors.push(property_chain_setter(
+                &function_name,
+                &struct_type,
+                field.ident,
+                &rust_type,
+                field.nullable,
+            ));
+        }
+    }
+
+    Ok(quote! {
+        crate::java_struct_standard_impl!(#struct_ident, #class_name);
+
+        #constructor
+
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub extern "system" fn #destructor_name(env: jni::JNIEnv, obj: jni::objects::JObject) {
+            crate::jni_guard::jni_guard(env, || -> crate::common::errors::JniResult<()> {
+                crate::java_stored_object::JavaStoredObject::<#struct_ident>::destroy(env, obj)?;
+                Ok(())
+            })
+        }
+
+        #(#accessors)*
+    })
+}
+
+/// `rustConstructor` taking the fields listed in `#[java_constructor(...)]` in that order, the
+/// remaining ones are defaulted. A bare `#[java_constructor]` takes no arguments and uses
+/// `Default` of the whole struct.
+fn constructor(
+    attribute: &Attribute,
+    class: &str,
+    struct_ident: &Ident,
+    fields: &[JavaField],
+) -> syn::Result<proc_macro2::TokenStream> {
+    let arguments = match attribute.parse_meta()? {
+        Meta::Path(_) => None,
+        Meta::List(list) => Some(
+            list.nested
+                .iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(Meta::Path(path)) => path
+                        .get_ident()
+                        .and_then(|ident| fields.iter().find(|field| field.ident == ident))
+                        .ok_or_else(|| syn::Error::new(path.span(), "Unknown field")),
+                    _ => Err(syn::Error::new(nested.span(), "Expected a field name")),
+                })
+                .collect::<syn::Result<Vec<_>>>()?,
+        ),
+        Meta::NameValue(name_value) => {
+            return Err(syn::Error::new(
+                name_value.span(),
+                "Expected #[java_constructor] or #[java_constructor(field, ...)]",
+            ))
+        }
+    };
+
+    let arguments = arguments.unwrap_or_default();
+    let signature = arguments
+        .iter()
+        .map(|field| field.signature.as_str())
+        .collect::<String>();
+    let function_name = Ident::new(
+        &format!("Java_{}_rustConstructor__{}", class, jni_mangle(&signature)),
+        Span::call_site(),
+    );
+    let parameters = arguments
+        .iter()
+        .map(|field| {
+            let ident = field.ident;
+            let rust_type = rust_type(&field.signature, Span::call_site())?;
+            Ok(quote! { #ident: jni::sys::#rust_type })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let conversions = arguments.iter().map(|field| {
+        let ident = field.ident;
+        let value = value_from_java(ident, field.nullable);
+        quote! { let #ident = #value; }
+    });
+    let value = if arguments.is_empty() {
+        quote! { <#struct_ident as Default>::default() }
+    } else {
+        let initializers = fields.iter().map(|field| {
+            let ident = field.ident;
+            match arguments.iter().any(|argument| argument.ident == ident) {
+                true => quote! { #ident },
+                false => quote! { #ident: Default::default() },
+            }
+        });
+        quote! { #struct_ident { #(#initializers),* } }
+    };
+
+    Ok(quote! {
+        #[no_mangle]
+        #[allow(non_snake_case)]
+        pub extern "system" fn #function_name(
+            env: jni::JNIEnv,
+            obj: jni::objects::JObject,
+            #(#parameters),*
+        ) {
+            crate::jni_guard::jni_guard(env, || -> crate::common::errors::JniResult<()> {
+                #(#conversions)*
+                crate::java_stored_object::JavaStoredObject::store(env, obj, #value)?;
+                Ok(())
+            })
+        }
+    })
+}
+
+fn java_field(field: &Field) -> syn::Result<JavaField<'_>> {
+    let ident = field
+        .ident
+        .as_ref()
+        .ok_or_else(|| syn::Error::new(field.span(), "Expected a named field"))?;
+    let method_name = |attribute: &Attribute| -> syn::Result<String> {
+        match attribute.parse_meta()? {
+            Meta::Path(_) => Ok(ident.to_string().to_case(Case::Camel)),
+            Meta::NameValue(_) => string_value(attribute).map(|name| name.value()),
+            Meta::List(list) => Err(syn::Error::new(
+                list.span(),
+                "Expected a method name, e.g. #[java_getter = \"isUpperBound\"]",
+            )),
+        }
+    };
+    let getter = find_attribute(&field.attrs, "java_getter")
+        .map(method_name)
+        .transpose()?;
+    let setter = find_attribute(&field.attrs, "java_setter")
+        .map(method_name)
+        .transpose()?;
+    let java_type = find_attribute(&field.attrs, "java_type")
+        .map(string_value)
+        .transpose()?;
+
+    let (ty, nullable) = match option_argument(&field.ty) {
+        Some(inner) if signature_of(&field.ty).is_none() => (inner, true),
+        _ => (&field.ty, false),
+    };
+    if is_unsigned(ty) {
+        return Err(syn::Error::new(
+            ty.span(),
+            "Java has no unsigned integers, use i32 or i64 so values can't wrap when converted",
+        ));
+    }
+    let signature =
+        match (signature_of(ty), java_type) {
+            (Some(derived), Some(java_type)) if derived != java_type.value() => {
+                return Err(syn::Error::new(
+                    java_type.span(),
+                    format!(
+                        "`{}` does not match the field type, expected `{}`",
+                        java_type.value(),
+                        derived
+                    ),
+                ))
+            }
+            (Some(derived), _) => derived.to_owned(),
+            (None, Some(java_type)) => {
+                let signature = java_type.value();
+                match JavaType::from_str(&signature) {
+                    Ok(JavaType::Object(_)) => signature,
+                    _ => return Err(syn::Error::new(
+                        java_type.span(),
+                        "Expected a class signature, e.g. \"Lorg/apache/kafka/common/MetricName;\"",
+                    )),
+                }
+            }
+            (None, None) => return Err(syn::Error::new(
+                ty.span(),
+                "Cannot derive the java type of this field, add #[java_type = \"Lpackage/Class;\"]",
+            )),
+        };
+    if nullable && !signature.starts_with('L') {
+        return Err(syn::Error::new(
+            field.ty.span(),
+            "Optional fields have to be mapped to a java class",
+        ));
+    }
+
+    Ok(JavaField {
+        ident,
+        ty,
+        signature,
+        nullable,
+        getter,
+        setter,
+    })
+}
+
+/// JNI signature of the java type `CloneToJava`/`CloneFromJava` convert `ty` from and to.
+fn signature_of(ty: &Type) -> Option<&'static str> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    let signature = match segment.ident.to_string().as_str() {
+        "bool" => "Z",
+        "Bytes" => "[B",
+        "f64" => "D",
+        "GlobalRef" => "Ljava/lang/Object;",
+        "HashMap" | "IndexMap" => "Ljava/util/Map;",
+        "i32" => "I",
+        "i64" => "J",
+        "IndexSet" => "Ljava/util/Set;",
+        "JavaOptional" => "Ljava/util/Optional;",
+        "JavaOptionalInt" => "Ljava/util/OptionalInt;",
+        "JavaOptionalLong" => "Ljava/util/OptionalLong;",
+        // Primitives are boxed, `None` is `null`. Other nullable fields use the signature of `T`.
+        "Option" => match option_argument(ty).and_then(signature_of) {
+            Some("Z") => "Ljava/lang/Boolean;",
+            Some("D") => "Ljava/lang/Double;",
+            Some("I") => "Ljava/lang/Integer;",
+            Some("J") => "Ljava/lang/Long;",
+            _ => return None,
+        },
+        "String" => "Ljava/lang/String;",
+        "Vec" => "Ljava/util/List;",
+        _ => return None,
+    };
+    Some(signature)
+}
+
+fn is_unsigned(ty: &Type) -> bool {
+    let ident = match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    };
+    matches!(
+        ident.as_deref(),
+        Some("u8" | "u16" | "u32" | "u64" | "u128" | "usize")
+    )
+}
+
+/// `T` of `Option<T>`.
+fn option_argument(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -1,11 +1,13 @@
 use java_enum::java_enum_impl;
 use java_property_getter::java_property_getter_impl;
+use java_struct::java_struct_impl;
 use proc_macro::TokenStream;
 use rust_property_chain_setter::rust_property_chain_setter_impl;
 use rust_property_getter::rust_property_getter_impl;
 
 mod java_enum;
 mod java_property_getter;
+mod java_struct;
 mod rust_property_chain_setter;
 mod rust_property_getter;
 mod utils;
@@ -16,12 +18,57 @@ pub fn java_property_getter(input: TokenStream) -> TokenStream {
     java_property_getter_impl(input)
 }
 
-#[proc_macro_derive(JavaEnum, attributes(java_class, java_variant))]
+/// Generates conversions of an enum to the constants of its java enum and back:
+/// - `#[java_class = "org/apache/kafka/common/record/CompressionType"]` - bound java enum,
+/// - `#[java_variant = "GZIP"]` on a variant - java constant of the variant,
+/// - `#[java_id_type = "i16"]` and `#[java_id = 1]` on every variant - `id()` and `from_id`,
+///   negative ids are written `#[java_id(-1)]`,
+/// - `#[java_name = "gzip"]` on every variant - `name()`, `Display` and `FromStr`,
+/// - `#[java_fallback]` on a variant - returned for unknown ids and unknown java constants,
+///   instead of `None` and an exception.
+///
+/// `values()` listing every variant is always generated.
+#[proc_macro_derive(
+    JavaEnum,
+    attributes(
+        java_class,
+        java_variant,
+        java_id_type,
+        java_id,
+        java_name,
+        java_fallback
+    )
+)]
 pub fn java_enum(input: TokenStream) -> TokenStream {
     java_enum_impl(input)
 }
 
+/// Generates JNI bindings of a struct stored in the `rustPointer` field of its java class:
+/// - `#[java_class = "org/apache/kafka/common/metrics/Quota"]` - bound java class,
+/// - `#[java_constructor]` / `#[java_constructor(field, ...)]` - `rustConstructor` defaulting
+///   the struct or taking listed fields as arguments,
+/// - `#[java_getter]` / `#[java_getter = "isUpperBound"]` on a field - getter, named after the
+///   field in camel case by default,
+/// - `#[java_setter]` / `#[java_setter = "eventWindow"]` on a field - setter returning `this`,
+/// - `#[java_type = "Lorg/apache/kafka/common/metrics/Quota;"]` on a field - java type of fields
+///   whose type can't be derived from the rust type.
+///
+/// Unsigned integer fields are rejected, they would wrap once converted to java `int`/`long`.
+///
+/// `rustDestructor` and `java_struct_standard_impl!` are always generated. Native functions use
+/// JNI long names, so overloaded java methods are supported.
+#[proc_macro_derive(
+    JavaStruct,
+    attributes(java_class, java_constructor, java_getter, java_setter, java_type)
+)]
+pub fn java_struct(input: TokenStream) -> TokenStream {
+    java_struct_impl(input)
+}
+
 /// If Signature includes arrays(e.g. `[B`) delimiter must be closed to be parsed by rust correctly(`[]B`)
+///
+/// The return type has to match `CloneToJava::Kind` of the field, e.g. `()J` on a `f64` field
+/// fails to compile.
 #[proc_macro]
 pub fn rust_property_getter(input: TokenStream) -> TokenStream {
     rust_property_getter_impl(input)
@@ -4,49 +4,70 @@ use proc_macro2::{Ident, Span};
 use quote::quote;
 use syn::Type;
 
-use crate::utils::{parse_jni_like_definition, parse_jni_method_comment, JniMethodMetadata};
+use crate::utils::{
+    parse_ident, parse_jni_like_definition, parse_jni_method_comment, JniMethodMetadata,
+};
 pub fn rust_property_chain_setter_impl(input: TokenStream) -> TokenStream {
-    let macro_args = parse_jni_like_definition(input);
+    match expand(input.into()) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+fn expand(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let macro_args = parse_jni_like_definition(input)?;
     let JniMethodMetadata {
         class_underscore_notation: class,
         method,
         rust_argument_types,
         ..
-    } = parse_jni_method_comment(&macro_args);
-    let struct_name = macro_args
-        .get("Struct")
-        .expect("No Struct value")
-        .to_owned();
+    } = parse_jni_method_comment(&macro_args)?;
+    let struct_name = macro_args.require("Struct")?;
     let nullable = macro_args
         .get("Nullable")
-        .cloned()
-        .unwrap_or_default()
-        .contains("True");
-    let override_function_name = macro_args.get("Function");
+        .map(|nullable| nullable.value.contains("True"))
+        .unwrap_or_default();
 
-    let struct_name: Type = syn::parse_str(&struct_name).unwrap();
-    let function_name = match override_function_name {
-        Some(override_name) => Ident::new(override_name, Span::call_site()),
-        None => Ident::new(&format!("Java_{}_{}", class, method), Span::call_site()),
+    let struct_name: Type = syn::parse_str(&struct_name.value)
+        .map_err(|error| syn::Error::new(struct_name.span, error))?;
+    let method_span = macro_args.require("Method")?.span;
+    let function_name = match macro_args.ident("Function")? {
+        Some(function_name) => function_name,
+        None => parse_ident(&format!("Java_{}_{}", class, method), method_span)?,
     };
-    let rust_field_name = Ident::new(&method.to_case(Case::Snake), Span::call_site());
-    let value_type = Ident::new(
-        rust_argument_types.get(0).unwrap().as_str(),
-        Span::call_site(),
-    );
-    let value_clone = if nullable {
-        quote! {
-            if val.is_null(){
-                None
-            } else {
-                Some(crate::clone_from_java::CloneFromJava::clone_from_java(env,val.into())?)
-            }
+    let rust_field_name = parse_ident(&method.to_case(Case::Snake), method_span)?;
+    let value_type = match rust_argument_types.as_slice() {
+        [value_type] => value_type,
+        _ => {
+            return Err(syn::Error::new(
+                macro_args.require("Signature")?.span,
+                "Setter signature has to take exactly one argument",
+            ))
         }
-    } else {
-        quote! {crate::clone_from_java::CloneFromJava::clone_from_java(env,val.into())?}
     };
 
-    let expanded = quote! {
+    Ok(property_chain_setter(
+        &function_name,
+        &struct_name,
+        &rust_field_name,
+        value_type,
+        nullable,
+    ))
+}
+
+/// Generates a JNI function replacing `rust_field_name` of the rust value backing the java
+/// object and returning the object itself, so calls can be chained from java.
+pub fn property_chain_setter(
+    function_name: &Ident,
+    struct_name: &Type,
+    rust_field_name: &Ident,
+    value_type: &Ident,
+    nullable: bool,
+) -> proc_macro2::TokenStream {
+    let val = Ident::new("val", Span::call_site());
+    let value_clone = value_from_java(&val, nullable);
+
+    quote! {
         #[no_mangle]
         #[allow(non_snake_case)]
         pub extern "system" fn #function_name (
@@ -55,21 +76,29 @@ pub fn rust_property_chain_setter_impl(input: TokenStream) -> TokenStream {
             val: jni::sys::#value_type,
         ) -> jni::sys::jobject {
 
-            let result = || -> jni::errors::Result<_> {
-                let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-                let mut rust_struct = unsafe { Box::from_raw(ptr as *mut #struct_name) };
-                rust_struct.#rust_field_name =  #value_clone;
-                let _ptr = Box::into_raw(rust_struct);
+            crate::jni_guard::jni_guard(env, || -> crate::common::errors::JniResult<jni::sys::jobject> {
+                let value = #value_clone;
+                let stored = crate::java_stored_object::JavaStoredObject::<#struct_name>::new(env, obj, "")?;
+                stored.lock().#rust_field_name = value;
 
                 Ok(obj.into_inner())
-            }();
+            })
+        }
+    }
+}
 
-            match result {
-                Ok(v) => v,
-                Err(jni::errors::Error::JavaException) => jni::objects::JObject::null().into_inner(),
-                _ =>  panic!("{:?}", result),
+/// Converts the JNI argument `val` to its rust counterpart, java `null` becomes `None` when
+/// `nullable`.
+pub fn value_from_java(val: &Ident, nullable: bool) -> proc_macro2::TokenStream {
+    if nullable {
+        quote! {
+            if #val.is_null(){
+                None
+            } else {
+                Some(crate::clone_from_java::CloneFromJava::clone_from_java(env,#val.into())?)
             }
         }
-    };
-    TokenStream::from(expanded)
+    } else {
+        quote! {crate::clone_from_java::CloneFromJava::clone_from_java(env,#val.into())?}
+    }
 }
@@ -1,65 +1,107 @@
 use convert_case::{Case, Casing};
 use proc_macro::TokenStream;
 use proc_macro2::{Ident, Span};
-use quote::quote;
-use syn::{Expr, Type};
+use quote::{quote, quote_spanned};
+use syn::Type;
 
-use crate::utils::{parse_jni_like_definition, parse_jni_method_comment, JniMethodMetadata};
+use crate::utils::{
+    parse_ident, parse_jni_like_definition, parse_jni_method_comment, JniMethodMetadata,
+};
 pub fn rust_property_getter_impl(input: TokenStream) -> TokenStream {
-    let macro_args = parse_jni_like_definition(input);
+    match expand(input.into()) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(error) => TokenStream::from(error.to_compile_error()),
+    }
+}
+
+fn expand(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let macro_args = parse_jni_like_definition(input)?;
     let JniMethodMetadata {
         class_underscore_notation: class,
         method,
         rust_return_type,
+        return_kind,
         value_getter,
         ..
-    } = parse_jni_method_comment(&macro_args);
-    let struct_name = macro_args
-        .get("Struct")
-        .expect("No Struct value")
-        .to_owned();
+    } = parse_jni_method_comment(&macro_args)?;
+    let struct_name = macro_args.require("Struct")?;
     let nullable = macro_args
         .get("Nullable")
-        .cloned()
-        .unwrap_or_default()
-        .contains("True");
-    let override_function_name = macro_args.get("Function");
+        .map(|nullable| nullable.value.contains("True"))
+        .unwrap_or_default();
 
-    let default: Expr = if ["jobject", "jbyteArray"].contains(&rust_return_type.as_str()) {
-        syn::parse_str("jni::objects::JObject::null().into_inner()").unwrap()
-    } else {
-        syn::parse_str("Default::default()").unwrap()
+    let struct_name: Type = syn::parse_str(&struct_name.value)
+        .map_err(|error| syn::Error::new(struct_name.span, error))?;
+    let method_span = macro_args.require("Method")?.span;
+    let function_name = match macro_args.ident("Function")? {
+        Some(function_name) => function_name,
+        None => parse_ident(&format!("Java_{}_{}", class, method), method_span)?,
     };
+    let rust_field_name = parse_ident(&method.to_case(Case::Snake), method_span)?;
 
-    let struct_name: Type = syn::parse_str(&struct_name).unwrap();
-    let function_name = match override_function_name {
-        Some(override_name) => Ident::new(override_name, Span::call_site()),
-        None => Ident::new(&format!("Java_{}_{}", class, method), Span::call_site()),
-    };
-    let rust_field_name = Ident::new(&method.to_case(Case::Snake), Span::call_site());
-    let rust_return_type = Ident::new(&rust_return_type, Span::call_site());
+    Ok(property_getter(
+        &function_name,
+        &struct_name,
+        &rust_field_name,
+        &rust_return_type,
+        &return_kind,
+        &value_getter,
+        nullable,
+    ))
+}
 
-    let getter = Ident::new(&value_getter, Span::call_site());
-    let getter = match value_getter.as_str() {
-        "l" => quote! {ret?.#getter().expect("failed data extraction from field").into_inner()},
-        _ => quote! {ret?.#getter().expect("failed data extraction from field")},
+/// Generates a JNI function returning a copy of `rust_field_name` of the rust value backing the
+/// java object. Fails to compile unless `CloneToJava` of the field (of its `Some` value when
+/// `nullable`) produces a `return_kind` value.
+pub fn property_getter(
+    function_name: &Ident,
+    struct_name: &Type,
+    rust_field_name: &Ident,
+    rust_return_type: &Ident,
+    return_kind: &Ident,
+    value_getter: &str,
+    nullable: bool,
+) -> proc_macro2::TokenStream {
+    let getter = Ident::new(value_getter, Span::call_site());
+    let getter = match value_getter {
+        "l" => quote! {ret?.#getter()?.into_inner()},
+        _ => quote! {ret?.#getter()?},
     };
 
     let val = match nullable {
         true => quote! {
-            if rust_struct.#rust_field_name.is_some() {
-                crate::clone_to_java::CloneToJava::clone_to_java(&rust_struct.#rust_field_name.clone().unwrap(),env)
-            }else{
-                Ok(JValue::Object(JObject::null()))
+            match &rust_struct.#rust_field_name {
+                Some(value) => crate::clone_to_java::CloneToJava::clone_to_java(value, env),
+                None => Ok(jni::objects::JValue::Object(jni::objects::JObject::null())),
             }
-
         },
         false => {
             quote! {crate::clone_to_java::CloneToJava::clone_to_java(&rust_struct.#rust_field_name, env)}
         }
     };
 
-    let expanded = quote! {
+    let field = match nullable {
+        true => quote_spanned! { return_kind.span() =>
+            rust_struct.#rust_field_name.as_ref().map(assert_kind)
+        },
+        false => {
+            quote_spanned! { return_kind.span() => assert_kind(&rust_struct.#rust_field_name) }
+        }
+    };
+    let kind_assertion = quote_spanned! { return_kind.span() =>
+        const _: fn() = || {
+            fn assert_kind<T>(_: &T)
+            where
+                T: crate::clone_to_java::CloneToJava<Kind = crate::clone_to_java::kind::#return_kind>,
+            {
+            }
+            let _ = |rust_struct: &#struct_name| #field;
+        };
+    };
+
+    quote! {
+        #kind_assertion
+
         #[no_mangle]
         #[allow(non_snake_case)]
         pub extern "system" fn #function_name (
@@ -67,22 +109,15 @@ pub fn rust_property_getter_impl(input: TokenStream) -> TokenStream {
             obj: jni::objects::JObject,
         ) -> jni::sys::#rust_return_type {
 
-            let result = || -> jni::errors::Result<_> {
-                let ptr = env.get_field(obj, "rustPointer", "J")?.j()?;
-                let rust_struct= unsafe { Box::from_raw(ptr as *mut #struct_name) };
+            crate::jni_guard::jni_guard(env, || -> crate::common::errors::JniResult<jni::sys::#rust_return_type> {
+                let stored = crate::java_stored_object::JavaStoredObject::<#struct_name>::new(env, obj, "")?;
+                let rust_struct = stored.lock();
                 let ret = #val;
-                let _ptr = Box::into_raw(rust_struct);
+                drop(rust_struct);
                 let ret = #getter;
 
-                Ok(ret)
-            };
-
-            match result() {
-                Ok(v) => v.into(), // into() because of jboolean which is alias for u8 not bool
-                Err(jni::errors::Error::JavaException) => #default,
-                _ => panic!(),
-            }
+                Ok(ret.into()) // into() because of jboolean which is alias for u8 not bool
+            })
         }
-    };
-    TokenStream::from(expanded)
+    }
 }
@@ -1,102 +1,250 @@
 use std::{collections::HashMap, str::FromStr};
 
 use jni::signature::JavaType;
-use proc_macro::{TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Ident, Span, TokenStream, TokenTree};
+use syn::{spanned::Spanned, Attribute, Lit, LitStr, Meta};
+
 pub struct JniMethodMetadata {
     pub class_underscore_notation: String,
-    pub class_java_notation: String,
     pub method: String,
-    pub java_argument_types: Vec<String>,
-    pub rust_argument_types: Vec<String>,
+    pub rust_argument_types: Vec<Ident>,
     pub java_return_type: String,
-    pub rust_return_type: String,
+    pub rust_return_type: Ident,
+    /// `CloneToJava::Kind` matching the return type.
+    pub return_kind: Ident,
     pub value_getter: String,
 }
 
-pub fn parse_jni_method_comment(macro_args: &HashMap<String, String>) -> JniMethodMetadata {
-    let class_underscore_notation = macro_args.get("Class").expect("No Class value").to_owned();
-    let class_java_notation = class_underscore_notation.replace('_', "\\");
-    let method = macro_args
-        .get("Method")
-        .expect("No Method value")
-        .to_owned();
-    let signature = macro_args
-        .get("Signature")
-        .expect("No Signature value")
-        .replace("[]", "[");
-
-    let signature_type =
-        JavaType::from_str(&signature).expect("Failed to parse function signature");
-    let (java_return_type, java_argument_types): (String, Vec<String>) = match signature_type {
-        JavaType::Method(signature) => (
-            signature.ret.to_string(),
-            signature.args.into_iter().map(|x| x.to_string()).collect(),
-        ),
-        _ => unimplemented!(),
-    };
+/// Value of a `* Key: value` line, spanned at its first token.
+pub struct JniLikeValue {
+    pub value: String,
+    pub span: Span,
+}
+
+pub struct JniLikeDefinition {
+    values: HashMap<String, JniLikeValue>,
+}
+
+impl JniLikeDefinition {
+    pub fn get(&self, key: &str) -> Option<&JniLikeValue> {
+        self.values.get(key)
+    }
+
+    pub fn require(&self, key: &str) -> syn::Result<&JniLikeValue> {
+        self.get(key).ok_or_else(|| {
+            syn::Error::new(
+                Span::call_site(),
+                format!("No {} value, expected ` * {}: ...`", key, key),
+            )
+        })
+    }
+
+    /// Parses the value of `key` as an identifier, e.g. a function name.
+    pub fn ident(&self, key: &str) -> syn::Result<Option<Ident>> {
+        self.get(key)
+            .map(|value| parse_ident(&value.value, value.span))
+            .transpose()
+    }
+}
 
-    let value_getter = java_return_type
-        .chars()
-        .next()
-        .map(|ch| if ch == '[' { 'l' } else { ch })
-        .unwrap()
-        .to_lowercase()
-        .to_string();
+pub fn parse_jni_method_comment(macro_args: &JniLikeDefinition) -> syn::Result<JniMethodMetadata> {
+    let class_underscore_notation = macro_args.require("Class")?.value.to_owned();
+    let method = macro_args.require("Method")?.value.to_owned();
+    let signature = macro_args.require("Signature")?;
+    let span = signature.span;
+    let signature = signature.value.replace("[]", "[");
+
+    let (java_return_type, java_argument_types): (String, Vec<String>) =
+        match JavaType::from_str(&signature) {
+            Ok(JavaType::Method(signature)) => (
+                signature.ret.to_string(),
+                signature.args.into_iter().map(|x| x.to_string()).collect(),
+            ),
+            Ok(_) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!(
+                        "`{}` is not a method signature, expected e.g. `()J`",
+                        signature
+                    ),
+                ))
+            }
+            Err(_) => {
+                return Err(syn::Error::new(
+                    span,
+                    format!("`{}` is not a valid JNI signature", signature),
+                ))
+            }
+        };
+
+    let value_getter = value_getter(&java_return_type);
 
     let rust_argument_types = java_argument_types
         .iter()
-        .map(|x| x.as_str())
-        .map(java_type_to_rust)
-        .map(ToOwned::to_owned)
-        .collect();
-    let rust_return_type = java_type_to_rust(&java_return_type).to_owned();
+        .map(|java_type| rust_type(java_type, span))
+        .collect::<syn::Result<_>>()?;
+    let rust_return_type = rust_type(&java_return_type, span)?;
+    let return_kind = java_kind(&java_return_type, span)?;
 
-    JniMethodMetadata {
+    Ok(JniMethodMetadata {
         class_underscore_notation,
-        class_java_notation,
         method,
-        java_argument_types,
         rust_argument_types,
         java_return_type,
-        value_getter,
         rust_return_type,
+        return_kind,
+        value_getter,
+    })
+}
+/// Name of the `JValue` accessor (`z`, `j`, `l`...) extracting a value of `java_type`.
+pub fn value_getter(java_type: &str) -> String {
+    match java_type.chars().next() {
+        Some('[') | None => "l".to_owned(),
+        Some(ch) => ch.to_lowercase().to_string(),
     }
 }
-fn java_type_to_rust(java_type: &str) -> &str {
+
+/// Escapes a class name, method name or type signature the way JNI expects it in native
+/// function names, e.g. `org/apache/kafka/common/MetricName` becomes
+/// `org_apache_kafka_common_MetricName`.
+pub fn jni_mangle(name: &str) -> String {
+    name.chars()
+        .map(|ch| match ch {
+            '/' => "_".to_owned(),
+            '_' => "_1".to_owned(),
+            ';' => "_2".to_owned(),
+            '[' => "_3".to_owned(),
+            ch if ch.is_ascii_alphanumeric() => ch.to_string(),
+            ch => format!("_0{:04x}", ch as u32),
+        })
+        .collect()
+}
+
+/// `jni::sys` type and `CloneToJava::Kind` of a JNI type signature.
+fn jni_type(java_type: &str) -> Option<(&'static str, &'static str)> {
     match java_type {
-        "J" => "jlong",
-        "I" => "jint",
-        "Z" => "jboolean",
-        "D" => "jdouble",
-        "[B" => "jbyteArray",
-        "Ljava/lang/String" => "jstring",
-        _ if java_type.starts_with('L') => "jobject",
-        _ => todo!("Unknown return type"),
+        "J" => Some(("jlong", "Long")),
+        "I" => Some(("jint", "Int")),
+        "Z" => Some(("jboolean", "Boolean")),
+        "D" => Some(("jdouble", "Double")),
+        "[B" => Some(("jbyteArray", "Object")),
+        _ if java_type.starts_with('L') => Some(("jobject", "Object")),
+        _ => None,
     }
 }
-pub fn parse_jni_like_definition(input: TokenStream) -> HashMap<String, String> {
-    input
-        .into_iter()
-        .fold(vec![], |mut acc, tt| {
-            match tt {
-                TokenTree::Punct(punct) if punct.as_char() == '*' => {
-                    acc.push(vec![]);
-                }
-                _ => {
-                    acc.last_mut().unwrap().push(tt);
-                }
-            }
-            acc
-        })
-        .into_iter()
-        .map(|x| {
-            x.into_iter()
-                .map(|tt| tt.to_string())
-                .collect::<Vec<_>>()
-                .join("")
-                .split_once(':')
-                .map(|r| (r.0.to_owned(), r.1.to_owned()))
-                .unwrap()
-        })
-        .collect::<HashMap<_, _>>()
+
+fn unsupported_type(java_type: &str, span: Span) -> syn::Error {
+    syn::Error::new(
+        span,
+        format!(
+            "Unsupported JNI type `{}`, expected one of `J`, `I`, `Z`, `D`, `[B` or a class",
+            java_type
+        ),
+    )
+}
+
+/// `jni::sys` type of values of `java_type` passed through JNI.
+pub fn rust_type(java_type: &str, span: Span) -> syn::Result<Ident> {
+    jni_type(java_type)
+        .map(|(rust_type, _)| Ident::new(rust_type, span))
+        .ok_or_else(|| unsupported_type(java_type, span))
+}
+
+/// Marker type in `crate::clone_to_java::kind` of values of `java_type`.
+pub fn java_kind(java_type: &str, span: Span) -> syn::Result<Ident> {
+    jni_type(java_type)
+        .map(|(_, kind)| Ident::new(kind, span))
+        .ok_or_else(|| unsupported_type(java_type, span))
+}
+
+pub fn parse_ident(value: &str, span: Span) -> syn::Result<Ident> {
+    syn::parse_str::<Ident>(value)
+        .map(|ident| Ident::new(&ident.to_string(), span))
+        .map_err(|_| syn::Error::new(span, format!("`{}` is not a valid identifier", value)))
+}
+
+pub fn find_attribute<'a>(attributes: &'a [Attribute], name: &str) -> Option<&'a Attribute> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.path.is_ident(name))
+}
+
+/// Value of `#[attribute = "value"]`.
+pub fn string_value(attribute: &Attribute) -> syn::Result<LitStr> {
+    match attribute.parse_meta()? {
+        Meta::NameValue(name_value) => match name_value.lit {
+            Lit::Str(value) => Ok(value),
+            lit => Err(syn::Error::new(lit.span(), "Expected a string literal")),
+        },
+        meta => Err(syn::Error::new(
+            meta.span(),
+            "Expected #[attribute = \"value\"]",
+        )),
+    }
+}
+
+/// Parses ` * Key: value` lines, mimicking headers generated by `javah`.
+pub fn parse_jni_like_definition(input: TokenStream) -> syn::Result<JniLikeDefinition> {
+    let mut lines: Vec<(Span, Vec<TokenTree>)> = vec![];
+    for tt in input {
+        match tt {
+            TokenTree::Punct(punct) if punct.as_char() == '*' => lines.push((punct.span(), vec![])),
+            tt => match lines.last_mut() {
+                Some((_, line)) => line.push(tt),
+                None => return Err(syn::Error::new(tt.span(), "Expected ` * Key: value`")),
+            },
+        }
+    }
+
+    let mut values = HashMap::new();
+    for (star, line) in lines {
+        let mut tokens = line.into_iter();
+        let key = match tokens.next() {
+            Some(TokenTree::Ident(key)) => key,
+            Some(tt) => return Err(syn::Error::new(tt.span(), "Expected a key, e.g. `Method`")),
+            None => return Err(syn::Error::new(star, "Expected ` * Key: value`")),
+        };
+        let colon = match tokens.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ':' => punct,
+            Some(tt) => return Err(syn::Error::new(tt.span(), "Expected `:`")),
+            None => return Err(syn::Error::new(key.span(), "Expected `:` after the key")),
+        };
+        let tokens = tokens.collect::<Vec<_>>();
+        let span = match tokens.first() {
+            Some(tt) => tt.span(),
+            None => return Err(syn::Error::new(colon.span(), "Expected a value")),
+        };
+        let value = JniLikeValue {
+            value: tokens.iter().map(token_to_string).collect(),
+            span,
+        };
+        if values.insert(key.to_string(), value).is_some() {
+            return Err(syn::Error::new(
+                key.span(),
+                format!("Duplicate {} value", key),
+            ));
+        }
+    }
+    Ok(JniLikeDefinition { values })
+}
+
+/// Writes tokens back without the whitespace `to_string` puts inside groups.
+fn token_to_string(tt: &TokenTree) -> String {
+    match tt {
+        TokenTree::Group(group) => {
+            let (open, close) = match group.delimiter() {
+                Delimiter::Parenthesis => ("(", ")"),
+                Delimiter::Bracket => ("[", "]"),
+                Delimiter::Brace => ("{", "}"),
+                Delimiter::None => ("", ""),
+            };
+            let inner = group
+                .stream()
+                .into_iter()
+                .map(|tt| token_to_string(&tt))
+                .collect::<String>();
+            format!("{}{}{}", open, inner, close)
+        }
+        tt => tt.to_string(),
+    }
 }
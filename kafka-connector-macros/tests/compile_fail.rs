@@ -0,0 +1,8 @@
+//! Invalid macro input has to fail to compile with an error pointing at the offending tokens.
+//! Expected errors are in `tests/ui/*.stderr`, regenerate them with `TRYBUILD=overwrite`.
+
+#[test]
+fn invalid_input_is_rejected() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,15 @@
+use kafka_connector_macros::JavaEnum;
+
+#[derive(JavaEnum)]
+#[java_class = "org/apache/kafka/common/IsolationLevel"]
+#[java_id_type = "i8"]
+pub enum IsolationLevel {
+    #[java_variant = "READ_UNCOMMITTED"]
+    #[java_id = 0]
+    ReadUncommitted,
+    #[java_variant = "READ_COMMITTED"]
+    #[java_id = 0]
+    ReadCommitted,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use kafka_connector_macros::JavaEnum;
+
+#[derive(JavaEnum)]
+#[java_class = "org/apache/kafka/common/IsolationLevel"]
+pub enum IsolationLevel {
+    #[java_variant = "READ_UNCOMMITTED"]
+    ReadUncommitted,
+    ReadCommitted,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use kafka_connector_macros::JavaEnum;
+
+#[derive(JavaEnum)]
+#[java_class = "org/apache/kafka/common/record/CompressionType"]
+pub enum CompressionType {
+    #[java_variant = "NONE"]
+    #[java_fallback]
+    None,
+    #[java_variant = "GZIP"]
+    #[java_fallback]
+    Gzip,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use kafka_connector_macros::JavaEnum;
+
+#[derive(JavaEnum)]
+#[java_class = "org/apache/kafka/common/record/CompressionType"]
+pub enum CompressionType {
+    #[java_variant = "NONE"]
+    None,
+    #[java_variant = "GZIP"]
+    Gzip(u8),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use kafka_connector_macros::JavaStruct;
+
+#[derive(JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/Quota"]
+pub struct Quota<T> {
+    #[java_getter]
+    pub bound: T,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use kafka_connector_macros::JavaStruct;
+
+#[derive(JavaStruct)]
+#[java_class = "org/apache/kafka/common/MetricName"]
+pub struct MetricName {
+    #[java_getter]
+    #[java_type = "Ljava/lang/Object;"]
+    pub name: String,
+}
+
+fn main() {}
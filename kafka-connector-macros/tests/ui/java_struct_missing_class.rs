@@ -0,0 +1,9 @@
+use kafka_connector_macros::JavaStruct;
+
+#[derive(JavaStruct)]
+pub struct Quota {
+    #[java_getter]
+    pub bound: f64,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use kafka_connector_macros::JavaStruct;
+
+#[derive(JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/MetricConfig"]
+pub struct MetricConfig {
+    #[java_getter]
+    pub event_window: Option<u64>,
+}
+
+fn main() {}
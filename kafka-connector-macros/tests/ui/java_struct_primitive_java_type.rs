@@ -0,0 +1,13 @@
+use kafka_connector_macros::JavaStruct;
+
+pub struct Quota;
+
+#[derive(JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/MetricConfig"]
+pub struct MetricConfig {
+    #[java_getter]
+    #[java_type = "J"]
+    pub quota: Quota,
+}
+
+fn main() {}
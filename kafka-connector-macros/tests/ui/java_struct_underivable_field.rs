@@ -0,0 +1,12 @@
+use kafka_connector_macros::JavaStruct;
+
+pub struct Quota;
+
+#[derive(JavaStruct)]
+#[java_class = "org/apache/kafka/common/metrics/MetricConfig"]
+pub struct MetricConfig {
+    #[java_getter]
+    pub quota: Quota,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use kafka_connector_macros::rust_property_chain_setter;
+
+pub struct MetricConfig {
+    pub samples: i32,
+}
+
+rust_property_chain_setter!(
+ * Struct:    MetricConfig
+ * Class:     org_apache_kafka_common_metrics_MetricConfig
+ * Method:    samples
+ * Signature: (IJ)Lorg/apache/kafka/common/metrics/MetricConfig;
+);
+
+fn main() {}
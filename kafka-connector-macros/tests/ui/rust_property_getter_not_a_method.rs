@@ -0,0 +1,14 @@
+use kafka_connector_macros::rust_property_getter;
+
+pub struct TokenBucket {
+    pub tokens: f64,
+}
+
+rust_property_getter!(
+ * Struct:    TokenBucket
+ * Class:     org_apache_kafka_common_metrics_stats_TokenBucket
+ * Method:    tokens
+ * Signature: D
+);
+
+fn main() {}
@@ -0,0 +1,68 @@
+// Stand-ins for the modules of `kafka-connector-jni` that generated code refers to through
+// `crate::`, so code expanded from valid input compiles and only checks of the macros fail.
+
+pub mod clone_to_java {
+    use jni::{errors::Result, objects::JValue, JNIEnv};
+
+    pub mod kind {
+        pub struct Double;
+        pub struct Long;
+    }
+
+    pub trait CloneToJava {
+        type Kind;
+
+        fn clone_to_java<'a>(&self, env: JNIEnv<'a>) -> Result<JValue<'a>>;
+    }
+
+    impl CloneToJava for f64 {
+        type Kind = kind::Double;
+
+        fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> Result<JValue<'a>> {
+            Ok(JValue::Double(*self))
+        }
+    }
+
+    impl CloneToJava for i64 {
+        type Kind = kind::Long;
+
+        fn clone_to_java<'a>(&self, _env: JNIEnv<'a>) -> Result<JValue<'a>> {
+            Ok(JValue::Long(*self))
+        }
+    }
+}
+
+pub mod common {
+    pub mod errors {
+        pub type JniResult<T> = jni::errors::Result<T>;
+    }
+}
+
+pub mod java_stored_object {
+    use std::{marker::PhantomData, sync::MutexGuard};
+
+    pub struct JavaStoredObject<T>(PhantomData<T>);
+
+    impl<T> JavaStoredObject<T> {
+        pub fn new(
+            _env: jni::JNIEnv,
+            _obj: jni::objects::JObject,
+            _class_name: &'static str,
+        ) -> jni::errors::Result<Self> {
+            unimplemented!()
+        }
+
+        pub fn lock(&self) -> MutexGuard<'_, T> {
+            unimplemented!()
+        }
+    }
+}
+
+pub mod jni_guard {
+    pub fn jni_guard<T, F>(_env: jni::JNIEnv, body: F) -> T
+    where
+        F: FnOnce() -> jni::errors::Result<T>,
+    {
+        body().unwrap()
+    }
+}
@@ -0,0 +1,65 @@
+package org.apache.kafka;
+
+
+import java.lang.ref.PhantomReference;
+import java.lang.ref.ReferenceQueue;
+import java.nio.ByteBuffer;
+import java.util.Set;
+import java.util.concurrent.ConcurrentHashMap;
+
+/**
+ * Direct buffers over memory owned by rust. The memory is released once the buffer, and every
+ * buffer derived from it, is garbage collected.
+ */
+public final class RustByteBuffers {
+
+    static {
+        RustLib.load();
+    }
+
+    private static final ReferenceQueue<ByteBuffer> COLLECTED = new ReferenceQueue<>();
+    // References have to stay reachable until they are enqueued.
+    private static final Set<BufferReference> REFERENCES = ConcurrentHashMap.newKeySet();
+
+    static {
+        Thread releaser = new Thread(RustByteBuffers::releaseCollected, "rust-byte-buffers-releaser");
+        releaser.setDaemon(true);
+        releaser.start();
+    }
+
+    private RustByteBuffers() {
+    }
+
+    /**
+     * Called by rust with a direct buffer over the memory retained for {@code handle}.
+     * Derived buffers reference the buffer they were created from, so the memory stays
+     * retained as long as any of them is reachable.
+     */
+    static ByteBuffer wrap(ByteBuffer buffer, long handle) {
+        REFERENCES.add(new BufferReference(buffer, handle));
+        return buffer.asReadOnlyBuffer();
+    }
+
+    private static void releaseCollected() {
+        while (true) {
+            try {
+                BufferReference reference = (BufferReference) COLLECTED.remove();
+                REFERENCES.remove(reference);
+                release(reference.handle);
+            } catch (InterruptedException e) {
+                return;
+            }
+        }
+    }
+
+    private static native void release(long handle);
+
+    private static final class BufferReference extends PhantomReference<ByteBuffer> {
+        private final long handle;
+
+        BufferReference(ByteBuffer buffer, long handle) {
+            super(buffer, COLLECTED);
+            this.handle = handle;
+        }
+    }
+}
@@ -6,14 +6,22 @@ import java.util.Iterator;
 
 public class RustLib {
 
+    // Set by the rust test harness to the library built by cargo test
+    private static final String LIBRARY_PATH_PROPERTY = "kafka.connector.jni.library";
+
     static {
-//        String version = "debug";
-        String version = "release";
-        File lib = new File("../../target/" + version + "/libkafka_connector_jni.so");
-        if (!lib.exists()) {
-            lib = new File("../../../target/" + version + "/libkafka_connector_jni.so");
+        String path = System.getProperty(LIBRARY_PATH_PROPERTY);
+        if (path != null) {
+            System.load(new File(path).getAbsolutePath());
+        } else {
+//            String version = "debug";
+            String version = "release";
+            File lib = new File("../../target/" + version + "/libkafka_connector_jni.so");
+            if (!lib.exists()) {
+                lib = new File("../../../target/" + version + "/libkafka_connector_jni.so");
+            }
+            System.load(lib.getAbsolutePath());
         }
-        System.load(lib.getAbsolutePath());
     }
 
     public static void load() {
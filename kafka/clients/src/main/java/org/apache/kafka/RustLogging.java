@@ -0,0 +1,21 @@
+package org.apache.kafka;
+
+/**
+ * Logging of the rust library, forwarded to SLF4J loggers named after the rust module logging,
+ * e.g. {@code kafka_connector_jni.clients.admin.kafka_admin_client}.
+ */
+public final class RustLogging {
+
+    static {
+        RustLib.load();
+    }
+
+    private RustLogging() {
+    }
+
+    /**
+     * Rust reads the level of a SLF4J logger once, the first time its module logs. Call after
+     * changing levels at runtime to have them read again.
+     */
+    public static native void refreshLevels();
+}
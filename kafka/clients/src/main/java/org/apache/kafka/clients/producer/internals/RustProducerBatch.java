@@ -0,0 +1,73 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements. See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License. You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.kafka.clients.producer.internals;
+
+import org.apache.kafka.RustLib;
+import org.apache.kafka.common.TopicPartition;
+
+import java.nio.ByteBuffer;
+
+/**
+ * A batch of records drained from a {@link RustRecordAccumulator}, backed by a rust
+ * {@code ProducerBatch}. Instances are only created by the accumulator.
+ */
+public final class RustProducerBatch {
+    static {
+        RustLib.load();
+    }
+
+    private long rustPointer;
+
+    public native void rustDestructor();
+
+    @Override
+    protected void finalize() throws Throwable {
+        rustDestructor();
+        super.finalize();
+    }
+
+    private RustProducerBatch() {
+    }
+
+    public native TopicPartition topicPartition();
+
+    public native int recordCount();
+
+    public native int attempts();
+
+    /**
+     * The closed record batch, as a read-only direct buffer over the memory of the batch.
+     */
+    public native ByteBuffer records();
+
+    /**
+     * Complete the batch successfully, running the callbacks of its records.
+     *
+     * @return true if the batch was completed by this call, false if it was completed before
+     */
+    public native boolean complete(long baseOffset, long logAppendTime);
+
+    /**
+     * Complete the batch with the exception of the {@link org.apache.kafka.common.protocol.Errors}
+     * with the given code.
+     *
+     * @return true if the batch was completed by this call, false if it was completed before
+     */
+    public native boolean completeExceptionally(short errorCode, String errorMessage);
+
+    public native boolean isDone();
+}
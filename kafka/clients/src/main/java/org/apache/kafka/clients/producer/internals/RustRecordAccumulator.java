@@ -0,0 +1,114 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements. See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License. You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.kafka.clients.producer.internals;
+
+import org.apache.kafka.RustLib;
+import org.apache.kafka.clients.producer.Callback;
+import org.apache.kafka.common.Cluster;
+import org.apache.kafka.common.Node;
+import org.apache.kafka.common.TopicPartition;
+import org.apache.kafka.common.header.internals.RecordHeaders;
+import org.apache.kafka.common.record.CompressionType;
+
+import java.nio.ByteBuffer;
+import java.util.List;
+import java.util.Map;
+import java.util.Set;
+
+/**
+ * The rust record accumulator, accumulating records into batches to be sent to the server.
+ * It mirrors {@link RecordAccumulator}, draining {@link RustProducerBatch}es instead.
+ */
+public final class RustRecordAccumulator {
+    static {
+        RustLib.load();
+    }
+
+    private long rustPointer;
+
+    private native void rustConstructor(int batchSize, long totalMemory, CompressionType compression,
+                                        int lingerMs, long retryBackoffMs, int deliveryTimeoutMs);
+
+    public native void rustDestructor();
+
+    @Override
+    protected void finalize() throws Throwable {
+        rustDestructor();
+        super.finalize();
+    }
+
+    public RustRecordAccumulator(int batchSize, long totalMemory, CompressionType compression,
+                                 int lingerMs, long retryBackoffMs, int deliveryTimeoutMs) {
+        rustConstructor(batchSize, totalMemory, compression, lingerMs, retryBackoffMs, deliveryTimeoutMs);
+    }
+
+    /**
+     * Add a record to the accumulator. The callback runs on a thread owned by the accumulator
+     * once the batch of the record is completed.
+     *
+     * The remaining bytes of direct key and value buffers are read by rust without copying them,
+     * heap buffers are copied.
+     *
+     * @return true if the batch of the record is full or a new batch was created, so the sender
+     * should be woken up
+     */
+    public native boolean append(TopicPartition tp, long timestamp, ByteBuffer key, ByteBuffer value,
+                                 RecordHeaders headers, Callback callback, long maxTimeToBlockMs, long nowMs);
+
+    public native ReadyCheckResult ready(Cluster cluster, long nowMs);
+
+    public native Map<Integer, List<RustProducerBatch>> drain(Cluster cluster, Set<Node> nodes, int maxSize, long nowMs);
+
+    public native List<RustProducerBatch> expiredBatches(long now);
+
+    public native void reenqueue(RustProducerBatch batch, long now);
+
+    public native int splitAndReenqueue(RustProducerBatch bigBatch);
+
+    public native void deallocate(RustProducerBatch batch);
+
+    public native void mutePartition(TopicPartition tp);
+
+    public native void unmutePartition(TopicPartition tp);
+
+    public native boolean hasUndrained();
+
+    public native boolean hasIncomplete();
+
+    public native void beginFlush();
+
+    public native void awaitFlushCompletion() throws InterruptedException;
+
+    public native void abortIncompleteBatches();
+
+    public native void close();
+
+    /**
+     * The set of nodes that have at least one complete record batch in the accumulator
+     */
+    public static final class ReadyCheckResult {
+        public final Set<Node> readyNodes;
+        public final long nextReadyCheckDelayMs;
+        public final Set<String> unknownLeaderTopics;
+
+        public ReadyCheckResult(Set<Node> readyNodes, long nextReadyCheckDelayMs, Set<String> unknownLeaderTopics) {
+            this.readyNodes = readyNodes;
+            this.nextReadyCheckDelayMs = nextReadyCheckDelayMs;
+            this.unknownLeaderTopics = unknownLeaderTopics;
+        }
+    }
+}
@@ -38,11 +38,13 @@ public class RecordHeader implements Header {
 
     public RecordHeader(ByteBuffer keyBuffer, ByteBuffer valueBuffer) {
         Objects.requireNonNull(keyBuffer, "Null header keys are not permitted");
-        rustConstructor(Utils.utf8(keyBuffer), Utils.toArray(valueBuffer));
+        rustConstructor(Utils.utf8(keyBuffer), valueBuffer);
     }
 
     public native void rustConstructor(String key, byte[] value);
 
+    public native void rustConstructor(String key, ByteBuffer value);
+
     public native void rustDestructor();
 
     @Override
@@ -55,6 +57,12 @@ public class RecordHeader implements Header {
 
     public native byte[] value();
 
+    /**
+     * The value as a read-only direct buffer over the native memory, unlike {@link #value()} it
+     * is not copied.
+     */
+    public native ByteBuffer valueBuffer();
+
     @Override
     public boolean equals(Object o) {
         if (this == o)
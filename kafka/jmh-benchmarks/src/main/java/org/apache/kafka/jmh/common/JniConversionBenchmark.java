@@ -0,0 +1,94 @@
+/*
+ * Licensed to the Apache Software Foundation (ASF) under one or more
+ * contributor license agreements. See the NOTICE file distributed with
+ * this work for additional information regarding copyright ownership.
+ * The ASF licenses this file to You under the Apache License, Version 2.0
+ * (the "License"); you may not use this file except in compliance with
+ * the License. You may obtain a copy of the License at
+ *
+ *    http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+package org.apache.kafka.jmh.common;
+
+import org.apache.kafka.common.MetricName;
+import org.apache.kafka.common.header.Header;
+import org.apache.kafka.common.header.internals.RecordHeaders;
+import org.apache.kafka.common.metrics.MetricConfig;
+import org.apache.kafka.common.metrics.SensorRecordingLevel;
+import org.openjdk.jmh.annotations.Benchmark;
+import org.openjdk.jmh.annotations.BenchmarkMode;
+import org.openjdk.jmh.annotations.Fork;
+import org.openjdk.jmh.annotations.Level;
+import org.openjdk.jmh.annotations.Measurement;
+import org.openjdk.jmh.annotations.Mode;
+import org.openjdk.jmh.annotations.OutputTimeUnit;
+import org.openjdk.jmh.annotations.Param;
+import org.openjdk.jmh.annotations.Scope;
+import org.openjdk.jmh.annotations.Setup;
+import org.openjdk.jmh.annotations.State;
+import org.openjdk.jmh.annotations.Warmup;
+
+import java.nio.charset.StandardCharsets;
+import java.util.LinkedHashMap;
+import java.util.Map;
+import java.util.concurrent.TimeUnit;
+
+/**
+ * Conversions of values crossing the JNI boundary, each call copies collections and enums
+ * between java and rust. Results of the builds before and after the JNI lookup cache are in
+ * {@code jmh-benchmarks/results/JniConversionBenchmark.md}.
+ */
+@State(Scope.Benchmark)
+@Fork(value = 1)
+@Warmup(iterations = 5)
+@Measurement(iterations = 15)
+@BenchmarkMode(Mode.AverageTime)
+@OutputTimeUnit(TimeUnit.NANOSECONDS)
+public class JniConversionBenchmark {
+
+    @Param({"1", "10", "100"})
+    private int size;
+
+    private Map<String, String> tags;
+    private MetricName metricName;
+    private MetricConfig metricConfig;
+    private RecordHeaders headers;
+
+    @Setup(Level.Trial)
+    public void setup() {
+        tags = new LinkedHashMap<>();
+        headers = new RecordHeaders();
+        for (int i = 0; i < size; i++) {
+            tags.put("tag" + i, "value" + i);
+            headers.add("header" + i, ("value" + i).getBytes(StandardCharsets.UTF_8));
+        }
+        metricName = new MetricName("name", "group", "description", tags);
+        metricConfig = new MetricConfig().tags(tags).recordLevel(SensorRecordingLevel.DEBUG);
+    }
+
+    @Benchmark
+    public Map<String, String> tagsToJava() {
+        return metricName.tags();
+    }
+
+    @Benchmark
+    public MetricConfig tagsFromJava() {
+        return metricConfig.tags(tags);
+    }
+
+    @Benchmark
+    public Header[] headersToJava() {
+        return headers.toArray();
+    }
+
+    @Benchmark
+    public Object enumToJava() {
+        return metricConfig.recordLevel();
+    }
+}